@@ -0,0 +1,191 @@
+//! `rtflow <command> ...`: the RT_Flow engines from a terminal.
+//!
+//! - `rtflow diff <db-path> <left-doc-id> <right-doc-id> [--stat]
+//!   [--tenant <id>] [--options <json>]`: compare two stored documents and
+//!   print unified-diff-like text with one `@@` hunk per changed block, or
+//!   with `--stat` a git-style diffstat. `--options` takes the compare
+//!   options `rtflow_compare` does.
+
+use std::process::ExitCode;
+
+use uuid::Uuid;
+
+use rt_compare::text::{render_diffstat, render_unified};
+use rt_compare::worker::{CompareConfig, CompareEngine};
+use rt_core::db::{create_pool, BlockStore, SqliteBlockStore};
+use rt_core::hashing::{align_anchors, check_contracts, default_hasher};
+use rt_core::tenant::TenantContext;
+use rt_core::{Result, RtError};
+
+const USAGE: &str = "usage: rtflow diff <db-path> <left-doc-id> <right-doc-id> [--stat] \
+                     [--tenant <id>] [--options <json>]";
+
+enum Command {
+    Diff(DiffArgs),
+}
+
+struct DiffArgs {
+    db_path: String,
+    left_id: Uuid,
+    right_id: Uuid,
+    stat: bool,
+    tenant: TenantContext,
+    options: String,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> std::result::Result<Command, String> {
+    match args.next().as_deref() {
+        Some("diff") => parse_diff_args(args).map(Command::Diff),
+        Some("-h" | "--help") | None => Err(USAGE.to_string()),
+        Some(other) => Err(format!("unknown command '{other}'\n{USAGE}")),
+    }
+}
+
+fn parse_diff_args(
+    mut args: impl Iterator<Item = String>,
+) -> std::result::Result<DiffArgs, String> {
+    let mut positional = Vec::new();
+    let mut stat = false;
+    let mut tenant = TenantContext::default();
+    let mut options = String::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stat" => stat = true,
+            "--tenant" => {
+                let id = args.next().ok_or("--tenant needs a tenant id")?;
+                tenant = TenantContext::new(id).map_err(|e| e.to_string())?;
+            }
+            "--options" => options = args.next().ok_or("--options needs compare options")?,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if positional.len() < 3 && !arg.starts_with('-') => positional.push(arg),
+            _ => return Err(format!("unexpected argument '{arg}'\n{USAGE}")),
+        }
+    }
+    let [db_path, left, right]: [String; 3] = positional.try_into().map_err(|_| USAGE)?;
+    let parse_id = |value: &str| {
+        Uuid::parse_str(value).map_err(|e| format!("invalid document id '{value}': {e}"))
+    };
+    Ok(DiffArgs {
+        left_id: parse_id(&left)?,
+        right_id: parse_id(&right)?,
+        db_path,
+        stat,
+        tenant,
+        options,
+    })
+}
+
+/// Compare the two documents as `rtflow_compare_text` does and render the
+/// requested view.
+fn diff(args: &DiffArgs) -> Result<String> {
+    let pool = create_pool(&args.db_path)?;
+    let store = SqliteBlockStore::with_tenant(pool.clone(), args.tenant.clone());
+    let engine = CompareEngine::new(CompareConfig::from_json(&args.options)?);
+
+    // Refuse mismatched hash contracts, and re-anchor the right side when
+    // the anchor strategies differ.
+    let left_doc = store.get_document(&args.left_id)?;
+    let right_doc = store.get_document(&args.right_id)?;
+    check_contracts(&[&left_doc, &right_doc])?;
+    let left = store.get_block_tree(&args.left_id)?;
+    let mut right = store.get_block_tree(&args.right_id)?;
+    if left_doc.anchor_version != right_doc.anchor_version {
+        let conn = pool.get().map_err(|e| RtError::Internal(e.to_string()))?;
+        let hasher = default_hasher(&conn)?;
+        align_anchors(&left_doc, &right_doc, &mut right, &hasher)?;
+    }
+
+    let result = engine.compare(args.left_id, args.right_id, &left, &right);
+    Ok(if args.stat {
+        render_diffstat(&result, &left, &right)
+    } else {
+        render_unified(&result, &left, &right)
+    })
+}
+
+fn main() -> ExitCode {
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+    match command {
+        Command::Diff(args) => match diff(&args) {
+            Ok(text) => {
+                print!("{text}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("rtflow: {e}");
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_core::ClauseHasher;
+    use rt_ingest::IngestOptions;
+
+    const CONTRACT: &str = "MASTER SERVICES AGREEMENT\n\n1. Payment\n\
+                            The Customer shall pay each invoice within thirty days.\n";
+
+    fn args(line: &str) -> std::result::Result<Command, String> {
+        parse_args(line.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn diff_needs_a_database_and_two_document_ids() {
+        assert!(args("").is_err());
+        assert!(args("merge a.db").is_err());
+        assert!(args("diff a.db").is_err());
+        assert!(args("diff a.db not-a-uuid also-not").is_err());
+
+        let (left, right) = (Uuid::new_v4(), Uuid::new_v4());
+        let Ok(Command::Diff(parsed)) = args(&format!("diff a.db {left} {right} --stat")) else {
+            panic!("diff arguments should parse");
+        };
+        assert_eq!(parsed.db_path, "a.db");
+        assert_eq!((parsed.left_id, parsed.right_id), (left, right));
+        assert!(parsed.stat);
+        assert_eq!(parsed.tenant, TenantContext::default());
+    }
+
+    #[test]
+    fn diff_renders_stored_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("contracts.db").to_string_lossy().into_owned();
+        let store = SqliteBlockStore::new(create_pool(&db_path).unwrap());
+        let ingest = |text: &str, name: &str| {
+            let (hasher, options) = (ClauseHasher::default(), IngestOptions::default());
+            rt_ingest::ingest_text(&store, text, name, Uuid::new_v4(), &hasher, &options)
+                .unwrap()
+                .document
+                .id
+        };
+        let left_id = ingest(CONTRACT, "v1");
+        let right_id = ingest(&CONTRACT.replace("thirty", "sixty"), "v2");
+
+        let mut args = DiffArgs {
+            db_path,
+            left_id,
+            right_id,
+            stat: false,
+            tenant: TenantContext::default(),
+            options: String::new(),
+        };
+        let unified = diff(&args).unwrap();
+        assert!(unified.contains("@@"));
+        assert!(unified.contains("sixty"));
+
+        args.stat = true;
+        assert!(diff(&args).unwrap().contains("1 block changed"));
+
+        args.tenant = TenantContext::new("acme").unwrap();
+        assert!(matches!(diff(&args), Err(RtError::NotFound(_))));
+    }
+}
@@ -2,27 +2,134 @@
 //!
 //! Aligns two sequences of blocks using a multi-pass strategy:
 //!
+//! 0. **Table alignment** — corresponding tables are aligned row by row and
+//!    column by column ([`crate::table`]); their blocks take no part in the
+//!    passes below.
 //! 1. **Exact structural_path match** — blocks whose `structural_path` is
 //!    identical are paired first.
 //! 2. **Anchor signature match** — among unmatched blocks, those with
 //!    identical `anchor_signature` are paired.
-//! 3. **Similarity scoring** — remaining blocks are scored pairwise using the
-//!    token Jaccard index; pairs above the similarity threshold are matched.
+//! 3. **Similarity scoring** — remaining blocks are scored pairwise with the
+//!    configured [`SimilarityMetric`] (the token Jaccard index by default);
+//!    pairs above the similarity threshold are matched.
+//!    When there are too many pairs to score, only blocks sharing token
+//!    shingles are ([`AlignThresholds::max_candidates`]).
 //! 4. **LCS-based alignment** — any still-unmatched blocks are aligned using
 //!    a longest-common-subsequence approach on their position in the flat list.
 //! 5. **Move detection** — pairs matched by content (anchor or similarity ≥ 0.85)
-//!    whose `structural_path` differs are reclassified as `Moved`.
+//!    whose `structural_path` differs are reclassified as `Moved`. Content
+//!    matches whose path differs and whose positions are more than
+//!    [`AlignThresholds::move_distance_max`] apart are not paired at all, so
+//!    such a block shows as deleted and inserted.
+//! 6. **Split / merge detection** (opt-in via [`AlignThresholds::split`]) —
+//!    a block that resembles the concatenation of several adjacent blocks on
+//!    the other side better than its current counterpart is reported as
+//!    `Split` (1:N) or `Merged` (N:1).
+//!
+//! Inserting or deleting a clause renumbers every clause after it, so their
+//! paths no longer line up. With [`AlignThresholds::ignore_renumbering`],
+//! pass 1 only pairs same-path blocks whose content is similar (the rest are
+//! paired by path after pass 3), and a changed path only makes a move when
+//! more than its trailing ordinal differs (`1.3` → `1.4` does not; `1.3` →
+//! `2.1` does).
 
 use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use rt_model::error::Result;
+use rt_model::Block;
+use serde::{Deserialize, Serialize};
+
+use crate::pool;
+use crate::table::{align_tables, TableAlignment};
+use crate::tokenize::normalize_token;
+
+/// Default similarity threshold: a pair with Jaccard ≥ 0.7 counts as a
+/// content match.
+pub const SIMILARITY_THRESHOLD: f64 = 0.7;
+
+/// Default move detection threshold: a pair with Jaccard ≥ 0.85 and a
+/// differing structural_path is classified as `Moved` rather than `Modified`.
+pub const MOVE_THRESHOLD: f64 = 0.85;
+
+/// Default maximum distance, in blocks, between a block's positions in the
+/// two documents for it to be reported as `Moved`.
+pub const MOVE_DISTANCE_MAX: usize = 50;
+
+/// Suggested split/merge threshold: a block with Jaccard ≥ 0.8 against the
+/// concatenated text of several adjacent blocks is reported as split into,
+/// or merged from, them.
+pub const SPLIT_THRESHOLD: f64 = 0.8;
 
-use rt_core::Block;
+/// Default cap on the block pairs pass 3 scores; see
+/// [`AlignThresholds::max_candidates`].
+pub const MAX_CANDIDATES: usize = 250_000;
 
-/// Similarity threshold: a pair with Jaccard ≥ 0.7 counts as a content match.
-const SIMILARITY_THRESHOLD: f64 = 0.7;
+/// Tokens per shingle when pass 3 draws candidates from shared shingles.
+const SHINGLE_LEN: usize = 2;
 
-/// Move detection threshold: a pair with Jaccard ≥ 0.85 and a differing
-/// structural_path is classified as `Moved` rather than `Modified`.
-const MOVE_THRESHOLD: f64 = 0.85;
+/// A shingle shared by more unmatched blocks than this is boilerplate
+/// ("the borrower", "in accordance") and does not make candidates.
+const MAX_SHINGLE_BUCKET: usize = 64;
+
+/// Similarity cut-offs used by [`align_blocks_with`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignThresholds {
+    /// Minimum Jaccard similarity for a content match (passes 3 and 4).
+    pub similarity: f64,
+    /// Minimum Jaccard similarity for a content match with a changed
+    /// `structural_path` to be reported as `Moved`.
+    pub moved: f64,
+    /// Maximum distance between a block's position in the left block list
+    /// and its counterpart's in the right one for a changed path to count as
+    /// a move; a content match with a changed path farther apart is not
+    /// paired, leaving a `DeletedLeft` and an `InsertedRight`. Default:
+    /// [`MOVE_DISTANCE_MAX`].
+    pub move_distance_max: usize,
+    /// Minimum Jaccard similarity between a block and the concatenation of
+    /// two or more adjacent blocks on the other side for them to be aligned
+    /// as `Split` / `Merged`. `None` (the default) disables 1:N and N:1
+    /// alignment.
+    pub split: Option<f64>,
+    /// Tolerate renumbering: pair by path only when the content is similar,
+    /// and do not report a block as `Moved` when only the trailing ordinal
+    /// of its path changed. Off by default.
+    pub ignore_renumbering: bool,
+    /// Maximum number of block pairs pass 3 scores. Up to this many
+    /// unmatched pairs are all scored; beyond it, only pairs of blocks that
+    /// share token shingles are, the most shingles first, at most this many
+    /// in all, so two documents with thousands of unmatched blocks still
+    /// align in bounded time and memory. Default: [`MAX_CANDIDATES`].
+    pub max_candidates: usize,
+    /// How blocks are scored against each other when pairing them by
+    /// content. Default: [`SimilarityKind::Jaccard`].
+    pub metric: SimilarityKind,
+    /// Words left out when scoring blocks by content (passes 1 to 4);
+    /// `None` (the default) scores every token.
+    pub stopwords: Option<Stopwords>,
+    /// Number of worker threads the parallel passes run on (see
+    /// [`crate::pool`]); `0` (the default) uses rayon's global pool.
+    pub worker_threads: usize,
+}
+
+impl Default for AlignThresholds {
+    fn default() -> Self {
+        Self {
+            similarity: SIMILARITY_THRESHOLD,
+            moved: MOVE_THRESHOLD,
+            move_distance_max: MOVE_DISTANCE_MAX,
+            split: None,
+            ignore_renumbering: false,
+            max_candidates: MAX_CANDIDATES,
+            metric: SimilarityKind::Jaccard,
+            stopwords: None,
+            worker_threads: 0,
+        }
+    }
+}
 
 // ---------------------------------------------------------------------------
 // Public types
@@ -30,7 +137,11 @@ const MOVE_THRESHOLD: f64 = 0.85;
 
 /// The outcome of aligning a single block from the left document against a
 /// block from the right document (or declaring it an insertion/deletion).
-#[derive(Debug, Clone)]
+///
+/// Serializes with a `kind` tag, e.g.
+/// `{"kind": "matched", "left": 0, "right": 0, "similarity": 1.0}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum BlockAlignment {
     /// Both blocks exist and their content is similar enough to be treated as
     /// the same logical block. `similarity` is the Jaccard token score.
@@ -49,6 +160,20 @@ pub enum BlockAlignment {
         right: usize,
         similarity: f64,
     },
+    /// A left block split into several adjacent right blocks (in document
+    /// order). `similarity` is the Jaccard score against their concatenation.
+    Split {
+        left: usize,
+        right: Vec<usize>,
+        similarity: f64,
+    },
+    /// Several adjacent left blocks (in document order) merged into one right
+    /// block. `similarity` is the Jaccard score of their concatenation.
+    Merged {
+        left: Vec<usize>,
+        right: usize,
+        similarity: f64,
+    },
 }
 
 // ---------------------------------------------------------------------------
@@ -61,10 +186,91 @@ pub enum BlockAlignment {
 /// The output is ordered: left-document blocks appear in their original order,
 /// with inserted right-document blocks interleaved at the position where they
 /// were first encountered.
+///
+/// Uses the default thresholds, so every alignment is 1:1; see
+/// [`align_blocks_with`] for split / merge detection.
 pub fn align_blocks(left: &[Block], right: &[Block]) -> Vec<BlockAlignment> {
-    // Track which indices have been matched so far.
-    let mut left_matched: HashSet<usize> = HashSet::new();
-    let mut right_matched: HashSet<usize> = HashSet::new();
+    align_blocks_with(left, right, &AlignThresholds::default())
+}
+
+/// [`align_blocks`] with caller-supplied similarity thresholds, e.g. ones
+/// tuned by [`crate::calibrate`].
+///
+/// With [`AlignThresholds::split`] set, a `Split` is emitted at its left
+/// block's position and a `Merged` at the position of its first left block.
+pub fn align_blocks_with(
+    left: &[Block],
+    right: &[Block],
+    thresholds: &AlignThresholds,
+) -> Vec<BlockAlignment> {
+    align_blocks_with_tables(left, right, thresholds, &align_tables(left, right))
+}
+
+/// [`align_blocks_with`] given the already computed [`align_tables`] result
+/// for the same block lists, so a caller can also report its row and column
+/// changes.
+pub fn align_blocks_with_tables(
+    left: &[Block],
+    right: &[Block],
+    thresholds: &AlignThresholds,
+    tables: &TableAlignment,
+) -> Vec<BlockAlignment> {
+    // Fall back to rayon's global pool rather than fail when the
+    // configured one cannot be built.
+    let thresholds = AlignThresholds {
+        worker_threads: pool::usable_threads(thresholds.worker_threads),
+        ..thresholds.clone()
+    };
+    align_blocks_observed(left, right, &thresholds, tables, |_| Ok(()))
+        .unwrap_or_else(|_| unreachable!("the observer never fails and the pool exists"))
+}
+
+/// [`align_blocks_with_tables`], calling `before_pass` with the number of
+/// each pass (1 to 5, as in the module docs) before running it; pass 5 only
+/// runs with split detection on. An error from `before_pass` abandons the
+/// alignment and is returned, e.g. to cancel it, as is a failure to build
+/// the pool of [`AlignThresholds::worker_threads`].
+pub fn align_blocks_observed<F>(
+    left: &[Block],
+    right: &[Block],
+    thresholds: &AlignThresholds,
+    tables: &TableAlignment,
+    before_pass: F,
+) -> Result<Vec<BlockAlignment>>
+where
+    F: FnMut(usize) -> Result<()>,
+{
+    let metric = thresholds.metric.metric(left, right);
+    match &thresholds.stopwords {
+        Some(stopwords) => {
+            let metric = WithoutStopwords {
+                metric: metric.as_ref(),
+                stopwords,
+            };
+            align_blocks_scored(left, right, thresholds, tables, &metric, before_pass)
+        }
+        None => align_blocks_scored(left, right, thresholds, tables, metric.as_ref(), before_pass),
+    }
+}
+
+/// [`align_blocks_observed`] scoring block pairs with `metric` in place of
+/// [`AlignThresholds::metric`], for a caller-supplied [`SimilarityMetric`].
+/// Split and merge detection (pass 5) always uses Jaccard similarity.
+pub fn align_blocks_scored<F>(
+    left: &[Block],
+    right: &[Block],
+    thresholds: &AlignThresholds,
+    tables: &TableAlignment,
+    metric: &dyn SimilarityMetric,
+    mut before_pass: F,
+) -> Result<Vec<BlockAlignment>>
+where
+    F: FnMut(usize) -> Result<()>,
+{
+    // Track which indices have been matched so far. Blocks of aligned tables
+    // count as matched until the final assembly.
+    let mut left_matched: HashSet<usize> = tables.left_settled.clone();
+    let mut right_matched: HashSet<usize> = tables.right_settled.clone();
 
     // paired[left_idx] = (right_idx, similarity)
     let mut pairs: Vec<(usize, usize, f64, bool)> = Vec::new(); // (l, r, sim, is_move)
@@ -72,26 +278,29 @@ pub fn align_blocks(left: &[Block], right: &[Block]) -> Vec<BlockAlignment> {
     // -----------------------------------------------------------------------
     // Pass 1: exact structural_path match
     // -----------------------------------------------------------------------
-    let right_by_path: HashMap<&str, usize> = right
-        .iter()
-        .enumerate()
-        .map(|(i, b)| (b.structural_path.as_str(), i))
-        .collect();
-
-    for (li, lb) in left.iter().enumerate() {
-        if let Some(&ri) = right_by_path.get(lb.structural_path.as_str()) {
-            if !right_matched.contains(&ri) {
-                let sim = block_similarity(lb, &right[ri]);
-                pairs.push((li, ri, sim, false));
-                left_matched.insert(li);
-                right_matched.insert(ri);
-            }
-        }
-    }
+    before_pass(1)?;
+    // When tolerating renumbering, a same-path pair of dissimilar blocks is
+    // more likely two different clauses shifted past each other; those are
+    // left for the content passes and paired by path only afterwards.
+    let path_min_similarity = if thresholds.ignore_renumbering {
+        thresholds.similarity
+    } else {
+        0.0
+    };
+    match_by_path(
+        left,
+        right,
+        metric,
+        path_min_similarity,
+        &mut pairs,
+        &mut left_matched,
+        &mut right_matched,
+    );
 
     // -----------------------------------------------------------------------
     // Pass 2: anchor_signature match for still-unmatched blocks
     // -----------------------------------------------------------------------
+    before_pass(2)?;
     let right_by_anchor: HashMap<&str, usize> = right
         .iter()
         .enumerate()
@@ -105,9 +314,12 @@ pub fn align_blocks(left: &[Block], right: &[Block]) -> Vec<BlockAlignment> {
         }
         if let Some(&ri) = right_by_anchor.get(lb.anchor_signature.as_str()) {
             if !right_matched.contains(&ri) {
-                let sim = block_similarity(lb, &right[ri]);
+                if too_far_to_move(left, right, li, ri, thresholds) {
+                    continue;
+                }
+                let sim = score(metric, lb, &right[ri]);
                 // Anchor matched but structural_path may differ → could be moved.
-                let is_move = lb.structural_path != right[ri].structural_path;
+                let is_move = moved(left, right, li, ri, thresholds);
                 pairs.push((li, ri, sim, is_move));
                 left_matched.insert(li);
                 right_matched.insert(ri);
@@ -118,48 +330,32 @@ pub fn align_blocks(left: &[Block], right: &[Block]) -> Vec<BlockAlignment> {
     // -----------------------------------------------------------------------
     // Pass 3: similarity scoring for remaining unmatched blocks
     // -----------------------------------------------------------------------
-    let unmatched_left: Vec<usize> = (0..left.len())
-        .filter(|i| !left_matched.contains(i))
-        .collect();
-    let unmatched_right: Vec<usize> = (0..right.len())
-        .filter(|i| !right_matched.contains(i))
-        .collect();
-
-    // Compute all pairwise similarities for unmatched blocks.
-    // For large documents this could be O(n*m); in practice legal documents
-    // have bounded block counts per section so this is acceptable.
-    let mut candidates: Vec<(usize, usize, f64)> = Vec::new();
-    for &li in &unmatched_left {
-        for &ri in &unmatched_right {
-            let sim = block_similarity(&left[li], &right[ri]);
-            if sim >= SIMILARITY_THRESHOLD {
-                candidates.push((li, ri, sim));
-            }
-        }
-    }
-
-    // Greedy best-first matching: sort by descending similarity, then pick
-    // the highest-scoring pair first, removing used indices.
-    candidates.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
-
-    let mut sim_left_used: HashSet<usize> = HashSet::new();
-    let mut sim_right_used: HashSet<usize> = HashSet::new();
-
-    for (li, ri, sim) in candidates {
-        if sim_left_used.contains(&li) || sim_right_used.contains(&ri) {
-            continue;
-        }
-        let is_move = left[li].structural_path != right[ri].structural_path && sim >= MOVE_THRESHOLD;
-        pairs.push((li, ri, sim, is_move));
-        left_matched.insert(li);
-        right_matched.insert(ri);
-        sim_left_used.insert(li);
-        sim_right_used.insert(ri);
+    before_pass(3)?;
+    match_by_similarity(
+        left,
+        right,
+        metric,
+        thresholds,
+        &mut pairs,
+        &mut left_matched,
+        &mut right_matched,
+    )?;
+    if thresholds.ignore_renumbering {
+        match_by_path(
+            left,
+            right,
+            metric,
+            0.0,
+            &mut pairs,
+            &mut left_matched,
+            &mut right_matched,
+        );
     }
 
     // -----------------------------------------------------------------------
     // Pass 4: LCS-based alignment for any blocks still unmatched after scoring
     // -----------------------------------------------------------------------
+    before_pass(4)?;
     // Collect the truly unmatched after Pass 3.
     let remaining_left: Vec<usize> = (0..left.len())
         .filter(|i| !left_matched.contains(i))
@@ -172,19 +368,81 @@ pub fn align_blocks(left: &[Block], right: &[Block]) -> Vec<BlockAlignment> {
     // as the comparison key.
     let lcs_pairs = lcs_align(&remaining_left, &remaining_right, left, right);
     for (li, ri) in lcs_pairs {
-        let sim = block_similarity(&left[li], &right[ri]);
-        if sim >= SIMILARITY_THRESHOLD {
-            let is_move = left[li].structural_path != right[ri].structural_path
-                && sim >= MOVE_THRESHOLD;
+        let sim = score(metric, &left[li], &right[ri]);
+        if sim >= thresholds.similarity && !too_far_to_move(left, right, li, ri, thresholds) {
+            let is_move = moved(left, right, li, ri, thresholds) && sim >= thresholds.moved;
             pairs.push((li, ri, sim, is_move));
             left_matched.insert(li);
             right_matched.insert(ri);
         }
     }
 
+    // -----------------------------------------------------------------------
+    // Pass 5: 1:N / N:1 alignment against runs of adjacent blocks
+    // -----------------------------------------------------------------------
+    let mut splits: HashMap<usize, (Vec<usize>, f64)> = HashMap::new();
+    // Keyed by the first left block of the run.
+    let mut merges: HashMap<usize, (Vec<usize>, usize, f64)> = HashMap::new();
+    if let Some(threshold) = thresholds.split {
+        before_pass(5)?;
+        for (li, run, sim) in detect_runs(
+            left,
+            right,
+            threshold,
+            thresholds.similarity,
+            &mut pairs,
+            &mut left_matched,
+            &mut right_matched,
+        ) {
+            splits.insert(li, (run, sim));
+        }
+
+        // The same with the documents' roles swapped.
+        let mut flipped: Vec<(usize, usize, f64, bool)> =
+            pairs.iter().map(|&(l, r, s, m)| (r, l, s, m)).collect();
+        for (ri, run, sim) in detect_runs(
+            right,
+            left,
+            threshold,
+            thresholds.similarity,
+            &mut flipped,
+            &mut right_matched,
+            &mut left_matched,
+        ) {
+            merges.insert(run[0], (run, ri, sim));
+        }
+        pairs = flipped.iter().map(|&(r, l, s, m)| (l, r, s, m)).collect();
+
+        // Blocks released from weak pairs may match each other.
+        match_by_similarity(
+            left,
+            right,
+            metric,
+            thresholds,
+            &mut pairs,
+            &mut left_matched,
+            &mut right_matched,
+        )?;
+    }
+    let merged_tail: HashSet<usize> = merges
+        .values()
+        .flat_map(|(run, _, _)| run[1..].iter().copied())
+        .collect();
+
     // -----------------------------------------------------------------------
     // Assemble final output in left-document order, interleaving insertions
     // -----------------------------------------------------------------------
+    // Table pairs join the others; unpaired blocks of aligned tables become
+    // plain insertions and deletions.
+    pairs.extend(
+        tables
+            .pairs
+            .iter()
+            .map(|&(l, r)| (l, r, score(metric, &left[l], &right[r]), false)),
+    );
+    let table_right: HashSet<usize> = tables.pairs.iter().map(|&(_, r)| r).collect();
+    right_matched.retain(|r| !tables.right_settled.contains(r) || table_right.contains(r));
+
     // Build a lookup from left_idx → (right_idx, sim, is_move).
     let pair_map: HashMap<usize, (usize, f64, bool)> = pairs
         .iter()
@@ -203,7 +461,19 @@ pub fn align_blocks(left: &[Block], right: &[Block]) -> Vec<BlockAlignment> {
 
     // Emit in left-document traversal order.
     for li in 0..left.len() {
-        if let Some(&(ri, sim, is_move)) = pair_map.get(&li) {
+        if merged_tail.contains(&li) {
+            // Emitted with the first block of its merge.
+            continue;
+        }
+        if let Some((run, sim)) = splits.remove(&li) {
+            emit_insertions_before(run[0], right, &mut right_emitted, &right_matched, &mut result);
+            right_emitted.extend(run.iter().copied());
+            result.push(BlockAlignment::Split { left: li, right: run, similarity: sim });
+        } else if let Some((run, ri, sim)) = merges.remove(&li) {
+            emit_insertions_before(ri, right, &mut right_emitted, &right_matched, &mut result);
+            right_emitted.insert(ri);
+            result.push(BlockAlignment::Merged { left: run, right: ri, similarity: sim });
+        } else if let Some(&(ri, sim, is_move)) = pair_map.get(&li) {
             // Before emitting this matched pair, emit any right blocks that
             // come before ri and have not been matched (insertions).
             emit_insertions_before(ri, right, &mut right_emitted, &right_matched, &mut result);
@@ -236,7 +506,7 @@ pub fn align_blocks(left: &[Block], right: &[Block]) -> Vec<BlockAlignment> {
         }
     }
 
-    result
+    Ok(result)
 }
 
 /// Compute the Jaccard similarity between two blocks using their token sets.
@@ -248,9 +518,219 @@ pub fn align_blocks(left: &[Block], right: &[Block]) -> Vec<BlockAlignment> {
 pub fn block_similarity(left: &Block, right: &Block) -> f64 {
     // If both blocks have tokens, use them; otherwise fall back to
     // tokenizing the canonical text on the fly.
-    let left_tokens = token_set(left);
-    let right_tokens = token_set(right);
+    token_jaccard(&token_set(left), &token_set(right))
+}
+
+// ---------------------------------------------------------------------------
+// Similarity metrics
+// ---------------------------------------------------------------------------
 
+/// Scores two blocks' normalized token lists (as [`block_similarity`] reads
+/// them) from 0.0, nothing in common, to 1.0, identical. Thresholds such as
+/// [`AlignThresholds::similarity`] apply to whatever metric is in use.
+pub trait SimilarityMetric: Send + Sync {
+    fn similarity(&self, left: &[String], right: &[String]) -> f64;
+}
+
+/// Multiset Jaccard index; see [`block_similarity`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Jaccard;
+
+impl SimilarityMetric for Jaccard {
+    fn similarity(&self, left: &[String], right: &[String]) -> f64 {
+        token_jaccard(left, right)
+    }
+}
+
+/// Cosine similarity of TF-IDF vectors, with document frequencies counted
+/// over a corpus of blocks. Tokens that occur in most blocks ("the",
+/// "shall") weigh little, so two short clauses sharing only boilerplate
+/// score low, while sharing a rare term counts for a lot.
+#[derive(Debug, Clone)]
+pub struct TfIdfCosine {
+    /// Smoothed inverse document frequency by token.
+    idf: HashMap<String, f64>,
+    /// Weight of a token absent from the corpus.
+    unseen_idf: f64,
+}
+
+impl TfIdfCosine {
+    /// Count document frequencies over `corpus`, one token list per block.
+    pub fn new<'a>(corpus: impl IntoIterator<Item = &'a [String]>) -> Self {
+        let mut df: HashMap<String, usize> = HashMap::new();
+        let mut blocks = 0usize;
+        for tokens in corpus {
+            blocks += 1;
+            let distinct: HashSet<&String> = tokens.iter().collect();
+            for token in distinct {
+                *df.entry(token.clone()).or_insert(0) += 1;
+            }
+        }
+        let smoothed = |count: usize| ((blocks + 1) as f64 / (count + 1) as f64).ln() + 1.0;
+        Self {
+            idf: df.into_iter().map(|(t, n)| (t, smoothed(n))).collect(),
+            unseen_idf: smoothed(0),
+        }
+    }
+
+    fn weights<'t>(&self, tokens: &'t [String]) -> HashMap<&'t str, f64> {
+        let mut weights: HashMap<&str, f64> = HashMap::new();
+        for token in tokens {
+            *weights.entry(token.as_str()).or_insert(0.0) += 1.0;
+        }
+        for (token, weight) in weights.iter_mut() {
+            *weight *= self.idf.get(*token).copied().unwrap_or(self.unseen_idf);
+        }
+        weights
+    }
+}
+
+impl SimilarityMetric for TfIdfCosine {
+    fn similarity(&self, left: &[String], right: &[String]) -> f64 {
+        if left.is_empty() && right.is_empty() {
+            return 1.0;
+        }
+        if left.is_empty() || right.is_empty() {
+            return 0.0;
+        }
+        let (left, right) = (self.weights(left), self.weights(right));
+        let dot: f64 = left
+            .iter()
+            .filter_map(|(token, w)| right.get(token).map(|v| w * v))
+            .sum();
+        let norm = |v: &HashMap<&str, f64>| v.values().map(|w| w * w).sum::<f64>().sqrt();
+        // Rounding can push identical vectors a hair past 1.0.
+        (dot / (norm(&left) * norm(&right))).min(1.0)
+    }
+}
+
+/// Normalized token edit distance: `1 - distance / longer length`, with
+/// the Levenshtein distance counted in tokens. Unlike the set-based
+/// metrics it is sensitive to word order, and one changed word in a short
+/// clause costs only that word.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EditRatio;
+
+impl SimilarityMetric for EditRatio {
+    fn similarity(&self, left: &[String], right: &[String]) -> f64 {
+        let longer = left.len().max(right.len());
+        if longer == 0 {
+            return 1.0;
+        }
+        // Two-row Levenshtein over tokens.
+        let mut previous: Vec<usize> = (0..=right.len()).collect();
+        let mut current = vec![0; right.len() + 1];
+        for (i, l) in left.iter().enumerate() {
+            current[0] = i + 1;
+            for (j, r) in right.iter().enumerate() {
+                let substitution = previous[j] + usize::from(l != r);
+                current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+        1.0 - previous[right.len()] as f64 / longer as f64
+    }
+}
+
+/// Boilerplate words of legal English that appear in almost every clause
+/// and say little about which clause is which; the default [`Stopwords`].
+pub const LEGAL_STOPWORDS: &[&str] = &[
+    "a", "an", "and", "any", "are", "as", "at", "be", "by", "for", "from", "hereby", "herein",
+    "hereof", "hereto", "hereunder", "if", "in", "is", "it", "its", "of", "on", "or", "such",
+    "shall", "that", "the", "thereof", "this", "to", "under", "which", "will", "with",
+];
+
+/// Tokens left out when scoring blocks, so alignment keys on their
+/// distinctive content words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stopwords(HashSet<String>);
+
+impl Stopwords {
+    /// The [`LEGAL_STOPWORDS`].
+    pub fn legal() -> Self {
+        Self::new(LEGAL_STOPWORDS)
+    }
+
+    /// A custom list; words are normalized as tokens are.
+    pub fn new<S: AsRef<str>>(words: impl IntoIterator<Item = S>) -> Self {
+        Self(words.into_iter().map(|w| normalize_token(w.as_ref())).collect())
+    }
+
+    pub fn contains(&self, token: &str) -> bool {
+        self.0.contains(token)
+    }
+
+    /// `tokens` without the stopwords; all of `tokens` when they are nothing
+    /// but stopwords, so two different all-boilerplate blocks do not look
+    /// identical.
+    pub fn strip(&self, tokens: &[String]) -> Vec<String> {
+        let content: Vec<String> = tokens.iter().filter(|t| !self.contains(t)).cloned().collect();
+        if content.is_empty() {
+            tokens.to_vec()
+        } else {
+            content
+        }
+    }
+}
+
+impl Default for Stopwords {
+    fn default() -> Self {
+        Self::legal()
+    }
+}
+
+/// `metric` scoring token lists with `stopwords` stripped.
+pub struct WithoutStopwords<'a> {
+    pub metric: &'a dyn SimilarityMetric,
+    pub stopwords: &'a Stopwords,
+}
+
+impl SimilarityMetric for WithoutStopwords<'_> {
+    fn similarity(&self, left: &[String], right: &[String]) -> f64 {
+        self.metric.similarity(&self.stopwords.strip(left), &self.stopwords.strip(right))
+    }
+}
+
+/// Built-in [`SimilarityMetric`]s, as selected by configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityKind {
+    /// [`Jaccard`].
+    #[default]
+    Jaccard,
+    /// [`TfIdfCosine`], with document frequencies over the blocks of both
+    /// documents being aligned.
+    TfidfCosine,
+    /// [`EditRatio`].
+    EditRatio,
+}
+
+impl SimilarityKind {
+    /// The metric for aligning `left` with `right`.
+    pub fn metric(self, left: &[Block], right: &[Block]) -> Box<dyn SimilarityMetric> {
+        match self {
+            Self::Jaccard => Box::new(Jaccard),
+            Self::TfidfCosine => {
+                let corpus: Vec<Vec<String>> = left.iter().chain(right).map(token_set).collect();
+                Box::new(TfIdfCosine::new(corpus.iter().map(Vec::as_slice)))
+            }
+            Self::EditRatio => Box::new(EditRatio),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// `metric`'s score of blocks `left` and `right`.
+fn score(metric: &dyn SimilarityMetric, left: &Block, right: &Block) -> f64 {
+    metric.similarity(&token_set(left), &token_set(right))
+}
+
+/// Multiset Jaccard index of two normalized token lists; see
+/// [`block_similarity`].
+pub(crate) fn token_jaccard(left_tokens: &[String], right_tokens: &[String]) -> f64 {
     if left_tokens.is_empty() && right_tokens.is_empty() {
         // Two empty blocks are identical.
         return 1.0;
@@ -261,11 +741,11 @@ pub fn block_similarity(left: &Block, right: &Block) -> f64 {
 
     // Use multiset Jaccard: count each normalized token.
     let mut left_counts: HashMap<&str, usize> = HashMap::new();
-    for t in &left_tokens {
+    for t in left_tokens {
         *left_counts.entry(t.as_str()).or_insert(0) += 1;
     }
     let mut right_counts: HashMap<&str, usize> = HashMap::new();
-    for t in &right_tokens {
+    for t in right_tokens {
         *right_counts.entry(t.as_str()).or_insert(0) += 1;
     }
 
@@ -286,19 +766,398 @@ pub fn block_similarity(left: &Block, right: &Block) -> f64 {
     }
 }
 
-// ---------------------------------------------------------------------------
-// Internal helpers
-// ---------------------------------------------------------------------------
+/// Pass 1: pair unmatched blocks with identical `structural_path` whose
+/// similarity reaches `min_similarity`.
+fn match_by_path(
+    left: &[Block],
+    right: &[Block],
+    metric: &dyn SimilarityMetric,
+    min_similarity: f64,
+    pairs: &mut Vec<(usize, usize, f64, bool)>,
+    left_matched: &mut HashSet<usize>,
+    right_matched: &mut HashSet<usize>,
+) {
+    let right_by_path: HashMap<&str, usize> = right
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (b.structural_path.as_str(), i))
+        .collect();
+
+    for (li, lb) in left.iter().enumerate() {
+        if left_matched.contains(&li) {
+            continue;
+        }
+        if let Some(&ri) = right_by_path.get(lb.structural_path.as_str()) {
+            if !right_matched.contains(&ri) {
+                let sim = score(metric, lb, &right[ri]);
+                if sim < min_similarity {
+                    continue;
+                }
+                pairs.push((li, ri, sim, false));
+                left_matched.insert(li);
+                right_matched.insert(ri);
+            }
+        }
+    }
+}
+
+/// Whether content match `left[li]` / `right[ri]` is a move: its path
+/// changed ([`path_moved`]) and its position by no more than
+/// [`AlignThresholds::move_distance_max`].
+fn moved(
+    left: &[Block],
+    right: &[Block],
+    li: usize,
+    ri: usize,
+    thresholds: &AlignThresholds,
+) -> bool {
+    li.abs_diff(ri) <= thresholds.move_distance_max
+        && path_moved(&left[li], &right[ri], thresholds)
+}
+
+/// Whether content match `left[li]` / `right[ri]` would be a move
+/// ([`path_moved`]) over more than [`AlignThresholds::move_distance_max`]
+/// positions; such a pair is left unmatched, as a deletion and an insertion.
+fn too_far_to_move(
+    left: &[Block],
+    right: &[Block],
+    li: usize,
+    ri: usize,
+    thresholds: &AlignThresholds,
+) -> bool {
+    li.abs_diff(ri) > thresholds.move_distance_max
+        && path_moved(&left[li], &right[ri], thresholds)
+}
+
+/// Whether a content match between `left` and `right` changed position, as
+/// far as move detection is concerned: their paths differ, and with
+/// [`AlignThresholds::ignore_renumbering`] more than the trailing ordinal
+/// differs.
+fn path_moved(left: &Block, right: &Block, thresholds: &AlignThresholds) -> bool {
+    if thresholds.ignore_renumbering {
+        ordinal_stem(&left.structural_path) != ordinal_stem(&right.structural_path)
+    } else {
+        left.structural_path != right.structural_path
+    }
+}
+
+/// `path` without its trailing ordinal and the punctuation around it:
+/// `"1.3"` → `"1."`, `"2.(b)"` → `"2."`, `"p12"` → `"p"`. A path that does not
+/// end in an ordinal is returned whole.
+fn ordinal_stem(path: &str) -> &str {
+    let trimmed = path.trim_end_matches('.');
+    if let Some(open) = trimmed.strip_suffix(')').and_then(|inner| inner.rfind('(')) {
+        let ordinal = &trimmed[open + 1..trimmed.len() - 1];
+        if !ordinal.is_empty() && ordinal.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return &trimmed[..open];
+        }
+        return path;
+    }
+    let stem = trimmed.trim_end_matches(|c: char| c.is_ascii_digit());
+    if stem.len() == trimmed.len() {
+        path
+    } else {
+        stem
+    }
+}
+
+/// Pass 3: pair unmatched blocks whose similarity reaches
+/// `thresholds.similarity`, best-scoring pairs first. Fails only when the
+/// pool of `thresholds.worker_threads` cannot be built.
+fn match_by_similarity(
+    left: &[Block],
+    right: &[Block],
+    metric: &dyn SimilarityMetric,
+    thresholds: &AlignThresholds,
+    pairs: &mut Vec<(usize, usize, f64, bool)>,
+    left_matched: &mut HashSet<usize>,
+    right_matched: &mut HashSet<usize>,
+) -> Result<()> {
+    let unmatched_left: Vec<usize> = (0..left.len())
+        .filter(|i| !left_matched.contains(i))
+        .collect();
+    let unmatched_right: Vec<usize> = (0..right.len())
+        .filter(|i| !right_matched.contains(i))
+        .collect();
+
+    // Score the candidate pairs (in parallel): all of them for ordinary
+    // documents, those sharing shingles when that would be too many. Each
+    // block is tokenized once, not once per pair.
+    let threads = thresholds.worker_threads;
+    let left_tokens = token_sets(left, &unmatched_left, threads)?;
+    let right_tokens = token_sets(right, &unmatched_right, threads)?;
+    let to_score = similarity_candidates(
+        left,
+        right,
+        &unmatched_left,
+        &unmatched_right,
+        thresholds.max_candidates,
+        threads,
+    )?;
+    #[cfg(feature = "parallel")]
+    let pair_iter = to_score.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let pair_iter = to_score.iter();
+    let mut candidates: Vec<(usize, usize, f64)> = pool::install(threads, || {
+        pair_iter
+            .filter_map(|&(li, ri)| {
+                let sim = metric.similarity(&left_tokens[&li], &right_tokens[&ri]);
+                (sim >= thresholds.similarity).then_some((li, ri, sim))
+            })
+            .collect()
+    })?;
+
+    // Greedy best-first matching: sort by descending similarity, then pick
+    // the highest-scoring pair first, removing used indices. Ties go to the
+    // earlier left block, then the earlier right block, however the pairs
+    // were scored.
+    candidates.sort_by(|a, b| {
+        b.2.partial_cmp(&a.2)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+            .then(a.1.cmp(&b.1))
+    });
+
+    let mut sim_left_used: HashSet<usize> = HashSet::new();
+    let mut sim_right_used: HashSet<usize> = HashSet::new();
+
+    for (li, ri, sim) in candidates {
+        if sim_left_used.contains(&li) || sim_right_used.contains(&ri) {
+            continue;
+        }
+        if too_far_to_move(left, right, li, ri, thresholds) {
+            continue;
+        }
+        let is_move = moved(left, right, li, ri, thresholds) && sim >= thresholds.moved;
+        pairs.push((li, ri, sim, is_move));
+        left_matched.insert(li);
+        right_matched.insert(ri);
+        sim_left_used.insert(li);
+        sim_right_used.insert(ri);
+    }
+    Ok(())
+}
+
+/// Pairs of `unmatched_left` x `unmatched_right` worth scoring, at most
+/// `limit` of them, ranked on `threads` workers: every pair when there are
+/// no more than `limit`;
+/// otherwise, for each left block, the right blocks sharing the most
+/// [`SHINGLE_LEN`]-token shingles with it (ignoring shingles of more than
+/// [`MAX_SHINGLE_BUCKET`] blocks), an equal share of `limit` each.
+///
+/// Blocks similar enough to match share most of their shingles, so few real
+/// matches are lost; ties go to the earlier right block, keeping the result
+/// deterministic.
+fn similarity_candidates(
+    left: &[Block],
+    right: &[Block],
+    unmatched_left: &[usize],
+    unmatched_right: &[usize],
+    limit: usize,
+    threads: usize,
+) -> Result<Vec<(usize, usize)>> {
+    if unmatched_left.len().saturating_mul(unmatched_right.len()) <= limit {
+        return Ok(unmatched_left
+            .iter()
+            .flat_map(|&li| unmatched_right.iter().map(move |&ri| (li, ri)))
+            .collect());
+    }
+
+    #[cfg(feature = "parallel")]
+    let right_iter = unmatched_right.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let right_iter = unmatched_right.iter();
+    let right_shingles: Vec<(usize, HashSet<u64>)> =
+        pool::install(threads, || right_iter.map(|&ri| (ri, shingles(&right[ri]))).collect())?;
+    let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (ri, block_shingles) in right_shingles {
+        for shingle in block_shingles {
+            buckets.entry(shingle).or_default().push(ri);
+        }
+    }
+    buckets.retain(|_, blocks| blocks.len() <= MAX_SHINGLE_BUCKET);
+
+    // Each left block ranks its right blocks independently; the ranked
+    // lists are concatenated in left order.
+    let per_left = (limit / unmatched_left.len()).max(1);
+    let rank = |li: usize| -> Vec<(usize, usize)> {
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for shingle in shingles(&left[li]) {
+            for &ri in buckets.get(&shingle).into_iter().flatten() {
+                *shared.entry(ri).or_insert(0) += 1;
+            }
+        }
+        let mut ranked: Vec<(usize, usize)> = shared.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.into_iter().take(per_left).map(|(ri, _)| (li, ri)).collect()
+    };
+    #[cfg(feature = "parallel")]
+    let left_iter = unmatched_left.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let left_iter = unmatched_left.iter();
+    let ranked: Vec<Vec<(usize, usize)>> =
+        pool::install(threads, || left_iter.map(|&li| rank(li)).collect())?;
+    let mut candidates: Vec<(usize, usize)> = ranked.into_iter().flatten().collect();
+    candidates.truncate(limit);
+    Ok(candidates)
+}
+
+/// Normalized tokens of each of `blocks[indices]`, by index, computed in
+/// parallel on `threads` workers.
+fn token_sets(
+    blocks: &[Block],
+    indices: &[usize],
+    threads: usize,
+) -> Result<HashMap<usize, Vec<String>>> {
+    #[cfg(feature = "parallel")]
+    let iter = indices.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let iter = indices.iter();
+    pool::install(threads, || iter.map(|&i| (i, token_set(&blocks[i]))).collect())
+}
+
+/// Hashes of the distinct [`SHINGLE_LEN`]-token windows of `block`'s
+/// normalized tokens; a shorter block is one shingle of all its tokens.
+fn shingles(block: &Block) -> HashSet<u64> {
+    let tokens = token_set(block);
+    let hash = |window: &[String]| {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        window.hash(&mut hasher);
+        hasher.finish()
+    };
+    if tokens.len() < SHINGLE_LEN {
+        return HashSet::from([hash(&tokens)]);
+    }
+    tokens.windows(SHINGLE_LEN).map(hash).collect()
+}
+
+/// For every block of `ones` that is unmatched or in a non-move pair, find
+/// the run of adjacent `others` blocks whose concatenated tokens it
+/// resembles most. A run of two or more blocks that reaches `threshold` and
+/// beats the block's current pair replaces that pair and is returned as
+/// `(one, run, similarity)`.
+///
+/// `pairs` holds `(one, other, similarity, is_move)`. A run may take over
+/// blocks in weak pairs (non-move, below `weak_below`, as left by a path
+/// match between unrelated blocks); their partners are released. A paired
+/// block only grows its run around its current counterpart; an unmatched
+/// block tries every free `others` block as a seed.
+fn detect_runs(
+    ones: &[Block],
+    others: &[Block],
+    threshold: f64,
+    weak_below: f64,
+    pairs: &mut Vec<(usize, usize, f64, bool)>,
+    ones_matched: &mut HashSet<usize>,
+    others_matched: &mut HashSet<usize>,
+) -> Vec<(usize, Vec<usize>, f64)> {
+    let mut by_one: HashMap<usize, (usize, f64, bool)> =
+        pairs.iter().map(|&(o, t, s, m)| (o, (t, s, m))).collect();
+    let mut by_other: HashMap<usize, usize> = pairs.iter().map(|&(o, t, _, _)| (t, o)).collect();
+    let other_tokens: Vec<Vec<String>> = others.iter().map(token_set).collect();
+
+    let mut found = Vec::new();
+    for (oi, one) in ones.iter().enumerate() {
+        let paired = by_one.get(&oi).copied();
+        match paired {
+            Some((_, _, true)) => continue,
+            // Nothing beats an identical counterpart.
+            Some((_, sim, _)) if sim >= 1.0 => continue,
+            None if ones_matched.contains(&oi) => continue,
+            _ => {}
+        }
+
+        let free = |t: usize| {
+            !others_matched.contains(&t)
+                || by_other.get(&t).is_some_and(|o| {
+                    let (_, sim, is_move) = by_one[o];
+                    !is_move && sim < weak_below
+                })
+        };
+        let seeds: Vec<usize> = match paired {
+            Some((seed, _, _)) => vec![seed],
+            None => (0..others.len()).filter(|&t| free(t)).collect(),
+        };
+
+        let one_tokens = token_set(one);
+        let mut best: Option<(Range<usize>, f64)> = None;
+        for seed in seeds {
+            if token_jaccard(&one_tokens, &other_tokens[seed]) == 0.0 {
+                continue;
+            }
+            let (run, sim) = grow_run(&one_tokens, &other_tokens, seed, |t| t == seed || free(t));
+            if run.len() >= 2 && sim >= threshold && best.as_ref().is_none_or(|(_, b)| sim > *b) {
+                best = Some((run, sim));
+            }
+        }
+
+        let Some((run, sim)) = best else {
+            continue;
+        };
+        if paired.is_some_and(|(_, pair_sim, _)| sim <= pair_sim) {
+            continue;
+        }
+        by_one.remove(&oi);
+        for t in run.clone() {
+            if let Some(o) = by_other.remove(&t) {
+                by_one.remove(&o);
+                ones_matched.remove(&o);
+            }
+            others_matched.insert(t);
+        }
+        ones_matched.insert(oi);
+        found.push((oi, run.collect(), sim));
+    }
+
+    *pairs = by_one.into_iter().map(|(o, (t, s, m))| (o, t, s, m)).collect();
+    pairs.sort_unstable_by_key(|p| p.0);
+    found
+}
+
+/// Grow a run of adjacent blocks around `seed`, one `free` neighbour at a
+/// time, for as long as adding the neighbour raises the similarity of the
+/// run's concatenated tokens to `one`.
+fn grow_run(
+    one: &[String],
+    others: &[Vec<String>],
+    seed: usize,
+    free: impl Fn(usize) -> bool,
+) -> (Range<usize>, f64) {
+    let concat = |run: &Range<usize>| -> Vec<String> { others[run.clone()].concat() };
+    let mut run = seed..seed + 1;
+    let mut sim = token_jaccard(one, &others[seed]);
+    loop {
+        let mut next: Option<(Range<usize>, f64)> = None;
+        let before = (run.start > 0).then(|| (run.start - 1, run.start - 1..run.end));
+        let after = (run.end < others.len()).then(|| (run.end, run.start..run.end + 1));
+        for (added, candidate) in [before, after].into_iter().flatten() {
+            if !free(added) {
+                continue;
+            }
+            let candidate_sim = token_jaccard(one, &concat(&candidate));
+            if candidate_sim > next.as_ref().map_or(sim, |(_, s)| *s) {
+                next = Some((candidate, candidate_sim));
+            }
+        }
+        match next {
+            Some((grown, grown_sim)) => {
+                run = grown;
+                sim = grown_sim;
+            }
+            None => return (run, sim),
+        }
+    }
+}
 
 /// Extract normalized token strings from a block.
 /// If the block's token list is populated, use that; otherwise tokenize
 /// the canonical text on the fly.
-fn token_set(block: &Block) -> Vec<String> {
+pub(crate) fn token_set(block: &Block) -> Vec<String> {
     if !block.tokens.is_empty() {
         block
             .tokens
             .iter()
-            .filter(|t| !matches!(t.kind, rt_core::TokenKind::Whitespace))
+            .filter(|t| !matches!(t.kind, rt_model::TokenKind::Whitespace))
             .map(|t| t.normalized.clone())
             .collect()
     } else {
@@ -342,14 +1201,26 @@ fn lcs_align(
         return Vec::new();
     }
 
+    // Intern the texts so the O(n*m) table and the backtracking compare
+    // integers rather than strings.
+    let mut interned: HashMap<&str, usize> = HashMap::new();
+    let text_ids: Vec<usize> = left_indices
+        .iter()
+        .map(|&li| &left[li])
+        .chain(right_indices.iter().map(|&ri| &right[ri]))
+        .map(|block| {
+            let next = interned.len();
+            *interned.entry(block.canonical_text.as_str()).or_insert(next)
+        })
+        .collect();
+    let (left_text, right_text) = text_ids.split_at(n);
+
     // DP table: dp[i][j] = LCS length for left[..i], right[..j]
     let mut dp = vec![vec![0usize; m + 1]; n + 1];
 
     for i in 1..=n {
         for j in 1..=m {
-            let li = left_indices[i - 1];
-            let ri = right_indices[j - 1];
-            if left[li].canonical_text == right[ri].canonical_text {
+            if left_text[i - 1] == right_text[j - 1] {
                 dp[i][j] = dp[i - 1][j - 1] + 1;
             } else {
                 dp[i][j] = dp[i - 1][j].max(dp[i][j - 1]);
@@ -362,10 +1233,8 @@ fn lcs_align(
     let mut i = n;
     let mut j = m;
     while i > 0 && j > 0 {
-        let li = left_indices[i - 1];
-        let ri = right_indices[j - 1];
-        if left[li].canonical_text == right[ri].canonical_text {
-            pairs.push((li, ri));
+        if left_text[i - 1] == right_text[j - 1] {
+            pairs.push((left_indices[i - 1], right_indices[j - 1]));
             i -= 1;
             j -= 1;
         } else if dp[i - 1][j] >= dp[i][j - 1] {
@@ -385,7 +1254,7 @@ fn lcs_align(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rt_core::{Block, BlockType};
+    use rt_model::{Block, BlockType};
     use uuid::Uuid;
 
     fn doc_id() -> Uuid {
@@ -469,6 +1338,290 @@ mod tests {
         assert!(matches!(alignments[0], BlockAlignment::Moved { .. }));
     }
 
+    #[test]
+    fn thresholds_control_matching_and_moves() {
+        let doc = doc_id();
+        // 4 of 6 distinct tokens shared: Jaccard ≈ 0.67.
+        let left = vec![make_block(doc, "1.1", "the lender may assign rights", 0)];
+        let right = vec![make_block(doc, "3.1", "the lender may transfer rights", 0)];
+
+        let strict = align_blocks(&left, &right);
+        assert!(matches!(strict[0], BlockAlignment::DeletedLeft { .. }));
+
+        let loose = AlignThresholds {
+            similarity: 0.6,
+            moved: 0.6,
+            ..AlignThresholds::default()
+        };
+        let alignments = align_blocks_with(&left, &right, &loose);
+        assert!(matches!(alignments[0], BlockAlignment::Moved { .. }));
+
+        let no_moves = AlignThresholds {
+            similarity: 0.6,
+            moved: 1.0,
+            ..AlignThresholds::default()
+        };
+        let alignments = align_blocks_with(&left, &right, &no_moves);
+        assert!(matches!(alignments[0], BlockAlignment::Matched { .. }));
+    }
+
+    #[test]
+    fn ordinal_stem_strips_trailing_ordinals() {
+        assert_eq!(ordinal_stem("1.3"), "1.");
+        assert_eq!(ordinal_stem("12."), "");
+        assert_eq!(ordinal_stem("2.(b)"), "2.");
+        assert_eq!(ordinal_stem("p12"), "p");
+        assert_eq!(ordinal_stem("1.3.s2"), "1.3.s");
+        assert_eq!(ordinal_stem("recitals"), "recitals");
+        assert_eq!(ordinal_stem("a (see below)"), "a (see below)");
+    }
+
+    #[test]
+    fn inserted_clause_does_not_renumber_into_moves() {
+        let doc = doc_id();
+        let texts = [
+            "the borrower shall repay the loan in monthly instalments",
+            "interest accrues daily at the agreed margin over base rate",
+            "the lender may assign its rights under this agreement",
+            "notices must be delivered in writing to the registered office",
+        ];
+        let left: Vec<Block> = texts
+            .iter()
+            .enumerate()
+            .map(|(i, t)| make_block(doc, &format!("1.{}", i + 1), t, i as i32))
+            .collect();
+        let mut right_texts = texts.to_vec();
+        right_texts.insert(1, "a default occurs if any payment is more than ten days late");
+        let right: Vec<Block> = right_texts
+            .iter()
+            .enumerate()
+            .map(|(i, t)| make_block(doc, &format!("1.{}", i + 1), t, i as i32))
+            .collect();
+
+        // (moved, inserted, matched to a different clause)
+        let count = |alignments: &[BlockAlignment]| {
+            let mut counts = (0, 0, 0);
+            for alignment in alignments {
+                match alignment {
+                    BlockAlignment::Moved { .. } => counts.0 += 1,
+                    BlockAlignment::InsertedRight { .. } => counts.1 += 1,
+                    BlockAlignment::Matched { left: l, right: r, .. }
+                        if left[*l].display_text != right[*r].display_text =>
+                    {
+                        counts.2 += 1
+                    }
+                    _ => {}
+                }
+            }
+            counts
+        };
+
+        // Path-first alignment pairs each shifted clause with its successor.
+        let strict = align_blocks(&left, &right);
+        assert_eq!(count(&strict).2, 3);
+
+        let tolerant = AlignThresholds {
+            ignore_renumbering: true,
+            ..AlignThresholds::default()
+        };
+        let alignments = align_blocks_with(&left, &right, &tolerant);
+        assert_eq!(count(&alignments), (0, 1, 0));
+    }
+
+    #[test]
+    fn moves_farther_than_the_distance_limit_are_deleted_and_inserted() {
+        let doc = doc_id();
+        let text = "the lender may assign its rights under this agreement";
+        let mut left = vec![make_block(doc, "1.1", text, 0)];
+        let mut right = Vec::new();
+        for i in 0..4 {
+            let filler = format!("schedule {i} lists the properties charged");
+            left.push(make_block(doc, &format!("2.{i}"), &filler, i + 1));
+            right.push(make_block(doc, &format!("2.{i}"), &filler, i));
+        }
+        right.push(make_block(doc, "3.1", text, 4));
+
+        let alignments = align_blocks(&left, &right);
+        assert!(matches!(alignments[0], BlockAlignment::Moved { left: 0, right: 4, .. }));
+
+        // The block moved 4 positions: a move at a limit of 4, a deletion
+        // and an insertion just beyond it.
+        let at_limit = AlignThresholds {
+            move_distance_max: 4,
+            ..AlignThresholds::default()
+        };
+        let alignments = align_blocks_with(&left, &right, &at_limit);
+        assert!(matches!(alignments[0], BlockAlignment::Moved { left: 0, right: 4, .. }));
+
+        let near = AlignThresholds {
+            move_distance_max: 3,
+            ..AlignThresholds::default()
+        };
+        let alignments = align_blocks_with(&left, &right, &near);
+        assert!(matches!(alignments[0], BlockAlignment::DeletedLeft { left: 0 }));
+        assert!(matches!(alignments.last(), Some(BlockAlignment::InsertedRight { right: 4 })));
+        assert_eq!(alignments.len(), 6);
+    }
+
+    #[test]
+    fn renumbering_tolerance_still_reports_moves_between_sections() {
+        let doc = doc_id();
+        let text = "the lender may assign its rights under this agreement";
+        let left = vec![make_block(doc, "1.3", text, 0)];
+        let right = vec![make_block(doc, "4.1", text, 0)];
+        let tolerant = AlignThresholds {
+            ignore_renumbering: true,
+            ..AlignThresholds::default()
+        };
+        let alignments = align_blocks_with(&left, &right, &tolerant);
+        assert!(matches!(alignments[0], BlockAlignment::Moved { .. }));
+    }
+
+    #[test]
+    fn capped_similarity_pass_matches_through_shared_shingles() {
+        let doc = doc_id();
+        let subjects = ["borrower", "lender", "guarantor", "agent", "trustee", "arranger"];
+        let duties = ["repay", "notify", "indemnify", "insure", "report", "assign"];
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for (i, subject) in subjects.iter().enumerate() {
+            for (j, duty) in duties.iter().enumerate() {
+                let n = i * duties.len() + j;
+                let text =
+                    format!("the {subject} shall {duty} within {n} days of each written request");
+                left.push(make_block(doc, &format!("1.{n}"), &text, n as i32));
+                // Renumbered and lightly edited, so only pass 3 can pair them.
+                let edited = format!("{text} made");
+                right.push(make_block(doc, &format!("2.{n}"), &edited, n as i32));
+            }
+        }
+        right.reverse();
+
+        let capped = AlignThresholds {
+            max_candidates: left.len() * 3,
+            ..AlignThresholds::default()
+        };
+        let unmatched: Vec<usize> = (0..left.len()).collect();
+        let candidates =
+            similarity_candidates(&left, &right, &unmatched, &unmatched, capped.max_candidates, 0)
+                .unwrap();
+        assert!(candidates.len() <= capped.max_candidates);
+
+        let alignments = align_blocks_with(&left, &right, &capped);
+        let paired = alignments
+            .iter()
+            .filter(|a| match a {
+                BlockAlignment::Moved { left: l, right: r, .. }
+                | BlockAlignment::Matched { left: l, right: r, .. } => *l + *r == left.len() - 1,
+                _ => false,
+            })
+            .count();
+        assert_eq!(paired, left.len());
+    }
+
+    #[test]
+    fn similarity_ties_go_to_the_earliest_blocks() {
+        let doc = doc_id();
+        let text = "the borrower shall repay the principal in full";
+        let left = vec![make_block(doc, "1.1", text, 0), make_block(doc, "1.2", text, 1)];
+        let right = vec![
+            make_block(doc, "7.1", &format!("{text} today"), 0),
+            make_block(doc, "7.2", &format!("{text} today"), 1),
+        ];
+        for _ in 0..8 {
+            let alignments = align_blocks(&left, &right);
+            assert!(
+                matches!(alignments[0], BlockAlignment::Matched { left: 0, right: 0, .. })
+                    || matches!(alignments[0], BlockAlignment::Moved { left: 0, right: 0, .. }),
+                "{alignments:?}"
+            );
+        }
+    }
+
+    fn with_splits() -> AlignThresholds {
+        AlignThresholds {
+            split: Some(SPLIT_THRESHOLD),
+            ..AlignThresholds::default()
+        }
+    }
+
+    #[test]
+    fn split_detected_against_adjacent_blocks() {
+        let doc = doc_id();
+        let left = vec![
+            make_block(doc, "1.1", "definitions clause text here", 0),
+            make_block(
+                doc,
+                "1.2",
+                "the borrower shall repay the loan. interest accrues monthly in arrears",
+                1,
+            ),
+        ];
+        let right = vec![
+            make_block(doc, "1.1", "definitions clause text here", 0),
+            make_block(doc, "1.2", "the borrower shall repay the loan.", 1),
+            make_block(doc, "1.3", "interest accrues monthly in arrears", 2),
+        ];
+
+        // Without split detection: a modification plus an insertion.
+        let plain = align_blocks(&left, &right);
+        assert!(plain.iter().any(|a| matches!(a, BlockAlignment::InsertedRight { right: 2 })));
+
+        let alignments = align_blocks_with(&left, &right, &with_splits());
+        assert_eq!(alignments.len(), 2);
+        assert!(matches!(alignments[0], BlockAlignment::Matched { left: 0, right: 0, .. }));
+        match &alignments[1] {
+            BlockAlignment::Split { left: 1, right, similarity } => {
+                assert_eq!(right, &vec![1, 2]);
+                assert!((similarity - 1.0).abs() < 1e-9);
+            }
+            other => panic!("expected a split, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn merge_detected_against_adjacent_blocks() {
+        let doc = doc_id();
+        let left = vec![
+            make_block(doc, "1.1", "the borrower shall repay the loan.", 0),
+            make_block(doc, "1.2", "interest accrues monthly in arrears", 1),
+            make_block(doc, "1.3", "termination rights described here", 2),
+        ];
+        let right = vec![
+            make_block(
+                doc,
+                "1.1",
+                "the borrower shall repay the loan. interest accrues monthly",
+                0,
+            ),
+            make_block(doc, "1.2", "termination rights described here", 1),
+        ];
+
+        let alignments = align_blocks_with(&left, &right, &with_splits());
+        assert_eq!(alignments.len(), 2);
+        match &alignments[0] {
+            BlockAlignment::Merged { left, right: 0, similarity } => {
+                assert_eq!(left, &vec![0, 1]);
+                assert!(*similarity >= SPLIT_THRESHOLD);
+            }
+            other => panic!("expected a merge, got {other:?}"),
+        }
+        assert!(matches!(alignments[1], BlockAlignment::Moved { left: 2, right: 1, .. }));
+    }
+
+    #[test]
+    fn unrelated_insertion_is_not_absorbed_into_a_split() {
+        let doc = doc_id();
+        let left = vec![make_block(doc, "1.1", "the borrower shall repay the loan", 0)];
+        let right = vec![
+            make_block(doc, "1.1", "the borrower shall promptly repay the loan", 0),
+            make_block(doc, "1.2", "a new indemnity clause is added", 1),
+        ];
+        let alignments = align_blocks_with(&left, &right, &with_splits());
+        assert!(matches!(alignments[0], BlockAlignment::Matched { left: 0, right: 0, .. }));
+        assert!(matches!(alignments[1], BlockAlignment::InsertedRight { right: 1 }));
+    }
+
     #[test]
     fn multiple_blocks_ordered() {
         let doc = doc_id();
@@ -511,6 +1664,86 @@ mod tests {
         assert!(sim < 0.1, "disjoint blocks should have near-zero similarity");
     }
 
+    fn words(text: &str) -> Vec<String> {
+        text.split(' ').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn tfidf_cosine_discounts_shared_boilerplate() {
+        let corpus = [
+            words("the borrower shall repay"),
+            words("the lender shall assign"),
+            words("the agent shall notify"),
+        ];
+        let metric = TfIdfCosine::new(corpus.iter().map(Vec::as_slice));
+        let boilerplate_only = metric.similarity(&corpus[0], &corpus[1]);
+        assert!(boilerplate_only < token_jaccard(&corpus[0], &corpus[1]));
+        assert!((metric.similarity(&corpus[2], &corpus[2]) - 1.0).abs() < 1e-9);
+        assert_eq!(metric.similarity(&corpus[0], &[]), 0.0);
+    }
+
+    #[test]
+    fn edit_ratio_counts_token_edits() {
+        let ratio =
+            EditRatio.similarity(&words("pay within ten days"), &words("pay within five days"));
+        assert!((ratio - 0.75).abs() < 1e-9);
+        // Reordering costs edits, unlike with the set-based metrics.
+        let swapped = EditRatio.similarity(&words("notify the agent"), &words("the agent notify"));
+        assert!(swapped < 1.0);
+        assert_eq!(EditRatio.similarity(&[], &[]), 1.0);
+    }
+
+    #[test]
+    fn stopwords_are_stripped_unless_nothing_else_is_left() {
+        let stopwords = Stopwords::legal();
+        assert_eq!(stopwords.strip(&words("the borrower shall repay")), words("borrower repay"));
+        assert_eq!(stopwords.strip(&words("as hereby")), words("as hereby"));
+        assert!(Stopwords::new(["Herein"]).contains("herein"));
+    }
+
+    #[test]
+    fn ignoring_stopwords_stops_boilerplate_from_matching_clauses() {
+        let doc = doc_id();
+        let left = vec![make_block(doc, "1.1", "the borrower shall pay the fees of the agent", 0)];
+        let right = vec![make_block(doc, "4.1", "the lender shall pay the fees of the agent", 0)];
+        let alignments = align_blocks(&left, &right);
+        assert!(matches!(alignments[0], BlockAlignment::Matched { left: 0, right: 0, .. }));
+
+        let thresholds = AlignThresholds {
+            stopwords: Some(Stopwords::legal()),
+            ..AlignThresholds::default()
+        };
+        let alignments = align_blocks_with(&left, &right, &thresholds);
+        assert!(matches!(alignments[0], BlockAlignment::DeletedLeft { left: 0 }));
+    }
+
+    #[test]
+    fn configured_metric_decides_content_matches() {
+        let doc = doc_id();
+        let mut left = vec![make_block(doc, "1.1", "the borrower shall repay the loan", 0)];
+        let mut right = vec![make_block(doc, "5.1", "the guarantor shall repay the loan", 0)];
+        // Unchanged clauses make everything but the parties boilerplate.
+        for (i, party) in ["agent", "arranger", "trustee"].iter().enumerate() {
+            let text = format!("the {party} shall repay the loan fees");
+            left.push(make_block(doc, &format!("2.{i}"), &text, i as i32 + 1));
+            right.push(make_block(doc, &format!("2.{i}"), &text, i as i32 + 1));
+        }
+        let matched = |metric| {
+            let thresholds = AlignThresholds {
+                metric,
+                ..AlignThresholds::default()
+            };
+            let alignments = align_blocks_with(&left, &right, &thresholds);
+            !alignments
+                .iter()
+                .any(|a| matches!(a, BlockAlignment::DeletedLeft { left: 0 }))
+        };
+        assert!(matched(SimilarityKind::Jaccard));
+        assert!(!matched(SimilarityKind::TfidfCosine));
+        let kind: SimilarityKind = serde_json::from_str(r#""edit_ratio""#).unwrap();
+        assert_eq!(kind, SimilarityKind::EditRatio);
+    }
+
     #[test]
     fn block_similarity_partial_overlap() {
         let doc = doc_id();
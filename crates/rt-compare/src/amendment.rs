@@ -0,0 +1,589 @@
+//! Amendment documents.
+//!
+//! An amendment does not restate the contract it changes; it instructs:
+//! "Section 3.2 is deleted and replaced with the following: …", "Section 4.1
+//! is deleted in its entirety", "Section 6.1 is amended by deleting “thirty
+//! (30)” and replacing it with “sixty (60)”", "A new Section 5.3 is inserted
+//! after Section 5.2: …". [`parse_amendment`] reads those instructions,
+//! [`apply_amendment`] applies them to the base document to build the
+//! as-amended view, and [`compare_amendment`] compares the base document
+//! against that view, tracing every change back to the amendment block that
+//! made it.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::{Block, BlockType, ClauseHasher};
+
+use crate::result::{CompareResult, DeltaKind};
+use crate::tokenize::tokenize;
+use crate::worker::{flatten_blocks, CompareEngine};
+use crate::xref::{extract_cross_references, normalize_path};
+
+/// Phrases (lowercase) marking an instruction that rewrites text inside a
+/// clause rather than the whole clause.
+const SUBSTITUTION_MARKERS: &[&str] =
+    &["amended by", "by deleting", "by replacing", "by substituting", "by striking"];
+
+/// Phrases (lowercase) marking an instruction that adds a new clause.
+const INSERTION_MARKERS: &[&str] = &["inserted", "added", "insert ", "add "];
+
+/// Phrases (lowercase) marking an instruction that replaces a whole clause.
+const REPLACEMENT_MARKERS: &[&str] = &[
+    "replaced",
+    "restated",
+    "amended to read",
+    "read as follows",
+    "reads as follows",
+];
+
+/// Phrases (lowercase) marking an instruction that removes a clause.
+const DELETION_MARKERS: &[&str] = &["deleted", "removed", "struck", "stricken"];
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// What an amendment instruction does to its target clause.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AmendmentAction {
+    /// The clause (and its sub-clauses) is replaced by `text`.
+    Replace,
+    /// The clause (and its sub-clauses) is deleted.
+    Delete,
+    /// A new clause with `text` is inserted after the clause at `after`, or
+    /// at the end of its parent when `after` is `None`.
+    Insert { after: Option<String> },
+    /// The first occurrence of `from` in the clause is replaced by `to`
+    /// (empty to delete the words).
+    Substitute { from: String, to: String },
+}
+
+/// One instruction read from an amendment document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmendmentInstruction {
+    /// Amendment block holding the instruction.
+    pub amendment_block_id: Uuid,
+    /// The instruction as written.
+    pub text: String,
+    #[serde(flatten)]
+    pub action: AmendmentAction,
+    /// Normalized structural path of the clause the instruction changes (for
+    /// an insertion, the path of the new clause).
+    pub target: String,
+    /// New clause text for `replace` and `insert`: quoted in the
+    /// instruction, or the block following it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_text: Option<String>,
+}
+
+/// An instruction [`apply_amendment`] could not apply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnappliedInstruction {
+    /// Index into the instruction list.
+    pub instruction: usize,
+    pub reason: String,
+}
+
+/// The base document with an amendment's instructions applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmendedView {
+    /// The as-amended document as a flat block list in document order.
+    /// Untouched clauses keep their base block; a replaced, rewritten or
+    /// inserted clause takes the id and document of the amendment block that
+    /// changed it.
+    pub blocks: Vec<Block>,
+    pub instructions: Vec<AmendmentInstruction>,
+    pub unapplied: Vec<UnappliedInstruction>,
+    /// View block id → index of the instruction that produced it.
+    #[serde(skip)]
+    produced_by: HashMap<Uuid, usize>,
+    /// Base block id → index of the instruction that removed it.
+    #[serde(skip)]
+    removed_by: HashMap<Uuid, usize>,
+}
+
+/// Links one changed delta to the instruction that caused it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmendmentTrace {
+    /// Index of the delta in [`CompareResult::deltas`].
+    pub delta: usize,
+    /// Index into the instruction list.
+    pub instruction: usize,
+    /// Amendment block holding the instruction.
+    pub amendment_block_id: Uuid,
+}
+
+/// A comparison of a base document against its as-amended view.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmendmentCompareResult {
+    /// Base document (left) against the as-amended view (right). The right
+    /// document is the amendment; see [`AmendedView::blocks`] for which
+    /// block ids the right side uses.
+    pub compare: CompareResult,
+    pub instructions: Vec<AmendmentInstruction>,
+    pub traces: Vec<AmendmentTrace>,
+    pub unapplied: Vec<UnappliedInstruction>,
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Read the instructions in amendment `blocks` (and their children), in
+/// document order.
+///
+/// A block is an instruction when it references a section and says what
+/// happens to it; other blocks (recitals, signature blocks) are ignored. A
+/// replacement or insertion whose new text is not quoted in the instruction
+/// takes the text of the following block, which is then not read as an
+/// instruction itself.
+pub fn parse_amendment(blocks: &[Block]) -> Vec<AmendmentInstruction> {
+    let flat = flatten_blocks(blocks);
+    let mut instructions = Vec::new();
+    let mut i = 0;
+    while i < flat.len() {
+        let block = &flat[i];
+        i += 1;
+        let Some(mut instruction) = instruction_in(block) else {
+            continue;
+        };
+        let needs_text = matches!(
+            instruction.action,
+            AmendmentAction::Replace | AmendmentAction::Insert { .. }
+        );
+        if needs_text && instruction.new_text.is_none() {
+            if let Some(next) = flat.get(i) {
+                instruction.new_text = Some(next.canonical_text.clone());
+                i += 1;
+            }
+        }
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+/// Apply the instructions in `amendment_blocks` (see [`parse_amendment`]) to
+/// `base_blocks`, in order, rehashing every changed clause with `hasher`.
+///
+/// Targets are matched on normalized structural path against the view as
+/// amended so far. An instruction whose target is missing, whose words to
+/// substitute do not occur, or which lacks new text is reported in
+/// [`AmendedView::unapplied`] and otherwise skipped.
+pub fn apply_amendment(
+    base_blocks: &[Block],
+    amendment_blocks: &[Block],
+    hasher: &ClauseHasher,
+) -> AmendedView {
+    let instructions = parse_amendment(amendment_blocks);
+    let amendment_doc_id = amendment_blocks.first().map(|b| b.document_id);
+    let mut view = AmendedView {
+        blocks: flatten_blocks(base_blocks),
+        instructions: Vec::new(),
+        unapplied: Vec::new(),
+        produced_by: HashMap::new(),
+        removed_by: HashMap::new(),
+    };
+    let mut used_ids: HashSet<Uuid> = view.blocks.iter().map(|b| b.id).collect();
+    for (index, instruction) in instructions.iter().enumerate() {
+        let outcome = view.apply(index, instruction, hasher, &mut used_ids);
+        if let Err(reason) = outcome {
+            view.unapplied.push(UnappliedInstruction { instruction: index, reason });
+        }
+    }
+    if let Some(doc_id) = amendment_doc_id {
+        for block in &mut view.blocks {
+            if view.produced_by.contains_key(&block.id) {
+                block.document_id = doc_id;
+            }
+        }
+    }
+    view.instructions = instructions;
+    view
+}
+
+/// Compare `base_blocks` against the view `amendment_blocks` produce when
+/// applied to them (see [`apply_amendment`]), and trace each changed delta
+/// to its instruction.
+pub fn compare_amendment(
+    engine: &CompareEngine,
+    base_doc_id: Uuid,
+    amendment_doc_id: Uuid,
+    base_blocks: &[Block],
+    amendment_blocks: &[Block],
+    hasher: &ClauseHasher,
+) -> AmendmentCompareResult {
+    let view = apply_amendment(base_blocks, amendment_blocks, hasher);
+    let compare = engine.compare(base_doc_id, amendment_doc_id, base_blocks, &view.blocks);
+    let mut traces = Vec::new();
+    for (index, delta) in compare.deltas.iter().enumerate() {
+        if delta.kind == DeltaKind::Unchanged {
+            continue;
+        }
+        let produced = delta.right_block_id.and_then(|id| view.produced_by.get(&id));
+        let removed = delta.left_block_id.and_then(|id| view.removed_by.get(&id));
+        if let Some(&instruction) = produced.or(removed) {
+            traces.push(AmendmentTrace {
+                delta: index,
+                instruction,
+                amendment_block_id: view.instructions[instruction].amendment_block_id,
+            });
+        }
+    }
+    AmendmentCompareResult {
+        compare,
+        instructions: view.instructions,
+        traces,
+        unapplied: view.unapplied,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Applying instructions
+// ---------------------------------------------------------------------------
+
+impl AmendedView {
+    fn apply(
+        &mut self,
+        index: usize,
+        instruction: &AmendmentInstruction,
+        hasher: &ClauseHasher,
+        used_ids: &mut HashSet<Uuid>,
+    ) -> Result<(), String> {
+        let mut new_id = || {
+            let id = instruction.amendment_block_id;
+            if used_ids.insert(id) {
+                id
+            } else {
+                let id = Uuid::new_v4();
+                used_ids.insert(id);
+                id
+            }
+        };
+        let missing = || format!("no clause {} in the base document", instruction.target);
+        let no_text = || "the instruction has no new text".to_string();
+
+        match &instruction.action {
+            AmendmentAction::Replace => {
+                let at = self.find(&instruction.target).ok_or_else(missing)?;
+                let text = instruction.new_text.as_deref().ok_or_else(no_text)?;
+                self.remove_descendants(at, index);
+                let old_id = self.blocks[at].id;
+                self.removed_by.insert(old_id, index);
+                let block = &mut self.blocks[at];
+                block.id = new_id();
+                set_text(block, text, hasher);
+                self.produced_by.insert(block.id, index);
+            }
+            AmendmentAction::Delete => {
+                let at = self.find(&instruction.target).ok_or_else(missing)?;
+                self.remove_descendants(at, index);
+                let removed = self.blocks.remove(at);
+                self.removed_by.insert(removed.id, index);
+            }
+            AmendmentAction::Insert { after } => {
+                let text = instruction.new_text.as_deref().ok_or_else(no_text)?;
+                let (at, parent_id) = match after {
+                    Some(after) => {
+                        let anchor = self
+                            .find(after)
+                            .ok_or_else(|| format!("no clause {after} in the base document"))?;
+                        (self.subtree_end(anchor), self.blocks[anchor].parent_id)
+                    }
+                    None => self.end_of_parent(&instruction.target),
+                };
+                // The document id is the amendment's; see `apply_amendment`.
+                let mut block = Block::new(
+                    BlockType::Clause,
+                    instruction.target.clone(),
+                    text,
+                    text,
+                    parent_id,
+                    Uuid::nil(),
+                    0,
+                );
+                block.level = at.checked_sub(1).map_or(0, |i| self.blocks[i].level);
+                block.id = new_id();
+                set_text(&mut block, text, hasher);
+                self.produced_by.insert(block.id, index);
+                self.blocks.insert(at, block);
+            }
+            AmendmentAction::Substitute { from, to } => {
+                let at = self.find(&instruction.target).ok_or_else(missing)?;
+                let end = self.subtree_end(at);
+                let holder = (at..end)
+                    .find(|&i| self.blocks[i].canonical_text.contains(from.as_str()))
+                    .ok_or_else(|| {
+                        format!("\u{201C}{from}\u{201D} does not occur in {}", instruction.target)
+                    })?;
+                let block = &mut self.blocks[holder];
+                let text = collapse_spaces(&block.canonical_text.replacen(from.as_str(), to, 1));
+                self.removed_by.insert(block.id, index);
+                block.id = new_id();
+                set_text(block, &text, hasher);
+                self.produced_by.insert(block.id, index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Index of the first block at normalized path `target`.
+    fn find(&self, target: &str) -> Option<usize> {
+        self.blocks.iter().position(|b| normalize_path(&b.structural_path) == target)
+    }
+
+    /// Index just past the block at `at` and its descendants.
+    fn subtree_end(&self, at: usize) -> usize {
+        let mut inside: HashSet<Uuid> = HashSet::from([self.blocks[at].id]);
+        let mut end = at + 1;
+        while let Some(parent) = self.blocks.get(end).and_then(|b| b.parent_id) {
+            if !inside.contains(&parent) {
+                break;
+            }
+            inside.insert(self.blocks[end].id);
+            end += 1;
+        }
+        end
+    }
+
+    /// Remove the descendants of the block at `at`, recording `instruction`
+    /// as their remover.
+    fn remove_descendants(&mut self, at: usize, instruction: usize) {
+        let end = self.subtree_end(at);
+        for removed in self.blocks.drain(at + 1..end) {
+            self.removed_by.insert(removed.id, instruction);
+        }
+    }
+
+    /// Where a new clause at `path` goes when no anchor is given: after the
+    /// last clause under its parent path (`5.3` → after `5` and `5.*`), or
+    /// at the end of the document; with the parent block's id.
+    fn end_of_parent(&self, path: &str) -> (usize, Option<Uuid>) {
+        let Some(dot) = path.rfind('.') else {
+            return (self.blocks.len(), None);
+        };
+        let parent = &path[..dot];
+        let prefix = format!("{parent}.");
+        let under = |b: &Block| {
+            let p = normalize_path(&b.structural_path);
+            p == parent || p.starts_with(&prefix)
+        };
+        match self.blocks.iter().rposition(under) {
+            Some(last) => (last + 1, self.find(parent).map(|i| self.blocks[i].id)),
+            None => (self.blocks.len(), None),
+        }
+    }
+}
+
+/// Give `block` new text, re-tokenized and rehashed.
+fn set_text(block: &mut Block, text: &str, hasher: &ClauseHasher) {
+    block.canonical_text = text.to_string();
+    block.display_text = text.to_string();
+    block.tokens = tokenize(text);
+    block.runs.clear();
+    block.rehash(hasher);
+}
+
+fn collapse_spaces(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// ---------------------------------------------------------------------------
+// Parsing instructions
+// ---------------------------------------------------------------------------
+
+/// The instruction in `block`, if it is one.
+fn instruction_in(block: &Block) -> Option<AmendmentInstruction> {
+    let text = &block.canonical_text;
+    let lower = text.to_lowercase();
+    let references = extract_cross_references(std::slice::from_ref(block));
+    let anchor = references.iter().position(|r| {
+        text[..r.offset].trim_end().to_lowercase().ends_with("after")
+    });
+    let target = references
+        .iter()
+        .enumerate()
+        .find(|(i, _)| Some(*i) != anchor)
+        .map(|(_, r)| r.target.clone())?;
+    let quotes = quoted_strings(text);
+    let has = |markers: &[&str]| markers.iter().any(|m| lower.contains(m));
+
+    let action = if has(SUBSTITUTION_MARKERS) && !quotes.is_empty() {
+        match quotes.as_slice() {
+            [from, to, ..] => AmendmentAction::Substitute { from: from.clone(), to: to.clone() },
+            [from] if has(DELETION_MARKERS) || lower.contains("deleting") => {
+                AmendmentAction::Substitute { from: from.clone(), to: String::new() }
+            }
+            _ => return None,
+        }
+    } else if has(INSERTION_MARKERS) {
+        AmendmentAction::Insert { after: anchor.map(|i| references[i].target.clone()) }
+    } else if has(REPLACEMENT_MARKERS) {
+        AmendmentAction::Replace
+    } else if has(DELETION_MARKERS) {
+        AmendmentAction::Delete
+    } else {
+        return None;
+    };
+    let new_text = match action {
+        AmendmentAction::Replace | AmendmentAction::Insert { .. } => quotes.into_iter().last(),
+        _ => None,
+    };
+    Some(AmendmentInstruction {
+        amendment_block_id: block.id,
+        text: text.clone(),
+        action,
+        target,
+        new_text,
+    })
+}
+
+/// The contents of the quoted spans in `text` (straight or curly quotes),
+/// trimmed, in order.
+fn quoted_strings(text: &str) -> Vec<String> {
+    let mut quoted = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find(['\u{201C}', '"']) {
+        let quote = rest[open..].chars().next().unwrap_or('"');
+        let closing = if quote == '"' { '"' } else { '\u{201D}' };
+        let inner = &rest[open + quote.len_utf8()..];
+        let Some(close) = inner.find(closing) else {
+            break;
+        };
+        quoted.push(inner[..close].trim().to_string());
+        rest = &inner[close + closing.len_utf8()..];
+    }
+    quoted
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(doc: Uuid, path: &str, text: &str, parent: Option<Uuid>) -> Block {
+        Block::new(BlockType::Clause, path, text, text, parent, doc, 0)
+    }
+
+    fn base() -> Vec<Block> {
+        let doc = Uuid::new_v4();
+        let mut three = block(doc, "3", "Services.", None);
+        three.children = vec![
+            block(doc, "3.1", "The Supplier shall provide the Services.", Some(three.id)),
+            block(doc, "3.2", "The Supplier shall report monthly.", Some(three.id)),
+        ];
+        let mut five = block(doc, "5", "Payment.", None);
+        five.children = vec![
+            block(doc, "5.1", "Invoices are payable within thirty (30) days.", Some(five.id)),
+            block(doc, "5.2", "Late payments bear interest.", Some(five.id)),
+        ];
+        let mut four = block(doc, "4", "Term.", None);
+        four.children = vec![block(doc, "4.1", "This Agreement lasts one year.", Some(four.id))];
+        vec![three, four, five]
+    }
+
+    #[test]
+    fn instructions_are_parsed_with_their_new_text() {
+        let doc = Uuid::new_v4();
+        let amendment = vec![
+            block(doc, "p0", "The parties agree to amend the Agreement as follows.", None),
+            block(doc, "1", "Section 3.2 is deleted and replaced with the following:", None),
+            block(doc, "p1", "The Supplier shall report weekly.", None),
+            block(doc, "2", "Section 4.1 is hereby deleted in its entirety.", None),
+            block(
+                doc,
+                "3",
+                "Section 5.1 is amended by deleting “thirty (30)” and replacing it with \
+                 “sixty (60)”.",
+                None,
+            ),
+            block(
+                doc,
+                "4",
+                "A new Section 5.3 is inserted after Section 5.2: “Disputed amounts may be \
+                 withheld.”",
+                None,
+            ),
+        ];
+        let instructions = parse_amendment(&amendment);
+        let summary: Vec<_> =
+            instructions.iter().map(|i| (i.target.as_str(), i.action.clone())).collect();
+        assert_eq!(
+            summary,
+            [
+                ("3.2", AmendmentAction::Replace),
+                ("4.1", AmendmentAction::Delete),
+                (
+                    "5.1",
+                    AmendmentAction::Substitute {
+                        from: "thirty (30)".into(),
+                        to: "sixty (60)".into()
+                    }
+                ),
+                ("5.3", AmendmentAction::Insert { after: Some("5.2".into()) }),
+            ]
+        );
+        assert_eq!(instructions[0].new_text.as_deref(), Some("The Supplier shall report weekly."));
+        assert_eq!(instructions[0].amendment_block_id, amendment[1].id);
+        assert_eq!(instructions[3].new_text.as_deref(), Some("Disputed amounts may be withheld."));
+    }
+
+    #[test]
+    fn changes_against_the_amended_view_trace_to_their_instructions() {
+        let base = base();
+        let doc = Uuid::new_v4();
+        let amendment = vec![
+            block(doc, "1", "Section 3.2 is deleted and replaced with the following:", None),
+            block(doc, "p0", "The Supplier shall report weekly.", None),
+            block(doc, "2", "Section 4.1 is hereby deleted in its entirety.", None),
+            block(
+                doc,
+                "3",
+                "Section 5.1 is amended by deleting “thirty (30)” and replacing it with \
+                 “sixty (60)”.",
+                None,
+            ),
+            block(doc, "4", "A new Section 5.3 is added: “Disputed amounts may be withheld.”", None),
+            block(doc, "5", "Section 9 is deleted.", None),
+        ];
+        let hasher = ClauseHasher::default();
+        let view = apply_amendment(&base, &amendment, &hasher);
+        let texts: Vec<_> = view.blocks.iter().map(|b| b.canonical_text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "Services.",
+                "The Supplier shall provide the Services.",
+                "The Supplier shall report weekly.",
+                "Term.",
+                "Payment.",
+                "Invoices are payable within sixty (60) days.",
+                "Late payments bear interest.",
+                "Disputed amounts may be withheld.",
+            ]
+        );
+        assert_eq!(view.blocks[2].id, amendment[0].id);
+        assert_eq!(view.blocks[7].parent_id, Some(base[2].id));
+        assert_eq!(view.unapplied.len(), 1);
+        assert_eq!(view.unapplied[0].instruction, 4);
+
+        let base_doc = base[0].document_id;
+        let engine = CompareEngine::default();
+        let result = compare_amendment(&engine, base_doc, doc, &base, &amendment, &hasher);
+        assert_eq!(result.compare.stats.deleted, 1);
+        assert_eq!(result.compare.stats.inserted, 1);
+        assert_eq!(result.compare.stats.modified, 2);
+        let traced: Vec<_> = result.traces.iter().map(|t| t.instruction).collect();
+        assert_eq!(traced, [0, 1, 2, 3]);
+        for trace in &result.traces {
+            let delta = &result.compare.deltas[trace.delta];
+            assert_ne!(delta.kind, DeltaKind::Unchanged);
+        }
+    }
+}
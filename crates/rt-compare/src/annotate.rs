@@ -0,0 +1,385 @@
+//! Compact per-change records for external summarizers.
+//!
+//! [`export_annotations`] flattens a [`CompareResult`] into one
+//! [`DeltaAnnotation`] per changed block — before / after text, the section
+//! heading it sits under, descriptive tags and a heuristic risk score — and
+//! [`annotations_to_jsonl`] writes them one JSON object per line, which is
+//! the form handed to an LLM or other summarizer.
+//!
+//! The summarizer's answers come back as JSONL too: [`parse_summaries`]
+//! accepts any records carrying `delta_id` and `summary` (extra fields are
+//! ignored, so the exported records with a `summary` added are accepted as
+//! is), and [`attach_summaries`] stores them in [`BlockDelta::summary`].
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+use rt_model::{Block, BlockType};
+
+use crate::diff::DiffKind;
+use crate::result::{BlockDelta, CompareResult, DeltaKind};
+use crate::text::{is_reportable, kind_name, BlockLookup};
+use crate::worker::{ensure_tokens, flatten_blocks};
+
+/// Words whose insertion or removal changes who must, may or may not do
+/// something; a change touching one is tagged `obligation_changed`.
+const OBLIGATION_WORDS: &[&str] = &[
+    "shall", "must", "may", "will", "not", "never", "only", "unless", "except",
+];
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One changed block, as handed to a summarizer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaAnnotation {
+    /// The [`BlockDelta::id`] summaries must be returned against.
+    pub delta_id: Uuid,
+    pub kind: DeltaKind,
+    /// Structural path of the block; `"L => R"` when it moved.
+    pub path: String,
+    /// Display text of the nearest enclosing section heading, if any.
+    pub section_heading: Option<String>,
+    /// Left (base) display text; `None` for insertions. The blocks of a
+    /// merge are joined by newlines.
+    pub before: Option<String>,
+    /// Right (incoming) display text; `None` for deletions. The blocks of a
+    /// split are joined by newlines.
+    pub after: Option<String>,
+    /// Descriptive tags, e.g. `"modified"`, `"clause"`, `"numbers_changed"`.
+    pub tags: Vec<String>,
+    /// Heuristic review priority in [0.0, 1.0]; higher means riskier.
+    pub risk_score: f64,
+}
+
+/// A summary returned by the summarizer for one delta.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaSummary {
+    pub delta_id: Uuid,
+    pub summary: String,
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Build one annotation per changed delta of `result`, in delta order.
+/// `left` and `right` are the block trees the result was computed from.
+///
+/// Unchanged matches are omitted; moves are always included.
+pub fn export_annotations(
+    result: &CompareResult,
+    left: &[Block],
+    right: &[Block],
+) -> Vec<DeltaAnnotation> {
+    let lookup = BlockLookup::new(left, right);
+    let left_headings = section_headings(left);
+    let right_headings = section_headings(right);
+    let coarse: Vec<Uuid> = result.warnings.iter().map(|w| w.delta_id).collect();
+
+    result
+        .deltas
+        .iter()
+        .filter(|d| {
+            let (ins, del) = lookup.token_counts(d);
+            is_reportable(d, ins, del)
+        })
+        .map(|delta| {
+            let left_block = lookup.left_block(delta);
+            let right_block = lookup.right_block(delta);
+            let section_heading = right_block
+                .and_then(|b| right_headings.get(&b.id))
+                .or_else(|| left_block.and_then(|b| left_headings.get(&b.id)))
+                .cloned();
+
+            let changed = changed_words(delta, left_block, right_block);
+            let numbers_changed = changed.iter().any(|w| w.chars().any(|c| c.is_ascii_digit()));
+            let obligation_changed = changed
+                .iter()
+                .any(|w| OBLIGATION_WORDS.contains(&w.to_lowercase().as_str()));
+
+            let mut tags = vec![kind_name(&delta.kind).to_string()];
+            if let Some(block) = right_block.or(left_block) {
+                tags.push(block.block_type.as_str().to_string());
+            }
+            if numbers_changed {
+                tags.push("numbers_changed".into());
+            }
+            if obligation_changed {
+                tags.push("obligation_changed".into());
+            }
+            if delta.kind == DeltaKind::Moved && !changed.is_empty() {
+                tags.push("moved_with_edits".into());
+            }
+            if coarse.contains(&delta.id) {
+                tags.push("coarse_diff".into());
+            }
+
+            DeltaAnnotation {
+                delta_id: delta.id,
+                kind: delta.kind.clone(),
+                path: lookup.label(delta),
+                section_heading,
+                before: joined_text(&lookup.left_blocks(delta)),
+                after: joined_text(&lookup.right_blocks(delta)),
+                tags,
+                risk_score: risk_score(delta, numbers_changed, obligation_changed),
+            }
+        })
+        .collect()
+}
+
+/// Serialize `annotations` as JSONL: one compact JSON object per line.
+pub fn annotations_to_jsonl(annotations: &[DeltaAnnotation]) -> Result<String> {
+    let mut out = String::new();
+    for annotation in annotations {
+        out.push_str(&serde_json::to_string(annotation)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Parse summarizer output: one JSON object per non-blank line, each with at
+/// least `delta_id` and `summary`.
+pub fn parse_summaries(jsonl: &str) -> Result<Vec<DeltaSummary>> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                RtError::InvalidInput(format!("summary line {}: {}", i + 1, e))
+            })
+        })
+        .collect()
+}
+
+/// Store each summary on the delta it names and return the number attached.
+///
+/// Every `delta_id` must belong to `result`; on an unknown id nothing is
+/// attached. A later summary for the same delta replaces an earlier one.
+pub fn attach_summaries(result: &mut CompareResult, summaries: &[DeltaSummary]) -> Result<usize> {
+    let index: HashMap<Uuid, usize> = result
+        .deltas
+        .iter()
+        .enumerate()
+        .map(|(i, d)| (d.id, i))
+        .collect();
+
+    if let Some(unknown) = summaries.iter().find(|s| !index.contains_key(&s.delta_id)) {
+        return Err(RtError::InvalidInput(format!(
+            "summary for unknown delta {}",
+            unknown.delta_id
+        )));
+    }
+
+    for s in summaries {
+        result.deltas[index[&s.delta_id]].summary = Some(s.summary.trim().to_string());
+    }
+    Ok(summaries.len())
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Block id → display text of its section heading: the nearest ancestor of
+/// type `Section`, or else the last `Section` block before it in document
+/// order (flat sources such as `.docx` carry headings as siblings).
+fn section_headings(blocks: &[Block]) -> HashMap<Uuid, String> {
+    let flat = flatten_blocks(blocks);
+    let by_id: HashMap<Uuid, &Block> = flat.iter().map(|b| (b.id, b)).collect();
+
+    let mut headings = HashMap::new();
+    let mut last_section: Option<&Block> = None;
+    for block in &flat {
+        let mut ancestor = block.parent_id.and_then(|id| by_id.get(&id).copied());
+        while let Some(a) = ancestor {
+            if a.block_type == BlockType::Section {
+                break;
+            }
+            ancestor = a.parent_id.and_then(|id| by_id.get(&id).copied());
+        }
+        if let Some(section) = ancestor.or(last_section) {
+            headings.insert(block.id, section.display_text.trim().to_string());
+        }
+        if block.block_type == BlockType::Section {
+            last_section = Some(block);
+        }
+    }
+    headings
+}
+
+/// Display texts of `blocks` joined by newlines; `None` when there are none.
+fn joined_text(blocks: &[&Block]) -> Option<String> {
+    (!blocks.is_empty()).then(|| {
+        blocks
+            .iter()
+            .map(|b| b.display_text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+/// Display text of every token the delta adds or removes.
+fn changed_words(delta: &BlockDelta, left: Option<&Block>, right: Option<&Block>) -> Vec<String> {
+    let all = |block: Option<&Block>| {
+        block.map_or_else(Vec::new, |b| ensure_tokens(b).into_iter().map(|t| t.text).collect())
+    };
+    match delta.kind {
+        DeltaKind::Inserted => all(right),
+        DeltaKind::Deleted => all(left),
+        DeltaKind::Unchanged => Vec::new(),
+        DeltaKind::Modified | DeltaKind::Moved | DeltaKind::Split | DeltaKind::Merged => delta
+            .token_diffs
+            .iter()
+            .filter(|d| d.kind != DiffKind::Equal)
+            .flat_map(|d| d.left_tokens.iter().chain(&d.right_tokens).cloned())
+            .collect(),
+    }
+}
+
+/// Deletions rank above insertions, which rank above edits scaled by how
+/// much text changed; pure moves, splits and merges rank lowest. Number and obligation changes
+/// each add 0.2.
+fn risk_score(delta: &BlockDelta, numbers_changed: bool, obligation_changed: bool) -> f64 {
+    let base = match delta.kind {
+        DeltaKind::Deleted => 0.6,
+        DeltaKind::Inserted => 0.5,
+        DeltaKind::Unchanged => 0.0,
+        DeltaKind::Modified => 0.2 + 0.4 * (1.0 - delta.similarity_score.unwrap_or(0.0)),
+        DeltaKind::Moved | DeltaKind::Split | DeltaKind::Merged => {
+            0.1 + 0.4 * (1.0 - delta.similarity_score.unwrap_or(1.0))
+        }
+    };
+    let bump = 0.2 * (numbers_changed as u8 + obligation_changed as u8) as f64;
+    (base + bump).min(1.0)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::CompareEngine;
+
+    fn block(doc: Uuid, block_type: BlockType, path: &str, text: &str, idx: i32) -> Block {
+        Block::new(block_type, path, text, text, None, doc, idx)
+    }
+
+    fn fixture() -> (Vec<Block>, Vec<Block>, CompareResult) {
+        let (l, r) = (Uuid::new_v4(), Uuid::new_v4());
+        let left = vec![
+            block(l, BlockType::Section, "1", "Repayment", 0),
+            block(l, BlockType::Clause, "1.1", "the borrower shall repay 100 dollars", 1),
+            block(l, BlockType::Clause, "1.2", "interest accrues daily", 2),
+            block(l, BlockType::Clause, "1.3", "notices must be in writing", 3),
+        ];
+        let right = vec![
+            block(r, BlockType::Section, "1", "Repayment", 0),
+            block(r, BlockType::Clause, "1.1", "the borrower shall repay 150 dollars", 1),
+            block(r, BlockType::Clause, "1.2", "interest accrues daily", 2),
+        ];
+        let result = CompareEngine::default().compare(l, r, &left, &right);
+        (left, right, result)
+    }
+
+    #[test]
+    fn export_lists_changed_blocks_with_context() {
+        let (left, right, result) = fixture();
+        let annotations = export_annotations(&result, &left, &right);
+        assert_eq!(annotations.len(), 2);
+
+        let modified = &annotations[0];
+        assert_eq!(modified.kind, DeltaKind::Modified);
+        assert_eq!(modified.path, "1.1");
+        assert_eq!(modified.section_heading.as_deref(), Some("Repayment"));
+        assert_eq!(modified.before.as_deref(), Some("the borrower shall repay 100 dollars"));
+        assert_eq!(modified.after.as_deref(), Some("the borrower shall repay 150 dollars"));
+        assert!(modified.tags.contains(&"numbers_changed".to_string()));
+        assert!(!modified.tags.contains(&"obligation_changed".to_string()));
+
+        let deleted = &annotations[1];
+        assert_eq!(deleted.kind, DeltaKind::Deleted);
+        assert!(deleted.after.is_none());
+        assert_eq!(deleted.tags[..2], ["deleted".to_string(), "clause".to_string()]);
+        assert!(deleted.tags.contains(&"obligation_changed".to_string()));
+        assert!(deleted.risk_score > modified.risk_score);
+        assert!((0.0..=1.0).contains(&deleted.risk_score));
+    }
+
+    #[test]
+    fn ancestor_section_wins_over_preceding_one() {
+        let doc = Uuid::new_v4();
+        let mut parent = block(doc, BlockType::Section, "1", "Payments", 0);
+        let mut child = block(doc, BlockType::Clause, "1.1", "pay", 0);
+        child.parent_id = Some(parent.id);
+        let mut nested = block(doc, BlockType::Section, "1.2", "Fees", 1);
+        nested.parent_id = Some(parent.id);
+        let mut late = block(doc, BlockType::Clause, "1.3", "late", 2);
+        late.parent_id = Some(parent.id);
+        let (child_id, late_id) = (child.id, late.id);
+        parent.children = vec![child, nested, late];
+
+        let headings = section_headings(&[parent]);
+        assert_eq!(headings[&child_id], "Payments");
+        assert_eq!(headings[&late_id], "Payments");
+    }
+
+    #[test]
+    fn jsonl_has_one_record_per_line() {
+        let (left, right, result) = fixture();
+        let annotations = export_annotations(&result, &left, &right);
+        let jsonl = annotations_to_jsonl(&annotations).unwrap();
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let back: DeltaAnnotation = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(back, annotations[0]);
+    }
+
+    #[test]
+    fn exported_records_with_summary_round_trip() {
+        let (left, right, mut result) = fixture();
+        let annotations = export_annotations(&result, &left, &right);
+        let returned: String = annotations
+            .iter()
+            .map(|a| {
+                let mut v = serde_json::to_value(a).unwrap();
+                v["summary"] = serde_json::json!(format!(" {} changed ", a.path));
+                format!("{v}\n\n")
+            })
+            .collect();
+
+        let summaries = parse_summaries(&returned).unwrap();
+        assert_eq!(attach_summaries(&mut result, &summaries).unwrap(), 2);
+
+        let delta = result.deltas.iter().find(|d| d.id == annotations[0].delta_id).unwrap();
+        assert_eq!(delta.summary.as_deref(), Some("1.1 changed"));
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json["deltas"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d.get("summary").is_none()));
+    }
+
+    #[test]
+    fn unknown_delta_or_malformed_line_is_rejected() {
+        let (_, _, mut result) = fixture();
+        let unknown = [DeltaSummary {
+            delta_id: Uuid::new_v4(),
+            summary: "x".into(),
+        }];
+        assert!(attach_summaries(&mut result, &unknown).is_err());
+        assert!(result.deltas.iter().all(|d| d.summary.is_none()));
+
+        let err = parse_summaries("{\"delta_id\": \"nope\"}").unwrap_err();
+        assert!(err.to_string().contains("line 1"), "{err}");
+    }
+}
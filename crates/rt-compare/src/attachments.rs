@@ -0,0 +1,238 @@
+//! Attachment comparison for aligned block pairs.
+//!
+//! Images and embedded objects are compared by content hash, never by
+//! position or file name, so re-saving a document that renames its media
+//! parts reports no change. [`compare_attachments`] walks the block
+//! alignment and, per block pair:
+//! - attachments whose hash appears on both sides are unchanged,
+//! - a remaining left and right attachment of the same type are paired, in
+//!   order, as a replacement (e.g. a new signature image),
+//! - anything left over is an addition or removal.
+//!
+//! Attachments of inserted / deleted blocks are reported as added / removed.
+//! A split or merged block is compared against all the blocks on the other
+//! side as one unit, so an image that stays with either half is unchanged.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::{Attachment, AttachmentType, Block};
+
+use crate::align::BlockAlignment;
+
+// ---------------------------------------------------------------------------
+// AttachmentChange
+// ---------------------------------------------------------------------------
+
+/// How an attachment differs between the two documents.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentChangeKind {
+    /// Present only in the right (incoming) block.
+    Added,
+    /// Present only in the left (base) block.
+    Removed,
+    /// Same type on both sides, different content.
+    Replaced,
+}
+
+/// One added, removed or replaced attachment.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AttachmentChange {
+    pub kind: AttachmentChangeKind,
+    pub attachment_type: AttachmentType,
+    /// Block holding the left attachment (for an addition, the first left
+    /// block of the alignment); `None` when the block itself was inserted.
+    pub left_block_id: Option<Uuid>,
+    /// Block holding the right attachment (for a removal, the first right
+    /// block of the alignment); `None` when the block itself was deleted.
+    pub right_block_id: Option<Uuid>,
+    /// Content hash of the left attachment; `None` for `added`.
+    pub left_hash: Option<String>,
+    /// Content hash of the right attachment; `None` for `removed`.
+    pub right_hash: Option<String>,
+    /// Name of the right attachment, or of the left one for `removed`.
+    pub name: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// compare_attachments
+// ---------------------------------------------------------------------------
+
+/// Attachment changes for every alignment, in alignment order.
+pub fn compare_attachments(
+    alignments: &[BlockAlignment],
+    left_flat: &[Block],
+    right_flat: &[Block],
+) -> Vec<AttachmentChange> {
+    let mut changes = Vec::new();
+    for alignment in alignments {
+        let (left, right): (Vec<&Block>, Vec<&Block>) = match alignment {
+            BlockAlignment::Matched { left, right, .. }
+            | BlockAlignment::Moved { left, right, .. } => {
+                (vec![&left_flat[*left]], vec![&right_flat[*right]])
+            }
+            BlockAlignment::Split { left, right, .. } => (
+                vec![&left_flat[*left]],
+                right.iter().map(|&r| &right_flat[r]).collect(),
+            ),
+            BlockAlignment::Merged { left, right, .. } => (
+                left.iter().map(|&l| &left_flat[l]).collect(),
+                vec![&right_flat[*right]],
+            ),
+            BlockAlignment::DeletedLeft { left } => (vec![&left_flat[*left]], vec![]),
+            BlockAlignment::InsertedRight { right } => (vec![], vec![&right_flat[*right]]),
+        };
+        compare_pair(&left, &right, &mut changes);
+    }
+    changes
+}
+
+/// Compare the attachments of the blocks on each side of one alignment.
+fn compare_pair(left: &[&Block], right: &[&Block], out: &mut Vec<AttachmentChange>) {
+    fn held<'a>(blocks: &[&'a Block]) -> Vec<(Uuid, &'a Attachment)> {
+        blocks
+            .iter()
+            .flat_map(|b| b.attachments.iter().map(move |a| (b.id, a)))
+            .collect()
+    }
+    let mut lefts = held(left);
+    let mut rights = held(right);
+
+    // Drop content present on both sides.
+    lefts.retain(|(_, l)| match rights.iter().position(|(_, r)| r.content_hash == l.content_hash) {
+        Some(i) => {
+            rights.remove(i);
+            false
+        }
+        None => true,
+    });
+
+    let (first_left, first_right) = (left.first().map(|b| b.id), right.first().map(|b| b.id));
+    let change = |kind, l: Option<(Uuid, &Attachment)>, r: Option<(Uuid, &Attachment)>| {
+        let (_, named) = r.or(l).expect("one side is present");
+        AttachmentChange {
+            kind,
+            attachment_type: named.attachment_type.clone(),
+            left_block_id: l.map(|(id, _)| id).or(first_left),
+            right_block_id: r.map(|(id, _)| id).or(first_right),
+            left_hash: l.map(|(_, a)| a.content_hash.clone()),
+            right_hash: r.map(|(_, a)| a.content_hash.clone()),
+            name: named.name.clone(),
+        }
+    };
+
+    for l in lefts {
+        match rights.iter().position(|(_, r)| r.attachment_type == l.1.attachment_type) {
+            Some(i) => {
+                let r = rights.remove(i);
+                out.push(change(AttachmentChangeKind::Replaced, Some(l), Some(r)));
+            }
+            None => out.push(change(AttachmentChangeKind::Removed, Some(l), None)),
+        }
+    }
+    for r in rights {
+        out.push(change(AttachmentChangeKind::Added, None, Some(r)));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::BlockType;
+
+    fn with_attachments(doc: Uuid, attachments: &[(AttachmentType, &str)]) -> Block {
+        let mut block = Block::new(BlockType::Paragraph, "p0", "Signed", "Signed", None, doc, 0);
+        block.attachments = attachments
+            .iter()
+            .map(|(t, hash)| Attachment {
+                attachment_type: t.clone(),
+                content_hash: hash.to_string(),
+                storage_ref: format!("word/media/{hash}"),
+                name: None,
+            })
+            .collect();
+        block
+    }
+
+    #[test]
+    fn same_content_under_a_new_part_name_is_unchanged() {
+        let left = with_attachments(Uuid::new_v4(), &[(AttachmentType::Image, "a")]);
+        let mut right = with_attachments(Uuid::new_v4(), &[(AttachmentType::Image, "a")]);
+        right.attachments[0].storage_ref = "word/media/image9.png".into();
+        let alignments = [BlockAlignment::Matched { left: 0, right: 0, similarity: 1.0 }];
+
+        assert!(compare_attachments(&alignments, &[left], &[right]).is_empty());
+    }
+
+    #[test]
+    fn pairs_report_replacements_additions_and_removals() {
+        use AttachmentType::{EmbeddedObject, Image};
+        let left = with_attachments(
+            Uuid::new_v4(),
+            &[(Image, "keep"), (Image, "old-sig"), (EmbeddedObject, "sheet")],
+        );
+        let right = with_attachments(
+            Uuid::new_v4(),
+            &[(Image, "new-sig"), (Image, "keep"), (Image, "logo")],
+        );
+        let alignments = [BlockAlignment::Moved { left: 0, right: 0, similarity: 1.0 }];
+
+        let changes = compare_attachments(
+            &alignments,
+            std::slice::from_ref(&left),
+            std::slice::from_ref(&right),
+        );
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.kind.clone(), c.left_hash.as_deref(), c.right_hash.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (AttachmentChangeKind::Replaced, Some("old-sig"), Some("new-sig")),
+                (AttachmentChangeKind::Removed, Some("sheet"), None),
+                (AttachmentChangeKind::Added, None, Some("logo")),
+            ]
+        );
+        assert!(changes.iter().all(|c| c.left_block_id == Some(left.id)));
+        assert!(changes.iter().all(|c| c.right_block_id == Some(right.id)));
+    }
+
+    #[test]
+    fn inserted_and_deleted_blocks_carry_their_attachments() {
+        let left = with_attachments(Uuid::new_v4(), &[(AttachmentType::Image, "a")]);
+        let right = with_attachments(Uuid::new_v4(), &[(AttachmentType::EmbeddedObject, "b")]);
+        let alignments = [
+            BlockAlignment::DeletedLeft { left: 0 },
+            BlockAlignment::InsertedRight { right: 0 },
+        ];
+
+        let changes = compare_attachments(&alignments, &[left], &[right]);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].kind, AttachmentChangeKind::Removed);
+        assert_eq!(changes[0].right_block_id, None);
+        assert_eq!(changes[1].kind, AttachmentChangeKind::Added);
+        assert_eq!(changes[1].attachment_type, AttachmentType::EmbeddedObject);
+    }
+
+    #[test]
+    fn split_block_keeps_attachments_that_moved_to_either_half() {
+        let doc = Uuid::new_v4();
+        let left = with_attachments(doc, &[(AttachmentType::Image, "a"), (AttachmentType::Image, "b")]);
+        let first = with_attachments(doc, &[(AttachmentType::Image, "a")]);
+        let second = with_attachments(doc, &[(AttachmentType::Image, "c")]);
+        let alignments = [BlockAlignment::Split { left: 0, right: vec![0, 1], similarity: 1.0 }];
+
+        let changes = compare_attachments(&alignments, &[left], &[first.clone(), second.clone()]);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, AttachmentChangeKind::Replaced);
+        assert_eq!(changes[0].right_hash.as_deref(), Some("c"));
+        assert_eq!(changes[0].right_block_id, Some(second.id));
+        assert_ne!(changes[0].right_block_id, Some(first.id));
+    }
+}
@@ -0,0 +1,260 @@
+//! Similarity threshold calibration from reviewer-labelled alignments.
+//!
+//! Reviewers correcting the aligner record, for a pair of blocks, whether
+//! the blocks are the same clause ([`AlignmentLabel::Match`]), the same
+//! clause moved elsewhere ([`AlignmentLabel::Move`]) or unrelated
+//! ([`AlignmentLabel::Distinct`]). [`calibrate`] picks the
+//! `similarity_threshold` and `move_threshold` that reproduce those labels
+//! best for a corpus; [`Calibration::apply`] turns the result into a
+//! [`CompareConfig`] preset.
+//!
+//! Each threshold is chosen independently by a one-dimensional search:
+//! candidate cut-offs are the midpoints between consecutive observed
+//! similarities (plus the current default), and the candidate classifying
+//! the most samples correctly wins, ties going to the one nearest the
+//! default so that sparse labels move the thresholds as little as possible.
+
+use serde::{Deserialize, Serialize};
+
+use rt_model::error::{Result, RtError};
+use rt_model::{AlignmentLabel, Block};
+
+use crate::align::{block_similarity, MOVE_THRESHOLD, SIMILARITY_THRESHOLD};
+use crate::worker::CompareConfig;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One labelled block pair, reduced to the features the aligner looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LabeledPair {
+    /// Token Jaccard similarity, as computed by [`block_similarity`].
+    pub similarity: f64,
+    /// Whether the two blocks have different `structural_path`s.
+    pub path_changed: bool,
+    pub label: AlignmentLabel,
+}
+
+impl LabeledPair {
+    pub fn from_blocks(left: &Block, right: &Block, label: AlignmentLabel) -> Self {
+        Self {
+            similarity: block_similarity(left, right),
+            path_changed: left.structural_path != right.structural_path,
+            label,
+        }
+    }
+}
+
+/// Thresholds fitted by [`calibrate`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    pub similarity_threshold: f64,
+    pub move_threshold: f64,
+    /// Number of labelled pairs used.
+    pub samples: usize,
+    /// Fraction of pairs whose aligned / distinct label the fitted
+    /// `similarity_threshold` reproduces.
+    pub similarity_accuracy: f64,
+    /// Fraction of aligned pairs with a changed path whose match / move
+    /// label the fitted `move_threshold` reproduces; `None` when there were
+    /// no such pairs and the default was kept.
+    pub move_accuracy: Option<f64>,
+}
+
+impl Calibration {
+    /// `base` with the calibrated thresholds.
+    pub fn apply(&self, base: &CompareConfig) -> CompareConfig {
+        CompareConfig {
+            similarity_threshold: self.similarity_threshold,
+            move_threshold: self.move_threshold,
+            ..base.clone()
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Fit `similarity_threshold` and `move_threshold` to `pairs`.
+///
+/// - `similarity_threshold` separates aligned pairs (`Match` / `Move`) from
+///   `Distinct` ones.
+/// - `move_threshold` separates `Move` from `Match` among aligned pairs whose
+///   structural path changed; other pairs carry no information about moves.
+pub fn calibrate(pairs: &[LabeledPair]) -> Result<Calibration> {
+    if pairs.is_empty() {
+        return Err(RtError::InvalidInput(
+            "calibration needs at least one labelled alignment".into(),
+        ));
+    }
+    if let Some(bad) = pairs.iter().find(|p| !(0.0..=1.0).contains(&p.similarity)) {
+        return Err(RtError::InvalidInput(format!(
+            "similarity must be between 0.0 and 1.0, got {}",
+            bad.similarity
+        )));
+    }
+
+    let similarity_samples: Vec<(f64, bool)> = pairs
+        .iter()
+        .map(|p| (p.similarity, p.label.is_aligned()))
+        .collect();
+    let (similarity_threshold, similarity_accuracy) =
+        best_threshold(&similarity_samples, SIMILARITY_THRESHOLD);
+
+    let move_samples: Vec<(f64, bool)> = pairs
+        .iter()
+        .filter(|p| p.label.is_aligned() && p.path_changed)
+        .map(|p| (p.similarity, p.label == AlignmentLabel::Move))
+        .collect();
+    let (move_threshold, move_accuracy) = if move_samples.is_empty() {
+        (MOVE_THRESHOLD, None)
+    } else {
+        let (t, acc) = best_threshold(&move_samples, MOVE_THRESHOLD);
+        (t, Some(acc))
+    };
+
+    Ok(Calibration {
+        similarity_threshold,
+        move_threshold,
+        samples: pairs.len(),
+        similarity_accuracy,
+        move_accuracy,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// The cut-off `t` maximising the number of `(value, positive)` samples with
+/// `value >= t` exactly when `positive`, and its accuracy.
+fn best_threshold(samples: &[(f64, bool)], default: f64) -> (f64, f64) {
+    let mut values: Vec<f64> = samples.iter().map(|s| s.0).collect();
+    values.sort_by(f64::total_cmp);
+    values.dedup();
+
+    let mut candidates = vec![0.0, default, 1.0];
+    candidates.extend(values.windows(2).map(|w| (w[0] + w[1]) / 2.0));
+
+    let correct = |t: f64| samples.iter().filter(|&&(v, pos)| (v >= t) == pos).count();
+
+    let mut best = default;
+    let mut best_correct = correct(default);
+    for t in candidates {
+        let c = correct(t);
+        if c > best_correct || (c == best_correct && (t - default).abs() < (best - default).abs()) {
+            best = t;
+            best_correct = c;
+        }
+    }
+    (best, best_correct as f64 / samples.len() as f64)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::BlockType;
+    use uuid::Uuid;
+
+    fn pair(similarity: f64, path_changed: bool, label: AlignmentLabel) -> LabeledPair {
+        LabeledPair {
+            similarity,
+            path_changed,
+            label,
+        }
+    }
+
+    #[test]
+    fn separable_labels_give_midpoint_threshold() {
+        let pairs = [
+            pair(0.55, false, AlignmentLabel::Match),
+            pair(0.62, false, AlignmentLabel::Match),
+            pair(0.90, false, AlignmentLabel::Match),
+            pair(0.30, false, AlignmentLabel::Distinct),
+            pair(0.45, false, AlignmentLabel::Distinct),
+        ];
+        let cal = calibrate(&pairs).unwrap();
+        assert!((cal.similarity_threshold - 0.5).abs() < 1e-9);
+        assert_eq!(cal.similarity_accuracy, 1.0);
+        assert_eq!(cal.move_threshold, MOVE_THRESHOLD);
+        assert_eq!(cal.move_accuracy, None);
+        assert_eq!(cal.samples, 5);
+    }
+
+    #[test]
+    fn consistent_labels_keep_defaults() {
+        let pairs = [
+            pair(0.95, false, AlignmentLabel::Match),
+            pair(0.10, false, AlignmentLabel::Distinct),
+        ];
+        let cal = calibrate(&pairs).unwrap();
+        assert_eq!(cal.similarity_threshold, SIMILARITY_THRESHOLD);
+    }
+
+    #[test]
+    fn move_threshold_fits_moved_versus_modified() {
+        let pairs = [
+            pair(0.80, true, AlignmentLabel::Move),
+            pair(0.78, true, AlignmentLabel::Move),
+            pair(0.72, true, AlignmentLabel::Match),
+            // Unchanged path: says nothing about moves.
+            pair(0.50, false, AlignmentLabel::Move),
+        ];
+        let cal = calibrate(&pairs).unwrap();
+        assert!((cal.move_threshold - 0.75).abs() < 1e-9);
+        assert_eq!(cal.move_accuracy, Some(1.0));
+    }
+
+    #[test]
+    fn overlapping_labels_maximise_accuracy() {
+        let pairs = [
+            pair(0.60, false, AlignmentLabel::Match),
+            pair(0.65, false, AlignmentLabel::Distinct),
+            pair(0.80, false, AlignmentLabel::Match),
+            pair(0.85, false, AlignmentLabel::Match),
+            pair(0.20, false, AlignmentLabel::Distinct),
+        ];
+        let cal = calibrate(&pairs).unwrap();
+        assert_eq!(cal.similarity_accuracy, 0.8);
+        assert!(cal.similarity_threshold > 0.65 && cal.similarity_threshold <= 0.8);
+    }
+
+    #[test]
+    fn apply_overrides_only_thresholds() {
+        let base = CompareConfig {
+            max_diff_groups: 7,
+            ..CompareConfig::default()
+        };
+        let cal = calibrate(&[
+            pair(0.5, false, AlignmentLabel::Match),
+            pair(0.2, false, AlignmentLabel::Distinct),
+        ])
+        .unwrap();
+        let tuned = cal.apply(&base);
+        assert_eq!(tuned.similarity_threshold, cal.similarity_threshold);
+        assert_eq!(tuned.max_diff_groups, 7);
+        assert!(tuned.validate().is_ok());
+    }
+
+    #[test]
+    fn empty_or_invalid_input_is_rejected() {
+        assert!(calibrate(&[]).is_err());
+        assert!(calibrate(&[pair(1.5, false, AlignmentLabel::Match)]).is_err());
+    }
+
+    #[test]
+    fn labeled_pair_from_blocks() {
+        let doc = Uuid::new_v4();
+        let left = Block::new(BlockType::Clause, "1.", "a b c", "a b c", None, doc, 0);
+        let right = Block::new(BlockType::Clause, "2.", "a b c", "a b c", None, doc, 0);
+        let p = LabeledPair::from_blocks(&left, &right, AlignmentLabel::Move);
+        assert_eq!(p.similarity, 1.0);
+        assert!(p.path_changed);
+    }
+}
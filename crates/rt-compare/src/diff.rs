@@ -5,11 +5,19 @@
 //!
 //! Consecutive operations of the same kind are grouped into a single
 //! [`TokenDiff`] entry to produce compact, human-readable output.
+//!
+//! [`sentence_diff`] groups the same diff by sentence, classifying each
+//! sentence as a whole, for reviewers who read changes sentence by sentence.
+//!
+//! A token-level diff reports "indemnification" vs "indemnifications" as a
+//! whole-word substitution. [`refine_substitutions`] optionally adds
+//! character-level diffs to such near-identical token pairs so a UI can
+//! highlight just the changed characters.
 
 use serde::{Deserialize, Serialize};
 use similar::{Algorithm, DiffOp};
 
-use rt_core::Token;
+use rt_model::{Token, TokenKind};
 
 // ---------------------------------------------------------------------------
 // Public types
@@ -42,6 +50,55 @@ pub struct TokenDiff {
     /// Byte offset of the first right token within the block's canonical text,
     /// or 0 if there is no right token (deletion).
     pub right_offset: usize,
+    /// Character-level diffs of the similar token pairs of a `Substituted`
+    /// group, filled in by [`refine_substitutions`]; empty otherwise.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub char_diffs: Vec<CharDiff>,
+}
+
+/// A run of characters within one token pair of a `Substituted` group.
+///
+/// The pair is `left_tokens[token]` and `right_tokens[token]`. `kind` is
+/// `Equal`, `Inserted` or `Deleted`; `left_text` is empty for insertions and
+/// `right_text` for deletions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CharDiff {
+    pub kind: DiffKind,
+    /// Index of the token pair within the group.
+    pub token: usize,
+    pub left_text: String,
+    pub right_text: String,
+    /// Byte offset of the run within the left token.
+    pub left_offset: usize,
+    /// Byte offset of the run within the right token.
+    pub right_offset: usize,
+}
+
+/// Classification of a sentence in [`sentence_diff`] output.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SentenceKind {
+    Unchanged,
+    /// Present on both sides with some token changed.
+    Changed,
+    /// Present only on the right.
+    Inserted,
+    /// Present only on the left.
+    Deleted,
+}
+
+/// A sentence of a block pair with the token diffs inside it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentenceDiff {
+    pub kind: SentenceKind,
+    /// Byte range (start, end) of the sentence within the left block's
+    /// canonical text; `None` for an inserted sentence.
+    pub left_span: Option<(usize, usize)>,
+    /// Byte range (start, end) of the sentence within the right block's
+    /// canonical text; `None` for a deleted sentence.
+    pub right_span: Option<(usize, usize)>,
+    /// The sentence's token diff, grouped as by [`token_diff`].
+    pub token_diffs: Vec<TokenDiff>,
 }
 
 // ---------------------------------------------------------------------------
@@ -55,41 +112,98 @@ pub struct TokenDiff {
 /// [`TokenDiff`] entries. Adjacent `Deleted`+`Inserted` groups are merged into
 /// `Substituted` entries.
 pub fn token_diff(left: &[Token], right: &[Token]) -> Vec<TokenDiff> {
+    group_and_merge(raw_changes(left, right))
+}
+
+/// Compute the token diff between `left` and `right` and split it into
+/// sentences.
+///
+/// A sentence ends after a `;`, or a `.` followed by whitespace or the end
+/// of the text, that is not inside parentheses or brackets; so "Section
+/// 3.2" and "(as defined in clause 4.)" do not end one. The diff is split
+/// after every token that ends a sentence on either side, and each piece is
+/// grouped as by [`token_diff`] and classified as a whole: unchanged when
+/// every token is equal, inserted or deleted when it has tokens on one side
+/// only, changed otherwise.
+pub fn sentence_diff(left: &[Token], right: &[Token]) -> Vec<SentenceDiff> {
+    let left_ends = sentence_ends(left);
+    let right_ends = sentence_ends(right);
+
+    let mut sentences = Vec::new();
+    let mut current: Vec<RawChange<'_>> = Vec::new();
+    for change in raw_changes(left, right) {
+        let ends = change.left_index.is_some_and(|i| left_ends[i])
+            || change.right_index.is_some_and(|i| right_ends[i]);
+        current.push(change);
+        if ends {
+            sentences.push(build_sentence(std::mem::take(&mut current)));
+        }
+    }
+    if !current.is_empty() {
+        sentences.push(build_sentence(current));
+    }
+    sentences
+}
+
+/// Add character-level diffs to the `Substituted` groups of `diffs`.
+///
+/// Groups with the same number of tokens on both sides are paired token by
+/// token; each differing pair whose character similarity (the
+/// [`similar::get_diff_ratio`] of their characters) is at least
+/// `min_similarity` gets [`CharDiff`] runs covering both tokens. Pairs below
+/// the threshold are left as whole-token substitutions, as are groups whose
+/// sides differ in length.
+pub fn refine_substitutions(diffs: &mut [TokenDiff], min_similarity: f64) {
+    for diff in diffs.iter_mut() {
+        if diff.kind != DiffKind::Substituted || diff.left_tokens.len() != diff.right_tokens.len() {
+            continue;
+        }
+        let mut char_diffs = Vec::new();
+        for (token, (left, right)) in diff.left_tokens.iter().zip(&diff.right_tokens).enumerate() {
+            char_diffs.extend(char_diff(token, left, right, min_similarity));
+        }
+        diff.char_diffs = char_diffs;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// Expand the Myers diff of `left` and `right` into a flat change stream,
+/// one change per token.
+fn raw_changes<'a>(left: &'a [Token], right: &'a [Token]) -> Vec<RawChange<'a>> {
     // Build string slices of normalized tokens for the diff engine.
     let left_norm: Vec<&str> = left.iter().map(|t| t.normalized.as_str()).collect();
     let right_norm: Vec<&str> = right.iter().map(|t| t.normalized.as_str()).collect();
 
     let ops = similar::capture_diff_slices(Algorithm::Myers, &left_norm, &right_norm);
 
-    // Expand DiffOps into a flat change stream.
     let mut changes: Vec<RawChange> = Vec::new();
+    let mut push = |tag, left_index: Option<usize>, right_index: Option<usize>| {
+        changes.push(RawChange {
+            tag,
+            left_token: left_index.map(|i| &left[i]),
+            right_token: right_index.map(|i| &right[i]),
+            left_index,
+            right_index,
+        });
+    };
     for op in &ops {
-        match op {
+        match *op {
             DiffOp::Equal { old_index, new_index, len } => {
-                for k in 0..*len {
-                    changes.push(RawChange {
-                        tag: RawTag::Equal,
-                        left_token: Some(&left[old_index + k]),
-                        right_token: Some(&right[new_index + k]),
-                    });
+                for k in 0..len {
+                    push(RawTag::Equal, Some(old_index + k), Some(new_index + k));
                 }
             }
             DiffOp::Delete { old_index, old_len, .. } => {
-                for k in 0..*old_len {
-                    changes.push(RawChange {
-                        tag: RawTag::Delete,
-                        left_token: Some(&left[old_index + k]),
-                        right_token: None,
-                    });
+                for k in 0..old_len {
+                    push(RawTag::Delete, Some(old_index + k), None);
                 }
             }
             DiffOp::Insert { new_index, new_len, .. } => {
-                for k in 0..*new_len {
-                    changes.push(RawChange {
-                        tag: RawTag::Insert,
-                        left_token: None,
-                        right_token: Some(&right[new_index + k]),
-                    });
+                for k in 0..new_len {
+                    push(RawTag::Insert, None, Some(new_index + k));
                 }
             }
             DiffOp::Replace {
@@ -99,30 +213,136 @@ pub fn token_diff(left: &[Token], right: &[Token]) -> Vec<TokenDiff> {
                 new_len,
             } => {
                 // Decompose Replace into Delete + Insert to enable Substituted merging.
-                for k in 0..*old_len {
-                    changes.push(RawChange {
-                        tag: RawTag::Delete,
-                        left_token: Some(&left[old_index + k]),
-                        right_token: None,
-                    });
+                for k in 0..old_len {
+                    push(RawTag::Delete, Some(old_index + k), None);
                 }
-                for k in 0..*new_len {
-                    changes.push(RawChange {
-                        tag: RawTag::Insert,
-                        left_token: None,
-                        right_token: Some(&right[new_index + k]),
-                    });
+                for k in 0..new_len {
+                    push(RawTag::Insert, None, Some(new_index + k));
                 }
             }
         }
     }
+    changes
+}
 
-    group_and_merge(changes)
+/// For each token, whether a sentence ends after it (see [`sentence_diff`]).
+fn sentence_ends(tokens: &[Token]) -> Vec<bool> {
+    let mut depth = 0usize;
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| {
+            if token.kind != TokenKind::Punctuation {
+                return false;
+            }
+            match token.text.as_str() {
+                "(" | "[" => {
+                    depth += 1;
+                    false
+                }
+                ")" | "]" => {
+                    depth = depth.saturating_sub(1);
+                    false
+                }
+                ";" => depth == 0,
+                "." => {
+                    let end = token.offset + token.text.len();
+                    depth == 0 && tokens.get(i + 1).is_none_or(|next| next.offset > end)
+                }
+                _ => false,
+            }
+        })
+        .collect()
 }
 
-// ---------------------------------------------------------------------------
-// Internal helpers
-// ---------------------------------------------------------------------------
+/// Classify and group the changes of one sentence.
+fn build_sentence(changes: Vec<RawChange<'_>>) -> SentenceDiff {
+    let span = |tokens: Vec<&Token>| {
+        let first = tokens.first()?;
+        let last = tokens.last()?;
+        Some((first.offset, last.offset + last.text.len()))
+    };
+    let left_span = span(changes.iter().filter_map(|c| c.left_token).collect());
+    let right_span = span(changes.iter().filter_map(|c| c.right_token).collect());
+    let kind = if changes.iter().all(|c| c.tag == RawTag::Equal) {
+        SentenceKind::Unchanged
+    } else if left_span.is_none() {
+        SentenceKind::Inserted
+    } else if right_span.is_none() {
+        SentenceKind::Deleted
+    } else {
+        SentenceKind::Changed
+    };
+    SentenceDiff {
+        kind,
+        left_span,
+        right_span,
+        token_diffs: group_and_merge(changes),
+    }
+}
+
+/// Character runs of one token pair, or none when the tokens are equal or
+/// less similar than `min_similarity`.
+fn char_diff(token: usize, left: &str, right: &str, min_similarity: f64) -> Vec<CharDiff> {
+    if left == right {
+        return vec![];
+    }
+    let left_chars: Vec<char> = left.chars().collect();
+    let right_chars: Vec<char> = right.chars().collect();
+    let ops = similar::capture_diff_slices(Algorithm::Myers, &left_chars, &right_chars);
+    let ratio = similar::get_diff_ratio(&ops, left_chars.len(), right_chars.len());
+    if (ratio as f64) < min_similarity {
+        return vec![];
+    }
+
+    // Byte offset of each character index, plus the end of the token.
+    let bytes = |text: &str| -> Vec<usize> {
+        text.char_indices().map(|(i, _)| i).chain(std::iter::once(text.len())).collect()
+    };
+    let (left_bytes, right_bytes) = (bytes(left), bytes(right));
+    let run = |kind, old: (usize, usize), new: (usize, usize)| CharDiff {
+        kind,
+        token,
+        left_text: left[left_bytes[old.0]..left_bytes[old.1]].to_string(),
+        right_text: right[right_bytes[new.0]..right_bytes[new.1]].to_string(),
+        left_offset: left_bytes[old.0],
+        right_offset: right_bytes[new.0],
+    };
+
+    let mut runs = Vec::new();
+    for op in &ops {
+        match *op {
+            DiffOp::Equal { old_index, new_index, len } => runs.push(run(
+                DiffKind::Equal,
+                (old_index, old_index + len),
+                (new_index, new_index + len),
+            )),
+            DiffOp::Delete { old_index, old_len, new_index } => runs.push(run(
+                DiffKind::Deleted,
+                (old_index, old_index + old_len),
+                (new_index, new_index),
+            )),
+            DiffOp::Insert { old_index, new_index, new_len } => runs.push(run(
+                DiffKind::Inserted,
+                (old_index, old_index),
+                (new_index, new_index + new_len),
+            )),
+            DiffOp::Replace { old_index, old_len, new_index, new_len } => {
+                runs.push(run(
+                    DiffKind::Deleted,
+                    (old_index, old_index + old_len),
+                    (new_index, new_index),
+                ));
+                runs.push(run(
+                    DiffKind::Inserted,
+                    (old_index + old_len, old_index + old_len),
+                    (new_index, new_index + new_len),
+                ));
+            }
+        }
+    }
+    runs
+}
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum RawTag {
@@ -131,18 +351,22 @@ enum RawTag {
     Insert,
 }
 
+/// A run of same-tag changes: (tag, left_texts, right_texts, left_offset, right_offset).
+type RawGroup = (RawTag, Vec<String>, Vec<String>, usize, usize);
+
 struct RawChange<'a> {
     tag: RawTag,
     left_token: Option<&'a Token>,
     right_token: Option<&'a Token>,
+    left_index: Option<usize>,
+    right_index: Option<usize>,
 }
 
 /// Group consecutive raw changes of the same tag, then merge adjacent
 /// Delete+Insert groups into Substituted groups.
 fn group_and_merge(changes: Vec<RawChange<'_>>) -> Vec<TokenDiff> {
     // Step 1: group consecutive same-tag runs.
-    // Each group is (tag, left_texts, right_texts, left_offset, right_offset).
-    let mut groups: Vec<(RawTag, Vec<String>, Vec<String>, usize, usize)> = Vec::new();
+    let mut groups: Vec<RawGroup> = Vec::new();
 
     for ch in changes {
         let lt = ch.left_token.map(|t| t.text.clone()).unwrap_or_default();
@@ -182,13 +406,14 @@ fn group_and_merge(changes: Vec<RawChange<'_>>) -> Vec<TokenDiff> {
             && i + 1 < groups.len()
             && groups[i + 1].0 == RawTag::Insert
         {
-            let (_, ref rt2, _, _, ro2) = groups[i + 1];
+            let (_, _, ref rt2, _, ro2) = groups[i + 1];
             result.push(TokenDiff {
                 kind: DiffKind::Substituted,
                 left_tokens: lt.clone(),
                 right_tokens: rt2.clone(),
                 left_offset: lo,
                 right_offset: ro2,
+                char_diffs: Vec::new(),
             });
             i += 2;
         } else {
@@ -203,6 +428,7 @@ fn group_and_merge(changes: Vec<RawChange<'_>>) -> Vec<TokenDiff> {
                 right_tokens: rt.clone(),
                 left_offset: lo,
                 right_offset: ro,
+                char_diffs: Vec::new(),
             });
             i += 1;
         }
@@ -218,7 +444,8 @@ fn group_and_merge(changes: Vec<RawChange<'_>>) -> Vec<TokenDiff> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rt_core::{Token, TokenKind};
+    use crate::tokenize::tokenize;
+    use rt_model::{Token, TokenKind};
 
     fn word(text: &str, offset: usize) -> Token {
         Token {
@@ -296,6 +523,19 @@ mod tests {
         assert!(has_change, "should detect substitution: {:?}", diffs);
     }
 
+    #[test]
+    fn substitution_carries_both_sides() {
+        let left = make_tokens(&["the", "borrower", "shall"]);
+        let right = make_tokens(&["the", "lender", "shall"]);
+        let diffs = token_diff(&left, &right);
+        let sub = diffs
+            .iter()
+            .find(|d| d.kind == DiffKind::Substituted)
+            .expect("substituted group");
+        assert_eq!(sub.left_tokens, vec!["borrower"]);
+        assert_eq!(sub.right_tokens, vec!["lender"]);
+    }
+
     #[test]
     fn fully_disjoint_produces_substituted_or_delete_insert() {
         let left = make_tokens(&["alpha", "beta"]);
@@ -386,6 +626,79 @@ mod tests {
         assert_eq!(diffs[0].left_offset, 0);
     }
 
+    #[test]
+    fn similar_substituted_tokens_get_char_diffs() {
+        let left = make_tokens(&["the", "indemnification", "clause"]);
+        let right = make_tokens(&["the", "indemnifications", "clause"]);
+        let mut diffs = token_diff(&left, &right);
+        refine_substitutions(&mut diffs, 0.8);
+
+        let sub = diffs.iter().find(|d| d.kind == DiffKind::Substituted).unwrap();
+        assert_eq!(sub.char_diffs.len(), 2);
+        assert_eq!(sub.char_diffs[0].kind, DiffKind::Equal);
+        assert_eq!(sub.char_diffs[0].left_text, "indemnification");
+        let inserted = &sub.char_diffs[1];
+        assert_eq!(inserted.kind, DiffKind::Inserted);
+        assert_eq!((inserted.token, inserted.right_text.as_str()), (0, "s"));
+        assert_eq!((inserted.left_offset, inserted.right_offset), (15, 15));
+        assert!(inserted.left_text.is_empty());
+    }
+
+    #[test]
+    fn dissimilar_or_unpaired_substitutions_are_not_refined() {
+        let left = make_tokens(&["shall", "pay"]);
+        let right = make_tokens(&["may", "pay"]);
+        let mut diffs = token_diff(&left, &right);
+        refine_substitutions(&mut diffs, 0.9);
+        assert!(diffs.iter().all(|d| d.char_diffs.is_empty()));
+
+        let left = make_tokens(&["the", "seller"]);
+        let right = make_tokens(&["the", "sellers", "jointly"]);
+        let mut diffs = token_diff(&left, &right);
+        refine_substitutions(&mut diffs, 0.5);
+        assert!(diffs.iter().all(|d| d.char_diffs.is_empty()));
+        let json = serde_json::to_string(&diffs).unwrap();
+        assert!(!json.contains("char_diffs"));
+    }
+
+    #[test]
+    fn sentence_diff_classifies_each_sentence() {
+        let left = tokenize(
+            "The Seller shall deliver the goods. Payment is due in 30 days; late fees apply.",
+        );
+        let right = tokenize(
+            "The Seller shall deliver the goods. Payment is due in 45 days; late fees apply. \
+             Title passes on delivery.",
+        );
+        let sentences = sentence_diff(&left, &right);
+        let kinds: Vec<_> = sentences.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                SentenceKind::Unchanged,
+                SentenceKind::Changed,
+                SentenceKind::Unchanged,
+                SentenceKind::Inserted,
+            ]
+        );
+        let changed = &sentences[1];
+        assert_eq!(changed.left_span, Some((36, 62)));
+        assert!(changed.token_diffs.iter().any(|d| d.kind == DiffKind::Substituted));
+        assert_eq!(sentences[3].left_span, None);
+    }
+
+    #[test]
+    fn sentences_do_not_end_inside_parentheses_or_numbers() {
+        let tokens = tokenize("Fees (see Section 3.2. above) are payable. Taxes are extra.");
+        let sentences = sentence_diff(&tokens, &tokens);
+        assert_eq!(sentences.len(), 2);
+        assert!(sentences.iter().all(|s| s.kind == SentenceKind::Unchanged));
+
+        let shorter = tokenize("Fees (see Section 3.2. above) are payable.");
+        let deleted = sentence_diff(&tokens, &shorter);
+        assert_eq!(deleted.last().unwrap().kind, SentenceKind::Deleted);
+    }
+
     #[test]
     fn token_diff_serializes_to_json() {
         let left = make_tokens(&["a"]);
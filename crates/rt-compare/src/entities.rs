@@ -0,0 +1,228 @@
+//! Document-level party and date entities.
+//!
+//! [`extract_entities`] reads a document's parties from the definitions in
+//! its preamble — `ACME Holdings, Inc. (the “Lender”)`, `Jane Doe
+//! (“Borrower”)` — and collects every date the tokenizer recognises (see
+//! [`find_dates`]). [`classify_party_refs`] then marks the tokens naming a
+//! party as [`TokenKind::PartyRef`].
+
+use std::collections::HashSet;
+
+use rt_model::{Block, DateMention, DocumentEntities, Party, Token, TokenKind};
+
+use crate::terms::normalize_term;
+use crate::tokenize::{find_dates, tokenize};
+
+/// Most leading blocks read as the preamble.
+const PREAMBLE_BLOCKS: usize = 10;
+
+/// Lowercase words allowed between `(` and the quoted party term.
+const LEAD_INS: &[&str] = &["the", "hereinafter", "referred", "to", "as", "each", "a"];
+
+/// Terms (normalized) defined in a preamble that name the document rather
+/// than a party.
+const NON_PARTY_TERMS: &[&str] = &["agreement", "contract", "amendment", "effective date"];
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Extract the parties defined in the preamble of `blocks` and the dates in
+/// all of them (children included), in document order.
+///
+/// The preamble is the leading blocks up to the first numbered one (a
+/// structural path starting with a digit), at most [`PREAMBLE_BLOCKS`]. A
+/// party defined twice keeps its first definition.
+pub fn extract_entities(blocks: &[Block]) -> DocumentEntities {
+    let mut entities = DocumentEntities::default();
+    let mut seen = HashSet::new();
+    let mut in_preamble = true;
+    let mut stack: Vec<&Block> = blocks.iter().rev().collect();
+    let mut index = 0;
+    while let Some(block) = stack.pop() {
+        let text = &block.canonical_text;
+        let tokens = tokenize(text);
+        in_preamble &= index < PREAMBLE_BLOCKS
+            && !block.structural_path.starts_with(|c: char| c.is_ascii_digit());
+        if in_preamble {
+            for (role, name) in parties_in(text, &tokens) {
+                let normalized = normalize_term(&role);
+                let is_party = !NON_PARTY_TERMS.contains(&normalized.as_str());
+                if !is_party || !seen.insert(normalized.clone()) {
+                    continue;
+                }
+                entities.parties.push(Party {
+                    role,
+                    normalized,
+                    name,
+                    block_id: block.id,
+                });
+            }
+        }
+        for date in find_dates(&tokens) {
+            let (start, end) = (&tokens[date.start], &tokens[date.end - 1]);
+            entities.dates.push(DateMention {
+                text: text[start.offset..end.offset + end.text.len()].to_string(),
+                value: date.value,
+                block_id: block.id,
+                offset: start.offset,
+            });
+        }
+        stack.extend(block.children.iter().rev());
+        index += 1;
+    }
+    entities
+}
+
+/// Mark every run of `tokens` spelling a party's role or name (longest
+/// first) as [`TokenKind::PartyRef`]. Only words and defined terms are
+/// re-classified. Returns the indices of the tokens whose kind changed.
+pub fn classify_party_refs(tokens: &mut [Token], parties: &[Party]) -> Vec<usize> {
+    let mut patterns: Vec<Vec<String>> = parties
+        .iter()
+        .flat_map(|p| std::iter::once(p.role.as_str()).chain(p.name.as_deref()))
+        .map(|text| tokenize(text).into_iter().map(|t| t.text).collect::<Vec<_>>())
+        .filter(|p| !p.is_empty())
+        .collect();
+    patterns.sort_by_key(|p| std::cmp::Reverse(p.len()));
+
+    let mut changed = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let matched = patterns.iter().find(|p| {
+            tokens[i..].len() >= p.len() && p.iter().zip(&tokens[i..]).all(|(w, t)| *w == t.text)
+        });
+        let Some(pattern) = matched else {
+            i += 1;
+            continue;
+        };
+        for (offset, token) in tokens[i..i + pattern.len()].iter_mut().enumerate() {
+            if matches!(token.kind, TokenKind::Word | TokenKind::DefinedTerm) {
+                token.kind = TokenKind::PartyRef;
+                changed.push(i + offset);
+            }
+        }
+        i += pattern.len();
+    }
+    changed
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// The parties defined in `text` (tokenized as `tokens`): the quoted term of
+/// each parenthetical `(the “Term”)`, with the name written before it.
+fn parties_in(text: &str, tokens: &[Token]) -> Vec<(String, Option<String>)> {
+    let mut parties = Vec::new();
+    for (open, token) in tokens.iter().enumerate() {
+        if token.text != "(" {
+            continue;
+        }
+        let mut i = open + 1;
+        while tokens.get(i).is_some_and(|t| LEAD_INS.contains(&t.normalized.as_str())) {
+            i += 1;
+        }
+        let is_quote = |t: &&Token| matches!(t.text.as_str(), "\u{201C}" | "\"");
+        let Some(quote) = tokens.get(i).filter(is_quote) else {
+            continue;
+        };
+        let closing = if quote.text == "\"" { "\"" } else { "\u{201D}" };
+        let Some(close) = tokens[i + 1..].iter().position(|t| t.text == closing).map(|p| i + 1 + p)
+        else {
+            continue;
+        };
+        if close == i + 1 || tokens.get(close + 1).is_none_or(|t| t.text != ")") {
+            continue;
+        }
+        let (first, last) = (&tokens[i + 1], &tokens[close - 1]);
+        let role = &text[first.offset..last.offset + last.text.len()];
+        if !role.starts_with(char::is_uppercase) {
+            continue;
+        }
+        parties.push((role.to_string(), name_before(text, &tokens[..open])));
+    }
+    parties
+}
+
+/// The name ending just before a parenthetical: the run of capitalised
+/// words, numbers, `.`, `,`, `&` and "of" ending at the last of `tokens`.
+fn name_before(text: &str, tokens: &[Token]) -> Option<String> {
+    let part_of_name = |t: &Token| {
+        t.text.starts_with(|c: char| c.is_uppercase() || c.is_ascii_digit())
+            || matches!(t.text.as_str(), "." | "," | "&" | "of")
+    };
+    let start = tokens.iter().rposition(|t| !part_of_name(t)).map_or(0, |p| p + 1);
+    let mut name = &tokens[start..];
+    while let [first, rest @ ..] = name {
+        if !first.text.starts_with(char::is_alphanumeric) {
+            name = rest;
+        } else {
+            break;
+        }
+    }
+    while let [rest @ .., last] = name {
+        if matches!(last.text.as_str(), "," | "of" | "&") {
+            name = rest;
+        } else {
+            break;
+        }
+    }
+    let (first, last) = (name.first()?, name.last()?);
+    Some(text[first.offset..last.offset + last.text.len()].to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::BlockType;
+    use uuid::Uuid;
+
+    fn block(path: &str, text: &str) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, Uuid::new_v4(), 0)
+    }
+
+    #[test]
+    fn parties_come_from_the_preamble_and_dates_from_everywhere() {
+        let blocks = vec![
+            block(
+                "p0",
+                "This Loan Agreement (this “Agreement”) is dated March 3, 2025 between \
+                 ACME Holdings, Inc. (the “Lender”) and Jane Doe (“Borrower”).",
+            ),
+            block("1.", "Repayment is due on 2026-03-03."),
+            block("2.", "Notices go to the Guarantor (the \"Guarantor\")."),
+        ];
+        let entities = extract_entities(&blocks);
+        let parties: Vec<_> =
+            entities.parties.iter().map(|p| (p.role.as_str(), p.name.as_deref())).collect();
+        assert_eq!(
+            parties,
+            [("Lender", Some("ACME Holdings, Inc.")), ("Borrower", Some("Jane Doe"))]
+        );
+        assert_eq!(entities.parties[0].block_id, blocks[0].id);
+
+        let dates: Vec<_> =
+            entities.dates.iter().map(|d| (d.text.as_str(), d.value.as_str())).collect();
+        assert_eq!(dates, [("March 3, 2025", "2025-03-03"), ("2026-03-03", "2026-03-03")]);
+        assert_eq!(entities.dates[1].block_id, blocks[1].id);
+        assert_eq!(entities.dates[1].offset, 20);
+    }
+
+    #[test]
+    fn party_names_and_roles_become_party_refs() {
+        let preamble = "ACME Holdings, Inc. (the “Lender”) and Jane Doe (“Borrower”).";
+        let blocks = vec![block("p0", preamble)];
+        let parties = extract_entities(&blocks).parties;
+        let mut tokens = tokenize("The Lender may notify Jane Doe in writing.");
+        let changed = classify_party_refs(&mut tokens, &parties);
+        assert_eq!(changed, [1, 4, 5]);
+        assert_eq!(tokens[1].kind, TokenKind::PartyRef);
+        assert_eq!(tokens[0].kind, TokenKind::DefinedTerm);
+        assert!(classify_party_refs(&mut tokens, &[]).is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+//! Document-level formatting drift comparison.
+//!
+//! Template-compliance reviews care when style names, numbering schemes or
+//! font sizes change even if the text is identical. [`compare_formatting`]
+//! builds a [`FormattingProfile`] for each side (style usage counts, numbering
+//! definitions and font-size distribution) and reports every key whose usage
+//! differs as a [`FormattingDrift`].
+//!
+//! Blocks are profiled in aggregate, independent of block alignment, so the
+//! result is unaffected by moves or insertions that do not change formatting.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use rt_model::Block;
+
+use crate::worker::flatten_blocks;
+
+/// Key used for blocks / runs that carry no explicit value.
+pub const INHERITED_KEY: &str = "(inherited)";
+
+// ---------------------------------------------------------------------------
+// FormattingProfile
+// ---------------------------------------------------------------------------
+
+/// Aggregate formatting usage for one document.
+///
+/// Maps are ordered so the serialized form is deterministic.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FormattingProfile {
+    /// Number of blocks using each named style; blocks with no style are
+    /// counted under [`INHERITED_KEY`].
+    pub style_usage: BTreeMap<String, usize>,
+    /// Numbering definitions in use: numbering id → sorted nesting levels.
+    pub numbering_definitions: BTreeMap<i32, Vec<i32>>,
+    /// Number of runs at each explicit point size (formatted with one decimal,
+    /// e.g. `"11.0"`); runs inheriting their size are counted under
+    /// [`INHERITED_KEY`].
+    pub font_size_distribution: BTreeMap<String, usize>,
+}
+
+impl FormattingProfile {
+    /// Build a profile from a block tree (or flat list).
+    pub fn from_blocks(blocks: &[Block]) -> Self {
+        let mut profile = FormattingProfile::default();
+        let mut numbering: BTreeMap<i32, BTreeSet<i32>> = BTreeMap::new();
+
+        for block in flatten_blocks(blocks) {
+            let meta = &block.formatting_meta;
+            let style = meta
+                .style_name
+                .clone()
+                .unwrap_or_else(|| INHERITED_KEY.to_string());
+            *profile.style_usage.entry(style).or_insert(0) += 1;
+
+            if let Some(num_id) = meta.numbering_id {
+                let levels = numbering.entry(num_id).or_default();
+                levels.insert(meta.numbering_level.unwrap_or(0));
+            }
+
+            for run in &block.runs {
+                let key = run
+                    .formatting
+                    .font_size
+                    .map(|s| format!("{:.1}", s))
+                    .unwrap_or_else(|| INHERITED_KEY.to_string());
+                *profile.font_size_distribution.entry(key).or_insert(0) += 1;
+            }
+        }
+
+        profile.numbering_definitions = numbering
+            .into_iter()
+            .map(|(id, levels)| (id, levels.into_iter().collect()))
+            .collect();
+        profile
+    }
+}
+
+// ---------------------------------------------------------------------------
+// FormattingDrift
+// ---------------------------------------------------------------------------
+
+/// A usage count that differs between the two documents.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UsageChange {
+    /// Style name or font-size key.
+    pub key: String,
+    /// Count in the left (base) document; 0 when absent.
+    pub left_count: usize,
+    /// Count in the right (incoming) document; 0 when absent.
+    pub right_count: usize,
+}
+
+/// A numbering definition whose set of levels differs between the documents.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NumberingChange {
+    pub numbering_id: i32,
+    /// Levels used in the left document; empty when the definition is absent.
+    pub left_levels: Vec<i32>,
+    /// Levels used in the right document; empty when the definition is absent.
+    pub right_levels: Vec<i32>,
+}
+
+/// Document-level formatting comparison reported alongside the content diff.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FormattingDrift {
+    /// Profile of the left (base) document.
+    pub left: FormattingProfile,
+    /// Profile of the right (incoming) document.
+    pub right: FormattingProfile,
+    /// Styles whose usage count changed, ordered by style name.
+    pub style_changes: Vec<UsageChange>,
+    /// Numbering definitions that were added, removed or re-levelled.
+    pub numbering_changes: Vec<NumberingChange>,
+    /// Font sizes whose run count changed, ordered by key.
+    pub font_size_changes: Vec<UsageChange>,
+}
+
+impl FormattingDrift {
+    /// `true` when any style, numbering or font-size usage differs.
+    pub fn has_drift(&self) -> bool {
+        !self.style_changes.is_empty()
+            || !self.numbering_changes.is_empty()
+            || !self.font_size_changes.is_empty()
+    }
+}
+
+/// Compare the formatting profiles of two documents.
+pub fn compare_formatting(left_blocks: &[Block], right_blocks: &[Block]) -> FormattingDrift {
+    let left = FormattingProfile::from_blocks(left_blocks);
+    let right = FormattingProfile::from_blocks(right_blocks);
+
+    let style_changes = usage_changes(&left.style_usage, &right.style_usage);
+    let font_size_changes =
+        usage_changes(&left.font_size_distribution, &right.font_size_distribution);
+
+    let ids: BTreeSet<i32> = left
+        .numbering_definitions
+        .keys()
+        .chain(right.numbering_definitions.keys())
+        .copied()
+        .collect();
+    let numbering_changes = ids
+        .into_iter()
+        .filter_map(|id| {
+            let l = left.numbering_definitions.get(&id).cloned().unwrap_or_default();
+            let r = right.numbering_definitions.get(&id).cloned().unwrap_or_default();
+            (l != r).then_some(NumberingChange {
+                numbering_id: id,
+                left_levels: l,
+                right_levels: r,
+            })
+        })
+        .collect();
+
+    FormattingDrift {
+        left,
+        right,
+        style_changes,
+        numbering_changes,
+        font_size_changes,
+    }
+}
+
+fn usage_changes(
+    left: &BTreeMap<String, usize>,
+    right: &BTreeMap<String, usize>,
+) -> Vec<UsageChange> {
+    let keys: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+    keys.into_iter()
+        .filter_map(|k| {
+            let l = left.get(k).copied().unwrap_or(0);
+            let r = right.get(k).copied().unwrap_or(0);
+            (l != r).then(|| UsageChange {
+                key: k.clone(),
+                left_count: l,
+                right_count: r,
+            })
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::{BlockType, Run, RunFormatting};
+    use uuid::Uuid;
+
+    fn block(style: Option<&str>, numbering: Option<(i32, i32)>, sizes: &[Option<f32>]) -> Block {
+        let mut b = Block::new(
+            BlockType::Clause,
+            "1",
+            "same text",
+            "same text",
+            None,
+            Uuid::new_v4(),
+            0,
+        );
+        b.formatting_meta.style_name = style.map(str::to_string);
+        if let Some((id, lvl)) = numbering {
+            b.formatting_meta.numbering_id = Some(id);
+            b.formatting_meta.numbering_level = Some(lvl);
+        }
+        b.runs = sizes
+            .iter()
+            .map(|s| Run {
+                text: "same".into(),
+                formatting: RunFormatting {
+                    font_size: *s,
+                    ..Default::default()
+                },
+                revision: None,
+            })
+            .collect();
+        b
+    }
+
+    #[test]
+    fn identical_formatting_has_no_drift() {
+        let left = vec![block(Some("Body Text"), Some((1, 0)), &[Some(11.0)])];
+        let right = vec![block(Some("Body Text"), Some((1, 0)), &[Some(11.0)])];
+        let drift = compare_formatting(&left, &right);
+        assert!(!drift.has_drift());
+        assert_eq!(drift.left, drift.right);
+    }
+
+    #[test]
+    fn style_rename_is_reported() {
+        let left = vec![block(Some("Body Text"), None, &[]), block(None, None, &[])];
+        let right = vec![block(Some("Normal"), None, &[]), block(None, None, &[])];
+        let drift = compare_formatting(&left, &right);
+        assert!(drift.has_drift());
+        assert_eq!(
+            drift.style_changes,
+            vec![
+                UsageChange { key: "Body Text".into(), left_count: 1, right_count: 0 },
+                UsageChange { key: "Normal".into(), left_count: 0, right_count: 1 },
+            ]
+        );
+        assert_eq!(drift.left.style_usage[INHERITED_KEY], 1);
+    }
+
+    #[test]
+    fn numbering_scheme_change_is_reported() {
+        let left = vec![block(None, Some((1, 0)), &[]), block(None, Some((1, 1)), &[])];
+        let right = vec![block(None, Some((2, 0)), &[]), block(None, Some((2, 1)), &[])];
+        let drift = compare_formatting(&left, &right);
+        assert_eq!(drift.numbering_changes.len(), 2);
+        assert_eq!(drift.numbering_changes[0].numbering_id, 1);
+        assert_eq!(drift.numbering_changes[0].left_levels, vec![0, 1]);
+        assert!(drift.numbering_changes[0].right_levels.is_empty());
+    }
+
+    #[test]
+    fn font_size_distribution_change_is_reported() {
+        let left = vec![block(None, None, &[Some(11.0), None])];
+        let right = vec![block(None, None, &[Some(12.0), None])];
+        let drift = compare_formatting(&left, &right);
+        let keys: Vec<&str> = drift.font_size_changes.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(keys, vec!["11.0", "12.0"]);
+        assert_eq!(drift.right.font_size_distribution[INHERITED_KEY], 1);
+    }
+}
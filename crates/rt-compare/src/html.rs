@@ -0,0 +1,309 @@
+//! Self-contained HTML redline of a [`CompareResult`].
+//!
+//! [`render_html`] produces one HTML page, styles inlined, that web hosts
+//! can display as-is: each block in document order, insertions underlined
+//! (`<ins>`), deletions struck through (`<del>`), and moved, split or merged
+//! blocks annotated with where they came from. Like the text renderings in
+//! [`crate::text`], it needs the block lists the result was computed from.
+
+use std::fmt::Write;
+
+use uuid::Uuid;
+
+use rt_model::{Block, Token};
+
+use crate::diff::{token_diff, DiffKind, TokenDiff};
+use crate::result::{BlockDelta, CompareResult, DeltaKind};
+use crate::text::{is_reportable, kind_name, BlockLookup};
+use crate::worker::ensure_tokens;
+
+/// Inline stylesheet of the page.
+const STYLE: &str = "\
+body{font-family:Georgia,serif;max-width:50em;margin:2em auto;padding:0 1em;\
+line-height:1.5;color:#222}\
+header{border-bottom:1px solid #ccc;margin-bottom:1.5em}\
+.meta,.path,.note{font-family:sans-serif;font-size:.8em;color:#666}\
+.block{margin:.75em 0;padding-left:.75em;border-left:3px solid transparent}\
+.block p{margin:0}\
+.inserted,.modified{border-color:#2a7}\
+.deleted{border-color:#c33}\
+.moved,.split,.merged{border-color:#36c}\
+.unchanged{color:#555}\
+ins{color:#1a6b3c;text-decoration:underline}\
+del{color:#b22;text-decoration:line-through}\
+.note{font-style:italic;color:#36c}";
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Render `result` as a self-contained HTML page; see the module docs.
+pub fn render_html(result: &CompareResult, left: &[Block], right: &[Block]) -> String {
+    render_deltas_html(result.left_doc_id, result.right_doc_id, &result.deltas, left, right)
+}
+
+/// As [`render_html`], for the bare deltas of a comparison (e.g. as stored
+/// for a recorded run). The summary line counts `deltas`.
+///
+/// A delta whose equal token groups were trimmed by the `changes_only`
+/// output mode is re-diffed from its blocks. Each block is given its
+/// delta's deep-link anchor as its `id`, when it has one.
+pub fn render_deltas_html(
+    left_doc_id: Uuid,
+    right_doc_id: Uuid,
+    deltas: &[BlockDelta],
+    left: &[Block],
+    right: &[Block],
+) -> String {
+    let lookup = BlockLookup::new(left, right);
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>Redline</title>\n");
+    let _ = writeln!(out, "<style>{STYLE}</style>");
+    out.push_str("</head>\n<body>\n<header>\n<h1>Redline</h1>\n");
+    let _ = writeln!(out, "<p class=\"meta\">{left_doc_id} &rarr; {right_doc_id}</p>");
+    let _ = writeln!(out, "<p class=\"summary\">{}</p>", summary(deltas));
+    out.push_str("</header>\n<main>\n");
+    for delta in deltas {
+        render_delta(&mut out, &lookup, delta);
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+fn render_delta(out: &mut String, lookup: &BlockLookup, delta: &BlockDelta) {
+    let (ins, del) = lookup.token_counts(delta);
+    let class = if is_reportable(delta, ins, del) {
+        kind_name(&delta.kind)
+    } else {
+        kind_name(&DeltaKind::Unchanged)
+    };
+    let _ = write!(out, "<div class=\"block {class}\"");
+    if let Some(anchors) = &delta.anchors {
+        let _ = write!(out, " id=\"{}\"", escape(&anchors.delta));
+    }
+    out.push_str(">\n");
+    let _ = writeln!(out, "<div class=\"path\">{}</div>", escape(&lookup.label(delta)));
+
+    let lefts = lookup.left_blocks(delta);
+    let rights = lookup.right_blocks(delta);
+    let body = match (&delta.kind, lefts.as_slice(), rights.as_slice()) {
+        (DeltaKind::Modified | DeltaKind::Moved | DeltaKind::Unchanged, [_], [r])
+            if ins + del == 0 =>
+        {
+            escape(&r.canonical_text)
+        }
+        (DeltaKind::Modified | DeltaKind::Moved, [l], [r]) => {
+            inline_diff(l, r, &delta.token_diffs)
+                .unwrap_or_else(|| replacement(&[*l], &[*r]))
+        }
+        (_, lefts, rights) => replacement(lefts, rights),
+    };
+    let _ = writeln!(out, "<p>{body}</p>");
+
+    let note = match delta.kind {
+        DeltaKind::Moved => lookup
+            .left_block(delta)
+            .map(|l| format!("Moved from {}", path_or_ordinal(l, delta.left_ordinal))),
+        DeltaKind::Split => Some(format!("Split into {} blocks", delta.group_block_ids.len())),
+        DeltaKind::Merged => Some(format!("Merged from {} blocks", delta.group_block_ids.len())),
+        _ => None,
+    };
+    for note in note.iter().chain(&delta.summary) {
+        let _ = writeln!(out, "<div class=\"note\">{}</div>", escape(note));
+    }
+    out.push_str("</div>\n");
+}
+
+/// Every left block struck through followed by every right block
+/// underlined; a block on one side only is just struck or underlined.
+fn replacement(lefts: &[&Block], rights: &[&Block]) -> String {
+    let mut out = String::new();
+    for l in lefts {
+        let _ = write!(out, "<del>{}</del>", escape(&l.canonical_text));
+    }
+    for r in rights {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        let _ = write!(out, "<ins>{}</ins>", escape(&r.canonical_text));
+    }
+    out
+}
+
+/// The right block's text with `diffs` marked up inline, keeping each
+/// side's original spacing; `None` when the diffs do not fit the blocks'
+/// tokens.
+fn inline_diff(left: &Block, right: &Block, diffs: &[TokenDiff]) -> Option<String> {
+    let left_tokens = ensure_tokens(left);
+    let right_tokens = ensure_tokens(right);
+    let trimmed = diffs
+        .iter()
+        .any(|d| d.kind == DiffKind::Equal && d.left_tokens.is_empty() && d.right_tokens.is_empty());
+    let recomputed;
+    let diffs = if trimmed {
+        recomputed = token_diff(&left_tokens, &right_tokens);
+        &recomputed
+    } else {
+        diffs
+    };
+
+    let mut out = String::new();
+    let (mut li, mut ri) = (0, 0);
+    for diff in diffs {
+        let l = left_tokens.get(li..li + diff.left_tokens.len())?;
+        let r = right_tokens.get(ri..ri + diff.right_tokens.len())?;
+        li += l.len();
+        ri += r.len();
+        let (from, side) = if r.is_empty() { (l, left) } else { (r, right) };
+        if !out.is_empty() && spaced(side, from) {
+            out.push(' ');
+        }
+        let (l, r) = (span(left, l)?, span(right, r)?);
+        match diff.kind {
+            DiffKind::Equal => out.push_str(&escape(r)),
+            DiffKind::Inserted => {
+                let _ = write!(out, "<ins>{}</ins>", escape(r));
+            }
+            DiffKind::Deleted => {
+                let _ = write!(out, "<del>{}</del>", escape(l));
+            }
+            DiffKind::Substituted => {
+                let _ = write!(out, "<del>{}</del> <ins>{}</ins>", escape(l), escape(r));
+            }
+        }
+    }
+    (li == left_tokens.len() && ri == right_tokens.len()).then_some(out)
+}
+
+/// The text of `block` from the first of `tokens` to the end of the last.
+fn span<'a>(block: &'a Block, tokens: &[Token]) -> Option<&'a str> {
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => {
+            block.canonical_text.get(first.offset..last.offset + last.text.len())
+        }
+        _ => Some(""),
+    }
+}
+
+/// Whether whitespace precedes the first of `tokens` in `block`.
+fn spaced(block: &Block, tokens: &[Token]) -> bool {
+    tokens.first().is_some_and(|t| {
+        block.canonical_text[..t.offset.min(block.canonical_text.len())]
+            .ends_with(char::is_whitespace)
+    })
+}
+
+fn path_or_ordinal(block: &Block, ordinal: Option<usize>) -> String {
+    match (block.structural_path.as_str(), ordinal) {
+        ("", Some(o)) => format!("#{o}"),
+        (path, _) => path.to_string(),
+    }
+}
+
+/// "2 modified, 1 deleted" — changed deltas counted by kind, in
+/// [`DeltaKind`] order; "No changes" when there are none.
+fn summary(deltas: &[BlockDelta]) -> String {
+    let kinds = [
+        DeltaKind::Inserted,
+        DeltaKind::Deleted,
+        DeltaKind::Modified,
+        DeltaKind::Moved,
+        DeltaKind::Split,
+        DeltaKind::Merged,
+    ];
+    let parts: Vec<String> = kinds
+        .iter()
+        .filter_map(|kind| {
+            let n = deltas.iter().filter(|d| d.kind == *kind).count();
+            (n > 0).then(|| format!("{n} {}", kind_name(kind)))
+        })
+        .collect();
+    if parts.is_empty() {
+        "No changes".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Escape `text` for HTML element content and attribute values.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::{CompareConfig, CompareEngine, CompareOutputMode};
+    use rt_model::BlockType;
+
+    fn block(doc: Uuid, path: &str, text: &str, idx: i32) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, doc, idx)
+    }
+
+    fn fixture() -> (Vec<Block>, Vec<Block>) {
+        let (l, r) = (Uuid::new_v4(), Uuid::new_v4());
+        let left = vec![
+            block(l, "1.", "The Borrower shall repay the Loan.", 0),
+            block(l, "2.", "The Lender may assign its rights.", 1),
+            block(l, "3.", "Notices must be in writing.", 2),
+        ];
+        let right = vec![
+            block(r, "1.", "The Borrower shall promptly repay the Loan.", 0),
+            block(r, "2.", "The Lender may assign its rights.", 1),
+            block(r, "4.", "Fees are <5% & payable \"net\".", 2),
+        ];
+        (left, right)
+    }
+
+    #[test]
+    fn changes_are_marked_up_inline_and_escaped() {
+        let (left, right) = fixture();
+        let (l, r) = (left[0].document_id, right[0].document_id);
+        let result = CompareEngine::default().compare(l, r, &left, &right);
+        let html = render_html(&result, &left, &right);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<style>"));
+        assert!(html.contains("The Borrower shall <ins>promptly</ins> repay the Loan."), "{html}");
+        assert!(html.contains("<del>Notices must be in writing.</del>"), "{html}");
+        assert!(html.contains("<ins>Fees are &lt;5% &amp; payable &quot;net&quot;.</ins>"));
+        assert!(html.contains("<p>The Lender may assign its rights.</p>"), "{html}");
+        assert!(html.contains("1 inserted, 1 deleted, 1 modified"), "{html}");
+        let anchor = result.deltas[0].anchors.as_ref().unwrap().delta.clone();
+        assert!(html.contains(&format!("id=\"{anchor}\"")));
+    }
+
+    #[test]
+    fn trimmed_deltas_are_rediffed() {
+        let (left, right) = fixture();
+        let (l, r) = (left[0].document_id, right[0].document_id);
+        let config = CompareConfig {
+            output_mode: CompareOutputMode::ChangesOnly,
+            ..Default::default()
+        };
+        let result = CompareEngine::new(config).compare(l, r, &left, &right);
+        let html = render_deltas_html(l, r, &result.deltas, &left, &right);
+        assert!(html.contains("The Borrower shall <ins>promptly</ins> repay the Loan."), "{html}");
+        assert!(!html.contains("The Lender may assign"));
+    }
+}
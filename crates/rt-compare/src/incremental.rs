@@ -0,0 +1,382 @@
+//! Incremental comparison.
+//!
+//! An editing host re-compares the same two documents after every small
+//! edit. [`CompareEngine::compare_incremental`] reuses the deltas of the
+//! previous comparison for every block the edit did not touch, aligns and
+//! diffs only the rest, and recomputes the stats and document-level
+//! reports over the combined deltas.
+
+use std::collections::{HashMap, HashSet};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use uuid::Uuid;
+
+use rt_model::manifest::InputDigest;
+use rt_model::{Block, BlockType};
+
+use crate::align::{align_blocks_with, BlockAlignment};
+use crate::attachments::compare_attachments;
+use crate::diff::DiffKind;
+use crate::formatting::compare_formatting;
+use crate::result::{BlockDelta, CompareResult, CompareStats, DeltaKind};
+use crate::worker::{locate_delta, section_paths, tally, CompareEngine};
+use crate::xref::broken_references;
+
+/// One alignment of the combined comparison, with the previous delta it
+/// reuses, if any.
+struct Entry {
+    alignment: BlockAlignment,
+    reused: Option<BlockDelta>,
+}
+
+impl CompareEngine {
+    /// Compare `left_blocks` and `right_blocks` again after an edit, reusing
+    /// `previous`, the deltas of an earlier comparison of the same two
+    /// documents (e.g. `CompareResult::deltas`, or a recorded run's stored
+    /// deltas).
+    ///
+    /// `dirty` lists every block whose text, path or type changed since
+    /// then, on either side, and every block added; removed blocks need not
+    /// be listed. A previous delta is reused, with its ordinals, section and
+    /// anchors updated, when all of its blocks still exist and none is
+    /// dirty — unless its equal token groups were trimmed by the
+    /// `changes_only` output mode or it is a whole-block replacement, which
+    /// may have been degraded by the size guard. Blocks no reused delta
+    /// covers are aligned among themselves and diffed as by
+    /// [`compare`](Self::compare).
+    ///
+    /// The result matches a full comparison when the edit leaves the
+    /// alignment of untouched blocks as it was. Documents with tables are
+    /// always compared in full: tables are aligned as a whole.
+    pub fn compare_incremental(
+        &self,
+        left_doc_id: Uuid,
+        right_doc_id: Uuid,
+        left_blocks: &[Block],
+        right_blocks: &[Block],
+        previous: &[BlockDelta],
+        dirty: &[Uuid],
+    ) -> CompareResult {
+        #[cfg(not(target_arch = "wasm32"))]
+        let start = Instant::now();
+
+        let left_flat = self.flatten(left_blocks);
+        let right_flat = self.flatten(right_blocks);
+        if has_tables(&left_flat) || has_tables(&right_flat) {
+            return self.compare(left_doc_id, right_doc_id, left_blocks, right_blocks);
+        }
+        let manifest = self.manifest(&[
+            InputDigest::from_blocks(left_doc_id, left_blocks),
+            InputDigest::from_blocks(right_doc_id, right_blocks),
+        ]);
+
+        // Reuse every previous delta the edit left alone.
+        let dirty: HashSet<Uuid> = dirty.iter().copied().collect();
+        let left_index = ordinals(&left_flat, &dirty);
+        let right_index = ordinals(&right_flat, &dirty);
+        let mut entries: Vec<Entry> = previous
+            .iter()
+            .filter(|delta| reusable(delta))
+            .filter_map(|delta| {
+                let alignment = realign(delta, &left_index, &right_index)?;
+                Some(Entry { alignment, reused: Some(delta.clone()) })
+            })
+            .collect();
+
+        // Align the blocks no reused delta covers among themselves.
+        let mut left_covered = vec![false; left_flat.len()];
+        let mut right_covered = vec![false; right_flat.len()];
+        for entry in &entries {
+            let (lefts, rights) = sides(&entry.alignment);
+            lefts.iter().for_each(|&l| left_covered[l] = true);
+            rights.iter().for_each(|&r| right_covered[r] = true);
+        }
+        let rest_left: Vec<usize> = (0..left_flat.len()).filter(|&l| !left_covered[l]).collect();
+        let rest_right: Vec<usize> =
+            (0..right_flat.len()).filter(|&r| !right_covered[r]).collect();
+        let pick = |flat: &[Block], rest: &[usize]| -> Vec<Block> {
+            rest.iter().map(|&i| flat[i].clone()).collect()
+        };
+        let realigned = align_blocks_with(
+            &pick(&left_flat, &rest_left),
+            &pick(&right_flat, &rest_right),
+            &self.thresholds(),
+        );
+        entries.extend(realigned.into_iter().map(|alignment| Entry {
+            alignment: remap(alignment, &rest_left, &rest_right),
+            reused: None,
+        }));
+        let entries = in_document_order(entries);
+
+        let left_sections = section_paths(&left_flat);
+        let right_sections = section_paths(&right_flat);
+        let mut deltas = Vec::new();
+        let mut warnings = Vec::new();
+        let mut stats = CompareStats {
+            blocks_left: left_flat.len(),
+            blocks_right: right_flat.len(),
+            inserted: 0,
+            deleted: 0,
+            modified: 0,
+            moved: 0,
+            unchanged: 0,
+            split: 0,
+            merged: 0,
+        };
+        let mut alignments = Vec::with_capacity(entries.len());
+        for Entry { alignment, reused } in entries {
+            let mut delta = match reused {
+                Some(mut delta) => {
+                    let (lefts, rights) = sides(&alignment);
+                    delta.left_ordinal = lefts.first().copied();
+                    delta.right_ordinal = rights.first().copied();
+                    delta
+                }
+                None => {
+                    let (delta, warning) = self.build_delta(&alignment, &left_flat, &right_flat);
+                    warnings.extend(warning);
+                    delta
+                }
+            };
+            locate_delta(&mut delta, &left_flat, &right_flat, &left_sections, &right_sections);
+            tally(&mut stats, &delta);
+            if self.config().keep(&mut delta) {
+                deltas.push(delta);
+            }
+            alignments.push(alignment);
+        }
+
+        let formatting_drift = compare_formatting(&left_flat, &right_flat);
+        let attachment_changes = compare_attachments(&alignments, &left_flat, &right_flat);
+        let broken_references = broken_references(&alignments, &left_flat, &right_flat);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+        #[cfg(target_arch = "wasm32")]
+        let elapsed_ms = 0u64;
+
+        CompareResult {
+            run_id: self.run_id(&manifest),
+            left_doc_id,
+            right_doc_id,
+            elapsed_ms: if self.config().deterministic { 0 } else { elapsed_ms },
+            stats,
+            deltas,
+            formatting_drift,
+            attachment_changes,
+            table_changes: Vec::new(),
+            broken_references,
+            warnings,
+            manifest: Some(manifest),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+fn has_tables(flat: &[Block]) -> bool {
+    flat.iter().any(|b| {
+        matches!(b.block_type, BlockType::Table | BlockType::TableRow | BlockType::TableCell)
+    })
+}
+
+/// Block id → ordinal of every block in `flat` that is not dirty.
+fn ordinals(flat: &[Block], dirty: &HashSet<Uuid>) -> HashMap<Uuid, usize> {
+    flat.iter()
+        .enumerate()
+        .filter(|(_, b)| !dirty.contains(&b.id))
+        .map(|(i, b)| (b.id, i))
+        .collect()
+}
+
+/// Whether `delta` carries everything a fresh comparison would produce:
+/// its equal token groups were not trimmed, and it is not a whole-block
+/// replacement (which the size guard may have produced, with a warning).
+fn reusable(delta: &BlockDelta) -> bool {
+    let trimmed = delta.token_diffs.iter().any(|d| {
+        d.kind == DiffKind::Equal && d.left_tokens.is_empty() && d.right_tokens.is_empty()
+    });
+    let replaced = matches!(delta.token_diffs.as_slice(), [d] if d.kind == DiffKind::Substituted);
+    !trimmed && !replaced
+}
+
+/// The alignment `delta` records, over the current ordinals; `None` when
+/// one of its blocks is gone or dirty.
+fn realign(
+    delta: &BlockDelta,
+    left: &HashMap<Uuid, usize>,
+    right: &HashMap<Uuid, usize>,
+) -> Option<BlockAlignment> {
+    let l = || delta.left_block_id.and_then(|id| left.get(&id).copied());
+    let r = || delta.right_block_id.and_then(|id| right.get(&id).copied());
+    let group = |index: &HashMap<Uuid, usize>| -> Option<Vec<usize>> {
+        delta.group_block_ids.iter().map(|id| index.get(id).copied()).collect()
+    };
+    let similarity = delta.similarity_score.unwrap_or(1.0);
+    Some(match delta.kind {
+        DeltaKind::Inserted => BlockAlignment::InsertedRight { right: r()? },
+        DeltaKind::Deleted => BlockAlignment::DeletedLeft { left: l()? },
+        DeltaKind::Modified | DeltaKind::Unchanged => {
+            BlockAlignment::Matched { left: l()?, right: r()?, similarity }
+        }
+        DeltaKind::Moved => BlockAlignment::Moved { left: l()?, right: r()?, similarity },
+        DeltaKind::Split => BlockAlignment::Split { left: l()?, right: group(right)?, similarity },
+        DeltaKind::Merged => BlockAlignment::Merged { left: group(left)?, right: r()?, similarity },
+    })
+}
+
+/// The left and right ordinals `alignment` covers, in document order.
+fn sides(alignment: &BlockAlignment) -> (Vec<usize>, Vec<usize>) {
+    match alignment {
+        BlockAlignment::Matched { left, right, .. } | BlockAlignment::Moved { left, right, .. } => {
+            (vec![*left], vec![*right])
+        }
+        BlockAlignment::InsertedRight { right } => (vec![], vec![*right]),
+        BlockAlignment::DeletedLeft { left } => (vec![*left], vec![]),
+        BlockAlignment::Split { left, right, .. } => (vec![*left], right.clone()),
+        BlockAlignment::Merged { left, right, .. } => (left.clone(), vec![*right]),
+    }
+}
+
+/// `alignment` over sub-lists, with its indices mapped through `left` and
+/// `right` back to full ordinals.
+fn remap(alignment: BlockAlignment, left: &[usize], right: &[usize]) -> BlockAlignment {
+    match alignment {
+        BlockAlignment::Matched { left: l, right: r, similarity } => {
+            BlockAlignment::Matched { left: left[l], right: right[r], similarity }
+        }
+        BlockAlignment::Moved { left: l, right: r, similarity } => {
+            BlockAlignment::Moved { left: left[l], right: right[r], similarity }
+        }
+        BlockAlignment::InsertedRight { right: r } => {
+            BlockAlignment::InsertedRight { right: right[r] }
+        }
+        BlockAlignment::DeletedLeft { left: l } => BlockAlignment::DeletedLeft { left: left[l] },
+        BlockAlignment::Split { left: l, right: rs, similarity } => BlockAlignment::Split {
+            left: left[l],
+            right: rs.into_iter().map(|r| right[r]).collect(),
+            similarity,
+        },
+        BlockAlignment::Merged { left: ls, right: r, similarity } => BlockAlignment::Merged {
+            left: ls.into_iter().map(|l| left[l]).collect(),
+            right: right[r],
+            similarity,
+        },
+    }
+}
+
+/// `entries` in left-document order, each insertion placed before the
+/// first entry with a later right block — the order
+/// [`align_blocks_with`] assembles its output in.
+fn in_document_order(entries: Vec<Entry>) -> Vec<Entry> {
+    let (mut anchored, mut inserted): (Vec<Entry>, Vec<Entry>) =
+        entries.into_iter().partition(|e| !sides(&e.alignment).0.is_empty());
+    anchored.sort_by_key(|e| sides(&e.alignment).0[0]);
+    inserted.sort_by_key(|e| sides(&e.alignment).1[0]);
+
+    let mut ordered = Vec::with_capacity(anchored.len() + inserted.len());
+    let mut pending = inserted.into_iter().peekable();
+    for entry in anchored {
+        if let Some(&right) = sides(&entry.alignment).1.first() {
+            while let Some(insert) = pending.next_if(|i| sides(&i.alignment).1[0] < right) {
+                ordered.push(insert);
+            }
+        }
+        ordered.push(entry);
+    }
+    ordered.extend(pending);
+    ordered
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::CompareConfig;
+
+    fn block(doc: Uuid, path: &str, text: &str, idx: i32) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, doc, idx)
+    }
+
+    fn summary(result: &CompareResult) -> Vec<(DeltaKind, Option<Uuid>, Option<Uuid>)> {
+        result
+            .deltas
+            .iter()
+            .map(|d| (d.kind.clone(), d.left_block_id, d.right_block_id))
+            .collect()
+    }
+
+    #[test]
+    fn incremental_compare_matches_a_full_compare() {
+        let (l, r) = (Uuid::new_v4(), Uuid::new_v4());
+        let left = vec![
+            block(l, "1.", "the borrower shall repay the loan in full", 0),
+            block(l, "2.", "the lender may assign its rights to an affiliate", 1),
+            block(l, "3.", "notices must be given in writing to the address below", 2),
+            block(l, "4.", "this agreement is governed by the laws of new york", 3),
+        ];
+        let mut right: Vec<Block> = left
+            .iter()
+            .map(|b| Block { document_id: r, ..b.clone() })
+            .collect();
+        let engine = CompareEngine::default();
+        let previous = engine.compare(l, r, &left, &right);
+        assert_eq!(previous.stats.unchanged, 4);
+
+        // Edit clause 2, delete clause 3 and add clause 5.
+        right[1] = block(r, "2.", "the lender may not assign its rights to an affiliate", 1);
+        right.remove(2);
+        right.push(block(r, "5.", "each party bears its own costs of enforcement", 3));
+        let dirty = [right[1].id, right[3].id];
+
+        let incremental =
+            engine.compare_incremental(l, r, &left, &right, &previous.deltas, &dirty);
+        let full = engine.compare(l, r, &left, &right);
+        assert_eq!(summary(&incremental), summary(&full));
+        assert_eq!(incremental.stats.modified, 1);
+        assert_eq!(incremental.stats.deleted, 1);
+        assert_eq!(incremental.stats.inserted, 1);
+        assert_eq!(incremental.stats.unchanged, 2);
+        // Reused deltas keep their ids; their ordinals follow the edit.
+        let last = incremental.deltas.iter().find(|d| d.left_block_id == Some(left[3].id));
+        let last = last.unwrap();
+        assert_eq!(last.id, previous.deltas[3].id);
+        assert_eq!(last.right_ordinal, Some(2));
+        let diffs = |d: &BlockDelta| {
+            d.token_diffs.iter().map(|t| (t.kind.clone(), t.right_tokens.clone())).collect::<Vec<_>>()
+        };
+        assert_eq!(diffs(&incremental.deltas[1]), diffs(&full.deltas[1]));
+    }
+
+    #[test]
+    fn trimmed_previous_deltas_are_recomputed() {
+        let (l, r) = (Uuid::new_v4(), Uuid::new_v4());
+        let left = vec![
+            block(l, "1.", "the borrower shall repay the loan", 0),
+            block(l, "2.", "the lender may assign its rights", 1),
+        ];
+        let right = vec![
+            block(r, "1.", "the borrower shall promptly repay the loan", 0),
+            block(r, "2.", "the lender may assign its rights", 1),
+        ];
+        let config = CompareConfig {
+            output_mode: crate::worker::CompareOutputMode::ChangesOnly,
+            ..Default::default()
+        };
+        let previous = CompareEngine::new(config).compare(l, r, &left, &right);
+        assert_eq!(previous.deltas.len(), 1);
+
+        let engine = CompareEngine::default();
+        let result = engine.compare_incremental(l, r, &left, &right, &previous.deltas, &[]);
+        assert_eq!(result.deltas.len(), 2);
+        assert_ne!(result.deltas[0].id, previous.deltas[0].id);
+        assert!(result.deltas[0].token_diffs.iter().all(|d| !d.left_tokens.is_empty()
+            || !d.right_tokens.is_empty()));
+    }
+}
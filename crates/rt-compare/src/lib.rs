@@ -1,8 +1,25 @@
 pub mod align;
+pub mod amendment;
+pub mod annotate;
+pub mod attachments;
+pub mod calibrate;
 pub mod tokenize;
 pub mod diff;
+pub mod entities;
+pub mod formatting;
+pub mod html;
+pub mod incremental;
+pub mod normalize;
+pub mod pool;
+pub mod regression;
 pub mod worker;
 pub mod result;
+pub mod table;
+pub mod terms;
+pub mod text;
+pub mod xref;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub use result::*;
-pub use worker::{CompareEngine, CompareConfig};
+pub use worker::{CompareConfig, CompareEngine, CompareOutputMode};
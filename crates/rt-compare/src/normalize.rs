@@ -0,0 +1,309 @@
+//! Normalization rules applied before alignment and diffing.
+//!
+//! Comparing a draft against a template, a firm usually does not care that
+//! the dates, amounts or party names differ. A [`NormalizationRule`] makes
+//! such spans compare equal: each span it matches — a run of tokens of one
+//! [`TokenKind`], or a regex match in the canonical text — becomes a single
+//! token whose normalized form is the rule's replacement, and the block's
+//! `clause_hash` and `anchor_signature` are recomputed from its canonical
+//! text with those spans replaced. Token diffs still show each side's own
+//! text.
+//!
+//! Rules apply in order, each to the tokens the previous ones left.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use rt_model::error::{Result, RtError};
+use rt_model::hash::compute_clause_hash;
+use rt_model::{compute_anchor_signature, Block, Token, TokenKind};
+
+use crate::tokenize::tokenize;
+use crate::worker::ensure_tokens;
+
+/// One rule of a [`Normalizer`], as given in the compare options:
+///
+/// ```json
+/// {"type": "token_kind", "kind": "date_ref", "replacement": "<date>"}
+/// {"type": "regex", "pattern": "\\$[0-9][0-9,.]*", "replacement": "<amount>"}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum NormalizationRule {
+    /// Runs of tokens of `kind`, with any punctuation between them, so that
+    /// `January 1, 2025` is one date and `1,000.00` one number.
+    TokenKind { kind: TokenKind, replacement: String },
+    /// Matches of the regular expression `pattern` in a block's canonical
+    /// text, widened to whole tokens.
+    Regex { pattern: String, replacement: String },
+}
+
+/// A compiled list of [`NormalizationRule`]s.
+#[derive(Debug, Clone, Default)]
+pub struct Normalizer {
+    rules: Vec<(Matcher, String)>,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Kind(TokenKind),
+    Regex(Regex),
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+impl Normalizer {
+    /// Compile `rules`. `InvalidInput` when a pattern is not a valid regular
+    /// expression or matches the empty string.
+    pub fn new(rules: &[NormalizationRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| match rule {
+                NormalizationRule::TokenKind { kind, replacement } => {
+                    Ok((Matcher::Kind(kind.clone()), replacement.clone()))
+                }
+                NormalizationRule::Regex { pattern, replacement } => {
+                    let regex = Regex::new(pattern).map_err(|e| {
+                        RtError::InvalidInput(format!("invalid normalization pattern: {e}"))
+                    })?;
+                    if regex.is_match("") {
+                        return Err(RtError::InvalidInput(format!(
+                            "normalization pattern {pattern:?} matches the empty string"
+                        )));
+                    }
+                    Ok((Matcher::Regex(regex), replacement.clone()))
+                }
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Whether there are no rules, so normalizing changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// [`tokenize`] `text` and apply the rules to its tokens.
+    pub fn tokenize(&self, text: &str) -> Vec<Token> {
+        self.apply(text, tokenize(text)).into_iter().map(|(token, _)| token).collect()
+    }
+
+    /// Apply the rules to `block`'s tokens (tokenizing its canonical text
+    /// when it has none) and recompute its hashes from the normalized text.
+    /// Children are left alone; normalize a flattened list. Does nothing
+    /// without rules.
+    pub fn normalize_block(&self, block: &mut Block) {
+        if self.is_empty() {
+            return;
+        }
+        let tokens = self.apply(&block.canonical_text, ensure_tokens(block));
+        let text = replace_spans(&block.canonical_text, &tokens);
+        block.tokens = tokens.into_iter().map(|(token, _)| token).collect();
+        block.clause_hash = compute_clause_hash(&text);
+        block.anchor_signature =
+            compute_anchor_signature(&block.block_type, &block.structural_path, &text);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// A token, and whether a rule replaced it.
+type Marked = (Token, bool);
+
+impl Normalizer {
+    /// `tokens` of `text` (in offset order) after every rule, each marked
+    /// with whether a rule produced it.
+    fn apply(&self, text: &str, tokens: Vec<Token>) -> Vec<Marked> {
+        let mut tokens: Vec<Marked> = tokens.into_iter().map(|t| (t, false)).collect();
+        for (matcher, replacement) in &self.rules {
+            tokens = match matcher {
+                Matcher::Kind(kind) => merge_kind_runs(text, &tokens, kind, replacement),
+                Matcher::Regex(regex) => merge_matches(text, &tokens, regex, replacement),
+            };
+        }
+        tokens
+    }
+}
+
+/// Merge each run of `tokens` starting and ending with a token of `kind`,
+/// with only punctuation (or whitespace) in between.
+fn merge_kind_runs(
+    text: &str,
+    tokens: &[Marked],
+    kind: &TokenKind,
+    replacement: &str,
+) -> Vec<Marked> {
+    let is_gap = |t: &Token| matches!(t.kind, TokenKind::Punctuation | TokenKind::Whitespace);
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        if tokens[i].0.kind != *kind {
+            out.push(tokens[i].clone());
+            i += 1;
+            continue;
+        }
+        let mut end = i + 1;
+        for (j, (token, _)) in tokens.iter().enumerate().skip(i + 1) {
+            if token.kind == *kind {
+                end = j + 1;
+            } else if !is_gap(token) {
+                break;
+            }
+        }
+        out.push((merge(text, &tokens[i..end], replacement), true));
+        i = end;
+    }
+    out
+}
+
+/// Merge the tokens overlapping each match of `regex` in `text`.
+fn merge_matches(text: &str, tokens: &[Marked], regex: &Regex, replacement: &str) -> Vec<Marked> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    for m in regex.find_iter(text) {
+        while i < tokens.len() && token_end(&tokens[i].0) <= m.start() {
+            out.push(tokens[i].clone());
+            i += 1;
+        }
+        let first = i;
+        while i < tokens.len() && tokens[i].0.offset < m.end() {
+            i += 1;
+        }
+        if i > first {
+            out.push((merge(text, &tokens[first..i], replacement), true));
+        }
+    }
+    out.extend_from_slice(&tokens[i..]);
+    out
+}
+
+/// One token spanning `run`, normalized to `replacement`.
+fn merge(text: &str, run: &[Marked], replacement: &str) -> Token {
+    let first = &run[0].0;
+    let end = run.iter().map(|(t, _)| token_end(t)).max().unwrap_or(first.offset);
+    let text = text.get(first.offset..end).map(str::to_string).unwrap_or_else(|| {
+        run.iter().map(|(t, _)| t.text.as_str()).collect::<Vec<_>>().join(" ")
+    });
+    Token {
+        text,
+        kind: first.kind.clone(),
+        normalized: replacement.to_string(),
+        offset: first.offset,
+    }
+}
+
+fn token_end(token: &Token) -> usize {
+    token.offset + token.text.len()
+}
+
+/// `text` with the span of every replaced token swapped for its normalized
+/// form. Tokens whose offsets do not fit `text` are kept as written.
+fn replace_spans(text: &str, tokens: &[Marked]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (token, _) in tokens.iter().filter(|(_, replaced)| *replaced) {
+        let Some(gap) = text.get(cursor..token.offset) else {
+            continue;
+        };
+        out.push_str(gap);
+        out.push_str(&token.normalized);
+        cursor = token_end(token);
+    }
+    out.push_str(text.get(cursor..).unwrap_or_default());
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::BlockType;
+    use uuid::Uuid;
+
+    fn rules(json: &str) -> Normalizer {
+        let rules: Vec<NormalizationRule> = serde_json::from_str(json).unwrap();
+        Normalizer::new(&rules).unwrap()
+    }
+
+    fn block(text: &str) -> Block {
+        Block::new(BlockType::Clause, "1.1", text, text, None, Uuid::new_v4(), 0)
+    }
+
+    #[test]
+    fn kind_rules_merge_runs_into_one_token() {
+        let normalizer =
+            rules(r#"[{"type": "token_kind", "kind": "date_ref", "replacement": "<date>"}]"#);
+        let tokens = normalizer.tokenize("Due on January 1, 2025, or later.");
+        let texts: Vec<_> =
+            tokens.iter().map(|t| (t.text.as_str(), t.normalized.as_str())).collect();
+        assert_eq!(
+            texts,
+            [
+                ("Due", "due"),
+                ("on", "on"),
+                ("January 1, 2025", "<date>"),
+                (",", ","),
+                ("or", "or"),
+                ("later", "later"),
+                (".", "."),
+            ]
+        );
+        assert_eq!(tokens[2].offset, 7);
+    }
+
+    #[test]
+    fn regex_rules_widen_matches_to_whole_tokens() {
+        let normalizer = rules(
+            r#"[{"type": "regex", "pattern": "\\$[0-9][0-9,.]*", "replacement": "<amount>"}]"#,
+        );
+        let tokens = normalizer.tokenize("A fee of $1,000.50 is due.");
+        let texts: Vec<_> = tokens.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["A", "fee", "of", "$1,000.50", "is", "due", "."]);
+        assert_eq!(tokens[3].normalized, "<amount>");
+    }
+
+    #[test]
+    fn normalized_blocks_hash_alike() {
+        let normalizer = rules(
+            r#"[{"type": "token_kind", "kind": "date_ref", "replacement": "<date>"},
+                {"type": "regex", "pattern": "ACME Corp\\.|Globex LLC",
+                 "replacement": "<party>"}]"#,
+        );
+        let mut left = block("ACME Corp. shall pay by March 3, 2025.");
+        let mut right = block("Globex LLC shall pay by 1 June 2026.");
+        let mut other = block("Globex LLC shall pay by 1 June 2026 at the latest.");
+        for b in [&mut left, &mut right, &mut other] {
+            normalizer.normalize_block(b);
+        }
+        assert_eq!(left.clause_hash, right.clause_hash);
+        assert_eq!(left.anchor_signature, right.anchor_signature);
+        assert_ne!(left.clause_hash, other.clause_hash);
+        assert_eq!(left.canonical_text, "ACME Corp. shall pay by March 3, 2025.");
+
+        let mut plain = block("ACME Corp. shall pay.");
+        let hash = plain.clause_hash.clone();
+        Normalizer::default().normalize_block(&mut plain);
+        assert_eq!(plain.clause_hash, hash);
+        assert!(plain.tokens.is_empty());
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        for pattern in ["(", "x*"] {
+            let rule = NormalizationRule::Regex {
+                pattern: pattern.into(),
+                replacement: "<x>".into(),
+            };
+            assert!(matches!(Normalizer::new(&[rule]), Err(RtError::InvalidInput(_))));
+        }
+        let unknown = r#"[{"type": "token_kind", "kind": "date_ref", "replacement": "", "x": 1}]"#;
+        assert!(serde_json::from_str::<Vec<NormalizationRule>>(unknown).is_err());
+    }
+}
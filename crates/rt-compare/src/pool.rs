@@ -0,0 +1,128 @@
+//! Rayon thread pools sized by [`CompareConfig::worker_threads`].
+//!
+//! Each distinct thread count gets one pool, built on first use and cached,
+//! so engines configured alike share their workers and a compare rarely
+//! pays for spawning threads. Counts are capped at [`max_worker_threads`]
+//! and at most [`MAX_CACHED_POOLS`] pools are kept, so callers choosing
+//! their own counts cannot grow the number of threads without bound. The
+//! parallel sections of alignment and diffing run inside [`install`].
+//!
+//! [`CompareConfig::worker_threads`]: crate::worker::CompareConfig::worker_threads
+
+#[cfg(feature = "parallel")]
+use std::collections::HashMap;
+#[cfg(feature = "parallel")]
+use std::sync::{Arc, Mutex, OnceLock};
+
+use rt_model::error::{Result, RtError};
+
+/// Worker count a compare may always ask for; see [`max_worker_threads`].
+pub const MAX_WORKER_THREADS: usize = 64;
+
+/// Number of pools kept at once; building another drops one of them, whose
+/// threads exit once the compares running on it finish.
+pub const MAX_CACHED_POOLS: usize = 4;
+
+/// Largest worker count a compare may ask for: [`MAX_WORKER_THREADS`], or
+/// the machine's available parallelism (rayon's global pool size) when
+/// that is larger.
+pub fn max_worker_threads() -> usize {
+    let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+    #[cfg(feature = "parallel")]
+    let available = available.max(rayon::current_num_threads());
+    available.max(MAX_WORKER_THREADS)
+}
+
+/// Fail unless `threads` is between 1 and [`max_worker_threads`].
+pub fn check_worker_threads(threads: usize) -> Result<()> {
+    let max = max_worker_threads();
+    if !(1..=max).contains(&threads) {
+        return Err(RtError::InvalidInput(format!(
+            "worker_threads must be between 1 and {max}, got {threads}"
+        )));
+    }
+    Ok(())
+}
+
+/// The cached pools, by thread count.
+#[cfg(feature = "parallel")]
+fn pools() -> &'static Mutex<HashMap<usize, Arc<rayon::ThreadPool>>> {
+    static POOLS: OnceLock<Mutex<HashMap<usize, Arc<rayon::ThreadPool>>>> = OnceLock::new();
+    POOLS.get_or_init(Default::default)
+}
+
+/// The pool of `threads` workers, built the first time it is asked for.
+/// Fails for a count beyond [`max_worker_threads`] or a pool the system
+/// will not start threads for.
+#[cfg(feature = "parallel")]
+pub fn thread_pool(threads: usize) -> Result<Arc<rayon::ThreadPool>> {
+    check_worker_threads(threads)?;
+    let mut pools = pools().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(pool) = pools.get(&threads) {
+        return Ok(pool.clone());
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(move |i| format!("rt-compare-{threads}-{i}"))
+        .build()
+        .map_err(|e| RtError::Internal(format!("failed to build the compare thread pool: {e}")))?;
+    if pools.len() >= MAX_CACHED_POOLS {
+        if let Some(&evicted) = pools.keys().next() {
+            pools.remove(&evicted);
+        }
+    }
+    Ok(pools.entry(threads).or_insert(Arc::new(pool)).clone())
+}
+
+/// Run `op` with its parallel iterators on the pool of `threads` workers;
+/// `0` runs it on rayon's global pool. Without the `parallel` feature `op`
+/// simply runs. Fails, without running `op`, when that pool cannot be had.
+pub fn install<R: Send>(threads: usize, op: impl FnOnce() -> R + Send) -> Result<R> {
+    #[cfg(feature = "parallel")]
+    if threads > 0 {
+        return Ok(thread_pool(threads)?.install(op));
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = threads;
+    Ok(op())
+}
+
+/// `threads` if its pool can be had, else `0` (rayon's global pool), for
+/// callers that cannot report a failure to build one.
+pub fn usable_threads(threads: usize) -> usize {
+    #[cfg(feature = "parallel")]
+    if threads > 0 && thread_pool(threads).is_err() {
+        return 0;
+    }
+    threads
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pools_are_sized_per_thread_count() {
+        let max = max_worker_threads();
+        assert_eq!(install(max, rayon::current_num_threads).unwrap(), max);
+        assert_eq!(install(1, rayon::current_num_threads).unwrap(), 1);
+    }
+
+    #[test]
+    fn thread_counts_beyond_the_cap_get_no_pool() {
+        let max = max_worker_threads();
+        assert!(matches!(thread_pool(0), Err(RtError::InvalidInput(_))));
+        assert!(matches!(thread_pool(max + 1), Err(RtError::InvalidInput(_))));
+        assert!(install(max + 1, || ()).is_err());
+        assert_eq!(usable_threads(max + 1), 0);
+        assert_eq!(usable_threads(1), 1);
+    }
+
+    #[test]
+    fn the_pool_cache_is_bounded() {
+        for threads in 1..=MAX_CACHED_POOLS + 2 {
+            assert_eq!(thread_pool(threads).unwrap().current_num_threads(), threads);
+        }
+        assert!(pools().lock().unwrap().len() <= MAX_CACHED_POOLS);
+    }
+}
@@ -0,0 +1,180 @@
+//! Differences between two compare results for the same document pair.
+//!
+//! Re-running a comparison after an engine upgrade or a config change
+//! should reproduce the deltas a reviewer has already checked. [`diff_results`]
+//! pairs the deltas of a `before` and an `after` result by the blocks they
+//! describe (`left_block_id`, `right_block_id`) and reports the deltas only
+//! one side has and the pairs whose [`DeltaKind`] changed; an empty
+//! [`ResultDiff`] means the runs classified every block pair alike.
+//!
+//! Deltas are paired by block id, so both results must be of the same two
+//! stored documents; a change in alignment (a modified pair becoming a
+//! deletion and an insertion, say) shows up as deltas disappearing and
+//! appearing rather than as a reclassification.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::result::{BlockDelta, CompareResult, DeltaKind};
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// A block pair both results report, with different kinds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reclassified {
+    pub left_block_id: Option<Uuid>,
+    pub right_block_id: Option<Uuid>,
+    pub before: DeltaKind,
+    pub after: DeltaKind,
+}
+
+/// What changed between two compare results; see [`diff_results`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResultDiff {
+    /// Deltas only the `after` result has, in its order.
+    pub appeared: Vec<BlockDelta>,
+    /// Deltas only the `before` result has, in its order.
+    pub disappeared: Vec<BlockDelta>,
+    /// Block pairs whose kind changed, in the `after` result's order.
+    pub reclassified: Vec<Reclassified>,
+    /// Number of block pairs both results classify alike.
+    pub unchanged: usize,
+}
+
+impl ResultDiff {
+    /// Whether the two results classify every block pair alike.
+    pub fn is_empty(&self) -> bool {
+        self.appeared.is_empty() && self.disappeared.is_empty() && self.reclassified.is_empty()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Diff the deltas of two compare results of the same document pair.
+pub fn diff_results(before: &CompareResult, after: &CompareResult) -> ResultDiff {
+    diff_deltas(&before.deltas, &after.deltas)
+}
+
+/// As [`diff_results`], for bare delta lists (e.g. the stored deltas of a
+/// recorded run).
+pub fn diff_deltas(before: &[BlockDelta], after: &[BlockDelta]) -> ResultDiff {
+    // A pair normally has one delta per result; queue repeats so each
+    // pairs with at most one delta of the other side, in order.
+    let mut unmatched: HashMap<(Option<Uuid>, Option<Uuid>), VecDeque<usize>> = HashMap::new();
+    for (index, delta) in before.iter().enumerate() {
+        unmatched.entry(key(delta)).or_default().push_back(index);
+    }
+
+    let mut diff = ResultDiff::default();
+    let mut matched = vec![false; before.len()];
+    for delta in after {
+        let Some(index) = unmatched.get_mut(&key(delta)).and_then(VecDeque::pop_front) else {
+            diff.appeared.push(delta.clone());
+            continue;
+        };
+        matched[index] = true;
+        let earlier = &before[index];
+        if earlier.kind == delta.kind {
+            diff.unchanged += 1;
+        } else {
+            diff.reclassified.push(Reclassified {
+                left_block_id: delta.left_block_id,
+                right_block_id: delta.right_block_id,
+                before: earlier.kind.clone(),
+                after: delta.kind.clone(),
+            });
+        }
+    }
+    diff.disappeared = before
+        .iter()
+        .zip(&matched)
+        .filter(|(_, &matched)| !matched)
+        .map(|(delta, _)| delta.clone())
+        .collect();
+    diff
+}
+
+fn key(delta: &BlockDelta) -> (Option<Uuid>, Option<Uuid>) {
+    (delta.left_block_id, delta.right_block_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::{CompareConfig, CompareEngine};
+    use rt_model::{Block, BlockType};
+
+    fn block(doc: Uuid, path: &str, text: &str, idx: i32) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, doc, idx)
+    }
+
+    fn fixture() -> (Vec<Block>, Vec<Block>) {
+        let (l, r) = (Uuid::new_v4(), Uuid::new_v4());
+        let left = vec![
+            block(l, "1.", "The Borrower shall repay the Loan within thirty days.", 0),
+            block(l, "2.", "The Lender may assign its rights.", 1),
+        ];
+        let right = vec![
+            block(r, "1.", "The Borrower shall repay the Loan within sixty days.", 0),
+            block(r, "2.", "The Lender may assign its rights.", 1),
+        ];
+        (left, right)
+    }
+
+    fn compare(config: CompareConfig, left: &[Block], right: &[Block]) -> CompareResult {
+        let (l, r) = (left[0].document_id, right[0].document_id);
+        CompareEngine::new(config).compare(l, r, left, right)
+    }
+
+    #[test]
+    fn rerunning_a_comparison_changes_nothing() {
+        let (left, right) = fixture();
+        let before = compare(CompareConfig::default(), &left, &right);
+        let after = compare(CompareConfig::default(), &left, &right);
+        let diff = diff_results(&before, &after);
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, before.deltas.len());
+    }
+
+    #[test]
+    fn a_realigned_pair_disappears_and_its_halves_appear() {
+        let (left, right) = fixture();
+        let before = compare(CompareConfig::default(), &left, &right);
+        assert_eq!(before.deltas[0].kind, DeltaKind::Modified);
+        let mut after = before.clone();
+        let mut deleted = after.deltas[0].clone();
+        (deleted.kind, deleted.right_block_id) = (DeltaKind::Deleted, None);
+        let mut inserted = after.deltas[0].clone();
+        (inserted.kind, inserted.left_block_id) = (DeltaKind::Inserted, None);
+        after.deltas.splice(0..1, [deleted, inserted]);
+        let diff = diff_results(&before, &after);
+
+        assert_eq!(diff.disappeared.len(), 1);
+        assert_eq!(diff.disappeared[0].kind, DeltaKind::Modified);
+        let appeared: Vec<_> = diff.appeared.iter().map(|d| d.kind.clone()).collect();
+        assert_eq!(appeared, vec![DeltaKind::Deleted, DeltaKind::Inserted]);
+        assert!(diff.reclassified.is_empty());
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    #[test]
+    fn a_changed_kind_is_reported_as_reclassified() {
+        let (left, right) = fixture();
+        let before = compare(CompareConfig::default(), &left, &right);
+        let mut after = before.clone();
+        after.deltas[1].kind = DeltaKind::Moved;
+        let diff = diff_results(&before, &after);
+
+        assert!(diff.appeared.is_empty() && diff.disappeared.is_empty());
+        assert_eq!(diff.reclassified.len(), 1);
+        let change = &diff.reclassified[0];
+        assert_eq!(change.left_block_id, before.deltas[1].left_block_id);
+        assert_eq!((&change.before, &change.after), (&before.deltas[1].kind, &DeltaKind::Moved));
+    }
+}
@@ -3,10 +3,17 @@
 //! These types are serialized to JSON and must match the contract defined in
 //! `contracts/compare-result.json`.
 
+use rt_model::anchor::delta_link_anchor;
+use rt_model::manifest::RunManifest;
+use rt_model::Block;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::diff::TokenDiff;
+use crate::attachments::AttachmentChange;
+use crate::diff::{SentenceDiff, TokenDiff};
+use crate::formatting::FormattingDrift;
+use crate::table::TableChange;
+use crate::xref::BrokenReference;
 
 // ---------------------------------------------------------------------------
 // DeltaKind
@@ -22,8 +29,14 @@ pub enum DeltaKind {
     Deleted,
     /// Block exists in both documents but its content has changed.
     Modified,
+    /// Block exists in both documents with identical content.
+    Unchanged,
     /// Block exists in both documents but its structural position has changed.
     Moved,
+    /// One left block was split into several adjacent right blocks.
+    Split,
+    /// Several adjacent left blocks were merged into one right block.
+    Merged,
 }
 
 // ---------------------------------------------------------------------------
@@ -33,7 +46,8 @@ pub enum DeltaKind {
 /// Comparison result for one aligned pair (or singleton) of blocks.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockDelta {
-    /// Stable unique identifier for this delta record (UUIDv4).
+    /// Stable unique identifier for this delta record (UUIDv4; derived from
+    /// its block ids and kind in deterministic mode).
     pub id: Uuid,
     /// Disposition of this block pair.
     pub kind: DeltaKind,
@@ -47,7 +61,7 @@ pub struct BlockDelta {
     /// Zero-based position of this block in the right document's flat block list;
     /// `None` for deleted blocks.
     pub right_ordinal: Option<usize>,
-    /// Token-level diffs; empty for non-modified deltas.
+    /// Token-level diffs; empty for inserted, deleted and unchanged deltas.
     pub token_diffs: Vec<TokenDiff>,
     /// Normalised text similarity in [0.0, 1.0] between the two block versions;
     /// `None` for inserted or deleted blocks.
@@ -55,6 +69,61 @@ pub struct BlockDelta {
     /// For `kind = Moved`: the UUID of the corresponding block in the target
     /// document; `None` otherwise.
     pub move_target_id: Option<Uuid>,
+    /// For `kind = Split`: every right block the left block was split into;
+    /// for `kind = Merged`: every left block merged into the right block. In
+    /// document order, so the first is `right_block_id` / `left_block_id`.
+    /// Empty (and omitted from JSON) otherwise.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub group_block_ids: Vec<Uuid>,
+    /// Plain-language summary of the change, attached after comparison by
+    /// [`crate::annotate::attach_summaries`]; omitted from JSON when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// `structural_path` of the top-level block (section) the changed block
+    /// belongs to — in the right document, or the left for deletions. Set
+    /// at compare time; omitted from JSON when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// `token_diffs` grouped by sentence, when the `sentence_diffs` compare
+    /// option is set; empty (and omitted from JSON) otherwise.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sentences: Vec<SentenceDiff>,
+    /// Content-derived deep-link anchors of the delta and its blocks, set at
+    /// compare time; omitted from JSON when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchors: Option<DeltaAnchors>,
+    /// Authors of the tracked changes (`TrackedChange::author`) on the
+    /// delta's blocks, either side, in document order without repeats, so a
+    /// host can filter the diff by reviewer. Set at compare time; empty (and
+    /// omitted from JSON) when none of the blocks carries a tracked change.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+}
+
+/// Deep-link anchors of a [`BlockDelta`] (see `rt_model::anchor`). They are
+/// derived from block content, not block ids, so the same change gets the
+/// same anchors every time the documents are compared.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaAnchors {
+    /// Anchor of the delta, built from the anchors of its two blocks.
+    pub delta: String,
+    /// Anchor of the left block; `None` for insertions.
+    pub left_block: Option<String>,
+    /// Anchor of the right block; `None` for deletions.
+    pub right_block: Option<String>,
+}
+
+impl DeltaAnchors {
+    /// Anchors of the delta between `left` and `right`.
+    pub fn new(left: Option<&Block>, right: Option<&Block>) -> Self {
+        let left_block = left.map(Block::link_anchor);
+        let right_block = right.map(Block::link_anchor);
+        Self {
+            delta: delta_link_anchor(left_block.as_deref(), right_block.as_deref()),
+            left_block,
+            right_block,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -78,6 +147,45 @@ pub struct CompareStats {
     pub moved: usize,
     /// Number of aligned block pairs that are identical in both documents.
     pub unchanged: usize,
+    /// Number of left blocks split into several right blocks.
+    #[serde(default)]
+    pub split: usize,
+    /// Number of right blocks formed by merging several left blocks.
+    #[serde(default)]
+    pub merged: usize,
+}
+
+// ---------------------------------------------------------------------------
+// CompareWarning
+// ---------------------------------------------------------------------------
+
+/// Reason a block pair was degraded to a coarse replacement.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareWarningKind {
+    /// One side exceeded `CompareConfig::max_block_tokens`; no token diff was
+    /// attempted.
+    BlockTooLarge,
+    /// The token diff exceeded `CompareConfig::max_diff_groups` groups.
+    DiffTooLarge,
+}
+
+/// A non-fatal condition encountered while comparing a block pair.
+///
+/// The referenced delta carries a single coarse token-diff group covering
+/// the whole block instead of a fine-grained diff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareWarning {
+    /// The [`BlockDelta::id`] the warning applies to.
+    pub delta_id: Uuid,
+    /// UUID of the left block, if any.
+    pub left_block_id: Option<Uuid>,
+    /// UUID of the right block, if any.
+    pub right_block_id: Option<Uuid>,
+    /// Why the delta was degraded.
+    pub kind: CompareWarningKind,
+    /// Human-readable description including the observed size and limit.
+    pub message: String,
 }
 
 // ---------------------------------------------------------------------------
@@ -90,18 +198,119 @@ pub struct CompareStats {
 /// `contracts/compare-result.json`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompareResult {
-    /// Stable unique identifier for this comparison run (UUIDv4).
+    /// Stable unique identifier for this comparison run (UUIDv4; derived from
+    /// the run's manifest in deterministic mode).
     pub run_id: Uuid,
     /// UUID of the left (base) document.
     pub left_doc_id: Uuid,
     /// UUID of the right (incoming) document.
     pub right_doc_id: Uuid,
-    /// Wall-clock duration of the comparison run in milliseconds.
+    /// Wall-clock duration of the comparison run in milliseconds; 0 in
+    /// deterministic mode.
     pub elapsed_ms: u64,
     /// Aggregate block-level counts for this comparison.
     pub stats: CompareStats,
     /// Ordered list of per-block deltas in left-document traversal order.
+    /// Trimmed or empty unless `CompareConfig::output_mode` is `full`;
+    /// `stats` still counts every delta.
     pub deltas: Vec<BlockDelta>,
+    /// Document-level formatting comparison (style usage, numbering
+    /// definitions, font sizes), independent of the content deltas.
+    pub formatting_drift: FormattingDrift,
+    /// Images and embedded objects added, removed or replaced, compared by
+    /// content hash; see [`crate::attachments`].
+    #[serde(default)]
+    pub attachment_changes: Vec<AttachmentChange>,
+    /// Rows and columns inserted into or deleted from aligned tables; the
+    /// cells involved also appear as inserted / deleted deltas. See
+    /// [`crate::table`].
+    #[serde(default)]
+    pub table_changes: Vec<TableChange>,
+    /// Cross-references in the right document whose target was deleted or
+    /// renumbered; see [`crate::xref`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub broken_references: Vec<BrokenReference>,
+    /// Per-block warnings, e.g. diffs degraded by the size guard.
+    pub warnings: Vec<CompareWarning>,
+    /// How this result was produced; absent on results serialised before
+    /// manifests were recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<RunManifest>,
+}
+
+// ---------------------------------------------------------------------------
+// CompareProgress
+// ---------------------------------------------------------------------------
+
+/// Stage of a running comparison, in the order the stages run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparePhase {
+    /// Flattening both block trees.
+    Flatten,
+    /// Aligning tables row by row and column by column.
+    AlignTables,
+    /// Alignment pass 1: exact structural path matches.
+    AlignPaths,
+    /// Alignment pass 2: anchor signature matches.
+    AlignAnchors,
+    /// Alignment pass 3: similarity scoring.
+    AlignSimilarity,
+    /// Alignment pass 4: LCS alignment of the blocks still unmatched.
+    AlignSequence,
+    /// Alignment pass 5: split and merge detection (only when enabled).
+    AlignSplits,
+    /// Diffing aligned blocks, batch by batch.
+    #[default]
+    Diff,
+    /// Formatting, attachment and cross-reference checks once every block
+    /// is diffed.
+    Stats,
+}
+
+impl ComparePhase {
+    /// The phase of alignment pass `pass` (1 to 5).
+    pub(crate) fn align_pass(pass: usize) -> Self {
+        match pass {
+            1 => Self::AlignPaths,
+            2 => Self::AlignAnchors,
+            3 => Self::AlignSimilarity,
+            4 => Self::AlignSequence,
+            _ => Self::AlignSplits,
+        }
+    }
+}
+
+/// How far a running comparison has got, reported by
+/// [`CompareEngine::compare_with_progress`](crate::worker::CompareEngine::compare_with_progress)
+/// as each phase starts and after each batch of the `diff` phase.
+///
+/// Blocks are counted by alignment: a matched pair, or a split or merged
+/// group, counts as one. The total is only known once alignment is done, so
+/// it is 0 before the `diff` phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompareProgress {
+    /// Absent on reports serialised before phases were reported, which were
+    /// all made while diffing.
+    #[serde(default)]
+    pub phase: ComparePhase,
+    pub blocks_processed: usize,
+    pub blocks_total: usize,
+}
+
+impl CompareProgress {
+    /// Share of the blocks processed, from 0.0 to 100.0: 0.0 before the
+    /// `diff` phase, and 100.0 from then on when there is nothing to
+    /// process.
+    pub fn percent(&self) -> f64 {
+        if self.phase < ComparePhase::Diff {
+            return 0.0;
+        }
+        if self.blocks_total == 0 {
+            return 100.0;
+        }
+        self.blocks_processed as f64 * 100.0 / self.blocks_total as f64
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -127,6 +336,8 @@ mod tests {
                 modified: 1,
                 moved: 0,
                 unchanged: 2,
+                split: 0,
+                merged: 0,
             },
             deltas: vec![
                 BlockDelta {
@@ -142,9 +353,16 @@ mod tests {
                         right_tokens: vec!["the".to_string()],
                         left_offset: 0,
                         right_offset: 0,
+                        char_diffs: vec![],
                     }],
                     similarity_score: Some(0.9),
                     move_target_id: None,
+                    group_block_ids: vec![],
+                    summary: None,
+                    section: None,
+                    sentences: vec![],
+                    anchors: None,
+                    authors: vec![],
                 },
                 BlockDelta {
                     id: Uuid::new_v4(),
@@ -156,8 +374,20 @@ mod tests {
                     token_diffs: vec![],
                     similarity_score: None,
                     move_target_id: None,
+                    group_block_ids: vec![],
+                    summary: None,
+                    section: None,
+                    sentences: vec![],
+                    anchors: None,
+                    authors: vec![],
                 },
             ],
+            formatting_drift: FormattingDrift::default(),
+            attachment_changes: vec![],
+            table_changes: vec![],
+            broken_references: vec![],
+            warnings: vec![],
+            manifest: None,
         }
     }
 
@@ -190,6 +420,18 @@ mod tests {
             serde_json::to_string(&DeltaKind::Moved).unwrap(),
             "\"moved\""
         );
+        assert_eq!(
+            serde_json::to_string(&DeltaKind::Split).unwrap(),
+            "\"split\""
+        );
+        assert_eq!(
+            serde_json::to_string(&DeltaKind::Merged).unwrap(),
+            "\"merged\""
+        );
+        assert_eq!(
+            serde_json::to_string(&DeltaKind::Unchanged).unwrap(),
+            "\"unchanged\""
+        );
     }
 
     #[test]
@@ -204,6 +446,12 @@ mod tests {
             token_diffs: vec![],
             similarity_score: None,
             move_target_id: None,
+            group_block_ids: vec![],
+            summary: None,
+            section: None,
+            sentences: vec![],
+            anchors: None,
+            authors: vec![],
         };
         let json = serde_json::to_string(&delta).expect("serialize");
         assert!(json.contains("\"left_block_id\":null"));
@@ -222,6 +470,8 @@ mod tests {
             modified: 0,
             moved: 0,
             unchanged: 0,
+            split: 0,
+            merged: 0,
         };
         let json = serde_json::to_string(&stats).expect("serialize");
         assert!(json.contains("\"blocks_left\":0"));
@@ -240,6 +490,12 @@ mod tests {
             token_diffs: vec![],
             similarity_score: Some(0.95),
             move_target_id: Some(target_id),
+            group_block_ids: vec![],
+            summary: None,
+            section: None,
+            sentences: vec![],
+            anchors: None,
+            authors: vec![],
         };
         let json = serde_json::to_string(&delta).expect("serialize");
         assert!(json.contains(&target_id.to_string()));
@@ -0,0 +1,455 @@
+//! Structural alignment of tables.
+//!
+//! Tables are ingested as `Table` → `TableRow` → `TableCell` blocks whose
+//! structural paths encode row and column positions, so aligning cells by
+//! path alone turns a single inserted column into a shifted, Modified copy
+//! of every cell. [`align_tables`] instead aligns each pair of corresponding
+//! tables as a grid:
+//!
+//! 1. **Tables** are paired best-first by the token similarity of all their
+//!    cells.
+//! 2. **Columns** are aligned in order, scoring each pair by the better of
+//!    header-cell similarity and whole-column similarity.
+//! 3. **Rows** are aligned in order by the similarity of their cells in the
+//!    aligned columns, so an inserted column does not disturb row matching.
+//!
+//! Rows or columns left between two aligned neighbours are paired by
+//! position when both sides left the same number, and otherwise by whatever
+//! text they still share. Cells are paired where an
+//! aligned row meets an aligned column; the rest are reported as inserted or
+//! deleted, together with a [`TableChange`] per inserted or deleted row and
+//! column.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::{Block, BlockType};
+
+use crate::align::{token_jaccard, token_set};
+
+/// Minimum similarity of two tables' cell text for them to be aligned as
+/// the same table.
+pub const TABLE_MATCH_THRESHOLD: f64 = 0.3;
+
+/// Minimum score for two rows, or two columns, to be aligned by content.
+pub const LINE_MATCH_THRESHOLD: f64 = 0.5;
+
+// ---------------------------------------------------------------------------
+// TableChange
+// ---------------------------------------------------------------------------
+
+/// What happened to a row or column of an aligned table.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TableChangeKind {
+    RowInserted,
+    RowDeleted,
+    ColumnInserted,
+    ColumnDeleted,
+}
+
+/// A row or column present in only one of two aligned tables.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TableChange {
+    pub kind: TableChangeKind,
+    pub left_table_id: Uuid,
+    pub right_table_id: Uuid,
+    /// Zero-based row or column index, in the right table for insertions
+    /// and in the left table for deletions.
+    pub index: usize,
+    /// The inserted or deleted `TableRow` block; `None` for columns.
+    pub row_block_id: Option<Uuid>,
+    /// The cells of the inserted or deleted row or column, in order.
+    pub cell_block_ids: Vec<Uuid>,
+}
+
+// ---------------------------------------------------------------------------
+// TableAlignment
+// ---------------------------------------------------------------------------
+
+/// Outcome of [`align_tables`], in flat-list indices.
+#[derive(Debug, Clone, Default)]
+pub struct TableAlignment {
+    /// Aligned `(left, right)` table, row and cell blocks.
+    pub pairs: Vec<(usize, usize)>,
+    /// Every table, row and cell block of an aligned left table, paired or
+    /// not; other alignment passes must leave these alone.
+    pub left_settled: HashSet<usize>,
+    /// As `left_settled`, for the right tables.
+    pub right_settled: HashSet<usize>,
+    /// Inserted and deleted rows and columns, table by table in left order.
+    pub changes: Vec<TableChange>,
+}
+
+/// Align the tables of two flat block lists; see the module docs. Tables
+/// without a counterpart are left to the regular alignment passes.
+pub fn align_tables(left: &[Block], right: &[Block]) -> TableAlignment {
+    let left_grids = grids(left);
+    let right_grids = grids(right);
+    let mut aligned = TableAlignment::default();
+    if left_grids.is_empty() || right_grids.is_empty() {
+        return aligned;
+    }
+
+    let left_text: Vec<Vec<String>> = left_grids.iter().map(|g| g.tokens(left)).collect();
+    let right_text: Vec<Vec<String>> = right_grids.iter().map(|g| g.tokens(right)).collect();
+    let mut candidates = Vec::new();
+    for (l, lt) in left_text.iter().enumerate() {
+        for (r, rt) in right_text.iter().enumerate() {
+            let sim = token_jaccard(lt, rt);
+            if sim >= TABLE_MATCH_THRESHOLD {
+                candidates.push((l, r, sim));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+
+    let mut table_pairs = Vec::new();
+    let (mut left_used, mut right_used) = (HashSet::new(), HashSet::new());
+    for (l, r, _) in candidates {
+        if left_used.contains(&l) || right_used.contains(&r) {
+            continue;
+        }
+        left_used.insert(l);
+        right_used.insert(r);
+        table_pairs.push((l, r));
+    }
+    table_pairs.sort_unstable();
+
+    for (l, r) in table_pairs {
+        align_grid(&left_grids[l], &right_grids[r], left, right, &mut aligned);
+    }
+    aligned
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// A table as flat-list indices: the table block, its rows, and each row's
+/// cells in column order.
+struct Grid {
+    table: usize,
+    rows: Vec<usize>,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    fn columns(&self) -> usize {
+        self.cells.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    fn cell(&self, row: usize, column: usize) -> Option<usize> {
+        self.cells[row].get(column).copied()
+    }
+
+    /// Tokens of every cell, row by row.
+    fn tokens(&self, blocks: &[Block]) -> Vec<String> {
+        self.cells.iter().flatten().flat_map(|&c| token_set(&blocks[c])).collect()
+    }
+
+    /// Tokens of the cells of `column`, top to bottom.
+    fn column_tokens(&self, blocks: &[Block], column: usize) -> Vec<String> {
+        (0..self.rows.len())
+            .filter_map(|row| self.cell(row, column))
+            .flat_map(|c| token_set(&blocks[c]))
+            .collect()
+    }
+
+    /// Tokens of the cells of `row` in `columns`, left to right.
+    fn row_tokens(&self, blocks: &[Block], row: usize, columns: &[usize]) -> Vec<String> {
+        columns
+            .iter()
+            .filter_map(|&column| self.cell(row, column))
+            .flat_map(|c| token_set(&blocks[c]))
+            .collect()
+    }
+
+    fn blocks(&self) -> impl Iterator<Item = usize> + '_ {
+        std::iter::once(self.table)
+            .chain(self.rows.iter().copied())
+            .chain(self.cells.iter().flatten().copied())
+    }
+}
+
+/// Every table of `blocks`, assembled through `parent_id` links, with rows
+/// and cells ordered by `position_index` (then list order).
+fn grids(blocks: &[Block]) -> Vec<Grid> {
+    let mut children: HashMap<Uuid, Vec<usize>> = HashMap::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if let Some(parent) = block.parent_id {
+            children.entry(parent).or_default().push(i);
+        }
+    }
+    let children_of = |parent: &Block, block_type: BlockType| -> Vec<usize> {
+        let mut found: Vec<usize> = children
+            .get(&parent.id)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|&i| blocks[i].block_type == block_type)
+            .collect();
+        found.sort_by_key(|&i| (blocks[i].position_index, i));
+        found
+    };
+
+    blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| b.block_type == BlockType::Table)
+        .map(|(table, block)| {
+            let rows = children_of(block, BlockType::TableRow);
+            let cells = rows
+                .iter()
+                .map(|&row| children_of(&blocks[row], BlockType::TableCell))
+                .collect();
+            Grid { table, rows, cells }
+        })
+        .collect()
+}
+
+/// Align the rows, columns and cells of one pair of tables into `out`.
+fn align_grid(lg: &Grid, rg: &Grid, left: &[Block], right: &[Block], out: &mut TableAlignment) {
+    let (left_columns, right_columns) = (lg.columns(), rg.columns());
+    let left_cols: Vec<Vec<String>> =
+        (0..left_columns).map(|c| lg.column_tokens(left, c)).collect();
+    let right_cols: Vec<Vec<String>> =
+        (0..right_columns).map(|c| rg.column_tokens(right, c)).collect();
+    let header = |grid: &Grid, blocks: &[Block], column: usize| -> Vec<String> {
+        match grid.cells.first().and_then(|row| row.get(column)) {
+            Some(&c) => token_set(&blocks[c]),
+            None => Vec::new(),
+        }
+    };
+    let column_pairs = align_sequence(left_columns, right_columns, |l, r| {
+        let (lh, rh) = (header(lg, left, l), header(rg, right, r));
+        let header_sim = if lh.is_empty() || rh.is_empty() {
+            0.0
+        } else {
+            token_jaccard(&lh, &rh)
+        };
+        header_sim.max(token_jaccard(&left_cols[l], &right_cols[r]))
+    });
+
+    let (lc, rc): (Vec<usize>, Vec<usize>) = column_pairs.iter().copied().unzip();
+    let left_rows: Vec<Vec<String>> =
+        (0..lg.rows.len()).map(|row| lg.row_tokens(left, row, &lc)).collect();
+    let right_rows: Vec<Vec<String>> =
+        (0..rg.rows.len()).map(|row| rg.row_tokens(right, row, &rc)).collect();
+    let row_pairs = align_sequence(lg.rows.len(), rg.rows.len(), |l, r| {
+        token_jaccard(&left_rows[l], &right_rows[r])
+    });
+
+    out.pairs.push((lg.table, rg.table));
+    for &(lr, rr) in &row_pairs {
+        out.pairs.push((lg.rows[lr], rg.rows[rr]));
+        for &(l, r) in &column_pairs {
+            if let (Some(lcell), Some(rcell)) = (lg.cell(lr, l), rg.cell(rr, r)) {
+                out.pairs.push((lcell, rcell));
+            }
+        }
+    }
+    out.left_settled.extend(lg.blocks());
+    out.right_settled.extend(rg.blocks());
+
+    let (left_table_id, right_table_id) = (left[lg.table].id, right[rg.table].id);
+    let change = |kind, index, row_block_id, cells: Vec<usize>, blocks: &[Block]| TableChange {
+        kind,
+        left_table_id,
+        right_table_id,
+        index,
+        row_block_id,
+        cell_block_ids: cells.into_iter().map(|c| blocks[c].id).collect(),
+    };
+    let aligned_rows: (HashSet<usize>, HashSet<usize>) = row_pairs.iter().copied().unzip();
+    let aligned_columns: (HashSet<usize>, HashSet<usize>) = column_pairs.iter().copied().unzip();
+    for (row, &block) in lg.rows.iter().enumerate() {
+        if !aligned_rows.0.contains(&row) {
+            let cells = lg.cells[row].clone();
+            let id = Some(left[block].id);
+            out.changes.push(change(TableChangeKind::RowDeleted, row, id, cells, left));
+        }
+    }
+    for (row, &block) in rg.rows.iter().enumerate() {
+        if !aligned_rows.1.contains(&row) {
+            let cells = rg.cells[row].clone();
+            let id = Some(right[block].id);
+            out.changes.push(change(TableChangeKind::RowInserted, row, id, cells, right));
+        }
+    }
+    for column in (0..left_columns).filter(|c| !aligned_columns.0.contains(c)) {
+        let cells = (0..lg.rows.len()).filter_map(|row| lg.cell(row, column)).collect();
+        out.changes.push(change(TableChangeKind::ColumnDeleted, column, None, cells, left));
+    }
+    for column in (0..right_columns).filter(|c| !aligned_columns.1.contains(c)) {
+        let cells = (0..rg.rows.len()).filter_map(|row| rg.cell(row, column)).collect();
+        out.changes.push(change(TableChangeKind::ColumnInserted, column, None, cells, right));
+    }
+}
+
+/// Order-preserving alignment of two sequences of `n` and `m` items that
+/// maximises the total `score` of pairs scoring at least
+/// [`LINE_MATCH_THRESHOLD`]. Items left unpaired between two consecutive
+/// pairs (or a sequence end) are then paired by position when both sides
+/// left the same number, and otherwise by any positive score. Returns the
+/// pairs in order.
+fn align_sequence(n: usize, m: usize, score: impl Fn(usize, usize) -> f64) -> Vec<(usize, usize)> {
+    let scores: Vec<Vec<f64>> = (0..n).map(|i| (0..m).map(|j| score(i, j)).collect()).collect();
+    let anchors = best_pairs(&scores, 0..n, 0..m, LINE_MATCH_THRESHOLD);
+
+    let mut pairs = Vec::new();
+    let (mut next_i, mut next_j) = (0, 0);
+    for (ai, aj) in anchors.into_iter().chain(std::iter::once((n, m))) {
+        if ai - next_i == aj - next_j {
+            pairs.extend((next_i..ai).zip(next_j..aj));
+        } else {
+            pairs.extend(best_pairs(&scores, next_i..ai, next_j..aj, f64::MIN_POSITIVE));
+        }
+        if ai < n && aj < m {
+            pairs.push((ai, aj));
+        }
+        (next_i, next_j) = (ai + 1, aj + 1);
+    }
+    pairs
+}
+
+/// The order-preserving pairs within `rows` × `cols` of `scores` that
+/// maximise the total score, counting only pairs scoring at least `min`.
+fn best_pairs(scores: &[Vec<f64>], rows: Range<usize>, cols: Range<usize>, min: f64) -> Vec<(usize, usize)> {
+    let (n, m) = (rows.len(), cols.len());
+    let score = |i: usize, j: usize| scores[rows.start + i][cols.start + j];
+    let mut best = vec![vec![0.0f64; m + 1]; n + 1];
+    for i in 1..=n {
+        for j in 1..=m {
+            let skip = best[i - 1][j].max(best[i][j - 1]);
+            let s = score(i - 1, j - 1);
+            best[i][j] = if s >= min {
+                skip.max(best[i - 1][j - 1] + s)
+            } else {
+                skip
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 && j > 0 {
+        let s = score(i - 1, j - 1);
+        if s >= min && best[i][j] == best[i - 1][j - 1] + s {
+            pairs.push((rows.start + i - 1, cols.start + j - 1));
+            i -= 1;
+            j -= 1;
+        } else if best[i - 1][j] >= best[i][j - 1] {
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+    }
+    pairs.reverse();
+    pairs
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A flat table as ingest produces it: table, then each row followed by
+    /// its cells.
+    fn table(rows: &[&[&str]]) -> Vec<Block> {
+        let doc = Uuid::new_v4();
+        let table = Block::new(BlockType::Table, "tbl0", "", "", None, doc, 0);
+        let table_id = table.id;
+        let mut blocks = vec![table];
+        for (r, cells) in rows.iter().enumerate() {
+            let path = format!("tbl0.r{r}");
+            let row = Block::new(BlockType::TableRow, &path, "", "", Some(table_id), doc, r as i32);
+            let row_id = row.id;
+            blocks.push(row);
+            for (c, text) in cells.iter().enumerate() {
+                let cell_path = format!("{path}.c{c}");
+                let cell =
+                    Block::new(BlockType::TableCell, cell_path, *text, *text, Some(row_id), doc, c as i32);
+                blocks.push(cell);
+            }
+        }
+        blocks
+    }
+
+    fn text_pairs(aligned: &TableAlignment, left: &[Block], right: &[Block]) -> Vec<(String, String)> {
+        aligned
+            .pairs
+            .iter()
+            .filter(|&&(l, _)| left[l].block_type == BlockType::TableCell)
+            .map(|&(l, r)| (left[l].display_text.clone(), right[r].display_text.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn inserted_column_keeps_cells_aligned() {
+        let left = table(&[&["Name", "Rate"], &["Alice", "5%"], &["Bob", "7%"]]);
+        let right = table(&[
+            &["Name", "Term", "Rate"],
+            &["Alice", "12 months", "5%"],
+            &["Bob", "24 months", "7%"],
+        ]);
+        let aligned = align_tables(&left, &right);
+
+        let pairs = text_pairs(&aligned, &left, &right);
+        assert_eq!(pairs.len(), 6);
+        assert!(pairs.iter().all(|(l, r)| l == r), "{pairs:?}");
+        assert_eq!(aligned.changes.len(), 1);
+        let change = &aligned.changes[0];
+        assert_eq!(change.kind, TableChangeKind::ColumnInserted);
+        assert_eq!(change.index, 1);
+        assert_eq!(change.cell_block_ids.len(), 3);
+        assert_eq!(change.row_block_id, None);
+        assert_eq!(aligned.right_settled.len(), right.len());
+    }
+
+    #[test]
+    fn deleted_row_and_renamed_header_are_reported() {
+        let left = table(&[
+            &["Party", "Amount"],
+            &["Lender", "100"],
+            &["Guarantor", "50"],
+            &["Borrower", "25"],
+        ]);
+        let right = table(&[&["Party", "Sum due"], &["Lender", "100"], &["Borrower", "30"]]);
+        let aligned = align_tables(&left, &right);
+
+        let pairs = text_pairs(&aligned, &left, &right);
+        assert!(pairs.contains(&("Amount".into(), "Sum due".into())), "{pairs:?}");
+        assert!(pairs.contains(&("25".into(), "30".into())), "{pairs:?}");
+        assert_eq!(aligned.changes.len(), 1);
+        let change = &aligned.changes[0];
+        assert_eq!(change.kind, TableChangeKind::RowDeleted);
+        assert_eq!(change.index, 2);
+        assert_eq!(change.row_block_id, Some(left[7].id));
+        assert_eq!(change.cell_block_ids, vec![left[8].id, left[9].id]);
+    }
+
+    #[test]
+    fn unrelated_tables_are_not_aligned() {
+        let left = table(&[&["alpha", "beta"]]);
+        let right = table(&[&["gamma", "delta"]]);
+        let aligned = align_tables(&left, &right);
+        assert!(aligned.pairs.is_empty());
+        assert!(aligned.right_settled.is_empty());
+    }
+
+    #[test]
+    fn sequence_gaps_of_equal_length_pair_by_position() {
+        let score = |l: usize, r: usize| if (l, r) == (1, 2) { 1.0 } else { 0.0 };
+        assert_eq!(align_sequence(3, 4, score), vec![(1, 2), (2, 3)]);
+        assert_eq!(align_sequence(2, 2, |_, _| 0.0), vec![(0, 0), (1, 1)]);
+        let weak = |l: usize, r: usize| if (l, r) == (1, 0) { 0.2 } else { 0.0 };
+        assert_eq!(align_sequence(2, 1, weak), vec![(1, 0)]);
+    }
+}
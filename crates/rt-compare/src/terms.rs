@@ -0,0 +1,237 @@
+//! Document-level defined-term dictionary.
+//!
+//! The tokenizer marks every Title Case or ALL CAPS word as a
+//! [`TokenKind::DefinedTerm`], which also catches sentence-initial words,
+//! headings and proper names. [`extract_defined_terms`] instead reads the
+//! terms a document actually defines from its definition clauses:
+//!
+//! - a quoted term followed by a defining verb: `“Borrower” means …`,
+//!   `"Effective Date" shall have the meaning …`;
+//! - a quoted term in a short parenthetical: `… ACME Corp. (the “Lender”)`.
+//!
+//! [`classify_tokens`] then re-classifies tokens against that dictionary.
+
+use std::collections::HashSet;
+
+use rt_model::{Block, DefinedTerm, Token, TokenKind};
+
+use crate::tokenize::tokenize;
+
+/// Words (lowercase) that, following a quoted term, make it a definition.
+const DEFINING_VERBS: &[&str] = &[
+    "means",
+    "shall mean",
+    "has the meaning",
+    "shall have the meaning",
+    "have the meaning",
+    "includes",
+    "shall include",
+    "refers to",
+    "is defined",
+];
+
+/// Longest text between an opening parenthesis and a quoted term for the
+/// parenthetical to count as a definition, in bytes.
+const MAX_PAREN_PREFIX: usize = 40;
+
+/// Most words a defined term can have.
+const MAX_TERM_WORDS: usize = 6;
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Extract the terms defined in `blocks` (and their children), in document
+/// order. A term defined more than once keeps its first definition.
+pub fn extract_defined_terms(blocks: &[Block]) -> Vec<DefinedTerm> {
+    let mut terms = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack: Vec<&Block> = blocks.iter().rev().collect();
+    while let Some(block) = stack.pop() {
+        for term in definitions_in(&block.canonical_text) {
+            let normalized = normalize_term(&term);
+            if !normalized.is_empty() && seen.insert(normalized.clone()) {
+                terms.push(DefinedTerm {
+                    term,
+                    normalized,
+                    block_id: block.id,
+                });
+            }
+        }
+        stack.extend(block.children.iter().rev());
+    }
+    terms
+}
+
+/// Re-classify `tokens` against `terms`: every run of tokens spelling a
+/// defined term (longest term first) becomes [`TokenKind::DefinedTerm`],
+/// and any other token the tokenizer's heuristic marked as a defined term
+/// becomes a [`TokenKind::Word`]. Returns the indices of the tokens whose
+/// kind changed.
+///
+/// With an empty dictionary nothing changes: a document without definition
+/// clauses keeps the heuristic classification.
+pub fn classify_tokens(tokens: &mut [Token], terms: &[DefinedTerm]) -> Vec<usize> {
+    if terms.is_empty() {
+        return vec![];
+    }
+    let mut patterns: Vec<Vec<String>> = terms
+        .iter()
+        .map(|t| tokenize(&t.term).into_iter().map(|t| t.text).collect::<Vec<_>>())
+        .filter(|p: &Vec<String>| !p.is_empty())
+        .collect();
+    patterns.sort_by_key(|p| std::cmp::Reverse(p.len()));
+
+    let mut defined = vec![false; tokens.len()];
+    let mut i = 0;
+    while i < tokens.len() {
+        let matched = patterns.iter().find(|p| {
+            tokens[i..].len() >= p.len() && p.iter().zip(&tokens[i..]).all(|(w, t)| *w == t.text)
+        });
+        match matched {
+            Some(pattern) => {
+                defined[i..i + pattern.len()].iter_mut().for_each(|d| *d = true);
+                i += pattern.len();
+            }
+            None => i += 1,
+        }
+    }
+
+    let mut changed = Vec::new();
+    for (index, (token, defined)) in tokens.iter_mut().zip(defined).enumerate() {
+        let kind = match (&token.kind, defined) {
+            (TokenKind::Word | TokenKind::DefinedTerm, true) => TokenKind::DefinedTerm,
+            (TokenKind::DefinedTerm, false) => TokenKind::Word,
+            _ => continue,
+        };
+        if token.kind != kind {
+            token.kind = kind;
+            changed.push(index);
+        }
+    }
+    changed
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// The terms defined in `text`.
+fn definitions_in(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut rest = 0;
+    while let Some((open, inner, close_end)) = next_quoted(text, rest) {
+        rest = close_end;
+        let term = text[inner.0..inner.1].trim();
+        if !looks_like_term(term) {
+            continue;
+        }
+        if is_followed_by_verb(&text[close_end..]) || is_parenthetical(&text[..open]) {
+            terms.push(term.to_string());
+        }
+    }
+    terms
+}
+
+/// The next quoted span at or after byte `from`: (opening quote offset,
+/// inner byte range, offset just past the closing quote).
+fn next_quoted(text: &str, from: usize) -> Option<(usize, (usize, usize), usize)> {
+    let (open, quote) = text[from..]
+        .char_indices()
+        .find(|(_, c)| matches!(c, '\u{201C}' | '"'))
+        .map(|(i, c)| (from + i, c))?;
+    let inner_start = open + quote.len_utf8();
+    let closing = if quote == '"' { '"' } else { '\u{201D}' };
+    let close = inner_start + text[inner_start..].find(closing)?;
+    Some((open, (inner_start, close), close + closing.len_utf8()))
+}
+
+/// A plausible term: starts with an uppercase letter and has at most
+/// [`MAX_TERM_WORDS`] words.
+fn looks_like_term(term: &str) -> bool {
+    term.chars().next().is_some_and(char::is_uppercase)
+        && term.split_whitespace().count() <= MAX_TERM_WORDS
+}
+
+/// Whether `after` (the text following a closing quote) starts with one of
+/// the [`DEFINING_VERBS`].
+fn is_followed_by_verb(after: &str) -> bool {
+    let after = after.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    let lower = after.to_lowercase();
+    DEFINING_VERBS.iter().any(|verb| {
+        lower.starts_with(verb)
+            && !lower[verb.len()..].starts_with(|c: char| c.is_alphanumeric())
+    })
+}
+
+/// Whether the quote following `before` sits in an open parenthesis
+/// started at most [`MAX_PAREN_PREFIX`] bytes earlier.
+fn is_parenthetical(before: &str) -> bool {
+    match (before.rfind('('), before.rfind(')')) {
+        (Some(open), close) if close.is_none_or(|c| c < open) => {
+            before.len() - open <= MAX_PAREN_PREFIX
+        }
+        _ => false,
+    }
+}
+
+/// Normalized tokens of `term` joined by single spaces.
+pub(crate) fn normalize_term(term: &str) -> String {
+    tokenize(term)
+        .into_iter()
+        .map(|t| t.normalized)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::BlockType;
+    use uuid::Uuid;
+
+    fn block(path: &str, text: &str) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, Uuid::new_v4(), 0)
+    }
+
+    #[test]
+    fn definitions_are_extracted_from_verbs_and_parentheticals() {
+        let blocks = vec![
+            block("1", "ACME Holdings Inc. (the “Lender”) and Jane Doe (“Borrower”) agree:"),
+            block("2.1", "“Effective Date” means the date of this Agreement."),
+            block("2.2", "\"Loan\" shall have the meaning given in Section 3."),
+            block("2.3", "The word “Borrower” means Jane Doe, and \"maybe\" is not a term."),
+            block("2.4", "Notices marked “Urgent” must be answered promptly."),
+        ];
+        let terms = extract_defined_terms(&blocks);
+        let names: Vec<_> = terms.iter().map(|t| t.term.as_str()).collect();
+        assert_eq!(names, ["Lender", "Borrower", "Effective Date", "Loan"]);
+        assert_eq!(terms[2].normalized, "effective date");
+        assert_eq!(terms[1].block_id, blocks[0].id);
+    }
+
+    #[test]
+    fn tokens_are_reclassified_against_the_dictionary() {
+        let blocks = vec![
+            block("1", "ACME Holdings Inc. (the “Lender”) and Jane Doe agree:"),
+            block("2", "“Effective Date” means the date the Lender signs."),
+        ];
+        let terms = extract_defined_terms(&blocks);
+        let mut tokens = tokenize("Payment Is due on the Effective Date to the Lender.");
+        let changed = classify_tokens(&mut tokens, &terms);
+        let kinds: Vec<_> = tokens.iter().map(|t| (t.text.as_str(), t.kind.clone())).collect();
+        assert_eq!(kinds[0], ("Payment", TokenKind::Word));
+        assert_eq!(kinds[5], ("Effective", TokenKind::DefinedTerm));
+        assert_eq!(kinds[6], ("Date", TokenKind::DefinedTerm));
+        assert_eq!(kinds[9], ("Lender", TokenKind::DefinedTerm));
+        assert_eq!(changed, [0, 1]);
+
+        let mut untouched = tokenize("Payment Is due.");
+        assert!(classify_tokens(&mut untouched, &[]).is_empty());
+        assert_eq!(untouched[0].kind, TokenKind::DefinedTerm);
+    }
+}
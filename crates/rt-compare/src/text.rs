@@ -0,0 +1,346 @@
+//! Plain-text renderings of a [`CompareResult`] for terminals and logs.
+//!
+//! [`render_diffstat`] produces a `git diff --stat`-style summary with one
+//! line per changed block; [`render_unified`] produces unified-diff-like
+//! text with one `@@` hunk per changed block. Both label blocks by their
+//! structural path, so they need the block lists the result was computed
+//! from. Unchanged blocks are omitted from both.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use uuid::Uuid;
+
+use rt_model::Block;
+
+use crate::diff::DiffKind;
+use crate::result::{BlockDelta, CompareResult, DeltaKind};
+use crate::worker::{ensure_tokens, flatten_blocks};
+
+/// Widest `+`/`-` bar drawn by [`render_diffstat`]; larger counts are scaled.
+const MAX_BAR_WIDTH: usize = 50;
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Render a git-style diffstat:
+///
+/// ```text
+///  1.(a)     | 4 ++--
+///  2.        | 3 +++
+///  1. => 3.  | 0
+///  3 blocks changed, 5 insertions(+), 2 deletions(-)
+/// ```
+///
+/// Counts are tokens inserted / deleted within each block.
+pub fn render_diffstat(result: &CompareResult, left: &[Block], right: &[Block]) -> String {
+    let lookup = BlockLookup::new(left, right);
+    let rows: Vec<(String, usize, usize)> = result
+        .deltas
+        .iter()
+        .filter_map(|d| {
+            let (ins, del) = lookup.token_counts(d);
+            is_reportable(d, ins, del).then(|| (lookup.label(d), ins, del))
+        })
+        .collect();
+
+    let label_width = rows.iter().map(|(l, _, _)| l.chars().count()).max().unwrap_or(0);
+    let count_width = rows
+        .iter()
+        .map(|(_, i, d)| (i + d).to_string().len())
+        .max()
+        .unwrap_or(1);
+    let max_total = rows.iter().map(|(_, i, d)| i + d).max().unwrap_or(0);
+
+    let mut out = String::new();
+    let (mut total_ins, mut total_del) = (0, 0);
+    for (label, ins, del) in &rows {
+        total_ins += ins;
+        total_del += del;
+        let (plus, minus) = scale_bar(*ins, *del, max_total);
+        let bar = format!("{}{}", "+".repeat(plus), "-".repeat(minus));
+        let line = format!(" {label:<label_width$} | {:>count_width$} {bar}", ins + del);
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    let _ = writeln!(
+        out,
+        " {} block{} changed, {} insertion{}(+), {} deletion{}(-)",
+        rows.len(),
+        plural(rows.len()),
+        total_ins,
+        plural(total_ins),
+        total_del,
+        plural(total_del),
+    );
+    out
+}
+
+/// Render unified-diff-like text:
+///
+/// ```text
+/// --- left/<left_doc_id>
+/// +++ right/<right_doc_id>
+/// @@ 1.(a) modified @@
+/// -The Borrower shall repay the Loan.
+/// +The Borrower shall promptly repay the Loan.
+/// ```
+///
+/// Blocks that moved without a text change are shown as context (` `) lines.
+/// A split or merged block is shown as every block on each side.
+pub fn render_unified(result: &CompareResult, left: &[Block], right: &[Block]) -> String {
+    let lookup = BlockLookup::new(left, right);
+    let mut out = String::new();
+    let _ = writeln!(out, "--- left/{}", result.left_doc_id);
+    let _ = writeln!(out, "+++ right/{}", result.right_doc_id);
+
+    for delta in &result.deltas {
+        let (ins, del) = lookup.token_counts(delta);
+        if !is_reportable(delta, ins, del) {
+            continue;
+        }
+        let _ = writeln!(out, "@@ {} {} @@", lookup.label(delta), kind_name(&delta.kind));
+
+        let lefts = lookup.left_blocks(delta);
+        let rights = lookup.right_blocks(delta);
+        match (lefts.as_slice(), rights.as_slice()) {
+            ([l], [_]) if ins + del == 0 => push_lines(&mut out, ' ', &l.display_text),
+            (lefts, rights) => {
+                for l in lefts {
+                    push_lines(&mut out, '-', &l.display_text);
+                }
+                for r in rights {
+                    push_lines(&mut out, '+', &r.display_text);
+                }
+            }
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// Block-id → block index over both documents' flattened block lists.
+pub(crate) struct BlockLookup {
+    left: HashMap<Uuid, Block>,
+    right: HashMap<Uuid, Block>,
+}
+
+impl BlockLookup {
+    pub(crate) fn new(left: &[Block], right: &[Block]) -> Self {
+        let index = |blocks: &[Block]| {
+            flatten_blocks(blocks)
+                .into_iter()
+                .map(|b| (b.id, b))
+                .collect::<HashMap<_, _>>()
+        };
+        Self {
+            left: index(left),
+            right: index(right),
+        }
+    }
+
+    pub(crate) fn left_block(&self, delta: &BlockDelta) -> Option<&Block> {
+        delta.left_block_id.and_then(|id| self.left.get(&id))
+    }
+
+    pub(crate) fn right_block(&self, delta: &BlockDelta) -> Option<&Block> {
+        delta.right_block_id.and_then(|id| self.right.get(&id))
+    }
+
+    /// Every left block of the delta: the whole run for a merge, otherwise
+    /// the left block, if any.
+    pub(crate) fn left_blocks(&self, delta: &BlockDelta) -> Vec<&Block> {
+        match delta.kind {
+            DeltaKind::Merged => group(&self.left, &delta.group_block_ids),
+            _ => self.left_block(delta).into_iter().collect(),
+        }
+    }
+
+    /// Every right block of the delta: the whole run for a split, otherwise
+    /// the right block, if any.
+    pub(crate) fn right_blocks(&self, delta: &BlockDelta) -> Vec<&Block> {
+        match delta.kind {
+            DeltaKind::Split => group(&self.right, &delta.group_block_ids),
+            _ => self.right_block(delta).into_iter().collect(),
+        }
+    }
+
+    /// Structural path of each side, falling back to `#<ordinal>` for blocks
+    /// without a path; `"L => R"` when the two differ.
+    pub(crate) fn label(&self, delta: &BlockDelta) -> String {
+        let side = |block: Option<&Block>, ordinal: Option<usize>| {
+            match block.map(|b| b.structural_path.as_str()).filter(|p| !p.is_empty()) {
+                Some(path) => Some(path.to_string()),
+                None => ordinal.map(|o| format!("#{o}")),
+            }
+        };
+        let l = side(self.left_block(delta), delta.left_ordinal);
+        let r = side(self.right_block(delta), delta.right_ordinal);
+        match (l, r) {
+            (Some(l), Some(r)) if l != r => format!("{l} => {r}"),
+            (Some(p), _) | (None, Some(p)) => p,
+            (None, None) => delta.id.to_string(),
+        }
+    }
+
+    /// `(inserted, deleted)` token counts for a delta.
+    pub(crate) fn token_counts(&self, delta: &BlockDelta) -> (usize, usize) {
+        match delta.kind {
+            DeltaKind::Inserted => (self.right_block(delta).map_or(0, |b| ensure_tokens(b).len()), 0),
+            DeltaKind::Deleted => (0, self.left_block(delta).map_or(0, |b| ensure_tokens(b).len())),
+            DeltaKind::Unchanged => (0, 0),
+            DeltaKind::Modified | DeltaKind::Moved | DeltaKind::Split | DeltaKind::Merged => {
+                delta.token_diffs.iter().fold((0, 0), |(ins, del), d| match d.kind {
+                    DiffKind::Equal => (ins, del),
+                    DiffKind::Inserted => (ins + d.right_tokens.len(), del),
+                    DiffKind::Deleted => (ins, del + d.left_tokens.len()),
+                    DiffKind::Substituted => {
+                        (ins + d.right_tokens.len(), del + d.left_tokens.len())
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// Unchanged matches (and edits that change no tokens) are omitted; moves,
+/// splits and merges are always reported.
+pub(crate) fn is_reportable(delta: &BlockDelta, ins: usize, del: usize) -> bool {
+    match delta.kind {
+        DeltaKind::Unchanged => false,
+        DeltaKind::Modified => ins + del > 0,
+        _ => true,
+    }
+}
+
+pub(crate) fn kind_name(kind: &DeltaKind) -> &'static str {
+    match kind {
+        DeltaKind::Inserted => "inserted",
+        DeltaKind::Deleted => "deleted",
+        DeltaKind::Modified => "modified",
+        DeltaKind::Unchanged => "unchanged",
+        DeltaKind::Moved => "moved",
+        DeltaKind::Split => "split",
+        DeltaKind::Merged => "merged",
+    }
+}
+
+fn group<'a>(blocks: &'a HashMap<Uuid, Block>, ids: &[Uuid]) -> Vec<&'a Block> {
+    ids.iter().filter_map(|id| blocks.get(id)).collect()
+}
+
+/// Scale `+`/`-` counts so the widest bar is at most [`MAX_BAR_WIDTH`],
+/// keeping at least one character for any non-zero count.
+fn scale_bar(ins: usize, del: usize, max_total: usize) -> (usize, usize) {
+    if max_total <= MAX_BAR_WIDTH {
+        return (ins, del);
+    }
+    let scale = |n: usize| {
+        if n == 0 {
+            0
+        } else {
+            (n * MAX_BAR_WIDTH / max_total).max(1)
+        }
+    };
+    (scale(ins), scale(del))
+}
+
+fn push_lines(out: &mut String, prefix: char, text: &str) {
+    if text.is_empty() {
+        out.push(prefix);
+        out.push('\n');
+        return;
+    }
+    for line in text.lines() {
+        out.push(prefix);
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::CompareEngine;
+    use rt_model::BlockType;
+
+    fn block(doc: Uuid, path: &str, text: &str, idx: i32) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, doc, idx)
+    }
+
+    fn fixture() -> (Vec<Block>, Vec<Block>, CompareResult) {
+        let (l, r) = (Uuid::new_v4(), Uuid::new_v4());
+        let left = vec![
+            block(l, "1.", "the borrower shall repay the loan", 0),
+            block(l, "2.", "the lender may assign its rights", 1),
+            block(l, "3.", "notices must be in writing", 2),
+        ];
+        let right = vec![
+            block(r, "1.", "the borrower shall promptly repay the loan", 0),
+            block(r, "2.", "the lender may assign its rights", 1),
+        ];
+        let result = CompareEngine::default().compare(l, r, &left, &right);
+        (left, right, result)
+    }
+
+    #[test]
+    fn diffstat_lists_changed_blocks_only() {
+        let (left, right, result) = fixture();
+        let stat = render_diffstat(&result, &left, &right);
+        let lines: Vec<&str> = stat.lines().collect();
+        assert_eq!(lines.len(), 3, "{stat}");
+        assert_eq!(lines[0], " 1. | 1 +");
+        assert_eq!(lines[1], " 3. | 5 -----");
+        assert_eq!(lines[2], " 2 blocks changed, 1 insertion(+), 5 deletions(-)");
+    }
+
+    #[test]
+    fn unified_has_headers_and_hunks() {
+        let (left, right, result) = fixture();
+        let text = render_unified(&result, &left, &right);
+        let expected = format!(
+            "--- left/{}\n+++ right/{}\n\
+             @@ 1. modified @@\n\
+             -the borrower shall repay the loan\n\
+             +the borrower shall promptly repay the loan\n\
+             @@ 3. deleted @@\n\
+             -notices must be in writing\n",
+            result.left_doc_id, result.right_doc_id
+        );
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn identical_documents_render_empty_summary() {
+        let doc = Uuid::new_v4();
+        let blocks = vec![block(doc, "1.", "same text", 0)];
+        let result = CompareEngine::default().compare(doc, doc, &blocks, &blocks);
+        assert_eq!(
+            render_diffstat(&result, &blocks, &blocks),
+            " 0 blocks changed, 0 insertions(+), 0 deletions(-)\n"
+        );
+        assert_eq!(render_unified(&result, &blocks, &blocks).lines().count(), 2);
+    }
+
+    #[test]
+    fn large_counts_are_scaled() {
+        assert_eq!(scale_bar(10, 5, 15), (10, 5));
+        assert_eq!(scale_bar(100, 0, 100), (50, 0));
+        assert_eq!(scale_bar(1, 99, 100), (1, 49));
+    }
+}
@@ -7,12 +7,41 @@
 //!   are preserved as part of the token they adjoin or as standalone tokens.
 //! - Capitalized terms that appear to be defined terms (Title Case words
 //!   that are not sentence-initial) are classified as DefinedTerm.
+//! - The words and numbers of a date (`January 1, 2025`, `1st day of March
+//!   2024`, `June 2025`, `2025-01-31`, `01/31/2025`) are classified as
+//!   DateRef; see [`find_dates`].
 //!
 //! Example:
 //!   "The Borrower shall, upon request," →
 //!   [The][Borrower][shall][,][upon][request][,]
 
-use rt_core::{Token, TokenKind};
+use rt_model::{Token, TokenKind};
+
+/// Month names, lowercase. Three-letter abbreviations and "sept" are
+/// accepted too.
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// A date found by [`find_dates`]: tokens `start..end`, and the date as
+/// `YYYY-MM-DD` (or `YYYY-MM` for a month without a day).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateSpan {
+    pub start: usize,
+    pub end: usize,
+    pub value: String,
+}
 
 // ---------------------------------------------------------------------------
 // Public API
@@ -78,9 +107,46 @@ pub fn tokenize(text: &str) -> Vec<Token> {
         });
     }
 
+    for date in find_dates(&tokens) {
+        for token in &mut tokens[date.start..date.end] {
+            if token.kind != TokenKind::Punctuation {
+                token.kind = TokenKind::DateRef;
+            }
+        }
+    }
+
     tokens
 }
 
+/// Find the dates in `tokens`, in order and without overlaps.
+///
+/// Recognised forms, with a capitalised month name, its three-letter
+/// abbreviation or "Sept":
+/// - month first: `January 1, 2025`, `Jan. 1st 2025`, `January 2025`;
+/// - day first: `1 January 2025`, `1st day of January, 2025`;
+/// - numeric, with a four-digit year and one separator (`-`, `/` or `.`)
+///   written without spaces: `2025-01-31` (year first), `01/31/2025`
+///   (month first, unless the first number cannot be a month).
+///
+/// Impossible dates (`February 30, 2025`) are not dates.
+pub fn find_dates(tokens: &[Token]) -> Vec<DateSpan> {
+    let mut dates = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let date = month_first_date(tokens, i)
+            .or_else(|| day_first_date(tokens, i))
+            .or_else(|| numeric_date(tokens, i));
+        match date {
+            Some(date) => {
+                i = date.end;
+                dates.push(date);
+            }
+            None => i += 1,
+        }
+    }
+    dates
+}
+
 /// Normalize a token for comparison: lowercase and strip diacritics.
 ///
 /// Diacritics are removed by a simple decomposition approach: any character
@@ -218,6 +284,144 @@ fn is_likely_defined_term(word: &str) -> bool {
     all_lower_rest || all_upper
 }
 
+/// `Month [day][,] year` starting at token `i`.
+fn month_first_date(tokens: &[Token], i: usize) -> Option<DateSpan> {
+    let month = month_at(tokens, i)?;
+    let mut j = skip_text(tokens, i + 1, ".");
+    let day = day_at(tokens, j);
+    if day.is_some() {
+        j += 1;
+    }
+    j = skip_text(tokens, j, ",");
+    let year = year_at(tokens, j)?;
+    date_span(i, j + 1, year, month, day)
+}
+
+/// `day [day of] Month[,] year` starting at token `i`.
+fn day_first_date(tokens: &[Token], i: usize) -> Option<DateSpan> {
+    let day = day_at(tokens, i)?;
+    let mut j = i + 1;
+    if tokens.get(j).is_some_and(|t| t.normalized == "day") {
+        j = skip_text(tokens, j + 1, "of");
+    }
+    let month = month_at(tokens, j)?;
+    j = skip_text(tokens, skip_text(tokens, j + 1, "."), ",");
+    let year = year_at(tokens, j)?;
+    date_span(i, j + 1, year, month, Some(day))
+}
+
+/// `n<sep>n<sep>n` starting at token `i`, with a four-digit year first or
+/// last.
+fn numeric_date(tokens: &[Token], i: usize) -> Option<DateSpan> {
+    let parts = tokens.get(i..i + 5)?;
+    let sep = parts[1].text.as_str();
+    if !matches!(sep, "-" | "/" | ".") || parts[3].text != sep {
+        return None;
+    }
+    if parts.windows(2).any(|w| w[0].offset + w[0].text.len() != w[1].offset) {
+        return None;
+    }
+    let [a, b, c] = [&parts[0], &parts[2], &parts[4]].map(plain_number);
+    let (a, b, c) = (a?, b?, c?);
+    let (year, month, day) = if parts[0].text.len() == 4 {
+        (a, b, c)
+    } else if parts[4].text.len() == 4 {
+        if a > 12 {
+            (c, b, a)
+        } else {
+            (c, a, b)
+        }
+    } else {
+        return None;
+    };
+    date_span(i, i + 5, year, month, Some(day))
+}
+
+/// A [`DateSpan`] over tokens `start..end`, when the date exists.
+fn date_span(
+    start: usize,
+    end: usize,
+    year: u32,
+    month: u32,
+    day: Option<u32>,
+) -> Option<DateSpan> {
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    let value = match day {
+        None => format!("{year:04}-{month:02}"),
+        Some(day) if (1..=days_in_month(year, month)).contains(&day) => {
+            format!("{year:04}-{month:02}-{day:02}")
+        }
+        Some(_) => return None,
+    };
+    Some(DateSpan { start, end, value })
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+fn is_leap_year(year: u32) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+/// The month number of a capitalised month name at token `i`.
+fn month_at(tokens: &[Token], i: usize) -> Option<u32> {
+    let token = tokens.get(i)?;
+    if !token.text.starts_with(char::is_uppercase) {
+        return None;
+    }
+    let name = token.normalized.as_str();
+    let index = MONTHS.iter().position(|month| {
+        *month == name
+            || (name.len() == 3 && month.starts_with(name))
+            || (name == "sept" && *month == "september")
+    })?;
+    Some(index as u32 + 1)
+}
+
+/// A day of the month (`1` to `31`, optionally ordinal) at token `i`.
+fn day_at(tokens: &[Token], i: usize) -> Option<u32> {
+    let token = tokens.get(i)?;
+    if !is_numeric(&token.text) {
+        return None;
+    }
+    let digits = token.text.trim_end_matches(char::is_alphabetic);
+    if digits.len() > 2 {
+        return None;
+    }
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+/// A four-digit year at token `i`.
+fn year_at(tokens: &[Token], i: usize) -> Option<u32> {
+    let token = tokens.get(i)?;
+    (token.text.len() == 4).then(|| plain_number(token)).flatten()
+}
+
+/// The value of a token made of ASCII digits only.
+fn plain_number(token: &Token) -> Option<u32> {
+    if token.text.is_empty() || !token.text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.text.parse().ok()
+}
+
+/// `i + 1` when token `i` is `text`, else `i`.
+fn skip_text(tokens: &[Token], i: usize, text: &str) -> usize {
+    if tokens.get(i).is_some_and(|t| t.normalized == text) {
+        i + 1
+    } else {
+        i
+    }
+}
+
 /// Strip common diacritics from a character, returning its base ASCII form
 /// when a simple mapping exists, or the original character otherwise.
 fn strip_diacritic(ch: char) -> char {
@@ -359,6 +563,30 @@ mod tests {
         assert_eq!(tokens[1].offset, 6);
     }
 
+    #[test]
+    fn dates_are_classified_as_date_refs() {
+        let kinds = |text: &str| {
+            tokenize(text)
+                .into_iter()
+                .filter(|t| t.kind == TokenKind::DateRef)
+                .map(|t| t.text)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kinds("due on January 1, 2025 at noon"), ["January", "1", "2025"]);
+        assert_eq!(kinds("the 1st day of March 2024"), ["1st", "day", "of", "March", "2024"]);
+        assert_eq!(
+            kinds("from 2025-01-31 until 31/01/2026"),
+            ["2025", "01", "31", "31", "01", "2026"]
+        );
+        assert!(kinds("Section 1.2 of 2025 and February 30, 2025; you may 2025").is_empty());
+
+        let values: Vec<_> = find_dates(&tokenize("Sept. 3rd, 2024, June 2025 and 02/29/2024"))
+            .into_iter()
+            .map(|d| d.value)
+            .collect();
+        assert_eq!(values, ["2024-09-03", "2025-06", "2024-02-29"]);
+    }
+
     #[test]
     fn decimal_number() {
         let tokens = tokenize("3.14");
@@ -0,0 +1,214 @@
+//! JavaScript bindings for client-side compares.
+//!
+//! Built with `--no-default-features --features wasm` for
+//! `wasm32-unknown-unknown`. Inputs and outputs are JSON strings using the
+//! same shapes as the native FFI (`Block` arrays in, `CompareResult` out), so
+//! the browser and desktop hosts share one contract.
+//!
+//! Besides whole compares, the building blocks are exported for UIs that
+//! work on single clauses: [`tokenize`], [`align_blocks`] and
+//! [`token_diff`]. A [`CompareEngine`](WasmCompareEngine) class holds
+//! validated compare options across calls.
+
+use uuid::Uuid;
+use wasm_bindgen::prelude::*;
+
+use rt_model::{Block, Token};
+
+use crate::worker::{CompareConfig, CompareEngine};
+
+/// Compare two JSON arrays of blocks and return the `CompareResult` JSON.
+///
+/// Document ids in the result are the nil UUID; use
+/// [`compare_documents`] when the caller has real ids.
+#[wasm_bindgen]
+pub fn compare_blocks(left_json: &str, right_json: &str) -> Result<String, JsError> {
+    let engine = CompareEngine::default();
+    compare_json(&engine, Uuid::nil(), Uuid::nil(), left_json, right_json)
+        .map_err(|e| JsError::new(&e))
+}
+
+/// Compare two documents given their ids and JSON block arrays, returning the
+/// `CompareResult` JSON.
+#[wasm_bindgen]
+pub fn compare_documents(
+    left_doc_id: &str,
+    right_doc_id: &str,
+    left_json: &str,
+    right_json: &str,
+) -> Result<String, JsError> {
+    compare_ids_json(&CompareEngine::default(), left_doc_id, right_doc_id, left_json, right_json)
+        .map_err(|e| JsError::new(&e))
+}
+
+/// Tokenize `text` and return the JSON array of tokens, the shape
+/// [`token_diff`] takes.
+#[wasm_bindgen]
+pub fn tokenize(text: &str) -> Result<String, JsError> {
+    tokenize_json(text).map_err(|e| JsError::new(&e))
+}
+
+/// Align two flat JSON arrays of blocks and return the JSON array of
+/// alignments. Each alignment has a `kind` (`matched`, `inserted_right`,
+/// `deleted_left`, `moved`, `split` or `merged`); its `left` and `right`
+/// fields are indices into the input arrays.
+#[wasm_bindgen]
+pub fn align_blocks(left_json: &str, right_json: &str) -> Result<String, JsError> {
+    align_json(left_json, right_json).map_err(|e| JsError::new(&e))
+}
+
+/// Diff two JSON token arrays (as returned by [`tokenize`]) and return the
+/// JSON array of token diffs.
+#[wasm_bindgen]
+pub fn token_diff(left_json: &str, right_json: &str) -> Result<String, JsError> {
+    token_diff_json(left_json, right_json).map_err(|e| JsError::new(&e))
+}
+
+/// A compare engine with fixed options, exported to JavaScript as
+/// `CompareEngine`.
+#[wasm_bindgen(js_name = CompareEngine)]
+pub struct WasmCompareEngine {
+    engine: CompareEngine,
+}
+
+#[wasm_bindgen(js_class = CompareEngine)]
+impl WasmCompareEngine {
+    /// Build an engine from a compare options JSON object, as accepted by
+    /// the native FFI; an empty string yields the defaults.
+    #[wasm_bindgen(constructor)]
+    pub fn new(options_json: &str) -> Result<WasmCompareEngine, JsError> {
+        let config =
+            CompareConfig::from_json(options_json).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(Self {
+            engine: CompareEngine::new(config),
+        })
+    }
+
+    /// Compare two documents given their ids and JSON block arrays,
+    /// returning the `CompareResult` JSON.
+    pub fn compare(
+        &self,
+        left_doc_id: &str,
+        right_doc_id: &str,
+        left_json: &str,
+        right_json: &str,
+    ) -> Result<String, JsError> {
+        compare_ids_json(&self.engine, left_doc_id, right_doc_id, left_json, right_json)
+            .map_err(|e| JsError::new(&e))
+    }
+}
+
+// Shared implementations; errors are plain strings so these can be exercised
+// off-wasm.
+
+fn parse_json<T: serde::de::DeserializeOwned>(json: &str, what: &str) -> Result<T, String> {
+    serde_json::from_str(json).map_err(|e| format!("failed to parse {what} JSON: {}", e))
+}
+
+fn to_json<T: serde::Serialize>(value: &T, what: &str) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| format!("failed to serialize {what}: {}", e))
+}
+
+fn compare_ids_json(
+    engine: &CompareEngine,
+    left_doc_id: &str,
+    right_doc_id: &str,
+    left_json: &str,
+    right_json: &str,
+) -> Result<String, String> {
+    let left_id =
+        Uuid::parse_str(left_doc_id).map_err(|e| format!("invalid left_doc_id UUID: {}", e))?;
+    let right_id =
+        Uuid::parse_str(right_doc_id).map_err(|e| format!("invalid right_doc_id UUID: {}", e))?;
+    compare_json(engine, left_id, right_id, left_json, right_json)
+}
+
+fn compare_json(
+    engine: &CompareEngine,
+    left_doc_id: Uuid,
+    right_doc_id: Uuid,
+    left_json: &str,
+    right_json: &str,
+) -> Result<String, String> {
+    let left: Vec<Block> = parse_json(left_json, "left blocks")?;
+    let right: Vec<Block> = parse_json(right_json, "right blocks")?;
+
+    let result = engine.compare(left_doc_id, right_doc_id, &left, &right);
+    to_json(&result, "CompareResult")
+}
+
+fn tokenize_json(text: &str) -> Result<String, String> {
+    to_json(&crate::tokenize::tokenize(text), "tokens")
+}
+
+fn align_json(left_json: &str, right_json: &str) -> Result<String, String> {
+    let left: Vec<Block> = parse_json(left_json, "left blocks")?;
+    let right: Vec<Block> = parse_json(right_json, "right blocks")?;
+    to_json(&crate::align::align_blocks(&left, &right), "alignments")
+}
+
+fn token_diff_json(left_json: &str, right_json: &str) -> Result<String, String> {
+    let left: Vec<Token> = parse_json(left_json, "left tokens")?;
+    let right: Vec<Token> = parse_json(right_json, "right tokens")?;
+    to_json(&crate::diff::token_diff(&left, &right), "token diffs")
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::BlockType;
+
+    fn blocks_json(doc: Uuid, texts: &[&str]) -> String {
+        let blocks: Vec<Block> = texts
+            .iter()
+            .enumerate()
+            .map(|(i, t)| Block::new(BlockType::Clause, "1", *t, *t, None, doc, i as i32))
+            .collect();
+        serde_json::to_string(&blocks).unwrap()
+    }
+
+    #[test]
+    fn compare_json_round_trips_result() {
+        let doc = Uuid::new_v4();
+        let left = blocks_json(doc, &["the borrower shall repay the loan promptly"]);
+        let right = blocks_json(doc, &["the borrower shall repay the loan immediately"]);
+        let engine = CompareEngine::default();
+        let out = compare_json(&engine, doc, doc, &left, &right).expect("compare");
+        let parsed: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed["stats"]["modified"], 1);
+        assert_eq!(parsed["left_doc_id"], doc.to_string());
+    }
+
+    #[test]
+    fn compare_json_rejects_bad_input() {
+        let engine = CompareEngine::default();
+        let err = compare_json(&engine, Uuid::nil(), Uuid::nil(), "not json", "[]").unwrap_err();
+        assert!(err.contains("left blocks"));
+        let err = compare_ids_json(&engine, "nope", &Uuid::nil().to_string(), "[]", "[]");
+        assert!(err.unwrap_err().contains("left_doc_id"));
+        assert!(token_diff_json("[]", "{").unwrap_err().contains("right tokens"));
+    }
+
+    #[test]
+    fn building_blocks_round_trip() {
+        let left = tokenize_json("the borrower shall repay").unwrap();
+        let right = tokenize_json("the lender shall repay").unwrap();
+        let tokens: Vec<Token> = serde_json::from_str(&left).unwrap();
+        assert_eq!(tokens.len(), 4);
+        let diffs: serde_json::Value =
+            serde_json::from_str(&token_diff_json(&left, &right).unwrap()).unwrap();
+        assert!(diffs.as_array().unwrap().len() > 1);
+
+        let doc = Uuid::new_v4();
+        let left = blocks_json(doc, &["the borrower shall repay the loan"]);
+        let right = blocks_json(doc, &["the borrower shall repay the loan in full"]);
+        let alignments: serde_json::Value =
+            serde_json::from_str(&align_json(&left, &right).unwrap()).unwrap();
+        assert_eq!(alignments[0]["kind"], "matched");
+        assert_eq!(alignments[0]["right"], 0);
+    }
+}
@@ -1,50 +1,287 @@
 //! Parallel compare engine using rayon for token-level diffing.
 //!
 //! [`CompareEngine`] is the primary entry point. It accepts two flat block
-//! slices, aligns them via [`crate::align::align_blocks`], then computes
+//! slices, aligns their tables via [`crate::table::align_tables`] and all
+//! blocks via [`crate::align::align_blocks_with_tables`], then computes
 //! token-level diffs for matched pairs in parallel using rayon, and assembles
 //! a [`CompareResult`].
+//!
+//! Without the `parallel` feature (e.g. on wasm32) the same pipeline runs
+//! sequentially and produces identical output.
 
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
 
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use rt_core::Block;
+use rt_model::error::{Result, RtError};
+use rt_model::manifest::{config_fingerprint, InputDigest, RunManifest};
+use rt_model::{derived_uuid, Block, CancelToken};
 
-use crate::align::{align_blocks, BlockAlignment};
-use crate::diff::token_diff;
-use crate::result::{BlockDelta, CompareResult, CompareStats, DeltaKind};
+use crate::align::{
+    align_blocks_observed, AlignThresholds, BlockAlignment, MAX_CANDIDATES, MOVE_DISTANCE_MAX,
+    MOVE_THRESHOLD, SPLIT_THRESHOLD, SimilarityKind, Stopwords,
+};
+use crate::attachments::compare_attachments;
+use crate::diff::{
+    refine_substitutions, sentence_diff, token_diff, DiffKind, SentenceDiff, TokenDiff,
+};
+use crate::formatting::compare_formatting;
+use crate::normalize::{NormalizationRule, Normalizer};
+use crate::pool;
+use crate::result::{
+    BlockDelta, ComparePhase, CompareProgress, CompareResult, CompareStats, CompareWarning,
+    CompareWarningKind, DeltaAnchors, DeltaKind,
+};
+use crate::table::align_tables;
 use crate::tokenize::tokenize;
+use crate::xref::broken_references;
+
+/// Version of this crate, recorded in every [`RunManifest`] it produces.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // ---------------------------------------------------------------------------
 // CompareConfig
 // ---------------------------------------------------------------------------
 
 /// Runtime configuration for the compare engine.
+///
+/// Deserializes from a JSON options object in which every field is optional
+/// (missing fields take their default). Unknown keys are rejected so that a
+/// misspelt or not-yet-supported option is reported rather than ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct CompareConfig {
     /// Minimum Jaccard similarity for two blocks to be considered a match.
     /// Default: 0.7.
     pub similarity_threshold: f64,
-    /// Maximum ordinal distance (in the right document) between a block's
-    /// original position and its new position for move detection to apply.
+    /// How blocks are scored against each other during alignment:
+    /// `"jaccard"` (token multiset overlap), `"tfidf_cosine"` (overlap
+    /// weighted towards rare terms, better for short clauses) or
+    /// `"edit_ratio"` (normalized token edit distance). The similarity and
+    /// move thresholds apply to the chosen metric.
+    /// Default: `"jaccard"`.
+    pub similarity_metric: SimilarityKind,
+    /// Leave stopwords out when scoring blocks during alignment, so that
+    /// boilerplate ("the", "shall", "herein") does not make unrelated
+    /// clauses look alike.
+    /// Default: `false`.
+    pub ignore_stopwords: bool,
+    /// The stopwords for `ignore_stopwords`; `null` for the built-in legal
+    /// English list ([`LEGAL_STOPWORDS`](crate::align::LEGAL_STOPWORDS)).
+    /// Default: `null`.
+    pub stopwords: Option<Vec<String>>,
+    /// Minimum Jaccard similarity for a matched block whose structural path
+    /// changed to be reported as moved rather than modified.
+    /// Default: 0.85.
+    pub move_threshold: f64,
+    /// Maximum distance, in leaf blocks, between a block's position in the
+    /// left document and its counterpart's in the right document for a
+    /// changed path to be reported as a move; blocks farther apart are
+    /// reported as a deletion and an insertion.
     /// Default: 50.
     pub move_distance_max: usize,
-    /// Number of rayon worker threads to use.
-    /// Default: `rayon::current_num_threads()`.
+    /// Minimum Jaccard similarity between a block and the concatenated text
+    /// of several adjacent blocks on the other side for it to be reported as
+    /// split into (or merged from) them; `null` disables split and merge
+    /// detection.
+    /// Default: 0.8.
+    pub split_threshold: Option<f64>,
+    /// Tolerate clause renumbering: a block is not reported as moved when
+    /// only the trailing ordinal of its structural path changed, so one
+    /// inserted or deleted clause does not turn every clause after it into a
+    /// move.
+    /// Default: `false`.
+    pub ignore_renumbering: bool,
+    /// Rules making spans that should not count as changes — dates,
+    /// amounts, party names — compare equal, applied to every block before
+    /// alignment and diffing; see [`crate::normalize`]. A configuration
+    /// that was not validated and holds an invalid pattern applies no rules.
+    /// Default: `[]`.
+    pub normalization_rules: Vec<NormalizationRule>,
+    /// Minimum character similarity for a token pair of a substituted group
+    /// to get a character-level diff ([`TokenDiff::char_diffs`]), so a UI
+    /// can highlight the changed suffix of "indemnification" vs
+    /// "indemnifications" rather than the whole word; `null` disables the
+    /// refinement.
+    /// Default: `null`.
+    pub char_diff_threshold: Option<f64>,
+    /// Also report each changed block pair's token diffs grouped by
+    /// sentence, with every sentence classified as unchanged, changed,
+    /// inserted or deleted ([`BlockDelta::sentences`]).
+    /// Default: `false`.
+    pub sentence_diffs: bool,
+    /// Number of rayon worker threads alignment and diffing run on; each
+    /// distinct count gets its own pool (see [`crate::pool`]). At most
+    /// [`pool::max_worker_threads`].
+    /// Default: `rayon::current_num_threads()`, or 1 without the `parallel`
+    /// feature.
     pub worker_threads: usize,
+    /// Maximum token count on either side of a block pair before token-level
+    /// diffing is skipped and the pair is reported as a coarse replacement.
+    /// Default: 20 000.
+    pub max_block_tokens: usize,
+    /// Maximum number of [`TokenDiff`] groups for a single block pair; larger
+    /// diffs are collapsed into a coarse replacement.
+    /// Default: 2 000.
+    pub max_diff_groups: usize,
+    /// Maximum number of unmatched block pairs scored by similarity during
+    /// alignment; beyond it, candidates are drawn from blocks sharing token
+    /// shingles ([`AlignThresholds::max_candidates`]).
+    /// Default: 250 000.
+    pub max_align_candidates: usize,
+    /// How much of each delta is kept in [`CompareResult::deltas`] (and the
+    /// streamed batches); stats always count every delta.
+    /// Default: `full`.
+    pub output_mode: CompareOutputMode,
+    /// Leave unchanged deltas out of [`CompareResult::deltas`] (and the
+    /// streamed batches) whatever the output mode, so a `full` result keeps
+    /// complete token diffs without the unchanged blocks; `stats.unchanged`
+    /// still counts them.
+    /// Default: `false`.
+    pub omit_unchanged: bool,
+    /// Make identical inputs produce byte-identical serialized results, for
+    /// golden-file tests: delta ids are derived from each delta's block ids
+    /// and kind, the run id from the run's manifest, and `elapsed_ms` is
+    /// reported as 0.
+    /// Default: `false`.
+    pub deterministic: bool,
+}
+
+/// Verbosity of the deltas a comparison returns. Large documents serialize
+/// to much smaller results in the reduced modes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareOutputMode {
+    /// Every delta, with complete token diffs.
+    #[default]
+    Full,
+    /// Changed deltas only: unchanged deltas are dropped, and equal token
+    /// diff groups keep their offsets but not their tokens.
+    ChangesOnly,
+    /// No deltas at all; only stats, warnings, formatting drift and
+    /// attachment changes.
+    StatsOnly,
+}
+
+impl CompareOutputMode {
+    /// Whether `delta` is kept in this mode, trimming its content if needed.
+    pub(crate) fn keep(&self, delta: &mut BlockDelta) -> bool {
+        match self {
+            CompareOutputMode::Full => true,
+            CompareOutputMode::ChangesOnly => {
+                if delta.kind == DeltaKind::Unchanged {
+                    return false;
+                }
+                let sentence_diffs = delta.sentences.iter_mut().flat_map(|s| &mut s.token_diffs);
+                for diff in delta.token_diffs.iter_mut().chain(sentence_diffs) {
+                    if diff.kind == DiffKind::Equal {
+                        diff.left_tokens.clear();
+                        diff.right_tokens.clear();
+                    }
+                }
+                true
+            }
+            CompareOutputMode::StatsOnly => false,
+        }
+    }
 }
 
 impl Default for CompareConfig {
     fn default() -> Self {
         Self {
             similarity_threshold: 0.7,
-            move_distance_max: 50,
-            worker_threads: rayon::current_num_threads(),
+            similarity_metric: SimilarityKind::Jaccard,
+            ignore_stopwords: false,
+            stopwords: None,
+            move_threshold: MOVE_THRESHOLD,
+            move_distance_max: MOVE_DISTANCE_MAX,
+            split_threshold: Some(SPLIT_THRESHOLD),
+            ignore_renumbering: false,
+            normalization_rules: Vec::new(),
+            char_diff_threshold: None,
+            sentence_diffs: false,
+            worker_threads: default_worker_threads(),
+            max_block_tokens: 20_000,
+            max_diff_groups: 2_000,
+            max_align_candidates: MAX_CANDIDATES,
+            output_mode: CompareOutputMode::Full,
+            omit_unchanged: false,
+            deterministic: false,
         }
     }
 }
 
+impl CompareConfig {
+    /// Whether `delta` is kept in the result under `output_mode` and
+    /// `omit_unchanged`, trimming its content if needed.
+    pub(crate) fn keep(&self, delta: &mut BlockDelta) -> bool {
+        if self.omit_unchanged && delta.kind == DeltaKind::Unchanged {
+            return false;
+        }
+        self.output_mode.keep(delta)
+    }
+
+    /// Name of the deltas this config keeps, for
+    /// [`RunManifest::output_mode`]: the `output_mode`, or
+    /// `full_without_unchanged` for a `full` run with `omit_unchanged`.
+    pub fn output_label(&self) -> &'static str {
+        match self.output_mode {
+            CompareOutputMode::Full if self.omit_unchanged => "full_without_unchanged",
+            CompareOutputMode::Full => "full",
+            CompareOutputMode::ChangesOnly => "changes_only",
+            CompareOutputMode::StatsOnly => "stats_only",
+        }
+    }
+
+    /// Parse a JSON options object (e.g. the `options_json` FFI argument)
+    /// and validate it. An empty or whitespace-only string yields defaults.
+    pub fn from_json(json: &str) -> Result<Self> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let config: Self = serde_json::from_str(json)
+            .map_err(|e| RtError::InvalidInput(format!("invalid compare options: {e}")))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check that every field is within its legal range.
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in [
+            ("similarity_threshold", Some(self.similarity_threshold)),
+            ("move_threshold", Some(self.move_threshold)),
+            ("split_threshold", self.split_threshold),
+            ("char_diff_threshold", self.char_diff_threshold),
+        ] {
+            let Some(value) = value else {
+                continue;
+            };
+            if !(0.0..=1.0).contains(&value) {
+                return Err(RtError::InvalidInput(format!(
+                    "{name} must be between 0.0 and 1.0, got {value}"
+                )));
+            }
+        }
+        pool::check_worker_threads(self.worker_threads)?;
+        for (name, value) in [
+            ("max_block_tokens", self.max_block_tokens),
+            ("max_diff_groups", self.max_diff_groups),
+            ("max_align_candidates", self.max_align_candidates),
+        ] {
+            if value == 0 {
+                return Err(RtError::InvalidInput(format!("{name} must be at least 1")));
+            }
+        }
+        Normalizer::new(&self.normalization_rules)?;
+        Ok(())
+    }
+}
+
 // ---------------------------------------------------------------------------
 // CompareEngine
 // ---------------------------------------------------------------------------
@@ -53,27 +290,42 @@ impl Default for CompareConfig {
 ///
 /// Call [`CompareEngine::compare`] with two pre-flattened block lists to get a
 /// [`CompareResult`].
+///
+/// The engine is never mutated after construction and is `Send + Sync`, so
+/// one engine can serve concurrent compares from rayon tasks or FFI threads.
 #[allow(dead_code)]
 pub struct CompareEngine {
     config: CompareConfig,
+    normalizer: Normalizer,
 }
 
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<CompareEngine>();
+};
+
 impl CompareEngine {
     /// Create a new engine with the given configuration.
     pub fn new(config: CompareConfig) -> Self {
-        Self { config }
+        let normalizer = Normalizer::new(&config.normalization_rules).unwrap_or_default();
+        Self { config, normalizer }
     }
 
     /// Compare two sets of blocks and produce a [`CompareResult`].
     ///
     /// # Steps
-    /// 1. Flatten left and right block trees to leaf blocks.
-    /// 2. Call [`align_blocks`] to get block-level alignments.
+    /// 1. Flatten left and right block trees to leaf blocks and apply the
+    ///    configured normalization rules.
+    /// 2. Align tables row by row and column by column with
+    ///    [`align_tables`], then call [`align_blocks_with_tables`] with the
+    ///    configured thresholds to get block-level alignments.
     /// 3. Use rayon `par_iter` to compute [`token_diff`] in parallel for each
-    ///    `Matched` or `Moved` alignment pair.
+    ///    `Matched`, `Moved`, `Split` or `Merged` alignment.
     /// 4. Build a [`BlockDelta`] for each alignment.
     /// 5. Compute aggregate stats.
-    /// 6. Record elapsed wall-clock time in milliseconds.
+    /// 6. Compare document-level formatting profiles and per-block
+    ///    attachments.
+    /// 7. Record elapsed wall-clock time in milliseconds.
     pub fn compare(
         &self,
         left_doc_id: Uuid,
@@ -81,57 +333,408 @@ impl CompareEngine {
         left_blocks: &[Block],
         right_blocks: &[Block],
     ) -> CompareResult {
+        self.compare_streaming(
+            left_doc_id,
+            right_doc_id,
+            left_blocks,
+            right_blocks,
+            usize::MAX,
+            |_| {},
+        )
+    }
+
+    /// As [`compare`](Self::compare), but hands deltas to `on_batch` as they
+    /// are computed, in document (alignment) order, so a UI can render the
+    /// first changes before the run finishes.
+    ///
+    /// Alignment runs up front; token diffs are then computed `batch_size`
+    /// alignments at a time (in parallel within a batch), and each batch is
+    /// emitted once complete. The returned [`CompareResult`] holds every
+    /// delta, identical to what `compare` produces. A `batch_size` of 0 is
+    /// treated as 1. Batches left empty by the output mode are not emitted.
+    pub fn compare_streaming<F>(
+        &self,
+        left_doc_id: Uuid,
+        right_doc_id: Uuid,
+        left_blocks: &[Block],
+        right_blocks: &[Block],
+        batch_size: usize,
+        on_batch: F,
+    ) -> CompareResult
+    where
+        F: FnMut(&[BlockDelta]),
+    {
+        self.compare_with_progress(
+            left_doc_id,
+            right_doc_id,
+            left_blocks,
+            right_blocks,
+            batch_size,
+            on_batch,
+            |_| {},
+        )
+    }
+
+    /// As [`compare_streaming`](Self::compare_streaming), also reporting a
+    /// [`CompareProgress`] to `on_progress` as each [`ComparePhase`] starts
+    /// and after every batch of the `diff` phase, including batches that emit
+    /// no deltas. The last report, of the `stats` phase, has every block
+    /// processed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn compare_with_progress<F, P>(
+        &self,
+        left_doc_id: Uuid,
+        right_doc_id: Uuid,
+        left_blocks: &[Block],
+        right_blocks: &[Block],
+        batch_size: usize,
+        on_batch: F,
+        on_progress: P,
+    ) -> CompareResult
+    where
+        F: FnMut(&[BlockDelta]),
+        P: FnMut(&CompareProgress),
+    {
+        // Fall back to rayon's global pool rather than fail when the
+        // configured one cannot be built.
+        self.compare_on(
+            pool::usable_threads(self.config.worker_threads),
+            left_doc_id,
+            right_doc_id,
+            left_blocks,
+            right_blocks,
+            batch_size,
+            on_batch,
+            on_progress,
+            &CancelToken::new(),
+        )
+        .unwrap_or_else(|_| unreachable!("the run's token is never cancelled and its pool exists"))
+    }
+
+    /// As [`compare_with_progress`](Self::compare_with_progress), giving up
+    /// with [`RtError::Cancelled`] once `cancel` is cancelled. The token is
+    /// checked before every alignment pass and before each block pair is
+    /// diffed; batches already emitted stay emitted. Fails too when the
+    /// pool of [`CompareConfig::worker_threads`] cannot be built.
+    #[allow(clippy::too_many_arguments)]
+    pub fn compare_cancellable<F, P>(
+        &self,
+        left_doc_id: Uuid,
+        right_doc_id: Uuid,
+        left_blocks: &[Block],
+        right_blocks: &[Block],
+        batch_size: usize,
+        on_batch: F,
+        on_progress: P,
+        cancel: &CancelToken,
+    ) -> Result<CompareResult>
+    where
+        F: FnMut(&[BlockDelta]),
+        P: FnMut(&CompareProgress),
+    {
+        self.compare_on(
+            self.config.worker_threads,
+            left_doc_id,
+            right_doc_id,
+            left_blocks,
+            right_blocks,
+            batch_size,
+            on_batch,
+            on_progress,
+            cancel,
+        )
+    }
+
+    /// [`compare_cancellable`](Self::compare_cancellable) on the pool of
+    /// `threads` workers.
+    #[allow(clippy::too_many_arguments)]
+    fn compare_on<F, P>(
+        &self,
+        threads: usize,
+        left_doc_id: Uuid,
+        right_doc_id: Uuid,
+        left_blocks: &[Block],
+        right_blocks: &[Block],
+        batch_size: usize,
+        mut on_batch: F,
+        mut on_progress: P,
+        cancel: &CancelToken,
+    ) -> Result<CompareResult>
+    where
+        F: FnMut(&[BlockDelta]),
+        P: FnMut(&CompareProgress),
+    {
+        #[cfg(not(target_arch = "wasm32"))]
         let start = Instant::now();
 
-        // Step 1: flatten both block trees.
-        let left_flat = flatten_blocks(left_blocks);
-        let right_flat = flatten_blocks(right_blocks);
+        let manifest = self.manifest(&[
+            InputDigest::from_blocks(left_doc_id, left_blocks),
+            InputDigest::from_blocks(right_doc_id, right_blocks),
+        ]);
+
+        let mut progress = CompareProgress {
+            phase: ComparePhase::Flatten,
+            blocks_processed: 0,
+            blocks_total: 0,
+        };
+        // Report `progress` as of `phase`.
+        let mut report = |phase: ComparePhase, progress: &mut CompareProgress| {
+            progress.phase = phase;
+            on_progress(progress);
+        };
+
+        // Step 1: flatten and normalize both block trees.
+        report(ComparePhase::Flatten, &mut progress);
+        let left_flat = self.flatten(left_blocks);
+        let right_flat = self.flatten(right_blocks);
 
         // Step 2: align.
-        let alignments = align_blocks(&left_flat, &right_flat);
+        let thresholds = AlignThresholds {
+            worker_threads: threads,
+            ..self.thresholds()
+        };
+        cancel.check()?;
+        report(ComparePhase::AlignTables, &mut progress);
+        let tables = align_tables(&left_flat, &right_flat);
+        let left_sections = section_paths(&left_flat);
+        let right_sections = section_paths(&right_flat);
+        let alignments =
+            align_blocks_observed(&left_flat, &right_flat, &thresholds, &tables, |pass| {
+                cancel.check()?;
+                report(ComparePhase::align_pass(pass), &mut progress);
+                Ok(())
+            })?;
 
-        // Step 3 & 4: compute token diffs in parallel and build BlockDeltas.
+        // Step 3 & 4: compute token diffs in parallel and build BlockDeltas,
+        // one batch of alignments at a time.
         //
-        // We collect (index, BlockDelta) pairs so we can maintain the original
-        // alignment order after parallel processing.
-        let indexed_deltas: Vec<(usize, BlockDelta)> = alignments
-            .par_iter()
-            .enumerate()
-            .map(|(idx, alignment)| {
-                let delta = self.build_delta(alignment, &left_flat, &right_flat);
-                (idx, delta)
-            })
-            .collect();
+        // We collect (index, BlockDelta, warning) triples so we can maintain
+        // the original alignment order after parallel processing.
+        //
+        // Stats count every delta, including those the output mode drops.
+        let mut deltas: Vec<BlockDelta> = Vec::new();
+        let mut warnings: Vec<CompareWarning> = Vec::new();
+        let mut stats = CompareStats {
+            blocks_left: left_flat.len(),
+            blocks_right: right_flat.len(),
+            inserted: 0,
+            deleted: 0,
+            modified: 0,
+            moved: 0,
+            unchanged: 0,
+            split: 0,
+            merged: 0,
+        };
+        progress.blocks_total = alignments.len();
+        report(ComparePhase::Diff, &mut progress);
+        for batch in alignments.chunks(batch_size.max(1)) {
+            #[cfg(feature = "parallel")]
+            let alignment_iter = batch.par_iter();
+            #[cfg(not(feature = "parallel"))]
+            let alignment_iter = batch.iter();
 
-        // Sort by index to restore traversal order.
-        let mut indexed_deltas = indexed_deltas;
-        indexed_deltas.sort_by_key(|(i, _)| *i);
-        let deltas: Vec<BlockDelta> = indexed_deltas.into_iter().map(|(_, d)| d).collect();
+            // `None` as soon as the token is cancelled.
+            let indexed_deltas: Option<Vec<(usize, BlockDelta, Option<CompareWarning>)>> =
+                pool::install(threads, || {
+                    alignment_iter
+                        .enumerate()
+                        .map(|(idx, alignment)| {
+                            if cancel.is_cancelled() {
+                                return None;
+                            }
+                            let (mut delta, warning) =
+                                self.build_delta(alignment, &left_flat, &right_flat);
+                            locate_delta(
+                                &mut delta,
+                                &left_flat,
+                                &right_flat,
+                                &left_sections,
+                                &right_sections,
+                            );
+                            Some((idx, delta, warning))
+                        })
+                        .collect()
+                })?;
 
-        // Step 5: compute stats.
-        let stats = compute_stats(&deltas, left_flat.len(), right_flat.len());
+            // Sort by index to restore traversal order.
+            let mut indexed_deltas = indexed_deltas.ok_or(RtError::Cancelled)?;
+            indexed_deltas.sort_by_key(|(i, _, _)| *i);
+            let first = deltas.len();
+            for (_, mut delta, warning) in indexed_deltas {
+                warnings.extend(warning);
+                tally(&mut stats, &delta);
+                if self.config.keep(&mut delta) {
+                    deltas.push(delta);
+                }
+            }
+            if deltas.len() > first {
+                on_batch(&deltas[first..]);
+            }
+            progress.blocks_processed += batch.len();
+            report(ComparePhase::Diff, &mut progress);
+        }
+
+        // Step 5: document-level formatting drift, per-block attachment
+        // changes and cross-references broken by the edit.
+        report(ComparePhase::Stats, &mut progress);
+        let formatting_drift = compare_formatting(&left_flat, &right_flat);
+        let attachment_changes = compare_attachments(&alignments, &left_flat, &right_flat);
+        let broken_references = broken_references(&alignments, &left_flat, &right_flat);
 
         // Step 6: record elapsed time.
+        // `Instant` is unavailable on wasm32-unknown-unknown; report 0 there.
+        #[cfg(not(target_arch = "wasm32"))]
         let elapsed_ms = start.elapsed().as_millis() as u64;
+        #[cfg(target_arch = "wasm32")]
+        let elapsed_ms = 0u64;
 
-        CompareResult {
-            run_id: Uuid::new_v4(),
+        Ok(CompareResult {
+            run_id: self.run_id(&manifest),
             left_doc_id,
             right_doc_id,
-            elapsed_ms,
+            elapsed_ms: if self.config.deterministic { 0 } else { elapsed_ms },
             stats,
             deltas,
+            formatting_drift,
+            attachment_changes,
+            table_changes: tables.changes,
+            broken_references,
+            warnings,
+            manifest: Some(manifest),
+        })
+    }
+
+    /// `blocks` flattened with [`flatten_blocks`], with the configured
+    /// normalization rules applied to each.
+    pub(crate) fn flatten(&self, blocks: &[Block]) -> Vec<Block> {
+        let mut flat = flatten_blocks(blocks);
+        for block in &mut flat {
+            self.normalizer.normalize_block(block);
+        }
+        flat
+    }
+
+    /// This engine's configuration.
+    pub(crate) fn config(&self) -> &CompareConfig {
+        &self.config
+    }
+
+    /// The alignment thresholds of this engine's configuration.
+    pub(crate) fn thresholds(&self) -> AlignThresholds {
+        AlignThresholds {
+            similarity: self.config.similarity_threshold,
+            moved: self.config.move_threshold,
+            move_distance_max: self.config.move_distance_max,
+            split: self.config.split_threshold,
+            ignore_renumbering: self.config.ignore_renumbering,
+            max_candidates: self.config.max_align_candidates,
+            metric: self.config.similarity_metric,
+            stopwords: self.config.ignore_stopwords.then(|| match &self.config.stopwords {
+                Some(words) => Stopwords::new(words),
+                None => Stopwords::legal(),
+            }),
+            worker_threads: self.config.worker_threads,
         }
     }
 
-    /// Build a single [`BlockDelta`] from one alignment entry.
-    fn build_delta(
+    /// The [`RunManifest`] of a run of this engine over `inputs` (left, then
+    /// right).
+    pub fn manifest(&self, inputs: &[InputDigest]) -> RunManifest {
+        let mut manifest = RunManifest::new(
+            [("rt-compare", ENGINE_VERSION)],
+            config_fingerprint(&self.config),
+            inputs.to_vec(),
+        );
+        manifest.output_mode = Some(self.config.output_label().to_string());
+        manifest
+    }
+
+    /// Id for a run of this engine described by `manifest`: fresh for every
+    /// run, or derived from the manifest in `deterministic` mode.
+    pub fn run_id(&self, manifest: &RunManifest) -> Uuid {
+        if self.config.deterministic {
+            let manifest = serde_json::to_string(manifest).unwrap_or_default();
+            derived_uuid(&manifest)
+        } else {
+            Uuid::new_v4()
+        }
+    }
+
+    /// Compute the token diff between two runs of blocks (one block each for
+    /// a 1:1 pair), enforcing the configured size limits, and its sentence
+    /// grouping when `sentence_diffs` is set.
+    ///
+    /// When either limit is exceeded the pair is collapsed into a single
+    /// coarse "block replaced" group, without sentences, and a
+    /// [`CompareWarning`] is returned alongside it (its `delta_id` is filled
+    /// in by the caller).
+    fn guarded_token_diff(
+        &self,
+        left: &[&Block],
+        right: &[&Block],
+    ) -> (Vec<TokenDiff>, Vec<SentenceDiff>, Option<CompareWarning>) {
+        let (lb, rb) = (left[0], right[0]);
+        let left_tokens = concat_tokens(left);
+        let right_tokens = concat_tokens(right);
+
+        let largest = left_tokens.len().max(right_tokens.len());
+        if largest > self.config.max_block_tokens {
+            let warning = CompareWarning {
+                delta_id: Uuid::nil(),
+                left_block_id: Some(lb.id),
+                right_block_id: Some(rb.id),
+                kind: CompareWarningKind::BlockTooLarge,
+                message: format!(
+                    "block has {} tokens (limit {}); reported as replaced without token diff",
+                    largest, self.config.max_block_tokens
+                ),
+            };
+            return (coarse_replacement(&left_tokens, &right_tokens), vec![], Some(warning));
+        }
+
+        let mut diffs = token_diff(&left_tokens, &right_tokens);
+        if diffs.len() > self.config.max_diff_groups {
+            let warning = CompareWarning {
+                delta_id: Uuid::nil(),
+                left_block_id: Some(lb.id),
+                right_block_id: Some(rb.id),
+                kind: CompareWarningKind::DiffTooLarge,
+                message: format!(
+                    "token diff has {} groups (limit {}); reported as replaced",
+                    diffs.len(),
+                    self.config.max_diff_groups
+                ),
+            };
+            return (coarse_replacement(&left_tokens, &right_tokens), vec![], Some(warning));
+        }
+
+        if let Some(threshold) = self.config.char_diff_threshold {
+            refine_substitutions(&mut diffs, threshold);
+        }
+        let sentences = if self.config.sentence_diffs {
+            let mut sentences = sentence_diff(&left_tokens, &right_tokens);
+            if let Some(threshold) = self.config.char_diff_threshold {
+                for sentence in &mut sentences {
+                    refine_substitutions(&mut sentence.token_diffs, threshold);
+                }
+            }
+            sentences
+        } else {
+            vec![]
+        };
+        (diffs, sentences, None)
+    }
+
+    /// Build a single [`BlockDelta`] from one alignment entry, plus a warning
+    /// when the diff size guard was triggered.
+    pub(crate) fn build_delta(
         &self,
         alignment: &BlockAlignment,
         left_flat: &[Block],
         right_flat: &[Block],
-    ) -> BlockDelta {
-        match alignment {
+    ) -> (BlockDelta, Option<CompareWarning>) {
+        let mut warning = None;
+        let mut delta = match alignment {
             BlockAlignment::Matched { left, right, similarity } => {
                 let lb = &left_flat[*left];
                 let rb = &right_flat[*right];
@@ -139,31 +742,18 @@ impl CompareEngine {
                 // Determine if there is actually any textual change.
                 let is_changed = lb.clause_hash != rb.clause_hash;
 
-                let token_diffs = if is_changed {
-                    let left_tokens = ensure_tokens(lb);
-                    let right_tokens = ensure_tokens(rb);
-                    token_diff(&left_tokens, &right_tokens)
+                let (token_diffs, sentences) = if is_changed {
+                    let (diffs, sentences, w) = self.guarded_token_diff(&[lb], &[rb]);
+                    warning = w;
+                    (diffs, sentences)
                 } else {
-                    vec![]
+                    (vec![], vec![])
                 };
 
                 let kind = if is_changed {
                     DeltaKind::Modified
                 } else {
-                    // We still emit the delta (unchanged) so stats can count it.
-                    // We represent it with Modified=false; caller uses stats.unchanged.
-                    // Use a sentinel: re-use Modified but with empty token_diffs and
-                    // similarity 1.0. Actually the spec only defines the 4 kinds.
-                    // Unchanged blocks are Matched with no diffs — we don't have an
-                    // "Unchanged" DeltaKind in the contract, so we emit Modified with
-                    // empty diffs when content is identical, and the stats counter
-                    // captures the actual breakdown.
-                    //
-                    // NOTE: The spec doesn't define an "unchanged" DeltaKind; only
-                    // the stats struct tracks it. We omit unchanged deltas to keep
-                    // the output compact. If callers need them, they can check
-                    // similarity_score == 1.0 and empty token_diffs.
-                    DeltaKind::Modified
+                    DeltaKind::Unchanged
                 };
 
                 BlockDelta {
@@ -176,6 +766,12 @@ impl CompareEngine {
                     token_diffs,
                     similarity_score: Some(*similarity),
                     move_target_id: None,
+                    group_block_ids: vec![],
+                    summary: None,
+                    section: None,
+                    sentences,
+                    anchors: None,
+                    authors: vec![],
                 }
             }
 
@@ -183,12 +779,12 @@ impl CompareEngine {
                 let lb = &left_flat[*left];
                 let rb = &right_flat[*right];
 
-                let left_tokens = ensure_tokens(lb);
-                let right_tokens = ensure_tokens(rb);
-                let token_diffs = if lb.clause_hash != rb.clause_hash {
-                    token_diff(&left_tokens, &right_tokens)
+                let (token_diffs, sentences) = if lb.clause_hash != rb.clause_hash {
+                    let (diffs, sentences, w) = self.guarded_token_diff(&[lb], &[rb]);
+                    warning = w;
+                    (diffs, sentences)
                 } else {
-                    vec![]
+                    (vec![], vec![])
                 };
 
                 BlockDelta {
@@ -201,6 +797,62 @@ impl CompareEngine {
                     token_diffs,
                     similarity_score: Some(*similarity),
                     move_target_id: Some(rb.id),
+                    group_block_ids: vec![],
+                    summary: None,
+                    section: None,
+                    sentences,
+                    anchors: None,
+                    authors: vec![],
+                }
+            }
+
+            BlockAlignment::Split { left, right, similarity } => {
+                let lb = &left_flat[*left];
+                let rbs: Vec<&Block> = right.iter().map(|&r| &right_flat[r]).collect();
+                let (token_diffs, sentences, w) = self.guarded_token_diff(&[lb], &rbs);
+                warning = w;
+
+                BlockDelta {
+                    id: Uuid::new_v4(),
+                    kind: DeltaKind::Split,
+                    left_block_id: Some(lb.id),
+                    right_block_id: Some(rbs[0].id),
+                    left_ordinal: Some(*left),
+                    right_ordinal: Some(right[0]),
+                    token_diffs,
+                    similarity_score: Some(*similarity),
+                    move_target_id: None,
+                    group_block_ids: rbs.iter().map(|b| b.id).collect(),
+                    summary: None,
+                    section: None,
+                    sentences,
+                    anchors: None,
+                    authors: vec![],
+                }
+            }
+
+            BlockAlignment::Merged { left, right, similarity } => {
+                let lbs: Vec<&Block> = left.iter().map(|&l| &left_flat[l]).collect();
+                let rb = &right_flat[*right];
+                let (token_diffs, sentences, w) = self.guarded_token_diff(&lbs, &[rb]);
+                warning = w;
+
+                BlockDelta {
+                    id: Uuid::new_v4(),
+                    kind: DeltaKind::Merged,
+                    left_block_id: Some(lbs[0].id),
+                    right_block_id: Some(rb.id),
+                    left_ordinal: Some(left[0]),
+                    right_ordinal: Some(*right),
+                    token_diffs,
+                    similarity_score: Some(*similarity),
+                    move_target_id: None,
+                    group_block_ids: lbs.iter().map(|b| b.id).collect(),
+                    summary: None,
+                    section: None,
+                    sentences,
+                    anchors: None,
+                    authors: vec![],
                 }
             }
 
@@ -216,6 +868,12 @@ impl CompareEngine {
                     token_diffs: vec![],
                     similarity_score: None,
                     move_target_id: None,
+                    group_block_ids: vec![],
+                    summary: None,
+                    section: None,
+                    sentences: vec![],
+                    anchors: None,
+                    authors: vec![],
                 }
             }
 
@@ -231,9 +889,27 @@ impl CompareEngine {
                     token_diffs: vec![],
                     similarity_score: None,
                     move_target_id: None,
+                    group_block_ids: vec![],
+                    summary: None,
+                    section: None,
+                    sentences: vec![],
+                    anchors: None,
+                    authors: vec![],
                 }
             }
+        };
+
+        if self.config.deterministic {
+            delta.id = derived_uuid(&format!(
+                "{:?}/{:?}/{:?}",
+                delta.left_block_id, delta.right_block_id, delta.kind
+            ));
         }
+        let warning = warning.map(|w| CompareWarning {
+            delta_id: delta.id,
+            ..w
+        });
+        (delta, warning)
     }
 }
 
@@ -247,6 +923,16 @@ impl Default for CompareEngine {
 // Internal helpers
 // ---------------------------------------------------------------------------
 
+#[cfg(feature = "parallel")]
+fn default_worker_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn default_worker_threads() -> usize {
+    1
+}
+
 /// Flatten a block tree into a pre-order list of all blocks (including
 /// interior nodes, not just leaves), preserving document order.
 pub fn flatten_blocks(blocks: &[Block]) -> Vec<Block> {
@@ -267,8 +953,27 @@ fn flatten_recursive(block: &Block, out: &mut Vec<Block>) {
     }
 }
 
+/// The `structural_path` of each flat block's top-level ancestor (its own
+/// path for a top-level block), following `parent_id` within `flat`.
+pub(crate) fn section_paths(flat: &[Block]) -> Vec<String> {
+    let index: HashMap<Uuid, usize> = flat.iter().enumerate().map(|(i, b)| (b.id, i)).collect();
+    flat.iter()
+        .map(|block| {
+            let mut root = block;
+            // Bounded so a parent cycle in malformed input cannot hang.
+            for _ in 0..flat.len() {
+                match root.parent_id.and_then(|p| index.get(&p)) {
+                    Some(&parent) => root = &flat[parent],
+                    None => break,
+                }
+            }
+            root.structural_path.clone()
+        })
+        .collect()
+}
+
 /// Return the block's existing token list, or tokenize on the fly if empty.
-fn ensure_tokens(block: &Block) -> Vec<rt_core::Token> {
+pub fn ensure_tokens(block: &Block) -> Vec<rt_model::Token> {
     if !block.tokens.is_empty() {
         block.tokens.clone()
     } else {
@@ -276,37 +981,107 @@ fn ensure_tokens(block: &Block) -> Vec<rt_core::Token> {
     }
 }
 
-/// Compute aggregate [`CompareStats`] from a list of deltas.
-fn compute_stats(deltas: &[BlockDelta], blocks_left: usize, blocks_right: usize) -> CompareStats {
-    let mut inserted = 0usize;
-    let mut deleted = 0usize;
-    let mut modified = 0usize;
-    let mut moved = 0usize;
-    let mut unchanged = 0usize;
-
-    for delta in deltas {
-        match delta.kind {
-            DeltaKind::Inserted => inserted += 1,
-            DeltaKind::Deleted => deleted += 1,
-            DeltaKind::Modified => {
-                if delta.token_diffs.is_empty() {
-                    unchanged += 1;
-                } else {
-                    modified += 1;
-                }
+/// The tokens of a run of blocks as one sequence, with offsets into the
+/// blocks' canonical texts joined by single spaces.
+fn concat_tokens(blocks: &[&Block]) -> Vec<rt_model::Token> {
+    if let [block] = blocks {
+        return ensure_tokens(block);
+    }
+    let mut tokens = Vec::new();
+    let mut base = 0;
+    for block in blocks {
+        tokens.extend(ensure_tokens(block).into_iter().map(|mut t| {
+            t.offset += base;
+            t
+        }));
+        base += block.canonical_text.len() + 1;
+    }
+    tokens
+}
+
+/// Collapse a block pair into a single coarse group covering every token on
+/// both sides, used when the full token diff would be too large.
+fn coarse_replacement(left: &[rt_model::Token], right: &[rt_model::Token]) -> Vec<TokenDiff> {
+    let kind = match (left.is_empty(), right.is_empty()) {
+        (true, true) => return vec![],
+        (false, true) => DiffKind::Deleted,
+        (true, false) => DiffKind::Inserted,
+        (false, false) => DiffKind::Substituted,
+    };
+    vec![TokenDiff {
+        kind,
+        left_tokens: left.iter().map(|t| t.text.clone()).collect(),
+        right_tokens: right.iter().map(|t| t.text.clone()).collect(),
+        left_offset: left.first().map(|t| t.offset).unwrap_or(0),
+        right_offset: right.first().map(|t| t.offset).unwrap_or(0),
+        char_diffs: Vec::new(),
+    }]
+}
+
+/// Set `delta`'s section and deep-link anchors from the flat block lists
+/// its ordinals index.
+pub(crate) fn locate_delta(
+    delta: &mut BlockDelta,
+    left_flat: &[Block],
+    right_flat: &[Block],
+    left_sections: &[String],
+    right_sections: &[String],
+) {
+    delta.section = match (delta.right_ordinal, delta.left_ordinal) {
+        (Some(r), _) => Some(right_sections[r].clone()),
+        (None, Some(l)) => Some(left_sections[l].clone()),
+        (None, None) => None,
+    };
+    delta.anchors = Some(DeltaAnchors::new(
+        delta.left_ordinal.map(|l| &left_flat[l]),
+        delta.right_ordinal.map(|r| &right_flat[r]),
+    ));
+    delta.authors = delta_authors(delta, left_flat, right_flat);
+}
+
+/// Authors of the tracked changes on `delta`'s blocks (the whole group of a
+/// split or merge), left side first, in block order without repeats.
+fn delta_authors(delta: &BlockDelta, left_flat: &[Block], right_flat: &[Block]) -> Vec<String> {
+    let group = |grouped: bool| grouped.then_some(delta.group_block_ids.as_slice());
+    let left = side_blocks(left_flat, delta.left_ordinal, group(delta.kind == DeltaKind::Merged));
+    let right = side_blocks(right_flat, delta.right_ordinal, group(delta.kind == DeltaKind::Split));
+    let mut authors: Vec<String> = Vec::new();
+    for block in left.into_iter().chain(right) {
+        if let Some(change) = &block.formatting_meta.tracked_change {
+            if !authors.contains(&change.author) {
+                authors.push(change.author.clone());
             }
-            DeltaKind::Moved => moved += 1,
         }
     }
+    authors
+}
+
+/// The blocks of `flat` one side of a delta covers: those of `group` when
+/// given, else the one at `ordinal`.
+fn side_blocks<'a>(
+    flat: &'a [Block],
+    ordinal: Option<usize>,
+    group: Option<&[Uuid]>,
+) -> Vec<&'a Block> {
+    match (ordinal, group) {
+        (None, _) => vec![],
+        (Some(_), Some(ids)) => {
+            ids.iter().filter_map(|id| flat.iter().find(|b| b.id == *id)).collect()
+        }
+        (Some(o), None) => vec![&flat[o]],
+    }
+}
 
-    CompareStats {
-        blocks_left,
-        blocks_right,
-        inserted,
-        deleted,
-        modified,
-        moved,
-        unchanged,
+/// Count `delta` in `stats`.
+pub(crate) fn tally(stats: &mut CompareStats, delta: &BlockDelta) {
+    match delta.kind {
+        DeltaKind::Inserted => stats.inserted += 1,
+        DeltaKind::Deleted => stats.deleted += 1,
+        DeltaKind::Modified => stats.modified += 1,
+        DeltaKind::Unchanged => stats.unchanged += 1,
+        DeltaKind::Moved => stats.moved += 1,
+        DeltaKind::Split => stats.split += 1,
+        DeltaKind::Merged => stats.merged += 1,
     }
 }
 
@@ -317,7 +1092,8 @@ fn compute_stats(deltas: &[BlockDelta], blocks_left: usize, blocks_right: usize)
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rt_core::{Block, BlockType};
+    use crate::diff::SentenceKind;
+    use rt_model::{Block, BlockType};
 
     fn make_block(doc: Uuid, path: &str, text: &str, idx: i32) -> Block {
         Block::new(BlockType::Clause, path, text, text, None, doc, idx)
@@ -384,6 +1160,275 @@ mod tests {
         assert!(!modified_delta.unwrap().token_diffs.is_empty());
     }
 
+    #[test]
+    fn delta_anchors_are_derived_from_content() {
+        let doc = Uuid::new_v4();
+        let versions = || {
+            let left = vec![
+                make_block(doc, "1.1", "the borrower shall repay the loan promptly", 0),
+                make_block(doc, "1.2", "this clause is removed", 1),
+            ];
+            let right = vec![make_block(doc, "1.1", "the borrower shall repay the loan today", 0)];
+            (left, right)
+        };
+        let engine = CompareEngine::default();
+        let (left, right) = versions();
+        let first = engine.compare(doc, doc, &left, &right);
+        let (left, right) = versions();
+        let second = engine.compare(doc, doc, &left, &right);
+
+        let anchors = |r: &CompareResult| -> Vec<DeltaAnchors> {
+            r.deltas.iter().map(|d| d.anchors.clone().unwrap()).collect()
+        };
+        assert_eq!(anchors(&first), anchors(&second));
+        let deleted = first.deltas.iter().find(|d| d.kind == DeltaKind::Deleted).unwrap();
+        let deleted = deleted.anchors.as_ref().unwrap();
+        assert_eq!(deleted.left_block.as_deref(), Some(left[1].link_anchor().as_str()));
+        assert!(deleted.right_block.is_none());
+        assert!(deleted.delta.ends_with("-_"));
+    }
+
+    #[test]
+    fn compare_reports_formatting_drift_for_identical_text() {
+        let doc = Uuid::new_v4();
+        let left = vec![make_block(doc, "1.1", "the borrower shall repay", 0)];
+        let mut right = left.clone();
+        right[0].formatting_meta.style_name = Some("Heading 2".to_string());
+        let engine = CompareEngine::default();
+        let result = engine.compare(doc, doc, &left, &right);
+        assert_eq!(result.stats.unchanged, 1);
+        assert!(result.formatting_drift.has_drift());
+        assert_eq!(result.formatting_drift.style_changes.len(), 2);
+    }
+
+    #[test]
+    fn compare_reports_replaced_attachment_for_identical_text() {
+        let doc = Uuid::new_v4();
+        let image = |hash: &str| rt_model::Attachment {
+            attachment_type: rt_model::AttachmentType::Image,
+            content_hash: hash.to_string(),
+            storage_ref: "word/media/image1.png".to_string(),
+            name: None,
+        };
+        let mut left = vec![make_block(doc, "1.1", "signed by the borrower", 0)];
+        left[0].attachments.push(image("old"));
+        let mut right = left.clone();
+        right[0].attachments[0] = image("new");
+
+        let result = CompareEngine::default().compare(doc, doc, &left, &right);
+        assert_eq!(result.stats.unchanged, 1);
+        assert_eq!(result.attachment_changes.len(), 1);
+        assert_eq!(result.attachment_changes[0].right_hash.as_deref(), Some("new"));
+    }
+
+    /// Flat table blocks: the table, then each row followed by its cells.
+    fn make_table(doc: Uuid, rows: &[&[&str]]) -> Vec<Block> {
+        use rt_model::BlockType;
+        let table = Block::new(BlockType::Table, "tbl0", "", "", None, doc, 0);
+        let mut blocks = vec![table.clone()];
+        for (r, cells) in rows.iter().enumerate() {
+            let path = format!("tbl0.r{r}");
+            let row = Block::new(BlockType::TableRow, &path, "", "", Some(table.id), doc, r as i32);
+            blocks.push(row.clone());
+            for (c, text) in cells.iter().enumerate() {
+                let cell_path = format!("{path}.c{c}");
+                blocks.push(Block::new(
+                    BlockType::TableCell,
+                    cell_path,
+                    *text,
+                    *text,
+                    Some(row.id),
+                    doc,
+                    c as i32,
+                ));
+            }
+        }
+        blocks
+    }
+
+    #[test]
+    fn inserted_table_column_is_reported_without_modifying_cells() {
+        let doc = Uuid::new_v4();
+        let left = make_table(doc, &[&["Name", "Rate"], &["Alice", "5%"], &["Bob", "7%"]]);
+        let right = make_table(
+            doc,
+            &[&["Name", "Term", "Rate"], &["Alice", "1 year", "5%"], &["Bob", "2 years", "9%"]],
+        );
+
+        let result = CompareEngine::default().compare(doc, doc, &left, &right);
+        assert_eq!(result.stats.inserted, 3);
+        assert_eq!(result.stats.modified, 1);
+        assert_eq!(result.stats.deleted, 0);
+        let modified = result.deltas.iter().find(|d| d.kind == DeltaKind::Modified).unwrap();
+        assert_eq!(modified.left_block_id, Some(left[9].id));
+        assert_eq!(modified.right_block_id, Some(right[12].id));
+        assert_eq!(result.table_changes.len(), 1);
+        assert_eq!(
+            result.table_changes[0].kind,
+            crate::table::TableChangeKind::ColumnInserted
+        );
+    }
+
+    #[test]
+    fn split_clause_is_reported_as_one_delta() {
+        let doc = Uuid::new_v4();
+        let left = vec![make_block(
+            doc,
+            "1.1",
+            "the borrower shall repay the loan. interest accrues monthly in arrears",
+            0,
+        )];
+        let right = vec![
+            make_block(doc, "1.1", "the borrower shall repay the loan.", 0),
+            make_block(doc, "1.2", "interest accrues monthly in arrears", 1),
+        ];
+
+        let result = CompareEngine::default().compare(doc, doc, &left, &right);
+        assert_eq!(result.deltas.len(), 1);
+        let delta = &result.deltas[0];
+        assert_eq!(delta.kind, DeltaKind::Split);
+        assert_eq!(delta.left_block_id, Some(left[0].id));
+        assert_eq!(delta.group_block_ids, vec![right[0].id, right[1].id]);
+        assert!(delta.token_diffs.iter().all(|d| d.kind == DiffKind::Equal));
+        assert_eq!(result.stats.split, 1);
+        assert_eq!(result.stats.inserted, 0);
+
+        let engine = CompareEngine::new(
+            CompareConfig::from_json(r#"{"split_threshold": null}"#).unwrap(),
+        );
+        let result = engine.compare(doc, doc, &left, &right);
+        assert_eq!(result.stats.split, 0);
+        assert_eq!(result.stats.inserted, 1);
+    }
+
+    #[test]
+    fn merged_clauses_are_reported_as_one_delta() {
+        let doc = Uuid::new_v4();
+        let left = vec![
+            make_block(doc, "1.1", "the borrower shall repay the loan.", 0),
+            make_block(doc, "1.2", "interest accrues monthly in arrears", 1),
+        ];
+        let right = vec![make_block(
+            doc,
+            "1.1",
+            "the borrower shall repay the loan. interest accrues quarterly in arrears",
+            0,
+        )];
+
+        let result = CompareEngine::default().compare(doc, doc, &left, &right);
+        assert_eq!(result.deltas.len(), 1);
+        let delta = &result.deltas[0];
+        assert_eq!(delta.kind, DeltaKind::Merged);
+        assert_eq!(delta.right_block_id, Some(right[0].id));
+        assert_eq!(delta.group_block_ids, vec![left[0].id, left[1].id]);
+        let changed = delta.token_diffs.iter().find(|d| d.kind == DiffKind::Substituted).unwrap();
+        assert_eq!(changed.left_tokens, vec!["monthly"]);
+        // Offsets on the merged side run through both blocks' texts.
+        assert!(changed.left_offset > left[0].canonical_text.len());
+        assert_eq!(result.stats.merged, 1);
+        assert_eq!(result.stats.deleted, 0);
+    }
+
+    #[test]
+    fn deltas_name_the_authors_of_their_tracked_changes() {
+        let doc = Uuid::new_v4();
+        let tracked = |mut block: Block, author: &str| {
+            block.formatting_meta.tracked_change = Some(
+                serde_json::from_value(serde_json::json!({
+                    "author": author,
+                    "date": "2025-03-01T00:00:00Z",
+                    "change_type": "insert",
+                    "original": null,
+                }))
+                .unwrap(),
+            );
+            block
+        };
+        let left = vec![
+            make_block(doc, "1.1", "the borrower shall repay the loan.", 0),
+            make_block(doc, "1.2", "interest accrues monthly in arrears", 1),
+        ];
+        let right = vec![
+            tracked(
+                make_block(doc, "1.1", "the borrower shall promptly repay the loan.", 0),
+                "Bob",
+            ),
+            make_block(doc, "1.2", "interest accrues monthly in arrears", 1),
+            tracked(make_block(doc, "1.3", "fees are payable on demand", 2), "Carol"),
+        ];
+
+        let result = CompareEngine::default().compare(doc, doc, &left, &right);
+        let authors: Vec<_> = result.deltas.iter().map(|d| d.authors.clone()).collect();
+        assert_eq!(authors, [vec!["Bob".to_string()], vec![], vec!["Carol".to_string()]]);
+        let json = serde_json::to_value(&result.deltas[1]).unwrap();
+        assert!(json.get("authors").is_none());
+
+        let merged = vec![tracked(
+            make_block(doc, "1.1", "the borrower shall repay the loan. interest accrues", 0),
+            "Bob",
+        )];
+        let left = vec![
+            left[0].clone(),
+            tracked(make_block(doc, "1.2", "interest accrues", 1), "Carol"),
+        ];
+        let result = CompareEngine::default().compare(doc, doc, &left, &merged);
+        assert_eq!(result.deltas[0].kind, DeltaKind::Merged);
+        assert_eq!(result.deltas[0].authors, ["Carol", "Bob"]);
+    }
+
+    #[test]
+    fn split_threshold_must_be_in_range() {
+        assert!(CompareConfig::from_json(r#"{"split_threshold": 1.5}"#).is_err());
+        assert_eq!(
+            CompareConfig::from_json("{}").unwrap().split_threshold,
+            Some(SPLIT_THRESHOLD)
+        );
+    }
+
+    #[test]
+    fn oversized_diff_degrades_to_coarse_replacement() {
+        let doc = Uuid::new_v4();
+        let left = vec![make_block(doc, "1.1", "a b c d e f g h", 0)];
+        let right = vec![make_block(doc, "1.1", "a x c y e z g w", 0)];
+        let engine = CompareEngine::new(CompareConfig {
+            max_diff_groups: 3,
+            ..Default::default()
+        });
+        let result = engine.compare(doc, doc, &left, &right);
+        let delta = &result.deltas[0];
+        assert_eq!(delta.token_diffs.len(), 1);
+        assert_eq!(delta.token_diffs[0].kind, DiffKind::Substituted);
+        assert_eq!(delta.token_diffs[0].left_tokens.len(), 8);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].kind, CompareWarningKind::DiffTooLarge);
+        assert_eq!(result.warnings[0].delta_id, delta.id);
+        assert_eq!(result.stats.modified, 1);
+    }
+
+    #[test]
+    fn oversized_block_skips_token_diff() {
+        let doc = Uuid::new_v4();
+        let left = vec![make_block(doc, "1.1", "one two three four five", 0)];
+        let right = vec![make_block(doc, "1.1", "one two three four six", 0)];
+        let engine = CompareEngine::new(CompareConfig {
+            max_block_tokens: 4,
+            ..Default::default()
+        });
+        let result = engine.compare(doc, doc, &left, &right);
+        assert_eq!(result.deltas[0].token_diffs.len(), 1);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].kind, CompareWarningKind::BlockTooLarge);
+    }
+
+    #[test]
+    fn diffs_within_limits_produce_no_warnings() {
+        let doc = Uuid::new_v4();
+        let left = vec![make_block(doc, "1.1", "the borrower shall repay the loan promptly", 0)];
+        let right = vec![make_block(doc, "1.1", "the borrower shall repay the loan immediately", 0)];
+        let result = CompareEngine::default().compare(doc, doc, &left, &right);
+        assert!(result.warnings.is_empty());
+    }
+
     #[test]
     fn compare_empty_documents() {
         let left_doc = Uuid::new_v4();
@@ -415,6 +1460,34 @@ mod tests {
         let _ = r.elapsed_ms;
     }
 
+    #[test]
+    fn deterministic_results_are_byte_identical() {
+        let doc = Uuid::new_v4();
+        let left = vec![
+            make_block(doc, "1.1", "the borrower shall repay the loan", 0),
+            make_block(doc, "1.2", "notices must be in writing", 1),
+        ];
+        let right = vec![
+            make_block(doc, "1.1", "the borrower shall promptly repay the loan", 0),
+            make_block(doc, "1.3", "fees are payable monthly", 1),
+        ];
+        let engine = CompareEngine::new(CompareConfig {
+            deterministic: true,
+            ..Default::default()
+        });
+        let first = engine.compare(doc, doc, &left, &right);
+        let second = engine.compare(doc, doc, &left, &right);
+        assert_eq!(first.elapsed_ms, 0);
+        assert_eq!(
+            serde_json::to_string(&first).unwrap(),
+            serde_json::to_string(&second).unwrap()
+        );
+
+        let random = CompareEngine::default().compare(doc, doc, &left, &right);
+        assert_ne!(random.run_id, first.run_id);
+        assert_ne!(random.deltas[0].id, first.deltas[0].id);
+    }
+
     #[test]
     fn compare_move_detected() {
         let doc = Uuid::new_v4();
@@ -426,11 +1499,109 @@ mod tests {
         assert_eq!(result.stats.moved, 1, "should detect one moved block");
     }
 
+    #[test]
+    fn move_distance_max_limits_reported_moves() {
+        let doc = Uuid::new_v4();
+        let text = "the lender may assign its rights under this agreement";
+        let left = vec![
+            make_block(doc, "1.1", text, 0),
+            make_block(doc, "2.1", "notices must be in writing", 1),
+        ];
+        let right = vec![
+            make_block(doc, "2.1", "notices must be in writing", 0),
+            make_block(doc, "3.1", text, 1),
+        ];
+        let result = CompareEngine::default().compare(doc, doc, &left, &right);
+        assert_eq!(result.stats.moved, 1);
+
+        let config = CompareConfig::from_json(r#"{"move_distance_max": 0}"#).unwrap();
+        let result = CompareEngine::new(config).compare(doc, doc, &left, &right);
+        let stats = &result.stats;
+        assert_eq!((stats.moved, stats.unchanged), (0, 1));
+        assert_eq!((stats.deleted, stats.inserted), (1, 1));
+    }
+
+    #[test]
+    fn ignore_renumbering_keeps_an_insertion_from_shifting_clauses() {
+        let doc = Uuid::new_v4();
+        let texts = [
+            "the borrower shall repay the loan in monthly instalments",
+            "interest accrues daily at the agreed margin over base rate",
+            "the lender may assign its rights under this agreement",
+        ];
+        let blocks = |texts: &[&str]| -> Vec<Block> {
+            texts
+                .iter()
+                .enumerate()
+                .map(|(i, t)| make_block(doc, &format!("1.{}", i + 1), t, i as i32))
+                .collect()
+        };
+        let left = blocks(&texts);
+        let mut right_texts = texts.to_vec();
+        right_texts.insert(0, "definitions in the schedule apply throughout this agreement");
+        let right = blocks(&right_texts);
+
+        let config = CompareConfig::from_json(r#"{"ignore_renumbering": true}"#).unwrap();
+        let result = CompareEngine::new(config).compare(doc, doc, &left, &right);
+        assert_eq!(result.stats.inserted, 1);
+        assert_eq!(result.stats.unchanged, 3);
+        assert_eq!(result.stats.moved, 0);
+        assert_eq!(result.stats.modified, 0);
+    }
+
+    #[test]
+    fn char_diff_threshold_refines_substituted_tokens() {
+        let doc = Uuid::new_v4();
+        let left = vec![make_block(doc, "1.1", "the indemnification obligations of the seller", 0)];
+        let right = vec![make_block(doc, "1.1", "the indemnifications obligations of the seller", 0)];
+        let substituted = |config: CompareConfig| {
+            let result = CompareEngine::new(config).compare(doc, doc, &left, &right);
+            result.deltas[0]
+                .token_diffs
+                .iter()
+                .find(|d| d.kind == DiffKind::Substituted)
+                .cloned()
+                .unwrap()
+        };
+
+        assert!(substituted(CompareConfig::default()).char_diffs.is_empty());
+        let refined = substituted(CompareConfig {
+            char_diff_threshold: Some(0.8),
+            ..CompareConfig::default()
+        });
+        let inserted: Vec<_> = refined
+            .char_diffs
+            .iter()
+            .filter(|c| c.kind == DiffKind::Inserted)
+            .map(|c| c.right_text.as_str())
+            .collect();
+        assert_eq!(inserted, ["s"]);
+        assert!(CompareConfig::from_json(r#"{"char_diff_threshold": 1.5}"#).is_err());
+    }
+
+    #[test]
+    fn sentence_diffs_group_changed_pairs_by_sentence() {
+        let doc = Uuid::new_v4();
+        let left = vec![make_block(doc, "1.1", "The fee is due monthly. Taxes are extra.", 0)];
+        let right = vec![make_block(doc, "1.1", "The fee is due quarterly. Taxes are extra.", 0)];
+
+        let result = CompareEngine::default().compare(doc, doc, &left, &right);
+        assert!(result.deltas[0].sentences.is_empty());
+
+        let engine = CompareEngine::new(CompareConfig {
+            sentence_diffs: true,
+            ..CompareConfig::default()
+        });
+        let result = engine.compare(doc, doc, &left, &right);
+        let kinds: Vec<_> = result.deltas[0].sentences.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, [SentenceKind::Changed, SentenceKind::Unchanged]);
+    }
+
     #[test]
     fn compare_parallel_produces_ordered_deltas() {
         let doc = Uuid::new_v4();
         let blocks: Vec<Block> = (0..20)
-            .map(|i| make_block(doc, &format!("1.{}", i), &format!("clause {} text here", i), i as i32))
+            .map(|i| make_block(doc, &format!("1.{}", i), &format!("clause {} text here", i), i))
             .collect();
         let engine = CompareEngine::default();
         let result = engine.compare(doc, doc, &blocks, &blocks);
@@ -457,6 +1628,317 @@ mod tests {
         assert_eq!(flat[2].structural_path, "1.2");
     }
 
+    #[test]
+    fn deltas_carry_their_top_level_section() {
+        let doc = Uuid::new_v4();
+        let section = |path: &str, children: &[(&str, &str)]| {
+            let mut parent = make_block(doc, path, &format!("section {path} heading"), 0);
+            for (i, (child_path, text)) in children.iter().enumerate() {
+                let mut child = make_block(doc, child_path, text, i as i32);
+                child.parent_id = Some(parent.id);
+                parent.children.push(child);
+            }
+            parent
+        };
+        let left = vec![
+            section("1.", &[("1.1", "the borrower shall repay the loan")]),
+            section("2.", &[("2.1", "interest accrues daily"), ("2.2", "notices in writing")]),
+        ];
+        let right = vec![
+            section("1.", &[("1.1", "the borrower shall repay the loan")]),
+            section("2.", &[("2.1", "interest accrues daily")]),
+        ];
+
+        let result = CompareEngine::default().compare(doc, doc, &left, &right);
+        let deleted = result.deltas.iter().find(|d| d.kind == DeltaKind::Deleted).unwrap();
+        assert_eq!(deleted.section.as_deref(), Some("2."));
+        let sections: Vec<_> = result.deltas.iter().map(|d| d.section.as_deref().unwrap()).collect();
+        assert_eq!(sections, ["1.", "1.", "2.", "2.", "2."]);
+    }
+
+    #[test]
+    fn streaming_emits_every_delta_in_order() {
+        let doc = Uuid::new_v4();
+        let left: Vec<Block> = (0..7)
+            .map(|i| make_block(doc, &format!("1.{i}"), &format!("clause {i} of the agreement"), i))
+            .collect();
+        let mut right = left.clone();
+        right[2] = make_block(doc, "1.2", "clause 2 of the amended agreement", 2);
+        right.remove(5);
+
+        let engine = CompareEngine::default();
+        let mut batches: Vec<Vec<Uuid>> = Vec::new();
+        let result = engine.compare_streaming(doc, doc, &left, &right, 3, |batch| {
+            batches.push(batch.iter().map(|d| d.id).collect());
+        });
+
+        assert!(batches.iter().all(|b| !b.is_empty() && b.len() <= 3));
+        let streamed: Vec<Uuid> = batches.into_iter().flatten().collect();
+        let ids: Vec<Uuid> = result.deltas.iter().map(|d| d.id).collect();
+        assert_eq!(streamed, ids);
+
+        let whole = engine.compare(doc, doc, &left, &right);
+        assert_eq!(
+            serde_json::to_value(&result.stats).unwrap(),
+            serde_json::to_value(&whole.stats).unwrap()
+        );
+        let kinds = |r: &CompareResult| r.deltas.iter().map(|d| d.kind.clone()).collect::<Vec<_>>();
+        assert_eq!(kinds(&result), kinds(&whole));
+    }
+
+    #[test]
+    fn identical_blocks_are_reported_as_unchanged() {
+        let doc = Uuid::new_v4();
+        let blocks = vec![make_block(doc, "1.1", "the borrower shall repay", 0)];
+        let result = CompareEngine::default().compare(doc, doc, &blocks, &blocks);
+        assert_eq!(result.deltas.len(), 1);
+        assert_eq!(result.deltas[0].kind, DeltaKind::Unchanged);
+        assert!(result.deltas[0].token_diffs.is_empty());
+    }
+
+    fn output_mode_fixture() -> (Uuid, Vec<Block>, Vec<Block>) {
+        let doc = Uuid::new_v4();
+        let left = vec![
+            make_block(doc, "1.1", "the borrower shall repay", 0),
+            make_block(doc, "1.2", "the lender may assign its rights promptly", 1),
+        ];
+        let mut right = left.clone();
+        right[1] = make_block(doc, "1.2", "the lender may assign its rights immediately", 1);
+        (doc, left, right)
+    }
+
+    #[test]
+    fn changes_only_drops_unchanged_deltas_and_equal_tokens() {
+        let (doc, left, right) = output_mode_fixture();
+        let engine = CompareEngine::new(CompareConfig {
+            output_mode: CompareOutputMode::ChangesOnly,
+            ..Default::default()
+        });
+
+        let mut streamed = 0;
+        let result = engine.compare_streaming(doc, doc, &left, &right, 1, |batch| {
+            streamed += batch.len();
+        });
+        assert_eq!(streamed, 1);
+        assert_eq!(result.deltas.len(), 1);
+        assert_eq!(result.stats.unchanged, 1);
+        assert_eq!(result.stats.modified, 1);
+
+        let diffs = &result.deltas[0].token_diffs;
+        let equal = diffs.iter().find(|d| d.kind == DiffKind::Equal).unwrap();
+        assert!(equal.left_tokens.is_empty() && equal.right_tokens.is_empty());
+        let changed = diffs.iter().find(|d| d.kind != DiffKind::Equal).unwrap();
+        assert_eq!(changed.right_tokens, vec!["immediately".to_string()]);
+    }
+
+    #[test]
+    fn one_engine_serves_many_threads() {
+        let (doc, left, right) = output_mode_fixture();
+        let engine = CompareEngine::default();
+        let expected = engine.compare(doc, doc, &left, &right);
+        let kinds = |r: &CompareResult| r.deltas.iter().map(|d| d.kind.clone()).collect::<Vec<_>>();
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let result = engine.compare(doc, doc, &left, &right);
+                    assert_eq!(kinds(&result), kinds(&expected));
+                    assert_eq!(
+                        serde_json::to_value(&result.stats).unwrap(),
+                        serde_json::to_value(&expected.stats).unwrap()
+                    );
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn progress_is_reported_after_every_batch() {
+        let (doc, left, right) = output_mode_fixture();
+        let engine = CompareEngine::new(CompareConfig {
+            output_mode: CompareOutputMode::StatsOnly,
+            ..Default::default()
+        });
+        let mut reports = Vec::new();
+        engine.compare_with_progress(doc, doc, &left, &right, 1, |_| {}, |p| reports.push(*p));
+        let processed: Vec<_> = reports
+            .iter()
+            .filter(|p| p.phase == ComparePhase::Diff)
+            .map(|p| p.blocks_processed)
+            .collect();
+        assert_eq!(processed, [0, 1, 2]);
+        let batch = reports.iter().find(|p| p.blocks_processed == 1).unwrap();
+        assert_eq!(batch.percent(), 50.0);
+        let last = reports.last().unwrap();
+        assert_eq!((last.phase, last.percent()), (ComparePhase::Stats, 100.0));
+
+        let mut reports = Vec::new();
+        engine.compare_with_progress(doc, doc, &[], &[], 1, |_| {}, |p| reports.push(*p));
+        assert_eq!(reports.last().unwrap().percent(), 100.0);
+    }
+
+    #[test]
+    fn phases_are_reported_in_order() {
+        let (doc, left, right) = output_mode_fixture();
+        let engine = CompareEngine::new(CompareConfig {
+            split_threshold: None,
+            ..Default::default()
+        });
+        let mut phases = Vec::new();
+        engine.compare_with_progress(doc, doc, &left, &right, 64, |_| {}, |p| {
+            if phases.last() != Some(&p.phase) {
+                phases.push(p.phase);
+            }
+            if p.phase < ComparePhase::Diff {
+                assert_eq!(p.percent(), 0.0);
+            }
+        });
+        assert_eq!(
+            phases,
+            [
+                ComparePhase::Flatten,
+                ComparePhase::AlignTables,
+                ComparePhase::AlignPaths,
+                ComparePhase::AlignAnchors,
+                ComparePhase::AlignSimilarity,
+                ComparePhase::AlignSequence,
+                ComparePhase::Diff,
+                ComparePhase::Stats,
+            ]
+        );
+    }
+
+    #[test]
+    fn cancelled_compare_stops_after_the_current_batch() {
+        let (doc, left, right) = output_mode_fixture();
+        let engine = CompareEngine::default();
+        let cancel = CancelToken::new();
+        let mut streamed = 0;
+        let result = engine.compare_cancellable(
+            doc,
+            doc,
+            &left,
+            &right,
+            1,
+            |batch| streamed += batch.len(),
+            |p| {
+                if p.blocks_processed > 0 {
+                    cancel.cancel();
+                }
+            },
+            &cancel,
+        );
+        assert!(matches!(result, Err(RtError::Cancelled)));
+        assert_eq!(streamed, 1);
+
+        let result =
+            engine.compare_cancellable(doc, doc, &left, &right, 1, |_| {}, |_| {}, &cancel);
+        assert!(matches!(result, Err(RtError::Cancelled)), "checked before aligning");
+    }
+
+    #[test]
+    fn stats_only_returns_no_deltas() {
+        let (doc, left, right) = output_mode_fixture();
+        let engine = CompareEngine::new(CompareConfig {
+            output_mode: CompareOutputMode::StatsOnly,
+            ..Default::default()
+        });
+        let mut batches = 0;
+        let result = engine.compare_streaming(doc, doc, &left, &right, 1, |_| batches += 1);
+        assert_eq!(batches, 0);
+        assert!(result.deltas.is_empty());
+        assert_eq!(result.stats.unchanged, 1);
+        assert_eq!(result.stats.modified, 1);
+    }
+
+    #[test]
+    fn omit_unchanged_keeps_full_token_diffs_of_the_rest() {
+        let (doc, left, right) = output_mode_fixture();
+        let config = CompareConfig::from_json(r#"{"omit_unchanged":true}"#).unwrap();
+        assert_eq!(config.output_mode, CompareOutputMode::Full);
+        let result = CompareEngine::new(config).compare(doc, doc, &left, &right);
+
+        assert_eq!(result.deltas.len(), 1);
+        assert_eq!(result.stats.unchanged, 1);
+        let diffs = &result.deltas[0].token_diffs;
+        let equal = diffs.iter().find(|d| d.kind == DiffKind::Equal).unwrap();
+        assert!(!equal.left_tokens.is_empty());
+    }
+
+    #[test]
+    fn worker_threads_do_not_change_the_result() {
+        let (doc, left, right) = output_mode_fixture();
+        let compare = |worker_threads| {
+            let config = CompareConfig {
+                worker_threads,
+                deterministic: true,
+                ..Default::default()
+            };
+            let result = CompareEngine::new(config).compare(doc, doc, &left, &right);
+            serde_json::to_value(&result.deltas).unwrap()
+        };
+        assert_eq!(compare(1), compare(3));
+    }
+
+    #[test]
+    fn output_mode_parses_from_options() {
+        let config = CompareConfig::from_json(r#"{"output_mode":"changes_only"}"#).unwrap();
+        assert_eq!(config.output_mode, CompareOutputMode::ChangesOnly);
+        assert!(CompareConfig::from_json(r#"{"output_mode":"terse"}"#).is_err());
+    }
+
+    #[test]
+    fn similarity_metric_parses_from_options() {
+        let config = CompareConfig::from_json(r#"{"similarity_metric":"tfidf_cosine"}"#).unwrap();
+        assert_eq!(config.similarity_metric, SimilarityKind::TfidfCosine);
+        assert_eq!(CompareConfig::default().similarity_metric, SimilarityKind::Jaccard);
+        assert!(CompareConfig::from_json(r#"{"similarity_metric":"dice"}"#).is_err());
+    }
+
+    #[test]
+    fn stopwords_option_selects_the_list() {
+        let engine = CompareEngine::default();
+        assert_eq!(engine.thresholds().stopwords, None);
+
+        let config = CompareConfig::from_json(r#"{"ignore_stopwords": true}"#).unwrap();
+        let thresholds = CompareEngine::new(config).thresholds();
+        assert_eq!(thresholds.stopwords, Some(Stopwords::legal()));
+
+        let json = r#"{"ignore_stopwords": true, "stopwords": ["Notwithstanding"]}"#;
+        let config = CompareConfig::from_json(json).unwrap();
+        let thresholds = CompareEngine::new(config).thresholds();
+        assert_eq!(thresholds.stopwords, Some(Stopwords::new(["notwithstanding"])));
+    }
+
+    #[test]
+    fn normalization_rules_hide_template_differences() {
+        let doc = Uuid::new_v4();
+        let left = vec![
+            make_block(doc, "1.1", "Fees of $1,000 are due by March 3, 2025.", 0),
+            make_block(doc, "1.2", "Interest accrues from January 1, 2025.", 1),
+        ];
+        let right = vec![
+            make_block(doc, "1.1", "Fees of $25,500 are due by 1 June 2026.", 0),
+            make_block(doc, "1.2", "Interest accrues daily from 1 June 2026.", 1),
+        ];
+        let json = r#"{"normalization_rules": [
+            {"type": "token_kind", "kind": "date_ref", "replacement": "<date>"},
+            {"type": "regex", "pattern": "\\$[0-9][0-9,.]*", "replacement": "<amount>"}
+        ]}"#;
+        let engine = CompareEngine::new(CompareConfig::from_json(json).unwrap());
+        let result = engine.compare(doc, doc, &left, &right);
+        assert_eq!((result.stats.unchanged, result.stats.modified), (1, 1));
+
+        let modified = &result.deltas[1];
+        let changed: Vec<_> =
+            modified.token_diffs.iter().filter(|d| d.kind != DiffKind::Equal).collect();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].right_tokens, ["daily"]);
+
+        let bad = r#"{"normalization_rules":
+            [{"type": "regex", "pattern": "(", "replacement": ""}]}"#;
+        assert!(CompareConfig::from_json(bad).is_err());
+    }
+
     #[test]
     fn compare_config_default_thresholds() {
         let cfg = CompareConfig::default();
@@ -464,4 +1946,31 @@ mod tests {
         assert_eq!(cfg.move_distance_max, 50);
         assert!(cfg.worker_threads >= 1);
     }
+
+    #[test]
+    fn compare_config_from_json_overrides_and_defaults() {
+        let cfg = CompareConfig::from_json(
+            r#"{"similarity_threshold": 0.9, "move_distance_max": 5, "worker_threads": 2}"#,
+        )
+        .unwrap();
+        assert!((cfg.similarity_threshold - 0.9).abs() < 1e-9);
+        assert_eq!(cfg.move_distance_max, 5);
+        assert_eq!(cfg.worker_threads, 2);
+        assert_eq!(cfg.max_block_tokens, 20_000);
+
+        let empty = CompareConfig::from_json("{}").unwrap();
+        assert_eq!(empty.move_distance_max, 50);
+        assert!(CompareConfig::from_json("").is_ok());
+    }
+
+    #[test]
+    fn compare_config_from_json_rejects_bad_input() {
+        assert!(CompareConfig::from_json(r#"{"ignore_cas": true}"#).is_err());
+        assert!(CompareConfig::from_json(r#"{"similarity_threshold": 1.5}"#).is_err());
+        assert!(CompareConfig::from_json(r#"{"move_threshold": -0.1}"#).is_err());
+        assert!(CompareConfig::from_json(r#"{"worker_threads": 0}"#).is_err());
+        let too_many = format!(r#"{{"worker_threads": {}}}"#, pool::max_worker_threads() + 1);
+        assert!(matches!(CompareConfig::from_json(&too_many), Err(RtError::InvalidInput(_))));
+        assert!(CompareConfig::from_json("not json").is_err());
+    }
 }
@@ -0,0 +1,379 @@
+//! Cross-reference extraction and broken-reference detection.
+//!
+//! [`extract_cross_references`] finds section references in block text —
+//! `Section 5.2(b)`, `Clauses 4.1 and 4.2`, `§ 7` — and normalizes each to
+//! the structural path it names. [`broken_references`] then checks the
+//! references of a new document version against an alignment with the old
+//! one: a reference whose target clause was deleted, or renumbered while the
+//! reference kept the old number, is reported as a [`BrokenReference`].
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::{Block, CrossReference};
+
+use crate::align::BlockAlignment;
+
+/// Words (lowercase) introducing a reference; a trailing `s` allows a list.
+const KEYWORDS: &[&str] = &["section", "clause", "article", "paragraph"];
+
+/// Words separating the paths of a listed reference (`Sections 4.1 and 4.2`).
+const LIST_SEPARATORS: &[&str] = &[",", "and", "or"];
+
+/// Longest sub-item label in parentheses, e.g. the `iii` of `5.2(a)(iii)`.
+const MAX_LABEL_LEN: usize = 4;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Why a reference is broken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokenReferenceKind {
+    /// The clause the reference pointed to was deleted.
+    TargetDeleted,
+    /// The clause the reference pointed to now has a different number, and
+    /// the reference still uses the old one.
+    TargetRenumbered,
+}
+
+/// A reference in the right document whose target is gone or renumbered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrokenReference {
+    pub kind: BrokenReferenceKind,
+    /// Right block holding the reference.
+    pub block_id: Uuid,
+    /// The reference as written.
+    pub text: String,
+    /// The structural path the reference names.
+    pub target: String,
+    /// Left block the reference pointed to.
+    pub left_target_id: Uuid,
+    /// For `TargetRenumbered`: the path the target has in the right
+    /// document; `None` otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renumbered_to: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Extract the cross-references in `blocks` (and their children), in
+/// document order.
+pub fn extract_cross_references(blocks: &[Block]) -> Vec<CrossReference> {
+    let mut references = Vec::new();
+    let mut stack: Vec<&Block> = blocks.iter().rev().collect();
+    while let Some(block) = stack.pop() {
+        references.extend(references_in(&block.canonical_text).into_iter().map(
+            |(offset, text, target)| CrossReference {
+                block_id: block.id,
+                text,
+                target,
+                offset,
+            },
+        ));
+        stack.extend(block.children.iter().rev());
+    }
+    references
+}
+
+/// Report the references in `right_flat` broken by the changes from
+/// `left_flat`, given their `alignments`.
+///
+/// A reference is checked against the left document when it was carried
+/// over from the left version of its block, or when it names a path the
+/// right document does not have. Its target there is the block at that path
+/// (or, for `5.2(b)` with no block of its own, the block at `5.2`). The
+/// reference is broken when that block was deleted, or when it was aligned
+/// to a right block at a different path.
+pub fn broken_references(
+    alignments: &[BlockAlignment],
+    left_flat: &[Block],
+    right_flat: &[Block],
+) -> Vec<BrokenReference> {
+    let mut left_to_right: Vec<Option<usize>> = vec![None; left_flat.len()];
+    let mut right_to_left: Vec<Option<usize>> = vec![None; right_flat.len()];
+    for alignment in alignments {
+        match alignment {
+            BlockAlignment::Matched { left, right, .. }
+            | BlockAlignment::Moved { left, right, .. } => {
+                left_to_right[*left] = Some(*right);
+                right_to_left[*right] = Some(*left);
+            }
+            BlockAlignment::Split { left, right, .. } => {
+                left_to_right[*left] = right.first().copied();
+                for &r in right {
+                    right_to_left[r] = Some(*left);
+                }
+            }
+            BlockAlignment::Merged { left, right, .. } => {
+                for &l in left {
+                    left_to_right[l] = Some(*right);
+                }
+                right_to_left[*right] = left.first().copied();
+            }
+            BlockAlignment::InsertedRight { .. } | BlockAlignment::DeletedLeft { .. } => {}
+        }
+    }
+    let left_paths = path_index(left_flat);
+    let right_paths = path_index(right_flat);
+
+    let mut broken = Vec::new();
+    for (r, block) in right_flat.iter().enumerate() {
+        let references = references_in(&block.canonical_text);
+        if references.is_empty() {
+            continue;
+        }
+        let carried: HashSet<String> = right_to_left[r]
+            .map(|l| {
+                references_in(&left_flat[l].canonical_text)
+                    .into_iter()
+                    .map(|(_, _, target)| target)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        for (_, text, target) in references {
+            if !carried.contains(&target) && resolve(&right_paths, &target).is_some() {
+                continue;
+            }
+            let Some((l, suffix)) = resolve(&left_paths, &target) else {
+                continue;
+            };
+            let (kind, renumbered_to) = match left_to_right[l] {
+                None => (BrokenReferenceKind::TargetDeleted, None),
+                Some(rt) => {
+                    let path = format!("{}{}", trim_path(&right_flat[rt].structural_path), suffix);
+                    if normalize_path(&path) == target {
+                        continue;
+                    }
+                    (BrokenReferenceKind::TargetRenumbered, Some(path))
+                }
+            };
+            broken.push(BrokenReference {
+                kind,
+                block_id: block.id,
+                text,
+                target,
+                left_target_id: left_flat[l].id,
+                renumbered_to,
+            });
+        }
+    }
+    broken
+}
+
+/// Normalize a structural path for matching: lowercased, without
+/// whitespace or a trailing `.`.
+pub fn normalize_path(path: &str) -> String {
+    let path: String = path.chars().filter(|c| !c.is_whitespace()).collect();
+    trim_path(&path).to_lowercase()
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// The references in `text`: (byte offset, text as written, normalized
+/// target).
+fn references_in(text: &str) -> Vec<(usize, String, String)> {
+    let lower = text.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let mut references = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let word_start = i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+        let Some((after, list)) = word_start.then(|| keyword_at(&lower, i)).flatten() else {
+            i += lower[i..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        let Some(mut end) = path_at(bytes, skip_spaces(bytes, after)) else {
+            i = after;
+            continue;
+        };
+        let target = normalize_path(&text[skip_spaces(bytes, after)..end]);
+        references.push((i, text[i..end].to_string(), target));
+        while let Some(next) = list.then(|| list_item_at(&lower, end)).flatten() {
+            let Some(next_end) = path_at(bytes, next) else {
+                break;
+            };
+            let written = &text[next..next_end];
+            references.push((next, written.to_string(), normalize_path(written)));
+            end = next_end;
+        }
+        i = end;
+    }
+    references
+}
+
+/// When a reference keyword starts at `i`: the offset after it and whether
+/// it may introduce a list.
+fn keyword_at(lower: &str, i: usize) -> Option<(usize, bool)> {
+    let rest = &lower[i..];
+    if rest.starts_with('§') {
+        let after = i + '§'.len_utf8();
+        let list = lower[after..].starts_with('§');
+        return Some((if list { after + '§'.len_utf8() } else { after }, list));
+    }
+    let keyword = KEYWORDS.iter().find(|k| rest.starts_with(*k))?;
+    let mut after = i + keyword.len();
+    let list = lower.as_bytes().get(after) == Some(&b's');
+    if list {
+        after += 1;
+    }
+    match lower.as_bytes().get(after) {
+        Some(b) if b.is_ascii_alphanumeric() => None,
+        _ => Some((after, list)),
+    }
+}
+
+/// The end of a structural path (`5`, `5.2`, `5.2(b)(iii)`) starting at
+/// `start`; `None` when none starts there.
+fn path_at(bytes: &[u8], start: usize) -> Option<usize> {
+    let digits = |from: usize| bytes[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut end = start + digits(start);
+    if end == start {
+        return None;
+    }
+    loop {
+        match bytes.get(end) {
+            Some(b'.') if digits(end + 1) > 0 => end += 1 + digits(end + 1),
+            Some(b'(') => {
+                let label = bytes[end + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_alphanumeric())
+                    .count();
+                let closed = bytes.get(end + 1 + label) == Some(&b')');
+                if label == 0 || label > MAX_LABEL_LEN || !closed {
+                    break;
+                }
+                end += label + 2;
+            }
+            _ => break,
+        }
+    }
+    Some(end)
+}
+
+/// Where the next path of a list starts after `end`, if a separator follows.
+fn list_item_at(lower: &str, end: usize) -> Option<usize> {
+    let bytes = lower.as_bytes();
+    let mut at = skip_spaces(bytes, end);
+    let mut separated = false;
+    while let Some(sep) = LIST_SEPARATORS.iter().find(|s| lower[at..].starts_with(*s)) {
+        let after = at + sep.len();
+        if sep.bytes().all(|b| b.is_ascii_alphabetic())
+            && bytes.get(after).is_some_and(|b| b.is_ascii_alphanumeric())
+        {
+            break;
+        }
+        separated = true;
+        at = skip_spaces(bytes, after);
+    }
+    (separated && bytes.get(at).is_some_and(u8::is_ascii_digit)).then_some(at)
+}
+
+fn skip_spaces(bytes: &[u8], from: usize) -> usize {
+    from + bytes[from..].iter().take_while(|b| **b == b' ').count()
+}
+
+fn trim_path(path: &str) -> &str {
+    path.trim().trim_end_matches('.')
+}
+
+/// Normalized structural path → index of the first block with it.
+fn path_index(flat: &[Block]) -> HashMap<String, usize> {
+    let mut index = HashMap::new();
+    for (i, block) in flat.iter().enumerate() {
+        index.entry(normalize_path(&block.structural_path)).or_insert(i);
+    }
+    index
+}
+
+/// The block `target` names in `paths`, trying `target` itself and then
+/// with trailing `(…)` labels dropped; with the dropped labels.
+fn resolve(paths: &HashMap<String, usize>, target: &str) -> Option<(usize, String)> {
+    let mut path = target;
+    loop {
+        if let Some(&i) = paths.get(path) {
+            return Some((i, target[path.len()..].to_string()));
+        }
+        path = path.strip_suffix(')').and_then(|p| p.rfind('(').map(|open| &p[..open]))?;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::{align_blocks_with, AlignThresholds};
+    use rt_model::BlockType;
+
+    fn block(path: &str, text: &str) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, Uuid::new_v4(), 0)
+    }
+
+    #[test]
+    fn references_are_extracted_and_normalized() {
+        let blocks = vec![
+            block("1.", "As set forth in Section 5.2(b), subject to Clauses 4.1, 4.2 and 7."),
+            block("2.", "Per § 3.1 and the sections below; see paragraph 9(xii) and Article 2."),
+        ];
+        let refs = extract_cross_references(&blocks);
+        let found: Vec<_> = refs.iter().map(|r| (r.text.as_str(), r.target.as_str())).collect();
+        assert_eq!(
+            found,
+            [
+                ("Section 5.2(b)", "5.2(b)"),
+                ("Clauses 4.1", "4.1"),
+                ("4.2", "4.2"),
+                ("7", "7"),
+                ("§ 3.1", "3.1"),
+                ("paragraph 9(xii)", "9(xii)"),
+                ("Article 2", "2"),
+            ]
+        );
+        assert_eq!(refs[0].offset, 16);
+        assert_eq!(refs[4].block_id, blocks[1].id);
+    }
+
+    #[test]
+    fn deleted_and_renumbered_targets_are_reported() {
+        let left = vec![
+            block("1.", "Definitions apply throughout."),
+            block("2.", "Payment is due as provided in Section 3.1(a)."),
+            block("3.1", "The borrower shall repay the principal in monthly instalments."),
+            block("4.", "Notices under Section 5 must be in writing and signed."),
+            block("5.", "Any notice is deemed received two days after posting."),
+        ];
+        let right = vec![
+            block("1.", "Definitions apply throughout."),
+            block("2.", "Payment is due as provided in Section 3.1(a)."),
+            block("3.1", "Interest accrues daily at the default rate on overdue sums."),
+            block("3.2", "The borrower shall repay the principal in monthly instalments."),
+            block("4.", "Notices under Section 5 must be in writing and signed."),
+        ];
+        let thresholds = AlignThresholds {
+            ignore_renumbering: true,
+            ..AlignThresholds::default()
+        };
+        let alignments = align_blocks_with(&left, &right, &thresholds);
+        let broken = broken_references(&alignments, &left, &right);
+
+        assert_eq!(broken.len(), 2, "{broken:?}");
+        assert_eq!(broken[0].kind, BrokenReferenceKind::TargetRenumbered);
+        assert_eq!(broken[0].block_id, right[1].id);
+        assert_eq!(broken[0].left_target_id, left[2].id);
+        assert_eq!(broken[0].renumbered_to.as_deref(), Some("3.2(a)"));
+        assert_eq!(broken[1].kind, BrokenReferenceKind::TargetDeleted);
+        assert_eq!(broken[1].text, "Section 5");
+        assert_eq!(broken[1].left_target_id, left[4].id);
+    }
+}
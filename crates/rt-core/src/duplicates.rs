@@ -0,0 +1,171 @@
+//! Duplicate clause detection.
+//!
+//! Blocks with the same `clause_hash` carry the same normalized text, so a
+//! clause that appears in several places — boilerplate repeated within a
+//! contract, or copied from a clause library into many contracts — shares
+//! one hash everywhere. [`find_duplicate_clauses`] groups the blocks of a
+//! document with every other stored block of the same hash.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use rt_model::error::Result;
+use rt_store::db::BlockStore;
+
+/// One place a duplicated clause appears.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClauseOccurrence {
+    pub document_id: Uuid,
+    pub document_name: String,
+    pub block_id: Uuid,
+    pub structural_path: String,
+}
+
+/// Blocks sharing a `clause_hash`, at least one of them in the queried
+/// document.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCluster {
+    pub clause_hash: String,
+    /// Display text of the first occurrence in the queried document.
+    pub display_text: String,
+    /// Number of distinct documents the clause appears in.
+    pub document_count: usize,
+    /// Every occurrence: the queried document's first, in document order,
+    /// then the other documents' ordered by ingestion time.
+    pub occurrences: Vec<ClauseOccurrence>,
+}
+
+/// The clauses of `doc_id` that appear more than once in the store, within
+/// `doc_id` or across documents, in the order of their first occurrence in
+/// `doc_id`. Blocks without text (e.g. empty paragraphs) are ignored.
+///
+/// Fails with [`RtError::NotFound`](rt_model::error::RtError::NotFound)
+/// when `doc_id` is not stored.
+pub fn find_duplicate_clauses(
+    store: &dyn BlockStore,
+    doc_id: &Uuid,
+) -> Result<Vec<DuplicateCluster>> {
+    let document = store.get_document(doc_id)?;
+    let mut blocks = store.get_blocks_by_document(doc_id)?;
+    blocks.sort_by_key(|b| b.position_index);
+
+    let mut documents = HashMap::from([(document.id, document)]);
+    let mut seen = HashSet::new();
+    let mut clusters = Vec::new();
+    for block in blocks {
+        if block.canonical_text.trim().is_empty() || !seen.insert(block.clause_hash.clone()) {
+            continue;
+        }
+        let mut matches = store.get_blocks_by_clause_hash(&block.clause_hash)?;
+        if matches.len() < 2 {
+            continue;
+        }
+        for other in &matches {
+            if let Entry::Vacant(slot) = documents.entry(other.document_id) {
+                slot.insert(store.get_document(&other.document_id)?);
+            }
+        }
+        matches.sort_by(|a, b| {
+            let (da, db) = (&documents[&a.document_id], &documents[&b.document_id]);
+            let key = |d: &rt_model::Document| (d.id != *doc_id, d.ingested_at, d.id);
+            (key(da), a.position_index).cmp(&(key(db), b.position_index))
+        });
+
+        let document_count = matches.iter().map(|b| b.document_id).collect::<HashSet<_>>().len();
+        let occurrences = matches
+            .into_iter()
+            .map(|b| ClauseOccurrence {
+                document_id: b.document_id,
+                document_name: documents[&b.document_id].name.clone(),
+                block_id: b.id,
+                structural_path: b.structural_path,
+            })
+            .collect();
+        clusters.push(DuplicateCluster {
+            clause_hash: block.clause_hash,
+            display_text: block.display_text,
+            document_count,
+            occurrences,
+        });
+    }
+    Ok(clusters)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rt_model::error::RtError;
+    use rt_model::{Block, BlockType, Document, DocumentType};
+    use rt_store::db::{create_memory_pool, SqliteBlockStore};
+
+    fn document(name: &str, days_ago: i64) -> Document {
+        Document {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: "1.0.0".into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now() - chrono::Duration::days(days_ago),
+            metadata: None,
+        }
+    }
+
+    fn clause(doc: &Document, path: &str, text: &str, idx: i32) -> Block {
+        let mut block = Block::new(BlockType::Clause, path, text, text, None, doc.id, idx);
+        block.clause_hash = rt_model::compute_clause_hash(text);
+        block
+    }
+
+    #[test]
+    fn clauses_are_grouped_within_and_across_documents() {
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let (nda, msa, lease) = (document("nda", 0), document("msa", 2), document("lease", 1));
+        let law = "This Agreement is governed by the laws of England.";
+        for (doc, blocks) in [
+            (
+                &nda,
+                vec![
+                    clause(&nda, "1.", "Confidential Information means all information.", 0),
+                    clause(&nda, "2.", law, 1),
+                    clause(&nda, "3.", "", 2),
+                    clause(&nda, "4.", "", 3),
+                    clause(&nda, "5.", law, 4),
+                ],
+            ),
+            (&msa, vec![clause(&msa, "9.", law, 0)]),
+            (&lease, vec![clause(&lease, "12.", law, 0)]),
+        ] {
+            store.insert_document(doc).unwrap();
+            store.insert_blocks(&blocks).unwrap();
+        }
+
+        let clusters = find_duplicate_clauses(&store, &nda.id).unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].display_text, law);
+        assert_eq!(clusters[0].document_count, 3);
+        let places: Vec<_> = clusters[0]
+            .occurrences
+            .iter()
+            .map(|o| (o.document_name.as_str(), o.structural_path.as_str()))
+            .collect();
+        assert_eq!(places, [("nda", "2."), ("nda", "5."), ("msa", "9."), ("lease", "12.")]);
+    }
+
+    #[test]
+    fn unknown_document_is_not_found() {
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let result = find_duplicate_clauses(&store, &Uuid::new_v4());
+        assert!(matches!(result, Err(RtError::NotFound(_))));
+    }
+}
@@ -0,0 +1,168 @@
+//! Clause history across document versions.
+//!
+//! A block's `anchor_signature` is stable across versions of a document, so
+//! every stored block sharing an anchor is the same clause as it appeared in
+//! some document. [`ClauseHistory::load`] collects those blocks,
+//! orders them by when their document was ingested, and diffs each version
+//! against the one before it.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use rt_compare::diff::{token_diff, DiffKind, TokenDiff};
+use rt_compare::worker::ensure_tokens;
+use rt_model::error::Result;
+use rt_model::{DocumentType, Token};
+use rt_store::db::BlockStore;
+
+/// The clause as it appears in one document.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClauseVersion {
+    pub document_id: Uuid,
+    pub document_name: String,
+    pub doc_type: DocumentType,
+    pub ingested_at: DateTime<Utc>,
+    pub block_id: Uuid,
+    pub structural_path: String,
+    pub clause_hash: String,
+    pub display_text: String,
+    /// Whether the text differs from the previous version; `false` for the
+    /// first version.
+    pub changed: bool,
+    /// Token diff from the previous version to this one; empty for the first
+    /// version.
+    pub diff: Vec<TokenDiff>,
+}
+
+/// Every stored version of the clause with a given anchor, oldest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClauseHistory {
+    pub anchor_signature: String,
+    pub versions: Vec<ClauseVersion>,
+}
+
+impl ClauseHistory {
+    /// Load the history of `anchor_signature` from `store`. Versions are
+    /// ordered by document ingestion time, then by position within the
+    /// document. An anchor with no stored blocks yields an empty history.
+    pub fn load(store: &dyn BlockStore, anchor_signature: &str) -> Result<Self> {
+        let mut blocks = store.get_blocks_by_anchor(anchor_signature)?;
+
+        let mut documents = HashMap::new();
+        for block in &blocks {
+            if let Entry::Vacant(slot) = documents.entry(block.document_id) {
+                slot.insert(store.get_document(&block.document_id)?);
+            }
+        }
+        blocks.sort_by(|a, b| {
+            let (da, db) = (&documents[&a.document_id], &documents[&b.document_id]);
+            (da.ingested_at, da.id, a.position_index).cmp(&(db.ingested_at, db.id, b.position_index))
+        });
+
+        let mut versions = Vec::with_capacity(blocks.len());
+        let mut previous: Option<Vec<Token>> = None;
+        for block in blocks {
+            let tokens = ensure_tokens(&block);
+            let (changed, diff) = match &previous {
+                Some(prev) => {
+                    let diff = token_diff(prev, &tokens);
+                    (diff.iter().any(|d| d.kind != DiffKind::Equal), diff)
+                }
+                None => (false, Vec::new()),
+            };
+            let document = &documents[&block.document_id];
+            versions.push(ClauseVersion {
+                document_id: document.id,
+                document_name: document.name.clone(),
+                doc_type: document.doc_type.clone(),
+                ingested_at: document.ingested_at,
+                block_id: block.id,
+                structural_path: block.structural_path,
+                clause_hash: block.clause_hash,
+                display_text: block.display_text,
+                changed,
+                diff,
+            });
+            previous = Some(tokens);
+        }
+
+        Ok(Self { anchor_signature: anchor_signature.to_string(), versions })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::{Block, BlockType, Document};
+    use rt_store::db::{create_memory_pool, SqliteBlockStore};
+
+    fn document(name: &str, days_ago: i64) -> Document {
+        Document {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: "1.0.0".into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now() - chrono::Duration::days(days_ago),
+            metadata: None,
+        }
+    }
+
+    fn clause(doc: &Document, text: &str) -> Block {
+        let mut block = Block::new(BlockType::Clause, "1.", text, text, None, doc.id, 0);
+        block.anchor_signature = "clause|1.|indemnity".into();
+        block.clause_hash = text.into();
+        block
+    }
+
+    #[test]
+    fn versions_are_ordered_by_ingestion_with_step_diffs() {
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        // Inserted out of order to check the sort.
+        let v2 = document("v2", 1);
+        let v1 = document("v1", 3);
+        let v3 = document("v3", 0);
+        for (doc, text) in [
+            (&v2, "supplier shall indemnify the customer fully"),
+            (&v1, "supplier shall indemnify the customer"),
+            (&v3, "supplier shall indemnify the customer fully"),
+        ] {
+            store.insert_document(doc).unwrap();
+            store.insert_block(&clause(doc, text)).unwrap();
+        }
+
+        let history = ClauseHistory::load(&store, "clause|1.|indemnity").unwrap();
+        let names: Vec<_> = history.versions.iter().map(|v| v.document_name.as_str()).collect();
+        assert_eq!(names, vec!["v1", "v2", "v3"]);
+
+        assert!(!history.versions[0].changed);
+        assert!(history.versions[0].diff.is_empty());
+        assert!(history.versions[1].changed);
+        let inserted: Vec<_> = history.versions[1]
+            .diff
+            .iter()
+            .filter(|d| d.kind == DiffKind::Inserted)
+            .flat_map(|d| d.right_tokens.clone())
+            .collect();
+        assert_eq!(inserted, vec!["fully"]);
+        assert!(!history.versions[2].changed);
+    }
+
+    #[test]
+    fn unknown_anchor_has_no_versions() {
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let history = ClauseHistory::load(&store, "nothing").unwrap();
+        assert!(history.versions.is_empty());
+    }
+}
@@ -1,11 +1,12 @@
-pub mod anchor;
-pub mod block;
-pub mod db;
-pub mod error;
-pub mod hash;
-pub mod schema;
+#[cfg(feature = "sqlite")]
+pub mod duplicates;
+#[cfg(feature = "sqlite")]
+pub mod history;
 
-pub use anchor::*;
-pub use block::*;
-pub use error::*;
-pub use hash::*;
+pub use rt_model::*;
+#[cfg(feature = "sqlite")]
+pub use rt_store::{
+    artifact, compare_sections, content, cross_references, db, defined_terms, entities,
+    fingerprint, gc, hashing, health, link_anchors, memory, overrides, presets,
+    review_activity, run_history, schema, snapshot, tenant, usage, validation, versions, vfs,
+};
@@ -0,0 +1,384 @@
+//! Registry of chunked block ingestions.
+//!
+//! `rtflow_ingest_blocks` parses its whole JSON array before writing, so a
+//! document of several hundred megabytes needs several times that in memory.
+//! A batch instead takes the blocks as NDJSON (one block object per line),
+//! in as many `rtflow_ingest_append` chunks as the host likes: each chunk is
+//! parsed and written as it arrives, and only a line split across two chunks
+//! is held back. Memory stays bounded by the chunk size.
+//!
+//! A batch owns a pooled connection with an immediate transaction open from
+//! `rtflow_ingest_begin` until `rtflow_ingest_commit`, so the document
+//! appears whole or not at all; other writers wait (up to SQLite's busy
+//! timeout) while it is open. A failed append, an abort or dropping the
+//! registry entry rolls the transaction back. So that a host that forgets a
+//! batch cannot block every writer for good, a batch left idle for
+//! [`BATCH_IDLE_TIMEOUT`] is rolled back and forgotten too.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Once, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::Serialize;
+use uuid::Uuid;
+
+use rt_core::anchor::ANCHOR_V1;
+use rt_core::block::{Block, Document, DocumentType};
+use rt_core::db::{write_blocks, write_document, ConflictPolicy, DbConn, DbPool};
+use rt_core::hash::ClauseHasher;
+use rt_core::schema::SCHEMA_VERSION;
+use rt_core::tenant::{ensure_document, TenantContext};
+
+/// How long a batch may go without an append before it is rolled back.
+pub const BATCH_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often idle batches are looked for.
+const REAP_INTERVAL: Duration = Duration::from_secs(10);
+
+struct Batch {
+    conn: DbConn,
+    tenant: TenantContext,
+    doc_id: Uuid,
+    /// Text after the last newline seen, completed by the next chunk.
+    partial: String,
+    /// Lines consumed so far, for error messages.
+    lines: usize,
+    count: usize,
+    /// When the batch was opened or last appended to.
+    last_used: Instant,
+    /// How long it may stay idle; [`BATCH_IDLE_TIMEOUT`].
+    idle_timeout: Duration,
+}
+
+impl Drop for Batch {
+    fn drop(&mut self) {
+        if !self.conn.is_autocommit() {
+            let _ = self.conn.execute_batch("ROLLBACK");
+        }
+    }
+}
+
+type Registry = Mutex<HashMap<Uuid, Arc<Mutex<Batch>>>>;
+
+static BATCHES: OnceLock<Registry> = OnceLock::new();
+
+fn batches() -> &'static Registry {
+    BATCHES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Roll back and forget every batch idle for longer than its timeout,
+/// skipping any with a call in progress. Returns how many were evicted.
+fn evict_idle() -> usize {
+    let now = Instant::now();
+    let mut registry = batches().lock().unwrap_or_else(|e| e.into_inner());
+    let before = registry.len();
+    registry.retain(|_, batch| {
+        // A call holds a clone from `find` (taken under the registry lock)
+        // until it returns, so a batch in use is never evicted under it.
+        if Arc::strong_count(batch) > 1 {
+            return true;
+        }
+        let batch = batch.lock().unwrap_or_else(|e| e.into_inner());
+        now.duration_since(batch.last_used) <= batch.idle_timeout
+    });
+    before - registry.len()
+}
+
+/// Start the thread that evicts idle batches, once per process.
+fn start_reaper() {
+    static REAPER: Once = Once::new();
+    REAPER.call_once(|| {
+        std::thread::spawn(|| loop {
+            std::thread::sleep(REAP_INTERVAL);
+            evict_idle();
+        });
+    });
+}
+
+fn find(batch_id: Uuid) -> Result<Arc<Mutex<Batch>>, String> {
+    batches()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&batch_id)
+        .cloned()
+        .ok_or_else(|| format!("unknown ingest batch {}", batch_id))
+}
+
+/// Progress of a batch, as reported by `rtflow_ingest_append`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchStatus {
+    pub batch_id: Uuid,
+    pub doc_id: Uuid,
+    /// Blocks written so far.
+    pub count: usize,
+    /// Tenant the batch writes for.
+    #[serde(skip)]
+    pub tenant: TenantContext,
+}
+
+/// Open a batch ingesting into `doc_id` for `tenant`, creating a minimal
+/// document record named `name`, under `hasher`'s contract, when `doc_id`
+/// does not exist yet.
+pub fn begin(
+    pool: &DbPool,
+    tenant: TenantContext,
+    doc_id: Uuid,
+    name: &str,
+    hasher: &ClauseHasher,
+) -> Result<BatchStatus, String> {
+    let conn = pool
+        .get()
+        .map_err(|e| format!("failed to acquire database connection: {}", e))?;
+    conn.execute_batch("BEGIN IMMEDIATE").map_err(|e| e.to_string())?;
+    let batch_id = Uuid::new_v4();
+    let batch = Batch {
+        conn,
+        tenant: tenant.clone(),
+        doc_id,
+        partial: String::new(),
+        lines: 0,
+        count: 0,
+        last_used: Instant::now(),
+        idle_timeout: BATCH_IDLE_TIMEOUT,
+    };
+
+    let doc = Document {
+        id: doc_id,
+        name: name.to_string(),
+        source_path: None,
+        doc_type: DocumentType::Original,
+        schema_version: SCHEMA_VERSION.to_string(),
+        normalization_version: "1.0.0".to_string(),
+        hash_contract_version: hasher.contract_version().to_string(),
+        anchor_version: ANCHOR_V1.to_string(),
+        ingested_at: Utc::now(),
+        metadata: None,
+    };
+    write_document(&batch.conn, &batch.tenant, &doc, ConflictPolicy::Skip)
+        .and_then(|()| ensure_document(&batch.conn, &batch.tenant, &doc_id))
+        .map_err(|e| format!("failed to create document record: {}", e))?;
+
+    batches()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(batch_id, Arc::new(Mutex::new(batch)));
+    start_reaper();
+    Ok(BatchStatus {
+        batch_id,
+        doc_id,
+        count: 0,
+        tenant,
+    })
+}
+
+/// Parse and write the complete lines of `chunk`, keeping a trailing partial
+/// line for the next chunk. Blank lines are skipped. On error the batch is
+/// rolled back and forgotten.
+pub fn append(batch_id: Uuid, chunk: &str) -> Result<BatchStatus, String> {
+    let batch = find(batch_id)?;
+    let mut guard = batch.lock().unwrap_or_else(|e| e.into_inner());
+    guard.last_used = Instant::now();
+    let outcome = write_lines(&mut guard, chunk);
+    let status = BatchStatus {
+        batch_id,
+        doc_id: guard.doc_id,
+        count: guard.count,
+        tenant: guard.tenant.clone(),
+    };
+    drop(guard);
+    match outcome {
+        Ok(()) => Ok(status),
+        Err(e) => {
+            abort(batch_id);
+            Err(e)
+        }
+    }
+}
+
+fn write_lines(batch: &mut Batch, chunk: &str) -> Result<(), String> {
+    let mut text = std::mem::take(&mut batch.partial);
+    text.push_str(chunk);
+    let complete = match text.rfind('\n') {
+        Some(end) => {
+            batch.partial = text[end + 1..].to_string();
+            &text[..end]
+        }
+        None => {
+            batch.partial = text;
+            return Ok(());
+        }
+    };
+    let mut blocks = Vec::new();
+    for line in complete.split('\n') {
+        batch.lines += 1;
+        blocks.extend(parse_line(batch, line)?);
+    }
+    write(batch, &blocks)
+}
+
+fn parse_line(batch: &Batch, line: &str) -> Result<Option<Block>, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let block: Block = serde_json::from_str(line)
+        .map_err(|e| format!("failed to parse block on line {}: {}", batch.lines, e))?;
+    if block.document_id != batch.doc_id {
+        return Err(format!(
+            "block on line {} belongs to document {}, not {}",
+            batch.lines, block.document_id, batch.doc_id
+        ));
+    }
+    Ok(Some(block))
+}
+
+fn write(batch: &mut Batch, blocks: &[Block]) -> Result<(), String> {
+    let written = write_blocks(&batch.conn, &batch.tenant, blocks, ConflictPolicy::Fail)
+        .map_err(|e| format!("failed to insert blocks: {}", e))?;
+    batch.count += written;
+    Ok(())
+}
+
+/// Write any final unterminated line, commit the batch and forget it.
+/// Returns the document and the number of blocks written.
+pub fn commit(batch_id: Uuid) -> Result<BatchStatus, String> {
+    let batch = batches()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&batch_id)
+        .ok_or_else(|| format!("unknown ingest batch {}", batch_id))?;
+    let mut batch = batch.lock().unwrap_or_else(|e| e.into_inner());
+    let last = std::mem::take(&mut batch.partial);
+    batch.lines += 1;
+    if let Some(block) = parse_line(&batch, &last)? {
+        write(&mut batch, &[block])?;
+    }
+    batch
+        .conn
+        .execute_batch("COMMIT")
+        .map_err(|e| format!("failed to commit ingest batch: {}", e))?;
+    Ok(BatchStatus {
+        batch_id,
+        doc_id: batch.doc_id,
+        count: batch.count,
+        tenant: batch.tenant.clone(),
+    })
+}
+
+/// Roll back `batch_id` and forget it. Returns `false` for an unknown batch.
+pub fn abort(batch_id: Uuid) -> bool {
+    let removed = batches().lock().unwrap_or_else(|e| e.into_inner()).remove(&batch_id);
+    removed.is_some()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_core::db::{create_memory_pool, BlockStore, SqliteBlockStore};
+    use rt_core::hash::HASH_CONTRACT_BLAKE3;
+    use rt_core::hashing::configure_hasher;
+    use rt_core::BlockType;
+
+    fn ndjson(doc_id: Uuid, from: i32, to: i32) -> String {
+        (from..to)
+            .map(|i| {
+                let text = format!("clause {i} of the agreement");
+                let block =
+                    Block::new(BlockType::Clause, format!("1.{i}"), &text, &text, None, doc_id, i);
+                serde_json::to_string(&block).unwrap() + "\n"
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chunks_split_mid_line_are_written_on_commit() {
+        let pool = create_memory_pool().unwrap();
+        let doc_id = Uuid::new_v4();
+        let text = ndjson(doc_id, 0, 5);
+        let (head, tail) = text.split_at(text.len() / 2);
+
+        let hasher = ClauseHasher::default();
+        let batch = begin(&pool, TenantContext::default(), doc_id, "big-doc", &hasher).unwrap();
+        let first = append(batch.batch_id, head).unwrap();
+        assert!(first.count < 5);
+        // Drop the final newline: the last line is completed by the commit.
+        append(batch.batch_id, tail.trim_end()).unwrap();
+        let done = commit(batch.batch_id).unwrap();
+        assert_eq!(done.count, 5);
+        assert!(!abort(batch.batch_id), "committed batches are forgotten");
+
+        let store = SqliteBlockStore::new(pool);
+        assert_eq!(store.get_document(&doc_id).unwrap().name, "big-doc");
+        assert_eq!(store.get_blocks_by_document(&doc_id).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn failed_and_aborted_batches_leave_nothing_behind() {
+        let pool = create_memory_pool().unwrap();
+        let store = SqliteBlockStore::new(pool.clone());
+        let doc_id = Uuid::new_v4();
+        let hasher = ClauseHasher::default();
+
+        let batch = begin(&pool, TenantContext::default(), doc_id, "doc", &hasher).unwrap();
+        append(batch.batch_id, &ndjson(doc_id, 0, 3)).unwrap();
+        let err = append(batch.batch_id, "{\"not\": \"a block\"}\n").unwrap_err();
+        assert!(err.contains("line 4"), "{err}");
+        assert!(append(batch.batch_id, "").unwrap_err().contains("unknown ingest batch"));
+        assert!(store.get_document(&doc_id).is_err());
+
+        let batch = begin(&pool, TenantContext::default(), doc_id, "doc", &hasher).unwrap();
+        let stranger = ndjson(Uuid::new_v4(), 0, 1);
+        assert!(append(batch.batch_id, &stranger).unwrap_err().contains("belongs to"));
+
+        let batch = begin(&pool, TenantContext::default(), doc_id, "doc", &hasher).unwrap();
+        append(batch.batch_id, &ndjson(doc_id, 0, 2)).unwrap();
+        assert!(abort(batch.batch_id));
+        assert!(store.get_document(&doc_id).is_err());
+    }
+
+    #[test]
+    fn documents_are_recorded_under_the_database_contract() {
+        let pool = create_memory_pool().unwrap();
+        let hasher = configure_hasher(&pool.get().unwrap(), HASH_CONTRACT_BLAKE3, None).unwrap();
+        let doc_id = Uuid::new_v4();
+
+        let batch = begin(&pool, TenantContext::default(), doc_id, "doc", &hasher).unwrap();
+        commit(batch.batch_id).unwrap();
+        let doc = SqliteBlockStore::new(pool).get_document(&doc_id).unwrap();
+        assert_eq!(doc.hash_contract_version, HASH_CONTRACT_BLAKE3);
+    }
+
+    #[test]
+    fn idle_batches_are_rolled_back_and_forgotten() {
+        let pool = create_memory_pool().unwrap();
+        let store = SqliteBlockStore::new(pool.clone());
+        let hasher = ClauseHasher::default();
+        let doc_id = Uuid::new_v4();
+
+        let idle = begin(&pool, TenantContext::default(), doc_id, "idle", &hasher).unwrap();
+        append(idle.batch_id, &ndjson(doc_id, 0, 2)).unwrap();
+        let batch = find(idle.batch_id).unwrap();
+        let mut guard = batch.lock().unwrap();
+        guard.idle_timeout = Duration::ZERO;
+        std::thread::sleep(Duration::from_millis(5));
+
+        // A batch with a call in progress is left alone.
+        evict_idle();
+        assert!(find(idle.batch_id).is_ok());
+        drop(guard);
+        drop(batch);
+
+        evict_idle();
+        assert!(append(idle.batch_id, "").unwrap_err().contains("unknown ingest batch"));
+        assert!(store.get_document(&doc_id).is_err());
+        // Its write lock is gone: a new batch can open and commit.
+        let next = begin(&pool, TenantContext::default(), doc_id, "next", &hasher).unwrap();
+        append(next.batch_id, &ndjson(doc_id, 0, 1)).unwrap();
+        assert_eq!(commit(next.batch_id).unwrap().count, 1);
+    }
+}
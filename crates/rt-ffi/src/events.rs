@@ -0,0 +1,90 @@
+//! Host notification of workflow and merge events.
+//!
+//! A host registers a callback with `rtflow_set_event_callback`;
+//! [`CallbackListener`] subscribes it to the [`rt_workflow::bus`] and hands
+//! it each published event as JSON.
+
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+
+use rt_workflow::BusEvent;
+
+/// Host callback receiving one event as a null-terminated UTF-8 JSON object
+/// (`{"kind": "workflow_event", "event": ..., "state": ...}` or
+/// `{"kind": "merge_conflicts", "merge_id": ..., "base_doc_id": ...,
+/// "pending_review": n}`). `user_data` is the pointer passed to
+/// `rtflow_set_event_callback`.
+///
+/// The callback runs synchronously on the thread that persisted the event,
+/// possibly on several threads at once, and `event_json` is only valid for
+/// the duration of the call.
+pub type RtflowEventFn = unsafe extern "C" fn(event_json: *const c_char, user_data: *mut c_void);
+
+/// Bus listener forwarding events to a host callback.
+#[derive(Debug, Clone, Copy)]
+pub struct CallbackListener {
+    callback: RtflowEventFn,
+    /// Host pointer handed back to `callback`; kept as an address so the
+    /// listener can be shared across threads. The host owns whatever it
+    /// points to.
+    user_data: usize,
+}
+
+impl CallbackListener {
+    pub fn new(callback: RtflowEventFn, user_data: *mut c_void) -> Self {
+        Self {
+            callback,
+            user_data: user_data as usize,
+        }
+    }
+
+    /// Serialize `event` and pass it to the host. Events that cannot be
+    /// serialized are dropped.
+    pub fn notify(&self, event: &BusEvent) {
+        let Some(json) = serde_json::to_string(event).ok().and_then(|j| CString::new(j).ok())
+        else {
+            return;
+        };
+        // SAFETY: the host guaranteed `callback` is callable when
+        // registering it; `json` outlives the call.
+        unsafe { (self.callback)(json.as_ptr(), self.user_data as *mut c_void) }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    type Received = Mutex<Vec<serde_json::Value>>;
+
+    unsafe extern "C" fn record(event_json: *const c_char, user_data: *mut c_void) {
+        let received = &*(user_data as *const Received);
+        let json = CStr::from_ptr(event_json).to_str().unwrap();
+        received.lock().unwrap().push(serde_json::from_str(json).unwrap());
+    }
+
+    #[test]
+    fn events_reach_the_callback_as_json() {
+        let received: Received = Mutex::new(Vec::new());
+        let listener = CallbackListener::new(record, &received as *const Received as *mut c_void);
+        let merge_id = Uuid::new_v4();
+        listener.notify(&BusEvent::MergeConflicts {
+            merge_id,
+            base_doc_id: Uuid::new_v4(),
+            pending_review: 3,
+        });
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0]["kind"], "merge_conflicts");
+        assert_eq!(received[0]["merge_id"], merge_id.to_string());
+        assert_eq!(received[0]["pending_review"], 3);
+    }
+}
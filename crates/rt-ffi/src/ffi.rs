@@ -1,24 +1,182 @@
+use std::ffi::c_void;
 use std::os::raw::c_char;
-use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{OnceLock, RwLock};
 
+use chrono::{DateTime, Utc};
+#[cfg(feature = "merge")]
+use chrono::Duration;
+use serde::Deserialize;
 use uuid::Uuid;
 
-use rt_core::db::{create_pool, DbPool, SqliteBlockStore, BlockStore};
+#[cfg(feature = "export")]
+use rt_core::artifact::store_artifact;
+use rt_core::compare_sections::{
+    append_compare_deltas, compare_sections, run_deltas, section_deltas, SectionDelta,
+    SectionDeltaQuery,
+};
+use rt_core::cross_references::{get_cross_references, replace_cross_references};
+use rt_core::defined_terms::{get_defined_terms, replace_defined_terms, set_token_kinds};
+use rt_core::entities::{get_document_entities, replace_document_entities};
+use rt_core::db::{
+    create_pool, write_blocks, write_document, BlockSearchFilter, BlockStore, ConflictPolicy,
+    DbPool, DocumentFilter, SqliteBlockStore,
+};
+use rt_core::fingerprint::{
+    find_near_duplicates, record_fingerprint, NearDuplicate, NEAR_DUPLICATE_THRESHOLD,
+};
+use rt_core::content::migrate_to_content_store;
+use rt_core::gc::{collect_garbage, GcOptions};
+use rt_core::hashing::{
+    align_anchors, check_contracts, configure_hasher, default_hasher, rehash_document,
+};
+use rt_core::duplicates::find_duplicate_clauses;
+use rt_core::history::ClauseHistory;
+use rt_core::health::{check_health, HealthReport};
+use rt_core::overrides::{list_overrides, record_override};
+use rt_core::link_anchors::{record_link_anchors, resolve_link_anchor, AnchorQuery};
+use rt_core::manifest::{InputDigest, RunManifest};
+use rt_core::presets::{get_compare_preset, save_compare_preset};
+use rt_core::review_activity::{delta_activity, DeltaActivityQuery};
+use rt_core::run_history::{get_run, record_run, runs_for_document, RunKind};
+use rt_core::schema::SCHEMA_VERSION;
+use rt_core::snapshot::create_snapshot;
+#[cfg(feature = "export")]
+use rt_core::tenant::ensure_workflow;
+use rt_core::tenant::TenantContext;
+use rt_core::AlignmentLabel;
+use rt_core::ClauseHasher;
+#[cfg(feature = "export")]
+use rt_core::vfs::{StdVfs, Vfs};
+use rt_core::usage::{record_usage, usage_report, UsageMetric};
+use rt_core::validation::validate_blocks;
+#[cfg(feature = "ingest")]
+use rt_core::versions::check_link;
+use rt_core::versions::{link_version, version_chain, DocumentVersion};
 use rt_core::block::{Block, Document, DocumentType};
+use rt_compare::amendment::{apply_amendment, compare_amendment};
+use rt_compare::annotate::{annotations_to_jsonl, attach_summaries, export_annotations, parse_summaries};
+use rt_compare::calibrate::{calibrate, LabeledPair};
+use rt_compare::html::render_deltas_html;
+use rt_compare::result::{CompareProgress, CompareResult};
+use rt_compare::entities::{classify_party_refs, extract_entities};
+use rt_compare::terms::{classify_tokens, extract_defined_terms};
+use rt_compare::xref::{extract_cross_references, normalize_path};
+use rt_compare::text::{render_diffstat, render_unified};
 use rt_compare::worker::{CompareEngine, CompareConfig};
-use rt_merge::merge::MergeEngine;
-use rt_workflow::commands::WorkflowEngine;
+#[cfg(feature = "export")]
+use rt_merge::export::{export_reviewer_redline, ExportFormat};
+#[cfg(feature = "export")]
+use rt_merge::layer::ReviewComment;
+#[cfg(feature = "merge")]
+use rt_merge::layer::{BlockDelta, DeltaType, LayerDeltas, ReviewLayer};
+#[cfg(feature = "merge")]
+use rt_merge::layer_store::{ReviewLayerStore, SqliteReviewLayerStore};
+#[cfg(feature = "merge")]
+use rt_merge::merge::{MergeConfig, MergeEngine};
+#[cfg(feature = "merge")]
+use rt_merge::run::{
+    resume_merge, run_merge, run_merge_cancellable, MergeRun, MergeRunState,
+    DEFAULT_MERGE_LEASE_SECS,
+};
+#[cfg(feature = "merge")]
+use rt_merge::store::{MergeStore, SqliteMergeStore};
+#[cfg(feature = "merge")]
+use rt_merge::suggest::suggest_resolutions;
+#[cfg(feature = "workflow")]
+use rt_workflow::commands::{CreateOptions, SubmitOptions, WorkflowEngine, WorkflowFilter};
+#[cfg(feature = "workflow")]
+use rt_workflow::chain::verify_chain;
+#[cfg(feature = "workflow")]
 use rt_workflow::event::EventType;
+#[cfg(feature = "workflow")]
+use rt_workflow::config::{get_config, layer_options, set_config, WorkflowConfig};
+#[cfg(feature = "workflow")]
+use rt_workflow::routing::{apply_routing, plan_routing, RoutingRules};
+#[cfg(feature = "workflow")]
+use rt_workflow::reviewers::ReviewerEngine;
+#[cfg(feature = "workflow")]
+use rt_workflow::progress::{record_progress, ProgressSettings, ProgressThrottle};
+#[cfg(feature = "workflow")]
+use rt_workflow::validator::validate_transition;
+#[cfg(feature = "workflow")]
+use rt_workflow::approval::{ApprovalPolicy, ApprovalStatus};
+#[cfg(feature = "workflow")]
+use rt_workflow::deadlines::{check_deadlines, validate_deadlines, Deadline};
+#[cfg(feature = "workflow")]
+use rt_workflow::subworkflows::{create_subworkflow, list_subworkflows};
+#[cfg(feature = "workflow")]
+use rt_workflow::machine::{current_machine, install_machine, reset_machine, StateMachine};
+#[cfg(feature = "workflow")]
+use rt_workflow::{bus, Subscription};
+#[cfg(all(feature = "merge", feature = "workflow"))]
+use rt_workflow::BusEvent;
 
 use crate::marshal::{cstring_to_str, deserialize_json};
 use crate::result::RtflowResult;
+use crate::jobs::{self, JobOutput};
+use crate::batch;
+use crate::stream::{self, DEFAULT_STREAM_BATCH};
+use crate::vfs::{CallbackVfs, RtflowWriteFn};
+#[cfg(feature = "workflow")]
+use crate::events::{CallbackListener, RtflowEventFn};
 
 // ---------------------------------------------------------------------------
 // Global database pool
 // ---------------------------------------------------------------------------
 
+/// Pool installed by `rtflow_init`.
+///
+/// Every `rtflow_*` function may be called from any host thread. Each call
+/// checks out its own connection from this pool, the engines it builds are
+/// `Send + Sync`, each call names the tenant it acts for, and the
+/// process-wide settings below sit behind locks or atomics, so calls need
+/// no external synchronization. Writes to one database still serialize on
+/// SQLite's write lock.
 static DB_POOL: OnceLock<DbPool> = OnceLock::new();
 
+/// Held while a pool is opened for `DB_POOL`, so concurrent `rtflow_init`
+/// calls neither open and migrate the database twice nor race the check.
+static INIT_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Open a pool with `open` and install it as `DB_POOL`, unless one is
+/// already installed; `caller` names the init function in the error.
+fn init_pool(
+    caller: &str,
+    open: impl FnOnce() -> rt_core::Result<DbPool>,
+) -> *mut RtflowResult {
+    let _guard = INIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    // Only the first caller wins; subsequent callers get a descriptive
+    // error rather than silently succeeding, and never touch the database.
+    if DB_POOL.get().is_some() {
+        return RtflowResult::failure(&format!(
+            "Database already initialized; {caller} may only be called once."
+        ));
+    }
+    match open() {
+        Ok(pool) => {
+            let _ = DB_POOL.set(pool);
+            RtflowResult::success("{}")
+        }
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Hasher bound by `rtflow_configure_hashing`; unset means the database's
+/// unkeyed default.
+static HASHER: OnceLock<ClauseHasher> = OnceLock::new();
+
+/// Whether stored enum strings are parsed strictly; see
+/// `rtflow_set_parsing_mode`.
+static STRICT_PARSING: AtomicBool = AtomicBool::new(false);
+
+/// File system bound by `rtflow_set_vfs`; unset means the local file system.
+static HOST_VFS: RwLock<Option<CallbackVfs>> = RwLock::new(None);
+
+/// Bus subscription of the callback bound by `rtflow_set_event_callback`.
+#[cfg(feature = "workflow")]
+static EVENT_CALLBACK: std::sync::Mutex<Option<Subscription>> = std::sync::Mutex::new(None);
+
 /// Return a reference to the global pool, or an error string if
 /// `rtflow_init` has not been called yet.
 fn get_pool() -> Result<&'static DbPool, String> {
@@ -27,6 +185,172 @@ fn get_pool() -> Result<&'static DbPool, String> {
         .ok_or_else(|| "Database not initialized. Call rtflow_init first.".to_string())
 }
 
+/// The tenant a call acts for: `tenant_id`, or the `default` tenant when it
+/// is null.
+///
+/// Every call that reads or writes documents, workflows, merges or runs
+/// takes the tenant it acts for as its first argument, `tenant_id`: 1 to 64
+/// ASCII letters, digits, `-`, `_` or `.`. Rows are created under that
+/// tenant, and those of other tenants are reported as not found. The
+/// `default` tenant also owns rows written before tenancy existed.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+unsafe fn tenant_arg(tenant_id: *const c_char) -> Result<TenantContext, String> {
+    if tenant_id.is_null() {
+        return Ok(TenantContext::default());
+    }
+    let id = cstring_to_str(tenant_id)?;
+    TenantContext::new(&id).map_err(|e| e.to_string())
+}
+
+/// A block store acting for `tenant`, parsing stored block types and token
+/// kinds as selected by `rtflow_set_parsing_mode`.
+fn block_store(pool: &DbPool, tenant: &TenantContext) -> SqliteBlockStore {
+    SqliteBlockStore::with_tenant(pool.clone(), tenant.clone())
+        .with_strict_parsing(STRICT_PARSING.load(Ordering::Relaxed))
+}
+
+/// The file system exports and artifacts are written to: the host's, when
+/// bound by `rtflow_set_vfs`, or the local one.
+#[cfg(feature = "export")]
+fn current_vfs() -> Box<dyn Vfs> {
+    match HOST_VFS.read().ok().and_then(|v| *v) {
+        Some(vfs) => Box::new(vfs),
+        None => Box::new(StdVfs),
+    }
+}
+
+/// The hasher new blocks are hashed with: the one bound by
+/// `rtflow_configure_hashing`, or the database's unkeyed default.
+fn current_hasher(pool: &DbPool) -> Result<ClauseHasher, String> {
+    if let Some(hasher) = HASHER.get() {
+        return Ok(hasher.clone());
+    }
+    let conn = pool
+        .get()
+        .map_err(|e| format!("failed to acquire database connection: {}", e))?;
+    default_hasher(&conn).map_err(|e| e.to_string())
+}
+
+/// Fingerprint a freshly ingested document and look for near duplicates,
+/// after recording its defined terms, parties and dates, cross-references
+/// and link anchors.
+///
+/// Never fails the ingest: the document is already stored, so problems are
+/// returned as warnings alongside any duplicates found.
+fn post_ingest_check(
+    pool: &DbPool,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+) -> (Vec<NearDuplicate>, Vec<String>) {
+    let mut warnings = Vec::new();
+    if let Err(e) = record_defined_terms(pool, tenant, doc_id) {
+        warnings.push(format!("defined-term extraction failed: {}", e));
+    }
+    if let Err(e) = record_entities(pool, tenant, doc_id) {
+        warnings.push(format!("entity extraction failed: {}", e));
+    }
+    if let Err(e) = record_cross_references(pool, tenant, doc_id) {
+        warnings.push(format!("cross-reference extraction failed: {}", e));
+    }
+    let anchors = pool
+        .get()
+        .map_err(|e| e.to_string())
+        .and_then(|conn| {
+            record_link_anchors(&conn, tenant, doc_id).map_err(|e| e.to_string())
+        });
+    if let Err(e) = anchors {
+        warnings.push(format!("recording link anchors failed: {}", e));
+    }
+    let check = pool
+        .get()
+        .map_err(|e| e.to_string())
+        .and_then(|conn| {
+            record_fingerprint(&conn, doc_id).map_err(|e| e.to_string())?;
+            find_near_duplicates(&conn, tenant, doc_id, NEAR_DUPLICATE_THRESHOLD)
+                .map_err(|e| e.to_string())
+        });
+    match check {
+        Ok(duplicates) => {
+            warnings.extend(duplicates.iter().map(|d| {
+                format!(
+                    "document is {:.0}% similar to existing document {} ({})",
+                    d.similarity * 100.0,
+                    d.name,
+                    d.document_id
+                )
+            }));
+            (duplicates, warnings)
+        }
+        Err(e) => {
+            warnings.push(format!("near-duplicate check failed: {}", e));
+            (Vec::new(), warnings)
+        }
+    }
+}
+
+/// Extract the defined-term dictionary of a stored document, store it, and
+/// re-classify the document's stored tokens against it.
+fn record_defined_terms(
+    pool: &DbPool,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+) -> Result<(), String> {
+    let tree = block_store(pool, tenant).get_block_tree(doc_id).map_err(|e| e.to_string())?;
+    let terms = extract_defined_terms(&tree);
+
+    let mut updates = Vec::new();
+    let mut stack: Vec<Block> = tree;
+    while let Some(mut block) = stack.pop() {
+        for seq in classify_tokens(&mut block.tokens, &terms) {
+            updates.push((block.id, seq, block.tokens[seq].kind.clone()));
+        }
+        stack.append(&mut block.children);
+    }
+
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    replace_defined_terms(&conn, tenant, doc_id, &terms).map_err(|e| e.to_string())?;
+    set_token_kinds(&conn, tenant, doc_id, &updates).map_err(|e| e.to_string())
+}
+
+/// Extract the parties and dates of a stored document, store them, and mark
+/// the document's stored tokens naming a party as party references.
+///
+/// Runs after [`record_defined_terms`], so party terms end up as party
+/// references rather than defined terms.
+fn record_entities(pool: &DbPool, tenant: &TenantContext, doc_id: &Uuid) -> Result<(), String> {
+    let tree = block_store(pool, tenant).get_block_tree(doc_id).map_err(|e| e.to_string())?;
+    let entities = extract_entities(&tree);
+
+    let mut updates = Vec::new();
+    let mut stack: Vec<Block> = tree;
+    while let Some(mut block) = stack.pop() {
+        for seq in classify_party_refs(&mut block.tokens, &entities.parties) {
+            updates.push((block.id, seq, block.tokens[seq].kind.clone()));
+        }
+        stack.append(&mut block.children);
+    }
+
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    replace_document_entities(&conn, tenant, doc_id, &entities).map_err(|e| e.to_string())?;
+    set_token_kinds(&conn, tenant, doc_id, &updates).map_err(|e| e.to_string())
+}
+
+/// Extract the cross-references of a stored document and store them.
+fn record_cross_references(
+    pool: &DbPool,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+) -> Result<(), String> {
+    let tree = block_store(pool, tenant).get_block_tree(doc_id).map_err(|e| e.to_string())?;
+    let references = extract_cross_references(&tree);
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    replace_cross_references(&conn, tenant, doc_id, &references)
+        .map_err(|e| e.to_string())
+}
+
 // ---------------------------------------------------------------------------
 // Memory management
 // ---------------------------------------------------------------------------
@@ -67,56 +391,79 @@ pub unsafe extern "C" fn rtflow_init(db_path: *const c_char) -> *mut RtflowResul
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    match create_pool(&path) {
-        Ok(pool) => {
-            // Only the first caller wins; subsequent callers get a
-            // descriptive error rather than silently succeeding.
-            if DB_POOL.set(pool).is_err() {
-                return RtflowResult::failure(
-                    "Database already initialized; rtflow_init may only be called once.",
-                );
-            }
-            RtflowResult::success("{}")
-        }
-        Err(e) => RtflowResult::failure(&e.to_string()),
+    init_pool("rtflow_init", || create_pool(&path))
+}
+
+/// Report liveness / readiness of the FFI layer for health probes.
+///
+/// Performs one database round-trip, checks that every schema table exists
+/// and that stored documents match the current schema version, and reports
+/// connection pool occupancy. See `rt_core::health::HealthReport` for the
+/// fields.
+///
+/// Always returns `ok = true` with a `HealthReport` JSON object in `data`,
+/// including before `rtflow_init` has been called (`"status":
+/// "unavailable"`); probes should gate on the report's `ready` field.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+#[no_mangle]
+pub extern "C" fn rtflow_health() -> *mut RtflowResult {
+    let report = match get_pool() {
+        Ok(pool) => check_health(pool),
+        Err(e) => HealthReport::unavailable(e),
+    };
+
+    match serde_json::to_string(&report) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize HealthReport: {}", e)),
     }
 }
 
-// ---------------------------------------------------------------------------
-// Document ingestion
-// ---------------------------------------------------------------------------
+/// Request envelope accepted by `rtflow_configure_hashing`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HashingRequest {
+    contract_version: String,
+    #[serde(default)]
+    key_hex: Option<String>,
+}
 
-/// Ingest a list of blocks (as a JSON array) into the store under `doc_id`.
+/// Select the hash contract (and key) used for clause hashes and anchor
+/// signatures of newly ingested documents.
 ///
-/// `json_ptr`    — null-terminated UTF-8 string containing the blocks JSON.
-/// `doc_id_ptr`  — null-terminated UTF-8 string containing the document UUID.
+/// `config_json` — null-terminated UTF-8 string: JSON object with the fields
+///   - `"contract_version"`: `"1.0.0"` (SHA-256), `"1.0.0-blake3"` or
+///     `"1.0.0-hmac-sha256"`
+///   - `"key_hex"`: hex-encoded key; required for HMAC, optional for BLAKE3
 ///
-/// Returns a `RtflowResult` whose `data` field is the ingested document UUID
-/// on success.
+/// The first configuration is recorded in the database (the key only as a
+/// fingerprint); later sessions must supply the same contract and key.
+/// May be called once per process, after `rtflow_init`. Blocks passed to
+/// `rtflow_ingest_blocks` carry caller-computed hashes and are not affected.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"contract_version": ..., "keyed": ...}` on success.
 ///
 /// The returned pointer must be freed with `rtflow_free`.
 ///
 /// # Safety
 ///
-/// Both pointer arguments must be valid, non-null, null-terminated C strings.
+/// `config_json` must be a valid, non-null, null-terminated C string.
 #[no_mangle]
-pub unsafe extern "C" fn rtflow_ingest_blocks(
-    json_ptr: *const c_char,
-    doc_id_ptr: *const c_char,
-) -> *mut RtflowResult {
-    let json = match cstring_to_str(json_ptr) {
+pub unsafe extern "C" fn rtflow_configure_hashing(config_json: *const c_char) -> *mut RtflowResult {
+    let json_str = match cstring_to_str(config_json) {
         Ok(s) => s,
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    let doc_id_str = match cstring_to_str(doc_id_ptr) {
-        Ok(s) => s,
-        Err(e) => return RtflowResult::failure(&e),
+    let request: HashingRequest = match serde_json::from_str(&json_str) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("invalid hashing config: {}", e)),
     };
 
-    let doc_id = match Uuid::parse_str(&doc_id_str) {
-        Ok(id) => id,
-        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    let key = match request.key_hex.as_deref().map(decode_hex).transpose() {
+        Ok(k) => k,
+        Err(e) => return RtflowResult::failure(&e),
     };
 
     let pool = match get_pool() {
@@ -124,96 +471,74 @@ pub unsafe extern "C" fn rtflow_ingest_blocks(
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    // Deserialize as an array of blocks.
-    let blocks: Vec<Block> = match deserialize_json(&json) {
-        Ok(b) => b,
-        Err(e) => return RtflowResult::failure(&format!("failed to parse blocks JSON: {}", e)),
-    };
-
-    let store = SqliteBlockStore::new(pool.clone());
-
-    // Ensure the document row exists; insert a minimal record if missing.
-    if store.get_document(&doc_id).is_err() {
-        use chrono::Utc;
-        use rt_core::schema::SCHEMA_VERSION;
-        let doc = Document {
-            id: doc_id,
-            name: doc_id_str.clone(),
-            source_path: None,
-            doc_type: DocumentType::Original,
-            schema_version: SCHEMA_VERSION.to_string(),
-            normalization_version: "1.0.0".to_string(),
-            hash_contract_version: "1.0.0".to_string(),
-            ingested_at: Utc::now(),
-            metadata: None,
-        };
-        if let Err(e) = store.insert_document(&doc) {
-            return RtflowResult::failure(&format!("failed to create document record: {}", e));
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
         }
-    }
-
-    let count = blocks.len();
+    };
 
-    if let Err(e) = store.insert_blocks(&blocks) {
-        return RtflowResult::failure(&format!("failed to insert blocks: {}", e));
-    }
+    let hasher = match configure_hasher(&conn, &request.contract_version, key.as_deref()) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
 
     let payload = serde_json::json!({
-        "doc_id": doc_id.to_string(),
-        "count": count,
+        "contract_version": hasher.contract_version(),
+        "keyed": hasher.is_keyed(),
     });
 
-    match serde_json::to_string(&payload) {
-        Ok(json_out) => RtflowResult::success(&json_out),
-        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    if HASHER.set(hasher).is_err() {
+        return RtflowResult::failure(
+            "Hashing already configured; rtflow_configure_hashing may only be called once.",
+        );
     }
-}
 
-// ---------------------------------------------------------------------------
-// Compare
-// ---------------------------------------------------------------------------
+    RtflowResult::success(&payload.to_string())
+}
 
-/// Compare two documents identified by their UUIDs.
+/// Recompute the clause hashes and anchor signatures of a stored document
+/// under the current hash contract.
 ///
-/// `left_doc_id`   — null-terminated UTF-8 string: UUID of the left document.
-/// `right_doc_id`  — null-terminated UTF-8 string: UUID of the right document.
-/// `options_json`  — null-terminated UTF-8 string: JSON object with compare
-///                   options (may be `"{}"` for defaults).
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
 ///
-/// Returns a `RtflowResult` whose `data` field is a `CompareResult` JSON
-/// object on success.
+/// `doc_id` — null-terminated UTF-8 string: UUID of the document.
+///
+/// Compare and merge refuse documents hashed under different contracts;
+/// rehashing the older one moves it onto the contract set by
+/// `rtflow_configure_hashing` (or the database's unkeyed default). Anchors
+/// keep the document's anchor strategy.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"doc_id": ..., "previous_contract": ..., "contract_version": ...,
+/// "updated": n}` on success, `updated` counting the blocks that changed.
 ///
 /// The returned pointer must be freed with `rtflow_free`.
 ///
 /// # Safety
 ///
-/// All pointer arguments must be valid, non-null, null-terminated C strings.
+/// `doc_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
 #[no_mangle]
-pub unsafe extern "C" fn rtflow_compare(
-    left_doc_id: *const c_char,
-    right_doc_id: *const c_char,
-    options_json: *const c_char,
+pub unsafe extern "C" fn rtflow_rehash_document(
+    tenant_id: *const c_char,
+    doc_id: *const c_char,
 ) -> *mut RtflowResult {
-    let left_str = match cstring_to_str(left_doc_id) {
-        Ok(s) => s,
-        Err(e) => return RtflowResult::failure(&e),
-    };
-    let right_str = match cstring_to_str(right_doc_id) {
-        Ok(s) => s,
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
         Err(e) => return RtflowResult::failure(&e),
     };
-    let _options_str = match cstring_to_str(options_json) {
+
+    let doc_str = match cstring_to_str(doc_id) {
         Ok(s) => s,
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    let left_id = match Uuid::parse_str(&left_str) {
-        Ok(id) => id,
-        Err(e) => return RtflowResult::failure(&format!("invalid left_doc_id UUID: {}", e)),
-    };
-    let right_id = match Uuid::parse_str(&right_str) {
+    let id = match Uuid::parse_str(&doc_str) {
         Ok(id) => id,
-        Err(e) => return RtflowResult::failure(&format!("invalid right_doc_id UUID: {}", e)),
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
     };
 
     let pool = match get_pool() {
@@ -221,182 +546,300 @@ pub unsafe extern "C" fn rtflow_compare(
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    let store = SqliteBlockStore::new(pool.clone());
-
-    let left_blocks = match store.get_block_tree(&left_id) {
-        Ok(b) => b,
-        Err(e) => {
-            return RtflowResult::failure(&format!("failed to load left document blocks: {}", e))
-        }
+    let hasher = match current_hasher(pool) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e),
     };
-    let right_blocks = match store.get_block_tree(&right_id) {
-        Ok(b) => b,
+
+    let conn = match pool.get() {
+        Ok(c) => c,
         Err(e) => {
-            return RtflowResult::failure(&format!("failed to load right document blocks: {}", e))
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
         }
     };
 
-    let engine = CompareEngine::new(CompareConfig::default());
-    let result = engine.compare(left_id, right_id, &left_blocks, &right_blocks);
+    let summary = match rehash_document(&conn, &tenant, &id, &hasher) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&format!("failed to rehash document: {}", e)),
+    };
 
-    match serde_json::to_string(&result) {
+    match serde_json::to_string(&summary) {
         Ok(json_out) => RtflowResult::success(&json_out),
-        Err(e) => RtflowResult::failure(&format!("failed to serialize CompareResult: {}", e)),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize rehash summary: {}", e)),
     }
 }
 
-// ---------------------------------------------------------------------------
-// Merge
-// ---------------------------------------------------------------------------
-
-/// Merge an incoming document into a base document.
+/// Enable content-addressed block storage and move the tenant's stored
+/// blocks onto it.
 ///
-/// `base_doc_id`     — null-terminated UTF-8 string: UUID of the base document.
-/// `incoming_doc_id` — null-terminated UTF-8 string: UUID of the incoming document.
-/// `options_json`    — null-terminated UTF-8 string: JSON object with merge
-///                     options (may be `"{}"` for defaults).
+/// From then on, blocks with the same clause hash, tokens and runs keep one
+/// shared copy of their tokens and runs instead of rows of their own; reads
+/// are unchanged. Calling it again moves blocks stored in between.
 ///
-/// Returns a `RtflowResult` whose `data` field is a `MergeResult` JSON object
-/// on success.
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"blocks": n, "shared": n, "contents": n}`: the blocks examined, those
+/// now sharing content, and the distinct contents stored.
 ///
 /// The returned pointer must be freed with `rtflow_free`.
 ///
 /// # Safety
 ///
-/// All pointer arguments must be valid, non-null, null-terminated C strings.
+/// `tenant_id` must be null or a valid, null-terminated C string.
 #[no_mangle]
-pub unsafe extern "C" fn rtflow_merge(
-    base_doc_id: *const c_char,
-    incoming_doc_id: *const c_char,
-    options_json: *const c_char,
+pub unsafe extern "C" fn rtflow_migrate_content_store(
+    tenant_id: *const c_char,
 ) -> *mut RtflowResult {
-    let base_str = match cstring_to_str(base_doc_id) {
-        Ok(s) => s,
-        Err(e) => return RtflowResult::failure(&e),
-    };
-    let incoming_str = match cstring_to_str(incoming_doc_id) {
-        Ok(s) => s,
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
         Err(e) => return RtflowResult::failure(&e),
     };
-    let _options_str = match cstring_to_str(options_json) {
-        Ok(s) => s,
-        Err(e) => return RtflowResult::failure(&e),
-    };
-
-    let base_id = match Uuid::parse_str(&base_str) {
-        Ok(id) => id,
-        Err(e) => return RtflowResult::failure(&format!("invalid base_doc_id UUID: {}", e)),
-    };
-    let incoming_id = match Uuid::parse_str(&incoming_str) {
-        Ok(id) => id,
-        Err(e) => return RtflowResult::failure(&format!("invalid incoming_doc_id UUID: {}", e)),
-    };
 
     let pool = match get_pool() {
         Ok(p) => p,
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    let store = SqliteBlockStore::new(pool.clone());
-
-    let base_blocks = match store.get_block_tree(&base_id) {
-        Ok(b) => b,
-        Err(e) => {
-            return RtflowResult::failure(&format!("failed to load base document blocks: {}", e))
-        }
-    };
-    let incoming_blocks = match store.get_block_tree(&incoming_id) {
-        Ok(b) => b,
+    let conn = match pool.get() {
+        Ok(c) => c,
         Err(e) => {
-            return RtflowResult::failure(&format!(
-                "failed to load incoming document blocks: {}",
-                e
-            ))
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
         }
     };
 
-    let engine = MergeEngine::new();
-    let result = engine.merge(base_id, incoming_id, &base_blocks, &incoming_blocks);
+    let migration = match migrate_to_content_store(&conn, &tenant) {
+        Ok(m) => m,
+        Err(e) => return RtflowResult::failure(&format!("failed to migrate blocks: {}", e)),
+    };
 
-    match serde_json::to_string(&result) {
+    match serde_json::to_string(&migration) {
         Ok(json_out) => RtflowResult::success(&json_out),
-        Err(e) => RtflowResult::failure(&format!("failed to serialize MergeResult: {}", e)),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize migration: {}", e)),
     }
 }
 
-// ---------------------------------------------------------------------------
-// Workflow
-// ---------------------------------------------------------------------------
+fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
+    if !s.len().is_multiple_of(2) {
+        return Err("key_hex must have an even number of digits".to_string());
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("key_hex is not valid hex at offset {}", i))
+        })
+        .collect()
+}
 
-/// Submit a workflow event and advance the workflow state machine.
-///
-/// `workflow_id` — null-terminated UTF-8 string: UUID of the workflow.
-/// `event_json`  — null-terminated UTF-8 string: JSON object describing the
-///                 event to apply.
-///
-/// The `event_json` object must contain at least:
-///   - `"event_type"`: string — a valid `EventType` snake_case value
-///   - `"actor"`:      string — identifier of the user/system submitting the event
+/// Select how stored block types and token kinds are parsed when blocks are
+/// read back.
 ///
-/// An optional `"payload"` key may hold any JSON value; it defaults to `{}`.
+/// `mode` — null-terminated UTF-8 string: `"lenient"` (the default) reads
+/// unknown block types as `paragraph` and unknown token kinds as `word`;
+/// `"strict"` fails the call with an error naming the unknown value, so a
+/// client writing bad values is noticed. Block JSON passed in (e.g. to
+/// `rtflow_ingest_blocks`) is always parsed strictly. The selection is
+/// process-wide.
 ///
-/// Returns a `RtflowResult` whose `data` field is the updated `Workflow`
-/// JSON object on success.
+/// Returns a `RtflowResult` whose `data` field is `{"parsing_mode": ...}` on
+/// success.
 ///
 /// The returned pointer must be freed with `rtflow_free`.
 ///
 /// # Safety
 ///
-/// Both pointer arguments must be valid, non-null, null-terminated C strings.
+/// `mode` must be a valid, non-null, null-terminated C string.
 #[no_mangle]
-pub unsafe extern "C" fn rtflow_workflow_event(
-    workflow_id: *const c_char,
-    event_json: *const c_char,
-) -> *mut RtflowResult {
-    let wf_id_str = match cstring_to_str(workflow_id) {
-        Ok(s) => s,
-        Err(e) => return RtflowResult::failure(&e),
-    };
-    let event_str = match cstring_to_str(event_json) {
+pub unsafe extern "C" fn rtflow_set_parsing_mode(mode: *const c_char) -> *mut RtflowResult {
+    let mode_str = match cstring_to_str(mode) {
         Ok(s) => s,
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    let wf_id = match Uuid::parse_str(&wf_id_str) {
-        Ok(id) => id,
-        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    let strict = match mode_str.as_str() {
+        "strict" => true,
+        "lenient" => false,
+        other => {
+            return RtflowResult::failure(&format!(
+                "unknown parsing mode '{}'; expected strict or lenient",
+                other
+            ))
+        }
     };
+    STRICT_PARSING.store(strict, Ordering::Relaxed);
 
-    // Parse the event JSON envelope.
-    let event_value: serde_json::Value = match deserialize_json(&event_str) {
-        Ok(v) => v,
-        Err(e) => return RtflowResult::failure(&format!("failed to parse event JSON: {}", e)),
+    RtflowResult::success(&serde_json::json!({ "parsing_mode": mode_str }).to_string())
+}
+
+/// Route file writes (exports and their artifacts) through the host.
+///
+/// `write` — callback writing a file, see `RtflowWriteFn`: called with the
+/// null-terminated UTF-8 path, the content bytes and their length, and
+/// `user_data`; returns 0 on success. Pass null to write to the local file
+/// system again (the default).
+/// `user_data` — opaque host pointer handed back to every `write` call; may
+/// be null.
+///
+/// The callback may be invoked from any thread and must stay callable until
+/// it is replaced. The selection is process-wide.
+///
+/// Returns a `RtflowResult` whose `data` field is `{"vfs": "host"}` or
+/// `{"vfs": "std"}` on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `write`, when non-null, must be safe to call with the arguments described
+/// above, and `user_data` must stay valid for as long as it is registered.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_set_vfs(
+    write: Option<RtflowWriteFn>,
+    user_data: *mut c_void,
+) -> *mut RtflowResult {
+    let vfs = write.map(|write| CallbackVfs::new(write, user_data));
+    let kind = if vfs.is_some() { "host" } else { "std" };
+    match HOST_VFS.write() {
+        Ok(mut current) => *current = vfs,
+        Err(e) => return RtflowResult::failure(&format!("failed to select file system: {}", e)),
+    }
+
+    RtflowResult::success(&serde_json::json!({ "vfs": kind }).to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Document ingestion
+// ---------------------------------------------------------------------------
+
+/// Ingest a list of blocks (as a JSON array) into the store under `doc_id`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `json_ptr`    — null-terminated UTF-8 string containing the blocks JSON.
+/// `doc_id_ptr`  — null-terminated UTF-8 string containing the document UUID.
+///
+/// The blocks are validated before anything is written: they must all
+/// belong to `doc_id`, list parents before children, and use each id and
+/// structural path once, none of them stored already. Invalid blocks fail
+/// the call with the first few problems; nothing is written, not even the
+/// document record created for a new `doc_id`.
+///
+/// After the blocks are stored the document is fingerprinted and compared
+/// with existing documents.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"doc_id": ..., "count": ..., "near_duplicates": [...], "warnings": [...],
+/// "validation": {...}}` on success; `near_duplicates` lists documents at
+/// least 95% similar and `validation` is the (clean) validation report.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_ingest_blocks(
+    tenant_id: *const c_char,
+    json_ptr: *const c_char,
+    doc_id_ptr: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
     };
 
-    let event_type_str = match event_value.get("event_type").and_then(|v| v.as_str()) {
-        Some(s) => s.to_owned(),
-        None => {
-            return RtflowResult::failure(
-                "event JSON must contain a string field \"event_type\"",
-            )
-        }
+    ingest_blocks_with(&tenant, json_ptr, doc_id_ptr, &BlockIngestOptions::default())
+}
+
+/// Options of `rtflow_ingest_blocks_with_options`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BlockIngestOptions {
+    /// How blocks whose id or structural path is already stored are written.
+    on_conflict: ConflictPolicy,
+    /// Validate only: return the report without writing anything.
+    dry_run: bool,
+    /// Stored document the ingested one is the next version of.
+    previous_version_id: Option<Uuid>,
+}
+
+/// [`rtflow_ingest_blocks`] with ingest options.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `options_json` — null-terminated UTF-8 string: JSON object with the
+///                  optional fields
+///   - `"on_conflict"`: `"fail"` (the default) rejects blocks already
+///     stored; `"skip"` keeps the stored block, so ingesting the same
+///     blocks again is a no-op; `"replace"` overwrites it
+///   - `"dry_run"`: `true` to validate without writing
+///   - `"previous_version_id"`: link the document as the next version of
+///     that stored document (see `rtflow_link_version`), in the same
+///     transaction as the blocks
+///
+/// Returns the same payload as `rtflow_ingest_blocks`, where `count` is the
+/// number of blocks written (skipped blocks are not counted). A dry run
+/// returns the validation report itself,
+/// `{"doc_id": ..., "blocks": n, "issues": [{"index": ..., "block_id": ...,
+/// "structural_path": ..., "kind": ..., "message": ...}]}`, valid or not.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_ingest_blocks_with_options(
+    tenant_id: *const c_char,
+    json_ptr: *const c_char,
+    doc_id_ptr: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
     };
 
-    let actor = match event_value.get("actor").and_then(|v| v.as_str()) {
-        Some(s) => s.to_owned(),
-        None => {
-            return RtflowResult::failure("event JSON must contain a string field \"actor\"")
-        }
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
     };
 
-    let payload = event_value
-        .get("payload")
-        .cloned()
-        .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+    let options: BlockIngestOptions = match serde_json::from_str(&options_str) {
+        Ok(o) => o,
+        Err(e) => return RtflowResult::failure(&format!("invalid ingest options JSON: {}", e)),
+    };
 
-    let event_type = match EventType::from_str(&event_type_str) {
-        Ok(et) => et,
-        Err(e) => return RtflowResult::failure(&format!("invalid event_type: {}", e)),
+    ingest_blocks_with(&tenant, json_ptr, doc_id_ptr, &options)
+}
+
+/// Shared body of the block ingest entry points.
+unsafe fn ingest_blocks_with(
+    tenant: &TenantContext,
+    json_ptr: *const c_char,
+    doc_id_ptr: *const c_char,
+    options: &BlockIngestOptions,
+) -> *mut RtflowResult {
+    let json = match cstring_to_str(json_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
     };
 
     let pool = match get_pool() {
@@ -404,46 +847,133 @@ pub unsafe extern "C" fn rtflow_workflow_event(
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    let conn = match pool.get() {
+    // Deserialize as an array of blocks.
+    let blocks: Vec<Block> = match deserialize_json(&json) {
+        Ok(b) => b,
+        Err(e) => return RtflowResult::failure(&format!("failed to parse blocks JSON: {}", e)),
+    };
+
+    let hasher = match current_hasher(pool) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let mut conn = match pool.get() {
         Ok(c) => c,
         Err(e) => {
             return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
         }
     };
+    let tx = match conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate) {
+        Ok(tx) => tx,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
 
-    match WorkflowEngine::submit_event(&conn, wf_id, event_type, &actor, payload) {
-        Ok(wf) => match serde_json::to_string(&wf) {
+    // Ensure the document row exists; insert a minimal record, under the
+    // database's hash contract, if missing.
+    let doc = Document {
+        id: doc_id,
+        name: doc_id_str.clone(),
+        source_path: None,
+        doc_type: DocumentType::Original,
+        schema_version: SCHEMA_VERSION.to_string(),
+        normalization_version: "1.0.0".to_string(),
+        hash_contract_version: hasher.contract_version().to_string(),
+        anchor_version: "1".to_string(),
+        ingested_at: Utc::now(),
+        metadata: None,
+    };
+    if let Err(e) = write_document(&tx, tenant, &doc, ConflictPolicy::Skip) {
+        return RtflowResult::failure(&format!("failed to create document record: {}", e));
+    }
+
+    let report = match validate_blocks(&tx, tenant, doc_id, &blocks, options.on_conflict) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("failed to validate blocks: {}", e)),
+    };
+    if options.dry_run {
+        return match serde_json::to_string(&report) {
             Ok(json_out) => RtflowResult::success(&json_out),
-            Err(e) => RtflowResult::failure(&format!("failed to serialize Workflow: {}", e)),
-        },
-        Err(e) => RtflowResult::failure(&e.to_string()),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize report: {}", e)),
+        };
+    }
+    if !report.is_valid() {
+        return RtflowResult::failure(&report.summary());
+    }
+
+    let count = match write_blocks(&tx, tenant, &blocks, options.on_conflict) {
+        Ok(n) => n,
+        Err(e) => return RtflowResult::failure(&format!("failed to insert blocks: {}", e)),
+    };
+    if let Some(previous) = &options.previous_version_id {
+        if let Err(e) = link_version(&tx, tenant, &doc_id, previous) {
+            return RtflowResult::failure(&format!("failed to link previous version: {}", e));
+        }
+    }
+    if let Err(e) = tx.commit() {
+        return RtflowResult::failure(&format!("failed to insert blocks: {}", e));
+    }
+    drop(conn);
+
+    let (near_duplicates, warnings) = post_ingest_check(pool, tenant, &doc_id);
+
+    let payload = serde_json::json!({
+        "doc_id": doc_id.to_string(),
+        "count": count,
+        "near_duplicates": near_duplicates,
+        "warnings": warnings,
+        "validation": report,
+    });
+
+    match serde_json::to_string(&payload) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
     }
 }
 
-/// Retrieve the current state of a workflow.
+/// Start ingesting blocks into `doc_id` in chunks.
 ///
-/// `workflow_id` — null-terminated UTF-8 string: UUID of the workflow.
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
 ///
-/// Returns a `RtflowResult` whose `data` field is the current `Workflow`
-/// JSON object on success.
+/// `doc_id_ptr` — null-terminated UTF-8 string containing the document UUID.
+///
+/// For documents too large to pass to `rtflow_ingest_blocks` in one JSON
+/// array. The blocks are then sent as NDJSON, one block object per line and
+/// parents before children, with `rtflow_ingest_append`, and stored by
+/// `rtflow_ingest_commit`. Everything happens in one transaction, held open
+/// until the commit, so other writers wait meanwhile; a missing document
+/// record is created as by `rtflow_ingest_blocks`. A batch with no append
+/// for five minutes is rolled back and forgotten, and later calls on it
+/// fail as for an unknown batch.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"batch_id": ..., "doc_id": ..., "count": 0}` on success.
 ///
 /// The returned pointer must be freed with `rtflow_free`.
 ///
 /// # Safety
 ///
-/// `workflow_id` must be a valid, non-null, null-terminated C string.
+/// `doc_id_ptr` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
 #[no_mangle]
-pub unsafe extern "C" fn rtflow_workflow_state(
-    workflow_id: *const c_char,
+pub unsafe extern "C" fn rtflow_ingest_begin(
+    tenant_id: *const c_char,
+    doc_id_ptr: *const c_char,
 ) -> *mut RtflowResult {
-    let wf_id_str = match cstring_to_str(workflow_id) {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
         Ok(s) => s,
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    let wf_id = match Uuid::parse_str(&wf_id_str) {
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
         Ok(id) => id,
-        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
     };
 
     let pool = match get_pool() {
@@ -451,501 +981,8120 @@ pub unsafe extern "C" fn rtflow_workflow_state(
         Err(e) => return RtflowResult::failure(&e),
     };
 
-    let conn = match pool.get() {
-        Ok(c) => c,
-        Err(e) => {
-            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
-        }
+    let hasher = match current_hasher(pool) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e),
     };
 
-    match WorkflowEngine::get_workflow(&conn, wf_id) {
-        Ok(wf) => match serde_json::to_string(&wf) {
-            Ok(json_out) => RtflowResult::success(&json_out),
-            Err(e) => RtflowResult::failure(&format!("failed to serialize Workflow: {}", e)),
-        },
-        Err(e) => RtflowResult::failure(&e.to_string()),
-    }
+    batch_result(batch::begin(pool, tenant.clone(), doc_id, &doc_id_str, &hasher))
 }
 
-// ---------------------------------------------------------------------------
-// Test helpers
-// ---------------------------------------------------------------------------
-
-/// Initialize the FFI layer using an in-memory SQLite database.
+/// Send the next chunk of an ingest batch.
 ///
-/// This function is provided for integration testing only.  It behaves
-/// identically to `rtflow_init` but uses an ephemeral in-memory database
-/// instead of a file on disk.
+/// `batch_id` — null-terminated UTF-8 string: `batch_id` returned by
+///              `rtflow_ingest_begin`.
+/// `chunk`    — null-terminated UTF-8 string: NDJSON text. Chunks may split a
+///              line anywhere; the incomplete end of a chunk is completed by
+///              the next one (or by the commit).
+///
+/// Each complete line is parsed and written as it arrives, so memory use is
+/// bounded by the chunk size. An invalid line, a block of another document
+/// or a failed insert rolls the whole batch back and forgets it.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"batch_id": ..., "doc_id": ..., "count": ...}` on success, `count`
+/// being the blocks written so far.
 ///
-/// Returns `RtflowResult` with `ok = true` and `data = "{}"` on success.
 /// The returned pointer must be freed with `rtflow_free`.
-#[cfg(test)]
-pub fn rtflow_init_memory() -> *mut RtflowResult {
-    use rt_core::db::create_memory_pool;
-    match create_memory_pool() {
-        Ok(pool) => {
-            if DB_POOL.set(pool).is_err() {
-                return RtflowResult::failure(
-                    "Database already initialized; rtflow_init_memory may only be called once.",
-                );
-            }
-            RtflowResult::success("{}")
-        }
-        Err(e) => RtflowResult::failure(&e.to_string()),
-    }
+///
+/// # Safety
+///
+/// Both pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_ingest_append(
+    batch_id: *const c_char,
+    chunk: *const c_char,
+) -> *mut RtflowResult {
+    let id = match parse_batch_id(batch_id) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let chunk_str = match cstring_to_str(chunk) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    batch_result(batch::append(id, &chunk_str))
 }
 
-// ---------------------------------------------------------------------------
-// Tests
-// ---------------------------------------------------------------------------
+/// Store an ingest batch: commit its transaction, then fingerprint the
+/// document and look for near duplicates as `rtflow_ingest_blocks` does.
+///
+/// `batch_id` — null-terminated UTF-8 string: `batch_id` returned by
+///              `rtflow_ingest_begin`.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"doc_id": ..., "count": ..., "near_duplicates": [...], "warnings": [...]}`
+/// on success, as for `rtflow_ingest_blocks`. The batch is forgotten either
+/// way.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `batch_id` must be a valid, non-null, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_ingest_commit(batch_id: *const c_char) -> *mut RtflowResult {
+    let id = match parse_batch_id(batch_id) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&e),
+    };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::ffi::CString;
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
 
-    use chrono::Utc;
-    use rt_core::block::{Block, BlockType, Document, DocumentType};
-    use rt_core::db::{create_memory_pool, DbPool, SqliteBlockStore, BlockStore};
-    use rt_core::schema::SCHEMA_VERSION;
+    let status = match batch::commit(id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
 
-    // -----------------------------------------------------------------------
-    // Helpers
-    // -----------------------------------------------------------------------
+    let (near_duplicates, warnings) = post_ingest_check(pool, &status.tenant, &status.doc_id);
 
-    /// Create an isolated in-memory pool for a single test.
-    fn make_test_pool() -> DbPool {
-        create_memory_pool().expect("in-memory pool")
-    }
+    let payload = serde_json::json!({
+        "doc_id": status.doc_id.to_string(),
+        "count": status.count,
+        "near_duplicates": near_duplicates,
+        "warnings": warnings,
+    });
 
-    fn make_test_store(pool: DbPool) -> SqliteBlockStore {
-        SqliteBlockStore::new(pool)
+    match serde_json::to_string(&payload) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
     }
+}
 
-    fn make_doc(pool: &DbPool) -> Document {
-        let doc = Document {
-            id: Uuid::new_v4(),
-            name: "test-doc".to_string(),
-            source_path: None,
-            doc_type: DocumentType::Original,
-            schema_version: SCHEMA_VERSION.to_string(),
-            normalization_version: "1.0.0".to_string(),
-            hash_contract_version: "1.0.0".to_string(),
+/// Abandon an ingest batch; nothing it wrote is kept.
+///
+/// `batch_id` — null-terminated UTF-8 string: `batch_id` returned by
+///              `rtflow_ingest_begin`.
+///
+/// Returns a `RtflowResult` whose `data` field is `{"aborted": bool}`;
+/// `false` means the batch was unknown or already finished.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `batch_id` must be a valid, non-null, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_ingest_abort(batch_id: *const c_char) -> *mut RtflowResult {
+    let id = match parse_batch_id(batch_id) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let aborted = batch::abort(id);
+    RtflowResult::success(&serde_json::json!({ "aborted": aborted }).to_string())
+}
+
+/// Parse the `batch_id` argument of the `rtflow_ingest_*` batch calls.
+///
+/// # Safety
+///
+/// `batch_id` must be a valid, non-null, null-terminated C string.
+unsafe fn parse_batch_id(batch_id: *const c_char) -> Result<Uuid, String> {
+    let batch_str = cstring_to_str(batch_id)?;
+    Uuid::parse_str(&batch_str).map_err(|e| format!("invalid batch_id UUID: {}", e))
+}
+
+fn batch_result(status: Result<batch::BatchStatus, String>) -> *mut RtflowResult {
+    match status.and_then(|s| serde_json::to_string(&s).map_err(|e| e.to_string())) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&e),
+    }
+}
+
+/// Read a `.docx` file directly and ingest it under `doc_id`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `path_ptr`   — null-terminated UTF-8 string: filesystem path of the .docx.
+/// `doc_id_ptr` — null-terminated UTF-8 string: UUID for the new document.
+///
+/// Paragraphs, numbering, tables, run formatting and tracked changes are
+/// extracted into blocks and persisted together with a new document record.
+/// Blocks are hashed under the contract set by `rtflow_configure_hashing`.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"doc_id": ..., "count": ..., "doc_type": ..., "near_duplicates": [...],
+/// "warnings": [...]}` on success; see `rtflow_ingest_blocks`.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "ingest")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_ingest_docx(
+    tenant_id: *const c_char,
+    path_ptr: *const c_char,
+    doc_id_ptr: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    ingest_docx_with(&tenant, path_ptr, doc_id_ptr, &DocxIngestOptions::default())
+}
+
+/// [`rtflow_ingest_docx`] with ingest options.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `options_json` — null-terminated UTF-8 string: JSON object
+///                  `{"max_block_tokens": 512}`; blocks longer than
+///                  `max_block_tokens` tokens are split at sentence
+///                  boundaries into child blocks. `"{}"` uses the defaults.
+///                  `"previous_version_id"` links the new document as the
+///                  next version of that stored document (see
+///                  `rtflow_link_version`), which is checked before ingesting.
+///                  `"anchor_version"` selects the anchor strategy, e.g.
+///                  `"2+shingles"` (see `rt_core::anchor_strategy`); the
+///                  default is `"1"`.
+///
+/// Returns the same payload as `rtflow_ingest_docx`; `count` includes the
+/// generated child blocks.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "ingest")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_ingest_docx_with_options(
+    tenant_id: *const c_char,
+    path_ptr: *const c_char,
+    doc_id_ptr: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let options: DocxIngestOptions = match serde_json::from_str(&options_str) {
+        Ok(o) => o,
+        Err(e) => return RtflowResult::failure(&format!("invalid ingest options JSON: {}", e)),
+    };
+
+    ingest_docx_with(&tenant, path_ptr, doc_id_ptr, &options)
+}
+
+/// Options of `rtflow_ingest_docx_with_options`: the fields of
+/// [`rt_ingest::IngestOptions`] and the document the new one is a version
+/// of. (Flattening `IngestOptions` would lose `deny_unknown_fields`.)
+#[cfg(feature = "ingest")]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DocxIngestOptions {
+    max_block_tokens: Option<usize>,
+    previous_version_id: Option<Uuid>,
+    anchor_version: Option<String>,
+}
+
+/// [`check_link`] or [`link_version`].
+#[cfg(feature = "ingest")]
+type VersionLinkFn =
+    fn(&rusqlite::Connection, &TenantContext, &Uuid, &Uuid) -> rt_core::Result<()>;
+
+/// Shared body of the `.docx` ingest entry points.
+#[cfg(feature = "ingest")]
+unsafe fn ingest_docx_with(
+    tenant: &TenantContext,
+    path_ptr: *const c_char,
+    doc_id_ptr: *const c_char,
+    options: &DocxIngestOptions,
+) -> *mut RtflowResult {
+    let path = match cstring_to_str(path_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let hasher = match current_hasher(pool) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, tenant);
+
+    // Refuse a link that cannot be made before ingesting anything.
+    let link = |link: VersionLinkFn| -> Result<(), String> {
+        let Some(previous) = &options.previous_version_id else {
+            return Ok(());
+        };
+        let conn = pool.get().map_err(|e| e.to_string())?;
+        link(&conn, store.tenant(), &doc_id, previous).map_err(|e| e.to_string())
+    };
+    if let Err(e) = link(check_link) {
+        return RtflowResult::failure(&e);
+    }
+
+    let summary = match rt_ingest::ingest_docx_with_options(
+        &store,
+        std::path::Path::new(&path),
+        doc_id,
+        &hasher,
+        &rt_ingest::IngestOptions {
+            max_block_tokens: options.max_block_tokens,
+            anchor_version: options.anchor_version.clone(),
+        },
+    ) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&format!("failed to ingest docx: {}", e)),
+    };
+
+    if let Err(e) = link(link_version) {
+        return RtflowResult::failure(&format!(
+            "document {} was ingested but not linked to its previous version: {}",
+            doc_id, e
+        ));
+    }
+
+    let (near_duplicates, warnings) = post_ingest_check(pool, tenant, &doc_id);
+
+    let payload = serde_json::json!({
+        "doc_id": doc_id.to_string(),
+        "count": summary.block_count,
+        "doc_type": summary.document.doc_type.as_str(),
+        "near_duplicates": near_duplicates,
+        "warnings": warnings,
+    });
+
+    match serde_json::to_string(&payload) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Segment plain contract text (OCR output, pasted text) into blocks and
+/// ingest it under `doc_id`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `text_ptr`     — null-terminated UTF-8 string: the text.
+/// `doc_id_ptr`   — null-terminated UTF-8 string: UUID for the new document.
+/// `options_json` — null-terminated UTF-8 string: JSON object
+///                  `{"name": "msa.txt", "max_block_tokens": 512}`; `name`
+///                  defaults to the document UUID. `"anchor_version"`
+///                  selects the anchor strategy as for
+///                  `rtflow_ingest_docx_with_options`. `"{}"` uses the
+///                  defaults.
+///
+/// Blocks start at lines opening with a numbering label (`ARTICLE IV`,
+/// `1.2`, `(a)`, `1.2(a)`, …), nested by the kind of label; see
+/// `rt_ingest::text`. Blocks are hashed under the contract set by
+/// `rtflow_configure_hashing`.
+///
+/// Returns the same payload as `rtflow_ingest_docx`. Fails when the text is
+/// blank.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "ingest")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_ingest_text(
+    tenant_id: *const c_char,
+    text_ptr: *const c_char,
+    doc_id_ptr: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let text = match cstring_to_str(text_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let options: TextIngestOptions = match serde_json::from_str(&options_str) {
+        Ok(o) => o,
+        Err(e) => return RtflowResult::failure(&format!("invalid ingest options JSON: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let hasher = match current_hasher(pool) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let name = options.name.unwrap_or_else(|| doc_id.to_string());
+    let summary = match rt_ingest::ingest_text(
+        &block_store(pool, &tenant),
+        &text,
+        &name,
+        doc_id,
+        &hasher,
+        &rt_ingest::IngestOptions {
+            max_block_tokens: options.max_block_tokens,
+            anchor_version: options.anchor_version.clone(),
+        },
+    ) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&format!("failed to ingest text: {}", e)),
+    };
+
+    let (near_duplicates, warnings) = post_ingest_check(pool, &tenant, &doc_id);
+
+    let payload = serde_json::json!({
+        "doc_id": doc_id.to_string(),
+        "count": summary.block_count,
+        "doc_type": summary.document.doc_type.as_str(),
+        "near_duplicates": near_duplicates,
+        "warnings": warnings,
+    });
+
+    match serde_json::to_string(&payload) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Options of `rtflow_ingest_text`.
+#[cfg(feature = "ingest")]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TextIngestOptions {
+    name: Option<String>,
+    max_block_tokens: Option<usize>,
+    anchor_version: Option<String>,
+}
+
+/// Re-read a `.docx` file into the already ingested document `doc_id`,
+/// writing only the blocks that changed.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `path_ptr`     — null-terminated UTF-8 string: filesystem path of the .docx.
+/// `doc_id_ptr`   — null-terminated UTF-8 string: UUID of the stored document.
+/// `options_json` — null-terminated UTF-8 string: ingest options as for
+///                  `rtflow_ingest_docx_with_options`, without
+///                  `previous_version_id`; `"{}"` uses the defaults and
+///                  keeps the document's anchor strategy.
+///
+/// Incoming blocks are matched to stored ones by anchor signature, then
+/// clause hash, then structural path. Matched blocks keep their ids, so
+/// existing deltas, conflicts and overrides still refer to them.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"doc_id": ..., "doc_type": ..., "unchanged": n, "inserted": [...],
+/// "updated": [...], "deleted": [...], "near_duplicates": [...],
+/// "warnings": [...]}` on success, listing block ids. Fails when `doc_id`
+/// is not stored.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "ingest")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_reingest_docx(
+    tenant_id: *const c_char,
+    path_ptr: *const c_char,
+    doc_id_ptr: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let path = match cstring_to_str(path_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+
+    let options: rt_ingest::IngestOptions = match serde_json::from_str(&options_str) {
+        Ok(o) => o,
+        Err(e) => return RtflowResult::failure(&format!("invalid ingest options JSON: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let hasher = match current_hasher(pool) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, &tenant);
+
+    let summary = match rt_ingest::reingest_docx(
+        &store,
+        std::path::Path::new(&path),
+        doc_id,
+        &hasher,
+        &options,
+    ) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&format!("failed to re-ingest docx: {}", e)),
+    };
+
+    let (near_duplicates, warnings) = post_ingest_check(pool, &tenant, &doc_id);
+
+    let payload = serde_json::json!({
+        "doc_id": doc_id.to_string(),
+        "doc_type": summary.document.doc_type.as_str(),
+        "unchanged": summary.unchanged,
+        "inserted": summary.inserted,
+        "updated": summary.updated,
+        "deleted": summary.deleted,
+        "near_duplicates": near_duplicates,
+        "warnings": warnings,
+    });
+
+    match serde_json::to_string(&payload) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Store the changes-accepted or changes-rejected reading of a redline as a
+/// new document, so compare and merge can run against the intended
+/// baseline.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id_ptr` — null-terminated UTF-8 string: UUID of the stored document.
+/// `mode`       — null-terminated UTF-8 string: `"accepted"` (every tracked
+///                change accepted) or `"rejected"` (every one rejected).
+///
+/// Tracked changes are read from the runs of the document's blocks; blocks
+/// ingested before runs recorded their revision read the same in both
+/// views. The view's metadata carries `view_of`, the id of the redline, and
+/// `view`, the mode.
+///
+/// Returns a `RtflowResult` whose `data` field is the view `Document` JSON
+/// object on success. Fails when `doc_id` is not stored.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "ingest")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_materialize_view(
+    tenant_id: *const c_char,
+    doc_id_ptr: *const c_char,
+    mode: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let mode_str = match cstring_to_str(mode) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+
+    let view = match mode_str.as_str() {
+        "accepted" => rt_ingest::RedlineView::Accepted,
+        "rejected" => rt_ingest::RedlineView::Rejected,
+        other => {
+            return RtflowResult::failure(&format!(
+                "unknown view mode '{}'; expected accepted or rejected",
+                other
+            ))
+        }
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let hasher = match current_hasher(pool) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, &tenant);
+    let document = match rt_ingest::materialize_view(&store, doc_id, view, &hasher) {
+        Ok(d) => d,
+        Err(e) => return RtflowResult::failure(&format!("failed to materialize view: {}", e)),
+    };
+
+    match serde_json::to_string(&document) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// List stored documents that are near duplicates of `doc_id`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id_ptr` — null-terminated UTF-8 string: UUID of the document.
+/// `threshold`  — minimum similarity in `(0, 1]`; pass `0` for the default
+///                of `0.95` used by the ingest check.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `{"document_id", "name", "similarity"}` objects, most similar first.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `doc_id_ptr` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_find_near_duplicates(
+    tenant_id: *const c_char,
+    doc_id_ptr: *const c_char,
+    threshold: f64,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+
+    let threshold = if threshold == 0.0 {
+        NEAR_DUPLICATE_THRESHOLD
+    } else {
+        threshold
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let duplicates = match find_near_duplicates(&conn, &tenant, &doc_id, threshold) {
+        Ok(d) => d,
+        Err(e) => return RtflowResult::failure(&format!("failed to find near duplicates: {}", e)),
+    };
+
+    match serde_json::to_string(&duplicates) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Return the defined-term dictionary of a stored document.
+///
+/// The dictionary is extracted from the document's definition clauses
+/// (`“Borrower” means …`, `(the “Lender”)`) when it is ingested, and the
+/// document's tokens are re-classified against it.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id_ptr` — null-terminated UTF-8 document UUID string.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `{ term, normalized, block_id }` objects in document order on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `doc_id_ptr` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_get_defined_terms(
+    tenant_id: *const c_char,
+    doc_id_ptr: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let terms = match get_defined_terms(&conn, &tenant, &doc_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&format!("failed to load defined terms: {}", e)),
+    };
+
+    match serde_json::to_string(&terms) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Return the parties and dates of a stored document.
+///
+/// Parties are read from the definitions in the document's preamble
+/// (`ACME Corp. (the “Lender”)`) and dates from all of its text when it is
+/// ingested; tokens naming a party are stored as `party_ref` tokens and
+/// tokens of a date as `date_ref` tokens.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id_ptr` — null-terminated UTF-8 document UUID string.
+///
+/// Returns a `RtflowResult` whose `data` field is a `DocumentEntities` JSON
+/// object — `parties` (`{ role, normalized, name, block_id }`) and `dates`
+/// (`{ text, value, block_id, offset }`), each in document order — on
+/// success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `doc_id_ptr` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_get_entities(
+    tenant_id: *const c_char,
+    doc_id_ptr: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let entities = match get_document_entities(&conn, &tenant, &doc_id) {
+        Ok(e) => e,
+        Err(e) => return RtflowResult::failure(&format!("failed to load entities: {}", e)),
+    };
+
+    match serde_json::to_string(&entities) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Return the cross-references of a stored document.
+///
+/// References such as `Section 5.2(b)` or `Clauses 4.1 and 4.2` are
+/// extracted from the document's text when it is ingested.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id_ptr` — null-terminated UTF-8 document UUID string.
+/// `target_ptr` — null-terminated UTF-8 structural path (e.g. `"5.2"`); only
+///                references to it are returned. Empty for all references.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `{ block_id, text, target, offset }` objects in document order on
+/// success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_get_cross_references(
+    tenant_id: *const c_char,
+    doc_id_ptr: *const c_char,
+    target_ptr: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let target = match cstring_to_str(target_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+    let target = (!target.trim().is_empty()).then(|| normalize_path(&target));
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let references =
+        match get_cross_references(&conn, &tenant, &doc_id, target.as_deref()) {
+            Ok(r) => r,
+            Err(e) => {
+                return RtflowResult::failure(&format!("failed to load cross-references: {}", e))
+            }
+        };
+
+    match serde_json::to_string(&references) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Resolve a deep-link anchor to current block ids.
+///
+/// Compare results carry content-derived `anchors` for every delta and its
+/// blocks. Anchors issued for earlier versions of a document keep resolving
+/// after re-ingestion, to the block that kept the id.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `query_json` — null-terminated UTF-8 JSON object: `"anchor"` plus the
+/// document it belongs to — `"doc_id"` for a block anchor, `"left_doc_id"` /
+/// `"right_doc_id"` for the sides a delta anchor has.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"kind": "block", "anchor", "block_id"}` or
+/// `{"kind": "delta", "anchor", "left", "right"}` (each side
+/// `{"anchor", "block_id"}` or null) on success; `block_id` is null when the
+/// block has since been deleted. Fails for an anchor never issued in its
+/// document.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `query_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_resolve_anchor(
+    tenant_id: *const c_char,
+    query_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let query_str = match cstring_to_str(query_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let query: AnchorQuery = match deserialize_json(&query_str) {
+        Ok(q) => q,
+        Err(e) => return RtflowResult::failure(&format!("invalid anchor query: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let resolution = match resolve_link_anchor(&conn, &tenant, &query) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("failed to resolve anchor: {}", e)),
+    };
+
+    match serde_json::to_string(&resolution) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Store a point-in-time snapshot of a document for audit.
+///
+/// The document and its block tree are deep-copied into a new `snapshot`
+/// document under new ids; its metadata carries `snapshot_of`, the id of
+/// the original. Later edits to the original do not reach the snapshot.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id_ptr` — null-terminated UTF-8 document UUID string.
+///
+/// Returns a `RtflowResult` whose `data` field is the snapshot `Document`
+/// JSON object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `doc_id_ptr` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_create_snapshot(
+    tenant_id: *const c_char,
+    doc_id_ptr: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(doc_id_ptr) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let snapshot = match create_snapshot(&conn, &tenant, &doc_id) {
+        Ok(d) => d,
+        Err(e) => return RtflowResult::failure(&format!("failed to create snapshot: {}", e)),
+    };
+
+    match serde_json::to_string(&snapshot) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// List stored documents matching a filter, oldest ingest first.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `filter_json` — null-terminated UTF-8 JSON object with optional
+/// `doc_type`, `name_contains` (case-insensitive), `ingested_from`
+/// (inclusive), `ingested_to` (exclusive), `metadata_key` and
+/// `metadata_value` filters plus `offset` / `limit` pagination. An empty
+/// string lists the first page of all documents.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of `Document`
+/// objects on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `filter_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_list_documents(
+    tenant_id: *const c_char,
+    filter_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let filter_str = match cstring_to_str(filter_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let filter: DocumentFilter = if filter_str.trim().is_empty() {
+        DocumentFilter::default()
+    } else {
+        match serde_json::from_str(&filter_str) {
+            Ok(f) => f,
+            Err(e) => return RtflowResult::failure(&format!("invalid document filter: {}", e)),
+        }
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, &tenant);
+    match store.list_documents(&filter) {
+        Ok(documents) => match serde_json::to_string(&documents) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize documents: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Full-text search over the text of stored blocks.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `query_json` — null-terminated UTF-8 JSON object with a required `query`
+/// string, whose words must all appear in a matching block's canonical
+/// text, plus optional `document_ids`, `doc_type` and `block_type` filters
+/// and `offset` / `limit` pagination.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of hits
+/// (`block_id`, `document_id`, `structural_path`, `block_type`, `snippet`,
+/// `score`), best match first. Each snippet marks the matched words with
+/// `<mark>` tags; its text is not HTML-escaped.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `query_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_search_blocks(
+    tenant_id: *const c_char,
+    query_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let query_str = match cstring_to_str(query_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let mut fields = match serde_json::from_str::<serde_json::Value>(&query_str) {
+        Ok(serde_json::Value::Object(map)) => map,
+        Ok(_) => return RtflowResult::failure("invalid search query: expected a JSON object"),
+        Err(e) => return RtflowResult::failure(&format!("invalid search query: {}", e)),
+    };
+    let query = match fields.remove("query") {
+        Some(serde_json::Value::String(q)) => q,
+        _ => return RtflowResult::failure("invalid search query: `query` must be a string"),
+    };
+    let filter: BlockSearchFilter = match serde_json::from_value(fields.into()) {
+        Ok(f) => f,
+        Err(e) => return RtflowResult::failure(&format!("invalid search query: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, &tenant);
+    match store.search(&query, &filter) {
+        Ok(hits) => match serde_json::to_string(&hits) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize hits: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Check that every stored block's runs reproduce its `display_text`, for
+/// all documents matching a filter.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `filter_json` — null-terminated UTF-8 JSON object with the same filters
+/// as `rtflow_list_documents`; `offset` / `limit` are ignored and every
+/// matching document is checked. An empty string checks all documents.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `FidelityReport` objects (`document_id`, `blocks_checked`,
+/// `blocks_without_runs`, `issues`) on success, one per document.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `filter_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "ingest")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_check_fidelity(
+    tenant_id: *const c_char,
+    filter_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let filter_str = match cstring_to_str(filter_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let filter: DocumentFilter = if filter_str.trim().is_empty() {
+        DocumentFilter::default()
+    } else {
+        match serde_json::from_str(&filter_str) {
+            Ok(f) => f,
+            Err(e) => return RtflowResult::failure(&format!("invalid document filter: {}", e)),
+        }
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, &tenant);
+    match rt_ingest::check_documents(&store, &filter) {
+        Ok(reports) => match serde_json::to_string(&reports) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize reports: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Document versions
+// ---------------------------------------------------------------------------
+
+/// Record that document `doc_id` is a new version of `previous_version_id`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id`              — null-terminated UTF-8 string: UUID of the newer
+///                         document.
+/// `previous_version_id` — null-terminated UTF-8 string: UUID of the document
+///                         it revises.
+///
+/// Both documents must be stored. Versions form a single chain: `doc_id`
+/// must not revise another document yet, `previous_version_id` must not
+/// have a newer version yet, and the link must not close a cycle. To link
+/// while ingesting, see the `previous_version_id` ingest options.
+///
+/// Returns a `RtflowResult` whose `data` field is the chain as for
+/// `rtflow_document_versions` on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_link_version(
+    tenant_id: *const c_char,
+    doc_id: *const c_char,
+    previous_version_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_str = match cstring_to_str(doc_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let previous_str = match cstring_to_str(previous_version_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&doc_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document UUID: {}", e)),
+    };
+
+    let previous = match Uuid::parse_str(&previous_str) {
+        Ok(id) => id,
+        Err(e) => {
+            return RtflowResult::failure(&format!("invalid previous_version_id UUID: {}", e))
+        }
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let chain = link_version(&conn, &tenant, &id, &previous)
+        .and_then(|()| version_chain(&conn, &tenant, &id));
+    match chain.map_err(|e| e.to_string()).and_then(|c| {
+        serde_json::to_string(&c).map_err(|e| format!("failed to serialize versions: {}", e))
+    }) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&e),
+    }
+}
+
+/// List the version chain a document belongs to.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id` — null-terminated UTF-8 string: UUID of any document in the
+///            chain.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array, oldest
+/// version first, of `{"doc_id": ..., "version": n, "name": ...,
+/// "ingested_at": ..., "previous_version_id": ...}` on success; `version`
+/// counts from 1. A document never linked is a chain of one.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `doc_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_document_versions(
+    tenant_id: *const c_char,
+    doc_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let chain = match load_version_chain(&tenant, doc_id) {
+        Ok(c) => c,
+        Err(failure) => return failure,
+    };
+
+    match serde_json::to_string(&chain) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize versions: {}", e)),
+    }
+}
+
+/// The version chain of the document `doc_id` points to.
+unsafe fn load_version_chain(
+    tenant: &TenantContext,
+    doc_id: *const c_char,
+) -> Result<Vec<DocumentVersion>, *mut RtflowResult> {
+    let doc_str = cstring_to_str(doc_id).map_err(|e| RtflowResult::failure(&e))?;
+    let id = Uuid::parse_str(&doc_str)
+        .map_err(|e| RtflowResult::failure(&format!("invalid document UUID: {}", e)))?;
+    let pool = get_pool().map_err(|e| RtflowResult::failure(&e))?;
+    let conn = pool.get().map_err(|e| {
+        RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+    })?;
+    version_chain(&conn, tenant, &id)
+        .map_err(|e| RtflowResult::failure(&e.to_string()))
+}
+
+// ---------------------------------------------------------------------------
+// Clause history
+// ---------------------------------------------------------------------------
+
+/// Every stored version of the clause with a given anchor signature.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `anchor_signature` — null-terminated UTF-8 string: the block's
+///                      `anchor_signature`.
+///
+/// Returns a `RtflowResult` whose `data` field is a `ClauseHistory` JSON
+/// object, `{"anchor_signature": ..., "versions": [...]}`. Versions are
+/// ordered by document ingestion time; each carries its document, block and
+/// text, a `changed` flag and the token `diff` from the previous version.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `anchor_signature` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_clause_history(
+    tenant_id: *const c_char,
+    anchor_signature: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let anchor = match cstring_to_str(anchor_signature) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    if anchor.is_empty() {
+        return RtflowResult::failure("anchor_signature must not be empty");
+    }
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, &tenant);
+    match ClauseHistory::load(&store, &anchor) {
+        Ok(history) => match serde_json::to_string(&history) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize clause history: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// The clauses of a document that also appear elsewhere, in the same
+/// document or in other stored documents, grouped by `clause_hash`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id` — null-terminated UTF-8 string: the document's UUID.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `{"clause_hash", "display_text", "document_count", "occurrences": [...]}`
+/// clusters, in the order of their first occurrence in the document. Each
+/// occurrence names its document and block; the document's own come first.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `doc_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_find_duplicate_clauses(
+    tenant_id: *const c_char,
+    doc_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id_str = match cstring_to_str(doc_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let id = match Uuid::parse_str(&id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid doc_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, &tenant);
+    match find_duplicate_clauses(&store, &id) {
+        Ok(clusters) => match serde_json::to_string(&clusters) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize clusters: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Compare
+// ---------------------------------------------------------------------------
+
+/// Compare two documents identified by their UUIDs.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `left_doc_id`   — null-terminated UTF-8 string: UUID of the left document.
+/// `right_doc_id`  — null-terminated UTF-8 string: UUID of the right document.
+/// `options_json`  — null-terminated UTF-8 string: JSON object with compare
+///                   options (may be `"{}"` for defaults).
+///
+/// `options_json` is parsed into a `CompareConfig`; every field
+/// (`similarity_threshold`, `move_threshold`, `move_distance_max`,
+/// `split_threshold`, `ignore_renumbering`, `char_diff_threshold`,
+/// `sentence_diffs`, `worker_threads`, `max_block_tokens`,
+/// `max_diff_groups`, `output_mode`, `omit_unchanged`) is optional;
+/// `"split_threshold": null`
+/// disables split / merge detection, and `"ignore_renumbering": true` keeps
+/// clauses renumbered by an insertion or deletion from being reported as
+/// moved. `"char_diff_threshold": 0.8` adds character-level `char_diffs` to
+/// substituted token pairs at least that similar, and `"sentence_diffs":
+/// true` adds each changed delta's diff grouped by sentence (`sentences`,
+/// each `unchanged`, `changed`, `inserted` or `deleted`). `"output_mode"` is
+/// `"full"` (default), `"changes_only"` (no unchanged deltas, equal token
+/// groups without tokens) or `"stats_only"` (no deltas); `"omit_unchanged":
+/// true` drops unchanged deltas in any mode, keeping `full` token diffs for
+/// the rest. Unknown keys or
+/// out-of-range values produce a failure result. `"deterministic": true`
+/// makes identical inputs produce byte-identical results (ids derived from
+/// the inputs, `elapsed_ms` 0); rerunning such a compare replaces the
+/// recorded run.
+///
+/// Every delta carries content-derived deep-link `anchors` for itself and its
+/// blocks; see `rtflow_resolve_anchor`. `broken_references` lists section
+/// references in the right document whose target was deleted or renumbered.
+///
+/// A `"workflow_id"` runs the compare in that workflow's context: the
+/// workflow's compare overrides (see `rtflow_workflow_set_config`) apply to
+/// every option not given in `options_json`. With it, `"progress_events"`
+/// (`true`, or `{"min_interval_ms": 5000, "min_step": 10}`) records the
+/// run's progress as throttled `compare_progress` events in the workflow's
+/// event log; failing to record one does not fail the run.
+///
+/// The run is recorded in the run history with its `manifest`; see
+/// `rtflow_reproduce_check`. Its deltas are stored by section (each delta's
+/// `section`); see `rtflow_compare_sections`.
+///
+/// Returns a `RtflowResult` whose `data` field is a `CompareResult` JSON
+/// object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare(
+    tenant_id: *const c_char,
+    left_doc_id: *const c_char,
+    right_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let (result, _, _) = match run_compare(&tenant, left_doc_id, right_doc_id, options_json) {
+        Ok(r) => r,
+        Err(failure) => return failure,
+    };
+
+    match serde_json::to_string(&result) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize CompareResult: {}", e)),
+    }
+}
+
+/// Compare the latest version of a document with the version before it.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `doc_id`       — null-terminated UTF-8 string: UUID of any document in the
+///                  version chain (see `rtflow_link_version`).
+/// `options_json` — null-terminated UTF-8 string: options as for
+///                  `rtflow_compare`.
+///
+/// Same as `rtflow_compare` with the second-latest version of the chain as
+/// the left document and the latest as the right. Fails when the chain has
+/// a single version.
+///
+/// Returns a `RtflowResult` whose `data` field is a `CompareResult` JSON
+/// object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_versions(
+    tenant_id: *const c_char,
+    doc_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let chain = match load_version_chain(&tenant, doc_id) {
+        Ok(c) => c,
+        Err(failure) => return failure,
+    };
+
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let [.., previous, latest] = chain.as_slice() else {
+        return RtflowResult::failure(&format!(
+            "document {} has no earlier version to compare with",
+            chain[0].doc_id
+        ));
+    };
+    let input = match load_compare_input_from(
+        &tenant,
+        &previous.doc_id.to_string(),
+        &latest.doc_id.to_string(),
+        &options_str,
+    ) {
+        Ok(i) => i,
+        Err(failure) => return failure,
+    };
+    let (result, _, _) = match compare_input(input) {
+        Ok(r) => r,
+        Err(failure) => return failure,
+    };
+
+    match serde_json::to_string(&result) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize CompareResult: {}", e)),
+    }
+}
+
+/// Compare two documents and render the result as terminal-friendly text.
+///
+/// Arguments are the same as for `rtflow_compare`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON object
+/// `{"diffstat": ..., "unified": ...}` holding a git-style diffstat and
+/// unified-diff-like text, each with one entry per changed block labelled
+/// by structural path.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_text(
+    tenant_id: *const c_char,
+    left_doc_id: *const c_char,
+    right_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let (result, left_blocks, right_blocks) =
+        match run_compare(&tenant, left_doc_id, right_doc_id, options_json) {
+            Ok(r) => r,
+            Err(failure) => return failure,
+        };
+
+    let payload = serde_json::json!({
+        "diffstat": render_diffstat(&result, &left_blocks, &right_blocks),
+        "unified": render_unified(&result, &left_blocks, &right_blocks),
+    });
+
+    match serde_json::to_string(&payload) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Compare two documents again after an edit, reusing an earlier comparison.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `previous`    — null-terminated UTF-8 string: either the UUID of a
+///   recorded compare run or a `CompareResult` JSON object as returned by
+///   `rtflow_compare`. Its two documents are compared again.
+/// `dirty_json`  — null-terminated UTF-8 JSON array of the UUIDs of every
+///   block, on either side, whose text, path or type changed since, and of
+///   every block added. Removed blocks need not be listed.
+/// `options_json` — compare options, as for `rtflow_compare`.
+///
+/// Deltas of blocks the edit did not touch are reused; only the rest are
+/// aligned and diffed, and the stats are recomputed. Documents with tables
+/// are compared in full. The run is recorded like one of `rtflow_compare`.
+///
+/// Returns a `RtflowResult` whose `data` field is a `CompareResult` JSON
+/// object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_incremental(
+    tenant_id: *const c_char,
+    previous: *const c_char,
+    dirty_json: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let previous_str = match cstring_to_str(previous) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let dirty_str = match cstring_to_str(dirty_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let dirty: Vec<Uuid> = match serde_json::from_str(&dirty_str) {
+        Ok(d) => d,
+        Err(e) => return RtflowResult::failure(&format!("invalid dirty block ids: {}", e)),
+    };
+    let previous = compare_deltas_of(&tenant, &previous_str);
+    let ComparedRun { left_id, right_id, deltas, .. } = match previous {
+        Ok(previous) => previous,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let input = match load_compare_input_from(
+        &tenant,
+        &left_id.to_string(),
+        &right_id.to_string(),
+        &options_str,
+    ) {
+        Ok(i) => i,
+        Err(failure) => return failure,
+    };
+    let engine = CompareEngine::new(input.config.clone());
+    let mut result = engine.compare_incremental(
+        input.left_id,
+        input.right_id,
+        &input.left_blocks,
+        &input.right_blocks,
+        &deltas,
+        &dirty,
+    );
+    let manifest = input.manifest(&engine);
+    if let Err(failure) = record_compare_run(&result.run_id, &manifest, &tenant) {
+        return failure;
+    }
+    if let Err(failure) = record_compare_deltas(&result.run_id, &result.deltas, &tenant) {
+        return failure;
+    }
+    result.manifest = Some(manifest);
+
+    match serde_json::to_string(&result) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize CompareResult: {}", e)),
+    }
+}
+
+/// Render a comparison as a self-contained HTML redline.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `run_id_or_json` — null-terminated UTF-8 string: either the UUID of a
+/// recorded compare run, whose stored deltas are rendered, or a
+/// `CompareResult` JSON object as returned by `rtflow_compare`. Both
+/// documents must still be in the store.
+///
+/// The page has its styles inlined: insertions are underlined, deletions
+/// struck through, and moved, split and merged blocks annotated. Each block
+/// carries its delta's deep-link anchor as its `id`.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON object
+/// `{"html": "..."}` on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `run_id_or_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_render_compare_html(
+    tenant_id: *const c_char,
+    run_id_or_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let input = match cstring_to_str(run_id_or_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let ComparedRun { left_id, right_id, deltas, .. } = match compare_deltas_of(&tenant, &input) {
+        Ok(compared) => compared,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, &tenant);
+    let left_blocks = match store.get_block_tree(&left_id) {
+        Ok(b) => b,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to load left document blocks: {}", e))
+        }
+    };
+    let right_blocks = match store.get_block_tree(&right_id) {
+        Ok(b) => b,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to load right document blocks: {}", e))
+        }
+    };
+
+    let html = render_deltas_html(left_id, right_id, &deltas, &left_blocks, &right_blocks);
+    match serde_json::to_string(&serde_json::json!({ "html": html })) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// A comparison loaded by [`compare_deltas_of`].
+struct ComparedRun {
+    left_id: Uuid,
+    right_id: Uuid,
+    /// The manifest's `output_mode`, when the comparison has one.
+    output_mode: Option<String>,
+    deltas: Vec<rt_compare::result::BlockDelta>,
+}
+
+/// The documents, output mode and deltas of a comparison given as either the
+/// UUID of a recorded compare run (its stored deltas) or a `CompareResult`
+/// JSON object.
+fn compare_deltas_of(tenant: &TenantContext, run_id_or_json: &str) -> Result<ComparedRun, String> {
+    let Ok(run_id) = Uuid::parse_str(run_id_or_json.trim()) else {
+        let result: CompareResult = serde_json::from_str(run_id_or_json)
+            .map_err(|e| format!("expected a run UUID or CompareResult JSON: {}", e))?;
+        return Ok(ComparedRun {
+            left_id: result.left_doc_id,
+            right_id: result.right_doc_id,
+            output_mode: result.manifest.and_then(|m| m.output_mode),
+            deltas: result.deltas,
+        });
+    };
+    let conn = get_pool()?
+        .get()
+        .map_err(|e| format!("failed to acquire database connection: {}", e))?;
+    let record =
+        get_run(&conn, tenant, &run_id).map_err(|e| format!("failed to load run: {}", e))?;
+    let (RunKind::Compare, [left, right]) = (record.run_kind, record.manifest.inputs.as_slice())
+    else {
+        return Err(format!("run {} is not a compare run", run_id));
+    };
+    let deltas = run_deltas(&conn, tenant, &run_id)
+        .map_err(|e| format!("failed to load deltas: {}", e))?
+        .into_iter()
+        .map(serde_json::from_value)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid stored delta: {}", e))?;
+    Ok(ComparedRun {
+        left_id: left.document_id,
+        right_id: right.document_id,
+        output_mode: record.manifest.output_mode.clone(),
+        deltas,
+    })
+}
+
+/// Report how two comparisons of the same document pair differ, e.g. to
+/// check that an engine upgrade or a config change does not regress the
+/// output on a corpus.
+///
+/// `before` and `after` — null-terminated UTF-8 strings, each the UUID of
+/// a recorded compare run or a `CompareResult` JSON object. Both must
+/// compare the same left and right documents with the same `output_mode`
+/// (as recorded in their manifests; `full_without_unchanged` for a `full`
+/// run with `omit_unchanged`), since the mode decides which deltas a run
+/// kept. A comparison without a manifest output mode is not checked.
+///
+/// Deltas are paired by their `left_block_id` and `right_block_id`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// Returns a `RtflowResult` whose `data` field is a `ResultDiff` JSON
+/// object on success:
+///
+/// - `appeared`: deltas only `after` has
+/// - `disappeared`: deltas only `before` has
+/// - `reclassified`: `{ left_block_id, right_block_id, before, after }`
+///   for each pair whose kind changed
+/// - `unchanged`: the number of pairs classified alike
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_result_diff(
+    tenant_id: *const c_char,
+    before: *const c_char,
+    after: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let load = |input| cstring_to_str(input).and_then(|s| compare_deltas_of(&tenant, &s));
+    let before = match load(before) {
+        Ok(c) => c,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let after = match load(after) {
+        Ok(c) => c,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    if (before.left_id, before.right_id) != (after.left_id, after.right_id) {
+        return RtflowResult::failure("the two comparisons are of different document pairs");
+    }
+    if let (Some(mode_before), Some(mode_after)) = (&before.output_mode, &after.output_mode) {
+        if mode_before != mode_after {
+            return RtflowResult::failure(&format!(
+                "the two comparisons have different output modes ({} and {}); \
+                 rerun them with the same output_mode",
+                mode_before, mode_after
+            ));
+        }
+    }
+
+    let diff = rt_compare::regression::diff_deltas(&before.deltas, &after.deltas);
+    match serde_json::to_string(&diff) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize ResultDiff: {}", e)),
+    }
+}
+
+/// Compare a base document against itself as changed by an amendment.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `base_doc_id` — null-terminated UTF-8 UUID of the amended document.
+/// `amendment_doc_id` — null-terminated UTF-8 UUID of the amendment, whose
+/// clauses give instructions ("Section 3.2 is deleted and replaced with the
+/// following: …", "Section 5.1 is amended by deleting “thirty” and replacing
+/// it with “sixty”", "A new Section 5.3 is inserted after Section 5.2: …").
+/// `options_json` — compare options, as for `rtflow_compare`.
+///
+/// The instructions are applied to the base document to build its
+/// as-amended view (see `rtflow_apply_amendment`), and the base document is
+/// compared against that view. A changed, inserted or rewritten clause has
+/// the id of the amendment block that changed it as its `right_block_id`.
+/// The run is not recorded in the run history.
+///
+/// Returns a `RtflowResult` whose `data` field is an `AmendmentCompareResult`
+/// JSON object — `compare` (a `CompareResult`), `instructions` (`{
+/// amendment_block_id, text, action, target, ... }`), `traces` (`{ delta,
+/// instruction, amendment_block_id }`, one per changed delta) and
+/// `unapplied` (`{ instruction, reason }`) — on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_amendment(
+    tenant_id: *const c_char,
+    base_doc_id: *const c_char,
+    amendment_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let input = match load_compare_input(&tenant, base_doc_id, amendment_doc_id, options_json) {
+        Ok(i) => i,
+        Err(failure) => return failure,
+    };
+    let hasher = match get_pool().and_then(current_hasher) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let engine = CompareEngine::new(input.config);
+    let result = compare_amendment(
+        &engine,
+        input.left_id,
+        input.right_id,
+        &input.left_blocks,
+        &input.right_blocks,
+        &hasher,
+    );
+    match serde_json::to_string(&result) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!(
+            "failed to serialize AmendmentCompareResult: {}",
+            e
+        )),
+    }
+}
+
+/// Build the as-amended view of a base document.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `base_doc_id` — null-terminated UTF-8 UUID of the amended document.
+/// `amendment_doc_id` — null-terminated UTF-8 UUID of the amendment.
+///
+/// Each instruction is applied in order to the base document as amended so
+/// far; one whose target clause or words are missing is skipped and listed
+/// in `unapplied`.
+///
+/// Returns a `RtflowResult` whose `data` field is an `AmendedView` JSON
+/// object — `blocks` (the as-amended document as a flat block list in
+/// document order), `instructions` and `unapplied` — on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_apply_amendment(
+    tenant_id: *const c_char,
+    base_doc_id: *const c_char,
+    amendment_doc_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let base_str = match cstring_to_str(base_doc_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let amendment_str = match cstring_to_str(amendment_doc_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let base_id = match Uuid::parse_str(&base_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid base_doc_id UUID: {}", e)),
+    };
+    let amendment_id = match Uuid::parse_str(&amendment_str) {
+        Ok(id) => id,
+        Err(e) => {
+            return RtflowResult::failure(&format!("invalid amendment_doc_id UUID: {}", e))
+        }
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let store = block_store(pool, &tenant);
+    let base_blocks = match store.get_block_tree(&base_id) {
+        Ok(b) => b,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to load base document blocks: {}", e))
+        }
+    };
+    let amendment_blocks = match store.get_block_tree(&amendment_id) {
+        Ok(b) => b,
+        Err(e) => {
+            return RtflowResult::failure(&format!(
+                "failed to load amendment document blocks: {}",
+                e
+            ))
+        }
+    };
+    let hasher = match current_hasher(pool) {
+        Ok(h) => h,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let view = apply_amendment(&base_blocks, &amendment_blocks, &hasher);
+    match serde_json::to_string(&view) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize AmendedView: {}", e)),
+    }
+}
+
+/// Start comparing two documents in the background and stream the deltas.
+///
+/// Arguments are the same as for `rtflow_compare`, plus `batch_size`: the
+/// number of aligned blocks diffed before their deltas become available to
+/// `rtflow_compare_poll` (0 for the default of 64).
+///
+/// Both documents are loaded and the options validated before this returns,
+/// so those errors are reported here rather than by the first poll.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// Returns a `RtflowResult` whose `data` field is `{"run_id": ...}` on
+/// success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_start(
+    tenant_id: *const c_char,
+    left_doc_id: *const c_char,
+    right_doc_id: *const c_char,
+    options_json: *const c_char,
+    batch_size: u32,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let input = match load_compare_input(&tenant, left_doc_id, right_doc_id, options_json) {
+        Ok(i) => i,
+        Err(failure) => return failure,
+    };
+
+    let batch_size = match batch_size {
+        0 => DEFAULT_STREAM_BATCH,
+        n => n as usize,
+    };
+    let engine = CompareEngine::new(input.config.clone());
+    let manifest = input.manifest(&engine);
+    let run_id = engine.run_id(&manifest);
+    if let Err(failure) = record_compare_run(&run_id, &manifest, &tenant) {
+        return failure;
+    }
+    let on_progress = input.progress_reporter(run_id);
+    stream::start(
+        run_id,
+        input.tenant,
+        engine,
+        manifest,
+        input.left_id,
+        input.right_id,
+        input.left_blocks,
+        input.right_blocks,
+        batch_size,
+        on_progress,
+    );
+
+    RtflowResult::success(&serde_json::json!({ "run_id": run_id }).to_string())
+}
+
+/// Fetch the deltas a streamed compare run has completed since the last poll.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `run_id`     — null-terminated UTF-8 string: `run_id` returned by
+///                `rtflow_compare_start`.
+/// `max_deltas` — at most this many deltas are returned (0 for all that are
+///                ready).
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"run_id": ..., "deltas": [...], "done": bool, "result": ...}`. Deltas
+/// arrive in document order. On the final poll `done` is `true` and `result`
+/// is the finished `CompareResult` with an empty `deltas` list (every delta
+/// has already been returned); the run is then forgotten.
+/// Returned deltas are stored by section, as for `rtflow_compare`. A run
+/// started by another tenant is reported unknown.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `run_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_poll(
+    tenant_id: *const c_char,
+    run_id: *const c_char,
+    max_deltas: u32,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let run_str = match cstring_to_str(run_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&run_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid run_id UUID: {}", e)),
+    };
+
+    match stream::poll(id, &tenant, max_deltas as usize) {
+        Ok(polled) => {
+            if let Err(failure) = record_compare_deltas(&id, &polled.deltas, &tenant) {
+                return failure;
+            }
+            match serde_json::to_string(&polled) {
+                Ok(json_out) => RtflowResult::success(&json_out),
+                Err(e) => RtflowResult::failure(&format!("failed to serialize poll: {}", e)),
+            }
+        }
+        Err(e) => RtflowResult::failure(&e),
+    }
+}
+
+/// Abandon a streamed compare run; the comparison stops and its
+/// undelivered deltas are discarded.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `run_id` — null-terminated UTF-8 string: `run_id` returned by
+///            `rtflow_compare_start`.
+///
+/// Returns a `RtflowResult` whose `data` field is `{"cancelled": bool}`;
+/// `false` means the run was unknown to the tenant or had already finished.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `run_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_cancel(
+    tenant_id: *const c_char,
+    run_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let run_str = match cstring_to_str(run_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&run_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid run_id UUID: {}", e)),
+    };
+
+    let cancelled = stream::cancel(id, &tenant);
+    RtflowResult::success(&serde_json::json!({ "cancelled": cancelled }).to_string())
+}
+
+/// Compare two documents as a background job.
+///
+/// Arguments are the same as for `rtflow_compare`. Both documents are loaded
+/// and the options validated before this returns, so those errors are
+/// reported here; the comparison itself runs on a background worker. At most
+/// four jobs run at a time; a later one reports `running` with no progress
+/// until a worker is free. Poll it with `rtflow_job_status` and collect its
+/// result with `rtflow_job_result`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"job_id": ..., "run_id": ...}` on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_async(
+    tenant_id: *const c_char,
+    left_doc_id: *const c_char,
+    right_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let input = match load_compare_input(&tenant, left_doc_id, right_doc_id, options_json) {
+        Ok(i) => i,
+        Err(failure) => return failure,
+    };
+
+    let engine = CompareEngine::new(input.config.clone());
+    let manifest = input.manifest(&engine);
+    let run_id = engine.run_id(&manifest);
+    if let Err(failure) = record_compare_run(&run_id, &manifest, &tenant) {
+        return failure;
+    }
+    let job_id = Uuid::new_v4();
+    let mut report = input.progress_reporter(run_id);
+    jobs::spawn(job_id, run_id, tenant.clone(), move |on_progress, cancel| {
+        let mut result = engine
+            .compare_cancellable(
+                input.left_id,
+                input.right_id,
+                &input.left_blocks,
+                &input.right_blocks,
+                DEFAULT_STREAM_BATCH,
+                |_| {},
+                |progress: &CompareProgress| {
+                    on_progress(progress);
+                    report(progress);
+                },
+                cancel,
+            )?;
+        result.run_id = run_id;
+        store_compare_deltas(&run_id, &result.deltas, &tenant).map_err(rt_core::RtError::Internal)?;
+        result.manifest = Some(manifest);
+        Ok(JobOutput::Compare(Box::new(result)))
+    });
+
+    RtflowResult::success(&serde_json::json!({ "job_id": job_id, "run_id": run_id }).to_string())
+}
+
+/// Report the progress of a background compare or merge job.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `job_id` — null-terminated UTF-8 string: `job_id` returned by
+///            `rtflow_compare_async` or `rtflow_merge_async`.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"job_id", "run_id", "state": "running" | "completed" | "failed" |
+/// "cancelled", "phase", "percent", "blocks_processed", "blocks_total",
+/// "error"}` on success; `run_id` is a merge job's `merge_id`. `phase` is
+/// the stage the comparison last reported (`"flatten"`, `"align_tables"`,
+/// the `"align_*"` passes, `"diff"`, `"stats"`); `percent` stays 0 until
+/// the `diff` phase. Blocks are counted by alignment, as in
+/// `compare_progress` events. A merge job reports no progress: it stays in
+/// the `flatten` phase at 0 percent. A job started by another tenant is
+/// reported unknown.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `job_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_job_status(
+    tenant_id: *const c_char,
+    job_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let job_str = match cstring_to_str(job_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&job_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid job_id UUID: {}", e)),
+    };
+
+    match jobs::status(id, &tenant) {
+        Ok(status) => match serde_json::to_string(&status) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize job status: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e),
+    }
+}
+
+/// Collect the result of a finished background compare or merge job.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `job_id` — null-terminated UTF-8 string: `job_id` returned by
+///            `rtflow_compare_async` or `rtflow_merge_async`.
+///
+/// Fails while the job is still running. Once the job has finished, the
+/// first call returns its `CompareResult` or `MergeResult` (or, for a
+/// failed job, its error) and the job is forgotten; later calls, like calls
+/// for another tenant, report it unknown. A finished job that is never
+/// collected is forgotten an hour after it finished. The result's deltas
+/// are stored by section, as for `rtflow_compare`.
+///
+/// Returns a `RtflowResult` whose `data` field is the `CompareResult` or
+/// `MergeResult` JSON object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `job_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_job_result(
+    tenant_id: *const c_char,
+    job_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let job_str = match cstring_to_str(job_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&job_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid job_id UUID: {}", e)),
+    };
+
+    match jobs::take_result(id, &tenant) {
+        Ok(result) => match serde_json::to_string(&result) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize job result: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e),
+    }
+}
+
+/// Ask a background compare or merge job to stop.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `job_id` — null-terminated UTF-8 string: `job_id` returned by
+///            `rtflow_compare_async` or `rtflow_merge_async`.
+///
+/// Cancellation is cooperative: the job stops at the engine's next check
+/// (before the next block is diffed or, for a merge, the next aligned pair
+/// is merged) and then reports the `cancelled` state; a cancelled merge is
+/// recorded `failed` and can be re-run with `rtflow_resume_merge`.
+/// Collecting its result afterwards fails and forgets the job.
+///
+/// Returns a `RtflowResult` whose `data` field is `{"cancelled": bool}`;
+/// `false` means the job was unknown to the tenant or had already finished.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `job_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_job_cancel(
+    tenant_id: *const c_char,
+    job_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let job_str = match cstring_to_str(job_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&job_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid job_id UUID: {}", e)),
+    };
+
+    let cancelled = jobs::cancel(id, &tenant);
+    RtflowResult::success(&serde_json::json!({ "cancelled": cancelled }).to_string())
+}
+
+/// Per-section delta counts of a compare run, for an outline navigation
+/// tree.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `run_id` — null-terminated UTF-8 string: `run_id` of a `rtflow_compare`
+///            or `rtflow_compare_start` run.
+///
+/// A delta's section is the structural path of the top-level block it
+/// belongs to. Only deltas already returned count: a streamed run's deltas
+/// are stored as they are polled, and deltas left out by the run's
+/// `output_mode` or `omit_unchanged` are not stored.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `{"section": ..., "total": n, "by_kind": {"modified": n, ...}}` objects,
+/// in document order, on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `run_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_sections(
+    tenant_id: *const c_char,
+    run_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let run_str = match cstring_to_str(run_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&run_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid run_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match compare_sections(&conn, &tenant, &id) {
+        Ok(sections) => match serde_json::to_string(&sections) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize sections: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// One page of the deltas of a compare run under one section.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `query_json` — null-terminated UTF-8 string: JSON object with `run_id`
+///                and `section` (as reported by `rtflow_compare_sections`),
+///                plus optional `kind` (e.g. `"modified"`), `offset` and
+///                `limit` (default 100, max 1000).
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `BlockDelta` objects in document order on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `query_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_compare_section_deltas(
+    tenant_id: *const c_char,
+    query_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let query_str = match cstring_to_str(query_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let query: SectionDeltaQuery = match serde_json::from_str(&query_str) {
+        Ok(q) => q,
+        Err(e) => return RtflowResult::failure(&format!("invalid section delta query: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match section_deltas(&conn, &tenant, &query) {
+        Ok(deltas) => match serde_json::to_string(&deltas) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize deltas: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Flatten a `CompareResult` into annotation records for an external
+/// summarizer.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `compare_result_json` — null-terminated UTF-8 string: a `CompareResult`
+///   JSON object as returned by `rtflow_compare`. Both of its documents must
+///   still be in the store.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON object
+/// `{"count": n, "jsonl": "..."}`; `jsonl` holds one record per changed
+/// block with `delta_id`, `kind`, `path`, `section_heading`, `before`,
+/// `after`, `tags` and `risk_score`.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `compare_result_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_export_annotations(
+    tenant_id: *const c_char,
+    compare_result_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let json = match cstring_to_str(compare_result_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let result: CompareResult = match serde_json::from_str(&json) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("invalid CompareResult JSON: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let store = block_store(pool, &tenant);
+
+    let left_blocks = match store.get_block_tree(&result.left_doc_id) {
+        Ok(b) => b,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to load left document blocks: {}", e))
+        }
+    };
+    let right_blocks = match store.get_block_tree(&result.right_doc_id) {
+        Ok(b) => b,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to load right document blocks: {}", e))
+        }
+    };
+
+    let annotations = export_annotations(&result, &left_blocks, &right_blocks);
+    let jsonl = match annotations_to_jsonl(&annotations) {
+        Ok(j) => j,
+        Err(e) => return RtflowResult::failure(&format!("failed to serialize annotations: {}", e)),
+    };
+
+    let payload = serde_json::json!({
+        "count": annotations.len(),
+        "jsonl": jsonl,
+    });
+
+    match serde_json::to_string(&payload) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Attach summarizer output to the deltas of a `CompareResult`.
+///
+/// `compare_result_json` — null-terminated UTF-8 string: the `CompareResult`
+///   the annotations were exported from.
+/// `summaries_jsonl`     — null-terminated UTF-8 string: one JSON object per
+///   line with `delta_id` and `summary`; other fields are ignored, so the
+///   exported records with a `summary` added are accepted.
+///
+/// Returns a `RtflowResult` whose `data` field is the `CompareResult` JSON
+/// object with each summary in its delta's `summary` field.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// Both pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_attach_summaries(
+    compare_result_json: *const c_char,
+    summaries_jsonl: *const c_char,
+) -> *mut RtflowResult {
+    let json = match cstring_to_str(compare_result_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let jsonl = match cstring_to_str(summaries_jsonl) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let mut result: CompareResult = match serde_json::from_str(&json) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("invalid CompareResult JSON: {}", e)),
+    };
+
+    let attached = parse_summaries(&jsonl).and_then(|s| attach_summaries(&mut result, &s));
+    if let Err(e) = attached {
+        return RtflowResult::failure(&e.to_string());
+    }
+
+    match serde_json::to_string(&result) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize CompareResult: {}", e)),
+    }
+}
+
+/// Shared argument parsing, block loading and comparison for the compare
+/// entry points. Returns the result together with both block trees, or a
+/// ready-made failure result.
+///
+/// # Safety
+///
+/// All pointer arguments must be valid, non-null, null-terminated C strings.
+unsafe fn run_compare(
+    tenant: &TenantContext,
+    left_doc_id: *const c_char,
+    right_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> Result<(CompareResult, Vec<Block>, Vec<Block>), *mut RtflowResult> {
+    let input = load_compare_input(tenant, left_doc_id, right_doc_id, options_json)?;
+    compare_input(input)
+}
+
+/// Run the comparison of [`run_compare`] on `input`.
+fn compare_input(
+    input: CompareInput,
+) -> Result<(CompareResult, Vec<Block>, Vec<Block>), *mut RtflowResult> {
+    let engine = CompareEngine::new(input.config.clone());
+    let manifest = input.manifest(&engine);
+    // Progress is reported per batch, so batch only when it is recorded.
+    let run_id = engine.run_id(&manifest);
+    let batch_size = if input.reports_progress() { DEFAULT_STREAM_BATCH } else { usize::MAX };
+    let mut result = engine.compare_with_progress(
+        input.left_id,
+        input.right_id,
+        &input.left_blocks,
+        &input.right_blocks,
+        batch_size,
+        |_| {},
+        input.progress_reporter(run_id),
+    );
+    result.run_id = run_id;
+    record_compare_run(&result.run_id, &manifest, &input.tenant)?;
+    record_compare_deltas(&result.run_id, &result.deltas, &input.tenant)?;
+    result.manifest = Some(manifest);
+    Ok((result, input.left_blocks, input.right_blocks))
+}
+
+/// Everything a compare run needs, loaded and validated up front.
+struct CompareInput {
+    config: CompareConfig,
+    left_id: Uuid,
+    right_id: Uuid,
+    left_blocks: Vec<Block>,
+    right_blocks: Vec<Block>,
+    documents: [Document; 2],
+    /// Tenant the run acts for.
+    tenant: TenantContext,
+    /// Workflow that records the run's progress, from `"progress_events"`.
+    #[cfg(feature = "workflow")]
+    progress: Option<ProgressTarget>,
+}
+
+/// Where and how often a compare run records `CompareProgress` events.
+#[cfg(feature = "workflow")]
+struct ProgressTarget {
+    workflow_id: Uuid,
+    settings: ProgressSettings,
+}
+
+impl CompareInput {
+    /// The manifest of `engine` comparing this input, including the ingest
+    /// versions of both documents.
+    fn manifest(&self, engine: &CompareEngine) -> RunManifest {
+        let mut manifest = engine.manifest(&[
+            InputDigest::from_blocks(self.left_id, &self.left_blocks),
+            InputDigest::from_blocks(self.right_id, &self.right_blocks),
+        ]);
+        for document in &self.documents {
+            manifest.attach_document(document);
+        }
+        manifest
+    }
+
+    /// Whether the run records progress events.
+    fn reports_progress(&self) -> bool {
+        #[cfg(feature = "workflow")]
+        return self.progress.is_some();
+        #[cfg(not(feature = "workflow"))]
+        false
+    }
+
+    /// Progress callback of compare run `run_id`: records the reports its
+    /// throttle admits as `CompareProgress` events of the target workflow.
+    /// Progress events are breadcrumbs, so a failure to record one is
+    /// ignored rather than failing the run.
+    #[cfg(feature = "workflow")]
+    fn progress_reporter(&self, run_id: Uuid) -> impl FnMut(&CompareProgress) + Send + 'static {
+        let tenant = self.tenant.clone();
+        let mut target = self
+            .progress
+            .as_ref()
+            .map(|t| (t.workflow_id, ProgressThrottle::new(t.settings.clone())));
+        move |progress: &CompareProgress| {
+            let Some((workflow_id, throttle)) = target.as_mut() else {
+                return;
+            };
+            if !throttle.admit(progress.percent(), std::time::Instant::now()) {
+                return;
+            }
+            let Ok(conn) = get_pool().and_then(|p| p.get().map_err(|e| e.to_string())) else {
+                return;
+            };
+            let _ = record_progress(
+                &conn,
+                &tenant,
+                *workflow_id,
+                run_id,
+                progress.blocks_processed,
+                progress.blocks_total,
+            );
+        }
+    }
+
+    /// Without workflow support no progress is recorded.
+    #[cfg(not(feature = "workflow"))]
+    fn progress_reporter(&self, _run_id: Uuid) -> impl FnMut(&CompareProgress) + Send + 'static {
+        |_: &CompareProgress| {}
+    }
+}
+
+/// Take `"progress_events"` out of a compare options object. It requires a
+/// `"workflow_id"` (left in place for [`workflow_options`]) naming a live
+/// workflow, and is either `true` for the default [`ProgressSettings`] or a
+/// settings object; `false` or `null` record nothing.
+#[cfg(feature = "workflow")]
+fn progress_options(
+    tenant: &TenantContext,
+    options_str: &str,
+) -> Result<(String, Option<ProgressTarget>), String> {
+    let mut options = match serde_json::from_str::<serde_json::Value>(options_str) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => return Ok((options_str.to_owned(), None)),
+    };
+    let settings = match options.remove("progress_events") {
+        None | Some(serde_json::Value::Null) | Some(serde_json::Value::Bool(false)) => {
+            return Ok((options_str.to_owned(), None))
+        }
+        Some(serde_json::Value::Bool(true)) => ProgressSettings::default(),
+        Some(value) => serde_json::from_value::<ProgressSettings>(value)
+            .map_err(|e| format!("invalid progress_events: {}", e))?,
+    };
+    settings.validate().map_err(|e| e.to_string())?;
+    let workflow_id = options
+        .get("workflow_id")
+        .and_then(|v| v.as_str())
+        .and_then(|s| Uuid::parse_str(s).ok())
+        .ok_or_else(|| "progress_events requires a workflow_id".to_owned())?;
+
+    let pool = get_pool()?;
+    let conn = pool
+        .get()
+        .map_err(|e| format!("failed to acquire database connection: {}", e))?;
+    let workflow = WorkflowEngine::get_workflow(&conn, tenant, workflow_id)
+        .map_err(|e| e.to_string())?;
+    let approvals = ApprovalStatus::default();
+    if validate_transition(&workflow.state, &EventType::CompareProgress, &approvals).is_err() {
+        return Err(format!(
+            "workflow {} is {} and cannot record progress",
+            workflow_id,
+            workflow.state.as_str()
+        ));
+    }
+
+    let target = ProgressTarget { workflow_id, settings };
+    Ok((serde_json::Value::Object(options).to_string(), Some(target)))
+}
+
+/// Store `deltas` of compare run `run_id` by section, after those already
+/// stored, for `rtflow_compare_sections` / `rtflow_compare_section_deltas`.
+fn record_compare_deltas(
+    run_id: &Uuid,
+    deltas: &[rt_compare::result::BlockDelta],
+    tenant: &TenantContext,
+) -> Result<(), *mut RtflowResult> {
+    store_compare_deltas(run_id, deltas, tenant).map_err(|e| RtflowResult::failure(&e))
+}
+
+/// As [`record_compare_deltas`], with the error as text (for background
+/// jobs).
+fn store_compare_deltas(
+    run_id: &Uuid,
+    deltas: &[rt_compare::result::BlockDelta],
+    tenant: &TenantContext,
+) -> Result<(), String> {
+    let mut stored = Vec::with_capacity(deltas.len());
+    for delta in deltas {
+        let value = serde_json::to_value(delta)
+            .map_err(|e| format!("failed to serialize delta: {}", e))?;
+        stored.push(SectionDelta {
+            section: delta.section.clone().unwrap_or_default(),
+            kind: value["kind"].as_str().unwrap_or_default().to_owned(),
+            delta: value,
+        });
+    }
+    let pool = get_pool()?;
+    let conn = pool
+        .get()
+        .map_err(|e| format!("failed to acquire database connection: {}", e))?;
+    append_compare_deltas(&conn, tenant, run_id, &stored)
+        .map_err(|e| format!("failed to record deltas: {}", e))
+}
+
+/// Record compare run `run_id` in `tenant`'s run history.
+fn record_compare_run(
+    run_id: &Uuid,
+    manifest: &RunManifest,
+    tenant: &TenantContext,
+) -> Result<(), *mut RtflowResult> {
+    let pool = get_pool().map_err(|e| RtflowResult::failure(&e))?;
+    let conn = pool.get().map_err(|e| {
+        RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+    })?;
+    record_run(&conn, tenant, run_id, RunKind::Compare, manifest)
+        .map_err(|e| RtflowResult::failure(&format!("failed to record run: {}", e)))
+}
+
+/// Resolve the options of a compare or merge run. When the options object
+/// carries a `"workflow_id"`, that key is removed and the workflow's
+/// overrides for `section` (`"compare"` or `"merge"`) are layered under the
+/// remaining options, which take precedence. Other input is returned
+/// unchanged for the engine's own parser to accept or reject.
+fn workflow_options(
+    tenant: &TenantContext,
+    options_str: &str,
+    section: &str,
+) -> Result<String, String> {
+    let mut options = match serde_json::from_str::<serde_json::Value>(options_str) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => return Ok(options_str.to_owned()),
+    };
+    let Some(workflow_id) = options.remove("workflow_id") else {
+        return Ok(options_str.to_owned());
+    };
+    let workflow_id = workflow_id
+        .as_str()
+        .and_then(|s| Uuid::parse_str(s).ok())
+        .ok_or_else(|| "invalid workflow_id: expected a UUID string".to_owned())?;
+
+    #[cfg(feature = "workflow")]
+    {
+        let pool = get_pool()?;
+        let conn = pool
+            .get()
+            .map_err(|e| format!("failed to acquire database connection: {}", e))?;
+        let config = get_config(&conn, tenant, workflow_id).map_err(|e| e.to_string())?;
+        let overrides = if section == "merge" { &config.merge } else { &config.compare };
+        Ok(serde_json::Value::Object(layer_options(overrides, options)).to_string())
+    }
+    #[cfg(not(feature = "workflow"))]
+    {
+        let _ = (tenant, workflow_id, section);
+        Err("workflow_id requires workflow support, which is not enabled".to_owned())
+    }
+}
+
+/// Parse the compare arguments, load both block trees and record the run in
+/// usage.
+unsafe fn load_compare_input(
+    tenant: &TenantContext,
+    left_doc_id: *const c_char,
+    right_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> Result<CompareInput, *mut RtflowResult> {
+    let left_str = cstring_to_str(left_doc_id).map_err(|e| RtflowResult::failure(&e))?;
+    let right_str = cstring_to_str(right_doc_id).map_err(|e| RtflowResult::failure(&e))?;
+    let options_str = cstring_to_str(options_json).map_err(|e| RtflowResult::failure(&e))?;
+    load_compare_input_from(tenant, &left_str, &right_str, &options_str)
+}
+
+/// [`load_compare_input`] given the arguments as strings.
+fn load_compare_input_from(
+    tenant: &TenantContext,
+    left_str: &str,
+    right_str: &str,
+    options_str: &str,
+) -> Result<CompareInput, *mut RtflowResult> {
+    #[cfg(feature = "workflow")]
+    let (options, progress) =
+        progress_options(tenant, options_str).map_err(|e| RtflowResult::failure(&e))?;
+    #[cfg(feature = "workflow")]
+    let options_str = options.as_str();
+    let options_str = workflow_options(tenant, options_str, "compare")
+        .map_err(|e| RtflowResult::failure(&e))?;
+
+    let config = CompareConfig::from_json(&options_str)
+        .map_err(|e| RtflowResult::failure(&e.to_string()))?;
+
+    let left_id = Uuid::parse_str(left_str)
+        .map_err(|e| RtflowResult::failure(&format!("invalid left_doc_id UUID: {}", e)))?;
+    let right_id = Uuid::parse_str(right_str)
+        .map_err(|e| RtflowResult::failure(&format!("invalid right_doc_id UUID: {}", e)))?;
+
+    let pool = get_pool().map_err(|e| RtflowResult::failure(&e))?;
+    let store = block_store(pool, tenant);
+
+    let left_blocks = store.get_block_tree(&left_id).map_err(|e| {
+        RtflowResult::failure(&format!("failed to load left document blocks: {}", e))
+    })?;
+    let mut right_blocks = store.get_block_tree(&right_id).map_err(|e| {
+        RtflowResult::failure(&format!("failed to load right document blocks: {}", e))
+    })?;
+    let load_document = |id: &Uuid| {
+        store
+            .get_document(id)
+            .map_err(|e| RtflowResult::failure(&format!("failed to load document {}: {}", id, e)))
+    };
+    let documents = [load_document(&left_id)?, load_document(&right_id)?];
+    check_contracts(&[&documents[0], &documents[1]])
+        .map_err(|e| RtflowResult::failure(&e.to_string()))?;
+    if documents[0].anchor_version != documents[1].anchor_version {
+        let hasher = current_hasher(pool).map_err(|e| RtflowResult::failure(&e))?;
+        align_anchors(&documents[0], &documents[1], &mut right_blocks, &hasher)
+            .map_err(|e| RtflowResult::failure(&e.to_string()))?;
+    }
+
+    let conn = pool.get().map_err(|e| {
+        RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+    })?;
+    record_usage(&conn, tenant, UsageMetric::CompareRuns, 1)
+        .map_err(|e| RtflowResult::failure(&format!("failed to record usage: {}", e)))?;
+
+    Ok(CompareInput {
+        config,
+        left_id,
+        right_id,
+        left_blocks,
+        right_blocks,
+        documents,
+        tenant: tenant.clone(),
+        #[cfg(feature = "workflow")]
+        progress,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Run history
+// ---------------------------------------------------------------------------
+
+/// Fetch the recorded manifest of a compare or merge run.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `run_id` — null-terminated UTF-8 string: `run_id` of a `CompareResult` or
+///            `merge_id` of a `MergeResult`.
+///
+/// Returns a `RtflowResult` whose `data` field is a `RunRecord` JSON object
+/// (`run_id`, `run_kind`, `manifest`, `recorded_at`) on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `run_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_run_manifest(
+    tenant_id: *const c_char,
+    run_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let run_str = match cstring_to_str(run_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let run_id = match Uuid::parse_str(&run_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid run_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let record = match get_run(&conn, &tenant, &run_id) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("failed to load run: {}", e)),
+    };
+
+    match serde_json::to_string(&record) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// List every recorded compare and merge run that read a document.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `document_id` — null-terminated UTF-8 string: UUID of the document.
+///
+/// Runs are kept after their documents are deleted.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `RunRecord` objects, newest first, on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `document_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_run_history(
+    tenant_id: *const c_char,
+    document_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_str = match cstring_to_str(document_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let doc_id = match Uuid::parse_str(&doc_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let runs = match runs_for_document(&conn, &tenant, &doc_id) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("failed to load run history: {}", e)),
+    };
+
+    match serde_json::to_string(&runs) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Check whether re-running a recorded compare or merge would reproduce it.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `run_id`       — null-terminated UTF-8 string: UUID of the recorded run.
+/// `options_json` — null-terminated UTF-8 string: the compare or merge
+///                  options a re-run would use (may be `"{}"` for defaults),
+///                  optionally with a `"workflow_id"` as for `rtflow_compare`.
+///
+/// The run's manifest is recomputed from the current engines, options and
+/// input documents and compared with the recorded one. Every component that
+/// changed is listed in `drift`; a deleted input document shows up with a
+/// `null` current value.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"run_id": ..., "run_kind": ..., "reproducible": bool, "drift": [...]}`
+/// on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_reproduce_check(
+    tenant_id: *const c_char,
+    run_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let run_str = match cstring_to_str(run_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let run_id = match Uuid::parse_str(&run_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid run_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+    let record = match get_run(&conn, &tenant, &run_id) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("failed to load run: {}", e)),
+    };
+
+    let store = block_store(pool, &tenant);
+    let mut inputs = Vec::with_capacity(record.manifest.inputs.len());
+    for recorded in &record.manifest.inputs {
+        let document = match store.get_document(&recorded.document_id) {
+            Ok(d) => d,
+            Err(rt_core::RtError::NotFound(_)) => continue,
+            Err(e) => return RtflowResult::failure(&format!("failed to load document: {}", e)),
+        };
+        let blocks = match store.get_block_tree(&document.id) {
+            Ok(b) => b,
+            Err(e) => {
+                return RtflowResult::failure(&format!("failed to load document blocks: {}", e))
+            }
+        };
+        inputs.push(InputDigest::from_blocks(document.id, &blocks).with_document(&document));
+    }
+
+    let current = match record.run_kind {
+        RunKind::Compare => match CompareConfig::from_json(&options_str) {
+            Ok(config) => CompareEngine::new(config).manifest(&inputs),
+            Err(e) => return RtflowResult::failure(&e.to_string()),
+        },
+        #[cfg(feature = "merge")]
+        RunKind::Merge => match MergeConfig::from_json(&options_str) {
+            Ok(config) => MergeEngine::new().with_config(config).manifest(&inputs),
+            Err(e) => return RtflowResult::failure(&e.to_string()),
+        },
+        #[cfg(not(feature = "merge"))]
+        RunKind::Merge => {
+            return RtflowResult::failure("merge runs cannot be checked: merge support is not enabled")
+        }
+    };
+    let drift = record.manifest.drift(&current);
+
+    let payload = serde_json::json!({
+        "run_id": run_id,
+        "run_kind": record.run_kind,
+        "reproducible": drift.is_empty(),
+        "drift": drift,
+    });
+    RtflowResult::success(&payload.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Threshold calibration
+// ---------------------------------------------------------------------------
+
+/// Alignment correction accepted by `rtflow_record_alignment_override`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverrideRequest {
+    corpus: String,
+    left_block_id: Uuid,
+    right_block_id: Uuid,
+    label: AlignmentLabel,
+}
+
+/// Record a reviewer's correction of how two blocks align.
+///
+/// `override_json` — null-terminated UTF-8 string: JSON object
+///   `{"corpus": ..., "left_block_id": ..., "right_block_id": ...,
+///   "label": "match" | "move" | "distinct"}`. A later correction of the
+///   same pair in the same corpus replaces the earlier one.
+///
+/// Returns a `RtflowResult` whose `data` field is the stored
+/// `AlignmentOverride` JSON object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `override_json` must be a valid, non-null, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_record_alignment_override(
+    override_json: *const c_char,
+) -> *mut RtflowResult {
+    let json = match cstring_to_str(override_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let request: OverrideRequest = match serde_json::from_str(&json) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("invalid alignment override: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let stored = match record_override(
+        &conn,
+        &request.corpus,
+        &request.left_block_id,
+        &request.right_block_id,
+        request.label,
+    ) {
+        Ok(o) => o,
+        Err(e) => return RtflowResult::failure(&format!("failed to record override: {}", e)),
+    };
+
+    match serde_json::to_string(&stored) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Fit compare thresholds to the alignment overrides of `corpus` and store
+/// them in the compare preset `preset_name`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `corpus`      — null-terminated UTF-8 string: corpus whose overrides are used.
+/// `preset_name` — null-terminated UTF-8 string: preset to create or update.
+///
+/// `similarity_threshold` and `move_threshold` are written into the preset;
+/// any other options already in it are kept.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"calibration": Calibration, "preset": {...}}` on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_calibrate_thresholds(
+    tenant_id: *const c_char,
+    corpus: *const c_char,
+    preset_name: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let corpus = match cstring_to_str(corpus) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let preset_name = match cstring_to_str(preset_name) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let overrides = match list_overrides(&conn, &corpus) {
+        Ok(o) => o,
+        Err(e) => return RtflowResult::failure(&format!("failed to load overrides: {}", e)),
+    };
+
+    let store = block_store(pool, &tenant);
+    let mut pairs = Vec::with_capacity(overrides.len());
+    for ov in &overrides {
+        let blocks = store
+            .get_block(&ov.left_block_id)
+            .and_then(|l| store.get_block(&ov.right_block_id).map(|r| (l, r)));
+        match blocks {
+            Ok((left, right)) => pairs.push(LabeledPair::from_blocks(&left, &right, ov.label)),
+            Err(e) => {
+                return RtflowResult::failure(&format!(
+                    "failed to load blocks of override {}: {}",
+                    ov.id, e
+                ))
+            }
+        }
+    }
+
+    let calibration = match calibrate(&pairs) {
+        Ok(c) => c,
+        Err(e) => return RtflowResult::failure(&format!("calibration failed: {}", e)),
+    };
+
+    let mut preset = match get_compare_preset(&conn, &preset_name) {
+        Ok(p) => p,
+        Err(rt_core::RtError::NotFound(_)) => serde_json::json!({}),
+        Err(e) => return RtflowResult::failure(&format!("failed to load preset: {}", e)),
+    };
+    preset["similarity_threshold"] = serde_json::json!(calibration.similarity_threshold);
+    preset["move_threshold"] = serde_json::json!(calibration.move_threshold);
+
+    if let Err(e) = CompareConfig::from_json(&preset.to_string()) {
+        return RtflowResult::failure(&format!("stored preset is not valid: {}", e));
+    }
+    if let Err(e) = save_compare_preset(&conn, &preset_name, &preset) {
+        return RtflowResult::failure(&format!("failed to save preset: {}", e));
+    }
+
+    let payload = serde_json::json!({
+        "calibration": calibration,
+        "preset": preset,
+    });
+
+    match serde_json::to_string(&payload) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize response: {}", e)),
+    }
+}
+
+/// Load a stored compare preset, ready to pass as `options_json` to
+/// `rtflow_compare`.
+///
+/// `name` — null-terminated UTF-8 string: preset name.
+///
+/// Returns a `RtflowResult` whose `data` field is the preset's options JSON
+/// object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `name` must be a valid, non-null, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_get_compare_preset(name: *const c_char) -> *mut RtflowResult {
+    let name = match cstring_to_str(name) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match get_compare_preset(&conn, &name) {
+        Ok(preset) => RtflowResult::success(&preset.to_string()),
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Merge
+// ---------------------------------------------------------------------------
+
+/// Merge an incoming document into a base document.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `base_doc_id`     — null-terminated UTF-8 string: UUID of the base document.
+/// `incoming_doc_id` — null-terminated UTF-8 string: UUID of the incoming document.
+/// `options_json`    — null-terminated UTF-8 string: JSON object with merge
+///                     options (may be `"{}"` for defaults).
+///
+/// `options_json` is parsed into a `MergeConfig`; every field
+/// (`similarity_threshold`, `auto_accept_insertions`, `auto_accept_deletions`)
+/// is optional. With an auto-accept rule turned off, the insertions or
+/// deletions it covers are held as `review_required` conflicts. A
+/// `"workflow_id"` applies that workflow's merge overrides as for
+/// `rtflow_compare`. Unknown keys or out-of-range values produce a failure
+/// result.
+///
+/// The merge run and its conflicts are recorded in the `merges` and
+/// `conflicts` tables so review can be resumed with `rtflow_get_merge` /
+/// `rtflow_list_conflicts`. The run is recorded as `running` before the
+/// documents are loaded; if the process dies before it completes, it can be
+/// re-run with `rtflow_resume_merge` once its lease has expired.
+///
+/// Returns a `RtflowResult` whose `data` field is a `MergeResult` JSON object
+/// on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_merge(
+    tenant_id: *const c_char,
+    base_doc_id: *const c_char,
+    incoming_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let request = merge_request(&tenant, base_doc_id, incoming_doc_id, options_json);
+    let (pool, engine, run) = match request {
+        Ok(request) => request,
+        Err(failure) => return failure,
+    };
+    execute_merge_run(pool, &tenant, &engine, &run)
+}
+
+/// Merge an incoming document into a base document as a background job.
+///
+/// Arguments are the same as for `rtflow_merge`, and the options and
+/// document ids are checked before this returns; the merge itself runs on a
+/// background worker, as for `rtflow_compare_async`. Poll it with
+/// `rtflow_job_status`, stop it with `rtflow_job_cancel` and collect its
+/// `MergeResult` with `rtflow_job_result`. The merge run is recorded under
+/// `merge_id` once a worker starts it.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"job_id": ..., "merge_id": ...}` on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_merge_async(
+    tenant_id: *const c_char,
+    base_doc_id: *const c_char,
+    incoming_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let request = merge_request(&tenant, base_doc_id, incoming_doc_id, options_json);
+    let (pool, engine, run) = match request {
+        Ok(request) => request,
+        Err(failure) => return failure,
+    };
+    let job_id = Uuid::new_v4();
+    let merge_id = run.merge_id;
+    jobs::spawn(job_id, merge_id, tenant.clone(), move |_, cancel| {
+        let store = SqliteBlockStore::with_tenant(pool.clone(), tenant.clone())
+            .with_strict_parsing(STRICT_PARSING.load(Ordering::Relaxed));
+        let merges = SqliteMergeStore::with_tenant(pool.clone(), tenant);
+        let lease = Duration::seconds(DEFAULT_MERGE_LEASE_SECS);
+        let result = run_merge_cancellable(&store, &merges, &engine, &run, lease, cancel)?;
+        publish_merge_conflicts(result.merge_id, result.base_doc_id, result.pending_review);
+        Ok(JobOutput::Merge(Box::new(result)))
+    });
+
+    let job = serde_json::json!({ "job_id": job_id, "merge_id": merge_id });
+    RtflowResult::success(&job.to_string())
+}
+
+/// The pool, engine and (not yet started) run of a two-way merge request.
+#[cfg(feature = "merge")]
+unsafe fn merge_request(
+    tenant: &TenantContext,
+    base_doc_id: *const c_char,
+    incoming_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> Result<(&'static DbPool, MergeEngine, MergeRun), *mut RtflowResult> {
+    let base_str = cstring_to_str(base_doc_id).map_err(|e| RtflowResult::failure(&e))?;
+    let incoming_str = cstring_to_str(incoming_doc_id).map_err(|e| RtflowResult::failure(&e))?;
+    let engine = merge_engine(tenant, options_json)?;
+
+    let base_id = Uuid::parse_str(&base_str)
+        .map_err(|e| RtflowResult::failure(&format!("invalid base_doc_id UUID: {}", e)))?;
+    let incoming_id = Uuid::parse_str(&incoming_str)
+        .map_err(|e| RtflowResult::failure(&format!("invalid incoming_doc_id UUID: {}", e)))?;
+
+    let pool = get_pool().map_err(|e| RtflowResult::failure(&e))?;
+    Ok((pool, engine, MergeRun::new(base_id, incoming_id, None)))
+}
+
+/// Build the merge engine for `options_json`, with any workflow overrides
+/// applied.
+#[cfg(feature = "merge")]
+unsafe fn merge_engine(
+    tenant: &TenantContext,
+    options_json: *const c_char,
+) -> Result<MergeEngine, *mut RtflowResult> {
+    let options_str = cstring_to_str(options_json).map_err(|e| RtflowResult::failure(&e))?;
+    let options_str =
+        workflow_options(tenant, &options_str, "merge").map_err(|e| RtflowResult::failure(&e))?;
+    let config =
+        MergeConfig::from_json(&options_str).map_err(|e| RtflowResult::failure(&e.to_string()))?;
+    Ok(MergeEngine::new().with_config(config))
+}
+
+/// Run `run` through `run_merge` with the FFI lease and serialize the result.
+#[cfg(feature = "merge")]
+fn execute_merge_run(
+    pool: &DbPool,
+    tenant: &TenantContext,
+    engine: &MergeEngine,
+    run: &MergeRun,
+) -> *mut RtflowResult {
+    let store = block_store(pool, tenant);
+    let merges = SqliteMergeStore::with_tenant(pool.clone(), tenant.clone());
+    let lease = Duration::seconds(DEFAULT_MERGE_LEASE_SECS);
+
+    let result = match run_merge(&store, &merges, engine, run, lease) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+    publish_merge_conflicts(result.merge_id, result.base_doc_id, result.pending_review);
+
+    match serde_json::to_string(&result) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize MergeResult: {}", e)),
+    }
+}
+
+/// Tell the host's event callback (see `rtflow_set_event_callback`) when a
+/// merge left conflicts for review.
+#[cfg(all(feature = "merge", feature = "workflow"))]
+fn publish_merge_conflicts(merge_id: Uuid, base_doc_id: Uuid, pending_review: usize) {
+    if pending_review == 0 {
+        return;
+    }
+    bus::publish(&BusEvent::MergeConflicts {
+        merge_id,
+        base_doc_id,
+        pending_review,
+    });
+}
+
+/// Without workflow support there is no event callback to tell.
+#[cfg(all(feature = "merge", not(feature = "workflow")))]
+fn publish_merge_conflicts(_merge_id: Uuid, _base_doc_id: Uuid, _pending_review: usize) {}
+
+/// Three-way merge of a base and an incoming document that both derive from
+/// a common ancestor.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `ancestor_doc_id` — null-terminated UTF-8 string: UUID of the ancestor.
+/// `base_doc_id`     — null-terminated UTF-8 string: UUID of the base document.
+/// `incoming_doc_id` — null-terminated UTF-8 string: UUID of the incoming document.
+/// `options_json`    — null-terminated UTF-8 string: JSON object with merge
+///                     options as for `rtflow_merge` (may be `"{}"` for
+///                     defaults).
+///
+/// Blocks changed on only one side are auto-resolved; only concurrent edits
+/// produce conflicts. The result is recorded like `rtflow_merge`'s.
+///
+/// Returns a `RtflowResult` whose `data` field is a `MergeResult` JSON object
+/// (with `ancestor_doc_id` set) on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_merge3(
+    tenant_id: *const c_char,
+    ancestor_doc_id: *const c_char,
+    base_doc_id: *const c_char,
+    incoming_doc_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let mut ids = [Uuid::nil(); 3];
+    for (slot, (ptr, name)) in ids.iter_mut().zip([
+        (ancestor_doc_id, "ancestor_doc_id"),
+        (base_doc_id, "base_doc_id"),
+        (incoming_doc_id, "incoming_doc_id"),
+    ]) {
+        let id_str = match cstring_to_str(ptr) {
+            Ok(s) => s,
+            Err(e) => return RtflowResult::failure(&e),
+        };
+        *slot = match Uuid::parse_str(&id_str) {
+            Ok(id) => id,
+            Err(e) => return RtflowResult::failure(&format!("invalid {} UUID: {}", name, e)),
+        };
+    }
+    let [ancestor_id, base_id, incoming_id] = ids;
+
+    let engine = match merge_engine(&tenant, options_json) {
+        Ok(engine) => engine,
+        Err(e) => return e,
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let run = MergeRun::new(base_id, incoming_id, Some(ancestor_id));
+    execute_merge_run(pool, &tenant, &engine, &run)
+}
+
+/// Reload a merge previously recorded by `rtflow_merge`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `merge_id` — null-terminated UTF-8 string: UUID of the merge run.
+///
+/// Returns a `RtflowResult` whose `data` field is a `MergeResult` JSON object
+/// on success, with `pending_review` reflecting the conflicts' current
+/// resolution states.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `merge_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_get_merge(
+    tenant_id: *const c_char,
+    merge_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let merge_str = match cstring_to_str(merge_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&merge_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid merge_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    match SqliteMergeStore::with_tenant(pool.clone(), tenant.clone()).get_merge(&id) {
+        Ok(result) => match serde_json::to_string(&result) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize MergeResult: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Re-run a merge recorded by `rtflow_merge` / `rtflow_merge3` that failed
+/// or was left `running` by a process that died.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `merge_id` — null-terminated UTF-8 string: UUID of the merge run.
+///
+/// Conflicts are detected again on the recorded documents; conflicts already
+/// stored for the same block and of the same type keep their id and
+/// resolution. Fails while the run is `running` with a live lease.
+///
+/// Returns a `RtflowResult` whose `data` field is a `MergeResult` JSON object
+/// on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `merge_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_resume_merge(
+    tenant_id: *const c_char,
+    merge_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let merge_str = match cstring_to_str(merge_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&merge_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid merge_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = block_store(pool, &tenant);
+    let merges = SqliteMergeStore::with_tenant(pool.clone(), tenant.clone());
+    let lease = Duration::seconds(DEFAULT_MERGE_LEASE_SECS);
+
+    match resume_merge(&store, &merges, &MergeEngine::new(), &id, lease) {
+        Ok(result) => match serde_json::to_string(&result) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize MergeResult: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// List the merge runs of the tenant in one run state.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `state` — null-terminated UTF-8 string: `"running"`, `"failed"` or
+///           `"completed"`.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of `MergeRun`
+/// objects (`merge_id`, document ids, `state`, `heartbeat_at`, `expires_at`,
+/// `failure`), oldest first.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `state` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_list_merge_runs(
+    tenant_id: *const c_char,
+    state: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let state_str = match cstring_to_str(state) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let state = match MergeRunState::from_str(&state_str) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    match SqliteMergeStore::with_tenant(pool.clone(), tenant.clone()).list_merge_runs(state) {
+        Ok(runs) => match serde_json::to_string(&runs) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize merge runs: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Mark every running merge of the tenant whose lease has expired
+/// as `failed`, so it can be resumed or deleted.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of the
+/// expired merge ids.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_expire_stale_merges(tenant_id: *const c_char) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    match SqliteMergeStore::with_tenant(pool.clone(), tenant.clone())
+        .expire_stale_merge_runs(Utc::now())
+    {
+        Ok(ids) => match serde_json::to_string(&ids) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize merge ids: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Delete a `failed` merge run together with its conflicts and suggestions.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `merge_id` — null-terminated UTF-8 string: UUID of the merge run.
+///
+/// Returns a `RtflowResult` whose `data` field is `{"merge_id": ...}` on
+/// success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `merge_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_delete_merge_run(
+    tenant_id: *const c_char,
+    merge_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let merge_str = match cstring_to_str(merge_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&merge_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid merge_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    match SqliteMergeStore::with_tenant(pool.clone(), tenant.clone()).delete_merge_run(&id) {
+        Ok(()) => RtflowResult::success(&serde_json::json!({ "merge_id": id }).to_string()),
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// List the conflicts recorded for a merge, including their resolution
+/// states.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `merge_id` — null-terminated UTF-8 string: UUID of the merge run.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `MergeConflict` objects on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `merge_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_list_conflicts(
+    tenant_id: *const c_char,
+    merge_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let merge_str = match cstring_to_str(merge_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&merge_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid merge_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    match SqliteMergeStore::with_tenant(pool.clone(), tenant.clone()).list_conflicts(&id) {
+        Ok(conflicts) => match serde_json::to_string(&conflicts) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize conflicts: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Compute resolution suggestions for the pending conflicts of a merge and
+/// store them alongside the conflicts. Nothing is resolved.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `merge_id` — null-terminated UTF-8 string: UUID of the merge run.
+///
+/// For three-way merges the ancestor's text of each conflicting block
+/// (matched by structural path) informs the `base_unchanged` /
+/// `incoming_unchanged` rules. Previously stored suggestions for the same
+/// conflicts are replaced.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `ResolutionSuggestion` objects (`conflict_id`, `rank`, `resolution`,
+/// `rule`, `confidence`, `rationale`), best first within each conflict.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `merge_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_suggest_resolutions(
+    tenant_id: *const c_char,
+    merge_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let merge_str = match cstring_to_str(merge_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&merge_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid merge_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let merges = SqliteMergeStore::with_tenant(pool.clone(), tenant.clone());
+    let merge = match merges.get_merge(&id) {
+        Ok(m) => m,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+
+    // Ancestor text by structural path, for three-way merges.
+    let blocks = block_store(pool, &tenant);
+    let ancestor_text: std::collections::HashMap<String, String> = match merge.ancestor_doc_id {
+        Some(ancestor_id) => match blocks.get_blocks_by_document(&ancestor_id) {
+            Ok(ancestor) => ancestor
+                .into_iter()
+                .map(|b| (b.structural_path, b.canonical_text))
+                .collect(),
+            Err(e) => {
+                return RtflowResult::failure(&format!("failed to load ancestor blocks: {}", e))
+            }
+        },
+        None => std::collections::HashMap::new(),
+    };
+
+    let mut suggestions = Vec::new();
+    for conflict in &merge.conflicts {
+        let ancestor = match blocks.get_block(&conflict.block_id) {
+            Ok(block) => ancestor_text.get(&block.structural_path).map(String::as_str),
+            Err(_) => None,
+        };
+        suggestions.extend(suggest_resolutions(conflict, ancestor));
+    }
+
+    if let Err(e) = merges.save_suggestions(&suggestions) {
+        return RtflowResult::failure(&format!("failed to save suggestions: {}", e));
+    }
+
+    match serde_json::to_string(&suggestions) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize suggestions: {}", e)),
+    }
+}
+
+/// List the resolution suggestions stored for a merge by
+/// `rtflow_suggest_resolutions`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `merge_id` — null-terminated UTF-8 string: UUID of the merge run.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `ResolutionSuggestion` objects in conflict order, by rank within each
+/// conflict.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `merge_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_list_suggestions(
+    tenant_id: *const c_char,
+    merge_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let merge_str = match cstring_to_str(merge_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id = match Uuid::parse_str(&merge_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid merge_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    match SqliteMergeStore::with_tenant(pool.clone(), tenant.clone()).list_suggestions(&id) {
+        Ok(suggestions) => match serde_json::to_string(&suggestions) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize suggestions: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Export
+// ---------------------------------------------------------------------------
+
+/// Request envelope accepted by `rtflow_export_reviewer_redline`.
+#[cfg(feature = "export")]
+#[derive(Deserialize)]
+struct ReviewerRedlineRequest {
+    layer: ReviewLayer,
+    #[serde(default)]
+    deltas: Vec<BlockDelta>,
+    #[serde(default)]
+    comments: Vec<ReviewComment>,
+    format: ExportFormat,
+    output_path: String,
+    #[serde(default)]
+    comments_output_path: Option<String>,
+}
+
+/// Render a single reviewer's changes against the base document, write the
+/// result to disk (or through the host's file system, see `rtflow_set_vfs`)
+/// and register it as a workflow artifact.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `base_doc_id` — null-terminated UTF-8 string: UUID of the base document.
+/// `export_json` — null-terminated UTF-8 string: JSON object with the fields
+///   - `"layer"`:       `ReviewLayer` whose deltas are rendered
+///   - `"deltas"`:      array of `BlockDelta`; deltas of other layers are ignored
+///   - `"comments"`:    optional array of `ReviewComment`, rendered as Word
+///     comments (DOCX only)
+///   - `"format"`:      `"html"` (a standalone page) or `"docx"` (a `.docx`
+///     package, comments included)
+///   - `"output_path"`: file path the rendered redline is written to
+///   - `"comments_output_path"`: optional file path the `word/comments.xml`
+///     part is also written to on its own when a DOCX export anchors any
+///     comment
+///
+/// The artifact is recorded against `layer.workflow_id`, which must be a
+/// workflow of the tenant reviewing `base_doc_id`.
+///
+/// Returns a `RtflowResult` whose `data` field is the recorded `Artifact`
+/// JSON object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "export")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_export_reviewer_redline(
+    tenant_id: *const c_char,
+    base_doc_id: *const c_char,
+    export_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let base_str = match cstring_to_str(base_doc_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let export_str = match cstring_to_str(export_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let base_id = match Uuid::parse_str(&base_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid base_doc_id UUID: {}", e)),
+    };
+
+    let request: ReviewerRedlineRequest = match deserialize_json(&export_str) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("failed to parse export JSON: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+    // The artifact is recorded against the layer's workflow, so it must be
+    // this tenant's and review the base document.
+    if let Err(e) = ensure_workflow(&conn, &tenant, &request.layer.workflow_id, &base_id) {
+        return RtflowResult::failure(&e.to_string());
+    }
+    drop(conn);
+
+    let store = block_store(pool, &tenant);
+    let base_blocks = match store.get_block_tree(&base_id) {
+        Ok(b) => b,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to load base document blocks: {}", e))
+        }
+    };
+
+    let redline = export_reviewer_redline(
+        &base_blocks,
+        &request.layer,
+        &request.deltas,
+        &request.comments,
+        request.format,
+    );
+
+    let vfs = current_vfs();
+    if let Some(path) = request.comments_output_path.as_deref() {
+        if let Err(e) = redline.write_comments(vfs.as_ref(), Some(path)) {
+            return RtflowResult::failure(&format!("failed to write comments to {}: {}", path, e));
+        }
+    }
+
+    let file = match redline.file_content() {
+        Ok(f) => f,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+    let artifact = match redline.to_artifact(request.layer.workflow_id, request.output_path) {
+        Ok(a) => a,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+    if let Err(e) = store_artifact(&conn, vfs.as_ref(), &artifact, &file) {
+        return RtflowResult::failure(&format!(
+            "failed to store redline at {}: {}",
+            artifact.file_path, e
+        ));
+    }
+
+    match serde_json::to_string(&artifact) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize Artifact: {}", e)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Workflow
+// ---------------------------------------------------------------------------
+
+/// Select the state machine every workflow follows.
+///
+/// `definition_json` — null-terminated UTF-8 string: JSON object
+///   `{"transitions": [{"from": "DRAFT", "event": "compare_started", "to":
+///   "COMPARE_RUNNING"}, ...]}` listing every allowed transition between the
+///   built-in states and events, or an empty string for the built-in
+///   machine (the default). `rtflow_get_state_machine` returns the current
+///   definition as a starting point.
+///
+/// Meant to be called once at start-up, after `rtflow_init`. `COMPLETED` and
+/// `ABORTED` stay terminal, `compare_progress` and `deadline_breached` stay
+/// legal in every other state, and `workflow_created` must keep a workflow
+/// in `DRAFT`; definitions breaking these are rejected. Stored workflows are
+/// replayed under the selected machine. The selection is process-wide.
+///
+/// Returns a `RtflowResult` whose `data` field is the selected definition on
+/// success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `definition_json` must be a valid, non-null, null-terminated C string.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_set_state_machine(
+    definition_json: *const c_char,
+) -> *mut RtflowResult {
+    let definition = match cstring_to_str(definition_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    if definition.trim().is_empty() {
+        reset_machine();
+    } else {
+        let installed = StateMachine::from_json(&definition).and_then(install_machine);
+        if let Err(e) = installed {
+            return RtflowResult::failure(&e.to_string());
+        }
+    }
+    rtflow_get_state_machine()
+}
+
+/// Report the state machine workflows follow, as accepted by
+/// `rtflow_set_state_machine`.
+///
+/// Returns a `RtflowResult` whose `data` field is the definition JSON.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub extern "C" fn rtflow_get_state_machine() -> *mut RtflowResult {
+    match serde_json::to_string(&*current_machine()) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize state machine: {}", e)),
+    }
+}
+
+/// Create a workflow for a document.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `document_id`  — null-terminated UTF-8 string: UUID of an ingested document.
+/// `initiator_id` — null-terminated UTF-8 string: identifier of the user or
+///                  system starting the workflow; must not be empty.
+///
+/// Inserts the workflow in `DRAFT` and emits its `workflow_created` event at
+/// seq=1; further events go through `rtflow_workflow_event`.
+///
+/// Returns a `RtflowResult` whose `data` field is the new `Workflow` JSON
+/// object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_create(
+    tenant_id: *const c_char,
+    document_id: *const c_char,
+    initiator_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(document_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let initiator = match cstring_to_str(initiator_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document_id UUID: {}", e)),
+    };
+
+    if initiator.trim().is_empty() {
+        return RtflowResult::failure("initiator_id must not be empty");
+    }
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match WorkflowEngine::create_workflow(&conn, &tenant, doc_id, &initiator) {
+        Ok(wf) => match serde_json::to_string(&wf) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize Workflow: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&format!("failed to create workflow: {}", e)),
+    }
+}
+
+/// Options accepted by `rtflow_workflow_create_with_options`.
+#[cfg(feature = "workflow")]
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct CreateWorkflowOptions {
+    approval_policy: Option<ApprovalPolicy>,
+    deadlines: Vec<Deadline>,
+}
+
+/// Create a workflow for a document, as `rtflow_workflow_create`, with
+/// options.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `document_id`  — null-terminated UTF-8 string: UUID of an ingested document.
+/// `initiator_id` — null-terminated UTF-8 string: identifier of the user or
+///                  system starting the workflow; must not be empty.
+/// `options_json` — null-terminated UTF-8 string: JSON object with an
+///   optional `approval_policy` (`{"required": n, "approvers": [...]}`) and
+///   optional `deadlines` (`[{"state": "IN_REVIEW", "within":
+///   {"business_days": 5}}, ...]`, spans in `hours` or `business_days`). An
+///   empty string means no options.
+///
+/// With an approval policy, `workflow_completed` is refused until `required`
+/// distinct approvers have each submitted an `approval_granted` event in
+/// `READY_FOR_FINALIZATION`. Approvers are those listed, or, when the list
+/// is empty, reviewers assigned with the `approver` role.
+///
+/// With deadlines, `rtflow_workflow_check_deadlines` reports the workflow
+/// once it overstays a state.
+///
+/// Returns a `RtflowResult` whose `data` field is the new `Workflow` JSON
+/// object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_create_with_options(
+    tenant_id: *const c_char,
+    document_id: *const c_char,
+    initiator_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id_str = match cstring_to_str(document_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let initiator = match cstring_to_str(initiator_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_id = match Uuid::parse_str(&doc_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document_id UUID: {}", e)),
+    };
+
+    if initiator.trim().is_empty() {
+        return RtflowResult::failure("initiator_id must not be empty");
+    }
+
+    let options: CreateWorkflowOptions = if options_str.trim().is_empty() {
+        CreateWorkflowOptions::default()
+    } else {
+        match serde_json::from_str(&options_str) {
+            Ok(o) => o,
+            Err(e) => return RtflowResult::failure(&format!("invalid workflow options: {}", e)),
+        }
+    };
+    let options = CreateOptions {
+        approval_policy: options.approval_policy,
+        deadlines: options.deadlines,
+    };
+    if let Some(Err(e)) = options.approval_policy.as_ref().map(ApprovalPolicy::validate) {
+        return RtflowResult::failure(&e.to_string());
+    }
+    if let Err(e) = validate_deadlines(&options.deadlines) {
+        return RtflowResult::failure(&e.to_string());
+    }
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match WorkflowEngine::create_workflow_with_options(&conn, &tenant, doc_id, &initiator, &options)
+    {
+        Ok(wf) => match serde_json::to_string(&wf) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize Workflow: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&format!("failed to create workflow: {}", e)),
+    }
+}
+
+/// Record a `deadline_breached` event for every workflow past due in its
+/// current state.
+///
+/// `now` — null-terminated UTF-8 string: RFC 3339 time to check against; an
+///         empty string means the current time.
+///
+/// Meant to be called on a schedule by the host. Workflows of every tenant
+/// are checked, and each stay in a state is reported at most once; the
+/// events reach the callback registered with `rtflow_set_event_callback`
+/// like any other.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `DeadlineBreach` objects (`workflow_id`, `state`, `due_at`) for the
+/// events recorded on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `now` must be a valid, non-null, null-terminated C string.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_check_deadlines(now: *const c_char) -> *mut RtflowResult {
+    let now_str = match cstring_to_str(now) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let now = if now_str.trim().is_empty() {
+        chrono::Utc::now()
+    } else {
+        match chrono::DateTime::parse_from_rfc3339(now_str.trim()) {
+            Ok(t) => t.with_timezone(&chrono::Utc),
+            Err(e) => return RtflowResult::failure(&format!("invalid now timestamp: {}", e)),
+        }
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match check_deadlines(&conn, now) {
+        Ok(breaches) => match serde_json::to_string(&breaches) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize breaches: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Report a workflow's approval policy and the sign-offs recorded so far.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id` — null-terminated UTF-8 string: UUID of the workflow.
+///
+/// Returns a `RtflowResult` whose `data` field is an `ApprovalStatus` JSON
+/// object (`policy`, null when the workflow has none; `approved_by`, the
+/// approvers in sign-off order) on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `workflow_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_approvals(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    // An empty log is indistinguishable from a missing workflow.
+    if let Err(e) = WorkflowEngine::get_workflow(&conn, &tenant, wf_id) {
+        return RtflowResult::failure(&e.to_string());
+    }
+    let status = WorkflowEngine::get_events(&conn, &tenant, wf_id)
+        .and_then(|events| ApprovalStatus::from_events(&events));
+    match status {
+        Ok(status) => match serde_json::to_string(&status) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize ApprovalStatus: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Submit a workflow event and advance the workflow state machine.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id` — null-terminated UTF-8 string: UUID of the workflow.
+/// `event_json`  — null-terminated UTF-8 string: JSON object describing the
+///                 event to apply.
+///
+/// The `event_json` object must contain at least:
+///   - `"event_type"`: string — a valid `EventType` snake_case value
+///   - `"actor"`:      string — identifier of the user/system submitting the event
+///
+/// An optional `"payload"` object defaults to `{}`. It is checked against the
+/// event's schema: `reviewer_assigned` requires `reviewer_id` (and takes an
+/// optional `role`: `editor`, the default, `approver` or `observer`),
+/// `reviewer_unassigned` requires `reviewer_id`, and `delta_submitted`
+/// requires `layer_id` and a non-empty `delta_ids` array of UUIDs. A
+/// malformed payload fails with `invalid '<event>' payload: ` and a JSON
+/// array of `{"field", "message"}` details; nothing is written.
+///
+/// A `delta_submitted` event fails unless `actor` is assigned to the
+/// workflow as an editor or approver (see `rtflow_workflow_reviewers`).
+///
+/// Setting `"auto_create": true` together with `"document_id"` makes a
+/// `compare_started` event on an unknown workflow create that workflow
+/// (bound to `document_id`) instead of failing with not-found.
+///
+/// A `workflow_completed` event fails until the workflow's approval policy
+/// (see `rtflow_workflow_create_with_options`) is satisfied.
+///
+/// Setting `"snapshot_on_close": true` on a `review_closed` event first
+/// stores a snapshot of the workflow's document (as `rtflow_create_snapshot`)
+/// and records its id in the event payload as `snapshot_id`.
+///
+/// Returns a `RtflowResult` whose `data` field is the updated `Workflow`
+/// JSON object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_event(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+    event_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let event_str = match cstring_to_str(event_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    // Parse the event JSON envelope.
+    let event_value: serde_json::Value = match deserialize_json(&event_str) {
+        Ok(v) => v,
+        Err(e) => return RtflowResult::failure(&format!("failed to parse event JSON: {}", e)),
+    };
+
+    let event_type_str = match event_value.get("event_type").and_then(|v| v.as_str()) {
+        Some(s) => s.to_owned(),
+        None => {
+            return RtflowResult::failure(
+                "event JSON must contain a string field \"event_type\"",
+            )
+        }
+    };
+
+    let actor = match event_value.get("actor").and_then(|v| v.as_str()) {
+        Some(s) => s.to_owned(),
+        None => {
+            return RtflowResult::failure("event JSON must contain a string field \"actor\"")
+        }
+    };
+
+    let payload = event_value
+        .get("payload")
+        .cloned()
+        .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+
+    let event_type = match EventType::from_str(&event_type_str) {
+        Ok(et) => et,
+        Err(e) => return RtflowResult::failure(&format!("invalid event_type: {}", e)),
+    };
+
+    let document_id = match event_value.get("document_id").and_then(|v| v.as_str()) {
+        Some(s) => match Uuid::parse_str(s) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                return RtflowResult::failure(&format!("invalid document_id UUID: {}", e))
+            }
+        },
+        None => None,
+    };
+
+    let options = SubmitOptions {
+        auto_create: event_value
+            .get("auto_create")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        document_id,
+        snapshot_on_close: event_value
+            .get("snapshot_on_close")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match WorkflowEngine::submit_event_with_options(
+        &conn,
+        &tenant,
+        wf_id,
+        event_type,
+        &actor,
+        payload,
+        &options,
+    ) {
+        Ok(wf) => match serde_json::to_string(&wf) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize Workflow: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Retrieve the current state of a workflow.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id` — null-terminated UTF-8 string: UUID of the workflow.
+///
+/// Returns a `RtflowResult` whose `data` field is the current `Workflow`
+/// JSON object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `workflow_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_state(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match WorkflowEngine::get_workflow(&conn, &tenant, wf_id) {
+        Ok(wf) => match serde_json::to_string(&wf) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize Workflow: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Options accepted by `rtflow_workflow_events`.
+#[cfg(feature = "workflow")]
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct EventLogOptions {
+    /// Only events with a greater `seq` are returned.
+    after_seq: i64,
+    /// At most this many events are returned.
+    limit: Option<usize>,
+}
+
+/// Retrieve the event log of a workflow, ordered by `seq`.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id`  — null-terminated UTF-8 string: UUID of the workflow.
+/// `options_json` — null-terminated UTF-8 string: JSON object with optional
+///   `after_seq` (exclusive; for fetching only new events) and `limit`
+///   fields. An empty string returns the whole log.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `WorkflowEvent` objects (`id`, `workflow_id`, `event_type`, `actor`,
+/// `payload`, `created_at`, `seq`) on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_events(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    let options: EventLogOptions = if options_str.trim().is_empty() {
+        EventLogOptions::default()
+    } else {
+        match serde_json::from_str(&options_str) {
+            Ok(o) => o,
+            Err(e) => return RtflowResult::failure(&format!("invalid event log options: {}", e)),
+        }
+    };
+    if options.limit == Some(0) {
+        return RtflowResult::failure("invalid event log options: limit must be greater than zero");
+    }
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    // An empty log is indistinguishable from a missing workflow, so check
+    // the workflow exists (for this tenant) first.
+    if let Err(e) = WorkflowEngine::get_workflow(&conn, &tenant, wf_id) {
+        return RtflowResult::failure(&e.to_string());
+    }
+
+    let events = match WorkflowEngine::get_events(&conn, &tenant, wf_id) {
+        Ok(events) => events,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+    let events: Vec<_> = events
+        .into_iter()
+        .filter(|e| e.seq > options.after_seq)
+        .take(options.limit.unwrap_or(usize::MAX))
+        .collect();
+
+    match serde_json::to_string(&events) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize events: {}", e)),
+    }
+}
+
+/// Check that a workflow's event log has not been altered.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id` — null-terminated UTF-8 string: UUID of the workflow.
+///
+/// Every event's hash is recomputed from its content and the hash of the
+/// event before it, and the newest one is compared with the head recorded on
+/// the workflow. An edited, reordered or deleted event fails the call with a
+/// "hash mismatch" error naming the first broken event.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"workflow_id": ..., "verified_events": ..., "unverified_events": ...,
+/// "head_hash": ...}` on success; `unverified_events` counts events recorded
+/// before the log was hash-chained.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `workflow_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_verify_chain(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match verify_chain(&conn, &tenant, wf_id) {
+        Ok(verification) => match serde_json::to_string(&verification) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize verification: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// List workflows matching a filter, oldest first.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `filter_json` — null-terminated UTF-8 JSON object with optional
+/// `document_id`, `state`, `initiator_id`, `created_from` (inclusive) and
+/// `created_to` (exclusive) filters plus `offset` / `limit` pagination.
+/// An empty string lists the first page of all workflows.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of `Workflow`
+/// objects on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `filter_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_list(
+    tenant_id: *const c_char,
+    filter_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let filter_str = match cstring_to_str(filter_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let filter: WorkflowFilter = if filter_str.trim().is_empty() {
+        WorkflowFilter::default()
+    } else {
+        match serde_json::from_str(&filter_str) {
+            Ok(f) => f,
+            Err(e) => return RtflowResult::failure(&format!("invalid workflow filter: {}", e)),
+        }
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match WorkflowEngine::list_workflows(&conn, &tenant, &filter) {
+        Ok(workflows) => match serde_json::to_string(&workflows) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize workflows: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Store a workflow's compare and merge configuration overrides.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id` — null-terminated UTF-8 string: UUID of the workflow.
+/// `config_json` — null-terminated UTF-8 string: `{"compare": {...},
+///                 "merge": {...}}`, each section holding options in the
+///                 shape `rtflow_compare` / `rtflow_merge` accept. Either
+///                 section may be omitted; an empty string clears every
+///                 override.
+///
+/// The overrides replace any stored before and apply to every compare or
+/// merge whose options carry this `"workflow_id"`. Each section is validated
+/// as the engine would parse it, so an unknown key or out-of-range value
+/// fails here rather than at the next run.
+///
+/// Returns a `RtflowResult` whose `data` field is the stored `WorkflowConfig`
+/// JSON object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_set_config(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+    config_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let config_str = match cstring_to_str(config_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    let config = match WorkflowConfig::from_json(&config_str) {
+        Ok(c) => c,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+    let compare = serde_json::Value::Object(config.compare.clone()).to_string();
+    if let Err(e) = CompareConfig::from_json(&compare) {
+        return RtflowResult::failure(&e.to_string());
+    }
+    #[cfg(feature = "merge")]
+    {
+        let merge = serde_json::Value::Object(config.merge.clone()).to_string();
+        if let Err(e) = MergeConfig::from_json(&merge) {
+            return RtflowResult::failure(&e.to_string());
+        }
+    }
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match set_config(&conn, &tenant, wf_id, &config) {
+        Ok(()) => match serde_json::to_string(&config) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize WorkflowConfig: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Retrieve a workflow's compare and merge configuration overrides.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id` — null-terminated UTF-8 string: UUID of the workflow.
+///
+/// Returns a `RtflowResult` whose `data` field is a `WorkflowConfig` JSON
+/// object (`{"compare": {...}, "merge": {...}}`, both empty when nothing is
+/// overridden) on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `workflow_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_get_config(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match get_config(&conn, &tenant, wf_id) {
+        Ok(config) => match serde_json::to_string(&config) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize WorkflowConfig: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// List the reviewers assigned to a workflow.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id` — null-terminated UTF-8 string: UUID of the workflow.
+///
+/// Reviewers are assigned and removed with `reviewer_assigned` and
+/// `reviewer_unassigned` events (see `rtflow_workflow_event`).
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `Assignment` objects (`workflow_id`, `reviewer_id`, `role`,
+/// `assigned_by`, `assigned_at`) in assignment order on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `workflow_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_reviewers(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match ReviewerEngine::list_assignments(&conn, &tenant, wf_id) {
+        Ok(assignments) => match serde_json::to_string(&assignments) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize assignments: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Start a sub-workflow of a workflow in review for one of its reviewers.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id` — null-terminated UTF-8 string: UUID of the parent workflow.
+/// `reviewer_id` — null-terminated UTF-8 string: reviewer assigned to it.
+///
+/// The sub-workflow is a workflow on the same document, initiated by the
+/// reviewer, that runs its own lifecycle through `rtflow_workflow_event`.
+/// Each change of its state is recorded in the parent's log as a
+/// `subworkflow_updated` event, and the parent's `review_closed` is refused
+/// while any of its sub-workflows is neither completed nor aborted.
+///
+/// Returns a `RtflowResult` whose `data` field is the new `Workflow` JSON
+/// object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_create_subworkflow(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+    reviewer_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let reviewer = match cstring_to_str(reviewer_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    if reviewer.trim().is_empty() {
+        return RtflowResult::failure("reviewer_id must not be empty");
+    }
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match create_subworkflow(&conn, &tenant, wf_id, &reviewer) {
+        Ok(wf) => match serde_json::to_string(&wf) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize Workflow: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// List the sub-workflows of a workflow with their latest states.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id` — null-terminated UTF-8 string: UUID of the parent workflow.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `Subworkflow` objects (`subworkflow_id`, `reviewer_id`, `state`) in
+/// creation order on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `workflow_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_subworkflows(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match list_subworkflows(&conn, &tenant, wf_id) {
+        Ok(children) => match serde_json::to_string(&children) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize sub-workflows: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Request accepted by `rtflow_workflow_route_review`.
+#[cfg(feature = "workflow")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RouteReviewRequest {
+    run_id: Uuid,
+    rules: RoutingRules,
+    /// Risk tags per section.
+    #[serde(default)]
+    section_tags: std::collections::HashMap<String, Vec<String>>,
+    /// Record the suggestions as `ReviewerAssigned` events.
+    #[serde(default)]
+    apply: bool,
+    /// Actor of the recorded events.
+    #[serde(default = "default_routing_actor")]
+    actor: String,
+}
+
+#[cfg(feature = "workflow")]
+fn default_routing_actor() -> String {
+    "system".into()
+}
+
+/// Route heavily changed or risky sections of a compare run to senior
+/// reviewers.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `workflow_id`  — null-terminated UTF-8 string: UUID of the workflow.
+/// `request_json` — null-terminated UTF-8 string: JSON object with
+///   `run_id` (a compare run, see `rtflow_compare_sections`), `rules`
+///   (`density_threshold`, default 0.5; `min_changes`, default 3;
+///   `risk_tags`; `senior_reviewers`, required), optional `section_tags`
+///   (`{"7.": ["indemnity"], ...}`), `apply` (default `false`) and `actor`
+///   (default `"system"`).
+///
+/// A section is routed when its share of changed deltas reaches
+/// `density_threshold` with at least `min_changes` changes, or when it has
+/// any change and carries one of `risk_tags`. Routed sections go densest
+/// first to the senior reviewer with the fewest assignments in the
+/// workflow so far. With `apply`, the suggestions are recorded as
+/// `ReviewerAssigned` events (all or none), which requires the workflow to
+/// be in review.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `RoutingSuggestion` objects (`section`, `reviewer_id`, `reason`,
+/// `density`, `changed`, `payload`) in section order on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_workflow_route_review(
+    tenant_id: *const c_char,
+    workflow_id: *const c_char,
+    request_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id_str = match cstring_to_str(workflow_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let request_str = match cstring_to_str(request_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let wf_id = match Uuid::parse_str(&wf_id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid workflow_id UUID: {}", e)),
+    };
+
+    let request: RouteReviewRequest = match serde_json::from_str(&request_str) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("invalid routing request: {}", e)),
+    };
+    if let Err(e) = request.rules.validate() {
+        return RtflowResult::failure(&e.to_string());
+    }
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    let suggestions = match plan_routing(
+        &conn,
+        &tenant,
+        wf_id,
+        &request.run_id,
+        &request.section_tags,
+        &request.rules,
+    ) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+    if request.apply {
+        if let Err(e) = apply_routing(&conn, &tenant, wf_id, &suggestions, &request.actor) {
+            return RtflowResult::failure(&e.to_string());
+        }
+    }
+
+    match serde_json::to_string(&suggestions) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize suggestions: {}", e)),
+    }
+}
+
+/// Notify the host of every persisted workflow event and of merges that
+/// leave conflicts for review.
+///
+/// `callback` — see `RtflowEventFn`: called with a null-terminated UTF-8
+/// JSON object and `user_data`. Workflow events arrive as
+/// `{"kind": "workflow_event", "event": WorkflowEvent, "state": ...}` (the
+/// workflow's state after the event); merges run by `rtflow_merge`,
+/// `rtflow_merge3` or `rtflow_merge_review_layers` with `pending_review > 0`
+/// arrive as `{"kind": "merge_conflicts", "merge_id", "base_doc_id",
+/// "pending_review"}`. Pass null to stop notifications.
+/// `user_data` — opaque host pointer handed back to every call; may be null.
+///
+/// Thread safety: the callback runs synchronously on the thread whose call
+/// persisted the event, and may run on several threads at once. It should
+/// return quickly and must not call `rtflow_set_event_callback` or any
+/// function that records workflow events. Once this function returns, the
+/// previous callback is no longer running and is not called again, so its
+/// `user_data` may be released. The registration is process-wide.
+///
+/// Returns a `RtflowResult` whose `data` field is `{"callback": "set"}` or
+/// `{"callback": "cleared"}` on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `callback`, when non-null, must be safe to call with the arguments
+/// described above, and `user_data` must stay valid for as long as it is
+/// registered.
+#[cfg(feature = "workflow")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_set_event_callback(
+    callback: Option<RtflowEventFn>,
+    user_data: *mut c_void,
+) -> *mut RtflowResult {
+    let mut current = match EVENT_CALLBACK.lock() {
+        Ok(c) => c,
+        Err(e) => return RtflowResult::failure(&format!("failed to set event callback: {}", e)),
+    };
+    if let Some(previous) = current.take() {
+        bus::unsubscribe(previous);
+    }
+    let state = match callback {
+        Some(callback) => {
+            let listener = CallbackListener::new(callback, user_data);
+            *current = Some(bus::subscribe(move |event| listener.notify(event)));
+            "set"
+        }
+        None => "cleared",
+    };
+
+    RtflowResult::success(&serde_json::json!({ "callback": state }).to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Review layers
+// ---------------------------------------------------------------------------
+
+/// Review layer accepted by `rtflow_create_review_layer`.
+#[cfg(feature = "merge")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewReviewLayer {
+    workflow_id: Uuid,
+    reviewer_id: String,
+    document_id: Uuid,
+}
+
+/// Delta accepted by `rtflow_append_review_delta`.
+#[cfg(feature = "merge")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewReviewDelta {
+    review_layer_id: Uuid,
+    block_id: Uuid,
+    delta_type: DeltaType,
+    token_start: usize,
+    token_end: usize,
+    #[serde(default)]
+    delta_payload: serde_json::Value,
+}
+
+/// Query accepted by `rtflow_list_review_deltas`; exactly one field is set.
+#[cfg(feature = "merge")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReviewDeltaQuery {
+    review_layer_id: Option<Uuid>,
+    block_id: Option<Uuid>,
+}
+
+/// Start a reviewer's layer of edits on a document.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `layer_json` — null-terminated UTF-8 string: JSON object
+///   `{"workflow_id": ..., "reviewer_id": ..., "document_id": ...}`. The
+///   document must be stored.
+///
+/// Returns a `RtflowResult` whose `data` field is the new `ReviewLayer` JSON
+/// object (with its generated `id`) on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `layer_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_create_review_layer(
+    tenant_id: *const c_char,
+    layer_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let json = match cstring_to_str(layer_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let request: NewReviewLayer = match serde_json::from_str(&json) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("invalid review layer: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let layer = ReviewLayer::new(request.workflow_id, request.reviewer_id, request.document_id);
+    let store = SqliteReviewLayerStore::with_tenant(pool.clone(), tenant.clone());
+    if let Err(e) = store.create_layer(&layer) {
+        return RtflowResult::failure(&e.to_string());
+    }
+
+    match serde_json::to_string(&layer) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize review layer: {}", e)),
+    }
+}
+
+/// Record one edit in a review layer.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `delta_json` — null-terminated UTF-8 string: JSON object
+///   `{"review_layer_id": ..., "block_id": ..., "delta_type": "insert" |
+///   "delete" | "modify", "token_start": n, "token_end": n,
+///   "delta_payload": {...}}` (`delta_payload` optional). The block must
+///   belong to the layer's document; the delta is attributed to the
+///   layer's reviewer.
+///
+/// Returns a `RtflowResult` whose `data` field is the recorded `BlockDelta`
+/// JSON object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `delta_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_append_review_delta(
+    tenant_id: *const c_char,
+    delta_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let json = match cstring_to_str(delta_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let request: NewReviewDelta = match serde_json::from_str(&json) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&format!("invalid review delta: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = SqliteReviewLayerStore::with_tenant(pool.clone(), tenant.clone());
+    let layer = match store.get_layer(&request.review_layer_id) {
+        Ok(l) => l,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+    let delta = BlockDelta::new(
+        layer.id,
+        layer.reviewer_id,
+        request.block_id,
+        request.delta_type,
+        request.token_start,
+        request.token_end,
+        request.delta_payload,
+    );
+    if let Err(e) = store.append_delta(&delta) {
+        return RtflowResult::failure(&e.to_string());
+    }
+
+    match serde_json::to_string(&delta) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize review delta: {}", e)),
+    }
+}
+
+/// List the recorded edits of a review layer, or of every layer on a block.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `query_json` — null-terminated UTF-8 string: JSON object with exactly
+///   one of `review_layer_id` or `block_id`.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `BlockDelta` objects, in the order they were recorded, on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `query_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_list_review_deltas(
+    tenant_id: *const c_char,
+    query_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let json = match cstring_to_str(query_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let query: ReviewDeltaQuery = match serde_json::from_str(&json) {
+        Ok(q) => q,
+        Err(e) => return RtflowResult::failure(&format!("invalid review delta query: {}", e)),
+    };
+
+    if query.review_layer_id.is_some() == query.block_id.is_some() {
+        return RtflowResult::failure(
+            "invalid review delta query: give exactly one of review_layer_id or block_id",
+        );
+    }
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = SqliteReviewLayerStore::with_tenant(pool.clone(), tenant.clone());
+    let deltas = match (query.review_layer_id, query.block_id) {
+        (Some(layer_id), _) => store.list_deltas_by_layer(&layer_id),
+        (_, Some(block_id)) => store.list_deltas_by_block(&block_id),
+        (None, None) => unreachable!("checked above"),
+    };
+
+    match deltas {
+        Ok(deltas) => match serde_json::to_string(&deltas) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize review deltas: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Delete a review layer and every edit recorded in it.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `review_layer_id` — null-terminated UTF-8 string: UUID of the layer.
+///
+/// Returns a `RtflowResult` whose `data` field is
+/// `{"review_layer_id": ..., "deltas_deleted": n}` on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `review_layer_id` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_delete_review_layer(
+    tenant_id: *const c_char,
+    review_layer_id: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let id_str = match cstring_to_str(review_layer_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let layer_id = match Uuid::parse_str(&id_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid review_layer_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let store = SqliteReviewLayerStore::with_tenant(pool.clone(), tenant.clone());
+    match store.delete_layer(&layer_id) {
+        Ok(deleted) => RtflowResult::success(
+            &serde_json::json!({ "review_layer_id": layer_id, "deltas_deleted": deleted })
+                .to_string(),
+        ),
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Merge every review layer recorded on a document.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `document_id`  — null-terminated UTF-8 string: UUID of the reviewed
+///                  (base) document.
+/// `options_json` — null-terminated UTF-8 string: merge options as for
+///                  `rtflow_merge` (may be `"{}"`).
+///
+/// Layers are merged in the order they were created. The result is not
+/// recorded.
+///
+/// Returns a `RtflowResult` whose `data` field is a `LayerMergeResult` JSON
+/// object (`layer_ids`, conflicted `blocks`, `auto_resolved`,
+/// `pending_review`) on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `tenant_id` must be null or a valid, null-terminated C string; the other
+/// pointer arguments must be valid, non-null, null-terminated C strings.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_merge_review_layers(
+    tenant_id: *const c_char,
+    document_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let doc_str = match cstring_to_str(document_id) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let engine = match merge_engine(&tenant, options_json) {
+        Ok(engine) => engine,
+        Err(e) => return e,
+    };
+    let doc_id = match Uuid::parse_str(&doc_str) {
+        Ok(id) => id,
+        Err(e) => return RtflowResult::failure(&format!("invalid document_id UUID: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let layers = SqliteReviewLayerStore::with_tenant(pool.clone(), tenant.clone());
+    let inputs = match layers.list_layers(&doc_id).and_then(|stored| {
+        stored.iter().map(|l| layers.load_layer(&l.id)).collect::<Result<Vec<_>, _>>()
+    }) {
+        Ok(inputs) => inputs,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+    let blocks = match block_store(pool, &tenant).get_blocks_by_document(&doc_id) {
+        Ok(b) => b,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+
+    let result = match engine.merge_layers(doc_id, &blocks, &inputs) {
+        Ok(r) => r,
+        Err(e) => return RtflowResult::failure(&e.to_string()),
+    };
+    publish_merge_conflicts(result.merge_id, result.base_doc_id, result.pending_review);
+
+    match serde_json::to_string(&result) {
+        Ok(json_out) => RtflowResult::success(&json_out),
+        Err(e) => RtflowResult::failure(&format!("failed to serialize LayerMergeResult: {}", e)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Review activity
+// ---------------------------------------------------------------------------
+
+/// Record a reviewer's layer and its deltas for review analytics.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `layer_json` — null-terminated UTF-8 string: a `LayerDeltas` object
+///   (`{"layer": ReviewLayer, "deltas": [BlockDelta, ...]}`). Every delta
+///   must belong to the layer and change a block of the layer's document;
+///   deltas already recorded are left unchanged, so a layer can be
+///   resubmitted as it grows.
+///
+/// Returns a `RtflowResult` whose `data` field is `{"review_layer_id": ...}`
+/// on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `layer_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[cfg(feature = "merge")]
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_save_review_layer(
+    tenant_id: *const c_char,
+    layer_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let layer_str = match cstring_to_str(layer_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let layer: LayerDeltas = match deserialize_json(&layer_str) {
+        Ok(l) => l,
+        Err(e) => return RtflowResult::failure(&format!("failed to parse review layer JSON: {}", e)),
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    match SqliteMergeStore::with_tenant(pool.clone(), tenant.clone()).save_layer_deltas(&layer) {
+        Ok(()) => RtflowResult::success(
+            &serde_json::json!({ "review_layer_id": layer.layer.id }).to_string(),
+        ),
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Count recorded review deltas, grouped for review analytics (e.g. deltas
+/// per reviewer per section today).
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `query_json` — null-terminated UTF-8 string: JSON object with optional
+///   `"group_by"` (array of `"reviewer"`, `"block"`, `"delta_type"`,
+///   `"day"`), `"reviewer_id"`, `"document_id"`, `"block_id"` and
+///   `"delta_type"` filters, and RFC 3339 `"from"` (inclusive) and `"to"`
+///   (exclusive) bounds on when the delta was recorded; `"{}"` or an empty
+///   string counts every delta.
+///
+/// Returns a `RtflowResult` whose `data` field is a JSON array of
+/// `DeltaActivity` objects, largest group first, on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `query_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_delta_activity(
+    tenant_id: *const c_char,
+    query_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let query_str = match cstring_to_str(query_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let query: DeltaActivityQuery = if query_str.trim().is_empty() {
+        DeltaActivityQuery::default()
+    } else {
+        match serde_json::from_str(&query_str) {
+            Ok(q) => q,
+            Err(e) => return RtflowResult::failure(&format!("invalid activity query: {}", e)),
+        }
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match delta_activity(&conn, &tenant, &query) {
+        Ok(rows) => match serde_json::to_string(&rows) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize DeltaActivity: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Usage
+// ---------------------------------------------------------------------------
+
+/// Date range accepted by `rtflow_usage_report`.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct UsageRange {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+/// Report a tenant's usage totals (documents ingested, blocks stored,
+/// compare runs, merge runs) for billing.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant whose usage is
+///               reported, or null for the `default` tenant.
+///
+/// `range_json` — null-terminated UTF-8 string: JSON object with optional
+///   RFC 3339 `"from"` (inclusive) and `"to"` (exclusive) bounds; `"{}"` or
+///   an empty string reports all recorded usage.
+///
+/// Returns a `RtflowResult` whose `data` field is a `UsageReport` JSON
+/// object on success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `range_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_usage_report(
+    tenant_id: *const c_char,
+    range_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+    let range_str = match cstring_to_str(range_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let range: UsageRange = if range_str.trim().is_empty() {
+        UsageRange::default()
+    } else {
+        match serde_json::from_str(&range_str) {
+            Ok(r) => r,
+            Err(e) => return RtflowResult::failure(&format!("invalid usage range: {}", e)),
+        }
+    };
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match usage_report(&conn, &tenant, range.from, range.to) {
+        Ok(report) => match serde_json::to_string(&report) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize UsageReport: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+/// Remove compare and merge data of the tenant that refers to
+/// deleted documents, and optionally data past a retention window.
+///
+/// `tenant_id` — null-terminated UTF-8 string: tenant the call acts for, or
+///               null for the `default` tenant.
+///
+/// `options_json` — null-terminated UTF-8 string: JSON object with optional
+///   `"dry_run"` (report without removing), `"run_retention_days"`,
+///   `"artifact_retention_days"` and `"interval_hours"` (skip the pass when
+///   the last one finished less than that long ago); `"{}"` or an empty
+///   string removes orphaned runs and merges only.
+///
+/// Hosts call this on their own schedule; with `"interval_hours"` it can be
+/// called on every start-up or timer tick. Artifact files are not deleted:
+/// the report's `expired_artifacts` lists the paths to remove.
+///
+/// Returns a `RtflowResult` whose `data` field is a `GcReport` JSON object on
+/// success.
+///
+/// The returned pointer must be freed with `rtflow_free`.
+///
+/// # Safety
+///
+/// `options_json` must be a valid, non-null, null-terminated C string.
+///
+/// `tenant_id` must be null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn rtflow_collect_garbage(
+    tenant_id: *const c_char,
+    options_json: *const c_char,
+) -> *mut RtflowResult {
+    let tenant = match tenant_arg(tenant_id) {
+        Ok(t) => t,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let options_str = match cstring_to_str(options_json) {
+        Ok(s) => s,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let options: GcOptions = if options_str.trim().is_empty() {
+        GcOptions::default()
+    } else {
+        match deserialize_json(&options_str) {
+            Ok(o) => o,
+            Err(e) => return RtflowResult::failure(&e),
+        }
+    };
+    if let Err(e) = options.validate() {
+        return RtflowResult::failure(&e.to_string());
+    }
+
+    let pool = match get_pool() {
+        Ok(p) => p,
+        Err(e) => return RtflowResult::failure(&e),
+    };
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            return RtflowResult::failure(&format!("failed to acquire database connection: {}", e))
+        }
+    };
+
+    match collect_garbage(&conn, &tenant, &options, Utc::now()) {
+        Ok(report) => match serde_json::to_string(&report) {
+            Ok(json_out) => RtflowResult::success(&json_out),
+            Err(e) => RtflowResult::failure(&format!("failed to serialize GcReport: {}", e)),
+        },
+        Err(e) => RtflowResult::failure(&e.to_string()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Test helpers
+// ---------------------------------------------------------------------------
+
+/// Initialize the FFI layer using an in-memory SQLite database.
+///
+/// This function is provided for integration testing only.  It behaves
+/// identically to `rtflow_init` but uses an ephemeral in-memory database
+/// instead of a file on disk.
+///
+/// Returns `RtflowResult` with `ok = true` and `data = "{}"` on success.
+/// The returned pointer must be freed with `rtflow_free`.
+#[cfg(test)]
+pub fn rtflow_init_memory() -> *mut RtflowResult {
+    init_pool("rtflow_init_memory", rt_core::db::create_memory_pool)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::ptr::null;
+
+    use chrono::Utc;
+    use rt_core::block::{Block, BlockType, Document, DocumentType};
+    use rt_core::db::{create_memory_pool, DbPool, SqliteBlockStore, BlockStore};
+    use rt_core::schema::SCHEMA_VERSION;
+
+    // -----------------------------------------------------------------------
+    // Helpers
+    // -----------------------------------------------------------------------
+
+    /// Create an isolated in-memory pool for a single test.
+    fn make_test_pool() -> DbPool {
+        create_memory_pool().expect("in-memory pool")
+    }
+
+    fn make_test_store(pool: DbPool) -> SqliteBlockStore {
+        SqliteBlockStore::new(pool)
+    }
+
+    fn make_doc(pool: &DbPool) -> Document {
+        let doc = Document {
+            id: Uuid::new_v4(),
+            name: "test-doc".to_string(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: SCHEMA_VERSION.to_string(),
+            normalization_version: "1.0.0".to_string(),
+            hash_contract_version: "1.0.0".to_string(),
+            anchor_version: "1".to_string(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        };
+        let store = SqliteBlockStore::new(pool.clone());
+        store.insert_document(&doc).expect("insert_document");
+        doc
+    }
+
+    fn make_block(doc_id: Uuid, path: &str, text: &str, pos: i32) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, doc_id, pos)
+    }
+
+    fn blocks_json(doc_id: Uuid) -> String {
+        let blocks: Vec<Block> = vec![
+            make_block(doc_id, "1.1", "the borrower shall repay the principal", 0),
+            make_block(doc_id, "1.2", "interest shall accrue at five percent per annum", 1),
+        ];
+        serde_json::to_string(&blocks).expect("serialize blocks")
+    }
+
+    fn to_cstr(s: &str) -> CString {
+        CString::new(s).expect("CString::new")
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: rtflow_free does not panic on null
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn free_null_is_noop() {
+        unsafe {
+            rtflow_free(std::ptr::null_mut());
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: RtflowResult success/failure round-trip
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn result_success_and_free() {
+        unsafe {
+            let ptr = RtflowResult::success(r#"{"ok":true}"#);
+            assert!(!ptr.is_null());
+            assert!((*ptr).ok);
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn result_failure_and_free() {
+        unsafe {
+            let ptr = RtflowResult::failure("something went wrong");
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            RtflowResult::free(ptr);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: rtflow_init with in-memory database (via test helper)
+    // -----------------------------------------------------------------------
+
+    // NOTE: Because DB_POOL is a process-global OnceLock the init tests
+    // interact; each test that needs an initialized pool must work with
+    // whatever state the OnceLock is already in.  The safe approach is to
+    // exercise init functionality via the store directly and only call
+    // rtflow_init_memory once per test binary.
+
+    #[test]
+    fn init_memory_succeeds() {
+        // Attempt to initialise; if the pool is already set from a previous
+        // test in this binary, the function returns an error string – that is
+        // acceptable behaviour which we simply tolerate here.
+        let ptr = rtflow_init_memory();
+        unsafe {
+            assert!(!ptr.is_null());
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn concurrent_init_installs_one_pool() {
+        // Whatever state earlier tests left behind, racing initializers
+        // must install at most one pool and leave one installed.
+        let wins: usize = std::thread::scope(|scope| {
+            let racers: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| unsafe {
+                        let ptr = rtflow_init_memory();
+                        let ok = (*ptr).ok;
+                        RtflowResult::free(ptr);
+                        usize::from(ok)
+                    })
+                })
+                .collect();
+            racers.into_iter().map(|r| r.join().unwrap()).sum()
+        });
+        assert!(wins <= 1);
+        assert!(DB_POOL.get().is_some());
+
+        // Once a pool is installed, later inits fail without opening
+        // (and so creating) the database they name.
+        let path = std::env::temp_dir().join(format!("rtflow-init-{}.db", Uuid::new_v4()));
+        let c_path = to_cstr(path.to_str().unwrap());
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| unsafe {
+                    let ptr = rtflow_init(c_path.as_ptr());
+                    assert!(!(*ptr).ok);
+                    RtflowResult::free(ptr);
+                });
+            }
+        });
+        assert!(!path.exists());
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: marshal helpers
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn cstring_to_str_null_returns_err() {
+        unsafe {
+            let result = cstring_to_str(std::ptr::null());
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn cstring_to_str_valid_returns_ok() {
+        let s = to_cstr("hello world");
+        unsafe {
+            let result = cstring_to_str(s.as_ptr());
+            assert_eq!(result.unwrap(), "hello world");
+        }
+    }
+
+    #[test]
+    fn deserialize_json_valid() {
+        let result: Result<serde_json::Value, _> = deserialize_json(r#"{"key": 42}"#);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap()["key"], 42);
+    }
+
+    #[test]
+    fn deserialize_json_invalid_returns_err() {
+        let result: Result<serde_json::Value, _> = deserialize_json("not json {{{");
+        assert!(result.is_err());
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: ingest blocks via store (unit-level, bypassing global state)
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn store_ingest_blocks_roundtrip() {
+        let pool = make_test_pool();
+        let doc = make_doc(&pool);
+        let store = make_test_store(pool);
+
+        let blocks: Vec<Block> = vec![
+            make_block(doc.id, "1.1", "the borrower shall repay the principal", 0),
+            make_block(doc.id, "1.2", "interest shall accrue at five percent", 1),
+        ];
+
+        store.insert_blocks(&blocks).expect("insert_blocks");
+
+        let fetched = store.get_block_tree(&doc.id).expect("get_block_tree");
+        assert_eq!(fetched.len(), 2);
+        assert_eq!(fetched[0].structural_path, "1.1");
+        assert_eq!(fetched[1].structural_path, "1.2");
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: compare two documents via engine (unit-level)
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn compare_two_docs_via_engine() {
+        let pool = make_test_pool();
+        let left_doc = make_doc(&pool);
+        let right_doc = make_doc(&pool);
+        let store = make_test_store(pool);
+
+        let left_blocks = vec![
+            make_block(left_doc.id, "1.1", "the borrower shall repay the principal", 0),
+            make_block(left_doc.id, "1.2", "interest accrues at five percent", 1),
+        ];
+        let right_blocks = vec![
+            make_block(right_doc.id, "1.1", "the borrower shall repay the principal", 0),
+            make_block(right_doc.id, "1.2", "interest accrues at six percent per annum", 1),
+        ];
+
+        store.insert_blocks(&left_blocks).expect("insert left");
+        store.insert_blocks(&right_blocks).expect("insert right");
+
+        let lft = store.get_block_tree(&left_doc.id).unwrap();
+        let rgt = store.get_block_tree(&right_doc.id).unwrap();
+
+        let engine = CompareEngine::new(CompareConfig::default());
+        let result = engine.compare(left_doc.id, right_doc.id, &lft, &rgt);
+
+        assert_eq!(result.stats.blocks_left, 2);
+        assert_eq!(result.stats.blocks_right, 2);
+        assert_eq!(result.stats.unchanged, 1);
+        assert_eq!(result.stats.modified, 1);
+
+        let json = serde_json::to_string(&result).expect("serialize CompareResult");
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(parsed.get("run_id").is_some());
+        assert!(parsed.get("deltas").is_some());
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: compare identical documents
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn compare_identical_docs_all_unchanged() {
+        let pool = make_test_pool();
+        let doc = make_doc(&pool);
+        let store = make_test_store(pool);
+
+        let blocks = vec![
+            make_block(doc.id, "1.1", "the borrower shall repay the principal", 0),
+            make_block(doc.id, "1.2", "interest shall accrue at five percent per annum", 1),
+        ];
+
+        store.insert_blocks(&blocks).expect("insert");
+
+        let fetched = store.get_block_tree(&doc.id).unwrap();
+
+        let engine = CompareEngine::new(CompareConfig::default());
+        let result = engine.compare(doc.id, doc.id, &fetched, &fetched);
+
+        assert_eq!(result.stats.unchanged, 2);
+        assert_eq!(result.stats.modified, 0);
+        assert_eq!(result.stats.inserted, 0);
+        assert_eq!(result.stats.deleted, 0);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: merge two documents via engine (unit-level)
+    // -----------------------------------------------------------------------
+
+    #[cfg(feature = "merge")]
+    #[test]
+    fn merge_two_docs_via_engine() {
+        let pool = make_test_pool();
+        let base_doc = make_doc(&pool);
+        let incoming_doc = make_doc(&pool);
+        let store = make_test_store(pool);
+
+        let base_blocks = vec![
+            make_block(base_doc.id, "1.1", "the borrower shall repay the principal", 0),
+            make_block(base_doc.id, "1.2", "interest accrues at five percent", 1),
+        ];
+        let incoming_blocks = vec![
+            make_block(incoming_doc.id, "1.1", "the borrower shall repay the principal", 0),
+            make_block(incoming_doc.id, "1.2", "interest accrues at six percent per annum", 1),
+        ];
+
+        store.insert_blocks(&base_blocks).expect("insert base");
+        store.insert_blocks(&incoming_blocks).expect("insert incoming");
+
+        let base = store.get_block_tree(&base_doc.id).unwrap();
+        let incoming = store.get_block_tree(&incoming_doc.id).unwrap();
+
+        let engine = MergeEngine::new();
+        let result = engine.merge(base_doc.id, incoming_doc.id, &base, &incoming);
+
+        let json = serde_json::to_string(&result).expect("serialize MergeResult");
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(parsed.get("merge_id").is_some());
+        assert!(parsed.get("conflicts").is_some());
+        assert!(parsed.get("auto_resolved").is_some());
+        assert!(parsed.get("pending_review").is_some());
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: reviewer redline export registers an artifact (unit-level)
+    // -----------------------------------------------------------------------
+
+    #[cfg(all(feature = "export", feature = "workflow"))]
+    #[test]
+    fn reviewer_redline_export_records_artifact() {
+        use rt_core::artifact::{get_artifacts_by_workflow, insert_artifact, ArtifactType};
+        use rt_merge::layer::DeltaType;
+
+        let pool = make_test_pool();
+        let base_doc = make_doc(&pool);
+        let store = make_test_store(pool.clone());
+
+        let base_blocks = vec![make_block(base_doc.id, "1.1", "the borrower shall repay", 0)];
+        store.insert_blocks(&base_blocks).expect("insert base");
+
+        let conn = pool.get().expect("connection");
+        let wf = WorkflowEngine::create_workflow(&conn, &TenantContext::default(), base_doc.id, "alice")
+            .expect("create_workflow");
+
+        let layer = ReviewLayer::new(wf.id, "bob", base_doc.id);
+        let deltas = vec![BlockDelta::new(
+            layer.id,
+            "bob",
+            base_blocks[0].id,
+            DeltaType::Modify,
+            1,
+            1,
+            serde_json::json!({"text": "lender"}),
+        )];
+
+        let base = store.get_block_tree(&base_doc.id).unwrap();
+        let redline = export_reviewer_redline(&base, &layer, &deltas, &[], ExportFormat::Html);
+        let artifact = redline.to_artifact(wf.id, "/tmp/bob.html").expect("to_artifact");
+        insert_artifact(&conn, &artifact).expect("insert_artifact");
+
+        let listed = get_artifacts_by_workflow(&conn, &wf.id).expect("list artifacts");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].artifact_type, ArtifactType::ReviewerRedlineHtml);
+        assert_eq!(listed[0].content_hash, artifact.content_hash);
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: workflow lifecycle via engine (unit-level)
+    // -----------------------------------------------------------------------
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn workflow_lifecycle_via_engine() {
+        let pool = make_test_pool();
+        let store = SqliteBlockStore::new(pool.clone());
+
+        // Insert a document row for the foreign-key constraint.
+        let doc_id = Uuid::new_v4();
+        let doc = Document {
+            id: doc_id,
+            name: "workflow-test-doc".to_string(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: SCHEMA_VERSION.to_string(),
+            normalization_version: "1.0.0".to_string(),
+            hash_contract_version: "1.0.0".to_string(),
+            anchor_version: "1".to_string(),
             ingested_at: Utc::now(),
             metadata: None,
         };
-        let store = SqliteBlockStore::new(pool.clone());
-        store.insert_document(&doc).expect("insert_document");
-        doc
+        store.insert_document(&doc).expect("insert document");
+
+        let conn = pool.get().expect("connection");
+
+        // Create workflow.
+        let wf = WorkflowEngine::create_workflow(&conn, &TenantContext::default(), doc_id, "alice")
+            .expect("create_workflow");
+
+        use rt_workflow::state::WorkflowState;
+
+        assert_eq!(wf.state, WorkflowState::Draft);
+
+        // Advance through the happy path.
+        let steps = vec![
+            (EventType::CompareStarted, "system"),
+            (EventType::CompareCompleted, "system"),
+            (EventType::ReviewStarted, "alice"),
+        ];
+
+        let mut current = wf;
+        for (et, actor) in steps {
+            current = WorkflowEngine::submit_event(
+                &conn,
+                &TenantContext::default(),
+                current.id,
+                et,
+                actor,
+                serde_json::Value::Null,
+            )
+            .expect("submit_event");
+        }
+
+        assert_eq!(current.state, WorkflowState::InReview);
+
+        // Retrieve via get_workflow and verify JSON serialisation.
+        let fetched = WorkflowEngine::get_workflow(&conn, &TenantContext::default(), current.id)
+            .expect("get_workflow");
+        let json = serde_json::to_string(&fetched).expect("serialize Workflow");
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(parsed.get("id").is_some());
+        assert!(parsed.get("state").is_some());
+        assert_eq!(
+            parsed["state"].as_str().unwrap(),
+            WorkflowState::InReview.as_str()
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: rtflow_ingest_blocks via FFI (requires initialized pool)
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn ffi_ingest_blocks_returns_success_or_not_initialized() {
+        let doc_id = Uuid::new_v4();
+        let json = blocks_json(doc_id);
+        let c_json = to_cstr(&json);
+        let c_doc_id = to_cstr(&doc_id.to_string());
+
+        unsafe {
+            let ptr = rtflow_ingest_blocks(null(), c_json.as_ptr(), c_doc_id.as_ptr());
+            assert!(!ptr.is_null());
+            // We accept either ok (pool initialized) or error (pool not yet set).
+            // The test merely verifies no panic / memory unsafety.
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn ffi_ingest_blocks_validates_and_skips_stored_blocks() {
+        unsafe { RtflowResult::free(rtflow_init_memory()) };
+        let doc_id = Uuid::new_v4();
+        let c_json = to_cstr(&blocks_json(doc_id));
+        let c_doc_id = to_cstr(&doc_id.to_string());
+        let skip = to_cstr(r#"{"on_conflict": "skip"}"#);
+        let ingest = |options: Option<&CString>| unsafe {
+            let (json, doc) = (c_json.as_ptr(), c_doc_id.as_ptr());
+            let ptr = match options {
+                Some(o) => rtflow_ingest_blocks_with_options(null(), json, doc, o.as_ptr()),
+                None => rtflow_ingest_blocks(null(), json, doc),
+            };
+            let out = if (*ptr).ok {
+                Ok(std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap().to_string())
+            } else {
+                Err(std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap().to_string())
+            };
+            RtflowResult::free(ptr);
+            out
+        };
+
+        let first: serde_json::Value = serde_json::from_str(&ingest(None).unwrap()).unwrap();
+        assert_eq!(first["count"], 2);
+        assert_eq!(first["validation"]["issues"], serde_json::json!([]));
+        let again = ingest(None).unwrap_err();
+        assert!(again.starts_with("4 invalid block issue(s)"), "{again}");
+        let skipped = ingest(Some(&skip)).unwrap();
+        let skipped: serde_json::Value = serde_json::from_str(&skipped).unwrap();
+        assert_eq!(skipped["count"], 0);
+
+        let stranger = to_cstr(&Uuid::new_v4().to_string());
+        let dry_run = to_cstr(r#"{"dry_run": true}"#);
+        unsafe {
+            let ptr = rtflow_ingest_blocks_with_options(
+                null(),
+                c_json.as_ptr(),
+                stranger.as_ptr(),
+                dry_run.as_ptr(),
+            );
+            assert!((*ptr).ok);
+            let data = std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap();
+            let report: serde_json::Value = serde_json::from_str(data).unwrap();
+            assert_eq!(report["issues"][0]["kind"], "wrong_document");
+            RtflowResult::free(ptr);
+        }
     }
 
-    fn make_block(doc_id: Uuid, path: &str, text: &str, pos: i32) -> Block {
-        Block::new(BlockType::Clause, path, text, text, None, doc_id, pos)
+    #[test]
+    fn ffi_versions_link_on_ingest_and_compare_the_latest_two() {
+        unsafe { RtflowResult::free(rtflow_init_memory()) };
+        let (v1, v2) = (Uuid::new_v4(), Uuid::new_v4());
+        let call = |ptr: *mut RtflowResult| unsafe {
+            let out = if (*ptr).ok {
+                Ok(std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap().to_string())
+            } else {
+                Err(std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap().to_string())
+            };
+            RtflowResult::free(ptr);
+            out
+        };
+        let (c_v1, c_v2) = (to_cstr(&v1.to_string()), to_cstr(&v2.to_string()));
+        let options = to_cstr(&format!(r#"{{"previous_version_id": "{v1}"}}"#));
+        let (json_v1, json_v2) = (to_cstr(&blocks_json(v1)), to_cstr(&blocks_json(v2)));
+        let empty = to_cstr("{}");
+        unsafe {
+            call(rtflow_ingest_blocks(null(), json_v1.as_ptr(), c_v1.as_ptr())).unwrap();
+            let alone = rtflow_compare_versions(null(), c_v1.as_ptr(), empty.as_ptr());
+            let alone = call(alone).unwrap_err();
+            assert!(alone.contains("no earlier version"), "{alone}");
+
+            let ingest = rtflow_ingest_blocks_with_options(
+                null(),
+                json_v2.as_ptr(),
+                c_v2.as_ptr(),
+                options.as_ptr(),
+            );
+            call(ingest).unwrap();
+            let chain = call(rtflow_document_versions(null(), c_v1.as_ptr())).unwrap();
+            let chain: serde_json::Value = serde_json::from_str(&chain).unwrap();
+            assert_eq!(chain[1]["doc_id"], v2.to_string());
+            assert_eq!(chain[1]["version"], 2);
+
+            let result = rtflow_compare_versions(null(), c_v1.as_ptr(), empty.as_ptr());
+            let result = call(result).unwrap();
+            let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+            assert_eq!(result["left_doc_id"], v1.to_string());
+            assert_eq!(result["right_doc_id"], v2.to_string());
+
+            let again = rtflow_link_version(null(), c_v2.as_ptr(), c_v1.as_ptr());
+            let again = call(again).unwrap_err();
+            assert!(again.contains("already revises"), "{again}");
+        }
     }
 
-    fn blocks_json(doc_id: Uuid) -> String {
-        let blocks: Vec<Block> = vec![
-            make_block(doc_id, "1.1", "the borrower shall repay the principal", 0),
-            make_block(doc_id, "1.2", "interest shall accrue at five percent per annum", 1),
+    #[test]
+    fn ffi_ingest_blocks_with_options_rejects_unknown_options() {
+        let doc_id = Uuid::new_v4();
+        let c_json = to_cstr(&blocks_json(doc_id));
+        let c_doc_id = to_cstr(&doc_id.to_string());
+        for options in [r#"{"on_conflict": "merge"}"#, r#"{"dryrun": true}"#] {
+            let c_options = to_cstr(options);
+            unsafe {
+                let ptr = rtflow_ingest_blocks_with_options(
+                    null(),
+                    c_json.as_ptr(),
+                    c_doc_id.as_ptr(),
+                    c_options.as_ptr(),
+                );
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains("invalid ingest options JSON"), "{msg}");
+                RtflowResult::free(ptr);
+            }
+        }
+    }
+
+    #[test]
+    fn ffi_ingest_batches_reject_bad_and_unknown_ids() {
+        let bad = to_cstr("not-a-uuid");
+        let unknown = to_cstr(&Uuid::new_v4().to_string());
+        let chunk = to_cstr("");
+        unsafe {
+            for (ptr, expected) in [
+                (rtflow_ingest_append(bad.as_ptr(), chunk.as_ptr()), "invalid batch_id"),
+                (rtflow_ingest_commit(bad.as_ptr()), "invalid batch_id"),
+                (rtflow_ingest_append(unknown.as_ptr(), chunk.as_ptr()), "unknown ingest batch"),
+            ] {
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
+                RtflowResult::free(ptr);
+            }
+
+            let ptr = rtflow_ingest_abort(unknown.as_ptr());
+            assert!((*ptr).ok);
+            let data = std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap();
+            assert_eq!(data, r#"{"aborted":false}"#);
+            RtflowResult::free(ptr);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Test: rtflow_workflow_event / rtflow_workflow_state via FFI
+    // (requires initialized pool; skips gracefully when not initialized)
+    // -----------------------------------------------------------------------
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_event_without_init_returns_error() {
+        // When the pool is not set the functions must return a failure result
+        // rather than panicking.  Because the OnceLock may already be set by
+        // init_memory_succeeds() we test the "not initialized" path only when
+        // we can confirm the lock is empty by using a fresh pool directly.
+        //
+        // If the pool IS already set we skip this particular assertion.
+        if DB_POOL.get().is_none() {
+            let wf_id = to_cstr(&Uuid::new_v4().to_string());
+            let event = to_cstr(r#"{"event_type":"compare_started","actor":"system"}"#);
+            unsafe {
+                let ptr = rtflow_workflow_event(null(), wf_id.as_ptr(), event.as_ptr());
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok, "expected failure when pool not initialized");
+                RtflowResult::free(ptr);
+            }
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_event_callback_is_set_and_cleared() {
+        unsafe extern "C" fn ignore(_event_json: *const c_char, _user_data: *mut c_void) {}
+        unsafe {
+            let set: Option<RtflowEventFn> = Some(ignore);
+            for (callback, expected) in [(set, "set"), (None, "cleared")] {
+                let ptr = rtflow_set_event_callback(callback, std::ptr::null_mut());
+                assert!((*ptr).ok);
+                let data = std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap();
+                let data: serde_json::Value = serde_json::from_str(data).unwrap();
+                assert_eq!(data["callback"], expected);
+                RtflowResult::free(ptr);
+            }
+        }
+        assert!(EVENT_CALLBACK.lock().unwrap().is_none());
+    }
+
+    #[cfg(feature = "merge")]
+    #[test]
+    fn ffi_suggest_resolutions_rejects_invalid_merge_id() {
+        let merge_id = to_cstr("not-a-uuid");
+        unsafe {
+            for ptr in [
+                rtflow_suggest_resolutions(null(), merge_id.as_ptr()),
+                rtflow_list_suggestions(null(), merge_id.as_ptr()),
+            ] {
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains("invalid merge_id"), "{msg}");
+                RtflowResult::free(ptr);
+            }
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_create_rejects_bad_arguments() {
+        let initiator = to_cstr("alice");
+        let bad_doc = to_cstr("not-a-uuid");
+        let doc = to_cstr(&Uuid::new_v4().to_string());
+        let blank = to_cstr("  ");
+        unsafe {
+            let ptr = rtflow_workflow_create(null(), bad_doc.as_ptr(), initiator.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid document_id"), "{msg}");
+            RtflowResult::free(ptr);
+
+            let ptr = rtflow_workflow_create(null(), doc.as_ptr(), blank.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("initiator_id"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn ffi_compare_poll_rejects_unknown_run() {
+        let bad = to_cstr("not-a-uuid");
+        let unknown = to_cstr(&Uuid::new_v4().to_string());
+        unsafe {
+            let ptr = rtflow_compare_poll(null(), bad.as_ptr(), 0);
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid run_id"), "{msg}");
+            RtflowResult::free(ptr);
+
+            let ptr = rtflow_compare_poll(null(), unknown.as_ptr(), 0);
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("unknown compare run"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn ffi_job_endpoints_reject_unknown_jobs() {
+        let bad = to_cstr("not-a-uuid");
+        let unknown = to_cstr(&Uuid::new_v4().to_string());
+        unsafe {
+            for (ptr, expected) in [
+                (rtflow_job_status(null(), bad.as_ptr()), "invalid job_id"),
+                (rtflow_job_result(null(), bad.as_ptr()), "invalid job_id"),
+                (rtflow_job_status(null(), unknown.as_ptr()), "unknown job"),
+                (rtflow_job_result(null(), unknown.as_ptr()), "unknown job"),
+                (rtflow_job_cancel(null(), bad.as_ptr()), "invalid job_id"),
+            ] {
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
+                RtflowResult::free(ptr);
+            }
+
+            let ptr = rtflow_job_cancel(null(), unknown.as_ptr());
+            assert!((*ptr).ok);
+            let data = std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap();
+            assert_eq!(data, r#"{"cancelled":false}"#);
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn ffi_run_history_rejects_invalid_ids() {
+        let bad = to_cstr("not-a-uuid");
+        let opts = to_cstr("{}");
+        unsafe {
+            for (ptr, expected) in [
+                (rtflow_run_manifest(null(), bad.as_ptr()), "invalid run_id"),
+                (rtflow_run_history(null(), bad.as_ptr()), "invalid document_id"),
+                (rtflow_reproduce_check(null(), bad.as_ptr(), opts.as_ptr()), "invalid run_id"),
+            ] {
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
+                RtflowResult::free(ptr);
+            }
+        }
+    }
+
+    #[test]
+    fn ffi_calls_act_for_the_tenant_they_name() {
+        unsafe { RtflowResult::free(rtflow_init_memory()) };
+        let doc_id = Uuid::new_v4();
+        let (json, c_doc_id) = (to_cstr(&blocks_json(doc_id)), to_cstr(&doc_id.to_string()));
+        let (acme, bad) = (to_cstr(&format!("acme-{}", doc_id.simple())), to_cstr("acme corp"));
+        let call = |ptr: *mut RtflowResult| unsafe {
+            let out = if (*ptr).ok {
+                Ok(std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap().to_string())
+            } else {
+                Err(std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap().to_string())
+            };
+            RtflowResult::free(ptr);
+            out
+        };
+        unsafe {
+            call(rtflow_ingest_blocks(acme.as_ptr(), json.as_ptr(), c_doc_id.as_ptr())).unwrap();
+            let chain = call(rtflow_document_versions(acme.as_ptr(), c_doc_id.as_ptr())).unwrap();
+            assert!(chain.contains(&doc_id.to_string()), "{chain}");
+            let other = rtflow_document_versions(null(), c_doc_id.as_ptr());
+            assert!(call(other).is_err(), "another tenant's document is not found");
+            let invalid = call(rtflow_document_versions(bad.as_ptr(), c_doc_id.as_ptr()));
+            assert!(invalid.unwrap_err().contains("invalid character"));
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_events_rejects_bad_arguments() {
+        let bad_id = to_cstr("not-a-uuid");
+        let wf_id = to_cstr(&Uuid::new_v4().to_string());
+        let empty = to_cstr("");
+        let zero = to_cstr(r#"{"limit": 0}"#);
+        unsafe {
+            let ptr = rtflow_workflow_events(null(), bad_id.as_ptr(), empty.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid workflow_id"), "{msg}");
+            RtflowResult::free(ptr);
+
+            let ptr = rtflow_workflow_events(null(), wf_id.as_ptr(), zero.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("limit"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_verify_chain_rejects_invalid_uuid() {
+        let bad = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_workflow_verify_chain(null(), bad.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid workflow_id"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn ffi_clause_history_rejects_empty_anchor() {
+        let anchor = to_cstr("");
+        unsafe {
+            let ptr = rtflow_clause_history(null(), anchor.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("anchor_signature"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn ffi_search_blocks_rejects_malformed_query() {
+        for (query, expected) in [
+            ("[]", "expected a JSON object"),
+            (r#"{"limit": 5}"#, "`query` must be a string"),
+            (r#"{"query": "law", "author": "x"}"#, "unknown field"),
+        ] {
+            let query = to_cstr(query);
+            unsafe {
+                let ptr = rtflow_search_blocks(null(), query.as_ptr());
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
+                RtflowResult::free(ptr);
+            }
+        }
+    }
+
+    #[test]
+    fn ffi_find_duplicate_clauses_rejects_invalid_uuid() {
+        let doc = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_find_duplicate_clauses(null(), doc.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid doc_id"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn ffi_create_snapshot_rejects_invalid_uuid() {
+        let doc = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_create_snapshot(null(), doc.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid document UUID"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn ffi_list_documents_rejects_unknown_filter_field() {
+        let filter = to_cstr(r#"{"author": "tester"}"#);
+        unsafe {
+            let ptr = rtflow_list_documents(null(), filter.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid document filter"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_list_rejects_unknown_filter_field() {
+        let filter = to_cstr(r#"{"status": "IN_REVIEW"}"#);
+        unsafe {
+            let ptr = rtflow_workflow_list(null(), filter.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid workflow filter"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_set_config_validates_sections() {
+        let wf_id = to_cstr(&Uuid::new_v4().to_string());
+        for (config, expected) in [
+            (r#"{"export":{}}"#, "invalid workflow config"),
+            (r#"{"compare":{"similarity_threshold":2.0}}"#, "similarity_threshold"),
+            (r#"{"merge":{"auto_accept":false}}"#, "invalid merge options"),
+        ] {
+            let config = to_cstr(config);
+            unsafe {
+                let ptr = rtflow_workflow_set_config(null(), wf_id.as_ptr(), config.as_ptr());
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
+                RtflowResult::free(ptr);
+            }
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_route_review_requires_senior_reviewers() {
+        let wf_id = to_cstr(&Uuid::new_v4().to_string());
+        let request = to_cstr(&format!(r#"{{"run_id":"{}","rules":{{}}}}"#, Uuid::new_v4()));
+        unsafe {
+            let ptr = rtflow_workflow_route_review(null(), wf_id.as_ptr(), request.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("senior_reviewers"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_create_with_options_validates_the_policy() {
+        let doc_id = to_cstr(&Uuid::new_v4().to_string());
+        let initiator = to_cstr("alice");
+        for (options, expected) in [
+            (r#"{"quorum":2}"#, "invalid workflow options"),
+            (r#"{"approval_policy":{"required":0}}"#, "at least one approval"),
+            (r#"{"approval_policy":{"required":2,"approvers":["carol"]}}"#, "2 approvals"),
+            (
+                r#"{"deadlines":[{"state":"COMPLETED","within":{"hours":1}}]}"#,
+                "cannot have a deadline",
+            ),
+        ] {
+            let options = to_cstr(options);
+            unsafe {
+                let ptr = rtflow_workflow_create_with_options(
+                    null(),
+                    doc_id.as_ptr(),
+                    initiator.as_ptr(),
+                    options.as_ptr(),
+                );
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
+                RtflowResult::free(ptr);
+            }
+        }
+        let wf_id = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_workflow_approvals(null(), wf_id.as_ptr());
+            assert!(!(*ptr).ok);
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_check_deadlines_rejects_invalid_time() {
+        let now = to_cstr("next tuesday");
+        unsafe {
+            let ptr = rtflow_workflow_check_deadlines(now.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid now timestamp"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_reviewers_rejects_invalid_workflow_id() {
+        let wf_id = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_workflow_reviewers(null(), wf_id.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid workflow_id"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_set_state_machine_rejects_invalid_definitions() {
+        let cases = [
+            ("not json", "invalid state machine"),
+            (r#"{"transitions": []}"#, "workflow_created"),
+        ];
+        unsafe {
+            for (definition, expected) in cases {
+                let definition = to_cstr(definition);
+                let ptr = rtflow_set_state_machine(definition.as_ptr());
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
+                RtflowResult::free(ptr);
+            }
+        }
+        let ptr = rtflow_get_state_machine();
+        unsafe {
+            assert!((*ptr).ok);
+            let data = std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap();
+            let machine = StateMachine::from_json(data).unwrap();
+            assert_eq!(machine, StateMachine::builtin());
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn ffi_workflow_create_subworkflow_validates_arguments() {
+        let cases = [
+            (to_cstr("not-a-uuid"), to_cstr("bob"), "invalid workflow_id"),
+            (to_cstr(&Uuid::new_v4().to_string()), to_cstr(" "), "reviewer_id must not be empty"),
         ];
-        serde_json::to_string(&blocks).expect("serialize blocks")
+        unsafe {
+            for (wf_id, reviewer_id, expected) in &cases {
+                let (wf_id, reviewer_id) = (wf_id.as_ptr(), reviewer_id.as_ptr());
+                let ptr = rtflow_workflow_create_subworkflow(null(), wf_id, reviewer_id);
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
+                RtflowResult::free(ptr);
+            }
+            let wf_id = to_cstr("not-a-uuid");
+            let ptr = rtflow_workflow_subworkflows(null(), wf_id.as_ptr());
+            assert!(!(*ptr).ok);
+            RtflowResult::free(ptr);
+        }
     }
 
-    fn to_cstr(s: &str) -> CString {
-        CString::new(s).expect("CString::new")
+    #[test]
+    fn workflow_options_only_rewrite_options_with_a_workflow_id() {
+        let tenant = TenantContext::default();
+        assert_eq!(workflow_options(&tenant, "", "compare").unwrap(), "");
+        assert_eq!(workflow_options(&tenant, "[1]", "compare").unwrap(), "[1]");
+        let plain = r#"{"similarity_threshold":0.8}"#;
+        assert_eq!(workflow_options(&tenant, plain, "merge").unwrap(), plain);
+        let err = workflow_options(&tenant, r#"{"workflow_id":"nope"}"#, "compare").unwrap_err();
+        assert!(err.contains("invalid workflow_id"), "{err}");
     }
 
-    // -----------------------------------------------------------------------
-    // Test: rtflow_free does not panic on null
-    // -----------------------------------------------------------------------
+    #[cfg(feature = "workflow")]
+    #[test]
+    fn progress_options_need_a_workflow_and_valid_settings() {
+        let tenant = TenantContext::default();
+        let plain = r#"{"similarity_threshold":0.8,"progress_events":false}"#;
+        let (options, target) = progress_options(&tenant, plain).unwrap();
+        assert_eq!(options, plain);
+        assert!(target.is_none());
+
+        let err = progress_options(&tenant, r#"{"progress_events":true}"#).err().unwrap();
+        assert!(err.contains("requires a workflow_id"), "{err}");
+        let options = format!(
+            r#"{{"workflow_id":"{}","progress_events":{{"min_step":150}}}}"#,
+            Uuid::new_v4()
+        );
+        let err = progress_options(&tenant, &options).err().unwrap();
+        assert!(err.contains("min_step"), "{err}");
+    }
 
+    #[cfg(feature = "merge")]
     #[test]
-    fn free_null_is_noop() {
+    fn ffi_merge_rejects_unknown_option() {
+        let base = to_cstr(&Uuid::new_v4().to_string());
+        let inc = to_cstr(&Uuid::new_v4().to_string());
+        let opts = to_cstr(r#"{"auto_accept_moves":true}"#);
         unsafe {
-            rtflow_free(std::ptr::null_mut());
+            for ptr in [
+                rtflow_merge(null(), base.as_ptr(), inc.as_ptr(), opts.as_ptr()),
+                rtflow_merge_async(null(), base.as_ptr(), inc.as_ptr(), opts.as_ptr()),
+            ] {
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains("invalid merge options"), "{msg}");
+                RtflowResult::free(ptr);
+            }
         }
     }
 
-    // -----------------------------------------------------------------------
-    // Test: RtflowResult success/failure round-trip
-    // -----------------------------------------------------------------------
-
+    #[cfg(feature = "merge")]
     #[test]
-    fn result_success_and_free() {
+    fn ffi_review_layer_endpoints_reject_malformed_input() {
+        let layer = to_cstr(r#"{"reviewer_id": "alice"}"#);
+        let delta = to_cstr(r#"{"review_layer_id": "x", "block_id": "y"}"#);
+        let both = to_cstr(&format!(
+            r#"{{"review_layer_id": "{}", "block_id": "{}"}}"#,
+            Uuid::new_v4(),
+            Uuid::new_v4()
+        ));
+        let id = to_cstr("not-a-uuid");
+        let opts = to_cstr("{}");
         unsafe {
-            let ptr = RtflowResult::success(r#"{"ok":true}"#);
-            assert!(!ptr.is_null());
-            assert!((*ptr).ok);
-            RtflowResult::free(ptr);
+            for (ptr, expected) in [
+                (rtflow_create_review_layer(null(), layer.as_ptr()), "invalid review layer"),
+                (rtflow_append_review_delta(null(), delta.as_ptr()), "invalid review delta"),
+                (rtflow_list_review_deltas(null(), both.as_ptr()), "exactly one"),
+                (rtflow_delete_review_layer(null(), id.as_ptr()), "invalid review_layer_id"),
+                (
+                    rtflow_merge_review_layers(null(), id.as_ptr(), opts.as_ptr()),
+                    "invalid document_id",
+                ),
+            ] {
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
+                RtflowResult::free(ptr);
+            }
         }
     }
 
+    #[cfg(feature = "workflow")]
     #[test]
-    fn result_failure_and_free() {
+    fn ffi_workflow_event_rejects_invalid_document_id() {
+        let wf_id = to_cstr(&Uuid::new_v4().to_string());
+        let event = to_cstr(
+            r#"{"event_type":"compare_started","actor":"system","auto_create":true,"document_id":"nope"}"#,
+        );
         unsafe {
-            let ptr = RtflowResult::failure("something went wrong");
+            let ptr = rtflow_workflow_event(null(), wf_id.as_ptr(), event.as_ptr());
             assert!(!ptr.is_null());
             assert!(!(*ptr).ok);
             RtflowResult::free(ptr);
         }
     }
 
-    // -----------------------------------------------------------------------
-    // Test: rtflow_init with in-memory database (via test helper)
-    // -----------------------------------------------------------------------
-
-    // NOTE: Because DB_POOL is a process-global OnceLock the init tests
-    // interact; each test that needs an initialized pool must work with
-    // whatever state the OnceLock is already in.  The safe approach is to
-    // exercise init functionality via the store directly and only call
-    // rtflow_init_memory once per test binary.
-
+    #[cfg(feature = "workflow")]
     #[test]
-    fn init_memory_succeeds() {
-        // Attempt to initialise; if the pool is already set from a previous
-        // test in this binary, the function returns an error string – that is
-        // acceptable behaviour which we simply tolerate here.
-        let ptr = rtflow_init_memory();
-        unsafe {
-            assert!(!ptr.is_null());
-            RtflowResult::free(ptr);
+    fn ffi_workflow_state_without_init_returns_error() {
+        if DB_POOL.get().is_none() {
+            let wf_id = to_cstr(&Uuid::new_v4().to_string());
+            unsafe {
+                let ptr = rtflow_workflow_state(null(), wf_id.as_ptr());
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok, "expected failure when pool not initialized");
+                RtflowResult::free(ptr);
+            }
         }
     }
 
     // -----------------------------------------------------------------------
-    // Test: marshal helpers
+    // Test: rtflow_compare / rtflow_merge via FFI
+    // (tolerates not-initialized state gracefully)
     // -----------------------------------------------------------------------
 
     #[test]
-    fn cstring_to_str_null_returns_err() {
+    fn ffi_compare_section_deltas_requires_run_and_section() {
+        let query = to_cstr(&format!(r#"{{"run_id":"{}"}}"#, Uuid::new_v4()));
         unsafe {
-            let result = cstring_to_str(std::ptr::null());
-            assert!(result.is_err());
+            let ptr = rtflow_compare_section_deltas(null(), query.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid section delta query"), "{msg}");
+            RtflowResult::free(ptr);
         }
     }
 
     #[test]
-    fn cstring_to_str_valid_returns_ok() {
-        let s = to_cstr("hello world");
-        unsafe {
-            let result = cstring_to_str(s.as_ptr());
-            assert_eq!(result.unwrap(), "hello world");
+    fn ffi_compare_without_init_returns_error() {
+        if DB_POOL.get().is_none() {
+            let left = to_cstr(&Uuid::new_v4().to_string());
+            let right = to_cstr(&Uuid::new_v4().to_string());
+            let opts = to_cstr("{}");
+            unsafe {
+                let ptr = rtflow_compare(null(), left.as_ptr(), right.as_ptr(), opts.as_ptr());
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok);
+                RtflowResult::free(ptr);
+            }
         }
     }
 
+    #[cfg(feature = "merge")]
     #[test]
-    fn deserialize_json_valid() {
-        let result: Result<serde_json::Value, _> = deserialize_json(r#"{"key": 42}"#);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap()["key"], 42);
+    fn ffi_merge_without_init_returns_error() {
+        if DB_POOL.get().is_none() {
+            let base = to_cstr(&Uuid::new_v4().to_string());
+            let inc = to_cstr(&Uuid::new_v4().to_string());
+            let opts = to_cstr("{}");
+            unsafe {
+                let ptr = rtflow_merge(null(), base.as_ptr(), inc.as_ptr(), opts.as_ptr());
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok);
+                RtflowResult::free(ptr);
+            }
+        }
     }
 
     #[test]
-    fn deserialize_json_invalid_returns_err() {
-        let result: Result<serde_json::Value, _> = deserialize_json("not json {{{");
-        assert!(result.is_err());
+    fn decode_hex_accepts_pairs_only() {
+        assert_eq!(decode_hex("00ff10").unwrap(), vec![0x00, 0xff, 0x10]);
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
     }
 
-    // -----------------------------------------------------------------------
-    // Test: ingest blocks via store (unit-level, bypassing global state)
-    // -----------------------------------------------------------------------
-
     #[test]
-    fn store_ingest_blocks_roundtrip() {
-        let pool = make_test_pool();
-        let doc = make_doc(&pool);
-        let store = make_test_store(pool);
-
-        let blocks: Vec<Block> = vec![
-            make_block(doc.id, "1.1", "the borrower shall repay the principal", 0),
-            make_block(doc.id, "1.2", "interest shall accrue at five percent", 1),
-        ];
-
-        store.insert_blocks(&blocks).expect("insert_blocks");
-
-        let fetched = store.get_block_tree(&doc.id).expect("get_block_tree");
-        assert_eq!(fetched.len(), 2);
-        assert_eq!(fetched[0].structural_path, "1.1");
-        assert_eq!(fetched[1].structural_path, "1.2");
+    fn ffi_configure_hashing_rejects_bad_config() {
+        for bad in [
+            r#"{"key_hex":"00"}"#,
+            r#"{"contract_version":"1.0.0-hmac-sha256","key_hex":"xyz"}"#,
+            r#"{"contract_version":"1.0.0","salt":"00"}"#,
+        ] {
+            let c = to_cstr(bad);
+            unsafe {
+                let ptr = rtflow_configure_hashing(c.as_ptr());
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok);
+                RtflowResult::free(ptr);
+            }
+        }
     }
 
-    // -----------------------------------------------------------------------
-    // Test: compare two documents via engine (unit-level)
-    // -----------------------------------------------------------------------
+    #[test]
+    fn ffi_delta_activity_rejects_malformed_query() {
+        for bad in [r#"{"group_by":["section"]}"#, r#"{"reviewer":"alice"}"#] {
+            let c = to_cstr(bad);
+            unsafe {
+                let ptr = rtflow_delta_activity(null(), c.as_ptr());
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok);
+                RtflowResult::free(ptr);
+            }
+        }
+    }
 
     #[test]
-    fn compare_two_docs_via_engine() {
-        let pool = make_test_pool();
-        let left_doc = make_doc(&pool);
-        let right_doc = make_doc(&pool);
-        let store = make_test_store(pool);
+    fn ffi_set_parsing_mode_accepts_known_modes_only() {
+        unsafe {
+            let bad = to_cstr("pedantic");
+            let ptr = rtflow_set_parsing_mode(bad.as_ptr());
+            assert!(!(*ptr).ok);
+            RtflowResult::free(ptr);
 
-        let left_blocks = vec![
-            make_block(left_doc.id, "1.1", "the borrower shall repay the principal", 0),
-            make_block(left_doc.id, "1.2", "interest accrues at five percent", 1),
-        ];
-        let right_blocks = vec![
-            make_block(right_doc.id, "1.1", "the borrower shall repay the principal", 0),
-            make_block(right_doc.id, "1.2", "interest accrues at six percent per annum", 1),
-        ];
+            // Other tests read blocks concurrently, so only the default is set.
+            let lenient = to_cstr("lenient");
+            let ptr = rtflow_set_parsing_mode(lenient.as_ptr());
+            assert!((*ptr).ok);
+            RtflowResult::free(ptr);
+        }
+    }
 
-        store.insert_blocks(&left_blocks).expect("insert left");
-        store.insert_blocks(&right_blocks).expect("insert right");
+    #[test]
+    fn ffi_usage_report_rejects_malformed_range() {
+        for bad in [r#"{"from":"yesterday"}"#, r#"{"since":"2024-01-01T00:00:00Z"}"#] {
+            let c = to_cstr(bad);
+            unsafe {
+                let ptr = rtflow_usage_report(null(), c.as_ptr());
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok);
+                RtflowResult::free(ptr);
+            }
+        }
+    }
 
-        let lft = store.get_block_tree(&left_doc.id).unwrap();
-        let rgt = store.get_block_tree(&right_doc.id).unwrap();
+    #[test]
+    fn ffi_collect_garbage_rejects_invalid_options() {
+        for bad in [r#"{"retention_days":30}"#, r#"{"run_retention_days":0}"#] {
+            let c = to_cstr(bad);
+            unsafe {
+                let ptr = rtflow_collect_garbage(null(), c.as_ptr());
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok);
+                RtflowResult::free(ptr);
+            }
+        }
+    }
 
-        let engine = CompareEngine::new(CompareConfig::default());
-        let result = engine.compare(left_doc.id, right_doc.id, &lft, &rgt);
+    #[test]
+    fn ffi_health_always_returns_report() {
+        let ptr = rtflow_health();
+        unsafe {
+            assert!(!ptr.is_null());
+            assert!((*ptr).ok);
+            let data = std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap();
+            let report: serde_json::Value = serde_json::from_str(data).unwrap();
+            if DB_POOL.get().is_none() {
+                assert_eq!(report["status"], "unavailable");
+                assert_eq!(report["ready"], false);
+            }
+            RtflowResult::free(ptr);
+        }
+    }
 
-        assert_eq!(result.stats.blocks_left, 2);
-        assert_eq!(result.stats.blocks_right, 2);
-        assert_eq!(result.stats.unchanged, 1);
-        assert_eq!(result.stats.modified, 1);
+    #[cfg(feature = "merge")]
+    #[test]
+    fn ffi_merge3_rejects_invalid_ancestor_uuid() {
+        let bad = to_cstr("not-a-uuid");
+        let base = to_cstr(&Uuid::new_v4().to_string());
+        let inc = to_cstr(&Uuid::new_v4().to_string());
+        let opts = to_cstr("{}");
+        unsafe {
+            let ptr =
+                rtflow_merge3(null(), bad.as_ptr(), base.as_ptr(), inc.as_ptr(), opts.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("ancestor_doc_id"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
 
-        let json = serde_json::to_string(&result).expect("serialize CompareResult");
-        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
-        assert!(parsed.get("run_id").is_some());
-        assert!(parsed.get("deltas").is_some());
+    #[cfg(feature = "merge")]
+    #[test]
+    fn ffi_merge_lookups_reject_invalid_uuid() {
+        let bad = to_cstr("not-a-uuid");
+        unsafe {
+            for ptr in [
+                rtflow_get_merge(null(), bad.as_ptr()),
+                rtflow_list_conflicts(null(), bad.as_ptr()),
+            ] {
+                assert!(!ptr.is_null());
+                assert!(!(*ptr).ok);
+                RtflowResult::free(ptr);
+            }
+        }
     }
 
     // -----------------------------------------------------------------------
-    // Test: compare identical documents
+    // Test: invalid UUID returns clean error
     // -----------------------------------------------------------------------
 
     #[test]
-    fn compare_identical_docs_all_unchanged() {
-        let pool = make_test_pool();
-        let doc = make_doc(&pool);
-        let store = make_test_store(pool);
-
-        let blocks = vec![
-            make_block(doc.id, "1.1", "the borrower shall repay the principal", 0),
-            make_block(doc.id, "1.2", "interest shall accrue at five percent per annum", 1),
-        ];
-
-        store.insert_blocks(&blocks).expect("insert");
-
-        let fetched = store.get_block_tree(&doc.id).unwrap();
-
-        let engine = CompareEngine::new(CompareConfig::default());
-        let result = engine.compare(doc.id, doc.id, &fetched, &fetched);
-
-        assert_eq!(result.stats.unchanged, 2);
-        assert_eq!(result.stats.modified, 0);
-        assert_eq!(result.stats.inserted, 0);
-        assert_eq!(result.stats.deleted, 0);
+    fn ffi_ingest_invalid_uuid_returns_failure() {
+        let c_json = to_cstr("[]");
+        let c_bad_id = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_ingest_blocks(null(), c_json.as_ptr(), c_bad_id.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            RtflowResult::free(ptr);
+        }
     }
 
-    // -----------------------------------------------------------------------
-    // Test: merge two documents via engine (unit-level)
-    // -----------------------------------------------------------------------
+    #[cfg(feature = "ingest")]
+    #[test]
+    fn ffi_ingest_docx_invalid_uuid_returns_failure() {
+        let path = to_cstr("/nonexistent.docx");
+        let doc_id = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_ingest_docx(null(), path.as_ptr(), doc_id.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            RtflowResult::free(ptr);
+        }
+    }
 
     #[test]
-    fn merge_two_docs_via_engine() {
-        let pool = make_test_pool();
-        let base_doc = make_doc(&pool);
-        let incoming_doc = make_doc(&pool);
-        let store = make_test_store(pool);
-
-        let base_blocks = vec![
-            make_block(base_doc.id, "1.1", "the borrower shall repay the principal", 0),
-            make_block(base_doc.id, "1.2", "interest accrues at five percent", 1),
-        ];
-        let incoming_blocks = vec![
-            make_block(incoming_doc.id, "1.1", "the borrower shall repay the principal", 0),
-            make_block(incoming_doc.id, "1.2", "interest accrues at six percent per annum", 1),
-        ];
+    fn ffi_attach_summaries_rejects_malformed_result() {
+        let result = to_cstr("{}");
+        let summaries = to_cstr("");
+        unsafe {
+            let ptr = rtflow_attach_summaries(result.as_ptr(), summaries.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid CompareResult JSON"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
 
-        store.insert_blocks(&base_blocks).expect("insert base");
-        store.insert_blocks(&incoming_blocks).expect("insert incoming");
+    #[test]
+    fn ffi_alignment_override_rejects_unknown_label() {
+        let json = to_cstr(&format!(
+            r#"{{"corpus": "loans", "left_block_id": "{}", "right_block_id": "{}", "label": "same"}}"#,
+            Uuid::new_v4(),
+            Uuid::new_v4()
+        ));
+        unsafe {
+            let ptr = rtflow_record_alignment_override(json.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid alignment override"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
 
-        let base = store.get_block_tree(&base_doc.id).unwrap();
-        let incoming = store.get_block_tree(&incoming_doc.id).unwrap();
+    #[test]
+    fn ffi_find_near_duplicates_rejects_invalid_uuid() {
+        let bad = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_find_near_duplicates(null(), bad.as_ptr(), 0.0);
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            RtflowResult::free(ptr);
+        }
+    }
 
-        let engine = MergeEngine::new();
-        let result = engine.merge(base_doc.id, incoming_doc.id, &base, &incoming);
+    #[test]
+    fn ffi_resolve_anchor_rejects_unknown_fields() {
+        let query = to_cstr(r#"{"anchor":"b-0123456789abcdef","document":"x"}"#);
+        unsafe {
+            let ptr = rtflow_resolve_anchor(null(), query.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid anchor query"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
 
-        let json = serde_json::to_string(&result).expect("serialize MergeResult");
-        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
-        assert!(parsed.get("merge_id").is_some());
-        assert!(parsed.get("conflicts").is_some());
-        assert!(parsed.get("auto_resolved").is_some());
-        assert!(parsed.get("pending_review").is_some());
+    #[test]
+    fn ffi_get_defined_terms_rejects_invalid_uuid() {
+        let bad = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_get_defined_terms(null(), bad.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid document UUID"), "{msg}");
+            RtflowResult::free(ptr);
+        }
     }
 
-    // -----------------------------------------------------------------------
-    // Test: workflow lifecycle via engine (unit-level)
-    // -----------------------------------------------------------------------
+    #[test]
+    fn ffi_get_entities_rejects_invalid_uuid() {
+        let bad = to_cstr("not-a-uuid");
+        unsafe {
+            let ptr = rtflow_get_entities(null(), bad.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid document UUID"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
 
     #[test]
-    fn workflow_lifecycle_via_engine() {
-        let pool = make_test_pool();
-        let store = SqliteBlockStore::new(pool.clone());
+    fn ffi_render_compare_html_rejects_malformed_input() {
+        let bad = to_cstr("not a run id or result");
+        unsafe {
+            let ptr = rtflow_render_compare_html(null(), bad.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("expected a run UUID or CompareResult JSON"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
 
-        // Insert a document row for the foreign-key constraint.
-        let doc_id = Uuid::new_v4();
-        let doc = Document {
-            id: doc_id,
-            name: "workflow-test-doc".to_string(),
-            source_path: None,
-            doc_type: DocumentType::Original,
-            schema_version: SCHEMA_VERSION.to_string(),
-            normalization_version: "1.0.0".to_string(),
-            hash_contract_version: "1.0.0".to_string(),
-            ingested_at: Utc::now(),
-            metadata: None,
+    #[test]
+    fn ffi_compare_result_diff_reports_reclassified_pairs() {
+        let (l, r) = (Uuid::new_v4(), Uuid::new_v4());
+        let block = |doc, text: &str| {
+            rt_core::Block::new(rt_core::BlockType::Clause, "1.", text, text, None, doc, 0)
         };
-        store.insert_document(&doc).expect("insert document");
-
-        let conn = pool.get().expect("connection");
+        let left = [block(l, "Fees are payable in thirty days.")];
+        let right = [block(r, "Fees are payable in sixty days.")];
+        let before = CompareEngine::default().compare(l, r, &left, &right);
+        let mut after = before.clone();
+        after.deltas[0].kind = rt_compare::result::DeltaKind::Moved;
+        let mut other_pair = before.clone();
+        other_pair.right_doc_id = Uuid::new_v4();
+
+        let json = |result: &CompareResult| to_cstr(&serde_json::to_string(result).unwrap());
+        unsafe {
+            let ptr =
+                rtflow_compare_result_diff(null(), json(&before).as_ptr(), json(&after).as_ptr());
+            assert!((*ptr).ok);
+            let data = std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap();
+            let diff: serde_json::Value = serde_json::from_str(data).unwrap();
+            assert_eq!(diff["reclassified"][0]["before"], "modified");
+            assert_eq!(diff["reclassified"][0]["after"], "moved");
+            assert_eq!(diff["unchanged"], 0);
+            RtflowResult::free(ptr);
 
-        // Create workflow.
-        let wf = WorkflowEngine::create_workflow(&conn, doc_id, "alice")
-            .expect("create_workflow");
+            let ptr = rtflow_compare_result_diff(
+                null(),
+                json(&before).as_ptr(),
+                json(&other_pair).as_ptr(),
+            );
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("different document pairs"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
 
-        use rt_workflow::state::WorkflowState;
+    #[test]
+    fn ffi_compare_result_diff_rejects_different_output_modes() {
+        let (l, r) = (Uuid::new_v4(), Uuid::new_v4());
+        let block = |doc, path: &str, text: &str, idx| {
+            rt_core::Block::new(rt_core::BlockType::Clause, path, text, text, None, doc, idx)
+        };
+        let left = [
+            block(l, "1.", "Fees are payable in thirty days.", 0),
+            block(l, "2.", "Notices are given in writing.", 1),
+        ];
+        let right = [
+            block(r, "1.", "Fees are payable in sixty days.", 0),
+            block(r, "2.", "Notices are given in writing.", 1),
+        ];
+        let compare = |options: &str| {
+            let config = CompareConfig::from_json(options).unwrap();
+            CompareEngine::new(config).compare(l, r, &left, &right)
+        };
+        let full = compare("{}");
+        let changes_only = compare(r#"{"output_mode":"changes_only"}"#);
+        let without_unchanged = compare(r#"{"omit_unchanged":true}"#);
 
-        assert_eq!(wf.state, WorkflowState::Draft);
+        let json = |result: &CompareResult| to_cstr(&serde_json::to_string(result).unwrap());
+        unsafe {
+            for other in [&changes_only, &without_unchanged] {
+                let ptr =
+                    rtflow_compare_result_diff(null(), json(&full).as_ptr(), json(other).as_ptr());
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains("different output modes"), "{msg}");
+                RtflowResult::free(ptr);
+            }
 
-        // Advance through the happy path.
-        let steps = vec![
-            (EventType::CompareStarted, "system"),
-            (EventType::CompareCompleted, "system"),
-            (EventType::ReviewStarted, "alice"),
-        ];
+            let ptr =
+                rtflow_compare_result_diff(null(), json(&full).as_ptr(), json(&full).as_ptr());
+            assert!((*ptr).ok);
+            RtflowResult::free(ptr);
+        }
+    }
 
-        let mut current = wf;
-        for (et, actor) in steps {
-            current = WorkflowEngine::submit_event(
-                &conn,
-                current.id,
-                et,
-                actor,
-                serde_json::Value::Null,
-            )
-            .expect("submit_event");
+    #[test]
+    fn ffi_compare_incremental_rejects_malformed_dirty_ids() {
+        let previous = to_cstr(&Uuid::new_v4().to_string());
+        let dirty = to_cstr("[\"not-a-uuid\"]");
+        let options = to_cstr("");
+        unsafe {
+            let ptr = rtflow_compare_incremental(
+                null(),
+                previous.as_ptr(),
+                dirty.as_ptr(),
+                options.as_ptr(),
+            );
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid dirty block ids"), "{msg}");
+            RtflowResult::free(ptr);
         }
+    }
 
-        assert_eq!(current.state, WorkflowState::InReview);
+    #[test]
+    fn ffi_amendment_endpoints_reject_invalid_uuids() {
+        let bad = to_cstr("not-a-uuid");
+        let good = to_cstr(&Uuid::new_v4().to_string());
+        let options = to_cstr("");
+        unsafe {
+            let ptr =
+                rtflow_compare_amendment(null(), bad.as_ptr(), good.as_ptr(), options.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid left_doc_id UUID"), "{msg}");
+            RtflowResult::free(ptr);
 
-        // Retrieve via get_workflow and verify JSON serialisation.
-        let fetched = WorkflowEngine::get_workflow(&conn, current.id).expect("get_workflow");
-        let json = serde_json::to_string(&fetched).expect("serialize Workflow");
-        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
-        assert!(parsed.get("id").is_some());
-        assert!(parsed.get("state").is_some());
-        assert_eq!(
-            parsed["state"].as_str().unwrap(),
-            WorkflowState::InReview.as_str()
-        );
+            let ptr = rtflow_apply_amendment(null(), good.as_ptr(), bad.as_ptr());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid amendment_doc_id UUID"), "{msg}");
+            RtflowResult::free(ptr);
+        }
     }
 
-    // -----------------------------------------------------------------------
-    // Test: rtflow_ingest_blocks via FFI (requires initialized pool)
-    // -----------------------------------------------------------------------
-
     #[test]
-    fn ffi_ingest_blocks_returns_success_or_not_initialized() {
-        let doc_id = Uuid::new_v4();
-        let json = blocks_json(doc_id);
-        let c_json = to_cstr(&json);
-        let c_doc_id = to_cstr(&doc_id.to_string());
-
+    fn ffi_get_cross_references_rejects_invalid_uuid() {
+        let bad = to_cstr("not-a-uuid");
+        let target = to_cstr("5.2");
         unsafe {
-            let ptr = rtflow_ingest_blocks(c_json.as_ptr(), c_doc_id.as_ptr());
+            let ptr = rtflow_get_cross_references(null(), bad.as_ptr(), target.as_ptr());
             assert!(!ptr.is_null());
-            // We accept either ok (pool initialized) or error (pool not yet set).
-            // The test merely verifies no panic / memory unsafety.
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid document UUID"), "{msg}");
             RtflowResult::free(ptr);
         }
     }
 
-    // -----------------------------------------------------------------------
-    // Test: rtflow_workflow_event / rtflow_workflow_state via FFI
-    // (requires initialized pool; skips gracefully when not initialized)
-    // -----------------------------------------------------------------------
-
+    #[cfg(feature = "ingest")]
     #[test]
-    fn ffi_workflow_event_without_init_returns_error() {
-        // When the pool is not set the functions must return a failure result
-        // rather than panicking.  Because the OnceLock may already be set by
-        // init_memory_succeeds() we test the "not initialized" path only when
-        // we can confirm the lock is empty by using a fresh pool directly.
-        //
-        // If the pool IS already set we skip this particular assertion.
-        if DB_POOL.get().is_none() {
-            let wf_id = to_cstr(&Uuid::new_v4().to_string());
-            let event = to_cstr(r#"{"event_type":"compare_started","actor":"system"}"#);
-            unsafe {
-                let ptr = rtflow_workflow_event(wf_id.as_ptr(), event.as_ptr());
-                assert!(!ptr.is_null());
-                assert!(!(*ptr).ok, "expected failure when pool not initialized");
-                RtflowResult::free(ptr);
-            }
+    fn ffi_ingest_docx_rejects_unknown_option() {
+        let path = to_cstr("/nonexistent.docx");
+        let doc_id = to_cstr(&Uuid::new_v4().to_string());
+        let opts = to_cstr(r#"{"max_block_token": 512}"#);
+        unsafe {
+            let ptr = rtflow_ingest_docx_with_options(
+                null(),
+                path.as_ptr(),
+                doc_id.as_ptr(),
+                opts.as_ptr(),
+            );
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid ingest options"), "{msg}");
+            RtflowResult::free(ptr);
         }
     }
 
     #[test]
-    fn ffi_workflow_state_without_init_returns_error() {
-        if DB_POOL.get().is_none() {
-            let wf_id = to_cstr(&Uuid::new_v4().to_string());
+    fn ffi_rehash_document_rejects_bad_and_unknown_ids() {
+        unsafe { RtflowResult::free(rtflow_init_memory()) };
+        let unknown = Uuid::new_v4().to_string();
+        for (id, expected) in [("nope", "invalid document UUID"), (&unknown, "failed to rehash")] {
+            let id = to_cstr(id);
             unsafe {
-                let ptr = rtflow_workflow_state(wf_id.as_ptr());
-                assert!(!ptr.is_null());
-                assert!(!(*ptr).ok, "expected failure when pool not initialized");
+                let ptr = rtflow_rehash_document(null(), id.as_ptr());
+                assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
                 RtflowResult::free(ptr);
             }
         }
     }
 
-    // -----------------------------------------------------------------------
-    // Test: rtflow_compare / rtflow_merge via FFI
-    // (tolerates not-initialized state gracefully)
-    // -----------------------------------------------------------------------
+    #[test]
+    fn ffi_migrate_content_store_reports_the_blocks_moved() {
+        unsafe { RtflowResult::free(rtflow_init_memory()) };
+        unsafe {
+            let ptr = rtflow_migrate_content_store(null());
+            assert!((*ptr).ok);
+            let data = std::ffi::CStr::from_ptr((*ptr).data).to_str().unwrap();
+            let migration: serde_json::Value = serde_json::from_str(data).unwrap();
+            assert_eq!(migration["shared"], 0);
+            RtflowResult::free(ptr);
+        }
+    }
 
+    #[cfg(feature = "ingest")]
     #[test]
-    fn ffi_compare_without_init_returns_error() {
-        if DB_POOL.get().is_none() {
-            let left = to_cstr(&Uuid::new_v4().to_string());
-            let right = to_cstr(&Uuid::new_v4().to_string());
-            let opts = to_cstr("{}");
+    fn ffi_ingest_text_rejects_bad_options_and_blank_text() {
+        unsafe { RtflowResult::free(rtflow_init_memory()) };
+        let doc_id = to_cstr(&Uuid::new_v4().to_string());
+        for (text, options, expected) in [
+            ("1. Scope", r#"{"title": "msa"}"#, "invalid ingest options"),
+            ("\n  \n", r#"{"name": "blank"}"#, "no content to ingest"),
+            ("1. Scope", r#"{"anchor_version": "2+path"}"#, "unknown anchor version"),
+        ] {
+            let (text, options) = (to_cstr(text), to_cstr(options));
             unsafe {
-                let ptr = rtflow_compare(left.as_ptr(), right.as_ptr(), opts.as_ptr());
-                assert!(!ptr.is_null());
+                let ptr =
+                    rtflow_ingest_text(null(), text.as_ptr(), doc_id.as_ptr(), options.as_ptr());
                 assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
                 RtflowResult::free(ptr);
             }
         }
     }
 
+    #[cfg(feature = "ingest")]
     #[test]
-    fn ffi_merge_without_init_returns_error() {
-        if DB_POOL.get().is_none() {
-            let base = to_cstr(&Uuid::new_v4().to_string());
-            let inc = to_cstr(&Uuid::new_v4().to_string());
-            let opts = to_cstr("{}");
+    fn ffi_check_fidelity_rejects_malformed_filter() {
+        let filter = to_cstr(r#"{"doc_type": 3}"#);
+        unsafe {
+            let ptr = rtflow_check_fidelity(null(), filter.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid document filter"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "ingest")]
+    #[test]
+    fn ffi_reingest_docx_rejects_invalid_uuid() {
+        let path = to_cstr("/nonexistent.docx");
+        let doc_id = to_cstr("not-a-uuid");
+        let opts = to_cstr("{}");
+        unsafe {
+            let ptr = rtflow_reingest_docx(null(), path.as_ptr(), doc_id.as_ptr(), opts.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("invalid document UUID"), "{msg}");
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[cfg(feature = "ingest")]
+    #[test]
+    fn ffi_materialize_view_rejects_invalid_input() {
+        let valid = Uuid::new_v4().to_string();
+        for (doc_id, mode, expected) in [
+            ("not-a-uuid", "accepted", "invalid document UUID"),
+            (valid.as_str(), "final", "unknown view mode"),
+        ] {
+            let doc_id = to_cstr(doc_id);
+            let mode = to_cstr(mode);
             unsafe {
-                let ptr = rtflow_merge(base.as_ptr(), inc.as_ptr(), opts.as_ptr());
+                let ptr = rtflow_materialize_view(null(), doc_id.as_ptr(), mode.as_ptr());
                 assert!(!ptr.is_null());
                 assert!(!(*ptr).ok);
+                let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+                assert!(msg.contains(expected), "{msg}");
                 RtflowResult::free(ptr);
             }
         }
     }
 
-    // -----------------------------------------------------------------------
-    // Test: invalid UUID returns clean error
-    // -----------------------------------------------------------------------
-
     #[test]
-    fn ffi_ingest_invalid_uuid_returns_failure() {
-        let c_json = to_cstr("[]");
-        let c_bad_id = to_cstr("not-a-uuid");
+    fn ffi_compare_rejects_unknown_option() {
+        let left = to_cstr(&Uuid::new_v4().to_string());
+        let right = to_cstr(&Uuid::new_v4().to_string());
+        let opts = to_cstr(r#"{"similarity_treshold": 0.5}"#);
         unsafe {
-            let ptr = rtflow_ingest_blocks(c_json.as_ptr(), c_bad_id.as_ptr());
+            let ptr = rtflow_compare(null(), left.as_ptr(), right.as_ptr(), opts.as_ptr());
             assert!(!ptr.is_null());
             assert!(!(*ptr).ok);
+            let msg = std::ffi::CStr::from_ptr((*ptr).error).to_str().unwrap();
+            assert!(msg.contains("similarity_treshold"), "got: {msg}");
             RtflowResult::free(ptr);
         }
     }
@@ -956,13 +9105,27 @@ mod tests {
         let good = to_cstr(&Uuid::new_v4().to_string());
         let opts = to_cstr("{}");
         unsafe {
-            let ptr = rtflow_compare(bad.as_ptr(), good.as_ptr(), opts.as_ptr());
+            let ptr = rtflow_compare(null(), bad.as_ptr(), good.as_ptr(), opts.as_ptr());
+            assert!(!ptr.is_null());
+            assert!(!(*ptr).ok);
+            RtflowResult::free(ptr);
+        }
+    }
+
+    #[test]
+    fn ffi_compare_text_invalid_uuid_returns_failure() {
+        let bad = to_cstr("bad-uuid");
+        let good = to_cstr(&Uuid::new_v4().to_string());
+        let opts = to_cstr("{}");
+        unsafe {
+            let ptr = rtflow_compare_text(null(), good.as_ptr(), bad.as_ptr(), opts.as_ptr());
             assert!(!ptr.is_null());
             assert!(!(*ptr).ok);
             RtflowResult::free(ptr);
         }
     }
 
+    #[cfg(feature = "workflow")]
     #[test]
     fn ffi_workflow_event_invalid_event_type() {
         // Pool may or may not be set; either way an invalid event_type must
@@ -970,19 +9133,20 @@ mod tests {
         let wf_id = to_cstr(&Uuid::new_v4().to_string());
         let event = to_cstr(r#"{"event_type":"not_real_event","actor":"system"}"#);
         unsafe {
-            let ptr = rtflow_workflow_event(wf_id.as_ptr(), event.as_ptr());
+            let ptr = rtflow_workflow_event(null(), wf_id.as_ptr(), event.as_ptr());
             assert!(!ptr.is_null());
             assert!(!(*ptr).ok);
             RtflowResult::free(ptr);
         }
     }
 
+    #[cfg(feature = "workflow")]
     #[test]
     fn ffi_workflow_event_missing_actor() {
         let wf_id = to_cstr(&Uuid::new_v4().to_string());
         let event = to_cstr(r#"{"event_type":"compare_started"}"#);
         unsafe {
-            let ptr = rtflow_workflow_event(wf_id.as_ptr(), event.as_ptr());
+            let ptr = rtflow_workflow_event(null(), wf_id.as_ptr(), event.as_ptr());
             assert!(!ptr.is_null());
             assert!(!(*ptr).ok);
             RtflowResult::free(ptr);
@@ -0,0 +1,424 @@
+//! Registry of background compare and merge jobs.
+//!
+//! `rtflow_compare_async` (or `rtflow_merge_async`) hands a whole comparison
+//! (or merge) to a background worker and returns at once; the job reports
+//! its progress here as it goes and keeps its `CompareResult` (or
+//! `MergeResult`) until the host collects it with `rtflow_job_result`.
+//! Unlike a streamed run (see [`crate::stream`]), a job delivers its deltas
+//! all at once; a merge job reports no progress until it finishes. At most
+//! [`JOB_WORKERS`] jobs run at a time; later ones wait their turn. A job is
+//! dropped from the registry once its result (or failure) has been
+//! collected, or [`JOB_RESULT_TTL`] after it finished if it never is.
+//! `rtflow_job_cancel` asks a running job to stop; it ends `cancelled` once
+//! the engine notices. A job is only visible to the tenant that started it;
+//! to any other it is unknown.
+
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use rt_compare::result::{ComparePhase, CompareProgress, CompareResult};
+use rt_core::tenant::TenantContext;
+use rt_core::{CancelToken, RtError};
+#[cfg(feature = "merge")]
+use rt_merge::MergeResult;
+
+/// Number of jobs that run at once.
+pub const JOB_WORKERS: usize = 4;
+
+/// How long a finished job's result is kept for `rtflow_job_result`.
+pub const JOB_RESULT_TTL: Duration = Duration::from_secs(3600);
+
+/// Lifecycle stage of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// What a finished job produced, serialized as the bare result object.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum JobOutput {
+    Compare(Box<CompareResult>),
+    #[cfg(feature = "merge")]
+    Merge(Box<MergeResult>),
+}
+
+/// One answer to `rtflow_job_status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub job_id: Uuid,
+    /// The compare run (or merge) the job computes.
+    pub run_id: Uuid,
+    pub state: JobState,
+    /// Phase the comparison last reported.
+    pub phase: ComparePhase,
+    /// Share of the blocks processed, from 0.0 to 100.0.
+    pub percent: f64,
+    pub blocks_processed: usize,
+    pub blocks_total: usize,
+    /// Why a `failed` or `cancelled` job stopped.
+    pub error: Option<String>,
+}
+
+struct Job {
+    run_id: Uuid,
+    tenant: TenantContext,
+    progress: CompareProgress,
+    cancel: CancelToken,
+    /// Set once the job has finished.
+    outcome: Option<Result<JobOutput, RtError>>,
+    /// When the job finished.
+    finished_at: Option<Instant>,
+    /// How long its outcome is kept once finished; [`JOB_RESULT_TTL`].
+    ttl: Duration,
+}
+
+type Registry = Mutex<HashMap<Uuid, Arc<Mutex<Job>>>>;
+
+static JOBS: OnceLock<Registry> = OnceLock::new();
+
+fn jobs() -> &'static Registry {
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// Queue of the [`JOB_WORKERS`] threads that run jobs, started on first use.
+fn workers() -> &'static Sender<Task> {
+    static WORKERS: OnceLock<Sender<Task>> = OnceLock::new();
+    WORKERS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Task>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..JOB_WORKERS {
+            let receiver = Arc::clone(&receiver);
+            std::thread::spawn(move || loop {
+                let task = receiver.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                match task {
+                    Ok(task) => task(),
+                    Err(_) => return,
+                }
+            });
+        }
+        sender
+    })
+}
+
+/// Forget every job whose outcome has outlived its TTL uncollected.
+/// Returns how many were forgotten.
+fn evict_expired() -> usize {
+    let now = Instant::now();
+    let mut registry = jobs().lock().unwrap_or_else(|e| e.into_inner());
+    let before = registry.len();
+    registry.retain(|_, job| {
+        let job = job.lock().unwrap_or_else(|e| e.into_inner());
+        job.finished_at.is_none_or(|at| now.duration_since(at) <= job.ttl)
+    });
+    before - registry.len()
+}
+
+fn find(job_id: Uuid, tenant: &TenantContext) -> Result<Arc<Mutex<Job>>, String> {
+    evict_expired();
+    jobs()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&job_id)
+        .filter(|job| job.lock().unwrap_or_else(|e| e.into_inner()).tenant == *tenant)
+        .cloned()
+        .ok_or_else(|| format!("unknown job {}", job_id))
+}
+
+/// Register job `job_id` of `tenant` computing compare run (or merge)
+/// `run_id` and queue `work` for the job workers; the job reports `running`
+/// from now on, with no progress until a worker picks it up. `work` reports
+/// progress through the callback it is given and should stop with
+/// [`RtError::Cancelled`] once the token it is given is cancelled; any other
+/// error, or a panic, fails the job.
+pub fn spawn<W>(job_id: Uuid, run_id: Uuid, tenant: TenantContext, work: W)
+where
+    W: FnOnce(&mut dyn FnMut(&CompareProgress), &CancelToken) -> Result<JobOutput, RtError>
+        + Send
+        + 'static,
+{
+    let cancel = CancelToken::new();
+    let job = Arc::new(Mutex::new(Job {
+        run_id,
+        tenant,
+        progress: CompareProgress {
+            phase: ComparePhase::Flatten,
+            blocks_processed: 0,
+            blocks_total: 0,
+        },
+        cancel: cancel.clone(),
+        outcome: None,
+        finished_at: None,
+        ttl: JOB_RESULT_TTL,
+    }));
+    evict_expired();
+    jobs()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(job_id, Arc::clone(&job));
+
+    let task = move || {
+        let outcome = catch_unwind(AssertUnwindSafe(|| {
+            let mut on_progress = |progress: &CompareProgress| {
+                job.lock().unwrap_or_else(|e| e.into_inner()).progress = *progress;
+            };
+            work(&mut on_progress, &cancel)
+        }))
+        .unwrap_or_else(|_| Err(RtError::Internal("job panicked".to_string())));
+        let mut job = job.lock().unwrap_or_else(|e| e.into_inner());
+        job.outcome = Some(outcome);
+        job.finished_at = Some(Instant::now());
+    };
+    // The workers never exit, so the queue is always open.
+    let _ = workers().send(Box::new(task));
+}
+
+/// Progress and state of `tenant`'s job `job_id`.
+pub fn status(job_id: Uuid, tenant: &TenantContext) -> Result<JobStatus, String> {
+    let job = find(job_id, tenant)?;
+    let job = job.lock().unwrap_or_else(|e| e.into_inner());
+    let (state, error) = match &job.outcome {
+        None => (JobState::Running, None),
+        Some(Ok(_)) => (JobState::Completed, None),
+        Some(Err(e @ RtError::Cancelled)) => (JobState::Cancelled, Some(e.to_string())),
+        Some(Err(e)) => (JobState::Failed, Some(e.to_string())),
+    };
+    let progress = job.progress;
+    Ok(JobStatus {
+        job_id,
+        run_id: job.run_id,
+        state,
+        phase: progress.phase,
+        percent: progress.percent(),
+        blocks_processed: progress.blocks_processed,
+        blocks_total: progress.blocks_total,
+        error,
+    })
+}
+
+/// Take the result of `tenant`'s finished job `job_id` and forget the job;
+/// a failed job's error is returned (and the job forgotten) instead. Fails
+/// without forgetting the job while it is still running.
+pub fn take_result(job_id: Uuid, tenant: &TenantContext) -> Result<JobOutput, String> {
+    let job = find(job_id, tenant)?;
+    let outcome = job.lock().unwrap_or_else(|e| e.into_inner()).outcome.take();
+    match outcome {
+        None => Err(format!("job {} is still running", job_id)),
+        Some(outcome) => {
+            jobs().lock().unwrap_or_else(|e| e.into_inner()).remove(&job_id);
+            outcome.map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Ask `tenant`'s running job `job_id` to stop. Returns `false` when the
+/// job is unknown to `tenant` or has already finished.
+pub fn cancel(job_id: Uuid, tenant: &TenantContext) -> bool {
+    let Ok(job) = find(job_id, tenant) else {
+        return false;
+    };
+    let job = job.lock().unwrap_or_else(|e| e.into_inner());
+    if job.outcome.is_some() {
+        return false;
+    }
+    job.cancel.cancel();
+    true
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_compare::worker::CompareEngine;
+    use rt_core::block::Block;
+    use rt_core::BlockType;
+
+    fn tenant() -> TenantContext {
+        TenantContext::default()
+    }
+
+    fn wait(job_id: Uuid) -> JobStatus {
+        loop {
+            let status = status(job_id, &tenant()).unwrap();
+            if status.state != JobState::Running {
+                return status;
+            }
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn finished_jobs_report_progress_and_hand_over_their_result_once() {
+        let doc = Uuid::new_v4();
+        let left: Vec<Block> = (0..4)
+            .map(|i| {
+                let text = format!("clause {i} of the agreement");
+                Block::new(BlockType::Clause, format!("1.{i}"), &text, &text, None, doc, i)
+            })
+            .collect();
+        let right = left[1..].to_vec();
+
+        let (job_id, run_id) = (Uuid::new_v4(), Uuid::new_v4());
+        spawn(job_id, run_id, tenant(), move |on_progress, _| {
+            let engine = CompareEngine::default();
+            let result =
+                engine.compare_with_progress(doc, doc, &left, &right, 1, |_| {}, on_progress);
+            Ok(JobOutput::Compare(Box::new(result)))
+        });
+
+        let finished = wait(job_id);
+        assert_eq!(finished.state, JobState::Completed);
+        assert_eq!(finished.run_id, run_id);
+        assert_eq!(finished.phase, ComparePhase::Stats);
+        assert_eq!(finished.percent, 100.0);
+        assert_eq!(finished.blocks_processed, finished.blocks_total);
+        let result = serde_json::to_value(take_result(job_id, &tenant()).unwrap()).unwrap();
+        assert_eq!(result["stats"]["deleted"], 1);
+        assert!(status(job_id, &tenant()).is_err(), "collected jobs are forgotten");
+        assert!(take_result(job_id, &tenant()).is_err());
+    }
+
+    #[test]
+    fn failed_jobs_report_their_error() {
+        let job_id = Uuid::new_v4();
+        let not_found = |_: &mut dyn FnMut(&CompareProgress), _: &CancelToken| {
+            Err(RtError::NotFound("document".to_string()))
+        };
+        spawn(job_id, Uuid::new_v4(), tenant(), not_found);
+        let failed = wait(job_id);
+        assert_eq!(failed.state, JobState::Failed);
+        assert_eq!(failed.error.as_deref(), Some("not found: document"));
+        assert_eq!(take_result(job_id, &tenant()).unwrap_err(), "not found: document");
+
+        let job_id = Uuid::new_v4();
+        spawn(job_id, Uuid::new_v4(), tenant(), |_, _| panic!("boom"));
+        assert_eq!(wait(job_id).error.as_deref(), Some("internal error: job panicked"));
+    }
+
+    #[test]
+    fn cancelled_jobs_stop_once_the_work_notices() {
+        let job_id = Uuid::new_v4();
+        let (started, start) = std::sync::mpsc::channel();
+        spawn(job_id, Uuid::new_v4(), tenant(), move |_, cancel| {
+            started.send(()).unwrap();
+            while !cancel.is_cancelled() {
+                std::thread::yield_now();
+            }
+            Err(RtError::Cancelled)
+        });
+        start.recv().unwrap();
+        assert_eq!(status(job_id, &tenant()).unwrap().state, JobState::Running);
+        assert!(cancel(job_id, &tenant()));
+
+        let cancelled = wait(job_id);
+        assert_eq!(cancelled.state, JobState::Cancelled);
+        assert!(!cancel(job_id, &tenant()), "finished jobs cannot be cancelled");
+        assert!(take_result(job_id, &tenant()).is_err());
+        assert!(!cancel(Uuid::new_v4(), &tenant()));
+    }
+
+    #[test]
+    fn jobs_failing_for_another_reason_after_a_cancel_report_failed() {
+        let job_id = Uuid::new_v4();
+        let (started, start) = std::sync::mpsc::channel();
+        spawn(job_id, Uuid::new_v4(), tenant(), move |_, cancel| {
+            started.send(()).unwrap();
+            while !cancel.is_cancelled() {
+                std::thread::yield_now();
+            }
+            Err(RtError::Internal("disk full".to_string()))
+        });
+        start.recv().unwrap();
+        assert!(cancel(job_id, &tenant()));
+
+        let failed = wait(job_id);
+        assert_eq!(failed.state, JobState::Failed);
+        assert_eq!(failed.error.as_deref(), Some("internal error: disk full"));
+    }
+
+    #[test]
+    fn no_more_than_job_workers_jobs_run_at_once() {
+        let (started, start) = std::sync::mpsc::channel();
+        let release = Arc::new((Mutex::new(false), std::sync::Condvar::new()));
+        let blocker = |started: std::sync::mpsc::Sender<()>| {
+            let release = Arc::clone(&release);
+            move |_: &mut dyn FnMut(&CompareProgress), _: &CancelToken| {
+                started.send(()).unwrap();
+                let (released, signal) = &*release;
+                let mut released = released.lock().unwrap();
+                while !*released {
+                    released = signal.wait(released).unwrap();
+                }
+                Err(RtError::Internal("released".to_string()))
+            }
+        };
+        let busy: Vec<Uuid> = (0..JOB_WORKERS).map(|_| Uuid::new_v4()).collect();
+        for &job_id in &busy {
+            spawn(job_id, Uuid::new_v4(), tenant(), blocker(started.clone()));
+        }
+        for _ in &busy {
+            start.recv().unwrap();
+        }
+
+        let queued = Uuid::new_v4();
+        spawn(queued, Uuid::new_v4(), tenant(), blocker(started.clone()));
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(start.try_recv().is_err(), "a job beyond the pool waits its turn");
+        assert_eq!(status(queued, &tenant()).unwrap().state, JobState::Running);
+
+        let (released, signal) = &*release;
+        *released.lock().unwrap() = true;
+        signal.notify_all();
+        start.recv().unwrap();
+        for job_id in busy.into_iter().chain([queued]) {
+            assert_eq!(wait(job_id).state, JobState::Failed);
+        }
+    }
+
+    #[test]
+    fn jobs_are_unknown_to_other_tenants() {
+        let (job_id, stranger) = (Uuid::new_v4(), TenantContext::new("acme").unwrap());
+        spawn(job_id, Uuid::new_v4(), tenant(), |_, cancel| {
+            while !cancel.is_cancelled() {
+                std::thread::yield_now();
+            }
+            Err(RtError::Cancelled)
+        });
+        assert!(status(job_id, &stranger).is_err());
+        assert!(take_result(job_id, &stranger).is_err());
+        assert!(!cancel(job_id, &stranger));
+
+        assert!(cancel(job_id, &tenant()));
+        assert_eq!(wait(job_id).state, JobState::Cancelled);
+    }
+
+    #[test]
+    fn uncollected_jobs_are_forgotten_after_their_ttl() {
+        let job_id = Uuid::new_v4();
+        spawn(job_id, Uuid::new_v4(), tenant(), |_, _| {
+            Err(RtError::Internal("never collected".into()))
+        });
+        wait(job_id);
+        let job = jobs().lock().unwrap().get(&job_id).cloned().unwrap();
+        job.lock().unwrap().ttl = Duration::ZERO;
+        drop(job);
+
+        evict_expired();
+        assert!(status(job_id, &tenant()).is_err());
+    }
+}
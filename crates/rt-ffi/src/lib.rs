@@ -1,6 +1,12 @@
 pub mod result;
 pub mod marshal;
 pub mod ffi;
+pub mod stream;
+pub mod jobs;
+pub mod batch;
+pub mod vfs;
+#[cfg(feature = "workflow")]
+pub mod events;
 
 // Re-export the C-ABI surface so consumers can reference the type directly.
 pub use result::RtflowResult;
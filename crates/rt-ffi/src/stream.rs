@@ -0,0 +1,250 @@
+//! Registry of streamed compare runs.
+//!
+//! `rtflow_compare_start` runs [`CompareEngine::compare_cancellable`] on a
+//! background thread; each completed batch of deltas is queued here until the
+//! host drains it with `rtflow_compare_poll`. A run is dropped from the
+//! registry once its final poll has been answered, or when it is cancelled;
+//! cancelling also stops the comparison at its next check. A run is only
+//! visible to the tenant that started it; to any other it is unknown.
+
+use std::collections::{HashMap, VecDeque};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use rt_compare::result::{BlockDelta, CompareProgress, CompareResult};
+use rt_compare::worker::CompareEngine;
+use rt_core::block::Block;
+use rt_core::manifest::RunManifest;
+use rt_core::tenant::TenantContext;
+use rt_core::{CancelToken, RtError};
+
+/// Alignments diffed per batch when the host does not choose.
+pub const DEFAULT_STREAM_BATCH: usize = 64;
+
+#[derive(Default)]
+struct RunState {
+    pending: VecDeque<BlockDelta>,
+    /// Set once the run has finished; its `deltas` are already streamed.
+    finished: Option<CompareResult>,
+    error: Option<String>,
+    cancel: CancelToken,
+    tenant: TenantContext,
+}
+
+type Registry = Mutex<HashMap<Uuid, Arc<Mutex<RunState>>>>;
+
+static RUNS: OnceLock<Registry> = OnceLock::new();
+
+fn runs() -> &'static Registry {
+    RUNS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// One answer to `rtflow_compare_poll`.
+#[derive(Debug, Serialize)]
+pub struct StreamPoll {
+    pub run_id: Uuid,
+    /// Deltas completed since the previous poll, in document order.
+    pub deltas: Vec<BlockDelta>,
+    /// `true` on the final poll of a run; later polls report it unknown.
+    pub done: bool,
+    /// The finished result with an empty `deltas` list (every delta has been
+    /// streamed); present only when `done`.
+    pub result: Option<CompareResult>,
+}
+
+/// Register a run of `tenant` and start comparing on a background thread,
+/// reporting progress to `on_progress` after each batch. The run's final
+/// `CompareResult` carries `run_id` and `manifest`.
+#[allow(clippy::too_many_arguments)]
+pub fn start<P>(
+    run_id: Uuid,
+    tenant: TenantContext,
+    engine: CompareEngine,
+    manifest: RunManifest,
+    left_doc_id: Uuid,
+    right_doc_id: Uuid,
+    left_blocks: Vec<Block>,
+    right_blocks: Vec<Block>,
+    batch_size: usize,
+    on_progress: P,
+) where
+    P: FnMut(&CompareProgress) + Send + 'static,
+{
+    let cancel = CancelToken::new();
+    let state = Arc::new(Mutex::new(RunState {
+        cancel: cancel.clone(),
+        tenant,
+        ..RunState::default()
+    }));
+    runs()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(run_id, Arc::clone(&state));
+
+    std::thread::spawn(move || {
+        let outcome = catch_unwind(AssertUnwindSafe(|| {
+            engine.compare_cancellable(
+                left_doc_id,
+                right_doc_id,
+                &left_blocks,
+                &right_blocks,
+                batch_size,
+                |batch| {
+                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    state.pending.extend(batch.iter().cloned());
+                },
+                on_progress,
+                &cancel,
+            )
+        }));
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        match outcome {
+            // Cancelled runs are already out of the registry.
+            Ok(Err(RtError::Cancelled)) => {}
+            Ok(Err(e)) => state.error = Some(e.to_string()),
+            Ok(Ok(mut result)) => {
+                result.run_id = run_id;
+                result.manifest = Some(manifest);
+                result.deltas.clear();
+                state.finished = Some(result);
+            }
+            Err(_) => state.error = Some("compare run panicked".to_string()),
+        }
+    });
+}
+
+/// The run `run_id` of `tenant`.
+fn find(run_id: Uuid, tenant: &TenantContext) -> Result<Arc<Mutex<RunState>>, String> {
+    runs()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&run_id)
+        .filter(|state| state.lock().unwrap_or_else(|e| e.into_inner()).tenant == *tenant)
+        .cloned()
+        .ok_or_else(|| format!("unknown compare run {}", run_id))
+}
+
+/// Take up to `max_deltas` queued deltas of `tenant`'s run `run_id` (all of
+/// them when 0).
+pub fn poll(run_id: Uuid, tenant: &TenantContext, max_deltas: usize) -> Result<StreamPoll, String> {
+    let state = find(run_id, tenant)?;
+
+    let mut guard = state.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(error) = guard.error.take() {
+        drop(guard);
+        cancel(run_id, tenant);
+        return Err(error);
+    }
+
+    let take = if max_deltas == 0 {
+        guard.pending.len()
+    } else {
+        max_deltas.min(guard.pending.len())
+    };
+    let deltas: Vec<BlockDelta> = guard.pending.drain(..take).collect();
+    let result = if guard.pending.is_empty() {
+        guard.finished.take()
+    } else {
+        None
+    };
+    drop(guard);
+
+    let done = result.is_some();
+    if done {
+        cancel(run_id, tenant);
+    }
+    Ok(StreamPoll {
+        run_id,
+        deltas,
+        done,
+        result,
+    })
+}
+
+/// Forget `tenant`'s run `run_id`, stopping it if it is still computing.
+/// Returns `false` for a run unknown to `tenant`.
+pub fn cancel(run_id: Uuid, tenant: &TenantContext) -> bool {
+    let Ok(state) = find(run_id, tenant) else {
+        return false;
+    };
+    runs().lock().unwrap_or_else(|e| e.into_inner()).remove(&run_id);
+    state.lock().unwrap_or_else(|e| e.into_inner()).cancel.cancel();
+    true
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_core::BlockType;
+
+    #[test]
+    fn polling_drains_every_delta_then_finishes() {
+        let doc = Uuid::new_v4();
+        let left: Vec<Block> = (0..5)
+            .map(|i| {
+                let text = format!("clause {i} of the agreement");
+                Block::new(BlockType::Clause, format!("1.{i}"), &text, &text, None, doc, i)
+            })
+            .collect();
+        let right = left[1..].to_vec();
+
+        let run_id = Uuid::new_v4();
+        let engine = CompareEngine::default();
+        let manifest = engine.manifest(&[]);
+        let tenant = TenantContext::default();
+        start(run_id, tenant.clone(), engine, manifest.clone(), doc, doc, left, right, 2, |_| {});
+
+        let mut deltas = Vec::new();
+        let result = loop {
+            let polled = poll(run_id, &tenant, 1).unwrap();
+            assert!(polled.deltas.len() <= 1);
+            deltas.extend(polled.deltas);
+            if polled.done {
+                break polled.result.unwrap();
+            }
+            std::thread::yield_now();
+        };
+
+        assert_eq!(deltas.len(), 5);
+        assert_eq!(result.run_id, run_id);
+        assert!(result.deltas.is_empty());
+        assert_eq!(result.manifest, Some(manifest));
+        assert_eq!(result.stats.deleted, 1);
+        assert!(poll(run_id, &tenant, 0).is_err(), "finished runs are forgotten");
+    }
+
+    #[test]
+    fn unknown_and_cancelled_runs_are_rejected() {
+        let (run_id, tenant) = (Uuid::new_v4(), TenantContext::default());
+        assert!(poll(run_id, &tenant, 0).is_err());
+        assert!(!cancel(run_id, &tenant));
+
+        let engine = CompareEngine::default();
+        let manifest = engine.manifest(&[]);
+        let (left, right) = (Vec::new(), Vec::new());
+        start(run_id, tenant.clone(), engine, manifest, run_id, run_id, left, right, 1, |_| {});
+        assert!(cancel(run_id, &tenant));
+        assert!(poll(run_id, &tenant, 0).is_err());
+    }
+
+    #[test]
+    fn runs_are_unknown_to_other_tenants() {
+        let (run_id, tenant) = (Uuid::new_v4(), TenantContext::default());
+        let stranger = TenantContext::new("acme").unwrap();
+        let engine = CompareEngine::default();
+        let manifest = engine.manifest(&[]);
+        let (left, right) = (Vec::new(), Vec::new());
+        start(run_id, tenant.clone(), engine, manifest, run_id, run_id, left, right, 1, |_| {});
+
+        assert!(poll(run_id, &stranger, 0).is_err());
+        assert!(!cancel(run_id, &stranger));
+        assert!(cancel(run_id, &tenant), "the run survives a stranger's cancel");
+    }
+}
@@ -0,0 +1,108 @@
+//! Host-provided file system for sandboxed hosts.
+//!
+//! A host that may not (or should not) let the library touch the file system
+//! directly registers a write callback with `rtflow_set_vfs`; exports and
+//! artifacts are then written through [`CallbackVfs`] instead of
+//! [`StdVfs`](rt_core::vfs::StdVfs).
+
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+
+use rt_core::vfs::Vfs;
+use rt_core::RtError;
+
+/// Host callback writing `len` bytes at `data` to the file at `path` (a
+/// null-terminated UTF-8 string), replacing any existing content. Returns 0
+/// on success and any other value on failure. `user_data` is the pointer
+/// passed to `rtflow_set_vfs`.
+///
+/// The callback may be invoked from any thread, and `path` and `data` are
+/// only valid for the duration of the call.
+pub type RtflowWriteFn = unsafe extern "C" fn(
+    path: *const c_char,
+    data: *const u8,
+    len: usize,
+    user_data: *mut c_void,
+) -> i32;
+
+/// [`Vfs`] writing through a host callback.
+#[derive(Debug, Clone, Copy)]
+pub struct CallbackVfs {
+    write: RtflowWriteFn,
+    /// Host pointer handed back to `write`; kept as an address so the VFS
+    /// can be shared across threads. The host owns whatever it points to.
+    user_data: usize,
+}
+
+impl CallbackVfs {
+    pub fn new(write: RtflowWriteFn, user_data: *mut c_void) -> Self {
+        Self {
+            write,
+            user_data: user_data as usize,
+        }
+    }
+}
+
+impl Vfs for CallbackVfs {
+    fn write(&self, path: &str, content: &[u8]) -> Result<(), RtError> {
+        let c_path = CString::new(path)
+            .map_err(|_| RtError::InvalidInput(format!("path contains a NUL byte: {path:?}")))?;
+        // SAFETY: the host guaranteed `write` is callable when registering
+        // it; both pointers stay valid for the duration of the call.
+        let user_data = self.user_data as *mut c_void;
+        let status =
+            unsafe { (self.write)(c_path.as_ptr(), content.as_ptr(), content.len(), user_data) };
+        if status != 0 {
+            return Err(RtError::Internal(format!(
+                "host write callback failed for {path} with status {status}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::sync::Mutex;
+
+    type Written = Mutex<Vec<(String, Vec<u8>)>>;
+
+    unsafe extern "C" fn record(
+        path: *const c_char,
+        data: *const u8,
+        len: usize,
+        user_data: *mut c_void,
+    ) -> i32 {
+        let written = &*(user_data as *const Written);
+        let path = CStr::from_ptr(path).to_str().unwrap().to_string();
+        if path.starts_with("/denied") {
+            return 13;
+        }
+        let bytes = std::slice::from_raw_parts(data, len).to_vec();
+        written.lock().unwrap().push((path, bytes));
+        0
+    }
+
+    #[test]
+    fn writes_go_through_the_callback() {
+        let written: Written = Mutex::new(Vec::new());
+        let vfs = CallbackVfs::new(record, &written as *const Written as *mut c_void);
+
+        vfs.write("exports/bob.html", b"<p>x</p>").unwrap();
+        assert_eq!(
+            *written.lock().unwrap(),
+            [("exports/bob.html".to_string(), b"<p>x</p>".to_vec())]
+        );
+
+        let err = vfs.write("/denied/bob.html", b"x").unwrap_err();
+        assert!(err.to_string().contains("status 13"), "{err}");
+        let err = vfs.write("bad\0path", b"x").unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(_)));
+    }
+}
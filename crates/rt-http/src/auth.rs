@@ -0,0 +1,110 @@
+//! API-key authentication and tenant resolution.
+
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use rt_core::tenant::TenantContext;
+
+use crate::{ApiError, AppState, TENANT_HEADER};
+
+/// The key a request presents: `Authorization: Bearer <key>`, else
+/// `X-Api-Key: <key>`.
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer
+        .or_else(|| {
+            headers
+                .get("x-api-key")
+                .and_then(|value| value.to_str().ok())
+        })
+        .map(str::trim)
+}
+
+/// Compare in time independent of where the first difference is, so a
+/// key cannot be guessed byte by byte from response timings.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Check a [`TENANT_HEADER`] the request sent against the tenant its key is
+/// bound to. The header is optional; naming any other tenant is refused.
+fn check_tenant_header(headers: &HeaderMap, bound: &TenantContext) -> Result<(), ApiError> {
+    let Some(value) = headers.get(TENANT_HEADER) else {
+        return Ok(());
+    };
+    let named = value
+        .to_str()
+        .map_err(|_| ApiError::bad_request(format!("{TENANT_HEADER} is not ASCII")))?;
+    if TenantContext::new(named)? != *bound {
+        return Err(ApiError::forbidden(format!(
+            "this API key may not act for tenant '{named}'"
+        )));
+    }
+    Ok(())
+}
+
+/// Let a request through only with one of the configured keys, and hand
+/// the handlers the tenant that key is bound to as a request extension.
+pub(crate) async fn require_api_key(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let key = presented_key(request.headers()).ok_or_else(ApiError::unauthorized)?;
+    // Visit every key so the time taken does not depend on which one matched.
+    let tenant = state
+        .api_keys
+        .iter()
+        .filter(|(candidate, _)| !candidate.is_empty())
+        .fold(None, |found, (candidate, tenant)| {
+            let matched = constant_time_eq(candidate.as_bytes(), key.as_bytes());
+            found.or(matched.then_some(tenant))
+        })
+        .cloned()
+        .ok_or_else(ApiError::unauthorized)?;
+    check_tenant_header(request.headers(), &tenant)?;
+    request.extensions_mut().insert(tenant);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_comes_from_bearer_or_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(presented_key(&headers), None);
+        headers.insert("x-api-key", "k1".parse().unwrap());
+        assert_eq!(presented_key(&headers), Some("k1"));
+        headers.insert(header::AUTHORIZATION, "Bearer k2".parse().unwrap());
+        assert_eq!(presented_key(&headers), Some("k2"));
+    }
+
+    #[test]
+    fn constant_time_eq_compares_whole_keys() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+    }
+
+    #[test]
+    fn tenant_header_must_match_the_key() {
+        let acme = TenantContext::new("acme").unwrap();
+        let mut headers = HeaderMap::new();
+        assert!(check_tenant_header(&headers, &acme).is_ok());
+        headers.insert(TENANT_HEADER, "acme".parse().unwrap());
+        assert!(check_tenant_header(&headers, &acme).is_ok());
+        headers.insert(TENANT_HEADER, "globex".parse().unwrap());
+        let err = check_tenant_header(&headers, &acme).unwrap_err();
+        assert_eq!(err.status, axum::http::StatusCode::FORBIDDEN);
+        headers.insert(TENANT_HEADER, "not valid!".parse().unwrap());
+        let err = check_tenant_header(&headers, &acme).unwrap_err();
+        assert_eq!(err.status, axum::http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,374 @@
+//! HTTP/JSON API over the RT_Flow engines.
+//!
+//! [`router`] serves one database to hosts that prefer process isolation
+//! over linking `rt-ffi`. Request and response bodies are the JSON the FFI
+//! takes and returns (see `contracts/`), so a host can switch between the
+//! two without re-mapping anything:
+//!
+//! - `GET /documents` (query: a document filter): `Document[]`
+//! - `POST /documents` (`{text, name}` or `{docx, name}` with the `.docx`
+//!   base64-encoded, plus `doc_id` and `options`): `{doc_id, count,
+//!   document}`
+//! - `GET /documents/{id}`: `Document`
+//! - `GET /documents/{id}/blocks` (query: `tree`): `Block[]`
+//! - `POST /compare` (`{left_doc_id, right_doc_id, options}`): `CompareResult`
+//! - `POST /merge` (`{base_doc_id, incoming_doc_id, options}`): `MergeResult`
+//! - `GET /workflows` (query: a workflow filter): `Workflow[]`
+//! - `POST /workflows` (`{document_id, initiator_id}`): `Workflow`
+//! - `GET /workflows/{id}`: `Workflow`
+//! - `GET /workflows/{id}/events`: `WorkflowEvent[]`
+//! - `POST /workflows/{id}/events` (`{event_type, actor, payload}`): the
+//!   workflow in its new state
+//!
+//! As through the FFI, compare and merge `options` may name a `workflow_id`
+//! whose stored overrides fill in what the request leaves unset, and each
+//! run is billed to the tenant's usage.
+//!
+//! Every request needs one of the configured API keys, as
+//! `Authorization: Bearer <key>` or `X-Api-Key: <key>`, and acts for the
+//! tenant that key is bound to. `X-Rtflow-Tenant` is optional; a request
+//! naming any tenant but its key's gets `403`. Failures come back as
+//! `{"error": "<message>"}` with a matching status code.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::DefaultBodyLimit;
+use axum::routing::{get, post};
+use axum::Router;
+
+use rt_core::db::DbPool;
+use rt_core::tenant::TenantContext;
+
+mod auth;
+mod error;
+mod routes;
+
+pub use error::ApiError;
+
+/// Default cap on request bodies: 16 MiB.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Request header a client may send to state the tenant it expects to act
+/// for. It must match the tenant its API key is bound to.
+pub const TENANT_HEADER: &str = "x-rtflow-tenant";
+
+/// Server settings.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Keys a request may authenticate with, each bound to the one tenant
+    /// it acts for. Must not be empty.
+    pub api_keys: HashMap<String, TenantContext>,
+    /// Largest request body accepted; larger ones get `413`.
+    pub max_body_bytes: usize,
+}
+
+impl Config {
+    pub fn new(api_keys: impl IntoIterator<Item = (String, TenantContext)>) -> Self {
+        Self {
+            api_keys: api_keys.into_iter().collect(),
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+        }
+    }
+
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+}
+
+/// What every handler shares.
+#[derive(Clone)]
+pub(crate) struct AppState {
+    pub(crate) pool: DbPool,
+    pub(crate) api_keys: Arc<HashMap<String, TenantContext>>,
+}
+
+/// The API over `pool`. Fails when `config` has no API keys, so a server
+/// can never come up open by accident.
+pub fn router(pool: DbPool, config: Config) -> Result<Router, ApiError> {
+    if config.api_keys.keys().all(|key| key.is_empty()) {
+        return Err(ApiError::config(
+            "at least one non-empty API key is required",
+        ));
+    }
+    let state = AppState {
+        pool,
+        api_keys: Arc::new(config.api_keys),
+    };
+
+    Ok(Router::new()
+        .route(
+            "/documents",
+            get(routes::list_documents).post(routes::ingest_document),
+        )
+        .route("/documents/:id", get(routes::get_document))
+        .route("/documents/:id/blocks", get(routes::get_blocks))
+        .route("/compare", post(routes::compare))
+        .route("/merge", post(routes::merge))
+        .route(
+            "/workflows",
+            get(routes::list_workflows).post(routes::create_workflow),
+        )
+        .route("/workflows/:id", get(routes::get_workflow))
+        .route(
+            "/workflows/:id/events",
+            get(routes::get_workflow_events).post(routes::submit_workflow_event),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_api_key,
+        ))
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
+        .with_state(state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use http_body_util::BodyExt;
+    use rt_core::db::create_memory_pool;
+    use rt_core::usage::usage_report;
+    use rt_workflow::config::{set_config, WorkflowConfig};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    const KEY: &str = "test-key";
+    const ACME_KEY: &str = "acme-key";
+
+    const CONTRACT: &str = "MASTER SERVICES AGREEMENT\n\n1. Payment\n\
+                            The Customer shall pay each invoice within thirty days.\n";
+
+    fn app() -> Router {
+        app_with_pool().0
+    }
+
+    /// The test app and the pool it serves, for checks the routes don't expose.
+    fn app_with_pool() -> (Router, DbPool) {
+        let config = Config::new([
+            (KEY.to_string(), TenantContext::default()),
+            (ACME_KEY.to_string(), TenantContext::new("acme").unwrap()),
+        ])
+        .with_max_body_bytes(4096);
+        let pool = create_memory_pool().unwrap();
+        (router(pool.clone(), config).unwrap(), pool)
+    }
+
+    async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        send_as(app, KEY, method, uri, body).await
+    }
+
+    async fn send_as(
+        app: &Router,
+        key: &str,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {key}"))
+            .header(header::CONTENT_TYPE, "application/json");
+        let body = body.map_or_else(Body::empty, |b| Body::from(b.to_string()));
+        let response = app
+            .clone()
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[test]
+    fn router_refuses_to_start_without_keys() {
+        let pool = create_memory_pool().unwrap();
+        let config = Config::new([(String::new(), TenantContext::default())]);
+        assert!(router(pool, config).is_err());
+    }
+
+    #[tokio::test]
+    async fn requests_need_a_configured_key() {
+        let app = app();
+        let unauthenticated = Request::get("/documents").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(unauthenticated).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let wrong = Request::get("/documents")
+            .header("x-api-key", "other-key")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            app.oneshot(wrong).await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_rejected() {
+        let text = "x".repeat(8192);
+        let (status, _) = send(
+            &app(),
+            "POST",
+            "/documents",
+            Some(json!({"text": text, "name": "big"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn ingest_compare_and_workflow_round_trip() {
+        let app = app();
+        let ingest = |text: String, name: &str| json!({"text": text, "name": name});
+        let (status, left) = send(
+            &app,
+            "POST",
+            "/documents",
+            Some(ingest(CONTRACT.into(), "v1")),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let amended = CONTRACT.replace("thirty", "sixty");
+        let (_, right) = send(&app, "POST", "/documents", Some(ingest(amended, "v2"))).await;
+
+        let (status, result) = send(
+            &app,
+            "POST",
+            "/compare",
+            Some(json!({"left_doc_id": left["doc_id"], "right_doc_id": right["doc_id"]})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["stats"]["modified"], 1);
+
+        let (status, workflow) = send(
+            &app,
+            "POST",
+            "/workflows",
+            Some(json!({"document_id": left["doc_id"], "initiator_id": "alice"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let events = format!("/workflows/{}/events", workflow["id"].as_str().unwrap());
+        let (status, _) = send(
+            &app,
+            "POST",
+            &events,
+            Some(json!({"event_type": "compare_started", "actor": "alice"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, log) = send(&app, "GET", &events, None).await;
+        assert_eq!(log.as_array().unwrap().len(), 2);
+
+        let (status, error) = send(&app, "GET", "/documents/not-a-uuid", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn compares_are_billed_and_take_workflow_overrides() {
+        let (app, pool) = app_with_pool();
+        let ingest = |text: String, name: &str| json!({"text": text, "name": name});
+        let (_, left) = send(&app, "POST", "/documents", Some(ingest(CONTRACT.into(), "v1"))).await;
+        let amended = CONTRACT.replace("thirty", "sixty");
+        let (_, right) = send(&app, "POST", "/documents", Some(ingest(amended, "v2"))).await;
+        let (_, workflow) = send(
+            &app,
+            "POST",
+            "/workflows",
+            Some(json!({"document_id": left["doc_id"], "initiator_id": "alice"})),
+        )
+        .await;
+        let workflow_id = workflow["id"].as_str().unwrap();
+
+        // An out-of-range override shows the workflow's settings are applied;
+        // an explicit option still wins over it.
+        let config = WorkflowConfig::from_json(r#"{"compare":{"similarity_threshold":2.0}}"#);
+        let conn = pool.get().unwrap();
+        let tenant = TenantContext::default();
+        set_config(&conn, &tenant, workflow_id.parse().unwrap(), &config.unwrap()).unwrap();
+        let compare = |options: Value| {
+            json!({
+                "left_doc_id": left["doc_id"],
+                "right_doc_id": right["doc_id"],
+                "options": options,
+            })
+        };
+        let (status, error) = send(
+            &app,
+            "POST",
+            "/compare",
+            Some(compare(json!({"workflow_id": workflow_id}))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error["error"].as_str().unwrap().contains("similarity_threshold"));
+        let options = json!({"workflow_id": workflow_id, "similarity_threshold": 0.5});
+        let (status, result) = send(&app, "POST", "/compare", Some(compare(options))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["stats"]["modified"], 1);
+        let (status, _) = send(
+            &app,
+            "POST",
+            "/compare",
+            Some(compare(json!({"workflow_id": "not-a-uuid"}))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Only the compare that ran is billed, to the caller's tenant.
+        let report = usage_report(&conn, &tenant, None, None).unwrap();
+        assert_eq!(report.compare_runs, 1);
+        let acme = TenantContext::new("acme").unwrap();
+        assert_eq!(usage_report(&conn, &acme, None, None).unwrap().compare_runs, 0);
+    }
+
+    #[tokio::test]
+    async fn keys_only_reach_their_own_tenant() {
+        let app = app();
+        let body = json!({"text": CONTRACT, "name": "acme"});
+        let (status, doc) = send_as(&app, ACME_KEY, "POST", "/documents", Some(body)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let uri = format!("/documents/{}", doc["doc_id"].as_str().unwrap());
+
+        let (status, _) = send_as(&app, ACME_KEY, "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&app, "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Naming another tenant in the header does not widen the key.
+        let request = Request::get(&uri)
+            .header(header::AUTHORIZATION, format!("Bearer {KEY}"))
+            .header(TENANT_HEADER, "acme")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn docx_uploads_must_be_base64() {
+        let (status, error) = send(
+            &app(),
+            "POST",
+            "/documents",
+            Some(json!({"docx": "not base64!", "name": "bad"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error["error"].as_str().unwrap().contains("base64"));
+    }
+}
@@ -0,0 +1,120 @@
+//! `rt-http <db-path> [--listen <addr>] [--max-body-bytes <n>]`: serve the
+//! HTTP API on the database at `<db-path>` until interrupted. API keys come
+//! from `RTFLOW_API_KEYS`, comma-separated, each as `<key>=<tenant>`; a bare
+//! `<key>` acts for the default tenant.
+
+use std::net::SocketAddr;
+use std::process::ExitCode;
+
+use rt_core::db::create_pool;
+use rt_core::tenant::TenantContext;
+use rt_http::{router, Config, DEFAULT_MAX_BODY_BYTES};
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+const API_KEYS_VAR: &str = "RTFLOW_API_KEYS";
+
+const USAGE: &str = "usage: rt-http <db-path> [--listen <addr>] [--max-body-bytes <n>]";
+
+struct Args {
+    db_path: String,
+    listen: SocketAddr,
+    max_body_bytes: usize,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut db_path = None;
+    let mut listen = DEFAULT_LISTEN.to_string();
+    let mut max_body_bytes = DEFAULT_MAX_BODY_BYTES.to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => listen = args.next().ok_or("--listen needs an address")?,
+            "--max-body-bytes" => {
+                max_body_bytes = args.next().ok_or("--max-body-bytes needs a size")?
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if db_path.is_none() && !arg.starts_with('-') => db_path = Some(arg),
+            _ => return Err(format!("unexpected argument '{arg}'\n{USAGE}")),
+        }
+    }
+    Ok(Args {
+        db_path: db_path.ok_or(USAGE)?,
+        listen: listen
+            .parse()
+            .map_err(|e| format!("invalid listen address '{listen}': {e}"))?,
+        max_body_bytes: max_body_bytes
+            .parse()
+            .map_err(|e| format!("invalid body size '{max_body_bytes}': {e}"))?,
+    })
+}
+
+fn api_keys() -> Result<Vec<(String, TenantContext)>, String> {
+    std::env::var(API_KEYS_VAR)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (key, tenant) = match entry.split_once('=') {
+                Some((key, tenant)) => (key.trim(), TenantContext::new(tenant.trim())),
+                None => (entry, Ok(TenantContext::default())),
+            };
+            let tenant = tenant.map_err(|e| format!("invalid tenant for an API key: {e}"))?;
+            Ok((key.to_string(), tenant))
+        })
+        .collect()
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+    let pool = match create_pool(&args.db_path) {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("rt-http: failed to open {}: {e}", args.db_path);
+            return ExitCode::FAILURE;
+        }
+    };
+    let api_keys = match api_keys() {
+        Ok(api_keys) => api_keys,
+        Err(message) => {
+            eprintln!("rt-http: {message} (in {API_KEYS_VAR})");
+            return ExitCode::FAILURE;
+        }
+    };
+    let config = Config::new(api_keys).with_max_body_bytes(args.max_body_bytes);
+    let app = match router(pool, config) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("rt-http: {} (set {API_KEYS_VAR})", e.message);
+            return ExitCode::FAILURE;
+        }
+    };
+    let listener = match tokio::net::TcpListener::bind(args.listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("rt-http: failed to listen on {}: {e}", args.listen);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    eprintln!("rt-http: serving {} on {}", args.db_path, args.listen);
+    let served = axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await;
+    match served {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("rt-http: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,371 @@
+//! Route handlers.
+//!
+//! Engine and database calls block, so each handler runs its work on
+//! tokio's blocking pool through [`blocking`].
+
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Duration;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use rt_compare::worker::{CompareConfig, CompareEngine};
+use rt_core::db::{BlockStore, DbConn, DocumentFilter, SqliteBlockStore};
+use rt_core::hashing::{align_anchors, check_contracts, default_hasher};
+use rt_core::tenant::TenantContext;
+use rt_core::usage::{record_usage, UsageMetric};
+use rt_ingest::IngestOptions;
+use rt_merge::merge::{MergeConfig, MergeEngine};
+use rt_merge::run::{run_merge, MergeRun, DEFAULT_MERGE_LEASE_SECS};
+use rt_merge::store::SqliteMergeStore;
+use rt_workflow::commands::{WorkflowEngine, WorkflowFilter};
+use rt_workflow::config::{get_config, layer_options};
+use rt_workflow::event::EventType;
+
+use crate::{ApiError, AppState};
+
+type ApiResult = Result<Json<Value>, ApiError>;
+type Created = Result<(StatusCode, Json<Value>), ApiError>;
+
+/// Run `job` on the blocking pool and answer with its result as JSON.
+async fn blocking<T, F>(job: F) -> ApiResult
+where
+    T: serde::Serialize,
+    F: FnOnce() -> Result<T, ApiError> + Send + 'static,
+    T: Send + 'static,
+{
+    let value = tokio::task::spawn_blocking(job)
+        .await
+        .map_err(|e| ApiError::internal(format!("request task failed: {e}")))??;
+    serde_json::to_value(value)
+        .map(Json)
+        .map_err(|e| ApiError::internal(format!("failed to serialize result: {e}")))
+}
+
+fn created(result: ApiResult) -> Created {
+    result.map(|json| (StatusCode::CREATED, json))
+}
+
+fn parse_id(value: &str, what: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(value).map_err(|e| ApiError::bad_request(format!("invalid {what}: {e}")))
+}
+
+impl AppState {
+    fn conn(&self) -> Result<DbConn, ApiError> {
+        self.pool
+            .get()
+            .map_err(|e| ApiError::internal(format!("failed to acquire connection: {e}")))
+    }
+
+    fn block_store(&self, tenant: &TenantContext) -> SqliteBlockStore {
+        SqliteBlockStore::with_tenant(self.pool.clone(), tenant.clone())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Documents
+// ---------------------------------------------------------------------------
+
+/// `POST /documents`: text to segment, or an uploaded `.docx`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct IngestRequest {
+    text: Option<String>,
+    name: Option<String>,
+    /// The `.docx` package, base64-encoded.
+    docx: Option<String>,
+    doc_id: Option<Uuid>,
+    #[serde(default)]
+    options: IngestOptions,
+}
+
+pub(crate) async fn ingest_document(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    body: Result<Json<IngestRequest>, JsonRejection>,
+) -> Created {
+    let Json(request) = body?;
+    let store = state.block_store(&tenant);
+    let doc_id = request.doc_id.unwrap_or_else(Uuid::new_v4);
+    created(
+        blocking(move || {
+            let conn = state.conn()?;
+            let hasher = default_hasher(&conn)?;
+            let options = &request.options;
+            let summary = match (request.text, request.docx) {
+                (Some(text), None) => {
+                    let name = request
+                        .name
+                        .ok_or_else(|| ApiError::bad_request("ingesting text needs a name"))?;
+                    rt_ingest::ingest_text(&store, &text, &name, doc_id, &hasher, options)?
+                }
+                (None, Some(docx)) => {
+                    let bytes = BASE64
+                        .decode(docx)
+                        .map_err(|e| ApiError::bad_request(format!("docx is not base64: {e}")))?;
+                    let name = request.name.unwrap_or_else(|| doc_id.to_string());
+                    rt_ingest::ingest_docx_bytes(&store, &bytes, &name, doc_id, &hasher, options)?
+                }
+                _ => return Err(ApiError::bad_request("give exactly one of text and docx")),
+            };
+            Ok(json!({
+                "doc_id": summary.document.id,
+                "count": summary.block_count,
+                "document": summary.document,
+            }))
+        })
+        .await,
+    )
+}
+
+pub(crate) async fn list_documents(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    filter: Result<Query<DocumentFilter>, QueryRejection>,
+) -> ApiResult {
+    let Query(filter) = filter?;
+    let store = state.block_store(&tenant);
+    blocking(move || Ok(store.list_documents(&filter)?)).await
+}
+
+pub(crate) async fn get_document(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(id): Path<String>,
+) -> ApiResult {
+    let id = parse_id(&id, "document id")?;
+    let store = state.block_store(&tenant);
+    blocking(move || Ok(store.get_document(&id)?)).await
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct BlocksQuery {
+    /// Root blocks with their `children` nested, rather than a flat list.
+    tree: bool,
+}
+
+pub(crate) async fn get_blocks(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(id): Path<String>,
+    query: Result<Query<BlocksQuery>, QueryRejection>,
+) -> ApiResult {
+    let Query(query) = query?;
+    let id = parse_id(&id, "document id")?;
+    let store = state.block_store(&tenant);
+    blocking(move || {
+        Ok(if query.tree {
+            store.get_block_tree(&id)?
+        } else {
+            store.get_blocks_by_document(&id)?
+        })
+    })
+    .await
+}
+
+// ---------------------------------------------------------------------------
+// Compare and merge
+// ---------------------------------------------------------------------------
+
+/// Resolve the options of a compare or merge request as the FFI does: when
+/// the options object carries a `"workflow_id"`, that key is removed and the
+/// workflow's overrides for `section` (`"compare"` or `"merge"`) are layered
+/// under the remaining options, which take precedence.
+fn run_options(
+    conn: &DbConn,
+    tenant: &TenantContext,
+    options: Option<Value>,
+    section: &str,
+) -> Result<String, ApiError> {
+    let mut options = match options {
+        Some(Value::Object(map)) => map,
+        other => return Ok(other.map(|o| o.to_string()).unwrap_or_default()),
+    };
+    let Some(workflow_id) = options.remove("workflow_id") else {
+        return Ok(Value::Object(options).to_string());
+    };
+    let workflow_id = workflow_id
+        .as_str()
+        .and_then(|s| Uuid::parse_str(s).ok())
+        .ok_or_else(|| ApiError::bad_request("invalid workflow_id: expected a UUID string"))?;
+    let config = get_config(conn, tenant, workflow_id)?;
+    let overrides = if section == "merge" { &config.merge } else { &config.compare };
+    Ok(Value::Object(layer_options(overrides, options)).to_string())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CompareRequest {
+    left_doc_id: Uuid,
+    right_doc_id: Uuid,
+    /// Compare options, as `rtflow_compare` takes them, including a
+    /// `workflow_id` whose compare overrides apply.
+    #[serde(default)]
+    options: Option<Value>,
+}
+
+pub(crate) async fn compare(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    body: Result<Json<CompareRequest>, JsonRejection>,
+) -> ApiResult {
+    let Json(request) = body?;
+    let store = state.block_store(&tenant);
+    let (left_id, right_id) = (request.left_doc_id, request.right_doc_id);
+    blocking(move || {
+        let conn = state.conn()?;
+        let options = run_options(&conn, &tenant, request.options, "compare")?;
+        let engine = CompareEngine::new(CompareConfig::from_json(&options)?);
+        // As `rtflow_compare`: refuse mismatched hash contracts, re-anchor
+        // the right side when the anchor strategies differ, and bill the run.
+        let left_doc = store.get_document(&left_id)?;
+        let right_doc = store.get_document(&right_id)?;
+        check_contracts(&[&left_doc, &right_doc])?;
+        let left = store.get_block_tree(&left_id)?;
+        let mut right = store.get_block_tree(&right_id)?;
+        if left_doc.anchor_version != right_doc.anchor_version {
+            let hasher = default_hasher(&conn)?;
+            align_anchors(&left_doc, &right_doc, &mut right, &hasher)?;
+        }
+        record_usage(&conn, &tenant, UsageMetric::CompareRuns, 1)?;
+        Ok(engine.compare(left_id, right_id, &left, &right))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MergeRequest {
+    base_doc_id: Uuid,
+    incoming_doc_id: Uuid,
+    /// Merge options, as `rtflow_merge` takes them, including a
+    /// `workflow_id` whose merge overrides apply.
+    #[serde(default)]
+    options: Option<Value>,
+}
+
+pub(crate) async fn merge(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    body: Result<Json<MergeRequest>, JsonRejection>,
+) -> ApiResult {
+    let Json(request) = body?;
+    let store = state.block_store(&tenant);
+    let merges = SqliteMergeStore::with_tenant(state.pool.clone(), tenant.clone());
+    let run = MergeRun::new(request.base_doc_id, request.incoming_doc_id, None);
+    blocking(move || {
+        let options = run_options(&state.conn()?, &tenant, request.options, "merge")?;
+        let engine = MergeEngine::new().with_config(MergeConfig::from_json(&options)?);
+        // The merge store bills the run as it saves it.
+        let lease = Duration::seconds(DEFAULT_MERGE_LEASE_SECS);
+        Ok(run_merge(&store, &merges, &engine, &run, lease)?)
+    })
+    .await
+}
+
+// ---------------------------------------------------------------------------
+// Workflows
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CreateWorkflowRequest {
+    document_id: Uuid,
+    initiator_id: String,
+}
+
+pub(crate) async fn create_workflow(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    body: Result<Json<CreateWorkflowRequest>, JsonRejection>,
+) -> Created {
+    let Json(request) = body?;
+    created(
+        blocking(move || {
+            let conn = state.conn()?;
+            Ok(WorkflowEngine::create_workflow(
+                &conn,
+                &tenant,
+                request.document_id,
+                &request.initiator_id,
+            )?)
+        })
+        .await,
+    )
+}
+
+pub(crate) async fn list_workflows(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    filter: Result<Query<WorkflowFilter>, QueryRejection>,
+) -> ApiResult {
+    let Query(filter) = filter?;
+    blocking(move || {
+        let conn = state.conn()?;
+        Ok(WorkflowEngine::list_workflows(&conn, &tenant, &filter)?)
+    })
+    .await
+}
+
+pub(crate) async fn get_workflow(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(id): Path<String>,
+) -> ApiResult {
+    let id = parse_id(&id, "workflow id")?;
+    blocking(move || {
+        let conn = state.conn()?;
+        Ok(WorkflowEngine::get_workflow(&conn, &tenant, id)?)
+    })
+    .await
+}
+
+pub(crate) async fn get_workflow_events(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(id): Path<String>,
+) -> ApiResult {
+    let id = parse_id(&id, "workflow id")?;
+    blocking(move || {
+        let conn = state.conn()?;
+        Ok(WorkflowEngine::get_events(&conn, &tenant, id)?)
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SubmitEventRequest {
+    event_type: EventType,
+    actor: String,
+    #[serde(default)]
+    payload: Option<Value>,
+}
+
+pub(crate) async fn submit_workflow_event(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(id): Path<String>,
+    body: Result<Json<SubmitEventRequest>, JsonRejection>,
+) -> ApiResult {
+    let id = parse_id(&id, "workflow id")?;
+    let Json(request) = body?;
+    blocking(move || {
+        let conn = state.conn()?;
+        Ok(WorkflowEngine::submit_event(
+            &conn,
+            &tenant,
+            id,
+            request.event_type,
+            &request.actor,
+            request.payload.unwrap_or_else(|| json!({})),
+        )?)
+    })
+    .await
+}
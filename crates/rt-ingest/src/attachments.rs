@@ -0,0 +1,185 @@
+//! Image and embedded-object capture via `word/_rels/document.xml.rels`.
+//!
+//! Pictures (`w:drawing > a:blip`, legacy `w:pict > v:imagedata`) and OLE
+//! objects (`w:object > o:OLEObject`) reference package parts by
+//! relationship id. [`AttachmentResolver`] maps those ids to part names and
+//! SHA-256 content hashes so each block records what it embeds without
+//! copying the bytes out of the package.
+
+use std::collections::HashMap;
+
+use rt_model::{sha256_hex_bytes, Attachment, AttachmentType};
+
+use crate::xml::XmlNode;
+
+/// Relationship id → (part name, content hash) for the embedded parts of
+/// `word/document.xml`.
+#[derive(Debug, Default)]
+pub struct AttachmentResolver {
+    parts: HashMap<String, (String, String)>,
+}
+
+impl AttachmentResolver {
+    /// Build a resolver from the parsed relationships part, reading each
+    /// internal target through `read_part` (which returns `None` for parts
+    /// missing from the package). External (linked) targets are ignored.
+    pub fn new(
+        rels: Option<&XmlNode>,
+        mut read_part: impl FnMut(&str) -> Option<Vec<u8>>,
+    ) -> Self {
+        let mut parts = HashMap::new();
+        let Some(root) = rels else {
+            return Self { parts };
+        };
+        for rel in root.children_named("Relationship") {
+            let (Some(id), Some(target)) = (rel.attr("Id"), rel.attr("Target")) else {
+                continue;
+            };
+            if rel.attr("TargetMode") == Some("External") || !is_embedding(rel.attr("Type")) {
+                continue;
+            }
+            let part = resolve_target(target);
+            if let Some(bytes) = read_part(&part) {
+                parts.insert(id.to_string(), (part, sha256_hex_bytes(&bytes)));
+            }
+        }
+        Self { parts }
+    }
+
+    /// Every image and embedded object inside `node`, in document order.
+    /// References to parts the package does not contain are skipped.
+    pub fn attachments(&self, node: &XmlNode) -> Vec<Attachment> {
+        let mut out = Vec::new();
+        self.collect(node, &mut out);
+        out
+    }
+
+    fn collect(&self, node: &XmlNode, out: &mut Vec<Attachment>) {
+        for child in &node.children {
+            let found = match child.name.as_str() {
+                "drawing" => child.find_descendant("blip").and_then(|blip| {
+                    let doc_pr = child.find_descendant("docPr");
+                    let name = doc_pr
+                        .and_then(|d| d.attr("descr").filter(|s| !s.is_empty()))
+                        .or_else(|| doc_pr.and_then(|d| d.attr("name")));
+                    self.resolve(AttachmentType::Image, blip.attr("embed"), name)
+                }),
+                // The object's preview image (`v:imagedata`) is not recorded
+                // separately.
+                "object" => child.find_descendant("OLEObject").and_then(|ole| {
+                    self.resolve(AttachmentType::EmbeddedObject, ole.attr("id"), ole.attr("ProgID"))
+                }),
+                "pict" => child.find_descendant("imagedata").and_then(|data| {
+                    self.resolve(AttachmentType::Image, data.attr("id"), data.attr("title"))
+                }),
+                "pPr" => continue,
+                _ => {
+                    self.collect(child, out);
+                    continue;
+                }
+            };
+            out.extend(found);
+        }
+    }
+
+    fn resolve(
+        &self,
+        attachment_type: AttachmentType,
+        rel_id: Option<&str>,
+        name: Option<&str>,
+    ) -> Option<Attachment> {
+        let (part, hash) = self.parts.get(rel_id?)?;
+        Some(Attachment {
+            attachment_type,
+            content_hash: hash.clone(),
+            storage_ref: part.clone(),
+            name: name.filter(|s| !s.is_empty()).map(str::to_string),
+        })
+    }
+}
+
+/// Relationship types whose targets are embedded binary content.
+fn is_embedding(rel_type: Option<&str>) -> bool {
+    let kind = rel_type.and_then(|t| t.rsplit('/').next()).unwrap_or_default();
+    matches!(kind, "image" | "oleObject" | "package")
+}
+
+/// Resolve a relationship target (relative to `word/`, or absolute from the
+/// package root) to a part name.
+fn resolve_target(target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut segments = vec!["word"];
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            "." | "" => {}
+            s => segments.push(s),
+        }
+    }
+    segments.join("/")
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    const RELS: &str = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+        <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/image1.png"/>
+        <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/oleObject" Target="embeddings/oleObject1.bin"/>
+        <Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="http://example.com/logo.png" TargetMode="External"/>
+        <Relationship Id="rId4" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+    </Relationships>"#;
+
+    fn resolver() -> AttachmentResolver {
+        let rels = xml::parse(RELS).unwrap();
+        AttachmentResolver::new(Some(&rels), |part| Some(part.as_bytes().to_vec()))
+    }
+
+    #[test]
+    fn only_internal_embeddings_are_resolved() {
+        let r = resolver();
+        assert_eq!(r.parts.len(), 2);
+        assert_eq!(r.parts["rId1"].0, "word/media/image1.png");
+        assert_eq!(r.parts["rId1"].1, sha256_hex_bytes(b"word/media/image1.png"));
+        assert_eq!(r.parts["rId2"].0, "word/embeddings/oleObject1.bin");
+    }
+
+    #[test]
+    fn drawings_and_objects_become_attachments() {
+        let para = xml::parse(
+            r#"<w:p xmlns:w="w" xmlns:wp="wp" xmlns:a="a" xmlns:o="o" xmlns:v="v" xmlns:r="r">
+                <w:r><w:drawing><wp:inline><wp:docPr id="1" name="Picture 1" descr="Signature"/>
+                    <a:graphic><a:graphicData><a:blip r:embed="rId1"/></a:graphicData></a:graphic>
+                </wp:inline></w:drawing></w:r>
+                <w:r><w:object><v:shape><v:imagedata r:id="rId1"/></v:shape>
+                    <o:OLEObject ProgID="Excel.Sheet.12" r:id="rId2"/></w:object></w:r>
+                <w:r><w:drawing><a:blip r:embed="rId9"/></w:drawing></w:r>
+            </w:p>"#,
+        )
+        .unwrap();
+
+        let found = resolver().attachments(&para);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].attachment_type, AttachmentType::Image);
+        assert_eq!(found[0].name.as_deref(), Some("Signature"));
+        assert_eq!(found[1].attachment_type, AttachmentType::EmbeddedObject);
+        assert_eq!(found[1].storage_ref, "word/embeddings/oleObject1.bin");
+        assert_eq!(found[1].name.as_deref(), Some("Excel.Sheet.12"));
+    }
+
+    #[test]
+    fn targets_resolve_relative_to_word() {
+        assert_eq!(resolve_target("media/a.png"), "word/media/a.png");
+        assert_eq!(resolve_target("../media/a.png"), "media/a.png");
+        assert_eq!(resolve_target("/word/media/a.png"), "word/media/a.png");
+    }
+}
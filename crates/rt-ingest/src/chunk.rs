@@ -0,0 +1,271 @@
+//! Splitting of oversized blocks into sentence-bounded sub-blocks.
+//!
+//! Some sources deliver whole sections as a single paragraph. Diffing such a
+//! block yields one enormous token group, so blocks above a token threshold
+//! are turned into containers: the block keeps its identity, display text,
+//! runs and formatting, but its `canonical_text` moves into child blocks of
+//! whole sentences, each at most `max_tokens` long where possible.
+//!
+//! Child paths are derived from the parent's: `p3` → `p3.s0`, `p3.s1`, …
+//! (`1.` → `1.s0`), so they stay unique and stable across re-ingests of the
+//! same text.
+
+use rt_compare::tokenize::tokenize;
+use rt_model::error::{Result, RtError};
+use rt_model::{compute_anchor_signature, compute_clause_hash, Block, FormattingMeta};
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Split every block in the flat, parent-before-child list `blocks` whose
+/// canonical text exceeds `max_tokens` tokens. Chunk blocks are inserted
+/// directly after their parent.
+///
+/// Chunks are built from whole sentences; a single sentence longer than
+/// `max_tokens` is further split between words.
+pub fn chunk_oversized_blocks(blocks: Vec<Block>, max_tokens: usize) -> Result<Vec<Block>> {
+    if max_tokens == 0 {
+        return Err(RtError::InvalidInput(
+            "max_block_tokens must be greater than zero".into(),
+        ));
+    }
+
+    let mut out = Vec::with_capacity(blocks.len());
+    for mut block in blocks {
+        let token_count = if block.tokens.is_empty() {
+            tokenize(&block.canonical_text).len()
+        } else {
+            block.tokens.len()
+        };
+        if token_count <= max_tokens {
+            out.push(block);
+            continue;
+        }
+
+        let chunks = chunk_text(&block.canonical_text, max_tokens);
+        let children: Vec<Block> = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| chunk_block(&block, i, text))
+            .collect();
+
+        block.canonical_text = String::new();
+        block.tokens = Vec::new();
+        block.anchor_signature = compute_anchor_signature(
+            &block.block_type,
+            &block.structural_path,
+            &block.canonical_text,
+        );
+        block.clause_hash = compute_clause_hash(&block.canonical_text);
+        out.push(block);
+        out.extend(children);
+    }
+    Ok(out)
+}
+
+/// Split `text` into sentences. A sentence ends at `.`, `!` or `?` (plus any
+/// closing quotes or brackets) followed by whitespace and an uppercase
+/// letter, digit or opening quote / bracket, which keeps `e.g. the` and
+/// `1.5 million` intact.
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut sentences = Vec::new();
+    let mut start = 0;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let (_, ch) = chars[i];
+        if matches!(ch, '.' | '!' | '?') {
+            let mut end = i + 1;
+            while end < chars.len() && matches!(chars[end].1, '"' | '\'' | ')' | ']' | '”' | '’') {
+                end += 1;
+            }
+            let mut next = end;
+            while next < chars.len() && chars[next].1.is_whitespace() {
+                next += 1;
+            }
+            let starts_sentence = next < chars.len()
+                && next > end
+                && (chars[next].1.is_uppercase()
+                    || chars[next].1.is_ascii_digit()
+                    || matches!(chars[next].1, '"' | '(' | '[' | '“' | '§'));
+            if starts_sentence {
+                let end_byte = chars.get(end).map(|c| c.0).unwrap_or(text.len());
+                sentences.push(text[start..end_byte].trim());
+                start = chars[next].0;
+                i = next;
+                continue;
+            }
+        }
+        i += 1;
+    }
+
+    let tail = text[start..].trim();
+    if !tail.is_empty() {
+        sentences.push(tail);
+    }
+    sentences
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Greedily pack sentences of `text` into chunks of at most `max_tokens`.
+fn chunk_text(text: &str, max_tokens: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+
+    for sentence in split_sentences(text) {
+        let n = tokenize(sentence).len();
+        if n > max_tokens {
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+                current_tokens = 0;
+            }
+            chunks.extend(split_words(sentence, max_tokens));
+            continue;
+        }
+        if current_tokens + n > max_tokens && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(sentence);
+        current_tokens += n;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Fallback for a single over-long sentence: pack whitespace-separated words
+/// into chunks of at most `max_tokens` (a lone word is never split).
+fn split_words(sentence: &str, max_tokens: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    for word in sentence.split_whitespace() {
+        let n = tokenize(word).len();
+        if current_tokens + n > max_tokens && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+        current_tokens += n;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn chunk_block(parent: &Block, index: usize, text: String) -> Block {
+    let mut block = Block::new(
+        parent.block_type.clone(),
+        format!("{}.s{}", parent.structural_path.trim_end_matches('.'), index),
+        text.clone(),
+        text,
+        Some(parent.id),
+        parent.document_id,
+        index as i32,
+    );
+    block.level = parent.level + 1;
+    block.formatting_meta = FormattingMeta {
+        style_name: parent.formatting_meta.style_name.clone(),
+        ..FormattingMeta::default()
+    };
+    block.tokens = tokenize(&block.canonical_text);
+    block
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::BlockType;
+    use uuid::Uuid;
+
+    fn paragraph(path: &str, text: &str) -> Block {
+        let mut b = Block::new(BlockType::Paragraph, path, text, text, None, Uuid::new_v4(), 0);
+        b.tokens = tokenize(text);
+        b
+    }
+
+    #[test]
+    fn sentences_split_on_terminal_punctuation() {
+        let s = split_sentences("The Borrower shall pay. Interest accrues daily! Is it due? Yes.");
+        assert_eq!(
+            s,
+            vec!["The Borrower shall pay.", "Interest accrues daily!", "Is it due?", "Yes."]
+        );
+    }
+
+    #[test]
+    fn abbreviations_and_decimals_do_not_split() {
+        let s = split_sentences("Fees, e.g. stamp duty, total 1.5 million. Section 2 applies.");
+        assert_eq!(s, vec!["Fees, e.g. stamp duty, total 1.5 million.", "Section 2 applies."]);
+    }
+
+    #[test]
+    fn small_blocks_are_untouched() {
+        let blocks = vec![paragraph("p0", "Short text.")];
+        let out = chunk_oversized_blocks(blocks.clone(), 10).unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].canonical_text, "Short text.");
+    }
+
+    #[test]
+    fn oversized_block_becomes_container_of_sentence_chunks() {
+        let text = "Alpha beta gamma. Delta epsilon zeta. Eta theta iota.";
+        let parent = paragraph("1.", text);
+        let out = chunk_oversized_blocks(vec![parent.clone()], 5).unwrap();
+
+        assert_eq!(out.len(), 4);
+        assert_eq!(out[0].id, parent.id);
+        assert!(out[0].canonical_text.is_empty());
+        assert!(out[0].tokens.is_empty());
+        assert_eq!(out[0].display_text, text, "display text kept on the parent");
+
+        let paths: Vec<_> = out[1..].iter().map(|b| b.structural_path.as_str()).collect();
+        assert_eq!(paths, vec!["1.s0", "1.s1", "1.s2"]);
+        for child in &out[1..] {
+            assert_eq!(child.parent_id, Some(parent.id));
+            assert_eq!(child.level, parent.level + 1);
+            assert!(child.tokens.len() <= 5);
+        }
+        assert_eq!(out[2].canonical_text, "Delta epsilon zeta.");
+    }
+
+    #[test]
+    fn sentences_are_packed_up_to_threshold() {
+        let text = "One two. Three four. Five six. Seven eight.";
+        let out = chunk_oversized_blocks(vec![paragraph("p0", text)], 6).unwrap();
+        let chunks: Vec<_> = out[1..].iter().map(|b| b.canonical_text.as_str()).collect();
+        assert_eq!(chunks, vec!["One two. Three four.", "Five six. Seven eight."]);
+    }
+
+    #[test]
+    fn overlong_sentence_is_split_between_words() {
+        let text = "a b c d e f g h i j";
+        let out = chunk_oversized_blocks(vec![paragraph("p0", text)], 4).unwrap();
+        let chunks: Vec<_> = out[1..].iter().map(|b| b.canonical_text.as_str()).collect();
+        assert_eq!(chunks, vec!["a b c d", "e f g h", "i j"]);
+    }
+
+    #[test]
+    fn zero_threshold_is_rejected() {
+        assert!(chunk_oversized_blocks(vec![], 0).is_err());
+    }
+}
@@ -0,0 +1,934 @@
+//! DOCX ingestion: reads a `.docx` package directly into the block model.
+//!
+//! This is the Rust counterpart of `RT.Document.DocxParser`; hosts no longer
+//! need to pre-parse documents and ship Block JSON over FFI. The output is a
+//! flat, parent-before-child block list in document order, ready for
+//! [`BlockStore::insert_blocks`].
+//!
+//! Extracted content:
+//! - top-level paragraphs (empty paragraphs without tracked changes skipped),
+//! - numbering → `structural_path` and `FormattingMeta::numbering_*`,
+//! - paragraph styles → `BlockType` and `FormattingMeta::style_name`,
+//! - runs with bold / italic / underline / strike / size / colour,
+//! - tracked insertions and deletions (`w:ins` / `w:del`), on the block
+//!   and on each run they touch,
+//! - images and embedded objects → `Block::attachments` (paragraphs holding
+//!   only an image, such as a signature, are kept),
+//! - tables as `Table` → `TableRow` → `TableCell` blocks.
+//!
+//! `structural_path` must be unique per document (see the
+//! `uq_blocks_document_structural_path` index). Numbered paragraphs use their
+//! list label; unnumbered paragraphs get a positional path (`p3`), tables use
+//! `tbl4`, `tbl4.r0`, `tbl4.r0.c1`, and any remaining collisions (e.g. two
+//! lists both starting at `1.`) are suffixed with `~2`, `~3`, ….
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_compare::tokenize::tokenize;
+use rt_model::error::{Result, RtError};
+use rt_model::{
+    anchor_strategy, compute_anchor_signature, AnchorStrategy, Block, ClauseHasher, BlockType,
+    ChangeType, Document, DocumentType, FormattingMeta, Run, RunFormatting, TrackedChange,
+    ANCHOR_V1,
+};
+use rt_store::db::BlockStore;
+use rt_store::schema::SCHEMA_VERSION;
+
+use crate::attachments::AttachmentResolver;
+use crate::chunk::chunk_oversized_blocks;
+use crate::numbering::NumberingResolver;
+use crate::styles::StyleResolver;
+use crate::xml::{self, XmlNode};
+
+const DOCUMENT_PART: &str = "word/document.xml";
+const STYLES_PART: &str = "word/styles.xml";
+const NUMBERING_PART: &str = "word/numbering.xml";
+const DOCUMENT_RELS_PART: &str = "word/_rels/document.xml.rels";
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Parse the `.docx` at `path` into a flat block list owned by `doc_id`.
+pub fn parse_docx(path: &Path, doc_id: Uuid) -> Result<Vec<Block>> {
+    let file = File::open(path)?;
+    parse_docx_reader(file, doc_id)
+}
+
+/// Parse a `.docx` package from any seekable reader.
+pub fn parse_docx_reader<R: Read + Seek>(reader: R, doc_id: Uuid) -> Result<Vec<Block>> {
+    let mut archive = zip::ZipArchive::new(reader)
+        .map_err(|e| RtError::InvalidInput(format!("not a valid .docx package: {}", e)))?;
+
+    let document_xml = read_part(&mut archive, DOCUMENT_PART)?
+        .ok_or_else(|| RtError::InvalidInput(format!("missing {}", DOCUMENT_PART)))?;
+    let styles_xml = read_part(&mut archive, STYLES_PART)?;
+    let numbering_xml = read_part(&mut archive, NUMBERING_PART)?;
+    let rels_xml = read_part(&mut archive, DOCUMENT_RELS_PART)?;
+
+    let document = xml::parse(&document_xml)?;
+    let styles_root = styles_xml.as_deref().map(xml::parse).transpose()?;
+    let numbering_root = numbering_xml.as_deref().map(xml::parse).transpose()?;
+    let rels_root = rels_xml.as_deref().map(xml::parse).transpose()?;
+
+    let styles = StyleResolver::new(styles_root.as_ref());
+    let mut numbering = NumberingResolver::new(numbering_root.as_ref());
+    let attachments = AttachmentResolver::new(rels_root.as_ref(), |part| {
+        let mut bytes = Vec::new();
+        let mut file = archive.by_name(part).ok()?;
+        file.read_to_end(&mut bytes).ok()?;
+        Some(bytes)
+    });
+
+    let mut blocks = extract_blocks(&document, doc_id, &styles, &mut numbering, &attachments);
+    dedupe_structural_paths(&mut blocks);
+    Ok(blocks)
+}
+
+/// Optional behaviour of [`ingest_docx_with_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IngestOptions {
+    /// Split blocks longer than this many tokens into sentence-bounded child
+    /// blocks (see [`crate::chunk`]). `None` leaves blocks whole.
+    pub max_block_tokens: Option<usize>,
+    /// Anchor strategy to sign blocks with (see
+    /// [`rt_model::anchor_strategy`]). `None` uses v1.
+    pub anchor_version: Option<String>,
+}
+
+impl IngestOptions {
+    /// The anchor strategy these options select.
+    pub fn anchor_strategy(&self) -> Result<Box<dyn AnchorStrategy>> {
+        anchor_strategy(self.anchor_version.as_deref().unwrap_or(ANCHOR_V1))
+    }
+}
+
+/// Outcome of [`ingest_docx`].
+#[derive(Debug, Clone)]
+pub struct IngestSummary {
+    /// The document row that was inserted.
+    pub document: Document,
+    /// Number of blocks persisted (including table rows and cells).
+    pub block_count: usize,
+}
+
+/// Parse the `.docx` at `path` and persist it as a new document.
+///
+/// The document is recorded as [`DocumentType::Redline`] when any block
+/// carries tracked changes, otherwise [`DocumentType::Original`].
+pub fn ingest_docx(store: &dyn BlockStore, path: &Path, doc_id: Uuid) -> Result<IngestSummary> {
+    ingest_docx_with_hasher(store, path, doc_id, &ClauseHasher::default())
+}
+
+/// [`ingest_docx`] with clause hashes and anchors computed by `hasher`; the
+/// document records `hasher`'s hash contract version.
+pub fn ingest_docx_with_hasher(
+    store: &dyn BlockStore,
+    path: &Path,
+    doc_id: Uuid,
+    hasher: &ClauseHasher,
+) -> Result<IngestSummary> {
+    ingest_docx_with_options(store, path, doc_id, hasher, &IngestOptions::default())
+}
+
+/// [`ingest_docx_with_hasher`] with additional [`IngestOptions`].
+pub fn ingest_docx_with_options(
+    store: &dyn BlockStore,
+    path: &Path,
+    doc_id: Uuid,
+    hasher: &ClauseHasher,
+    options: &IngestOptions,
+) -> Result<IngestSummary> {
+    let (doc, blocks) = prepare_docx(path, doc_id, hasher, options)?;
+    store.insert_document(&doc)?;
+    store.insert_blocks(&blocks)?;
+    Ok(IngestSummary {
+        document: doc,
+        block_count: blocks.len(),
+    })
+}
+
+/// Persist an uploaded `.docx` package held in memory as a new document
+/// named `name`. Behaves like [`ingest_docx_with_options`] except that no
+/// source path is recorded.
+pub fn ingest_docx_bytes(
+    store: &dyn BlockStore,
+    bytes: &[u8],
+    name: &str,
+    doc_id: Uuid,
+    hasher: &ClauseHasher,
+    options: &IngestOptions,
+) -> Result<IngestSummary> {
+    let blocks = parse_docx_reader(Cursor::new(bytes), doc_id)?;
+    let (doc, blocks) = finish_docx(blocks, name.to_string(), None, doc_id, hasher, options)?;
+    store.insert_document(&doc)?;
+    store.insert_blocks(&blocks)?;
+    Ok(IngestSummary {
+        document: doc,
+        block_count: blocks.len(),
+    })
+}
+
+/// Parse, chunk and hash the `.docx` at `path`, and build its document row,
+/// without storing anything.
+pub(crate) fn prepare_docx(
+    path: &Path,
+    doc_id: Uuid,
+    hasher: &ClauseHasher,
+    options: &IngestOptions,
+) -> Result<(Document, Vec<Block>)> {
+    let blocks = parse_docx(path, doc_id)?;
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| doc_id.to_string());
+    let source_path = Some(path.to_string_lossy().into_owned());
+    finish_docx(blocks, name, source_path, doc_id, hasher, options)
+}
+
+/// Chunk and hash parsed `blocks` per `options` and build their document row.
+fn finish_docx(
+    mut blocks: Vec<Block>,
+    name: String,
+    source_path: Option<String>,
+    doc_id: Uuid,
+    hasher: &ClauseHasher,
+    options: &IngestOptions,
+) -> Result<(Document, Vec<Block>)> {
+    let strategy = options.anchor_strategy()?;
+    if let Some(max_tokens) = options.max_block_tokens {
+        blocks = chunk_oversized_blocks(blocks, max_tokens)?;
+    }
+    for block in &mut blocks {
+        block.rehash_with(hasher, strategy.as_ref());
+    }
+
+    let is_redline = blocks.iter().any(|b| b.formatting_meta.is_redline);
+    let doc = Document {
+        id: doc_id,
+        name,
+        source_path,
+        doc_type: if is_redline {
+            DocumentType::Redline
+        } else {
+            DocumentType::Original
+        },
+        schema_version: SCHEMA_VERSION.to_string(),
+        normalization_version: "1.0.0".to_string(),
+        hash_contract_version: hasher.contract_version().to_string(),
+        anchor_version: strategy.version(),
+        ingested_at: Utc::now(),
+        metadata: None,
+    };
+    Ok((doc, blocks))
+}
+
+/// Collapse runs of whitespace to a single space and trim, matching the
+/// host-side `DocxParser.NormalizeCanonical`.
+pub fn normalize_canonical(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// ---------------------------------------------------------------------------
+// Extraction
+// ---------------------------------------------------------------------------
+
+fn read_part<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Result<Option<String>> {
+    let mut file = match archive.by_name(name) {
+        Ok(f) => f,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(RtError::InvalidInput(format!("failed to read {}: {}", name, e))),
+    };
+    let mut s = String::new();
+    file.read_to_string(&mut s)?;
+    Ok(Some(s))
+}
+
+fn extract_blocks(
+    document: &XmlNode,
+    doc_id: Uuid,
+    styles: &StyleResolver,
+    numbering: &mut NumberingResolver,
+    attachments: &AttachmentResolver,
+) -> Vec<Block> {
+    let Some(body) = document.child("body") else {
+        return Vec::new();
+    };
+
+    let mut blocks = Vec::new();
+    let mut index = 0i32;
+
+    for element in &body.children {
+        match element.name.as_str() {
+            "p" => {
+                let runs = extract_runs(element);
+                let text: String = runs.iter().map(|r| r.text.as_str()).collect();
+                let found = attachments.attachments(element);
+                if text.trim().is_empty() && found.is_empty() && !has_tracked_changes(element) {
+                    continue;
+                }
+                let mut block =
+                    process_paragraph(element, runs, index, doc_id, styles, numbering);
+                block.attachments = found;
+                blocks.push(block);
+                index += 1;
+            }
+            "tbl" => {
+                let table_blocks = process_table(element, index, doc_id, attachments);
+                index += table_blocks.len() as i32;
+                blocks.extend(table_blocks);
+            }
+            _ => {}
+        }
+    }
+
+    blocks
+}
+
+fn process_paragraph(
+    para: &XmlNode,
+    runs: Vec<Run>,
+    index: i32,
+    doc_id: Uuid,
+    styles: &StyleResolver,
+    numbering: &mut NumberingResolver,
+) -> Block {
+    let display_text: String = runs.iter().map(|r| r.text.as_str()).collect();
+    let canonical = strip_numbering_prefix(&normalize_canonical(&display_text));
+    let mut structural_path = numbering.resolve_structural_path(para);
+    if structural_path.is_empty() {
+        structural_path = format!("p{}", index);
+    }
+
+    let style_id = para.path(&["pPr", "pStyle"]).and_then(|s| s.attr("val"));
+    let block_type = styles.resolve_block_type(style_id);
+    let (numbering_id, numbering_level) = numbering.numbering_info(para);
+    let tracked_change = extract_tracked_change(para);
+
+    let mut block = Block::new(
+        block_type,
+        structural_path,
+        canonical,
+        display_text,
+        None,
+        doc_id,
+        index,
+    );
+    block.formatting_meta = FormattingMeta {
+        style_name: styles.style_name(style_id),
+        numbering_id,
+        numbering_level,
+        is_redline: tracked_change.is_some(),
+        tracked_change,
+    };
+    block.tokens = tokenize(&block.canonical_text);
+    block.runs = runs;
+    block
+}
+
+fn process_table(
+    table: &XmlNode,
+    start_index: i32,
+    doc_id: Uuid,
+    attachments: &AttachmentResolver,
+) -> Vec<Block> {
+    let mut blocks = Vec::new();
+
+    let table_path = format!("tbl{}", start_index);
+    let table_block = structural_block(BlockType::Table, &table_path, doc_id, None, 0, start_index);
+    let table_id = table_block.id;
+    blocks.push(table_block);
+
+    for (row_index, row) in table.children_named("tr").enumerate() {
+        let row_index = row_index as i32;
+        let row_path = format!("{}.r{}", table_path, row_index);
+        let row_block =
+            structural_block(BlockType::TableRow, &row_path, doc_id, Some(table_id), 1, row_index);
+        let row_id = row_block.id;
+        blocks.push(row_block);
+
+        for (cell_index, cell) in row.children_named("tc").enumerate() {
+            let runs: Vec<Run> = cell.children_named("p").flat_map(extract_runs).collect();
+            let display_text: String = runs.iter().map(|r| r.text.as_str()).collect();
+            let canonical = normalize_canonical(&display_text);
+
+            let mut cell_block = Block::new(
+                BlockType::TableCell,
+                format!("{}.c{}", row_path, cell_index),
+                canonical,
+                display_text,
+                Some(row_id),
+                doc_id,
+                cell_index as i32,
+            );
+            cell_block.level = 2;
+            cell_block.tokens = tokenize(&cell_block.canonical_text);
+            cell_block.runs = runs;
+            cell_block.attachments = attachments.attachments(cell);
+            blocks.push(cell_block);
+        }
+    }
+
+    blocks
+}
+
+/// A text-less container block (table / row).
+fn structural_block(
+    block_type: BlockType,
+    path: &str,
+    doc_id: Uuid,
+    parent_id: Option<Uuid>,
+    level: i32,
+    position_index: i32,
+) -> Block {
+    let mut block = Block::new(block_type, path, "", "", parent_id, doc_id, position_index);
+    block.level = level;
+    block
+}
+
+/// Suffix repeated structural paths with `~n` so they satisfy the per-document
+/// uniqueness constraint, recomputing the anchor of every renamed block.
+pub(crate) fn dedupe_structural_paths(blocks: &mut [Block]) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for block in blocks.iter_mut() {
+        let count = seen.entry(block.structural_path.clone()).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            block.structural_path = format!("{}~{}", block.structural_path, count);
+            block.anchor_signature = compute_anchor_signature(
+                &block.block_type,
+                &block.structural_path,
+                &block.canonical_text,
+            );
+        }
+    }
+}
+
+/// Collect every run in `node`, including runs nested in tracked-change,
+/// hyperlink, field and content-control wrappers. Runs inside `w:ins` and
+/// `w:del` carry their revision. Paragraph properties are skipped so the
+/// paragraph mark's `w:rPr` is not mistaken for a run.
+fn extract_runs(node: &XmlNode) -> Vec<Run> {
+    let mut out = Vec::new();
+    collect_runs(node, None, &mut out);
+    out
+}
+
+fn collect_runs(node: &XmlNode, revision: Option<ChangeType>, out: &mut Vec<Run>) {
+    for child in &node.children {
+        match child.name.as_str() {
+            "r" => {
+                let text = run_text(child);
+                if !text.is_empty() {
+                    out.push(Run {
+                        text,
+                        formatting: run_formatting(child.child("rPr")),
+                        revision: revision.clone(),
+                    });
+                }
+            }
+            "pPr" => {}
+            "ins" => collect_runs(child, Some(ChangeType::Insert), out),
+            "del" => collect_runs(child, Some(ChangeType::Delete), out),
+            _ => collect_runs(child, revision.clone(), out),
+        }
+    }
+}
+
+/// Text of a `w:r`: `w:t`, `w:delText` (inside `w:del`) and text-wrapping
+/// breaks rendered as `\n`.
+fn run_text(run: &XmlNode) -> String {
+    let mut s = String::new();
+    for child in &run.children {
+        match child.name.as_str() {
+            "t" | "delText" => s.push_str(&child.text),
+            "br" => {
+                if matches!(child.attr("type"), None | Some("textWrapping")) {
+                    s.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+    s
+}
+
+fn run_formatting(props: Option<&XmlNode>) -> RunFormatting {
+    let Some(props) = props else {
+        return RunFormatting::default();
+    };
+
+    let font_size = props
+        .child("sz")
+        .and_then(|n| n.attr("val"))
+        .and_then(|v| v.parse::<f32>().ok())
+        .map(|half_points| half_points / 2.0);
+
+    let color = props
+        .child("color")
+        .and_then(|n| n.attr("val"))
+        .filter(|v| !v.is_empty() && *v != "auto")
+        .map(|v| format!("#{}", v));
+
+    RunFormatting {
+        bold: on_off(props.child("b")),
+        italic: on_off(props.child("i")),
+        underline: props
+            .child("u")
+            .is_some_and(|u| u.attr("val") != Some("none")),
+        strikethrough: on_off(props.child("strike")),
+        font_size,
+        color,
+    }
+}
+
+/// OOXML on/off property: present with no `val`, or any `val` other than a
+/// false-like value, means on.
+fn on_off(node: Option<&XmlNode>) -> bool {
+    match node {
+        None => false,
+        Some(n) => !matches!(n.attr("val"), Some("false" | "0" | "off")),
+    }
+}
+
+fn has_tracked_changes(para: &XmlNode) -> bool {
+    para.has_descendant("ins") || para.has_descendant("del")
+}
+
+/// The first tracked insertion, or failing that the first deletion, in the
+/// paragraph.
+fn extract_tracked_change(para: &XmlNode) -> Option<TrackedChange> {
+    if let Some(ins) = para.find_descendant("ins") {
+        return Some(TrackedChange {
+            author: revision_author(ins),
+            date: revision_date(ins),
+            change_type: ChangeType::Insert,
+            original: None,
+        });
+    }
+
+    let del = para.find_descendant("del")?;
+    let mut original = String::new();
+    collect_del_text(del, &mut original);
+    Some(TrackedChange {
+        author: revision_author(del),
+        date: revision_date(del),
+        change_type: ChangeType::Delete,
+        original: (!original.is_empty()).then_some(original),
+    })
+}
+
+fn collect_del_text(node: &XmlNode, out: &mut String) {
+    for child in &node.children {
+        if child.name == "delText" {
+            out.push_str(&child.text);
+        } else {
+            collect_del_text(child, out);
+        }
+    }
+}
+
+fn revision_author(node: &XmlNode) -> String {
+    node.attr("author").unwrap_or("Unknown").to_string()
+}
+
+fn revision_date(node: &XmlNode) -> DateTime<Utc> {
+    node.attr("date")
+        .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+        .map(|d| d.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+/// Strip a leading list label ("1.2", "(a)", "iv.") from canonical text,
+/// using the same pattern as the host-side parser so clause hashes agree.
+pub(crate) fn strip_numbering_prefix(text: &str) -> String {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let re = PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^\s*(?:\d+(?:\.\d+)*[.\):]?|[a-zA-Z][.\):]?|\([a-zA-Z0-9]+\)|[ivxlcdmIVXLCDM]+[.\):]?)\s+",
+        )
+        .expect("valid numbering prefix pattern")
+    });
+    match re.find(text) {
+        Some(m) => text[m.end()..].trim_start().to_string(),
+        None => text.to_string(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    use rt_store::db::{create_memory_pool, SqliteBlockStore};
+
+    const W: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main""#;
+
+    fn build_docx(body: &str, styles: Option<&str>, numbering: Option<&str>) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buf);
+            let opts = zip::write::SimpleFileOptions::default();
+            zip.start_file(DOCUMENT_PART, opts).unwrap();
+            write!(zip, r#"<?xml version="1.0"?><w:document {W}><w:body>{body}</w:body></w:document>"#)
+                .unwrap();
+            if let Some(s) = styles {
+                zip.start_file(STYLES_PART, opts).unwrap();
+                write!(zip, r#"<w:styles {W}>{s}</w:styles>"#).unwrap();
+            }
+            if let Some(n) = numbering {
+                zip.start_file(NUMBERING_PART, opts).unwrap();
+                write!(zip, r#"<w:numbering {W}>{n}</w:numbering>"#).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        buf.into_inner()
+    }
+
+    fn parse_bytes(bytes: Vec<u8>) -> Vec<Block> {
+        parse_docx_reader(Cursor::new(bytes), Uuid::new_v4()).expect("parse docx")
+    }
+
+    #[test]
+    fn paragraphs_and_run_formatting() {
+        let blocks = parse_bytes(build_docx(
+            r#"<w:p><w:r><w:rPr><w:b/><w:sz w:val="24"/><w:color w:val="FF0000"/></w:rPr><w:t xml:space="preserve">The Borrower  </w:t></w:r><w:r><w:t>shall repay.</w:t></w:r></w:p>
+               <w:p/>
+               <w:p><w:r><w:t>Second</w:t></w:r></w:p>"#,
+            None,
+            None,
+        ));
+        assert_eq!(blocks.len(), 2, "empty paragraph skipped");
+        let b = &blocks[0];
+        assert_eq!(b.block_type, BlockType::Paragraph);
+        assert_eq!(b.display_text, "The Borrower  shall repay.");
+        assert_eq!(b.canonical_text, "The Borrower shall repay.");
+        assert_eq!(b.runs.len(), 2);
+        assert!(b.runs[0].formatting.bold);
+        assert_eq!(b.runs[0].formatting.font_size, Some(12.0));
+        assert_eq!(b.runs[0].formatting.color.as_deref(), Some("#FF0000"));
+        assert!(!b.tokens.is_empty());
+        assert_eq!(blocks[1].position_index, 1);
+    }
+
+    #[test]
+    fn styles_and_numbering_populate_meta() {
+        let blocks = parse_bytes(build_docx(
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Definitions</w:t></w:r></w:p>
+               <w:p><w:pPr><w:pStyle w:val="ListParagraph"/><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>1. Loan terms apply</w:t></w:r></w:p>
+               <w:p><w:pPr><w:numPr><w:ilvl w:val="1"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Interest accrues</w:t></w:r></w:p>"#,
+            Some(r#"<w:style w:styleId="Heading1"><w:name w:val="heading 1"/></w:style>"#),
+            Some(
+                r#"<w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:numFmt w:val="decimal"/></w:lvl><w:lvl w:ilvl="1"><w:numFmt w:val="lowerLetter"/></w:lvl></w:abstractNum>
+                   <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>"#,
+            ),
+        ));
+        assert_eq!(blocks[0].block_type, BlockType::Section);
+        assert_eq!(blocks[0].formatting_meta.style_name.as_deref(), Some("heading 1"));
+        assert_eq!(blocks[1].block_type, BlockType::Clause);
+        assert_eq!(blocks[1].structural_path, "1.");
+        assert_eq!(blocks[1].canonical_text, "Loan terms apply", "numbering prefix stripped");
+        assert_eq!(blocks[1].formatting_meta.numbering_id, Some(1));
+        assert_eq!(blocks[2].structural_path, "1.(a)");
+        assert_eq!(blocks[2].formatting_meta.numbering_level, Some(1));
+    }
+
+    #[test]
+    fn tracked_changes_are_captured() {
+        let blocks = parse_bytes(build_docx(
+            r#"<w:p><w:r><w:t xml:space="preserve">Pay </w:t></w:r><w:del w:id="1" w:author="Bob" w:date="2024-03-01T10:00:00Z"><w:r><w:delText>ten</w:delText></w:r></w:del><w:ins w:id="2" w:author="Bob" w:date="2024-03-01T10:00:00Z"><w:r><w:t>twelve</w:t></w:r></w:ins></w:p>
+               <w:p><w:del w:id="3" w:author="Carol"><w:r><w:delText>Removed clause</w:delText></w:r></w:del></w:p>"#,
+            None,
+            None,
+        ));
+        let first = &blocks[0];
+        assert!(first.formatting_meta.is_redline);
+        let tc = first.formatting_meta.tracked_change.as_ref().unwrap();
+        assert_eq!(tc.change_type, ChangeType::Insert);
+        assert_eq!(tc.author, "Bob");
+        assert_eq!(tc.date.to_rfc3339(), "2024-03-01T10:00:00+00:00");
+        assert_eq!(first.display_text, "Pay tentwelve");
+        let revisions: Vec<_> = first.runs.iter().map(|r| r.revision.clone()).collect();
+        assert_eq!(revisions, [None, Some(ChangeType::Delete), Some(ChangeType::Insert)]);
+
+        let tc = blocks[1].formatting_meta.tracked_change.as_ref().unwrap();
+        assert_eq!(tc.change_type, ChangeType::Delete);
+        assert_eq!(tc.original.as_deref(), Some("Removed clause"));
+    }
+
+    #[test]
+    fn tables_become_nested_blocks() {
+        let blocks = parse_bytes(build_docx(
+            r#"<w:p><w:r><w:t>Intro</w:t></w:r></w:p>
+               <w:tbl><w:tr><w:tc><w:p><w:r><w:t>A1</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>B1</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+               <w:p><w:r><w:t>Outro</w:t></w:r></w:p>"#,
+            None,
+            None,
+        ));
+        let types: Vec<_> = blocks.iter().map(|b| b.block_type.clone()).collect();
+        assert_eq!(
+            types,
+            vec![
+                BlockType::Paragraph,
+                BlockType::Table,
+                BlockType::TableRow,
+                BlockType::TableCell,
+                BlockType::TableCell,
+                BlockType::Paragraph,
+            ]
+        );
+        assert_eq!(blocks[2].parent_id, Some(blocks[1].id));
+        assert_eq!(blocks[3].parent_id, Some(blocks[2].id));
+        assert_eq!(blocks[4].canonical_text, "B1");
+        assert_eq!(blocks[4].level, 2);
+        assert_eq!(blocks[5].position_index, 5);
+        assert_eq!(blocks[4].structural_path, "tbl1.r0.c1");
+    }
+
+    #[test]
+    fn images_are_captured_as_attachments() {
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buf);
+            let opts = zip::write::SimpleFileOptions::default();
+            zip.start_file(DOCUMENT_PART, opts).unwrap();
+            write!(
+                zip,
+                r#"<?xml version="1.0"?><w:document {W} xmlns:a="a" xmlns:r="r" xmlns:wp="wp"><w:body>
+                   <w:p><w:r><w:t>Signed:</w:t></w:r></w:p>
+                   <w:p><w:r><w:drawing><wp:inline><wp:docPr id="1" name="Signature"/>
+                       <a:graphic><a:graphicData><a:blip r:embed="rId7"/></a:graphicData></a:graphic>
+                   </wp:inline></w:drawing></w:r></w:p>
+                   </w:body></w:document>"#
+            )
+            .unwrap();
+            zip.start_file(DOCUMENT_RELS_PART, opts).unwrap();
+            write!(
+                zip,
+                r#"<Relationships><Relationship Id="rId7" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/image1.png"/></Relationships>"#
+            )
+            .unwrap();
+            zip.start_file("word/media/image1.png", opts).unwrap();
+            zip.write_all(b"\x89PNG").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let blocks = parse_bytes(buf.into_inner());
+        assert_eq!(blocks.len(), 2, "image-only paragraph is kept");
+        assert!(blocks[0].attachments.is_empty());
+        let image = &blocks[1].attachments[0];
+        assert_eq!(image.attachment_type, rt_model::AttachmentType::Image);
+        assert_eq!(image.storage_ref, "word/media/image1.png");
+        assert_eq!(image.content_hash, rt_model::sha256_hex_bytes(b"\x89PNG"));
+        assert_eq!(image.name.as_deref(), Some("Signature"));
+    }
+
+    #[test]
+    fn structural_paths_are_unique() {
+        let blocks = parse_bytes(build_docx(
+            r#"<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>First list</w:t></w:r></w:p>
+               <w:p><w:r><w:t>Plain</w:t></w:r></w:p>
+               <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="2"/></w:numPr></w:pPr><w:r><w:t>Second list</w:t></w:r></w:p>"#,
+            None,
+            None,
+        ));
+        let paths: Vec<_> = blocks.iter().map(|b| b.structural_path.as_str()).collect();
+        assert_eq!(paths, vec!["1.", "p1", "1.~2"]);
+        assert_eq!(
+            blocks[2].anchor_signature,
+            compute_anchor_signature(&BlockType::Paragraph, "1.~2", "Second list")
+        );
+    }
+
+    #[test]
+    fn invalid_package_is_rejected() {
+        let err = parse_docx_reader(Cursor::new(b"not a zip".to_vec()), Uuid::new_v4());
+        assert!(matches!(err, Err(RtError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn ingest_persists_document_and_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("facility.docx");
+        std::fs::write(
+            &path,
+            build_docx(
+                r#"<w:p><w:r><w:t>One</w:t></w:r></w:p><w:p><w:ins w:author="A"><w:r><w:t>Two</w:t></w:r></w:ins></w:p>"#,
+                None,
+                None,
+            ),
+        )
+        .unwrap();
+
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let doc_id = Uuid::new_v4();
+        let summary = ingest_docx(&store, &path, doc_id).expect("ingest");
+        assert_eq!(summary.document.name, "facility");
+        assert_eq!(summary.document.doc_type, DocumentType::Redline);
+        assert_eq!(summary.block_count, 2);
+
+        let stored = store.get_blocks_by_document(&doc_id).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(stored[1].formatting_meta.is_redline);
+    }
+
+    #[test]
+    fn ingest_docx_bytes_records_no_source_path() {
+        let bytes = build_docx(r#"<w:p><w:r><w:t>One</w:t></w:r></w:p>"#, None, None);
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let doc_id = Uuid::new_v4();
+        let summary = ingest_docx_bytes(
+            &store,
+            &bytes,
+            "upload",
+            doc_id,
+            &ClauseHasher::default(),
+            &IngestOptions::default(),
+        )
+        .expect("ingest");
+        assert_eq!(summary.document.name, "upload");
+        assert_eq!(summary.document.source_path, None);
+        assert_eq!(summary.block_count, 1);
+
+        let err = ingest_docx_bytes(
+            &store,
+            b"not a zip",
+            "bad",
+            Uuid::new_v4(),
+            &ClauseHasher::default(),
+            &IngestOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn ingest_with_keyed_hasher_records_contract() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keyed.docx");
+        std::fs::write(&path, build_docx(r#"<w:p><w:r><w:t>One</w:t></w:r></w:p>"#, None, None))
+            .unwrap();
+
+        let hasher =
+            ClauseHasher::for_contract(rt_model::HASH_CONTRACT_HMAC_SHA256, Some(b"key")).unwrap();
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let doc_id = Uuid::new_v4();
+        let summary = ingest_docx_with_hasher(&store, &path, doc_id, &hasher).expect("ingest");
+        assert_eq!(summary.document.hash_contract_version, rt_model::HASH_CONTRACT_HMAC_SHA256);
+
+        let stored = store.get_blocks_by_document(&doc_id).unwrap();
+        let canonical = &stored[0].canonical_text;
+        assert_eq!(stored[0].clause_hash, hasher.clause_hash(canonical));
+        assert_ne!(stored[0].clause_hash, rt_model::compute_clause_hash(canonical));
+    }
+
+    #[test]
+    fn ingest_chunks_oversized_paragraphs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("long.docx");
+        std::fs::write(
+            &path,
+            build_docx(
+                r#"<w:p><w:r><w:t>The Borrower shall repay the Loan. Interest accrues daily. Fees are payable quarterly.</w:t></w:r></w:p>
+                   <w:p><w:r><w:t>Short.</w:t></w:r></w:p>"#,
+                None,
+                None,
+            ),
+        )
+        .unwrap();
+
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let doc_id = Uuid::new_v4();
+        let options = IngestOptions {
+            max_block_tokens: Some(7),
+            ..IngestOptions::default()
+        };
+        let summary =
+            ingest_docx_with_options(&store, &path, doc_id, &ClauseHasher::default(), &options)
+                .expect("ingest");
+        assert_eq!(summary.block_count, 5);
+
+        let tree = store.get_block_tree(&doc_id).unwrap();
+        assert_eq!(tree.len(), 2);
+        assert!(tree[0].canonical_text.is_empty());
+        let paths: Vec<_> = tree[0].children.iter().map(|c| c.structural_path.as_str()).collect();
+        assert_eq!(paths, vec!["p0.s0", "p0.s1", "p0.s2"]);
+        assert_eq!(tree[0].children[1].canonical_text, "Interest accrues daily.");
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn reingest_keeps_ids_of_unchanged_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("facility.docx");
+        let paragraphs = |texts: &[&str]| {
+            let body: String =
+                texts.iter().map(|t| format!("<w:p><w:r><w:t>{t}</w:t></w:r></w:p>")).collect();
+            build_docx(&body, None, None)
+        };
+        std::fs::write(&path, paragraphs(&["One", "Two", "Three", "Four"])).unwrap();
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let doc_id = Uuid::new_v4();
+        ingest_docx(&store, &path, doc_id).expect("ingest");
+        let before = store.get_blocks_by_document(&doc_id).unwrap();
+
+        std::fs::write(&path, paragraphs(&["One", "Two amended", "Four", "Five"])).unwrap();
+        let summary = crate::reingest_docx(
+            &store,
+            &path,
+            doc_id,
+            &ClauseHasher::default(),
+            &IngestOptions::default(),
+        )
+        .expect("reingest");
+        assert_eq!(summary.unchanged, 1);
+        assert_eq!(summary.updated, vec![before[1].id, before[3].id]);
+        assert_eq!(summary.inserted.len(), 1);
+        assert_eq!(summary.deleted, vec![before[2].id]);
+
+        let after = store.get_blocks_by_document(&doc_id).unwrap();
+        let texts: Vec<_> = after.iter().map(|b| b.canonical_text.as_str()).collect();
+        assert_eq!(texts, vec!["One", "Two amended", "Four", "Five"]);
+        assert_eq!(after[0].id, before[0].id);
+        assert_eq!(after[2].id, before[3].id);
+        assert_eq!(after[3].id, summary.inserted[0]);
+    }
+
+    #[test]
+    fn reingest_requires_an_existing_document() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.docx");
+        std::fs::write(&path, build_docx(r#"<w:p><w:r><w:t>One</w:t></w:r></w:p>"#, None, None))
+            .unwrap();
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let err = crate::reingest_docx(
+            &store,
+            &path,
+            Uuid::new_v4(),
+            &ClauseHasher::default(),
+            &IngestOptions::default(),
+        );
+        assert!(matches!(err, Err(RtError::NotFound(_))));
+    }
+
+    #[test]
+    fn numbering_prefix_pattern() {
+        assert_eq!(strip_numbering_prefix("1.2 Scope of work"), "Scope of work");
+        assert_eq!(strip_numbering_prefix("(a) the Borrower"), "the Borrower");
+        assert_eq!(strip_numbering_prefix("Scope"), "Scope");
+    }
+}
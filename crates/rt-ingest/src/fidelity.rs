@@ -0,0 +1,309 @@
+//! Round-trip fidelity checks for display text.
+//!
+//! Ingest derives a block's `display_text` by concatenating its runs, and
+//! export renders the runs back out, so the two must stay identical through
+//! ingest, storage and export. [`check_block`] rebuilds the text from the
+//! runs and compares it with `display_text` character by character, so that
+//! a non-breaking space turned into a plain one, a dropped line break or a
+//! mangled special character is reported with the exact position and code
+//! points involved.
+//!
+//! Blocks without runs (table and row containers, chunked sentences, blocks
+//! ingested as JSON without formatting) have nothing to compare and are only
+//! counted.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::Result;
+use rt_model::Block;
+use rt_store::db::{BlockStore, DocumentFilter, MAX_LIST_LIMIT};
+
+/// Blocks fetched per page by [`check_document`].
+const CHECK_BATCH: usize = 500;
+
+// ---------------------------------------------------------------------------
+// Report types
+// ---------------------------------------------------------------------------
+
+/// A block whose runs do not reproduce its `display_text`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FidelityIssue {
+    pub block_id: Uuid,
+    pub structural_path: String,
+    /// The stored `display_text`.
+    pub display_text: String,
+    /// The concatenated text of the block's runs.
+    pub run_text: String,
+    /// Index (in characters) of the first difference.
+    pub char_index: usize,
+    /// Human-readable description of the first difference, naming code
+    /// points so invisible characters are identifiable.
+    pub message: String,
+}
+
+/// Outcome of checking one document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FidelityReport {
+    pub document_id: Uuid,
+    /// Blocks whose runs were compared with their `display_text`.
+    pub blocks_checked: usize,
+    /// Blocks without runs, which were not compared.
+    pub blocks_without_runs: usize,
+    pub issues: Vec<FidelityIssue>,
+}
+
+impl FidelityReport {
+    /// True when no checked block has a discrepancy.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Compare `block`'s run text with its `display_text`; `None` when they are
+/// identical or the block has no runs. Children are not checked.
+pub fn check_block(block: &Block) -> Option<FidelityIssue> {
+    if block.runs.is_empty() {
+        return None;
+    }
+    let run_text: String = block.runs.iter().map(|r| r.text.as_str()).collect();
+    if run_text == block.display_text {
+        return None;
+    }
+
+    let mut expected = block.display_text.chars();
+    let mut found = run_text.chars();
+    let mut char_index = 0;
+    let (want, got) = loop {
+        match (expected.next(), found.next()) {
+            (Some(a), Some(b)) if a == b => char_index += 1,
+            other => break other,
+        }
+    };
+    let message = match (want, got) {
+        (Some(a), Some(b)) => format!(
+            "display_text has {} at char {char_index} where runs have {}",
+            describe(a),
+            describe(b)
+        ),
+        (Some(a), None) => format!(
+            "runs end at char {char_index}; display_text continues with {}",
+            describe(a)
+        ),
+        (None, Some(b)) => format!(
+            "display_text ends at char {char_index}; runs continue with {}",
+            describe(b)
+        ),
+        (None, None) => unreachable!("texts differ"),
+    };
+
+    Some(FidelityIssue {
+        block_id: block.id,
+        structural_path: block.structural_path.clone(),
+        display_text: block.display_text.clone(),
+        run_text,
+        char_index,
+        message,
+    })
+}
+
+/// Check every block of `blocks` (a flat list or a tree, whose children are
+/// checked too) as belonging to `document_id`.
+pub fn check_blocks(document_id: Uuid, blocks: &[Block]) -> FidelityReport {
+    let mut report = FidelityReport {
+        document_id,
+        ..FidelityReport::default()
+    };
+    let mut pending: Vec<&Block> = blocks.iter().rev().collect();
+    while let Some(block) = pending.pop() {
+        tally(&mut report, block);
+        pending.extend(block.children.iter().rev());
+    }
+    report
+}
+
+/// Check every stored block of `doc_id`, reading them a page at a time.
+pub fn check_document(store: &dyn BlockStore, doc_id: &Uuid) -> Result<FidelityReport> {
+    store.get_document(doc_id)?;
+    let mut report = FidelityReport {
+        document_id: *doc_id,
+        ..FidelityReport::default()
+    };
+    for block in store.stream_blocks(doc_id, CHECK_BATCH) {
+        tally(&mut report, &block?);
+    }
+    Ok(report)
+}
+
+/// Check every document matching `filter`'s criteria, one report per
+/// document in listing order. The filter's `offset` and `limit` are ignored:
+/// the whole matching set is checked.
+pub fn check_documents(store: &dyn BlockStore, filter: &DocumentFilter) -> Result<Vec<FidelityReport>> {
+    let mut page = DocumentFilter {
+        offset: 0,
+        limit: Some(MAX_LIST_LIMIT),
+        ..filter.clone()
+    };
+    let mut reports = Vec::new();
+    loop {
+        let documents = store.list_documents(&page)?;
+        for doc in &documents {
+            reports.push(check_document(store, &doc.id)?);
+        }
+        if documents.len() < MAX_LIST_LIMIT {
+            return Ok(reports);
+        }
+        page.offset += documents.len();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+fn tally(report: &mut FidelityReport, block: &Block) {
+    if block.runs.is_empty() {
+        report.blocks_without_runs += 1;
+        return;
+    }
+    report.blocks_checked += 1;
+    report.issues.extend(check_block(block));
+}
+
+/// `'x' (U+0078)`, with whitespace and control characters escaped.
+fn describe(c: char) -> String {
+    let shown = if c.is_whitespace() || c.is_control() {
+        c.escape_unicode().to_string()
+    } else {
+        c.to_string()
+    };
+    format!("'{shown}' (U+{:04X})", c as u32)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::{BlockType, Document, DocumentType, Run, RunFormatting};
+    use rt_store::db::{create_memory_pool, SqliteBlockStore};
+    use std::io::Write;
+
+    fn block_with_runs(display: &str, runs: &[&str]) -> Block {
+        let mut block =
+            Block::new(BlockType::Paragraph, "p0", display, display, None, Uuid::new_v4(), 0);
+        block.runs = runs
+            .iter()
+            .map(|t| Run {
+                text: t.to_string(),
+                formatting: RunFormatting::default(),
+                revision: None,
+            })
+            .collect();
+        block
+    }
+
+    #[test]
+    fn matching_runs_are_clean() {
+        let block = block_with_runs("Pay\u{a0}£10\nnow", &["Pay\u{a0}", "£10\n", "now"]);
+        assert_eq!(check_block(&block), None);
+    }
+
+    #[test]
+    fn replaced_non_breaking_space_is_reported() {
+        let block = block_with_runs("10\u{a0}days", &["10 ", "days"]);
+        let issue = check_block(&block).unwrap();
+        assert_eq!(issue.char_index, 2);
+        assert_eq!(issue.run_text, "10 days");
+        assert!(issue.message.contains("U+00A0"), "{}", issue.message);
+        assert!(issue.message.contains("U+0020"), "{}", issue.message);
+    }
+
+    #[test]
+    fn truncated_runs_are_reported() {
+        let issue = check_block(&block_with_runs("Section 1.", &["Section 1"])).unwrap();
+        assert_eq!(issue.char_index, 9);
+        assert!(issue.message.starts_with("runs end"), "{}", issue.message);
+    }
+
+    #[test]
+    fn blocks_without_runs_are_counted_not_checked() {
+        let mut parent = block_with_runs("A", &["A"]);
+        let mut child = block_with_runs("B", &["C"]);
+        child.runs.clear();
+        parent.children.push(child);
+        parent.children.push(block_with_runs("D", &["E"]));
+        let report = check_blocks(Uuid::new_v4(), &[parent]);
+        assert_eq!(report.blocks_checked, 2);
+        assert_eq!(report.blocks_without_runs, 1);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].display_text, "D");
+    }
+
+    #[test]
+    fn stored_documents_are_checked_after_the_round_trip() {
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let mut ids = Vec::new();
+        for (name, runs) in [("clean", ["a\u{a0}", "b"]), ("broken", ["a", "b"])] {
+            let doc = Document {
+                id: Uuid::new_v4(),
+                name: name.into(),
+                source_path: None,
+                doc_type: DocumentType::Original,
+                schema_version: "1".into(),
+                normalization_version: "1".into(),
+                hash_contract_version: "1".into(),
+                anchor_version: "1".into(),
+                ingested_at: chrono::Utc::now(),
+                metadata: None,
+            };
+            store.insert_document(&doc).unwrap();
+            let mut block = block_with_runs("a\u{a0}b", &runs);
+            block.document_id = doc.id;
+            store.insert_block(&block).unwrap();
+            ids.push(doc.id);
+        }
+
+        let reports = check_documents(&store, &DocumentFilter::default()).unwrap();
+        assert_eq!(reports.len(), 2);
+        let report = |id| reports.iter().find(|r| r.document_id == id).unwrap();
+        assert!(report(ids[0]).is_clean());
+        assert_eq!(report(ids[0]).blocks_checked, 1);
+        assert_eq!(report(ids[1]).issues.len(), 1);
+        assert_eq!(report(ids[1]).issues[0].char_index, 1);
+    }
+
+    #[test]
+    fn ingested_docx_survives_the_store_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("special.docx");
+        {
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+            zip.start_file("word/document.xml", zip::write::SimpleFileOptions::default())
+                .unwrap();
+            write!(
+                zip,
+                r#"<?xml version="1.0"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p><w:r><w:t xml:space="preserve">Pay&#160;€10 </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>“now”</w:t><w:br/><w:t>&amp; later</w:t></w:r></w:p><w:tbl><w:tr><w:tc><w:p><w:r><w:t>cell&#xA0;1</w:t></w:r></w:p></w:tc></w:tr></w:tbl></w:body></w:document>"#
+            )
+            .unwrap();
+            zip.finish().unwrap();
+        }
+
+        let store = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let doc_id = Uuid::new_v4();
+        crate::ingest_docx(&store, &path, doc_id).unwrap();
+
+        let report = check_document(&store, &doc_id).unwrap();
+        assert!(report.is_clean(), "{:?}", report.issues);
+        assert_eq!(report.blocks_checked, 2);
+        let stored = store.get_blocks_by_document(&doc_id).unwrap();
+        assert!(stored.iter().any(|b| b.display_text == "Pay\u{a0}€10 “now”\n& later"));
+        assert!(stored.iter().any(|b| b.display_text == "cell\u{a0}1"));
+    }
+}
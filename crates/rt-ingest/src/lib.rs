@@ -0,0 +1,19 @@
+pub mod attachments;
+pub mod chunk;
+pub mod docx;
+pub mod fidelity;
+pub mod numbering;
+pub mod reingest;
+pub mod styles;
+pub mod text;
+pub mod views;
+pub mod xml;
+
+pub use docx::{
+    ingest_docx, ingest_docx_bytes, ingest_docx_with_hasher, ingest_docx_with_options, parse_docx,
+    parse_docx_reader, IngestOptions, IngestSummary,
+};
+pub use fidelity::{check_documents, FidelityIssue, FidelityReport};
+pub use reingest::{reingest_docx, ReingestSummary};
+pub use text::{ingest_text, parse_text};
+pub use views::{materialize_view, RedlineView};
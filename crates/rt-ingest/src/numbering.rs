@@ -0,0 +1,255 @@
+//! Numbering resolution for `word/numbering.xml`.
+//!
+//! Mirrors `RT.Document.NumberingResolver`: tracks list counters as
+//! paragraphs are visited in document order and renders structural paths
+//! such as `"1.2.(a)(iii)"`.
+
+use std::collections::HashMap;
+
+use crate::xml::XmlNode;
+
+#[derive(Debug, Clone)]
+struct LevelDef {
+    format: String,
+    start: i32,
+}
+
+/// Stateful numbering resolver; call [`NumberingResolver::resolve_structural_path`]
+/// once per paragraph, in document order.
+#[derive(Debug, Default)]
+pub struct NumberingResolver {
+    /// abstractNumId → (ilvl → level definition)
+    abstract_nums: HashMap<i32, HashMap<i32, LevelDef>>,
+    /// numId → (abstractNumId, ilvl → start override)
+    nums: HashMap<i32, (i32, HashMap<i32, i32>)>,
+    /// (numId, ilvl) → current counter value
+    counters: HashMap<(i32, i32), i32>,
+}
+
+impl NumberingResolver {
+    /// Build a resolver from the parsed `numbering.xml` root, if the part exists.
+    pub fn new(numbering: Option<&XmlNode>) -> Self {
+        let mut resolver = Self::default();
+        let Some(root) = numbering else {
+            return resolver;
+        };
+
+        for abstract_num in root.children_named("abstractNum") {
+            let abstract_id = int_attr(abstract_num, "abstractNumId").unwrap_or(0);
+            let mut levels = HashMap::new();
+            for lvl in abstract_num.children_named("lvl") {
+                let ilvl = int_attr(lvl, "ilvl").unwrap_or(0);
+                let format = lvl
+                    .child("numFmt")
+                    .and_then(|n| n.attr("val"))
+                    .unwrap_or("decimal")
+                    .to_string();
+                let start = lvl.child("start").and_then(|n| int_attr(n, "val")).unwrap_or(1);
+                levels.insert(ilvl, LevelDef { format, start });
+            }
+            resolver.abstract_nums.insert(abstract_id, levels);
+        }
+
+        for num in root.children_named("num") {
+            let num_id = int_attr(num, "numId").unwrap_or(0);
+            let abstract_ref = num
+                .child("abstractNumId")
+                .and_then(|n| int_attr(n, "val"))
+                .unwrap_or(0);
+            let mut overrides = HashMap::new();
+            for lvl_override in num.children_named("lvlOverride") {
+                let ilvl = int_attr(lvl_override, "ilvl").unwrap_or(0);
+                if let Some(start) = lvl_override
+                    .child("startOverride")
+                    .and_then(|n| int_attr(n, "val"))
+                {
+                    overrides.insert(ilvl, start);
+                }
+            }
+            resolver.nums.insert(num_id, (abstract_ref, overrides));
+        }
+
+        resolver
+    }
+
+    /// Numbering instance id and level of a `w:p`, or `(None, None)` for
+    /// paragraphs that are not numbered.
+    pub fn numbering_info(&self, para: &XmlNode) -> (Option<i32>, Option<i32>) {
+        let Some(num_pr) = para.path(&["pPr", "numPr"]) else {
+            return (None, None);
+        };
+        let num_id = num_pr.child("numId").and_then(|n| int_attr(n, "val"));
+        let ilvl = num_pr.child("ilvl").and_then(|n| int_attr(n, "val"));
+        match num_id {
+            None | Some(0) => (None, None),
+            Some(id) => (Some(id), Some(ilvl.unwrap_or(0))),
+        }
+    }
+
+    /// Structural path for a paragraph (e.g. `"1.2.(a)"`). Advances the
+    /// counter for numbered paragraphs and resets deeper levels.
+    pub fn resolve_structural_path(&mut self, para: &XmlNode) -> String {
+        let (Some(num_id), Some(ilvl)) = self.numbering_info(para) else {
+            return String::new();
+        };
+
+        let key = (num_id, ilvl);
+        let next = match self.counters.get(&key) {
+            Some(current) => current + 1,
+            None => self.start_value(num_id, ilvl),
+        };
+        self.counters.insert(key, next);
+        self.counters.retain(|&(n, l), _| n != num_id || l <= ilvl);
+
+        (0..=ilvl)
+            .map(|l| {
+                let count = self
+                    .counters
+                    .get(&(num_id, l))
+                    .copied()
+                    .unwrap_or_else(|| self.start_value(num_id, l));
+                self.format_number(count, num_id, l)
+            })
+            .collect()
+    }
+
+    fn level_def(&self, num_id: i32, ilvl: i32) -> Option<&LevelDef> {
+        let (abstract_id, _) = self.nums.get(&num_id)?;
+        self.abstract_nums.get(abstract_id)?.get(&ilvl)
+    }
+
+    fn start_value(&self, num_id: i32, ilvl: i32) -> i32 {
+        if let Some(start) = self.nums.get(&num_id).and_then(|(_, o)| o.get(&ilvl)) {
+            return *start;
+        }
+        self.level_def(num_id, ilvl).map(|d| d.start).unwrap_or(1)
+    }
+
+    fn format_number(&self, value: i32, num_id: i32, ilvl: i32) -> String {
+        let format = self
+            .level_def(num_id, ilvl)
+            .map(|d| d.format.to_lowercase())
+            .unwrap_or_else(|| "decimal".to_string());
+        match format.as_str() {
+            "lowerletter" => format!("({})", to_letter(value, false)),
+            "upperletter" => format!("({})", to_letter(value, true)),
+            "lowerroman" => format!("({})", to_roman(value, false)),
+            "upperroman" => format!("({})", to_roman(value, true)),
+            "bullet" => String::new(),
+            _ => format!("{}.", value),
+        }
+    }
+}
+
+fn int_attr(node: &XmlNode, name: &str) -> Option<i32> {
+    node.attr(name).and_then(|v| v.trim().parse().ok())
+}
+
+fn to_letter(mut value: i32, upper: bool) -> String {
+    let mut letters = Vec::new();
+    while value > 0 {
+        value -= 1;
+        letters.push((b'a' + (value % 26) as u8) as char);
+        value /= 26;
+    }
+    let s: String = letters.into_iter().rev().collect();
+    if upper {
+        s.to_uppercase()
+    } else {
+        s
+    }
+}
+
+pub(crate) fn to_roman(mut value: i32, upper: bool) -> String {
+    const NUMERALS: &[(i32, &str)] = &[
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut out = String::new();
+    for &(v, numeral) in NUMERALS {
+        while value >= v {
+            out.push_str(numeral);
+            value -= v;
+        }
+    }
+    if upper {
+        out.to_uppercase()
+    } else {
+        out
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::parse;
+
+    const NUMBERING: &str = r#"<w:numbering xmlns:w="urn:w">
+        <w:abstractNum w:abstractNumId="0">
+            <w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/></w:lvl>
+            <w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="lowerLetter"/></w:lvl>
+            <w:lvl w:ilvl="2"><w:start w:val="1"/><w:numFmt w:val="lowerRoman"/></w:lvl>
+        </w:abstractNum>
+        <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+        <w:num w:numId="2"><w:abstractNumId w:val="0"/>
+            <w:lvlOverride w:ilvl="0"><w:startOverride w:val="5"/></w:lvlOverride>
+        </w:num>
+    </w:numbering>"#;
+
+    fn para(num_id: i32, ilvl: i32) -> XmlNode {
+        parse(&format!(
+            r#"<w:p xmlns:w="urn:w"><w:pPr><w:numPr><w:ilvl w:val="{ilvl}"/><w:numId w:val="{num_id}"/></w:numPr></w:pPr></w:p>"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn nested_levels_render_paths() {
+        let root = parse(NUMBERING).unwrap();
+        let mut r = NumberingResolver::new(Some(&root));
+        assert_eq!(r.resolve_structural_path(&para(1, 0)), "1.");
+        assert_eq!(r.resolve_structural_path(&para(1, 1)), "1.(a)");
+        assert_eq!(r.resolve_structural_path(&para(1, 1)), "1.(b)");
+        assert_eq!(r.resolve_structural_path(&para(1, 2)), "1.(b)(i)");
+        assert_eq!(r.resolve_structural_path(&para(1, 0)), "2.");
+        // Deeper counters reset after a shallower level advances.
+        assert_eq!(r.resolve_structural_path(&para(1, 1)), "2.(a)");
+    }
+
+    #[test]
+    fn start_override_is_honoured() {
+        let root = parse(NUMBERING).unwrap();
+        let mut r = NumberingResolver::new(Some(&root));
+        assert_eq!(r.resolve_structural_path(&para(2, 0)), "5.");
+    }
+
+    #[test]
+    fn unnumbered_paragraph_has_empty_path() {
+        let mut r = NumberingResolver::new(None);
+        let p = parse(r#"<w:p xmlns:w="urn:w"/>"#).unwrap();
+        assert_eq!(r.resolve_structural_path(&p), "");
+        assert_eq!(r.numbering_info(&p), (None, None));
+    }
+
+    #[test]
+    fn letters_and_romans() {
+        assert_eq!(to_letter(1, false), "a");
+        assert_eq!(to_letter(27, true), "AA");
+        assert_eq!(to_roman(14, false), "xiv");
+    }
+}
@@ -0,0 +1,237 @@
+//! Incremental re-ingest: update a stored document from a new revision of
+//! its source, touching only the blocks that changed.
+//!
+//! Incoming blocks are paired with the stored blocks of the document in three
+//! passes, each only considering blocks not yet paired:
+//! 1. same `anchor_signature` (same type, path and opening text),
+//! 2. same `clause_hash` (same text, e.g. after renumbering),
+//! 3. same `block_type` and `structural_path` (text edited in place).
+//!
+//! A paired block keeps its stored id, so deltas, conflicts and overrides
+//! that reference it stay valid; it is rewritten only if anything stored for
+//! it differs. Unpaired incoming blocks are inserted and unpaired stored
+//! blocks are deleted.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use rt_model::error::Result;
+use rt_model::{Block, ClauseHasher, Document};
+use rt_store::db::{BlockStore, ConflictPolicy};
+
+use crate::docx::{prepare_docx, IngestOptions};
+
+// ---------------------------------------------------------------------------
+// BlockChanges
+// ---------------------------------------------------------------------------
+
+/// What a re-ingest does to the blocks of a document.
+#[derive(Debug, Clone, Default)]
+pub struct BlockChanges {
+    /// Blocks to write, parents before children: new blocks and changed
+    /// blocks (under their stored ids).
+    pub upserts: Vec<Block>,
+    /// Ids of the new blocks.
+    pub inserted: Vec<Uuid>,
+    /// Stored ids of the changed blocks.
+    pub updated: Vec<Uuid>,
+    /// Stored ids of the blocks no longer present.
+    pub deleted: Vec<Uuid>,
+    /// Number of stored blocks left exactly as they are.
+    pub unchanged: usize,
+}
+
+/// Pair `incoming` (flat, parent before child) with the stored blocks
+/// `existing` of the same document and work out the changes.
+pub fn plan_block_changes(existing: &[Block], mut incoming: Vec<Block>) -> BlockChanges {
+    let mut paired: Vec<Option<usize>> = vec![None; incoming.len()];
+    let mut taken = vec![false; existing.len()];
+
+    type Key = fn(&Block) -> String;
+    let passes: [Key; 3] = [
+        |b| b.anchor_signature.clone(),
+        |b| b.clause_hash.clone(),
+        |b| format!("{}|{}", b.block_type, b.structural_path),
+    ];
+    for key in passes {
+        let mut candidates: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, block) in existing.iter().enumerate().rev() {
+            if !taken[index] {
+                candidates.entry(key(block)).or_default().push(index);
+            }
+        }
+        for (slot, block) in paired.iter_mut().zip(&incoming) {
+            if slot.is_some() {
+                continue;
+            }
+            if let Some(index) = candidates.get_mut(&key(block)).and_then(Vec::pop) {
+                *slot = Some(index);
+                taken[index] = true;
+            }
+        }
+    }
+
+    // Move paired blocks onto their stored ids, then re-point children.
+    let mut stored_ids = HashMap::new();
+    for (block, slot) in incoming.iter_mut().zip(&paired) {
+        if let Some(index) = slot {
+            stored_ids.insert(block.id, existing[*index].id);
+            block.id = existing[*index].id;
+        }
+    }
+    let mut changes = BlockChanges::default();
+    for (mut block, slot) in incoming.into_iter().zip(paired) {
+        block.parent_id = block.parent_id.map(|p| stored_ids.get(&p).copied().unwrap_or(p));
+        match slot {
+            Some(index) if same_stored_content(&existing[index], &block) => changes.unchanged += 1,
+            Some(_) => {
+                changes.updated.push(block.id);
+                changes.upserts.push(block);
+            }
+            None => {
+                changes.inserted.push(block.id);
+                changes.upserts.push(block);
+            }
+        }
+    }
+    changes.deleted = existing
+        .iter()
+        .zip(&taken)
+        .filter(|(_, taken)| !**taken)
+        .map(|(block, _)| block.id)
+        .collect();
+    changes
+}
+
+/// Whether writing `incoming` over `stored` (same id) would change anything.
+/// Tokens are derived from `canonical_text` and not compared separately.
+fn same_stored_content(stored: &Block, incoming: &Block) -> bool {
+    stored.parent_id == incoming.parent_id
+        && stored.block_type == incoming.block_type
+        && stored.level == incoming.level
+        && stored.structural_path == incoming.structural_path
+        && stored.anchor_signature == incoming.anchor_signature
+        && stored.clause_hash == incoming.clause_hash
+        && stored.canonical_text == incoming.canonical_text
+        && stored.display_text == incoming.display_text
+        && stored.position_index == incoming.position_index
+        && json(&stored.formatting_meta) == json(&incoming.formatting_meta)
+        && json(&stored.runs) == json(&incoming.runs)
+        && stored.attachments == incoming.attachments
+}
+
+/// Formatting types do not implement `PartialEq`; compare their JSON form.
+fn json<T: Serialize>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// reingest_docx
+// ---------------------------------------------------------------------------
+
+/// Outcome of [`reingest_docx`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ReingestSummary {
+    /// The document row as updated.
+    pub document: Document,
+    pub unchanged: usize,
+    pub inserted: Vec<Uuid>,
+    pub updated: Vec<Uuid>,
+    pub deleted: Vec<Uuid>,
+}
+
+/// Re-read the `.docx` at `path` into the existing document `doc_id`,
+/// writing only the blocks that changed (see the module docs). The document
+/// row is updated like a fresh ingest would set it. Blocks keep the stored
+/// document's anchor strategy unless `options` names another.
+///
+/// Fails with `NotFound` when `doc_id` is not stored.
+pub fn reingest_docx(
+    store: &dyn BlockStore,
+    path: &Path,
+    doc_id: Uuid,
+    hasher: &ClauseHasher,
+    options: &IngestOptions,
+) -> Result<ReingestSummary> {
+    let stored = store.get_document(&doc_id)?;
+    let existing = store.get_blocks_by_document(&doc_id)?;
+    // Stored anchors only match incoming ones signed by the same strategy.
+    let options = IngestOptions {
+        anchor_version: Some(options.anchor_version.clone().unwrap_or(stored.anchor_version)),
+        ..options.clone()
+    };
+    let (mut document, incoming) = prepare_docx(path, doc_id, hasher, &options)?;
+    document.name = stored.name;
+    document.metadata = stored.metadata;
+
+    let changes = plan_block_changes(&existing, incoming);
+    store.apply_block_changes(&doc_id, &changes.upserts, &changes.deleted)?;
+    store.upsert_document(&document, ConflictPolicy::Replace)?;
+
+    Ok(ReingestSummary {
+        document,
+        unchanged: changes.unchanged,
+        inserted: changes.inserted,
+        updated: changes.updated,
+        deleted: changes.deleted,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::BlockType;
+
+    fn clause(doc: Uuid, path: &str, text: &str, position: i32) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, doc, position)
+    }
+
+    #[test]
+    fn unchanged_blocks_keep_their_ids() {
+        let doc = Uuid::new_v4();
+        let existing = vec![clause(doc, "1.", "alpha", 0), clause(doc, "2.", "beta", 1)];
+        let incoming = vec![clause(doc, "1.", "alpha", 0), clause(doc, "2.", "beta", 1)];
+
+        let changes = plan_block_changes(&existing, incoming);
+        assert_eq!(changes.unchanged, 2);
+        assert!(changes.upserts.is_empty());
+        assert!(changes.deleted.is_empty());
+    }
+
+    #[test]
+    fn edits_renumbering_and_removals_are_classified() {
+        let doc = Uuid::new_v4();
+        let existing = vec![
+            clause(doc, "1.", "alpha", 0),
+            clause(doc, "2.", "beta", 1),
+            clause(doc, "3.", "gamma", 2),
+        ];
+        let mut parent = clause(doc, "1.", "alpha", 0);
+        let mut child = clause(doc, "1.(a)", "new subclause", 1);
+        child.parent_id = Some(parent.id);
+        parent.display_text = "Alpha".into();
+        let incoming = vec![
+            parent,
+            child,
+            // "gamma" renumbered from 3. to 2.; "beta" removed.
+            clause(doc, "2.", "gamma", 2),
+        ];
+
+        let changes = plan_block_changes(&existing, incoming);
+        assert_eq!(changes.unchanged, 0);
+        assert_eq!(changes.updated, vec![existing[0].id, existing[2].id]);
+        assert_eq!(changes.inserted.len(), 1);
+        assert_eq!(changes.deleted, vec![existing[1].id]);
+
+        let new_child = changes.upserts.iter().find(|b| b.id == changes.inserted[0]).unwrap();
+        assert_eq!(new_child.parent_id, Some(existing[0].id));
+        assert_eq!(changes.upserts[2].structural_path, "2.");
+    }
+}
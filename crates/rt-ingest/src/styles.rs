@@ -0,0 +1,159 @@
+//! Style resolution for `word/styles.xml`.
+//!
+//! Mirrors `RT.Document.StyleResolver`: maps Word paragraph style ids to
+//! [`BlockType`]s and exposes human-readable style names for
+//! [`rt_model::FormattingMeta::style_name`].
+
+use std::collections::HashMap;
+
+use rt_model::BlockType;
+
+use crate::xml::XmlNode;
+
+const LIST_STYLES: &[&str] = &[
+    "listparagraph",
+    "listbullet",
+    "listnumber",
+    "listbullet2",
+    "listbullet3",
+    "listnumber2",
+    "listnumber3",
+    "listcontinue",
+    "listcontinue2",
+    "listcontinue3",
+];
+
+const CLAUSE_STYLE_NAMES: &[&str] = &[
+    "listparagraph",
+    "listbullet",
+    "listnumber",
+    "listcontinue",
+    "bodytext",
+    "bodytext2",
+    "bodytext3",
+];
+
+/// Style id → style name lookup built from `word/styles.xml`.
+#[derive(Debug, Default)]
+pub struct StyleResolver {
+    /// Keyed by lowercased style id (Word treats ids case-insensitively).
+    names: HashMap<String, String>,
+}
+
+impl StyleResolver {
+    /// Build a resolver from the parsed `styles.xml` root, if the part exists.
+    pub fn new(styles: Option<&XmlNode>) -> Self {
+        let mut names = HashMap::new();
+        if let Some(root) = styles {
+            for style in root.children_named("style") {
+                if let Some(id) = style.attr("styleId").filter(|s| !s.is_empty()) {
+                    let name = style
+                        .path(&["name"])
+                        .and_then(|n| n.attr("val"))
+                        .unwrap_or_default();
+                    names.insert(id.to_lowercase(), name.to_string());
+                }
+            }
+        }
+        Self { names }
+    }
+
+    /// Map a Word style id to a canonical [`BlockType`].
+    pub fn resolve_block_type(&self, style_id: Option<&str>) -> BlockType {
+        let Some(style_id) = style_id.filter(|s| !s.is_empty()) else {
+            return BlockType::Paragraph;
+        };
+
+        let normalized = style_id.replace(' ', "").to_lowercase();
+        if normalized.starts_with("heading") || normalized == "title" || normalized == "subtitle" {
+            return BlockType::Section;
+        }
+        if LIST_STYLES.contains(&normalized.as_str()) {
+            return list_block_type(&normalized);
+        }
+
+        if let Some(name) = self.names.get(&style_id.to_lowercase()) {
+            let name = name.to_lowercase().replace(' ', "");
+            if name.starts_with("heading") {
+                return BlockType::Section;
+            }
+            if CLAUSE_STYLE_NAMES.contains(&name.as_str()) {
+                return list_block_type(&name);
+            }
+        }
+
+        BlockType::Paragraph
+    }
+
+    /// Human-readable style name (e.g. `"Heading 1"`), falling back to the id
+    /// for styles missing from `styles.xml`.
+    pub fn style_name(&self, style_id: Option<&str>) -> Option<String> {
+        let style_id = style_id.filter(|s| !s.is_empty())?;
+        match self.names.get(&style_id.to_lowercase()) {
+            Some(name) if !name.is_empty() => Some(name.clone()),
+            Some(_) => None,
+            None => Some(style_id.to_string()),
+        }
+    }
+}
+
+/// Deeper list styles (`…2`, `…3`) are sub-clauses.
+fn list_block_type(normalized: &str) -> BlockType {
+    if normalized.ends_with('2') || normalized.ends_with('3') {
+        BlockType::Subclause
+    } else {
+        BlockType::Clause
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::parse;
+
+    fn resolver() -> StyleResolver {
+        let root = parse(
+            r#"<w:styles xmlns:w="urn:w">
+                <w:style w:styleId="Heading1"><w:name w:val="heading 1"/></w:style>
+                <w:style w:styleId="BodyText2"><w:name w:val="Body Text 2"/></w:style>
+                <w:style w:styleId="Custom"><w:name w:val="My Custom"/></w:style>
+            </w:styles>"#,
+        )
+        .unwrap();
+        StyleResolver::new(Some(&root))
+    }
+
+    #[test]
+    fn headings_map_to_sections() {
+        let r = resolver();
+        assert_eq!(r.resolve_block_type(Some("Heading1")), BlockType::Section);
+        assert_eq!(r.resolve_block_type(Some("Title")), BlockType::Section);
+    }
+
+    #[test]
+    fn list_styles_map_to_clauses() {
+        let r = resolver();
+        assert_eq!(r.resolve_block_type(Some("ListParagraph")), BlockType::Clause);
+        assert_eq!(r.resolve_block_type(Some("ListNumber2")), BlockType::Subclause);
+        assert_eq!(r.resolve_block_type(Some("BodyText2")), BlockType::Subclause);
+    }
+
+    #[test]
+    fn unknown_styles_are_paragraphs() {
+        let r = resolver();
+        assert_eq!(r.resolve_block_type(Some("Custom")), BlockType::Paragraph);
+        assert_eq!(r.resolve_block_type(None), BlockType::Paragraph);
+    }
+
+    #[test]
+    fn style_names_fall_back_to_id() {
+        let r = resolver();
+        assert_eq!(r.style_name(Some("Heading1")).as_deref(), Some("heading 1"));
+        assert_eq!(r.style_name(Some("Missing")).as_deref(), Some("Missing"));
+        assert_eq!(r.style_name(None), None);
+    }
+}
@@ -0,0 +1,533 @@
+//! Plain-text ingestion: segments contract text into blocks by its numbering.
+//!
+//! OCR output and pasted text carry no styles or list definitions, only the
+//! labels typed in front of clauses. [`parse_text`] starts a block at every
+//! line opening with a label it recognises and infers the nesting from the
+//! kind of label, outermost first:
+//!
+//! - `ARTICLE IV`, `Part 2` → [`BlockType::Section`],
+//! - `1.`, `1.2`, `Section 4.1` → [`BlockType::Clause`], nested by the count
+//!   of numbers,
+//! - `(a)`, then `(iii)`, then `(A)`, `(IV)` and `(1)` →
+//!   [`BlockType::Subclause`]; `(i)` right after `(h)` is a letter, and a
+//!   combined label such as `1.2(a)` opens `1.2` as well when needed.
+//!
+//! A block runs until the next labelled line or blank line; unlabelled lines
+//! below it are its hard-wrapped continuation. Text after a blank line with
+//! no label becomes a [`BlockType::Paragraph`] under the innermost open
+//! label. Structural paths are rendered as for `.docx` lists
+//! (`ARTICLE IV.4.1.(a)(iii)`), unlabelled paragraphs get `p3`, and
+//! collisions are suffixed as in [`crate::docx`].
+
+use std::sync::OnceLock;
+
+use chrono::Utc;
+use regex::Regex;
+use uuid::Uuid;
+
+use rt_compare::tokenize::tokenize;
+use rt_model::error::{Result, RtError};
+use rt_model::{Block, BlockType, ClauseHasher, Document, DocumentType};
+use rt_store::db::BlockStore;
+use rt_store::schema::SCHEMA_VERSION;
+
+use crate::chunk::chunk_oversized_blocks;
+use crate::docx::{dedupe_structural_paths, normalize_canonical, IngestOptions, IngestSummary};
+use crate::numbering::to_roman;
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Segment `text` into a flat, parent-before-child block list owned by
+/// `doc_id`.
+pub fn parse_text(text: &str, doc_id: Uuid) -> Vec<Block> {
+    let mut segmenter = Segmenter {
+        doc_id,
+        stack: Vec::new(),
+        blocks: Vec::new(),
+        current: None,
+    };
+    for line in text.lines() {
+        segmenter.line(line.trim());
+    }
+    segmenter.finish();
+    let mut blocks = segmenter.blocks;
+    dedupe_structural_paths(&mut blocks);
+    blocks
+}
+
+/// Segment `text` and persist it as a new document named `name`. Blocks are
+/// hashed by `hasher` and split per `options` as for `.docx` ingestion.
+pub fn ingest_text(
+    store: &dyn BlockStore,
+    text: &str,
+    name: &str,
+    doc_id: Uuid,
+    hasher: &ClauseHasher,
+    options: &IngestOptions,
+) -> Result<IngestSummary> {
+    let strategy = options.anchor_strategy()?;
+    let mut blocks = parse_text(text, doc_id);
+    if blocks.is_empty() {
+        return Err(RtError::InvalidInput("text has no content to ingest".into()));
+    }
+    if let Some(max_tokens) = options.max_block_tokens {
+        blocks = chunk_oversized_blocks(blocks, max_tokens)?;
+    }
+    for block in &mut blocks {
+        block.rehash_with(hasher, strategy.as_ref());
+    }
+
+    let doc = Document {
+        id: doc_id,
+        name: name.to_string(),
+        source_path: None,
+        doc_type: DocumentType::Original,
+        schema_version: SCHEMA_VERSION.to_string(),
+        normalization_version: "1.0.0".to_string(),
+        hash_contract_version: hasher.contract_version().to_string(),
+        anchor_version: strategy.version(),
+        ingested_at: Utc::now(),
+        metadata: None,
+    };
+    store.insert_document(&doc)?;
+    store.insert_blocks(&blocks)?;
+    Ok(IngestSummary {
+        document: doc,
+        block_count: blocks.len(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Labels
+// ---------------------------------------------------------------------------
+
+/// Kind of a label, ordered from outermost to innermost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Heading,
+    /// A decimal label of this many numbers.
+    Decimal(usize),
+    LowerLetter,
+    LowerRoman,
+    UpperLetter,
+    UpperRoman,
+    Number,
+}
+
+/// One part of a label as written: `ARTICLE IV`, `1.2` or the inside of
+/// `(a)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Heading(String),
+    Decimal(String),
+    Paren(String),
+}
+
+fn heading_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(ARTICLE|Article|PART|Part)\s+([IVXLCDM]+|\d{1,3})\b\.?")
+            .expect("valid heading label pattern")
+    })
+}
+
+fn section_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(?:SECTION|Section|§)\s*(\d{1,3}(?:\.\d{1,3})*)\.?")
+            .expect("valid section label pattern")
+    })
+}
+
+fn decimal_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(\d{1,3}(?:\.\d{1,3})*)([.)])?").expect("valid decimal label pattern")
+    })
+}
+
+fn paren_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^\(([a-zA-Z]{1,5}|\d{1,3})\)").expect("valid parenthesized label pattern")
+    })
+}
+
+/// The label `line` opens with and the text after it, or `None` when the
+/// line has no label. A label must be followed by whitespace or end the
+/// line, and a bare number only counts with a `.`, `)` or `(a)` after it.
+fn split_label(line: &str) -> Option<(Vec<Part>, &str)> {
+    let mut parts = Vec::new();
+    let mut rest = line;
+    if let Some(c) = heading_pattern().captures(rest) {
+        parts.push(Part::Heading(format!("{} {}", c[1].to_uppercase(), &c[2])));
+        rest = &rest[c[0].len()..];
+    } else {
+        if let Some(c) = section_pattern().captures(rest) {
+            parts.push(Part::Decimal(c[1].to_string()));
+            rest = &rest[c[0].len()..];
+        } else if let Some(c) = decimal_pattern().captures(rest) {
+            let after = &rest[c[0].len()..];
+            if c[1].contains('.') || c.get(2).is_some() || after.starts_with('(') {
+                parts.push(Part::Decimal(c[1].to_string()));
+                rest = after;
+            }
+        }
+        while let Some(c) = paren_pattern().captures(rest) {
+            parts.push(Part::Paren(c[1].to_string()));
+            rest = &rest[c[0].len()..];
+        }
+    }
+    if parts.is_empty() || !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+        return None;
+    }
+    let body = rest.trim_start().trim_start_matches(['-', '–', '—', ':']);
+    Some((parts, body.trim_start()))
+}
+
+/// Value of a roman numeral written in canonical form, in either case.
+fn roman_value(numeral: &str) -> Option<i32> {
+    let (mut total, mut largest) = (0, 0);
+    for c in numeral.chars().rev() {
+        let value = match c.to_ascii_lowercase() {
+            'i' => 1,
+            'v' => 5,
+            'x' => 10,
+            'l' => 50,
+            'c' => 100,
+            'd' => 500,
+            'm' => 1000,
+            _ => return None,
+        };
+        if value < largest {
+            total -= value;
+        } else {
+            total += value;
+            largest = value;
+        }
+    }
+    (total > 0 && to_roman(total, false) == numeral.to_ascii_lowercase()).then_some(total)
+}
+
+// ---------------------------------------------------------------------------
+// Segmentation
+// ---------------------------------------------------------------------------
+
+/// A label whose block is still open to nested labels.
+struct Open {
+    kind: Kind,
+    label: String,
+    path: String,
+    /// `None` for the `1.2` of `1.2(a)` when no `1.2` block precedes it.
+    id: Option<Uuid>,
+}
+
+/// The block being read, turned into a [`Block`] once it ends.
+struct Pending {
+    id: Uuid,
+    block_type: BlockType,
+    path: String,
+    parent_id: Option<Uuid>,
+    level: i32,
+    display: Vec<String>,
+    body: Vec<String>,
+}
+
+struct Segmenter {
+    doc_id: Uuid,
+    stack: Vec<Open>,
+    blocks: Vec<Block>,
+    current: Option<Pending>,
+}
+
+impl Segmenter {
+    fn line(&mut self, line: &str) {
+        if line.is_empty() {
+            self.finish();
+            return;
+        }
+        if let Some((parts, body)) = split_label(line) {
+            if let Some(kinds) = self.kinds(&parts) {
+                self.finish();
+                self.open_label(&parts, &kinds, line, body);
+                return;
+            }
+        }
+        match &mut self.current {
+            Some(pending) => {
+                pending.display.push(line.to_string());
+                pending.body.push(line.to_string());
+            }
+            None => {
+                let path = format!("p{}", self.blocks.len());
+                let (parent_id, level) = (self.parent(), self.stack.len() as i32);
+                self.start(BlockType::Paragraph, path, parent_id, level, line, line);
+            }
+        }
+    }
+
+    /// The kind of each part, or `None` when a parenthesized part is not a
+    /// letter, numeral or number (as in `(see)`).
+    fn kinds(&self, parts: &[Part]) -> Option<Vec<Kind>> {
+        parts
+            .iter()
+            .map(|part| match part {
+                Part::Heading(_) => Some(Kind::Heading),
+                Part::Decimal(label) => Some(Kind::Decimal(label.split('.').count())),
+                Part::Paren(label) => self.paren_kind(label),
+            })
+            .collect()
+    }
+
+    fn paren_kind(&self, label: &str) -> Option<Kind> {
+        if label.chars().all(|c| c.is_ascii_digit()) {
+            return Some(Kind::Number);
+        }
+        let (letter, roman) = if label.chars().all(|c| c.is_ascii_lowercase()) {
+            (Kind::LowerLetter, Kind::LowerRoman)
+        } else if label.chars().all(|c| c.is_ascii_uppercase()) {
+            (Kind::UpperLetter, Kind::UpperRoman)
+        } else {
+            return None;
+        };
+        let is_roman = roman_value(label).is_some();
+        if label.len() > 1 {
+            return is_roman.then_some(roman);
+        }
+        // `(i)` after `(h)` continues the letters, `(v)` after `(iv)` the
+        // numerals; otherwise only `(i)` starts numerals.
+        let c = label.as_bytes()[0];
+        let follows = |open: &Open| open.kind == letter && open.label.as_bytes() == [c - 1];
+        if self.stack.iter().any(follows) {
+            Some(letter)
+        } else if is_roman && (c.eq_ignore_ascii_case(&b'i') || self.is_open(roman)) {
+            Some(roman)
+        } else {
+            Some(letter)
+        }
+    }
+
+    fn is_open(&self, kind: Kind) -> bool {
+        self.stack.iter().any(|open| open.kind == kind)
+    }
+
+    /// Innermost open label with a block of its own.
+    fn parent(&self) -> Option<Uuid> {
+        self.stack.iter().rev().find_map(|open| open.id)
+    }
+
+    /// Close the labels nested in `kind` and open one for each part, the
+    /// last with a block for `line`. A leading part that repeats an open
+    /// label (the `1.2` of `1.2(a)` below `1.2`) reuses it.
+    fn open_label(&mut self, parts: &[Part], kinds: &[Kind], line: &str, body: &str) {
+        for (i, (part, &kind)) in parts.iter().zip(kinds).enumerate() {
+            let (label, component) = match part {
+                Part::Heading(label) | Part::Decimal(label) => (label, format!("{label}.")),
+                Part::Paren(label) => (label, format!("({label})")),
+            };
+            let last = i + 1 == parts.len();
+            if !last {
+                let same = |open: &Open| open.kind == kind && open.label == *label;
+                if let Some(at) = self.stack.iter().position(same) {
+                    self.stack.truncate(at + 1);
+                    continue;
+                }
+            }
+            self.stack.retain(|open| open.kind < kind);
+            // Decimal labels carry their whole number, so only a heading
+            // prefixes them.
+            let base = match kind {
+                Kind::Decimal(_) => self.stack.iter().find(|open| open.kind == Kind::Heading),
+                _ => self.stack.last(),
+            };
+            let path = base.map(|open| open.path.as_str()).unwrap_or("").to_string() + &component;
+            let id = last.then(|| {
+                let block_type = match kind {
+                    Kind::Heading => BlockType::Section,
+                    Kind::Decimal(_) => BlockType::Clause,
+                    _ => BlockType::Subclause,
+                };
+                let (parent_id, level) = (self.parent(), self.stack.len() as i32);
+                self.start(block_type, path.clone(), parent_id, level, line, body)
+            });
+            self.stack.push(Open {
+                kind,
+                label: label.clone(),
+                path,
+                id,
+            });
+        }
+    }
+
+    fn start(
+        &mut self,
+        block_type: BlockType,
+        path: String,
+        parent_id: Option<Uuid>,
+        level: i32,
+        line: &str,
+        body: &str,
+    ) -> Uuid {
+        let id = Uuid::new_v4();
+        self.current = Some(Pending {
+            id,
+            block_type,
+            path,
+            parent_id,
+            level,
+            display: vec![line.to_string()],
+            body: vec![body.to_string()],
+        });
+        id
+    }
+
+    /// Turn the block being read, if any, into a [`Block`].
+    fn finish(&mut self) {
+        let Some(pending) = self.current.take() else {
+            return;
+        };
+        let canonical = normalize_canonical(&pending.body.join(" "));
+        let mut block = Block::new(
+            pending.block_type,
+            pending.path,
+            canonical,
+            pending.display.join(" "),
+            pending.parent_id,
+            self.doc_id,
+            self.blocks.len() as i32,
+        );
+        block.id = pending.id;
+        block.level = pending.level;
+        block.tokens = tokenize(&block.canonical_text);
+        self.blocks.push(block);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_store::memory::MemoryBlockStore;
+
+    const CONTRACT: &str = "\
+MASTER SERVICES AGREEMENT
+
+ARTICLE I - DEFINITIONS
+1.1 \"Services\" means the services
+described in Schedule A.
+1.2(a) first limb;
+(b) second limb, including
+(i) the first item; and
+(ii) the second item.
+
+ARTICLE II - PAYMENT
+Section 2.1 Fees are due within 30 days.
+
+Late payments bear interest.
+";
+
+    fn outline(blocks: &[Block]) -> Vec<(&str, i32, BlockType)> {
+        blocks
+            .iter()
+            .map(|b| (b.structural_path.as_str(), b.level, b.block_type.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn labels_become_nested_blocks() {
+        let blocks = parse_text(CONTRACT, Uuid::new_v4());
+        assert_eq!(
+            outline(&blocks),
+            [
+                ("p0", 0, BlockType::Paragraph),
+                ("ARTICLE I.", 0, BlockType::Section),
+                ("ARTICLE I.1.1.", 1, BlockType::Clause),
+                ("ARTICLE I.1.2.(a)", 2, BlockType::Subclause),
+                ("ARTICLE I.1.2.(b)", 2, BlockType::Subclause),
+                ("ARTICLE I.1.2.(b)(i)", 3, BlockType::Subclause),
+                ("ARTICLE I.1.2.(b)(ii)", 3, BlockType::Subclause),
+                ("ARTICLE II.", 0, BlockType::Section),
+                ("ARTICLE II.2.1.", 1, BlockType::Clause),
+                ("p9", 2, BlockType::Paragraph),
+            ]
+        );
+
+        let clause = &blocks[2];
+        assert_eq!(clause.parent_id, Some(blocks[1].id));
+        let services = "\"Services\" means the services described in Schedule A.";
+        assert_eq!(clause.canonical_text, services);
+        assert!(clause.display_text.starts_with("1.1 \"Services\""));
+        assert!(!clause.tokens.is_empty());
+        assert_eq!(blocks[1].canonical_text, "DEFINITIONS");
+        // `1.2` has no block of its own, so `(a)` hangs off the article.
+        assert_eq!(blocks[3].parent_id, Some(blocks[1].id));
+        assert_eq!(blocks[5].parent_id, Some(blocks[4].id));
+        assert_eq!(blocks[9].parent_id, Some(blocks[8].id));
+    }
+
+    #[test]
+    fn roman_numerals_are_told_from_letters() {
+        let text = "1. Scope\n(a) a\n(h) h\n(i) i\n(j) j\n(i) one\n(ii) two\n(iv) four\n(v) five\n";
+        let paths: Vec<String> = parse_text(text, Uuid::new_v4())
+            .into_iter()
+            .map(|b| b.structural_path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "1.", "1.(a)", "1.(h)", "1.(i)", "1.(j)", "1.(j)(i)", "1.(j)(ii)", "1.(j)(iv)",
+                "1.(j)(v)",
+            ]
+        );
+        assert_eq!(roman_value("xiv"), Some(14));
+        assert_eq!(roman_value("iiii"), None);
+    }
+
+    #[test]
+    fn prose_is_not_mistaken_for_labels() {
+        for line in ["2024 was a good year.", "(see above) for details", "1.2million units"] {
+            assert!(split_label(line).is_none(), "{line}");
+        }
+        let text = "The parties agree\n(see) Schedule A, where 1 plus 1 is 2.";
+        let blocks = parse_text(text, Uuid::new_v4());
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            blocks[0].canonical_text,
+            "The parties agree (see) Schedule A, where 1 plus 1 is 2."
+        );
+    }
+
+    #[test]
+    fn ingest_text_stores_the_document() {
+        let store = MemoryBlockStore::new();
+        let doc_id = Uuid::new_v4();
+        let hasher = ClauseHasher::default();
+        let options = IngestOptions::default();
+        let summary = ingest_text(&store, CONTRACT, "msa.txt", doc_id, &hasher, &options).unwrap();
+        assert_eq!(summary.block_count, 10);
+        assert_eq!(store.get_document(&doc_id).unwrap().name, "msa.txt");
+        assert_eq!(store.get_blocks_by_document(&doc_id).unwrap().len(), 10);
+
+        let err = ingest_text(&store, "\n \n", "empty", Uuid::new_v4(), &hasher, &options);
+        assert!(err.unwrap_err().to_string().contains("no content"));
+
+        let options = IngestOptions {
+            anchor_version: Some("2+shingles".into()),
+            ..IngestOptions::default()
+        };
+        let doc_id = Uuid::new_v4();
+        ingest_text(&store, CONTRACT, "msa-v2.txt", doc_id, &hasher, &options).unwrap();
+        assert_eq!(store.get_document(&doc_id).unwrap().anchor_version, "2+shingles");
+        let options = IngestOptions {
+            anchor_version: Some("3".into()),
+            ..IngestOptions::default()
+        };
+        let err = ingest_text(&store, CONTRACT, "msa-v3.txt", Uuid::new_v4(), &hasher, &options);
+        assert!(err.unwrap_err().to_string().contains("unknown anchor version"));
+    }
+}
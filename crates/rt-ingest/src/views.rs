@@ -0,0 +1,308 @@
+//! Accepted and rejected views of a redline.
+//!
+//! A redline's blocks hold the text of every tracked insertion and deletion
+//! at once ("Pay tentwelve"). To compare or merge against the baseline a
+//! reviewer means, [`materialize_view`] stores one reading of it as a new
+//! document: every change accepted (inserted runs kept, deleted runs
+//! dropped) or every change rejected (the reverse). A paragraph left without
+//! text is dropped, its children moving up to its parent; the view's blocks
+//! carry no tracked changes.
+//!
+//! Revisions are read from [`Run::revision`]. Blocks ingested before runs
+//! were marked have none and read the same in both views; re-ingest the
+//! source to get their revisions.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_compare::tokenize::tokenize;
+use rt_model::error::Result;
+use rt_model::{
+    anchor_strategy, Block, BlockType, ChangeType, ClauseHasher, Document, DocumentType, Run,
+};
+use rt_store::db::BlockStore;
+
+use crate::docx::{normalize_canonical, strip_numbering_prefix};
+
+/// Metadata key of a view holding the id of the redline it reads.
+pub const VIEW_OF_KEY: &str = "view_of";
+
+/// Metadata key of a view holding its [`RedlineView`].
+pub const VIEW_KEY: &str = "view";
+
+/// Which reading of a redline to materialize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedlineView {
+    /// Every tracked change accepted.
+    Accepted,
+    /// Every tracked change rejected: the text before the revisions.
+    Rejected,
+}
+
+impl RedlineView {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RedlineView::Accepted => "accepted",
+            RedlineView::Rejected => "rejected",
+        }
+    }
+
+    /// Whether text of `revision` reads in this view.
+    pub fn keeps(&self, revision: Option<&ChangeType>) -> bool {
+        !matches!(
+            (self, revision),
+            (RedlineView::Accepted, Some(ChangeType::Delete))
+                | (RedlineView::Rejected, Some(ChangeType::Insert))
+        )
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Store `view` of document `doc_id` as a new document, and return it.
+/// `NotFound` when `doc_id` is not stored.
+///
+/// The view keeps the redline's name, source path and metadata, adding
+/// [`VIEW_OF_KEY`] and [`VIEW_KEY`]; it is an `original` document ingested
+/// at the time of the call. Its blocks get new ids and are hashed with
+/// `hasher`, under the redline's anchor strategy. A document without tracked
+/// changes yields a plain copy.
+pub fn materialize_view(
+    store: &dyn BlockStore,
+    doc_id: Uuid,
+    view: RedlineView,
+    hasher: &ClauseHasher,
+) -> Result<Document> {
+    let redline = store.get_document(&doc_id)?;
+    let tree = store.get_block_tree(&doc_id)?;
+
+    let mut metadata = match redline.metadata {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    metadata.insert(VIEW_OF_KEY.into(), serde_json::json!(doc_id));
+    metadata.insert(VIEW_KEY.into(), serde_json::json!(view));
+    let document = Document {
+        id: Uuid::new_v4(),
+        doc_type: DocumentType::Original,
+        hash_contract_version: hasher.contract_version().to_string(),
+        ingested_at: Utc::now(),
+        metadata: Some(serde_json::Value::Object(metadata)),
+        ..redline
+    };
+
+    let mut blocks = view_blocks(&tree, view, document.id, hasher);
+    let strategy = anchor_strategy(&document.anchor_version)?;
+    for block in &mut blocks {
+        block.reanchor(hasher, strategy.as_ref());
+    }
+    store.insert_document(&document)?;
+    store.insert_blocks(&blocks)?;
+    Ok(document)
+}
+
+/// The block tree `tree` as read in `view`: a flat, parent-before-child
+/// list under fresh ids in document `doc_id`, hashed with `hasher`.
+pub fn view_blocks(
+    tree: &[Block],
+    view: RedlineView,
+    doc_id: Uuid,
+    hasher: &ClauseHasher,
+) -> Vec<Block> {
+    let mut out = Vec::new();
+    for block in tree {
+        collect_view(block, None, view, doc_id, hasher, &mut out);
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+fn collect_view(
+    block: &Block,
+    parent_id: Option<Uuid>,
+    view: RedlineView,
+    doc_id: Uuid,
+    hasher: &ClauseHasher,
+    out: &mut Vec<Block>,
+) {
+    let parent_id = match block_view(block, view) {
+        Some(mut copy) => {
+            copy.id = Uuid::new_v4();
+            copy.document_id = doc_id;
+            copy.parent_id = parent_id;
+            copy.rehash(hasher);
+            let id = copy.id;
+            out.push(copy);
+            Some(id)
+        }
+        None => parent_id,
+    };
+    for child in &block.children {
+        collect_view(child, parent_id, view, doc_id, hasher, out);
+    }
+}
+
+/// `block` without its children as read in `view`, or `None` when the view
+/// leaves it without text or attachments.
+fn block_view(block: &Block, view: RedlineView) -> Option<Block> {
+    let mut copy = Block {
+        children: Vec::new(),
+        ..block.clone()
+    };
+    copy.formatting_meta.is_redline = false;
+    copy.formatting_meta.tracked_change = None;
+    if block.runs.iter().all(|r| r.revision.is_none()) {
+        return Some(copy);
+    }
+
+    copy.runs = block
+        .runs
+        .iter()
+        .filter(|r| view.keeps(r.revision.as_ref()))
+        .map(|r| Run {
+            revision: None,
+            ..r.clone()
+        })
+        .collect();
+    copy.display_text = copy.runs.iter().map(|r| r.text.as_str()).collect();
+    if copy.display_text.trim().is_empty() && copy.attachments.is_empty() {
+        return None;
+    }
+    copy.canonical_text = normalize_canonical(&copy.display_text);
+    if copy.block_type != BlockType::TableCell {
+        copy.canonical_text = strip_numbering_prefix(&copy.canonical_text);
+    }
+    copy.tokens = tokenize(&copy.canonical_text);
+    Some(copy)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::{RunFormatting, TrackedChange};
+    use rt_store::db::{create_memory_pool, SqliteBlockStore};
+
+    fn run(text: &str, revision: Option<ChangeType>) -> Run {
+        Run {
+            text: text.into(),
+            formatting: RunFormatting::default(),
+            revision,
+        }
+    }
+
+    fn redline_block(doc: Uuid, path: &str, runs: Vec<Run>, position: i32) -> Block {
+        let text: String = runs.iter().map(|r| r.text.as_str()).collect();
+        let mut block = Block::new(BlockType::Paragraph, path, &text, &text, None, doc, position);
+        block.formatting_meta.is_redline = runs.iter().any(|r| r.revision.is_some());
+        block.formatting_meta.tracked_change = block.formatting_meta.is_redline.then(|| {
+            TrackedChange {
+                author: "Bob".into(),
+                date: Utc::now(),
+                change_type: ChangeType::Insert,
+                original: None,
+            }
+        });
+        block.runs = runs;
+        block
+    }
+
+    fn texts(blocks: &[Block]) -> Vec<&str> {
+        blocks.iter().map(|b| b.canonical_text.as_str()).collect()
+    }
+
+    #[test]
+    fn views_keep_one_side_of_each_revision() {
+        let doc = Uuid::new_v4();
+        let tree = vec![
+            redline_block(
+                doc,
+                "1.",
+                vec![
+                    run("Pay ", None),
+                    run("ten", Some(ChangeType::Delete)),
+                    run("twelve", Some(ChangeType::Insert)),
+                    run(" dollars.", None),
+                ],
+                0,
+            ),
+            redline_block(doc, "2.", vec![run("Removed clause", Some(ChangeType::Delete))], 1),
+            redline_block(doc, "3.", vec![run("Unchanged clause", None)], 2),
+        ];
+        let hasher = ClauseHasher::default();
+
+        let accepted = view_blocks(&tree, RedlineView::Accepted, doc, &hasher);
+        assert_eq!(texts(&accepted), ["Pay twelve dollars.", "Unchanged clause"]);
+        assert_eq!(accepted[0].tokens.len(), 4);
+        assert!(accepted[0].runs.iter().all(|r| r.revision.is_none()));
+        assert!(!accepted[0].formatting_meta.is_redline);
+        assert_eq!(accepted[0].clause_hash, hasher.clause_hash("Pay twelve dollars."));
+
+        let rejected = view_blocks(&tree, RedlineView::Rejected, doc, &hasher);
+        assert_eq!(
+            texts(&rejected),
+            ["Pay ten dollars.", "Removed clause", "Unchanged clause"]
+        );
+        assert_ne!(rejected[2].id, tree[2].id);
+    }
+
+    #[test]
+    fn dropped_blocks_hand_their_children_to_their_parent() {
+        let doc = Uuid::new_v4();
+        let mut parent = redline_block(doc, "1.", vec![run("Intro", Some(ChangeType::Insert))], 0);
+        let child = redline_block(doc, "1.(a)", vec![run("Detail", None)], 1);
+        parent.children.push(child);
+
+        let rejected = view_blocks(&[parent], RedlineView::Rejected, doc, &ClauseHasher::default());
+        assert_eq!(texts(&rejected), ["Detail"]);
+        assert_eq!(rejected[0].parent_id, None);
+    }
+
+    #[test]
+    fn materialized_views_are_stored_documents() {
+        let pool = create_memory_pool().unwrap();
+        let store = SqliteBlockStore::new(pool);
+        let redline = Document {
+            id: Uuid::new_v4(),
+            name: "msa".into(),
+            source_path: None,
+            doc_type: DocumentType::Redline,
+            schema_version: "1.0.0".into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        };
+        store.insert_document(&redline).unwrap();
+        let block = redline_block(
+            redline.id,
+            "1.",
+            vec![run("Fees are ", None), run("not ", Some(ChangeType::Insert)), run("due.", None)],
+            0,
+        );
+        store.insert_blocks(&[block]).unwrap();
+
+        let hasher = ClauseHasher::default();
+        let view = materialize_view(&store, redline.id, RedlineView::Rejected, &hasher).unwrap();
+        assert_eq!(view.doc_type, DocumentType::Original);
+        let metadata = view.metadata.clone().unwrap();
+        assert_eq!(metadata[VIEW_OF_KEY], redline.id.to_string());
+        assert_eq!(metadata[VIEW_KEY], "rejected");
+        let blocks = store.get_blocks_by_document(&view.id).unwrap();
+        assert_eq!(texts(&blocks), ["Fees are due."]);
+
+        let missing = materialize_view(&store, Uuid::new_v4(), RedlineView::Accepted, &hasher);
+        assert!(missing.is_err());
+    }
+}
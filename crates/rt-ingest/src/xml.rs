@@ -0,0 +1,178 @@
+//! Minimal XML element tree for reading OOXML parts.
+//!
+//! OOXML parts are small enough to hold in memory, and a tree is much easier
+//! to walk than a pull parser when resolving nested constructs such as
+//! `w:ins > w:r > w:t`. Element and attribute names are stored by **local
+//! name** only (`w:p` → `p`), since WordprocessingML prefixes are not fixed.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use rt_model::error::{Result, RtError};
+
+/// A single XML element with its attributes, children and direct text.
+#[derive(Debug, Clone, Default)]
+pub struct XmlNode {
+    /// Local element name (no namespace prefix).
+    pub name: String,
+    /// Attributes as `(local_name, unescaped_value)` pairs.
+    pub attrs: Vec<(String, String)>,
+    /// Child elements in document order.
+    pub children: Vec<XmlNode>,
+    /// Concatenated text content directly inside this element.
+    pub text: String,
+}
+
+impl XmlNode {
+    /// Value of the attribute with the given local name.
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// First direct child with the given local name.
+    pub fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// All direct children with the given local name.
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlNode> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Follow a path of direct-child names, e.g. `["pPr", "pStyle"]`.
+    pub fn path(&self, names: &[&str]) -> Option<&XmlNode> {
+        names.iter().try_fold(self, |node, n| node.child(n))
+    }
+
+    /// Depth-first search for any descendant with the given local name.
+    pub fn has_descendant(&self, name: &str) -> bool {
+        self.children
+            .iter()
+            .any(|c| c.name == name || c.has_descendant(name))
+    }
+
+    /// First descendant (depth-first, document order) with the given name.
+    pub fn find_descendant(&self, name: &str) -> Option<&XmlNode> {
+        for c in &self.children {
+            if c.name == name {
+                return Some(c);
+            }
+            if let Some(found) = c.find_descendant(name) {
+                return Some(found);
+            }
+        }
+        None
+    }
+}
+
+/// Parse an XML document and return its root element.
+pub fn parse(xml: &str) -> Result<XmlNode> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(false);
+
+    // Synthetic container so the root element ends up as its only child.
+    let mut stack: Vec<XmlNode> = vec![XmlNode::default()];
+
+    loop {
+        match reader.read_event().map_err(xml_err)? {
+            Event::Start(e) => stack.push(start_node(&e)?),
+            Event::Empty(e) => {
+                let node = start_node(&e)?;
+                push_child(&mut stack, node);
+            }
+            Event::End(_) => {
+                let node = stack
+                    .pop()
+                    .ok_or_else(|| RtError::InvalidInput("unbalanced XML".into()))?;
+                push_child(&mut stack, node);
+            }
+            Event::Text(t) => {
+                let text = t.unescape().map_err(xml_err)?;
+                if let Some(top) = stack.last_mut() {
+                    top.text.push_str(&text);
+                }
+            }
+            Event::CData(c) => {
+                if let Some(top) = stack.last_mut() {
+                    top.text.push_str(&String::from_utf8_lossy(&c));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let mut container = stack
+        .pop()
+        .ok_or_else(|| RtError::InvalidInput("unbalanced XML".into()))?;
+    if !stack.is_empty() {
+        return Err(RtError::InvalidInput("unterminated XML element".into()));
+    }
+    container
+        .children
+        .pop()
+        .ok_or_else(|| RtError::InvalidInput("XML document has no root element".into()))
+}
+
+fn start_node(e: &BytesStart<'_>) -> Result<XmlNode> {
+    let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+    let mut attrs = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr.map_err(xml_err)?;
+        let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned();
+        let value = attr.unescape_value().map_err(xml_err)?.into_owned();
+        attrs.push((key, value));
+    }
+    Ok(XmlNode {
+        name,
+        attrs,
+        ..Default::default()
+    })
+}
+
+fn push_child(stack: &mut [XmlNode], node: XmlNode) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(node);
+    }
+}
+
+fn xml_err(e: impl std::fmt::Display) -> RtError {
+    RtError::InvalidInput(format!("malformed XML: {}", e))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_elements_by_local_name() {
+        let root = parse(
+            r#"<w:document xmlns:w="urn:w"><w:body><w:p><w:r><w:t xml:space="preserve"> a &amp; b</w:t></w:r></w:p></w:body></w:document>"#,
+        )
+        .unwrap();
+        assert_eq!(root.name, "document");
+        let t = root.path(&["body", "p", "r", "t"]).unwrap();
+        assert_eq!(t.text, " a & b");
+        assert_eq!(t.attr("space"), Some("preserve"));
+    }
+
+    #[test]
+    fn empty_elements_are_children() {
+        let root = parse(r#"<a><b val="1"/><b val="2"/></a>"#).unwrap();
+        let vals: Vec<_> = root.children_named("b").filter_map(|b| b.attr("val")).collect();
+        assert_eq!(vals, vec!["1", "2"]);
+        assert!(root.has_descendant("b"));
+    }
+
+    #[test]
+    fn malformed_xml_is_rejected() {
+        assert!(parse("<a><b></a>").is_err());
+    }
+}
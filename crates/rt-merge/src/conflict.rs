@@ -1,3 +1,4 @@
+use rt_core::RtError;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -17,6 +18,31 @@ pub enum ConflictType {
     MoveCollision,
     /// One reviewer deleted a block that another reviewer modified.
     DeleteModify,
+    /// A one-sided insertion or deletion that the merge's auto-accept rules
+    /// hold for review (see [`crate::MergeConfig`]).
+    ReviewRequired,
+}
+
+impl ConflictType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictType::ContentOverlap => "content_overlap",
+            ConflictType::MoveCollision => "move_collision",
+            ConflictType::DeleteModify => "delete_modify",
+            ConflictType::ReviewRequired => "review_required",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, RtError> {
+        match s {
+            "content_overlap" => Ok(ConflictType::ContentOverlap),
+            "move_collision" => Ok(ConflictType::MoveCollision),
+            "delete_modify" => Ok(ConflictType::DeleteModify),
+            "review_required" => Ok(ConflictType::ReviewRequired),
+            other => Err(RtError::InvalidInput(format!("unknown conflict type: {other}"))),
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -37,6 +63,28 @@ pub enum ConflictResolution {
     Manual,
 }
 
+impl ConflictResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictResolution::Pending => "pending",
+            ConflictResolution::AcceptedBase => "accepted_base",
+            ConflictResolution::AcceptedIncoming => "accepted_incoming",
+            ConflictResolution::Manual => "manual",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, RtError> {
+        match s {
+            "pending" => Ok(ConflictResolution::Pending),
+            "accepted_base" => Ok(ConflictResolution::AcceptedBase),
+            "accepted_incoming" => Ok(ConflictResolution::AcceptedIncoming),
+            "manual" => Ok(ConflictResolution::Manual),
+            other => Err(RtError::InvalidInput(format!("unknown conflict resolution: {other}"))),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // MergeConflict
 // ---------------------------------------------------------------------------
@@ -355,4 +403,26 @@ mod tests {
         // base[0] conflicts with incoming[0]; base[1] conflicts with incoming[1].
         assert_eq!(conflicts.len(), 2);
     }
+
+    #[test]
+    fn enum_names_round_trip() {
+        for t in [
+            ConflictType::ContentOverlap,
+            ConflictType::MoveCollision,
+            ConflictType::DeleteModify,
+            ConflictType::ReviewRequired,
+        ] {
+            assert_eq!(ConflictType::from_str(t.as_str()).unwrap(), t);
+        }
+        for r in [
+            ConflictResolution::Pending,
+            ConflictResolution::AcceptedBase,
+            ConflictResolution::AcceptedIncoming,
+            ConflictResolution::Manual,
+        ] {
+            assert_eq!(ConflictResolution::from_str(r.as_str()).unwrap(), r);
+        }
+        assert!(ConflictType::from_str("nope").is_err());
+        assert!(ConflictResolution::from_str("nope").is_err());
+    }
 }
@@ -0,0 +1,735 @@
+//! Reviewer-specific redline export.
+//!
+//! Renders the deltas of a single [`ReviewLayer`] against the base document
+//! so that each reviewer can see only their own proposed changes. Two output
+//! formats are supported:
+//!
+//! - **HTML** — a standalone page using `<ins>` / `<del>` markup.
+//! - **DOCX** — a minimal `.docx` package whose main document part
+//!   (`word/document.xml`) serialises each change as a `w:ins` / `w:del`
+//!   tracked revision.
+//!
+//! DOCX redlines also carry reviewer comments as native Word comments: each
+//! comment's token range becomes a `w:commentRangeStart` / `w:commentRangeEnd`
+//! pair placed on the run boundaries of those tokens, and the comment bodies,
+//! with their authors, go into a separate `word/comments.xml` part.
+//!
+//! [`ReviewerRedline::content`] holds the HTML page or the main document
+//! part; [`ReviewerRedline::file_content`] is the file written to disk.
+
+use std::io::{Cursor, Write};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use rt_compare::tokenize::tokenize;
+use rt_compare::worker::flatten_blocks;
+use rt_core::artifact::{Artifact, ArtifactType};
+use rt_core::vfs::Vfs;
+use rt_core::{sha256_hex, Block, RtError, Token};
+
+use crate::layer::{BlockDelta, DeltaType, ReviewComment, ReviewLayer};
+
+// ---------------------------------------------------------------------------
+// Public types
+// ---------------------------------------------------------------------------
+
+/// Output format of a reviewer redline.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Html,
+    Docx,
+}
+
+/// A rendered redline covering one reviewer's layer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewerRedline {
+    /// The review layer whose deltas were rendered.
+    pub review_layer_id: Uuid,
+    /// Reviewer who owns the layer.
+    pub reviewer_id: String,
+    /// Format of `content`.
+    pub format: ExportFormat,
+    /// Rendered document: the HTML page, or the `word/document.xml` part of
+    /// a DOCX redline.
+    pub content: String,
+    /// Number of layer deltas rendered into `content`.
+    pub deltas_applied: usize,
+    /// Number of comments anchored in `content`; always 0 for HTML.
+    #[serde(default)]
+    pub comments_applied: usize,
+    /// The `word/comments.xml` part holding the anchored comments; `None`
+    /// when no comment was anchored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comments_part: Option<String>,
+    /// SHA-256 over the base blocks' clause hashes, identifying the exact base
+    /// state the redline was rendered against.
+    pub source_document_hash: String,
+}
+
+impl ReviewerRedline {
+    /// The artifact type corresponding to this redline's format.
+    pub fn artifact_type(&self) -> ArtifactType {
+        match self.format {
+            ExportFormat::Html => ArtifactType::ReviewerRedlineHtml,
+            ExportFormat::Docx => ArtifactType::ReviewerRedlineDocx,
+        }
+    }
+
+    /// The file this redline is written as: the HTML page, or a `.docx`
+    /// package holding `content` and, when comments were anchored,
+    /// `comments_part`.
+    pub fn file_content(&self) -> Result<Vec<u8>, RtError> {
+        match self.format {
+            ExportFormat::Html => Ok(self.content.clone().into_bytes()),
+            ExportFormat::Docx => package_docx(&self.content, self.comments_part.as_deref()),
+        }
+    }
+
+    /// Build the [`Artifact`] record for this redline once its
+    /// [`file_content`](Self::file_content) has been written to `file_path`.
+    pub fn to_artifact(
+        &self,
+        workflow_id: Uuid,
+        file_path: impl Into<String>,
+    ) -> Result<Artifact, RtError> {
+        Ok(Artifact::new(
+            workflow_id,
+            self.artifact_type(),
+            file_path,
+            self.file_content()?,
+            Some(self.source_document_hash.clone()),
+        ))
+    }
+
+    /// Also write [`ReviewerRedline::comments_part`] on its own to `path`
+    /// through `vfs`, for hosts that assemble their own package. Nothing is
+    /// written without a path or an anchored comment.
+    pub fn write_comments(&self, vfs: &dyn Vfs, path: Option<&str>) -> Result<(), RtError> {
+        match (&self.comments_part, path) {
+            (Some(part), Some(path)) => vfs.write(path, part.as_bytes()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Render the deltas belonging to `layer` against `base_blocks`.
+///
+/// Only deltas whose `review_layer_id` matches `layer.id` and whose
+/// `reviewer_id` matches `layer.reviewer_id` are rendered; everything else in
+/// `deltas` is ignored, so callers may pass the full delta set of a workflow.
+///
+/// `comments` are rendered into DOCX output only, each attributed to its own
+/// author; comments on blocks outside `base_blocks` are ignored.
+///
+/// Token ranges are interpreted against each base block's token stream (the
+/// stored `tokens`, or the tokenized `canonical_text` when none are stored),
+/// matching the indices produced by the merge engine.
+pub fn export_reviewer_redline(
+    base_blocks: &[Block],
+    layer: &ReviewLayer,
+    deltas: &[BlockDelta],
+    comments: &[ReviewComment],
+    format: ExportFormat,
+) -> ReviewerRedline {
+    let flat = flatten_blocks(base_blocks);
+
+    let layer_deltas: Vec<&BlockDelta> = deltas
+        .iter()
+        .filter(|d| d.review_layer_id == layer.id && d.reviewer_id == layer.reviewer_id)
+        .collect();
+
+    let mut deltas_applied = 0usize;
+    let mut paragraphs: Vec<(&Block, Vec<Segment>)> = Vec::with_capacity(flat.len());
+    for block in &flat {
+        let block_deltas: Vec<&BlockDelta> = layer_deltas
+            .iter()
+            .copied()
+            .filter(|d| d.block_id == block.id)
+            .collect();
+        deltas_applied += block_deltas.len();
+        let block_comments: Vec<usize> = (0..comments.len())
+            .filter(|&i| comments[i].block_id == block.id)
+            .collect();
+        paragraphs.push((block, block_segments(block, &block_deltas, comments, &block_comments)));
+    }
+
+    let (content, anchored) = match format {
+        ExportFormat::Html => (render_html(&layer.reviewer_id, &paragraphs), Vec::new()),
+        ExportFormat::Docx => render_docx(&layer.reviewer_id, &paragraphs),
+    };
+    let comments_part = (!anchored.is_empty()).then(|| render_comments(comments, &anchored));
+
+    let source_document_hash = sha256_hex(
+        &flat
+            .iter()
+            .map(|b| b.clause_hash.as_str())
+            .collect::<Vec<_>>()
+            .join("|"),
+    );
+
+    ReviewerRedline {
+        review_layer_id: layer.id,
+        reviewer_id: layer.reviewer_id.clone(),
+        format,
+        content,
+        deltas_applied,
+        comments_applied: anchored.len(),
+        comments_part,
+        source_document_hash,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SegmentKind {
+    Equal,
+    Inserted,
+    Deleted,
+    /// Start of the range of the comment at this index of `comments`; the
+    /// segment has no text.
+    CommentStart(usize),
+    /// End of that range.
+    CommentEnd(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    kind: SegmentKind,
+    text: String,
+    /// Revision timestamp for inserted / deleted segments.
+    date: Option<DateTime<Utc>>,
+}
+
+/// Split a block into equal / inserted / deleted segments according to the
+/// supplied deltas, with comment range markers on the token boundaries of the
+/// comments at `block_comments` (indices into `comments`). Adjacent segments
+/// of the same kind and date are merged.
+fn block_segments(
+    block: &Block,
+    deltas: &[&BlockDelta],
+    comments: &[ReviewComment],
+    block_comments: &[usize],
+) -> Vec<Segment> {
+    let tokens: Vec<Token> = if block.tokens.is_empty() {
+        tokenize(&block.canonical_text)
+    } else {
+        block.tokens.clone()
+    };
+    let n = tokens.len();
+
+    // deleted[i] = revision date if token i is removed by some delta.
+    let mut deleted: Vec<Option<DateTime<Utc>>> = vec![None; n];
+    // inserts[i] = text inserted before token i (index n = after the last token).
+    let mut inserts: Vec<Vec<(String, DateTime<Utc>)>> = vec![Vec::new(); n + 1];
+
+    let mut ordered: Vec<&BlockDelta> = deltas.to_vec();
+    ordered.sort_by_key(|d| (d.token_start, d.created_at));
+
+    for d in ordered {
+        let text = d
+            .delta_payload
+            .get("text")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let start = d.token_start.min(n);
+        match d.delta_type {
+            DeltaType::Insert => {
+                if let Some(t) = text {
+                    inserts[start].push((t, d.created_at));
+                }
+            }
+            DeltaType::Delete | DeltaType::Modify => {
+                let end = d.token_end.min(n.saturating_sub(1));
+                if start < n {
+                    for slot in &mut deleted[start..=end.max(start)] {
+                        slot.get_or_insert(d.created_at);
+                    }
+                }
+                if d.delta_type == DeltaType::Modify {
+                    if let Some(t) = text {
+                        inserts[start].push((t, d.created_at));
+                    }
+                }
+            }
+        }
+    }
+
+    // A comment opens before the text inserted ahead of its first token, so
+    // a replacement of the commented range stays inside it, and closes after
+    // its last token. Ranges past the end collapse onto the block end.
+    let mut comment_starts: Vec<Vec<usize>> = vec![Vec::new(); n + 1];
+    let mut comment_ends: Vec<Vec<usize>> = vec![Vec::new(); n + 1];
+    for &index in block_comments {
+        let comment = &comments[index];
+        let start = comment.token_start.min(n);
+        let end = if start < n { comment.token_end.clamp(start, n - 1) } else { n };
+        comment_starts[start].push(index);
+        comment_ends[end].push(index);
+    }
+
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut push = |kind: SegmentKind, text: String, date: Option<DateTime<Utc>>| {
+        if let Some(last) = segments.last_mut() {
+            if last.kind == kind && last.date == date {
+                last.text.push_str(&text);
+                return;
+            }
+        }
+        segments.push(Segment { kind, text, date });
+    };
+
+    for i in 0..=n {
+        for &index in &comment_starts[i] {
+            push(SegmentKind::CommentStart(index), String::new(), None);
+        }
+        for (text, date) in &inserts[i] {
+            push(SegmentKind::Inserted, format!("{text} "), Some(*date));
+        }
+        if i < n {
+            let text = token_text_with_gap(&block.canonical_text, &tokens, i);
+            match deleted[i] {
+                Some(date) => push(SegmentKind::Deleted, text, Some(date)),
+                None => push(SegmentKind::Equal, text, None),
+            }
+        }
+        for &index in &comment_ends[i] {
+            push(SegmentKind::CommentEnd(index), String::new(), None);
+        }
+    }
+
+    segments
+}
+
+/// Source text of token `i` including the whitespace that follows it, taken
+/// from `canonical_text` so the original spacing survives the round trip.
+fn token_text_with_gap(canonical_text: &str, tokens: &[Token], i: usize) -> String {
+    let start = tokens[i].offset;
+    let end = tokens
+        .get(i + 1)
+        .map(|t| t.offset)
+        .unwrap_or(canonical_text.len());
+    match canonical_text.get(start..end) {
+        Some(s) if s.starts_with(tokens[i].text.as_str()) => s.to_string(),
+        _ => format!("{} ", tokens[i].text),
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn render_html(reviewer_id: &str, paragraphs: &[(&Block, Vec<Segment>)]) -> String {
+    let reviewer = escape_xml(reviewer_id);
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>Redline: {reviewer}</title>\n"));
+    out.push_str(
+        "<style>ins{color:#1a7f37;text-decoration:underline}\
+         del{color:#cf222e;text-decoration:line-through}</style>\n",
+    );
+    out.push_str("</head>\n<body>\n");
+
+    for (block, segments) in paragraphs {
+        out.push_str(&format!(
+            "<p data-block-id=\"{}\" data-path=\"{}\">",
+            block.id,
+            escape_xml(&block.structural_path)
+        ));
+        for seg in segments {
+            let text = escape_xml(&seg.text);
+            match seg.kind {
+                SegmentKind::Equal => out.push_str(&text),
+                SegmentKind::Inserted => {
+                    out.push_str(&format!("<ins data-author=\"{reviewer}\">{text}</ins>"))
+                }
+                SegmentKind::Deleted => {
+                    out.push_str(&format!("<del data-author=\"{reviewer}\">{text}</del>"))
+                }
+                SegmentKind::CommentStart(_) | SegmentKind::CommentEnd(_) => {}
+            }
+        }
+        out.push_str("</p>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Render `word/document.xml`, returning it with the `(comment index, w:id)`
+/// of every anchored comment in document order. Comments and revisions share
+/// one id sequence.
+fn render_docx(
+    reviewer_id: &str,
+    paragraphs: &[(&Block, Vec<Segment>)],
+) -> (String, Vec<(usize, usize)>) {
+    let author = escape_xml(reviewer_id);
+    let mut rev_id = 1usize;
+    let mut anchored: Vec<(usize, usize)> = Vec::new();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+    out.push_str(
+        "<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\
+         <w:body>\n",
+    );
+
+    for (_, segments) in paragraphs {
+        out.push_str("<w:p>");
+        for seg in segments {
+            let text = escape_xml(&seg.text);
+            let date = seg
+                .date
+                .map(|d| d.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                .unwrap_or_default();
+            match seg.kind {
+                SegmentKind::Equal => out.push_str(&format!(
+                    "<w:r><w:t xml:space=\"preserve\">{text}</w:t></w:r>"
+                )),
+                SegmentKind::Inserted => {
+                    out.push_str(&format!(
+                        "<w:ins w:id=\"{rev_id}\" w:author=\"{author}\" w:date=\"{date}\">\
+                         <w:r><w:t xml:space=\"preserve\">{text}</w:t></w:r></w:ins>"
+                    ));
+                    rev_id += 1;
+                }
+                SegmentKind::Deleted => {
+                    out.push_str(&format!(
+                        "<w:del w:id=\"{rev_id}\" w:author=\"{author}\" w:date=\"{date}\">\
+                         <w:r><w:delText xml:space=\"preserve\">{text}</w:delText></w:r></w:del>"
+                    ));
+                    rev_id += 1;
+                }
+                SegmentKind::CommentStart(index) => {
+                    out.push_str(&format!("<w:commentRangeStart w:id=\"{rev_id}\"/>"));
+                    anchored.push((index, rev_id));
+                    rev_id += 1;
+                }
+                SegmentKind::CommentEnd(index) => {
+                    let (_, id) = anchored
+                        .iter()
+                        .find(|(i, _)| *i == index)
+                        .copied()
+                        .expect("comment ranges close after they open");
+                    out.push_str(&format!(
+                        "<w:commentRangeEnd w:id=\"{id}\"/>\
+                         <w:r><w:commentReference w:id=\"{id}\"/></w:r>"
+                    ));
+                }
+            }
+        }
+        out.push_str("</w:p>\n");
+    }
+
+    out.push_str("</w:body></w:document>\n");
+    (out, anchored)
+}
+
+/// Render the `word/comments.xml` part for the `anchored` comments.
+fn render_comments(comments: &[ReviewComment], anchored: &[(usize, usize)]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+    out.push_str(
+        "<w:comments xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\n",
+    );
+    for &(index, id) in anchored {
+        let comment = &comments[index];
+        out.push_str(&format!(
+            "<w:comment w:id=\"{id}\" w:author=\"{}\" w:date=\"{}\">",
+            escape_xml(&comment.author),
+            comment.created_at.format("%Y-%m-%dT%H:%M:%SZ"),
+        ));
+        for line in comment.text.lines() {
+            out.push_str(&format!(
+                "<w:p><w:r><w:t xml:space=\"preserve\">{}</w:t></w:r></w:p>",
+                escape_xml(line)
+            ));
+        }
+        if comment.text.is_empty() {
+            out.push_str("<w:p/>");
+        }
+        out.push_str("</w:comment>\n");
+    }
+    out.push_str("</w:comments>\n");
+    out
+}
+
+const RELATIONSHIPS_NS: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+const OFFICE_RELATIONSHIPS: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// Zip `document` and, if any, `comments` into the smallest package Word
+/// opens: the two parts, their content types and the relationships from the
+/// package to the document and from the document to the comments.
+fn package_docx(document: &str, comments: Option<&str>) -> Result<Vec<u8>, RtError> {
+    let comments_type = if comments.is_some() {
+        "<Override PartName=\"/word/comments.xml\" ContentType=\"application/\
+         vnd.openxmlformats-officedocument.wordprocessingml.comments+xml\"/>"
+    } else {
+        ""
+    };
+    let content_types = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+         <Default Extension=\"rels\" \
+         ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+         <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+         <Override PartName=\"/word/document.xml\" ContentType=\"application/\
+         vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>\
+         {comments_type}</Types>\n"
+    );
+    let package_rels = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Relationships xmlns=\"{RELATIONSHIPS_NS}\">\
+         <Relationship Id=\"rId1\" Type=\"{OFFICE_RELATIONSHIPS}/officeDocument\" \
+         Target=\"word/document.xml\"/></Relationships>\n"
+    );
+    let comments_rel = if comments.is_some() {
+        format!(
+            "<Relationship Id=\"rId1\" Type=\"{OFFICE_RELATIONSHIPS}/comments\" \
+             Target=\"comments.xml\"/>"
+        )
+    } else {
+        String::new()
+    };
+    let document_rels = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Relationships xmlns=\"{RELATIONSHIPS_NS}\">{comments_rel}</Relationships>\n"
+    );
+
+    let mut parts = vec![
+        ("[Content_Types].xml", content_types.as_str()),
+        ("_rels/.rels", package_rels.as_str()),
+        ("word/document.xml", document),
+        ("word/_rels/document.xml.rels", document_rels.as_str()),
+    ];
+    if let Some(comments) = comments {
+        parts.push(("word/comments.xml", comments));
+    }
+
+    // A fixed timestamp keeps the package, and so the artifact hash, the
+    // same for the same redline.
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default());
+    let zip_error = |e: zip::result::ZipError| {
+        RtError::Internal(format!("failed to package the docx redline: {e}"))
+    };
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, part) in parts {
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(part.as_bytes())?;
+    }
+    Ok(zip.finish().map_err(zip_error)?.into_inner())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_core::vfs::StdVfs;
+    use rt_core::BlockType;
+    use serde_json::json;
+
+    fn make_block(doc_id: Uuid, path: &str, text: &str, pos: i32) -> Block {
+        Block::new(BlockType::Clause, path, text, text, None, doc_id, pos)
+    }
+
+    fn setup() -> (Vec<Block>, ReviewLayer) {
+        let doc = Uuid::new_v4();
+        let blocks = vec![
+            make_block(doc, "1.1", "the borrower shall repay the loan", 0),
+            make_block(doc, "1.2", "interest accrues monthly", 1),
+        ];
+        let layer = ReviewLayer::new(Uuid::new_v4(), "bob", doc);
+        (blocks, layer)
+    }
+
+    #[test]
+    fn html_renders_only_own_layer() {
+        let (blocks, layer) = setup();
+        let other = ReviewLayer::new(layer.workflow_id, "carol", layer.document_id);
+
+        let deltas = vec![
+            // bob: "borrower" (token 1) → "lender"
+            BlockDelta::new(layer.id, "bob", blocks[0].id, DeltaType::Modify, 1, 1, json!({"text": "lender"})),
+            // carol: delete "monthly"
+            BlockDelta::new(other.id, "carol", blocks[1].id, DeltaType::Delete, 2, 2, json!({})),
+        ];
+
+        let redline = export_reviewer_redline(&blocks, &layer, &deltas, &[], ExportFormat::Html);
+        assert_eq!(redline.deltas_applied, 1);
+        assert!(redline.content.contains("<del data-author=\"bob\">borrower </del>"));
+        assert!(redline.content.contains("<ins data-author=\"bob\">lender </ins>"));
+        assert!(!redline.content.contains("<del data-author=\"carol\""));
+        assert!(redline.content.contains("interest accrues monthly"));
+    }
+
+    #[test]
+    fn docx_emits_tracked_revisions() {
+        let (blocks, layer) = setup();
+        let deltas = vec![
+            BlockDelta::new(layer.id, "bob", blocks[1].id, DeltaType::Insert, 3, 3, json!({"text": "in arrears"})),
+            BlockDelta::new(layer.id, "bob", blocks[0].id, DeltaType::Delete, 4, 5, json!({})),
+        ];
+
+        let redline = export_reviewer_redline(&blocks, &layer, &deltas, &[], ExportFormat::Docx);
+        assert_eq!(redline.deltas_applied, 2);
+        assert!(redline.content.contains("<w:del w:id=\"1\" w:author=\"bob\""));
+        assert!(redline.content.contains("<w:ins w:id=\"2\" w:author=\"bob\""));
+        assert!(redline.content.contains("<w:delText xml:space=\"preserve\">the loan</w:delText>"));
+        assert!(redline.content.contains("<w:t xml:space=\"preserve\">in arrears </w:t>"));
+    }
+
+    #[test]
+    fn docx_anchors_comments_on_token_boundaries() {
+        let (blocks, layer) = setup();
+        let deltas = vec![BlockDelta::new(
+            layer.id, "bob", blocks[0].id, DeltaType::Modify, 1, 1, json!({"text": "lender"}),
+        )];
+        let comments = vec![
+            // "borrower shall" in 1.1, by another reviewer.
+            ReviewComment::new(blocks[0].id, "carol", "Check the\ndefinition", 1, 2),
+            ReviewComment::new(blocks[1].id, "bob", "Why <monthly>?", 2, 9),
+            ReviewComment::new(Uuid::new_v4(), "bob", "elsewhere", 0, 0),
+        ];
+
+        let redline = export_reviewer_redline(&blocks, &layer, &deltas, &comments, ExportFormat::Docx);
+        assert_eq!(redline.comments_applied, 2);
+        // The range opens before the replacement and closes after "shall".
+        assert!(redline.content.contains(
+            "<w:commentRangeStart w:id=\"1\"/><w:ins w:id=\"2\" w:author=\"bob\""
+        ));
+        assert!(redline.content.contains(
+            "<w:t xml:space=\"preserve\">shall </w:t></w:r>\
+             <w:commentRangeEnd w:id=\"1\"/><w:r><w:commentReference w:id=\"1\"/></w:r>"
+        ));
+        // A range past the end of the block is clamped to its last token.
+        assert!(redline.content.contains(
+            "<w:commentRangeStart w:id=\"4\"/><w:r><w:t xml:space=\"preserve\">monthly</w:t></w:r>\
+             <w:commentRangeEnd w:id=\"4\"/>"
+        ));
+
+        let part = redline.comments_part.unwrap();
+        assert!(part.contains("<w:comment w:id=\"1\" w:author=\"carol\""));
+        assert!(part.contains("<w:p><w:r><w:t xml:space=\"preserve\">definition</w:t></w:r></w:p>"));
+        assert!(part.contains("<w:comment w:id=\"4\" w:author=\"bob\""));
+        assert!(part.contains("Why &lt;monthly&gt;?"));
+        assert!(!part.contains("elsewhere"));
+
+        let html = export_reviewer_redline(&blocks, &layer, &deltas, &comments, ExportFormat::Html);
+        assert_eq!(html.comments_applied, 0);
+        assert!(html.comments_part.is_none());
+    }
+
+    #[test]
+    fn comments_part_is_written_through_the_vfs() {
+        let (blocks, layer) = setup();
+        let comments = vec![ReviewComment::new(blocks[0].id, "bob", "Why?", 0, 1)];
+        let redline = export_reviewer_redline(&blocks, &layer, &[], &comments, ExportFormat::Docx);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("comments.xml");
+        let path = path.to_str().unwrap();
+
+        redline.write_comments(&StdVfs, None).unwrap();
+        redline.write_comments(&StdVfs, Some(path)).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), redline.comments_part.unwrap());
+
+        let html = export_reviewer_redline(&blocks, &layer, &[], &comments, ExportFormat::Html);
+        html.write_comments(&StdVfs, None).unwrap();
+    }
+
+    #[test]
+    fn docx_file_is_a_package_word_can_open() {
+        let (blocks, layer) = setup();
+        let read = |redline: &ReviewerRedline| {
+            let file = redline.file_content().unwrap();
+            let mut archive = zip::ZipArchive::new(Cursor::new(file)).unwrap();
+            let mut parts = std::collections::BTreeMap::new();
+            for i in 0..archive.len() {
+                let mut part = archive.by_index(i).unwrap();
+                let mut text = String::new();
+                std::io::Read::read_to_string(&mut part, &mut text).unwrap();
+                parts.insert(part.name().to_string(), text);
+            }
+            parts
+        };
+
+        let plain = export_reviewer_redline(&blocks, &layer, &[], &[], ExportFormat::Docx);
+        let parts = read(&plain);
+        assert_eq!(
+            parts.keys().map(String::as_str).collect::<Vec<_>>(),
+            [
+                "[Content_Types].xml",
+                "_rels/.rels",
+                "word/_rels/document.xml.rels",
+                "word/document.xml"
+            ]
+        );
+        assert_eq!(parts["word/document.xml"], plain.content);
+        assert!(parts["_rels/.rels"].contains("Target=\"word/document.xml\""));
+        assert!(!parts["[Content_Types].xml"].contains("comments"));
+        assert_eq!(plain.file_content().unwrap(), plain.file_content().unwrap());
+
+        let comments = vec![ReviewComment::new(blocks[0].id, "bob", "Why?", 0, 1)];
+        let commented =
+            export_reviewer_redline(&blocks, &layer, &[], &comments, ExportFormat::Docx);
+        let parts = read(&commented);
+        assert_eq!(Some(&parts["word/comments.xml"]), commented.comments_part.as_ref());
+        assert!(parts["word/_rels/document.xml.rels"].contains("Target=\"comments.xml\""));
+        assert!(parts["[Content_Types].xml"].contains("/word/comments.xml"));
+
+        let html = export_reviewer_redline(&blocks, &layer, &[], &[], ExportFormat::Html);
+        assert_eq!(html.file_content().unwrap(), html.content.as_bytes());
+    }
+
+    #[test]
+    fn content_is_escaped() {
+        let doc = Uuid::new_v4();
+        let blocks = vec![make_block(doc, "1", "A & B", 0)];
+        let layer = ReviewLayer::new(Uuid::new_v4(), "o'neil", doc);
+        let redline = export_reviewer_redline(&blocks, &layer, &[], &[], ExportFormat::Html);
+        assert!(redline.content.contains("A &amp; B"));
+        assert!(redline.content.contains("o&apos;neil"));
+    }
+
+    #[test]
+    fn to_artifact_uses_format_and_content_hash() {
+        let (blocks, layer) = setup();
+        let redline = export_reviewer_redline(&blocks, &layer, &[], &[], ExportFormat::Docx);
+        let wf = Uuid::new_v4();
+        let artifact = redline.to_artifact(wf, "/tmp/bob.docx").unwrap();
+        assert_eq!(artifact.workflow_id, wf);
+        assert_eq!(artifact.artifact_type, ArtifactType::ReviewerRedlineDocx);
+        let file = redline.file_content().unwrap();
+        assert_eq!(artifact.content_hash, rt_core::sha256_hex_bytes(&file));
+        assert_eq!(
+            artifact.source_document_hash.as_deref(),
+            Some(redline.source_document_hash.as_str())
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use chrono::{DateTime, Utc};
+use rt_core::RtError;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -55,6 +56,28 @@ pub enum DeltaType {
     Modify,
 }
 
+impl DeltaType {
+    /// Value stored in `block_deltas.delta_type`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeltaType::Insert => "insert",
+            DeltaType::Delete => "delete",
+            DeltaType::Modify => "modify",
+        }
+    }
+
+    /// Parse a `block_deltas.delta_type` value.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, RtError> {
+        match s {
+            "insert" => Ok(DeltaType::Insert),
+            "delete" => Ok(DeltaType::Delete),
+            "modify" => Ok(DeltaType::Modify),
+            other => Err(RtError::InvalidInput(format!("unknown delta type: {other}"))),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // BlockDelta
 // ---------------------------------------------------------------------------
@@ -116,6 +139,68 @@ impl BlockDelta {
     }
 }
 
+// ---------------------------------------------------------------------------
+// ReviewComment
+// ---------------------------------------------------------------------------
+
+/// A reviewer's comment on a range of a base block's tokens.
+///
+/// The token range follows [`BlockDelta`]: both ends are inclusive token
+/// indices into the block's token stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewComment {
+    /// Stable unique identifier for this comment (UUIDv4).
+    pub id: Uuid,
+    /// The block the comment is anchored to.
+    pub block_id: Uuid,
+    /// Who wrote the comment.
+    pub author: String,
+    /// Comment body; line breaks separate paragraphs.
+    pub text: String,
+    /// First commented token (inclusive).
+    pub token_start: usize,
+    /// Last commented token (inclusive).
+    pub token_end: usize,
+    /// UTC timestamp when the comment was made.
+    pub created_at: DateTime<Utc>,
+}
+
+impl ReviewComment {
+    /// Construct a new `ReviewComment` with a freshly generated `id` and
+    /// `created_at` set to now.
+    pub fn new(
+        block_id: Uuid,
+        author: impl Into<String>,
+        text: impl Into<String>,
+        token_start: usize,
+        token_end: usize,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            block_id,
+            author: author.into(),
+            text: text.into(),
+            token_start,
+            token_end,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// LayerDeltas
+// ---------------------------------------------------------------------------
+
+/// A [`ReviewLayer`] together with the [`BlockDelta`]s recorded in it; the
+/// per-reviewer input to [`crate::MergeEngine::merge_layers`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerDeltas {
+    pub layer: ReviewLayer,
+    /// Deltas against the base document's blocks; every delta's
+    /// `review_layer_id` must equal `layer.id`.
+    pub deltas: Vec<BlockDelta>,
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -0,0 +1,453 @@
+//! Persistence for review layers in the `review_layers` and `block_deltas`
+//! tables.
+//!
+//! A reviewer's layer is created once with [`ReviewLayerStore::create_layer`]
+//! and grows one edit at a time through [`ReviewLayerStore::append_delta`].
+//! [`ReviewLayerStore::load_layer`] reads a layer back as [`LayerDeltas`],
+//! the input [`crate::MergeEngine::merge_layers`] takes.
+//!
+//! Layers belong to a tenant through their document: a store only sees the
+//! layers, and the deltas, of its tenant's documents. Rows of `block_deltas`
+//! recorded without a layer (review analytics only) are not listed.
+
+use chrono::{DateTime, Utc};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, OptionalExtension, TransactionBehavior};
+use uuid::Uuid;
+
+use rt_core::db::DbPool;
+use rt_core::review_activity::activity_timestamp;
+use rt_core::tenant::{ensure_document, ensure_workflow, TenantContext};
+use rt_core::RtError;
+
+use crate::layer::{BlockDelta, DeltaType, LayerDeltas, ReviewLayer};
+
+type Result<T> = std::result::Result<T, RtError>;
+
+/// Columns read by [`row_to_delta`], in order.
+const DELTA_COLUMNS: &str = "d.id, d.review_layer_id, d.reviewer_id, d.block_id, d.delta_type, \
+     d.token_start, d.token_end, d.delta_payload, d.created_at";
+
+// ---------------------------------------------------------------------------
+// ReviewLayerStore trait
+// ---------------------------------------------------------------------------
+
+/// Persistence interface for review layers and their deltas.
+pub trait ReviewLayerStore: Send + Sync {
+    /// Record `layer`. Its document must be stored, and its workflow must
+    /// be the store's tenant's and review that document; a layer id already
+    /// in use is rejected.
+    fn create_layer(&self, layer: &ReviewLayer) -> Result<()>;
+    fn get_layer(&self, layer_id: &Uuid) -> Result<ReviewLayer>;
+    /// Every layer recorded on `document_id`, oldest first.
+    fn list_layers(&self, document_id: &Uuid) -> Result<Vec<ReviewLayer>>;
+    /// Append `delta` to its layer, which must be stored. The delta must
+    /// change a block of the layer's document; a delta id already in use is
+    /// rejected.
+    fn append_delta(&self, delta: &BlockDelta) -> Result<()>;
+    /// The deltas of `layer_id`, in the order they were recorded.
+    fn list_deltas_by_layer(&self, layer_id: &Uuid) -> Result<Vec<BlockDelta>>;
+    /// Every layer's deltas on `block_id`, in the order they were recorded.
+    fn list_deltas_by_block(&self, block_id: &Uuid) -> Result<Vec<BlockDelta>>;
+    /// A layer together with its deltas.
+    fn load_layer(&self, layer_id: &Uuid) -> Result<LayerDeltas>;
+    /// Delete a layer and its deltas, returning the number of deltas
+    /// deleted.
+    fn delete_layer(&self, layer_id: &Uuid) -> Result<usize>;
+}
+
+// ---------------------------------------------------------------------------
+// SqliteReviewLayerStore
+// ---------------------------------------------------------------------------
+
+pub struct SqliteReviewLayerStore {
+    pool: DbPool,
+    tenant: TenantContext,
+}
+
+impl SqliteReviewLayerStore {
+    /// A store acting for the default tenant.
+    pub fn new(pool: DbPool) -> Self {
+        Self::with_tenant(pool, TenantContext::default())
+    }
+
+    pub fn with_tenant(pool: DbPool, tenant: TenantContext) -> Self {
+        Self { pool, tenant }
+    }
+
+    pub fn tenant(&self) -> &TenantContext {
+        &self.tenant
+    }
+
+    /// This tenant's layer `layer_id`, or [`RtError::NotFound`].
+    fn find_layer(&self, conn: &rusqlite::Connection, layer_id: &Uuid) -> Result<ReviewLayer> {
+        let row = conn
+            .query_row(
+                "SELECT l.id, l.workflow_id, l.reviewer_id, l.document_id, l.created_at
+                   FROM review_layers l
+                   JOIN documents doc ON doc.id = l.document_id
+                  WHERE l.id = ?1 AND doc.tenant_id = ?2",
+                params![layer_id.to_string(), self.tenant.id()],
+                layer_row,
+            )
+            .optional()?;
+        match row {
+            Some(row) => row_to_layer(row),
+            None => Err(RtError::NotFound(format!("review layer {layer_id}"))),
+        }
+    }
+
+    fn query_deltas(&self, filter: &str, id: &Uuid) -> Result<Vec<BlockDelta>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {DELTA_COLUMNS}
+               FROM block_deltas d
+               JOIN review_layers l ON l.id = d.review_layer_id
+               JOIN documents doc ON doc.id = l.document_id
+              WHERE {filter} = ?1 AND doc.tenant_id = ?2
+              ORDER BY d.created_at ASC, d.rowid ASC"
+        ))?;
+        let rows = stmt.query_map(params![id.to_string(), self.tenant.id()], delta_row)?;
+        rows.map(|row| row_to_delta(row?)).collect()
+    }
+
+    fn conn(&self) -> Result<r2d2::PooledConnection<SqliteConnectionManager>> {
+        self.pool
+            .get()
+            .map_err(|e| RtError::Internal(e.to_string()))
+    }
+}
+
+impl ReviewLayerStore for SqliteReviewLayerStore {
+    fn create_layer(&self, layer: &ReviewLayer) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        ensure_document(&tx, &self.tenant, &layer.document_id)?;
+        ensure_workflow(&tx, &self.tenant, &layer.workflow_id, &layer.document_id)?;
+        let taken = tx
+            .query_row(
+                "SELECT 1 FROM review_layers WHERE id = ?1",
+                params![layer.id.to_string()],
+                |_| Ok(()),
+            )
+            .optional()?;
+        if taken.is_some() {
+            return Err(RtError::InvalidInput(format!(
+                "review layer {} already exists",
+                layer.id
+            )));
+        }
+        tx.execute(
+            "INSERT INTO review_layers (id, workflow_id, reviewer_id, document_id, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                layer.id.to_string(),
+                layer.workflow_id.to_string(),
+                layer.reviewer_id,
+                layer.document_id.to_string(),
+                activity_timestamp(&layer.created_at),
+            ],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn get_layer(&self, layer_id: &Uuid) -> Result<ReviewLayer> {
+        let conn = self.conn()?;
+        self.find_layer(&conn, layer_id)
+    }
+
+    fn list_layers(&self, document_id: &Uuid) -> Result<Vec<ReviewLayer>> {
+        let conn = self.conn()?;
+        ensure_document(&conn, &self.tenant, document_id)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, workflow_id, reviewer_id, document_id, created_at
+               FROM review_layers
+              WHERE document_id = ?1
+              ORDER BY created_at ASC, rowid ASC",
+        )?;
+        let rows = stmt.query_map(params![document_id.to_string()], layer_row)?;
+        rows.map(|row| row_to_layer(row?)).collect()
+    }
+
+    fn append_delta(&self, delta: &BlockDelta) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let layer = self.find_layer(&tx, &delta.review_layer_id)?;
+        let on_document = tx
+            .query_row(
+                "SELECT 1 FROM blocks WHERE id = ?1 AND document_id = ?2",
+                params![delta.block_id.to_string(), layer.document_id.to_string()],
+                |_| Ok(()),
+            )
+            .optional()?;
+        if on_document.is_none() {
+            return Err(RtError::NotFound(format!("block {}", delta.block_id)));
+        }
+        let taken = tx
+            .query_row(
+                "SELECT 1 FROM block_deltas WHERE id = ?1",
+                params![delta.id.to_string()],
+                |_| Ok(()),
+            )
+            .optional()?;
+        if taken.is_some() {
+            return Err(RtError::InvalidInput(format!("delta {} already exists", delta.id)));
+        }
+        tx.execute(
+            "INSERT INTO block_deltas
+                (id, review_layer_id, reviewer_id, block_id, delta_type,
+                 token_start, token_end, delta_payload, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                delta.id.to_string(),
+                layer.id.to_string(),
+                delta.reviewer_id,
+                delta.block_id.to_string(),
+                delta.delta_type.as_str(),
+                delta.token_start as i64,
+                delta.token_end as i64,
+                serde_json::to_string(&delta.delta_payload)?,
+                activity_timestamp(&delta.created_at),
+            ],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn list_deltas_by_layer(&self, layer_id: &Uuid) -> Result<Vec<BlockDelta>> {
+        self.get_layer(layer_id)?;
+        self.query_deltas("d.review_layer_id", layer_id)
+    }
+
+    fn list_deltas_by_block(&self, block_id: &Uuid) -> Result<Vec<BlockDelta>> {
+        self.query_deltas("d.block_id", block_id)
+    }
+
+    fn load_layer(&self, layer_id: &Uuid) -> Result<LayerDeltas> {
+        let layer = self.get_layer(layer_id)?;
+        let deltas = self.query_deltas("d.review_layer_id", layer_id)?;
+        Ok(LayerDeltas { layer, deltas })
+    }
+
+    fn delete_layer(&self, layer_id: &Uuid) -> Result<usize> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        self.find_layer(&tx, layer_id)?;
+        // `block_deltas.review_layer_id` has no foreign key, so the deltas
+        // do not cascade.
+        let deltas = tx.execute(
+            "DELETE FROM block_deltas WHERE review_layer_id = ?1",
+            params![layer_id.to_string()],
+        )?;
+        tx.execute("DELETE FROM review_layers WHERE id = ?1", params![layer_id.to_string()])?;
+        tx.commit()?;
+        Ok(deltas)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+type LayerRow = (String, Option<String>, Option<String>, String, String);
+
+fn layer_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<LayerRow> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+}
+
+/// A layer recorded without a workflow reads back with the nil workflow id.
+fn row_to_layer(r: LayerRow) -> Result<ReviewLayer> {
+    Ok(ReviewLayer {
+        id: parse_uuid(&r.0)?,
+        workflow_id: r.1.as_deref().map(parse_uuid).transpose()?.unwrap_or_default(),
+        reviewer_id: r.2.unwrap_or_default(),
+        document_id: parse_uuid(&r.3)?,
+        created_at: parse_time(&r.4)?,
+    })
+}
+
+type DeltaRow = (
+    String,
+    String,
+    Option<String>,
+    String,
+    String,
+    Option<i64>,
+    Option<i64>,
+    String,
+    String,
+);
+
+fn delta_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DeltaRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+        row.get(8)?,
+    ))
+}
+
+fn row_to_delta(r: DeltaRow) -> Result<BlockDelta> {
+    Ok(BlockDelta {
+        id: parse_uuid(&r.0)?,
+        review_layer_id: parse_uuid(&r.1)?,
+        reviewer_id: r.2.unwrap_or_default(),
+        block_id: parse_uuid(&r.3)?,
+        delta_type: DeltaType::from_str(&r.4)?,
+        token_start: r.5.unwrap_or_default() as usize,
+        token_end: r.6.unwrap_or_default() as usize,
+        delta_payload: serde_json::from_str(&r.7)?,
+        created_at: parse_time(&r.8)?,
+    })
+}
+
+fn parse_uuid(s: &str) -> Result<Uuid> {
+    Uuid::parse_str(s).map_err(|e| RtError::InvalidInput(e.to_string()))
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>> {
+    s.parse::<DateTime<Utc>>().map_err(|e| RtError::InvalidInput(e.to_string()))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_core::db::{create_pool, BlockStore, SqliteBlockStore};
+    use rt_core::schema::SCHEMA_VERSION;
+    use rt_core::{Block, BlockType, Document, DocumentType};
+    use tempfile::TempDir;
+
+    use crate::merge::MergeEngine;
+
+    /// A file-backed pool with one stored document of two blocks, reviewed
+    /// by one workflow.
+    fn setup() -> (TempDir, DbPool, Document, Vec<Block>, Uuid) {
+        let dir = TempDir::new().unwrap();
+        let pool = create_pool(dir.path().join("layers.db").to_str().unwrap()).unwrap();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let doc = Document {
+            id: Uuid::new_v4(),
+            name: "base".into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: SCHEMA_VERSION.into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        };
+        blocks.insert_document(&doc).unwrap();
+        let stored = vec![
+            Block::new(BlockType::Clause, "1.", "pay the fee", "pay the fee", None, doc.id, 0),
+            Block::new(BlockType::Clause, "2.", "keep records", "keep records", None, doc.id, 1),
+        ];
+        blocks.insert_blocks(&stored).unwrap();
+        let workflow_id = Uuid::new_v4();
+        pool.get()
+            .unwrap()
+            .execute(
+                "INSERT INTO workflows (id, document_id, state, created_at, updated_at)
+                 VALUES (?1, ?2, 'DRAFT', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z')",
+                params![workflow_id.to_string(), doc.id.to_string()],
+            )
+            .unwrap();
+        (dir, pool, doc, stored, workflow_id)
+    }
+
+    fn modify(layer: &ReviewLayer, block: &Block, text: &str) -> BlockDelta {
+        BlockDelta::new(
+            layer.id,
+            layer.reviewer_id.clone(),
+            block.id,
+            DeltaType::Modify,
+            0,
+            0,
+            serde_json::json!({ "text": text }),
+        )
+    }
+
+    #[test]
+    fn layers_and_deltas_round_trip_and_feed_the_merge() {
+        let (_dir, pool, doc, blocks, workflow_id) = setup();
+        let store = SqliteReviewLayerStore::new(pool.clone());
+        let alice = ReviewLayer::new(workflow_id, "alice", doc.id);
+        let bob = ReviewLayer::new(workflow_id, "bob", doc.id);
+        store.create_layer(&alice).unwrap();
+        store.create_layer(&bob).unwrap();
+        assert!(matches!(store.create_layer(&alice), Err(RtError::InvalidInput(_))));
+
+        let first = modify(&alice, &blocks[0], "settle");
+        store.append_delta(&first).unwrap();
+        store.append_delta(&modify(&alice, &blocks[1], "retain")).unwrap();
+        store.append_delta(&modify(&bob, &blocks[0], "remit")).unwrap();
+        assert!(matches!(store.append_delta(&first), Err(RtError::InvalidInput(_))));
+
+        let layers = store.list_layers(&doc.id).unwrap();
+        assert_eq!(layers.iter().map(|l| l.id).collect::<Vec<_>>(), [alice.id, bob.id]);
+        let by_layer = store.list_deltas_by_layer(&alice.id).unwrap();
+        assert_eq!(by_layer.len(), 2);
+        assert_eq!(by_layer[0].id, first.id);
+        assert_eq!(by_layer[0].delta_payload, serde_json::json!({ "text": "settle" }));
+        assert_eq!(store.list_deltas_by_block(&blocks[0].id).unwrap().len(), 2);
+
+        let inputs: Vec<LayerDeltas> =
+            layers.iter().map(|l| store.load_layer(&l.id).unwrap()).collect();
+        let merged = MergeEngine::new().merge_layers(doc.id, &blocks, &inputs).unwrap();
+        assert_eq!(merged.layer_ids, [alice.id, bob.id]);
+        assert_eq!(merged.blocks.len(), 1);
+        assert_eq!(merged.blocks[0].block_id, blocks[0].id);
+
+        assert_eq!(store.delete_layer(&alice.id).unwrap(), 2);
+        assert!(matches!(store.get_layer(&alice.id), Err(RtError::NotFound(_))));
+        assert_eq!(store.list_deltas_by_block(&blocks[0].id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn deltas_must_target_the_layers_document() {
+        let (_dir, pool, doc, _, workflow_id) = setup();
+        let store = SqliteReviewLayerStore::new(pool.clone());
+        let layer = ReviewLayer::new(workflow_id, "alice", doc.id);
+
+        let unstored = Block::new(BlockType::Clause, "9.", "x", "x", None, doc.id, 9);
+        let delta = modify(&layer, &unstored, "y");
+        // First the layer, then the block is missing.
+        assert!(matches!(store.append_delta(&delta), Err(RtError::NotFound(_))));
+        store.create_layer(&layer).unwrap();
+        assert!(matches!(store.append_delta(&delta), Err(RtError::NotFound(_))));
+
+        let other_tenant = SqliteReviewLayerStore::with_tenant(pool, TenantContext::new("acme").unwrap());
+        assert!(matches!(other_tenant.get_layer(&layer.id), Err(RtError::NotFound(_))));
+    }
+
+    #[test]
+    fn layers_must_belong_to_a_workflow_of_the_tenant() {
+        let (_dir, pool, doc, _, workflow_id) = setup();
+        let store = SqliteReviewLayerStore::new(pool.clone());
+        let unknown = ReviewLayer::new(Uuid::new_v4(), "alice", doc.id);
+        assert!(matches!(store.create_layer(&unknown), Err(RtError::NotFound(_))));
+
+        // Another tenant's workflow, on another tenant's document.
+        let acme = TenantContext::new("acme").unwrap();
+        let acme_doc = Document {
+            id: Uuid::new_v4(),
+            ..doc.clone()
+        };
+        SqliteBlockStore::with_tenant(pool.clone(), acme.clone())
+            .insert_document(&acme_doc)
+            .unwrap();
+        let acme_store = SqliteReviewLayerStore::with_tenant(pool, acme);
+        let foreign = ReviewLayer::new(workflow_id, "mallory", acme_doc.id);
+        assert!(matches!(acme_store.create_layer(&foreign), Err(RtError::NotFound(_))));
+    }
+}
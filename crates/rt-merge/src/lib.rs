@@ -1,8 +1,28 @@
 pub mod layer;
+pub mod layer_store;
 pub mod conflict;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "export")]
+pub mod markers;
 pub mod merge;
 pub mod resolution;
+pub mod run;
+pub mod store;
+pub mod suggest;
 
-pub use merge::{MergeEngine, MergeResult};
+pub use merge::{
+    BlockLayerConflicts, LayerConflict, LayerMergeResult, MergeConfig, MergeEngine, MergeResult,
+};
 pub use conflict::{MergeConflict, ConflictType, ConflictResolution};
-pub use layer::{ReviewLayer, BlockDelta, DeltaType};
+pub use layer::{ReviewLayer, BlockDelta, DeltaType, LayerDeltas, ReviewComment};
+pub use layer_store::{ReviewLayerStore, SqliteReviewLayerStore};
+pub use run::{resume_merge, run_merge, run_merge_cancellable, MergeRun, MergeRunState};
+pub use store::{MergeStore, SqliteMergeStore};
+pub use suggest::{suggest_resolutions, ResolutionSuggestion, SuggestionRule};
+#[cfg(feature = "export")]
+pub use export::{export_reviewer_redline, ExportFormat, ReviewerRedline};
+#[cfg(feature = "export")]
+pub use markers::{
+    apply_marker_resolutions, export_conflict_markers, import_conflict_markers, MarkerResolution,
+};
@@ -0,0 +1,407 @@
+//! Three-way conflict-marker export and re-import.
+//!
+//! [`export_conflict_markers`] renders the base document as flat
+//! text/Markdown, one paragraph per block, wrapping every block with pending
+//! conflicts in Git-style markers:
+//!
+//! ```text
+//! <!-- rtflow:block 6f1c… conflicts=0b7e…,91d2… -->
+//! <<<<<<< base
+//! The Borrower shall repay the Loan.
+//! =======
+//! The Lender shall repay the Loan.
+//! >>>>>>> incoming
+//! ```
+//!
+//! Each block is preceded by an HTML comment carrying its id (and the ids of
+//! its pending conflicts), which Markdown renderers hide. The comments are
+//! what [`import_conflict_markers`] uses to map an edited file back to
+//! conflicts, so they must be left in place; everything between one comment
+//! and the next is that block's text.
+//!
+//! A block whose markers have been removed is treated as resolved with the
+//! remaining text. Text identical to one side is reported as
+//! [`ConflictResolution::AcceptedBase`] / [`ConflictResolution::AcceptedIncoming`];
+//! anything else as [`ConflictResolution::Manual`]. Blocks that still
+//! contain markers stay pending.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_compare::align::{align_blocks, BlockAlignment};
+use rt_core::{Block, RtError};
+
+use crate::conflict::{ConflictResolution, MergeConflict};
+use crate::merge::{MergeEngine, MergeResult};
+
+const BLOCK_PREFIX: &str = "<!-- rtflow:block ";
+const BLOCK_SUFFIX: &str = " -->";
+const CONFLICTS_ATTR: &str = "conflicts=";
+const MARKER_BASE: &str = "<<<<<<< base";
+const MARKER_SEPARATOR: &str = "=======";
+const MARKER_INCOMING: &str = ">>>>>>> incoming";
+
+// ---------------------------------------------------------------------------
+// Public types
+// ---------------------------------------------------------------------------
+
+/// A conflict resolution read back from an edited marker file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MarkerResolution {
+    /// The [`MergeConflict::id`] being resolved.
+    pub conflict_id: Uuid,
+    /// Base block the conflict belongs to.
+    pub block_id: Uuid,
+    /// Resolution inferred from the edited text.
+    pub resolution: ConflictResolution,
+    /// The block text chosen by the editor.
+    pub text: String,
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Render `base_blocks` as marker text, wrapping blocks with pending
+/// conflicts from `result` in `<<<<<<< base` / `=======` / `>>>>>>> incoming`.
+///
+/// `incoming_blocks` supplies the incoming side of each conflicted block;
+/// they are aligned against the base exactly as [`MergeEngine::merge`] does.
+pub fn export_conflict_markers(
+    base_blocks: &[Block],
+    incoming_blocks: &[Block],
+    result: &MergeResult,
+) -> String {
+    let mut pending: HashMap<Uuid, Vec<&MergeConflict>> = HashMap::new();
+    for conflict in result.conflicts.iter().filter(|c| !c.is_resolved()) {
+        pending.entry(conflict.block_id).or_default().push(conflict);
+    }
+
+    let counterparts: HashMap<usize, usize> = align_blocks(base_blocks, incoming_blocks)
+        .into_iter()
+        .filter_map(|a| match a {
+            BlockAlignment::Matched { left, right, .. }
+            | BlockAlignment::Moved { left, right, .. } => Some((left, right)),
+            _ => None,
+        })
+        .collect();
+
+    let mut out = String::new();
+    for (idx, block) in base_blocks.iter().enumerate() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(BLOCK_PREFIX);
+        out.push_str(&block.id.to_string());
+
+        let Some(conflicts) = pending.get(&block.id) else {
+            out.push_str(BLOCK_SUFFIX);
+            out.push('\n');
+            push_text(&mut out, &block.display_text);
+            continue;
+        };
+
+        let ids: Vec<String> = conflicts.iter().map(|c| c.id.to_string()).collect();
+        out.push(' ');
+        out.push_str(CONFLICTS_ATTR);
+        out.push_str(&ids.join(","));
+        out.push_str(BLOCK_SUFFIX);
+        out.push('\n');
+
+        let incoming_text = match counterparts.get(&idx) {
+            Some(&right) => incoming_blocks[right].display_text.clone(),
+            None => conflicts
+                .iter()
+                .filter_map(|c| c.incoming_content.as_deref())
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+
+        out.push_str(MARKER_BASE);
+        out.push('\n');
+        push_text(&mut out, &block.display_text);
+        out.push_str(MARKER_SEPARATOR);
+        out.push('\n');
+        push_text(&mut out, &incoming_text);
+        out.push_str(MARKER_INCOMING);
+        out.push('\n');
+    }
+    out
+}
+
+/// Read an edited marker file produced by [`export_conflict_markers`].
+///
+/// Returns one [`MarkerResolution`] per conflict whose block no longer
+/// contains markers. Conflict ids in the file that are not pending in
+/// `result` are ignored; a block whose markers are only partially removed
+/// is rejected with [`RtError::InvalidInput`].
+pub fn import_conflict_markers(
+    text: &str,
+    base_blocks: &[Block],
+    incoming_blocks: &[Block],
+    result: &MergeResult,
+) -> Result<Vec<MarkerResolution>, RtError> {
+    let pending: HashMap<Uuid, &MergeConflict> = result
+        .conflicts
+        .iter()
+        .filter(|c| !c.is_resolved())
+        .map(|c| (c.id, c))
+        .collect();
+
+    // Recover both sides' texts the same way the export produced them.
+    let exported = export_conflict_markers(base_blocks, incoming_blocks, result);
+    let original: HashMap<Uuid, Section> = parse_sections(&exported)?
+        .into_iter()
+        .map(|s| (s.block_id, s))
+        .collect();
+
+    let mut resolutions = Vec::new();
+    for section in parse_sections(text)? {
+        if section.conflict_ids.is_empty() {
+            continue;
+        }
+
+        let marker_lines = section
+            .body
+            .lines()
+            .filter(|l| is_marker_line(l))
+            .count();
+        if marker_lines == 3 {
+            continue; // still unresolved
+        }
+        if marker_lines != 0 {
+            return Err(RtError::InvalidInput(format!(
+                "block {} has incomplete conflict markers",
+                section.block_id
+            )));
+        }
+
+        let chosen = section.body.trim_end_matches('\n').to_string();
+        let sides = original.get(&section.block_id).and_then(|s| s.sides());
+        let resolution = match sides {
+            Some((base, _)) if base == chosen => ConflictResolution::AcceptedBase,
+            Some((_, incoming)) if incoming == chosen => ConflictResolution::AcceptedIncoming,
+            _ => ConflictResolution::Manual,
+        };
+
+        for conflict_id in &section.conflict_ids {
+            if let Some(conflict) = pending.get(conflict_id) {
+                resolutions.push(MarkerResolution {
+                    conflict_id: *conflict_id,
+                    block_id: conflict.block_id,
+                    resolution: resolution.clone(),
+                    text: chosen.clone(),
+                });
+            }
+        }
+    }
+    Ok(resolutions)
+}
+
+/// Apply `resolutions` to the matching conflicts in `result` and recompute
+/// `pending_review`.
+pub fn apply_marker_resolutions(
+    result: &mut MergeResult,
+    resolutions: &[MarkerResolution],
+) -> Result<(), RtError> {
+    for r in resolutions {
+        let conflict = result
+            .conflicts
+            .iter_mut()
+            .find(|c| c.id == r.conflict_id)
+            .ok_or_else(|| RtError::NotFound(format!("conflict {}", r.conflict_id)))?;
+        MergeEngine::resolve_conflict(conflict, r.resolution.clone())?;
+    }
+    result.pending_review = result.conflicts.iter().filter(|c| !c.is_resolved()).count();
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// One block's header and the lines that follow it.
+struct Section {
+    block_id: Uuid,
+    conflict_ids: Vec<Uuid>,
+    body: String,
+}
+
+impl Section {
+    /// `(base, incoming)` texts of an exported, still-marked section.
+    fn sides(&self) -> Option<(String, String)> {
+        let body = self.body.strip_prefix(MARKER_BASE)?.strip_prefix('\n')?;
+        let (base, rest) = body.split_once(&format!("{MARKER_SEPARATOR}\n"))?;
+        let (incoming, _) = rest.split_once(MARKER_INCOMING)?;
+        Some((
+            base.trim_end_matches('\n').to_string(),
+            incoming.trim_end_matches('\n').to_string(),
+        ))
+    }
+}
+
+fn parse_sections(text: &str) -> Result<Vec<Section>, RtError> {
+    let mut sections: Vec<Section> = Vec::new();
+    for line in text.lines() {
+        if let Some(header) = line
+            .strip_prefix(BLOCK_PREFIX)
+            .and_then(|h| h.strip_suffix(BLOCK_SUFFIX))
+        {
+            sections.push(parse_header(header)?);
+            continue;
+        }
+        match sections.last_mut() {
+            Some(section) => {
+                section.body.push_str(line);
+                section.body.push('\n');
+            }
+            None if line.trim().is_empty() => {}
+            None => {
+                return Err(RtError::InvalidInput(
+                    "text before the first block header".into(),
+                ))
+            }
+        }
+    }
+
+    // Drop the blank separator line between sections.
+    for section in &mut sections {
+        let trimmed = section.body.trim_end_matches('\n').len();
+        section.body.truncate(trimmed);
+        if !section.body.is_empty() {
+            section.body.push('\n');
+        }
+    }
+    Ok(sections)
+}
+
+fn parse_header(header: &str) -> Result<Section, RtError> {
+    let invalid = |what: &str| RtError::InvalidInput(format!("invalid block header: {what}"));
+    let mut parts = header.split_whitespace();
+    let block_id = parts
+        .next()
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or_else(|| invalid(header))?;
+
+    let mut conflict_ids = Vec::new();
+    for part in parts {
+        let ids = part.strip_prefix(CONFLICTS_ATTR).ok_or_else(|| invalid(part))?;
+        for id in ids.split(',') {
+            conflict_ids.push(Uuid::parse_str(id).map_err(|_| invalid(id))?);
+        }
+    }
+
+    Ok(Section {
+        block_id,
+        conflict_ids,
+        body: String::new(),
+    })
+}
+
+fn is_marker_line(line: &str) -> bool {
+    line.starts_with("<<<<<<<") || line == MARKER_SEPARATOR || line.starts_with(">>>>>>>")
+}
+
+fn push_text(out: &mut String, text: &str) {
+    out.push_str(text);
+    if !text.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_compare::tokenize::tokenize;
+    use rt_core::BlockType;
+
+    fn make_block(doc_id: Uuid, path: &str, text: &str, pos: i32) -> Block {
+        let mut block = Block::new(BlockType::Clause, path, text, text, None, doc_id, pos);
+        block.tokens = tokenize(text);
+        block
+    }
+
+    fn setup() -> (Vec<Block>, Vec<Block>, MergeResult) {
+        let (b, i) = (Uuid::new_v4(), Uuid::new_v4());
+        let base = vec![
+            make_block(b, "1", "the borrower shall repay the loan in full on demand", 0),
+            make_block(b, "2", "interest accrues monthly", 1),
+        ];
+        let incoming = vec![
+            make_block(i, "1", "the lender shall repay the loan in full on demand", 0),
+            make_block(i, "2", "interest accrues monthly", 1),
+        ];
+        let result = MergeEngine::new().merge(b, i, &base, &incoming);
+        assert!(result.pending_review > 0, "fixture must produce a conflict");
+        (base, incoming, result)
+    }
+
+    #[test]
+    fn export_wraps_conflicted_blocks_only() {
+        let (base, incoming, result) = setup();
+        let text = export_conflict_markers(&base, &incoming, &result);
+
+        let expected_block = "<<<<<<< base\nthe borrower shall repay the loan in full on demand\n\
+                              =======\nthe lender shall repay the loan in full on demand\n\
+                              >>>>>>> incoming\n";
+        assert!(text.contains(expected_block), "{text}");
+        assert!(text.contains(&format!("{BLOCK_PREFIX}{}{BLOCK_SUFFIX}\ninterest", base[1].id)));
+        assert_eq!(text.matches(MARKER_BASE).count(), 1);
+    }
+
+    #[test]
+    fn untouched_file_imports_nothing() {
+        let (base, incoming, result) = setup();
+        let text = export_conflict_markers(&base, &incoming, &result);
+        let resolutions = import_conflict_markers(&text, &base, &incoming, &result).unwrap();
+        assert!(resolutions.is_empty());
+    }
+
+    #[test]
+    fn manual_edit_imports_as_manual() {
+        let (base, incoming, mut result) = setup();
+        let text = export_conflict_markers(&base, &incoming, &result);
+        let start = text.find(MARKER_BASE).unwrap();
+        let end = text.find(MARKER_INCOMING).unwrap() + MARKER_INCOMING.len();
+        let edited = format!("{}the agent shall repay the loan in full on demand{}", &text[..start], &text[end..]);
+
+        let resolutions = import_conflict_markers(&edited, &base, &incoming, &result).unwrap();
+        assert_eq!(resolutions.len(), result.pending_review);
+        assert!(resolutions.iter().all(|r| r.resolution == ConflictResolution::Manual));
+        assert_eq!(resolutions[0].text, "the agent shall repay the loan in full on demand");
+        assert_eq!(resolutions[0].block_id, base[0].id);
+
+        apply_marker_resolutions(&mut result, &resolutions).unwrap();
+        assert_eq!(result.pending_review, 0);
+        assert!(result.conflicts.iter().all(|c| c.is_resolved()));
+    }
+
+    #[test]
+    fn keeping_one_side_verbatim_is_accept() {
+        let (base, incoming, result) = setup();
+        let text = export_conflict_markers(&base, &incoming, &result);
+        let start = text.find(MARKER_BASE).unwrap();
+        let end = text.find(MARKER_INCOMING).unwrap() + MARKER_INCOMING.len();
+        let edited = format!("{}the lender shall repay the loan in full on demand{}", &text[..start], &text[end..]);
+
+        let resolutions = import_conflict_markers(&edited, &base, &incoming, &result).unwrap();
+        assert!(resolutions
+            .iter()
+            .all(|r| r.resolution == ConflictResolution::AcceptedIncoming));
+    }
+
+    #[test]
+    fn partial_markers_are_rejected() {
+        let (base, incoming, result) = setup();
+        let text = export_conflict_markers(&base, &incoming, &result);
+        let edited = text.replacen(&format!("{MARKER_INCOMING}\n"), "", 1);
+        let err = import_conflict_markers(&edited, &base, &incoming, &result);
+        assert!(matches!(err, Err(RtError::InvalidInput(_))));
+    }
+}
@@ -1,13 +1,20 @@
-use rt_core::{Block, RtError};
-use rt_compare::align::{align_blocks, BlockAlignment};
-use rt_compare::diff::{token_diff, DiffKind};
+use rt_core::manifest::{config_fingerprint, InputDigest, RunManifest};
+use rt_core::{Block, CancelToken, RtError};
+use rt_compare::align::{align_blocks_with, AlignThresholds, BlockAlignment, SIMILARITY_THRESHOLD};
+use rt_compare::diff::{token_diff, DiffKind, TokenDiff};
+use rt_compare::worker::{ensure_tokens, flatten_blocks};
+use rt_compare::xref::{broken_references, BrokenReference};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
-use crate::conflict::{detect_conflicts, ConflictResolution, MergeConflict};
-use crate::layer::{BlockDelta, DeltaType};
+use crate::conflict::{detect_conflicts, ConflictResolution, ConflictType, MergeConflict};
+use crate::layer::{BlockDelta, DeltaType, LayerDeltas};
 use crate::resolution::validate_resolution;
 
+/// Version of this crate, recorded in every [`RunManifest`] it produces.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 // ---------------------------------------------------------------------------
 // MergeResult
 // ---------------------------------------------------------------------------
@@ -23,6 +30,9 @@ pub struct MergeResult {
     pub base_doc_id: Uuid,
     /// UUID of the incoming (reviewer / redlined) document.
     pub incoming_doc_id: Uuid,
+    /// UUID of the common ancestor document, for three-way merges.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ancestor_doc_id: Option<Uuid>,
     /// UUID of the newly created merged output document, if one was produced.
     pub output_doc_id: Option<Uuid>,
     /// All conflicts detected during the merge (resolved and unresolved).
@@ -31,6 +41,123 @@ pub struct MergeResult {
     pub auto_resolved: usize,
     /// Number of conflicts still in `Pending` state requiring human review.
     pub pending_review: usize,
+    /// Cross-references in the incoming document whose target the incoming
+    /// side deleted or renumbered, relative to the base.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub broken_references: Vec<BrokenReference>,
+    /// How this result was produced; absent on merges recorded before
+    /// manifests were.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<RunManifest>,
+}
+
+// ---------------------------------------------------------------------------
+// LayerMergeResult
+// ---------------------------------------------------------------------------
+
+/// A conflict between the deltas of two reviewer layers on one block.
+///
+/// The wrapped conflict's `base_content` is the first layer's edit and its
+/// `incoming_content` the second's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerConflict {
+    pub first_layer_id: Uuid,
+    pub first_reviewer_id: String,
+    pub second_layer_id: Uuid,
+    pub second_reviewer_id: String,
+    pub conflict: MergeConflict,
+}
+
+/// Every cross-reviewer conflict on a single base block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockLayerConflicts {
+    pub block_id: Uuid,
+    /// Reviewers involved in at least one of `conflicts`, sorted.
+    pub reviewer_ids: Vec<String>,
+    pub conflicts: Vec<LayerConflict>,
+}
+
+/// The output of [`MergeEngine::merge_layers`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerMergeResult {
+    /// Stable unique identifier for this merge run (UUIDv4).
+    pub merge_id: Uuid,
+    /// UUID of the base document all layers were recorded against.
+    pub base_doc_id: Uuid,
+    /// The merged layers, in input order.
+    pub layer_ids: Vec<Uuid>,
+    /// Conflicted blocks in base document order.
+    pub blocks: Vec<BlockLayerConflicts>,
+    /// Number of edited blocks whose layers do not conflict.
+    pub auto_resolved: usize,
+    /// Number of conflicts still in `Pending` state requiring human review.
+    pub pending_review: usize,
+}
+
+// ---------------------------------------------------------------------------
+// MergeConfig
+// ---------------------------------------------------------------------------
+
+/// Runtime configuration for the merge engine.
+///
+/// Deserializes from a JSON options object in which every field is optional
+/// (missing fields take their default). Unknown keys are rejected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MergeConfig {
+    /// Minimum Jaccard similarity for two blocks to be aligned as the same
+    /// block. Default: 0.7.
+    pub similarity_threshold: f64,
+    /// Accept blocks that only the incoming document has without review.
+    /// When `false` each one is raised as a `review_required` conflict.
+    /// Default: `true`.
+    pub auto_accept_insertions: bool,
+    /// Accept blocks the incoming document removed (and did not otherwise
+    /// contest) without review. When `false` each one is raised as a
+    /// `review_required` conflict. Default: `true`.
+    pub auto_accept_deletions: bool,
+}
+
+impl Default for MergeConfig {
+    fn default() -> Self {
+        Self {
+            similarity_threshold: SIMILARITY_THRESHOLD,
+            auto_accept_insertions: true,
+            auto_accept_deletions: true,
+        }
+    }
+}
+
+impl MergeConfig {
+    /// Parse a JSON options object (e.g. the `options_json` FFI argument)
+    /// and validate it. An empty or whitespace-only string yields defaults.
+    pub fn from_json(json: &str) -> Result<Self, RtError> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let config: Self = serde_json::from_str(json)
+            .map_err(|e| RtError::InvalidInput(format!("invalid merge options: {e}")))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check that every field is within its legal range.
+    pub fn validate(&self) -> Result<(), RtError> {
+        if !(0.0..=1.0).contains(&self.similarity_threshold) {
+            return Err(RtError::InvalidInput(format!(
+                "similarity_threshold must be between 0.0 and 1.0, got {}",
+                self.similarity_threshold
+            )));
+        }
+        Ok(())
+    }
+
+    fn thresholds(&self) -> AlignThresholds {
+        AlignThresholds {
+            similarity: self.similarity_threshold,
+            ..AlignThresholds::default()
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -38,22 +165,38 @@ pub struct MergeResult {
 // ---------------------------------------------------------------------------
 
 /// Stateless engine that merges two block sequences and detects conflicts.
+///
+/// Merging borrows the engine immutably and it is `Send + Sync`, so one
+/// engine can be shared across threads.
 pub struct MergeEngine {
     /// Reviewer identifier used for base-side deltas.
     base_reviewer_id: String,
     /// Reviewer identifier used for incoming-side deltas.
     incoming_reviewer_id: String,
+    config: MergeConfig,
 }
 
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<MergeEngine>();
+};
+
 impl MergeEngine {
     /// Create a `MergeEngine` with default reviewer labels.
     pub fn new() -> Self {
         Self {
             base_reviewer_id: "base".to_string(),
             incoming_reviewer_id: "incoming".to_string(),
+            config: MergeConfig::default(),
         }
     }
 
+    /// This engine with `config` in place of the default configuration.
+    pub fn with_config(mut self, config: MergeConfig) -> Self {
+        self.config = config;
+        self
+    }
+
     /// Create a `MergeEngine` with custom reviewer labels (useful for tests).
     pub fn with_reviewers(
         base_reviewer_id: impl Into<String>,
@@ -62,6 +205,7 @@ impl MergeEngine {
         Self {
             base_reviewer_id: base_reviewer_id.into(),
             incoming_reviewer_id: incoming_reviewer_id.into(),
+            config: MergeConfig::default(),
         }
     }
 
@@ -69,13 +213,18 @@ impl MergeEngine {
     /// conflicts.
     ///
     /// Algorithm:
-    /// 1. Align the two block sequences using `rt_compare::align::align_blocks`.
+    /// 1. Align the two block sequences using `rt_compare::align::align_blocks_with`
+    ///    and the configured similarity threshold.
     /// 2. For each matched pair whose `clause_hash` differs, compute a
     ///    token-level diff with `rt_compare::diff::token_diff`.
     /// 3. Convert diff operations into `BlockDelta` records.
     /// 4. Run `detect_conflicts` on each block's delta set.
     /// 5. Tally `auto_resolved` (modified pairs with no conflicts) and
     ///    `pending_review` (conflict count still in Pending state).
+    ///
+    /// Insertions and deletions are auto-resolved unless the configuration
+    /// turns that off, in which case each is a `ReviewRequired` conflict on
+    /// the inserted (incoming) or deleted (base) block.
     pub fn merge(
         &self,
         base_doc_id: Uuid,
@@ -83,12 +232,35 @@ impl MergeEngine {
         base_blocks: &[Block],
         incoming_blocks: &[Block],
     ) -> MergeResult {
-        let alignments = align_blocks(base_blocks, incoming_blocks);
+        self.merge_cancellable(
+            base_doc_id,
+            incoming_doc_id,
+            base_blocks,
+            incoming_blocks,
+            &CancelToken::new(),
+        )
+        .unwrap_or_else(|_| unreachable!("the merge's token is never cancelled"))
+    }
+
+    /// As [`merge`](Self::merge), giving up with [`RtError::Cancelled`] once
+    /// `cancel` is cancelled. The token is checked after alignment and before
+    /// each aligned pair is diffed.
+    pub fn merge_cancellable(
+        &self,
+        base_doc_id: Uuid,
+        incoming_doc_id: Uuid,
+        base_blocks: &[Block],
+        incoming_blocks: &[Block],
+        cancel: &CancelToken,
+    ) -> Result<MergeResult, RtError> {
+        cancel.check()?;
+        let alignments = align_blocks_with(base_blocks, incoming_blocks, &self.config.thresholds());
 
         let mut all_conflicts: Vec<MergeConflict> = Vec::new();
         let mut auto_resolved: usize = 0;
 
         for alignment in &alignments {
+            cancel.check()?;
             match alignment {
                 BlockAlignment::Matched { left, right, .. }
                 | BlockAlignment::Moved { left, right, .. } => {
@@ -133,14 +305,163 @@ impl MergeEngine {
                     }
                 }
 
-                // Pure insertion: block added in incoming — auto-accept.
-                BlockAlignment::InsertedRight { .. } => {
-                    auto_resolved += 1;
+                // Pure insertion: block added in incoming — auto-accept
+                // unless held for review.
+                BlockAlignment::InsertedRight { right } => {
+                    if self.config.auto_accept_insertions {
+                        auto_resolved += 1;
+                    } else {
+                        all_conflicts.push(review_required(None, Some(&incoming_blocks[*right])));
+                    }
+                }
+
+                // Pure deletion: block removed in incoming — auto-accept
+                // unless held for review.
+                BlockAlignment::DeletedLeft { left } => {
+                    if self.config.auto_accept_deletions {
+                        auto_resolved += 1;
+                    } else {
+                        all_conflicts.push(review_required(Some(&base_blocks[*left]), None));
+                    }
+                }
+
+                // `align_blocks` leaves split / merge detection off.
+                BlockAlignment::Split { .. } | BlockAlignment::Merged { .. } => {}
+            }
+        }
+
+        let pending_review = all_conflicts
+            .iter()
+            .filter(|c| c.resolution == ConflictResolution::Pending)
+            .count();
+
+        Ok(MergeResult {
+            merge_id: Uuid::new_v4(),
+            base_doc_id,
+            incoming_doc_id,
+            ancestor_doc_id: None,
+            output_doc_id: Some(Uuid::new_v4()),
+            conflicts: all_conflicts,
+            auto_resolved,
+            pending_review,
+            broken_references: self.broken_references(base_blocks, incoming_blocks),
+            manifest: Some(self.manifest(&[
+                InputDigest::from_blocks(base_doc_id, base_blocks),
+                InputDigest::from_blocks(incoming_doc_id, incoming_blocks),
+            ])),
+        })
+    }
+
+    /// Merge `base_blocks` and `incoming_blocks`, both derived from
+    /// `ancestor_blocks`.
+    ///
+    /// Each side is aligned against the ancestor, so a block changed on only
+    /// one side is taken from that side without conflict. Conflicts are
+    /// raised only for concurrent edits:
+    /// - `ContentOverlap`: both sides changed overlapping token ranges of
+    ///   the same ancestor block (identical edits do not conflict).
+    /// - `DeleteModify`: one side deleted a block the other side changed.
+    /// - `MoveCollision`: both sides moved a block to different paths.
+    ///
+    /// Blocks added on either side are auto-resolved. Conflicts reference
+    /// the base block, or the ancestor block when the base deleted it.
+    ///
+    /// The configuration's auto-accept rules apply to the incoming side: with
+    /// them turned off, a block only incoming added, or an unchanged block
+    /// only incoming deleted, is a `ReviewRequired` conflict.
+    #[allow(clippy::too_many_arguments)]
+    pub fn merge_three_way(
+        &self,
+        ancestor_doc_id: Uuid,
+        base_doc_id: Uuid,
+        incoming_doc_id: Uuid,
+        ancestor_blocks: &[Block],
+        base_blocks: &[Block],
+        incoming_blocks: &[Block],
+    ) -> MergeResult {
+        let thresholds = self.config.thresholds();
+        let (base_map, base_added) = counterparts(ancestor_blocks, base_blocks, &thresholds);
+        let (inc_map, inc_added) = counterparts(ancestor_blocks, incoming_blocks, &thresholds);
+
+        let mut all_conflicts: Vec<MergeConflict> = Vec::new();
+        let mut auto_resolved: usize = base_added.len();
+        if self.config.auto_accept_insertions {
+            auto_resolved += inc_added.len();
+        } else {
+            all_conflicts.extend(
+                inc_added
+                    .iter()
+                    .map(|&i| review_required(None, Some(&incoming_blocks[i]))),
+            );
+        }
+
+        for (index, ancestor) in ancestor_blocks.iter().enumerate() {
+            let base = base_map.get(&index).map(|&(i, moved)| (&base_blocks[i], moved));
+            let incoming = inc_map.get(&index).map(|&(i, moved)| (&incoming_blocks[i], moved));
+
+            let changed = |b: &Block| b.clause_hash != ancestor.clause_hash;
+
+            match (base, incoming) {
+                // Deleted on both sides, or deleted on one side and left
+                // untouched on the other.
+                (None, None) => auto_resolved += 1,
+                (Some((b, _)), None) if !changed(b) => {
+                    if self.config.auto_accept_deletions {
+                        auto_resolved += 1;
+                    } else {
+                        all_conflicts.push(review_required(Some(b), None));
+                    }
                 }
+                (None, Some((i, _))) if !changed(i) => auto_resolved += 1,
+
+                (Some((b, _)), None) => all_conflicts.push(MergeConflict::new(
+                    b.id,
+                    ConflictType::DeleteModify,
+                    Some(b.canonical_text.clone()),
+                    None,
+                )),
+                (None, Some((i, _))) => all_conflicts.push(MergeConflict::new(
+                    ancestor.id,
+                    ConflictType::DeleteModify,
+                    None,
+                    Some(i.canonical_text.clone()),
+                )),
+
+                (Some((b, b_moved)), Some((i, i_moved))) => {
+                    if b_moved && i_moved && b.structural_path != i.structural_path {
+                        all_conflicts.push(MergeConflict::new(
+                            b.id,
+                            ConflictType::MoveCollision,
+                            Some(b.structural_path.clone()),
+                            Some(i.structural_path.clone()),
+                        ));
+                        continue;
+                    }
+
+                    // At most one side changed, or both made the same edit.
+                    if !changed(b) || !changed(i) || b.clause_hash == i.clause_hash {
+                        auto_resolved += 1;
+                        continue;
+                    }
+
+                    let ancestor_tokens = ensure_tokens(ancestor);
+                    let base_deltas = side_deltas(
+                        &token_diff(&ancestor_tokens, &ensure_tokens(b)),
+                        b.id,
+                        &self.base_reviewer_id,
+                    );
+                    let incoming_deltas = side_deltas(
+                        &token_diff(&ancestor_tokens, &ensure_tokens(i)),
+                        b.id,
+                        &self.incoming_reviewer_id,
+                    );
 
-                // Pure deletion: block removed in incoming — auto-accept.
-                BlockAlignment::DeletedLeft { .. } => {
-                    auto_resolved += 1;
+                    let block_conflicts = detect_conflicts(&base_deltas, &incoming_deltas);
+                    if block_conflicts.is_empty() {
+                        auto_resolved += 1;
+                    } else {
+                        all_conflicts.extend(block_conflicts);
+                    }
                 }
             }
         }
@@ -154,11 +475,172 @@ impl MergeEngine {
             merge_id: Uuid::new_v4(),
             base_doc_id,
             incoming_doc_id,
+            ancestor_doc_id: Some(ancestor_doc_id),
             output_doc_id: Some(Uuid::new_v4()),
             conflicts: all_conflicts,
             auto_resolved,
             pending_review,
+            broken_references: self.broken_references(base_blocks, incoming_blocks),
+            manifest: Some(self.manifest(&[
+                InputDigest::from_blocks(ancestor_doc_id, ancestor_blocks),
+                InputDigest::from_blocks(base_doc_id, base_blocks),
+                InputDigest::from_blocks(incoming_doc_id, incoming_blocks),
+            ])),
+        }
+    }
+
+    /// Cross-references in `incoming_blocks` broken relative to
+    /// `base_blocks`. Both trees are flattened and aligned block by block,
+    /// so references to sub-clauses are checked too.
+    fn broken_references(
+        &self,
+        base_blocks: &[Block],
+        incoming_blocks: &[Block],
+    ) -> Vec<BrokenReference> {
+        let base_flat = flatten_blocks(base_blocks);
+        let incoming_flat = flatten_blocks(incoming_blocks);
+        let alignments = align_blocks_with(&base_flat, &incoming_flat, &self.config.thresholds());
+        broken_references(&alignments, &base_flat, &incoming_flat)
+    }
+
+    /// The [`RunManifest`] of a merge by this engine over `inputs`: base and
+    /// incoming, preceded by the ancestor for three-way merges.
+    pub fn manifest(&self, inputs: &[InputDigest]) -> RunManifest {
+        let config = serde_json::json!({
+            "base_reviewer_id": self.base_reviewer_id,
+            "incoming_reviewer_id": self.incoming_reviewer_id,
+            "config": self.config,
+        });
+        RunManifest::new(
+            [
+                ("rt-merge", ENGINE_VERSION),
+                ("rt-compare", rt_compare::worker::ENGINE_VERSION),
+            ],
+            config_fingerprint(&config),
+            inputs.to_vec(),
+        )
+    }
+
+    /// Combine any number of reviewer layers recorded against `base_blocks`.
+    ///
+    /// For every base block edited by two or more layers, each pair of layers
+    /// is checked with `detect_conflicts`; identical edits made by both
+    /// layers of a pair are treated as agreement. Conflicts are aggregated
+    /// per block. A block edited by a single layer, or by several layers
+    /// without conflicts, counts as auto-resolved.
+    ///
+    /// Returns `InvalidInput` when a layer belongs to another document, a
+    /// layer appears twice, or a delta names another layer or a block that
+    /// is not in `base_blocks` (searched recursively).
+    pub fn merge_layers(
+        &self,
+        base_doc_id: Uuid,
+        base_blocks: &[Block],
+        layers: &[LayerDeltas],
+    ) -> Result<LayerMergeResult, RtError> {
+        let flat = flatten_blocks(base_blocks);
+        let block_ids: HashSet<Uuid> = flat.iter().map(|b| b.id).collect();
+
+        let mut seen_layers = HashSet::new();
+        for input in layers {
+            let layer = &input.layer;
+            if layer.document_id != base_doc_id {
+                return Err(RtError::InvalidInput(format!(
+                    "review layer {} belongs to document {}, not {}",
+                    layer.id, layer.document_id, base_doc_id
+                )));
+            }
+            if !seen_layers.insert(layer.id) {
+                return Err(RtError::InvalidInput(format!(
+                    "review layer {} supplied more than once",
+                    layer.id
+                )));
+            }
+            for delta in &input.deltas {
+                if delta.review_layer_id != layer.id {
+                    return Err(RtError::InvalidInput(format!(
+                        "delta {} belongs to review layer {}, not {}",
+                        delta.id, delta.review_layer_id, layer.id
+                    )));
+                }
+                if !block_ids.contains(&delta.block_id) {
+                    return Err(RtError::InvalidInput(format!(
+                        "delta {} targets block {} which is not in the base document",
+                        delta.id, delta.block_id
+                    )));
+                }
+            }
+        }
+
+        let mut blocks = Vec::new();
+        let mut auto_resolved = 0;
+
+        for block in &flat {
+            let edits: Vec<(&LayerDeltas, Vec<BlockDelta>)> = layers
+                .iter()
+                .map(|input| {
+                    let deltas = input
+                        .deltas
+                        .iter()
+                        .filter(|d| d.block_id == block.id)
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    (input, deltas)
+                })
+                .filter(|(_, deltas)| !deltas.is_empty())
+                .collect();
+            if edits.is_empty() {
+                continue;
+            }
+
+            let mut conflicts = Vec::new();
+            for (i, (first, first_deltas)) in edits.iter().enumerate() {
+                for (second, second_deltas) in &edits[i + 1..] {
+                    let a = without_shared_edits(first_deltas, second_deltas);
+                    let b = without_shared_edits(second_deltas, first_deltas);
+                    for conflict in detect_conflicts(&a, &b) {
+                        conflicts.push(LayerConflict {
+                            first_layer_id: first.layer.id,
+                            first_reviewer_id: first.layer.reviewer_id.clone(),
+                            second_layer_id: second.layer.id,
+                            second_reviewer_id: second.layer.reviewer_id.clone(),
+                            conflict,
+                        });
+                    }
+                }
+            }
+
+            if conflicts.is_empty() {
+                auto_resolved += 1;
+                continue;
+            }
+            let mut reviewer_ids: Vec<String> = conflicts
+                .iter()
+                .flat_map(|c| [c.first_reviewer_id.clone(), c.second_reviewer_id.clone()])
+                .collect();
+            reviewer_ids.sort();
+            reviewer_ids.dedup();
+            blocks.push(BlockLayerConflicts {
+                block_id: block.id,
+                reviewer_ids,
+                conflicts,
+            });
         }
+
+        let pending_review = blocks
+            .iter()
+            .flat_map(|b| &b.conflicts)
+            .filter(|c| c.conflict.resolution == ConflictResolution::Pending)
+            .count();
+
+        Ok(LayerMergeResult {
+            merge_id: Uuid::new_v4(),
+            base_doc_id,
+            layer_ids: layers.iter().map(|l| l.layer.id).collect(),
+            blocks,
+            auto_resolved,
+            pending_review,
+        })
     }
 
     /// Apply a `resolution` to `conflict`, validating the state transition first.
@@ -346,6 +828,104 @@ impl Default for MergeEngine {
     }
 }
 
+/// A `ReviewRequired` conflict for a block only `base` (deleted by
+/// incoming) or only `incoming` (inserted) has.
+fn review_required(base: Option<&Block>, incoming: Option<&Block>) -> MergeConflict {
+    let block = base.or(incoming).expect("a base or an incoming block");
+    MergeConflict::new(
+        block.id,
+        ConflictType::ReviewRequired,
+        base.map(|b| b.canonical_text.clone()),
+        incoming.map(|b| b.canonical_text.clone()),
+    )
+}
+
+/// Map each ancestor index to its `(index, moved)` counterpart in `side`,
+/// and list the blocks that exist only in `side`.
+fn counterparts(
+    ancestor: &[Block],
+    side: &[Block],
+    thresholds: &AlignThresholds,
+) -> (HashMap<usize, (usize, bool)>, Vec<usize>) {
+    let mut map = HashMap::new();
+    let mut added = Vec::new();
+    for alignment in align_blocks_with(ancestor, side, thresholds) {
+        match alignment {
+            BlockAlignment::Matched { left, right, .. } => {
+                map.insert(left, (right, false));
+            }
+            BlockAlignment::Moved { left, right, .. } => {
+                map.insert(left, (right, true));
+            }
+            BlockAlignment::InsertedRight { right } => added.push(right),
+            BlockAlignment::DeletedLeft { .. } => {}
+            // `align_blocks` leaves split / merge detection off.
+            BlockAlignment::Split { .. } | BlockAlignment::Merged { .. } => {}
+        }
+    }
+    (map, added)
+}
+
+/// `deltas` minus those that `other` also made verbatim (same type, token
+/// range and payload).
+fn without_shared_edits(deltas: &[BlockDelta], other: &[BlockDelta]) -> Vec<BlockDelta> {
+    deltas
+        .iter()
+        .filter(|d| {
+            !other.iter().any(|o| {
+                o.delta_type == d.delta_type
+                    && o.token_start == d.token_start
+                    && o.token_end == d.token_end
+                    && o.delta_payload == d.delta_payload
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Deltas describing one side's edits to an ancestor block, positioned in
+/// ancestor token coordinates so both sides' deltas can be overlap-checked.
+/// Token deletions and substitutions are `Modify` deltas (deleted text has an
+/// empty payload); insertions are `Insert` deltas anchored at the ancestor
+/// position they precede.
+fn side_deltas(diffs: &[TokenDiff], block_id: Uuid, reviewer_id: &str) -> Vec<BlockDelta> {
+    let layer_id = Uuid::new_v4();
+    let mut deltas = Vec::new();
+    let mut ancestor_idx: usize = 0;
+
+    for diff in diffs {
+        let left_len = diff.left_tokens.len();
+        let payload = serde_json::json!({ "text": diff.right_tokens.join(" ") });
+        match diff.kind {
+            DiffKind::Equal => {}
+            DiffKind::Inserted => deltas.push(BlockDelta::new(
+                layer_id,
+                reviewer_id,
+                block_id,
+                DeltaType::Insert,
+                ancestor_idx,
+                ancestor_idx,
+                payload,
+            )),
+            DiffKind::Deleted | DiffKind::Substituted if left_len > 0 => {
+                deltas.push(BlockDelta::new(
+                    layer_id,
+                    reviewer_id,
+                    block_id,
+                    DeltaType::Modify,
+                    ancestor_idx,
+                    ancestor_idx + left_len - 1,
+                    payload,
+                ))
+            }
+            DiffKind::Deleted | DiffKind::Substituted => {}
+        }
+        ancestor_idx += left_len;
+    }
+
+    deltas
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -353,6 +933,7 @@ impl Default for MergeEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layer::ReviewLayer;
     use rt_core::{Block, BlockType};
 
     fn make_block(doc_id: Uuid, path: &str, text: &str, pos: i32) -> Block {
@@ -390,6 +971,60 @@ mod tests {
         assert_eq!(result.auto_resolved, base_blocks.len());
     }
 
+    #[test]
+    fn cancelled_merge_returns_cancelled() {
+        let doc = Uuid::new_v4();
+        let blocks = vec![make_block(doc, "1.1", "the borrower shall repay the principal", 0)];
+        let cancel = CancelToken::new();
+        let engine = MergeEngine::new();
+        assert!(engine.merge_cancellable(doc, doc, &blocks, &blocks, &cancel).is_ok());
+        cancel.cancel();
+        let result = engine.merge_cancellable(doc, doc, &blocks, &blocks, &cancel);
+        assert!(matches!(result, Err(RtError::Cancelled)));
+    }
+
+    #[test]
+    fn auto_accept_rules_hold_insertions_and_deletions_for_review() {
+        let base_doc = Uuid::new_v4();
+        let inc_doc = Uuid::new_v4();
+        let base_blocks = vec![
+            make_block(base_doc, "1.1", "the borrower shall repay the principal", 0),
+            make_block(base_doc, "1.2", "the guarantor waives all defences", 1),
+        ];
+        let incoming_blocks = vec![
+            make_block(inc_doc, "1.1", "the borrower shall repay the principal", 0),
+            make_block(inc_doc, "1.3", "an entirely new indemnity is given", 1),
+        ];
+
+        let default = MergeEngine::new().merge(base_doc, inc_doc, &base_blocks, &incoming_blocks);
+        assert!(default.conflicts.is_empty());
+        assert_eq!(default.auto_resolved, 3);
+
+        let strict = MergeEngine::new().with_config(MergeConfig {
+            auto_accept_insertions: false,
+            auto_accept_deletions: false,
+            ..MergeConfig::default()
+        });
+        let result = strict.merge(base_doc, inc_doc, &base_blocks, &incoming_blocks);
+        assert_eq!(result.auto_resolved, 1);
+        assert_eq!(result.pending_review, 2);
+        assert!(result.conflicts.iter().all(|c| c.conflict_type == ConflictType::ReviewRequired));
+        let deleted = result.conflicts.iter().find(|c| c.block_id == base_blocks[1].id).unwrap();
+        assert_eq!(deleted.incoming_content, None);
+        let inserted = result.conflicts.iter().find(|c| c.block_id == incoming_blocks[1].id).unwrap();
+        assert_eq!(inserted.base_content, None);
+    }
+
+    #[test]
+    fn merge_config_parses_and_validates() {
+        let config = MergeConfig::from_json(r#"{"auto_accept_deletions": false}"#).unwrap();
+        assert!(config.auto_accept_insertions);
+        assert!(!config.auto_accept_deletions);
+        assert_eq!(MergeConfig::from_json("").unwrap(), MergeConfig::default());
+        assert!(MergeConfig::from_json(r#"{"similarity_threshold": 2}"#).is_err());
+        assert!(MergeConfig::from_json(r#"{"auto_accept": true}"#).is_err());
+    }
+
     // -----------------------------------------------------------------------
     // Test: edits in separate blocks auto-merge without conflict
     // -----------------------------------------------------------------------
@@ -561,4 +1196,309 @@ mod tests {
         assert_eq!(result.auto_resolved, 0);
         assert_eq!(result.conflicts.len(), 0);
     }
+
+    // -----------------------------------------------------------------------
+    // Three-way merge
+    // -----------------------------------------------------------------------
+
+    /// Ancestor / base / incoming single-block documents at path "1.".
+    fn three_docs(ancestor: &str, base: &str, incoming: &str) -> [(Uuid, Vec<Block>); 3] {
+        [ancestor, base, incoming].map(|text| {
+            let doc = Uuid::new_v4();
+            (doc, vec![make_block(doc, "1.", text, 0)])
+        })
+    }
+
+    fn merge3(docs: &[(Uuid, Vec<Block>); 3]) -> MergeResult {
+        let [(a, ab), (b, bb), (i, ib)] = docs;
+        MergeEngine::new().merge_three_way(*a, *b, *i, ab, bb, ib)
+    }
+
+    const ORIGINAL: &str = "the borrower shall repay the loan on the first business day";
+
+    #[test]
+    fn one_sided_change_auto_resolves() {
+        let docs = three_docs(
+            ORIGINAL,
+            ORIGINAL,
+            "the borrower must repay the loan on the second business day",
+        );
+        let result = merge3(&docs);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.auto_resolved, 1);
+        assert_eq!(result.ancestor_doc_id, Some(docs[0].0));
+    }
+
+    #[test]
+    fn disjoint_concurrent_edits_auto_merge() {
+        let docs = three_docs(
+            ORIGINAL,
+            "the borrower must repay the loan on the first business day",
+            "the borrower shall repay the loan on the second business day",
+        );
+        // Two-way merge cannot tell whose edit is whose and reports conflicts.
+        let tokenized = |blocks: &[Block]| -> Vec<Block> {
+            blocks
+                .iter()
+                .map(|b| Block { tokens: ensure_tokens(b), ..b.clone() })
+                .collect()
+        };
+        let two_way = MergeEngine::new().merge(
+            docs[1].0,
+            docs[2].0,
+            &tokenized(&docs[1].1),
+            &tokenized(&docs[2].1),
+        );
+        assert!(!two_way.conflicts.is_empty());
+
+        let result = merge3(&docs);
+        assert!(result.conflicts.is_empty(), "{:?}", result.conflicts);
+        assert_eq!(result.auto_resolved, 1);
+    }
+
+    #[test]
+    fn overlapping_concurrent_edits_conflict() {
+        let docs = three_docs(
+            ORIGINAL,
+            "the borrower shall repay the loan on the third business day",
+            "the borrower shall repay the loan on the second business day",
+        );
+        let result = merge3(&docs);
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.conflict_type, ConflictType::ContentOverlap);
+        assert_eq!(conflict.block_id, docs[1].1[0].id);
+        assert_eq!(conflict.base_content.as_deref(), Some("third"));
+        assert_eq!(conflict.incoming_content.as_deref(), Some("second"));
+        assert_eq!(result.pending_review, 1);
+    }
+
+    #[test]
+    fn identical_concurrent_edits_do_not_conflict() {
+        let edited = "the borrower shall repay the loan on the last business day";
+        let result = merge3(&three_docs(ORIGINAL, edited, edited));
+        assert!(result.conflicts.is_empty());
+    }
+
+    #[test]
+    fn delete_versus_modify_conflicts() {
+        let ancestor_doc = Uuid::new_v4();
+        let ancestor = vec![
+            make_block(ancestor_doc, "1.", ORIGINAL, 0),
+            make_block(ancestor_doc, "2.", "notices must be given in writing to the agent", 1),
+        ];
+        let base_doc = Uuid::new_v4();
+        // Base deletes clause 2.
+        let base = vec![make_block(base_doc, "1.", ORIGINAL, 0)];
+        let inc_doc = Uuid::new_v4();
+        let incoming = vec![
+            make_block(inc_doc, "1.", ORIGINAL, 0),
+            make_block(inc_doc, "2.", "notices must be given by email to the agent", 1),
+        ];
+
+        let result = MergeEngine::new()
+            .merge_three_way(ancestor_doc, base_doc, inc_doc, &ancestor, &base, &incoming);
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.conflict_type, ConflictType::DeleteModify);
+        assert_eq!(conflict.block_id, ancestor[1].id);
+        assert_eq!(conflict.base_content, None);
+        assert_eq!(result.auto_resolved, 1);
+    }
+
+    #[test]
+    fn unmodified_block_deleted_on_one_side_auto_resolves() {
+        let ancestor_doc = Uuid::new_v4();
+        let ancestor = vec![
+            make_block(ancestor_doc, "1.", ORIGINAL, 0),
+            make_block(ancestor_doc, "2.", "notices must be given in writing to the agent", 1),
+        ];
+        let base_doc = Uuid::new_v4();
+        let base = vec![make_block(base_doc, "1.", ORIGINAL, 0)];
+
+        let result = MergeEngine::new()
+            .merge_three_way(ancestor_doc, base_doc, ancestor_doc, &ancestor, &base, &ancestor);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.auto_resolved, 2);
+    }
+
+    #[test]
+    fn three_way_auto_accept_rules_apply_to_incoming_changes() {
+        let ancestor_doc = Uuid::new_v4();
+        let ancestor = vec![
+            make_block(ancestor_doc, "1.", ORIGINAL, 0),
+            make_block(ancestor_doc, "2.", "notices must be given in writing to the agent", 1),
+        ];
+        let inc_doc = Uuid::new_v4();
+        let incoming = vec![
+            make_block(inc_doc, "1.", ORIGINAL, 0),
+            make_block(inc_doc, "3.", "this agreement is governed by english law", 1),
+        ];
+        let engine = MergeEngine::new().with_config(MergeConfig {
+            auto_accept_insertions: false,
+            auto_accept_deletions: false,
+            ..MergeConfig::default()
+        });
+
+        // Base is the ancestor: incoming deleted clause 2 and added clause 3.
+        let result =
+            engine.merge_three_way(ancestor_doc, ancestor_doc, inc_doc, &ancestor, &ancestor, &incoming);
+        assert_eq!(result.auto_resolved, 1);
+        let mut held: Vec<Uuid> = result.conflicts.iter().map(|c| c.block_id).collect();
+        held.sort();
+        let mut expected = vec![ancestor[1].id, incoming[1].id];
+        expected.sort();
+        assert_eq!(held, expected);
+    }
+
+    #[test]
+    fn two_way_result_omits_ancestor_in_json() {
+        let doc = Uuid::new_v4();
+        let blocks = vec![make_block(doc, "1.1", "text", 0)];
+        let result = MergeEngine::new().merge(doc, doc, &blocks, &blocks);
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json.get("ancestor_doc_id").is_none());
+    }
+
+    // -----------------------------------------------------------------------
+    // N-way layer merge
+    // -----------------------------------------------------------------------
+
+    fn layer_with(
+        doc: Uuid,
+        reviewer: &str,
+        edits: &[(Uuid, DeltaType, usize, usize, &str)],
+    ) -> LayerDeltas {
+        let layer = ReviewLayer::new(Uuid::nil(), reviewer, doc);
+        let deltas = edits
+            .iter()
+            .map(|(block, kind, start, end, text)| {
+                BlockDelta::new(
+                    layer.id,
+                    reviewer,
+                    *block,
+                    kind.clone(),
+                    *start,
+                    *end,
+                    serde_json::json!({ "text": text }),
+                )
+            })
+            .collect();
+        LayerDeltas { layer, deltas }
+    }
+
+    fn base_doc() -> (Uuid, Vec<Block>) {
+        let doc = Uuid::new_v4();
+        let blocks = vec![
+            make_block(doc, "1.", "the borrower shall repay the loan", 0),
+            make_block(doc, "2.", "interest accrues daily", 1),
+        ];
+        (doc, blocks)
+    }
+
+    #[test]
+    fn layers_editing_disjoint_blocks_auto_resolve() {
+        let (doc, base) = base_doc();
+        let layers = vec![
+            layer_with(doc, "alice", &[(base[0].id, DeltaType::Modify, 0, 1, "a")]),
+            layer_with(doc, "bob", &[(base[1].id, DeltaType::Modify, 0, 0, "b")]),
+            layer_with(doc, "carol", &[(base[0].id, DeltaType::Modify, 4, 5, "c")]),
+        ];
+        let result = MergeEngine::new().merge_layers(doc, &base, &layers).unwrap();
+        assert!(result.blocks.is_empty());
+        assert_eq!(result.auto_resolved, 2);
+        assert_eq!(result.layer_ids.len(), 3);
+    }
+
+    #[test]
+    fn conflicts_are_detected_pairwise_and_grouped_per_block() {
+        let (doc, base) = base_doc();
+        let block = base[0].id;
+        let layers = vec![
+            layer_with(doc, "alice", &[(block, DeltaType::Modify, 1, 2, "lender")]),
+            layer_with(doc, "bob", &[(block, DeltaType::Modify, 2, 3, "must")]),
+            layer_with(doc, "carol", &[(block, DeltaType::Modify, 0, 0, "a")]),
+            layer_with(doc, "dave", &[(block, DeltaType::Delete, 5, 5, "")]),
+        ];
+        let result = MergeEngine::new().merge_layers(doc, &base, &layers).unwrap();
+
+        assert_eq!(result.blocks.len(), 1);
+        let entry = &result.blocks[0];
+        assert_eq!(entry.block_id, block);
+        // alice×bob overlap, plus dave's delete against each of the others.
+        assert_eq!(entry.conflicts.len(), 4);
+        assert_eq!(entry.reviewer_ids, vec!["alice", "bob", "carol", "dave"]);
+        let overlap = entry
+            .conflicts
+            .iter()
+            .find(|c| c.conflict.conflict_type == ConflictType::ContentOverlap)
+            .unwrap();
+        assert_eq!(overlap.first_reviewer_id, "alice");
+        assert_eq!(overlap.second_reviewer_id, "bob");
+        assert_eq!(overlap.conflict.base_content.as_deref(), Some("lender"));
+        assert_eq!(result.pending_review, 4);
+        assert_eq!(result.auto_resolved, 0);
+    }
+
+    #[test]
+    fn identical_layer_edits_do_not_conflict() {
+        let (doc, base) = base_doc();
+        let edit = (base[1].id, DeltaType::Modify, 2, 2, "monthly");
+        let layers = vec![
+            layer_with(doc, "alice", std::slice::from_ref(&edit)),
+            layer_with(doc, "bob", &[edit]),
+        ];
+        let result = MergeEngine::new().merge_layers(doc, &base, &layers).unwrap();
+        assert!(result.blocks.is_empty());
+        assert_eq!(result.auto_resolved, 1);
+    }
+
+    #[test]
+    fn merge_layers_rejects_inconsistent_input() {
+        let (doc, base) = base_doc();
+        let engine = MergeEngine::new();
+
+        let foreign = layer_with(Uuid::new_v4(), "alice", &[]);
+        assert!(engine.merge_layers(doc, &base, &[foreign]).is_err());
+
+        let unknown_block = layer_with(doc, "alice", &[(Uuid::new_v4(), DeltaType::Insert, 0, 0, "x")]);
+        assert!(engine.merge_layers(doc, &base, &[unknown_block]).is_err());
+
+        let mut wrong_layer = layer_with(doc, "alice", &[(base[0].id, DeltaType::Insert, 0, 0, "x")]);
+        wrong_layer.deltas[0].review_layer_id = Uuid::new_v4();
+        assert!(engine.merge_layers(doc, &base, &[wrong_layer]).is_err());
+
+        let twice = layer_with(doc, "alice", &[]);
+        assert!(engine.merge_layers(doc, &base, &[twice.clone(), twice]).is_err());
+    }
+
+    #[test]
+    fn references_to_deleted_sub_clauses_are_reported() {
+        let base_doc = Uuid::new_v4();
+        let inc_doc = Uuid::new_v4();
+
+        let mut base_parent = make_block(base_doc, "1", "payment terms for the loan", 0);
+        base_parent.children = vec![
+            make_block(base_doc, "1.1", "the borrower shall repay the principal monthly", 0),
+            make_block(base_doc, "1.2", "late payments accrue a fee of two percent", 1),
+        ];
+        let base_blocks = vec![
+            base_parent,
+            make_block(base_doc, "2", "except as provided in Section 1.2, no fees apply", 1),
+        ];
+        let mut inc_parent = make_block(inc_doc, "1", "payment terms for the loan", 0);
+        inc_parent.children =
+            vec![make_block(inc_doc, "1.1", "the borrower shall repay the principal monthly", 0)];
+        let incoming_blocks = vec![
+            inc_parent,
+            make_block(inc_doc, "2", "except as provided in Section 1.2, no fees apply", 1),
+        ];
+
+        let result = MergeEngine::new().merge(base_doc, inc_doc, &base_blocks, &incoming_blocks);
+        assert_eq!(result.broken_references.len(), 1);
+        let broken = &result.broken_references[0];
+        assert_eq!(broken.kind, rt_compare::xref::BrokenReferenceKind::TargetDeleted);
+        assert_eq!(broken.target, "1.2");
+        assert_eq!(broken.block_id, incoming_blocks[1].id);
+    }
 }
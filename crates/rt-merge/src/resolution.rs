@@ -68,12 +68,7 @@ pub fn all_resolved(conflicts: &[MergeConflict]) -> bool {
 // ---------------------------------------------------------------------------
 
 fn resolution_name(r: &ConflictResolution) -> &'static str {
-    match r {
-        ConflictResolution::Pending => "pending",
-        ConflictResolution::AcceptedBase => "accepted_base",
-        ConflictResolution::AcceptedIncoming => "accepted_incoming",
-        ConflictResolution::Manual => "manual",
-    }
+    r.as_str()
 }
 
 // ---------------------------------------------------------------------------
@@ -0,0 +1,440 @@
+//! Merge runs that survive a crash.
+//!
+//! A merge is recorded in `merges` as soon as it starts, in the `running`
+//! state with a lease (`expires_at`) that the runner extends with
+//! heartbeats. When the run finishes its conflicts are saved and it becomes
+//! `completed`; when it fails it becomes `failed`. A process that dies
+//! mid-merge leaves its run `running` until the lease runs out, after which
+//! [`MergeStore::expire_stale_merge_runs`] marks it failed.
+//!
+//! Any run whose lease is not live can be re-run with [`resume_merge`]:
+//! conflict detection is repeated on the recorded documents, and conflicts
+//! that were already stored keep their id and resolution (see
+//! [`MergeStore::save_merge`]), so re-running a merge is idempotent.
+
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_core::db::BlockStore;
+use rt_core::hashing::{check_anchors, check_contracts};
+use rt_core::{CancelToken, RtError};
+
+use crate::merge::{MergeEngine, MergeResult};
+use crate::store::MergeStore;
+
+type Result<T> = std::result::Result<T, RtError>;
+
+/// Lease given to a merge run by `rtflow_merge` and friends, in seconds.
+pub const DEFAULT_MERGE_LEASE_SECS: i64 = 300;
+
+// ---------------------------------------------------------------------------
+// MergeRunState
+// ---------------------------------------------------------------------------
+
+/// Execution state of a merge run (`merges.run_state`). Independent of the
+/// review `status`, which tracks pending conflicts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeRunState {
+    Running,
+    Failed,
+    Completed,
+}
+
+impl MergeRunState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MergeRunState::Running => "running",
+            MergeRunState::Failed => "failed",
+            MergeRunState::Completed => "completed",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "running" => Ok(MergeRunState::Running),
+            "failed" => Ok(MergeRunState::Failed),
+            "completed" => Ok(MergeRunState::Completed),
+            other => Err(RtError::InvalidInput(format!("unknown merge run state: {other}"))),
+        }
+    }
+}
+
+impl fmt::Display for MergeRunState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// MergeRun
+// ---------------------------------------------------------------------------
+
+/// The inputs and execution state of one merge run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeRun {
+    pub merge_id: Uuid,
+    pub base_doc_id: Uuid,
+    pub incoming_doc_id: Uuid,
+    /// Set for three-way merges.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ancestor_doc_id: Option<Uuid>,
+    pub state: MergeRunState,
+    /// Last sign of life from the runner.
+    pub heartbeat_at: Option<DateTime<Utc>>,
+    /// When a `running` run is presumed dead; `None` once it has ended.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Why a `failed` run failed.
+    pub failure: Option<String>,
+}
+
+impl MergeRun {
+    /// A run of a new merge (fresh `merge_id`) that has not started yet.
+    pub fn new(base_doc_id: Uuid, incoming_doc_id: Uuid, ancestor_doc_id: Option<Uuid>) -> Self {
+        Self {
+            merge_id: Uuid::new_v4(),
+            base_doc_id,
+            incoming_doc_id,
+            ancestor_doc_id,
+            state: MergeRunState::Running,
+            heartbeat_at: None,
+            expires_at: None,
+            failure: None,
+        }
+    }
+
+    /// Whether the run is `running` but its lease ran out before `now`.
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        self.state == MergeRunState::Running && self.expires_at.is_none_or(|at| at <= now)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// run_merge / resume_merge
+// ---------------------------------------------------------------------------
+
+/// Execute `run` as a tracked merge: claim it in `merges` with a lease of
+/// `lease`, load the documents from `blocks`, merge them (three-way when
+/// `run.ancestor_doc_id` is set) and save the result under `run.merge_id`.
+///
+/// If anything fails after the run was claimed it is marked `failed` and
+/// the error returned.
+pub fn run_merge(
+    blocks: &dyn BlockStore,
+    merges: &dyn MergeStore,
+    engine: &MergeEngine,
+    run: &MergeRun,
+    lease: Duration,
+) -> Result<MergeResult> {
+    run_merge_cancellable(blocks, merges, engine, run, lease, &CancelToken::new())
+}
+
+/// As [`run_merge`], giving up with [`RtError::Cancelled`] (and marking the
+/// run `failed`, so it can be resumed) once `cancel` is cancelled. The token
+/// is checked once the documents are loaded and, for a two-way merge, as
+/// [`MergeEngine::merge_cancellable`] checks it.
+pub fn run_merge_cancellable(
+    blocks: &dyn BlockStore,
+    merges: &dyn MergeStore,
+    engine: &MergeEngine,
+    run: &MergeRun,
+    lease: Duration,
+    cancel: &CancelToken,
+) -> Result<MergeResult> {
+    merges.begin_merge_run(run, lease)?;
+    execute(blocks, merges, engine, run, lease, cancel).inspect_err(|e| {
+        // The original error matters more than a failure to record it.
+        let _ = merges.fail_merge_run(&run.merge_id, &e.to_string());
+    })
+}
+
+/// Re-run the merge recorded as `merge_id` on the same documents, keeping
+/// the resolutions of conflicts that are detected again. Fails if the run
+/// is still `running` with a live lease.
+pub fn resume_merge(
+    blocks: &dyn BlockStore,
+    merges: &dyn MergeStore,
+    engine: &MergeEngine,
+    merge_id: &Uuid,
+    lease: Duration,
+) -> Result<MergeResult> {
+    let run = merges.get_merge_run(merge_id)?;
+    run_merge(blocks, merges, engine, &run, lease)
+}
+
+fn execute(
+    blocks: &dyn BlockStore,
+    merges: &dyn MergeStore,
+    engine: &MergeEngine,
+    run: &MergeRun,
+    lease: Duration,
+    cancel: &CancelToken,
+) -> Result<MergeResult> {
+    let load = |id: &Uuid, role: &str| {
+        blocks
+            .get_block_tree(id)
+            .map_err(|e| RtError::Internal(format!("failed to load {role} document blocks: {e}")))
+    };
+    let mut ids = vec![run.base_doc_id, run.incoming_doc_id];
+    ids.extend(run.ancestor_doc_id);
+    let documents = ids.iter().map(|id| blocks.get_document(id)).collect::<Result<Vec<_>>>()?;
+    let documents = documents.iter().collect::<Vec<_>>();
+    check_contracts(&documents)?;
+    check_anchors(&documents)?;
+
+    let base = load(&run.base_doc_id, "base")?;
+    let incoming = load(&run.incoming_doc_id, "incoming")?;
+    let ancestor = run.ancestor_doc_id.map(|id| load(&id, "ancestor")).transpose()?;
+    merges.heartbeat_merge_run(&run.merge_id, lease)?;
+    cancel.check()?;
+
+    let mut result = match (&ancestor, run.ancestor_doc_id) {
+        (Some(ancestor), Some(ancestor_id)) => engine.merge_three_way(
+            ancestor_id,
+            run.base_doc_id,
+            run.incoming_doc_id,
+            ancestor,
+            &base,
+            &incoming,
+        ),
+        _ => engine.merge_cancellable(
+            run.base_doc_id,
+            run.incoming_doc_id,
+            &base,
+            &incoming,
+            cancel,
+        )?,
+    };
+    result.merge_id = run.merge_id;
+    if let Some(manifest) = &mut result.manifest {
+        let ids: Vec<Uuid> = manifest.inputs.iter().map(|i| i.document_id).collect();
+        for id in ids {
+            if let Ok(document) = blocks.get_document(&id) {
+                manifest.attach_document(&document);
+            }
+        }
+    }
+
+    merges.save_merge(&mut result)?;
+    Ok(result)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_core::db::{create_pool, DbPool, SqliteBlockStore};
+    use rt_core::schema::SCHEMA_VERSION;
+    use rt_core::{Block, BlockType, Document, DocumentType};
+    use tempfile::TempDir;
+
+    use crate::conflict::ConflictResolution;
+    use crate::store::SqliteMergeStore;
+
+    const ORIGINAL: &str = "the borrower shall repay the loan on the first business day";
+
+    fn store_doc(blocks: &SqliteBlockStore, text: &str) -> Uuid {
+        let doc = Document {
+            id: Uuid::new_v4(),
+            name: "doc".into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: SCHEMA_VERSION.into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        };
+        blocks.insert_document(&doc).unwrap();
+        let block = Block::new(BlockType::Clause, "1.", text, text, None, doc.id, 0);
+        blocks.insert_block(&block).unwrap();
+        doc.id
+    }
+
+    /// A file-backed pool holding ancestor, base and incoming documents whose
+    /// concurrent edits conflict, and a three-way run over them.
+    fn setup() -> (TempDir, DbPool, MergeRun) {
+        let dir = TempDir::new().unwrap();
+        let pool = create_pool(dir.path().join("run.db").to_str().unwrap()).unwrap();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let ancestor = store_doc(&blocks, ORIGINAL);
+        let base = store_doc(
+            &blocks,
+            "the borrower shall repay the loan on the third business day",
+        );
+        let incoming = store_doc(
+            &blocks,
+            "the borrower shall repay the loan on the second business day",
+        );
+        (dir, pool, MergeRun::new(base, incoming, Some(ancestor)))
+    }
+
+    fn lease() -> Duration {
+        Duration::seconds(DEFAULT_MERGE_LEASE_SECS)
+    }
+
+    #[test]
+    fn run_merge_records_a_completed_run() {
+        let (_dir, pool, run) = setup();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let merges = SqliteMergeStore::new(pool);
+
+        let result = run_merge(&blocks, &merges, &MergeEngine::new(), &run, lease()).unwrap();
+        assert_eq!(result.merge_id, run.merge_id);
+        assert_eq!(result.conflicts.len(), 1);
+
+        let stored = merges.get_merge_run(&run.merge_id).unwrap();
+        assert_eq!(stored.state, MergeRunState::Completed);
+        assert_eq!(stored.expires_at, None);
+        assert_eq!(stored.ancestor_doc_id, run.ancestor_doc_id);
+        assert!(stored.heartbeat_at.is_some());
+        assert_eq!(merges.get_merge(&run.merge_id).unwrap().conflicts[0].id, result.conflicts[0].id);
+    }
+
+    #[test]
+    fn documents_of_other_hash_contracts_fail_the_run() {
+        let (_dir, pool, run) = setup();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let merges = SqliteMergeStore::new(pool.clone());
+        pool.get()
+            .unwrap()
+            .execute(
+                "UPDATE documents SET hash_contract_version = '1.0.0-blake3' WHERE id = ?1",
+                [run.incoming_doc_id.to_string()],
+            )
+            .unwrap();
+
+        let err = run_merge(&blocks, &merges, &MergeEngine::new(), &run, lease()).unwrap_err();
+        assert!(err.to_string().contains("different contracts"), "{err}");
+        assert_eq!(merges.get_merge_run(&run.merge_id).unwrap().state, MergeRunState::Failed);
+    }
+
+    #[test]
+    fn cancelled_runs_fail_and_can_be_resumed() {
+        let (_dir, pool, run) = setup();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let merges = SqliteMergeStore::new(pool);
+        let run = MergeRun::new(run.base_doc_id, run.incoming_doc_id, None);
+        let cancel = CancelToken::new();
+        cancel.cancel();
+
+        let engine = MergeEngine::new();
+        let err =
+            run_merge_cancellable(&blocks, &merges, &engine, &run, lease(), &cancel).unwrap_err();
+        assert!(matches!(err, RtError::Cancelled), "{err:?}");
+        assert_eq!(merges.get_merge_run(&run.merge_id).unwrap().state, MergeRunState::Failed);
+
+        resume_merge(&blocks, &merges, &engine, &run.merge_id, lease()).unwrap();
+        assert_eq!(merges.get_merge_run(&run.merge_id).unwrap().state, MergeRunState::Completed);
+    }
+
+    #[test]
+    fn live_run_cannot_be_claimed_twice() {
+        let (_dir, pool, run) = setup();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let merges = SqliteMergeStore::new(pool);
+
+        let claimed = merges.begin_merge_run(&run, lease()).unwrap();
+        assert_eq!(claimed.state, MergeRunState::Running);
+        assert!(!claimed.is_stale(Utc::now()));
+
+        let err = resume_merge(&blocks, &merges, &MergeEngine::new(), &run.merge_id, lease())
+            .unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(_)), "{err:?}");
+        // The rejected claim did not disturb the live run.
+        assert_eq!(merges.get_merge_run(&run.merge_id).unwrap().state, MergeRunState::Running);
+    }
+
+    #[test]
+    fn crashed_run_expires_and_resumes() {
+        let (_dir, pool, run) = setup();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let merges = SqliteMergeStore::new(pool);
+
+        // A runner claims the merge and dies without a heartbeat.
+        merges.begin_merge_run(&run, lease()).unwrap();
+        assert!(merges.expire_stale_merge_runs(Utc::now()).unwrap().is_empty());
+
+        let later = Utc::now() + lease() + Duration::seconds(1);
+        assert_eq!(merges.expire_stale_merge_runs(later).unwrap(), vec![run.merge_id]);
+        let failed = merges.list_merge_runs(MergeRunState::Failed).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0].failure.as_deref().unwrap().starts_with("lease expired"));
+        assert!(merges.heartbeat_merge_run(&run.merge_id, lease()).is_err());
+
+        let result =
+            resume_merge(&blocks, &merges, &MergeEngine::new(), &run.merge_id, lease()).unwrap();
+        assert_eq!(result.conflicts.len(), 1);
+        let stored = merges.get_merge_run(&run.merge_id).unwrap();
+        assert_eq!(stored.state, MergeRunState::Completed);
+        assert_eq!(stored.failure, None);
+        assert!(merges.list_merge_runs(MergeRunState::Failed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rerun_keeps_conflict_ids_and_resolutions() {
+        let (_dir, pool, run) = setup();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let merges = SqliteMergeStore::new(pool);
+        let engine = MergeEngine::new();
+
+        let first = run_merge(&blocks, &merges, &engine, &run, lease()).unwrap();
+        let conflict_id = first.conflicts[0].id;
+        merges
+            .update_conflict_resolution(&conflict_id, ConflictResolution::AcceptedIncoming)
+            .unwrap();
+
+        let again = resume_merge(&blocks, &merges, &engine, &run.merge_id, lease()).unwrap();
+        assert_eq!(again.conflicts.len(), 1);
+        assert_eq!(again.conflicts[0].id, conflict_id);
+        assert_eq!(again.conflicts[0].resolution, ConflictResolution::AcceptedIncoming);
+        assert_eq!(again.pending_review, 0);
+
+        let loaded = merges.get_merge(&run.merge_id).unwrap();
+        assert_eq!(loaded.conflicts.len(), 1);
+        assert_eq!(loaded.conflicts[0].id, conflict_id);
+        assert_eq!(loaded.pending_review, 0);
+    }
+
+    #[test]
+    fn failed_runs_can_be_deleted() {
+        let (_dir, pool, run) = setup();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let merges = SqliteMergeStore::new(pool);
+
+        run_merge(&blocks, &merges, &MergeEngine::new(), &run, lease()).unwrap();
+        let err = merges.delete_merge_run(&run.merge_id).unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(_)), "{err:?}");
+
+        merges.begin_merge_run(&run, lease()).unwrap();
+        merges.fail_merge_run(&run.merge_id, "runner aborted").unwrap();
+        let failed = merges.get_merge_run(&run.merge_id).unwrap();
+        assert_eq!(failed.failure.as_deref(), Some("runner aborted"));
+        // Conflicts of the earlier completed run survive the failure.
+        assert_eq!(merges.list_conflicts(&run.merge_id).unwrap().len(), 1);
+
+        merges.delete_merge_run(&run.merge_id).unwrap();
+        assert!(matches!(merges.get_merge_run(&run.merge_id), Err(RtError::NotFound(_))));
+        assert!(matches!(merges.get_merge(&run.merge_id), Err(RtError::NotFound(_))));
+    }
+
+    #[test]
+    fn run_on_missing_document_is_rejected() {
+        let (_dir, pool, mut run) = setup();
+        let blocks = SqliteBlockStore::new(pool.clone());
+        let merges = SqliteMergeStore::new(pool);
+
+        run.incoming_doc_id = Uuid::new_v4();
+        let err = run_merge(&blocks, &merges, &MergeEngine::new(), &run, lease()).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)), "{err:?}");
+        assert!(matches!(merges.get_merge_run(&run.merge_id), Err(RtError::NotFound(_))));
+    }
+}
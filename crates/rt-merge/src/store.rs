@@ -0,0 +1,1068 @@
+//! Persistence for merge runs in the `merges` and `conflicts` tables.
+//!
+//! A saved merge can be reloaded in a later session with
+//! [`MergeStore::get_merge`], and its conflicts resolved one at a time with
+//! [`MergeStore::update_conflict_resolution`]; the merge's `status` column
+//! tracks whether any conflict is still pending. Resolution suggestions are
+//! kept per conflict in `conflict_suggestions`, and the merge's manifest in
+//! the run history (`run_manifests`).
+//!
+//! Each merge row also carries its run lifecycle (`run_state`, heartbeat
+//! and lease expiry); see [`crate::run`].
+//!
+//! Submitted review layers and their deltas are recorded in `review_layers`
+//! and `block_deltas`, where `rt_core::review_activity` aggregates them.
+//!
+//! A store is scoped to one tenant: merges are stamped with its `tenant_id`
+//! and merges, conflicts and suggestions of other tenants are not found.
+
+use chrono::{DateTime, Duration, Utc};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, OptionalExtension, TransactionBehavior};
+use uuid::Uuid;
+
+use rt_core::db::DbPool;
+use rt_core::review_activity::activity_timestamp;
+use rt_core::run_history::{find_run, record_run, RunKind};
+use rt_core::tenant::{ensure_document, TenantContext};
+use rt_core::usage::{record_usage, UsageMetric};
+use rt_core::RtError;
+
+use crate::conflict::{ConflictResolution, ConflictType, MergeConflict};
+use crate::layer::LayerDeltas;
+use crate::merge::MergeResult;
+use crate::resolution::validate_resolution;
+use crate::run::{MergeRun, MergeRunState};
+use crate::suggest::{ResolutionSuggestion, SuggestionRule};
+
+type Result<T> = std::result::Result<T, RtError>;
+
+/// `merges.status` while at least one conflict is pending.
+pub const STATUS_PENDING_REVIEW: &str = "pending_review";
+/// `merges.status` once every conflict has been resolved.
+pub const STATUS_RESOLVED: &str = "resolved";
+
+// ---------------------------------------------------------------------------
+// MergeStore trait
+// ---------------------------------------------------------------------------
+
+/// Persistence interface for merge runs and their conflicts.
+pub trait MergeStore: Send + Sync {
+    /// Record `result` and all of its conflicts in one transaction and mark
+    /// the run `completed`.
+    ///
+    /// Saving a merge that is already stored (a resumed or repeated run)
+    /// replaces its conflicts: a new conflict on the same block and of the
+    /// same type as a stored one takes over the stored conflict's id and
+    /// resolution, which are written back into `result`; stored conflicts
+    /// not detected again are removed.
+    ///
+    /// `output_doc_id` is stored only when it names an existing document;
+    /// otherwise it is recorded as `NULL` and reloads as `None`.
+    fn save_merge(&self, result: &mut MergeResult) -> Result<()>;
+    /// Reload a merge run, with `pending_review` recomputed from the stored
+    /// conflict resolutions.
+    fn get_merge(&self, merge_id: &Uuid) -> Result<MergeResult>;
+    /// Every conflict recorded for `merge_id`, in the order they were saved.
+    fn list_conflicts(&self, merge_id: &Uuid) -> Result<Vec<MergeConflict>>;
+    /// Move a conflict to `resolution` (subject to [`validate_resolution`])
+    /// and refresh its merge's status. Returns the updated conflict.
+    fn update_conflict_resolution(
+        &self,
+        conflict_id: &Uuid,
+        resolution: ConflictResolution,
+    ) -> Result<MergeConflict>;
+    /// Replace the stored suggestions of every conflict that appears in
+    /// `suggestions` with those given. Nothing is applied to the conflicts.
+    fn save_suggestions(&self, suggestions: &[ResolutionSuggestion]) -> Result<()>;
+    /// Stored suggestions for the conflicts of `merge_id`, in conflict order
+    /// and by rank within each conflict.
+    fn list_suggestions(&self, merge_id: &Uuid) -> Result<Vec<ResolutionSuggestion>>;
+    /// Claim `run.merge_id` for a runner: record the run as `running` with a
+    /// lease of `lease` from now and return it as stored. An existing merge
+    /// (of the same documents) may be claimed again unless it is running
+    /// with a live lease.
+    fn begin_merge_run(&self, run: &MergeRun, lease: Duration) -> Result<MergeRun>;
+    /// Extend the lease of a running merge to `lease` from now. Fails once
+    /// the run is no longer `running`, e.g. after it was expired.
+    fn heartbeat_merge_run(&self, merge_id: &Uuid, lease: Duration) -> Result<()>;
+    /// Mark a running merge `failed` with `failure`. Conflicts stored by an
+    /// earlier completed run are kept.
+    fn fail_merge_run(&self, merge_id: &Uuid, failure: &str) -> Result<()>;
+    fn get_merge_run(&self, merge_id: &Uuid) -> Result<MergeRun>;
+    /// Merge runs in `state`, oldest first.
+    fn list_merge_runs(&self, state: MergeRunState) -> Result<Vec<MergeRun>>;
+    /// Mark every running merge whose lease expired at or before `now`
+    /// `failed`, returning their ids.
+    fn expire_stale_merge_runs(&self, now: DateTime<Utc>) -> Result<Vec<Uuid>>;
+    /// Delete a `failed` merge run with its conflicts and suggestions.
+    fn delete_merge_run(&self, merge_id: &Uuid) -> Result<()>;
+    /// Record a review layer and its deltas. Every delta must belong to the
+    /// layer and change a block of the layer's document. Deltas already
+    /// stored are left unchanged, so a layer can be resubmitted as it grows.
+    fn save_layer_deltas(&self, layer: &LayerDeltas) -> Result<()>;
+}
+
+// ---------------------------------------------------------------------------
+// SqliteMergeStore
+// ---------------------------------------------------------------------------
+
+pub struct SqliteMergeStore {
+    pool: DbPool,
+    tenant: TenantContext,
+}
+
+impl SqliteMergeStore {
+    /// A store acting for the default tenant.
+    pub fn new(pool: DbPool) -> Self {
+        Self::with_tenant(pool, TenantContext::default())
+    }
+
+    pub fn with_tenant(pool: DbPool, tenant: TenantContext) -> Self {
+        Self { pool, tenant }
+    }
+
+    pub fn tenant(&self) -> &TenantContext {
+        &self.tenant
+    }
+
+    /// Fail with [`RtError::NotFound`] unless `merge_id` is one of this
+    /// tenant's merges.
+    fn ensure_merge(&self, conn: &rusqlite::Connection, merge_id: &Uuid) -> Result<()> {
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM merges WHERE id = ?1 AND tenant_id = ?2",
+                params![merge_id.to_string(), self.tenant.id()],
+                |_| Ok(()),
+            )
+            .optional()?;
+        if exists.is_none() {
+            return Err(RtError::NotFound(format!("merge {merge_id}")));
+        }
+        Ok(())
+    }
+
+    /// This tenant's run of `merge_id`, if any. A merge id taken by another
+    /// tenant is reported as [`RtError::NotFound`], since it cannot be
+    /// claimed.
+    fn find_run(&self, conn: &rusqlite::Connection, merge_id: &Uuid) -> Result<Option<MergeRun>> {
+        let row = conn
+            .query_row(
+                &format!("SELECT {MERGE_RUN_COLUMNS}, tenant_id FROM merges WHERE id = ?1"),
+                params![merge_id.to_string()],
+                |row| Ok((merge_run_row(row)?, row.get::<_, String>(8)?)),
+            )
+            .optional()?;
+        match row {
+            None => Ok(None),
+            Some((_, tenant)) if tenant != self.tenant.id() => {
+                Err(RtError::NotFound(format!("merge {merge_id}")))
+            }
+            Some((run, _)) => row_to_merge_run(run).map(Some),
+        }
+    }
+
+    fn conn(&self) -> Result<r2d2::PooledConnection<SqliteConnectionManager>> {
+        self.pool
+            .get()
+            .map_err(|e| RtError::Internal(e.to_string()))
+    }
+}
+
+impl MergeStore for SqliteMergeStore {
+    fn save_merge(&self, result: &mut MergeResult) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        for doc_id in [Some(result.base_doc_id), Some(result.incoming_doc_id), result.ancestor_doc_id]
+            .into_iter()
+            .flatten()
+        {
+            ensure_document(&tx, &self.tenant, &doc_id)?;
+        }
+        let output_doc_id = match result.output_doc_id {
+            Some(id) => tx
+                .query_row(
+                    "SELECT id FROM documents WHERE id = ?1 AND tenant_id = ?2",
+                    params![id.to_string(), self.tenant.id()],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?,
+            None => None,
+        };
+
+        let merge_id = result.merge_id.to_string();
+        let now = Utc::now().to_rfc3339();
+        let stored = self.find_run(&tx, &result.merge_id)?;
+        if stored.is_some() {
+            reattach_conflicts(&tx, result)?;
+            tx.execute(
+                "UPDATE merges
+                    SET output_doc_id = ?2, status = ?3, auto_resolved = ?4,
+                        run_state = 'completed', heartbeat_at = ?5, expires_at = NULL,
+                        failure = NULL
+                  WHERE id = ?1",
+                params![
+                    merge_id,
+                    output_doc_id,
+                    merge_status(&result.conflicts),
+                    result.auto_resolved as i64,
+                    now,
+                ],
+            )?;
+        } else {
+            tx.execute(
+                "INSERT INTO merges
+                    (id, base_doc_id, incoming_doc_id, ancestor_doc_id, output_doc_id,
+                     status, auto_resolved, created_at, tenant_id, run_state, heartbeat_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'completed', ?8)",
+                params![
+                    merge_id,
+                    result.base_doc_id.to_string(),
+                    result.incoming_doc_id.to_string(),
+                    result.ancestor_doc_id.map(|id| id.to_string()),
+                    output_doc_id,
+                    merge_status(&result.conflicts),
+                    result.auto_resolved as i64,
+                    now,
+                    self.tenant.id(),
+                ],
+            )?;
+        }
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO conflicts
+                    (id, merge_id, block_id, conflict_type, base_content,
+                     incoming_content, resolution)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(id) DO UPDATE SET
+                    base_content     = excluded.base_content,
+                    incoming_content = excluded.incoming_content",
+            )?;
+            for conflict in &result.conflicts {
+                stmt.execute(params![
+                    conflict.id.to_string(),
+                    merge_id,
+                    conflict.block_id.to_string(),
+                    conflict.conflict_type.as_str(),
+                    conflict.base_content,
+                    conflict.incoming_content,
+                    conflict.resolution.as_str(),
+                ])?;
+            }
+        }
+        if let Some(manifest) = &result.manifest {
+            record_run(&tx, &self.tenant, &result.merge_id, RunKind::Merge, manifest)?;
+        }
+        record_usage(&tx, &self.tenant, UsageMetric::MergeRuns, 1)?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn get_merge(&self, merge_id: &Uuid) -> Result<MergeResult> {
+        let conn = self.conn()?;
+
+        let row = conn
+            .query_row(
+                "SELECT base_doc_id, incoming_doc_id, ancestor_doc_id, output_doc_id,
+                        auto_resolved
+                   FROM merges
+                  WHERE id = ?1 AND tenant_id = ?2",
+                params![merge_id.to_string(), self.tenant.id()],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, i64>(4)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((base, incoming, ancestor, output, auto_resolved)) = row else {
+            return Err(RtError::NotFound(format!("merge {merge_id}")));
+        };
+
+        let conflicts = query_conflicts(&conn, merge_id)?;
+        let pending_review = conflicts.iter().filter(|c| !c.is_resolved()).count();
+        let manifest = find_run(&conn, &self.tenant, merge_id)?.map(|run| run.manifest);
+
+        Ok(MergeResult {
+            merge_id: *merge_id,
+            base_doc_id: parse_uuid(&base)?,
+            incoming_doc_id: parse_uuid(&incoming)?,
+            ancestor_doc_id: ancestor.as_deref().map(parse_uuid).transpose()?,
+            output_doc_id: output.as_deref().map(parse_uuid).transpose()?,
+            conflicts,
+            auto_resolved: auto_resolved as usize,
+            pending_review,
+            // Not persisted: recomputed only by a fresh merge.
+            broken_references: Vec::new(),
+            manifest,
+        })
+    }
+
+    fn list_conflicts(&self, merge_id: &Uuid) -> Result<Vec<MergeConflict>> {
+        let conn = self.conn()?;
+        self.ensure_merge(&conn, merge_id)?;
+        query_conflicts(&conn, merge_id)
+    }
+
+    fn update_conflict_resolution(
+        &self,
+        conflict_id: &Uuid,
+        resolution: ConflictResolution,
+    ) -> Result<MergeConflict> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let merge_id: Option<String> = tx
+            .query_row(
+                "SELECT c.merge_id FROM conflicts c
+                   JOIN merges m ON m.id = c.merge_id
+                  WHERE c.id = ?1 AND m.tenant_id = ?2",
+                params![conflict_id.to_string(), self.tenant.id()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(merge_id) = merge_id else {
+            return Err(RtError::NotFound(format!("conflict {conflict_id}")));
+        };
+        let merge_id = parse_uuid(&merge_id)?;
+
+        let mut conflicts = query_conflicts(&tx, &merge_id)?;
+        let conflict = conflicts
+            .iter_mut()
+            .find(|c| c.id == *conflict_id)
+            .ok_or_else(|| RtError::Internal(format!("conflict {conflict_id} vanished")))?;
+        validate_resolution(&conflict.resolution, &resolution)?;
+        conflict.resolution = resolution;
+        let updated = conflict.clone();
+
+        tx.execute(
+            "UPDATE conflicts SET resolution = ?1 WHERE id = ?2",
+            params![updated.resolution.as_str(), conflict_id.to_string()],
+        )?;
+        tx.execute(
+            "UPDATE merges SET status = ?1 WHERE id = ?2",
+            params![merge_status(&conflicts), merge_id.to_string()],
+        )?;
+
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    fn save_suggestions(&self, suggestions: &[ResolutionSuggestion]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let now = Utc::now().to_rfc3339();
+
+        let mut cleared: Vec<Uuid> = Vec::new();
+        for s in suggestions {
+            if !cleared.contains(&s.conflict_id) {
+                let exists = tx
+                    .query_row(
+                        "SELECT 1 FROM conflicts c
+                           JOIN merges m ON m.id = c.merge_id
+                          WHERE c.id = ?1 AND m.tenant_id = ?2",
+                        params![s.conflict_id.to_string(), self.tenant.id()],
+                        |_| Ok(()),
+                    )
+                    .optional()?;
+                if exists.is_none() {
+                    return Err(RtError::NotFound(format!("conflict {}", s.conflict_id)));
+                }
+                tx.execute(
+                    "DELETE FROM conflict_suggestions WHERE conflict_id = ?1",
+                    params![s.conflict_id.to_string()],
+                )?;
+                cleared.push(s.conflict_id);
+            }
+            tx.execute(
+                "INSERT INTO conflict_suggestions
+                    (conflict_id, rank, resolution, rule, confidence, rationale, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    s.conflict_id.to_string(),
+                    s.rank,
+                    s.resolution.as_str(),
+                    s.rule.as_str(),
+                    s.confidence,
+                    s.rationale,
+                    now,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn list_suggestions(&self, merge_id: &Uuid) -> Result<Vec<ResolutionSuggestion>> {
+        let conn = self.conn()?;
+        self.ensure_merge(&conn, merge_id)?;
+
+        let mut stmt = conn.prepare(
+            "SELECT s.conflict_id, s.rank, s.resolution, s.rule, s.confidence, s.rationale
+               FROM conflict_suggestions s
+               JOIN conflicts c ON c.id = s.conflict_id
+              WHERE c.merge_id = ?1
+              ORDER BY c.rowid ASC, s.rank ASC",
+        )?;
+        let rows = stmt.query_map(params![merge_id.to_string()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, u32>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?;
+
+        let mut suggestions = Vec::new();
+        for row in rows {
+            let r = row?;
+            suggestions.push(ResolutionSuggestion {
+                conflict_id: parse_uuid(&r.0)?,
+                rank: r.1,
+                resolution: ConflictResolution::from_str(&r.2)?,
+                rule: SuggestionRule::from_str(&r.3)?,
+                confidence: r.4,
+                rationale: r.5,
+            });
+        }
+        Ok(suggestions)
+    }
+
+    fn begin_merge_run(&self, run: &MergeRun, lease: Duration) -> Result<MergeRun> {
+        if lease <= Duration::zero() {
+            return Err(RtError::InvalidInput(format!("lease must be positive, got {lease}")));
+        }
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        for doc_id in [Some(run.base_doc_id), Some(run.incoming_doc_id), run.ancestor_doc_id]
+            .into_iter()
+            .flatten()
+        {
+            ensure_document(&tx, &self.tenant, &doc_id)?;
+        }
+
+        let now = Utc::now();
+        let expires_at = now + lease;
+        match self.find_run(&tx, &run.merge_id)? {
+            Some(stored) => {
+                if stored.state == MergeRunState::Running && !stored.is_stale(now) {
+                    return Err(RtError::InvalidInput(format!(
+                        "merge {} is already running until {}",
+                        run.merge_id,
+                        stored.expires_at.map(|t| t.to_rfc3339()).unwrap_or_default()
+                    )));
+                }
+                if (stored.base_doc_id, stored.incoming_doc_id, stored.ancestor_doc_id)
+                    != (run.base_doc_id, run.incoming_doc_id, run.ancestor_doc_id)
+                {
+                    return Err(RtError::InvalidInput(format!(
+                        "merge {} was recorded for different documents",
+                        run.merge_id
+                    )));
+                }
+                tx.execute(
+                    "UPDATE merges
+                        SET run_state = 'running', heartbeat_at = ?2, expires_at = ?3,
+                            failure = NULL
+                      WHERE id = ?1",
+                    params![run.merge_id.to_string(), now.to_rfc3339(), expires_at.to_rfc3339()],
+                )?;
+            }
+            None => {
+                tx.execute(
+                    "INSERT INTO merges
+                        (id, base_doc_id, incoming_doc_id, ancestor_doc_id, status,
+                         created_at, tenant_id, run_state, heartbeat_at, expires_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'running', ?6, ?8)",
+                    params![
+                        run.merge_id.to_string(),
+                        run.base_doc_id.to_string(),
+                        run.incoming_doc_id.to_string(),
+                        run.ancestor_doc_id.map(|id| id.to_string()),
+                        STATUS_PENDING_REVIEW,
+                        now.to_rfc3339(),
+                        self.tenant.id(),
+                        expires_at.to_rfc3339(),
+                    ],
+                )?;
+            }
+        }
+
+        let claimed = self
+            .find_run(&tx, &run.merge_id)?
+            .ok_or_else(|| RtError::Internal(format!("merge {} vanished", run.merge_id)))?;
+        tx.commit()?;
+        Ok(claimed)
+    }
+
+    fn heartbeat_merge_run(&self, merge_id: &Uuid, lease: Duration) -> Result<()> {
+        if lease <= Duration::zero() {
+            return Err(RtError::InvalidInput(format!("lease must be positive, got {lease}")));
+        }
+        let conn = self.conn()?;
+        let now = Utc::now();
+        let updated = conn.execute(
+            "UPDATE merges SET heartbeat_at = ?3, expires_at = ?4
+              WHERE id = ?1 AND tenant_id = ?2 AND run_state = 'running'",
+            params![
+                merge_id.to_string(),
+                self.tenant.id(),
+                now.to_rfc3339(),
+                (now + lease).to_rfc3339(),
+            ],
+        )?;
+        if updated == 0 {
+            self.ensure_merge(&conn, merge_id)?;
+            return Err(RtError::InvalidInput(format!("merge {merge_id} is not running")));
+        }
+        Ok(())
+    }
+
+    fn fail_merge_run(&self, merge_id: &Uuid, failure: &str) -> Result<()> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE merges SET run_state = 'failed', expires_at = NULL, failure = ?3
+              WHERE id = ?1 AND tenant_id = ?2 AND run_state = 'running'",
+            params![merge_id.to_string(), self.tenant.id(), failure],
+        )?;
+        if updated == 0 {
+            self.ensure_merge(&conn, merge_id)?;
+            return Err(RtError::InvalidInput(format!("merge {merge_id} is not running")));
+        }
+        Ok(())
+    }
+
+    fn get_merge_run(&self, merge_id: &Uuid) -> Result<MergeRun> {
+        let conn = self.conn()?;
+        self.find_run(&conn, merge_id)?
+            .ok_or_else(|| RtError::NotFound(format!("merge {merge_id}")))
+    }
+
+    fn list_merge_runs(&self, state: MergeRunState) -> Result<Vec<MergeRun>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {MERGE_RUN_COLUMNS} FROM merges
+              WHERE tenant_id = ?1 AND run_state = ?2
+              ORDER BY created_at ASC, rowid ASC"
+        ))?;
+        let rows = stmt.query_map(params![self.tenant.id(), state.as_str()], merge_run_row)?;
+        rows.map(|row| row_to_merge_run(row?)).collect()
+    }
+
+    fn expire_stale_merge_runs(&self, now: DateTime<Utc>) -> Result<Vec<Uuid>> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        // `expires_at` is written with `to_rfc3339()`; a bound formatted the
+        // same way compares correctly as text.
+        let expired: Vec<String> = {
+            let mut stmt = tx.prepare(
+                "SELECT id FROM merges
+                  WHERE tenant_id = ?1 AND run_state = 'running'
+                    AND (expires_at IS NULL OR expires_at <= ?2)
+                  ORDER BY created_at ASC, rowid ASC",
+            )?;
+            let rows = stmt.query_map(params![self.tenant.id(), now.to_rfc3339()], |row| {
+                row.get(0)
+            })?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        for id in &expired {
+            tx.execute(
+                "UPDATE merges
+                    SET run_state = 'failed', failure = 'lease expired at ' || expires_at,
+                        expires_at = NULL
+                  WHERE id = ?1",
+                params![id],
+            )?;
+        }
+        tx.commit()?;
+        expired.iter().map(|id| parse_uuid(id)).collect()
+    }
+
+    fn delete_merge_run(&self, merge_id: &Uuid) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let Some(run) = self.find_run(&tx, merge_id)? else {
+            return Err(RtError::NotFound(format!("merge {merge_id}")));
+        };
+        if run.state != MergeRunState::Failed {
+            return Err(RtError::InvalidInput(format!(
+                "merge {merge_id} is {}; only failed runs can be deleted",
+                run.state
+            )));
+        }
+        // Conflicts and their suggestions cascade.
+        tx.execute("DELETE FROM merges WHERE id = ?1", params![merge_id.to_string()])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_layer_deltas(&self, layer_deltas: &LayerDeltas) -> Result<()> {
+        let layer = &layer_deltas.layer;
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        ensure_document(&tx, &self.tenant, &layer.document_id)?;
+
+        let stored_doc: Option<String> = tx
+            .query_row(
+                "SELECT document_id FROM review_layers WHERE id = ?1",
+                params![layer.id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match stored_doc {
+            Some(doc) if doc != layer.document_id.to_string() => {
+                return Err(RtError::NotFound(format!("review layer {}", layer.id)));
+            }
+            Some(_) => {}
+            None => {
+                tx.execute(
+                    "INSERT INTO review_layers (id, workflow_id, reviewer_id, document_id, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        layer.id.to_string(),
+                        layer.workflow_id.to_string(),
+                        layer.reviewer_id,
+                        layer.document_id.to_string(),
+                        activity_timestamp(&layer.created_at),
+                    ],
+                )?;
+            }
+        }
+
+        {
+            let mut on_document =
+                tx.prepare("SELECT 1 FROM blocks WHERE id = ?1 AND document_id = ?2")?;
+            let mut insert = tx.prepare(
+                "INSERT INTO block_deltas
+                    (id, review_layer_id, reviewer_id, block_id, delta_type,
+                     token_start, token_end, delta_payload, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT(id) DO NOTHING",
+            )?;
+            for delta in &layer_deltas.deltas {
+                if delta.review_layer_id != layer.id {
+                    return Err(RtError::InvalidInput(format!(
+                        "delta {} belongs to review layer {}, not {}",
+                        delta.id, delta.review_layer_id, layer.id
+                    )));
+                }
+                let found = on_document
+                    .query_row(
+                        params![delta.block_id.to_string(), layer.document_id.to_string()],
+                        |_| Ok(()),
+                    )
+                    .optional()?;
+                if found.is_none() {
+                    return Err(RtError::NotFound(format!("block {}", delta.block_id)));
+                }
+                insert.execute(params![
+                    delta.id.to_string(),
+                    layer.id.to_string(),
+                    delta.reviewer_id,
+                    delta.block_id.to_string(),
+                    delta.delta_type.as_str(),
+                    delta.token_start as i64,
+                    delta.token_end as i64,
+                    serde_json::to_string(&delta.delta_payload)?,
+                    activity_timestamp(&delta.created_at),
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// Columns read by [`merge_run_row`], in order.
+const MERGE_RUN_COLUMNS: &str = "id, base_doc_id, incoming_doc_id, ancestor_doc_id, run_state, \
+     heartbeat_at, expires_at, failure";
+
+type MergeRunRow = (
+    String,
+    String,
+    String,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+fn merge_run_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<MergeRunRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+    ))
+}
+
+fn row_to_merge_run(r: MergeRunRow) -> Result<MergeRun> {
+    let parse_time = |s: Option<String>| {
+        s.map(|s| {
+            s.parse::<DateTime<Utc>>()
+                .map_err(|e| RtError::InvalidInput(e.to_string()))
+        })
+        .transpose()
+    };
+    Ok(MergeRun {
+        merge_id: parse_uuid(&r.0)?,
+        base_doc_id: parse_uuid(&r.1)?,
+        incoming_doc_id: parse_uuid(&r.2)?,
+        ancestor_doc_id: r.3.as_deref().map(parse_uuid).transpose()?,
+        state: MergeRunState::from_str(&r.4)?,
+        heartbeat_at: parse_time(r.5)?,
+        expires_at: parse_time(r.6)?,
+        failure: r.7,
+    })
+}
+
+/// Give the conflicts of `result` the ids and resolutions of the matching
+/// conflicts already stored for its merge (same block, same type, in order),
+/// delete the stored conflicts that no longer match, and refresh
+/// `result.pending_review`.
+fn reattach_conflicts(conn: &rusqlite::Connection, result: &mut MergeResult) -> Result<()> {
+    let mut stored: Vec<Option<MergeConflict>> =
+        query_conflicts(conn, &result.merge_id)?.into_iter().map(Some).collect();
+    for conflict in &mut result.conflicts {
+        let matched = stored.iter_mut().find(|slot| {
+            slot.as_ref().is_some_and(|s| {
+                s.block_id == conflict.block_id && s.conflict_type == conflict.conflict_type
+            })
+        });
+        if let Some(previous) = matched.and_then(Option::take) {
+            conflict.id = previous.id;
+            conflict.resolution = previous.resolution;
+        }
+    }
+    for gone in stored.into_iter().flatten() {
+        conn.execute("DELETE FROM conflicts WHERE id = ?1", params![gone.id.to_string()])?;
+    }
+    result.pending_review = result.conflicts.iter().filter(|c| !c.is_resolved()).count();
+    Ok(())
+}
+
+fn merge_status(conflicts: &[MergeConflict]) -> &'static str {
+    if conflicts.iter().any(|c| !c.is_resolved()) {
+        STATUS_PENDING_REVIEW
+    } else {
+        STATUS_RESOLVED
+    }
+}
+
+fn query_conflicts(conn: &rusqlite::Connection, merge_id: &Uuid) -> Result<Vec<MergeConflict>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, block_id, conflict_type, base_content, incoming_content, resolution
+           FROM conflicts
+          WHERE merge_id = ?1
+          ORDER BY rowid ASC",
+    )?;
+    let rows = stmt.query_map(params![merge_id.to_string()], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, String>(5)?,
+        ))
+    })?;
+
+    let mut conflicts = Vec::new();
+    for row in rows {
+        let r = row?;
+        conflicts.push(MergeConflict {
+            id: parse_uuid(&r.0)?,
+            block_id: parse_uuid(&r.1)?,
+            conflict_type: ConflictType::from_str(&r.2)?,
+            base_content: r.3,
+            incoming_content: r.4,
+            resolution: ConflictResolution::from_str(&r.5)?,
+        });
+    }
+    Ok(conflicts)
+}
+
+fn parse_uuid(s: &str) -> Result<Uuid> {
+    Uuid::parse_str(s).map_err(|e| RtError::InvalidInput(e.to_string()))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_core::db::{create_pool, BlockStore, SqliteBlockStore};
+    use rt_core::schema::SCHEMA_VERSION;
+    use rt_core::manifest::InputDigest;
+    use rt_core::{Block, BlockType, Document, DocumentType};
+    use tempfile::TempDir;
+
+    use crate::merge::MergeEngine;
+
+    fn make_doc(name: &str, doc_type: DocumentType) -> Document {
+        Document {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            source_path: None,
+            doc_type,
+            schema_version: SCHEMA_VERSION.into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        }
+    }
+
+    /// Store two documents (one block in the base) and return a merge result
+    /// with one pending conflict on that block. The pool is file-backed so
+    /// every pooled connection sees the same data.
+    fn setup() -> (TempDir, DbPool, MergeResult) {
+        let dir = TempDir::new().unwrap();
+        let pool = create_pool(dir.path().join("merge.db").to_str().unwrap()).unwrap();
+        let blocks = SqliteBlockStore::new(pool.clone());
+
+        let base = make_doc("base", DocumentType::Original);
+        let incoming = make_doc("incoming", DocumentType::Redline);
+        blocks.insert_document(&base).unwrap();
+        blocks.insert_document(&incoming).unwrap();
+        let block = Block::new(BlockType::Clause, "1.", "a", "a", None, base.id, 0);
+        blocks.insert_block(&block).unwrap();
+
+        let result = MergeResult {
+            merge_id: Uuid::new_v4(),
+            base_doc_id: base.id,
+            incoming_doc_id: incoming.id,
+            ancestor_doc_id: None,
+            output_doc_id: Some(Uuid::new_v4()),
+            conflicts: vec![MergeConflict::new(
+                block.id,
+                ConflictType::ContentOverlap,
+                Some("a".into()),
+                Some("b".into()),
+            )],
+            auto_resolved: 3,
+            pending_review: 1,
+            broken_references: vec![],
+            manifest: None,
+        };
+        (dir, pool, result)
+    }
+
+    fn status(pool: &DbPool, merge_id: &Uuid) -> String {
+        pool.get()
+            .unwrap()
+            .query_row(
+                "SELECT status FROM merges WHERE id = ?1",
+                params![merge_id.to_string()],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn save_and_reload_round_trips() {
+        let (_dir, pool, mut result) = setup();
+        let store = SqliteMergeStore::new(pool.clone());
+        store.save_merge(&mut result).unwrap();
+
+        let loaded = store.get_merge(&result.merge_id).unwrap();
+        assert_eq!(loaded.base_doc_id, result.base_doc_id);
+        assert_eq!(loaded.incoming_doc_id, result.incoming_doc_id);
+        // The output id names no stored document, so it is not persisted.
+        assert_eq!(loaded.output_doc_id, None);
+        assert_eq!(loaded.ancestor_doc_id, None);
+        assert_eq!(loaded.auto_resolved, 3);
+        assert_eq!(loaded.pending_review, 1);
+        assert_eq!(loaded.conflicts.len(), 1);
+        assert_eq!(loaded.conflicts[0].id, result.conflicts[0].id);
+        assert_eq!(loaded.conflicts[0].incoming_content.as_deref(), Some("b"));
+        assert_eq!(status(&pool, &result.merge_id), STATUS_PENDING_REVIEW);
+    }
+
+    #[test]
+    fn ancestor_doc_id_round_trips() {
+        let (_dir, pool, mut result) = setup();
+        result.ancestor_doc_id = Some(result.base_doc_id);
+        let store = SqliteMergeStore::new(pool);
+        store.save_merge(&mut result).unwrap();
+        let loaded = store.get_merge(&result.merge_id).unwrap();
+        assert_eq!(loaded.ancestor_doc_id, Some(result.base_doc_id));
+    }
+
+    #[test]
+    fn manifest_round_trips() {
+        let (_dir, pool, mut result) = setup();
+        let manifest = MergeEngine::new().manifest(&[
+            InputDigest::from_blocks(result.base_doc_id, &[]),
+            InputDigest::from_blocks(result.incoming_doc_id, &[]),
+        ]);
+        result.manifest = Some(manifest.clone());
+        let store = SqliteMergeStore::new(pool);
+        store.save_merge(&mut result).unwrap();
+        assert_eq!(store.get_merge(&result.merge_id).unwrap().manifest, Some(manifest));
+    }
+
+    #[test]
+    fn resolving_last_conflict_marks_merge_resolved() {
+        let (_dir, pool, mut result) = setup();
+        let store = SqliteMergeStore::new(pool.clone());
+        store.save_merge(&mut result).unwrap();
+
+        let conflict_id = result.conflicts[0].id;
+        let updated = store
+            .update_conflict_resolution(&conflict_id, ConflictResolution::AcceptedIncoming)
+            .unwrap();
+        assert_eq!(updated.resolution, ConflictResolution::AcceptedIncoming);
+
+        let conflicts = store.list_conflicts(&result.merge_id).unwrap();
+        assert_eq!(conflicts[0].resolution, ConflictResolution::AcceptedIncoming);
+        assert_eq!(store.get_merge(&result.merge_id).unwrap().pending_review, 0);
+        assert_eq!(status(&pool, &result.merge_id), STATUS_RESOLVED);
+    }
+
+    #[test]
+    fn re_resolving_a_conflict_is_rejected() {
+        let (_dir, pool, mut result) = setup();
+        let store = SqliteMergeStore::new(pool);
+        store.save_merge(&mut result).unwrap();
+
+        let conflict_id = result.conflicts[0].id;
+        store
+            .update_conflict_resolution(&conflict_id, ConflictResolution::AcceptedBase)
+            .unwrap();
+        let err = store
+            .update_conflict_resolution(&conflict_id, ConflictResolution::Manual)
+            .unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(_)), "{err:?}");
+    }
+
+    #[test]
+    fn layer_deltas_are_counted_by_review_activity() {
+        use crate::layer::{BlockDelta, DeltaType, ReviewLayer};
+        use rt_core::review_activity::{delta_activity, DeltaActivityQuery, DeltaDimension};
+
+        let (_dir, pool, result) = setup();
+        let block_id = result.conflicts[0].block_id;
+        let layer = ReviewLayer::new(Uuid::new_v4(), "alice", result.base_doc_id);
+        let delta = |kind| {
+            BlockDelta::new(layer.id, "alice", block_id, kind, 0, 0, serde_json::json!({}))
+        };
+        let mut submitted = LayerDeltas {
+            layer: layer.clone(),
+            deltas: vec![delta(DeltaType::Insert)],
+        };
+        let store = SqliteMergeStore::new(pool.clone());
+        store.save_layer_deltas(&submitted).unwrap();
+        // Resubmitting the grown layer stores only the new delta.
+        submitted.deltas.push(delta(DeltaType::Delete));
+        store.save_layer_deltas(&submitted).unwrap();
+
+        let query = DeltaActivityQuery {
+            group_by: vec![DeltaDimension::Reviewer],
+            ..Default::default()
+        };
+        let rows = delta_activity(&pool.get().unwrap(), store.tenant(), &query).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].reviewer_id.as_deref(), Some("alice"));
+        assert_eq!(rows[0].count, 2);
+
+        // A delta on a block of another document is rejected.
+        submitted.deltas = vec![BlockDelta::new(
+            layer.id,
+            "alice",
+            Uuid::new_v4(),
+            DeltaType::Modify,
+            0,
+            0,
+            serde_json::json!({}),
+        )];
+        let err = store.save_layer_deltas(&submitted).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)), "{err:?}");
+    }
+
+    #[test]
+    fn suggestions_are_stored_without_resolving() {
+        let (_dir, pool, mut result) = setup();
+        let store = SqliteMergeStore::new(pool);
+        store.save_merge(&mut result).unwrap();
+
+        let suggestions = crate::suggest::suggest_resolutions(&result.conflicts[0], Some("a"));
+        store.save_suggestions(&suggestions).unwrap();
+        // Saving again replaces rather than duplicates.
+        store.save_suggestions(&suggestions).unwrap();
+
+        let loaded = store.list_suggestions(&result.merge_id).unwrap();
+        assert_eq!(loaded, suggestions);
+        assert_eq!(loaded[0].rule, SuggestionRule::BaseUnchanged);
+        assert_eq!(store.get_merge(&result.merge_id).unwrap().pending_review, 1);
+
+        let mut stray = suggestions[0].clone();
+        stray.conflict_id = Uuid::new_v4();
+        assert!(matches!(store.save_suggestions(&[stray]), Err(RtError::NotFound(_))));
+        assert!(matches!(
+            store.list_suggestions(&Uuid::new_v4()),
+            Err(RtError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn missing_merge_and_conflict_are_not_found() {
+        let (_dir, pool, _) = setup();
+        let store = SqliteMergeStore::new(pool);
+        let id = Uuid::new_v4();
+        assert!(matches!(store.get_merge(&id), Err(RtError::NotFound(_))));
+        assert!(matches!(store.list_conflicts(&id), Err(RtError::NotFound(_))));
+        assert!(matches!(
+            store.update_conflict_resolution(&id, ConflictResolution::Manual),
+            Err(RtError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn merges_of_other_tenants_are_not_found() {
+        let (_dir, pool, mut result) = setup();
+        let owner = SqliteMergeStore::new(pool.clone());
+        owner.save_merge(&mut result).unwrap();
+        let conflict_id = result.conflicts[0].id;
+
+        let other = SqliteMergeStore::with_tenant(pool, TenantContext::new("acme").unwrap());
+        assert!(matches!(other.get_merge(&result.merge_id), Err(RtError::NotFound(_))));
+        assert!(matches!(other.list_conflicts(&result.merge_id), Err(RtError::NotFound(_))));
+        assert!(matches!(other.list_suggestions(&result.merge_id), Err(RtError::NotFound(_))));
+        assert!(matches!(
+            other.update_conflict_resolution(&conflict_id, ConflictResolution::Manual),
+            Err(RtError::NotFound(_))
+        ));
+        // Nor can it record a merge over documents it does not own.
+        let mut foreign = result.clone();
+        foreign.merge_id = Uuid::new_v4();
+        assert!(matches!(other.save_merge(&mut foreign), Err(RtError::NotFound(_))));
+
+        assert_eq!(owner.list_conflicts(&result.merge_id).unwrap()[0].id, conflict_id);
+    }
+}
@@ -0,0 +1,354 @@
+//! Resolution suggestions for pending merge conflicts.
+//!
+//! [`suggest_resolutions`] inspects one conflict (and, for merges with a
+//! common ancestor, the ancestor's text of the block) and returns candidate
+//! resolutions ranked by confidence, each with the rule that produced it
+//! and a one-line rationale. Suggestions are advisory: they are stored next
+//! to the conflict by [`crate::MergeStore::save_suggestions`] but never
+//! applied; a reviewer still resolves the conflict through
+//! [`crate::MergeStore::update_conflict_resolution`].
+
+use rt_core::RtError;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::conflict::{ConflictResolution, ConflictType, MergeConflict};
+
+// ---------------------------------------------------------------------------
+// SuggestionRule
+// ---------------------------------------------------------------------------
+
+/// The heuristic behind a [`ResolutionSuggestion`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionRule {
+    /// Both sides carry the same text once whitespace is normalised.
+    Identical,
+    /// The base side still matches the ancestor; only incoming changed it.
+    BaseUnchanged,
+    /// The incoming side still matches the ancestor; only base changed it.
+    IncomingUnchanged,
+    /// The sides differ only in punctuation, case or whitespace.
+    PunctuationOnly,
+    /// One side's text contains the other's, i.e. it only adds wording.
+    Subsumes,
+    /// One side deleted what the other edited; keeping the edit loses less.
+    KeepModified,
+    /// No heuristic applies; a reviewer has to decide.
+    NeedsReview,
+}
+
+impl SuggestionRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionRule::Identical => "identical",
+            SuggestionRule::BaseUnchanged => "base_unchanged",
+            SuggestionRule::IncomingUnchanged => "incoming_unchanged",
+            SuggestionRule::PunctuationOnly => "punctuation_only",
+            SuggestionRule::Subsumes => "subsumes",
+            SuggestionRule::KeepModified => "keep_modified",
+            SuggestionRule::NeedsReview => "needs_review",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, RtError> {
+        match s {
+            "identical" => Ok(SuggestionRule::Identical),
+            "base_unchanged" => Ok(SuggestionRule::BaseUnchanged),
+            "incoming_unchanged" => Ok(SuggestionRule::IncomingUnchanged),
+            "punctuation_only" => Ok(SuggestionRule::PunctuationOnly),
+            "subsumes" => Ok(SuggestionRule::Subsumes),
+            "keep_modified" => Ok(SuggestionRule::KeepModified),
+            "needs_review" => Ok(SuggestionRule::NeedsReview),
+            other => Err(RtError::InvalidInput(format!("unknown suggestion rule: {other}"))),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ResolutionSuggestion
+// ---------------------------------------------------------------------------
+
+/// One candidate resolution for a conflict.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResolutionSuggestion {
+    pub conflict_id: Uuid,
+    /// 1 for the best candidate of its conflict, 2 for the next, ….
+    pub rank: u32,
+    /// Suggested target state; never `Pending`.
+    pub resolution: ConflictResolution,
+    pub rule: SuggestionRule,
+    /// Heuristic confidence in [0.0, 1.0].
+    pub confidence: f64,
+    pub rationale: String,
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Ranked candidate resolutions for `conflict`, best first.
+///
+/// `ancestor_content` is the ancestor's text of the conflicting block, when
+/// the merge has a common ancestor; without it the `*_unchanged` rules are
+/// skipped. Resolved conflicts get no suggestions. Otherwise the list is
+/// never empty: it always ends with a `Manual` / `NeedsReview` candidate
+/// unless a stronger rule already suggested `Manual`.
+pub fn suggest_resolutions(
+    conflict: &MergeConflict,
+    ancestor_content: Option<&str>,
+) -> Vec<ResolutionSuggestion> {
+    if conflict.is_resolved() {
+        return Vec::new();
+    }
+
+    let base = conflict.base_content.as_deref();
+    let incoming = conflict.incoming_content.as_deref();
+    let mut candidates: Vec<(ConflictResolution, SuggestionRule, f64, String)> = Vec::new();
+
+    // Move collisions carry structural paths, not text; only a reviewer can
+    // pick between two destinations.
+    if conflict.conflict_type != ConflictType::MoveCollision {
+        if let (Some(b), Some(i)) = (base, incoming) {
+            if collapse(b) == collapse(i) {
+                candidates.push((
+                    ConflictResolution::AcceptedIncoming,
+                    SuggestionRule::Identical,
+                    0.99,
+                    "both sides have the same text; either choice yields it".into(),
+                ));
+            }
+        }
+
+        if let Some(a) = ancestor_content.map(collapse) {
+            if base.map(collapse).as_deref() == Some(a.as_str()) {
+                candidates.push((
+                    ConflictResolution::AcceptedIncoming,
+                    SuggestionRule::BaseUnchanged,
+                    0.95,
+                    "base still matches the common ancestor, so only incoming changed it".into(),
+                ));
+            } else if incoming.map(collapse).as_deref() == Some(a.as_str()) {
+                candidates.push((
+                    ConflictResolution::AcceptedBase,
+                    SuggestionRule::IncomingUnchanged,
+                    0.95,
+                    "incoming still matches the common ancestor, so only base changed it".into(),
+                ));
+            }
+        }
+
+        if let (Some(b), Some(i)) = (base, incoming) {
+            let (b_words, i_words) = (words(b), words(i));
+            if b_words == i_words && collapse(b) != collapse(i) {
+                candidates.push((
+                    ConflictResolution::AcceptedIncoming,
+                    SuggestionRule::PunctuationOnly,
+                    0.8,
+                    "the sides differ only in punctuation, case or spacing; \
+                     incoming has the later wording"
+                        .into(),
+                ));
+            } else if !b_words.is_empty() && contains_run(&i_words, &b_words) {
+                candidates.push((
+                    ConflictResolution::AcceptedIncoming,
+                    SuggestionRule::Subsumes,
+                    0.6,
+                    "incoming keeps all of base's wording and adds to it".into(),
+                ));
+            } else if !i_words.is_empty() && contains_run(&b_words, &i_words) {
+                candidates.push((
+                    ConflictResolution::AcceptedBase,
+                    SuggestionRule::Subsumes,
+                    0.6,
+                    "base keeps all of incoming's wording and adds to it".into(),
+                ));
+            }
+        }
+
+        if conflict.conflict_type == ConflictType::DeleteModify {
+            match (base, incoming) {
+                (None, Some(_)) => candidates.push((
+                    ConflictResolution::AcceptedIncoming,
+                    SuggestionRule::KeepModified,
+                    0.55,
+                    "base deleted text that incoming edited; keeping the edit loses nothing"
+                        .into(),
+                )),
+                (Some(_), None) => candidates.push((
+                    ConflictResolution::AcceptedBase,
+                    SuggestionRule::KeepModified,
+                    0.55,
+                    "incoming deleted text that base edited; keeping the edit loses nothing"
+                        .into(),
+                )),
+                _ => {}
+            }
+        }
+    }
+
+    candidates.push((
+        ConflictResolution::Manual,
+        SuggestionRule::NeedsReview,
+        0.3,
+        match conflict.conflict_type {
+            ConflictType::MoveCollision => "the sides moved the block to different places",
+            ConflictType::ReviewRequired => "the merge's auto-accept rules hold this change for review",
+            _ => "the sides made competing edits",
+        }
+        .to_string(),
+    ));
+
+    // Keep the strongest candidate per target resolution.
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+    let mut seen: Vec<ConflictResolution> = Vec::new();
+    candidates
+        .into_iter()
+        .filter(|(resolution, ..)| {
+            let fresh = !seen.contains(resolution);
+            seen.push(resolution.clone());
+            fresh
+        })
+        .enumerate()
+        .map(|(i, (resolution, rule, confidence, rationale))| ResolutionSuggestion {
+            conflict_id: conflict.id,
+            rank: i as u32 + 1,
+            resolution,
+            rule,
+            confidence,
+            rationale,
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// `text` with runs of whitespace collapsed to one space and trimmed.
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Lowercased alphanumeric words of `text`, punctuation dropped.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// `true` when `needle` occurs as a contiguous run inside `haystack`.
+fn contains_run(haystack: &[String], needle: &[String]) -> bool {
+    needle.len() <= haystack.len() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conflict(kind: ConflictType, base: Option<&str>, incoming: Option<&str>) -> MergeConflict {
+        MergeConflict::new(Uuid::new_v4(), kind, base.map(Into::into), incoming.map(Into::into))
+    }
+
+    #[test]
+    fn base_unchanged_from_ancestor_suggests_incoming() {
+        let c = conflict(
+            ConflictType::ContentOverlap,
+            Some("the fee is 5 dollars"),
+            Some("the fee is 7 dollars"),
+        );
+        let s = suggest_resolutions(&c, Some("the fee is  5 dollars"));
+        assert_eq!(s[0].rule, SuggestionRule::BaseUnchanged);
+        assert_eq!(s[0].resolution, ConflictResolution::AcceptedIncoming);
+        assert_eq!(s[0].rank, 1);
+        assert_eq!(s.last().unwrap().resolution, ConflictResolution::Manual);
+    }
+
+    #[test]
+    fn incoming_unchanged_from_ancestor_suggests_base() {
+        let c = conflict(ConflictType::ContentOverlap, Some("new"), Some("old"));
+        let s = suggest_resolutions(&c, Some("old"));
+        assert_eq!(s[0].rule, SuggestionRule::IncomingUnchanged);
+        assert_eq!(s[0].resolution, ConflictResolution::AcceptedBase);
+    }
+
+    #[test]
+    fn punctuation_only_differences_are_suggested() {
+        let c = conflict(
+            ConflictType::ContentOverlap,
+            Some("Payment is due, on demand"),
+            Some("payment is due on demand."),
+        );
+        let s = suggest_resolutions(&c, None);
+        assert_eq!(s[0].rule, SuggestionRule::PunctuationOnly);
+        assert_eq!(s[0].resolution, ConflictResolution::AcceptedIncoming);
+        assert!(s[0].confidence > s[1].confidence);
+    }
+
+    #[test]
+    fn extension_and_delete_modify_rules() {
+        let extended = conflict(
+            ConflictType::ContentOverlap,
+            Some("shall pay"),
+            Some("shall promptly pay"),
+        );
+        // "shall pay" is not a contiguous run of the incoming words.
+        assert_eq!(suggest_resolutions(&extended, None)[0].rule, SuggestionRule::NeedsReview);
+
+        let appended = conflict(ConflictType::ContentOverlap, Some("shall pay"), Some("shall pay now"));
+        assert_eq!(suggest_resolutions(&appended, None)[0].rule, SuggestionRule::Subsumes);
+
+        let deleted = conflict(ConflictType::DeleteModify, None, Some("edited text"));
+        let s = suggest_resolutions(&deleted, None);
+        assert_eq!(s[0].rule, SuggestionRule::KeepModified);
+        assert_eq!(s[0].resolution, ConflictResolution::AcceptedIncoming);
+    }
+
+    #[test]
+    fn move_collisions_and_resolved_conflicts() {
+        let moved = conflict(ConflictType::MoveCollision, Some("2."), Some("2"));
+        let s = suggest_resolutions(&moved, None);
+        assert_eq!(s.len(), 1);
+        assert_eq!(s[0].rule, SuggestionRule::NeedsReview);
+
+        let mut resolved = conflict(ConflictType::ContentOverlap, Some("a"), Some("a"));
+        resolved.resolution = ConflictResolution::AcceptedBase;
+        assert!(suggest_resolutions(&resolved, None).is_empty());
+    }
+
+    #[test]
+    fn candidates_are_unique_per_resolution() {
+        let c = conflict(ConflictType::ContentOverlap, Some("same text"), Some("same  text"));
+        let s = suggest_resolutions(&c, Some("same text"));
+        let incoming = s
+            .iter()
+            .filter(|x| x.resolution == ConflictResolution::AcceptedIncoming)
+            .count();
+        assert_eq!(incoming, 1);
+        assert_eq!(s[0].rule, SuggestionRule::Identical);
+        let ranks: Vec<u32> = s.iter().map(|x| x.rank).collect();
+        assert_eq!(ranks, (1..=s.len() as u32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn suggestion_rule_round_trips() {
+        for rule in [
+            SuggestionRule::Identical,
+            SuggestionRule::BaseUnchanged,
+            SuggestionRule::IncomingUnchanged,
+            SuggestionRule::PunctuationOnly,
+            SuggestionRule::Subsumes,
+            SuggestionRule::KeepModified,
+            SuggestionRule::NeedsReview,
+        ] {
+            assert_eq!(SuggestionRule::from_str(rule.as_str()).unwrap(), rule);
+        }
+        assert!(SuggestionRule::from_str("guess").is_err());
+    }
+}
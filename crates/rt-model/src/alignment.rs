@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, RtError};
+
+// ---------------------------------------------------------------------------
+// AlignmentLabel
+// ---------------------------------------------------------------------------
+
+/// A reviewer's verdict on how two blocks from different documents relate,
+/// recorded as an alignment correction and used for threshold calibration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlignmentLabel {
+    /// The same logical block, at the same place in the document.
+    Match,
+    /// The same logical block, relocated to a different structural path.
+    Move,
+    /// Unrelated blocks that must not be aligned.
+    Distinct,
+}
+
+impl AlignmentLabel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlignmentLabel::Match => "match",
+            AlignmentLabel::Move => "move",
+            AlignmentLabel::Distinct => "distinct",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "match" => Ok(AlignmentLabel::Match),
+            "move" => Ok(AlignmentLabel::Move),
+            "distinct" => Ok(AlignmentLabel::Distinct),
+            other => Err(RtError::InvalidInput(format!("unknown alignment label: {other}"))),
+        }
+    }
+
+    /// `true` for labels that say the two blocks should be aligned.
+    pub fn is_aligned(&self) -> bool {
+        !matches!(self, AlignmentLabel::Distinct)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_names_round_trip() {
+        for label in [AlignmentLabel::Match, AlignmentLabel::Move, AlignmentLabel::Distinct] {
+            assert_eq!(AlignmentLabel::from_str(label.as_str()).unwrap(), label);
+        }
+        assert!(AlignmentLabel::from_str("maybe").is_err());
+    }
+}
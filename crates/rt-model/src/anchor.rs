@@ -0,0 +1,386 @@
+use crate::block::BlockType;
+use crate::error::{Result, RtError};
+use crate::hash::sha256_hex;
+
+/// Primary anchor signature.
+///
+/// Computed as SHA256 of the concatenation:
+///   `{block_type_str}|{structural_path}|{first_128_chars_of_canonical_text}`
+///
+/// Using only the first 128 characters of the canonical text keeps the anchor
+/// stable through minor textual edits while still discriminating between
+/// structurally co-located blocks with meaningfully different content.
+pub fn compute_anchor_signature(
+    block_type: &BlockType,
+    structural_path: &str,
+    canonical_text: &str,
+) -> String {
+    sha256_hex(&anchor_payload(block_type, structural_path, canonical_text))
+}
+
+/// The string hashed into an anchor signature, shared with keyed hashers
+/// (`ClauseHasher::anchor_signature`).
+pub(crate) fn anchor_payload(
+    block_type: &BlockType,
+    structural_path: &str,
+    canonical_text: &str,
+) -> String {
+    let type_str = block_type_str(block_type);
+    let prefix: String = canonical_text.chars().take(128).collect();
+    format!("{}|{}|{}", type_str, structural_path, prefix)
+}
+
+// ---------------------------------------------------------------------------
+// Anchor strategies
+// ---------------------------------------------------------------------------
+
+/// `anchor_version` of [`AnchorV1`], recorded on every document ingested
+/// before anchor strategies were selectable.
+pub const ANCHOR_V1: &str = "1";
+
+/// Words per shingle of [`AnchorContent::Shingles`].
+const SHINGLE_WORDS: usize = 3;
+
+/// Shingles kept in the sketch of [`AnchorContent::Shingles`].
+const SHINGLE_SKETCH: usize = 4;
+
+/// How a block's anchor signature is derived from its type, structural path
+/// and canonical text.
+///
+/// A document records the strategy its anchors were computed with as its
+/// `anchor_version`. Anchors of different strategies never match, so
+/// documents anchored differently are re-anchored onto one strategy before
+/// they are aligned.
+pub trait AnchorStrategy: Send + Sync {
+    /// The `anchor_version` recorded on documents anchored this way.
+    fn version(&self) -> String;
+
+    /// The string hashed into an anchor signature.
+    fn payload(&self, block_type: &BlockType, structural_path: &str, canonical_text: &str)
+        -> String;
+}
+
+/// The original strategy: `{block_type}|{structural_path}|{first 128 chars}`
+/// (see [`compute_anchor_signature`]). Stable through edits after the first
+/// 128 characters, but renumbering a clause changes its anchor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnchorV1;
+
+impl AnchorStrategy for AnchorV1 {
+    fn version(&self) -> String {
+        ANCHOR_V1.to_string()
+    }
+
+    fn payload(
+        &self,
+        block_type: &BlockType,
+        structural_path: &str,
+        canonical_text: &str,
+    ) -> String {
+        anchor_payload(block_type, structural_path, canonical_text)
+    }
+}
+
+/// The text an [`AnchorV2`] anchor covers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnchorContent {
+    /// The first 128 characters, as in [`AnchorV1`].
+    #[default]
+    Prefix,
+    /// A sketch of the whole text: the [`SHINGLE_SKETCH`] smallest hashes of
+    /// its [`SHINGLE_WORDS`]-word shingles. An edit anywhere only changes
+    /// the anchor if it touches one of those shingles.
+    Shingles,
+}
+
+/// Anchors with configurable components: the block type, optionally the
+/// structural path, and the [`AnchorContent`]. Leaving the path out keeps
+/// anchors stable when clauses are renumbered.
+///
+/// Its `anchor_version` is `"2"` followed by `+path` and / or `+shingles`;
+/// a v2 anchor must leave out the path or use shingles (with both it would
+/// be [`AnchorV1`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnchorV2 {
+    pub structural_path: bool,
+    pub content: AnchorContent,
+}
+
+impl AnchorStrategy for AnchorV2 {
+    fn version(&self) -> String {
+        let mut version = "2".to_string();
+        if self.structural_path {
+            version.push_str("+path");
+        }
+        if self.content == AnchorContent::Shingles {
+            version.push_str("+shingles");
+        }
+        version
+    }
+
+    fn payload(
+        &self,
+        block_type: &BlockType,
+        structural_path: &str,
+        canonical_text: &str,
+    ) -> String {
+        let path = if self.structural_path { structural_path } else { "" };
+        let content = match self.content {
+            AnchorContent::Prefix => canonical_text.chars().take(128).collect(),
+            AnchorContent::Shingles => shingle_sketch(canonical_text),
+        };
+        format!("v2|{}|{}|{}", block_type_str(block_type), path, content)
+    }
+}
+
+/// The strategy recorded as `version` on a document.
+pub fn anchor_strategy(version: &str) -> Result<Box<dyn AnchorStrategy>> {
+    if version == ANCHOR_V1 {
+        return Ok(Box::new(AnchorV1));
+    }
+    let invalid = || RtError::InvalidInput(format!("unknown anchor version: {version}"));
+    let mut components = version.split('+');
+    if components.next() != Some("2") {
+        return Err(invalid());
+    }
+    let mut strategy = AnchorV2 {
+        structural_path: false,
+        content: AnchorContent::Prefix,
+    };
+    for component in components {
+        match component {
+            "path" if !strategy.structural_path => strategy.structural_path = true,
+            "shingles" if strategy.content == AnchorContent::Prefix => {
+                strategy.content = AnchorContent::Shingles
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    // One spelling per strategy, and `2+path` would be v1 under another name.
+    let canonical = strategy.version() == version;
+    let is_v1 = strategy.structural_path && strategy.content == AnchorContent::Prefix;
+    if !canonical || is_v1 {
+        return Err(invalid());
+    }
+    Ok(Box::new(strategy))
+}
+
+/// The smallest shingle hashes of `canonical_text`, case-folded, sorted and
+/// comma-separated. Text shorter than one shingle is one shingle.
+fn shingle_sketch(canonical_text: &str) -> String {
+    let lower = canonical_text.to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    let mut hashes: Vec<String> = words
+        .windows(SHINGLE_WORDS.min(words.len()).max(1))
+        .map(|shingle| sha256_hex(&shingle.join(" ")))
+        .collect();
+    hashes.sort();
+    hashes.dedup();
+    hashes.truncate(SHINGLE_SKETCH);
+    hashes.join(",")
+}
+
+/// Secondary discriminator — SHA256 of the full canonical text.
+///
+/// Use this when you need to detect even minor textual changes that the
+/// anchor (which only hashes the first 128 chars) might miss.
+pub fn compute_full_text_hash(canonical_text: &str) -> String {
+    sha256_hex(canonical_text)
+}
+
+// ---------------------------------------------------------------------------
+// Deep-link anchors
+// ---------------------------------------------------------------------------
+
+/// Hex digits of the anchor signature kept in a link anchor.
+const LINK_ANCHOR_HEX: usize = 16;
+
+/// Deep-link anchor of a block: `b-` followed by the first 16 hex digits of
+/// its unkeyed anchor signature.
+///
+/// Unlike block ids, which are random, the same content at the same
+/// structural path always gets the same link anchor, so a UI can put it in a
+/// URL. Unlike `anchor_signature` it does not depend on the hashing key.
+pub fn block_link_anchor(
+    block_type: &BlockType,
+    structural_path: &str,
+    canonical_text: &str,
+) -> String {
+    let signature = compute_anchor_signature(block_type, structural_path, canonical_text);
+    format!("b-{}", &signature[..LINK_ANCHOR_HEX])
+}
+
+/// Deep-link anchor of the delta between the blocks with link anchors `left`
+/// and `right`: `d-{left}-{right}` with the `b-` prefixes dropped and `_`
+/// standing in for a missing side.
+pub fn delta_link_anchor(left: Option<&str>, right: Option<&str>) -> String {
+    let side = |anchor: Option<&str>| {
+        anchor.map_or("_", |a| a.strip_prefix("b-").unwrap_or(a)).to_string()
+    };
+    format!("d-{}-{}", side(left), side(right))
+}
+
+/// A parsed deep-link anchor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkAnchor {
+    /// A block's link anchor.
+    Block(String),
+    /// A delta's, as the link anchors of the blocks on either side.
+    Delta {
+        left: Option<String>,
+        right: Option<String>,
+    },
+}
+
+/// Parse a link anchor produced by [`block_link_anchor`] or
+/// [`delta_link_anchor`].
+pub fn parse_link_anchor(anchor: &str) -> Result<LinkAnchor> {
+    let invalid = || RtError::InvalidInput(format!("invalid link anchor: {anchor:?}"));
+    let is_hash =
+        |s: &str| s.len() == LINK_ANCHOR_HEX && s.bytes().all(|b| b.is_ascii_hexdigit());
+    if let Some(hash) = anchor.strip_prefix("b-") {
+        return if is_hash(hash) {
+            Ok(LinkAnchor::Block(anchor.to_string()))
+        } else {
+            Err(invalid())
+        };
+    }
+    let (left, right) = anchor
+        .strip_prefix("d-")
+        .and_then(|rest| rest.split_once('-'))
+        .ok_or_else(invalid)?;
+    let side = |s: &str| match s {
+        "_" => Ok(None),
+        hash if is_hash(hash) => Ok(Some(format!("b-{hash}"))),
+        _ => Err(invalid()),
+    };
+    match (side(left)?, side(right)?) {
+        (None, None) => Err(invalid()),
+        (left, right) => Ok(LinkAnchor::Delta { left, right }),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+fn block_type_str(bt: &BlockType) -> &'static str {
+    match bt {
+        BlockType::Section => "section",
+        BlockType::Clause => "clause",
+        BlockType::Subclause => "subclause",
+        BlockType::Paragraph => "paragraph",
+        BlockType::Table => "table",
+        BlockType::TableRow => "table_row",
+        BlockType::TableCell => "table_cell",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchor_is_deterministic() {
+        let sig1 = compute_anchor_signature(
+            &BlockType::Clause,
+            "1.2(a)",
+            "The borrower shall repay the loan.",
+        );
+        let sig2 = compute_anchor_signature(
+            &BlockType::Clause,
+            "1.2(a)",
+            "The borrower shall repay the loan.",
+        );
+        assert_eq!(sig1, sig2);
+    }
+
+    #[test]
+    fn anchor_differs_by_type() {
+        let sig_clause = compute_anchor_signature(
+            &BlockType::Clause,
+            "1.2(a)",
+            "Same text",
+        );
+        let sig_para = compute_anchor_signature(
+            &BlockType::Paragraph,
+            "1.2(a)",
+            "Same text",
+        );
+        assert_ne!(sig_clause, sig_para);
+    }
+
+    #[test]
+    fn anchor_differs_by_path() {
+        let sig1 = compute_anchor_signature(&BlockType::Clause, "1.1", "Text");
+        let sig2 = compute_anchor_signature(&BlockType::Clause, "1.2", "Text");
+        assert_ne!(sig1, sig2);
+    }
+
+    #[test]
+    fn anchor_stable_after_128_chars() {
+        let base: String = "a".repeat(200);
+        let extended = format!("{}{}", base, "b".repeat(50));
+
+        // Both have the same first 128 chars and the same path/type.
+        let sig1 = compute_anchor_signature(&BlockType::Paragraph, "2.1", &base);
+        let sig2 = compute_anchor_signature(&BlockType::Paragraph, "2.1", &extended);
+        // First 128 chars are identical, so anchors should match.
+        assert_eq!(sig1, sig2);
+    }
+
+    #[test]
+    fn v2_anchors_survive_renumbering_and_edits() {
+        let text = "the supplier shall deliver the goods to the premises of the customer \
+                    within thirty days of the order";
+        // This edit misses the four sketched shingles of `text`.
+        let edited = text.replace("order", "purchase order");
+        let unpathed = anchor_strategy("2").unwrap();
+        assert_eq!(
+            unpathed.payload(&BlockType::Clause, "4.1", text),
+            unpathed.payload(&BlockType::Clause, "4.2", text)
+        );
+        let shingled = anchor_strategy("2+path+shingles").unwrap();
+        let sketch = |path, text| shingled.payload(&BlockType::Clause, path, text);
+        assert_eq!(sketch("4.1", text), sketch("4.1", &edited));
+        assert_ne!(sketch("4.1", text), sketch("4.2", text));
+        assert_eq!(shingled.version(), "2+path+shingles");
+        assert_ne!(
+            anchor_strategy(ANCHOR_V1).unwrap().payload(&BlockType::Clause, "4.1", text),
+            anchor_strategy("2+path+shingles").unwrap().payload(&BlockType::Clause, "4.1", text)
+        );
+        assert_eq!(sketch("", ""), "v2|clause||");
+
+        for bad in ["", "3", "2+path", "2+shingles+path", "2+shingles+shingles", "2+words"] {
+            assert!(anchor_strategy(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn link_anchors_round_trip() {
+        let block = block_link_anchor(&BlockType::Clause, "1.1", "Text");
+        assert_eq!(block.len(), 18);
+        assert_eq!(parse_link_anchor(&block).unwrap(), LinkAnchor::Block(block.clone()));
+
+        let delta = delta_link_anchor(None, Some(&block));
+        assert_eq!(delta, format!("d-_-{}", &block[2..]));
+        assert_eq!(
+            parse_link_anchor(&delta).unwrap(),
+            LinkAnchor::Delta { left: None, right: Some(block) }
+        );
+
+        for bad in ["", "b-xyz", "d-_-_", "d-abc", "x-0123456789abcdef"] {
+            assert!(parse_link_anchor(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn full_text_hash_detects_tail_change() {
+        let base: String = "a".repeat(200);
+        let extended = format!("{}{}", base, "extra");
+        assert_ne!(
+            compute_full_text_hash(&base),
+            compute_full_text_hash(&extended)
+        );
+    }
+}
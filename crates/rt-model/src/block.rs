@@ -2,8 +2,11 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::anchor::compute_anchor_signature;
-use crate::hash::compute_clause_hash;
+use crate::anchor::{
+    block_link_anchor, compute_anchor_signature, AnchorStrategy, AnchorV1, ANCHOR_V1,
+};
+use crate::error::{Result, RtError};
+use crate::hash::{compute_clause_hash, ClauseHasher};
 
 // ---------------------------------------------------------------------------
 // BlockType
@@ -63,7 +66,7 @@ pub struct Token {
 // ---------------------------------------------------------------------------
 
 /// Typographic attributes attached to a [`Run`].
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RunFormatting {
     pub bold: bool,
     pub italic: bool,
@@ -75,19 +78,6 @@ pub struct RunFormatting {
     pub color: Option<String>,
 }
 
-impl Default for RunFormatting {
-    fn default() -> Self {
-        Self {
-            bold: false,
-            italic: false,
-            underline: false,
-            strikethrough: false,
-            font_size: None,
-            color: None,
-        }
-    }
-}
-
 /// A contiguous span of text that shares a single set of formatting attributes.
 ///
 /// Analogous to a DOCX `<w:r>` element.
@@ -95,6 +85,11 @@ impl Default for RunFormatting {
 pub struct Run {
     pub text: String,
     pub formatting: RunFormatting,
+    /// The tracked revision the run belongs to: `Insert` for text only in
+    /// the changes-accepted view, `Delete` for text only in the
+    /// changes-rejected one. `None` for text in both.
+    #[serde(default)]
+    pub revision: Option<ChangeType>,
 }
 
 // ---------------------------------------------------------------------------
@@ -130,7 +125,7 @@ pub struct TrackedChange {
 /// Document-level and paragraph-level formatting metadata.
 ///
 /// Stored as a JSON blob in the database; not used for hashing.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FormattingMeta {
     /// Named paragraph/character style (e.g. `"Heading 1"`, `"Body Text"`).
     pub style_name: Option<String>,
@@ -144,16 +139,34 @@ pub struct FormattingMeta {
     pub tracked_change: Option<TrackedChange>,
 }
 
-impl Default for FormattingMeta {
-    fn default() -> Self {
-        Self {
-            style_name: None,
-            numbering_id: None,
-            numbering_level: None,
-            is_redline: false,
-            tracked_change: None,
-        }
-    }
+// ---------------------------------------------------------------------------
+// AttachmentType / Attachment
+// ---------------------------------------------------------------------------
+
+/// Kind of non-text content embedded in a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentType {
+    /// A picture, e.g. a signature image or logo.
+    Image,
+    /// An embedded OLE object, e.g. a spreadsheet.
+    EmbeddedObject,
+}
+
+/// Binary content anchored in a block (an image or embedded object).
+///
+/// Only a reference and a content hash are stored; the bytes stay in the
+/// source package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub attachment_type: AttachmentType,
+    /// SHA-256 of the attachment bytes; identifies the content across
+    /// documents.
+    pub content_hash: String,
+    /// Where the bytes live, e.g. the package part `"word/media/image1.png"`.
+    pub storage_ref: String,
+    /// Display name or OLE program id (e.g. `"Excel.Sheet.12"`), if known.
+    pub name: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -189,12 +202,20 @@ pub struct Document {
     pub normalization_version: String,
     /// Semver string identifying the clause-hashing contract.
     pub hash_contract_version: String,
+    /// Strategy the anchor signatures were computed with; see
+    /// [`crate::anchor::anchor_strategy`].
+    #[serde(default = "default_anchor_version")]
+    pub anchor_version: String,
     /// UTC timestamp when the document was ingested.
     pub ingested_at: DateTime<Utc>,
     /// Arbitrary key/value metadata (e.g. parties, jurisdiction, matter ID).
     pub metadata: Option<serde_json::Value>,
 }
 
+fn default_anchor_version() -> String {
+    ANCHOR_V1.to_string()
+}
+
 // ---------------------------------------------------------------------------
 // Block
 // ---------------------------------------------------------------------------
@@ -238,6 +259,9 @@ pub struct Block {
     pub tokens: Vec<Token>,
     /// Run stream derived from `display_text` (preserves formatting spans).
     pub runs: Vec<Run>,
+    /// Images and embedded objects in document order; not used for hashing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
     /// Direct children in document order.
     pub children: Vec<Block>,
 }
@@ -263,22 +287,54 @@ impl std::fmt::Display for BlockType {
     }
 }
 
-impl From<&str> for BlockType {
-    fn from(s: &str) -> Self {
-        match s {
-            "section" => BlockType::Section,
-            "clause" => BlockType::Clause,
-            "subclause" => BlockType::Subclause,
-            "paragraph" => BlockType::Paragraph,
-            "table" => BlockType::Table,
-            "table_row" => BlockType::TableRow,
-            "table_cell" => BlockType::TableCell,
-            _ => BlockType::Paragraph, // graceful fallback
-        }
+impl BlockType {
+    /// Every variant, in declaration order.
+    pub const ALL: [BlockType; 7] = [
+        BlockType::Section,
+        BlockType::Clause,
+        BlockType::Subclause,
+        BlockType::Paragraph,
+        BlockType::Table,
+        BlockType::TableRow,
+        BlockType::TableCell,
+    ];
+
+    /// Parse `s`, mapping unknown strings to `Paragraph`. Use
+    /// [`TryFrom<&str>`] where unknown strings should be rejected.
+    pub fn from_str_lenient(s: &str) -> Self {
+        Self::try_from(s).unwrap_or(BlockType::Paragraph)
+    }
+}
+
+impl TryFrom<&str> for BlockType {
+    type Error = RtError;
+
+    fn try_from(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| unknown_variant("block type", s, Self::ALL.iter().map(Self::as_str)))
     }
 }
 
 impl TokenKind {
+    /// Every variant, in declaration order.
+    pub const ALL: [TokenKind; 7] = [
+        TokenKind::Word,
+        TokenKind::Number,
+        TokenKind::Punctuation,
+        TokenKind::Whitespace,
+        TokenKind::DefinedTerm,
+        TokenKind::PartyRef,
+        TokenKind::DateRef,
+    ];
+
+    /// Parse `s`, mapping unknown strings to `Word`. Use [`TryFrom<&str>`]
+    /// where unknown strings should be rejected.
+    pub fn from_str_lenient(s: &str) -> Self {
+        Self::try_from(s).unwrap_or(TokenKind::Word)
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             TokenKind::Word => "word",
@@ -292,20 +348,25 @@ impl TokenKind {
     }
 }
 
-impl From<&str> for TokenKind {
-    fn from(s: &str) -> Self {
-        match s {
-            "number" => TokenKind::Number,
-            "punctuation" => TokenKind::Punctuation,
-            "whitespace" => TokenKind::Whitespace,
-            "defined_term" => TokenKind::DefinedTerm,
-            "party_ref" => TokenKind::PartyRef,
-            "date_ref" => TokenKind::DateRef,
-            _ => TokenKind::Word, // graceful fallback
-        }
+impl TryFrom<&str> for TokenKind {
+    type Error = RtError;
+
+    fn try_from(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| unknown_variant("token kind", s, Self::ALL.iter().map(Self::as_str)))
     }
 }
 
+/// [`RtError::InvalidInput`] naming the unknown `value` and the accepted ones.
+fn unknown_variant<'a>(what: &str, value: &str, accepted: impl Iterator<Item = &'a str>) -> RtError {
+    RtError::InvalidInput(format!(
+        "unknown {what} '{value}'; expected one of: {}",
+        accepted.collect::<Vec<_>>().join(", ")
+    ))
+}
+
 impl ChangeType {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -326,6 +387,24 @@ impl From<&str> for ChangeType {
     }
 }
 
+impl AttachmentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttachmentType::Image => "image",
+            AttachmentType::EmbeddedObject => "embedded_object",
+        }
+    }
+}
+
+impl From<&str> for AttachmentType {
+    fn from(s: &str) -> Self {
+        match s {
+            "image" => AttachmentType::Image,
+            _ => AttachmentType::EmbeddedObject,
+        }
+    }
+}
+
 impl DocumentType {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -352,8 +431,9 @@ impl Block {
     /// Construct a new `Block`, auto-generating its `id` and computing both
     /// `anchor_signature` and `clause_hash` from the supplied text.
     ///
-    /// `tokens`, `runs`, `children`, and `formatting_meta` are initialised to
-    /// empty / default values; callers may populate them afterwards.
+    /// `tokens`, `runs`, `attachments`, `children`, and `formatting_meta` are
+    /// initialised to empty / default values; callers may populate them
+    /// afterwards.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         block_type: BlockType,
@@ -387,9 +467,49 @@ impl Block {
             position_index,
             tokens: Vec::new(),
             runs: Vec::new(),
+            attachments: Vec::new(),
             children: Vec::new(),
         }
     }
+
+    /// Deep-link anchor of this block; see [`block_link_anchor`].
+    pub fn link_anchor(&self) -> String {
+        block_link_anchor(&self.block_type, &self.structural_path, &self.canonical_text)
+    }
+
+    /// Recompute `anchor_signature` and `clause_hash` under `hasher`, for
+    /// this block and all of its children.
+    pub fn rehash(&mut self, hasher: &ClauseHasher) {
+        self.rehash_with(hasher, &AnchorV1);
+    }
+
+    /// [`rehash`](Self::rehash) with anchors of `strategy`.
+    pub fn rehash_with(&mut self, hasher: &ClauseHasher, strategy: &dyn AnchorStrategy) {
+        self.anchor_signature = hasher.anchor_signature_with(
+            strategy,
+            &self.block_type,
+            &self.structural_path,
+            &self.canonical_text,
+        );
+        self.clause_hash = hasher.clause_hash(&self.canonical_text);
+        for child in &mut self.children {
+            child.rehash_with(hasher, strategy);
+        }
+    }
+
+    /// Recompute the anchor signature of this block and its children under
+    /// `strategy`, leaving clause hashes alone.
+    pub fn reanchor(&mut self, hasher: &ClauseHasher, strategy: &dyn AnchorStrategy) {
+        self.anchor_signature = hasher.anchor_signature_with(
+            strategy,
+            &self.block_type,
+            &self.structural_path,
+            &self.canonical_text,
+        );
+        for child in &mut self.children {
+            child.reanchor(hasher, strategy);
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -434,12 +554,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn enum_strings_parse_strictly_or_leniently() {
+        for t in BlockType::ALL {
+            assert_eq!(BlockType::try_from(t.as_str()).unwrap(), t);
+        }
+        for k in TokenKind::ALL {
+            assert_eq!(TokenKind::try_from(k.as_str()).unwrap(), k);
+        }
+
+        let err = BlockType::try_from("chapter").unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(_)));
+        assert!(err.to_string().contains("unknown block type 'chapter'"), "{err}");
+        assert!(err.to_string().contains("table_cell"), "{err}");
+        assert!(TokenKind::try_from("Word").is_err());
+
+        assert_eq!(BlockType::from_str_lenient("chapter"), BlockType::Paragraph);
+        assert_eq!(TokenKind::from_str_lenient("emoji"), TokenKind::Word);
+    }
+
+    #[test]
+    fn rehash_applies_keyed_contract() {
+        let doc = make_doc_id();
+        let mut b = Block::new(BlockType::Clause, "1.", "text", "Text", None, doc, 0);
+        b.children
+            .push(Block::new(BlockType::Subclause, "1.(a)", "sub", "Sub", Some(b.id), doc, 0));
+        let hasher = ClauseHasher::for_contract(
+            crate::hash::HASH_CONTRACT_HMAC_SHA256,
+            Some(b"key"),
+        )
+        .unwrap();
+
+        b.rehash(&hasher);
+        assert_eq!(b.clause_hash, hasher.clause_hash("text"));
+        assert_eq!(
+            b.anchor_signature,
+            hasher.anchor_signature(&BlockType::Clause, "1.", "text")
+        );
+        assert_eq!(b.children[0].clause_hash, hasher.clause_hash("sub"));
+    }
+
     #[test]
     fn block_defaults_are_empty() {
         let doc = make_doc_id();
         let b = Block::new(BlockType::Paragraph, "3.2", "x", "x", None, doc, 0);
         assert!(b.tokens.is_empty());
         assert!(b.runs.is_empty());
+        assert!(b.attachments.is_empty());
         assert!(b.children.is_empty());
         assert!(!b.formatting_meta.is_redline);
         assert_eq!(b.level, 0);
@@ -0,0 +1,59 @@
+//! Cooperative cancellation of long-running runs.
+//!
+//! A [`CancelToken`] is shared between whoever started a run and the engine
+//! computing it. Cancelling only raises a flag; the engine checks it at
+//! convenient points (between passes, before each block) and gives up with
+//! [`RtError::Cancelled`], so a run stops soon after, not at once.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::error::{Result, RtError};
+
+/// Shared cancellation flag. Clones observe the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// A token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every run holding this token (or a clone of it) to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `Err(RtError::Cancelled)` once the token has been cancelled.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(RtError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_flag() {
+        let token = CancelToken::new();
+        let held_by_run = token.clone();
+        assert!(held_by_run.check().is_ok());
+        token.cancel();
+        assert!(held_by_run.is_cancelled());
+        assert!(matches!(held_by_run.check(), Err(RtError::Cancelled)));
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ---------------------------------------------------------------------------
+// Party
+// ---------------------------------------------------------------------------
+
+/// A party to a document, defined in its preamble, e.g. `ACME Corp.
+/// (the “Lender”)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Party {
+    /// The term the party is referred to by, e.g. "Lender".
+    pub role: String,
+    /// Normalized tokens of `role` joined by single spaces; unique within a
+    /// document.
+    pub normalized: String,
+    /// The party's name as written before the definition, e.g. "ACME Corp.",
+    /// when there is one.
+    pub name: Option<String>,
+    /// Block holding the definition.
+    pub block_id: Uuid,
+}
+
+// ---------------------------------------------------------------------------
+// DateMention
+// ---------------------------------------------------------------------------
+
+/// A date written in a document, e.g. "January 1, 2025".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateMention {
+    /// The date as written.
+    pub text: String,
+    /// The date as `YYYY-MM-DD`, or `YYYY-MM` for a month without a day.
+    pub value: String,
+    /// Block whose text holds the date.
+    pub block_id: Uuid,
+    /// Byte offset of `text` in the block's canonical text.
+    pub offset: usize,
+}
+
+// ---------------------------------------------------------------------------
+// DocumentEntities
+// ---------------------------------------------------------------------------
+
+/// The parties and dates of a document, in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentEntities {
+    pub parties: Vec<Party>,
+    pub dates: Vec<DateMention>,
+}
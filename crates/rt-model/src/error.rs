@@ -2,9 +2,10 @@
 
 use thiserror::Error;
 
-/// Top-level error type for the rt-core crate and dependents.
+/// Top-level error type shared by rt-model, rt-store and their dependents.
 #[derive(Debug, Error)]
 pub enum RtError {
+    #[cfg(feature = "sqlite")]
     #[error("database error: {0}")]
     Database(#[from] rusqlite::Error),
 
@@ -28,6 +29,9 @@ pub enum RtError {
 
     #[error("internal error: {0}")]
     Internal(String),
+
+    #[error("cancelled")]
+    Cancelled,
 }
 
 /// Convenience Result alias used across the workspace.
@@ -0,0 +1,306 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::anchor::{anchor_payload, AnchorStrategy};
+use crate::block::BlockType;
+use crate::error::{Result, RtError};
+
+/// `hash_contract_version` for plain SHA-256 hashes (the default).
+pub const HASH_CONTRACT_SHA256: &str = "1.0.0";
+/// `hash_contract_version` for BLAKE3 hashes, keyed when a key is configured.
+pub const HASH_CONTRACT_BLAKE3: &str = "1.0.0-blake3";
+/// `hash_contract_version` for HMAC-SHA256 hashes; always keyed.
+pub const HASH_CONTRACT_HMAC_SHA256: &str = "1.0.0-hmac-sha256";
+
+/// Context string used to derive a 32-byte BLAKE3 key from arbitrary key
+/// material.
+const BLAKE3_KEY_CONTEXT: &str = "rtflow 2024 clause hash key";
+
+/// Generic SHA256 helper — returns a lowercase hex-encoded digest.
+pub fn sha256_hex(input: &str) -> String {
+    sha256_hex_bytes(input.as_bytes())
+}
+
+/// [`sha256_hex`] over raw bytes, e.g. binary attachment content.
+pub fn sha256_hex_bytes(input: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(input);
+    format!("{:x}", hasher.finalize())
+}
+
+/// SHA256 hash of `canonical_text`.
+///
+/// Always applied after normalization so identical semantic content
+/// produces an identical hash regardless of source formatting.
+pub fn compute_clause_hash(canonical_text: &str) -> String {
+    sha256_hex(canonical_text)
+}
+
+// ---------------------------------------------------------------------------
+// HashAlgorithm
+// ---------------------------------------------------------------------------
+
+/// Digest used for clause hashes and anchor signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+    HmacSha256,
+}
+
+impl HashAlgorithm {
+    /// The `hash_contract_version` recorded on documents hashed with this
+    /// algorithm.
+    pub fn contract_version(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => HASH_CONTRACT_SHA256,
+            HashAlgorithm::Blake3 => HASH_CONTRACT_BLAKE3,
+            HashAlgorithm::HmacSha256 => HASH_CONTRACT_HMAC_SHA256,
+        }
+    }
+
+    /// Inverse of [`HashAlgorithm::contract_version`].
+    pub fn from_contract_version(version: &str) -> Result<Self> {
+        match version {
+            HASH_CONTRACT_SHA256 => Ok(HashAlgorithm::Sha256),
+            HASH_CONTRACT_BLAKE3 => Ok(HashAlgorithm::Blake3),
+            HASH_CONTRACT_HMAC_SHA256 => Ok(HashAlgorithm::HmacSha256),
+            other => Err(RtError::InvalidInput(format!(
+                "unknown hash contract version: {other}"
+            ))),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ClauseHasher
+// ---------------------------------------------------------------------------
+
+/// Computes clause hashes and anchor signatures under one hash contract.
+///
+/// The default is unkeyed SHA-256, identical to [`compute_clause_hash`] and
+/// [`crate::anchor::compute_anchor_signature`]. A keyed hasher produces
+/// digests that cannot be precomputed without the key; all documents that
+/// are compared or merged with each other must share the same hasher.
+#[derive(Clone, Default)]
+pub struct ClauseHasher {
+    algorithm: HashAlgorithm,
+    key: Option<Vec<u8>>,
+}
+
+impl std::fmt::Debug for ClauseHasher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material.
+        f.debug_struct("ClauseHasher")
+            .field("algorithm", &self.algorithm)
+            .field("keyed", &self.key.is_some())
+            .finish()
+    }
+}
+
+impl ClauseHasher {
+    /// Build the hasher for `contract_version`, keyed with `key` if given.
+    ///
+    /// SHA-256 rejects a key; HMAC-SHA256 requires one; BLAKE3 accepts
+    /// either. Keys must be non-empty.
+    pub fn for_contract(contract_version: &str, key: Option<&[u8]>) -> Result<Self> {
+        let algorithm = HashAlgorithm::from_contract_version(contract_version)?;
+        if key.is_some_and(|k| k.is_empty()) {
+            return Err(RtError::InvalidInput("hash key must not be empty".into()));
+        }
+        match (algorithm, key) {
+            (HashAlgorithm::Sha256, Some(_)) => Err(RtError::InvalidInput(format!(
+                "hash contract {contract_version} does not take a key"
+            ))),
+            (HashAlgorithm::HmacSha256, None) => Err(RtError::InvalidInput(format!(
+                "hash contract {contract_version} requires a key"
+            ))),
+            _ => Ok(Self {
+                algorithm,
+                key: key.map(<[u8]>::to_vec),
+            }),
+        }
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn is_keyed(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// The `hash_contract_version` to record on documents hashed with this
+    /// hasher.
+    pub fn contract_version(&self) -> &'static str {
+        self.algorithm.contract_version()
+    }
+
+    /// Lowercase hex digest of `input`.
+    pub fn hash_hex(&self, input: &str) -> String {
+        match (self.algorithm, &self.key) {
+            (HashAlgorithm::Sha256, _) => sha256_hex(input),
+            (HashAlgorithm::Blake3, None) => blake3::hash(input.as_bytes()).to_hex().to_string(),
+            (HashAlgorithm::Blake3, Some(key)) => {
+                let derived = blake3::derive_key(BLAKE3_KEY_CONTEXT, key);
+                blake3::keyed_hash(&derived, input.as_bytes()).to_hex().to_string()
+            }
+            (HashAlgorithm::HmacSha256, key) => {
+                let key = key.as_deref().unwrap_or_default();
+                let mut mac = Hmac::<Sha256>::new_from_slice(key)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(input.as_bytes());
+                hex(&mac.finalize().into_bytes())
+            }
+        }
+    }
+
+    /// Clause hash of `canonical_text` under this contract.
+    pub fn clause_hash(&self, canonical_text: &str) -> String {
+        self.hash_hex(canonical_text)
+    }
+
+    /// Anchor signature under this contract; same payload as
+    /// [`crate::anchor::compute_anchor_signature`].
+    pub fn anchor_signature(
+        &self,
+        block_type: &BlockType,
+        structural_path: &str,
+        canonical_text: &str,
+    ) -> String {
+        self.hash_hex(&anchor_payload(block_type, structural_path, canonical_text))
+    }
+
+    /// Anchor signature of `strategy` under this contract.
+    pub fn anchor_signature_with(
+        &self,
+        strategy: &dyn AnchorStrategy,
+        block_type: &BlockType,
+        structural_path: &str,
+        canonical_text: &str,
+    ) -> String {
+        self.hash_hex(&strategy.payload(block_type, structural_path, canonical_text))
+    }
+}
+
+/// Short, non-reversible identifier for key material, safe to store next to
+/// the hashes it produced so a later session can check it was handed the
+/// same key.
+pub fn key_fingerprint(key: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"rtflow-key-fingerprint:");
+    hasher.update(key);
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// UUID derived from the SHA-256 of `input` (a version 8, custom-layout
+/// UUID), so the same input always yields the same id.
+pub fn derived_uuid(input: &str) -> Uuid {
+    let digest = Sha256::digest(input.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_hex_known_vector() {
+        // SHA-256("") = e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855
+        let digest = sha256_hex("");
+        assert_eq!(
+            digest,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn compute_clause_hash_is_deterministic() {
+        let text = "The borrower shall repay the principal.";
+        assert_eq!(compute_clause_hash(text), compute_clause_hash(text));
+    }
+
+    #[test]
+    fn derived_uuid_is_stable_per_input() {
+        let id = derived_uuid("left/right/modified");
+        assert_eq!(id, derived_uuid("left/right/modified"));
+        assert_ne!(id, derived_uuid("left/right/moved"));
+        assert_eq!(id.get_version_num(), 8);
+    }
+
+    #[test]
+    fn compute_clause_hash_differs_on_different_input() {
+        assert_ne!(
+            compute_clause_hash("foo"),
+            compute_clause_hash("bar")
+        );
+    }
+
+    #[test]
+    fn default_hasher_matches_free_functions() {
+        let hasher = ClauseHasher::default();
+        assert_eq!(hasher.contract_version(), HASH_CONTRACT_SHA256);
+        assert_eq!(hasher.clause_hash("x"), compute_clause_hash("x"));
+        assert_eq!(
+            hasher.anchor_signature(&BlockType::Clause, "1.", "x"),
+            crate::anchor::compute_anchor_signature(&BlockType::Clause, "1.", "x")
+        );
+    }
+
+    #[test]
+    fn blake3_known_vector() {
+        let hasher = ClauseHasher::for_contract(HASH_CONTRACT_BLAKE3, None).unwrap();
+        assert_eq!(
+            hasher.hash_hex(""),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+
+    #[test]
+    fn hmac_sha256_known_vector() {
+        // RFC 4231 test case 2.
+        let hasher =
+            ClauseHasher::for_contract(HASH_CONTRACT_HMAC_SHA256, Some(b"Jefe")).unwrap();
+        assert_eq!(
+            hasher.hash_hex("what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn keyed_hashes_depend_on_key() {
+        for version in [HASH_CONTRACT_BLAKE3, HASH_CONTRACT_HMAC_SHA256] {
+            let a = ClauseHasher::for_contract(version, Some(b"key-a")).unwrap();
+            let b = ClauseHasher::for_contract(version, Some(b"key-b")).unwrap();
+            assert_ne!(a.clause_hash("text"), b.clause_hash("text"), "{version}");
+            assert_eq!(a.clause_hash("text").len(), 64);
+        }
+    }
+
+    #[test]
+    fn contract_key_rules_are_enforced() {
+        assert!(ClauseHasher::for_contract(HASH_CONTRACT_SHA256, Some(b"k")).is_err());
+        assert!(ClauseHasher::for_contract(HASH_CONTRACT_HMAC_SHA256, None).is_err());
+        assert!(ClauseHasher::for_contract(HASH_CONTRACT_BLAKE3, Some(b"")).is_err());
+        assert!(ClauseHasher::for_contract("9.9.9", None).is_err());
+    }
+
+    #[test]
+    fn debug_hides_key() {
+        let hasher =
+            ClauseHasher::for_contract(HASH_CONTRACT_HMAC_SHA256, Some(b"secret")).unwrap();
+        assert!(!format!("{hasher:?}").contains("secret"));
+        assert_eq!(key_fingerprint(b"secret").len(), 16);
+        assert_ne!(key_fingerprint(b"secret"), key_fingerprint(b"other"));
+    }
+}
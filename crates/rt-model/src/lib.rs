@@ -0,0 +1,21 @@
+pub mod alignment;
+pub mod anchor;
+pub mod block;
+pub mod cancel;
+pub mod entity;
+pub mod error;
+pub mod hash;
+pub mod manifest;
+pub mod terms;
+pub mod xref;
+
+pub use alignment::*;
+pub use anchor::*;
+pub use block::*;
+pub use cancel::*;
+pub use entity::*;
+pub use error::*;
+pub use hash::*;
+pub use manifest::*;
+pub use terms::*;
+pub use xref::*;
@@ -0,0 +1,270 @@
+//! Reproducibility manifests for compare and merge runs.
+//!
+//! A [`RunManifest`] records everything a run's output depends on: the
+//! engine versions, a fingerprint of the configuration, and a digest of each
+//! input document together with the versions it was ingested under.
+//! Recomputing the manifest later and diffing it against the recorded one
+//! ([`RunManifest::drift`]) tells whether re-running would reproduce the
+//! result.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::block::{Block, Document};
+use crate::hash::sha256_hex;
+
+// ---------------------------------------------------------------------------
+// InputDigest
+// ---------------------------------------------------------------------------
+
+/// Content digest of one input document of a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputDigest {
+    pub document_id: Uuid,
+    /// Number of blocks, children included, covered by `digest`.
+    pub block_count: usize,
+    /// SHA-256 over the `anchor_signature` and `clause_hash` of every block
+    /// in document order.
+    pub digest: String,
+    /// Versions the document was ingested under; absent when the run was
+    /// given bare blocks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_contract_version: Option<String>,
+}
+
+impl InputDigest {
+    /// Digest `blocks` (and their children, depth first) of `document_id`.
+    pub fn from_blocks(document_id: Uuid, blocks: &[Block]) -> Self {
+        fn walk(blocks: &[Block], parts: &mut Vec<String>) {
+            for block in blocks {
+                parts.push(format!("{}:{}", block.anchor_signature, block.clause_hash));
+                walk(&block.children, parts);
+            }
+        }
+        let mut parts = Vec::new();
+        walk(blocks, &mut parts);
+        Self {
+            document_id,
+            block_count: parts.len(),
+            digest: sha256_hex(&parts.join("\n")),
+            schema_version: None,
+            normalization_version: None,
+            hash_contract_version: None,
+        }
+    }
+
+    /// The compared fields of this digest, by name.
+    fn components(&self) -> [(&'static str, Option<&str>); 4] {
+        [
+            ("digest", Some(&self.digest)),
+            ("schema_version", self.schema_version.as_deref()),
+            ("normalization_version", self.normalization_version.as_deref()),
+            ("hash_contract_version", self.hash_contract_version.as_deref()),
+        ]
+    }
+
+    /// Record the versions `document` was ingested under.
+    pub fn with_document(mut self, document: &Document) -> Self {
+        self.schema_version = Some(document.schema_version.clone());
+        self.normalization_version = Some(document.normalization_version.clone());
+        self.hash_contract_version = Some(document.hash_contract_version.clone());
+        self
+    }
+}
+
+// ---------------------------------------------------------------------------
+// RunManifest
+// ---------------------------------------------------------------------------
+
+/// How a compare or merge result was produced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// Producing crates and their versions, e.g. `{"rt-compare": "0.1.0"}`.
+    pub engines: BTreeMap<String, String>,
+    /// SHA-256 of the engine configuration serialised as JSON.
+    pub config_fingerprint: String,
+    /// Input documents in the order the engine took them.
+    pub inputs: Vec<InputDigest>,
+    /// Which deltas a compare run kept (e.g. `"changes_only"`); `None` for
+    /// merges and for runs recorded without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_mode: Option<String>,
+}
+
+/// One component that differs between a recorded and a current manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestDrift {
+    /// What changed, e.g. `engine rt-compare`, `config` or
+    /// `input <uuid> digest`.
+    pub component: String,
+    /// Value in the recorded manifest; `None` when it had none.
+    pub recorded: Option<String>,
+    /// Value now; `None` when the component is gone.
+    pub current: Option<String>,
+}
+
+impl RunManifest {
+    pub fn new(
+        engines: impl IntoIterator<Item = (&'static str, &'static str)>,
+        config_fingerprint: String,
+        inputs: Vec<InputDigest>,
+    ) -> Self {
+        Self {
+            engines: engines
+                .into_iter()
+                .map(|(name, version)| (name.to_string(), version.to_string()))
+                .collect(),
+            config_fingerprint,
+            inputs,
+            output_mode: None,
+        }
+    }
+
+    /// Set the ingest versions of the input for `document`, if it is one.
+    pub fn attach_document(&mut self, document: &Document) {
+        for input in &mut self.inputs {
+            if input.document_id == document.id {
+                *input = input.clone().with_document(document);
+            }
+        }
+    }
+
+    /// Every component of `self` (as recorded) that differs in `current`.
+    /// Empty when the run is reproducible.
+    pub fn drift(&self, current: &RunManifest) -> Vec<ManifestDrift> {
+        let mut drift = Vec::new();
+        let mut check = |component: String, recorded: Option<&str>, now: Option<&str>| {
+            if recorded != now {
+                drift.push(ManifestDrift {
+                    component,
+                    recorded: recorded.map(str::to_string),
+                    current: now.map(str::to_string),
+                });
+            }
+        };
+
+        let names: std::collections::BTreeSet<&String> =
+            self.engines.keys().chain(current.engines.keys()).collect();
+        for name in names {
+            check(
+                format!("engine {name}"),
+                self.engines.get(name).map(String::as_str),
+                current.engines.get(name).map(String::as_str),
+            );
+        }
+        check(
+            "config".to_string(),
+            Some(&self.config_fingerprint),
+            Some(&current.config_fingerprint),
+        );
+
+        for recorded in &self.inputs {
+            let id = recorded.document_id;
+            let now = current
+                .inputs
+                .iter()
+                .find(|i| i.document_id == id)
+                .map(InputDigest::components);
+            for (index, (field, value)) in recorded.components().into_iter().enumerate() {
+                let now_value = now.and_then(|c| c[index].1);
+                check(format!("input {id} {field}"), value, now_value);
+            }
+        }
+        drift
+    }
+}
+
+/// SHA-256 of `config` serialised as JSON, for [`RunManifest::config_fingerprint`].
+pub fn config_fingerprint<T: Serialize>(config: &T) -> String {
+    sha256_hex(&serde_json::to_string(config).unwrap_or_default())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{BlockType, DocumentType};
+    use chrono::Utc;
+
+    fn blocks(doc: Uuid, texts: &[&str]) -> Vec<Block> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| {
+                Block::new(BlockType::Clause, format!("{}", i + 1), t, t, None, doc, i as i32)
+            })
+            .collect()
+    }
+
+    fn document(id: Uuid, normalization_version: &str) -> Document {
+        Document {
+            id,
+            name: "doc".into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: "1.0.0".into(),
+            normalization_version: normalization_version.into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn identical_manifests_have_no_drift() {
+        let doc = Uuid::new_v4();
+        let mut manifest = RunManifest::new(
+            [("rt-compare", "0.1.0")],
+            config_fingerprint(&serde_json::json!({"threshold": 0.5})),
+            vec![InputDigest::from_blocks(doc, &blocks(doc, &["alpha", "beta"]))],
+        );
+        manifest.attach_document(&document(doc, "1.0.0"));
+        assert_eq!(manifest.inputs[0].block_count, 2);
+        assert_eq!(manifest.inputs[0].normalization_version.as_deref(), Some("1.0.0"));
+        assert!(manifest.drift(&manifest.clone()).is_empty());
+    }
+
+    #[test]
+    fn every_changed_component_is_reported() {
+        let doc = Uuid::new_v4();
+        let input = InputDigest::from_blocks(doc, &blocks(doc, &["alpha", "beta"]));
+        let recorded = RunManifest::new(
+            [("rt-compare", "0.1.0")],
+            config_fingerprint(&1),
+            vec![input.clone().with_document(&document(doc, "1.0.0"))],
+        );
+        let current = RunManifest::new(
+            [("rt-compare", "0.2.0")],
+            config_fingerprint(&2),
+            vec![InputDigest::from_blocks(doc, &blocks(doc, &["alpha", "gamma"]))
+                .with_document(&document(doc, "1.1.0"))],
+        );
+
+        let components: Vec<String> =
+            recorded.drift(&current).into_iter().map(|d| d.component).collect();
+        assert_eq!(
+            components,
+            vec![
+                "engine rt-compare".to_string(),
+                "config".to_string(),
+                format!("input {doc} digest"),
+                format!("input {doc} normalization_version"),
+            ]
+        );
+
+        let gone = RunManifest::new([("rt-compare", "0.1.0")], config_fingerprint(&1), Vec::new());
+        let drift = recorded.drift(&gone);
+        assert!(drift.iter().all(|d| d.current.is_none()));
+        assert_eq!(drift.len(), 4);
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ---------------------------------------------------------------------------
+// DefinedTerm
+// ---------------------------------------------------------------------------
+
+/// A term a document defines, e.g. "Borrower" from `“Borrower” means …`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefinedTerm {
+    /// The term as written in its definition.
+    pub term: String,
+    /// Normalized tokens of the term joined by single spaces; unique within
+    /// a document.
+    pub normalized: String,
+    /// Block holding the definition.
+    pub block_id: Uuid,
+}
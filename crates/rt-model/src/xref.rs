@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ---------------------------------------------------------------------------
+// CrossReference
+// ---------------------------------------------------------------------------
+
+/// A reference from a block's text to another part of the document, e.g.
+/// `Section 5.2(b)` in "as set forth in Section 5.2(b)".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossReference {
+    /// Block whose text holds the reference.
+    pub block_id: Uuid,
+    /// The reference as written.
+    pub text: String,
+    /// Referenced structural path, lowercased and without a trailing `.`.
+    pub target: String,
+    /// Byte offset of `text` in the block's canonical text.
+    pub offset: usize,
+}
@@ -0,0 +1,96 @@
+// Tests of the `@rtflow/node` addon; run with `npm run build && npm test`.
+
+import assert from 'node:assert/strict';
+import { mkdtempSync } from 'node:fs';
+import { createRequire } from 'node:module';
+import { tmpdir } from 'node:os';
+import { join } from 'node:path';
+import { before, test } from 'node:test';
+
+const rtflow = createRequire(import.meta.url)('../index.js');
+
+const CONTRACT = `MASTER SERVICES AGREEMENT
+
+1. Term
+This Agreement begins on the Effective Date and continues for two years.
+
+2. Payment
+The Customer shall pay each invoice within thirty days.
+`;
+
+const AMENDED = CONTRACT.replace('thirty days', 'sixty days');
+
+before(() => {
+  rtflow.init(join(mkdtempSync(join(tmpdir(), 'rtflow-')), 'contracts.db'));
+});
+
+test('init may only be called once', () => {
+  assert.throws(() => rtflow.init(':memory:'), /already initialized/);
+});
+
+test('ingest sync and async', async () => {
+  const sync = rtflow.ingestText(CONTRACT, 'msa.txt');
+  const async_ = await rtflow.ingestTextAsync(CONTRACT, 'msa-copy.txt');
+  assert.equal(sync.count, async_.count);
+  assert.equal(sync.document.name, 'msa.txt');
+
+  assert.throws(() => rtflow.ingestText(CONTRACT, 'bad.txt', 'not-a-uuid'), /invalid docId/);
+  await assert.rejects(rtflow.ingestDocxAsync(join(tmpdir(), 'missing.docx')));
+});
+
+test('ingestBlocks validates before writing', () => {
+  const blocks = rtflow.getBlocks(rtflow.ingestText(CONTRACT, 'source.txt').doc_id);
+  const stranger = '11111111-1111-1111-1111-111111111111';
+  assert.throws(() => rtflow.ingestBlocks(stranger, blocks));
+  assert.deepEqual(rtflow.getBlocks(stranger), []);
+});
+
+test('compare stored documents and blocks', async () => {
+  const left = rtflow.ingestText(CONTRACT, 'v1.txt').doc_id;
+  const right = rtflow.ingestText(AMENDED, 'v2.txt').doc_id;
+  const options = { output_mode: 'changes_only' };
+
+  const sync = rtflow.compare(left, right, options);
+  const async_ = await rtflow.compareAsync(left, right, options);
+  assert.equal(sync.stats.modified, 1);
+  assert.deepEqual(async_.stats, sync.stats);
+  assert.equal(async_.left_doc_id, left);
+
+  const blocks = await rtflow.compareBlocksAsync(
+    rtflow.getBlocks(left),
+    rtflow.getBlocks(right, true),
+    options,
+  );
+  assert.deepEqual(blocks.stats, sync.stats);
+
+  assert.throws(() => rtflow.compare(left, right, { no_such_option: true }));
+  // Bad arguments throw before a Promise is handed back.
+  assert.throws(() => rtflow.compareAsync('not-a-uuid', right), /invalid leftDocId/);
+});
+
+test('merge stored documents and blocks', async () => {
+  const base = rtflow.ingestText(CONTRACT, 'base.txt');
+  const incoming = rtflow.ingestText(AMENDED, 'incoming.txt');
+
+  const stored = await rtflow.mergeAsync(base.doc_id, incoming.doc_id);
+  assert.equal(stored.base_doc_id, base.doc_id);
+  const transient = rtflow.mergeBlocks(
+    rtflow.getBlocks(base.doc_id),
+    rtflow.getBlocks(incoming.doc_id),
+  );
+  assert.equal(transient.auto_resolved, stored.auto_resolved);
+});
+
+test('workflow', () => {
+  const docId = rtflow.ingestText(CONTRACT, 'wf.txt').doc_id;
+  const created = rtflow.workflowCreate(docId, 'alice');
+  const running = rtflow.workflowEvent(created.id, 'compare_started', 'alice');
+  assert.notEqual(running.state, created.state);
+  assert.equal(rtflow.workflowState(created.id).state, running.state);
+  assert.deepEqual(
+    rtflow.workflowEvents(created.id).map((e) => e.event_type),
+    ['workflow_created', 'compare_started'],
+  );
+  assert.equal(rtflow.workflowList({ document_id: docId }).length, 1);
+  assert.throws(() => rtflow.workflowEvent(created.id, 'not_an_event', 'alice'));
+});
@@ -0,0 +1,94 @@
+//! `compare` and `compareBlocks`.
+
+use napi::bindgen_prelude::AsyncTask;
+use napi_derive::napi;
+use serde_json::Value;
+use uuid::Uuid;
+
+use rt_compare::worker::{CompareConfig, CompareEngine};
+use rt_core::db::BlockStore;
+use rt_core::hashing::{align_anchors, check_contracts};
+use rt_core::Block;
+
+use crate::{
+    block_store, hasher, options_text, parse_id, reason, run, spawn, to_value, EngineTask, Job,
+};
+
+fn engine(options: &Option<Value>) -> napi::Result<CompareEngine> {
+    let config = CompareConfig::from_json(&options_text(options)).map_err(reason)?;
+    Ok(CompareEngine::new(config))
+}
+
+/// Loads both documents up front, so a missing document or a hash contract
+/// mismatch throws before any work is queued.
+fn documents_job(left: String, right: String, options: Option<Value>) -> napi::Result<Job> {
+    let engine = engine(&options)?;
+    let left_id = parse_id(&left, "leftDocId")?;
+    let right_id = parse_id(&right, "rightDocId")?;
+    let blocks = block_store()?;
+
+    let left_doc = blocks.get_document(&left_id).map_err(reason)?;
+    let right_doc = blocks.get_document(&right_id).map_err(reason)?;
+    check_contracts(&[&left_doc, &right_doc]).map_err(reason)?;
+    let left = blocks.get_block_tree(&left_id).map_err(reason)?;
+    let mut right = blocks.get_block_tree(&right_id).map_err(reason)?;
+    if left_doc.anchor_version != right_doc.anchor_version {
+        align_anchors(&left_doc, &right_doc, &mut right, &hasher()?).map_err(reason)?;
+    }
+
+    Ok(Box::new(move || {
+        to_value(&engine.compare(left_id, right_id, &left, &right))
+    }))
+}
+
+fn blocks_job(left: Value, right: Value, options: Option<Value>) -> napi::Result<Job> {
+    let engine = engine(&options)?;
+    let left: Vec<Block> =
+        serde_json::from_value(left).map_err(|e| reason(format!("invalid left blocks: {e}")))?;
+    let right: Vec<Block> =
+        serde_json::from_value(right).map_err(|e| reason(format!("invalid right blocks: {e}")))?;
+    Ok(Box::new(move || {
+        to_value(&engine.compare(Uuid::nil(), Uuid::nil(), &left, &right))
+    }))
+}
+
+/// Compare two stored documents. `options` takes the compare options of
+/// the native `rtflow_compare` (`similarity_threshold`, `output_mode`,
+/// `deterministic`, …); unknown ones throw. Documents hashed under
+/// different contracts are refused; differing anchor strategies are
+/// reconciled by re-anchoring the right document's blocks.
+#[napi]
+pub fn compare(
+    left_doc_id: String,
+    right_doc_id: String,
+    options: Option<Value>,
+) -> napi::Result<Value> {
+    run(documents_job(left_doc_id, right_doc_id, options))
+}
+
+/// [`compare`] off the event loop.
+#[napi]
+pub fn compare_async(
+    left_doc_id: String,
+    right_doc_id: String,
+    options: Option<Value>,
+) -> napi::Result<AsyncTask<EngineTask>> {
+    spawn(documents_job(left_doc_id, right_doc_id, options))
+}
+
+/// Compare two lists of blocks (flat or nested) without touching the
+/// database. The document ids in the result are the nil UUID.
+#[napi]
+pub fn compare_blocks(left: Value, right: Value, options: Option<Value>) -> napi::Result<Value> {
+    run(blocks_job(left, right, options))
+}
+
+/// [`compare_blocks`] off the event loop.
+#[napi]
+pub fn compare_blocks_async(
+    left: Value,
+    right: Value,
+    options: Option<Value>,
+) -> napi::Result<AsyncTask<EngineTask>> {
+    spawn(blocks_job(left, right, options))
+}
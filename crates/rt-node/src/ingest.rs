@@ -0,0 +1,169 @@
+//! `ingestDocx`, `ingestText` and `ingestBlocks`, and `getBlocks` to read
+//! a document back.
+
+use chrono::Utc;
+use napi::bindgen_prelude::AsyncTask;
+use napi_derive::napi;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use rt_core::db::{BlockStore, ConflictPolicy};
+use rt_core::schema::SCHEMA_VERSION;
+use rt_core::tenant::TenantContext;
+use rt_core::validation::validate_blocks;
+use rt_core::{Block, Document, DocumentType};
+use rt_ingest::{IngestOptions, IngestSummary};
+
+use crate::{
+    block_store, from_value_or_default, hasher, parse_id, pool, reason, run, spawn, to_value,
+    EngineTask, Job,
+};
+
+/// The ingest result handed back to JS.
+fn summary_value(summary: &IngestSummary) -> Value {
+    json!({
+        "doc_id": summary.document.id,
+        "count": summary.block_count,
+        "document": summary.document,
+    })
+}
+
+fn new_doc_id(doc_id: Option<&str>) -> napi::Result<Uuid> {
+    doc_id.map_or_else(|| Ok(Uuid::new_v4()), |id| parse_id(id, "docId"))
+}
+
+fn docx_job(path: String, doc_id: Option<String>, options: Option<Value>) -> napi::Result<Job> {
+    let doc_id = new_doc_id(doc_id.as_deref())?;
+    let options: IngestOptions = from_value_or_default(options, "ingest options")?;
+    let hasher = hasher()?;
+    let store = block_store()?;
+    Ok(Box::new(move || {
+        rt_ingest::ingest_docx_with_options(&store, path.as_ref(), doc_id, &hasher, &options)
+            .map(|summary| summary_value(&summary))
+            .map_err(reason)
+    }))
+}
+
+fn text_job(
+    text: String,
+    name: String,
+    doc_id: Option<String>,
+    options: Option<Value>,
+) -> napi::Result<Job> {
+    let doc_id = new_doc_id(doc_id.as_deref())?;
+    let options: IngestOptions = from_value_or_default(options, "ingest options")?;
+    let hasher = hasher()?;
+    let store = block_store()?;
+    Ok(Box::new(move || {
+        rt_ingest::ingest_text(&store, &text, &name, doc_id, &hasher, &options)
+            .map(|summary| summary_value(&summary))
+            .map_err(reason)
+    }))
+}
+
+/// Ingest the `.docx` at `path` as a new document. `options` takes
+/// `max_block_tokens` and `anchor_version`.
+#[napi]
+pub fn ingest_docx(
+    path: String,
+    doc_id: Option<String>,
+    options: Option<Value>,
+) -> napi::Result<Value> {
+    run(docx_job(path, doc_id, options))
+}
+
+/// [`ingest_docx`] off the event loop.
+#[napi]
+pub fn ingest_docx_async(
+    path: String,
+    doc_id: Option<String>,
+    options: Option<Value>,
+) -> napi::Result<AsyncTask<EngineTask>> {
+    spawn(docx_job(path, doc_id, options))
+}
+
+/// Segment plain contract text into blocks and ingest it as a new document
+/// named `name`. Takes the same options as `ingestDocx`.
+#[napi]
+pub fn ingest_text(
+    text: String,
+    name: String,
+    doc_id: Option<String>,
+    options: Option<Value>,
+) -> napi::Result<Value> {
+    run(text_job(text, name, doc_id, options))
+}
+
+/// [`ingest_text`] off the event loop.
+#[napi]
+pub fn ingest_text_async(
+    text: String,
+    name: String,
+    doc_id: Option<String>,
+    options: Option<Value>,
+) -> napi::Result<AsyncTask<EngineTask>> {
+    spawn(text_job(text, name, doc_id, options))
+}
+
+/// Store already built blocks (parents before children) under `docId`,
+/// creating a minimal document record when there is none. `onConflict` is
+/// `"fail"` (the default), `"skip"` or `"replace"`. Blocks that fail
+/// validation (a dangling parent, a repeated structural path, another
+/// document's id) are refused and nothing is written. Returns the number of
+/// blocks written.
+#[napi(ts_args_type = "docId: string, blocks: object[], onConflict?: string")]
+pub fn ingest_blocks(
+    doc_id: String,
+    blocks: Value,
+    on_conflict: Option<String>,
+) -> napi::Result<u32> {
+    let id = parse_id(&doc_id, "docId")?;
+    let blocks: Vec<Block> =
+        serde_json::from_value(blocks).map_err(|e| reason(format!("invalid blocks: {e}")))?;
+    let policy: ConflictPolicy =
+        serde_json::from_value(Value::String(on_conflict.unwrap_or_else(|| "fail".into())))
+            .map_err(|e| reason(format!("invalid onConflict: {e}")))?;
+
+    let hasher = hasher()?;
+    let conn = pool()?.get().map_err(reason)?;
+    let report =
+        validate_blocks(&conn, &TenantContext::default(), id, &blocks, policy).map_err(reason)?;
+    drop(conn);
+    if !report.is_valid() {
+        return Err(reason(report.summary()));
+    }
+
+    let store = block_store()?;
+    let doc = Document {
+        id,
+        name: doc_id,
+        source_path: None,
+        doc_type: DocumentType::Original,
+        schema_version: SCHEMA_VERSION.to_string(),
+        normalization_version: "1.0.0".to_string(),
+        hash_contract_version: hasher.contract_version().to_string(),
+        anchor_version: "1".to_string(),
+        ingested_at: Utc::now(),
+        metadata: None,
+    };
+    store
+        .upsert_document(&doc, ConflictPolicy::Skip)
+        .map_err(reason)?;
+    let written = store.upsert_blocks(&blocks, policy).map_err(reason)?;
+    Ok(written as u32)
+}
+
+/// The blocks of `docId` in document order: flat, or with `tree` the root
+/// blocks with their `children` nested.
+#[napi]
+pub fn get_blocks(doc_id: String, tree: Option<bool>) -> napi::Result<Value> {
+    let id = parse_id(&doc_id, "docId")?;
+    let store = block_store()?;
+    let blocks = if tree.unwrap_or(false) {
+        store.get_block_tree(&id)
+    } else {
+        store.get_blocks_by_document(&id)
+    }
+    .map_err(reason)?;
+    to_value(&blocks)
+}
@@ -0,0 +1,136 @@
+//! Node.js bindings for the RT_Flow engines.
+//!
+//! ```js
+//! const rtflow = require('@rtflow/node');
+//!
+//! rtflow.init('contracts.db');
+//! const left = rtflow.ingestDocx('msa-v1.docx').doc_id;
+//! const right = rtflow.ingestDocx('msa-v2.docx').doc_id;
+//! const result = await rtflow.compareAsync(left, right, { output_mode: 'changes_only' });
+//! ```
+//!
+//! Like the C ABI in `rt-ffi`, the module works on one database opened by
+//! [`init`]. Results are plain JS objects shaped as the JSON contracts in
+//! `contracts/`, and failures throw (or reject) an `Error` with the engine's
+//! message. Each engine call has a synchronous form and an `…Async` form
+//! that runs it on the libuv thread pool and returns a `Promise`, so a long
+//! compare or merge does not block the event loop.
+
+use std::sync::OnceLock;
+
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Task};
+use napi_derive::napi;
+use serde_json::Value;
+use uuid::Uuid;
+
+use rt_core::db::{create_pool, DbPool, SqliteBlockStore};
+use rt_core::hashing::default_hasher;
+use rt_core::ClauseHasher;
+
+mod compare;
+mod ingest;
+mod merge;
+mod workflow;
+
+static DB_POOL: OnceLock<DbPool> = OnceLock::new();
+
+/// Open (creating and migrating as needed) the database at `db_path`. May
+/// be called once per process.
+#[napi]
+pub fn init(db_path: String) -> napi::Result<()> {
+    let pool = create_pool(&db_path).map_err(reason)?;
+    DB_POOL
+        .set(pool)
+        .map_err(|_| reason("database already initialized; init may only be called once"))
+}
+
+// ---------------------------------------------------------------------------
+// Shared helpers
+// ---------------------------------------------------------------------------
+
+pub(crate) fn reason(e: impl std::fmt::Display) -> napi::Error {
+    napi::Error::from_reason(e.to_string())
+}
+
+pub(crate) fn pool() -> napi::Result<&'static DbPool> {
+    DB_POOL
+        .get()
+        .ok_or_else(|| reason("database not initialized; call init first"))
+}
+
+pub(crate) fn block_store() -> napi::Result<SqliteBlockStore> {
+    Ok(SqliteBlockStore::new(pool()?.clone()))
+}
+
+/// The hasher new blocks are hashed with, per the stored hash config.
+pub(crate) fn hasher() -> napi::Result<ClauseHasher> {
+    let conn = pool()?.get().map_err(reason)?;
+    default_hasher(&conn).map_err(reason)
+}
+
+pub(crate) fn parse_id(value: &str, name: &str) -> napi::Result<Uuid> {
+    Uuid::parse_str(value).map_err(|e| reason(format!("invalid {name} UUID: {e}")))
+}
+
+/// The JSON text of an optional options object; absent is the empty
+/// string, which the engines read as "all defaults".
+pub(crate) fn options_text(options: &Option<Value>) -> String {
+    options.as_ref().map(Value::to_string).unwrap_or_default()
+}
+
+/// Decode an optional options or filter object, `T::default()` when absent.
+pub(crate) fn from_value_or_default<T: serde::de::DeserializeOwned + Default>(
+    value: Option<Value>,
+    what: &str,
+) -> napi::Result<T> {
+    match value {
+        None => Ok(T::default()),
+        Some(value) => {
+            serde_json::from_value(value).map_err(|e| reason(format!("invalid {what}: {e}")))
+        }
+    }
+}
+
+pub(crate) fn to_value(value: &impl serde::Serialize) -> napi::Result<Value> {
+    serde_json::to_value(value).map_err(|e| reason(format!("failed to serialize result: {e}")))
+}
+
+// ---------------------------------------------------------------------------
+// Async tasks
+// ---------------------------------------------------------------------------
+
+/// An engine call, prepared on the JS thread and run by [`run`] or
+/// [`EngineTask`].
+pub(crate) type Job = Box<dyn FnOnce() -> napi::Result<Value> + Send>;
+
+/// Run `job` on the calling (JS) thread.
+pub(crate) fn run(job: napi::Result<Job>) -> napi::Result<Value> {
+    job?()
+}
+
+/// A [`Job`] run on the libuv thread pool; resolves its `Promise` with the
+/// job's result.
+pub struct EngineTask {
+    job: Option<Job>,
+}
+
+/// `job` as a `Promise`-returning task. Arguments are checked when the job
+/// is prepared, so bad input still throws synchronously.
+pub(crate) fn spawn(job: napi::Result<Job>) -> napi::Result<AsyncTask<EngineTask>> {
+    Ok(AsyncTask::new(EngineTask { job: Some(job?) }))
+}
+
+impl Task for EngineTask {
+    type Output = Value;
+    type JsValue = Value;
+
+    fn compute(&mut self) -> napi::Result<Value> {
+        let job = self.job.take().ok_or_else(|| reason("task already ran"))?;
+        job()
+    }
+
+    fn resolve(&mut self, _env: Env, output: Value) -> napi::Result<Value> {
+        Ok(output)
+    }
+}
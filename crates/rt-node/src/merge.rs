@@ -0,0 +1,87 @@
+//! `merge` and `mergeBlocks`.
+
+use chrono::Duration;
+use napi::bindgen_prelude::AsyncTask;
+use napi_derive::napi;
+use serde_json::Value;
+use uuid::Uuid;
+
+use rt_core::Block;
+use rt_merge::merge::{MergeConfig, MergeEngine};
+use rt_merge::run::{run_merge, MergeRun, DEFAULT_MERGE_LEASE_SECS};
+use rt_merge::store::SqliteMergeStore;
+
+use crate::{
+    block_store, options_text, parse_id, pool, reason, run, spawn, to_value, EngineTask, Job,
+};
+
+fn engine(options: &Option<Value>) -> napi::Result<MergeEngine> {
+    let config = MergeConfig::from_json(&options_text(options)).map_err(reason)?;
+    Ok(MergeEngine::new().with_config(config))
+}
+
+fn documents_job(base: String, incoming: String, options: Option<Value>) -> napi::Result<Job> {
+    let engine = engine(&options)?;
+    let run = MergeRun::new(
+        parse_id(&base, "baseDocId")?,
+        parse_id(&incoming, "incomingDocId")?,
+        None,
+    );
+    let blocks = block_store()?;
+    let merges = SqliteMergeStore::new(pool()?.clone());
+    Ok(Box::new(move || {
+        let lease = Duration::seconds(DEFAULT_MERGE_LEASE_SECS);
+        let result = run_merge(&blocks, &merges, &engine, &run, lease).map_err(reason)?;
+        to_value(&result)
+    }))
+}
+
+fn blocks_job(base: Value, incoming: Value, options: Option<Value>) -> napi::Result<Job> {
+    let engine = engine(&options)?;
+    let base: Vec<Block> =
+        serde_json::from_value(base).map_err(|e| reason(format!("invalid base blocks: {e}")))?;
+    let incoming: Vec<Block> = serde_json::from_value(incoming)
+        .map_err(|e| reason(format!("invalid incoming blocks: {e}")))?;
+    Ok(Box::new(move || {
+        to_value(&engine.merge(Uuid::nil(), Uuid::nil(), &base, &incoming))
+    }))
+}
+
+/// Merge two stored documents, recording the merge run and its conflicts
+/// for review. `options` takes the merge options of the native
+/// `rtflow_merge`; unknown ones throw.
+#[napi]
+pub fn merge(
+    base_doc_id: String,
+    incoming_doc_id: String,
+    options: Option<Value>,
+) -> napi::Result<Value> {
+    run(documents_job(base_doc_id, incoming_doc_id, options))
+}
+
+/// [`merge`] off the event loop.
+#[napi]
+pub fn merge_async(
+    base_doc_id: String,
+    incoming_doc_id: String,
+    options: Option<Value>,
+) -> napi::Result<AsyncTask<EngineTask>> {
+    spawn(documents_job(base_doc_id, incoming_doc_id, options))
+}
+
+/// Merge two lists of blocks without storing anything. The document ids in
+/// the result are the nil UUID.
+#[napi]
+pub fn merge_blocks(base: Value, incoming: Value, options: Option<Value>) -> napi::Result<Value> {
+    run(blocks_job(base, incoming, options))
+}
+
+/// [`merge_blocks`] off the event loop.
+#[napi]
+pub fn merge_blocks_async(
+    base: Value,
+    incoming: Value,
+    options: Option<Value>,
+) -> napi::Result<AsyncTask<EngineTask>> {
+    spawn(blocks_job(base, incoming, options))
+}
@@ -0,0 +1,93 @@
+//! `workflowCreate`, `workflowEvent`, `workflowState`, `workflowEvents` and
+//! `workflowList`.
+//!
+//! Workflow calls are short database reads and writes, so they only come in
+//! synchronous form.
+
+use napi_derive::napi;
+use serde_json::{json, Value};
+
+use rt_core::db::DbConn;
+use rt_core::tenant::TenantContext;
+use rt_workflow::commands::{WorkflowEngine, WorkflowFilter};
+use rt_workflow::event::EventType;
+
+use crate::{from_value_or_default, parse_id, pool, reason, to_value};
+
+fn conn() -> napi::Result<DbConn> {
+    pool()?
+        .get()
+        .map_err(|e| reason(format!("failed to acquire connection: {e}")))
+}
+
+/// Start a workflow on the stored document `documentId`.
+#[napi]
+pub fn workflow_create(document_id: String, initiator_id: String) -> napi::Result<Value> {
+    let document_id = parse_id(&document_id, "documentId")?;
+    let conn = conn()?;
+    let workflow = WorkflowEngine::create_workflow(
+        &conn,
+        &TenantContext::default(),
+        document_id,
+        &initiator_id,
+    )
+    .map_err(reason)?;
+    to_value(&workflow)
+}
+
+/// Apply the event `eventType` (e.g. `"compare_started"`) by `actor` and
+/// return the workflow in its new state. Throws for a transition the
+/// workflow's state does not allow.
+#[napi]
+pub fn workflow_event(
+    workflow_id: String,
+    event_type: String,
+    actor: String,
+    payload: Option<Value>,
+) -> napi::Result<Value> {
+    let workflow_id = parse_id(&workflow_id, "workflowId")?;
+    let event_type: EventType = serde_json::from_value(Value::String(event_type))
+        .map_err(|e| reason(format!("invalid event type: {e}")))?;
+    let conn = conn()?;
+    let workflow = WorkflowEngine::submit_event(
+        &conn,
+        &TenantContext::default(),
+        workflow_id,
+        event_type,
+        &actor,
+        payload.unwrap_or_else(|| json!({})),
+    )
+    .map_err(reason)?;
+    to_value(&workflow)
+}
+
+/// The workflow `workflowId`, replayed from its event log.
+#[napi]
+pub fn workflow_state(workflow_id: String) -> napi::Result<Value> {
+    let workflow_id = parse_id(&workflow_id, "workflowId")?;
+    let conn = conn()?;
+    let workflow = WorkflowEngine::get_workflow(&conn, &TenantContext::default(), workflow_id)
+        .map_err(reason)?;
+    to_value(&workflow)
+}
+
+/// The events of `workflowId`, oldest first.
+#[napi]
+pub fn workflow_events(workflow_id: String) -> napi::Result<Value> {
+    let workflow_id = parse_id(&workflow_id, "workflowId")?;
+    let conn = conn()?;
+    let events = WorkflowEngine::get_events(&conn, &TenantContext::default(), workflow_id)
+        .map_err(reason)?;
+    to_value(&events)
+}
+
+/// Workflows, oldest first. `filter` takes `document_id`, `state`,
+/// `initiator_id`, `created_from`, `created_to`, `offset` and `limit`.
+#[napi]
+pub fn workflow_list(filter: Option<Value>) -> napi::Result<Value> {
+    let filter: WorkflowFilter = from_value_or_default(filter, "workflow filter")?;
+    let conn = conn()?;
+    let workflows = WorkflowEngine::list_workflows(&conn, &TenantContext::default(), &filter)
+        .map_err(reason)?;
+    to_value(&workflows)
+}
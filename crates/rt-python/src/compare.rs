@@ -0,0 +1,81 @@
+//! `rtflow.CompareEngine`.
+
+use pyo3::prelude::*;
+
+use rt_compare::worker::{CompareConfig, CompareEngine};
+use rt_core::db::BlockStore;
+use rt_core::hashing::{align_anchors, check_contracts};
+use rt_core::Block;
+
+use crate::store::PyStore;
+use crate::{from_py, options_text, parse_id, parse_id_or_nil, py_err, to_py};
+
+/// A compare engine with fixed options.
+///
+/// `options` takes the compare options of the native `rtflow_compare`
+/// (`similarity_threshold`, `output_mode`, `deterministic`, …); omitted
+/// ones keep their defaults and unknown ones raise `ValueError`. Results
+/// are `CompareResult` dicts (see `contracts/compare-result.json`).
+#[pyclass(name = "CompareEngine", module = "rtflow", frozen)]
+pub struct PyCompareEngine {
+    engine: CompareEngine,
+}
+
+#[pymethods]
+impl PyCompareEngine {
+    #[new]
+    #[pyo3(signature = (options=None))]
+    fn new(options: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let config = CompareConfig::from_json(&options_text(options)?).map_err(py_err)?;
+        Ok(Self {
+            engine: CompareEngine::new(config),
+        })
+    }
+
+    /// Compare two lists of blocks (flat or nested), e.g. from
+    /// `Store.blocks`. The document ids in the result default to the nil
+    /// UUID.
+    #[pyo3(signature = (left, right, left_doc_id=None, right_doc_id=None))]
+    fn compare<'py>(
+        &self,
+        py: Python<'py>,
+        left: &Bound<'py, PyAny>,
+        right: &Bound<'py, PyAny>,
+        left_doc_id: Option<&str>,
+        right_doc_id: Option<&str>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let left_id = parse_id_or_nil(left_doc_id, "left_doc_id")?;
+        let right_id = parse_id_or_nil(right_doc_id, "right_doc_id")?;
+        let left: Vec<Block> = from_py(left, "left blocks")?;
+        let right: Vec<Block> = from_py(right, "right blocks")?;
+        let result = py.allow_threads(|| self.engine.compare(left_id, right_id, &left, &right));
+        to_py(py, &result)
+    }
+
+    /// Compare two documents stored in `store`. Documents hashed under
+    /// different contracts are refused; differing anchor strategies are
+    /// reconciled by re-anchoring the right document's blocks.
+    fn compare_documents<'py>(
+        &self,
+        py: Python<'py>,
+        store: PyRef<'py, PyStore>,
+        left_doc_id: &str,
+        right_doc_id: &str,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let left_id = parse_id(left_doc_id, "left_doc_id")?;
+        let right_id = parse_id(right_doc_id, "right_doc_id")?;
+        let blocks = store.block_store();
+
+        let left_doc = blocks.get_document(&left_id).map_err(py_err)?;
+        let right_doc = blocks.get_document(&right_id).map_err(py_err)?;
+        check_contracts(&[&left_doc, &right_doc]).map_err(py_err)?;
+        let left = blocks.get_block_tree(&left_id).map_err(py_err)?;
+        let mut right = blocks.get_block_tree(&right_id).map_err(py_err)?;
+        if left_doc.anchor_version != right_doc.anchor_version {
+            align_anchors(&left_doc, &right_doc, &mut right, &store.hasher()?).map_err(py_err)?;
+        }
+
+        let result = py.allow_threads(|| self.engine.compare(left_id, right_id, &left, &right));
+        to_py(py, &result)
+    }
+}
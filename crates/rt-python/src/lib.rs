@@ -0,0 +1,114 @@
+//! Python bindings for the RT_Flow engines, as the `rtflow` module.
+//!
+//! ```python
+//! import rtflow
+//!
+//! store = rtflow.Store("contracts.db")
+//! left = store.ingest_docx("msa-v1.docx")["doc_id"]
+//! right = store.ingest_docx("msa-v2.docx")["doc_id"]
+//! result = rtflow.CompareEngine({"output_mode": "changes_only"}).compare_documents(
+//!     store, left, right
+//! )
+//! print(result["stats"])
+//! ```
+//!
+//! Structured values cross the boundary as plain Python data: arguments
+//! such as blocks, options and filters are dicts and lists (or the same
+//! value as a JSON string), and results come back as dicts and lists with
+//! the shapes of the JSON contracts in `contracts/`. Engine errors are
+//! raised as [`RtflowError`]; invalid input as `ValueError` and missing
+//! documents, blocks and workflows as `LookupError`.
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyLookupError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+use rt_core::RtError;
+
+mod compare;
+mod merge;
+mod store;
+mod workflow;
+
+create_exception!(
+    rtflow,
+    RtflowError,
+    PyException,
+    "An RT_Flow engine or storage failure."
+);
+
+/// The Python exception for `error`.
+pub(crate) fn py_err(error: RtError) -> PyErr {
+    match error {
+        RtError::NotFound(_) => PyLookupError::new_err(error.to_string()),
+        RtError::InvalidInput(_) | RtError::HashMismatch { .. } => {
+            PyValueError::new_err(error.to_string())
+        }
+        _ => RtflowError::new_err(error.to_string()),
+    }
+}
+
+/// `value` as Python data, by way of its JSON form.
+pub(crate) fn to_py<'py, T: Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| RtflowError::new_err(format!("failed to serialize result: {e}")))?;
+    py.import_bound("json")?.call_method1("loads", (json,))
+}
+
+/// The JSON text of `value`: a `str` is taken as JSON already, anything
+/// else is encoded with the `json` module.
+pub(crate) fn json_text(value: &Bound<'_, PyAny>) -> PyResult<String> {
+    if value.is_instance_of::<PyString>() {
+        return value.extract();
+    }
+    value
+        .py()
+        .import_bound("json")?
+        .call_method1("dumps", (value,))?
+        .extract()
+}
+
+/// The JSON text of an optional options or filter argument; `None` is the
+/// empty string, which the engines read as "all defaults".
+pub(crate) fn options_text(value: Option<&Bound<'_, PyAny>>) -> PyResult<String> {
+    value.map_or_else(|| Ok(String::new()), json_text)
+}
+
+/// Decode `value` (Python data or a JSON string) as a `T`; `what` names it
+/// in the error.
+pub(crate) fn from_py<T: DeserializeOwned>(value: &Bound<'_, PyAny>, what: &str) -> PyResult<T> {
+    serde_json::from_str(&json_text(value)?)
+        .map_err(|e| PyValueError::new_err(format!("invalid {what}: {e}")))
+}
+
+/// [`from_py`] for an optional argument, `T::default()` when absent.
+pub(crate) fn from_py_or_default<T: DeserializeOwned + Default>(
+    value: Option<&Bound<'_, PyAny>>,
+    what: &str,
+) -> PyResult<T> {
+    value.map_or_else(|| Ok(T::default()), |v| from_py(v, what))
+}
+
+/// Parse the UUID argument `name`.
+pub(crate) fn parse_id(value: &str, name: &str) -> PyResult<Uuid> {
+    Uuid::parse_str(value).map_err(|e| PyValueError::new_err(format!("invalid {name} UUID: {e}")))
+}
+
+/// Parse an optional UUID argument, the nil UUID when absent.
+pub(crate) fn parse_id_or_nil(value: Option<&str>, name: &str) -> PyResult<Uuid> {
+    value.map_or(Ok(Uuid::nil()), |v| parse_id(v, name))
+}
+
+#[pymodule]
+fn rtflow(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("RtflowError", m.py().get_type_bound::<RtflowError>())?;
+    m.add_class::<store::PyStore>()?;
+    m.add_class::<compare::PyCompareEngine>()?;
+    m.add_class::<merge::PyMergeEngine>()?;
+    m.add_class::<workflow::PyWorkflowEngine>()?;
+    Ok(())
+}
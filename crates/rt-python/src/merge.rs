@@ -0,0 +1,77 @@
+//! `rtflow.MergeEngine`.
+
+use chrono::Duration;
+use pyo3::prelude::*;
+
+use rt_core::Block;
+use rt_merge::merge::{MergeConfig, MergeEngine};
+use rt_merge::run::{run_merge, MergeRun, DEFAULT_MERGE_LEASE_SECS};
+use rt_merge::store::SqliteMergeStore;
+
+use crate::store::PyStore;
+use crate::{from_py, options_text, parse_id, parse_id_or_nil, py_err, to_py};
+
+/// A merge engine with fixed options.
+///
+/// `options` takes the merge options of the native `rtflow_merge`; omitted
+/// ones keep their defaults and unknown ones raise `ValueError`. Results
+/// are `MergeResult` dicts (see `contracts/merge-result.json`).
+#[pyclass(name = "MergeEngine", module = "rtflow", frozen)]
+pub struct PyMergeEngine {
+    engine: MergeEngine,
+}
+
+#[pymethods]
+impl PyMergeEngine {
+    #[new]
+    #[pyo3(signature = (options=None))]
+    fn new(options: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let config = MergeConfig::from_json(&options_text(options)?).map_err(py_err)?;
+        Ok(Self {
+            engine: MergeEngine::new().with_config(config),
+        })
+    }
+
+    /// Merge two lists of blocks without storing anything. The document
+    /// ids in the result default to the nil UUID.
+    #[pyo3(signature = (base, incoming, base_doc_id=None, incoming_doc_id=None))]
+    fn merge<'py>(
+        &self,
+        py: Python<'py>,
+        base: &Bound<'py, PyAny>,
+        incoming: &Bound<'py, PyAny>,
+        base_doc_id: Option<&str>,
+        incoming_doc_id: Option<&str>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let base_id = parse_id_or_nil(base_doc_id, "base_doc_id")?;
+        let incoming_id = parse_id_or_nil(incoming_doc_id, "incoming_doc_id")?;
+        let base: Vec<Block> = from_py(base, "base blocks")?;
+        let incoming: Vec<Block> = from_py(incoming, "incoming blocks")?;
+        let result =
+            py.allow_threads(|| self.engine.merge(base_id, incoming_id, &base, &incoming));
+        to_py(py, &result)
+    }
+
+    /// Merge two documents stored in `store`, recording the merge run and
+    /// its conflicts there for review.
+    fn merge_documents<'py>(
+        &self,
+        py: Python<'py>,
+        store: PyRef<'py, PyStore>,
+        base_doc_id: &str,
+        incoming_doc_id: &str,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let run = MergeRun::new(
+            parse_id(base_doc_id, "base_doc_id")?,
+            parse_id(incoming_doc_id, "incoming_doc_id")?,
+            None,
+        );
+        let blocks = store.block_store();
+        let merges = SqliteMergeStore::new(store.pool.clone());
+        let lease = Duration::seconds(DEFAULT_MERGE_LEASE_SECS);
+        let result = py
+            .allow_threads(|| run_merge(&blocks, &merges, &self.engine, &run, lease))
+            .map_err(py_err)?;
+        to_py(py, &result)
+    }
+}
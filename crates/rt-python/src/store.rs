@@ -0,0 +1,231 @@
+//! `rtflow.Store`: a document database and ingestion into it.
+
+use std::path::PathBuf;
+
+use chrono::Utc;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use uuid::Uuid;
+
+use rt_core::db::{
+    create_memory_pool, create_pool, BlockSearchFilter, BlockStore, ConflictPolicy, DbPool,
+    DocumentFilter, SqliteBlockStore,
+};
+use rt_core::hashing::default_hasher;
+use rt_core::schema::SCHEMA_VERSION;
+use rt_core::tenant::TenantContext;
+use rt_core::validation::validate_blocks;
+use rt_core::{ClauseHasher, Document, DocumentType};
+use rt_ingest::{IngestOptions, IngestSummary};
+
+use crate::{from_py, from_py_or_default, parse_id, py_err, to_py, RtflowError};
+
+/// A document database: a SQLite file, or an in-memory database that lives
+/// as long as the store.
+///
+/// ```python
+/// store = rtflow.Store("contracts.db")
+/// doc_id = store.ingest_text(text, "msa.txt")["doc_id"]
+/// blocks = store.blocks(doc_id)
+/// ```
+#[pyclass(name = "Store", module = "rtflow", frozen)]
+pub struct PyStore {
+    pub(crate) pool: DbPool,
+}
+
+impl PyStore {
+    pub(crate) fn block_store(&self) -> SqliteBlockStore {
+        SqliteBlockStore::new(self.pool.clone())
+    }
+
+    /// The hasher new blocks are hashed with, per the stored hash config.
+    pub(crate) fn hasher(&self) -> PyResult<ClauseHasher> {
+        let conn = self
+            .pool
+            .get()
+            .map_err(|e| RtflowError::new_err(format!("failed to acquire connection: {e}")))?;
+        default_hasher(&conn).map_err(py_err)
+    }
+}
+
+/// The ingest result handed back to Python.
+fn summary_to_py<'py>(py: Python<'py>, summary: &IngestSummary) -> PyResult<Bound<'py, PyAny>> {
+    to_py(
+        py,
+        &serde_json::json!({
+            "doc_id": summary.document.id,
+            "count": summary.block_count,
+            "document": summary.document,
+        }),
+    )
+}
+
+fn new_doc_id(doc_id: Option<&str>) -> PyResult<Uuid> {
+    doc_id.map_or_else(|| Ok(Uuid::new_v4()), |id| parse_id(id, "doc_id"))
+}
+
+#[pymethods]
+impl PyStore {
+    /// Open (creating and migrating as needed) the database at `path`, or a
+    /// fresh in-memory database without one.
+    #[new]
+    #[pyo3(signature = (path=None))]
+    fn new(path: Option<PathBuf>) -> PyResult<Self> {
+        let pool = match path {
+            Some(path) => create_pool(&path.to_string_lossy()),
+            None => create_memory_pool(),
+        }
+        .map_err(py_err)?;
+        Ok(Self { pool })
+    }
+
+    /// Ingest the `.docx` at `path` as a new document. `options` takes
+    /// `max_block_tokens` and `anchor_version`. Returns
+    /// `{"doc_id": ..., "count": ..., "document": {...}}`.
+    #[pyo3(signature = (path, doc_id=None, options=None))]
+    fn ingest_docx<'py>(
+        &self,
+        py: Python<'py>,
+        path: PathBuf,
+        doc_id: Option<&str>,
+        options: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let doc_id = new_doc_id(doc_id)?;
+        let options: IngestOptions = from_py_or_default(options, "ingest options")?;
+        let hasher = self.hasher()?;
+        let store = self.block_store();
+        let summary = py
+            .allow_threads(|| {
+                rt_ingest::ingest_docx_with_options(&store, &path, doc_id, &hasher, &options)
+            })
+            .map_err(py_err)?;
+        summary_to_py(py, &summary)
+    }
+
+    /// Segment plain contract text into blocks and ingest it as a new
+    /// document named `name`. Takes the same options as `ingest_docx` and
+    /// returns the same dict.
+    #[pyo3(signature = (text, name, doc_id=None, options=None))]
+    fn ingest_text<'py>(
+        &self,
+        py: Python<'py>,
+        text: &str,
+        name: &str,
+        doc_id: Option<&str>,
+        options: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let doc_id = new_doc_id(doc_id)?;
+        let options: IngestOptions = from_py_or_default(options, "ingest options")?;
+        let hasher = self.hasher()?;
+        let store = self.block_store();
+        let summary = py
+            .allow_threads(|| {
+                rt_ingest::ingest_text(&store, text, name, doc_id, &hasher, &options)
+            })
+            .map_err(py_err)?;
+        summary_to_py(py, &summary)
+    }
+
+    /// Store already built blocks (parents before children) under
+    /// `doc_id`, creating a minimal document record when there is none.
+    /// `on_conflict` is `"fail"`, `"skip"` or `"replace"`. Blocks that fail
+    /// validation (a dangling parent, a repeated structural path, another
+    /// document's id) raise `ValueError` and nothing is written. Returns the
+    /// number of blocks written.
+    #[pyo3(signature = (doc_id, blocks, on_conflict="fail"))]
+    fn ingest_blocks(
+        &self,
+        doc_id: &str,
+        blocks: &Bound<'_, PyAny>,
+        on_conflict: &str,
+    ) -> PyResult<usize> {
+        let id = parse_id(doc_id, "doc_id")?;
+        let blocks: Vec<rt_core::Block> = from_py(blocks, "blocks")?;
+        let policy: ConflictPolicy =
+            serde_json::from_value(serde_json::Value::String(on_conflict.into()))
+                .map_err(|e| PyValueError::new_err(format!("invalid on_conflict: {e}")))?;
+
+        let hasher = self.hasher()?;
+        let conn = self
+            .pool
+            .get()
+            .map_err(|e| RtflowError::new_err(format!("failed to acquire connection: {e}")))?;
+        let report = validate_blocks(&conn, &TenantContext::default(), id, &blocks, policy)
+            .map_err(py_err)?;
+        drop(conn);
+        if !report.is_valid() {
+            return Err(PyValueError::new_err(report.summary()));
+        }
+
+        let store = self.block_store();
+        let doc = Document {
+            id,
+            name: doc_id.to_string(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: SCHEMA_VERSION.to_string(),
+            normalization_version: "1.0.0".to_string(),
+            hash_contract_version: hasher.contract_version().to_string(),
+            anchor_version: "1".to_string(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        };
+        store.upsert_document(&doc, ConflictPolicy::Skip).map_err(py_err)?;
+        store.upsert_blocks(&blocks, policy).map_err(py_err)
+    }
+
+    /// The stored document `doc_id`.
+    fn document<'py>(&self, py: Python<'py>, doc_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let id = parse_id(doc_id, "doc_id")?;
+        let doc = self.block_store().get_document(&id).map_err(py_err)?;
+        to_py(py, &doc)
+    }
+
+    /// The blocks of `doc_id` in document order: flat, or with `tree=True`
+    /// the root blocks with their `children` nested.
+    #[pyo3(signature = (doc_id, tree=false))]
+    fn blocks<'py>(
+        &self,
+        py: Python<'py>,
+        doc_id: &str,
+        tree: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let id = parse_id(doc_id, "doc_id")?;
+        let store = self.block_store();
+        let blocks = if tree {
+            store.get_block_tree(&id)
+        } else {
+            store.get_blocks_by_document(&id)
+        }
+        .map_err(py_err)?;
+        to_py(py, &blocks)
+    }
+
+    /// Stored documents, oldest first. `filter` takes `doc_type`,
+    /// `name_contains`, `ingested_from`, `ingested_to`, `metadata_key`,
+    /// `metadata_value`, `offset` and `limit`.
+    #[pyo3(signature = (filter=None))]
+    fn list_documents<'py>(
+        &self,
+        py: Python<'py>,
+        filter: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let filter: DocumentFilter = from_py_or_default(filter, "document filter")?;
+        let documents = self.block_store().list_documents(&filter).map_err(py_err)?;
+        to_py(py, &documents)
+    }
+
+    /// Full-text search over block text, best match first. `filter` takes
+    /// `document_ids`, `doc_type`, `block_type`, `offset` and `limit`.
+    #[pyo3(signature = (query, filter=None))]
+    fn search<'py>(
+        &self,
+        py: Python<'py>,
+        query: &str,
+        filter: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let filter: BlockSearchFilter = from_py_or_default(filter, "search filter")?;
+        let hits = self.block_store().search(query, &filter).map_err(py_err)?;
+        to_py(py, &hits)
+    }
+}
@@ -0,0 +1,130 @@
+//! `rtflow.WorkflowEngine`.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use rt_core::db::{DbConn, DbPool};
+use rt_core::tenant::TenantContext;
+use rt_workflow::commands::{WorkflowEngine, WorkflowFilter};
+use rt_workflow::event::EventType;
+
+use crate::store::PyStore;
+use crate::{from_py, from_py_or_default, parse_id, py_err, to_py, RtflowError};
+
+/// Review workflows over the documents of a store.
+///
+/// ```python
+/// workflows = rtflow.WorkflowEngine(store)
+/// wf = workflows.create(doc_id, "alice")
+/// wf = workflows.submit(wf["id"], "compare_started", "alice")
+/// ```
+///
+/// Workflows come back as dicts with their current `state`; events as
+/// dicts shaped as in `contracts/workflow-events.json`.
+#[pyclass(name = "WorkflowEngine", module = "rtflow", frozen)]
+pub struct PyWorkflowEngine {
+    pool: DbPool,
+}
+
+impl PyWorkflowEngine {
+    fn conn(&self) -> PyResult<DbConn> {
+        self.pool
+            .get()
+            .map_err(|e| RtflowError::new_err(format!("failed to acquire connection: {e}")))
+    }
+}
+
+#[pymethods]
+impl PyWorkflowEngine {
+    #[new]
+    fn new(store: PyRef<'_, PyStore>) -> Self {
+        Self {
+            pool: store.pool.clone(),
+        }
+    }
+
+    /// Start a workflow on the stored document `document_id`.
+    fn create<'py>(
+        &self,
+        py: Python<'py>,
+        document_id: &str,
+        initiator_id: &str,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let document_id = parse_id(document_id, "document_id")?;
+        let conn = self.conn()?;
+        let workflow = WorkflowEngine::create_workflow(
+            &conn,
+            &TenantContext::default(),
+            document_id,
+            initiator_id,
+        )
+        .map_err(py_err)?;
+        to_py(py, &workflow)
+    }
+
+    /// Apply the event `event_type` (e.g. `"compare_started"`) by `actor`
+    /// and return the workflow in its new state. Raises `ValueError` for a
+    /// transition the workflow's state does not allow.
+    #[pyo3(signature = (workflow_id, event_type, actor, payload=None))]
+    fn submit<'py>(
+        &self,
+        py: Python<'py>,
+        workflow_id: &str,
+        event_type: &str,
+        actor: &str,
+        payload: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let workflow_id = parse_id(workflow_id, "workflow_id")?;
+        let event_type: EventType =
+            serde_json::from_value(serde_json::Value::String(event_type.into()))
+                .map_err(|e| PyValueError::new_err(format!("invalid event type: {e}")))?;
+        let payload: serde_json::Value = match payload {
+            Some(payload) => from_py(payload, "event payload")?,
+            None => serde_json::json!({}),
+        };
+        let conn = self.conn()?;
+        let workflow = WorkflowEngine::submit_event(
+            &conn,
+            &TenantContext::default(),
+            workflow_id,
+            event_type,
+            actor,
+            payload,
+        )
+        .map_err(py_err)?;
+        to_py(py, &workflow)
+    }
+
+    /// The workflow `workflow_id`, replayed from its event log.
+    fn get<'py>(&self, py: Python<'py>, workflow_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let workflow_id = parse_id(workflow_id, "workflow_id")?;
+        let conn = self.conn()?;
+        let workflow = WorkflowEngine::get_workflow(&conn, &TenantContext::default(), workflow_id)
+            .map_err(py_err)?;
+        to_py(py, &workflow)
+    }
+
+    /// The events of `workflow_id`, oldest first.
+    fn events<'py>(&self, py: Python<'py>, workflow_id: &str) -> PyResult<Bound<'py, PyAny>> {
+        let workflow_id = parse_id(workflow_id, "workflow_id")?;
+        let conn = self.conn()?;
+        let events = WorkflowEngine::get_events(&conn, &TenantContext::default(), workflow_id)
+            .map_err(py_err)?;
+        to_py(py, &events)
+    }
+
+    /// Workflows, oldest first. `filter` takes `document_id`, `state`,
+    /// `initiator_id`, `created_from`, `created_to`, `offset` and `limit`.
+    #[pyo3(signature = (filter=None))]
+    fn list<'py>(
+        &self,
+        py: Python<'py>,
+        filter: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let filter: WorkflowFilter = from_py_or_default(filter, "workflow filter")?;
+        let conn = self.conn()?;
+        let workflows = WorkflowEngine::list_workflows(&conn, &TenantContext::default(), &filter)
+            .map_err(py_err)?;
+        to_py(py, &workflows)
+    }
+}
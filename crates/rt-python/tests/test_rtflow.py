@@ -0,0 +1,102 @@
+"""Tests of the `rtflow` module; run with `maturin develop && pytest`."""
+
+import json
+
+import pytest
+
+import rtflow
+
+CONTRACT = """\
+MASTER SERVICES AGREEMENT
+
+1. Term
+This Agreement begins on the Effective Date and continues for two years.
+
+2. Payment
+The Customer shall pay each invoice within thirty days.
+"""
+
+AMENDED = CONTRACT.replace("thirty days", "sixty days")
+
+
+@pytest.fixture
+def store(tmp_path):
+    return rtflow.Store(tmp_path / "contracts.db")
+
+
+def test_ingest_and_read_back(store):
+    ingested = store.ingest_text(CONTRACT, "msa.txt")
+    doc_id = ingested["doc_id"]
+    assert ingested["count"] == len(store.blocks(doc_id))
+    assert store.document(doc_id)["name"] == "msa.txt"
+    assert [d["id"] for d in store.list_documents({"name_contains": "MSA"})] == [doc_id]
+    assert store.search("invoice")[0]["document_id"] == doc_id
+
+    with pytest.raises(LookupError):
+        store.document("00000000-0000-0000-0000-000000000000")
+    with pytest.raises(ValueError):
+        store.ingest_text(CONTRACT, "bad.txt", options={"anchor_version": "3"})
+
+
+def test_ingest_blocks_validates_and_records_the_contract(store, tmp_path):
+    source = rtflow.Store(tmp_path / "source.db")
+    ingested = source.ingest_text(CONTRACT, "msa.txt")
+    doc_id = ingested["doc_id"]
+    blocks = source.blocks(doc_id)
+
+    # Blocks claiming another document are refused before anything is written.
+    stranger = "11111111-1111-1111-1111-111111111111"
+    with pytest.raises(ValueError):
+        store.ingest_blocks(stranger, blocks)
+    with pytest.raises(LookupError):
+        store.document(stranger)
+
+    assert store.ingest_blocks(doc_id, blocks) == len(blocks)
+    contract = ingested["document"]["hash_contract_version"]
+    assert store.document(doc_id)["hash_contract_version"] == contract
+
+
+def test_compare_documents_and_blocks(store):
+    left = store.ingest_text(CONTRACT, "v1.txt")["doc_id"]
+    right = store.ingest_text(AMENDED, "v2.txt")["doc_id"]
+    engine = rtflow.CompareEngine({"output_mode": "changes_only"})
+
+    result = engine.compare_documents(store, left, right)
+    assert result["stats"]["modified"] == 1
+    assert result["left_doc_id"] == left
+
+    # Blocks round-trip as plain data, or as the same data in JSON.
+    same = engine.compare(store.blocks(left), json.dumps(store.blocks(right)))
+    assert same["stats"] == result["stats"]
+
+    with pytest.raises(ValueError):
+        rtflow.CompareEngine({"no_such_option": True})
+
+
+def test_merge(store):
+    base = store.ingest_text(CONTRACT, "base.txt")["doc_id"]
+    incoming = store.ingest_text(AMENDED, "incoming.txt")["doc_id"]
+    engine = rtflow.MergeEngine()
+
+    stored = engine.merge_documents(store, base, incoming)
+    assert stored["base_doc_id"] == base
+    transient = engine.merge(store.blocks(base), store.blocks(incoming))
+    assert transient["auto_resolved"] == stored["auto_resolved"]
+
+
+def test_workflow(store):
+    doc_id = store.ingest_text(CONTRACT, "msa.txt")["doc_id"]
+    workflows = rtflow.WorkflowEngine(store)
+
+    created = workflows.create(doc_id, "alice")
+    running = workflows.submit(created["id"], "compare_started", "alice")
+    assert running["state"] != created["state"]
+    assert workflows.get(created["id"])["state"] == running["state"]
+    assert [e["event_type"] for e in workflows.events(created["id"])] == [
+        "workflow_created",
+        "compare_started",
+    ]
+    assert len(workflows.list({"document_id": doc_id})) == 1
+
+    with pytest.raises(ValueError):
+        workflows.submit(created["id"], "not_an_event", "alice")
@@ -0,0 +1,173 @@
+//! API-key authentication and tenant resolution.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tonic::metadata::MetadataMap;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+use rt_core::tenant::TenantContext;
+use rt_core::RtError;
+
+use crate::{status, TENANT_HEADER};
+
+/// Interceptor letting a call through only with one of the configured API
+/// keys, as `authorization: Bearer <key>` or `x-api-key: <key>` metadata.
+/// The call then acts for the tenant that key is bound to.
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    keys: Arc<HashMap<String, TenantContext>>,
+}
+
+impl ApiKeyAuth {
+    /// Authenticate against `keys`, each bound to the one tenant it acts
+    /// for. Fails without a non-empty key, so a server can never come up
+    /// open by accident.
+    pub fn new(keys: impl IntoIterator<Item = (String, TenantContext)>) -> Result<Self, RtError> {
+        let keys: HashMap<_, _> = keys
+            .into_iter()
+            .filter(|(key, _)| !key.is_empty())
+            .collect();
+        if keys.is_empty() {
+            return Err(RtError::InvalidInput(
+                "at least one non-empty API key is required".into(),
+            ));
+        }
+        Ok(Self {
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// The tenant bound to `key`. Every key is visited so the time taken
+    /// does not depend on which one matched.
+    fn tenant_for(&self, key: &str) -> Option<TenantContext> {
+        self.keys
+            .iter()
+            .fold(None, |found, (candidate, tenant)| {
+                let matched = constant_time_eq(candidate.as_bytes(), key.as_bytes());
+                found.or(matched.then_some(tenant))
+            })
+            .cloned()
+    }
+}
+
+impl Interceptor for ApiKeyAuth {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let unauthenticated = || Status::unauthenticated("missing or unknown API key");
+        let key = presented_key(request.metadata()).ok_or_else(unauthenticated)?;
+        let tenant = self.tenant_for(key).ok_or_else(unauthenticated)?;
+        check_tenant_header(request.metadata(), &tenant)?;
+        request.extensions_mut().insert(tenant);
+        Ok(request)
+    }
+}
+
+/// The key a call presents: `authorization: Bearer <key>`, else
+/// `x-api-key: <key>`.
+fn presented_key(metadata: &MetadataMap) -> Option<&str> {
+    let bearer = metadata
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer
+        .or_else(|| {
+            metadata
+                .get("x-api-key")
+                .and_then(|value| value.to_str().ok())
+        })
+        .map(str::trim)
+}
+
+/// Compare in time independent of where the first difference is, so a
+/// key cannot be guessed byte by byte from response timings.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Check a [`TENANT_HEADER`] the call sent against the tenant its key is
+/// bound to. The entry is optional; naming any other tenant is refused.
+fn check_tenant_header(metadata: &MetadataMap, bound: &TenantContext) -> Result<(), Status> {
+    let Some(value) = metadata.get(TENANT_HEADER) else {
+        return Ok(());
+    };
+    let named = value
+        .to_str()
+        .map_err(|_| Status::invalid_argument(format!("{TENANT_HEADER} is not ASCII")))?;
+    if TenantContext::new(named).map_err(status)? != *bound {
+        return Err(Status::permission_denied(format!(
+            "this API key may not act for tenant '{named}'"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth() -> ApiKeyAuth {
+        ApiKeyAuth::new([
+            ("default-key".to_string(), TenantContext::default()),
+            ("acme-key".to_string(), TenantContext::new("acme").unwrap()),
+        ])
+        .unwrap()
+    }
+
+    fn call(
+        auth: &mut ApiKeyAuth,
+        metadata: &[(&'static str, &str)],
+    ) -> Result<Request<()>, Status> {
+        let mut request = Request::new(());
+        for (name, value) in metadata {
+            request.metadata_mut().insert(*name, value.parse().unwrap());
+        }
+        auth.call(request)
+    }
+
+    #[test]
+    fn refuses_to_start_without_keys() {
+        assert!(ApiKeyAuth::new([(String::new(), TenantContext::default())]).is_err());
+    }
+
+    #[test]
+    fn calls_act_for_their_key_tenant() {
+        let mut auth = auth();
+        let request = call(&mut auth, &[("authorization", "Bearer acme-key")]).unwrap();
+        assert_eq!(
+            request.extensions().get::<TenantContext>(),
+            Some(&TenantContext::new("acme").unwrap())
+        );
+        let request = call(&mut auth, &[("x-api-key", "default-key")]).unwrap();
+        assert_eq!(
+            request.extensions().get::<TenantContext>(),
+            Some(&TenantContext::default())
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_unauthenticated() {
+        let mut auth = auth();
+        let code = |result: Result<Request<()>, Status>| result.unwrap_err().code();
+        assert_eq!(code(call(&mut auth, &[])), tonic::Code::Unauthenticated);
+        assert_eq!(
+            code(call(&mut auth, &[("x-api-key", "other-key")])),
+            tonic::Code::Unauthenticated
+        );
+    }
+
+    #[test]
+    fn tenant_metadata_must_match_the_key() {
+        let mut auth = auth();
+        let with_tenant = |tenant| [("x-api-key", "default-key"), (TENANT_HEADER, tenant)];
+        assert!(call(&mut auth, &with_tenant("default")).is_ok());
+        assert_eq!(
+            call(&mut auth, &with_tenant("acme")).unwrap_err().code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            call(&mut auth, &with_tenant("not valid!")).unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Engine types to their protobuf messages.
+//!
+//! Field for field where the message has the field; the rest goes into the
+//! message's `*_json` strings, serialized exactly as in the JSON contracts.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use rt_compare::result::{BlockDelta, CompareResult, CompareStats};
+use rt_merge::conflict::MergeConflict;
+use rt_merge::merge::MergeResult;
+use rt_workflow::state::Workflow;
+
+use crate::proto;
+
+/// The snake_case name an enum serializes as.
+fn name(value: &impl Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        Ok(other) => other.to_string(),
+        Err(e) => format!("<{e}>"),
+    }
+}
+
+fn json_text(value: &impl Serialize) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn id(value: &Option<Uuid>) -> Option<String> {
+    value.map(|id| id.to_string())
+}
+
+/// RFC 3339 in UTC, as chrono serializes timestamps into the JSON.
+fn timestamp(value: &DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+impl From<&rt_core::Block> for proto::Block {
+    fn from(block: &rt_core::Block) -> Self {
+        Self {
+            id: block.id.to_string(),
+            document_id: block.document_id.to_string(),
+            parent_id: id(&block.parent_id),
+            block_type: name(&block.block_type),
+            level: block.level,
+            structural_path: block.structural_path.clone(),
+            anchor_signature: block.anchor_signature.clone(),
+            clause_hash: block.clause_hash.clone(),
+            canonical_text: block.canonical_text.clone(),
+            display_text: block.display_text.clone(),
+            position_index: block.position_index,
+            formatting_meta_json: json_text(&block.formatting_meta),
+            tokens_json: json_text(&block.tokens),
+            runs_json: json_text(&block.runs),
+            attachments_json: json_text(&block.attachments),
+            children: block.children.iter().map(Self::from).collect(),
+        }
+    }
+}
+
+impl From<&CompareStats> for proto::CompareStats {
+    fn from(stats: &CompareStats) -> Self {
+        Self {
+            blocks_left: stats.blocks_left as u64,
+            blocks_right: stats.blocks_right as u64,
+            inserted: stats.inserted as u64,
+            deleted: stats.deleted as u64,
+            modified: stats.modified as u64,
+            moved: stats.moved as u64,
+            unchanged: stats.unchanged as u64,
+            split: stats.split as u64,
+            merged: stats.merged as u64,
+        }
+    }
+}
+
+impl From<&CompareResult> for proto::CompareSummary {
+    fn from(result: &CompareResult) -> Self {
+        Self {
+            run_id: result.run_id.to_string(),
+            left_doc_id: result.left_doc_id.to_string(),
+            right_doc_id: result.right_doc_id.to_string(),
+            elapsed_ms: result.elapsed_ms,
+            stats: Some((&result.stats).into()),
+            delta_count: result.deltas.len() as u64,
+            details_json: json!({
+                "formatting_drift": result.formatting_drift,
+                "attachment_changes": result.attachment_changes,
+                "table_changes": result.table_changes,
+                "broken_references": result.broken_references,
+                "warnings": result.warnings,
+                "manifest": result.manifest,
+            })
+            .to_string(),
+        }
+    }
+}
+
+impl From<&BlockDelta> for proto::BlockDelta {
+    fn from(delta: &BlockDelta) -> Self {
+        Self {
+            id: delta.id.to_string(),
+            kind: name(&delta.kind),
+            left_block_id: id(&delta.left_block_id),
+            right_block_id: id(&delta.right_block_id),
+            left_ordinal: delta.left_ordinal.map(|o| o as u64),
+            right_ordinal: delta.right_ordinal.map(|o| o as u64),
+            similarity_score: delta.similarity_score,
+            move_target_id: id(&delta.move_target_id),
+            group_block_ids: delta.group_block_ids.iter().map(Uuid::to_string).collect(),
+            summary: delta.summary.clone(),
+            section: delta.section.clone(),
+            authors: delta.authors.clone(),
+            details_json: json!({
+                "token_diffs": delta.token_diffs,
+                "sentences": delta.sentences,
+                "anchors": delta.anchors,
+            })
+            .to_string(),
+        }
+    }
+}
+
+impl From<&MergeResult> for proto::MergeSummary {
+    fn from(result: &MergeResult) -> Self {
+        Self {
+            merge_id: result.merge_id.to_string(),
+            base_doc_id: result.base_doc_id.to_string(),
+            incoming_doc_id: result.incoming_doc_id.to_string(),
+            ancestor_doc_id: id(&result.ancestor_doc_id),
+            output_doc_id: id(&result.output_doc_id),
+            auto_resolved: result.auto_resolved as u64,
+            pending_review: result.pending_review as u64,
+            conflict_count: result.conflicts.len() as u64,
+            details_json: json!({
+                "broken_references": result.broken_references,
+                "manifest": result.manifest,
+            })
+            .to_string(),
+        }
+    }
+}
+
+impl From<&MergeConflict> for proto::MergeConflict {
+    fn from(conflict: &MergeConflict) -> Self {
+        Self {
+            id: conflict.id.to_string(),
+            block_id: conflict.block_id.to_string(),
+            conflict_type: conflict.conflict_type.as_str().to_string(),
+            base_content: conflict.base_content.clone(),
+            incoming_content: conflict.incoming_content.clone(),
+            resolution: conflict.resolution.as_str().to_string(),
+        }
+    }
+}
+
+impl From<&Workflow> for proto::Workflow {
+    fn from(workflow: &Workflow) -> Self {
+        Self {
+            id: workflow.id.to_string(),
+            document_id: workflow.document_id.to_string(),
+            state: name(&workflow.state),
+            initiator_id: workflow.initiator_id.clone(),
+            created_at: timestamp(&workflow.created_at),
+            updated_at: timestamp(&workflow.updated_at),
+        }
+    }
+}
+
+/// The chunks a compare result streams as: its summary, then its deltas.
+pub(crate) fn compare_chunks(
+    result: &CompareResult,
+) -> impl Iterator<Item = proto::CompareChunk> + '_ {
+    use proto::compare_chunk::Chunk;
+    std::iter::once(Chunk::Summary(result.into()))
+        .chain(result.deltas.iter().map(|d| Chunk::Delta(d.into())))
+        .map(|chunk| proto::CompareChunk { chunk: Some(chunk) })
+}
+
+/// The chunks a merge result streams as: its summary, then its conflicts.
+pub(crate) fn merge_chunks(result: &MergeResult) -> impl Iterator<Item = proto::MergeChunk> + '_ {
+    use proto::merge_chunk::Chunk;
+    std::iter::once(Chunk::Summary(result.into()))
+        .chain(result.conflicts.iter().map(|c| Chunk::Conflict(c.into())))
+        .map(|chunk| proto::MergeChunk { chunk: Some(chunk) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_compare::worker::{CompareConfig, CompareEngine};
+    use rt_ingest::parse_text;
+    use rt_workflow::state::WorkflowState;
+
+    fn blocks(text: &str) -> Vec<rt_core::Block> {
+        parse_text(text, Uuid::new_v4())
+    }
+
+    #[test]
+    fn block_keeps_ids_text_and_nested_fields() {
+        let block = &blocks("1. Term\nThe term is two years.\n")[0];
+        let message = proto::Block::from(block);
+        assert_eq!(message.id, block.id.to_string());
+        assert_eq!(message.parent_id, block.parent_id.map(|id| id.to_string()));
+        assert_eq!(message.block_type, block.block_type.as_str());
+        assert_eq!(message.canonical_text, block.canonical_text);
+        let tokens: serde_json::Value = serde_json::from_str(&message.tokens_json).unwrap();
+        assert_eq!(tokens, serde_json::to_value(&block.tokens).unwrap());
+    }
+
+    #[test]
+    fn compare_streams_summary_then_deltas() {
+        let left = blocks("1. Payment\nPay within thirty days.\n");
+        let right = blocks("1. Payment\nPay within sixty days.\n");
+        let engine = CompareEngine::new(CompareConfig::default());
+        let result = engine.compare(Uuid::new_v4(), Uuid::new_v4(), &left, &right);
+
+        let chunks: Vec<_> = compare_chunks(&result).collect();
+        assert_eq!(chunks.len(), result.deltas.len() + 1);
+        let Some(proto::compare_chunk::Chunk::Summary(summary)) = &chunks[0].chunk else {
+            panic!("first chunk is not the summary");
+        };
+        assert_eq!(summary.delta_count, result.deltas.len() as u64);
+        assert_eq!(
+            summary.stats.as_ref().unwrap().modified,
+            result.stats.modified as u64
+        );
+        assert!(chunks[1..]
+            .iter()
+            .all(|c| matches!(c.chunk, Some(proto::compare_chunk::Chunk::Delta(_)))));
+    }
+
+    #[test]
+    fn workflow_names_state_and_formats_timestamps_as_json_does() {
+        let mut workflow = Workflow::new(Uuid::new_v4(), "alice");
+        workflow.state = WorkflowState::Draft;
+        let message = proto::Workflow::from(&workflow);
+        let json = serde_json::to_value(&workflow).unwrap();
+        assert_eq!(message.state, json["state"]);
+        assert_eq!(message.created_at, json["created_at"]);
+    }
+}
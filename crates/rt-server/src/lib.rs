@@ -0,0 +1,113 @@
+//! gRPC service over the RT_Flow engines.
+//!
+//! [`RtFlowService`] implements the `rtflow.v1.RtFlow` service of
+//! `proto/rtflow/v1/rtflow.proto` on one database, so RT_Flow can run as a
+//! sidecar process instead of being linked in through `rt-ffi`. The
+//! `rt-server` binary serves it:
+//!
+//! ```text
+//! rt-server contracts.db --listen 127.0.0.1:50051
+//! ```
+//!
+//! Every call needs one of the configured API keys (see [`ApiKeyAuth`]) and
+//! acts for the tenant that key is bound to. Compare and merge results
+//! stream back in chunks (a summary, then one message per delta or
+//! conflict), so a large result never has to fit in a single gRPC message.
+
+use tonic::service::interceptor::InterceptedService;
+use tonic::{Request, Status};
+
+use rt_core::db::DbPool;
+use rt_core::tenant::TenantContext;
+use rt_core::RtError;
+
+mod auth;
+mod convert;
+mod service;
+
+pub use auth::ApiKeyAuth;
+
+/// Messages and service stubs generated from `rtflow.proto`.
+pub mod proto {
+    tonic::include_proto!("rtflow.v1");
+}
+
+/// Request metadata a client may send to state the tenant it expects to act
+/// for. It must match the tenant its API key is bound to.
+pub const TENANT_HEADER: &str = "x-rtflow-tenant";
+
+/// The `rtflow.v1.RtFlow` service.
+#[derive(Clone)]
+pub struct RtFlowService {
+    pool: DbPool,
+}
+
+impl RtFlowService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// This service as a tonic server behind `auth`, ready for
+    /// `Server::add_service`.
+    pub fn into_server(
+        self,
+        auth: ApiKeyAuth,
+    ) -> InterceptedService<proto::rt_flow_server::RtFlowServer<Self>, ApiKeyAuth> {
+        proto::rt_flow_server::RtFlowServer::with_interceptor(self, auth)
+    }
+}
+
+/// The gRPC status an engine error is reported as.
+pub(crate) fn status(e: RtError) -> Status {
+    match e {
+        RtError::NotFound(_) => Status::not_found(e.to_string()),
+        RtError::InvalidInput(_) | RtError::HashMismatch { .. } => {
+            Status::invalid_argument(e.to_string())
+        }
+        RtError::Cancelled => Status::cancelled(e.to_string()),
+        _ => Status::internal(e.to_string()),
+    }
+}
+
+/// The tenant [`ApiKeyAuth`] resolved for `request`. A call that did not
+/// pass through it is refused rather than run for the default tenant.
+pub(crate) fn tenant<T>(request: &Request<T>) -> Result<TenantContext, Status> {
+    request
+        .extensions()
+        .get::<TenantContext>()
+        .cloned()
+        .ok_or_else(|| Status::unauthenticated("call was not authenticated"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_map_to_matching_status_codes() {
+        let code = |e| status(e).code();
+        assert_eq!(code(RtError::NotFound("doc".into())), tonic::Code::NotFound);
+        assert_eq!(
+            code(RtError::InvalidInput("limit".into())),
+            tonic::Code::InvalidArgument
+        );
+        assert_eq!(code(RtError::Cancelled), tonic::Code::Cancelled);
+        assert_eq!(
+            code(RtError::Internal("boom".into())),
+            tonic::Code::Internal
+        );
+    }
+
+    #[test]
+    fn tenant_comes_from_authentication() {
+        let mut request = Request::new(());
+        assert_eq!(
+            tenant(&request).unwrap_err().code(),
+            tonic::Code::Unauthenticated
+        );
+
+        let acme = TenantContext::new("acme").unwrap();
+        request.extensions_mut().insert(acme.clone());
+        assert_eq!(tenant(&request).unwrap(), acme);
+    }
+}
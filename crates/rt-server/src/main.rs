@@ -0,0 +1,98 @@
+//! `rt-server <db-path> [--listen <addr>]`: serve the `rtflow.v1.RtFlow`
+//! gRPC service on the database at `<db-path>` until interrupted. API keys
+//! come from `RTFLOW_API_KEYS`, comma-separated, each as `<key>=<tenant>`; a
+//! bare `<key>` acts for the default tenant.
+
+use std::net::SocketAddr;
+use std::process::ExitCode;
+
+use rt_core::db::create_pool;
+use rt_core::tenant::TenantContext;
+use rt_server::{ApiKeyAuth, RtFlowService};
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:50051";
+
+const API_KEYS_VAR: &str = "RTFLOW_API_KEYS";
+
+const USAGE: &str = "usage: rt-server <db-path> [--listen <addr>]";
+
+struct Args {
+    db_path: String,
+    listen: SocketAddr,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut db_path = None;
+    let mut listen = DEFAULT_LISTEN.to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => listen = args.next().ok_or("--listen needs an address")?,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if db_path.is_none() && !arg.starts_with('-') => db_path = Some(arg),
+            _ => return Err(format!("unexpected argument '{arg}'\n{USAGE}")),
+        }
+    }
+    Ok(Args {
+        db_path: db_path.ok_or(USAGE)?,
+        listen: listen
+            .parse()
+            .map_err(|e| format!("invalid listen address '{listen}': {e}"))?,
+    })
+}
+
+fn api_keys() -> Result<Vec<(String, TenantContext)>, String> {
+    std::env::var(API_KEYS_VAR)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (key, tenant) = match entry.split_once('=') {
+                Some((key, tenant)) => (key.trim(), TenantContext::new(tenant.trim())),
+                None => (entry, Ok(TenantContext::default())),
+            };
+            let tenant = tenant.map_err(|e| format!("invalid tenant for an API key: {e}"))?;
+            Ok((key.to_string(), tenant))
+        })
+        .collect()
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+    let auth = match api_keys().and_then(|keys| ApiKeyAuth::new(keys).map_err(|e| e.to_string())) {
+        Ok(auth) => auth,
+        Err(message) => {
+            eprintln!("rt-server: {message} (set {API_KEYS_VAR})");
+            return ExitCode::FAILURE;
+        }
+    };
+    let pool = match create_pool(&args.db_path) {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("rt-server: failed to open {}: {e}", args.db_path);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    eprintln!("rt-server: serving {} on {}", args.db_path, args.listen);
+    let served = tonic::transport::Server::builder()
+        .add_service(RtFlowService::new(pool).into_server(auth))
+        .serve_with_shutdown(args.listen, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await;
+    match served {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("rt-server: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! The `RtFlow` RPCs.
+//!
+//! Engine and database work is blocking, so every call runs it on tokio's
+//! blocking pool. Streaming calls send through a bounded channel, so a slow
+//! client holds the producer back instead of the whole response piling up
+//! in memory; a client that hangs up stops it.
+
+use std::pin::Pin;
+
+use chrono::Duration;
+use serde::de::DeserializeOwned;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use rt_compare::worker::{CompareConfig, CompareEngine};
+use rt_core::db::{BlockStore, DbConn, SqliteBlockStore};
+use rt_core::hashing::{align_anchors, check_contracts, default_hasher};
+use rt_ingest::{IngestOptions, IngestSummary};
+use rt_merge::merge::{MergeConfig, MergeEngine};
+use rt_merge::run::{run_merge, MergeRun, DEFAULT_MERGE_LEASE_SECS};
+use rt_merge::store::SqliteMergeStore;
+use rt_workflow::commands::{WorkflowEngine, WorkflowFilter};
+use rt_workflow::event::EventType;
+
+use crate::convert::{compare_chunks, merge_chunks};
+use crate::proto::rt_flow_server::RtFlow;
+use crate::proto::{
+    Block, CompareChunk, CompareRequest, CreateWorkflowRequest, GetBlocksRequest,
+    GetWorkflowRequest, IngestDocxRequest, IngestResponse, IngestTextRequest, ListWorkflowsRequest,
+    MergeChunk, MergeRequest, SubmitEventRequest, Workflow,
+};
+use crate::{status, tenant, RtFlowService};
+
+/// Messages a streaming call buffers ahead of its client.
+const STREAM_BUFFER: usize = 32;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+fn parse_id(value: &str, field: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(value).map_err(|e| Status::invalid_argument(format!("invalid {field}: {e}")))
+}
+
+/// Decode a `*_json` request field; empty is `T::default()`.
+fn from_json<T: DeserializeOwned + Default>(text: &str, what: &str) -> Result<T, Status> {
+    if text.trim().is_empty() {
+        return Ok(T::default());
+    }
+    serde_json::from_str(text).map_err(|e| Status::invalid_argument(format!("invalid {what}: {e}")))
+}
+
+/// Run `job` on the blocking pool.
+async fn blocking<T, F>(job: F) -> Result<T, Status>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Status> + Send + 'static,
+{
+    tokio::task::spawn_blocking(job)
+        .await
+        .map_err(|e| Status::internal(format!("request task failed: {e}")))?
+}
+
+/// Run `job` on the blocking pool, streaming what it sends. An error it
+/// returns ends the stream with that status.
+fn stream<T, F>(job: F) -> Response<ResponseStream<T>>
+where
+    T: Send + 'static,
+    F: FnOnce(&Sink<T>) -> Result<(), Status> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    tokio::task::spawn_blocking(move || {
+        let sink = Sink(tx);
+        if let Err(e) = job(&sink) {
+            let _ = sink.0.blocking_send(Err(e));
+        }
+    });
+    Response::new(Box::pin(ReceiverStream::new(rx)))
+}
+
+struct Sink<T>(mpsc::Sender<Result<T, Status>>);
+
+impl<T> Sink<T> {
+    /// Send every item, stopping early once the client has gone.
+    fn send_all(&self, items: impl IntoIterator<Item = T>) {
+        for item in items {
+            if self.0.blocking_send(Ok(item)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl RtFlowService {
+    fn conn(&self) -> Result<DbConn, Status> {
+        self.pool
+            .get()
+            .map_err(|e| Status::unavailable(format!("failed to acquire connection: {e}")))
+    }
+
+    fn block_store<T>(&self, request: &Request<T>) -> Result<SqliteBlockStore, Status> {
+        Ok(SqliteBlockStore::with_tenant(
+            self.pool.clone(),
+            tenant(request)?,
+        ))
+    }
+}
+
+fn ingest_response(summary: IngestSummary) -> Response<IngestResponse> {
+    Response::new(IngestResponse {
+        doc_id: summary.document.id.to_string(),
+        block_count: summary.block_count as u64,
+    })
+}
+
+#[tonic::async_trait]
+impl RtFlow for RtFlowService {
+    type GetBlocksStream = ResponseStream<Block>;
+    type CompareStream = ResponseStream<CompareChunk>;
+    type MergeStream = ResponseStream<MergeChunk>;
+    type ListWorkflowsStream = ResponseStream<Workflow>;
+
+    async fn ingest_docx(
+        &self,
+        request: Request<IngestDocxRequest>,
+    ) -> Result<Response<IngestResponse>, Status> {
+        let store = self.block_store(&request)?;
+        let service = self.clone();
+        let request = request.into_inner();
+        let doc_id = match &request.doc_id {
+            Some(id) => parse_id(id, "doc_id")?,
+            None => Uuid::new_v4(),
+        };
+        let options: IngestOptions = from_json(&request.options_json, "ingest options")?;
+        let name = if request.name.is_empty() {
+            doc_id.to_string()
+        } else {
+            request.name
+        };
+        blocking(move || {
+            let conn = service.conn()?;
+            let hasher = default_hasher(&conn).map_err(status)?;
+            rt_ingest::ingest_docx_bytes(&store, &request.docx, &name, doc_id, &hasher, &options)
+                .map_err(status)
+        })
+        .await
+        .map(ingest_response)
+    }
+
+    async fn ingest_text(
+        &self,
+        request: Request<IngestTextRequest>,
+    ) -> Result<Response<IngestResponse>, Status> {
+        let store = self.block_store(&request)?;
+        let service = self.clone();
+        let request = request.into_inner();
+        let doc_id = match &request.doc_id {
+            Some(id) => parse_id(id, "doc_id")?,
+            None => Uuid::new_v4(),
+        };
+        let options: IngestOptions = from_json(&request.options_json, "ingest options")?;
+        blocking(move || {
+            let conn = service.conn()?;
+            let hasher = default_hasher(&conn).map_err(status)?;
+            rt_ingest::ingest_text(
+                &store,
+                &request.text,
+                &request.name,
+                doc_id,
+                &hasher,
+                &options,
+            )
+            .map_err(status)
+        })
+        .await
+        .map(ingest_response)
+    }
+
+    async fn get_blocks(
+        &self,
+        request: Request<GetBlocksRequest>,
+    ) -> Result<Response<Self::GetBlocksStream>, Status> {
+        let store = self.block_store(&request)?;
+        let doc_id = parse_id(&request.get_ref().doc_id, "doc_id")?;
+        Ok(stream(move |sink| {
+            let blocks = store.get_blocks_by_document(&doc_id).map_err(status)?;
+            sink.send_all(blocks.iter().map(Block::from));
+            Ok(())
+        }))
+    }
+
+    async fn compare(
+        &self,
+        request: Request<CompareRequest>,
+    ) -> Result<Response<Self::CompareStream>, Status> {
+        let store = self.block_store(&request)?;
+        let service = self.clone();
+        let request = request.into_inner();
+        let left_id = parse_id(&request.left_doc_id, "left_doc_id")?;
+        let right_id = parse_id(&request.right_doc_id, "right_doc_id")?;
+        let config = CompareConfig::from_json(&request.options_json).map_err(status)?;
+        Ok(stream(move |sink| {
+            // As `rtflow_compare`: refuse mismatched hash contracts, and
+            // re-anchor the right side when the anchor strategies differ.
+            let left_doc = store.get_document(&left_id).map_err(status)?;
+            let right_doc = store.get_document(&right_id).map_err(status)?;
+            check_contracts(&[&left_doc, &right_doc]).map_err(status)?;
+            let left = store.get_block_tree(&left_id).map_err(status)?;
+            let mut right = store.get_block_tree(&right_id).map_err(status)?;
+            if left_doc.anchor_version != right_doc.anchor_version {
+                let conn = service.conn()?;
+                let hasher = default_hasher(&conn).map_err(status)?;
+                align_anchors(&left_doc, &right_doc, &mut right, &hasher).map_err(status)?;
+            }
+
+            let result = CompareEngine::new(config).compare(left_id, right_id, &left, &right);
+            sink.send_all(compare_chunks(&result));
+            Ok(())
+        }))
+    }
+
+    async fn merge(
+        &self,
+        request: Request<MergeRequest>,
+    ) -> Result<Response<Self::MergeStream>, Status> {
+        let store = self.block_store(&request)?;
+        let merges = SqliteMergeStore::with_tenant(self.pool.clone(), tenant(&request)?);
+        let request = request.into_inner();
+        let run = MergeRun::new(
+            parse_id(&request.base_doc_id, "base_doc_id")?,
+            parse_id(&request.incoming_doc_id, "incoming_doc_id")?,
+            None,
+        );
+        let config = MergeConfig::from_json(&request.options_json).map_err(status)?;
+        Ok(stream(move |sink| {
+            let engine = MergeEngine::new().with_config(config);
+            let lease = Duration::seconds(DEFAULT_MERGE_LEASE_SECS);
+            let result = run_merge(&store, &merges, &engine, &run, lease).map_err(status)?;
+            sink.send_all(merge_chunks(&result));
+            Ok(())
+        }))
+    }
+
+    async fn create_workflow(
+        &self,
+        request: Request<CreateWorkflowRequest>,
+    ) -> Result<Response<Workflow>, Status> {
+        let tenant = tenant(&request)?;
+        let service = self.clone();
+        let request = request.into_inner();
+        let document_id = parse_id(&request.document_id, "document_id")?;
+        blocking(move || {
+            let conn = service.conn()?;
+            WorkflowEngine::create_workflow(&conn, &tenant, document_id, &request.initiator_id)
+                .map_err(status)
+        })
+        .await
+        .map(|workflow| Response::new((&workflow).into()))
+    }
+
+    async fn submit_event(
+        &self,
+        request: Request<SubmitEventRequest>,
+    ) -> Result<Response<Workflow>, Status> {
+        let tenant = tenant(&request)?;
+        let service = self.clone();
+        let request = request.into_inner();
+        let workflow_id = parse_id(&request.workflow_id, "workflow_id")?;
+        let event_type: EventType =
+            serde_json::from_value(serde_json::Value::String(request.event_type))
+                .map_err(|e| Status::invalid_argument(format!("invalid event_type: {e}")))?;
+        let payload = if request.payload_json.trim().is_empty() {
+            serde_json::json!({})
+        } else {
+            from_json(&request.payload_json, "payload")?
+        };
+        blocking(move || {
+            let conn = service.conn()?;
+            WorkflowEngine::submit_event(
+                &conn,
+                &tenant,
+                workflow_id,
+                event_type,
+                &request.actor,
+                payload,
+            )
+            .map_err(status)
+        })
+        .await
+        .map(|workflow| Response::new((&workflow).into()))
+    }
+
+    async fn get_workflow(
+        &self,
+        request: Request<GetWorkflowRequest>,
+    ) -> Result<Response<Workflow>, Status> {
+        let tenant = tenant(&request)?;
+        let service = self.clone();
+        let workflow_id = parse_id(&request.get_ref().workflow_id, "workflow_id")?;
+        blocking(move || {
+            let conn = service.conn()?;
+            WorkflowEngine::get_workflow(&conn, &tenant, workflow_id).map_err(status)
+        })
+        .await
+        .map(|workflow| Response::new((&workflow).into()))
+    }
+
+    async fn list_workflows(
+        &self,
+        request: Request<ListWorkflowsRequest>,
+    ) -> Result<Response<Self::ListWorkflowsStream>, Status> {
+        let tenant = tenant(&request)?;
+        let service = self.clone();
+        let filter: WorkflowFilter = from_json(&request.get_ref().filter_json, "workflow filter")?;
+        Ok(stream(move |sink| {
+            let conn = service.conn()?;
+            let workflows =
+                WorkflowEngine::list_workflows(&conn, &tenant, &filter).map_err(status)?;
+            sink.send_all(workflows.iter().map(Workflow::from));
+            Ok(())
+        }))
+    }
+}
@@ -0,0 +1,287 @@
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+use rt_model::hash::sha256_hex_bytes;
+
+use crate::vfs::Vfs;
+
+// ---------------------------------------------------------------------------
+// ArtifactType
+// ---------------------------------------------------------------------------
+
+/// Kind of file produced by a workflow and recorded in the `artifacts` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactType {
+    /// HTML redline showing a single reviewer's changes against the base.
+    ReviewerRedlineHtml,
+    /// `.docx` redline showing a single reviewer's changes as tracked
+    /// `w:ins` / `w:del` revisions.
+    ReviewerRedlineDocx,
+}
+
+impl ArtifactType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtifactType::ReviewerRedlineHtml => "reviewer_redline_html",
+            ArtifactType::ReviewerRedlineDocx => "reviewer_redline_docx",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "reviewer_redline_html" => Ok(ArtifactType::ReviewerRedlineHtml),
+            "reviewer_redline_docx" => Ok(ArtifactType::ReviewerRedlineDocx),
+            other => Err(RtError::InvalidInput(format!("unknown artifact type: {other}"))),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Artifact
+// ---------------------------------------------------------------------------
+
+/// A file produced on behalf of a workflow (exports, snapshots, reports).
+///
+/// The file itself lives outside the database; the row records where it was
+/// written and a SHA-256 of its content so consumers can detect tampering or
+/// stale copies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    /// Stable unique identifier (UUIDv4).
+    pub id: Uuid,
+    /// The workflow this artifact was produced for.
+    pub workflow_id: Uuid,
+    /// Kind of file.
+    pub artifact_type: ArtifactType,
+    /// Filesystem or object-storage path the content was written to.
+    pub file_path: String,
+    /// SHA-256 of the artifact content.
+    pub content_hash: String,
+    /// Hash identifying the source document state the artifact was rendered
+    /// from, if known.
+    pub source_document_hash: Option<String>,
+    /// UTC timestamp when the artifact was recorded.
+    pub created_at: DateTime<Utc>,
+}
+
+impl Artifact {
+    /// Construct a new `Artifact` for `content`, computing `content_hash` and
+    /// generating a fresh `id`.
+    pub fn new(
+        workflow_id: Uuid,
+        artifact_type: ArtifactType,
+        file_path: impl Into<String>,
+        content: impl AsRef<[u8]>,
+        source_document_hash: Option<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            workflow_id,
+            artifact_type,
+            file_path: file_path.into(),
+            content_hash: sha256_hex_bytes(content.as_ref()),
+            source_document_hash,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+/// Insert `artifact` into the `artifacts` table.
+pub fn insert_artifact(conn: &rusqlite::Connection, artifact: &Artifact) -> Result<()> {
+    conn.execute(
+        "INSERT INTO artifacts
+            (id, workflow_id, artifact_type, file_path, content_hash,
+             source_document_hash, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            artifact.id.to_string(),
+            artifact.workflow_id.to_string(),
+            artifact.artifact_type.as_str(),
+            artifact.file_path,
+            artifact.content_hash,
+            artifact.source_document_hash,
+            artifact.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Write `content` to `artifact.file_path` through `vfs`, then record
+/// `artifact`. Fails with `InvalidInput`, writing nothing, when `content`
+/// does not match `artifact.content_hash`.
+pub fn store_artifact(
+    conn: &rusqlite::Connection,
+    vfs: &dyn Vfs,
+    artifact: &Artifact,
+    content: impl AsRef<[u8]>,
+) -> Result<()> {
+    let content = content.as_ref();
+    if sha256_hex_bytes(content) != artifact.content_hash {
+        return Err(RtError::InvalidInput(format!(
+            "content does not match the hash of artifact {}",
+            artifact.id
+        )));
+    }
+    vfs.write(&artifact.file_path, content)?;
+    insert_artifact(conn, artifact)
+}
+
+/// Return every artifact recorded for `workflow_id`, oldest first.
+pub fn get_artifacts_by_workflow(
+    conn: &rusqlite::Connection,
+    workflow_id: &Uuid,
+) -> Result<Vec<Artifact>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, workflow_id, artifact_type, file_path, content_hash,
+                source_document_hash, created_at
+           FROM artifacts
+          WHERE workflow_id = ?1
+          ORDER BY created_at ASC",
+    )?;
+
+    let rows = stmt.query_map(params![workflow_id.to_string()], |row| {
+        let id: String = row.get(0)?;
+        let wid: String = row.get(1)?;
+        let artifact_type: String = row.get(2)?;
+        let file_path: String = row.get(3)?;
+        let content_hash: String = row.get(4)?;
+        let source_document_hash: Option<String> = row.get(5)?;
+        let created_at: String = row.get(6)?;
+        Ok((
+            id,
+            wid,
+            artifact_type,
+            file_path,
+            content_hash,
+            source_document_hash,
+            created_at,
+        ))
+    })?;
+
+    let mut artifacts = Vec::new();
+    for row in rows {
+        let r = row?;
+        artifacts.push(Artifact {
+            id: Uuid::parse_str(&r.0).map_err(|e| RtError::InvalidInput(e.to_string()))?,
+            workflow_id: Uuid::parse_str(&r.1)
+                .map_err(|e| RtError::InvalidInput(e.to_string()))?,
+            artifact_type: ArtifactType::from_str(&r.2)?,
+            file_path: r.3,
+            content_hash: r.4,
+            source_document_hash: r.5,
+            created_at: chrono::DateTime::parse_from_rfc3339(&r.6)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| RtError::InvalidInput(e.to_string()))?,
+        });
+    }
+    Ok(artifacts)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rt_model::hash::sha256_hex;
+    use rusqlite::Connection;
+    use std::sync::Mutex;
+
+    fn setup() -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        let wf_id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO documents
+             (id, name, doc_type, schema_version, normalization_version,
+              hash_contract_version, ingested_at, metadata)
+             VALUES (?1, 'doc', 'original', '1.0.0', '1.0.0', '1.0.0',
+                     '2024-01-01T00:00:00Z', '{}')",
+            params![doc_id.to_string()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO workflows (id, document_id, state, initiator_id, created_at, updated_at)
+             VALUES (?1, ?2, 'DRAFT', 'alice', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z')",
+            params![wf_id.to_string(), doc_id.to_string()],
+        )
+        .unwrap();
+        (conn, wf_id)
+    }
+
+    #[test]
+    fn artifact_type_round_trips() {
+        for t in [ArtifactType::ReviewerRedlineHtml, ArtifactType::ReviewerRedlineDocx] {
+            assert_eq!(ArtifactType::from_str(t.as_str()).unwrap(), t);
+        }
+        assert!(ArtifactType::from_str("nope").is_err());
+    }
+
+    #[test]
+    fn new_artifact_hashes_content() {
+        let a = Artifact::new(
+            Uuid::new_v4(),
+            ArtifactType::ReviewerRedlineHtml,
+            "/tmp/a.html",
+            "<p>x</p>",
+            None,
+        );
+        assert_eq!(a.content_hash, sha256_hex("<p>x</p>"));
+    }
+
+    #[test]
+    fn insert_and_list_artifacts() {
+        let (conn, wf_id) = setup();
+        let a = Artifact::new(
+            wf_id,
+            ArtifactType::ReviewerRedlineDocx,
+            "/tmp/bob.xml",
+            "<w:document/>",
+            Some("abc".into()),
+        );
+        insert_artifact(&conn, &a).unwrap();
+
+        let listed = get_artifacts_by_workflow(&conn, &wf_id).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, a.id);
+        assert_eq!(listed[0].artifact_type, ArtifactType::ReviewerRedlineDocx);
+        assert_eq!(listed[0].source_document_hash.as_deref(), Some("abc"));
+    }
+
+    #[derive(Default)]
+    struct RecordingVfs(Mutex<Vec<(String, Vec<u8>)>>);
+
+    impl Vfs for RecordingVfs {
+        fn write(&self, path: &str, content: &[u8]) -> Result<()> {
+            self.0.lock().unwrap().push((path.to_string(), content.to_vec()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn store_artifact_writes_through_the_vfs() {
+        let (conn, wf_id) = setup();
+        let vfs = RecordingVfs::default();
+        let a = Artifact::new(wf_id, ArtifactType::ReviewerRedlineHtml, "out.html", "<p>x</p>", None);
+
+        let err = store_artifact(&conn, &vfs, &a, "<p>y</p>").unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(_)));
+        assert!(vfs.0.lock().unwrap().is_empty());
+
+        store_artifact(&conn, &vfs, &a, "<p>x</p>").unwrap();
+        assert_eq!(*vfs.0.lock().unwrap(), [("out.html".to_string(), b"<p>x</p>".to_vec())]);
+        assert_eq!(get_artifacts_by_workflow(&conn, &wf_id).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,302 @@
+//! Compare deltas stored by section, for outline navigation.
+//!
+//! The deltas of a recorded compare run are kept in `compare_deltas` in
+//! document order, each with the section (top-level block path) that owns
+//! it, so a client can fetch "all deltas under Section 7" a page at a time
+//! and show per-section counts in its navigation tree without loading the
+//! whole result. Deltas are stored as opaque JSON: the store does not depend
+//! on the compare engine. They belong to a tenant through their run in
+//! `run_manifests`, and are deleted with it.
+
+use std::collections::BTreeMap;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+
+use crate::db::{DEFAULT_LIST_LIMIT, MAX_LIST_LIMIT};
+use crate::run_history::find_run;
+use crate::tenant::TenantContext;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One delta to store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionDelta {
+    /// Section the delta belongs to.
+    pub section: String,
+    /// Delta kind (e.g. `"modified"`), counted by [`compare_sections`].
+    pub kind: String,
+    /// The delta itself.
+    pub delta: serde_json::Value,
+}
+
+/// Delta counts of one section of a compare run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionSummary {
+    pub section: String,
+    /// Number of deltas in the section.
+    pub total: u64,
+    /// Number of deltas of each kind.
+    pub by_kind: BTreeMap<String, u64>,
+}
+
+/// A page of one section's deltas, for [`section_deltas`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SectionDeltaQuery {
+    pub run_id: Uuid,
+    pub section: String,
+    /// Only deltas of this kind.
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Number of matching deltas to skip.
+    #[serde(default)]
+    pub offset: usize,
+    /// Page size; defaults to [`DEFAULT_LIST_LIMIT`], at most
+    /// [`MAX_LIST_LIMIT`].
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+// ---------------------------------------------------------------------------
+// Storage and queries
+// ---------------------------------------------------------------------------
+
+/// Append `deltas` to the stored deltas of compare run `run_id`, after any
+/// already stored; `NotFound` when `tenant` has no such run.
+pub fn append_compare_deltas(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    run_id: &Uuid,
+    deltas: &[SectionDelta],
+) -> Result<()> {
+    require_run(conn, tenant, run_id)?;
+    let next: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(seq) + 1, 0) FROM compare_deltas WHERE run_id = ?1",
+        params![run_id.to_string()],
+        |row| row.get(0),
+    )?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO compare_deltas (run_id, seq, section, kind, delta)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (offset, delta) in deltas.iter().enumerate() {
+        stmt.execute(params![
+            run_id.to_string(),
+            next + offset as i64,
+            delta.section,
+            delta.kind,
+            serde_json::to_string(&delta.delta)?,
+        ])?;
+    }
+    Ok(())
+}
+
+/// Per-section delta counts of compare run `run_id`, sections in the order
+/// their first delta appears; `NotFound` when `tenant` has no such run.
+pub fn compare_sections(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    run_id: &Uuid,
+) -> Result<Vec<SectionSummary>> {
+    require_run(conn, tenant, run_id)?;
+    let mut stmt = conn.prepare(
+        "SELECT section, kind, COUNT(*), MIN(seq) FROM compare_deltas
+          WHERE run_id = ?1
+          GROUP BY section, kind",
+    )?;
+    let rows = stmt
+        .query_map(params![run_id.to_string()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut sections: Vec<(i64, SectionSummary)> = Vec::new();
+    for (section, kind, count, first) in rows {
+        let index = match sections.iter().position(|(_, s)| s.section == section) {
+            Some(i) => i,
+            None => {
+                sections.push((
+                    first,
+                    SectionSummary {
+                        section,
+                        total: 0,
+                        by_kind: BTreeMap::new(),
+                    },
+                ));
+                sections.len() - 1
+            }
+        };
+        let (section_first, summary) = &mut sections[index];
+        *section_first = (*section_first).min(first);
+        summary.total += count as u64;
+        summary.by_kind.insert(kind, count as u64);
+    }
+    sections.sort_by_key(|(first, _)| *first);
+    Ok(sections.into_iter().map(|(_, s)| s).collect())
+}
+
+/// One page of the deltas of `query.section` in document order; `NotFound`
+/// when `tenant` has no such run.
+pub fn section_deltas(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    query: &SectionDeltaQuery,
+) -> Result<Vec<serde_json::Value>> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+    if limit == 0 || limit > MAX_LIST_LIMIT {
+        return Err(RtError::InvalidInput(format!(
+            "limit must be between 1 and {MAX_LIST_LIMIT}, got {limit}"
+        )));
+    }
+    require_run(conn, tenant, &query.run_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT delta FROM compare_deltas
+          WHERE run_id = ?1 AND section = ?2 AND (?3 IS NULL OR kind = ?3)
+          ORDER BY seq
+          LIMIT ?4 OFFSET ?5",
+    )?;
+    let rows = stmt
+        .query_map(
+            params![
+                query.run_id.to_string(),
+                query.section,
+                query.kind,
+                limit as i64,
+                query.offset as i64,
+            ],
+            |row| row.get::<_, String>(0),
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    rows.iter()
+        .map(|json| serde_json::from_str(json).map_err(RtError::from))
+        .collect()
+}
+
+/// Every stored delta of compare run `run_id` in document order; `NotFound`
+/// when `tenant` has no such run.
+pub fn run_deltas(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    run_id: &Uuid,
+) -> Result<Vec<serde_json::Value>> {
+    require_run(conn, tenant, run_id)?;
+    let mut stmt = conn.prepare("SELECT delta FROM compare_deltas WHERE run_id = ?1 ORDER BY seq")?;
+    let rows = stmt
+        .query_map(params![run_id.to_string()], |row| row.get::<_, String>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    rows.iter()
+        .map(|json| serde_json::from_str(json).map_err(RtError::from))
+        .collect()
+}
+
+fn require_run(conn: &rusqlite::Connection, tenant: &TenantContext, run_id: &Uuid) -> Result<()> {
+    match find_run(conn, tenant, run_id)? {
+        Some(_) => Ok(()),
+        None => Err(RtError::NotFound(format!("run {run_id}"))),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_history::{record_run, RunKind};
+    use crate::schema::run_migrations;
+    use rt_model::manifest::RunManifest;
+    use rusqlite::Connection;
+    use serde_json::json;
+
+    fn setup() -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let run_id = Uuid::new_v4();
+        let manifest = RunManifest::new([("rt-compare", "0.1.0")], "cfg".into(), vec![]);
+        record_run(&conn, &TenantContext::default(), &run_id, RunKind::Compare, &manifest).unwrap();
+        (conn, run_id)
+    }
+
+    fn delta(section: &str, kind: &str, n: u32) -> SectionDelta {
+        SectionDelta {
+            section: section.into(),
+            kind: kind.into(),
+            delta: json!({ "n": n }),
+        }
+    }
+
+    #[test]
+    fn sections_are_counted_in_document_order() {
+        let (conn, run) = setup();
+        let tenant = TenantContext::default();
+        append_compare_deltas(
+            &conn,
+            &tenant,
+            &run,
+            &[delta("7.", "unchanged", 0), delta("2.", "modified", 1), delta("7.", "inserted", 2)],
+        )
+        .unwrap();
+        append_compare_deltas(&conn, &tenant, &run, &[delta("7.", "inserted", 3)]).unwrap();
+
+        let sections = compare_sections(&conn, &tenant, &run).unwrap();
+        let names: Vec<_> = sections.iter().map(|s| s.section.as_str()).collect();
+        assert_eq!(names, ["7.", "2."]);
+        assert_eq!(sections[0].total, 3);
+        assert_eq!(sections[0].by_kind["inserted"], 2);
+        assert_eq!(sections[1].by_kind["modified"], 1);
+    }
+
+    #[test]
+    fn section_deltas_are_paged_and_filtered() {
+        let (conn, run) = setup();
+        let tenant = TenantContext::default();
+        let deltas: Vec<_> = (0..5)
+            .map(|n| delta(if n == 2 { "1." } else { "7." }, if n == 4 { "deleted" } else { "modified" }, n))
+            .collect();
+        append_compare_deltas(&conn, &tenant, &run, &deltas).unwrap();
+
+        let query = |offset, limit, kind: Option<&str>| SectionDeltaQuery {
+            run_id: run,
+            section: "7.".into(),
+            kind: kind.map(String::from),
+            offset,
+            limit,
+        };
+        let page = section_deltas(&conn, &tenant, &query(1, Some(2), None)).unwrap();
+        assert_eq!(page, [json!({"n": 1}), json!({"n": 3})]);
+        let deleted = section_deltas(&conn, &tenant, &query(0, None, Some("deleted"))).unwrap();
+        assert_eq!(deleted, [json!({"n": 4})]);
+        let all = run_deltas(&conn, &tenant, &run).unwrap();
+        assert_eq!(all.len(), 5);
+        assert_eq!(all[2], json!({"n": 2}));
+        assert!(matches!(
+            section_deltas(&conn, &tenant, &query(0, Some(0), None)),
+            Err(RtError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn runs_of_other_tenants_are_not_found() {
+        let (conn, run) = setup();
+        let other = TenantContext::new("other").unwrap();
+        assert!(matches!(
+            append_compare_deltas(&conn, &other, &run, &[delta("1.", "modified", 0)]),
+            Err(RtError::NotFound(_))
+        ));
+        assert!(matches!(compare_sections(&conn, &other, &run), Err(RtError::NotFound(_))));
+        assert!(compare_sections(&conn, &TenantContext::default(), &run).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+//! Content-addressed block storage.
+//!
+//! A portfolio stores the same boilerplate clause thousands of times, and
+//! every copy writes its own `tokens` and `runs` rows, one per token and
+//! per run. With content addressing enabled ([`set_content_addressing`]), a
+//! block's tokens and runs are instead kept once in `block_contents`, keyed
+//! by clause hash, and the block row references them through
+//! `content_hash`. Reads through [`BlockStore`](crate::db::BlockStore) are
+//! unchanged: a referenced block comes back with the shared tokens and runs.
+//!
+//! The first block written under a clause hash defines its content. A later
+//! block with the same hash shares it only when its tokens and runs are the
+//! same (the same text formatted differently has other runs); otherwise it
+//! keeps its own rows, as does every block written while addressing is off.
+//! `canonical_text` stays on the block row, where the full-text index and
+//! the per-document SQL reads expect it.
+//!
+//! Blocks stored before addressing was enabled are moved over by
+//! [`migrate_to_content_store`]. Turning addressing off again only stops
+//! new sharing; shared content stays readable. Content no block references
+//! any more is deleted with its last block.
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use rt_model::block::{Run, Token};
+use rt_model::error::Result;
+
+use crate::db::{inline_runs, inline_tokens, insert_inline_sub_rows};
+use crate::tenant::TenantContext;
+
+/// Whether new blocks share their content; off until enabled.
+pub fn content_addressing_enabled(conn: &rusqlite::Connection) -> Result<bool> {
+    let enabled: Option<bool> = conn
+        .prepare_cached("SELECT content_addressed FROM storage_config WHERE id = 1")?
+        .query_row([], |row| row.get(0))
+        .optional()?;
+    Ok(enabled.unwrap_or(false))
+}
+
+/// Turn content addressing on or off for blocks written from now on.
+pub fn set_content_addressing(conn: &rusqlite::Connection, enabled: bool) -> Result<()> {
+    conn.execute(
+        "INSERT INTO storage_config (id, content_addressed, configured_at)
+         VALUES (1, ?1, ?2)
+         ON CONFLICT(id) DO UPDATE SET
+            content_addressed = excluded.content_addressed,
+            configured_at     = excluded.configured_at",
+        params![enabled, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Outcome of [`migrate_to_content_store`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ContentMigration {
+    /// Blocks that kept their own tokens and runs before the migration.
+    pub blocks: usize,
+    /// Of those, blocks now referencing shared content.
+    pub shared: usize,
+    /// Distinct contents stored once the migration is done.
+    pub contents: usize,
+}
+
+/// Enable content addressing and move the tokens and runs of `tenant`'s
+/// blocks into shared content where they match (see the module docs).
+/// Runs in one transaction; migrating again moves only blocks written in
+/// between.
+pub fn migrate_to_content_store(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+) -> Result<ContentMigration> {
+    let tx = conn.unchecked_transaction()?;
+    set_content_addressing(&tx, true)?;
+    let rows: Vec<(String, String)> = tx
+        .prepare(
+            "SELECT id, clause_hash
+               FROM blocks
+              WHERE content_hash IS NULL
+                AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?1)
+              ORDER BY rowid",
+        )?
+        .query_map(params![tenant.id()], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut migration = ContentMigration {
+        blocks: rows.len(),
+        ..ContentMigration::default()
+    };
+    for (id, clause_hash) in rows {
+        let tokens = inline_tokens(&tx, &id, false)?;
+        let runs = inline_runs(&tx, &id)?;
+        let Some(content) = share_content(&tx, &clause_hash, &tokens, &runs)? else {
+            continue;
+        };
+        store_content(&tx, &content)?;
+        tx.execute(
+            "UPDATE blocks SET content_hash = ?2 WHERE id = ?1",
+            params![id, content.clause_hash],
+        )?;
+        for table in ["tokens", "runs"] {
+            tx.execute(&format!("DELETE FROM {table} WHERE block_id = ?1"), params![id])?;
+        }
+        migration.shared += 1;
+    }
+    migration.contents =
+        tx.query_row("SELECT COUNT(*) FROM block_contents", [], |row| row.get::<_, i64>(0))?
+            as usize;
+    tx.commit()?;
+    Ok(migration)
+}
+
+// ---------------------------------------------------------------------------
+// Crate-internal helpers
+// ---------------------------------------------------------------------------
+
+/// Content a block is about to reference.
+pub(crate) struct SharedContent {
+    pub clause_hash: String,
+    tokens: String,
+    runs: String,
+    /// Not stored yet: the block is the first with this content.
+    new: bool,
+}
+
+/// The content a block with `clause_hash`, `tokens` and `runs` would share,
+/// or `None` when it keeps its own rows: addressing is off, there is
+/// nothing to share, or another content is stored under the hash.
+pub(crate) fn share_content(
+    conn: &rusqlite::Connection,
+    clause_hash: &str,
+    tokens: &[Token],
+    runs: &[Run],
+) -> Result<Option<SharedContent>> {
+    if (tokens.is_empty() && runs.is_empty()) || !content_addressing_enabled(conn)? {
+        return Ok(None);
+    }
+    let tokens = serde_json::to_string(tokens)?;
+    let runs = serde_json::to_string(runs)?;
+    let stored: Option<(String, String)> = conn
+        .prepare_cached("SELECT tokens, runs FROM block_contents WHERE clause_hash = ?1")?
+        .query_row(params![clause_hash], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()?;
+    let new = match stored {
+        None => true,
+        Some(stored) if stored == (tokens.clone(), runs.clone()) => false,
+        Some(_) => return Ok(None),
+    };
+    Ok(Some(SharedContent {
+        clause_hash: clause_hash.to_string(),
+        tokens,
+        runs,
+        new,
+    }))
+}
+
+/// Store `content` if it is not stored yet.
+pub(crate) fn store_content(conn: &rusqlite::Connection, content: &SharedContent) -> Result<()> {
+    if content.new {
+        conn.prepare_cached(
+            "INSERT OR IGNORE INTO block_contents (clause_hash, tokens, runs, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?
+        .execute(params![
+            content.clause_hash,
+            content.tokens,
+            content.runs,
+            Utc::now().to_rfc3339(),
+        ])?;
+    }
+    Ok(())
+}
+
+/// The shared tokens and runs of block `block_id`, or `None` when it keeps
+/// its own rows.
+pub(crate) fn shared_content(
+    conn: &rusqlite::Connection,
+    block_id: &str,
+) -> Result<Option<(Vec<Token>, Vec<Run>)>> {
+    let stored: Option<(String, String)> = conn
+        .prepare_cached(
+            "SELECT c.tokens, c.runs
+               FROM blocks b
+               JOIN block_contents c ON c.clause_hash = b.content_hash
+              WHERE b.id = ?1",
+        )?
+        .query_row(params![block_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()?;
+    stored
+        .map(|(tokens, runs)| Ok((serde_json::from_str(&tokens)?, serde_json::from_str(&runs)?)))
+        .transpose()
+}
+
+/// Give each of `block_ids` in document `doc_id` that shares its content
+/// its own copy of the tokens and runs, so that they can be edited in place.
+pub(crate) fn unshare_content(
+    conn: &rusqlite::Connection,
+    doc_id: &str,
+    block_ids: &HashSet<String>,
+) -> Result<()> {
+    for id in block_ids {
+        let in_document = conn
+            .prepare_cached("SELECT 1 FROM blocks WHERE id = ?1 AND document_id = ?2")?
+            .exists(params![id, doc_id])?;
+        if !in_document {
+            continue;
+        }
+        let Some((tokens, runs)) = shared_content(conn, id)? else {
+            continue;
+        };
+        insert_inline_sub_rows(conn, id, &tokens, &runs)?;
+        conn.execute("UPDATE blocks SET content_hash = NULL WHERE id = ?1", params![id])?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_memory_pool, BlockStore, DbPool, SqliteBlockStore};
+    use crate::defined_terms::set_token_kinds;
+    use rt_model::block::{BlockType, Document, DocumentType, RunFormatting, TokenKind};
+    use rt_model::Block;
+    use uuid::Uuid;
+
+    const BOILERPLATE: &str = "This Agreement is governed by the laws of England.";
+
+    fn store_doc(pool: &DbPool) -> Uuid {
+        let doc = Document {
+            id: Uuid::new_v4(),
+            name: "doc".into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: "1.0.0".into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        };
+        SqliteBlockStore::new(pool.clone()).insert_document(&doc).unwrap();
+        doc.id
+    }
+
+    fn clause(doc_id: Uuid, path: &str, bold: bool) -> Block {
+        let mut block =
+            Block::new(BlockType::Clause, path, BOILERPLATE, BOILERPLATE, None, doc_id, 0);
+        block.tokens = BOILERPLATE
+            .split(' ')
+            .map(|word| Token {
+                text: word.into(),
+                kind: TokenKind::Word,
+                normalized: word.to_lowercase(),
+                offset: 0,
+            })
+            .collect();
+        block.runs = vec![Run {
+            text: BOILERPLATE.into(),
+            formatting: RunFormatting {
+                bold,
+                ..RunFormatting::default()
+            },
+            revision: None,
+        }];
+        block
+    }
+
+    fn count(pool: &DbPool, table: &str) -> i64 {
+        let conn = pool.get().unwrap();
+        conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn identical_blocks_share_one_content_and_read_back_whole() {
+        let pool = create_memory_pool().unwrap();
+        set_content_addressing(&pool.get().unwrap(), true).unwrap();
+        let store = SqliteBlockStore::new(pool.clone());
+        let docs: Vec<Uuid> = (0..3).map(|_| store_doc(&pool)).collect();
+        for doc in &docs {
+            store.insert_block(&clause(*doc, "12.1", false)).unwrap();
+        }
+        // Same text, other formatting: keeps its own rows.
+        store.insert_block(&clause(docs[0], "12.2", true)).unwrap();
+
+        assert_eq!(count(&pool, "block_contents"), 1);
+        assert_eq!(count(&pool, "tokens"), 9);
+        assert_eq!(count(&pool, "runs"), 1);
+        for doc in &docs {
+            let blocks = store.get_blocks_by_document(doc).unwrap();
+            assert_eq!(blocks[0].tokens.len(), 9);
+            assert_eq!(blocks[0].tokens[8].text, "England.");
+            assert!(!blocks[0].runs[0].formatting.bold);
+        }
+        assert!(store.get_blocks_by_document(&docs[0]).unwrap()[1].runs[0].formatting.bold);
+
+        // Content goes with the last block referencing it.
+        let conn = pool.get().unwrap();
+        conn.execute("DELETE FROM documents WHERE id = ?1", params![docs[0].to_string()])
+            .unwrap();
+        conn.execute("DELETE FROM documents WHERE id = ?1", params![docs[1].to_string()])
+            .unwrap();
+        assert_eq!(count(&pool, "block_contents"), 1);
+        conn.execute("DELETE FROM documents WHERE id = ?1", params![docs[2].to_string()])
+            .unwrap();
+        assert_eq!(count(&pool, "block_contents"), 0);
+    }
+
+    #[test]
+    fn migration_moves_stored_blocks_and_edits_copy_on_write() {
+        let pool = create_memory_pool().unwrap();
+        let store = SqliteBlockStore::new(pool.clone());
+        let [first, second] = [store_doc(&pool), store_doc(&pool)];
+        let block = clause(first, "1", false);
+        store.insert_block(&block).unwrap();
+        store.insert_block(&clause(second, "1", false)).unwrap();
+        store.insert_block(&clause(second, "2", true)).unwrap();
+        assert_eq!(count(&pool, "tokens"), 27);
+
+        let conn = pool.get().unwrap();
+        let tenant = TenantContext::default();
+        let migration = migrate_to_content_store(&conn, &tenant).unwrap();
+        assert_eq!(
+            migration,
+            ContentMigration {
+                blocks: 3,
+                shared: 2,
+                contents: 1
+            }
+        );
+        assert_eq!(count(&pool, "tokens"), 9);
+        assert_eq!(migrate_to_content_store(&conn, &tenant).unwrap().shared, 0);
+
+        // Re-classifying a token of one block leaves the other's alone.
+        set_token_kinds(&conn, &tenant, &first, &[(block.id, 1, TokenKind::DefinedTerm)])
+            .unwrap();
+        let edited = store.get_blocks_by_document(&first).unwrap();
+        assert_eq!(edited[0].tokens[1].kind, TokenKind::DefinedTerm);
+        let untouched = store.get_blocks_by_document(&second).unwrap();
+        assert_eq!(untouched[0].tokens[1].kind, TokenKind::Word);
+        assert_eq!(count(&pool, "block_contents"), 1);
+    }
+}
@@ -0,0 +1,154 @@
+//! Stored cross-references.
+//!
+//! The section references found in a document's text (see
+//! `rt_compare::xref`) are kept in `cross_references` in document order and
+//! deleted with the document or the block holding them. Recomputing a
+//! document's references replaces them whole.
+
+use rusqlite::params;
+use uuid::Uuid;
+
+use rt_model::error::Result;
+use rt_model::CrossReference;
+
+use crate::tenant::{ensure_document, TenantContext};
+
+/// Replace the cross-references of `doc_id` with `references`; `NotFound`
+/// when `tenant` has no such document.
+pub fn replace_cross_references(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    references: &[CrossReference],
+) -> Result<()> {
+    ensure_document(conn, tenant, doc_id)?;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "DELETE FROM cross_references WHERE document_id = ?1",
+        params![doc_id.to_string()],
+    )?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO cross_references (document_id, seq, block_id, text, target, offset)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (seq, reference) in references.iter().enumerate() {
+            stmt.execute(params![
+                doc_id.to_string(),
+                seq as i64,
+                reference.block_id.to_string(),
+                reference.text,
+                reference.target,
+                reference.offset as i64,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// The cross-references of `doc_id` in document order, only those naming
+/// `target` (a normalized structural path) when given. `NotFound` when
+/// `tenant` has no such document.
+pub fn get_cross_references(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    target: Option<&str>,
+) -> Result<Vec<CrossReference>> {
+    ensure_document(conn, tenant, doc_id)?;
+    let mut stmt = conn.prepare(
+        "SELECT block_id, text, target, offset FROM cross_references
+          WHERE document_id = ?1 AND (?2 IS NULL OR target = ?2)
+          ORDER BY seq",
+    )?;
+    let references = stmt
+        .query_map(params![doc_id.to_string(), target], |row| {
+            let block_id: String = row.get(0)?;
+            Ok(CrossReference {
+                block_id: Uuid::parse_str(&block_id).map_err(|e| {
+                    let text = rusqlite::types::Type::Text;
+                    rusqlite::Error::FromSqlConversionFailure(0, text, Box::new(e))
+                })?,
+                text: row.get(1)?,
+                target: row.get(2)?,
+                offset: row.get::<_, i64>(3)? as usize,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(references)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rt_model::error::RtError;
+    use rusqlite::Connection;
+
+    fn setup() -> (Connection, Uuid, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        let block_id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO documents
+             (id, name, doc_type, schema_version, normalization_version,
+              hash_contract_version, ingested_at, metadata)
+             VALUES (?1, 'test-doc', 'CONTRACT', '1.0.0', '1.0.0', '1.0.0',
+                     '2024-01-01T00:00:00Z', '{}')",
+            params![doc_id.to_string()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO blocks
+             (id, document_id, block_type, level, structural_path, anchor_signature,
+              clause_hash, canonical_text, display_text, formatting_meta, position_index)
+             VALUES (?1, ?2, 'clause', 0, '1.', '', '', 'See Section 5.', 'See Section 5.',
+                     '{}', 0)",
+            params![block_id.to_string(), doc_id.to_string()],
+        )
+        .unwrap();
+        (conn, doc_id, block_id)
+    }
+
+    fn reference(block_id: Uuid, target: &str) -> CrossReference {
+        CrossReference {
+            block_id,
+            text: format!("Section {target}"),
+            target: target.into(),
+            offset: 4,
+        }
+    }
+
+    #[test]
+    fn references_are_replaced_and_filtered_by_target() {
+        let (conn, doc, block) = setup();
+        let tenant = TenantContext::default();
+        let refs = vec![reference(block, "5"), reference(block, "2.1"), reference(block, "5")];
+        replace_cross_references(&conn, &tenant, &doc, &refs).unwrap();
+        assert_eq!(get_cross_references(&conn, &tenant, &doc, None).unwrap(), refs);
+        assert_eq!(get_cross_references(&conn, &tenant, &doc, Some("5")).unwrap().len(), 2);
+
+        replace_cross_references(&conn, &tenant, &doc, &refs[1..2]).unwrap();
+        assert_eq!(get_cross_references(&conn, &tenant, &doc, None).unwrap(), &refs[1..2]);
+
+        conn.execute("DELETE FROM blocks WHERE id = ?1", params![block.to_string()]).unwrap();
+        assert!(get_cross_references(&conn, &tenant, &doc, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn documents_of_other_tenants_are_not_found() {
+        let (conn, doc, block) = setup();
+        let other = TenantContext::new("other").unwrap();
+        let refs = [reference(block, "5")];
+        let err = replace_cross_references(&conn, &other, &doc, &refs).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+        let err = get_cross_references(&conn, &other, &doc, None).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+    }
+}
@@ -0,0 +1,2040 @@
+use std::collections::{HashSet, VecDeque};
+
+use chrono::{DateTime, Utc};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::block::{
+    Attachment, AttachmentType, Block, BlockType, ChangeType, Document, DocumentType,
+    FormattingMeta, Run, RunFormatting, Token, TokenKind, TrackedChange,
+};
+use rt_model::error::{Result, RtError};
+use crate::content::{share_content, shared_content, store_content};
+use crate::schema::run_migrations;
+use crate::tenant::{ensure_document, TenantContext};
+use crate::usage::{record_usage, UsageMetric};
+
+// ---------------------------------------------------------------------------
+// Pool type alias
+// ---------------------------------------------------------------------------
+
+pub type DbPool = Pool<SqliteConnectionManager>;
+
+/// A connection checked out of a [`DbPool`].
+pub type DbConn = r2d2::PooledConnection<SqliteConnectionManager>;
+
+// ---------------------------------------------------------------------------
+// Pool constructors
+// ---------------------------------------------------------------------------
+
+/// Open a connection pool backed by a file-based SQLite database.
+pub fn create_pool(db_path: &str) -> Result<DbPool> {
+    let manager = SqliteConnectionManager::file(db_path)
+        .with_init(|conn| {
+            conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA journal_mode = WAL;")?;
+            Ok(())
+        });
+
+    let pool = Pool::builder()
+        .max_size(16)
+        .build(manager)
+        .map_err(|e| RtError::Internal(e.to_string()))?;
+
+    let conn = pool.get().map_err(|e| RtError::Internal(e.to_string()))?;
+    run_migrations(&conn)?;
+
+    Ok(pool)
+}
+
+/// Open a connection pool backed by a shared in-memory SQLite database.
+///
+/// The connections share the database through SQLite's `memdb` VFS rather
+/// than shared-cache mode: shared-cache reports lock conflicts between
+/// connections as `SQLITE_LOCKED` at once, while `memdb` uses ordinary
+/// database locks, so concurrent writers wait out the busy timeout as they
+/// do on a file. The database lives as long as one of its connections, so
+/// the pool never retires them.
+pub fn create_memory_pool() -> Result<DbPool> {
+    let uri = format!("file:/rtflow-{}?vfs=memdb", Uuid::new_v4());
+    let manager = SqliteConnectionManager::file(uri)
+        .with_init(|conn| {
+            conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+            Ok(())
+        });
+
+    let pool = Pool::builder()
+        .max_size(4)
+        .max_lifetime(None)
+        .idle_timeout(None)
+        .build(manager)
+        .map_err(|e| RtError::Internal(e.to_string()))?;
+
+    let conn = pool.get().map_err(|e| RtError::Internal(e.to_string()))?;
+    run_migrations(&conn)?;
+
+    Ok(pool)
+}
+
+// ---------------------------------------------------------------------------
+// ConflictPolicy
+// ---------------------------------------------------------------------------
+
+/// How an insert resolves a collision with a row that already exists.
+///
+/// Documents collide on `id`; blocks collide on `id` or on
+/// `(document_id, structural_path)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Abort with the underlying constraint error. This is what the plain
+    /// `insert_*` methods do.
+    #[default]
+    Fail,
+    /// Keep the existing row and silently drop the incoming one.
+    Skip,
+    /// Overwrite the existing row. A replaced block keeps no tokens, runs,
+    /// attachments or tracked changes from the previous version, and any
+    /// block deltas that referenced a displaced block are removed with it.
+    Replace,
+}
+
+// ---------------------------------------------------------------------------
+// DocumentFilter
+// ---------------------------------------------------------------------------
+
+/// Default page size of [`BlockStore::list_documents`].
+pub const DEFAULT_LIST_LIMIT: usize = 100;
+/// Largest page size [`BlockStore::list_documents`] accepts.
+pub const MAX_LIST_LIMIT: usize = 1000;
+
+/// Criteria for [`BlockStore::list_documents`]; every field is optional and
+/// unset fields do not filter.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DocumentFilter {
+    pub doc_type: Option<DocumentType>,
+    /// Case-insensitive substring of the document name.
+    pub name_contains: Option<String>,
+    /// Inclusive lower bound on `ingested_at`.
+    pub ingested_from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on `ingested_at`.
+    pub ingested_to: Option<DateTime<Utc>>,
+    /// Top-level metadata key the document must have.
+    pub metadata_key: Option<String>,
+    /// Value `metadata_key` must hold; requires `metadata_key`.
+    pub metadata_value: Option<serde_json::Value>,
+    /// Number of matching documents to skip.
+    pub offset: usize,
+    /// Page size; defaults to [`DEFAULT_LIST_LIMIT`], at most
+    /// [`MAX_LIST_LIMIT`].
+    pub limit: Option<usize>,
+}
+
+// ---------------------------------------------------------------------------
+// Block search
+// ---------------------------------------------------------------------------
+
+/// Criteria narrowing a [`BlockStore::search`]; every field is optional and
+/// unset fields do not filter.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlockSearchFilter {
+    /// Only blocks of these documents; empty for every document.
+    pub document_ids: Vec<Uuid>,
+    pub doc_type: Option<DocumentType>,
+    pub block_type: Option<BlockType>,
+    /// Number of hits to skip.
+    pub offset: usize,
+    /// Page size; defaults to [`DEFAULT_LIST_LIMIT`], at most
+    /// [`MAX_LIST_LIMIT`].
+    pub limit: Option<usize>,
+}
+
+/// A block matching a [`BlockStore::search`].
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockSearchHit {
+    pub block_id: Uuid,
+    pub document_id: Uuid,
+    pub structural_path: String,
+    pub block_type: BlockType,
+    /// Excerpt of the block's `canonical_text` around the matches, each
+    /// match wrapped in `<mark>` … `</mark>`. The text is not HTML-escaped.
+    pub snippet: String,
+    /// Relevance (negated BM25); higher is better.
+    pub score: f64,
+}
+
+/// Each whitespace-separated word of `query` as a quoted FTS5 string, so
+/// punctuation in user input is matched rather than parsed as query syntax.
+/// Every word must appear in a matching block.
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ---------------------------------------------------------------------------
+// BlockStore trait
+// ---------------------------------------------------------------------------
+
+/// Persistence interface for blocks and their parent documents.
+///
+/// # Concurrency
+///
+/// Several processes may ingest into the same database at once. Each
+/// `upsert_*` call runs in a single immediate (write-locking) transaction,
+/// so a batch is applied either entirely or not at all and never interleaves
+/// with another writer's batch. Writers that find the database locked wait
+/// for SQLite's busy timeout before failing with [`RtError::Database`].
+///
+/// Two writers racing on the same `doc_id` should both use
+/// [`ConflictPolicy::Skip`] (first writer wins) or
+/// [`ConflictPolicy::Replace`] (last writer wins); with
+/// [`ConflictPolicy::Fail`] the slower writer receives a unique-constraint
+/// error.
+///
+/// Within a process, one store can be shared by any number of threads:
+/// implementations are `Send + Sync`, and every call checks out its own
+/// pooled connection for its duration.
+pub trait BlockStore: Send + Sync {
+    fn insert_document(&self, doc: &Document) -> Result<()>;
+    /// Insert `doc`, resolving an existing row with the same id per `policy`.
+    /// Replacing a document updates it in place and keeps its blocks.
+    fn upsert_document(&self, doc: &Document, policy: ConflictPolicy) -> Result<()>;
+    fn get_document(&self, id: &Uuid) -> Result<Document>;
+    /// Documents matching `filter`, oldest ingest first, one page at a time.
+    fn list_documents(&self, filter: &DocumentFilter) -> Result<Vec<Document>>;
+    fn insert_block(&self, block: &Block) -> Result<()>;
+    fn insert_blocks(&self, blocks: &[Block]) -> Result<()>;
+    /// Insert `blocks` (parents before children) in one transaction,
+    /// resolving collisions per `policy`. Returns the number of blocks
+    /// written; skipped blocks are not counted.
+    fn upsert_blocks(&self, blocks: &[Block], policy: ConflictPolicy) -> Result<usize>;
+    fn get_blocks_by_document(&self, doc_id: &Uuid) -> Result<Vec<Block>>;
+    /// At most `limit` blocks of `doc_id`, skipping the first `offset`, in
+    /// the same order as [`BlockStore::get_blocks_by_document`].
+    fn get_blocks_page(&self, doc_id: &Uuid, offset: usize, limit: usize) -> Result<Vec<Block>>;
+    /// Iterate over the blocks of `doc_id` in pages of `batch_size`, so only
+    /// one page (with its tokens and runs) is held in memory at a time.
+    fn stream_blocks(&self, doc_id: &Uuid, batch_size: usize) -> BlockStream<'_>;
+    fn get_block(&self, id: &Uuid) -> Result<Block>;
+    fn get_block_children(&self, parent_id: &Uuid) -> Result<Vec<Block>>;
+    fn get_block_tree(&self, doc_id: &Uuid) -> Result<Vec<Block>>;
+    fn update_block(&self, block: &Block) -> Result<()>;
+    fn delete_block(&self, id: &Uuid) -> Result<()>;
+    fn get_blocks_by_anchor(&self, anchor_signature: &str) -> Result<Vec<Block>>;
+    /// Every block, in any document, whose `clause_hash` is `clause_hash`.
+    fn get_blocks_by_clause_hash(&self, clause_hash: &str) -> Result<Vec<Block>>;
+    /// Full-text search over block `canonical_text`: the blocks containing
+    /// every word of `query` and matching `filter`, best match first.
+    fn search(&self, query: &str, filter: &BlockSearchFilter) -> Result<Vec<BlockSearchHit>>;
+    /// Rewrite part of document `doc_id` in one transaction: delete the
+    /// blocks in `deleted`, then write `upserts` (parents before children),
+    /// updating blocks whose id already exists in place. Blocks may trade
+    /// structural paths with each other; blocks in neither list are left
+    /// untouched.
+    fn apply_block_changes(&self, doc_id: &Uuid, upserts: &[Block], deleted: &[Uuid]) -> Result<()>;
+}
+
+// ---------------------------------------------------------------------------
+// BlockStream
+// ---------------------------------------------------------------------------
+
+/// Iterator returned by [`BlockStore::stream_blocks`].
+///
+/// Each page is fetched with [`BlockStore::get_blocks_page`] on demand and
+/// no connection is held between pages, so blocks written or deleted while
+/// streaming may be skipped or seen twice. The iterator ends after the first
+/// error.
+pub struct BlockStream<'a> {
+    store: &'a dyn BlockStore,
+    doc_id: Uuid,
+    batch_size: usize,
+    offset: usize,
+    buffer: VecDeque<Block>,
+    done: bool,
+}
+
+impl<'a> BlockStream<'a> {
+    pub fn new(store: &'a dyn BlockStore, doc_id: Uuid, batch_size: usize) -> Self {
+        Self {
+            store,
+            doc_id,
+            batch_size,
+            offset: 0,
+            buffer: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl Iterator for BlockStream<'_> {
+    type Item = Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(block) = self.buffer.pop_front() {
+            return Some(Ok(block));
+        }
+        if self.done {
+            return None;
+        }
+        if self.batch_size == 0 {
+            self.done = true;
+            return Some(Err(RtError::InvalidInput(
+                "batch_size must be greater than zero".into(),
+            )));
+        }
+
+        match self.store.get_blocks_page(&self.doc_id, self.offset, self.batch_size) {
+            Ok(page) => {
+                self.done = page.len() < self.batch_size;
+                self.offset += page.len();
+                self.buffer.extend(page);
+                self.buffer.pop_front().map(Ok)
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// SqliteBlockStore
+// ---------------------------------------------------------------------------
+
+/// [`BlockStore`] over SQLite, scoped to one tenant: documents and blocks
+/// owned by other tenants are invisible to it.
+pub struct SqliteBlockStore {
+    pool: DbPool,
+    tenant: TenantContext,
+    strict: bool,
+}
+
+impl SqliteBlockStore {
+    /// A store acting for the default tenant.
+    pub fn new(pool: DbPool) -> Self {
+        Self::with_tenant(pool, TenantContext::default())
+    }
+
+    pub fn with_tenant(pool: DbPool, tenant: TenantContext) -> Self {
+        Self {
+            pool,
+            tenant,
+            strict: false,
+        }
+    }
+
+    /// Reject stored block types and token kinds this build does not know,
+    /// instead of reading them as `paragraph` / `word`.
+    pub fn with_strict_parsing(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn tenant(&self) -> &TenantContext {
+        &self.tenant
+    }
+
+    fn conn(&self) -> Result<DbConn> {
+        self.pool
+            .get()
+            .map_err(|e| RtError::Internal(e.to_string()))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helper: row -> Block (without tokens / runs)
+// ---------------------------------------------------------------------------
+
+fn row_to_block(row: &rusqlite::Row<'_>, strict: bool) -> rusqlite::Result<Block> {
+    let id_str: String = row.get(0)?;
+    let document_id_str: String = row.get(1)?;
+    let parent_id_str: Option<String> = row.get(2)?;
+    let block_type_str: String = row.get(3)?;
+    let level: i64 = row.get(4)?;
+    let structural_path: String = row.get(5)?;
+    let anchor_signature: String = row.get(6)?;
+    let clause_hash: String = row.get(7)?;
+    let canonical_text: String = row.get(8)?;
+    let display_text: String = row.get(9)?;
+    let formatting_meta_json: String = row.get(10)?;
+    let position_index: i64 = row.get(11)?;
+
+    let id = Uuid::parse_str(&id_str)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?;
+    let document_id = Uuid::parse_str(&document_id_str)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?;
+    let parent_id = parent_id_str
+        .map(|s| Uuid::parse_str(&s))
+        .transpose()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?;
+
+    let formatting_meta: FormattingMeta =
+        serde_json::from_str(&formatting_meta_json).unwrap_or_default();
+    let block_type = if strict {
+        BlockType::try_from(block_type_str.as_str())
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?
+    } else {
+        BlockType::from_str_lenient(&block_type_str)
+    };
+
+    Ok(Block {
+        id,
+        document_id,
+        parent_id,
+        block_type,
+        level: level as i32,
+        structural_path,
+        anchor_signature,
+        clause_hash,
+        canonical_text,
+        display_text,
+        formatting_meta,
+        position_index: position_index as i32,
+        tokens: Vec::new(),
+        runs: Vec::new(),
+        attachments: Vec::new(),
+        children: Vec::new(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Helper: row -> Token
+// ---------------------------------------------------------------------------
+
+fn row_to_token(row: &rusqlite::Row<'_>, strict: bool) -> rusqlite::Result<Token> {
+    // Columns: seq, text, kind, normalized, offset
+    let _seq: i64 = row.get(0)?;
+    let text: String = row.get(1)?;
+    let kind_str: String = row.get(2)?;
+    let normalized: String = row.get(3)?;
+    let offset: i64 = row.get(4)?;
+    let kind = if strict {
+        TokenKind::try_from(kind_str.as_str())
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?
+    } else {
+        TokenKind::from_str_lenient(&kind_str)
+    };
+
+    Ok(Token {
+        text,
+        kind,
+        normalized,
+        offset: offset as usize,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Helper: row -> Run
+// ---------------------------------------------------------------------------
+
+fn row_to_run(row: &rusqlite::Row<'_>) -> rusqlite::Result<Run> {
+    // Columns: seq, text, bold, italic, underline, strikethrough, font_size, color,
+    //          revision
+    let _seq: i64 = row.get(0)?;
+    let text: String = row.get(1)?;
+    let bold: i32 = row.get(2)?;
+    let italic: i32 = row.get(3)?;
+    let underline: i32 = row.get(4)?;
+    let strikethrough: i32 = row.get(5)?;
+    let font_size: Option<f64> = row.get(6)?;
+    let color: Option<String> = row.get(7)?;
+    let revision: Option<String> = row.get(8)?;
+
+    Ok(Run {
+        text,
+        formatting: RunFormatting {
+            bold: bold != 0,
+            italic: italic != 0,
+            underline: underline != 0,
+            strikethrough: strikethrough != 0,
+            font_size: font_size.map(|v| v as f32),
+            color,
+        },
+        revision: revision.as_deref().map(ChangeType::from),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Helper: row -> Document
+// ---------------------------------------------------------------------------
+
+fn row_to_document(row: &rusqlite::Row<'_>) -> rusqlite::Result<Document> {
+    // Columns: id, name, source_path, doc_type, schema_version,
+    //          normalization_version, hash_contract_version, ingested_at, metadata,
+    //          anchor_version
+    let id_str: String = row.get(0)?;
+    let doc_type_str: String = row.get(3)?;
+    let ingested_at_str: String = row.get(7)?;
+    let metadata_json: String = row.get(8)?;
+
+    let id = Uuid::parse_str(&id_str).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    let ingested_at = DateTime::parse_from_rfc3339(&ingested_at_str)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(7, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+
+    Ok(Document {
+        id,
+        name: row.get(1)?,
+        source_path: row.get(2)?,
+        doc_type: DocumentType::from(doc_type_str.as_str()),
+        schema_version: row.get(4)?,
+        normalization_version: row.get(5)?,
+        hash_contract_version: row.get(6)?,
+        anchor_version: row.get(9)?,
+        ingested_at,
+        metadata: serde_json::from_str(&metadata_json).ok(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Helper: row -> Attachment
+// ---------------------------------------------------------------------------
+
+fn row_to_attachment(row: &rusqlite::Row<'_>) -> rusqlite::Result<Attachment> {
+    // Columns: seq, attachment_type, content_hash, storage_ref, name
+    let _seq: i64 = row.get(0)?;
+    let attachment_type: String = row.get(1)?;
+
+    Ok(Attachment {
+        attachment_type: AttachmentType::from(attachment_type.as_str()),
+        content_hash: row.get(2)?,
+        storage_ref: row.get(3)?,
+        name: row.get(4)?,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Helpers: populate tokens, runs + attachments onto a flat block list
+// ---------------------------------------------------------------------------
+
+fn populate_block_rows(
+    conn: &rusqlite::Connection,
+    blocks: &mut [Block],
+    strict: bool,
+) -> Result<()> {
+    for block in blocks.iter_mut() {
+        let id = block.id.to_string();
+        (block.tokens, block.runs) = match shared_content(conn, &id)? {
+            Some(shared) => shared,
+            None => (inline_tokens(conn, &id, strict)?, inline_runs(conn, &id)?),
+        };
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT seq, attachment_type, content_hash, storage_ref, name
+               FROM block_attachments
+              WHERE block_id = ?1
+              ORDER BY seq ASC",
+        )?;
+        let attachments: Vec<Attachment> = stmt
+            .query_map(params![block.id.to_string()], row_to_attachment)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        block.attachments = attachments;
+    }
+    Ok(())
+}
+
+/// The tokens block `block_id` keeps in its own rows.
+pub(crate) fn inline_tokens(
+    conn: &rusqlite::Connection,
+    block_id: &str,
+    strict: bool,
+) -> Result<Vec<Token>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT seq, text, kind, normalized, offset
+           FROM tokens
+          WHERE block_id = ?1
+          ORDER BY seq ASC",
+    )?;
+    let tokens = stmt
+        .query_map(params![block_id], |row| row_to_token(row, strict))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(tokens)
+}
+
+/// The runs block `block_id` keeps in its own rows.
+pub(crate) fn inline_runs(conn: &rusqlite::Connection, block_id: &str) -> Result<Vec<Run>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT seq, text, bold, italic, underline, strikethrough, font_size, color,
+                revision
+           FROM runs
+          WHERE block_id = ?1
+          ORDER BY seq ASC",
+    )?;
+    let runs = stmt
+        .query_map(params![block_id], row_to_run)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(runs)
+}
+
+// ---------------------------------------------------------------------------
+// Helpers: documents and blocks on a connection
+// ---------------------------------------------------------------------------
+
+/// Write `doc` for `tenant`, resolving an id collision per `policy`.
+pub fn write_document(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc: &Document,
+    policy: ConflictPolicy,
+) -> Result<()> {
+    let metadata_json = serde_json::to_string(&doc.metadata)?;
+
+    // `INSERT OR REPLACE` would delete the row first and cascade to its
+    // blocks, so replacement is an in-place update instead.
+    let on_conflict = match policy {
+        ConflictPolicy::Fail => "",
+        ConflictPolicy::Skip => "ON CONFLICT(id) DO NOTHING",
+        ConflictPolicy::Replace => {
+            "ON CONFLICT(id) DO UPDATE SET
+                name                  = excluded.name,
+                source_path           = excluded.source_path,
+                doc_type              = excluded.doc_type,
+                schema_version        = excluded.schema_version,
+                normalization_version = excluded.normalization_version,
+                hash_contract_version = excluded.hash_contract_version,
+                anchor_version        = excluded.anchor_version,
+                ingested_at           = excluded.ingested_at,
+                metadata              = excluded.metadata
+             WHERE documents.tenant_id = excluded.tenant_id"
+        }
+    };
+
+    let written = conn.execute(
+        &format!(
+            "INSERT INTO documents
+                (id, name, source_path, doc_type, schema_version,
+                 normalization_version, hash_contract_version, ingested_at, metadata,
+                 tenant_id, anchor_version)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             {on_conflict}"
+        ),
+        params![
+            doc.id.to_string(),
+            doc.name,
+            doc.source_path,
+            doc.doc_type.as_str(),
+            doc.schema_version,
+            doc.normalization_version,
+            doc.hash_contract_version,
+            doc.ingested_at.to_rfc3339(),
+            metadata_json,
+            tenant.id(),
+            doc.anchor_version,
+        ],
+    )?;
+    if written == 0 && policy == ConflictPolicy::Replace {
+        // The id is taken by another tenant's document.
+        return Err(RtError::NotFound(format!("document {}", doc.id)));
+    }
+    record_usage(conn, tenant, UsageMetric::DocumentsIngested, written as u64)
+}
+
+/// Write `blocks` (parents before children) of documents owned by `tenant`,
+/// resolving collisions per `policy`, in the caller's transaction if any.
+/// Returns the number of blocks written.
+pub fn write_blocks(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    blocks: &[Block],
+    policy: ConflictPolicy,
+) -> Result<usize> {
+    let mut checked = HashSet::new();
+    let mut written = 0;
+    for block in blocks {
+        if checked.insert(block.document_id) {
+            ensure_document(conn, tenant, &block.document_id)?;
+        }
+        if upsert_block_row(conn, block, policy)? {
+            written += 1;
+        }
+    }
+    record_usage(conn, tenant, UsageMetric::BlocksStored, written as u64)?;
+    Ok(written)
+}
+
+/// The document `id` of `tenant`; `NotFound` when there is none.
+pub(crate) fn read_document(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    id: &Uuid,
+) -> Result<Document> {
+    let result = conn.query_row(
+        "SELECT id, name, source_path, doc_type, schema_version,
+                normalization_version, hash_contract_version, ingested_at, metadata,
+                anchor_version
+           FROM documents
+          WHERE id = ?1 AND tenant_id = ?2",
+        params![id.to_string(), tenant.id()],
+        row_to_document,
+    );
+
+    match result {
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            Err(RtError::NotFound(format!("document {id}")))
+        }
+        Err(e) => Err(RtError::Database(e)),
+        Ok(doc) => Ok(doc),
+    }
+}
+
+/// The blocks of `doc_id` of `tenant`, flat and in document order.
+pub(crate) fn read_blocks(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    strict: bool,
+) -> Result<Vec<Block>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, document_id, parent_id, block_type, level, structural_path,
+                anchor_signature, clause_hash, canonical_text, display_text,
+                formatting_meta, position_index
+           FROM blocks
+          WHERE document_id = ?1
+            AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?2)
+          ORDER BY position_index ASC, rowid ASC",
+    )?;
+
+    let mut blocks: Vec<Block> = stmt
+        .query_map(params![doc_id.to_string(), tenant.id()], |row| row_to_block(row, strict))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    populate_block_rows(conn, &mut blocks, strict)?;
+    Ok(blocks)
+}
+
+// ---------------------------------------------------------------------------
+// Helpers: insert a single block's sub-rows
+// ---------------------------------------------------------------------------
+
+const INSERT_BLOCK_SQL: &str = "INSERT INTO blocks
+        (id, document_id, parent_id, block_type, level, structural_path,
+         anchor_signature, clause_hash, canonical_text, display_text,
+         formatting_meta, position_index, content_hash)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)";
+
+pub(crate) fn insert_block_row(conn: &rusqlite::Connection, block: &Block) -> Result<()> {
+    upsert_block_row(conn, block, ConflictPolicy::Fail).map(|_| ())
+}
+
+/// Write one block and its sub-rows, resolving collisions per `policy`.
+/// Returns `false` when the block was skipped.
+fn upsert_block_row(
+    conn: &rusqlite::Connection,
+    block: &Block,
+    policy: ConflictPolicy,
+) -> Result<bool> {
+    let formatting_meta_json = serde_json::to_string(&block.formatting_meta)?;
+    let id = block.id.to_string();
+
+    let sql = match policy {
+        ConflictPolicy::Fail => INSERT_BLOCK_SQL.to_string(),
+        ConflictPolicy::Skip => format!("{INSERT_BLOCK_SQL} ON CONFLICT DO NOTHING"),
+        ConflictPolicy::Replace => {
+            // A different block occupying the same structural path is
+            // displaced entirely; a block with the same id is updated in
+            // place so that children keep their parent link.
+            conn.execute(
+                "DELETE FROM blocks
+                  WHERE document_id = ?1 AND structural_path = ?2 AND id <> ?3",
+                params![block.document_id.to_string(), block.structural_path, id],
+            )?;
+            // Only a block of the same document is replaced by id, which
+            // keeps a replace from reaching into another tenant's rows.
+            for table in ["tokens", "runs", "tracked_changes", "block_attachments"] {
+                conn.execute(
+                    &format!(
+                        "DELETE FROM {table}
+                          WHERE block_id = ?1
+                            AND block_id IN (SELECT id FROM blocks WHERE document_id = ?2)"
+                    ),
+                    params![id, block.document_id.to_string()],
+                )?;
+            }
+            format!(
+                "{INSERT_BLOCK_SQL}
+                 ON CONFLICT(id) DO UPDATE SET
+                    document_id      = excluded.document_id,
+                    parent_id        = excluded.parent_id,
+                    block_type       = excluded.block_type,
+                    level            = excluded.level,
+                    structural_path  = excluded.structural_path,
+                    anchor_signature = excluded.anchor_signature,
+                    clause_hash      = excluded.clause_hash,
+                    canonical_text   = excluded.canonical_text,
+                    display_text     = excluded.display_text,
+                    formatting_meta  = excluded.formatting_meta,
+                    position_index   = excluded.position_index,
+                    content_hash     = excluded.content_hash
+                 WHERE blocks.document_id = excluded.document_id"
+            )
+        }
+    };
+    // Looked up after any displaced block is gone, which may have taken
+    // the last reference to its content with it.
+    let content = share_content(conn, &block.clause_hash, &block.tokens, &block.runs)?;
+
+    let written = conn.execute(
+        &sql,
+        params![
+            id,
+            block.document_id.to_string(),
+            block.parent_id.map(|u| u.to_string()),
+            block.block_type.as_str(),
+            block.level as i64,
+            block.structural_path,
+            block.anchor_signature,
+            block.clause_hash,
+            block.canonical_text,
+            block.display_text,
+            formatting_meta_json,
+            block.position_index as i64,
+            content.as_ref().map(|c| c.clause_hash.as_str()),
+        ],
+    )?;
+    if written == 0 {
+        return Ok(false);
+    }
+
+    match &content {
+        Some(content) => store_content(conn, content)?,
+        None => insert_inline_sub_rows(conn, &id, &block.tokens, &block.runs)?,
+    }
+
+    for (seq, attachment) in block.attachments.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO block_attachments
+                (id, block_id, seq, attachment_type, content_hash, storage_ref, name)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                Uuid::new_v4().to_string(),
+                block.id.to_string(),
+                seq as i64,
+                attachment.attachment_type.as_str(),
+                attachment.content_hash,
+                attachment.storage_ref,
+                attachment.name,
+            ],
+        )?;
+    }
+
+    if let Some(tc) = &block.formatting_meta.tracked_change {
+        insert_tracked_change(conn, tc, &block.id)?;
+    }
+
+    Ok(true)
+}
+
+/// Write `tokens` and `runs` as rows of their own for block `block_id`.
+pub(crate) fn insert_inline_sub_rows(
+    conn: &rusqlite::Connection,
+    block_id: &str,
+    tokens: &[Token],
+    runs: &[Run],
+) -> Result<()> {
+    for (seq, token) in tokens.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO tokens (id, block_id, seq, text, kind, normalized, offset)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                Uuid::new_v4().to_string(),
+                block_id,
+                seq as i64,
+                token.text,
+                token.kind.as_str(),
+                token.normalized,
+                token.offset as i64,
+            ],
+        )?;
+    }
+
+    for (seq, run) in runs.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO runs
+                (id, block_id, seq, text, bold, italic, underline, strikethrough,
+                 font_size, color, revision)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                Uuid::new_v4().to_string(),
+                block_id,
+                seq as i64,
+                run.text,
+                run.formatting.bold as i32,
+                run.formatting.italic as i32,
+                run.formatting.underline as i32,
+                run.formatting.strikethrough as i32,
+                run.formatting.font_size.map(|v| v as f64),
+                run.formatting.color,
+                run.revision.as_ref().map(ChangeType::as_str),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn insert_tracked_change(
+    conn: &rusqlite::Connection,
+    tc: &TrackedChange,
+    block_id: &Uuid,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tracked_changes
+            (id, block_id, author, changed_at, change_type, original)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            Uuid::new_v4().to_string(),
+            block_id.to_string(),
+            tc.author,
+            tc.date.to_rfc3339(),
+            tc.change_type.as_str(),
+            tc.original,
+        ],
+    )?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Helper: build block tree from flat list
+// ---------------------------------------------------------------------------
+
+pub(crate) fn build_tree(flat: Vec<Block>) -> Vec<Block> {
+    use std::collections::HashMap;
+
+    let mut map: HashMap<Uuid, Block> = flat.into_iter().map(|b| (b.id, b)).collect();
+
+    let child_ids: Vec<(Uuid, Uuid)> = map
+        .values()
+        .filter_map(|b| b.parent_id.map(|pid| (pid, b.id)))
+        .collect();
+
+    let mut pending: Vec<(Uuid, Uuid)> = Vec::new();
+    for (pid, cid) in child_ids {
+        if map.contains_key(&pid) {
+            pending.push((pid, cid));
+        }
+    }
+
+    let mut children_map: HashMap<Uuid, Vec<Block>> = HashMap::new();
+    for (pid, cid) in &pending {
+        if let Some(child) = map.remove(cid) {
+            children_map.entry(*pid).or_default().push(child);
+        }
+    }
+
+    for children in children_map.values_mut() {
+        children.sort_by_key(|b| b.position_index);
+    }
+
+    fn attach(block: &mut Block, children_map: &mut HashMap<Uuid, Vec<Block>>) {
+        if let Some(mut kids) = children_map.remove(&block.id) {
+            for kid in &mut kids {
+                attach(kid, children_map);
+            }
+            block.children = kids;
+        }
+    }
+
+    let mut roots: Vec<Block> = map.into_values().collect();
+    for root in &mut roots {
+        attach(root, &mut children_map);
+    }
+
+    roots.sort_by_key(|b| b.position_index);
+    roots
+}
+
+// ---------------------------------------------------------------------------
+// BlockStore implementation
+// ---------------------------------------------------------------------------
+
+impl BlockStore for SqliteBlockStore {
+    fn insert_document(&self, doc: &Document) -> Result<()> {
+        self.upsert_document(doc, ConflictPolicy::Fail)
+    }
+
+    fn upsert_document(&self, doc: &Document, policy: ConflictPolicy) -> Result<()> {
+        let conn = self.conn()?;
+        write_document(&conn, &self.tenant, doc, policy)
+    }
+
+    fn get_document(&self, id: &Uuid) -> Result<Document> {
+        let conn = self.conn()?;
+        read_document(&conn, &self.tenant, id)
+    }
+
+    fn list_documents(&self, filter: &DocumentFilter) -> Result<Vec<Document>> {
+        let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+        if limit == 0 || limit > MAX_LIST_LIMIT {
+            return Err(RtError::InvalidInput(format!(
+                "limit must be between 1 and {MAX_LIST_LIMIT}, got {limit}"
+            )));
+        }
+        if let (Some(from), Some(to)) = (filter.ingested_from, filter.ingested_to) {
+            if from > to {
+                return Err(RtError::InvalidInput(format!(
+                    "ingested_from {from} is after ingested_to {to}"
+                )));
+            }
+        }
+        let metadata_path = match &filter.metadata_key {
+            Some(key) if key.is_empty() || key.contains('"') => {
+                return Err(RtError::InvalidInput(format!("invalid metadata_key {key:?}")));
+            }
+            Some(key) => Some(format!("$.\"{key}\"")),
+            None if filter.metadata_value.is_some() => {
+                return Err(RtError::InvalidInput(
+                    "metadata_value requires metadata_key".into(),
+                ));
+            }
+            None => None,
+        };
+        let metadata_value = filter
+            .metadata_value
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+
+        // `ingested_at` is written with `to_rfc3339()`; bounds formatted the
+        // same way compare correctly as text. Metadata values are compared
+        // after both sides go through `json_extract`, so numbers, booleans,
+        // strings and nested values match by JSON value.
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, source_path, doc_type, schema_version,
+                    normalization_version, hash_contract_version, ingested_at, metadata,
+                    anchor_version
+               FROM documents
+              WHERE tenant_id = ?9
+                AND (?1 IS NULL OR doc_type = ?1)
+                AND (?2 IS NULL OR instr(lower(name), lower(?2)) > 0)
+                AND (?3 IS NULL OR ingested_at >= ?3)
+                AND (?4 IS NULL OR ingested_at < ?4)
+                AND (?5 IS NULL OR (json_type(metadata, ?5) IS NOT NULL
+                     AND (?6 IS NULL OR json_extract(metadata, ?5) IS json_extract(?6, '$'))))
+              ORDER BY ingested_at ASC, rowid ASC
+              LIMIT ?7 OFFSET ?8",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                filter.doc_type.as_ref().map(|t| t.as_str()),
+                filter.name_contains,
+                filter.ingested_from.map(|t| t.to_rfc3339()),
+                filter.ingested_to.map(|t| t.to_rfc3339()),
+                metadata_path,
+                metadata_value,
+                limit as i64,
+                filter.offset as i64,
+                self.tenant.id(),
+            ],
+            row_to_document,
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    fn insert_block(&self, block: &Block) -> Result<()> {
+        let conn = self.conn()?;
+        ensure_document(&conn, &self.tenant, &block.document_id)?;
+        insert_block_row(&conn, block)?;
+        record_usage(&conn, &self.tenant, UsageMetric::BlocksStored, 1)
+    }
+
+    fn insert_blocks(&self, blocks: &[Block]) -> Result<()> {
+        self.upsert_blocks(blocks, ConflictPolicy::Fail).map(|_| ())
+    }
+
+    fn upsert_blocks(&self, blocks: &[Block], policy: ConflictPolicy) -> Result<usize> {
+        let mut conn = self.conn()?;
+        // Take the write lock up front: a deferred transaction that later
+        // upgrades can deadlock against a concurrent writer and fail with
+        // SQLITE_BUSY without waiting on the busy timeout.
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let written = write_blocks(&tx, &self.tenant, blocks, policy)?;
+        tx.commit()?;
+        Ok(written)
+    }
+
+    fn get_blocks_by_document(&self, doc_id: &Uuid) -> Result<Vec<Block>> {
+        let conn = self.conn()?;
+        read_blocks(&conn, &self.tenant, doc_id, self.strict)
+    }
+
+    fn get_blocks_page(&self, doc_id: &Uuid, offset: usize, limit: usize) -> Result<Vec<Block>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, document_id, parent_id, block_type, level, structural_path,
+                    anchor_signature, clause_hash, canonical_text, display_text,
+                    formatting_meta, position_index
+               FROM blocks
+              WHERE document_id = ?1
+                AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?4)
+              ORDER BY position_index ASC, rowid ASC
+              LIMIT ?2 OFFSET ?3",
+        )?;
+
+        let mut blocks: Vec<Block> = stmt
+            .query_map(
+                params![doc_id.to_string(), limit as i64, offset as i64, self.tenant.id()],
+                |row| row_to_block(row, self.strict),
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        populate_block_rows(&conn, &mut blocks, self.strict)?;
+        Ok(blocks)
+    }
+
+    fn stream_blocks(&self, doc_id: &Uuid, batch_size: usize) -> BlockStream<'_> {
+        BlockStream::new(self, *doc_id, batch_size)
+    }
+
+    fn get_block(&self, id: &Uuid) -> Result<Block> {
+        let conn = self.conn()?;
+
+        let result = conn.query_row(
+            "SELECT id, document_id, parent_id, block_type, level, structural_path,
+                    anchor_signature, clause_hash, canonical_text, display_text,
+                    formatting_meta, position_index
+               FROM blocks
+              WHERE id = ?1
+                AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?2)",
+            params![id.to_string(), self.tenant.id()],
+            |row| row_to_block(row, self.strict),
+        );
+
+        let mut block = match result {
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return Err(RtError::NotFound(format!("block {id}")));
+            }
+            Err(e) => return Err(RtError::Database(e)),
+            Ok(b) => b,
+        };
+
+        let mut blocks = vec![block];
+        populate_block_rows(&conn, &mut blocks, self.strict)?;
+        block = blocks.remove(0);
+        Ok(block)
+    }
+
+    fn get_block_children(&self, parent_id: &Uuid) -> Result<Vec<Block>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, document_id, parent_id, block_type, level, structural_path,
+                    anchor_signature, clause_hash, canonical_text, display_text,
+                    formatting_meta, position_index
+               FROM blocks
+              WHERE parent_id = ?1
+                AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?2)
+              ORDER BY position_index ASC",
+        )?;
+
+        let mut blocks: Vec<Block> = stmt
+            .query_map(params![parent_id.to_string(), self.tenant.id()], |row| {
+                row_to_block(row, self.strict)
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        populate_block_rows(&conn, &mut blocks, self.strict)?;
+        Ok(blocks)
+    }
+
+    fn get_block_tree(&self, doc_id: &Uuid) -> Result<Vec<Block>> {
+        let flat = self.get_blocks_by_document(doc_id)?;
+        Ok(build_tree(flat))
+    }
+
+    fn update_block(&self, block: &Block) -> Result<()> {
+        let conn = self.conn()?;
+        ensure_document(&conn, &self.tenant, &block.document_id)?;
+        let formatting_meta_json = serde_json::to_string(&block.formatting_meta)?;
+
+        let affected = conn.execute(
+            "UPDATE blocks
+                SET document_id      = ?2,
+                    parent_id        = ?3,
+                    block_type       = ?4,
+                    level            = ?5,
+                    structural_path  = ?6,
+                    anchor_signature = ?7,
+                    clause_hash      = ?8,
+                    canonical_text   = ?9,
+                    display_text     = ?10,
+                    formatting_meta  = ?11,
+                    position_index   = ?12
+              WHERE id = ?1
+                AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?13)",
+            params![
+                block.id.to_string(),
+                block.document_id.to_string(),
+                block.parent_id.map(|u| u.to_string()),
+                block.block_type.as_str(),
+                block.level as i64,
+                block.structural_path,
+                block.anchor_signature,
+                block.clause_hash,
+                block.canonical_text,
+                block.display_text,
+                formatting_meta_json,
+                block.position_index as i64,
+                self.tenant.id(),
+            ],
+        )?;
+
+        if affected == 0 {
+            return Err(RtError::NotFound(format!("block {}", block.id)));
+        }
+        Ok(())
+    }
+
+    fn delete_block(&self, id: &Uuid) -> Result<()> {
+        let conn = self.conn()?;
+
+        let affected = conn.execute(
+            "DELETE FROM blocks
+              WHERE id = ?1
+                AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?2)",
+            params![id.to_string(), self.tenant.id()],
+        )?;
+
+        if affected == 0 {
+            return Err(RtError::NotFound(format!("block {id}")));
+        }
+        Ok(())
+    }
+
+    fn apply_block_changes(&self, doc_id: &Uuid, upserts: &[Block], deleted: &[Uuid]) -> Result<()> {
+        if let Some(block) = upserts.iter().find(|b| b.document_id != *doc_id) {
+            return Err(RtError::InvalidInput(format!(
+                "block {} belongs to document {}, not {doc_id}",
+                block.id, block.document_id
+            )));
+        }
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        ensure_document(&tx, &self.tenant, doc_id)?;
+        let doc = doc_id.to_string();
+
+        // Park rewritten blocks on a path no block can hold, so that a
+        // renumbering that swaps two paths does not collide midway.
+        for block in upserts {
+            tx.execute(
+                "UPDATE blocks SET structural_path = '~' || id WHERE id = ?1 AND document_id = ?2",
+                params![block.id.to_string(), doc],
+            )?;
+        }
+        for id in deleted {
+            tx.execute(
+                "DELETE FROM blocks WHERE id = ?1 AND document_id = ?2",
+                params![id.to_string(), doc],
+            )?;
+        }
+        for block in upserts {
+            upsert_block_row(&tx, block, ConflictPolicy::Replace)?;
+        }
+        record_usage(&tx, &self.tenant, UsageMetric::BlocksStored, upserts.len() as u64)?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn get_blocks_by_anchor(&self, anchor_signature: &str) -> Result<Vec<Block>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, document_id, parent_id, block_type, level, structural_path,
+                    anchor_signature, clause_hash, canonical_text, display_text,
+                    formatting_meta, position_index
+               FROM blocks
+              WHERE anchor_signature = ?1
+                AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?2)
+              ORDER BY position_index ASC",
+        )?;
+
+        let mut blocks: Vec<Block> = stmt
+            .query_map(params![anchor_signature, self.tenant.id()], |row| {
+                row_to_block(row, self.strict)
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        populate_block_rows(&conn, &mut blocks, self.strict)?;
+        Ok(blocks)
+    }
+
+    fn get_blocks_by_clause_hash(&self, clause_hash: &str) -> Result<Vec<Block>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, document_id, parent_id, block_type, level, structural_path,
+                    anchor_signature, clause_hash, canonical_text, display_text,
+                    formatting_meta, position_index
+               FROM blocks
+              WHERE clause_hash = ?1
+                AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?2)
+              ORDER BY document_id ASC, position_index ASC",
+        )?;
+
+        let mut blocks: Vec<Block> = stmt
+            .query_map(params![clause_hash, self.tenant.id()], |row| {
+                row_to_block(row, self.strict)
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        populate_block_rows(&conn, &mut blocks, self.strict)?;
+        Ok(blocks)
+    }
+
+    fn search(&self, query: &str, filter: &BlockSearchFilter) -> Result<Vec<BlockSearchHit>> {
+        let fts = fts_query(query);
+        if fts.is_empty() {
+            return Err(RtError::InvalidInput("search query must not be empty".into()));
+        }
+        let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+        if limit == 0 || limit > MAX_LIST_LIMIT {
+            return Err(RtError::InvalidInput(format!(
+                "limit must be between 1 and {MAX_LIST_LIMIT}, got {limit}"
+            )));
+        }
+        let document_ids = (!filter.document_ids.is_empty())
+            .then(|| serde_json::to_string(&filter.document_ids))
+            .transpose()?;
+
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT b.id, b.document_id, b.structural_path, b.block_type,
+                    snippet(blocks_fts, 0, '<mark>', '</mark>', '…', 16),
+                    bm25(blocks_fts)
+               FROM blocks_fts
+               JOIN blocks b ON b.rowid = blocks_fts.rowid
+               JOIN documents d ON d.id = b.document_id
+              WHERE blocks_fts MATCH ?1
+                AND d.tenant_id = ?2
+                AND (?3 IS NULL OR b.document_id IN (SELECT value FROM json_each(?3)))
+                AND (?4 IS NULL OR d.doc_type = ?4)
+                AND (?5 IS NULL OR b.block_type = ?5)
+              ORDER BY bm25(blocks_fts) ASC, b.document_id ASC, b.position_index ASC
+              LIMIT ?6 OFFSET ?7",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                fts,
+                self.tenant.id(),
+                document_ids,
+                filter.doc_type.as_ref().map(|t| t.as_str()),
+                filter.block_type.as_ref().map(|t| t.as_str()),
+                limit as i64,
+                filter.offset as i64,
+            ],
+            |row| {
+                let uuid = |idx: usize| {
+                    let s: String = row.get(idx)?;
+                    Uuid::parse_str(&s).map_err(|e| {
+                        rusqlite::Error::FromSqlConversionFailure(
+                            idx,
+                            rusqlite::types::Type::Text,
+                            Box::new(e),
+                        )
+                    })
+                };
+                let block_type: String = row.get(3)?;
+                let bm25: f64 = row.get(5)?;
+                Ok(BlockSearchHit {
+                    block_id: uuid(0)?,
+                    document_id: uuid(1)?,
+                    structural_path: row.get(2)?,
+                    block_type: BlockType::from_str_lenient(&block_type),
+                    snippet: row.get(4)?,
+                    score: -bm25,
+                })
+            },
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::block::{BlockType, DocumentType, FormattingMeta, Run, RunFormatting, Token, TokenKind};
+    use crate::schema::SCHEMA_VERSION;
+    use crate::tenant::TenantContext;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn make_store() -> SqliteBlockStore {
+        let pool = create_memory_pool().expect("memory pool");
+        SqliteBlockStore::new(pool)
+    }
+
+    fn make_doc() -> Document {
+        Document {
+            id: Uuid::new_v4(),
+            name: "Test Document".into(),
+            source_path: Some("/tmp/test.docx".into()),
+            doc_type: DocumentType::Original,
+            schema_version: SCHEMA_VERSION.into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: Some(serde_json::json!({"author": "tester"})),
+        }
+    }
+
+    fn make_block(doc_id: Uuid, position_index: i32) -> Block {
+        Block {
+            id: Uuid::new_v4(),
+            document_id: doc_id,
+            parent_id: None,
+            block_type: BlockType::Paragraph,
+            level: 0,
+            structural_path: format!("{position_index}"),
+            anchor_signature: format!("anchor-{position_index}"),
+            clause_hash: "abc123".into(),
+            canonical_text: "hello world".into(),
+            display_text: "Hello World".into(),
+            formatting_meta: FormattingMeta::default(),
+            position_index,
+            tokens: vec![Token {
+                text: "hello".into(),
+                kind: TokenKind::Word,
+                normalized: "hello".into(),
+                offset: 0,
+            }],
+            runs: vec![Run {
+                text: "Hello World".into(),
+                formatting: RunFormatting {
+                    font_size: Some(12.0),
+                    ..RunFormatting::default()
+                },
+                revision: Some(ChangeType::Insert),
+            }],
+            attachments: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn insert_and_get_document() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).expect("insert");
+        let fetched = store.get_document(&doc.id).expect("get");
+        assert_eq!(fetched.id, doc.id);
+        assert_eq!(fetched.name, doc.name);
+    }
+
+    #[test]
+    fn get_document_not_found() {
+        let store = make_store();
+        let result = store.get_document(&Uuid::new_v4());
+        assert!(matches!(result, Err(RtError::NotFound(_))));
+    }
+
+    #[test]
+    fn writes_are_recorded_as_usage() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+        store.upsert_document(&doc, ConflictPolicy::Skip).unwrap();
+        store
+            .insert_blocks(&[make_block(doc.id, 0), make_block(doc.id, 1)])
+            .unwrap();
+
+        let conn = store.conn().unwrap();
+        let report = crate::usage::usage_report(&conn, &store.tenant, None, None).unwrap();
+        // The skipped re-insert wrote nothing and is not counted.
+        assert_eq!(report.documents_ingested, 1);
+        assert_eq!(report.blocks_stored, 2);
+    }
+
+    #[test]
+    fn insert_and_get_block() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        let block = make_block(doc.id, 0);
+        store.insert_block(&block).unwrap();
+
+        let fetched = store.get_block(&block.id).unwrap();
+        assert_eq!(fetched.id, block.id);
+        assert_eq!(fetched.canonical_text, block.canonical_text);
+        assert_eq!(fetched.tokens.len(), 1);
+        assert_eq!(fetched.runs.len(), 1);
+        assert_eq!(fetched.runs[0].revision, Some(ChangeType::Insert));
+    }
+
+    #[test]
+    fn unknown_stored_enum_strings_are_rejected_in_strict_mode() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+        let block = make_block(doc.id, 0);
+        store.insert_block(&block).unwrap();
+        let conn = store.pool.get().unwrap();
+        conn.execute("UPDATE blocks SET block_type = 'chapter'", []).unwrap();
+        conn.execute("UPDATE tokens SET kind = 'emoji'", []).unwrap();
+
+        let lenient = store.get_block(&block.id).unwrap();
+        assert_eq!(lenient.block_type, BlockType::Paragraph);
+        assert_eq!(lenient.tokens[0].kind, TokenKind::Word);
+
+        let strict = SqliteBlockStore::new(store.pool.clone()).with_strict_parsing(true);
+        let err = strict.get_block(&block.id).unwrap_err();
+        assert!(err.to_string().contains("unknown block type 'chapter'"), "{err}");
+        conn.execute("UPDATE blocks SET block_type = 'clause'", []).unwrap();
+        let err = strict.get_blocks_by_document(&doc.id).unwrap_err();
+        assert!(err.to_string().contains("unknown token kind 'emoji'"), "{err}");
+    }
+
+    #[test]
+    fn attachments_round_trip_and_are_replaced() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        let mut block = make_block(doc.id, 0);
+        block.attachments = vec![
+            Attachment {
+                attachment_type: AttachmentType::Image,
+                content_hash: "h1".into(),
+                storage_ref: "word/media/image1.png".into(),
+                name: Some("Signature".into()),
+            },
+            Attachment {
+                attachment_type: AttachmentType::EmbeddedObject,
+                content_hash: "h2".into(),
+                storage_ref: "word/embeddings/sheet1.xlsx".into(),
+                name: Some("Excel.Sheet.12".into()),
+            },
+        ];
+        store.insert_block(&block).unwrap();
+        assert_eq!(store.get_block(&block.id).unwrap().attachments, block.attachments);
+
+        block.attachments.truncate(1);
+        store.upsert_blocks(&[block.clone()], ConflictPolicy::Replace).unwrap();
+        assert_eq!(store.get_block(&block.id).unwrap().attachments, block.attachments);
+    }
+
+    #[test]
+    fn insert_blocks_transaction() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        let blocks: Vec<Block> = (0..5).map(|i| make_block(doc.id, i)).collect();
+        store.insert_blocks(&blocks).unwrap();
+
+        let fetched = store.get_blocks_by_document(&doc.id).unwrap();
+        assert_eq!(fetched.len(), 5);
+    }
+
+    #[test]
+    fn get_blocks_by_document_ordered() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        for i in [3i32, 1, 4, 0, 2] {
+            let mut b = make_block(doc.id, i);
+            b.structural_path = i.to_string();
+            store.insert_block(&b).unwrap();
+        }
+
+        let fetched = store.get_blocks_by_document(&doc.id).unwrap();
+        let indices: Vec<i32> = fetched.iter().map(|b| b.position_index).collect();
+        assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn get_block_children() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        let mut parent = make_block(doc.id, 0);
+        parent.structural_path = "0".into();
+        store.insert_block(&parent).unwrap();
+
+        for i in 0..3i32 {
+            let mut child = make_block(doc.id, i);
+            child.parent_id = Some(parent.id);
+            child.structural_path = format!("0.{i}");
+            child.anchor_signature = format!("child-anchor-{i}");
+            store.insert_block(&child).unwrap();
+        }
+
+        let children = store.get_block_children(&parent.id).unwrap();
+        assert_eq!(children.len(), 3);
+    }
+
+    #[test]
+    fn get_block_tree_builds_hierarchy() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        let mut root = make_block(doc.id, 0);
+        root.structural_path = "0".into();
+        store.insert_block(&root).unwrap();
+
+        let mut child = make_block(doc.id, 0);
+        child.parent_id = Some(root.id);
+        child.structural_path = "0.0".into();
+        child.anchor_signature = "child-anchor".into();
+        store.insert_block(&child).unwrap();
+
+        let tree = store.get_block_tree(&doc.id).unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].children.len(), 1);
+    }
+
+    #[test]
+    fn update_block() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        let mut block = make_block(doc.id, 0);
+        store.insert_block(&block).unwrap();
+
+        block.canonical_text = "updated text".into();
+        store.update_block(&block).unwrap();
+
+        let fetched = store.get_block(&block.id).unwrap();
+        assert_eq!(fetched.canonical_text, "updated text");
+    }
+
+    #[test]
+    fn delete_block() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        let block = make_block(doc.id, 0);
+        store.insert_block(&block).unwrap();
+        store.delete_block(&block.id).unwrap();
+
+        let result = store.get_block(&block.id);
+        assert!(matches!(result, Err(RtError::NotFound(_))));
+    }
+
+    #[test]
+    fn apply_block_changes_swaps_paths_and_deletes() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+        let (mut first, mut second, gone) =
+            (make_block(doc.id, 0), make_block(doc.id, 1), make_block(doc.id, 2));
+        store.insert_blocks(&[first.clone(), second.clone(), gone.clone()]).unwrap();
+
+        // Swap the paths of the first two blocks and add a new one.
+        std::mem::swap(&mut first.structural_path, &mut second.structural_path);
+        let added = make_block(doc.id, 3);
+        store
+            .apply_block_changes(&doc.id, &[first.clone(), second.clone(), added.clone()], &[gone.id])
+            .unwrap();
+
+        let stored = store.get_blocks_by_document(&doc.id).unwrap();
+        let paths: Vec<(Uuid, String)> =
+            stored.iter().map(|b| (b.id, b.structural_path.clone())).collect();
+        assert_eq!(
+            paths,
+            vec![(first.id, "1".into()), (second.id, "0".into()), (added.id, "3".into())]
+        );
+        assert_eq!(stored[0].tokens.len(), 1);
+
+        let foreign = make_block(Uuid::new_v4(), 9);
+        assert!(matches!(
+            store.apply_block_changes(&doc.id, &[foreign], &[]),
+            Err(RtError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn get_blocks_by_anchor() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        let block = make_block(doc.id, 0);
+        let sig = block.anchor_signature.clone();
+        store.insert_block(&block).unwrap();
+
+        let found = store.get_blocks_by_anchor(&sig).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, block.id);
+    }
+
+    #[test]
+    fn search_finds_ranked_blocks_and_follows_writes() {
+        let store = make_store();
+        let (doc, other) = (make_doc(), make_doc());
+        store.insert_document(&doc).unwrap();
+        store.insert_document(&other).unwrap();
+        let texts = [
+            "the supplier shall indemnify the customer",
+            "governing law: england (and wales)",
+            "the customer shall pay the supplier",
+        ];
+        let mut blocks = Vec::new();
+        for (i, text) in texts.iter().enumerate() {
+            let mut block = make_block(doc.id, i as i32);
+            block.canonical_text = text.to_string();
+            blocks.push(block);
+        }
+        store.insert_blocks(&blocks).unwrap();
+        let mut elsewhere = make_block(other.id, 0);
+        elsewhere.canonical_text = "the supplier shall deliver".into();
+        store.insert_block(&elsewhere).unwrap();
+
+        let all = BlockSearchFilter::default();
+        let hits = store.search("supplier shall", &all).unwrap();
+        assert_eq!(hits.len(), 3);
+        assert!(hits.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(hits[0].snippet.contains("<mark>supplier</mark>"), "{}", hits[0].snippet);
+
+        let only_doc = BlockSearchFilter {
+            document_ids: vec![doc.id],
+            ..Default::default()
+        };
+        assert_eq!(store.search("supplier", &only_doc).unwrap().len(), 2);
+        let law = store.search("law: (and", &all).unwrap();
+        assert_eq!(law.len(), 1);
+        assert_eq!(law[0].block_id, blocks[1].id);
+
+        blocks[0].canonical_text = "the vendor shall indemnify the customer".into();
+        store.update_block(&blocks[0]).unwrap();
+        store.delete_block(&elsewhere.id).unwrap();
+        let hits = store.search("supplier", &all).unwrap();
+        assert_eq!(hits.iter().map(|h| h.block_id).collect::<Vec<_>>(), [blocks[2].id]);
+        assert_eq!(store.search("vendor", &all).unwrap().len(), 1);
+
+        assert!(matches!(store.search("  ", &all), Err(RtError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn get_blocks_by_clause_hash() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+
+        let block = make_block(doc.id, 0);
+        store.insert_block(&block).unwrap();
+
+        let found = store.get_blocks_by_clause_hash(&block.clause_hash).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, block.id);
+        assert!(store.get_blocks_by_clause_hash("nothing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn insert_blocks_fails_on_duplicate_path() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+        store.insert_blocks(&[make_block(doc.id, 0)]).unwrap();
+
+        let result = store.insert_blocks(&[make_block(doc.id, 0)]);
+        assert!(matches!(result, Err(RtError::Database(_))));
+    }
+
+    #[test]
+    fn upsert_document_skip_and_replace() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+        store.insert_blocks(&[make_block(doc.id, 0)]).unwrap();
+
+        let mut renamed = doc.clone();
+        renamed.name = "Renamed".into();
+        assert!(store.insert_document(&renamed).is_err());
+
+        store.upsert_document(&renamed, ConflictPolicy::Skip).unwrap();
+        assert_eq!(store.get_document(&doc.id).unwrap().name, "Test Document");
+
+        store.upsert_document(&renamed, ConflictPolicy::Replace).unwrap();
+        assert_eq!(store.get_document(&doc.id).unwrap().name, "Renamed");
+        // Replacing the document row must not cascade to its blocks.
+        assert_eq!(store.get_blocks_by_document(&doc.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn upsert_blocks_skip_keeps_existing() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+        let original = make_block(doc.id, 0);
+        store.insert_block(&original).unwrap();
+
+        let mut incoming = make_block(doc.id, 0);
+        incoming.canonical_text = "incoming".into();
+        let written = store
+            .upsert_blocks(&[incoming, make_block(doc.id, 1)], ConflictPolicy::Skip)
+            .unwrap();
+        assert_eq!(written, 1);
+
+        let fetched = store.get_blocks_by_document(&doc.id).unwrap();
+        assert_eq!(fetched.len(), 2);
+        assert_eq!(fetched[0].id, original.id);
+        assert_eq!(fetched[0].canonical_text, "hello world");
+    }
+
+    #[test]
+    fn upsert_blocks_replace_by_path_and_id() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+        let original = make_block(doc.id, 0);
+        store.insert_block(&original).unwrap();
+
+        // Same id: updated in place, sub-rows rewritten rather than appended.
+        let mut same_id = original.clone();
+        same_id.canonical_text = "updated".into();
+        assert_eq!(store.upsert_blocks(&[same_id], ConflictPolicy::Replace).unwrap(), 1);
+        let fetched = store.get_block(&original.id).unwrap();
+        assert_eq!(fetched.canonical_text, "updated");
+        assert_eq!(fetched.tokens.len(), 1);
+        assert_eq!(fetched.runs.len(), 1);
+
+        // Different id, same structural path: the old block is displaced.
+        let mut same_path = make_block(doc.id, 0);
+        same_path.canonical_text = "displaced".into();
+        store.upsert_blocks(&[same_path.clone()], ConflictPolicy::Replace).unwrap();
+        let fetched = store.get_blocks_by_document(&doc.id).unwrap();
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].id, same_path.id);
+        assert!(matches!(store.get_block(&original.id), Err(RtError::NotFound(_))));
+    }
+
+    #[test]
+    fn cross_tenant_reads_and_writes_are_not_found() {
+        let pool = create_memory_pool().unwrap();
+        let acme = SqliteBlockStore::with_tenant(pool.clone(), TenantContext::new("acme").unwrap());
+        let globex =
+            SqliteBlockStore::with_tenant(pool, TenantContext::new("globex").unwrap());
+
+        let doc = make_doc();
+        acme.insert_document(&doc).unwrap();
+        let block = make_block(doc.id, 0);
+        acme.insert_block(&block).unwrap();
+
+        assert!(matches!(globex.get_document(&doc.id), Err(RtError::NotFound(_))));
+        assert!(matches!(globex.get_block(&block.id), Err(RtError::NotFound(_))));
+        assert!(globex.get_blocks_by_document(&doc.id).unwrap().is_empty());
+        assert!(globex.get_blocks_page(&doc.id, 0, 10).unwrap().is_empty());
+        assert!(globex.get_blocks_by_anchor(&block.anchor_signature).unwrap().is_empty());
+        assert!(globex.list_documents(&DocumentFilter::default()).unwrap().is_empty());
+
+        // Writes into or over the other tenant's rows are refused.
+        assert!(matches!(
+            globex.insert_block(&make_block(doc.id, 1)),
+            Err(RtError::NotFound(_))
+        ));
+        assert!(matches!(
+            globex.upsert_document(&doc, ConflictPolicy::Replace),
+            Err(RtError::NotFound(_))
+        ));
+        assert!(matches!(globex.update_block(&block), Err(RtError::NotFound(_))));
+        assert!(matches!(globex.delete_block(&block.id), Err(RtError::NotFound(_))));
+
+        let fetched = acme.get_block(&block.id).unwrap();
+        assert_eq!(fetched.canonical_text, block.canonical_text);
+        assert_eq!(acme.get_document(&doc.id).unwrap().name, doc.name);
+    }
+
+    #[test]
+    fn list_documents_applies_every_filter() {
+        let store = make_store();
+        let t0 = Utc::now() - chrono::Duration::days(3);
+        let docs: Vec<Document> = [
+            ("Master Services Agreement", DocumentType::Original, json!({"matter": 7, "party": "Acme"})),
+            ("MSA redline", DocumentType::Redline, json!({"matter": 7, "party": "Globex"})),
+            ("Lease", DocumentType::Original, json!({"matter": "7"})),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, doc_type, metadata))| Document {
+            name: name.into(),
+            doc_type,
+            ingested_at: t0 + chrono::Duration::days(i as i64),
+            metadata: Some(metadata),
+            ..make_doc()
+        })
+        .collect();
+        for doc in &docs {
+            store.insert_document(doc).unwrap();
+        }
+        let names = |filter: DocumentFilter| -> Vec<String> {
+            store.list_documents(&filter).unwrap().into_iter().map(|d| d.name).collect()
+        };
+
+        assert_eq!(names(DocumentFilter::default()).len(), 3);
+        assert_eq!(
+            names(DocumentFilter { doc_type: Some(DocumentType::Original), ..Default::default() }),
+            vec!["Master Services Agreement", "Lease"]
+        );
+        assert_eq!(
+            names(DocumentFilter { name_contains: Some("msa".into()), ..Default::default() }),
+            vec!["MSA redline"]
+        );
+        assert_eq!(
+            names(DocumentFilter {
+                ingested_from: Some(docs[1].ingested_at),
+                ingested_to: Some(docs[2].ingested_at),
+                ..Default::default()
+            }),
+            vec!["MSA redline"]
+        );
+        assert_eq!(
+            names(DocumentFilter {
+                metadata_key: Some("party".into()),
+                ..Default::default()
+            }),
+            vec!["Master Services Agreement", "MSA redline"]
+        );
+        // Values match by JSON type: the number 7 is not the string "7".
+        assert_eq!(
+            names(DocumentFilter {
+                metadata_key: Some("matter".into()),
+                metadata_value: Some(json!("7")),
+                ..Default::default()
+            }),
+            vec!["Lease"]
+        );
+        assert_eq!(
+            names(DocumentFilter { offset: 1, limit: Some(1), ..Default::default() }),
+            vec!["MSA redline"]
+        );
+
+        for bad in [
+            DocumentFilter { limit: Some(0), ..Default::default() },
+            DocumentFilter { metadata_value: Some(json!(1)), ..Default::default() },
+            DocumentFilter {
+                ingested_from: Some(docs[2].ingested_at),
+                ingested_to: Some(docs[0].ingested_at),
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(store.list_documents(&bad), Err(RtError::InvalidInput(_))));
+        }
+    }
+
+    #[test]
+    fn concurrent_upserts_on_same_document_succeed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("concurrent.db");
+        let pool = create_pool(path.to_str().unwrap()).unwrap();
+        let doc = make_doc();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let store = SqliteBlockStore::new(pool.clone());
+                let doc = doc.clone();
+                std::thread::spawn(move || {
+                    store.upsert_document(&doc, ConflictPolicy::Skip)?;
+                    let blocks: Vec<Block> = (0..10).map(|i| make_block(doc.id, i)).collect();
+                    store.upsert_blocks(&blocks, ConflictPolicy::Skip)
+                })
+            })
+            .collect();
+
+        let written: usize = handles
+            .into_iter()
+            .map(|h| h.join().unwrap().expect("upsert"))
+            .sum();
+        assert_eq!(written, 10);
+
+        let store = SqliteBlockStore::new(pool);
+        assert_eq!(store.get_blocks_by_document(&doc.id).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn one_store_serves_many_threads() {
+        // A shared in-memory pool: every connection must see the same
+        // database, and readers must not trip over writers.
+        let store = make_store();
+        let docs: Vec<Document> = (0..8).map(|_| make_doc()).collect();
+
+        std::thread::scope(|scope| {
+            for doc in &docs {
+                let store = &store;
+                scope.spawn(move || {
+                    store.insert_document(doc).unwrap();
+                    let blocks: Vec<Block> = (0..5).map(|i| make_block(doc.id, i)).collect();
+                    store.insert_blocks(&blocks).unwrap();
+                    assert_eq!(store.get_blocks_by_document(&doc.id).unwrap().len(), 5);
+                    store.list_documents(&DocumentFilter::default()).unwrap();
+                });
+            }
+        });
+
+        let listed = store.list_documents(&DocumentFilter::default()).unwrap();
+        assert_eq!(listed.len(), docs.len());
+    }
+
+    #[test]
+    fn blocks_page_slices_document_order() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+        let blocks: Vec<Block> = (0..7).map(|i| make_block(doc.id, i)).collect();
+        store.insert_blocks(&blocks).unwrap();
+
+        let page = store.get_blocks_page(&doc.id, 2, 3).unwrap();
+        let positions: Vec<i32> = page.iter().map(|b| b.position_index).collect();
+        assert_eq!(positions, vec![2, 3, 4]);
+        assert_eq!(page[0].tokens.len(), 1, "tokens loaded per page");
+        assert_eq!(page[0].runs.len(), 1, "runs loaded per page");
+
+        assert_eq!(store.get_blocks_page(&doc.id, 5, 10).unwrap().len(), 2);
+        assert!(store.get_blocks_page(&doc.id, 7, 10).unwrap().is_empty());
+        assert!(store.get_blocks_page(&doc.id, 0, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn stream_blocks_yields_every_block_in_order() {
+        let store = make_store();
+        let doc = make_doc();
+        store.insert_document(&doc).unwrap();
+        let blocks: Vec<Block> = (0..7).map(|i| make_block(doc.id, i)).collect();
+        store.insert_blocks(&blocks).unwrap();
+
+        for batch_size in [1, 3, 7, 50] {
+            let streamed: Vec<Uuid> = store
+                .stream_blocks(&doc.id, batch_size)
+                .map(|b| b.unwrap().id)
+                .collect();
+            let loaded: Vec<Uuid> = store
+                .get_blocks_by_document(&doc.id)
+                .unwrap()
+                .into_iter()
+                .map(|b| b.id)
+                .collect();
+            assert_eq!(streamed, loaded, "batch_size {batch_size}");
+        }
+
+        let mut zero = store.stream_blocks(&doc.id, 0);
+        assert!(matches!(zero.next(), Some(Err(RtError::InvalidInput(_)))));
+        assert!(zero.next().is_none());
+    }
+}
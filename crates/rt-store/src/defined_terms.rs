@@ -0,0 +1,206 @@
+//! Stored defined-term dictionaries.
+//!
+//! Each document's dictionary (see `rt_compare::terms`) is kept in
+//! `defined_terms` in document order, one row per term, and deleted with the
+//! document. Recomputing a dictionary replaces it whole, and token kinds
+//! re-classified against it are written back to `tokens`.
+
+use std::collections::HashSet;
+
+use rusqlite::params;
+use uuid::Uuid;
+
+use rt_model::error::Result;
+use rt_model::{DefinedTerm, TokenKind};
+
+use crate::content::unshare_content;
+use crate::tenant::{ensure_document, TenantContext};
+
+/// Replace the dictionary of `doc_id` with `terms`; `NotFound` when `tenant`
+/// has no such document.
+pub fn replace_defined_terms(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    terms: &[DefinedTerm],
+) -> Result<()> {
+    ensure_document(conn, tenant, doc_id)?;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "DELETE FROM defined_terms WHERE document_id = ?1",
+        params![doc_id.to_string()],
+    )?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO defined_terms (document_id, seq, normalized, term, block_id)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (seq, term) in terms.iter().enumerate() {
+            stmt.execute(params![
+                doc_id.to_string(),
+                seq as i64,
+                term.normalized,
+                term.term,
+                term.block_id.to_string(),
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// The dictionary of `doc_id` in document order; empty when none was
+/// recorded. `NotFound` when `tenant` has no such document.
+pub fn get_defined_terms(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+) -> Result<Vec<DefinedTerm>> {
+    ensure_document(conn, tenant, doc_id)?;
+    let mut stmt = conn.prepare(
+        "SELECT term, normalized, block_id FROM defined_terms
+          WHERE document_id = ?1
+          ORDER BY seq",
+    )?;
+    let terms = stmt
+        .query_map(params![doc_id.to_string()], |row| {
+            let block_id: String = row.get(2)?;
+            Ok(DefinedTerm {
+                term: row.get(0)?,
+                normalized: row.get(1)?,
+                block_id: Uuid::parse_str(&block_id).map_err(|e| {
+                    let text = rusqlite::types::Type::Text;
+                    rusqlite::Error::FromSqlConversionFailure(2, text, Box::new(e))
+                })?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(terms)
+}
+
+/// Set the kind of token `seq` of block `block_id` for each update, e.g.
+/// after re-classifying a document's tokens against its dictionary. Tokens
+/// of blocks outside `doc_id` are left alone; blocks sharing their content
+/// get a copy of their own first. `NotFound` when `tenant` has no such
+/// document.
+pub fn set_token_kinds(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    updates: &[(Uuid, usize, TokenKind)],
+) -> Result<()> {
+    ensure_document(conn, tenant, doc_id)?;
+    let tx = conn.unchecked_transaction()?;
+    let blocks: HashSet<String> = updates.iter().map(|(id, _, _)| id.to_string()).collect();
+    unshare_content(&tx, &doc_id.to_string(), &blocks)?;
+    {
+        let mut stmt = tx.prepare(
+            "UPDATE tokens SET kind = ?1
+              WHERE block_id = ?2 AND seq = ?3
+                AND block_id IN (SELECT id FROM blocks WHERE document_id = ?4)",
+        )?;
+        for (block_id, seq, kind) in updates {
+            stmt.execute(params![
+                kind.as_str(),
+                block_id.to_string(),
+                *seq as i64,
+                doc_id.to_string(),
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rt_model::error::RtError;
+    use rusqlite::Connection;
+
+    fn setup() -> (Connection, Uuid, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        let block_id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO documents
+             (id, name, doc_type, schema_version, normalization_version,
+              hash_contract_version, ingested_at, metadata)
+             VALUES (?1, 'test-doc', 'CONTRACT', '1.0.0', '1.0.0', '1.0.0',
+                     '2024-01-01T00:00:00Z', '{}')",
+            params![doc_id.to_string()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO blocks
+             (id, document_id, block_type, level, structural_path, anchor_signature,
+              clause_hash, canonical_text, display_text, formatting_meta, position_index)
+             VALUES (?1, ?2, 'clause', 0, '1.', '', '', 'The Lender', 'The Lender', '{}', 0)",
+            params![block_id.to_string(), doc_id.to_string()],
+        )
+        .unwrap();
+        for (seq, text) in ["The", "Lender"].iter().enumerate() {
+            conn.execute(
+                "INSERT INTO tokens (id, block_id, seq, text, kind, normalized, offset)
+                 VALUES (?1, ?2, ?3, ?4, 'defined_term', lower(?4), 0)",
+                params![Uuid::new_v4().to_string(), block_id.to_string(), seq as i64, text],
+            )
+            .unwrap();
+        }
+        (conn, doc_id, block_id)
+    }
+
+    fn term(name: &str, block_id: Uuid) -> DefinedTerm {
+        DefinedTerm {
+            term: name.into(),
+            normalized: name.to_lowercase(),
+            block_id,
+        }
+    }
+
+    #[test]
+    fn dictionaries_are_replaced_whole_and_kept_in_order() {
+        let (conn, doc, block) = setup();
+        let tenant = TenantContext::default();
+        assert!(get_defined_terms(&conn, &tenant, &doc).unwrap().is_empty());
+
+        let terms = vec![term("Lender", block), term("Borrower", block)];
+        replace_defined_terms(&conn, &tenant, &doc, &terms).unwrap();
+        assert_eq!(get_defined_terms(&conn, &tenant, &doc).unwrap(), terms);
+
+        replace_defined_terms(&conn, &tenant, &doc, &terms[1..]).unwrap();
+        assert_eq!(get_defined_terms(&conn, &tenant, &doc).unwrap(), &terms[1..]);
+    }
+
+    #[test]
+    fn token_kinds_are_updated_within_the_document() {
+        let (conn, doc, block) = setup();
+        let tenant = TenantContext::default();
+        set_token_kinds(&conn, &tenant, &doc, &[(block, 0, TokenKind::Word)]).unwrap();
+        let kinds: Vec<String> = conn
+            .prepare("SELECT kind FROM tokens WHERE block_id = ?1 ORDER BY seq")
+            .unwrap()
+            .query_map(params![block.to_string()], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(kinds, ["word", "defined_term"]);
+    }
+
+    #[test]
+    fn documents_of_other_tenants_are_not_found() {
+        let (conn, doc, block) = setup();
+        let other = TenantContext::new("other").unwrap();
+        assert!(matches!(get_defined_terms(&conn, &other, &doc), Err(RtError::NotFound(_))));
+        let err = replace_defined_terms(&conn, &other, &doc, &[term("Lender", block)]).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+        let err = set_token_kinds(&conn, &other, &doc, &[]).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+    }
+}
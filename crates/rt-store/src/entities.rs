@@ -0,0 +1,184 @@
+//! Stored party and date entities.
+//!
+//! Each document's entities (see `rt_compare::entities`) are kept in
+//! `document_parties` and `document_dates` in document order and deleted
+//! with the document. Recomputing a document's entities replaces them whole.
+
+use rusqlite::params;
+use uuid::Uuid;
+
+use rt_model::error::Result;
+use rt_model::{DateMention, DocumentEntities, Party};
+
+use crate::tenant::{ensure_document, TenantContext};
+
+/// Replace the entities of `doc_id` with `entities`; `NotFound` when
+/// `tenant` has no such document.
+pub fn replace_document_entities(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    entities: &DocumentEntities,
+) -> Result<()> {
+    ensure_document(conn, tenant, doc_id)?;
+    let tx = conn.unchecked_transaction()?;
+    for table in ["document_parties", "document_dates"] {
+        tx.execute(
+            &format!("DELETE FROM {table} WHERE document_id = ?1"),
+            params![doc_id.to_string()],
+        )?;
+    }
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO document_parties (document_id, seq, normalized, role, name, block_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (seq, party) in entities.parties.iter().enumerate() {
+            stmt.execute(params![
+                doc_id.to_string(),
+                seq as i64,
+                party.normalized,
+                party.role,
+                party.name,
+                party.block_id.to_string(),
+            ])?;
+        }
+        let mut stmt = tx.prepare(
+            "INSERT INTO document_dates (document_id, seq, text, value, block_id, offset)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (seq, date) in entities.dates.iter().enumerate() {
+            stmt.execute(params![
+                doc_id.to_string(),
+                seq as i64,
+                date.text,
+                date.value,
+                date.block_id.to_string(),
+                date.offset as i64,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// The entities of `doc_id` in document order; empty when none were
+/// recorded. `NotFound` when `tenant` has no such document.
+pub fn get_document_entities(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+) -> Result<DocumentEntities> {
+    ensure_document(conn, tenant, doc_id)?;
+    let mut stmt = conn.prepare(
+        "SELECT role, normalized, name, block_id FROM document_parties
+          WHERE document_id = ?1
+          ORDER BY seq",
+    )?;
+    let parties = stmt
+        .query_map(params![doc_id.to_string()], |row| {
+            Ok(Party {
+                role: row.get(0)?,
+                normalized: row.get(1)?,
+                name: row.get(2)?,
+                block_id: uuid_column(row, 3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut stmt = conn.prepare(
+        "SELECT text, value, block_id, offset FROM document_dates
+          WHERE document_id = ?1
+          ORDER BY seq",
+    )?;
+    let dates = stmt
+        .query_map(params![doc_id.to_string()], |row| {
+            Ok(DateMention {
+                text: row.get(0)?,
+                value: row.get(1)?,
+                block_id: uuid_column(row, 2)?,
+                offset: row.get::<_, i64>(3)? as usize,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(DocumentEntities { parties, dates })
+}
+
+fn uuid_column(row: &rusqlite::Row<'_>, idx: usize) -> rusqlite::Result<Uuid> {
+    let id: String = row.get(idx)?;
+    Uuid::parse_str(&id).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rt_model::error::RtError;
+    use rusqlite::Connection;
+
+    fn setup() -> (Connection, Uuid, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        let block_id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO documents
+             (id, name, doc_type, schema_version, normalization_version,
+              hash_contract_version, ingested_at, metadata)
+             VALUES (?1, 'test-doc', 'CONTRACT', '1.0.0', '1.0.0', '1.0.0',
+                     '2024-01-01T00:00:00Z', '{}')",
+            params![doc_id.to_string()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO blocks
+             (id, document_id, block_type, level, structural_path, anchor_signature,
+              clause_hash, canonical_text, display_text, formatting_meta, position_index)
+             VALUES (?1, ?2, 'clause', 0, 'p0', '', '', 'x', 'x', '{}', 0)",
+            params![block_id.to_string(), doc_id.to_string()],
+        )
+        .unwrap();
+        (conn, doc_id, block_id)
+    }
+
+    #[test]
+    fn entities_are_replaced_whole_and_kept_in_order() {
+        let (conn, doc, block) = setup();
+        let tenant = TenantContext::default();
+        let empty = get_document_entities(&conn, &tenant, &doc).unwrap();
+        assert_eq!(empty, DocumentEntities::default());
+
+        let party = |role: &str, name: Option<&str>| Party {
+            role: role.into(),
+            normalized: role.to_lowercase(),
+            name: name.map(String::from),
+            block_id: block,
+        };
+        let entities = DocumentEntities {
+            parties: vec![party("Lender", Some("ACME Corp.")), party("Borrower", None)],
+            dates: vec![DateMention {
+                text: "January 1, 2025".into(),
+                value: "2025-01-01".into(),
+                block_id: block,
+                offset: 12,
+            }],
+        };
+        replace_document_entities(&conn, &tenant, &doc, &entities).unwrap();
+        assert_eq!(get_document_entities(&conn, &tenant, &doc).unwrap(), entities);
+
+        let fewer = DocumentEntities { parties: entities.parties[1..].to_vec(), dates: vec![] };
+        replace_document_entities(&conn, &tenant, &doc, &fewer).unwrap();
+        assert_eq!(get_document_entities(&conn, &tenant, &doc).unwrap(), fewer);
+
+        let other = TenantContext::new("other").unwrap();
+        assert!(matches!(
+            get_document_entities(&conn, &other, &doc),
+            Err(RtError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,376 @@
+//! Near-duplicate document detection.
+//!
+//! Each document gets a MinHash fingerprint over the word 3-shingles of its
+//! blocks' canonical text. The fraction of matching MinHash slots estimates
+//! the Jaccard similarity of two documents' shingle sets, so the same
+//! contract ingested twice under different names scores ≈1.0 even when a
+//! few words differ, while unrelated documents score near 0.
+//!
+//! Shingles never span blocks, which makes the fingerprint independent of
+//! block order and of how positions are numbered.
+
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+
+use crate::tenant::{ensure_document, TenantContext};
+
+/// Similarity at or above which two documents are reported as near
+/// duplicates.
+pub const NEAR_DUPLICATE_THRESHOLD: f64 = 0.95;
+
+/// Number of MinHash slots per fingerprint.
+pub const MINHASH_SIZE: usize = 128;
+
+/// Words per shingle.
+const SHINGLE_WORDS: usize = 3;
+
+// ---------------------------------------------------------------------------
+// DocumentFingerprint
+// ---------------------------------------------------------------------------
+
+/// MinHash signature of one document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentFingerprint {
+    /// `MINHASH_SIZE` minimum hash values, one per hash function.
+    pub minhash: Vec<u64>,
+    /// Number of shingle occurrences hashed; `0` for a document without text.
+    pub shingle_count: usize,
+}
+
+impl DocumentFingerprint {
+    /// Fingerprint the canonical texts of a document's blocks.
+    pub fn from_texts<'a>(texts: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut minhash = vec![u64::MAX; MINHASH_SIZE];
+        let mut shingle_count = 0;
+
+        for text in texts {
+            let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+            if words.is_empty() {
+                continue;
+            }
+            let width = SHINGLE_WORDS.min(words.len());
+            for shingle in words.windows(width) {
+                let base = fnv1a(&shingle.join(" "));
+                for (slot, seed) in minhash.iter_mut().zip(seeds()) {
+                    *slot = (*slot).min(splitmix64(base ^ seed));
+                }
+                shingle_count += 1;
+            }
+        }
+
+        Self {
+            minhash,
+            shingle_count,
+        }
+    }
+
+    /// Estimated Jaccard similarity in `[0, 1]`. A document without text is
+    /// similar to nothing.
+    pub fn similarity(&self, other: &DocumentFingerprint) -> f64 {
+        if self.shingle_count == 0 || other.shingle_count == 0 {
+            return 0.0;
+        }
+        let equal = self
+            .minhash
+            .iter()
+            .zip(&other.minhash)
+            .filter(|(a, b)| a == b)
+            .count();
+        equal as f64 / MINHASH_SIZE as f64
+    }
+
+    fn to_hex(&self) -> String {
+        self.minhash.iter().map(|v| format!("{v:016x}")).collect()
+    }
+
+    fn from_hex(hex: &str, shingle_count: usize) -> Result<Self> {
+        if hex.len() != MINHASH_SIZE * 16 {
+            return Err(RtError::InvalidInput(format!(
+                "fingerprint has {} hex digits, expected {}",
+                hex.len(),
+                MINHASH_SIZE * 16
+            )));
+        }
+        let minhash = (0..MINHASH_SIZE)
+            .map(|i| {
+                u64::from_str_radix(&hex[i * 16..(i + 1) * 16], 16)
+                    .map_err(|e| RtError::InvalidInput(format!("invalid fingerprint: {e}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            minhash,
+            shingle_count,
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// NearDuplicate
+// ---------------------------------------------------------------------------
+
+/// A stored document found to be near-identical to the queried one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NearDuplicate {
+    pub document_id: Uuid,
+    pub name: String,
+    /// Estimated similarity in `[threshold, 1]`.
+    pub similarity: f64,
+}
+
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+/// Compute the fingerprint of `doc_id` from its stored blocks and save it,
+/// replacing any previous one.
+pub fn record_fingerprint(conn: &rusqlite::Connection, doc_id: &Uuid) -> Result<DocumentFingerprint> {
+    let mut stmt = conn.prepare("SELECT canonical_text FROM blocks WHERE document_id = ?1")?;
+    let texts = stmt
+        .query_map(params![doc_id.to_string()], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let fingerprint = DocumentFingerprint::from_texts(texts.iter().map(String::as_str));
+
+    conn.execute(
+        "INSERT OR REPLACE INTO document_fingerprints
+             (document_id, minhash, shingle_count, computed_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            doc_id.to_string(),
+            fingerprint.to_hex(),
+            fingerprint.shingle_count as i64,
+            Utc::now().to_rfc3339()
+        ],
+    )?;
+    Ok(fingerprint)
+}
+
+/// The stored fingerprint of `doc_id`, if one has been recorded.
+pub fn get_fingerprint(
+    conn: &rusqlite::Connection,
+    doc_id: &Uuid,
+) -> Result<Option<DocumentFingerprint>> {
+    conn.query_row(
+        "SELECT minhash, shingle_count FROM document_fingerprints WHERE document_id = ?1",
+        params![doc_id.to_string()],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+    )
+    .optional()?
+    .map(|(hex, count)| DocumentFingerprint::from_hex(&hex, count as usize))
+    .transpose()
+}
+
+/// Every other document of `tenant` whose similarity to `doc_id` is at least
+/// `threshold`, most similar first.
+///
+/// Documents without a stored fingerprint (including `doc_id` itself, e.g.
+/// when ingested before fingerprinting existed) are fingerprinted first.
+pub fn find_near_duplicates(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    threshold: f64,
+) -> Result<Vec<NearDuplicate>> {
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(RtError::InvalidInput(format!(
+            "similarity threshold must be in (0, 1], got {threshold}"
+        )));
+    }
+
+    ensure_document(conn, tenant, doc_id)?;
+
+    let missing = {
+        let mut stmt = conn.prepare(
+            "SELECT d.id FROM documents d
+              LEFT JOIN document_fingerprints f ON f.document_id = d.id
+              WHERE f.document_id IS NULL AND d.tenant_id = ?1",
+        )?;
+        let ids = stmt
+            .query_map(params![tenant.id()], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        ids
+    };
+    for id in missing {
+        let id = Uuid::parse_str(&id).map_err(|e| RtError::InvalidInput(e.to_string()))?;
+        record_fingerprint(conn, &id)?;
+    }
+
+    let target = get_fingerprint(conn, doc_id)?
+        .ok_or_else(|| RtError::Internal(format!("fingerprint for {doc_id} not recorded")))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT d.id, d.name, f.minhash, f.shingle_count
+           FROM document_fingerprints f
+           JOIN documents d ON d.id = f.document_id
+          WHERE d.id <> ?1 AND d.tenant_id = ?2",
+    )?;
+    let rows = stmt.query_map(params![doc_id.to_string(), tenant.id()], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+
+    let mut duplicates = Vec::new();
+    for row in rows {
+        let (id, name, hex, count) = row?;
+        let similarity = target.similarity(&DocumentFingerprint::from_hex(&hex, count as usize)?);
+        if similarity >= threshold {
+            duplicates.push(NearDuplicate {
+                document_id: Uuid::parse_str(&id)
+                    .map_err(|e| RtError::InvalidInput(e.to_string()))?,
+                name,
+                similarity,
+            });
+        }
+    }
+    duplicates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    Ok(duplicates)
+}
+
+// ---------------------------------------------------------------------------
+// Hashing helpers
+// ---------------------------------------------------------------------------
+
+/// 64-bit FNV-1a; stable across platforms and Rust versions, unlike
+/// `DefaultHasher`, so stored fingerprints stay comparable.
+fn fnv1a(s: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in s.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// One seed per MinHash slot, deriving `MINHASH_SIZE` hash functions from
+/// a single shingle hash.
+fn seeds() -> impl Iterator<Item = u64> {
+    (0..MINHASH_SIZE as u64).map(splitmix64)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rusqlite::Connection;
+
+    const CONTRACT: &[&str] = &[
+        "The Borrower shall repay the Loan in full on the Termination Date.",
+        "Interest accrues on the outstanding principal at the Applicable Rate, calculated daily on a 365 day basis.",
+        "Each Party shall keep the terms of this Agreement confidential and shall not disclose them to any third party without consent.",
+        "This Agreement is governed by the laws of England and the courts of London have exclusive jurisdiction over any dispute.",
+        "Any notice under this Agreement must be given in writing and delivered by hand, courier or email to the address of the recipient.",
+    ];
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        conn
+    }
+
+    fn insert_doc(conn: &Connection, name: &str, texts: &[&str]) -> Uuid {
+        let id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO documents
+             (id, name, doc_type, schema_version, normalization_version,
+              hash_contract_version, ingested_at, metadata)
+             VALUES (?1, ?2, 'original', '1.0.0', '1.0.0', '1.0.0',
+                     '2024-01-01T00:00:00Z', '{}')",
+            params![id.to_string(), name],
+        )
+        .unwrap();
+        for (i, text) in texts.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO blocks
+                 (id, document_id, block_type, level, structural_path, anchor_signature,
+                  clause_hash, canonical_text, display_text, formatting_meta, position_index)
+                 VALUES (?1, ?2, 'clause', 0, ?3, '', '', ?4, ?4, '{}', ?5)",
+                params![Uuid::new_v4().to_string(), id.to_string(), format!("{}.", i + 1), text, i as i64],
+            )
+            .unwrap();
+        }
+        id
+    }
+
+    #[test]
+    fn identical_text_is_fully_similar() {
+        let a = DocumentFingerprint::from_texts(CONTRACT.iter().copied());
+        let b = DocumentFingerprint::from_texts(CONTRACT.iter().rev().copied());
+        assert_eq!(a.similarity(&b), 1.0, "block order does not matter");
+    }
+
+    #[test]
+    fn unrelated_text_is_dissimilar() {
+        let a = DocumentFingerprint::from_texts(CONTRACT.iter().copied());
+        let b = DocumentFingerprint::from_texts([
+            "Lorem ipsum dolor sit amet consectetur adipiscing elit sed do eiusmod tempor.",
+        ]);
+        assert!(a.similarity(&b) < 0.2);
+    }
+
+    #[test]
+    fn empty_document_matches_nothing() {
+        let empty = DocumentFingerprint::from_texts(std::iter::empty());
+        assert_eq!(empty.shingle_count, 0);
+        assert_eq!(empty.similarity(&empty), 0.0);
+    }
+
+    #[test]
+    fn fingerprint_round_trips_through_store() {
+        let conn = setup();
+        let id = insert_doc(&conn, "a", CONTRACT);
+        let recorded = record_fingerprint(&conn, &id).unwrap();
+        assert_eq!(get_fingerprint(&conn, &id).unwrap(), Some(recorded));
+    }
+
+    #[test]
+    fn near_duplicates_are_found_above_threshold() {
+        let conn = setup();
+        let original = insert_doc(&conn, "facility", CONTRACT);
+        let copy = insert_doc(&conn, "facility (final)", CONTRACT);
+        let _other = insert_doc(&conn, "nda", &["Lorem ipsum dolor sit amet consectetur."]);
+        let foreign = insert_doc(&conn, "facility (acme)", CONTRACT);
+        conn.execute(
+            "UPDATE documents SET tenant_id = 'acme' WHERE id = ?1",
+            params![foreign.to_string()],
+        )
+        .unwrap();
+
+        let tenant = TenantContext::default();
+        let found = find_near_duplicates(&conn, &tenant, &copy, NEAR_DUPLICATE_THRESHOLD).unwrap();
+        assert_eq!(found.len(), 1, "other tenants' documents are not candidates");
+        assert_eq!(found[0].document_id, original);
+        assert_eq!(found[0].name, "facility");
+        assert_eq!(found[0].similarity, 1.0);
+    }
+
+    #[test]
+    fn invalid_threshold_and_unknown_document_are_rejected() {
+        let conn = setup();
+        let id = insert_doc(&conn, "a", CONTRACT);
+        let tenant = TenantContext::default();
+        assert!(find_near_duplicates(&conn, &tenant, &id, 0.0).is_err());
+        assert!(find_near_duplicates(&conn, &tenant, &id, 1.5).is_err());
+        assert!(matches!(
+            find_near_duplicates(&conn, &tenant, &Uuid::new_v4(), 0.95),
+            Err(RtError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,431 @@
+//! Garbage collection of compare and merge data.
+//!
+//! Run history deliberately outlives documents (see [`crate::run_history`]),
+//! and merges whose documents were deleted with foreign keys off linger too.
+//! [`collect_garbage`] removes, for one tenant:
+//!
+//! - recorded runs that read a document which no longer exists, with their
+//!   stored deltas;
+//! - merges whose base, incoming or ancestor document no longer exists, with
+//!   their conflicts;
+//! - optionally, runs and artifact records older than a retention window.
+//!   A run whose merge is still stored is kept: its manifest belongs to it.
+//!
+//! A dry run reports what would be removed without removing it. Artifact
+//! files live outside the database and are not touched: the report lists the
+//! paths of the records removed so the host can delete the files.
+//!
+//! Hosts schedule collection themselves (on start-up, on a timer); with
+//! [`GcOptions::interval_hours`] a pass that comes too soon after the last
+//! one is skipped, so it is safe to trigger often.
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+
+use crate::tenant::TenantContext;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// What a collection pass removes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GcOptions {
+    /// Report what would be removed without removing anything.
+    pub dry_run: bool,
+    /// Also remove runs recorded more than this many days ago.
+    pub run_retention_days: Option<u32>,
+    /// Also remove artifact records created more than this many days ago.
+    pub artifact_retention_days: Option<u32>,
+    /// Skip the pass when the last (non-dry) pass for the tenant finished
+    /// less than this many hours ago.
+    pub interval_hours: Option<u32>,
+}
+
+impl GcOptions {
+    /// Check that every window given is at least one day or hour.
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in [
+            ("run_retention_days", self.run_retention_days),
+            ("artifact_retention_days", self.artifact_retention_days),
+            ("interval_hours", self.interval_hours),
+        ] {
+            if value == Some(0) {
+                return Err(RtError::InvalidInput(format!("{name} must be at least 1")));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An artifact record removed (or, in a dry run, due for removal).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcArtifact {
+    pub id: Uuid,
+    pub workflow_id: Uuid,
+    /// Where the artifact's file was written; the file itself is left alone.
+    pub file_path: String,
+}
+
+/// The outcome of a collection pass.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    /// The pass was skipped because the last one was too recent; every list
+    /// is then empty.
+    pub skipped: bool,
+    /// When the pass ran (or was skipped).
+    pub ran_at: DateTime<Utc>,
+    /// Earliest time the next pass is due, with
+    /// [`GcOptions::interval_hours`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_due_at: Option<DateTime<Utc>>,
+    /// Runs that read a document which no longer exists.
+    pub orphaned_runs: Vec<Uuid>,
+    /// Merges whose base, incoming or ancestor document no longer exists.
+    pub orphaned_merges: Vec<Uuid>,
+    /// Runs older than the run retention window.
+    pub expired_runs: Vec<Uuid>,
+    /// Artifact records older than the artifact retention window.
+    pub expired_artifacts: Vec<GcArtifact>,
+    /// Stored compare deltas of the removed runs.
+    pub deltas: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Collection
+// ---------------------------------------------------------------------------
+
+/// When `tenant`'s last non-dry collection pass ran, if ever.
+pub fn last_gc(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+) -> Result<Option<DateTime<Utc>>> {
+    let at: Option<String> = conn
+        .query_row(
+            "SELECT last_run_at FROM gc_runs WHERE tenant_id = ?1",
+            params![tenant.id()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    at.as_deref().map(parse_timestamp).transpose()
+}
+
+/// Run a collection pass for `tenant` at `now` with `options`.
+pub fn collect_garbage(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    options: &GcOptions,
+    now: DateTime<Utc>,
+) -> Result<GcReport> {
+    options.validate()?;
+    let interval = options.interval_hours.map(|h| Duration::hours(h.into()));
+    let last = last_gc(conn, tenant)?;
+    let mut report = GcReport {
+        dry_run: options.dry_run,
+        skipped: false,
+        ran_at: now,
+        next_due_at: None,
+        orphaned_runs: vec![],
+        orphaned_merges: vec![],
+        expired_runs: vec![],
+        expired_artifacts: vec![],
+        deltas: 0,
+    };
+    if let (Some(interval), Some(last)) = (interval, last) {
+        if now < last + interval {
+            report.skipped = true;
+            report.next_due_at = Some(last + interval);
+            return Ok(report);
+        }
+    }
+
+    report.orphaned_runs = query_ids(
+        conn,
+        "SELECT DISTINCT r.run_id FROM run_manifests r
+           JOIN run_manifest_inputs i ON i.run_id = r.run_id
+          WHERE r.tenant_id = ?1
+            AND NOT EXISTS (SELECT 1 FROM documents d WHERE d.id = i.document_id)
+          ORDER BY r.run_id",
+        tenant,
+    )?;
+    report.orphaned_merges = query_ids(
+        conn,
+        "SELECT m.id FROM merges m
+          WHERE m.tenant_id = ?1
+            AND (NOT EXISTS (SELECT 1 FROM documents d WHERE d.id = m.base_doc_id)
+              OR NOT EXISTS (SELECT 1 FROM documents d WHERE d.id = m.incoming_doc_id)
+              OR (m.ancestor_doc_id IS NOT NULL
+                  AND NOT EXISTS (SELECT 1 FROM documents d WHERE d.id = m.ancestor_doc_id)))
+          ORDER BY m.id",
+        tenant,
+    )?;
+    if let Some(days) = options.run_retention_days {
+        let cutoff = now - Duration::days(days.into());
+        report.expired_runs = expired_runs(conn, tenant, cutoff)?
+            .into_iter()
+            .filter(|id| !report.orphaned_runs.contains(id))
+            .collect();
+    }
+    if let Some(days) = options.artifact_retention_days {
+        report.expired_artifacts =
+            expired_artifacts(conn, tenant, now - Duration::days(days.into()))?;
+    }
+
+    let runs: Vec<&Uuid> = report.orphaned_runs.iter().chain(&report.expired_runs).collect();
+    for run_id in &runs {
+        report.deltas += conn.query_row(
+            "SELECT COUNT(*) FROM compare_deltas WHERE run_id = ?1",
+            params![run_id.to_string()],
+            |row| row.get::<_, i64>(0),
+        )? as u64;
+    }
+    if options.dry_run {
+        return Ok(report);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    for run_id in &runs {
+        tx.execute(
+            "DELETE FROM run_manifests WHERE run_id = ?1 AND tenant_id = ?2",
+            params![run_id.to_string(), tenant.id()],
+        )?;
+    }
+    for merge_id in &report.orphaned_merges {
+        tx.execute(
+            "DELETE FROM merges WHERE id = ?1 AND tenant_id = ?2",
+            params![merge_id.to_string(), tenant.id()],
+        )?;
+    }
+    for artifact in &report.expired_artifacts {
+        tx.execute("DELETE FROM artifacts WHERE id = ?1", params![artifact.id.to_string()])?;
+    }
+    tx.execute(
+        "INSERT INTO gc_runs (tenant_id, last_run_at) VALUES (?1, ?2)
+         ON CONFLICT (tenant_id) DO UPDATE SET last_run_at = excluded.last_run_at",
+        params![tenant.id(), now.to_rfc3339()],
+    )?;
+    tx.commit()?;
+    if let Some(interval) = interval {
+        report.next_due_at = Some(now + interval);
+    }
+    Ok(report)
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+fn query_ids(conn: &rusqlite::Connection, sql: &str, tenant: &TenantContext) -> Result<Vec<Uuid>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map(params![tenant.id()], |row| row.get::<_, String>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    rows.iter().map(|id| parse_uuid(id)).collect()
+}
+
+/// Runs of `tenant` recorded before `cutoff`, except those whose merge is
+/// still stored.
+fn expired_runs(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    cutoff: DateTime<Utc>,
+) -> Result<Vec<Uuid>> {
+    let mut stmt = conn.prepare(
+        "SELECT r.run_id, r.recorded_at FROM run_manifests r
+          WHERE r.tenant_id = ?1
+            AND NOT EXISTS (SELECT 1 FROM merges m WHERE m.id = r.run_id)
+          ORDER BY r.run_id",
+    )?;
+    let rows = stmt
+        .query_map(params![tenant.id()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut expired = Vec::new();
+    for (id, recorded_at) in rows {
+        if parse_timestamp(&recorded_at)? < cutoff {
+            expired.push(parse_uuid(&id)?);
+        }
+    }
+    Ok(expired)
+}
+
+/// Artifacts of `tenant`'s workflows created before `cutoff`, oldest first.
+fn expired_artifacts(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    cutoff: DateTime<Utc>,
+) -> Result<Vec<GcArtifact>> {
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.workflow_id, a.file_path, a.created_at FROM artifacts a
+           JOIN workflows w ON w.id = a.workflow_id
+          WHERE w.tenant_id = ?1
+          ORDER BY a.created_at, a.id",
+    )?;
+    let rows = stmt
+        .query_map(params![tenant.id()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut expired = Vec::new();
+    for (id, workflow_id, file_path, created_at) in rows {
+        if parse_timestamp(&created_at)? < cutoff {
+            expired.push(GcArtifact {
+                id: parse_uuid(&id)?,
+                workflow_id: parse_uuid(&workflow_id)?,
+                file_path,
+            });
+        }
+    }
+    Ok(expired)
+}
+
+fn parse_uuid(id: &str) -> Result<Uuid> {
+    Uuid::parse_str(id).map_err(|e| RtError::Internal(format!("invalid stored id {id}: {e}")))
+}
+
+fn parse_timestamp(at: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(at)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| RtError::Internal(format!("invalid stored timestamp {at}: {e}")))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare_sections::{append_compare_deltas, SectionDelta};
+    use crate::run_history::{find_run, record_run, RunKind};
+    use crate::schema::run_migrations;
+    use rt_model::manifest::{InputDigest, RunManifest};
+    use rusqlite::Connection;
+
+    fn insert_document(conn: &Connection) -> Uuid {
+        let id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO documents
+             (id, name, doc_type, schema_version, normalization_version,
+              hash_contract_version, ingested_at, metadata)
+             VALUES (?1, 'doc', 'CONTRACT', '1.0.0', '1.0.0', '1.0.0',
+                     '2024-01-01T00:00:00Z', '{}')",
+            params![id.to_string()],
+        )
+        .unwrap();
+        id
+    }
+
+    fn record(conn: &Connection, docs: &[Uuid]) -> Uuid {
+        let run_id = Uuid::new_v4();
+        let inputs = docs.iter().map(|d| InputDigest::from_blocks(*d, &[])).collect();
+        let manifest = RunManifest::new([("rt-compare", "0.1.0")], "cfg".into(), inputs);
+        record_run(conn, &TenantContext::default(), &run_id, RunKind::Compare, &manifest).unwrap();
+        run_id
+    }
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        conn
+    }
+
+    #[test]
+    fn orphaned_runs_are_reported_then_removed() {
+        let conn = setup();
+        let tenant = TenantContext::default();
+        let (kept, gone) = (insert_document(&conn), insert_document(&conn));
+        let live = record(&conn, &[kept, kept]);
+        let orphan = record(&conn, &[kept, gone]);
+        let delta = SectionDelta {
+            section: "1.".into(),
+            kind: "modified".into(),
+            delta: serde_json::json!({}),
+        };
+        append_compare_deltas(&conn, &tenant, &orphan, &[delta.clone(), delta]).unwrap();
+        conn.execute("DELETE FROM documents WHERE id = ?1", params![gone.to_string()]).unwrap();
+
+        let now = Utc::now();
+        let dry = GcOptions { dry_run: true, ..Default::default() };
+        let report = collect_garbage(&conn, &tenant, &dry, now).unwrap();
+        assert_eq!(report.orphaned_runs, [orphan]);
+        assert_eq!(report.deltas, 2);
+        assert!(find_run(&conn, &tenant, &orphan).unwrap().is_some());
+        assert_eq!(last_gc(&conn, &tenant).unwrap(), None);
+
+        let other = TenantContext::new("other").unwrap();
+        let report = collect_garbage(&conn, &other, &GcOptions::default(), now).unwrap();
+        assert!(report.orphaned_runs.is_empty());
+
+        let report = collect_garbage(&conn, &tenant, &GcOptions::default(), now).unwrap();
+        assert_eq!(report.orphaned_runs, [orphan]);
+        assert!(find_run(&conn, &tenant, &orphan).unwrap().is_none());
+        assert!(find_run(&conn, &tenant, &live).unwrap().is_some());
+        let deltas: i64 =
+            conn.query_row("SELECT COUNT(*) FROM compare_deltas", [], |row| row.get(0)).unwrap();
+        assert_eq!(deltas, 0);
+    }
+
+    #[test]
+    fn retention_windows_and_intervals_apply() {
+        let conn = setup();
+        let tenant = TenantContext::default();
+        let doc = insert_document(&conn);
+        let run = record(&conn, &[doc, doc]);
+        let workflow_id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO workflows (id, document_id, state, created_at, updated_at)
+             VALUES (?1, ?2, 'draft', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z')",
+            params![workflow_id.to_string(), doc.to_string()],
+        )
+        .unwrap();
+        let artifact_id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO artifacts (id, workflow_id, artifact_type, file_path, content_hash,
+                                    created_at)
+             VALUES (?1, ?2, 'reviewer_redline_html', '/out/a.html', 'h',
+                     '2024-01-01T00:00:00Z')",
+            params![artifact_id.to_string(), workflow_id.to_string()],
+        )
+        .unwrap();
+
+        let options = GcOptions {
+            run_retention_days: Some(30),
+            artifact_retention_days: Some(30),
+            interval_hours: Some(24),
+            ..Default::default()
+        };
+        let now = Utc::now();
+        let report = collect_garbage(&conn, &tenant, &options, now).unwrap();
+        assert!(report.expired_runs.is_empty());
+        assert_eq!(report.expired_artifacts.len(), 1);
+        assert_eq!(report.expired_artifacts[0].file_path, "/out/a.html");
+        assert_eq!(report.next_due_at, Some(now + Duration::hours(24)));
+
+        let soon = collect_garbage(&conn, &tenant, &options, now + Duration::hours(1)).unwrap();
+        assert!(soon.skipped);
+
+        let later = now + Duration::days(31);
+        let report = collect_garbage(&conn, &tenant, &options, later).unwrap();
+        assert_eq!(report.expired_runs, [run]);
+        assert!(report.expired_artifacts.is_empty());
+
+        let invalid = GcOptions { run_retention_days: Some(0), ..Default::default() };
+        assert!(matches!(
+            collect_garbage(&conn, &tenant, &invalid, now),
+            Err(RtError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,420 @@
+//! Per-database hash contract configuration.
+//!
+//! A database records which hash contract its documents use and, for keyed
+//! contracts, a fingerprint of the key (never the key itself). Hosts supply
+//! the key at startup; [`configure_hasher`] checks it against the stored
+//! fingerprint so a wrong or missing key is caught before any hashes are
+//! written.
+//!
+//! Hashes of different contracts never match, so documents hashed under
+//! different contracts cannot be compared or merged ([`check_contracts`]);
+//! [`rehash_document`] moves a stored document onto the current contract.
+//! Documents anchored by different strategies can be compared once one side
+//! is re-anchored ([`align_anchors`]).
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::anchor::anchor_strategy;
+use rt_model::block::{Block, BlockType, Document};
+use rt_model::error::{Result, RtError};
+use rt_model::hash::{key_fingerprint, ClauseHasher};
+
+use crate::tenant::{ensure_document, TenantContext};
+
+/// The row stored in the single-row `hash_config` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashConfig {
+    /// `hash_contract_version` every document in the database is hashed with.
+    pub contract_version: String,
+    /// [`key_fingerprint`] of the configured key, for keyed contracts.
+    pub key_fingerprint: Option<String>,
+    pub configured_at: DateTime<Utc>,
+}
+
+/// The database's recorded hash configuration, if one has been set.
+pub fn get_hash_config(conn: &rusqlite::Connection) -> Result<Option<HashConfig>> {
+    let row = conn
+        .query_row(
+            "SELECT contract_version, key_fingerprint, configured_at
+               FROM hash_config
+              WHERE id = 1",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    row.map(|(contract_version, key_fingerprint, configured_at)| {
+        Ok(HashConfig {
+            contract_version,
+            key_fingerprint,
+            configured_at: DateTime::parse_from_rfc3339(&configured_at)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| RtError::InvalidInput(e.to_string()))?,
+        })
+    })
+    .transpose()
+}
+
+/// Build the hasher for `contract_version` / `key` and bind it to the
+/// database.
+///
+/// On first use the configuration is recorded, provided every stored
+/// document already uses `contract_version` (documents hashed under another
+/// contract could never be compared with new ones). Afterwards the arguments
+/// must match the recorded contract and key fingerprint.
+pub fn configure_hasher(
+    conn: &rusqlite::Connection,
+    contract_version: &str,
+    key: Option<&[u8]>,
+) -> Result<ClauseHasher> {
+    let hasher = ClauseHasher::for_contract(contract_version, key)?;
+    let fingerprint = key.map(key_fingerprint);
+
+    if let Some(existing) = get_hash_config(conn)? {
+        if existing.contract_version != contract_version {
+            return Err(RtError::InvalidInput(format!(
+                "database is configured for hash contract {}, not {contract_version}",
+                existing.contract_version
+            )));
+        }
+        if existing.key_fingerprint != fingerprint {
+            return Err(RtError::InvalidInput(
+                "hash key does not match the key this database was configured with".into(),
+            ));
+        }
+        return Ok(hasher);
+    }
+
+    let mismatched: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM documents WHERE hash_contract_version <> ?1",
+        params![contract_version],
+        |row| row.get(0),
+    )?;
+    if mismatched > 0 {
+        return Err(RtError::InvalidInput(format!(
+            "{mismatched} stored document(s) use a hash contract other than {contract_version}"
+        )));
+    }
+
+    conn.execute(
+        "INSERT INTO hash_config (id, contract_version, key_fingerprint, configured_at)
+         VALUES (1, ?1, ?2, ?3)",
+        params![contract_version, fingerprint, Utc::now().to_rfc3339()],
+    )?;
+    Ok(hasher)
+}
+
+/// The hasher to use without host-supplied key material: the default
+/// SHA-256 hasher when the database is unconfigured or configured for an
+/// unkeyed contract, otherwise an error asking for the key.
+pub fn default_hasher(conn: &rusqlite::Connection) -> Result<ClauseHasher> {
+    match get_hash_config(conn)? {
+        None => Ok(ClauseHasher::default()),
+        Some(config) if config.key_fingerprint.is_none() => {
+            ClauseHasher::for_contract(&config.contract_version, None)
+        }
+        Some(config) => Err(RtError::InvalidInput(format!(
+            "database uses keyed hash contract {}; configure the hash key first",
+            config.contract_version
+        ))),
+    }
+}
+
+/// Refuse `documents` that were not all hashed under the same contract and
+/// normalized by the same version: their clause hashes and anchors would
+/// not match, and every block would look changed.
+pub fn check_contracts(documents: &[&Document]) -> Result<()> {
+    let Some((first, rest)) = documents.split_first() else {
+        return Ok(());
+    };
+    for doc in rest {
+        if doc.hash_contract_version != first.hash_contract_version {
+            return Err(RtError::InvalidInput(format!(
+                "documents {} and {} were hashed under different contracts ({} and {}); \
+                 rehash one of them first",
+                first.id, doc.id, first.hash_contract_version, doc.hash_contract_version
+            )));
+        }
+        if doc.normalization_version != first.normalization_version {
+            return Err(RtError::InvalidInput(format!(
+                "documents {} and {} were normalized by different versions ({} and {}); \
+                 re-ingest one of them first",
+                first.id, doc.id, first.normalization_version, doc.normalization_version
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Refuse `documents` whose anchors were computed by different strategies,
+/// for callers that cannot re-anchor them with [`align_anchors`].
+pub fn check_anchors(documents: &[&Document]) -> Result<()> {
+    let Some((first, rest)) = documents.split_first() else {
+        return Ok(());
+    };
+    for doc in rest {
+        if doc.anchor_version != first.anchor_version {
+            return Err(RtError::InvalidInput(format!(
+                "documents {} and {} were anchored by different strategies ({} and {}); \
+                 re-ingest one of them first",
+                first.id, doc.id, first.anchor_version, doc.anchor_version
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Recompute the anchors of `blocks` (and their children), which belong to
+/// `document`, under the anchor strategy of `target`, so that the two
+/// documents align anchor to anchor. Nothing changes when both already use
+/// the same strategy. `hasher` must be of `document`'s contract.
+pub fn align_anchors(
+    target: &Document,
+    document: &Document,
+    blocks: &mut [Block],
+    hasher: &ClauseHasher,
+) -> Result<()> {
+    if target.anchor_version == document.anchor_version {
+        return Ok(());
+    }
+    if hasher.contract_version() != document.hash_contract_version {
+        return Err(RtError::InvalidInput(format!(
+            "cannot re-anchor document {} under hash contract {}; it was hashed under {}",
+            document.id,
+            hasher.contract_version(),
+            document.hash_contract_version
+        )));
+    }
+    let strategy = anchor_strategy(&target.anchor_version)?;
+    for block in blocks {
+        block.reanchor(hasher, strategy.as_ref());
+    }
+    Ok(())
+}
+
+/// Outcome of [`rehash_document`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RehashSummary {
+    pub doc_id: Uuid,
+    /// Contract the document was hashed under before.
+    pub previous_contract: String,
+    pub contract_version: String,
+    /// Blocks whose clause hash or anchor signature changed.
+    pub updated: usize,
+}
+
+/// Recompute the clause hash and anchor signature of every block of
+/// `doc_id` under `hasher`, and record `hasher`'s contract on the document.
+/// Anchors keep the document's anchor strategy.
+/// Runs in one transaction; rehashing under the contract already recorded
+/// changes nothing.
+pub fn rehash_document(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    hasher: &ClauseHasher,
+) -> Result<RehashSummary> {
+    ensure_document(conn, tenant, doc_id)?;
+    let tx = conn.unchecked_transaction()?;
+    let (previous_contract, anchor_version): (String, String) = tx.query_row(
+        "SELECT hash_contract_version, anchor_version FROM documents WHERE id = ?1",
+        params![doc_id.to_string()],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let strategy = anchor_strategy(&anchor_version)?;
+
+    let mut updated = 0;
+    {
+        let mut select = tx.prepare(
+            "SELECT id, block_type, structural_path, canonical_text, anchor_signature, clause_hash
+               FROM blocks
+              WHERE document_id = ?1",
+        )?;
+        let rows = select
+            .query_map(params![doc_id.to_string()], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut update =
+            tx.prepare("UPDATE blocks SET anchor_signature = ?2, clause_hash = ?3 WHERE id = ?1")?;
+        for (id, block_type, path, canonical, anchor, hash) in rows {
+            let block_type = BlockType::from_str_lenient(&block_type);
+            let new_anchor =
+                hasher.anchor_signature_with(strategy.as_ref(), &block_type, &path, &canonical);
+            let new_hash = hasher.clause_hash(&canonical);
+            if new_anchor != anchor || new_hash != hash {
+                update.execute(params![id, new_anchor, new_hash])?;
+                updated += 1;
+            }
+        }
+    }
+    tx.execute(
+        "UPDATE documents SET hash_contract_version = ?2 WHERE id = ?1",
+        params![doc_id.to_string(), hasher.contract_version()],
+    )?;
+    tx.commit()?;
+
+    Ok(RehashSummary {
+        doc_id: *doc_id,
+        previous_contract,
+        contract_version: hasher.contract_version().to_string(),
+        updated,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_memory_pool, BlockStore, SqliteBlockStore};
+    use crate::schema::run_migrations;
+    use rt_model::block::{Block, DocumentType};
+    use rt_model::hash::{HASH_CONTRACT_BLAKE3, HASH_CONTRACT_HMAC_SHA256, HASH_CONTRACT_SHA256};
+    use rusqlite::Connection;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        conn
+    }
+
+    #[test]
+    fn first_configuration_is_recorded() {
+        let conn = setup();
+        assert!(get_hash_config(&conn).unwrap().is_none());
+        let hasher = configure_hasher(&conn, HASH_CONTRACT_HMAC_SHA256, Some(b"k1")).unwrap();
+        assert!(hasher.is_keyed());
+
+        let config = get_hash_config(&conn).unwrap().unwrap();
+        assert_eq!(config.contract_version, HASH_CONTRACT_HMAC_SHA256);
+        assert_eq!(config.key_fingerprint, Some(key_fingerprint(b"k1")));
+    }
+
+    #[test]
+    fn reconfiguration_must_match() {
+        let conn = setup();
+        configure_hasher(&conn, HASH_CONTRACT_HMAC_SHA256, Some(b"k1")).unwrap();
+        configure_hasher(&conn, HASH_CONTRACT_HMAC_SHA256, Some(b"k1")).unwrap();
+        assert!(configure_hasher(&conn, HASH_CONTRACT_HMAC_SHA256, Some(b"k2")).is_err());
+        assert!(configure_hasher(&conn, HASH_CONTRACT_BLAKE3, Some(b"k1")).is_err());
+    }
+
+    #[test]
+    fn existing_documents_block_contract_change() {
+        let conn = setup();
+        conn.execute(
+            "INSERT INTO documents
+             (id, name, doc_type, schema_version, normalization_version,
+              hash_contract_version, ingested_at, metadata)
+             VALUES ('d1', 'doc', 'original', '1.0.0', '1.0.0', '1.0.0',
+                     '2024-01-01T00:00:00Z', '{}')",
+            [],
+        )
+        .unwrap();
+        assert!(configure_hasher(&conn, HASH_CONTRACT_BLAKE3, None).is_err());
+        assert!(configure_hasher(&conn, HASH_CONTRACT_SHA256, None).is_ok());
+    }
+
+    #[test]
+    fn documents_of_other_contracts_are_refused_until_rehashed() {
+        let pool = create_memory_pool().unwrap();
+        let store = SqliteBlockStore::new(pool.clone());
+        let blake3 = ClauseHasher::for_contract(HASH_CONTRACT_BLAKE3, None).unwrap();
+        let [mut left, mut right] = [HASH_CONTRACT_SHA256, HASH_CONTRACT_BLAKE3].map(|contract| {
+            let doc = Document {
+                id: Uuid::new_v4(),
+                name: "doc".into(),
+                source_path: None,
+                doc_type: DocumentType::Original,
+                schema_version: "1.0.0".into(),
+                normalization_version: "1.0.0".into(),
+                hash_contract_version: contract.into(),
+                anchor_version: "1".into(),
+                ingested_at: Utc::now(),
+                metadata: None,
+            };
+            store.insert_document(&doc).unwrap();
+            doc
+        });
+        let mut block = Block::new(BlockType::Clause, "1.", "text", "text", None, left.id, 0);
+        store.insert_block(&block).unwrap();
+        let err = check_contracts(&[&left, &right]).unwrap_err();
+        assert!(err.to_string().contains("different contracts"), "{err}");
+
+        let conn = pool.get().unwrap();
+        let tenant = TenantContext::default();
+        let summary = rehash_document(&conn, &tenant, &left.id, &blake3).unwrap();
+        assert_eq!(summary.previous_contract, HASH_CONTRACT_SHA256);
+        assert_eq!(summary.updated, 1);
+        left = store.get_document(&left.id).unwrap();
+        check_contracts(&[&left, &right]).unwrap();
+        block.rehash(&blake3);
+        let stored = &store.get_blocks_by_document(&left.id).unwrap()[0];
+        assert_eq!(stored.clause_hash, block.clause_hash);
+        assert_eq!(stored.anchor_signature, block.anchor_signature);
+        assert_eq!(rehash_document(&conn, &tenant, &left.id, &blake3).unwrap().updated, 0);
+
+        right.normalization_version = "2.0.0".into();
+        let err = check_contracts(&[&left, &right]).unwrap_err();
+        assert!(err.to_string().contains("normalized"), "{err}");
+    }
+
+    #[test]
+    fn anchors_are_aligned_to_the_other_documents_strategy() {
+        let hasher = ClauseHasher::default();
+        let [left, right] = ["1", "2+shingles"].map(|anchor_version| Document {
+            id: Uuid::new_v4(),
+            name: "doc".into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: "1.0.0".into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: hasher.contract_version().into(),
+            anchor_version: anchor_version.into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        });
+        let text = "Payment is due within thirty days of invoice.";
+        let block = Block::new(BlockType::Clause, "4.2", text, text, None, right.id, 0);
+        let mut renumbered = Block::new(BlockType::Clause, "5.2", text, text, None, left.id, 0);
+        let mut blocks = [block.clone()];
+        blocks[0].rehash_with(&hasher, anchor_strategy("2+shingles").unwrap().as_ref());
+        let err = check_anchors(&[&left, &right]).unwrap_err();
+        assert!(err.to_string().contains("different strategies"), "{err}");
+
+        align_anchors(&right, &left, std::slice::from_mut(&mut renumbered), &hasher).unwrap();
+        assert_eq!(renumbered.anchor_signature, blocks[0].anchor_signature);
+        assert_eq!(renumbered.clause_hash, block.clause_hash);
+
+        let blake3 = ClauseHasher::for_contract(HASH_CONTRACT_BLAKE3, None).unwrap();
+        let err = align_anchors(&left, &right, &mut blocks, &blake3).unwrap_err();
+        assert!(err.to_string().contains("cannot re-anchor"), "{err}");
+    }
+
+    #[test]
+    fn default_hasher_requires_key_for_keyed_database() {
+        let conn = setup();
+        assert_eq!(default_hasher(&conn).unwrap().contract_version(), HASH_CONTRACT_SHA256);
+        configure_hasher(&conn, HASH_CONTRACT_BLAKE3, Some(b"k")).unwrap();
+        assert!(default_hasher(&conn).is_err());
+    }
+}
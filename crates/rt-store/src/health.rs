@@ -0,0 +1,218 @@
+//! Cheap liveness / readiness checks for embedding services.
+//!
+//! [`check_health`] borrows one pooled connection, runs a trivial query and
+//! inspects the schema; it never writes and never scans block data, so it is
+//! safe to call from a probe every few seconds.
+
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbPool;
+use crate::schema::{SCHEMA_VERSION, TABLES};
+
+// ---------------------------------------------------------------------------
+// Report types
+// ---------------------------------------------------------------------------
+
+/// Overall verdict of a health check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// Database reachable and schema complete.
+    Ok,
+    /// Database reachable but the schema is incomplete or holds documents
+    /// written under a different schema version.
+    Degraded,
+    /// No database connection could be obtained or queried.
+    Unavailable,
+}
+
+/// Connection pool occupancy at the time of the check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolStatus {
+    /// Configured upper bound on pooled connections.
+    pub max_size: u32,
+    /// Connections currently open (idle + in use).
+    pub connections: u32,
+    /// Open connections not checked out.
+    pub idle_connections: u32,
+}
+
+/// Structured result of [`check_health`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    /// `true` when the service can take traffic (`status == Ok`).
+    pub ready: bool,
+    /// Schema version this build writes.
+    pub schema_version: String,
+    /// Wall-clock time of the `SELECT 1` round-trip, in milliseconds.
+    pub db_latency_ms: Option<f64>,
+    /// Tables from [`TABLES`] not present in the database.
+    pub missing_tables: Vec<String>,
+    /// Number of documents whose `schema_version` differs from
+    /// [`SCHEMA_VERSION`].
+    pub stale_documents: u64,
+    pub pool: Option<PoolStatus>,
+    /// Human-readable reason when `status != Ok`.
+    pub error: Option<String>,
+}
+
+impl HealthReport {
+    /// Report for a process whose database has not been opened.
+    pub fn unavailable(reason: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Unavailable,
+            ready: false,
+            schema_version: SCHEMA_VERSION.to_string(),
+            db_latency_ms: None,
+            missing_tables: Vec::new(),
+            stale_documents: 0,
+            pool: None,
+            error: Some(reason.into()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Probe `pool`: one DB round-trip, a schema completeness check and the pool
+/// occupancy. Never returns an error — failures are reported in the result.
+pub fn check_health(pool: &DbPool) -> HealthReport {
+    let state = pool.state();
+    let pool_status = PoolStatus {
+        max_size: pool.max_size(),
+        connections: state.connections,
+        idle_connections: state.idle_connections,
+    };
+
+    let mut report = HealthReport::unavailable("");
+    report.pool = Some(pool_status);
+
+    let conn = match pool.get() {
+        Ok(c) => c,
+        Err(e) => {
+            report.error = Some(format!("failed to acquire database connection: {e}"));
+            return report;
+        }
+    };
+
+    let started = Instant::now();
+    if let Err(e) = conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0)) {
+        report.error = Some(format!("database round-trip failed: {e}"));
+        return report;
+    }
+    report.db_latency_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+
+    let mut problems = Vec::new();
+    for table in TABLES {
+        let exists = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|n| n > 0);
+        match exists {
+            Ok(true) => {}
+            Ok(false) => report.missing_tables.push(table.to_string()),
+            Err(e) => problems.push(format!("schema inspection failed: {e}")),
+        }
+    }
+    if !report.missing_tables.is_empty() {
+        problems.push(format!("missing tables: {}", report.missing_tables.join(", ")));
+    } else {
+        match conn.query_row(
+            "SELECT COUNT(*) FROM documents WHERE schema_version <> ?1",
+            [SCHEMA_VERSION],
+            |row| row.get::<_, i64>(0),
+        ) {
+            Ok(n) => report.stale_documents = n as u64,
+            Err(e) => problems.push(format!("schema version check failed: {e}")),
+        }
+        if report.stale_documents > 0 {
+            problems.push(format!(
+                "{} document(s) written under a schema other than {SCHEMA_VERSION}",
+                report.stale_documents
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        report.status = HealthStatus::Ok;
+        report.ready = true;
+        report.error = None;
+    } else {
+        report.status = HealthStatus::Degraded;
+        report.error = Some(problems.join("; "));
+    }
+    report
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_pool;
+    use tempfile::TempDir;
+
+    fn file_pool() -> (TempDir, DbPool) {
+        let dir = TempDir::new().unwrap();
+        let pool = create_pool(dir.path().join("health.db").to_str().unwrap()).unwrap();
+        (dir, pool)
+    }
+
+    #[test]
+    fn fresh_database_is_ready() {
+        let (_dir, pool) = file_pool();
+        let report = check_health(&pool);
+        assert_eq!(report.status, HealthStatus::Ok, "{:?}", report.error);
+        assert!(report.ready);
+        assert!(report.db_latency_ms.is_some());
+        assert!(report.missing_tables.is_empty());
+        assert_eq!(report.pool.unwrap().max_size, 16);
+    }
+
+    #[test]
+    fn missing_table_is_degraded() {
+        let (_dir, pool) = file_pool();
+        pool.get().unwrap().execute_batch("DROP TABLE artifacts;").unwrap();
+        let report = check_health(&pool);
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(!report.ready);
+        assert_eq!(report.missing_tables, vec!["artifacts".to_string()]);
+    }
+
+    #[test]
+    fn stale_documents_are_degraded() {
+        let (_dir, pool) = file_pool();
+        pool.get()
+            .unwrap()
+            .execute(
+                "INSERT INTO documents
+                 (id, name, doc_type, schema_version, normalization_version,
+                  hash_contract_version, ingested_at, metadata)
+                 VALUES ('d1', 'doc', 'original', '0.9.0', '1.0.0', '1.0.0',
+                         '2024-01-01T00:00:00Z', '{}')",
+                [],
+            )
+            .unwrap();
+        let report = check_health(&pool);
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert_eq!(report.stale_documents, 1);
+    }
+
+    #[test]
+    fn unavailable_report_serializes() {
+        let json = serde_json::to_value(HealthReport::unavailable("no db")).unwrap();
+        assert_eq!(json["status"], "unavailable");
+        assert_eq!(json["ready"], false);
+        assert_eq!(json["error"], "no db");
+    }
+}
@@ -0,0 +1,24 @@
+pub mod artifact;
+pub mod compare_sections;
+pub mod content;
+pub mod cross_references;
+pub mod db;
+pub mod defined_terms;
+pub mod entities;
+pub mod fingerprint;
+pub mod gc;
+pub mod hashing;
+pub mod health;
+pub mod link_anchors;
+pub mod memory;
+pub mod overrides;
+pub mod presets;
+pub mod review_activity;
+pub mod run_history;
+pub mod schema;
+pub mod snapshot;
+pub mod tenant;
+pub mod usage;
+pub mod validation;
+pub mod versions;
+pub mod vfs;
@@ -0,0 +1,314 @@
+//! Deep-link anchor lineage.
+//!
+//! Link anchors (see `rt_model::anchor`) are derived from block content, so
+//! an edited block gets a new one. `link_anchors` remembers, per document,
+//! every anchor any of its blocks has had and the block it was last issued
+//! for. Re-ingestion keeps the ids of matched blocks, so an anchor taken
+//! from an older version still resolves to the block's current id after its
+//! text changes; an anchor whose block was deleted resolves to nothing.
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::anchor::{block_link_anchor, parse_link_anchor, LinkAnchor};
+use rt_model::error::{Result, RtError};
+use rt_model::BlockType;
+
+use crate::tenant::{ensure_document, TenantContext};
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// An anchor to resolve, for [`resolve_link_anchor`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnchorQuery {
+    pub anchor: String,
+    /// Document of a block anchor.
+    #[serde(default)]
+    pub doc_id: Option<Uuid>,
+    /// Left document of a delta anchor; needed when the delta has a left
+    /// block.
+    #[serde(default)]
+    pub left_doc_id: Option<Uuid>,
+    /// Right document of a delta anchor; needed when the delta has a right
+    /// block.
+    #[serde(default)]
+    pub right_doc_id: Option<Uuid>,
+}
+
+/// A block anchor and the block it currently refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedBlock {
+    pub anchor: String,
+    /// Current id of the block; `None` when it has since been deleted.
+    pub block_id: Option<Uuid>,
+}
+
+/// The result of [`resolve_link_anchor`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AnchorResolution {
+    Block(ResolvedBlock),
+    Delta {
+        anchor: String,
+        /// The left block; `None` for an insertion.
+        left: Option<ResolvedBlock>,
+        /// The right block; `None` for a deletion.
+        right: Option<ResolvedBlock>,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// Recording and resolution
+// ---------------------------------------------------------------------------
+
+/// Record the current link anchor of every block of `doc_id`, each pointing
+/// at its block; anchors of earlier versions are kept. Returns the number of
+/// blocks recorded. `NotFound` when `tenant` has no such document.
+pub fn record_link_anchors(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+) -> Result<usize> {
+    ensure_document(conn, tenant, doc_id)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, block_type, structural_path, canonical_text FROM blocks
+          WHERE document_id = ?1",
+    )?;
+    let blocks = stmt
+        .query_map(params![doc_id.to_string()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT OR REPLACE INTO link_anchors (document_id, anchor, block_id)
+             VALUES (?1, ?2, ?3)",
+        )?;
+        for (id, block_type, path, text) in &blocks {
+            let anchor = block_link_anchor(&BlockType::from_str_lenient(block_type), path, text);
+            insert.execute(params![doc_id.to_string(), anchor, id])?;
+        }
+    }
+    tx.commit()?;
+    Ok(blocks.len())
+}
+
+/// Resolve a block or delta anchor to current block ids. `InvalidInput` for
+/// a malformed anchor or a missing document id, `NotFound` for an anchor
+/// never issued in its document (or a document `tenant` does not have).
+///
+/// Documents ingested before anchors were recorded are backfilled with
+/// their current anchors on first use.
+pub fn resolve_link_anchor(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    query: &AnchorQuery,
+) -> Result<AnchorResolution> {
+    let require = |doc: Option<Uuid>, field: &str| {
+        doc.ok_or_else(|| {
+            RtError::InvalidInput(format!("{field} is required to resolve {}", query.anchor))
+        })
+    };
+    match parse_link_anchor(&query.anchor)? {
+        LinkAnchor::Block(anchor) => {
+            let doc_id = require(query.doc_id, "doc_id")?;
+            Ok(AnchorResolution::Block(resolve_block(conn, tenant, &doc_id, anchor)?))
+        }
+        LinkAnchor::Delta { left, right } => {
+            let left = match left {
+                Some(anchor) => {
+                    let doc_id = require(query.left_doc_id, "left_doc_id")?;
+                    Some(resolve_block(conn, tenant, &doc_id, anchor)?)
+                }
+                None => None,
+            };
+            let right = match right {
+                Some(anchor) => {
+                    let doc_id = require(query.right_doc_id, "right_doc_id")?;
+                    Some(resolve_block(conn, tenant, &doc_id, anchor)?)
+                }
+                None => None,
+            };
+            Ok(AnchorResolution::Delta {
+                anchor: query.anchor.clone(),
+                left,
+                right,
+            })
+        }
+    }
+}
+
+fn resolve_block(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    anchor: String,
+) -> Result<ResolvedBlock> {
+    ensure_document(conn, tenant, doc_id)?;
+    let recorded: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM link_anchors WHERE document_id = ?1",
+        params![doc_id.to_string()],
+        |row| row.get(0),
+    )?;
+    if recorded == 0 {
+        record_link_anchors(conn, tenant, doc_id)?;
+    }
+
+    let issued: Option<String> = conn
+        .query_row(
+            "SELECT block_id FROM link_anchors WHERE document_id = ?1 AND anchor = ?2",
+            params![doc_id.to_string(), anchor],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(block_id) = issued else {
+        return Err(RtError::NotFound(format!("anchor {anchor} in document {doc_id}")));
+    };
+    let current: Option<String> = conn
+        .query_row(
+            "SELECT id FROM blocks WHERE id = ?1 AND document_id = ?2",
+            params![block_id, doc_id.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let block_id = current
+        .map(|id| Uuid::parse_str(&id).map_err(|e| RtError::Internal(e.to_string())))
+        .transpose()?;
+    Ok(ResolvedBlock { anchor, block_id })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rt_model::anchor::delta_link_anchor;
+    use rusqlite::Connection;
+
+    fn setup() -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO documents
+             (id, name, doc_type, schema_version, normalization_version,
+              hash_contract_version, ingested_at, metadata)
+             VALUES (?1, 'test-doc', 'CONTRACT', '1.0.0', '1.0.0', '1.0.0',
+                     '2024-01-01T00:00:00Z', '{}')",
+            params![doc_id.to_string()],
+        )
+        .unwrap();
+        (conn, doc_id)
+    }
+
+    fn insert_block(conn: &Connection, doc: Uuid, path: &str, text: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO blocks
+             (id, document_id, block_type, level, structural_path, anchor_signature,
+              clause_hash, canonical_text, display_text, formatting_meta, position_index)
+             VALUES (?1, ?2, 'clause', 0, ?3, '', '', ?4, ?4, '{}', 0)",
+            params![id.to_string(), doc.to_string(), path, text],
+        )
+        .unwrap();
+        id
+    }
+
+    fn block_query(doc: Uuid, anchor: &str) -> AnchorQuery {
+        AnchorQuery {
+            anchor: anchor.into(),
+            doc_id: Some(doc),
+            left_doc_id: None,
+            right_doc_id: None,
+        }
+    }
+
+    #[test]
+    fn anchors_of_edited_blocks_resolve_to_the_same_block() {
+        let (conn, doc) = setup();
+        let tenant = TenantContext::default();
+        let kept = insert_block(&conn, doc, "1.", "The Borrower shall repay.");
+        let dropped = insert_block(&conn, doc, "2.", "Interest accrues daily.");
+        let old_kept = block_link_anchor(&BlockType::Clause, "1.", "The Borrower shall repay.");
+        let old_dropped = block_link_anchor(&BlockType::Clause, "2.", "Interest accrues daily.");
+        assert_eq!(record_link_anchors(&conn, &tenant, &doc).unwrap(), 2);
+
+        // Re-ingestion: block 1 keeps its id with new text, block 2 is gone.
+        conn.execute(
+            "UPDATE blocks SET canonical_text = 'The Borrower must repay.' WHERE id = ?1",
+            params![kept.to_string()],
+        )
+        .unwrap();
+        conn.execute("DELETE FROM blocks WHERE id = ?1", params![dropped.to_string()]).unwrap();
+        record_link_anchors(&conn, &tenant, &doc).unwrap();
+
+        let new_kept = block_link_anchor(&BlockType::Clause, "1.", "The Borrower must repay.");
+        for anchor in [&old_kept, &new_kept] {
+            let resolved = resolve_link_anchor(&conn, &tenant, &block_query(doc, anchor)).unwrap();
+            assert_eq!(
+                resolved,
+                AnchorResolution::Block(ResolvedBlock {
+                    anchor: anchor.clone(),
+                    block_id: Some(kept),
+                })
+            );
+        }
+        let AnchorResolution::Block(gone) =
+            resolve_link_anchor(&conn, &tenant, &block_query(doc, &old_dropped)).unwrap()
+        else {
+            panic!("expected a block resolution");
+        };
+        assert_eq!(gone.block_id, None);
+
+        let unknown = block_link_anchor(&BlockType::Clause, "9.", "never ingested");
+        let err = resolve_link_anchor(&conn, &tenant, &block_query(doc, &unknown)).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+    }
+
+    #[test]
+    fn delta_anchors_resolve_both_sides_and_backfill() {
+        let (conn, doc) = setup();
+        let tenant = TenantContext::default();
+        let block = insert_block(&conn, doc, "1.", "The Borrower shall repay.");
+        let anchor = block_link_anchor(&BlockType::Clause, "1.", "The Borrower shall repay.");
+        let delta = delta_link_anchor(None, Some(&anchor));
+
+        let mut query = AnchorQuery {
+            anchor: delta.clone(),
+            doc_id: None,
+            left_doc_id: None,
+            right_doc_id: None,
+        };
+        let err = resolve_link_anchor(&conn, &tenant, &query).unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(_)));
+
+        query.right_doc_id = Some(doc);
+        let resolved = resolve_link_anchor(&conn, &tenant, &query).unwrap();
+        assert_eq!(
+            resolved,
+            AnchorResolution::Delta {
+                anchor: delta,
+                left: None,
+                right: Some(ResolvedBlock { anchor, block_id: Some(block) }),
+            }
+        );
+
+        let other = TenantContext::new("other").unwrap();
+        let err = resolve_link_anchor(&conn, &other, &query).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+    }
+}
@@ -0,0 +1,823 @@
+//! A [`BlockStore`] held entirely in memory.
+//!
+//! [`MemoryBlockStore`] keeps documents and blocks in hash maps instead of
+//! SQLite, so a compare or merge pipeline can run over transient data with
+//! no database, and tests of the layers above the store skip opening a pool
+//! and running migrations. Nothing survives the store being dropped.
+//!
+//! It follows [`SqliteBlockStore`](crate::db::SqliteBlockStore) wherever
+//! callers can observe the difference: the same ordering, the same
+//! [`ConflictPolicy`] resolution, the same `NotFound` and `InvalidInput`
+//! errors, and collisions that the schema would reject (a duplicate id, a
+//! second block on a structural path, a missing parent) are rejected as
+//! [`RtError::InvalidInput`]. Differences:
+//!
+//! - There is one tenant: the store sees everything written to it.
+//! - [`BlockStore::search`] matches case-insensitive alphanumeric words
+//!   instead of using FTS5. A hit's snippet is the whole `canonical_text`
+//!   and its score is the share of the block's words that match, so scores
+//!   are not comparable with the SQLite store's BM25.
+//! - A batch write works on a copy of the store and swaps it in on success,
+//!   so its cost grows with the size of the store.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use uuid::Uuid;
+
+use rt_model::block::{Block, Document};
+use rt_model::error::{Result, RtError};
+
+use crate::db::{
+    build_tree, BlockSearchFilter, BlockSearchHit, BlockStore, BlockStream, ConflictPolicy,
+    DocumentFilter, DEFAULT_LIST_LIMIT, MAX_LIST_LIMIT,
+};
+
+// ---------------------------------------------------------------------------
+// State
+// ---------------------------------------------------------------------------
+
+/// A stored value with its insertion sequence, which stands in for SQLite's
+/// rowid when ordering ties.
+#[derive(Debug, Clone)]
+struct Stored<T> {
+    seq: u64,
+    value: T,
+}
+
+#[derive(Debug, Clone, Default)]
+struct State {
+    documents: HashMap<Uuid, Stored<Document>>,
+    /// Blocks, stored flat: `children` is always empty.
+    blocks: HashMap<Uuid, Stored<Block>>,
+    /// `(document_id, structural_path)` → block id.
+    paths: HashMap<(Uuid, String), Uuid>,
+    next_seq: u64,
+}
+
+impl State {
+    fn seq(&mut self) -> u64 {
+        self.next_seq += 1;
+        self.next_seq
+    }
+
+    fn ensure_document(&self, doc_id: &Uuid) -> Result<()> {
+        if !self.documents.contains_key(doc_id) {
+            return Err(RtError::NotFound(format!("document {doc_id}")));
+        }
+        Ok(())
+    }
+
+    fn write_document(&mut self, doc: &Document, policy: ConflictPolicy) -> Result<()> {
+        match self.documents.get_mut(&doc.id) {
+            None => {
+                let seq = self.seq();
+                self.documents.insert(doc.id, Stored { seq, value: doc.clone() });
+            }
+            Some(stored) => match policy {
+                ConflictPolicy::Fail => {
+                    return Err(RtError::InvalidInput(format!(
+                        "document {} already exists",
+                        doc.id
+                    )));
+                }
+                ConflictPolicy::Skip => {}
+                ConflictPolicy::Replace => stored.value = doc.clone(),
+            },
+        }
+        Ok(())
+    }
+
+    fn path_owner(&self, doc_id: &Uuid, path: &str) -> Option<Uuid> {
+        self.paths.get(&(*doc_id, path.to_string())).copied()
+    }
+
+    fn check_parent(&self, block: &Block) -> Result<()> {
+        match block.parent_id {
+            Some(parent) if parent != block.id && !self.blocks.contains_key(&parent) => {
+                Err(RtError::InvalidInput(format!(
+                    "parent block {parent} of block {} does not exist",
+                    block.id
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Write one block, resolving collisions per `policy`. Returns `false`
+    /// when the block was skipped.
+    fn write_block(&mut self, block: &Block, policy: ConflictPolicy) -> Result<bool> {
+        self.ensure_document(&block.document_id)?;
+        self.check_parent(block)?;
+        let path_owner = self
+            .path_owner(&block.document_id, &block.structural_path)
+            .filter(|owner| *owner != block.id);
+
+        match policy {
+            ConflictPolicy::Fail => {
+                if self.blocks.contains_key(&block.id) {
+                    return Err(RtError::InvalidInput(format!(
+                        "block {} already exists",
+                        block.id
+                    )));
+                }
+                if path_owner.is_some() {
+                    return Err(RtError::InvalidInput(format!(
+                        "document {} already has a block at {:?}",
+                        block.document_id, block.structural_path
+                    )));
+                }
+            }
+            ConflictPolicy::Skip => {
+                if path_owner.is_some() || self.blocks.contains_key(&block.id) {
+                    return Ok(false);
+                }
+            }
+            ConflictPolicy::Replace => {
+                // As in SQLite: a different block on the same path is
+                // displaced, and only a block of the same document is
+                // replaced by id.
+                if let Some(owner) = path_owner {
+                    self.remove_block(&owner);
+                }
+                if let Some(existing) = self.blocks.get(&block.id) {
+                    if existing.value.document_id != block.document_id {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+
+        let seq = match self.remove_entry(&block.id) {
+            Some(stored) => stored.seq,
+            None => self.seq(),
+        };
+        self.put(seq, block.clone());
+        Ok(true)
+    }
+
+    fn put(&mut self, seq: u64, mut block: Block) {
+        block.children.clear();
+        self.paths
+            .insert((block.document_id, block.structural_path.clone()), block.id);
+        self.blocks.insert(block.id, Stored { seq, value: block });
+    }
+
+    /// Take `id` out of the maps without touching its children.
+    fn remove_entry(&mut self, id: &Uuid) -> Option<Stored<Block>> {
+        let stored = self.blocks.remove(id)?;
+        let key = (stored.value.document_id, stored.value.structural_path.clone());
+        if self.paths.get(&key) == Some(id) {
+            self.paths.remove(&key);
+        }
+        Some(stored)
+    }
+
+    /// Delete `id`; its children become roots, as `ON DELETE SET NULL`
+    /// leaves them.
+    fn remove_block(&mut self, id: &Uuid) -> Option<Block> {
+        let stored = self.remove_entry(id)?;
+        for child in self.blocks.values_mut() {
+            if child.value.parent_id == Some(*id) {
+                child.value.parent_id = None;
+            }
+        }
+        Some(stored.value)
+    }
+
+    /// The blocks matching `keep`, ordered by `key` and then by insertion.
+    fn select<K: Ord>(
+        &self,
+        keep: impl Fn(&Block) -> bool,
+        key: impl Fn(&Block) -> K,
+    ) -> Vec<Block> {
+        let mut matched: Vec<&Stored<Block>> =
+            self.blocks.values().filter(|b| keep(&b.value)).collect();
+        matched.sort_by(|a, b| key(&a.value).cmp(&key(&b.value)).then(a.seq.cmp(&b.seq)));
+        matched.into_iter().map(|b| b.value.clone()).collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// MemoryBlockStore
+// ---------------------------------------------------------------------------
+
+/// [`BlockStore`] over in-memory maps; see the [module docs](self).
+#[derive(Debug, Default)]
+pub struct MemoryBlockStore {
+    state: RwLock<State>,
+}
+
+impl MemoryBlockStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, State>> {
+        self.state
+            .read()
+            .map_err(|_| RtError::Internal("memory store lock poisoned".into()))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, State>> {
+        self.state
+            .write()
+            .map_err(|_| RtError::Internal("memory store lock poisoned".into()))
+    }
+
+    /// Run `f` against a copy of the state and keep the copy only if `f`
+    /// succeeds, so a failed batch leaves the store untouched.
+    fn transaction<T>(&self, f: impl FnOnce(&mut State) -> Result<T>) -> Result<T> {
+        let mut state = self.write()?;
+        let mut work = state.clone();
+        let out = f(&mut work)?;
+        *state = work;
+        Ok(out)
+    }
+}
+
+fn validate_limit(limit: Option<usize>) -> Result<usize> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT);
+    if limit == 0 || limit > MAX_LIST_LIMIT {
+        return Err(RtError::InvalidInput(format!(
+            "limit must be between 1 and {MAX_LIST_LIMIT}, got {limit}"
+        )));
+    }
+    Ok(limit)
+}
+
+// ---------------------------------------------------------------------------
+// Search
+// ---------------------------------------------------------------------------
+
+/// The alphanumeric words of `text`, lowercased, with their byte ranges.
+fn words(text: &str) -> Vec<(usize, usize, String)> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (start, c.is_alphanumeric()) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                out.push((s, i, text[s..i].to_lowercase()));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Match `phrases` against `text`: `None` unless every phrase occurs, else
+/// the score and the text with each occurrence wrapped in `<mark>`.
+fn match_text(text: &str, phrases: &[Vec<String>]) -> Option<(f64, String)> {
+    let words = words(text);
+    let mut spans = Vec::new();
+    for phrase in phrases {
+        if phrase.is_empty() || phrase.len() > words.len() {
+            return None;
+        }
+        let before = spans.len();
+        for start in 0..=words.len() - phrase.len() {
+            let window = &words[start..start + phrase.len()];
+            if window.iter().zip(phrase).all(|(w, p)| w.2 == *p) {
+                spans.push((window[0].0, window[phrase.len() - 1].1));
+            }
+        }
+        if spans.len() == before {
+            return None;
+        }
+    }
+    let score = spans.len() as f64 / words.len() as f64;
+
+    spans.sort_unstable();
+    let mut snippet = String::with_capacity(text.len() + spans.len() * 13);
+    let mut cursor = 0;
+    let mut spans = spans.into_iter().peekable();
+    while let Some((start, mut end)) = spans.next() {
+        while let Some(&(next_start, next_end)) = spans.peek() {
+            if next_start > end {
+                break;
+            }
+            end = end.max(next_end);
+            spans.next();
+        }
+        let start = start.max(cursor);
+        snippet.push_str(&text[cursor..start]);
+        snippet.push_str("<mark>");
+        snippet.push_str(&text[start..end]);
+        snippet.push_str("</mark>");
+        cursor = end;
+    }
+    snippet.push_str(&text[cursor..]);
+    Some((score, snippet))
+}
+
+// ---------------------------------------------------------------------------
+// BlockStore implementation
+// ---------------------------------------------------------------------------
+
+impl BlockStore for MemoryBlockStore {
+    fn insert_document(&self, doc: &Document) -> Result<()> {
+        self.upsert_document(doc, ConflictPolicy::Fail)
+    }
+
+    fn upsert_document(&self, doc: &Document, policy: ConflictPolicy) -> Result<()> {
+        self.write()?.write_document(doc, policy)
+    }
+
+    fn get_document(&self, id: &Uuid) -> Result<Document> {
+        self.read()?
+            .documents
+            .get(id)
+            .map(|d| d.value.clone())
+            .ok_or_else(|| RtError::NotFound(format!("document {id}")))
+    }
+
+    fn list_documents(&self, filter: &DocumentFilter) -> Result<Vec<Document>> {
+        let limit = validate_limit(filter.limit)?;
+        if let (Some(from), Some(to)) = (filter.ingested_from, filter.ingested_to) {
+            if from > to {
+                return Err(RtError::InvalidInput(format!(
+                    "ingested_from {from} is after ingested_to {to}"
+                )));
+            }
+        }
+        match &filter.metadata_key {
+            Some(key) if key.is_empty() || key.contains('"') => {
+                return Err(RtError::InvalidInput(format!("invalid metadata_key {key:?}")));
+            }
+            None if filter.metadata_value.is_some() => {
+                return Err(RtError::InvalidInput(
+                    "metadata_value requires metadata_key".into(),
+                ));
+            }
+            _ => {}
+        }
+        let name = filter.name_contains.as_ref().map(|n| n.to_lowercase());
+
+        let state = self.read()?;
+        let mut matched: Vec<&Stored<Document>> = state
+            .documents
+            .values()
+            .filter(|stored| {
+                let doc = &stored.value;
+                let metadata = filter.metadata_key.as_ref().map(|key| {
+                    doc.metadata.as_ref().and_then(|m| m.get(key))
+                });
+                filter.doc_type.as_ref().is_none_or(|t| *t == doc.doc_type)
+                    && name.as_ref().is_none_or(|n| doc.name.to_lowercase().contains(n))
+                    && filter.ingested_from.is_none_or(|from| doc.ingested_at >= from)
+                    && filter.ingested_to.is_none_or(|to| doc.ingested_at < to)
+                    && match metadata {
+                        None => true,
+                        Some(None) => false,
+                        Some(Some(value)) => {
+                            filter.metadata_value.as_ref().is_none_or(|v| v == value)
+                        }
+                    }
+            })
+            .collect();
+        matched.sort_by(|a, b| {
+            a.value
+                .ingested_at
+                .cmp(&b.value.ingested_at)
+                .then(a.seq.cmp(&b.seq))
+        });
+        Ok(matched
+            .into_iter()
+            .skip(filter.offset)
+            .take(limit)
+            .map(|d| d.value.clone())
+            .collect())
+    }
+
+    fn insert_block(&self, block: &Block) -> Result<()> {
+        self.transaction(|state| state.write_block(block, ConflictPolicy::Fail).map(|_| ()))
+    }
+
+    fn insert_blocks(&self, blocks: &[Block]) -> Result<()> {
+        self.upsert_blocks(blocks, ConflictPolicy::Fail).map(|_| ())
+    }
+
+    fn upsert_blocks(&self, blocks: &[Block], policy: ConflictPolicy) -> Result<usize> {
+        self.transaction(|state| {
+            let mut written = 0;
+            for block in blocks {
+                if state.write_block(block, policy)? {
+                    written += 1;
+                }
+            }
+            Ok(written)
+        })
+    }
+
+    fn get_blocks_by_document(&self, doc_id: &Uuid) -> Result<Vec<Block>> {
+        Ok(self
+            .read()?
+            .select(|b| b.document_id == *doc_id, |b| b.position_index))
+    }
+
+    fn get_blocks_page(&self, doc_id: &Uuid, offset: usize, limit: usize) -> Result<Vec<Block>> {
+        let blocks = self.get_blocks_by_document(doc_id)?;
+        Ok(blocks.into_iter().skip(offset).take(limit).collect())
+    }
+
+    fn stream_blocks(&self, doc_id: &Uuid, batch_size: usize) -> BlockStream<'_> {
+        BlockStream::new(self, *doc_id, batch_size)
+    }
+
+    fn get_block(&self, id: &Uuid) -> Result<Block> {
+        self.read()?
+            .blocks
+            .get(id)
+            .map(|b| b.value.clone())
+            .ok_or_else(|| RtError::NotFound(format!("block {id}")))
+    }
+
+    fn get_block_children(&self, parent_id: &Uuid) -> Result<Vec<Block>> {
+        Ok(self
+            .read()?
+            .select(|b| b.parent_id == Some(*parent_id), |b| b.position_index))
+    }
+
+    fn get_block_tree(&self, doc_id: &Uuid) -> Result<Vec<Block>> {
+        let flat = self.get_blocks_by_document(doc_id)?;
+        Ok(build_tree(flat))
+    }
+
+    fn update_block(&self, block: &Block) -> Result<()> {
+        let mut state = self.write()?;
+        state.ensure_document(&block.document_id)?;
+        let Some(existing) = state.blocks.get(&block.id) else {
+            return Err(RtError::NotFound(format!("block {}", block.id)));
+        };
+        state.check_parent(block)?;
+        if state
+            .path_owner(&block.document_id, &block.structural_path)
+            .is_some_and(|owner| owner != block.id)
+        {
+            return Err(RtError::InvalidInput(format!(
+                "document {} already has a block at {:?}",
+                block.document_id, block.structural_path
+            )));
+        }
+
+        // Like the SQL update, only the block row changes: tokens, runs and
+        // attachments stay as stored.
+        let mut updated = block.clone();
+        updated.tokens = existing.value.tokens.clone();
+        updated.runs = existing.value.runs.clone();
+        updated.attachments = existing.value.attachments.clone();
+        let seq = existing.seq;
+        state.remove_entry(&block.id);
+        state.put(seq, updated);
+        Ok(())
+    }
+
+    fn delete_block(&self, id: &Uuid) -> Result<()> {
+        self.write()?
+            .remove_block(id)
+            .map(|_| ())
+            .ok_or_else(|| RtError::NotFound(format!("block {id}")))
+    }
+
+    fn get_blocks_by_anchor(&self, anchor_signature: &str) -> Result<Vec<Block>> {
+        Ok(self
+            .read()?
+            .select(|b| b.anchor_signature == anchor_signature, |b| b.position_index))
+    }
+
+    fn get_blocks_by_clause_hash(&self, clause_hash: &str) -> Result<Vec<Block>> {
+        Ok(self.read()?.select(
+            |b| b.clause_hash == clause_hash,
+            |b| (b.document_id.to_string(), b.position_index),
+        ))
+    }
+
+    fn search(&self, query: &str, filter: &BlockSearchFilter) -> Result<Vec<BlockSearchHit>> {
+        let phrases: Vec<Vec<String>> = query
+            .split_whitespace()
+            .map(|word| words(word).into_iter().map(|(_, _, w)| w).collect())
+            .collect();
+        if phrases.is_empty() {
+            return Err(RtError::InvalidInput("search query must not be empty".into()));
+        }
+        let limit = validate_limit(filter.limit)?;
+        let document_ids: HashSet<&Uuid> = filter.document_ids.iter().collect();
+
+        let state = self.read()?;
+        let mut hits = Vec::new();
+        for stored in state.blocks.values() {
+            let block = &stored.value;
+            if !document_ids.is_empty() && !document_ids.contains(&block.document_id) {
+                continue;
+            }
+            if filter.block_type.as_ref().is_some_and(|t| *t != block.block_type) {
+                continue;
+            }
+            if let Some(doc_type) = &filter.doc_type {
+                let doc = state.documents.get(&block.document_id);
+                if doc.is_none_or(|d| d.value.doc_type != *doc_type) {
+                    continue;
+                }
+            }
+            if let Some((score, snippet)) = match_text(&block.canonical_text, &phrases) {
+                let hit = BlockSearchHit {
+                    block_id: block.id,
+                    document_id: block.document_id,
+                    structural_path: block.structural_path.clone(),
+                    block_type: block.block_type.clone(),
+                    snippet,
+                    score,
+                };
+                hits.push((hit, block.position_index, stored.seq));
+            }
+        }
+        hits.sort_by(|(a, a_pos, a_seq), (b, b_pos, b_seq)| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.document_id.to_string().cmp(&b.document_id.to_string()))
+                .then(a_pos.cmp(b_pos))
+                .then(a_seq.cmp(b_seq))
+        });
+        Ok(hits
+            .into_iter()
+            .skip(filter.offset)
+            .take(limit)
+            .map(|(hit, _, _)| hit)
+            .collect())
+    }
+
+    fn apply_block_changes(&self, doc_id: &Uuid, upserts: &[Block], deleted: &[Uuid]) -> Result<()> {
+        if let Some(block) = upserts.iter().find(|b| b.document_id != *doc_id) {
+            return Err(RtError::InvalidInput(format!(
+                "block {} belongs to document {}, not {doc_id}",
+                block.id, block.document_id
+            )));
+        }
+
+        self.transaction(|state| {
+            state.ensure_document(doc_id)?;
+            // Park rewritten blocks off their paths, so that a renumbering
+            // that swaps two paths does not displace either block.
+            for block in upserts {
+                if let Some(stored) = state.blocks.get(&block.id) {
+                    if stored.value.document_id == *doc_id {
+                        let Stored { seq, value } = state.remove_entry(&block.id).unwrap();
+                        let parked = Block {
+                            structural_path: format!("~{}", value.id),
+                            ..value
+                        };
+                        state.put(seq, parked);
+                    }
+                }
+            }
+            for id in deleted {
+                if state.blocks.get(id).is_some_and(|b| b.value.document_id == *doc_id) {
+                    state.remove_block(id);
+                }
+            }
+            for block in upserts {
+                state.write_block(block, ConflictPolicy::Replace)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_memory_pool, SqliteBlockStore};
+    use chrono::{Duration, Utc};
+    use rt_model::block::{BlockType, DocumentType, FormattingMeta, Token, TokenKind};
+    use serde_json::json;
+
+    fn make_doc(name: &str) -> Document {
+        Document {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: "1.0.0".into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        }
+    }
+
+    fn make_block(doc_id: Uuid, path: &str, position_index: i32, text: &str) -> Block {
+        Block {
+            id: Uuid::new_v4(),
+            document_id: doc_id,
+            parent_id: None,
+            block_type: BlockType::Clause,
+            level: 0,
+            structural_path: path.into(),
+            anchor_signature: format!("anchor-{path}"),
+            clause_hash: format!("hash-{text}"),
+            canonical_text: text.into(),
+            display_text: text.into(),
+            formatting_meta: FormattingMeta::default(),
+            position_index,
+            tokens: vec![Token {
+                text: text.into(),
+                kind: TokenKind::Word,
+                normalized: text.into(),
+                offset: 0,
+            }],
+            runs: Vec::new(),
+            attachments: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Run the same writes against `store`, returning what each read saw.
+    fn exercise(store: &dyn BlockStore) -> Vec<String> {
+        let doc = make_doc("msa");
+        store.insert_document(&doc).unwrap();
+        let parent = make_block(doc.id, "1", 0, "term");
+        let mut child = make_block(doc.id, "1.1", 0, "renewal");
+        child.parent_id = Some(parent.id);
+        let other = make_block(doc.id, "2", 1, "payment");
+        store.insert_blocks(&[parent.clone(), child.clone(), other.clone()]).unwrap();
+
+        let mut seen = Vec::new();
+        let mut record = |label: &str, blocks: &[Block]| {
+            let shown: Vec<String> = blocks
+                .iter()
+                .map(|b| format!("{}:{}:{:?}", b.structural_path, b.canonical_text, b.parent_id))
+                .collect();
+            seen.push(format!("{label} {}", shown.join(", ")));
+        };
+
+        let dup = make_block(doc.id, "2", 5, "duplicate path");
+        assert!(store.insert_block(&dup).is_err());
+        assert_eq!(store.upsert_blocks(std::slice::from_ref(&dup), ConflictPolicy::Skip).unwrap(), 0);
+        record("skip", &store.get_blocks_by_document(&doc.id).unwrap());
+
+        let renamed = Block {
+            canonical_text: "term, renamed".into(),
+            ..parent.clone()
+        };
+        assert_eq!(
+            store.upsert_blocks(&[renamed, dup], ConflictPolicy::Replace).unwrap(),
+            2
+        );
+        record("replace", &store.get_blocks_by_document(&doc.id).unwrap());
+
+        let swapped = [
+            Block { structural_path: "1.1".into(), ..parent.clone() },
+            Block { structural_path: "1".into(), ..child.clone() },
+        ];
+        store.apply_block_changes(&doc.id, &swapped, &[]).unwrap();
+        record("swap", &store.get_blocks_by_document(&doc.id).unwrap());
+
+        store.delete_block(&parent.id).unwrap();
+        record("delete", &store.get_blocks_by_document(&doc.id).unwrap());
+        record("page", &store.get_blocks_page(&doc.id, 1, 5).unwrap());
+        seen
+    }
+
+    #[test]
+    fn writes_and_reads_match_the_sqlite_store() {
+        let sqlite = SqliteBlockStore::new(create_memory_pool().unwrap());
+        let memory_seen = exercise(&MemoryBlockStore::new());
+        let sqlite_seen = exercise(&sqlite);
+        assert_eq!(memory_seen.len(), 5);
+        // Block ids differ between the runs; compare paths, text and
+        // whether a parent is set.
+        let strip = |lines: Vec<String>| -> Vec<String> {
+            lines
+                .into_iter()
+                .map(|l| {
+                    l.split(", ")
+                        .map(|b| b.split(":Some(").next().unwrap().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .collect()
+        };
+        assert_eq!(strip(memory_seen.clone()), strip(sqlite_seen));
+        assert!(memory_seen[3].contains("1:renewal:None"), "{}", memory_seen[3]);
+    }
+
+    #[test]
+    fn tree_lookups_and_errors() {
+        let store = MemoryBlockStore::new();
+        let doc = make_doc("msa");
+        let orphan = make_block(doc.id, "1", 0, "term");
+        assert!(matches!(store.insert_block(&orphan), Err(RtError::NotFound(_))));
+        store.insert_document(&doc).unwrap();
+        assert!(matches!(store.insert_document(&doc), Err(RtError::InvalidInput(_))));
+
+        let parent = make_block(doc.id, "1", 0, "term");
+        let mut second = make_block(doc.id, "1.2", 1, "second");
+        second.parent_id = Some(parent.id);
+        let mut first = make_block(doc.id, "1.1", 0, "first");
+        first.parent_id = Some(parent.id);
+        let mut missing_parent = make_block(doc.id, "3", 2, "stray");
+        missing_parent.parent_id = Some(Uuid::new_v4());
+        assert!(store.insert_blocks(&[parent.clone(), missing_parent]).is_err());
+        assert!(store.get_blocks_by_document(&doc.id).unwrap().is_empty());
+
+        store.insert_blocks(&[parent.clone(), second, first.clone()]).unwrap();
+        let tree = store.get_block_tree(&doc.id).unwrap();
+        assert_eq!(tree.len(), 1);
+        let paths: Vec<_> = tree[0].children.iter().map(|b| b.structural_path.as_str()).collect();
+        assert_eq!(paths, ["1.1", "1.2"]);
+        assert_eq!(store.get_block_children(&parent.id).unwrap().len(), 2);
+        assert_eq!(store.get_blocks_by_anchor("anchor-1.1").unwrap()[0].id, first.id);
+        assert_eq!(store.get_blocks_by_clause_hash("hash-term").unwrap()[0].id, parent.id);
+        let streamed: Vec<_> = store.stream_blocks(&doc.id, 2).collect::<Result<_>>().unwrap();
+        assert_eq!(streamed.len(), 3);
+
+        let updated = Block {
+            canonical_text: "first, amended".into(),
+            tokens: Vec::new(),
+            ..first.clone()
+        };
+        store.update_block(&updated).unwrap();
+        let stored = store.get_block(&first.id).unwrap();
+        assert_eq!(stored.canonical_text, "first, amended");
+        assert_eq!(stored.tokens.len(), 1);
+        let clash = Block { structural_path: "1.2".into(), ..first.clone() };
+        assert!(matches!(store.update_block(&clash), Err(RtError::InvalidInput(_))));
+
+        let ghost = Uuid::new_v4();
+        let err = store.get_block(&ghost).unwrap_err().to_string();
+        assert_eq!(err, format!("not found: block {ghost}"));
+        assert!(matches!(store.delete_block(&ghost), Err(RtError::NotFound(_))));
+    }
+
+    #[test]
+    fn list_documents_and_search_filter() {
+        let store = MemoryBlockStore::new();
+        let now = Utc::now();
+        let mut older = make_doc("Alpha Lease");
+        older.ingested_at = now - Duration::days(2);
+        older.metadata = Some(json!({"matter": 7}));
+        let mut newer = make_doc("Beta lease");
+        newer.ingested_at = now;
+        newer.doc_type = DocumentType::Redline;
+        store.insert_document(&newer).unwrap();
+        store.insert_document(&older).unwrap();
+
+        let names = |filter: DocumentFilter| -> Vec<String> {
+            store.list_documents(&filter).unwrap().into_iter().map(|d| d.name).collect()
+        };
+        assert_eq!(names(DocumentFilter::default()), ["Alpha Lease", "Beta lease"]);
+        let filter = DocumentFilter {
+            name_contains: Some("LEASE".into()),
+            ingested_from: Some(now - Duration::days(1)),
+            ..DocumentFilter::default()
+        };
+        assert_eq!(names(filter), ["Beta lease"]);
+        let filter = DocumentFilter {
+            metadata_key: Some("matter".into()),
+            metadata_value: Some(json!(7)),
+            ..DocumentFilter::default()
+        };
+        assert_eq!(names(filter), ["Alpha Lease"]);
+        let bad = DocumentFilter { limit: Some(0), ..DocumentFilter::default() };
+        assert!(store.list_documents(&bad).is_err());
+
+        store
+            .insert_blocks(&[
+                make_block(older.id, "1", 0, "The Tenant shall pay rent; rent is due monthly."),
+                make_block(older.id, "2", 1, "The Landlord keeps the deposit."),
+                make_block(newer.id, "1", 0, "Rent and non-compete terms apply."),
+            ])
+            .unwrap();
+        let hits = store.search("RENT", &BlockSearchFilter::default()).unwrap();
+        assert_eq!(hits.len(), 2);
+        // Two matches in nine words outrank one in six.
+        assert_eq!(
+            hits[0].snippet,
+            "The Tenant shall pay <mark>rent</mark>; <mark>rent</mark> is due monthly."
+        );
+        assert_eq!(hits[1].snippet, "<mark>Rent</mark> and non-compete terms apply.");
+
+        let hits = store.search("non-compete rent", &BlockSearchFilter::default()).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            hits[0].snippet,
+            "<mark>Rent</mark> and <mark>non-compete</mark> terms apply."
+        );
+        let filter = BlockSearchFilter {
+            doc_type: Some(DocumentType::Original),
+            ..BlockSearchFilter::default()
+        };
+        let hits = store.search("rent", &filter).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document_id, older.id);
+        assert!(store.search("  ", &filter).is_err());
+        assert!(store.search("deposit tenant", &filter).unwrap().is_empty());
+    }
+}
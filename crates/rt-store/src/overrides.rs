@@ -0,0 +1,175 @@
+//! Reviewer corrections to block alignment.
+//!
+//! An override records that two blocks from different documents are the
+//! same clause, the same clause moved, or unrelated, regardless of what the
+//! aligner decided. Overrides are grouped into named corpora (e.g. one per
+//! client or contract family) so thresholds can be calibrated per corpus.
+
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+use rt_model::AlignmentLabel;
+
+/// One stored alignment correction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlignmentOverride {
+    pub id: Uuid,
+    pub corpus: String,
+    pub left_block_id: Uuid,
+    pub right_block_id: Uuid,
+    pub label: AlignmentLabel,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Record `label` for the block pair within `corpus`. A later correction of
+/// the same pair in the same corpus replaces the earlier one.
+pub fn record_override(
+    conn: &rusqlite::Connection,
+    corpus: &str,
+    left_block_id: &Uuid,
+    right_block_id: &Uuid,
+    label: AlignmentLabel,
+) -> Result<AlignmentOverride> {
+    if corpus.trim().is_empty() {
+        return Err(RtError::InvalidInput("corpus must not be empty".into()));
+    }
+    if left_block_id == right_block_id {
+        return Err(RtError::InvalidInput(
+            "an alignment override needs two different blocks".into(),
+        ));
+    }
+
+    let ov = AlignmentOverride {
+        id: Uuid::new_v4(),
+        corpus: corpus.to_string(),
+        left_block_id: *left_block_id,
+        right_block_id: *right_block_id,
+        label,
+        created_at: Utc::now(),
+    };
+    conn.execute(
+        "INSERT OR REPLACE INTO alignment_overrides
+             (id, corpus, left_block_id, right_block_id, label, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            ov.id.to_string(),
+            ov.corpus,
+            ov.left_block_id.to_string(),
+            ov.right_block_id.to_string(),
+            ov.label.as_str(),
+            ov.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(ov)
+}
+
+/// Every override recorded for `corpus`, oldest first.
+pub fn list_overrides(conn: &rusqlite::Connection, corpus: &str) -> Result<Vec<AlignmentOverride>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, corpus, left_block_id, right_block_id, label, created_at
+           FROM alignment_overrides
+          WHERE corpus = ?1
+          ORDER BY created_at ASC, rowid ASC",
+    )?;
+    let rows = stmt.query_map(params![corpus], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+        ))
+    })?;
+
+    let parse_id = |s: &str| Uuid::parse_str(s).map_err(|e| RtError::InvalidInput(e.to_string()));
+    let mut overrides = Vec::new();
+    for row in rows {
+        let (id, corpus, left, right, label, created_at) = row?;
+        overrides.push(AlignmentOverride {
+            id: parse_id(&id)?,
+            corpus,
+            left_block_id: parse_id(&left)?,
+            right_block_id: parse_id(&right)?,
+            label: AlignmentLabel::from_str(&label)?,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| RtError::InvalidInput(e.to_string()))?,
+        });
+    }
+    Ok(overrides)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rusqlite::Connection;
+
+    fn setup() -> (Connection, Uuid, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        conn.execute(
+            "INSERT INTO documents
+             (id, name, doc_type, schema_version, normalization_version,
+              hash_contract_version, ingested_at, metadata)
+             VALUES ('d1', 'doc', 'original', '1.0.0', '1.0.0', '1.0.0',
+                     '2024-01-01T00:00:00Z', '{}')",
+            [],
+        )
+        .unwrap();
+        let ids = [Uuid::new_v4(), Uuid::new_v4()];
+        for (i, id) in ids.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO blocks
+                 (id, document_id, block_type, structural_path, anchor_signature,
+                  clause_hash, canonical_text, display_text)
+                 VALUES (?1, 'd1', 'clause', ?2, '', '', 'text', 'text')",
+                params![id.to_string(), format!("{i}.")],
+            )
+            .unwrap();
+        }
+        (conn, ids[0], ids[1])
+    }
+
+    #[test]
+    fn overrides_round_trip_per_corpus() {
+        let (conn, a, b) = setup();
+        let recorded = record_override(&conn, "loans", &a, &b, AlignmentLabel::Move).unwrap();
+        record_override(&conn, "leases", &b, &a, AlignmentLabel::Distinct).unwrap();
+
+        let loans = list_overrides(&conn, "loans").unwrap();
+        assert_eq!(loans.len(), 1);
+        assert_eq!(loans[0].id, recorded.id);
+        assert_eq!(loans[0].label, AlignmentLabel::Move);
+        assert!(list_overrides(&conn, "other").unwrap().is_empty());
+    }
+
+    #[test]
+    fn later_correction_replaces_earlier() {
+        let (conn, a, b) = setup();
+        record_override(&conn, "loans", &a, &b, AlignmentLabel::Match).unwrap();
+        record_override(&conn, "loans", &a, &b, AlignmentLabel::Distinct).unwrap();
+        let loans = list_overrides(&conn, "loans").unwrap();
+        assert_eq!(loans.len(), 1);
+        assert_eq!(loans[0].label, AlignmentLabel::Distinct);
+    }
+
+    #[test]
+    fn invalid_overrides_are_rejected() {
+        let (conn, a, _) = setup();
+        assert!(record_override(&conn, " ", &a, &Uuid::new_v4(), AlignmentLabel::Match).is_err());
+        assert!(record_override(&conn, "loans", &a, &a, AlignmentLabel::Match).is_err());
+        // Unknown block: foreign key violation.
+        assert!(
+            record_override(&conn, "loans", &a, &Uuid::new_v4(), AlignmentLabel::Match).is_err()
+        );
+    }
+}
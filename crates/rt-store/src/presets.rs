@@ -0,0 +1,82 @@
+//! Named compare option presets.
+//!
+//! A preset is a stored JSON object in the shape of the compare engine's
+//! options (`CompareConfig`), e.g. thresholds tuned by calibration. The store
+//! treats it as opaque JSON; hosts load it and pass it as compare options.
+
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+use rt_model::error::{Result, RtError};
+
+/// Save `config` under `name`, replacing any existing preset of that name.
+pub fn save_compare_preset(
+    conn: &rusqlite::Connection,
+    name: &str,
+    config: &serde_json::Value,
+) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(RtError::InvalidInput("preset name must not be empty".into()));
+    }
+    if !config.is_object() {
+        return Err(RtError::InvalidInput("preset config must be a JSON object".into()));
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO compare_presets (name, config, updated_at)
+         VALUES (?1, ?2, ?3)",
+        params![name, serde_json::to_string(config)?, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// The preset stored under `name`.
+pub fn get_compare_preset(conn: &rusqlite::Connection, name: &str) -> Result<serde_json::Value> {
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT config FROM compare_presets WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match json {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Err(RtError::NotFound(format!("compare preset {name}"))),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rusqlite::Connection;
+    use serde_json::json;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        conn
+    }
+
+    #[test]
+    fn presets_round_trip_and_replace() {
+        let conn = setup();
+        save_compare_preset(&conn, "loans", &json!({"similarity_threshold": 0.6})).unwrap();
+        save_compare_preset(&conn, "loans", &json!({"similarity_threshold": 0.65})).unwrap();
+        assert_eq!(
+            get_compare_preset(&conn, "loans").unwrap(),
+            json!({"similarity_threshold": 0.65})
+        );
+    }
+
+    #[test]
+    fn missing_and_invalid_presets() {
+        let conn = setup();
+        assert!(matches!(get_compare_preset(&conn, "nope"), Err(RtError::NotFound(_))));
+        assert!(save_compare_preset(&conn, "", &json!({})).is_err());
+        assert!(save_compare_preset(&conn, "x", &json!([1])).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! Aggregate counts over submitted review deltas for review analytics.
+//!
+//! Counts rows of `block_deltas`, optionally filtered by reviewer, document,
+//! block, delta type and a `created_at` window, grouped by any combination
+//! of [`DeltaDimension`]s — e.g. deltas per reviewer per section today.
+//! Deltas belong to a tenant through their block's document, so every query
+//! is scoped by a [`TenantContext`].
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+
+use crate::tenant::TenantContext;
+
+// ---------------------------------------------------------------------------
+// DeltaDimension
+// ---------------------------------------------------------------------------
+
+/// A column [`delta_activity`] can group by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeltaDimension {
+    /// `block_deltas.reviewer_id`.
+    Reviewer,
+    /// The changed block, reported with its structural path (its section).
+    Block,
+    /// `block_deltas.delta_type`.
+    DeltaType,
+    /// UTC calendar day of `created_at`, as `YYYY-MM-DD`.
+    Day,
+}
+
+impl DeltaDimension {
+    /// SQL expressions selected (and grouped by) for this dimension.
+    fn columns(&self) -> &'static [&'static str] {
+        match self {
+            DeltaDimension::Reviewer => &["d.reviewer_id"],
+            DeltaDimension::Block => &["d.block_id", "b.structural_path"],
+            DeltaDimension::DeltaType => &["d.delta_type"],
+            DeltaDimension::Day => &["substr(d.created_at, 1, 10)"],
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DeltaActivityQuery / DeltaActivity
+// ---------------------------------------------------------------------------
+
+/// Filters and grouping for [`delta_activity`]. Every field is optional; the
+/// default counts all of the tenant's deltas as a single row.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeltaActivityQuery {
+    /// Dimensions to group by, in the order they should be sorted.
+    pub group_by: Vec<DeltaDimension>,
+    pub reviewer_id: Option<String>,
+    pub document_id: Option<Uuid>,
+    pub block_id: Option<Uuid>,
+    pub delta_type: Option<String>,
+    /// Inclusive lower bound on `created_at`.
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on `created_at`.
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// One group of deltas. Only the fields of the grouped dimensions are set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaActivity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewer_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structural_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day: Option<String>,
+    /// Number of deltas in the group.
+    pub count: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Queries
+// ---------------------------------------------------------------------------
+
+/// Count `tenant`'s deltas matching `query`, one row per group, largest
+/// group first (ties in group-column order).
+pub fn delta_activity(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    query: &DeltaActivityQuery,
+) -> Result<Vec<DeltaActivity>> {
+    if let (Some(f), Some(t)) = (query.from, query.to) {
+        if f > t {
+            return Err(RtError::InvalidInput(format!(
+                "activity range start {f} is after its end {t}"
+            )));
+        }
+    }
+    let mut dimensions: Vec<DeltaDimension> = Vec::new();
+    for dimension in &query.group_by {
+        if dimensions.contains(dimension) {
+            return Err(RtError::InvalidInput(format!(
+                "duplicate group_by dimension: {dimension:?}"
+            )));
+        }
+        dimensions.push(*dimension);
+    }
+
+    let columns: Vec<&str> = dimensions.iter().flat_map(|d| d.columns()).copied().collect();
+    let (select, group, order) = if columns.is_empty() {
+        (String::new(), String::new(), String::new())
+    } else {
+        let list = columns.join(", ");
+        (format!("{list}, "), format!("GROUP BY {list}"), format!(", {list}"))
+    };
+    let sql = format!(
+        "SELECT {select}COUNT(*)
+           FROM block_deltas d
+           JOIN blocks b      ON b.id = d.block_id
+           JOIN documents doc ON doc.id = b.document_id
+          WHERE doc.tenant_id = ?1
+            AND (?2 IS NULL OR d.reviewer_id = ?2)
+            AND (?3 IS NULL OR b.document_id = ?3)
+            AND (?4 IS NULL OR d.block_id = ?4)
+            AND (?5 IS NULL OR d.delta_type = ?5)
+            AND (?6 IS NULL OR d.created_at >= ?6)
+            AND (?7 IS NULL OR d.created_at < ?7)
+          {group}
+          ORDER BY COUNT(*) DESC{order}"
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(
+        params![
+            tenant.id(),
+            query.reviewer_id,
+            query.document_id.map(|id| id.to_string()),
+            query.block_id.map(|id| id.to_string()),
+            query.delta_type,
+            query.from.as_ref().map(activity_timestamp),
+            query.to.as_ref().map(activity_timestamp),
+        ],
+        |row| {
+            let mut activity = DeltaActivity::default();
+            let mut col = 0;
+            let mut block_id: Option<String> = None;
+            for dimension in &dimensions {
+                match dimension {
+                    DeltaDimension::Reviewer => activity.reviewer_id = row.get(col)?,
+                    DeltaDimension::Block => {
+                        block_id = row.get(col)?;
+                        activity.structural_path = row.get(col + 1)?;
+                    }
+                    DeltaDimension::DeltaType => activity.delta_type = row.get(col)?,
+                    DeltaDimension::Day => activity.day = row.get(col)?,
+                }
+                col += dimension.columns().len();
+            }
+            activity.count = row.get::<_, i64>(col)? as u64;
+            Ok((activity, block_id))
+        },
+    )?;
+
+    let mut out = Vec::new();
+    for row in rows {
+        let (mut activity, block_id) = row?;
+        if let Some(id) = block_id {
+            activity.block_id = Some(
+                Uuid::parse_str(&id)
+                    .map_err(|e| RtError::Internal(format!("invalid block id {id}: {e}")))?,
+            );
+        }
+        // An ungrouped query over no deltas still yields its single row.
+        if activity.count > 0 {
+            out.push(activity);
+        }
+    }
+    Ok(out)
+}
+
+/// Fixed-width UTC timestamp written to `block_deltas.created_at`, so that
+/// time windows compare lexically.
+pub fn activity_timestamp(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use chrono::Duration;
+    use rusqlite::Connection;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        conn
+    }
+
+    fn insert_doc(conn: &Connection, tenant: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO documents
+                (id, name, doc_type, schema_version, normalization_version,
+                 hash_contract_version, ingested_at, tenant_id)
+             VALUES (?1, 'doc', 'original', '1', '1', '1', '2024-01-01T00:00:00Z', ?2)",
+            params![id.to_string(), tenant],
+        )
+        .unwrap();
+        id
+    }
+
+    fn insert_block(conn: &Connection, doc: Uuid, path: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO blocks
+                (id, document_id, block_type, structural_path, anchor_signature,
+                 clause_hash, canonical_text, display_text)
+             VALUES (?1, ?2, 'clause', ?3, '', '', '', '')",
+            params![id.to_string(), doc.to_string(), path],
+        )
+        .unwrap();
+        id
+    }
+
+    fn insert_delta(conn: &Connection, block: Uuid, reviewer: &str, kind: &str, at: DateTime<Utc>) {
+        conn.execute(
+            "INSERT INTO block_deltas (id, reviewer_id, block_id, delta_type, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                Uuid::new_v4().to_string(),
+                reviewer,
+                block.to_string(),
+                kind,
+                activity_timestamp(&at)
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn counts_per_reviewer_per_section() {
+        let conn = setup();
+        let doc = insert_doc(&conn, "default");
+        let (b1, b2) = (insert_block(&conn, doc, "1.1"), insert_block(&conn, doc, "1.2"));
+        let now = Utc::now();
+        insert_delta(&conn, b1, "alice", "insert", now);
+        insert_delta(&conn, b1, "alice", "modify", now);
+        insert_delta(&conn, b2, "alice", "delete", now);
+        insert_delta(&conn, b2, "bob", "insert", now);
+
+        let query = DeltaActivityQuery {
+            group_by: vec![DeltaDimension::Reviewer, DeltaDimension::Block],
+            ..Default::default()
+        };
+        let rows = delta_activity(&conn, &TenantContext::default(), &query).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].reviewer_id.as_deref(), Some("alice"));
+        assert_eq!(rows[0].block_id, Some(b1));
+        assert_eq!(rows[0].structural_path.as_deref(), Some("1.1"));
+        assert_eq!(rows[0].count, 2);
+        assert!(rows.iter().all(|r| r.delta_type.is_none() && r.day.is_none()));
+    }
+
+    #[test]
+    fn filters_by_type_and_time_window() {
+        let conn = setup();
+        let doc = insert_doc(&conn, "default");
+        let block = insert_block(&conn, doc, "2");
+        let now = Utc::now();
+        insert_delta(&conn, block, "alice", "insert", now - Duration::days(2));
+        insert_delta(&conn, block, "alice", "insert", now);
+        insert_delta(&conn, block, "alice", "delete", now);
+
+        let query = DeltaActivityQuery {
+            group_by: vec![DeltaDimension::Day],
+            delta_type: Some("insert".into()),
+            from: Some(now - Duration::hours(1)),
+            ..Default::default()
+        };
+        let rows = delta_activity(&conn, &TenantContext::default(), &query).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].count, 1);
+        assert_eq!(rows[0].day, Some(now.format("%Y-%m-%d").to_string()));
+    }
+
+    #[test]
+    fn ungrouped_query_counts_only_own_tenant() {
+        let conn = setup();
+        let ours = insert_block(&conn, insert_doc(&conn, "default"), "1");
+        let theirs = insert_block(&conn, insert_doc(&conn, "acme"), "1");
+        insert_delta(&conn, ours, "alice", "insert", Utc::now());
+        insert_delta(&conn, theirs, "alice", "insert", Utc::now());
+
+        let tenant = TenantContext::default();
+        let rows = delta_activity(&conn, &tenant, &DeltaActivityQuery::default()).unwrap();
+        assert_eq!(rows, vec![DeltaActivity { count: 1, ..Default::default() }]);
+
+        let nobody = DeltaActivityQuery {
+            reviewer_id: Some("carol".into()),
+            ..Default::default()
+        };
+        assert!(delta_activity(&conn, &tenant, &nobody).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_inverted_range_and_duplicate_dimensions() {
+        let conn = setup();
+        let tenant = TenantContext::default();
+        let now = Utc::now();
+        let inverted = DeltaActivityQuery {
+            from: Some(now),
+            to: Some(now - Duration::days(1)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            delta_activity(&conn, &tenant, &inverted),
+            Err(RtError::InvalidInput(_))
+        ));
+        let duplicate = DeltaActivityQuery {
+            group_by: vec![DeltaDimension::Day, DeltaDimension::Day],
+            ..Default::default()
+        };
+        assert!(matches!(
+            delta_activity(&conn, &tenant, &duplicate),
+            Err(RtError::InvalidInput(_))
+        ));
+    }
+}
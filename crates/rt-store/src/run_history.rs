@@ -0,0 +1,226 @@
+//! History of compare and merge runs with their reproducibility manifests.
+//!
+//! Every run is recorded in `run_manifests`, together with the
+//! [`RunManifest`] that describes how it was produced; its input documents
+//! are indexed in `run_manifest_inputs` so the runs that touched a document
+//! can be listed. History outlives the documents: deleting a document does
+//! not delete the runs that read it, until [`crate::gc`] collects them.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+use rt_model::manifest::RunManifest;
+
+use crate::tenant::TenantContext;
+
+// ---------------------------------------------------------------------------
+// RunKind
+// ---------------------------------------------------------------------------
+
+/// Operation a recorded run performed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunKind {
+    Compare,
+    Merge,
+}
+
+impl RunKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunKind::Compare => "compare",
+            RunKind::Merge => "merge",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "compare" => Ok(RunKind::Compare),
+            "merge" => Ok(RunKind::Merge),
+            other => Err(RtError::InvalidInput(format!("unknown run kind: {other}"))),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// RunRecord
+// ---------------------------------------------------------------------------
+
+/// One recorded run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// `CompareResult::run_id` or `MergeResult::merge_id`.
+    pub run_id: Uuid,
+    pub run_kind: RunKind,
+    pub manifest: RunManifest,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Record run `run_id` of `tenant` with its manifest, replacing an earlier
+/// record of the same run (a resumed merge is recorded again).
+pub fn record_run(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    run_id: &Uuid,
+    run_kind: RunKind,
+    manifest: &RunManifest,
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM run_manifests WHERE run_id = ?1 AND tenant_id = ?2",
+        params![run_id.to_string(), tenant.id()],
+    )?;
+    conn.execute(
+        "INSERT INTO run_manifests (run_id, run_kind, tenant_id, manifest, recorded_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            run_id.to_string(),
+            run_kind.as_str(),
+            tenant.id(),
+            serde_json::to_string(manifest)?,
+            Utc::now().to_rfc3339(),
+        ],
+    )?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO run_manifest_inputs (run_id, seq, document_id) VALUES (?1, ?2, ?3)",
+    )?;
+    for (seq, input) in manifest.inputs.iter().enumerate() {
+        stmt.execute(params![run_id.to_string(), seq as i64, input.document_id.to_string()])?;
+    }
+    Ok(())
+}
+
+/// The record of run `run_id`, or `None` when `tenant` has no such run.
+pub fn find_run(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    run_id: &Uuid,
+) -> Result<Option<RunRecord>> {
+    conn.query_row(
+        "SELECT run_id, run_kind, manifest, recorded_at FROM run_manifests
+          WHERE run_id = ?1 AND tenant_id = ?2",
+        params![run_id.to_string(), tenant.id()],
+        read_row,
+    )
+    .optional()?
+    .map(parse_row)
+    .transpose()
+}
+
+/// The record of run `run_id`; `NotFound` when `tenant` has no such run.
+pub fn get_run(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    run_id: &Uuid,
+) -> Result<RunRecord> {
+    find_run(conn, tenant, run_id)?.ok_or_else(|| RtError::NotFound(format!("run {run_id}")))
+}
+
+/// Every run of `tenant` that read `document_id`, newest first.
+pub fn runs_for_document(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    document_id: &Uuid,
+) -> Result<Vec<RunRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT r.run_id, r.run_kind, r.manifest, r.recorded_at
+           FROM run_manifests r
+           JOIN run_manifest_inputs i ON i.run_id = r.run_id
+          WHERE i.document_id = ?1 AND r.tenant_id = ?2
+          ORDER BY r.recorded_at DESC, r.run_id",
+    )?;
+    let rows = stmt
+        .query_map(params![document_id.to_string(), tenant.id()], read_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    rows.into_iter().map(parse_row).collect()
+}
+
+type RawRow = (String, String, String, String);
+
+fn read_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RawRow> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+}
+
+fn parse_row((run_id, run_kind, manifest, recorded_at): RawRow) -> Result<RunRecord> {
+    Ok(RunRecord {
+        run_id: Uuid::parse_str(&run_id)
+            .map_err(|e| RtError::Internal(format!("invalid run id {run_id}: {e}")))?,
+        run_kind: RunKind::from_str(&run_kind)?,
+        manifest: serde_json::from_str(&manifest)?,
+        recorded_at: DateTime::parse_from_rfc3339(&recorded_at)
+            .map_err(|e| RtError::Internal(format!("invalid recorded_at {recorded_at}: {e}")))?
+            .with_timezone(&Utc),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rt_model::manifest::InputDigest;
+    use rusqlite::Connection;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        conn
+    }
+
+    fn manifest(docs: &[Uuid]) -> RunManifest {
+        RunManifest::new(
+            [("rt-compare", "0.1.0")],
+            "cfg".into(),
+            docs.iter().map(|d| InputDigest::from_blocks(*d, &[])).collect(),
+        )
+    }
+
+    #[test]
+    fn runs_round_trip_and_are_listed_per_document() {
+        let conn = setup();
+        let tenant = TenantContext::default();
+        let (left, right, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let compare = Uuid::new_v4();
+        let merge = Uuid::new_v4();
+        record_run(&conn, &tenant, &compare, RunKind::Compare, &manifest(&[left, right])).unwrap();
+        record_run(&conn, &tenant, &merge, RunKind::Merge, &manifest(&[left, other])).unwrap();
+
+        let record = get_run(&conn, &tenant, &compare).unwrap();
+        assert_eq!(record.run_kind, RunKind::Compare);
+        assert_eq!(record.manifest, manifest(&[left, right]));
+
+        let ids = |doc| -> Vec<Uuid> {
+            let mut ids: Vec<Uuid> = runs_for_document(&conn, &tenant, &doc)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.run_id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let mut both = vec![compare, merge];
+        both.sort();
+        assert_eq!(ids(left), both);
+        assert_eq!(ids(other), vec![merge]);
+    }
+
+    #[test]
+    fn runs_of_other_tenants_are_not_found() {
+        let conn = setup();
+        let run_id = Uuid::new_v4();
+        let doc = Uuid::new_v4();
+        let acme = TenantContext::new("acme").unwrap();
+        record_run(&conn, &acme, &run_id, RunKind::Compare, &manifest(&[doc])).unwrap();
+
+        let default = TenantContext::default();
+        assert!(matches!(get_run(&conn, &default, &run_id), Err(RtError::NotFound(_))));
+        assert!(runs_for_document(&conn, &default, &doc).unwrap().is_empty());
+        assert!(find_run(&conn, &acme, &run_id).unwrap().is_some());
+    }
+}
@@ -0,0 +1,854 @@
+use rt_model::error::Result;
+
+/// Monotonic version string recorded in every `documents` row so that readers
+/// can detect when a database was created by an older build.
+pub const SCHEMA_VERSION: &str = "1.0.0";
+
+/// Every table created by [`CREATE_TABLES`], in creation order.
+pub const TABLES: &[&str] = &[
+    "documents",
+    "blocks",
+    "tokens",
+    "runs",
+    "tracked_changes",
+    "block_attachments",
+    "block_deltas",
+    "review_layers",
+    "workflows",
+    "workflow_events",
+    "merges",
+    "conflicts",
+    "artifacts",
+    "usage_events",
+    "hash_config",
+    "document_fingerprints",
+    "alignment_overrides",
+    "compare_presets",
+    "conflict_suggestions",
+    "run_manifests",
+    "run_manifest_inputs",
+    "compare_deltas",
+    "defined_terms",
+    "link_anchors",
+    "cross_references",
+    "gc_runs",
+    "document_parties",
+    "document_dates",
+    "reviewers",
+    "assignments",
+    "workflow_snapshots",
+    "block_contents",
+    "storage_config",
+];
+
+// ---------------------------------------------------------------------------
+// DDL
+// ---------------------------------------------------------------------------
+
+/// Full DDL for every table and index in the RT_Flow SQLite schema.
+///
+/// All tables use `CREATE TABLE IF NOT EXISTS` so that `run_migrations` is
+/// idempotent and safe to call on an already-initialised database.
+pub const CREATE_TABLES: &str = "
+-- -------------------------------------------------------------------------
+-- documents
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS documents (
+    id                      TEXT    NOT NULL PRIMARY KEY,
+    name                    TEXT    NOT NULL,
+    source_path             TEXT,
+    doc_type                TEXT    NOT NULL,
+    schema_version          TEXT    NOT NULL,
+    normalization_version   TEXT    NOT NULL,
+    hash_contract_version   TEXT    NOT NULL,
+    ingested_at             TEXT    NOT NULL,
+    metadata                TEXT    NOT NULL DEFAULT '{}',
+    tenant_id               TEXT    NOT NULL DEFAULT 'default',
+    previous_version_id     TEXT             REFERENCES documents(id) ON DELETE SET NULL,
+    anchor_version          TEXT    NOT NULL DEFAULT '1'
+);
+
+-- -------------------------------------------------------------------------
+-- blocks
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS blocks (
+    id                  TEXT    NOT NULL PRIMARY KEY,
+    document_id         TEXT    NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    parent_id           TEXT             REFERENCES blocks(id)    ON DELETE SET NULL,
+    block_type          TEXT    NOT NULL,
+    level               INTEGER NOT NULL DEFAULT 0,
+    structural_path     TEXT    NOT NULL,
+    anchor_signature    TEXT    NOT NULL,
+    clause_hash         TEXT    NOT NULL,
+    canonical_text      TEXT    NOT NULL,
+    display_text        TEXT    NOT NULL,
+    formatting_meta     TEXT    NOT NULL DEFAULT '{}',
+    position_index      INTEGER NOT NULL DEFAULT 0,
+    content_hash        TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_blocks_document_id
+    ON blocks (document_id);
+
+CREATE INDEX IF NOT EXISTS idx_blocks_parent_id
+    ON blocks (parent_id);
+
+CREATE INDEX IF NOT EXISTS idx_blocks_anchor_signature
+    ON blocks (anchor_signature);
+
+CREATE INDEX IF NOT EXISTS idx_blocks_clause_hash
+    ON blocks (clause_hash);
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_blocks_document_structural_path
+    ON blocks (document_id, structural_path);
+
+-- -------------------------------------------------------------------------
+-- tokens
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS tokens (
+    id          TEXT    NOT NULL PRIMARY KEY,
+    block_id    TEXT    NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    seq         INTEGER NOT NULL,
+    text        TEXT    NOT NULL,
+    kind        TEXT    NOT NULL,
+    normalized  TEXT    NOT NULL,
+    offset      INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_tokens_block_id
+    ON tokens (block_id);
+
+-- -------------------------------------------------------------------------
+-- runs
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS runs (
+    id            TEXT    NOT NULL PRIMARY KEY,
+    block_id      TEXT    NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    seq           INTEGER NOT NULL,
+    text          TEXT    NOT NULL,
+    bold          INTEGER NOT NULL DEFAULT 0,
+    italic        INTEGER NOT NULL DEFAULT 0,
+    underline     INTEGER NOT NULL DEFAULT 0,
+    strikethrough INTEGER NOT NULL DEFAULT 0,
+    font_size     REAL,
+    color         TEXT,
+    revision      TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_runs_block_id
+    ON runs (block_id);
+
+-- -------------------------------------------------------------------------
+-- tracked_changes
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS tracked_changes (
+    id          TEXT NOT NULL PRIMARY KEY,
+    block_id    TEXT NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    author      TEXT NOT NULL,
+    changed_at  TEXT NOT NULL,
+    change_type TEXT NOT NULL,
+    original    TEXT
+);
+
+-- -------------------------------------------------------------------------
+-- block_attachments
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS block_attachments (
+    id              TEXT    NOT NULL PRIMARY KEY,
+    block_id        TEXT    NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    seq             INTEGER NOT NULL,
+    attachment_type TEXT    NOT NULL,
+    content_hash    TEXT    NOT NULL,
+    storage_ref     TEXT    NOT NULL,
+    name            TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_block_attachments_block_id
+    ON block_attachments (block_id);
+
+-- -------------------------------------------------------------------------
+-- block_deltas
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS block_deltas (
+    id               TEXT NOT NULL PRIMARY KEY,
+    review_layer_id  TEXT,
+    reviewer_id      TEXT,
+    block_id         TEXT NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    delta_type       TEXT NOT NULL,
+    token_start      INTEGER,
+    token_end        INTEGER,
+    delta_payload    TEXT NOT NULL DEFAULT '{}',
+    created_at       TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_block_deltas_block_id
+    ON block_deltas (block_id);
+
+CREATE INDEX IF NOT EXISTS idx_block_deltas_reviewer_created
+    ON block_deltas (reviewer_id, created_at);
+
+CREATE INDEX IF NOT EXISTS idx_block_deltas_created_at
+    ON block_deltas (created_at);
+
+-- -------------------------------------------------------------------------
+-- review_layers
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS review_layers (
+    id           TEXT NOT NULL PRIMARY KEY,
+    workflow_id  TEXT,
+    reviewer_id  TEXT,
+    document_id  TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    created_at   TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_review_layers_document_id
+    ON review_layers (document_id);
+
+-- -------------------------------------------------------------------------
+-- workflows
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS workflows (
+    id           TEXT NOT NULL PRIMARY KEY,
+    document_id  TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    state        TEXT NOT NULL,
+    initiator_id TEXT,
+    created_at   TEXT NOT NULL,
+    updated_at   TEXT NOT NULL,
+    tenant_id    TEXT NOT NULL DEFAULT 'default',
+    head_hash    TEXT,
+    config_overrides TEXT NOT NULL DEFAULT '{}',
+    parent_id    TEXT REFERENCES workflows(id) ON DELETE CASCADE
+);
+
+-- -------------------------------------------------------------------------
+-- workflow_events
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS workflow_events (
+    id           TEXT    NOT NULL PRIMARY KEY,
+    workflow_id  TEXT    NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+    event_type   TEXT    NOT NULL,
+    actor        TEXT,
+    payload      TEXT    NOT NULL DEFAULT '{}',
+    created_at   TEXT    NOT NULL,
+    seq          INTEGER NOT NULL,
+    prev_hash    TEXT,
+    event_hash   TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_workflow_events_workflow_seq
+    ON workflow_events (workflow_id, seq);
+
+-- -------------------------------------------------------------------------
+-- merges
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS merges (
+    id               TEXT NOT NULL PRIMARY KEY,
+    base_doc_id      TEXT NOT NULL REFERENCES documents(id) ON DELETE RESTRICT,
+    incoming_doc_id  TEXT NOT NULL REFERENCES documents(id) ON DELETE RESTRICT,
+    output_doc_id    TEXT          REFERENCES documents(id) ON DELETE SET NULL,
+    status           TEXT NOT NULL,
+    auto_resolved    INTEGER NOT NULL DEFAULT 0,
+    ancestor_doc_id  TEXT          REFERENCES documents(id) ON DELETE RESTRICT,
+    created_at       TEXT NOT NULL,
+    tenant_id        TEXT NOT NULL DEFAULT 'default',
+    run_state        TEXT NOT NULL DEFAULT 'completed',
+    heartbeat_at     TEXT,
+    expires_at       TEXT,
+    failure          TEXT
+);
+
+-- -------------------------------------------------------------------------
+-- conflicts
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS conflicts (
+    id               TEXT NOT NULL PRIMARY KEY,
+    merge_id         TEXT NOT NULL REFERENCES merges(id)  ON DELETE CASCADE,
+    block_id         TEXT NOT NULL REFERENCES blocks(id)  ON DELETE CASCADE,
+    conflict_type    TEXT NOT NULL,
+    base_content     TEXT,
+    incoming_content TEXT,
+    resolution       TEXT NOT NULL DEFAULT 'pending'
+);
+
+CREATE INDEX IF NOT EXISTS idx_conflicts_merge_id
+    ON conflicts (merge_id);
+
+-- -------------------------------------------------------------------------
+-- artifacts
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS artifacts (
+    id                   TEXT NOT NULL PRIMARY KEY,
+    workflow_id          TEXT NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+    artifact_type        TEXT NOT NULL,
+    file_path            TEXT NOT NULL,
+    content_hash         TEXT NOT NULL,
+    source_document_hash TEXT,
+    created_at           TEXT NOT NULL
+);
+
+-- -------------------------------------------------------------------------
+-- usage_events
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS usage_events (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    metric      TEXT    NOT NULL,
+    quantity    INTEGER NOT NULL,
+    recorded_at TEXT    NOT NULL,
+    tenant_id   TEXT    NOT NULL DEFAULT 'default'
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_events_recorded_at
+    ON usage_events (recorded_at);
+
+-- -------------------------------------------------------------------------
+-- hash_config (single row)
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS hash_config (
+    id               INTEGER NOT NULL PRIMARY KEY CHECK (id = 1),
+    contract_version TEXT    NOT NULL,
+    key_fingerprint  TEXT,
+    configured_at    TEXT    NOT NULL
+);
+
+-- -------------------------------------------------------------------------
+-- document_fingerprints
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS document_fingerprints (
+    document_id   TEXT    NOT NULL PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+    minhash       TEXT    NOT NULL,
+    shingle_count INTEGER NOT NULL,
+    computed_at   TEXT    NOT NULL
+);
+
+-- -------------------------------------------------------------------------
+-- alignment_overrides
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS alignment_overrides (
+    id              TEXT NOT NULL PRIMARY KEY,
+    corpus          TEXT NOT NULL,
+    left_block_id   TEXT NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    right_block_id  TEXT NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    label           TEXT NOT NULL,
+    created_at      TEXT NOT NULL,
+    UNIQUE (corpus, left_block_id, right_block_id)
+);
+
+-- -------------------------------------------------------------------------
+-- compare_presets
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS compare_presets (
+    name        TEXT NOT NULL PRIMARY KEY,
+    config      TEXT NOT NULL,
+    updated_at  TEXT NOT NULL
+);
+
+-- -------------------------------------------------------------------------
+-- conflict_suggestions
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS conflict_suggestions (
+    conflict_id  TEXT    NOT NULL REFERENCES conflicts(id) ON DELETE CASCADE,
+    rank         INTEGER NOT NULL,
+    resolution   TEXT    NOT NULL,
+    rule         TEXT    NOT NULL,
+    confidence   REAL    NOT NULL,
+    rationale    TEXT    NOT NULL,
+    created_at   TEXT    NOT NULL,
+    PRIMARY KEY (conflict_id, rank)
+);
+
+-- -------------------------------------------------------------------------
+-- run_manifests / run_manifest_inputs
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS run_manifests (
+    run_id       TEXT NOT NULL PRIMARY KEY,
+    run_kind     TEXT NOT NULL,
+    tenant_id    TEXT NOT NULL DEFAULT 'default',
+    manifest     TEXT NOT NULL,
+    recorded_at  TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS run_manifest_inputs (
+    run_id       TEXT    NOT NULL REFERENCES run_manifests(run_id) ON DELETE CASCADE,
+    seq          INTEGER NOT NULL,
+    document_id  TEXT    NOT NULL,
+    PRIMARY KEY (run_id, seq)
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_manifest_inputs_document
+    ON run_manifest_inputs (document_id);
+
+-- -------------------------------------------------------------------------
+-- compare_deltas
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS compare_deltas (
+    run_id       TEXT    NOT NULL REFERENCES run_manifests(run_id) ON DELETE CASCADE,
+    seq          INTEGER NOT NULL,
+    section      TEXT    NOT NULL,
+    kind         TEXT    NOT NULL,
+    delta        TEXT    NOT NULL,
+    PRIMARY KEY (run_id, seq)
+);
+
+CREATE INDEX IF NOT EXISTS idx_compare_deltas_section
+    ON compare_deltas (run_id, section, seq);
+
+-- -------------------------------------------------------------------------
+-- defined_terms
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS defined_terms (
+    document_id  TEXT    NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    seq          INTEGER NOT NULL,
+    normalized   TEXT    NOT NULL,
+    term         TEXT    NOT NULL,
+    block_id     TEXT    NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    PRIMARY KEY (document_id, normalized)
+);
+
+-- -------------------------------------------------------------------------
+-- link_anchors
+-- -------------------------------------------------------------------------
+-- block_id is deliberately not a foreign key: anchors of deleted blocks are
+-- kept so they resolve to a deleted block rather than to nothing.
+CREATE TABLE IF NOT EXISTS link_anchors (
+    document_id  TEXT    NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    anchor       TEXT    NOT NULL,
+    block_id     TEXT    NOT NULL,
+    PRIMARY KEY (document_id, anchor)
+);
+
+-- -------------------------------------------------------------------------
+-- cross_references
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS cross_references (
+    document_id  TEXT    NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    seq          INTEGER NOT NULL,
+    block_id     TEXT    NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    text         TEXT    NOT NULL,
+    target       TEXT    NOT NULL,
+    offset       INTEGER NOT NULL,
+    PRIMARY KEY (document_id, seq)
+);
+
+CREATE INDEX IF NOT EXISTS idx_cross_references_target
+    ON cross_references (document_id, target);
+
+-- -------------------------------------------------------------------------
+-- gc_runs
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS gc_runs (
+    tenant_id    TEXT NOT NULL PRIMARY KEY,
+    last_run_at  TEXT NOT NULL
+);
+
+-- -------------------------------------------------------------------------
+-- document_parties / document_dates
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS document_parties (
+    document_id  TEXT    NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    seq          INTEGER NOT NULL,
+    normalized   TEXT    NOT NULL,
+    role         TEXT    NOT NULL,
+    name         TEXT,
+    block_id     TEXT    NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    PRIMARY KEY (document_id, normalized)
+);
+
+CREATE TABLE IF NOT EXISTS document_dates (
+    document_id  TEXT    NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    seq          INTEGER NOT NULL,
+    text         TEXT    NOT NULL,
+    value        TEXT    NOT NULL,
+    block_id     TEXT    NOT NULL REFERENCES blocks(id) ON DELETE CASCADE,
+    offset       INTEGER NOT NULL,
+    PRIMARY KEY (document_id, seq)
+);
+
+-- -------------------------------------------------------------------------
+-- reviewers / assignments
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS reviewers (
+    tenant_id    TEXT NOT NULL,
+    id           TEXT NOT NULL,
+    created_at   TEXT NOT NULL,
+    PRIMARY KEY (tenant_id, id)
+);
+
+CREATE TABLE IF NOT EXISTS assignments (
+    workflow_id  TEXT NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+    reviewer_id  TEXT NOT NULL,
+    role         TEXT NOT NULL,
+    assigned_by  TEXT NOT NULL,
+    assigned_at  TEXT NOT NULL,
+    PRIMARY KEY (workflow_id, reviewer_id)
+);
+
+-- -------------------------------------------------------------------------
+-- workflow_snapshots
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS workflow_snapshots (
+    workflow_id  TEXT    NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+    seq          INTEGER NOT NULL,
+    state        TEXT    NOT NULL,
+    updated_at   TEXT    NOT NULL,
+    approvals    TEXT    NOT NULL DEFAULT '{}',
+    created_at   TEXT    NOT NULL,
+    PRIMARY KEY (workflow_id, seq)
+);
+
+-- -------------------------------------------------------------------------
+-- block_contents / storage_config (single row)
+-- -------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS block_contents (
+    clause_hash  TEXT NOT NULL PRIMARY KEY,
+    tokens       TEXT NOT NULL,
+    runs         TEXT NOT NULL,
+    created_at   TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS storage_config (
+    id                 INTEGER NOT NULL PRIMARY KEY CHECK (id = 1),
+    content_addressed  INTEGER NOT NULL DEFAULT 0,
+    configured_at      TEXT    NOT NULL
+);
+";
+
+/// Deletes shared block content (see [`crate::content`]) once no block
+/// references it.
+const CREATE_CONTENT_TRIGGERS: &str = "
+CREATE TRIGGER IF NOT EXISTS block_contents_release_delete
+AFTER DELETE ON blocks WHEN old.content_hash IS NOT NULL BEGIN
+    DELETE FROM block_contents
+     WHERE clause_hash = old.content_hash
+       AND NOT EXISTS (SELECT 1 FROM blocks WHERE content_hash = old.content_hash);
+END;
+
+CREATE TRIGGER IF NOT EXISTS block_contents_release_update
+AFTER UPDATE OF content_hash ON blocks
+WHEN old.content_hash IS NOT NULL AND old.content_hash IS NOT new.content_hash BEGIN
+    DELETE FROM block_contents
+     WHERE clause_hash = old.content_hash
+       AND NOT EXISTS (SELECT 1 FROM blocks WHERE content_hash = old.content_hash);
+END;
+";
+
+/// Full-text index over `blocks.canonical_text`, an external-content FTS5
+/// table kept in step with `blocks` by triggers, so every write path
+/// (including cascading deletes) maintains it.
+const CREATE_BLOCKS_FTS: &str = "
+CREATE VIRTUAL TABLE IF NOT EXISTS blocks_fts USING fts5(
+    canonical_text,
+    content = 'blocks',
+    content_rowid = 'rowid'
+);
+
+CREATE TRIGGER IF NOT EXISTS blocks_fts_insert AFTER INSERT ON blocks BEGIN
+    INSERT INTO blocks_fts (rowid, canonical_text) VALUES (new.rowid, new.canonical_text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS blocks_fts_delete AFTER DELETE ON blocks BEGIN
+    INSERT INTO blocks_fts (blocks_fts, rowid, canonical_text)
+    VALUES ('delete', old.rowid, old.canonical_text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS blocks_fts_update AFTER UPDATE OF canonical_text ON blocks BEGIN
+    INSERT INTO blocks_fts (blocks_fts, rowid, canonical_text)
+    VALUES ('delete', old.rowid, old.canonical_text);
+    INSERT INTO blocks_fts (rowid, canonical_text) VALUES (new.rowid, new.canonical_text);
+END;
+";
+
+// ---------------------------------------------------------------------------
+// Migration runner
+// ---------------------------------------------------------------------------
+
+/// Initialise (or upgrade) the database schema.
+///
+/// This function is **idempotent**: it is safe to call on a database that has
+/// already been initialised.
+///
+/// Steps performed:
+/// 1. Enable WAL journal mode for better concurrent read performance.
+/// 2. Enable foreign-key enforcement.
+/// 3. Execute the full `CREATE TABLE / INDEX IF NOT EXISTS` DDL.
+/// 4. Add columns introduced after a table was first created, and index them.
+/// 5. Create the full-text index over block text.
+/// 6. Fill newly created tables derived from older data.
+pub fn run_migrations(conn: &rusqlite::Connection) -> Result<()> {
+    // WAL mode gives better read/write concurrency and is safe for the
+    // single-writer, multiple-reader pattern used by the connection pool.
+    conn.execute_batch("PRAGMA journal_mode = WAL;")?;
+
+    // SQLite does not enforce foreign keys by default; every connection must
+    // opt in.
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+
+    // Create all tables and indices.
+    let had_assignments = table_exists(conn, "assignments")?;
+    conn.execute_batch(CREATE_TABLES)?;
+
+    // `CREATE TABLE IF NOT EXISTS` leaves older tables untouched, so columns
+    // added since must be patched in explicitly.
+    add_column_if_missing(conn, "merges", "auto_resolved", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(
+        conn,
+        "merges",
+        "ancestor_doc_id",
+        "TEXT REFERENCES documents(id) ON DELETE RESTRICT",
+    )?;
+    // Rows from before tenancy belong to the default tenant.
+    for table in ["documents", "workflows", "merges", "usage_events"] {
+        add_column_if_missing(conn, table, "tenant_id", "TEXT NOT NULL DEFAULT 'default'")?;
+    }
+    // Hash chain of the workflow event log; events recorded before chaining
+    // have no hashes.
+    add_column_if_missing(conn, "workflows", "head_hash", "TEXT")?;
+    add_column_if_missing(conn, "workflow_events", "prev_hash", "TEXT")?;
+    add_column_if_missing(conn, "workflow_events", "event_hash", "TEXT")?;
+    // Per-workflow compare/merge option overrides; none by default.
+    add_column_if_missing(conn, "workflows", "config_overrides", "TEXT NOT NULL DEFAULT '{}'")?;
+    // Parent of a sub-workflow; workflows before sub-workflows have none.
+    add_column_if_missing(
+        conn,
+        "workflows",
+        "parent_id",
+        "TEXT REFERENCES workflows(id) ON DELETE CASCADE",
+    )?;
+    // Merge run lifecycle; merges recorded before it were saved complete.
+    add_column_if_missing(conn, "merges", "run_state", "TEXT NOT NULL DEFAULT 'completed'")?;
+    add_column_if_missing(conn, "merges", "heartbeat_at", "TEXT")?;
+    add_column_if_missing(conn, "merges", "expires_at", "TEXT")?;
+    add_column_if_missing(conn, "merges", "failure", "TEXT")?;
+    // Tracked revision of a run; runs stored before it are not marked.
+    add_column_if_missing(conn, "runs", "revision", "TEXT")?;
+    // Version chains; documents ingested before them are unlinked.
+    add_column_if_missing(
+        conn,
+        "documents",
+        "previous_version_id",
+        "TEXT REFERENCES documents(id) ON DELETE SET NULL",
+    )?;
+    // Anchor strategies; documents ingested before them were anchored by v1.
+    add_column_if_missing(conn, "documents", "anchor_version", "TEXT NOT NULL DEFAULT '1'")?;
+    // Shared block content; blocks stored before it keep their own rows.
+    add_column_if_missing(conn, "blocks", "content_hash", "TEXT")?;
+    // Indexed here rather than in `CREATE_TABLES`, which runs before the
+    // column exists on upgraded databases.
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_documents_tenant ON documents (tenant_id);
+         CREATE UNIQUE INDEX IF NOT EXISTS idx_documents_previous_version
+             ON documents (previous_version_id);
+         CREATE INDEX IF NOT EXISTS idx_workflows_tenant ON workflows (tenant_id);
+         CREATE INDEX IF NOT EXISTS idx_workflows_parent ON workflows (parent_id);
+         CREATE INDEX IF NOT EXISTS idx_merges_tenant ON merges (tenant_id);
+         CREATE INDEX IF NOT EXISTS idx_usage_events_tenant
+             ON usage_events (tenant_id, recorded_at);
+         CREATE INDEX IF NOT EXISTS idx_merges_run_state ON merges (run_state, expires_at);
+         CREATE INDEX IF NOT EXISTS idx_blocks_content_hash ON blocks (content_hash);",
+    )?;
+    conn.execute_batch(CREATE_CONTENT_TRIGGERS)?;
+
+    // Blocks stored before the full-text index existed are indexed once,
+    // when it is created.
+    let indexed = table_exists(conn, "blocks_fts")?;
+    conn.execute_batch(CREATE_BLOCKS_FTS)?;
+    if !indexed {
+        conn.execute_batch("INSERT INTO blocks_fts (blocks_fts) VALUES ('rebuild');")?;
+    }
+
+    // Reviewers assigned before assignments were tracked are recovered once
+    // from their `reviewer_assigned` events, as editors unless the event
+    // names a role.
+    if !had_assignments {
+        conn.execute_batch(BACKFILL_ASSIGNMENTS)?;
+    }
+
+    Ok(())
+}
+
+/// Fill `assignments` and `reviewers` from the workflow event log.
+const BACKFILL_ASSIGNMENTS: &str = "
+INSERT OR REPLACE INTO assignments (workflow_id, reviewer_id, role, assigned_by, assigned_at)
+SELECT workflow_id, json_extract(payload, '$.reviewer_id'),
+       COALESCE(json_extract(payload, '$.role'), 'editor'), COALESCE(actor, ''), created_at
+  FROM workflow_events
+ WHERE event_type = 'reviewer_assigned' AND json_extract(payload, '$.reviewer_id') IS NOT NULL
+ ORDER BY workflow_id, seq;
+
+INSERT OR IGNORE INTO reviewers (tenant_id, id, created_at)
+SELECT w.tenant_id, a.reviewer_id, MIN(a.assigned_at)
+  FROM assignments a JOIN workflows w ON w.id = a.workflow_id
+ GROUP BY w.tenant_id, a.reviewer_id;
+";
+
+fn table_exists(conn: &rusqlite::Connection, table: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        rusqlite::params![table],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// `ALTER TABLE <table> ADD COLUMN <column> <decl>` unless the column exists.
+fn add_column_if_missing(
+    conn: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> Result<()> {
+    let exists: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM pragma_table_info('{table}') WHERE name = ?1"),
+        rusqlite::params![column],
+        |row| row.get(0),
+    )?;
+    if exists == 0 {
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl};"))?;
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn open_memory() -> Connection {
+        Connection::open_in_memory().expect("in-memory db")
+    }
+
+    #[test]
+    fn migrations_are_idempotent() {
+        let conn = open_memory();
+        run_migrations(&conn).expect("first migration");
+        // Running a second time must not fail (all DDL uses IF NOT EXISTS).
+        run_migrations(&conn).expect("second migration");
+    }
+
+    #[test]
+    fn all_tables_exist_after_migration() {
+        let conn = open_memory();
+        run_migrations(&conn).unwrap();
+
+        for table in TABLES {
+            let count: i64 = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name=?1",
+                    rusqlite::params![table],
+                    |row| row.get(0),
+                )
+                .unwrap_or(0);
+            assert_eq!(count, 1, "table '{table}' should exist");
+        }
+    }
+
+    #[test]
+    fn migrations_index_blocks_stored_before_full_text_search() {
+        let conn = open_memory();
+        conn.execute_batch(CREATE_TABLES).unwrap();
+        conn.execute_batch(
+            "INSERT INTO documents (id, name, doc_type, schema_version, normalization_version,
+                                    hash_contract_version, ingested_at)
+             VALUES ('d', 'Doc', 'original', '1', '1', '1', '2024-01-01T00:00:00Z');
+             INSERT INTO blocks (id, document_id, block_type, structural_path, anchor_signature,
+                                 clause_hash, canonical_text, display_text)
+             VALUES ('b', 'd', 'clause', '1.', 'a', 'h', 'governing law', 'Governing law');",
+        )
+        .unwrap();
+        run_migrations(&conn).unwrap();
+
+        let hits: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM blocks_fts WHERE blocks_fts MATCH 'governing'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(hits, 1);
+    }
+
+    #[test]
+    fn migrations_recover_assignments_from_the_event_log() {
+        let conn = open_memory();
+        conn.execute_batch(CREATE_TABLES).unwrap();
+        conn.execute_batch(
+            "DROP TABLE assignments;
+             DROP TABLE reviewers;
+             INSERT INTO documents (id, name, doc_type, schema_version, normalization_version,
+                                    hash_contract_version, ingested_at)
+             VALUES ('d', 'Doc', 'original', '1', '1', '1', '2024-01-01T00:00:00Z');
+             INSERT INTO workflows (id, document_id, state, created_at, updated_at)
+             VALUES ('w', 'd', 'IN_REVIEW', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');
+             INSERT INTO workflow_events (id, workflow_id, event_type, actor, payload,
+                                          created_at, seq)
+             VALUES ('e1', 'w', 'reviewer_assigned', 'alice', '{\"reviewer_id\":\"bob\"}',
+                     '2024-01-02T00:00:00Z', 1),
+                    ('e2', 'w', 'reviewer_assigned', 'alice',
+                     '{\"reviewer_id\":\"carol\",\"role\":\"observer\"}',
+                     '2024-01-03T00:00:00Z', 2);",
+        )
+        .unwrap();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT reviewer_id, role FROM assignments ORDER BY reviewer_id")
+            .unwrap();
+        let roles: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            roles,
+            [("bob".to_string(), "editor".to_string()), ("carol".into(), "observer".into())]
+        );
+        let reviewers: i64 = conn
+            .query_row("SELECT COUNT(*) FROM reviewers WHERE tenant_id = 'default'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(reviewers, 2);
+    }
+
+    #[test]
+    fn migrations_add_missing_merge_columns() {
+        let conn = open_memory();
+        conn.execute_batch(
+            "CREATE TABLE merges (
+                id TEXT NOT NULL PRIMARY KEY,
+                base_doc_id TEXT NOT NULL,
+                incoming_doc_id TEXT NOT NULL,
+                output_doc_id TEXT,
+                status TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+        run_migrations(&conn).unwrap();
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('merges')
+                  WHERE name IN ('auto_resolved', 'ancestor_doc_id', 'tenant_id',
+                                 'run_state', 'heartbeat_at', 'expires_at', 'failure')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 7);
+    }
+
+    #[test]
+    fn wal_mode_is_active() {
+        let conn = open_memory();
+        run_migrations(&conn).unwrap();
+        // For an in-memory database SQLite returns "memory", not "wal", but
+        // the PRAGMA must not error.
+        let _mode: String = conn
+            .query_row("PRAGMA journal_mode", [], |r| r.get(0))
+            .unwrap();
+    }
+}
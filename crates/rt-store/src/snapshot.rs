@@ -0,0 +1,173 @@
+//! Point-in-time document snapshots.
+//!
+//! When a review closes, the reviewed document is preserved for audit as a
+//! separate [`DocumentType::Snapshot`] document: a deep copy of its block
+//! tree under new ids, so later edits to the original — or deleting it —
+//! leave the snapshot untouched. The snapshot's metadata links back to the
+//! original through `snapshot_of`.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use rt_model::block::{Block, Document, DocumentType};
+use rt_model::error::Result;
+
+use crate::db::{insert_block_row, read_blocks, read_document, write_document, ConflictPolicy};
+use crate::tenant::TenantContext;
+use crate::usage::{record_usage, UsageMetric};
+
+/// Metadata key of a snapshot holding the id of the document it copies.
+pub const SNAPSHOT_OF_KEY: &str = "snapshot_of";
+
+/// Copy document `doc_id` of `tenant` and its blocks (with their tokens,
+/// runs and attachments) into a new snapshot document, all or nothing, and
+/// return the snapshot. `NotFound` when `tenant` has no such document.
+///
+/// The snapshot keeps the original's name, source path, versions and
+/// metadata, adding [`SNAPSHOT_OF_KEY`]; its `ingested_at` is the time of
+/// the copy. Blocks keep their hashes, paths and order.
+pub fn create_snapshot(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+) -> Result<Document> {
+    let original = read_document(conn, tenant, doc_id)?;
+    let blocks = read_blocks(conn, tenant, doc_id, false)?;
+
+    let mut metadata = match original.metadata {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    metadata.insert(SNAPSHOT_OF_KEY.into(), serde_json::json!(doc_id));
+    let snapshot = Document {
+        id: Uuid::new_v4(),
+        doc_type: DocumentType::Snapshot,
+        ingested_at: Utc::now(),
+        metadata: Some(serde_json::Value::Object(metadata)),
+        ..original
+    };
+
+    // Inside a caller's transaction (a review closing with a snapshot), the
+    // copy commits or rolls back with it.
+    let tx = conn.is_autocommit().then(|| conn.unchecked_transaction()).transpose()?;
+    write_document(conn, tenant, &snapshot, ConflictPolicy::Fail)?;
+    let copies = copy_blocks(&blocks, snapshot.id);
+    for block in &copies {
+        insert_block_row(conn, block)?;
+    }
+    record_usage(conn, tenant, UsageMetric::BlocksStored, copies.len() as u64)?;
+    if let Some(tx) = tx {
+        tx.commit()?;
+    }
+    Ok(snapshot)
+}
+
+/// `blocks` re-keyed under fresh ids in document `doc_id`, parents before
+/// their children so each row's `parent_id` exists when it is written.
+fn copy_blocks(blocks: &[Block], doc_id: Uuid) -> Vec<Block> {
+    let ids: HashMap<Uuid, Uuid> = blocks.iter().map(|b| (b.id, Uuid::new_v4())).collect();
+    let mut children: HashMap<Option<Uuid>, Vec<&Block>> = HashMap::new();
+    for block in blocks {
+        let parent = block.parent_id.filter(|p| ids.contains_key(p));
+        children.entry(parent).or_default().push(block);
+    }
+
+    let mut copies = Vec::with_capacity(blocks.len());
+    let mut pending: Vec<&Block> = children.remove(&None).unwrap_or_default();
+    pending.reverse();
+    while let Some(block) = pending.pop() {
+        copies.push(Block {
+            id: ids[&block.id],
+            document_id: doc_id,
+            parent_id: block.parent_id.and_then(|p| ids.get(&p).copied()),
+            children: Vec::new(),
+            ..block.clone()
+        });
+        if let Some(mut kids) = children.remove(&Some(block.id)) {
+            kids.reverse();
+            pending.extend(kids);
+        }
+    }
+    copies
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_memory_pool, BlockStore, SqliteBlockStore};
+    use rt_model::error::RtError;
+    use rt_model::BlockType;
+
+    fn document() -> Document {
+        Document {
+            id: Uuid::new_v4(),
+            name: "msa".into(),
+            source_path: Some("/matters/msa.docx".into()),
+            doc_type: DocumentType::Original,
+            schema_version: "1.0.0".into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: Some(serde_json::json!({ "matter": "M-7" })),
+        }
+    }
+
+    #[test]
+    fn snapshot_deep_copies_the_block_tree() {
+        let pool = create_memory_pool().unwrap();
+        let store = SqliteBlockStore::new(pool.clone());
+        let doc = document();
+        store.insert_document(&doc).unwrap();
+        // The clause sorts before its section by position, so the copy must
+        // reorder parents before children.
+        let section = Block::new(BlockType::Section, "1", "Payment", "Payment", None, doc.id, 1);
+        let clause = Block::new(
+            BlockType::Clause,
+            "1.1",
+            "Fees are due monthly.",
+            "Fees are due monthly.",
+            Some(section.id),
+            doc.id,
+            0,
+        );
+        store.insert_blocks(&[section.clone(), clause.clone()]).unwrap();
+
+        let conn = pool.get().unwrap();
+        let snapshot = create_snapshot(&conn, &TenantContext::default(), &doc.id).unwrap();
+        assert_eq!(snapshot.doc_type, DocumentType::Snapshot);
+        assert_eq!(snapshot.name, "msa");
+        let metadata = snapshot.metadata.clone().unwrap();
+        assert_eq!(metadata["matter"], "M-7");
+        assert_eq!(metadata[SNAPSHOT_OF_KEY], doc.id.to_string());
+
+        let copied = store.get_blocks_by_document(&snapshot.id).unwrap();
+        assert_eq!(copied.len(), 2);
+        let copied_section = copied.iter().find(|b| b.structural_path == "1").unwrap();
+        let copied_clause = copied.iter().find(|b| b.structural_path == "1.1").unwrap();
+        assert_ne!(copied_section.id, section.id);
+        assert_eq!(copied_clause.parent_id, Some(copied_section.id));
+        assert_eq!(copied_clause.clause_hash, clause.clause_hash);
+        assert_eq!(copied_clause.tokens.len(), clause.tokens.len());
+
+        // Editing the original leaves the snapshot as it was.
+        store.delete_block(&clause.id).unwrap();
+        assert_eq!(store.get_blocks_by_document(&snapshot.id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn snapshot_of_other_tenants_document_is_not_found() {
+        let pool = create_memory_pool().unwrap();
+        let doc = document();
+        SqliteBlockStore::new(pool.clone()).insert_document(&doc).unwrap();
+        let other = TenantContext::new("other").unwrap();
+        let result = create_snapshot(&pool.get().unwrap(), &other, &doc.id);
+        assert!(matches!(result, Err(RtError::NotFound(_))));
+    }
+}
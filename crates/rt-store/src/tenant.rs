@@ -0,0 +1,179 @@
+//! Tenant scoping for databases shared by several clients.
+//!
+//! Documents, workflows and merges carry a `tenant_id`; blocks, events and
+//! conflicts belong to a tenant through their parent row. Every store query
+//! over those tables is scoped by a [`TenantContext`], and a row owned by
+//! another tenant is reported exactly like a missing one, so callers cannot
+//! probe for foreign ids.
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+
+/// Tenant that owns rows written before tenancy existed, and the tenant used
+/// by single-client hosts that never select one.
+pub const DEFAULT_TENANT_ID: &str = "default";
+
+/// Longest accepted tenant id.
+pub const MAX_TENANT_ID_LEN: usize = 64;
+
+/// The tenant every store call acts on behalf of.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TenantContext {
+    tenant_id: String,
+}
+
+impl TenantContext {
+    /// A context for `tenant_id`: 1 to [`MAX_TENANT_ID_LEN`] ASCII letters,
+    /// digits, `-`, `_` or `.`.
+    pub fn new(tenant_id: impl Into<String>) -> Result<Self> {
+        let tenant_id = tenant_id.into();
+        if tenant_id.is_empty() || tenant_id.len() > MAX_TENANT_ID_LEN {
+            return Err(RtError::InvalidInput(format!(
+                "tenant id must be 1 to {MAX_TENANT_ID_LEN} characters, got {}",
+                tenant_id.len()
+            )));
+        }
+        if let Some(c) = tenant_id
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        {
+            return Err(RtError::InvalidInput(format!(
+                "tenant id '{tenant_id}' contains invalid character {c:?}"
+            )));
+        }
+        Ok(Self { tenant_id })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.tenant_id
+    }
+}
+
+impl Default for TenantContext {
+    fn default() -> Self {
+        Self {
+            tenant_id: DEFAULT_TENANT_ID.to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for TenantContext {
+    type Error = RtError;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::new(value)
+    }
+}
+
+impl From<TenantContext> for String {
+    fn from(tenant: TenantContext) -> Self {
+        tenant.tenant_id
+    }
+}
+
+impl std::fmt::Display for TenantContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.tenant_id)
+    }
+}
+
+/// Fail with [`RtError::NotFound`] unless document `doc_id` exists and is
+/// owned by `tenant`.
+pub fn ensure_document(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+) -> Result<()> {
+    let owned: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM documents WHERE id = ?1 AND tenant_id = ?2",
+        params![doc_id.to_string(), tenant.id()],
+        |row| row.get(0),
+    )?;
+    if owned == 0 {
+        return Err(RtError::NotFound(format!("document {doc_id}")));
+    }
+    Ok(())
+}
+
+/// Fail with [`RtError::NotFound`] unless workflow `workflow_id` exists, is
+/// owned by `tenant` and reviews document `doc_id`.
+pub fn ensure_workflow(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    workflow_id: &Uuid,
+    doc_id: &Uuid,
+) -> Result<()> {
+    let owned: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM workflows WHERE id = ?1 AND tenant_id = ?2 AND document_id = ?3",
+        params![workflow_id.to_string(), tenant.id(), doc_id.to_string()],
+        |row| row.get(0),
+    )?;
+    if owned == 0 {
+        return Err(RtError::NotFound(format!(
+            "workflow {workflow_id} of document {doc_id}"
+        )));
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use rusqlite::Connection;
+
+    #[test]
+    fn tenant_ids_are_validated() {
+        assert_eq!(TenantContext::default().id(), DEFAULT_TENANT_ID);
+        assert_eq!(TenantContext::new("acme-corp.eu_1").unwrap().id(), "acme-corp.eu_1");
+        assert!(TenantContext::new("").is_err());
+        assert!(TenantContext::new("acme corp").is_err());
+        assert!(TenantContext::new("a".repeat(MAX_TENANT_ID_LEN + 1)).is_err());
+        assert!(serde_json::from_str::<TenantContext>(r#""bad'id""#).is_err());
+    }
+
+    #[test]
+    fn foreign_documents_and_workflows_are_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        let doc_id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO documents
+                (id, name, doc_type, schema_version, normalization_version,
+                 hash_contract_version, ingested_at, tenant_id)
+             VALUES (?1, 'doc', 'original', '1.0.0', '1.0.0', '1.0.0', '2024-01-01T00:00:00Z', 'acme')",
+            params![doc_id.to_string()],
+        )
+        .unwrap();
+
+        let acme = TenantContext::new("acme").unwrap();
+        ensure_document(&conn, &acme, &doc_id).unwrap();
+        assert!(matches!(
+            ensure_document(&conn, &TenantContext::default(), &doc_id),
+            Err(RtError::NotFound(_))
+        ));
+
+        let workflow_id = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO workflows (id, document_id, state, created_at, updated_at, tenant_id)
+             VALUES (?1, ?2, 'DRAFT', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z', 'acme')",
+            params![workflow_id.to_string(), doc_id.to_string()],
+        )
+        .unwrap();
+        ensure_workflow(&conn, &acme, &workflow_id, &doc_id).unwrap();
+        for (tenant, doc) in [(TenantContext::default(), doc_id), (acme, Uuid::new_v4())] {
+            assert!(matches!(
+                ensure_workflow(&conn, &tenant, &workflow_id, &doc),
+                Err(RtError::NotFound(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use rt_model::error::{Result, RtError};
+
+use crate::tenant::TenantContext;
+
+// ---------------------------------------------------------------------------
+// UsageMetric
+// ---------------------------------------------------------------------------
+
+/// Billable quantity recorded in the `usage_events` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageMetric {
+    /// Document rows written (inserted or replaced).
+    DocumentsIngested,
+    /// Block rows written (inserted or replaced).
+    BlocksStored,
+    /// Document comparisons run.
+    CompareRuns,
+    /// Merge runs recorded.
+    MergeRuns,
+}
+
+impl UsageMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageMetric::DocumentsIngested => "documents_ingested",
+            UsageMetric::BlocksStored => "blocks_stored",
+            UsageMetric::CompareRuns => "compare_runs",
+            UsageMetric::MergeRuns => "merge_runs",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "documents_ingested" => Ok(UsageMetric::DocumentsIngested),
+            "blocks_stored" => Ok(UsageMetric::BlocksStored),
+            "compare_runs" => Ok(UsageMetric::CompareRuns),
+            "merge_runs" => Ok(UsageMetric::MergeRuns),
+            other => Err(RtError::InvalidInput(format!("unknown usage metric: {other}"))),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// UsageReport
+// ---------------------------------------------------------------------------
+
+/// Totals per [`UsageMetric`] of one tenant over a time range.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Inclusive lower bound of the range, if any.
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound of the range, if any.
+    pub to: Option<DateTime<Utc>>,
+    pub documents_ingested: u64,
+    pub blocks_stored: u64,
+    pub compare_runs: u64,
+    pub merge_runs: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+/// Add `quantity` of `metric` to `tenant`'s usage ledger, timestamped now.
+/// A zero quantity records nothing.
+pub fn record_usage(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    metric: UsageMetric,
+    quantity: u64,
+) -> Result<()> {
+    if quantity == 0 {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO usage_events (metric, quantity, recorded_at, tenant_id)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![metric.as_str(), quantity as i64, timestamp(&Utc::now()), tenant.id()],
+    )?;
+    Ok(())
+}
+
+/// Sum the usage `tenant` recorded with `from <= recorded_at < to`. Either
+/// bound may be omitted to leave that side of the range open.
+pub fn usage_report(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<UsageReport> {
+    if let (Some(f), Some(t)) = (from, to) {
+        if f > t {
+            return Err(RtError::InvalidInput(format!(
+                "usage range start {f} is after its end {t}"
+            )));
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT metric, SUM(quantity)
+           FROM usage_events
+          WHERE (?1 IS NULL OR recorded_at >= ?1)
+            AND (?2 IS NULL OR recorded_at < ?2)
+            AND tenant_id = ?3
+          GROUP BY metric",
+    )?;
+    let rows = stmt.query_map(
+        params![from.as_ref().map(timestamp), to.as_ref().map(timestamp), tenant.id()],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+    )?;
+
+    let mut report = UsageReport {
+        from,
+        to,
+        ..UsageReport::default()
+    };
+    for row in rows {
+        let (metric, total) = row?;
+        let total = total as u64;
+        match UsageMetric::from_str(&metric)? {
+            UsageMetric::DocumentsIngested => report.documents_ingested = total,
+            UsageMetric::BlocksStored => report.blocks_stored = total,
+            UsageMetric::CompareRuns => report.compare_runs = total,
+            UsageMetric::MergeRuns => report.merge_runs = total,
+        }
+    }
+    Ok(report)
+}
+
+/// Fixed-width UTC timestamp so that `recorded_at` orders lexically.
+fn timestamp(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::run_migrations;
+    use chrono::Duration;
+    use rusqlite::Connection;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        conn
+    }
+
+    fn record_at(conn: &Connection, metric: UsageMetric, quantity: i64, at: DateTime<Utc>) {
+        conn.execute(
+            "INSERT INTO usage_events (metric, quantity, recorded_at) VALUES (?1, ?2, ?3)",
+            params![metric.as_str(), quantity, timestamp(&at)],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn report_sums_per_metric() {
+        let (conn, tenant) = (setup(), TenantContext::default());
+        record_usage(&conn, &tenant, UsageMetric::DocumentsIngested, 1).unwrap();
+        record_usage(&conn, &tenant, UsageMetric::BlocksStored, 12).unwrap();
+        record_usage(&conn, &tenant, UsageMetric::BlocksStored, 3).unwrap();
+        record_usage(&conn, &tenant, UsageMetric::CompareRuns, 1).unwrap();
+        record_usage(&conn, &tenant, UsageMetric::MergeRuns, 0).unwrap();
+
+        let report = usage_report(&conn, &tenant, None, None).unwrap();
+        assert_eq!(report.documents_ingested, 1);
+        assert_eq!(report.blocks_stored, 15);
+        assert_eq!(report.compare_runs, 1);
+        assert_eq!(report.merge_runs, 0);
+    }
+
+    #[test]
+    fn report_honours_half_open_range() {
+        let (conn, tenant) = (setup(), TenantContext::default());
+        let jan = DateTime::parse_from_rfc3339("2024-01-15T00:00:00Z").unwrap().with_timezone(&Utc);
+        let feb = jan + Duration::days(31);
+        record_at(&conn, UsageMetric::CompareRuns, 2, jan);
+        record_at(&conn, UsageMetric::CompareRuns, 5, feb);
+
+        let report = usage_report(&conn, &tenant, Some(jan), Some(feb)).unwrap();
+        assert_eq!(report.compare_runs, 2);
+        let report = usage_report(&conn, &tenant, Some(feb), None).unwrap();
+        assert_eq!(report.compare_runs, 5);
+        let report = usage_report(&conn, &tenant, None, Some(jan)).unwrap();
+        assert_eq!(report.compare_runs, 0);
+    }
+
+    #[test]
+    fn inverted_range_is_rejected() {
+        let (conn, tenant) = (setup(), TenantContext::default());
+        let now = Utc::now();
+        let yesterday = now - Duration::days(1);
+        let err = usage_report(&conn, &tenant, Some(now), Some(yesterday)).unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn report_covers_only_the_tenant() {
+        let conn = setup();
+        let (default, acme) = (TenantContext::default(), TenantContext::new("acme").unwrap());
+        record_usage(&conn, &default, UsageMetric::CompareRuns, 2).unwrap();
+        record_usage(&conn, &acme, UsageMetric::CompareRuns, 5).unwrap();
+
+        assert_eq!(usage_report(&conn, &default, None, None).unwrap().compare_runs, 2);
+        assert_eq!(usage_report(&conn, &acme, None, None).unwrap().compare_runs, 5);
+    }
+
+    #[test]
+    fn metric_names_round_trip() {
+        for m in [
+            UsageMetric::DocumentsIngested,
+            UsageMetric::BlocksStored,
+            UsageMetric::CompareRuns,
+            UsageMetric::MergeRuns,
+        ] {
+            assert_eq!(UsageMetric::from_str(m.as_str()).unwrap(), m);
+        }
+        assert!(UsageMetric::from_str("nope").is_err());
+    }
+}
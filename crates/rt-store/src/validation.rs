@@ -0,0 +1,278 @@
+//! Checks of incoming blocks before they are written.
+//!
+//! Writing a bad block fails on whichever constraint it hits first, with a
+//! raw SQLite error that names neither the block nor the problem.
+//! [`validate_blocks`] looks at the whole set up front and reports every
+//! problem found, per block: blocks of another document, ids or structural
+//! paths used twice, parents that are neither stored nor earlier in the
+//! set, and (unless the write resolves collisions) ids or paths already
+//! stored.
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use uuid::Uuid;
+
+use rt_model::block::Block;
+use rt_model::error::Result;
+
+use crate::db::ConflictPolicy;
+use crate::tenant::TenantContext;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// What is wrong with an incoming block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Its `document_id` is not the document being written.
+    WrongDocument,
+    /// An earlier block in the set has the same id.
+    DuplicateId,
+    /// An earlier block in the set has the same structural path.
+    DuplicatePath,
+    /// Its parent is neither an earlier block in the set nor stored in the
+    /// document.
+    MissingParent,
+    /// Its id belongs to a block of another document.
+    IdInOtherDocument,
+    /// A block with its id is already stored (with [`ConflictPolicy::Fail`]).
+    IdTaken,
+    /// A block with its structural path is already stored (with
+    /// [`ConflictPolicy::Fail`]).
+    PathTaken,
+}
+
+/// One problem with one incoming block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockIssue {
+    /// Position of the block in the incoming set.
+    pub index: usize,
+    pub block_id: Uuid,
+    pub structural_path: String,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+/// Outcome of [`validate_blocks`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub doc_id: Uuid,
+    /// Number of blocks checked.
+    pub blocks: usize,
+    /// Every problem found, in block order; empty when the set may be
+    /// written.
+    pub issues: Vec<BlockIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// One line naming the number of issues and the first few of them.
+    pub fn summary(&self) -> String {
+        const SHOWN: usize = 3;
+        let mut shown: Vec<String> = self
+            .issues
+            .iter()
+            .take(SHOWN)
+            .map(|i| format!("block {} ({}): {}", i.index, i.structural_path, i.message))
+            .collect();
+        if self.issues.len() > SHOWN {
+            shown.push(format!("and {} more", self.issues.len() - SHOWN));
+        }
+        format!("{} invalid block issue(s): {}", self.issues.len(), shown.join("; "))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Check `blocks`, in the order they would be written, for a write into
+/// document `doc_id` of `tenant` under `policy`. Nothing is written.
+pub fn validate_blocks(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: Uuid,
+    blocks: &[Block],
+    policy: ConflictPolicy,
+) -> Result<ValidationReport> {
+    let stored = stored_paths(conn, tenant, doc_id)?;
+    let stored_ids: HashSet<&str> = stored.values().map(String::as_str).collect();
+    let mut owner = conn.prepare("SELECT document_id FROM blocks WHERE id = ?1")?;
+
+    let mut issues = Vec::new();
+    let mut seen_ids = HashSet::new();
+    let mut seen_paths = HashSet::new();
+    for (index, block) in blocks.iter().enumerate() {
+        let mut report = |kind: IssueKind, message: String| {
+            issues.push(BlockIssue {
+                index,
+                block_id: block.id,
+                structural_path: block.structural_path.clone(),
+                kind,
+                message,
+            })
+        };
+        let id = block.id.to_string();
+
+        if block.document_id != doc_id {
+            report(
+                IssueKind::WrongDocument,
+                format!("belongs to document {}, not {doc_id}", block.document_id),
+            );
+        }
+        if !seen_ids.insert(block.id) {
+            report(IssueKind::DuplicateId, format!("id {id} appears more than once"));
+        }
+        if !seen_paths.insert(block.structural_path.as_str()) {
+            report(IssueKind::DuplicatePath, "structural path appears more than once".into());
+        }
+        if let Some(parent) = block.parent_id {
+            let parent_str = parent.to_string();
+            if !seen_ids.contains(&parent) && !stored_ids.contains(parent_str.as_str()) {
+                report(
+                    IssueKind::MissingParent,
+                    format!("parent {parent} is neither an earlier block nor stored"),
+                );
+            }
+        }
+
+        let owned_by: Option<String> =
+            owner.query_row(params![id], |row| row.get(0)).optional()?;
+        match owned_by {
+            Some(other) if other != doc_id.to_string() => report(
+                IssueKind::IdInOtherDocument,
+                format!("id {id} is used by a block of another document"),
+            ),
+            Some(_) if policy == ConflictPolicy::Fail => {
+                report(IssueKind::IdTaken, format!("block {id} is already stored"))
+            }
+            _ => {}
+        }
+        if policy == ConflictPolicy::Fail && stored.contains_key(&block.structural_path) {
+            report(IssueKind::PathTaken, "structural path is already stored".into());
+        }
+    }
+
+    Ok(ValidationReport {
+        doc_id,
+        blocks: blocks.len(),
+        issues,
+    })
+}
+
+/// Structural path → block id of the blocks stored in `doc_id`.
+fn stored_paths(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: Uuid,
+) -> Result<HashMap<String, String>> {
+    let mut stmt = conn.prepare(
+        "SELECT structural_path, id
+           FROM blocks
+          WHERE document_id = ?1
+            AND document_id IN (SELECT id FROM documents WHERE tenant_id = ?2)",
+    )?;
+    let rows = stmt.query_map(params![doc_id.to_string(), tenant.id()], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?;
+    Ok(rows.collect::<rusqlite::Result<HashMap<_, _>>>()?)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_memory_pool, BlockStore, DbPool, SqliteBlockStore};
+    use chrono::Utc;
+    use rt_model::block::{BlockType, Document, DocumentType};
+
+    fn pool_with_doc() -> (DbPool, Uuid) {
+        let pool = create_memory_pool().unwrap();
+        let store = SqliteBlockStore::new(pool.clone());
+        let doc = Document {
+            id: Uuid::new_v4(),
+            name: "doc".into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: "1.0.0".into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        };
+        store.insert_document(&doc).unwrap();
+        (pool, doc.id)
+    }
+
+    fn block(doc_id: Uuid, path: &str, parent: Option<Uuid>, pos: i32) -> Block {
+        let text = format!("clause {path}");
+        Block::new(BlockType::Clause, path, &text, &text, parent, doc_id, pos)
+    }
+
+    fn kinds(report: &ValidationReport) -> Vec<(usize, IssueKind)> {
+        report.issues.iter().map(|i| (i.index, i.kind)).collect()
+    }
+
+    #[test]
+    fn problems_within_the_set_are_reported_per_block() {
+        let (pool, doc_id) = pool_with_doc();
+        let root = block(doc_id, "1", None, 0);
+        let child = block(doc_id, "1.1", Some(root.id), 0);
+        let orphan = block(doc_id, "1.2", Some(Uuid::new_v4()), 1);
+        let twin = block(doc_id, "1.1", None, 2);
+        let stranger = block(Uuid::new_v4(), "2", None, 1);
+        let blocks = [root.clone(), child, orphan, twin, stranger, root];
+
+        let conn = pool.get().unwrap();
+        let tenant = TenantContext::default();
+        let report =
+            validate_blocks(&conn, &tenant, doc_id, &blocks, ConflictPolicy::Fail).unwrap();
+        assert_eq!(
+            kinds(&report),
+            [
+                (2, IssueKind::MissingParent),
+                (3, IssueKind::DuplicatePath),
+                (4, IssueKind::WrongDocument),
+                (5, IssueKind::DuplicateId),
+                (5, IssueKind::DuplicatePath),
+            ]
+        );
+        assert!(report.summary().starts_with("5 invalid block issue(s): block 2 (1.2)"));
+    }
+
+    #[test]
+    fn stored_blocks_conflict_only_when_the_write_would_fail() {
+        let (pool, doc_id) = pool_with_doc();
+        let root = block(doc_id, "1", None, 0);
+        SqliteBlockStore::new(pool.clone()).insert_blocks(std::slice::from_ref(&root)).unwrap();
+        let child = block(doc_id, "1.1", Some(root.id), 0);
+        let renamed = block(doc_id, "1", None, 1);
+        let blocks = [root, child, renamed];
+
+        let conn = pool.get().unwrap();
+        let tenant = TenantContext::default();
+        let fail = validate_blocks(&conn, &tenant, doc_id, &blocks, ConflictPolicy::Fail).unwrap();
+        assert_eq!(
+            kinds(&fail),
+            [
+                (0, IssueKind::IdTaken),
+                (0, IssueKind::PathTaken),
+                (2, IssueKind::DuplicatePath),
+                (2, IssueKind::PathTaken),
+            ]
+        );
+        let skip = validate_blocks(&conn, &tenant, doc_id, &blocks[..2], ConflictPolicy::Skip);
+        assert!(skip.unwrap().is_valid());
+    }
+}
@@ -0,0 +1,238 @@
+//! Document version chains.
+//!
+//! Ingesting an updated file creates a new document, unrelated to the one it
+//! revises unless it is linked to it: [`link_version`] records the earlier
+//! document in the new one's `previous_version_id`. Each document has at most
+//! one previous and one next version, so linked documents form a chain,
+//! listed oldest first by [`version_chain`]. Deleting a document unlinks its
+//! successor, splitting the chain in two.
+//!
+//! Unlike a [snapshot](crate::snapshot), which copies a document as it was, a
+//! version is a separate ingest with its own blocks.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use uuid::Uuid;
+
+use rt_model::error::{Result, RtError};
+
+use crate::tenant::{ensure_document, TenantContext};
+
+/// One document of a version chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DocumentVersion {
+    pub doc_id: Uuid,
+    /// Position in the chain, from 1 for the oldest document.
+    pub version: usize,
+    pub name: String,
+    pub ingested_at: DateTime<Utc>,
+    pub previous_version_id: Option<Uuid>,
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Record `previous_id` as the version `doc_id` revises. Both documents must
+/// belong to `tenant`; `doc_id` must not have a previous version yet and
+/// `previous_id` no next one, and the link must not close a cycle.
+pub fn link_version(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    previous_id: &Uuid,
+) -> Result<()> {
+    ensure_document(conn, tenant, doc_id)?;
+    check_link(conn, tenant, doc_id, previous_id)?;
+    conn.execute(
+        "UPDATE documents SET previous_version_id = ?2 WHERE id = ?1",
+        params![doc_id.to_string(), previous_id.to_string()],
+    )?;
+    Ok(())
+}
+
+/// Check that [`link_version`] would accept linking `doc_id` to
+/// `previous_id`, without linking them. A `doc_id` that is not stored yet
+/// passes as far as it is concerned.
+pub fn check_link(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+    previous_id: &Uuid,
+) -> Result<()> {
+    if doc_id == previous_id {
+        return Err(RtError::InvalidInput(format!(
+            "document {doc_id} cannot be its own previous version"
+        )));
+    }
+    ensure_document(conn, tenant, previous_id)?;
+    if let Some(current) = previous_version(conn, doc_id)? {
+        return Err(RtError::InvalidInput(format!(
+            "document {doc_id} already revises {current}"
+        )));
+    }
+    if let Some(next) = next_version(conn, previous_id)? {
+        return Err(RtError::InvalidInput(format!(
+            "document {previous_id} already has a next version, {next}"
+        )));
+    }
+    // `doc_id` has no previous version, so it heads its chain; linking
+    // would close a cycle only if `previous_id` descends from it.
+    let mut cursor = previous_version(conn, previous_id)?;
+    while let Some(ancestor) = cursor {
+        if ancestor == *doc_id {
+            return Err(RtError::InvalidInput(format!(
+                "document {previous_id} is a later version of {doc_id}"
+            )));
+        }
+        cursor = previous_version(conn, &ancestor)?;
+    }
+    Ok(())
+}
+
+/// Every version in the chain of `doc_id`, oldest first. A document never
+/// linked is a chain of one. `NotFound` when `tenant` has no such document.
+pub fn version_chain(
+    conn: &rusqlite::Connection,
+    tenant: &TenantContext,
+    doc_id: &Uuid,
+) -> Result<Vec<DocumentVersion>> {
+    ensure_document(conn, tenant, doc_id)?;
+    let mut first = *doc_id;
+    while let Some(previous) = previous_version(conn, &first)? {
+        first = previous;
+    }
+
+    let mut chain = Vec::new();
+    let mut cursor = Some(first);
+    while let Some(id) = cursor {
+        chain.push(read_version(conn, &id, chain.len() + 1)?);
+        cursor = next_version(conn, &id)?;
+    }
+    Ok(chain)
+}
+
+fn previous_version(conn: &rusqlite::Connection, doc_id: &Uuid) -> Result<Option<Uuid>> {
+    let previous: Option<Option<String>> = conn
+        .query_row(
+            "SELECT previous_version_id FROM documents WHERE id = ?1",
+            params![doc_id.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    previous.flatten().map(|s| parse_id(&s)).transpose()
+}
+
+fn next_version(conn: &rusqlite::Connection, doc_id: &Uuid) -> Result<Option<Uuid>> {
+    let next: Option<String> = conn
+        .query_row(
+            "SELECT id FROM documents WHERE previous_version_id = ?1",
+            params![doc_id.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    next.map(|s| parse_id(&s)).transpose()
+}
+
+fn read_version(
+    conn: &rusqlite::Connection,
+    doc_id: &Uuid,
+    version: usize,
+) -> Result<DocumentVersion> {
+    let (name, ingested_at, previous): (String, String, Option<String>) = conn.query_row(
+        "SELECT name, ingested_at, previous_version_id FROM documents WHERE id = ?1",
+        params![doc_id.to_string()],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    Ok(DocumentVersion {
+        doc_id: *doc_id,
+        version,
+        name,
+        ingested_at: ingested_at
+            .parse::<DateTime<Utc>>()
+            .map_err(|e| RtError::InvalidInput(e.to_string()))?,
+        previous_version_id: previous.map(|s| parse_id(&s)).transpose()?,
+    })
+}
+
+fn parse_id(s: &str) -> Result<Uuid> {
+    Uuid::parse_str(s).map_err(|e| RtError::InvalidInput(e.to_string()))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{create_memory_pool, BlockStore, DbPool, SqliteBlockStore};
+    use rt_model::block::{Document, DocumentType};
+
+    fn ingest(pool: &DbPool, name: &str) -> Uuid {
+        let doc = Document {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            source_path: None,
+            doc_type: DocumentType::Original,
+            schema_version: "1.0.0".into(),
+            normalization_version: "1.0.0".into(),
+            hash_contract_version: "1.0.0".into(),
+            anchor_version: "1".into(),
+            ingested_at: Utc::now(),
+            metadata: None,
+        };
+        SqliteBlockStore::new(pool.clone()).insert_document(&doc).unwrap();
+        doc.id
+    }
+
+    #[test]
+    fn linked_versions_form_a_chain() {
+        let pool = create_memory_pool().unwrap();
+        let conn = pool.get().unwrap();
+        let tenant = TenantContext::default();
+        let [v1, v2, v3] = ["msa-v1", "msa-v2", "msa-v3"].map(|name| ingest(&pool, name));
+        link_version(&conn, &tenant, &v2, &v1).unwrap();
+        link_version(&conn, &tenant, &v3, &v2).unwrap();
+
+        for doc in [v1, v2, v3] {
+            let chain = version_chain(&conn, &tenant, &doc).unwrap();
+            let ids: Vec<Uuid> = chain.iter().map(|v| v.doc_id).collect();
+            assert_eq!(ids, [v1, v2, v3]);
+        }
+        let chain = version_chain(&conn, &tenant, &v1).unwrap();
+        assert_eq!(chain[2].version, 3);
+        assert_eq!(chain[2].name, "msa-v3");
+        assert_eq!(chain[2].previous_version_id, Some(v2));
+        assert_eq!(chain[0].previous_version_id, None);
+
+        // Deleting the middle version splits the chain.
+        conn.execute("DELETE FROM documents WHERE id = ?1", params![v2.to_string()])
+            .unwrap();
+        assert_eq!(version_chain(&conn, &tenant, &v3).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn links_must_keep_the_chain_linear() {
+        let pool = create_memory_pool().unwrap();
+        let conn = pool.get().unwrap();
+        let tenant = TenantContext::default();
+        let [v1, v2, v3] = ["a", "b", "c"].map(|name| ingest(&pool, name));
+        link_version(&conn, &tenant, &v2, &v1).unwrap();
+
+        for (doc, previous, expected) in [
+            (v1, v1, "its own previous version"),
+            (v3, v1, "already has a next version"),
+            (v2, v3, "already revises"),
+            (v1, v2, "is a later version of"),
+        ] {
+            let err = link_version(&conn, &tenant, &doc, &previous).unwrap_err();
+            assert!(err.to_string().contains(expected), "{err}");
+        }
+        let other = TenantContext::new("other").unwrap();
+        let err = link_version(&conn, &other, &v3, &v2).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)), "{err}");
+        assert!(version_chain(&conn, &other, &v1).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+//! File system abstraction for exports and artifacts.
+//!
+//! Exporters and artifact storage write files through a [`Vfs`] rather than
+//! `std::fs`, so a sandboxed host (a desktop app restricted to its own file
+//! APIs) can supply its own implementation. [`StdVfs`] writes to the local
+//! file system and is what every caller uses unless told otherwise.
+
+use rt_model::error::Result;
+
+/// A place files can be written to.
+pub trait Vfs: Send + Sync {
+    /// Write `content` to `path`, creating the file or replacing its
+    /// content. Parent directories are not created.
+    fn write(&self, path: &str, content: &[u8]) -> Result<()>;
+}
+
+/// [`Vfs`] backed by the local file system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdVfs;
+
+impl Vfs for StdVfs {
+    fn write(&self, path: &str, content: &[u8]) -> Result<()> {
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rt_model::error::RtError;
+
+    #[test]
+    fn std_vfs_writes_and_replaces_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.html");
+        let path = path.to_str().unwrap();
+        StdVfs.write(path, b"first").unwrap();
+        StdVfs.write(path, b"second").unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), b"second");
+
+        let missing = dir.path().join("missing").join("out.html");
+        let err = StdVfs.write(missing.to_str().unwrap(), b"x").unwrap_err();
+        assert!(matches!(err, RtError::Io(_)));
+    }
+}
@@ -0,0 +1,265 @@
+//! Approval gating of workflow completion.
+//!
+//! A workflow may be created with an [`ApprovalPolicy`]: `required` of its
+//! approvers must sign off before it can complete. The policy travels in
+//! the `WorkflowCreated` payload as `approval_policy`, and each sign-off is
+//! an `ApprovalGranted` event whose actor is the approver, so the
+//! [`ApprovalStatus`] of a workflow is a replay of its log like its state.
+//! [`validate_transition`](crate::validator::validate_transition) refuses
+//! `WorkflowCompleted` until the status is satisfied. A workflow without a
+//! policy completes as before.
+//!
+//! Approvals are granted once the edits are compiled, in
+//! `READY_FOR_FINALIZATION`, by a distinct approver each: one of the
+//! policy's `approvers`, or, when it names none, any reviewer assigned to
+//! the workflow as an approver (see [`reviewers`](crate::reviewers)).
+
+use rt_core::RtError;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::event::{EventType, WorkflowEvent};
+use crate::reviewers::{assigned_role, ReviewerRole};
+
+/// Key of the policy in a `WorkflowCreated` payload.
+pub const POLICY_KEY: &str = "approval_policy";
+
+/// How many sign-offs a workflow needs, and from whom.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalPolicy {
+    /// Number of distinct approvers that must sign off; at least 1.
+    pub required: usize,
+    /// Who may sign off. Empty means any reviewer assigned as an approver
+    /// when they sign off.
+    #[serde(default)]
+    pub approvers: Vec<String>,
+}
+
+impl ApprovalPolicy {
+    /// Reject policies that could never be satisfied.
+    pub fn validate(&self) -> Result<(), RtError> {
+        if self.required == 0 {
+            return Err(RtError::InvalidInput(
+                "approval policy must require at least one approval".into(),
+            ));
+        }
+        if self.approvers.iter().any(|a| a.trim().is_empty()) {
+            return Err(RtError::InvalidInput("approvers must not be empty".into()));
+        }
+        let mut distinct = self.approvers.clone();
+        distinct.sort();
+        distinct.dedup();
+        if !self.approvers.is_empty() && self.required > distinct.len() {
+            return Err(RtError::InvalidInput(format!(
+                "approval policy requires {} approvals from {} approvers",
+                self.required,
+                distinct.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A workflow's policy and the sign-offs recorded against it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalStatus {
+    pub policy: Option<ApprovalPolicy>,
+    /// Approvers who signed off, in order.
+    pub approved_by: Vec<String>,
+}
+
+impl ApprovalStatus {
+    /// Replay `events` (in `seq` order).
+    pub fn from_events(events: &[WorkflowEvent]) -> Result<Self, RtError> {
+        let mut status = Self::default();
+        for event in events {
+            status.apply(event)?;
+        }
+        Ok(status)
+    }
+
+    /// Take the policy from a `WorkflowCreated` event, or the approver from
+    /// an `ApprovalGranted` one; other events change nothing.
+    pub fn apply(&mut self, event: &WorkflowEvent) -> Result<(), RtError> {
+        match event.event_type {
+            EventType::WorkflowCreated => {
+                if let Some(policy) = event.payload.get(POLICY_KEY) {
+                    self.policy = Some(serde_json::from_value(policy.clone())?);
+                }
+            }
+            EventType::ApprovalGranted => self.approved_by.push(event.actor.clone()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Whether the workflow may complete: it has no policy, or enough
+    /// approvers signed off.
+    pub fn is_satisfied(&self) -> bool {
+        self.policy.as_ref().is_none_or(|p| self.approved_by.len() >= p.required)
+    }
+
+    /// Approvals still missing.
+    pub fn outstanding(&self) -> usize {
+        self.policy
+            .as_ref()
+            .map_or(0, |p| p.required.saturating_sub(self.approved_by.len()))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Event hooks
+// ---------------------------------------------------------------------------
+
+/// Reject an `ApprovalGranted` from `actor` when the workflow has no policy,
+/// `actor` may not approve it, or has already. Other events pass.
+pub(crate) fn check_event(
+    conn: &Connection,
+    workflow_id: Uuid,
+    event_type: &EventType,
+    actor: &str,
+    status: &ApprovalStatus,
+) -> Result<(), RtError> {
+    if *event_type != EventType::ApprovalGranted {
+        return Ok(());
+    }
+    let Some(policy) = &status.policy else {
+        return Err(RtError::InvalidInput(format!(
+            "workflow {workflow_id} has no approval policy"
+        )));
+    };
+    let may_approve = if policy.approvers.is_empty() {
+        assigned_role(conn, workflow_id, actor)? == Some(ReviewerRole::Approver)
+    } else {
+        policy.approvers.iter().any(|a| a == actor)
+    };
+    if !may_approve {
+        return Err(RtError::InvalidInput(format!(
+            "'{actor}' is not an approver of workflow {workflow_id}"
+        )));
+    }
+    if status.approved_by.iter().any(|a| a == actor) {
+        return Err(RtError::InvalidInput(format!(
+            "'{actor}' has already approved workflow {workflow_id}"
+        )));
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_document;
+    use crate::commands::{CreateOptions, WorkflowEngine};
+    use crate::state::WorkflowState;
+    use rt_core::schema::run_migrations;
+    use rt_core::tenant::TenantContext;
+
+    fn policy(required: usize, approvers: &[&str]) -> ApprovalPolicy {
+        ApprovalPolicy {
+            required,
+            approvers: approvers.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// A workflow under `policy`, advanced to `READY_FOR_FINALIZATION`.
+    fn ready(policy: Option<ApprovalPolicy>) -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        insert_document(&conn, doc_id);
+        let tenant = TenantContext::default();
+        let options = CreateOptions {
+            approval_policy: policy,
+            ..CreateOptions::default()
+        };
+        let wf =
+            WorkflowEngine::create_workflow_with_options(&conn, &tenant, doc_id, "alice", &options)
+                .unwrap();
+        for event in [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+            EventType::ReviewClosed,
+            EventType::EditCompilationStarted,
+            EventType::EditCompilationCompleted,
+        ] {
+            let null = serde_json::Value::Null;
+            WorkflowEngine::submit_event(&conn, &tenant, wf.id, event, "system", null).unwrap();
+        }
+        (conn, wf.id)
+    }
+
+    fn submit(conn: &Connection, wf: Uuid, event: EventType, actor: &str) -> Result<(), RtError> {
+        let null = serde_json::Value::Null;
+        WorkflowEngine::submit_event(conn, &TenantContext::default(), wf, event, actor, null)
+            .map(|_| ())
+    }
+
+    #[test]
+    fn completion_waits_for_enough_distinct_approvers() {
+        let (conn, wf) = ready(Some(policy(2, &["carol", "dave", "erin"])));
+        let err = submit(&conn, wf, EventType::WorkflowCompleted, "alice").unwrap_err();
+        assert!(err.to_string().contains("0 of 2 approvals"), "{err}");
+
+        submit(&conn, wf, EventType::ApprovalGranted, "carol").unwrap();
+        let again = submit(&conn, wf, EventType::ApprovalGranted, "carol").unwrap_err();
+        assert!(again.to_string().contains("already approved"), "{again}");
+        let stranger = submit(&conn, wf, EventType::ApprovalGranted, "mallory").unwrap_err();
+        assert!(stranger.to_string().contains("not an approver"), "{stranger}");
+        assert!(submit(&conn, wf, EventType::WorkflowCompleted, "alice").is_err());
+
+        submit(&conn, wf, EventType::ApprovalGranted, "erin").unwrap();
+        submit(&conn, wf, EventType::WorkflowCompleted, "alice").unwrap();
+
+        let tenant = TenantContext::default();
+        let events = WorkflowEngine::get_events(&conn, &tenant, wf).unwrap();
+        let status = ApprovalStatus::from_events(&events).unwrap();
+        assert_eq!(status.approved_by, ["carol", "erin"]);
+        assert!(status.is_satisfied());
+        let workflow = WorkflowEngine::get_workflow(&conn, &tenant, wf).unwrap();
+        assert_eq!(workflow.state, WorkflowState::Completed);
+    }
+
+    #[test]
+    fn open_policies_accept_reviewers_assigned_as_approvers() {
+        let (conn, wf) = ready(Some(policy(1, &[])));
+        let tenant = TenantContext::default();
+        assert!(submit(&conn, wf, EventType::ApprovalGranted, "carol").is_err());
+        conn.execute(
+            "INSERT INTO assignments (workflow_id, reviewer_id, role, assigned_by, assigned_at)
+             VALUES (?1, 'carol', 'approver', 'alice', '2024-01-01T00:00:00Z')",
+            rusqlite::params![wf.to_string()],
+        )
+        .unwrap();
+        submit(&conn, wf, EventType::ApprovalGranted, "carol").unwrap();
+        submit(&conn, wf, EventType::WorkflowCompleted, "alice").unwrap();
+        assert_eq!(
+            WorkflowEngine::get_workflow(&conn, &tenant, wf).unwrap().state,
+            WorkflowState::Completed
+        );
+    }
+
+    #[test]
+    fn workflows_without_a_policy_complete_unapproved() {
+        let (conn, wf) = ready(None);
+        let err = submit(&conn, wf, EventType::ApprovalGranted, "carol").unwrap_err();
+        assert!(err.to_string().contains("no approval policy"), "{err}");
+        submit(&conn, wf, EventType::WorkflowCompleted, "alice").unwrap();
+    }
+
+    #[test]
+    fn unsatisfiable_policies_are_rejected() {
+        assert!(policy(0, &["carol"]).validate().is_err());
+        assert!(policy(2, &["carol", "carol"]).validate().is_err());
+        assert!(policy(1, &[" "]).validate().is_err());
+        assert!(policy(3, &[]).validate().is_ok());
+        assert!(serde_json::from_str::<ApprovalPolicy>(r#"{"required":1,"quorum":2}"#).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! In-process event bus.
+//!
+//! Hosts want to react when a workflow changes state or a merge leaves
+//! conflicts for review without polling. Every workflow event the engine
+//! persists is published here as a [`BusEvent`], and callers that run merges
+//! publish [`BusEvent::MergeConflicts`]; listeners registered with
+//! [`subscribe`] receive them.
+//!
+//! Listeners are called synchronously, on the thread that published the
+//! event and while it still holds its database connection, so they should
+//! return quickly (e.g. by handing the event to a queue). Several threads
+//! may publish at once, so a listener may be called concurrently with
+//! itself. Listeners run under the bus's read lock: [`unsubscribe`] waits
+//! for running calls to finish, and a listener must not subscribe,
+//! unsubscribe or persist workflow events itself.
+//!
+//! A workflow event is published once the transaction it was written in
+//! commits, and never when it rolls back (as [`apply_routing`]'s does when
+//! one of its events is rejected). Until then it is held for its connection
+//! ([`defer`]). The engine publishes the events of the transactions it opens
+//! itself; a caller that submits events inside a transaction of its own
+//! calls [`publish_committed`] once that commits. Events still held when
+//! the engine next opens a transaction on the connection are dropped.
+//!
+//! [`apply_routing`]: crate::routing::apply_routing
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use rusqlite::Connection;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::event::WorkflowEvent;
+use crate::state::WorkflowState;
+
+/// A notification published on the bus.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BusEvent {
+    /// A workflow event was persisted, moving the workflow to `state`.
+    WorkflowEvent {
+        event: WorkflowEvent,
+        state: WorkflowState,
+    },
+    /// A merge finished with conflicts awaiting review.
+    MergeConflicts {
+        merge_id: Uuid,
+        base_doc_id: Uuid,
+        pending_review: usize,
+    },
+}
+
+/// Handle of a registered listener, for [`unsubscribe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Subscription(u64);
+
+type Listener = Arc<dyn Fn(&BusEvent) + Send + Sync>;
+
+static LISTENERS: RwLock<Vec<(Subscription, Listener)>> = RwLock::new(Vec::new());
+static NEXT_SUBSCRIPTION: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// Events written in a transaction that has not committed yet, by the
+    /// address of its connection.
+    static PENDING: RefCell<HashMap<usize, Vec<BusEvent>>> = RefCell::new(HashMap::new());
+}
+
+fn connection_key(conn: &Connection) -> usize {
+    conn as *const Connection as usize
+}
+
+/// Register `listener` for every event published from now on.
+pub fn subscribe(listener: impl Fn(&BusEvent) + Send + Sync + 'static) -> Subscription {
+    let subscription = Subscription(NEXT_SUBSCRIPTION.fetch_add(1, Ordering::Relaxed));
+    let mut listeners = LISTENERS.write().unwrap_or_else(|e| e.into_inner());
+    listeners.push((subscription, Arc::new(listener)));
+    subscription
+}
+
+/// Remove a listener, once none of its calls is still running. Returns
+/// `false` when it was not registered.
+pub fn unsubscribe(subscription: Subscription) -> bool {
+    let mut listeners = LISTENERS.write().unwrap_or_else(|e| e.into_inner());
+    let before = listeners.len();
+    listeners.retain(|(s, _)| *s != subscription);
+    listeners.len() != before
+}
+
+/// Call every registered listener with `event`, in registration order.
+pub fn publish(event: &BusEvent) {
+    let listeners = LISTENERS.read().unwrap_or_else(|e| e.into_inner());
+    for (_, listener) in listeners.iter() {
+        listener(event);
+    }
+}
+
+/// Hold `event`, written on `conn` inside a transaction, until that
+/// transaction commits.
+pub(crate) fn defer(conn: &Connection, event: BusEvent) {
+    PENDING.with(|pending| {
+        pending.borrow_mut().entry(connection_key(conn)).or_default().push(event);
+    });
+}
+
+/// Publish, in the order they were written, the events held for `conn`
+/// once the transaction they were written in has committed.
+pub fn publish_committed(conn: &Connection) {
+    let events = PENDING.with(|pending| pending.borrow_mut().remove(&connection_key(conn)));
+    for event in events.into_iter().flatten() {
+        publish(&event);
+    }
+}
+
+/// Drop the events held for `conn`, whose transaction rolled back.
+pub fn discard_pending(conn: &Connection) {
+    PENDING.with(|pending| pending.borrow_mut().remove(&connection_key(conn)));
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn listeners_receive_events_until_unsubscribed() {
+        let merge_id = Uuid::new_v4();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let subscription = subscribe(move |event| {
+            if let BusEvent::MergeConflicts { merge_id: id, pending_review, .. } = event {
+                sink.lock().unwrap().push((*id, *pending_review));
+            }
+        });
+
+        let event = BusEvent::MergeConflicts {
+            merge_id,
+            base_doc_id: Uuid::new_v4(),
+            pending_review: 2,
+        };
+        publish(&event);
+        assert!(unsubscribe(subscription));
+        assert!(!unsubscribe(subscription));
+        publish(&event);
+
+        // Other tests publish concurrently; count only this test's merge.
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.iter().filter(|(id, _)| *id == merge_id).count(), 1);
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["kind"], "merge_conflicts");
+        assert_eq!(json["pending_review"], 2);
+    }
+}
@@ -0,0 +1,288 @@
+//! Hash chaining of the workflow event log.
+//!
+//! Each event stores the hash of the event before it (`prev_hash`) and a
+//! hash over its own content and that link (`event_hash`); the workflow row
+//! keeps the hash of the newest event (`head_hash`). Editing, reordering or
+//! deleting any chained event — including the last one — therefore changes
+//! a hash that [`verify_chain`] recomputes.
+//!
+//! Events recorded before chaining existed have no hashes. They may only
+//! precede the chained events and are reported as unverified.
+
+use rt_core::tenant::TenantContext;
+use rt_core::{sha256_hex, RtError};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::commands::WorkflowEngine;
+use crate::event::WorkflowEvent;
+
+/// `prev_hash` of the first chained event of a workflow.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// SHA-256 over `prev_hash` and every stored field of `event`, as lowercase
+/// hex. The fields are hashed as a JSON array so no two distinct events
+/// share an input.
+pub fn event_hash(prev_hash: &str, event: &WorkflowEvent) -> String {
+    let input = serde_json::json!([
+        prev_hash,
+        event.id.to_string(),
+        event.workflow_id.to_string(),
+        event.event_type.as_str(),
+        event.actor,
+        event.payload,
+        event.created_at.to_rfc3339(),
+        event.seq,
+    ]);
+    sha256_hex(&input.to_string())
+}
+
+/// Link `event` to the current head of its workflow's chain, store it, and
+/// advance the head; returns the event as stored. The event's own
+/// `prev_hash` / `event_hash` are ignored.
+pub(crate) fn append_event(
+    conn: &Connection,
+    mut event: WorkflowEvent,
+) -> Result<WorkflowEvent, RtError> {
+    let workflow_id = event.workflow_id.to_string();
+    let head: Option<String> = conn
+        .query_row(
+            "SELECT event_hash FROM workflow_events
+              WHERE workflow_id = ?1
+              ORDER BY seq DESC
+              LIMIT 1",
+            rusqlite::params![workflow_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    let prev_hash = head.unwrap_or_else(|| GENESIS_HASH.to_string());
+    let hash = event_hash(&prev_hash, &event);
+    event.prev_hash = Some(prev_hash);
+    event.event_hash = Some(hash);
+
+    conn.execute(
+        "INSERT INTO workflow_events
+            (id, workflow_id, event_type, actor, payload, created_at, seq, prev_hash, event_hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![
+            event.id.to_string(),
+            workflow_id,
+            event.event_type.as_str(),
+            event.actor,
+            event.payload.to_string(),
+            event.created_at.to_rfc3339(),
+            event.seq,
+            event.prev_hash,
+            event.event_hash,
+        ],
+    )?;
+    conn.execute(
+        "UPDATE workflows SET head_hash = ?1 WHERE id = ?2",
+        rusqlite::params![event.event_hash, workflow_id],
+    )?;
+    Ok(event)
+}
+
+/// Outcome of a successful [`verify_chain`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainVerification {
+    pub workflow_id: Uuid,
+    /// Events whose hashes were recomputed and matched.
+    pub verified_events: usize,
+    /// Leading events recorded before the log was chained.
+    pub unverified_events: usize,
+    /// Hash of the newest event; `None` when no event is chained.
+    pub head_hash: Option<String>,
+}
+
+/// Recompute the hash chain of `workflow_id` and check it end to end.
+///
+/// Fails with [`RtError::HashMismatch`] naming the first event (by `seq`)
+/// whose stored hashes disagree with its content or with its predecessor,
+/// and when the newest event is not the recorded head (e.g. it was
+/// deleted). Fails with `NotFound` when the workflow does not exist for
+/// `tenant`.
+pub fn verify_chain(
+    conn: &Connection,
+    tenant: &TenantContext,
+    workflow_id: Uuid,
+) -> Result<ChainVerification, RtError> {
+    let stored_head: Option<Option<String>> = conn
+        .query_row(
+            "SELECT head_hash FROM workflows WHERE id = ?1 AND tenant_id = ?2",
+            rusqlite::params![workflow_id.to_string(), tenant.id()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(stored_head) = stored_head else {
+        return Err(RtError::NotFound(format!("workflow not found: {workflow_id}")));
+    };
+
+    let events = WorkflowEngine::get_events(conn, tenant, workflow_id)?;
+    let mut verified = 0;
+    let mut unverified = 0;
+    let mut head: Option<String> = None;
+
+    for (index, event) in events.iter().enumerate() {
+        let expected_seq = index as i64 + 1;
+        if event.seq != expected_seq {
+            return Err(RtError::HashMismatch {
+                expected: format!("seq {expected_seq}"),
+                actual: format!("seq {} (event {})", event.seq, event.id),
+            });
+        }
+
+        let (Some(prev_hash), Some(hash)) = (&event.prev_hash, &event.event_hash) else {
+            if head.is_some() {
+                return Err(RtError::HashMismatch {
+                    expected: format!("a chained event at seq {}", event.seq),
+                    actual: "an event without hashes".into(),
+                });
+            }
+            unverified += 1;
+            continue;
+        };
+
+        let expected_prev = head.as_deref().unwrap_or(GENESIS_HASH);
+        if prev_hash != expected_prev {
+            return Err(RtError::HashMismatch {
+                expected: expected_prev.to_string(),
+                actual: format!("{prev_hash} as prev_hash of seq {}", event.seq),
+            });
+        }
+        let recomputed = event_hash(prev_hash, event);
+        if *hash != recomputed {
+            return Err(RtError::HashMismatch {
+                expected: recomputed,
+                actual: format!("{hash} as event_hash of seq {}", event.seq),
+            });
+        }
+        head = Some(recomputed);
+        verified += 1;
+    }
+
+    if head != stored_head {
+        return Err(RtError::HashMismatch {
+            expected: format!("head {}", stored_head.as_deref().unwrap_or("<none>")),
+            actual: format!("head {}", head.as_deref().unwrap_or("<none>")),
+        });
+    }
+
+    Ok(ChainVerification {
+        workflow_id,
+        verified_events: verified,
+        unverified_events: unverified,
+        head_hash: head,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_document;
+    use crate::event::EventType;
+    use rt_core::schema::run_migrations;
+
+    fn setup() -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        insert_document(&conn, doc_id);
+
+        let tenant = TenantContext::default();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant, doc_id, "alice").unwrap();
+        for event in [EventType::CompareStarted, EventType::CompareCompleted] {
+            WorkflowEngine::submit_event(&conn, &tenant, wf.id, event, "system", serde_json::Value::Null)
+                .unwrap();
+        }
+        (conn, wf.id)
+    }
+
+    fn verify(conn: &Connection, workflow_id: Uuid) -> Result<ChainVerification, RtError> {
+        verify_chain(conn, &TenantContext::default(), workflow_id)
+    }
+
+    #[test]
+    fn untouched_log_verifies() {
+        let (conn, wf_id) = setup();
+        let result = verify(&conn, wf_id).unwrap();
+        assert_eq!(result.verified_events, 3);
+        assert_eq!(result.unverified_events, 0);
+
+        let events = WorkflowEngine::get_events(&conn, &TenantContext::default(), wf_id).unwrap();
+        assert_eq!(events[0].prev_hash.as_deref(), Some(GENESIS_HASH));
+        assert_eq!(events[1].prev_hash, events[0].event_hash);
+        assert_eq!(result.head_hash, events[2].event_hash);
+    }
+
+    #[test]
+    fn edited_event_breaks_the_chain() {
+        let (conn, wf_id) = setup();
+        conn.execute(
+            "UPDATE workflow_events SET actor = 'mallory' WHERE workflow_id = ?1 AND seq = 2",
+            rusqlite::params![wf_id.to_string()],
+        )
+        .unwrap();
+        let err = verify(&conn, wf_id).unwrap_err();
+        assert!(matches!(err, RtError::HashMismatch { .. }));
+        assert!(err.to_string().contains("seq 2"), "{err}");
+    }
+
+    #[test]
+    fn deleted_events_break_the_chain() {
+        let (conn, wf_id) = setup();
+        conn.execute(
+            "DELETE FROM workflow_events WHERE workflow_id = ?1 AND seq = 3",
+            rusqlite::params![wf_id.to_string()],
+        )
+        .unwrap();
+        let err = verify(&conn, wf_id).unwrap_err();
+        assert!(err.to_string().contains("head"), "{err}");
+
+        let (conn, wf_id) = setup();
+        conn.execute(
+            "DELETE FROM workflow_events WHERE workflow_id = ?1 AND seq = 2",
+            rusqlite::params![wf_id.to_string()],
+        )
+        .unwrap();
+        assert!(matches!(verify(&conn, wf_id), Err(RtError::HashMismatch { .. })));
+    }
+
+    #[test]
+    fn legacy_events_are_reported_unverified() {
+        let (conn, wf_id) = setup();
+        conn.execute(
+            "UPDATE workflow_events SET prev_hash = NULL, event_hash = NULL WHERE workflow_id = ?1",
+            rusqlite::params![wf_id.to_string()],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE workflows SET head_hash = NULL WHERE id = ?1",
+            rusqlite::params![wf_id.to_string()],
+        )
+        .unwrap();
+        let result = verify(&conn, wf_id).unwrap();
+        assert_eq!((result.verified_events, result.unverified_events), (0, 3));
+
+        // New events chain on from the genesis hash.
+        WorkflowEngine::submit_event(
+            &conn,
+            &TenantContext::default(),
+            wf_id,
+            EventType::ReviewStarted,
+            "system",
+            serde_json::Value::Null,
+        )
+        .unwrap();
+        let result = verify(&conn, wf_id).unwrap();
+        assert_eq!((result.verified_events, result.unverified_events), (1, 3));
+
+        assert!(matches!(verify(&conn, Uuid::new_v4()), Err(RtError::NotFound(_))));
+    }
+}
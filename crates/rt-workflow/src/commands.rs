@@ -1,26 +1,144 @@
+use crate::approval::{ApprovalPolicy, ApprovalStatus, POLICY_KEY};
+use crate::bus::{self, BusEvent};
+use crate::deadlines::{validate_deadlines, Deadline, DEADLINES_KEY};
+use crate::chain::append_event;
 use crate::event::{EventType, WorkflowEvent};
-use crate::projector::project_state;
+use crate::projector::Projection;
+use crate::snapshots::{latest_snapshot, record_snapshot, WorkflowSnapshot, SNAPSHOT_INTERVAL};
 use crate::state::{Workflow, WorkflowState};
-use chrono::Utc;
-use rusqlite::Connection;
+use chrono::{DateTime, Utc};
+use rt_core::snapshot::create_snapshot;
+use rt_core::tenant::{ensure_document, TenantContext};
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+use serde::Deserialize;
 use uuid::Uuid;
 
+/// Options controlling [`WorkflowEngine::create_workflow_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    /// Sign-off the workflow needs before it can complete (see
+    /// [`approval`](crate::approval)); recorded in its `WorkflowCreated`
+    /// payload. `None` lets it complete unapproved.
+    pub approval_policy: Option<ApprovalPolicy>,
+    /// How long the workflow may stay in each state (see
+    /// [`deadlines`](crate::deadlines)); recorded in its `WorkflowCreated`
+    /// payload. Empty sets none.
+    pub deadlines: Vec<Deadline>,
+}
+
+/// Options controlling [`WorkflowEngine::submit_event_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct SubmitOptions {
+    /// When `true`, a `CompareStarted` event for a workflow id that does not
+    /// exist first creates that workflow (with its `WorkflowCreated` event)
+    /// bound to `document_id`, instead of failing with `NotFound`.
+    pub auto_create: bool,
+    /// Document the auto-created workflow is bound to. Required when
+    /// `auto_create` is set and the workflow does not exist.
+    pub document_id: Option<Uuid>,
+    /// When `true`, a `ReviewClosed` event first snapshots the workflow's
+    /// document (see [`create_snapshot`]) and records the snapshot's id in
+    /// the event payload as `snapshot_id`. Other events ignore it.
+    pub snapshot_on_close: bool,
+}
+
+/// Default page size of [`WorkflowEngine::list_workflows`].
+pub const DEFAULT_LIST_LIMIT: usize = 100;
+/// Largest page size [`WorkflowEngine::list_workflows`] accepts.
+pub const MAX_LIST_LIMIT: usize = 1000;
+
+/// Criteria for [`WorkflowEngine::list_workflows`]; every field is optional
+/// and unset fields do not filter.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkflowFilter {
+    pub document_id: Option<Uuid>,
+    pub state: Option<WorkflowState>,
+    pub initiator_id: Option<String>,
+    /// Inclusive lower bound on `created_at`.
+    pub created_from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on `created_at`.
+    pub created_to: Option<DateTime<Utc>>,
+    /// Number of matching workflows to skip.
+    pub offset: usize,
+    /// Page size; defaults to [`DEFAULT_LIST_LIMIT`], at most
+    /// [`MAX_LIST_LIMIT`].
+    pub limit: Option<usize>,
+}
+
+/// Workflow commands and queries. Every call acts for one tenant: workflows
+/// are created under it, and workflows and events of other tenants are
+/// reported as not found.
+///
+/// Commands take any connection, pooled or not. Each one writes in a single
+/// transaction of its own, so a workflow's row never disagrees with its
+/// event log; on a connection already inside a transaction it joins that
+/// one instead, and commits or rolls back with the caller.
 pub struct WorkflowEngine;
 
 impl WorkflowEngine {
     /// Insert a new workflow row into `workflows`, emit a `WorkflowCreated`
-    /// event at seq=1, and return the resulting `Workflow`.
+    /// event at seq=1, and return the resulting `Workflow`. The document must
+    /// belong to `tenant`.
     pub fn create_workflow(
         conn: &Connection,
+        tenant: &TenantContext,
+        document_id: Uuid,
+        initiator_id: &str,
+    ) -> Result<Workflow, rt_core::RtError> {
+        Self::create_workflow_with_options(
+            conn,
+            tenant,
+            document_id,
+            initiator_id,
+            &CreateOptions::default(),
+        )
+    }
+
+    /// As [`create_workflow`](Self::create_workflow), with [`CreateOptions`].
+    /// `InvalidInput` when the approval policy could never be satisfied or
+    /// the deadlines are invalid.
+    pub fn create_workflow_with_options(
+        conn: &Connection,
+        tenant: &TenantContext,
         document_id: Uuid,
         initiator_id: &str,
+        options: &CreateOptions,
     ) -> Result<Workflow, rt_core::RtError> {
+        let mut payload = serde_json::Map::new();
+        if let Some(policy) = &options.approval_policy {
+            policy.validate()?;
+            payload.insert(POLICY_KEY.into(), serde_json::to_value(policy)?);
+        }
+        if !options.deadlines.is_empty() {
+            validate_deadlines(&options.deadlines)?;
+            payload.insert(DEADLINES_KEY.into(), serde_json::to_value(&options.deadlines)?);
+        }
         let wf = Workflow::new(document_id, initiator_id);
+        in_transaction(conn, |conn| {
+            Self::insert_workflow(conn, tenant, &wf, None, serde_json::Value::Object(payload))
+        })?;
+        Ok(wf)
+    }
+
+    /// Persist `wf`, a sub-workflow of `parent_id` if set, and its
+    /// `WorkflowCreated` event (seq=1) carrying `payload`. The event is
+    /// published once the write is committed.
+    pub(crate) fn insert_workflow(
+        conn: &Connection,
+        tenant: &TenantContext,
+        wf: &Workflow,
+        parent_id: Option<Uuid>,
+        payload: serde_json::Value,
+    ) -> Result<(), rt_core::RtError> {
+        ensure_document(conn, tenant, &wf.document_id)?;
         let now_str = wf.created_at.to_rfc3339();
 
         conn.execute(
-            "INSERT INTO workflows (id, document_id, state, initiator_id, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO workflows
+                (id, document_id, state, initiator_id, created_at, updated_at, tenant_id,
+                 parent_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             rusqlite::params![
                 wf.id.to_string(),
                 wf.document_id.to_string(),
@@ -28,82 +146,230 @@ impl WorkflowEngine {
                 wf.initiator_id,
                 now_str,
                 now_str,
+                tenant.id(),
+                parent_id.map(|id| id.to_string()),
             ],
         )?;
 
-        let event_id = Uuid::new_v4();
-        conn.execute(
-            "INSERT INTO workflow_events (id, workflow_id, event_type, actor, payload, created_at, seq)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            rusqlite::params![
-                event_id.to_string(),
-                wf.id.to_string(),
-                EventType::WorkflowCreated.as_str(),
-                initiator_id,
-                "{}",
-                now_str,
-                1i64,
-            ],
+        let event = append_event(
+            conn,
+            WorkflowEvent {
+                id: Uuid::new_v4(),
+                workflow_id: wf.id,
+                event_type: EventType::WorkflowCreated,
+                actor: wf.initiator_id.clone(),
+                payload,
+                created_at: wf.created_at,
+                seq: 1,
+                prev_hash: None,
+                event_hash: None,
+            },
         )?;
-
-        Ok(wf)
+        bus::defer(
+            conn,
+            BusEvent::WorkflowEvent {
+                event,
+                state: wf.state.clone(),
+            },
+        );
+        Ok(())
     }
 
     /// Validate and apply `event_type` to the workflow identified by
-    /// `workflow_id`.  Persists the event and updates the workflow row.
-    /// Returns the updated `Workflow`.
+    /// `workflow_id`.  Persists the event, updates the workflow row and
+    /// publishes the event on the [bus](crate::bus). Returns the updated
+    /// `Workflow`.
+    ///
+    /// `payload` must match the event's schema (see
+    /// [`payload_schema`](crate::validator::payload_schema)); a malformed
+    /// payload fails with `InvalidInput` and nothing is written. So does a
+    /// `DeltaSubmitted` from an `actor` not assigned to submit; assignment
+    /// events also update the [`reviewers`](crate::reviewers) tables. A
+    /// `ReviewClosed` waits for the workflow's
+    /// [`subworkflows`](crate::subworkflows) to finish, and a state change of
+    /// a sub-workflow is recorded in its parent's log as well.
     pub fn submit_event(
         conn: &Connection,
+        tenant: &TenantContext,
+        workflow_id: Uuid,
+        event_type: EventType,
+        actor: &str,
+        payload: serde_json::Value,
+    ) -> Result<Workflow, rt_core::RtError> {
+        Self::submit_event_with_options(
+            conn,
+            tenant,
+            workflow_id,
+            event_type,
+            actor,
+            payload,
+            &SubmitOptions::default(),
+        )
+    }
+
+    /// As [`submit_event`](Self::submit_event), with [`SubmitOptions`].
+    ///
+    /// With `auto_create`, a `CompareStarted` on an unknown `workflow_id`
+    /// creates the workflow under that id (initiated by `actor`) before the
+    /// event is applied, so the log still begins with `WorkflowCreated` at
+    /// seq=1. Any other event on an unknown workflow still fails with
+    /// `NotFound`.
+    ///
+    /// With `snapshot_on_close`, a `ReviewClosed` event is written only once
+    /// the snapshot of the workflow's document has been stored.
+    ///
+    /// The state is read, the event appended at the next `seq` and the row
+    /// updated in one transaction, together with any workflow created or
+    /// parent updated along the way; the events are published once it
+    /// commits. When `conn` is already inside a transaction, the events wait
+    /// for that one to commit (see [`bus::publish_committed`]).
+    pub fn submit_event_with_options(
+        conn: &Connection,
+        tenant: &TenantContext,
         workflow_id: Uuid,
         event_type: EventType,
         actor: &str,
         payload: serde_json::Value,
+        options: &SubmitOptions,
     ) -> Result<Workflow, rt_core::RtError> {
+        in_transaction(conn, |conn| {
+            Self::write_event(conn, tenant, workflow_id, event_type, actor, payload, options)
+        })?;
+
+        // Return the full projected workflow (re-loads to include the new event).
+        Self::get_workflow(conn, tenant, workflow_id)
+    }
+
+    /// The writes of [`submit_event_with_options`](Self::submit_event_with_options),
+    /// on a connection inside a transaction. The events are published once
+    /// it commits.
+    fn write_event(
+        conn: &Connection,
+        tenant: &TenantContext,
+        workflow_id: Uuid,
+        event_type: EventType,
+        actor: &str,
+        mut payload: serde_json::Value,
+        options: &SubmitOptions,
+    ) -> Result<(), rt_core::RtError> {
         // Load current projected state.
-        let current = Self::get_workflow(conn, workflow_id)?;
+        let current = match Self::get_workflow(conn, tenant, workflow_id) {
+            Err(rt_core::RtError::NotFound(_))
+                if options.auto_create && event_type == EventType::CompareStarted =>
+            {
+                Self::bootstrap_workflow(conn, tenant, workflow_id, actor, options.document_id)?
+            }
+            other => other?,
+        };
+
+        // Only completion and sign-off depend on the approvals, so only they
+        // replay the log for them.
+        let approvals =
+            if matches!(event_type, EventType::WorkflowCompleted | EventType::ApprovalGranted) {
+                ApprovalStatus::from_events(&Self::get_events(conn, tenant, workflow_id)?)?
+            } else {
+                ApprovalStatus::default()
+            };
 
-        // Validate the transition upfront so we fail fast without writing.
-        let new_state = crate::validator::validate_transition(&current.state, &event_type)?;
+        // Validate the transition and payload before writing anything.
+        let new_state =
+            crate::validator::validate_transition(&current.state, &event_type, &approvals)?;
+        crate::validator::validate_payload(&event_type, &payload)?;
+        crate::reviewers::check_event(conn, workflow_id, &event_type, actor, &payload)?;
+        crate::approval::check_event(conn, workflow_id, &event_type, actor, &approvals)?;
+        crate::subworkflows::check_event(conn, tenant, workflow_id, &event_type, &payload)?;
+
+        if options.snapshot_on_close && event_type == EventType::ReviewClosed {
+            let snapshot = create_snapshot(conn, tenant, &current.document_id)?;
+            let mut fields = payload.as_object().cloned().unwrap_or_default();
+            fields.insert("snapshot_id".into(), serde_json::json!(snapshot.id));
+            payload = serde_json::Value::Object(fields);
+        }
 
         let seq = Self::next_seq(conn, workflow_id)?;
         let now = Utc::now();
         let now_str = now.to_rfc3339();
-        let event_id = Uuid::new_v4();
 
-        conn.execute(
-            "INSERT INTO workflow_events (id, workflow_id, event_type, actor, payload, created_at, seq)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            rusqlite::params![
-                event_id.to_string(),
-                workflow_id.to_string(),
-                event_type.as_str(),
-                actor,
-                payload.to_string(),
-                now_str,
+        let event = append_event(
+            conn,
+            WorkflowEvent {
+                id: Uuid::new_v4(),
+                workflow_id,
+                event_type,
+                actor: actor.to_string(),
+                payload,
+                created_at: now,
                 seq,
-            ],
+                prev_hash: None,
+                event_hash: None,
+            },
         )?;
+        crate::reviewers::record_event(conn, tenant, &event)?;
 
         conn.execute(
             "UPDATE workflows SET state = ?1, updated_at = ?2 WHERE id = ?3",
             rusqlite::params![new_state.as_str(), now_str, workflow_id.to_string()],
         )?;
+        if seq % SNAPSHOT_INTERVAL == 0 {
+            let projection = Self::project(conn, tenant, workflow_id)?;
+            record_snapshot(conn, &WorkflowSnapshot::of(&projection))?;
+        }
+        crate::subworkflows::record_event(conn, tenant, &current, &new_state, actor)?;
+        bus::defer(
+            conn,
+            BusEvent::WorkflowEvent {
+                event,
+                state: new_state,
+            },
+        );
+        Ok(())
+    }
 
-        // Return the full projected workflow (re-loads to include the new event).
-        Self::get_workflow(conn, workflow_id)
+    /// Create workflow `workflow_id` on behalf of an auto-creating submit.
+    fn bootstrap_workflow(
+        conn: &Connection,
+        tenant: &TenantContext,
+        workflow_id: Uuid,
+        actor: &str,
+        document_id: Option<Uuid>,
+    ) -> Result<Workflow, rt_core::RtError> {
+        let document_id = document_id.ok_or_else(|| {
+            rt_core::RtError::InvalidInput(format!(
+                "auto_create for unknown workflow {workflow_id} requires a document_id"
+            ))
+        })?;
+
+        let wf = Workflow {
+            id: workflow_id,
+            ..Workflow::new(document_id, actor)
+        };
+        let payload = serde_json::json!({ "auto_created": true });
+        Self::insert_workflow(conn, tenant, &wf, None, payload)?;
+        Ok(wf)
     }
 
     /// Load a workflow by id, replay all of its events, and return the
-    /// resulting `Workflow`.  Returns `RtError::NotFound` when no row exists.
+    /// resulting `Workflow`.  Returns `RtError::NotFound` when no row exists
+    /// for `tenant`.
     pub fn get_workflow(
         conn: &Connection,
+        tenant: &TenantContext,
         workflow_id: Uuid,
     ) -> Result<Workflow, rt_core::RtError> {
+        Ok(Self::project(conn, tenant, workflow_id)?.workflow)
+    }
+
+    /// The replay behind [`get_workflow`](Self::get_workflow), resumable.
+    fn project(
+        conn: &Connection,
+        tenant: &TenantContext,
+        workflow_id: Uuid,
+    ) -> Result<Projection, rt_core::RtError> {
         let wf = conn
             .query_row(
                 "SELECT id, document_id, state, initiator_id, created_at, updated_at
-                 FROM workflows WHERE id = ?1",
-                rusqlite::params![workflow_id.to_string()],
+                 FROM workflows WHERE id = ?1 AND tenant_id = ?2",
+                rusqlite::params![workflow_id.to_string(), tenant.id()],
                 |row| {
                     let id_str: String = row.get(0)?;
                     let doc_id_str: String = row.get(1)?;
@@ -155,30 +421,130 @@ impl WorkflowEngine {
         // We use the snapshot directly because the DB row already stores the
         // current state; however we still replay to keep the projector as the
         // single source of truth for timestamps and state.
-        // Build a base workflow at Draft so we replay from the very beginning.
-        let base = Workflow {
-            state: WorkflowState::Draft,
-            updated_at: snapshot.created_at,
-            ..snapshot.clone()
+        // Resume from the latest state snapshot, or build a base workflow at
+        // Draft so we replay from the very beginning.
+        let start = match latest_snapshot(conn, workflow_id)? {
+            Some(state_snapshot) => state_snapshot.resume_from(&snapshot),
+            None => Projection::start(&Workflow {
+                state: WorkflowState::Draft,
+                updated_at: snapshot.created_at,
+                ..snapshot.clone()
+            }),
         };
 
-        let events = Self::get_events(conn, workflow_id)?;
-        project_state(&base, &events)
+        let events = Self::events_after(conn, tenant, workflow_id, start.seq)?;
+        start.resume(&events)
+    }
+
+    /// Workflows matching `filter`, oldest first, one page at a time. The
+    /// state reported is the current state recorded on the workflow row.
+    pub fn list_workflows(
+        conn: &Connection,
+        tenant: &TenantContext,
+        filter: &WorkflowFilter,
+    ) -> Result<Vec<Workflow>, rt_core::RtError> {
+        let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+        if limit == 0 || limit > MAX_LIST_LIMIT {
+            return Err(rt_core::RtError::InvalidInput(format!(
+                "limit must be between 1 and {MAX_LIST_LIMIT}, got {limit}"
+            )));
+        }
+        if let (Some(from), Some(to)) = (filter.created_from, filter.created_to) {
+            if from > to {
+                return Err(rt_core::RtError::InvalidInput(format!(
+                    "created_from {from} is after created_to {to}"
+                )));
+            }
+        }
+
+        // `created_at` is written with `to_rfc3339()`; bounds formatted the
+        // same way compare correctly as text.
+        let mut stmt = conn.prepare(
+            "SELECT id, document_id, state, initiator_id, created_at, updated_at
+               FROM workflows
+              WHERE tenant_id = ?8
+                AND (?1 IS NULL OR document_id = ?1)
+                AND (?2 IS NULL OR state = ?2)
+                AND (?3 IS NULL OR initiator_id = ?3)
+                AND (?4 IS NULL OR created_at >= ?4)
+                AND (?5 IS NULL OR created_at < ?5)
+              ORDER BY created_at ASC, rowid ASC
+              LIMIT ?6 OFFSET ?7",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![
+                filter.document_id.map(|id| id.to_string()),
+                filter.state.as_ref().map(|s| s.as_str()),
+                filter.initiator_id,
+                filter.created_from.map(|t| t.to_rfc3339()),
+                filter.created_to.map(|t| t.to_rfc3339()),
+                limit as i64,
+                filter.offset as i64,
+                tenant.id(),
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            },
+        )?;
+
+        let parse_time = |s: &str| {
+            s.parse::<DateTime<Utc>>()
+                .map_err(|e| rt_core::RtError::InvalidInput(e.to_string()))
+        };
+        let mut workflows = Vec::new();
+        for row in rows {
+            let r = row?;
+            workflows.push(Workflow {
+                id: Uuid::parse_str(&r.0)
+                    .map_err(|e| rt_core::RtError::InvalidInput(e.to_string()))?,
+                document_id: Uuid::parse_str(&r.1)
+                    .map_err(|e| rt_core::RtError::InvalidInput(e.to_string()))?,
+                state: WorkflowState::from_str(&r.2)?,
+                initiator_id: r.3.unwrap_or_default(),
+                created_at: parse_time(&r.4)?,
+                updated_at: parse_time(&r.5)?,
+            });
+        }
+        Ok(workflows)
     }
 
-    /// Return all events for `workflow_id` sorted by `seq` ascending.
+    /// Return all events for `workflow_id` sorted by `seq` ascending; empty
+    /// when the workflow belongs to another tenant.
     pub fn get_events(
         conn: &Connection,
+        tenant: &TenantContext,
+        workflow_id: Uuid,
+    ) -> Result<Vec<WorkflowEvent>, rt_core::RtError> {
+        Self::events_after(conn, tenant, workflow_id, 0)
+    }
+
+    /// The events of [`get_events`](Self::get_events) with a `seq` above
+    /// `after`.
+    fn events_after(
+        conn: &Connection,
+        tenant: &TenantContext,
         workflow_id: Uuid,
+        after: i64,
     ) -> Result<Vec<WorkflowEvent>, rt_core::RtError> {
         let mut stmt = conn.prepare(
-            "SELECT id, workflow_id, event_type, actor, payload, created_at, seq
+            "SELECT id, workflow_id, event_type, actor, payload, created_at, seq,
+                    prev_hash, event_hash
              FROM workflow_events
              WHERE workflow_id = ?1
+               AND workflow_id IN (SELECT id FROM workflows WHERE tenant_id = ?2)
+               AND seq > ?3
              ORDER BY seq ASC",
         )?;
 
-        let rows = stmt.query_map(rusqlite::params![workflow_id.to_string()], |row| {
+        let params = rusqlite::params![workflow_id.to_string(), tenant.id(), after];
+        let rows = stmt.query_map(params, |row| {
             let id_str: String = row.get(0)?;
             let wid_str: String = row.get(1)?;
             let et_str: String = row.get(2)?;
@@ -186,6 +552,8 @@ impl WorkflowEngine {
             let payload_str: String = row.get(4)?;
             let created_at_str: String = row.get(5)?;
             let seq: i64 = row.get(6)?;
+            let prev_hash: Option<String> = row.get(7)?;
+            let event_hash: Option<String> = row.get(8)?;
             Ok((
                 id_str,
                 wid_str,
@@ -194,6 +562,8 @@ impl WorkflowEngine {
                 payload_str,
                 created_at_str,
                 seq,
+                prev_hash,
+                event_hash,
             ))
         })?;
 
@@ -218,6 +588,8 @@ impl WorkflowEngine {
                 payload,
                 created_at,
                 seq: r.6,
+                prev_hash: r.7,
+                event_hash: r.8,
             });
         }
         Ok(events)
@@ -234,27 +606,60 @@ impl WorkflowEngine {
     }
 }
 
+/// Run `f` in a transaction on `conn` and commit it when `f` succeeds, then
+/// publish the bus events written in it ([`bus::defer`]); when it rolls
+/// back they are dropped. The transaction is immediate, so concurrent
+/// writers queue before `f` reads anything it decides on. When `conn` is
+/// already inside a transaction, `f` runs in that one and its events wait
+/// for whoever opened it to commit.
+pub(crate) fn in_transaction<T>(
+    conn: &Connection,
+    f: impl FnOnce(&Connection) -> Result<T, rt_core::RtError>,
+) -> Result<T, rt_core::RtError> {
+    if !conn.is_autocommit() {
+        return f(conn);
+    }
+    // Events of an earlier transaction its opener never published.
+    bus::discard_pending(conn);
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    let out = f(&tx).and_then(|out| {
+        tx.commit()?;
+        Ok(out)
+    });
+    match out {
+        Ok(_) => bus::publish_committed(conn),
+        Err(_) => bus::discard_pending(conn),
+    }
+    out
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
 
+/// Insert a minimal documents row so that foreign-key constraints are met;
+/// shared by the tests of every module that creates workflows.
+#[cfg(test)]
+pub(crate) fn insert_document(conn: &rusqlite::Connection, doc_id: Uuid) {
+    conn.execute(
+        "INSERT INTO documents
+         (id, name, doc_type, schema_version, normalization_version,
+          hash_contract_version, ingested_at, metadata)
+         VALUES (?1, 'test-doc', 'CONTRACT', '1.0.0', '1.0.0', '1.0.0',
+                 '2024-01-01T00:00:00Z', '{}')",
+        rusqlite::params![doc_id.to_string()],
+    )
+    .expect("insert document");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rt_core::schema::run_migrations;
     use rusqlite::Connection;
 
-    /// Insert a minimal documents row so that foreign-key constraints are met.
-    fn insert_document(conn: &Connection, doc_id: Uuid) {
-        conn.execute(
-            "INSERT INTO documents
-             (id, name, doc_type, schema_version, normalization_version,
-              hash_contract_version, ingested_at, metadata)
-             VALUES (?1, 'test-doc', 'CONTRACT', '1.0.0', '1.0.0', '1.0.0',
-                     '2024-01-01T00:00:00Z', '{}')",
-            rusqlite::params![doc_id.to_string()],
-        )
-        .expect("insert document");
+    fn tenant() -> TenantContext {
+        TenantContext::default()
     }
 
     fn setup() -> (Connection, Uuid) {
@@ -268,14 +673,14 @@ mod tests {
     #[test]
     fn create_workflow_persists_and_returns_draft() {
         let (conn, doc_id) = setup();
-        let wf = WorkflowEngine::create_workflow(&conn, doc_id, "alice")
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice")
             .expect("create_workflow should succeed");
         assert_eq!(wf.state, WorkflowState::Draft);
         assert_eq!(wf.initiator_id, "alice");
         assert_eq!(wf.document_id, doc_id);
 
         // Event should exist.
-        let events = WorkflowEngine::get_events(&conn, wf.id).expect("get_events");
+        let events = WorkflowEngine::get_events(&conn, &tenant(), wf.id).expect("get_events");
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].event_type, EventType::WorkflowCreated);
         assert_eq!(events[0].seq, 1);
@@ -284,7 +689,7 @@ mod tests {
     #[test]
     fn get_unknown_workflow_returns_not_found() {
         let (conn, _) = setup();
-        let result = WorkflowEngine::get_workflow(&conn, Uuid::new_v4());
+        let result = WorkflowEngine::get_workflow(&conn, &tenant(), Uuid::new_v4());
         assert!(
             matches!(result, Err(rt_core::RtError::NotFound(_))),
             "expected NotFound, got {:?}",
@@ -295,30 +700,36 @@ mod tests {
     #[test]
     fn full_lifecycle_eight_events_to_completed() {
         let (conn, doc_id) = setup();
-        let wf = WorkflowEngine::create_workflow(&conn, doc_id, "alice").unwrap();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
         let wid = wf.id;
 
-        let steps: Vec<(EventType, &str)> = vec![
-            (EventType::CompareStarted, "system"),
-            (EventType::CompareCompleted, "system"),
-            (EventType::ReviewStarted, "alice"),
-            (EventType::ReviewerAssigned, "alice"),
-            (EventType::DeltaSubmitted, "bob"),
-            (EventType::ReviewClosed, "alice"),
-            (EventType::EditCompilationStarted, "system"),
-            (EventType::EditCompilationCompleted, "system"),
+        let null = serde_json::Value::Null;
+        let steps: Vec<(EventType, &str, serde_json::Value)> = vec![
+            (EventType::CompareStarted, "system", null.clone()),
+            (EventType::CompareCompleted, "system", null.clone()),
+            (EventType::ReviewStarted, "alice", null.clone()),
+            (
+                EventType::ReviewerAssigned,
+                "alice",
+                serde_json::json!({ "reviewer_id": "bob" }),
+            ),
+            (
+                EventType::DeltaSubmitted,
+                "bob",
+                serde_json::json!({
+                    "layer_id": Uuid::new_v4().to_string(),
+                    "delta_ids": [Uuid::new_v4().to_string()],
+                }),
+            ),
+            (EventType::ReviewClosed, "alice", null.clone()),
+            (EventType::EditCompilationStarted, "system", null.clone()),
+            (EventType::EditCompilationCompleted, "system", null),
         ];
 
         let mut last_wf = wf;
-        for (et, actor) in steps {
-            last_wf = WorkflowEngine::submit_event(
-                &conn,
-                wid,
-                et,
-                actor,
-                serde_json::Value::Null,
-            )
-            .expect("submit_event should succeed");
+        for (et, actor, payload) in steps {
+            last_wf = WorkflowEngine::submit_event(&conn, &tenant(), wid, et, actor, payload)
+                .expect("submit_event should succeed");
         }
 
         assert_eq!(
@@ -330,6 +741,7 @@ mod tests {
         // Final event to reach Completed.
         let final_wf = WorkflowEngine::submit_event(
             &conn,
+            &tenant(),
             wid,
             EventType::WorkflowCompleted,
             "alice",
@@ -339,16 +751,17 @@ mod tests {
         assert_eq!(final_wf.state, WorkflowState::Completed);
 
         // Total events: 1 (WorkflowCreated) + 8 + 1 = 10
-        let events = WorkflowEngine::get_events(&conn, wid).unwrap();
+        let events = WorkflowEngine::get_events(&conn, &tenant(), wid).unwrap();
         assert_eq!(events.len(), 10);
     }
 
     #[test]
     fn abort_from_draft() {
         let (conn, doc_id) = setup();
-        let wf = WorkflowEngine::create_workflow(&conn, doc_id, "alice").unwrap();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
         let result = WorkflowEngine::submit_event(
             &conn,
+            &tenant(),
             wf.id,
             EventType::WorkflowAborted,
             "alice",
@@ -361,7 +774,7 @@ mod tests {
     #[test]
     fn abort_from_in_review() {
         let (conn, doc_id) = setup();
-        let wf = WorkflowEngine::create_workflow(&conn, doc_id, "alice").unwrap();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
         let wid = wf.id;
 
         for et in [
@@ -369,12 +782,13 @@ mod tests {
             EventType::CompareCompleted,
             EventType::ReviewStarted,
         ] {
-            WorkflowEngine::submit_event(&conn, wid, et, "system", serde_json::Value::Null)
+            WorkflowEngine::submit_event(&conn, &tenant(), wid, et, "system", serde_json::Value::Null)
                 .unwrap();
         }
 
         let result = WorkflowEngine::submit_event(
             &conn,
+            &tenant(),
             wid,
             EventType::WorkflowAborted,
             "alice",
@@ -387,7 +801,7 @@ mod tests {
     #[test]
     fn abort_from_review_closed() {
         let (conn, doc_id) = setup();
-        let wf = WorkflowEngine::create_workflow(&conn, doc_id, "alice").unwrap();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
         let wid = wf.id;
 
         for et in [
@@ -396,12 +810,13 @@ mod tests {
             EventType::ReviewStarted,
             EventType::ReviewClosed,
         ] {
-            WorkflowEngine::submit_event(&conn, wid, et, "system", serde_json::Value::Null)
+            WorkflowEngine::submit_event(&conn, &tenant(), wid, et, "system", serde_json::Value::Null)
                 .unwrap();
         }
 
         let result = WorkflowEngine::submit_event(
             &conn,
+            &tenant(),
             wid,
             EventType::WorkflowAborted,
             "alice",
@@ -414,7 +829,7 @@ mod tests {
     #[test]
     fn abort_from_completed_fails() {
         let (conn, doc_id) = setup();
-        let wf = WorkflowEngine::create_workflow(&conn, doc_id, "alice").unwrap();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
         let wid = wf.id;
 
         for et in [
@@ -426,12 +841,13 @@ mod tests {
             EventType::EditCompilationCompleted,
             EventType::WorkflowCompleted,
         ] {
-            WorkflowEngine::submit_event(&conn, wid, et, "system", serde_json::Value::Null)
+            WorkflowEngine::submit_event(&conn, &tenant(), wid, et, "system", serde_json::Value::Null)
                 .unwrap();
         }
 
         let result = WorkflowEngine::submit_event(
             &conn,
+            &tenant(),
             wid,
             EventType::WorkflowAborted,
             "alice",
@@ -442,4 +858,459 @@ mod tests {
             "aborting a Completed workflow should fail"
         );
     }
+
+    #[test]
+    fn malformed_payload_is_rejected_without_writing() {
+        let (conn, doc_id) = setup();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
+        let wid = wf.id;
+
+        for et in [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+        ] {
+            WorkflowEngine::submit_event(&conn, &tenant(), wid, et, "system", serde_json::Value::Null)
+                .unwrap();
+        }
+
+        let result = WorkflowEngine::submit_event(
+            &conn,
+            &tenant(),
+            wid,
+            EventType::DeltaSubmitted,
+            "bob",
+            serde_json::json!({ "layer_id": Uuid::new_v4().to_string() }),
+        );
+        match result {
+            Err(rt_core::RtError::InvalidInput(msg)) => assert!(msg.contains("delta_ids"), "{msg}"),
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+        assert_eq!(WorkflowEngine::get_events(&conn, &tenant(), wid).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn failed_state_update_rolls_back_the_event() {
+        let (conn, doc_id) = setup();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
+        let head = |conn: &Connection| -> Option<String> {
+            conn.query_row(
+                "SELECT head_hash FROM workflows WHERE id = ?1",
+                rusqlite::params![wf.id.to_string()],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        let before = head(&conn);
+        conn.execute_batch(
+            "CREATE TRIGGER fail_state BEFORE UPDATE OF state ON workflows
+             BEGIN SELECT RAISE(ABORT, 'disk full'); END;",
+        )
+        .unwrap();
+
+        let null = serde_json::Value::Null;
+        let result = WorkflowEngine::submit_event(
+            &conn,
+            &tenant(),
+            wf.id,
+            EventType::CompareStarted,
+            "system",
+            null.clone(),
+        );
+        assert!(result.is_err());
+        assert!(conn.is_autocommit());
+        assert_eq!(WorkflowEngine::get_events(&conn, &tenant(), wf.id).unwrap().len(), 1);
+        assert_eq!(head(&conn), before);
+
+        conn.execute_batch("DROP TRIGGER fail_state;").unwrap();
+        let wf = WorkflowEngine::submit_event(
+            &conn,
+            &tenant(),
+            wf.id,
+            EventType::CompareStarted,
+            "system",
+            null,
+        )
+        .unwrap();
+        assert_eq!(wf.state, WorkflowState::CompareRunning);
+        let events = WorkflowEngine::get_events(&conn, &tenant(), wf.id).unwrap();
+        assert_eq!(events.last().unwrap().seq, 2);
+    }
+
+    #[test]
+    fn submits_join_the_callers_transaction() {
+        let (conn, doc_id) = setup();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        for event in [EventType::CompareStarted, EventType::CompareCompleted] {
+            let null = serde_json::Value::Null;
+            WorkflowEngine::submit_event(&tx, &tenant(), wf.id, event, "system", null).unwrap();
+        }
+        drop(tx);
+
+        let wf = WorkflowEngine::get_workflow(&conn, &tenant(), wf.id).unwrap();
+        assert_eq!(wf.state, WorkflowState::Draft);
+        assert_eq!(WorkflowEngine::get_events(&conn, &tenant(), wf.id).unwrap().len(), 1);
+    }
+
+    /// The seqs of `workflow_id`'s events published while `f` runs.
+    fn published_seqs(workflow_id: Uuid, f: impl FnOnce()) -> Vec<i64> {
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let subscription = bus::subscribe(move |event| {
+            if let BusEvent::WorkflowEvent { event, .. } = event {
+                // Other tests publish concurrently; keep only this workflow's events.
+                if event.workflow_id == workflow_id {
+                    sink.lock().unwrap().push(event.seq);
+                }
+            }
+        });
+        f();
+        bus::unsubscribe(subscription);
+        let seen = seen.lock().unwrap().clone();
+        seen
+    }
+
+    #[test]
+    fn events_of_a_rolled_back_transaction_are_never_published() {
+        let (conn, doc_id) = setup();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
+
+        let seen = published_seqs(wf.id, || {
+            let tx = conn.unchecked_transaction().unwrap();
+            let (started, null) = (EventType::CompareStarted, serde_json::Value::Null);
+            WorkflowEngine::submit_event(&tx, &tenant(), wf.id, started, "system", null).unwrap();
+            drop(tx);
+            // A later transaction of the engine's own publishes only its events.
+            let (started, null) = (EventType::CompareStarted, serde_json::Value::Null);
+            WorkflowEngine::submit_event(&conn, &tenant(), wf.id, started, "system", null).unwrap();
+        });
+        assert_eq!(seen, vec![2]);
+    }
+
+    #[test]
+    fn events_of_a_callers_transaction_are_published_once_it_commits() {
+        let (conn, doc_id) = setup();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
+
+        let seen = published_seqs(wf.id, || {
+            let tx = conn.unchecked_transaction().unwrap();
+            for event in [EventType::CompareStarted, EventType::CompareCompleted] {
+                let null = serde_json::Value::Null;
+                WorkflowEngine::submit_event(&tx, &tenant(), wf.id, event, "system", null).unwrap();
+            }
+            tx.commit().unwrap();
+            bus::publish_committed(&conn);
+        });
+        assert_eq!(seen, vec![2, 3]);
+    }
+
+    #[test]
+    fn compare_started_on_unknown_workflow_fails_by_default() {
+        let (conn, _) = setup();
+        let result = WorkflowEngine::submit_event(
+            &conn,
+            &tenant(),
+            Uuid::new_v4(),
+            EventType::CompareStarted,
+            "system",
+            serde_json::Value::Null,
+        );
+        assert!(matches!(result, Err(rt_core::RtError::NotFound(_))));
+    }
+
+    #[test]
+    fn auto_create_bootstraps_unknown_workflow() {
+        let (conn, doc_id) = setup();
+        let wid = Uuid::new_v4();
+        let options = SubmitOptions {
+            auto_create: true,
+            document_id: Some(doc_id),
+            ..Default::default()
+        };
+
+        let wf = WorkflowEngine::submit_event_with_options(
+            &conn,
+            &tenant(),
+            wid,
+            EventType::CompareStarted,
+            "system",
+            serde_json::Value::Null,
+            &options,
+        )
+        .expect("auto-create should succeed");
+        assert_eq!(wf.id, wid);
+        assert_eq!(wf.document_id, doc_id);
+        assert_eq!(wf.state, WorkflowState::CompareRunning);
+
+        let events = WorkflowEngine::get_events(&conn, &tenant(), wid).unwrap();
+        let types: Vec<_> = events.iter().map(|e| (e.event_type.clone(), e.seq)).collect();
+        assert_eq!(
+            types,
+            vec![(EventType::WorkflowCreated, 1), (EventType::CompareStarted, 2)]
+        );
+        assert_eq!(events[0].payload["auto_created"], true);
+
+        // A second CompareStarted hits the existing workflow and is rejected
+        // by the state machine rather than creating anything.
+        let again = WorkflowEngine::submit_event_with_options(
+            &conn,
+            &tenant(),
+            wid,
+            EventType::CompareStarted,
+            "system",
+            serde_json::Value::Null,
+            &options,
+        );
+        assert!(matches!(again, Err(rt_core::RtError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn auto_create_requires_document_id_and_compare_started() {
+        let (conn, _) = setup();
+        let wid = Uuid::new_v4();
+        let no_doc = SubmitOptions {
+            auto_create: true,
+            document_id: None,
+            ..Default::default()
+        };
+        let result = WorkflowEngine::submit_event_with_options(
+            &conn,
+            &tenant(),
+            wid,
+            EventType::CompareStarted,
+            "system",
+            serde_json::Value::Null,
+            &no_doc,
+        );
+        assert!(matches!(result, Err(rt_core::RtError::InvalidInput(_))));
+
+        let (conn, doc_id) = setup();
+        let with_doc = SubmitOptions {
+            auto_create: true,
+            document_id: Some(doc_id),
+            ..Default::default()
+        };
+        let result = WorkflowEngine::submit_event_with_options(
+            &conn,
+            &tenant(),
+            wid,
+            EventType::ReviewStarted,
+            "system",
+            serde_json::Value::Null,
+            &with_doc,
+        );
+        assert!(matches!(result, Err(rt_core::RtError::NotFound(_))));
+        assert!(WorkflowEngine::get_events(&conn, &tenant(), wid).unwrap().is_empty());
+    }
+
+    #[test]
+    fn persisted_events_are_published_with_the_new_state() {
+        let (conn, doc_id) = setup();
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let subscription = bus::subscribe(move |event| {
+            if let BusEvent::WorkflowEvent { event, state } = event {
+                sink.lock().unwrap().push((event.workflow_id, event.seq, state.clone()));
+            }
+        });
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
+        WorkflowEngine::submit_event(
+            &conn,
+            &tenant(),
+            wf.id,
+            EventType::CompareStarted,
+            "system",
+            serde_json::Value::Null,
+        )
+        .unwrap();
+        bus::unsubscribe(subscription);
+
+        // Other tests publish concurrently; keep only this workflow's events.
+        let seen: Vec<_> = seen
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _, _)| *id == wf.id)
+            .map(|(_, seq, state)| (*seq, state.clone()))
+            .collect();
+        assert_eq!(seen, [(1, WorkflowState::Draft), (2, WorkflowState::CompareRunning)]);
+    }
+
+    #[test]
+    fn review_close_snapshots_the_document_when_asked() {
+        let (conn, doc_id) = setup();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
+        let steps = [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+        ];
+        for et in steps {
+            let null = serde_json::Value::Null;
+            WorkflowEngine::submit_event(&conn, &tenant(), wf.id, et, "alice", null).unwrap();
+        }
+        let options = SubmitOptions {
+            snapshot_on_close: true,
+            ..Default::default()
+        };
+        WorkflowEngine::submit_event_with_options(
+            &conn,
+            &tenant(),
+            wf.id,
+            EventType::ReviewClosed,
+            "alice",
+            serde_json::Value::Null,
+            &options,
+        )
+        .unwrap();
+
+        let events = WorkflowEngine::get_events(&conn, &tenant(), wf.id).unwrap();
+        let snapshot_id = events.last().unwrap().payload["snapshot_id"].as_str().unwrap();
+        let (doc_type, snapshot_of): (String, String) = conn
+            .query_row(
+                "SELECT doc_type, json_extract(metadata, '$.snapshot_of')
+                   FROM documents WHERE id = ?1",
+                rusqlite::params![snapshot_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(doc_type, "snapshot");
+        assert_eq!(snapshot_of, doc_id.to_string());
+    }
+
+    // -----------------------------------------------------------------------
+    // list_workflows
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn list_workflows_filters_and_paginates() {
+        let (conn, doc_a) = setup();
+        let doc_b = Uuid::new_v4();
+        insert_document(&conn, doc_b);
+
+        let first = WorkflowEngine::create_workflow(&conn, &tenant(), doc_a, "alice").unwrap();
+        let second = WorkflowEngine::create_workflow(&conn, &tenant(), doc_a, "bob").unwrap();
+        let third = WorkflowEngine::create_workflow(&conn, &tenant(), doc_b, "alice").unwrap();
+        WorkflowEngine::submit_event(
+            &conn,
+            &tenant(),
+            second.id,
+            EventType::CompareStarted,
+            "system",
+            serde_json::Value::Null,
+        )
+        .unwrap();
+
+        let ids = |filter: WorkflowFilter| -> Vec<Uuid> {
+            WorkflowEngine::list_workflows(&conn, &tenant(), &filter)
+                .unwrap()
+                .into_iter()
+                .map(|w| w.id)
+                .collect()
+        };
+
+        assert_eq!(ids(WorkflowFilter::default()), vec![first.id, second.id, third.id]);
+        assert_eq!(
+            ids(WorkflowFilter {
+                document_id: Some(doc_a),
+                ..WorkflowFilter::default()
+            }),
+            vec![first.id, second.id]
+        );
+        assert_eq!(
+            ids(WorkflowFilter {
+                state: Some(WorkflowState::CompareRunning),
+                ..WorkflowFilter::default()
+            }),
+            vec![second.id]
+        );
+        assert_eq!(
+            ids(WorkflowFilter {
+                initiator_id: Some("alice".into()),
+                offset: 1,
+                limit: Some(5),
+                ..WorkflowFilter::default()
+            }),
+            vec![third.id]
+        );
+        assert!(ids(WorkflowFilter {
+            created_from: Some(third.created_at + chrono::Duration::seconds(1)),
+            ..WorkflowFilter::default()
+        })
+        .is_empty());
+        assert_eq!(
+            ids(WorkflowFilter {
+                created_from: Some(first.created_at),
+                created_to: Some(third.created_at + chrono::Duration::seconds(1)),
+                ..WorkflowFilter::default()
+            })
+            .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn list_workflows_rejects_bad_pages_and_ranges() {
+        let (conn, _) = setup();
+        let zero = WorkflowFilter {
+            limit: Some(0),
+            ..WorkflowFilter::default()
+        };
+        assert!(WorkflowEngine::list_workflows(&conn, &tenant(), &zero).is_err());
+
+        let now = Utc::now();
+        let inverted = WorkflowFilter {
+            created_from: Some(now),
+            created_to: Some(now - chrono::Duration::hours(1)),
+            ..WorkflowFilter::default()
+        };
+        assert!(WorkflowEngine::list_workflows(&conn, &tenant(), &inverted).is_err());
+
+        let parsed: WorkflowFilter =
+            serde_json::from_str(r#"{"state": "IN_REVIEW", "limit": 10}"#).unwrap();
+        assert_eq!(parsed.state, Some(WorkflowState::InReview));
+        assert!(serde_json::from_str::<WorkflowFilter>(r#"{"status": "x"}"#).is_err());
+    }
+
+    // -----------------------------------------------------------------------
+    // Tenant isolation
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn workflows_of_other_tenants_are_invisible() {
+        let (conn, doc_id) = setup();
+        let acme = TenantContext::new("acme").unwrap();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant(), doc_id, "alice").unwrap();
+
+        assert!(matches!(
+            WorkflowEngine::get_workflow(&conn, &acme, wf.id),
+            Err(rt_core::RtError::NotFound(_))
+        ));
+        assert!(WorkflowEngine::get_events(&conn, &acme, wf.id).unwrap().is_empty());
+        assert!(WorkflowEngine::list_workflows(&conn, &acme, &WorkflowFilter::default())
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            WorkflowEngine::submit_event(
+                &conn,
+                &acme,
+                wf.id,
+                EventType::CompareStarted,
+                "mallory",
+                serde_json::Value::Null,
+            ),
+            Err(rt_core::RtError::NotFound(_))
+        ));
+        // Nor can a workflow be opened on another tenant's document.
+        assert!(matches!(
+            WorkflowEngine::create_workflow(&conn, &acme, doc_id, "mallory"),
+            Err(rt_core::RtError::NotFound(_))
+        ));
+
+        let own = WorkflowEngine::get_workflow(&conn, &tenant(), wf.id).unwrap();
+        assert_eq!(own.state, WorkflowState::Draft);
+        assert_eq!(WorkflowEngine::get_events(&conn, &tenant(), wf.id).unwrap().len(), 1);
+    }
 }
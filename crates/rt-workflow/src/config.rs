@@ -0,0 +1,158 @@
+//! Per-workflow configuration overrides.
+//!
+//! A matter may need a stricter similarity threshold or different
+//! auto-accept rules than the global defaults. The overrides are stored on
+//! the workflow row as two opaque JSON objects — one of compare options, one
+//! of merge options — and are layered under the options of any compare or
+//! merge run invoked in that workflow's context: options passed with the run
+//! win, the workflow's overrides fill in the rest, and the engines' defaults
+//! cover whatever neither sets.
+//!
+//! The sections are not interpreted here (the workflow crate does not depend
+//! on the engines); callers validate them with the engines' own option
+//! parsers before storing them.
+
+use rt_core::tenant::TenantContext;
+use rt_core::RtError;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+/// Option overrides stored with a workflow.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkflowConfig {
+    /// Compare options, in the shape the compare engine accepts.
+    pub compare: Map<String, Value>,
+    /// Merge options, in the shape the merge engine accepts.
+    pub merge: Map<String, Value>,
+}
+
+impl WorkflowConfig {
+    /// Parse overrides from JSON; an empty string means no overrides.
+    pub fn from_json(json: &str) -> Result<Self, RtError> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(json)
+            .map_err(|e| RtError::InvalidInput(format!("invalid workflow config: {e}")))
+    }
+
+    /// True when neither section overrides anything.
+    pub fn is_empty(&self) -> bool {
+        self.compare.is_empty() && self.merge.is_empty()
+    }
+}
+
+/// `overrides` with every key of `explicit` laid over it: options given for
+/// a run take precedence over the workflow's overrides.
+pub fn layer_options(overrides: &Map<String, Value>, explicit: Map<String, Value>) -> Map<String, Value> {
+    let mut layered = overrides.clone();
+    layered.extend(explicit);
+    layered
+}
+
+/// The overrides stored with `workflow_id`; `NotFound` when the workflow
+/// does not exist for `tenant`.
+pub fn get_config(
+    conn: &Connection,
+    tenant: &TenantContext,
+    workflow_id: Uuid,
+) -> Result<WorkflowConfig, RtError> {
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT config_overrides FROM workflows WHERE id = ?1 AND tenant_id = ?2",
+            rusqlite::params![workflow_id.to_string(), tenant.id()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match stored {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Err(RtError::NotFound(format!("workflow not found: {workflow_id}"))),
+    }
+}
+
+/// Replace the overrides stored with `workflow_id`; `NotFound` when the
+/// workflow does not exist for `tenant`.
+pub fn set_config(
+    conn: &Connection,
+    tenant: &TenantContext,
+    workflow_id: Uuid,
+    config: &WorkflowConfig,
+) -> Result<(), RtError> {
+    let updated = conn.execute(
+        "UPDATE workflows SET config_overrides = ?1 WHERE id = ?2 AND tenant_id = ?3",
+        rusqlite::params![serde_json::to_string(config)?, workflow_id.to_string(), tenant.id()],
+    )?;
+    if updated == 0 {
+        return Err(RtError::NotFound(format!("workflow not found: {workflow_id}")));
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_document;
+    use crate::commands::WorkflowEngine;
+    use rt_core::schema::run_migrations;
+    use serde_json::json;
+
+    fn setup() -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        insert_document(&conn, doc_id);
+        let wf = WorkflowEngine::create_workflow(&conn, &TenantContext::default(), doc_id, "alice")
+            .unwrap();
+        (conn, wf.id)
+    }
+
+    #[test]
+    fn new_workflows_have_no_overrides_and_updates_round_trip() {
+        let (conn, wf) = setup();
+        let tenant = TenantContext::default();
+        assert!(get_config(&conn, &tenant, wf).unwrap().is_empty());
+
+        let config = WorkflowConfig::from_json(
+            r#"{"compare":{"similarity_threshold":0.9},"merge":{"auto_accept_deletions":false}}"#,
+        )
+        .unwrap();
+        set_config(&conn, &tenant, wf, &config).unwrap();
+        assert_eq!(get_config(&conn, &tenant, wf).unwrap(), config);
+    }
+
+    #[test]
+    fn other_tenants_and_unknown_workflows_are_not_found() {
+        let (conn, wf) = setup();
+        let other = TenantContext::new("other").unwrap();
+        assert!(matches!(get_config(&conn, &other, wf), Err(RtError::NotFound(_))));
+        let err = set_config(&conn, &other, wf, &WorkflowConfig::default()).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+        let err = get_config(&conn, &TenantContext::default(), Uuid::new_v4()).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+    }
+
+    #[test]
+    fn explicit_options_win_over_overrides() {
+        let config = WorkflowConfig::from_json(
+            r#"{"compare":{"similarity_threshold":0.9,"detect_moves":false}}"#,
+        )
+        .unwrap();
+        let explicit = json!({"similarity_threshold": 0.6}).as_object().unwrap().clone();
+        let layered = layer_options(&config.compare, explicit);
+        assert_eq!(layered["similarity_threshold"], json!(0.6));
+        assert_eq!(layered["detect_moves"], json!(false));
+
+        assert!(WorkflowConfig::from_json("").unwrap().is_empty());
+        assert!(matches!(
+            WorkflowConfig::from_json(r#"{"export":{}}"#),
+            Err(RtError::InvalidInput(_))
+        ));
+    }
+}
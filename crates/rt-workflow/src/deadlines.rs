@@ -0,0 +1,337 @@
+//! Per-state deadlines and SLA breaches.
+//!
+//! A workflow may be created with [`Deadline`]s: how long it may stay in a
+//! state ("review must close within 5 business days"). They travel in the
+//! `WorkflowCreated` payload as `deadlines`. The clock of a state starts
+//! when the workflow enters it, so each stay is timed afresh.
+//!
+//! Nothing here runs on its own: the host calls [`check_deadlines`] on a
+//! schedule, which records a `DeadlineBreached` event (from `system`) for
+//! every workflow overdue in its current state, once per stay. Like any
+//! event it is published on the [bus](crate::bus), where hosts hang their
+//! escalation logic; it leaves the state unchanged.
+//!
+//! Business days skip Saturdays and Sundays; holidays are not known.
+
+use chrono::{DateTime, Datelike, Duration, Utc, Weekday};
+use rt_core::tenant::TenantContext;
+use rt_core::RtError;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::approval::ApprovalStatus;
+use crate::commands::WorkflowEngine;
+use crate::event::{EventType, WorkflowEvent};
+use crate::state::WorkflowState;
+use crate::validator::validate_transition;
+
+/// Key of the deadlines in a `WorkflowCreated` payload.
+pub const DEADLINES_KEY: &str = "deadlines";
+
+/// Actor of the `DeadlineBreached` events [`check_deadlines`] records.
+pub const DEADLINE_ACTOR: &str = "system";
+
+/// How long a workflow may stay in one state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Deadline {
+    pub state: WorkflowState,
+    pub within: DeadlineSpan,
+}
+
+/// A length of time, as `{"hours": n}` or `{"business_days": n}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadlineSpan {
+    Hours(u32),
+    BusinessDays(u32),
+}
+
+impl DeadlineSpan {
+    /// When a span starting at `start` ends.
+    pub fn due_from(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        match *self {
+            DeadlineSpan::Hours(hours) => start + Duration::hours(hours.into()),
+            DeadlineSpan::BusinessDays(days) => {
+                let mut due = start;
+                let mut left = days;
+                while left > 0 {
+                    due += Duration::days(1);
+                    if !matches!(due.weekday(), Weekday::Sat | Weekday::Sun) {
+                        left -= 1;
+                    }
+                }
+                due
+            }
+        }
+    }
+}
+
+/// Reject deadlines that are empty, on a terminal state, or set twice for
+/// one state.
+pub fn validate_deadlines(deadlines: &[Deadline]) -> Result<(), RtError> {
+    for (i, deadline) in deadlines.iter().enumerate() {
+        if matches!(deadline.state, WorkflowState::Completed | WorkflowState::Aborted) {
+            return Err(RtError::InvalidInput(format!(
+                "{} is terminal and cannot have a deadline",
+                deadline.state.as_str()
+            )));
+        }
+        if matches!(deadline.within, DeadlineSpan::Hours(0) | DeadlineSpan::BusinessDays(0)) {
+            return Err(RtError::InvalidInput(format!(
+                "deadline for {} must be longer than zero",
+                deadline.state.as_str()
+            )));
+        }
+        if deadlines[..i].iter().any(|d| d.state == deadline.state) {
+            return Err(RtError::InvalidInput(format!(
+                "{} has more than one deadline",
+                deadline.state.as_str()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The deadline a workflow is running against in its current state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadlineStatus {
+    pub state: WorkflowState,
+    /// When the workflow entered `state`.
+    pub entered_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    /// Whether a `DeadlineBreached` event was recorded during this stay.
+    pub breached: bool,
+}
+
+/// A breach recorded by [`check_deadlines`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadlineBreach {
+    pub workflow_id: Uuid,
+    pub state: WorkflowState,
+    pub due_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Replay `events` (a workflow's whole log, in `seq` order) and return the
+/// deadline of the state it ended in, or `None` when that state has none.
+pub fn deadline_status(events: &[WorkflowEvent]) -> Result<Option<DeadlineStatus>, RtError> {
+    let Some(first) = events.first() else {
+        return Ok(None);
+    };
+    let deadlines: Vec<Deadline> = match first.payload.get(DEADLINES_KEY) {
+        Some(deadlines) => serde_json::from_value(deadlines.clone())?,
+        None => return Ok(None),
+    };
+
+    let mut state = WorkflowState::Draft;
+    let mut entered_at = first.created_at;
+    let mut breached = false;
+    let mut approvals = ApprovalStatus::default();
+    for event in events {
+        let next = validate_transition(&state, &event.event_type, &approvals)?;
+        approvals.apply(event)?;
+        if next != state {
+            state = next;
+            entered_at = event.created_at;
+            breached = false;
+        }
+        if event.event_type == EventType::DeadlineBreached {
+            breached = true;
+        }
+    }
+
+    Ok(deadlines.iter().find(|d| d.state == state).map(|d| DeadlineStatus {
+        state: state.clone(),
+        entered_at,
+        due_at: d.within.due_from(entered_at),
+        breached,
+    }))
+}
+
+/// Record a `DeadlineBreached` event for every workflow, of any tenant,
+/// that is past due in its current state at `now` and has not been
+/// reported during this stay. Returns the breaches recorded.
+pub fn check_deadlines(
+    conn: &Connection,
+    now: DateTime<Utc>,
+) -> Result<Vec<DeadlineBreach>, RtError> {
+    let mut stmt = conn.prepare(
+        "SELECT w.id, w.tenant_id
+           FROM workflows w
+           JOIN workflow_events e ON e.workflow_id = w.id AND e.seq = 1
+          WHERE w.state NOT IN ('COMPLETED', 'ABORTED')
+            AND json_extract(e.payload, '$.deadlines') IS NOT NULL
+          ORDER BY w.created_at ASC, w.rowid ASC",
+    )?;
+    let candidates = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut breaches = Vec::new();
+    for (id, tenant_id) in candidates {
+        let workflow_id =
+            Uuid::parse_str(&id).map_err(|e| RtError::InvalidInput(e.to_string()))?;
+        let tenant = TenantContext::new(tenant_id)?;
+        let events = WorkflowEngine::get_events(conn, &tenant, workflow_id)?;
+        let Some(status) = deadline_status(&events)? else {
+            continue;
+        };
+        if status.breached || now < status.due_at {
+            continue;
+        }
+        WorkflowEngine::submit_event(
+            conn,
+            &tenant,
+            workflow_id,
+            EventType::DeadlineBreached,
+            DEADLINE_ACTOR,
+            serde_json::json!({
+                "state": status.state,
+                "entered_at": status.entered_at,
+                "due_at": status.due_at,
+            }),
+        )?;
+        breaches.push(DeadlineBreach {
+            workflow_id,
+            state: status.state,
+            due_at: status.due_at,
+        });
+    }
+    Ok(breaches)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_document;
+    use crate::commands::CreateOptions;
+    use chrono::TimeZone;
+    use rt_core::schema::run_migrations;
+
+    fn setup(deadlines: Vec<Deadline>) -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        insert_document(&conn, doc_id);
+        let options = CreateOptions {
+            deadlines,
+            ..CreateOptions::default()
+        };
+        let tenant = TenantContext::default();
+        let wf =
+            WorkflowEngine::create_workflow_with_options(&conn, &tenant, doc_id, "alice", &options)
+                .unwrap();
+        (conn, wf.id)
+    }
+
+    fn submit(conn: &Connection, wf: Uuid, event: EventType) {
+        let null = serde_json::Value::Null;
+        WorkflowEngine::submit_event(conn, &TenantContext::default(), wf, event, "system", null)
+            .unwrap();
+    }
+
+    fn review_within(span: DeadlineSpan) -> Vec<Deadline> {
+        vec![Deadline {
+            state: WorkflowState::InReview,
+            within: span,
+        }]
+    }
+
+    #[test]
+    fn business_days_skip_weekends() {
+        // Thursday 2024-06-06, 09:00.
+        let thursday = Utc.with_ymd_and_hms(2024, 6, 6, 9, 0, 0).unwrap();
+        let due = DeadlineSpan::BusinessDays(5).due_from(thursday);
+        assert_eq!(due, Utc.with_ymd_and_hms(2024, 6, 13, 9, 0, 0).unwrap());
+        let saturday = Utc.with_ymd_and_hms(2024, 6, 8, 12, 0, 0).unwrap();
+        let due = DeadlineSpan::BusinessDays(1).due_from(saturday);
+        assert_eq!(due, Utc.with_ymd_and_hms(2024, 6, 10, 12, 0, 0).unwrap());
+        let due = DeadlineSpan::Hours(36).due_from(thursday);
+        assert_eq!(due, Utc.with_ymd_and_hms(2024, 6, 7, 21, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn overdue_workflows_are_reported_once_per_stay() {
+        let (conn, wf) = setup(review_within(DeadlineSpan::Hours(48)));
+        let tenant = TenantContext::default();
+        let later = Utc::now() + Duration::days(30);
+        assert!(check_deadlines(&conn, later).unwrap().is_empty(), "Draft has no deadline");
+
+        let to_review = [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+        ];
+        for event in to_review {
+            submit(&conn, wf, event);
+        }
+        let status = deadline_status(&WorkflowEngine::get_events(&conn, &tenant, wf).unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.state, WorkflowState::InReview);
+        assert_eq!(status.due_at, status.entered_at + Duration::hours(48));
+        assert!(check_deadlines(&conn, status.due_at - Duration::minutes(1)).unwrap().is_empty());
+
+        let breaches = check_deadlines(&conn, later).unwrap();
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].workflow_id, wf);
+        assert_eq!(breaches[0].state, WorkflowState::InReview);
+        assert!(check_deadlines(&conn, later).unwrap().is_empty(), "reported once");
+
+        let workflow = WorkflowEngine::get_workflow(&conn, &tenant, wf).unwrap();
+        assert_eq!(workflow.state, WorkflowState::InReview);
+        let events = WorkflowEngine::get_events(&conn, &tenant, wf).unwrap();
+        let breach = events.last().unwrap();
+        assert_eq!(breach.event_type, EventType::DeadlineBreached);
+        assert_eq!(breach.actor, DEADLINE_ACTOR);
+        assert_eq!(breach.payload["state"], "IN_REVIEW");
+
+        submit(&conn, wf, EventType::ReviewClosed);
+        assert!(check_deadlines(&conn, later).unwrap().is_empty());
+    }
+
+    #[test]
+    fn workflows_without_deadlines_are_never_breached() {
+        let (conn, wf) = setup(Vec::new());
+        let to_review = [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+        ];
+        for event in to_review {
+            submit(&conn, wf, event);
+        }
+        let later = Utc::now() + Duration::days(365);
+        assert!(check_deadlines(&conn, later).unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_deadlines_are_rejected() {
+        let twice = [
+            review_within(DeadlineSpan::Hours(1)),
+            review_within(DeadlineSpan::BusinessDays(2)),
+        ]
+        .concat();
+        assert!(validate_deadlines(&twice).is_err());
+        assert!(validate_deadlines(&review_within(DeadlineSpan::BusinessDays(0))).is_err());
+        let terminal = vec![Deadline {
+            state: WorkflowState::Completed,
+            within: DeadlineSpan::Hours(1),
+        }];
+        assert!(validate_deadlines(&terminal).is_err());
+
+        let json = r#"[{"state": "IN_REVIEW", "within": {"business_days": 5}}]"#;
+        let parsed: Vec<Deadline> = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed, review_within(DeadlineSpan::BusinessDays(5)));
+        assert!(validate_deadlines(&parsed).is_ok());
+    }
+}
@@ -8,14 +8,25 @@ pub enum EventType {
     WorkflowCreated,
     CompareStarted,
     CompareCompleted,
+    /// Periodic progress of a long compare run; leaves the state unchanged.
+    CompareProgress,
+    /// The workflow overstayed its deadline in its current state (see
+    /// [`deadlines`](crate::deadlines)); leaves the state unchanged.
+    DeadlineBreached,
     FlowCreated,
     ReviewStarted,
     ReviewerAssigned,
+    ReviewerUnassigned,
+    /// A sub-workflow changed state (see
+    /// [`subworkflows`](crate::subworkflows)); leaves the state unchanged.
+    SubworkflowUpdated,
     DeltaSubmitted,
     ReviewClosed,
     EditCompilationStarted,
     EditCompilationCompleted,
     FinalizationReady,
+    /// Sign-off by one approver; see [`approval`](crate::approval).
+    ApprovalGranted,
     WorkflowCompleted,
     WorkflowAborted,
 }
@@ -26,32 +37,43 @@ impl EventType {
             EventType::WorkflowCreated => "workflow_created",
             EventType::CompareStarted => "compare_started",
             EventType::CompareCompleted => "compare_completed",
+            EventType::CompareProgress => "compare_progress",
+            EventType::DeadlineBreached => "deadline_breached",
             EventType::FlowCreated => "flow_created",
             EventType::ReviewStarted => "review_started",
             EventType::ReviewerAssigned => "reviewer_assigned",
+            EventType::ReviewerUnassigned => "reviewer_unassigned",
+            EventType::SubworkflowUpdated => "subworkflow_updated",
             EventType::DeltaSubmitted => "delta_submitted",
             EventType::ReviewClosed => "review_closed",
             EventType::EditCompilationStarted => "edit_compilation_started",
             EventType::EditCompilationCompleted => "edit_compilation_completed",
             EventType::FinalizationReady => "finalization_ready",
+            EventType::ApprovalGranted => "approval_granted",
             EventType::WorkflowCompleted => "workflow_completed",
             EventType::WorkflowAborted => "workflow_aborted",
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Result<Self, rt_core::RtError> {
         match s {
             "workflow_created" => Ok(EventType::WorkflowCreated),
             "compare_started" => Ok(EventType::CompareStarted),
             "compare_completed" => Ok(EventType::CompareCompleted),
+            "compare_progress" => Ok(EventType::CompareProgress),
+            "deadline_breached" => Ok(EventType::DeadlineBreached),
             "flow_created" => Ok(EventType::FlowCreated),
             "review_started" => Ok(EventType::ReviewStarted),
             "reviewer_assigned" => Ok(EventType::ReviewerAssigned),
+            "reviewer_unassigned" => Ok(EventType::ReviewerUnassigned),
+            "subworkflow_updated" => Ok(EventType::SubworkflowUpdated),
             "delta_submitted" => Ok(EventType::DeltaSubmitted),
             "review_closed" => Ok(EventType::ReviewClosed),
             "edit_compilation_started" => Ok(EventType::EditCompilationStarted),
             "edit_compilation_completed" => Ok(EventType::EditCompilationCompleted),
             "finalization_ready" => Ok(EventType::FinalizationReady),
+            "approval_granted" => Ok(EventType::ApprovalGranted),
             "workflow_completed" => Ok(EventType::WorkflowCompleted),
             "workflow_aborted" => Ok(EventType::WorkflowAborted),
             other => Err(rt_core::RtError::InvalidInput(format!(
@@ -70,6 +92,14 @@ pub struct WorkflowEvent {
     pub payload: serde_json::Value,
     pub created_at: DateTime<Utc>,
     pub seq: i64,
+    /// Hash of the preceding event, or [`GENESIS_HASH`](crate::chain::GENESIS_HASH)
+    /// for the first chained event. `None` for events recorded before the
+    /// log was hash-chained.
+    #[serde(default)]
+    pub prev_hash: Option<String>,
+    /// [`event_hash`](crate::chain::event_hash) of this event.
+    #[serde(default)]
+    pub event_hash: Option<String>,
 }
 
 #[cfg(test)]
@@ -82,14 +112,19 @@ mod tests {
             EventType::WorkflowCreated,
             EventType::CompareStarted,
             EventType::CompareCompleted,
+            EventType::CompareProgress,
+            EventType::DeadlineBreached,
             EventType::FlowCreated,
             EventType::ReviewStarted,
             EventType::ReviewerAssigned,
+            EventType::ReviewerUnassigned,
+            EventType::SubworkflowUpdated,
             EventType::DeltaSubmitted,
             EventType::ReviewClosed,
             EventType::EditCompilationStarted,
             EventType::EditCompilationCompleted,
             EventType::FinalizationReady,
+            EventType::ApprovalGranted,
             EventType::WorkflowCompleted,
             EventType::WorkflowAborted,
         ];
@@ -3,7 +3,33 @@ pub mod state;
 pub mod projector;
 pub mod validator;
 pub mod commands;
+pub mod chain;
+pub mod config;
+pub mod routing;
+pub mod progress;
+pub mod bus;
+pub mod reviewers;
+pub mod approval;
+pub mod deadlines;
+pub mod subworkflows;
+pub mod snapshots;
+pub mod machine;
 
 pub use state::*;
 pub use event::*;
-pub use commands::WorkflowEngine;
+pub use commands::{CreateOptions, SubmitOptions, WorkflowEngine, WorkflowFilter};
+pub use approval::{ApprovalPolicy, ApprovalStatus};
+pub use deadlines::{check_deadlines, Deadline, DeadlineBreach, DeadlineSpan, DeadlineStatus};
+pub use bus::{
+    discard_pending, publish, publish_committed, subscribe, unsubscribe, BusEvent, Subscription,
+};
+pub use chain::{verify_chain, ChainVerification};
+pub use config::{get_config, layer_options, set_config, WorkflowConfig};
+pub use progress::{record_progress, ProgressSettings, ProgressThrottle};
+pub use reviewers::{Assignment, Reviewer, ReviewerEngine, ReviewerRole};
+pub use machine::{current_machine, install_machine, reset_machine, StateMachine, Transition};
+pub use snapshots::{latest_snapshot, WorkflowSnapshot, SNAPSHOT_INTERVAL};
+pub use subworkflows::{create_subworkflow, list_subworkflows, Subworkflow, Subworkflows};
+pub use routing::{
+    apply_routing, plan_routing, route_sections, RoutingReason, RoutingRules, RoutingSuggestion,
+};
@@ -0,0 +1,309 @@
+//! Declarative workflow state machines.
+//!
+//! Which event moves a workflow from which state to which is a table of
+//! [`Transition`]s, a [`StateMachine`], rather than code. The built-in
+//! machine ([`StateMachine::builtin`]) is the RT_Flow lifecycle; an
+//! organization with another review process installs its own at start-up
+//! with [`install_machine`], e.g. sending a closed review back to
+//! `IN_REVIEW` for another negotiation round:
+//!
+//! ```json
+//! {"transitions": [
+//!   {"from": "DRAFT", "event": "workflow_created", "to": "DRAFT"},
+//!   {"from": "REVIEW_CLOSED", "event": "review_started", "to": "IN_REVIEW"},
+//!   ...
+//! ]}
+//! ```
+//!
+//! A machine is built from the existing [`WorkflowState`]s and
+//! [`EventType`]s, and some rules hold under every machine: `COMPLETED` and
+//! `ABORTED` are terminal, progress breadcrumbs and deadline breaches are
+//! legal in every other state, and `workflow_completed` waits for the
+//! approval policy. Logs are replayed under the machine installed, so a
+//! machine should only be narrowed once no stored workflow relies on the
+//! transitions it drops.
+
+use std::sync::{Arc, OnceLock, RwLock};
+
+use rt_core::RtError;
+use serde::{Deserialize, Serialize};
+
+use crate::approval::ApprovalStatus;
+use crate::event::EventType;
+use crate::state::WorkflowState;
+
+/// One allowed move: `event` takes a workflow in `from` to `to`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Transition {
+    pub from: WorkflowState,
+    pub event: EventType,
+    pub to: WorkflowState,
+}
+
+/// The transitions a workflow may take; any other (state, event) pair is
+/// illegal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateMachine {
+    pub transitions: Vec<Transition>,
+}
+
+/// Events legal in every state but the terminal ones, whatever the machine.
+const BREADCRUMBS: [EventType; 2] = [EventType::CompareProgress, EventType::DeadlineBreached];
+
+static BUILTIN: OnceLock<Arc<StateMachine>> = OnceLock::new();
+static INSTALLED: RwLock<Option<Arc<StateMachine>>> = RwLock::new(None);
+
+impl StateMachine {
+    /// The RT_Flow lifecycle: compare, review, compile edits, finalize.
+    pub fn builtin() -> Self {
+        use EventType as E;
+        use WorkflowState as S;
+        let table = [
+            (S::Draft, E::WorkflowCreated, S::Draft),
+            (S::Draft, E::CompareStarted, S::CompareRunning),
+            (S::Draft, E::WorkflowAborted, S::Aborted),
+            (S::CompareRunning, E::CompareCompleted, S::FlowCreated),
+            (S::FlowCreated, E::ReviewStarted, S::InReview),
+            (S::InReview, E::ReviewerAssigned, S::InReview),
+            (S::InReview, E::ReviewerUnassigned, S::InReview),
+            (S::InReview, E::SubworkflowUpdated, S::InReview),
+            (S::InReview, E::DeltaSubmitted, S::InReview),
+            (S::InReview, E::ReviewClosed, S::ReviewClosed),
+            (S::InReview, E::WorkflowAborted, S::Aborted),
+            (S::ReviewClosed, E::EditCompilationStarted, S::CompilingEdits),
+            (S::ReviewClosed, E::WorkflowAborted, S::Aborted),
+            (S::CompilingEdits, E::EditCompilationCompleted, S::ReadyForFinalization),
+            (S::ReadyForFinalization, E::ApprovalGranted, S::ReadyForFinalization),
+            (S::ReadyForFinalization, E::WorkflowCompleted, S::Completed),
+        ];
+        StateMachine {
+            transitions: table
+                .into_iter()
+                .map(|(from, event, to)| Transition { from, event, to })
+                .collect(),
+        }
+    }
+
+    /// Parse a machine from its JSON definition and [`validate`](Self::validate) it.
+    pub fn from_json(json: &str) -> Result<Self, RtError> {
+        let machine: StateMachine = serde_json::from_str(json)
+            .map_err(|e| RtError::InvalidInput(format!("invalid state machine: {e}")))?;
+        machine.validate()?;
+        Ok(machine)
+    }
+
+    /// Reject machines the engine cannot run: a transition out of a terminal
+    /// state, one listing a breadcrumb event, two for the same state and
+    /// event, or no `workflow_created` keeping a workflow in `DRAFT` (every
+    /// log starts with one).
+    pub fn validate(&self) -> Result<(), RtError> {
+        for (i, t) in self.transitions.iter().enumerate() {
+            if t.from.is_terminal() {
+                return Err(RtError::InvalidInput(format!(
+                    "{} is terminal and cannot have transitions",
+                    t.from.as_str()
+                )));
+            }
+            if BREADCRUMBS.contains(&t.event) {
+                return Err(RtError::InvalidInput(format!(
+                    "{} is legal in every live state and cannot be configured",
+                    t.event.as_str()
+                )));
+            }
+            if self.transitions[..i].iter().any(|u| u.from == t.from && u.event == t.event) {
+                return Err(RtError::InvalidInput(format!(
+                    "more than one transition for {} in {}",
+                    t.event.as_str(),
+                    t.from.as_str()
+                )));
+            }
+        }
+        let created = Transition {
+            from: WorkflowState::Draft,
+            event: EventType::WorkflowCreated,
+            to: WorkflowState::Draft,
+        };
+        if !self.transitions.contains(&created) {
+            return Err(RtError::InvalidInput(
+                "state machine must keep workflow_created in DRAFT".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The state `event` takes a workflow in `current` to. `InvalidInput`
+    /// when the machine does not allow it, or for `WorkflowCompleted` while
+    /// `approvals` is not satisfied.
+    pub fn next(
+        &self,
+        current: &WorkflowState,
+        event: &EventType,
+        approvals: &ApprovalStatus,
+    ) -> Result<WorkflowState, RtError> {
+        if current.is_terminal() {
+            return Err(RtError::InvalidInput(format!(
+                "workflow is already {}; event '{}' is not permitted",
+                current.as_str(),
+                event.as_str()
+            )));
+        }
+        if BREADCRUMBS.contains(event) {
+            return Ok(current.clone());
+        }
+        let Some(transition) =
+            self.transitions.iter().find(|t| t.from == *current && t.event == *event)
+        else {
+            return Err(RtError::InvalidInput(format!(
+                "illegal transition: event '{}' is not permitted in state '{}'",
+                event.as_str(),
+                current.as_str()
+            )));
+        };
+        if *event == EventType::WorkflowCompleted && !approvals.is_satisfied() {
+            return Err(RtError::InvalidInput(format!(
+                "approval policy not satisfied: {} of {} approvals",
+                approvals.approved_by.len(),
+                approvals.approved_by.len() + approvals.outstanding()
+            )));
+        }
+        Ok(transition.to.clone())
+    }
+
+    /// Events legal in `state`, in table order, followed by the breadcrumbs;
+    /// empty for terminal states.
+    pub fn legal_events(&self, state: &WorkflowState) -> Vec<EventType> {
+        if state.is_terminal() {
+            return Vec::new();
+        }
+        let mut events: Vec<EventType> = self
+            .transitions
+            .iter()
+            .filter(|t| t.from == *state)
+            .map(|t| t.event.clone())
+            .collect();
+        events.extend(BREADCRUMBS);
+        events
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Validate `machine` and make it the process-wide machine every workflow
+/// follows.
+pub fn install_machine(machine: StateMachine) -> Result<(), RtError> {
+    machine.validate()?;
+    *INSTALLED.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(machine));
+    Ok(())
+}
+
+/// Go back to the built-in machine.
+pub fn reset_machine() {
+    *INSTALLED.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// The machine workflows follow: the installed one, or the built-in.
+pub fn current_machine() -> Arc<StateMachine> {
+    let installed = INSTALLED.read().unwrap_or_else(|e| e.into_inner());
+    match installed.as_ref() {
+        Some(machine) => machine.clone(),
+        None => BUILTIN.get_or_init(|| Arc::new(StateMachine::builtin())).clone(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negotiation() -> StateMachine {
+        let mut machine = StateMachine::builtin();
+        machine.transitions.push(Transition {
+            from: WorkflowState::ReviewClosed,
+            event: EventType::ReviewStarted,
+            to: WorkflowState::InReview,
+        });
+        machine
+    }
+
+    #[test]
+    fn builtin_machine_round_trips_through_json() {
+        let builtin = StateMachine::builtin();
+        builtin.validate().unwrap();
+        let json = serde_json::to_string(&builtin).unwrap();
+        let first_move = r#"{"from":"DRAFT","event":"compare_started","to":"COMPARE_RUNNING"}"#;
+        assert!(json.contains(first_move));
+        assert_eq!(StateMachine::from_json(&json).unwrap(), builtin);
+        // Other tests rely on the built-in machine, so only it is installed.
+        install_machine(builtin.clone()).unwrap();
+        assert_eq!(*current_machine(), builtin);
+        reset_machine();
+    }
+
+    #[test]
+    fn custom_machines_allow_review_loops() {
+        let approvals = ApprovalStatus::default();
+        let closed = WorkflowState::ReviewClosed;
+        let started = EventType::ReviewStarted;
+        assert!(StateMachine::builtin().next(&closed, &started, &approvals).is_err());
+
+        let machine = negotiation();
+        machine.validate().unwrap();
+        assert_eq!(machine.next(&closed, &started, &approvals).unwrap(), WorkflowState::InReview);
+        let events = machine.legal_events(&closed);
+        assert_eq!(
+            events,
+            [
+                EventType::EditCompilationStarted,
+                EventType::WorkflowAborted,
+                EventType::ReviewStarted,
+                EventType::CompareProgress,
+                EventType::DeadlineBreached,
+            ]
+        );
+        let progress = machine.next(&closed, &EventType::CompareProgress, &approvals).unwrap();
+        assert_eq!(progress, closed);
+    }
+
+    #[test]
+    fn machines_the_engine_cannot_run_are_rejected() {
+        let mut terminal = StateMachine::builtin();
+        terminal.transitions.push(Transition {
+            from: WorkflowState::Completed,
+            event: EventType::ReviewStarted,
+            to: WorkflowState::InReview,
+        });
+        let mut duplicate = negotiation();
+        duplicate.transitions.push(Transition {
+            from: WorkflowState::ReviewClosed,
+            event: EventType::ReviewStarted,
+            to: WorkflowState::FlowCreated,
+        });
+        let mut breadcrumb = StateMachine::builtin();
+        breadcrumb.transitions.push(Transition {
+            from: WorkflowState::Draft,
+            event: EventType::CompareProgress,
+            to: WorkflowState::Draft,
+        });
+        let mut uncreated = StateMachine::builtin();
+        uncreated.transitions.remove(0);
+
+        for (machine, expected) in [
+            (terminal, "terminal"),
+            (duplicate, "more than one"),
+            (breadcrumb, "every live state"),
+            (uncreated, "workflow_created"),
+        ] {
+            let err = install_machine(machine).unwrap_err();
+            assert!(err.to_string().contains(expected), "{err}");
+        }
+        assert!(StateMachine::from_json(r#"{"transitions": [], "states": []}"#).is_err());
+        assert_eq!(*current_machine(), StateMachine::builtin());
+    }
+}
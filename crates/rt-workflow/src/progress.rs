@@ -0,0 +1,187 @@
+//! Progress breadcrumbs in the workflow event log.
+//!
+//! A long compare run started in a workflow's context can record how far it
+//! has got as `CompareProgress` events, so the workflow's history doubles as
+//! an operational timeline ("compare 40% through at 10:02, done at 10:05").
+//! Runs report progress after every batch of blocks; a [`ProgressThrottle`]
+//! keeps only a few of those reports so a large document does not flood the
+//! log.
+//!
+//! Progress events never change the workflow's state and are legal in every
+//! state but the terminal ones.
+
+use std::time::{Duration, Instant};
+
+use rt_core::tenant::TenantContext;
+use rt_core::RtError;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::commands::WorkflowEngine;
+use crate::event::EventType;
+use crate::state::Workflow;
+
+/// How often progress is recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProgressSettings {
+    /// Record progress once at least this many milliseconds have passed
+    /// since the last recorded event. Default: 5 000.
+    pub min_interval_ms: u64,
+    /// Record progress once it has advanced by at least this many
+    /// percentage points since the last recorded event, however little time
+    /// has passed. Default: 10.
+    pub min_step: f64,
+}
+
+impl Default for ProgressSettings {
+    fn default() -> Self {
+        Self {
+            min_interval_ms: 5_000,
+            min_step: 10.0,
+        }
+    }
+}
+
+impl ProgressSettings {
+    /// Check that `min_step` is a percentage.
+    pub fn validate(&self) -> Result<(), RtError> {
+        if !(0.0..=100.0).contains(&self.min_step) {
+            return Err(RtError::InvalidInput(format!(
+                "min_step must be between 0 and 100, got {}",
+                self.min_step
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Decides which progress reports of one run are recorded.
+///
+/// The first report and the one reaching 100% are always recorded; any
+/// other report is recorded when it is [`ProgressSettings::min_step`] ahead
+/// of the last recorded one, or ahead at all once
+/// [`ProgressSettings::min_interval_ms`] has passed.
+#[derive(Debug, Clone)]
+pub struct ProgressThrottle {
+    settings: ProgressSettings,
+    last: Option<(Instant, f64)>,
+}
+
+impl ProgressThrottle {
+    pub fn new(settings: ProgressSettings) -> Self {
+        Self { settings, last: None }
+    }
+
+    /// Whether a report of `percent` made at `now` should be recorded; when
+    /// it should, it becomes the last recorded report.
+    pub fn admit(&mut self, percent: f64, now: Instant) -> bool {
+        let admit = match self.last {
+            None => true,
+            Some((_, last)) if percent <= last => false,
+            Some((at, last)) => {
+                percent >= 100.0
+                    || percent - last >= self.settings.min_step
+                    || now.duration_since(at) >= Duration::from_millis(self.settings.min_interval_ms)
+            }
+        };
+        if admit {
+            self.last = Some((now, percent));
+        }
+        admit
+    }
+}
+
+/// Record a `CompareProgress` event for compare run `run_id` under
+/// `workflow_id`, with `blocks_processed` of `blocks_total` done. The event
+/// is submitted by `"system"`.
+pub fn record_progress(
+    conn: &Connection,
+    tenant: &TenantContext,
+    workflow_id: Uuid,
+    run_id: Uuid,
+    blocks_processed: usize,
+    blocks_total: usize,
+) -> Result<Workflow, RtError> {
+    let percent = if blocks_total == 0 {
+        100.0
+    } else {
+        blocks_processed as f64 * 100.0 / blocks_total as f64
+    };
+    WorkflowEngine::submit_event(
+        conn,
+        tenant,
+        workflow_id,
+        EventType::CompareProgress,
+        "system",
+        serde_json::json!({
+            "run_id": run_id,
+            "percent": percent,
+            "blocks_processed": blocks_processed,
+            "blocks_total": blocks_total,
+        }),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_document;
+    use crate::state::WorkflowState;
+    use rt_core::schema::run_migrations;
+
+    #[test]
+    fn throttle_keeps_first_large_steps_late_reports_and_completion() {
+        let mut throttle = ProgressThrottle::new(ProgressSettings {
+            min_interval_ms: 1_000,
+            min_step: 25.0,
+        });
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        assert!(throttle.admit(5.0, at(0)));
+        assert!(!throttle.admit(20.0, at(100)));
+        assert!(throttle.admit(30.0, at(200)));
+        assert!(!throttle.admit(35.0, at(900)));
+        assert!(throttle.admit(36.0, at(1_300)));
+        assert!(throttle.admit(100.0, at(1_301)));
+        assert!(!throttle.admit(100.0, at(5_000)));
+    }
+
+    #[test]
+    fn progress_is_recorded_without_changing_state() {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        insert_document(&conn, doc_id);
+        let tenant = TenantContext::default();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant, doc_id, "alice").unwrap();
+        WorkflowEngine::submit_event(
+            &conn,
+            &tenant,
+            wf.id,
+            EventType::CompareStarted,
+            "system",
+            serde_json::Value::Null,
+        )
+        .unwrap();
+
+        let run_id = Uuid::new_v4();
+        let updated = record_progress(&conn, &tenant, wf.id, run_id, 3, 4).unwrap();
+        assert_eq!(updated.state, WorkflowState::CompareRunning);
+
+        let events = WorkflowEngine::get_events(&conn, &tenant, wf.id).unwrap();
+        let last = events.last().unwrap();
+        assert_eq!(last.event_type, EventType::CompareProgress);
+        assert_eq!(last.payload["percent"], 75.0);
+        assert_eq!(last.payload["run_id"], run_id.to_string());
+
+        let invalid = ProgressSettings { min_step: 150.0, ..Default::default() };
+        assert!(matches!(invalid.validate(), Err(RtError::InvalidInput(_))));
+    }
+}
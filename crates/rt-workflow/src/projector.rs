@@ -1,26 +1,61 @@
+use crate::approval::ApprovalStatus;
 use crate::event::WorkflowEvent;
 use crate::state::Workflow;
 use crate::validator::validate_transition;
 
+/// A replay of a workflow's log up to some event, with what it takes to
+/// resume it: the approvals tallied on the way and the last `seq` applied.
+#[derive(Debug, Clone)]
+pub struct Projection {
+    pub workflow: Workflow,
+    pub approvals: ApprovalStatus,
+    /// `seq` of the last event applied; 0 before any.
+    pub seq: i64,
+}
+
+impl Projection {
+    /// `workflow` before its first event.
+    pub fn start(workflow: &Workflow) -> Self {
+        Projection {
+            workflow: workflow.clone(),
+            approvals: ApprovalStatus::default(),
+            seq: 0,
+        }
+    }
+
+    /// Apply `events` (sorted by `seq`) on top of this projection. They
+    /// should follow [`seq`](Self::seq) without a gap.
+    ///
+    /// Returns `Err` if any event in the sequence would cause an illegal
+    /// state transition.
+    pub fn resume(mut self, events: &[WorkflowEvent]) -> Result<Self, rt_core::RtError> {
+        let mut sorted_events = events.to_vec();
+        sorted_events.sort_by_key(|e| e.seq);
+        for event in &sorted_events {
+            let new_state =
+                validate_transition(&self.workflow.state, &event.event_type, &self.approvals)?;
+            self.approvals.apply(event)?;
+            self.workflow.state = new_state;
+            self.workflow.updated_at = event.created_at;
+            self.seq = event.seq;
+        }
+        Ok(self)
+    }
+}
+
 /// Replay `events` onto `workflow` (sorted by `seq`) and return the resulting
 /// `Workflow`.  The original `workflow` is treated as the snapshot to apply
 /// events on top of; it is not mutated.
 ///
 /// Returns `Err` if any event in the sequence would cause an illegal
-/// state transition.
+/// state transition. Approvals are tallied from `events` alone, so they
+/// should start at the workflow's `WorkflowCreated` event; to resume a
+/// replay with its approvals, use [`Projection::resume`].
 pub fn project_state(
     workflow: &Workflow,
     events: &[WorkflowEvent],
 ) -> Result<Workflow, rt_core::RtError> {
-    let mut current = workflow.clone();
-    let mut sorted_events = events.to_vec();
-    sorted_events.sort_by_key(|e| e.seq);
-    for event in &sorted_events {
-        let new_state = validate_transition(&current.state, &event.event_type)?;
-        current.state = new_state;
-        current.updated_at = event.created_at;
-    }
-    Ok(current)
+    Ok(Projection::start(workflow).resume(events)?.workflow)
 }
 
 #[cfg(test)]
@@ -40,6 +75,8 @@ mod tests {
             payload: serde_json::Value::Null,
             created_at: Utc::now(),
             seq,
+            prev_hash: None,
+            event_hash: None,
         }
     }
 
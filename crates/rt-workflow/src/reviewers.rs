@@ -0,0 +1,432 @@
+//! Reviewer assignments and roles.
+//!
+//! Who reviews a workflow is recorded by its `ReviewerAssigned` and
+//! `ReviewerUnassigned` events; the `assignments` table keeps the current
+//! outcome (one row per assigned reviewer, with their [`ReviewerRole`]) so
+//! it can be checked without replaying the log, and `reviewers` lists every
+//! reviewer a tenant has ever assigned. Both are written by
+//! [`WorkflowEngine::submit_event`] as it persists the events, so an
+//! assignment made through [`ReviewerEngine`], [`apply_routing`] or a raw
+//! event reads the same.
+//!
+//! A `DeltaSubmitted` event is only accepted from an actor assigned to the
+//! workflow as an editor or approver; observers may follow the review but
+//! not submit to it.
+//!
+//! [`apply_routing`]: crate::routing::apply_routing
+
+use chrono::{DateTime, Utc};
+use rt_core::tenant::TenantContext;
+use rt_core::RtError;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::commands::WorkflowEngine;
+use crate::event::{EventType, WorkflowEvent};
+use crate::state::Workflow;
+
+/// What an assigned reviewer may do in a workflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewerRole {
+    /// Proposes changes.
+    Editor,
+    /// Signs off on the review; may also submit changes.
+    Approver,
+    /// Follows the review without submitting to it.
+    Observer,
+}
+
+impl ReviewerRole {
+    /// Role of an assignment whose event names none.
+    pub const DEFAULT: ReviewerRole = ReviewerRole::Editor;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewerRole::Editor => "editor",
+            ReviewerRole::Approver => "approver",
+            ReviewerRole::Observer => "observer",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, RtError> {
+        match s {
+            "editor" => Ok(ReviewerRole::Editor),
+            "approver" => Ok(ReviewerRole::Approver),
+            "observer" => Ok(ReviewerRole::Observer),
+            other => Err(RtError::InvalidInput(format!("unknown reviewer role: {other}"))),
+        }
+    }
+
+    /// Whether a reviewer in this role may submit deltas.
+    pub fn can_submit(&self) -> bool {
+        !matches!(self, ReviewerRole::Observer)
+    }
+}
+
+/// A reviewer currently assigned to a workflow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Assignment {
+    pub workflow_id: Uuid,
+    pub reviewer_id: String,
+    pub role: ReviewerRole,
+    /// Actor of the event that last assigned the reviewer.
+    pub assigned_by: String,
+    pub assigned_at: DateTime<Utc>,
+}
+
+/// A reviewer known to a tenant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reviewer {
+    pub id: String,
+    /// When the reviewer was first assigned to any of the tenant's workflows.
+    pub created_at: DateTime<Utc>,
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Reviewer assignment commands and queries, scoped to one tenant like
+/// [`WorkflowEngine`].
+pub struct ReviewerEngine;
+
+impl ReviewerEngine {
+    /// Assign `reviewer_id` to `workflow_id` in `role` by recording a
+    /// `ReviewerAssigned` event from `actor`; assigning a reviewer again
+    /// changes their role. The workflow must be in review.
+    pub fn assign(
+        conn: &Connection,
+        tenant: &TenantContext,
+        workflow_id: Uuid,
+        reviewer_id: &str,
+        role: ReviewerRole,
+        actor: &str,
+    ) -> Result<Workflow, RtError> {
+        WorkflowEngine::submit_event(
+            conn,
+            tenant,
+            workflow_id,
+            EventType::ReviewerAssigned,
+            actor,
+            serde_json::json!({ "reviewer_id": reviewer_id, "role": role }),
+        )
+    }
+
+    /// Remove `reviewer_id` from `workflow_id` by recording a
+    /// `ReviewerUnassigned` event from `actor`. `NotFound` when the reviewer
+    /// is not assigned.
+    pub fn unassign(
+        conn: &Connection,
+        tenant: &TenantContext,
+        workflow_id: Uuid,
+        reviewer_id: &str,
+        actor: &str,
+    ) -> Result<Workflow, RtError> {
+        WorkflowEngine::submit_event(
+            conn,
+            tenant,
+            workflow_id,
+            EventType::ReviewerUnassigned,
+            actor,
+            serde_json::json!({ "reviewer_id": reviewer_id }),
+        )
+    }
+
+    /// Reviewers assigned to `workflow_id`, in the order they were first
+    /// assigned. `NotFound` when the workflow does not exist for `tenant`.
+    pub fn list_assignments(
+        conn: &Connection,
+        tenant: &TenantContext,
+        workflow_id: Uuid,
+    ) -> Result<Vec<Assignment>, RtError> {
+        WorkflowEngine::get_workflow(conn, tenant, workflow_id)?;
+        let mut stmt = conn.prepare(
+            "SELECT reviewer_id, role, assigned_by, assigned_at
+               FROM assignments
+              WHERE workflow_id = ?1
+              ORDER BY rowid ASC",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![workflow_id.to_string()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+        let mut assignments = Vec::new();
+        for row in rows {
+            let (reviewer_id, role, assigned_by, assigned_at) = row?;
+            assignments.push(Assignment {
+                workflow_id,
+                reviewer_id,
+                role: ReviewerRole::from_str(&role)?,
+                assigned_by,
+                assigned_at: parse_time(&assigned_at)?,
+            });
+        }
+        Ok(assignments)
+    }
+
+    /// Every reviewer `tenant` has assigned, by id.
+    pub fn list_reviewers(
+        conn: &Connection,
+        tenant: &TenantContext,
+    ) -> Result<Vec<Reviewer>, RtError> {
+        let mut stmt = conn.prepare(
+            "SELECT id, created_at FROM reviewers WHERE tenant_id = ?1 ORDER BY id ASC",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![tenant.id()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut reviewers = Vec::new();
+        for row in rows {
+            let (id, created_at) = row?;
+            reviewers.push(Reviewer {
+                id,
+                created_at: parse_time(&created_at)?,
+            });
+        }
+        Ok(reviewers)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Event hooks
+// ---------------------------------------------------------------------------
+
+/// Reject an event `actor` may not submit to `workflow_id` given its
+/// current assignments: a `DeltaSubmitted` from anyone not assigned as an
+/// editor or approver, or a `ReviewerUnassigned` of a reviewer who is not
+/// assigned. Other events pass.
+pub(crate) fn check_event(
+    conn: &Connection,
+    workflow_id: Uuid,
+    event_type: &EventType,
+    actor: &str,
+    payload: &serde_json::Value,
+) -> Result<(), RtError> {
+    match event_type {
+        EventType::DeltaSubmitted => match assigned_role(conn, workflow_id, actor)? {
+            Some(role) if role.can_submit() => Ok(()),
+            Some(role) => Err(RtError::InvalidInput(format!(
+                "reviewer '{actor}' is assigned as {} and cannot submit deltas",
+                role.as_str()
+            ))),
+            None => Err(RtError::InvalidInput(format!(
+                "'{actor}' is not assigned to review workflow {workflow_id}"
+            ))),
+        },
+        EventType::ReviewerUnassigned => {
+            let reviewer = payload_reviewer(payload);
+            match assigned_role(conn, workflow_id, reviewer)? {
+                Some(_) => Ok(()),
+                None => Err(RtError::NotFound(format!(
+                    "reviewer '{reviewer}' is not assigned to workflow {workflow_id}"
+                ))),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Bring the assignment tables up to date with `event`, which has just been
+/// persisted. An assignment without a role keeps the reviewer's current
+/// role, or gets [`ReviewerRole::DEFAULT`].
+pub(crate) fn record_event(
+    conn: &Connection,
+    tenant: &TenantContext,
+    event: &WorkflowEvent,
+) -> Result<(), RtError> {
+    let reviewer = payload_reviewer(&event.payload);
+    match event.event_type {
+        EventType::ReviewerAssigned => {
+            let role = event.payload.get("role").and_then(|r| r.as_str());
+            let at = event.created_at.to_rfc3339();
+            conn.execute(
+                "INSERT OR IGNORE INTO reviewers (tenant_id, id, created_at) VALUES (?1, ?2, ?3)",
+                rusqlite::params![tenant.id(), reviewer, at],
+            )?;
+            conn.execute(
+                "INSERT INTO assignments (workflow_id, reviewer_id, role, assigned_by, assigned_at)
+                 VALUES (?1, ?2, COALESCE(?3, ?4), ?5, ?6)
+                 ON CONFLICT (workflow_id, reviewer_id) DO UPDATE SET
+                     role = COALESCE(?3, role),
+                     assigned_by = excluded.assigned_by,
+                     assigned_at = excluded.assigned_at",
+                rusqlite::params![
+                    event.workflow_id.to_string(),
+                    reviewer,
+                    role,
+                    ReviewerRole::DEFAULT.as_str(),
+                    event.actor,
+                    at,
+                ],
+            )?;
+        }
+        EventType::ReviewerUnassigned => {
+            conn.execute(
+                "DELETE FROM assignments WHERE workflow_id = ?1 AND reviewer_id = ?2",
+                rusqlite::params![event.workflow_id.to_string(), reviewer],
+            )?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+pub(crate) fn assigned_role(
+    conn: &Connection,
+    workflow_id: Uuid,
+    reviewer_id: &str,
+) -> Result<Option<ReviewerRole>, RtError> {
+    let role: Option<String> = conn
+        .query_row(
+            "SELECT role FROM assignments WHERE workflow_id = ?1 AND reviewer_id = ?2",
+            rusqlite::params![workflow_id.to_string(), reviewer_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    role.map(|r| ReviewerRole::from_str(&r)).transpose()
+}
+
+/// The `reviewer_id` of a validated assignment payload.
+fn payload_reviewer(payload: &serde_json::Value) -> &str {
+    payload.get("reviewer_id").and_then(|r| r.as_str()).unwrap_or_default()
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>, RtError> {
+    s.parse::<DateTime<Utc>>().map_err(|e| RtError::InvalidInput(e.to_string()))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_document;
+    use rt_core::schema::run_migrations;
+
+    fn setup() -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        insert_document(&conn, doc_id);
+        let tenant = TenantContext::default();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant, doc_id, "alice").unwrap();
+        for event in [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+        ] {
+            let null = serde_json::Value::Null;
+            WorkflowEngine::submit_event(&conn, &tenant, wf.id, event, "system", null).unwrap();
+        }
+        (conn, wf.id)
+    }
+
+    fn submit_delta(conn: &Connection, wf: Uuid, actor: &str) -> Result<Workflow, RtError> {
+        let payload = serde_json::json!({
+            "layer_id": Uuid::new_v4().to_string(),
+            "delta_ids": [Uuid::new_v4().to_string()],
+        });
+        let tenant = TenantContext::default();
+        WorkflowEngine::submit_event(conn, &tenant, wf, EventType::DeltaSubmitted, actor, payload)
+    }
+
+    #[test]
+    fn assignments_track_assign_and_unassign_events() {
+        let (conn, wf) = setup();
+        let tenant = TenantContext::default();
+        ReviewerEngine::assign(&conn, &tenant, wf, "bob", ReviewerRole::Editor, "alice").unwrap();
+        ReviewerEngine::assign(&conn, &tenant, wf, "carol", ReviewerRole::Observer, "alice")
+            .unwrap();
+        ReviewerEngine::assign(&conn, &tenant, wf, "bob", ReviewerRole::Approver, "alice").unwrap();
+
+        let assignments = ReviewerEngine::list_assignments(&conn, &tenant, wf).unwrap();
+        let roles: Vec<_> =
+            assignments.iter().map(|a| (a.reviewer_id.as_str(), a.role)).collect();
+        assert_eq!(roles, [("bob", ReviewerRole::Approver), ("carol", ReviewerRole::Observer)]);
+        assert_eq!(assignments[0].assigned_by, "alice");
+
+        ReviewerEngine::unassign(&conn, &tenant, wf, "carol", "alice").unwrap();
+        let assignments = ReviewerEngine::list_assignments(&conn, &tenant, wf).unwrap();
+        assert_eq!(assignments.len(), 1);
+        let err = ReviewerEngine::unassign(&conn, &tenant, wf, "carol", "alice").unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+
+        let events = WorkflowEngine::get_events(&conn, &tenant, wf).unwrap();
+        assert_eq!(events.last().unwrap().event_type, EventType::ReviewerUnassigned);
+        let reviewers: Vec<_> = ReviewerEngine::list_reviewers(&conn, &tenant)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(reviewers, ["bob", "carol"]);
+    }
+
+    #[test]
+    fn assignments_without_a_role_keep_the_current_one() {
+        let (conn, wf) = setup();
+        let tenant = TenantContext::default();
+        let assign = |payload: serde_json::Value| {
+            let event = EventType::ReviewerAssigned;
+            WorkflowEngine::submit_event(&conn, &tenant, wf, event, "alice", payload).unwrap();
+        };
+        assign(serde_json::json!({ "reviewer_id": "dave", "section": "7." }));
+        assert_eq!(assigned_role(&conn, wf, "dave").unwrap(), Some(ReviewerRole::DEFAULT));
+        assign(serde_json::json!({ "reviewer_id": "dave", "role": "approver" }));
+        assign(serde_json::json!({ "reviewer_id": "dave", "section": "8." }));
+        assert_eq!(assigned_role(&conn, wf, "dave").unwrap(), Some(ReviewerRole::Approver));
+    }
+
+    #[test]
+    fn only_assigned_editors_and_approvers_submit_deltas() {
+        let (conn, wf) = setup();
+        let tenant = TenantContext::default();
+        let err = submit_delta(&conn, wf, "mallory").unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(ref m) if m.contains("not assigned")), "{err}");
+
+        ReviewerEngine::assign(&conn, &tenant, wf, "carol", ReviewerRole::Observer, "alice")
+            .unwrap();
+        let err = submit_delta(&conn, wf, "carol").unwrap_err();
+        assert!(matches!(err, RtError::InvalidInput(ref m) if m.contains("observer")), "{err}");
+
+        for (reviewer, role) in [("bob", ReviewerRole::Editor), ("erin", ReviewerRole::Approver)] {
+            ReviewerEngine::assign(&conn, &tenant, wf, reviewer, role, "alice").unwrap();
+            submit_delta(&conn, wf, reviewer).unwrap();
+        }
+        ReviewerEngine::unassign(&conn, &tenant, wf, "bob", "alice").unwrap();
+        assert!(submit_delta(&conn, wf, "bob").is_err());
+    }
+
+    #[test]
+    fn unknown_roles_and_other_tenants_are_rejected() {
+        let (conn, wf) = setup();
+        let tenant = TenantContext::default();
+        let result = WorkflowEngine::submit_event(
+            &conn,
+            &tenant,
+            wf,
+            EventType::ReviewerAssigned,
+            "alice",
+            serde_json::json!({ "reviewer_id": "bob", "role": "owner" }),
+        );
+        assert!(matches!(result, Err(RtError::InvalidInput(ref m)) if m.contains("role")));
+        assert!(ReviewerRole::from_str("owner").is_err());
+
+        let other = TenantContext::new("other").unwrap();
+        let err = ReviewerEngine::list_assignments(&conn, &other, wf).unwrap_err();
+        assert!(matches!(err, RtError::NotFound(_)));
+        assert!(ReviewerEngine::list_reviewers(&conn, &other).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,348 @@
+//! Change-density based review routing.
+//!
+//! After a compare, each section's share of changed deltas (its density)
+//! and any risk tags the caller attaches to it decide whether the section
+//! needs a senior reviewer. [`route_sections`] applies [`RoutingRules`] to
+//! the per-section counts stored with the compare run
+//! ([`rt_core::compare_sections`]) and suggests one `ReviewerAssigned`
+//! payload per routed section, spreading sections over the senior reviewers
+//! by their current load in the workflow. [`plan_routing`] reads that load
+//! from the workflow's earlier assignments, and [`apply_routing`] records
+//! the suggestions as events.
+
+use std::collections::HashMap;
+
+use rt_core::compare_sections::{compare_sections, SectionSummary};
+use rt_core::tenant::TenantContext;
+use rt_core::RtError;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::commands::{in_transaction, WorkflowEngine};
+use crate::event::EventType;
+use crate::state::Workflow;
+
+// ---------------------------------------------------------------------------
+// Rules and suggestions
+// ---------------------------------------------------------------------------
+
+/// When a section is routed to a senior reviewer, and to whom.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoutingRules {
+    /// Share of a section's deltas that changed (anything but `unchanged`)
+    /// at or above which the section is routed. Default: 0.5.
+    pub density_threshold: f64,
+    /// Fewest changed deltas a section needs to be routed by density, so a
+    /// one-line section with its only line edited is not. Default: 3.
+    pub min_changes: u64,
+    /// Sections carrying any of these tags are routed whenever they have a
+    /// change at all. Default: none.
+    pub risk_tags: Vec<String>,
+    /// Reviewers routed sections are assigned to; must not be empty.
+    pub senior_reviewers: Vec<String>,
+}
+
+impl Default for RoutingRules {
+    fn default() -> Self {
+        Self {
+            density_threshold: 0.5,
+            min_changes: 3,
+            risk_tags: Vec::new(),
+            senior_reviewers: Vec::new(),
+        }
+    }
+}
+
+impl RoutingRules {
+    /// Reject rules no section could be routed under.
+    pub fn validate(&self) -> Result<(), RtError> {
+        if !(0.0..=1.0).contains(&self.density_threshold) {
+            return Err(RtError::InvalidInput(format!(
+                "density_threshold must be between 0.0 and 1.0, got {}",
+                self.density_threshold
+            )));
+        }
+        if self.senior_reviewers.iter().all(|r| r.trim().is_empty()) {
+            return Err(RtError::InvalidInput(
+                "senior_reviewers must name at least one reviewer".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Why a section was routed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum RoutingReason {
+    /// The section's change density reached the threshold.
+    Density,
+    /// The section carries a risk tag.
+    RiskTag { tag: String },
+}
+
+/// A suggested senior-reviewer assignment for one section.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingSuggestion {
+    pub section: String,
+    pub reviewer_id: String,
+    pub reason: RoutingReason,
+    /// Changed deltas over all deltas of the section.
+    pub density: f64,
+    /// Number of changed deltas.
+    pub changed: u64,
+    /// `ReviewerAssigned` payload recording the assignment.
+    pub payload: serde_json::Value,
+}
+
+// ---------------------------------------------------------------------------
+// Routing
+// ---------------------------------------------------------------------------
+
+/// Route `sections` under `rules`. `tags` maps a section to its risk tags;
+/// `load` counts the sections each reviewer is already assigned.
+///
+/// Routed sections are assigned densest first, each to the least-loaded
+/// senior reviewer (earliest in `rules.senior_reviewers` on a tie); the
+/// suggestions are returned in section order.
+pub fn route_sections(
+    sections: &[SectionSummary],
+    tags: &HashMap<String, Vec<String>>,
+    rules: &RoutingRules,
+    load: &HashMap<String, usize>,
+) -> Vec<RoutingSuggestion> {
+    let mut routed: Vec<(usize, RoutingReason, f64, u64)> = Vec::new();
+    for (index, section) in sections.iter().enumerate() {
+        let unchanged = section.by_kind.get("unchanged").copied().unwrap_or(0);
+        let changed = section.total.saturating_sub(unchanged);
+        if changed == 0 {
+            continue;
+        }
+        let density = changed as f64 / section.total as f64;
+        let risk = tags
+            .get(&section.section)
+            .into_iter()
+            .flatten()
+            .find(|tag| rules.risk_tags.contains(tag));
+        let reason = match risk {
+            Some(tag) => RoutingReason::RiskTag { tag: tag.clone() },
+            None if density >= rules.density_threshold && changed >= rules.min_changes => {
+                RoutingReason::Density
+            }
+            None => continue,
+        };
+        routed.push((index, reason, density, changed));
+    }
+    routed.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+
+    let reviewers: Vec<&String> =
+        rules.senior_reviewers.iter().filter(|r| !r.trim().is_empty()).collect();
+    let mut load = load.clone();
+    let mut suggestions: Vec<(usize, RoutingSuggestion)> = Vec::with_capacity(routed.len());
+    for (index, reason, density, changed) in routed {
+        let Some(reviewer) = reviewers
+            .iter()
+            .min_by_key(|r| load.get(r.as_str()).copied().unwrap_or(0))
+        else {
+            break;
+        };
+        *load.entry(reviewer.to_string()).or_default() += 1;
+        let section = sections[index].section.clone();
+        let payload = serde_json::json!({
+            "reviewer_id": reviewer,
+            "section": section,
+            "routing": reason,
+        });
+        suggestions.push((
+            index,
+            RoutingSuggestion {
+                section,
+                reviewer_id: reviewer.to_string(),
+                reason,
+                density,
+                changed,
+                payload,
+            },
+        ));
+    }
+    suggestions.sort_by_key(|(index, _)| *index);
+    suggestions.into_iter().map(|(_, s)| s).collect()
+}
+
+/// Suggest senior-reviewer assignments for the sections of compare run
+/// `run_id` in workflow `workflow_id`, balancing against the reviewers'
+/// earlier `ReviewerAssigned` events in that workflow.
+pub fn plan_routing(
+    conn: &Connection,
+    tenant: &TenantContext,
+    workflow_id: Uuid,
+    run_id: &Uuid,
+    tags: &HashMap<String, Vec<String>>,
+    rules: &RoutingRules,
+) -> Result<Vec<RoutingSuggestion>, RtError> {
+    rules.validate()?;
+    WorkflowEngine::get_workflow(conn, tenant, workflow_id)?;
+    let sections = compare_sections(conn, tenant, run_id)?;
+
+    let mut load: HashMap<String, usize> = HashMap::new();
+    for event in WorkflowEngine::get_events(conn, tenant, workflow_id)? {
+        if event.event_type != EventType::ReviewerAssigned {
+            continue;
+        }
+        if let Some(reviewer) = event.payload.get("reviewer_id").and_then(|v| v.as_str()) {
+            *load.entry(reviewer.to_owned()).or_default() += 1;
+        }
+    }
+    Ok(route_sections(&sections, tags, rules, &load))
+}
+
+/// Record `suggestions` as `ReviewerAssigned` events by `actor`, all or
+/// none, and publish them once all are written. The workflow must be in
+/// review.
+pub fn apply_routing(
+    conn: &Connection,
+    tenant: &TenantContext,
+    workflow_id: Uuid,
+    suggestions: &[RoutingSuggestion],
+    actor: &str,
+) -> Result<Workflow, RtError> {
+    in_transaction(conn, |conn| {
+        let mut workflow = WorkflowEngine::get_workflow(conn, tenant, workflow_id)?;
+        for suggestion in suggestions {
+            workflow = WorkflowEngine::submit_event(
+                conn,
+                tenant,
+                workflow_id,
+                EventType::ReviewerAssigned,
+                actor,
+                suggestion.payload.clone(),
+            )?;
+        }
+        Ok(workflow)
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_document;
+    use crate::state::WorkflowState;
+    use rt_core::compare_sections::{append_compare_deltas, SectionDelta};
+    use rt_core::manifest::RunManifest;
+    use rt_core::run_history::{record_run, RunKind};
+    use rt_core::schema::run_migrations;
+    use std::collections::BTreeMap;
+
+    fn summary(section: &str, kinds: &[(&str, u64)]) -> SectionSummary {
+        let by_kind: BTreeMap<String, u64> = kinds.iter().map(|(k, n)| (k.to_string(), *n)).collect();
+        SectionSummary {
+            section: section.into(),
+            total: by_kind.values().sum(),
+            by_kind,
+        }
+    }
+
+    fn rules(reviewers: &[&str]) -> RoutingRules {
+        RoutingRules {
+            risk_tags: vec!["indemnity".into()],
+            senior_reviewers: reviewers.iter().map(|r| r.to_string()).collect(),
+            ..RoutingRules::default()
+        }
+    }
+
+    #[test]
+    fn dense_and_risky_sections_are_routed_to_the_least_loaded_reviewer() {
+        let sections = [
+            summary("1.", &[("unchanged", 9), ("modified", 1)]),
+            summary("2.", &[("modified", 3), ("inserted", 1)]),
+            summary("3.", &[("unchanged", 5), ("modified", 1)]),
+            summary("4.", &[("modified", 2), ("unchanged", 1)]),
+            summary("5.", &[("modified", 3), ("unchanged", 2)]),
+        ];
+        let tags = HashMap::from([("3.".to_string(), vec!["indemnity".to_string()])]);
+        let load = HashMap::from([("carol".to_string(), 1)]);
+
+        let suggestions = route_sections(&sections, &tags, &rules(&["carol", "dave"]), &load);
+        let routed: Vec<_> = suggestions
+            .iter()
+            .map(|s| (s.section.as_str(), s.reviewer_id.as_str()))
+            .collect();
+        // 2. (density 1.0) goes first to dave, the least loaded; 5. (0.6)
+        // then to carol on the tie; 3. (risk) to dave. 4. has too few changes.
+        assert_eq!(routed, [("2.", "dave"), ("3.", "dave"), ("5.", "carol")]);
+        assert_eq!(suggestions[1].reason, RoutingReason::RiskTag { tag: "indemnity".into() });
+        assert_eq!(suggestions[0].payload["reviewer_id"], "dave");
+        assert_eq!(suggestions[0].payload["section"], "2.");
+    }
+
+    #[test]
+    fn rules_are_validated() {
+        assert!(rules(&[]).validate().is_err());
+        assert!(rules(&[" "]).validate().is_err());
+        let bad = RoutingRules {
+            density_threshold: 1.5,
+            ..rules(&["carol"])
+        };
+        assert!(bad.validate().is_err());
+        assert!(rules(&["carol"]).validate().is_ok());
+    }
+
+    #[test]
+    fn planned_assignments_are_recorded_as_events() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        let tenant = TenantContext::default();
+        let doc_id = Uuid::new_v4();
+        insert_document(&conn, doc_id);
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant, doc_id, "alice").unwrap();
+        for event in [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+        ] {
+            WorkflowEngine::submit_event(&conn, &tenant, wf.id, event, "system", serde_json::Value::Null)
+                .unwrap();
+        }
+        WorkflowEngine::submit_event(
+            &conn,
+            &tenant,
+            wf.id,
+            EventType::ReviewerAssigned,
+            "alice",
+            serde_json::json!({ "reviewer_id": "carol" }),
+        )
+        .unwrap();
+
+        let run_id = Uuid::new_v4();
+        let manifest = RunManifest::new([("rt-compare", "0.1.0")], "cfg".into(), vec![]);
+        record_run(&conn, &tenant, &run_id, RunKind::Compare, &manifest).unwrap();
+        let deltas: Vec<_> = (0..4)
+            .map(|n| SectionDelta {
+                section: "7.".into(),
+                kind: "modified".into(),
+                delta: serde_json::json!({ "n": n }),
+            })
+            .collect();
+        append_compare_deltas(&conn, &tenant, &run_id, &deltas).unwrap();
+
+        let suggestions =
+            plan_routing(&conn, &tenant, wf.id, &run_id, &HashMap::new(), &rules(&["carol", "dave"]))
+                .unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].reviewer_id, "dave");
+
+        let workflow = apply_routing(&conn, &tenant, wf.id, &suggestions, "router").unwrap();
+        assert_eq!(workflow.state, WorkflowState::InReview);
+        let events = WorkflowEngine::get_events(&conn, &tenant, wf.id).unwrap();
+        let last = events.last().unwrap();
+        assert_eq!(last.event_type, EventType::ReviewerAssigned);
+        assert_eq!(last.payload["section"], "7.");
+        assert_eq!(last.actor, "router");
+    }
+}
@@ -0,0 +1,250 @@
+//! Snapshots of projected workflow state.
+//!
+//! [`WorkflowEngine::get_workflow`](crate::commands::WorkflowEngine::get_workflow)
+//! finds a workflow's state by replaying its log. So that reads do not slow
+//! down as the history grows, every [`SNAPSHOT_INTERVAL`]th event also
+//! stores the projection at that event in `workflow_snapshots`: the state,
+//! `updated_at` and the approvals tallied so far. Reads resume from the
+//! latest snapshot and replay only the events after it.
+//!
+//! Snapshots are derived from the log and written in the same transaction
+//! as the event they end at. A workflow without one, such as one recorded
+//! before snapshots were taken, is replayed from its first event. They are
+//! unrelated to document snapshots ([`rt_core::snapshot`]).
+
+use chrono::{DateTime, Utc};
+use rt_core::RtError;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::approval::ApprovalStatus;
+use crate::projector::Projection;
+use crate::state::{Workflow, WorkflowState};
+
+/// Number of events between two snapshots of a workflow.
+pub const SNAPSHOT_INTERVAL: i64 = 100;
+
+/// A workflow's projection as of one event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowSnapshot {
+    pub workflow_id: Uuid,
+    /// `seq` of the last event included.
+    pub seq: i64,
+    pub state: WorkflowState,
+    pub updated_at: DateTime<Utc>,
+    pub approvals: ApprovalStatus,
+}
+
+impl WorkflowSnapshot {
+    pub fn of(projection: &Projection) -> Self {
+        WorkflowSnapshot {
+            workflow_id: projection.workflow.id,
+            seq: projection.seq,
+            state: projection.workflow.state.clone(),
+            updated_at: projection.workflow.updated_at,
+            approvals: projection.approvals.clone(),
+        }
+    }
+
+    /// The projection of `workflow` (as stored) this snapshot holds, to
+    /// resume with the events after [`seq`](Self::seq).
+    pub fn resume_from(&self, workflow: &Workflow) -> Projection {
+        Projection {
+            workflow: Workflow {
+                state: self.state.clone(),
+                updated_at: self.updated_at,
+                ..workflow.clone()
+            },
+            approvals: self.approvals.clone(),
+            seq: self.seq,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// The most recent snapshot of `workflow_id`, if any.
+pub fn latest_snapshot(
+    conn: &Connection,
+    workflow_id: Uuid,
+) -> Result<Option<WorkflowSnapshot>, RtError> {
+    let row: Option<(i64, String, String, String)> = conn
+        .query_row(
+            "SELECT seq, state, updated_at, approvals
+               FROM workflow_snapshots
+              WHERE workflow_id = ?1
+              ORDER BY seq DESC
+              LIMIT 1",
+            rusqlite::params![workflow_id.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()?;
+    let Some((seq, state, updated_at, approvals)) = row else {
+        return Ok(None);
+    };
+    Ok(Some(WorkflowSnapshot {
+        workflow_id,
+        seq,
+        state: WorkflowState::from_str(&state)?,
+        updated_at: updated_at
+            .parse::<DateTime<Utc>>()
+            .map_err(|e| RtError::InvalidInput(e.to_string()))?,
+        approvals: serde_json::from_str(&approvals)?,
+    }))
+}
+
+/// Store `snapshot`, replacing any taken at the same event.
+pub(crate) fn record_snapshot(
+    conn: &Connection,
+    snapshot: &WorkflowSnapshot,
+) -> Result<(), RtError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO workflow_snapshots
+            (workflow_id, seq, state, updated_at, approvals, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            snapshot.workflow_id.to_string(),
+            snapshot.seq,
+            snapshot.state.as_str(),
+            snapshot.updated_at.to_rfc3339(),
+            serde_json::to_string(&snapshot.approvals)?,
+            Utc::now().to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_document;
+    use crate::approval::ApprovalPolicy;
+    use crate::commands::{CreateOptions, WorkflowEngine};
+    use crate::event::EventType;
+    use rt_core::schema::run_migrations;
+    use rt_core::tenant::TenantContext;
+
+    fn setup(options: &CreateOptions) -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        insert_document(&conn, doc_id);
+        let tenant = TenantContext::default();
+        let wf =
+            WorkflowEngine::create_workflow_with_options(&conn, &tenant, doc_id, "alice", options)
+                .unwrap();
+        (conn, wf.id)
+    }
+
+    fn submit(conn: &Connection, wf: Uuid, event: EventType, actor: &str) -> Workflow {
+        let null = serde_json::Value::Null;
+        WorkflowEngine::submit_event(conn, &TenantContext::default(), wf, event, actor, null)
+            .unwrap()
+    }
+
+    /// Record progress events until the log holds `seq` events.
+    fn progress_until(conn: &Connection, wf: Uuid, seq: i64) {
+        let tenant = TenantContext::default();
+        let run_id = Uuid::new_v4();
+        let logged = WorkflowEngine::get_events(conn, &tenant, wf).unwrap().len() as i64;
+        for _ in logged..seq {
+            let payload = serde_json::json!({
+                "run_id": run_id, "percent": 10, "blocks_processed": 1
+            });
+            WorkflowEngine::submit_event(
+                conn,
+                &tenant,
+                wf,
+                EventType::CompareProgress,
+                "system",
+                payload,
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn snapshots_are_taken_every_interval() {
+        let (conn, wf) = setup(&CreateOptions::default());
+        submit(&conn, wf, EventType::CompareStarted, "system");
+        progress_until(&conn, wf, SNAPSHOT_INTERVAL - 1);
+        assert_eq!(latest_snapshot(&conn, wf).unwrap(), None);
+
+        progress_until(&conn, wf, SNAPSHOT_INTERVAL + 1);
+        let snapshot = latest_snapshot(&conn, wf).unwrap().expect("snapshot");
+        assert_eq!(snapshot.seq, SNAPSHOT_INTERVAL);
+        assert_eq!(snapshot.state, WorkflowState::CompareRunning);
+
+        let workflow = submit(&conn, wf, EventType::CompareCompleted, "system");
+        assert_eq!(workflow.state, WorkflowState::FlowCreated);
+    }
+
+    #[test]
+    fn reads_resume_from_the_latest_snapshot() {
+        let (conn, wf) = setup(&CreateOptions::default());
+        submit(&conn, wf, EventType::CompareStarted, "system");
+        progress_until(&conn, wf, SNAPSHOT_INTERVAL);
+
+        // A snapshot claiming another state shows the replay starts there.
+        let mut snapshot = latest_snapshot(&conn, wf).unwrap().unwrap();
+        snapshot.state = WorkflowState::Aborted;
+        record_snapshot(&conn, &snapshot).unwrap();
+        let tenant = TenantContext::default();
+        let workflow = WorkflowEngine::get_workflow(&conn, &tenant, wf).unwrap();
+        assert_eq!(workflow.state, WorkflowState::Aborted);
+
+        conn.execute("DELETE FROM workflow_snapshots", []).unwrap();
+        let workflow = WorkflowEngine::get_workflow(&conn, &tenant, wf).unwrap();
+        assert_eq!(workflow.state, WorkflowState::CompareRunning);
+    }
+
+    #[test]
+    fn snapshots_carry_the_approvals_tallied() {
+        let options = CreateOptions {
+            approval_policy: Some(ApprovalPolicy {
+                required: 1,
+                approvers: vec!["carol".into()],
+            }),
+            ..CreateOptions::default()
+        };
+        let (conn, wf) = setup(&options);
+        for event in [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+            EventType::ReviewClosed,
+            EventType::EditCompilationStarted,
+            EventType::EditCompilationCompleted,
+        ] {
+            submit(&conn, wf, event, "system");
+        }
+        submit(&conn, wf, EventType::ApprovalGranted, "carol");
+        let tenant = TenantContext::default();
+        let logged = WorkflowEngine::get_events(&conn, &tenant, wf).unwrap().len() as i64;
+        let payload = serde_json::json!({ "state": "READY_FOR_FINALIZATION",
+                                          "due_at": "2024-01-01T00:00:00Z" });
+        for _ in logged..SNAPSHOT_INTERVAL {
+            WorkflowEngine::submit_event(
+                &conn,
+                &tenant,
+                wf,
+                EventType::DeadlineBreached,
+                "system",
+                payload.clone(),
+            )
+            .unwrap();
+        }
+
+        let snapshot = latest_snapshot(&conn, wf).unwrap().expect("snapshot");
+        assert_eq!(snapshot.approvals.approved_by, ["carol"]);
+        let workflow = submit(&conn, wf, EventType::WorkflowCompleted, "alice");
+        assert_eq!(workflow.state, WorkflowState::Completed);
+    }
+}
@@ -31,6 +31,7 @@ impl WorkflowState {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Result<Self, rt_core::RtError> {
         match s {
             "DRAFT" => Ok(WorkflowState::Draft),
@@ -47,6 +48,11 @@ impl WorkflowState {
             ))),
         }
     }
+
+    /// Whether no event can move the workflow on: completed or aborted.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, WorkflowState::Completed | WorkflowState::Aborted)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,421 @@
+//! Sub-workflows: one review lifecycle per reviewer.
+//!
+//! A review usually fans out to several reviewers who each work through the
+//! lifecycle on their own. [`create_subworkflow`] starts such a child for a
+//! reviewer assigned to a parent workflow in review: a workflow on the same
+//! document, initiated by the reviewer, whose `WorkflowCreated` payload
+//! names its `parent_id` and `reviewer_id`. The `workflows.parent_id` column
+//! records the link for lookups.
+//!
+//! Every change of a child's state is copied into the parent's log as a
+//! `SubworkflowUpdated` event (`subworkflow_id`, `reviewer_id`, `state`), so
+//! the parent's [`Subworkflows`] is a replay of its own log like its state.
+//! The parent cannot close its review while any child is still open, that
+//! is, neither completed nor aborted. Once the parent has left review its
+//! children's later changes are no longer copied.
+
+use rt_core::tenant::TenantContext;
+use rt_core::RtError;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::commands::{in_transaction, WorkflowEngine};
+use crate::event::{EventType, WorkflowEvent};
+use crate::reviewers::assigned_role;
+use crate::state::{Workflow, WorkflowState};
+
+/// Key of the parent's id in a child's `WorkflowCreated` payload.
+pub const PARENT_KEY: &str = "parent_id";
+
+/// A child workflow as last recorded in its parent's log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subworkflow {
+    #[serde(rename = "subworkflow_id")]
+    pub workflow_id: Uuid,
+    pub reviewer_id: String,
+    pub state: WorkflowState,
+}
+
+impl Subworkflow {
+    /// Whether the child may still change: neither completed nor aborted.
+    pub fn is_open(&self) -> bool {
+        !self.state.is_terminal()
+    }
+}
+
+/// The children of a workflow, projected from its `SubworkflowUpdated`
+/// events.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subworkflows {
+    /// In the order they were created.
+    pub children: Vec<Subworkflow>,
+}
+
+impl Subworkflows {
+    /// Replay `events` (in `seq` order).
+    pub fn from_events(events: &[WorkflowEvent]) -> Result<Self, RtError> {
+        let mut subworkflows = Self::default();
+        for event in events {
+            subworkflows.apply(event)?;
+        }
+        Ok(subworkflows)
+    }
+
+    /// Record the child state carried by a `SubworkflowUpdated` event;
+    /// other events change nothing.
+    pub fn apply(&mut self, event: &WorkflowEvent) -> Result<(), RtError> {
+        if event.event_type != EventType::SubworkflowUpdated {
+            return Ok(());
+        }
+        let update: Subworkflow = serde_json::from_value(event.payload.clone())?;
+        match self.children.iter_mut().find(|c| c.workflow_id == update.workflow_id) {
+            Some(child) => child.state = update.state,
+            None => self.children.push(update),
+        }
+        Ok(())
+    }
+
+    /// Children that are neither completed nor aborted.
+    pub fn open(&self) -> impl Iterator<Item = &Subworkflow> {
+        self.children.iter().filter(|c| c.is_open())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Start a sub-workflow of `parent_id` for `reviewer_id` and return it.
+///
+/// The parent must be in review, `reviewer_id` assigned to it, and without
+/// an open sub-workflow of it already; otherwise `InvalidInput`. The child
+/// starts in `DRAFT`, initiated by the reviewer, and is recorded in the
+/// parent's log with a `SubworkflowUpdated` event from the reviewer.
+pub fn create_subworkflow(
+    conn: &Connection,
+    tenant: &TenantContext,
+    parent_id: Uuid,
+    reviewer_id: &str,
+) -> Result<Workflow, RtError> {
+    let parent = WorkflowEngine::get_workflow(conn, tenant, parent_id)?;
+    if parent.state != WorkflowState::InReview {
+        return Err(RtError::InvalidInput(format!(
+            "workflow {parent_id} is {}, not in review",
+            parent.state.as_str()
+        )));
+    }
+    if assigned_role(conn, parent_id, reviewer_id)?.is_none() {
+        return Err(RtError::InvalidInput(format!(
+            "'{reviewer_id}' is not assigned to review workflow {parent_id}"
+        )));
+    }
+    let events = WorkflowEngine::get_events(conn, tenant, parent_id)?;
+    let existing = Subworkflows::from_events(&events)?;
+    if existing.open().any(|c| c.reviewer_id == reviewer_id) {
+        return Err(RtError::InvalidInput(format!(
+            "'{reviewer_id}' already has an open sub-workflow of workflow {parent_id}"
+        )));
+    }
+
+    let child = Workflow::new(parent.document_id, reviewer_id);
+    let payload = serde_json::json!({ PARENT_KEY: parent_id, "reviewer_id": reviewer_id });
+    in_transaction(conn, |conn| {
+        WorkflowEngine::insert_workflow(conn, tenant, &child, Some(parent_id), payload)?;
+        let update = Subworkflow {
+            workflow_id: child.id,
+            reviewer_id: reviewer_id.to_string(),
+            state: child.state.clone(),
+        };
+        notify_parent(conn, tenant, parent_id, update, reviewer_id)
+    })?;
+    Ok(child)
+}
+
+/// The sub-workflows of `parent_id` with their latest states, in creation
+/// order. `NotFound` when the workflow does not exist for `tenant`.
+pub fn list_subworkflows(
+    conn: &Connection,
+    tenant: &TenantContext,
+    parent_id: Uuid,
+) -> Result<Vec<Subworkflow>, RtError> {
+    WorkflowEngine::get_workflow(conn, tenant, parent_id)?;
+    let events = WorkflowEngine::get_events(conn, tenant, parent_id)?;
+    Ok(Subworkflows::from_events(&events)?.children)
+}
+
+// ---------------------------------------------------------------------------
+// Event hooks
+// ---------------------------------------------------------------------------
+
+/// Reject a `ReviewClosed` of a workflow with open sub-workflows, and a
+/// `SubworkflowUpdated` that does not match a child of the workflow as it
+/// stands. Other events pass.
+pub(crate) fn check_event(
+    conn: &Connection,
+    tenant: &TenantContext,
+    workflow_id: Uuid,
+    event_type: &EventType,
+    payload: &serde_json::Value,
+) -> Result<(), RtError> {
+    match event_type {
+        EventType::ReviewClosed => {
+            let events = WorkflowEngine::get_events(conn, tenant, workflow_id)?;
+            let subworkflows = Subworkflows::from_events(&events)?;
+            let open = subworkflows.open().count();
+            if open > 0 {
+                return Err(RtError::InvalidInput(format!(
+                    "{open} of {} sub-workflows of workflow {workflow_id} still open",
+                    subworkflows.children.len()
+                )));
+            }
+            Ok(())
+        }
+        EventType::SubworkflowUpdated => {
+            let update: Subworkflow = serde_json::from_value(payload.clone())?;
+            let child: Option<(String, Option<String>)> = conn
+                .query_row(
+                    "SELECT state, initiator_id FROM workflows WHERE id = ?1 AND parent_id = ?2",
+                    rusqlite::params![update.workflow_id.to_string(), workflow_id.to_string()],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let Some((state, reviewer)) = child else {
+                return Err(RtError::NotFound(format!(
+                    "workflow {} is not a sub-workflow of workflow {workflow_id}",
+                    update.workflow_id
+                )));
+            };
+            if state != update.state.as_str() || reviewer.as_deref() != Some(&update.reviewer_id) {
+                return Err(RtError::InvalidInput(format!(
+                    "sub-workflow {} is {state} for '{}'",
+                    update.workflow_id,
+                    reviewer.unwrap_or_default()
+                )));
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Copy the move of `child` to `state`, which has just been persisted, into
+/// its parent's log when it has a parent still in review. `child` is the
+/// workflow as it was before the move.
+pub(crate) fn record_event(
+    conn: &Connection,
+    tenant: &TenantContext,
+    child: &Workflow,
+    state: &WorkflowState,
+    actor: &str,
+) -> Result<(), RtError> {
+    if child.state == *state {
+        return Ok(());
+    }
+    let parent_id: Option<String> = conn
+        .query_row(
+            "SELECT parent_id FROM workflows WHERE id = ?1",
+            rusqlite::params![child.id.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+    let parent_id =
+        Uuid::parse_str(&parent_id).map_err(|e| RtError::InvalidInput(e.to_string()))?;
+    if WorkflowEngine::get_workflow(conn, tenant, parent_id)?.state != WorkflowState::InReview {
+        return Ok(());
+    }
+    let update = Subworkflow {
+        workflow_id: child.id,
+        reviewer_id: child.initiator_id.clone(),
+        state: state.clone(),
+    };
+    notify_parent(conn, tenant, parent_id, update, actor)
+}
+
+// ---------------------------------------------------------------------------
+// Internal helpers
+// ---------------------------------------------------------------------------
+
+/// Record `update` in the log of `parent_id` as a `SubworkflowUpdated` event.
+fn notify_parent(
+    conn: &Connection,
+    tenant: &TenantContext,
+    parent_id: Uuid,
+    update: Subworkflow,
+    actor: &str,
+) -> Result<(), RtError> {
+    WorkflowEngine::submit_event(
+        conn,
+        tenant,
+        parent_id,
+        EventType::SubworkflowUpdated,
+        actor,
+        serde_json::to_value(update)?,
+    )?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_document;
+    use crate::reviewers::{ReviewerEngine, ReviewerRole};
+    use rt_core::schema::run_migrations;
+
+    /// A workflow in review with `reviewers` assigned as editors.
+    fn setup(reviewers: &[&str]) -> (Connection, Uuid) {
+        let conn = Connection::open_in_memory().expect("in-memory db");
+        run_migrations(&conn).expect("migrations");
+        let doc_id = Uuid::new_v4();
+        insert_document(&conn, doc_id);
+        let tenant = TenantContext::default();
+        let wf = WorkflowEngine::create_workflow(&conn, &tenant, doc_id, "alice").unwrap();
+        let to_review = [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+        ];
+        for event in to_review {
+            submit(&conn, wf.id, event, "system").unwrap();
+        }
+        for reviewer in reviewers {
+            ReviewerEngine::assign(&conn, &tenant, wf.id, reviewer, ReviewerRole::Editor, "alice")
+                .unwrap();
+        }
+        (conn, wf.id)
+    }
+
+    fn submit(conn: &Connection, wf: Uuid, event: EventType, actor: &str) -> Result<(), RtError> {
+        let null = serde_json::Value::Null;
+        WorkflowEngine::submit_event(conn, &TenantContext::default(), wf, event, actor, null)
+            .map(|_| ())
+    }
+
+    fn states(conn: &Connection, parent: Uuid) -> Vec<(String, WorkflowState)> {
+        list_subworkflows(conn, &TenantContext::default(), parent)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.reviewer_id, c.state))
+            .collect()
+    }
+
+    #[test]
+    fn children_states_are_projected_into_the_parent() {
+        let (conn, parent) = setup(&["bob", "carol"]);
+        let tenant = TenantContext::default();
+        let bob = create_subworkflow(&conn, &tenant, parent, "bob").unwrap();
+        create_subworkflow(&conn, &tenant, parent, "carol").unwrap();
+        assert_eq!(bob.state, WorkflowState::Draft);
+        assert_eq!(bob.initiator_id, "bob");
+
+        submit(&conn, bob.id, EventType::CompareStarted, "bob").unwrap();
+        // Events that keep the child's state are not copied.
+        let progress = serde_json::json!({
+            "run_id": Uuid::new_v4(), "percent": 50, "blocks_processed": 1
+        });
+        WorkflowEngine::submit_event(
+            &conn,
+            &tenant,
+            bob.id,
+            EventType::CompareProgress,
+            "bob",
+            progress,
+        )
+        .unwrap();
+        assert_eq!(
+            states(&conn, parent),
+            [
+                ("bob".to_string(), WorkflowState::CompareRunning),
+                ("carol".to_string(), WorkflowState::Draft),
+            ]
+        );
+        let events = WorkflowEngine::get_events(&conn, &tenant, parent).unwrap();
+        let updates =
+            events.iter().filter(|e| e.event_type == EventType::SubworkflowUpdated).count();
+        assert_eq!(updates, 3);
+        assert_eq!(
+            WorkflowEngine::get_workflow(&conn, &tenant, parent).unwrap().state,
+            WorkflowState::InReview
+        );
+    }
+
+    #[test]
+    fn review_closes_once_every_child_is_finished() {
+        let (conn, parent) = setup(&["bob", "carol"]);
+        let tenant = TenantContext::default();
+        let bob = create_subworkflow(&conn, &tenant, parent, "bob").unwrap();
+        let carol = create_subworkflow(&conn, &tenant, parent, "carol").unwrap();
+
+        let err = submit(&conn, parent, EventType::ReviewClosed, "alice").unwrap_err();
+        assert!(err.to_string().contains("2 of 2 sub-workflows"), "{err}");
+
+        for event in [
+            EventType::CompareStarted,
+            EventType::CompareCompleted,
+            EventType::ReviewStarted,
+            EventType::ReviewClosed,
+            EventType::EditCompilationStarted,
+            EventType::EditCompilationCompleted,
+            EventType::WorkflowCompleted,
+        ] {
+            submit(&conn, bob.id, event, "bob").unwrap();
+        }
+        let err = submit(&conn, parent, EventType::ReviewClosed, "alice").unwrap_err();
+        assert!(err.to_string().contains("1 of 2 sub-workflows"), "{err}");
+
+        submit(&conn, carol.id, EventType::WorkflowAborted, "carol").unwrap();
+        submit(&conn, parent, EventType::ReviewClosed, "alice").unwrap();
+        assert_eq!(
+            states(&conn, parent),
+            [
+                ("bob".to_string(), WorkflowState::Completed),
+                ("carol".to_string(), WorkflowState::Aborted),
+            ]
+        );
+    }
+
+    #[test]
+    fn subworkflows_need_an_assigned_reviewer_and_a_parent_in_review() {
+        let (conn, parent) = setup(&["bob"]);
+        let tenant = TenantContext::default();
+        let err = create_subworkflow(&conn, &tenant, parent, "mallory").unwrap_err();
+        assert!(err.to_string().contains("not assigned"), "{err}");
+
+        let bob = create_subworkflow(&conn, &tenant, parent, "bob").unwrap();
+        let again = create_subworkflow(&conn, &tenant, parent, "bob").unwrap_err();
+        assert!(again.to_string().contains("already has an open"), "{again}");
+        let nested = create_subworkflow(&conn, &tenant, bob.id, "bob").unwrap_err();
+        assert!(nested.to_string().contains("not in review"), "{nested}");
+
+        let missing = create_subworkflow(&conn, &tenant, Uuid::new_v4(), "bob");
+        assert!(matches!(missing, Err(RtError::NotFound(_))));
+    }
+
+    #[test]
+    fn forged_updates_are_rejected() {
+        let (conn, parent) = setup(&["bob"]);
+        let tenant = TenantContext::default();
+        let bob = create_subworkflow(&conn, &tenant, parent, "bob").unwrap();
+        let forge = |id: Uuid, state: &str| {
+            WorkflowEngine::submit_event(
+                &conn,
+                &tenant,
+                parent,
+                EventType::SubworkflowUpdated,
+                "bob",
+                serde_json::json!({ "subworkflow_id": id, "reviewer_id": "bob", "state": state }),
+            )
+        };
+        assert!(matches!(forge(Uuid::new_v4(), "DRAFT"), Err(RtError::NotFound(_))));
+        assert!(forge(bob.id, "COMPLETED").is_err());
+        assert_eq!(states(&conn, parent), [("bob".to_string(), WorkflowState::Draft)]);
+    }
+}
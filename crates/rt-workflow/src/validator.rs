@@ -1,107 +1,252 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::approval::{ApprovalPolicy, ApprovalStatus};
+use crate::deadlines::{validate_deadlines, Deadline};
 use crate::event::EventType;
+use crate::machine::current_machine;
+use crate::reviewers::ReviewerRole;
 use crate::state::WorkflowState;
 
-/// Validate that `event` is a legal transition from `current` and return the
+/// JSON type a payload field must have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// A non-empty string.
+    String,
+    /// A string holding a UUID.
+    Uuid,
+    /// A non-empty array of UUID strings.
+    UuidList,
+    Bool,
+    /// A non-negative integer.
+    Count,
+    /// A number from 0 to 100.
+    Percent,
+    /// A [`ReviewerRole`] name.
+    Role,
+    /// A valid [`ApprovalPolicy`] object.
+    Policy,
+    /// A valid array of [`Deadline`] objects.
+    Deadlines,
+    /// An RFC 3339 timestamp string.
+    Timestamp,
+    /// A [`WorkflowState`] name.
+    State,
+}
+
+impl FieldKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldKind::String => "non-empty string",
+            FieldKind::Uuid => "UUID string",
+            FieldKind::UuidList => "non-empty array of UUID strings",
+            FieldKind::Bool => "boolean",
+            FieldKind::Count => "non-negative integer",
+            FieldKind::Percent => "number between 0 and 100",
+            FieldKind::Role => "reviewer role (editor, approver or observer)",
+            FieldKind::Policy => "approval policy",
+            FieldKind::Deadlines => "array of deadlines",
+            FieldKind::Timestamp => "RFC 3339 timestamp",
+            FieldKind::State => "workflow state",
+        }
+    }
+
+    fn accepts(&self, value: &serde_json::Value) -> bool {
+        let is_uuid = |v: &serde_json::Value| v.as_str().is_some_and(|s| Uuid::parse_str(s).is_ok());
+        match self {
+            FieldKind::String => value.as_str().is_some_and(|s| !s.trim().is_empty()),
+            FieldKind::Uuid => is_uuid(value),
+            FieldKind::UuidList => value
+                .as_array()
+                .is_some_and(|items| !items.is_empty() && items.iter().all(is_uuid)),
+            FieldKind::Bool => value.is_boolean(),
+            FieldKind::Count => value.is_u64(),
+            FieldKind::Percent => value.as_f64().is_some_and(|p| (0.0..=100.0).contains(&p)),
+            FieldKind::Role => value.as_str().is_some_and(|r| ReviewerRole::from_str(r).is_ok()),
+            FieldKind::Policy => serde_json::from_value::<ApprovalPolicy>(value.clone())
+                .is_ok_and(|p| p.validate().is_ok()),
+            FieldKind::Deadlines => serde_json::from_value::<Vec<Deadline>>(value.clone())
+                .is_ok_and(|d| validate_deadlines(&d).is_ok()),
+            FieldKind::Timestamp => value
+                .as_str()
+                .is_some_and(|t| chrono::DateTime::parse_from_rfc3339(t).is_ok()),
+            FieldKind::State => value.as_str().is_some_and(|s| WorkflowState::from_str(s).is_ok()),
+        }
+    }
+}
+
+/// One field of an event payload schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+}
+
+const fn required(name: &'static str, kind: FieldKind) -> FieldSpec {
+    FieldSpec { name, kind, required: true }
+}
+
+const fn optional(name: &'static str, kind: FieldKind) -> FieldSpec {
+    FieldSpec { name, kind, required: false }
+}
+
+/// A single problem found in an event payload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PayloadViolation {
+    /// Offending field; empty when the payload as a whole is malformed.
+    pub field: String,
+    pub message: String,
+}
+
+/// Validate that `event` is a legal transition from `current` under the
+/// [current machine](crate::machine::current_machine) and return the
 /// resulting `WorkflowState`.  Returns `Err(InvalidInput)` when the
-/// combination is not permitted.
+/// combination is not permitted, including `WorkflowCompleted` while
+/// `approvals` is not satisfied.
 pub fn validate_transition(
     current: &WorkflowState,
     event: &EventType,
+    approvals: &ApprovalStatus,
 ) -> Result<WorkflowState, rt_core::RtError> {
-    let next = match (current, event) {
-        // Draft transitions
-        (WorkflowState::Draft, EventType::WorkflowCreated) => WorkflowState::Draft,
-        (WorkflowState::Draft, EventType::CompareStarted) => WorkflowState::CompareRunning,
-        (WorkflowState::Draft, EventType::WorkflowAborted) => WorkflowState::Aborted,
-
-        // CompareRunning transitions
-        (WorkflowState::CompareRunning, EventType::CompareCompleted) => WorkflowState::FlowCreated,
-
-        // FlowCreated transitions
-        (WorkflowState::FlowCreated, EventType::ReviewStarted) => WorkflowState::InReview,
-
-        // InReview transitions
-        (WorkflowState::InReview, EventType::ReviewerAssigned) => WorkflowState::InReview,
-        (WorkflowState::InReview, EventType::DeltaSubmitted) => WorkflowState::InReview,
-        (WorkflowState::InReview, EventType::ReviewClosed) => WorkflowState::ReviewClosed,
-        (WorkflowState::InReview, EventType::WorkflowAborted) => WorkflowState::Aborted,
-
-        // ReviewClosed transitions
-        (WorkflowState::ReviewClosed, EventType::EditCompilationStarted) => {
-            WorkflowState::CompilingEdits
-        }
-        (WorkflowState::ReviewClosed, EventType::WorkflowAborted) => WorkflowState::Aborted,
+    current_machine().next(current, event, approvals)
+}
 
-        // CompilingEdits transitions
-        (WorkflowState::CompilingEdits, EventType::EditCompilationCompleted) => {
-            WorkflowState::ReadyForFinalization
-        }
+/// Return the set of events that are legally applicable to `state` under the
+/// current machine.
+pub fn legal_transitions(state: &WorkflowState) -> Vec<EventType> {
+    current_machine().legal_events(state)
+}
 
-        // ReadyForFinalization transitions
-        (WorkflowState::ReadyForFinalization, EventType::WorkflowCompleted) => {
-            WorkflowState::Completed
-        }
+/// Known payload fields for `event`. Fields not listed are allowed and
+/// stored as given; listed fields must have the stated type, and required
+/// ones must be present.
+pub fn payload_schema(event: &EventType) -> &'static [FieldSpec] {
+    match event {
+        EventType::WorkflowCreated => WORKFLOW_CREATED_FIELDS,
+        EventType::CompareStarted => COMPARE_STARTED_FIELDS,
+        EventType::CompareCompleted => COMPARE_COMPLETED_FIELDS,
+        EventType::CompareProgress => COMPARE_PROGRESS_FIELDS,
+        EventType::DeadlineBreached => DEADLINE_BREACHED_FIELDS,
+        EventType::ReviewerAssigned => REVIEWER_ASSIGNED_FIELDS,
+        EventType::ReviewerUnassigned => REVIEWER_UNASSIGNED_FIELDS,
+        EventType::SubworkflowUpdated => SUBWORKFLOW_UPDATED_FIELDS,
+        EventType::DeltaSubmitted => DELTA_SUBMITTED_FIELDS,
+        EventType::ReviewClosed => REVIEW_CLOSED_FIELDS,
+        EventType::WorkflowAborted => WORKFLOW_ABORTED_FIELDS,
+        EventType::ApprovalGranted => APPROVAL_GRANTED_FIELDS,
+        EventType::FlowCreated
+        | EventType::ReviewStarted
+        | EventType::EditCompilationStarted
+        | EventType::EditCompilationCompleted
+        | EventType::FinalizationReady
+        | EventType::WorkflowCompleted => &[],
+    }
+}
 
-        // Terminal states – nothing is legal
-        (WorkflowState::Completed, _) => {
-            return Err(rt_core::RtError::InvalidInput(format!(
-                "workflow is already COMPLETED; event '{}' is not permitted",
-                event.as_str()
-            )));
-        }
-        (WorkflowState::Aborted, _) => {
-            return Err(rt_core::RtError::InvalidInput(format!(
-                "workflow is already ABORTED; event '{}' is not permitted",
-                event.as_str()
-            )));
-        }
+const WORKFLOW_CREATED_FIELDS: &[FieldSpec] = &[
+    optional("auto_created", FieldKind::Bool),
+    optional("approval_policy", FieldKind::Policy),
+    optional("deadlines", FieldKind::Deadlines),
+    optional("parent_id", FieldKind::Uuid),
+    optional("reviewer_id", FieldKind::String),
+];
+const COMPARE_STARTED_FIELDS: &[FieldSpec] = &[
+    optional("left_doc_id", FieldKind::Uuid),
+    optional("right_doc_id", FieldKind::Uuid),
+];
+const COMPARE_COMPLETED_FIELDS: &[FieldSpec] = &[optional("run_id", FieldKind::Uuid)];
+const COMPARE_PROGRESS_FIELDS: &[FieldSpec] = &[
+    required("run_id", FieldKind::Uuid),
+    required("percent", FieldKind::Percent),
+    required("blocks_processed", FieldKind::Count),
+    optional("blocks_total", FieldKind::Count),
+];
+const DEADLINE_BREACHED_FIELDS: &[FieldSpec] = &[
+    required("state", FieldKind::String),
+    required("due_at", FieldKind::Timestamp),
+    optional("entered_at", FieldKind::Timestamp),
+];
+const REVIEWER_ASSIGNED_FIELDS: &[FieldSpec] = &[
+    required("reviewer_id", FieldKind::String),
+    optional("layer_id", FieldKind::Uuid),
+    optional("section", FieldKind::String),
+    optional("role", FieldKind::Role),
+];
+const REVIEWER_UNASSIGNED_FIELDS: &[FieldSpec] = &[required("reviewer_id", FieldKind::String)];
+const SUBWORKFLOW_UPDATED_FIELDS: &[FieldSpec] = &[
+    required("subworkflow_id", FieldKind::Uuid),
+    required("reviewer_id", FieldKind::String),
+    required("state", FieldKind::State),
+];
+const DELTA_SUBMITTED_FIELDS: &[FieldSpec] = &[
+    required("layer_id", FieldKind::Uuid),
+    required("delta_ids", FieldKind::UuidList),
+];
+const REVIEW_CLOSED_FIELDS: &[FieldSpec] = &[optional("snapshot_id", FieldKind::Uuid)];
+const WORKFLOW_ABORTED_FIELDS: &[FieldSpec] = &[optional("reason", FieldKind::String)];
+const APPROVAL_GRANTED_FIELDS: &[FieldSpec] = &[optional("comment", FieldKind::String)];
 
-        // All other combinations are illegal
-        (state, ev) => {
-            return Err(rt_core::RtError::InvalidInput(format!(
-                "illegal transition: event '{}' is not permitted in state '{}'",
-                ev.as_str(),
-                state.as_str()
-            )));
+/// Check `payload` against the schema for `event` and return every
+/// violation found (empty when the payload is valid). A `null` payload is
+/// treated as an empty object.
+pub fn check_payload(event: &EventType, payload: &serde_json::Value) -> Vec<PayloadViolation> {
+    let empty = serde_json::Map::new();
+    let fields = match payload {
+        serde_json::Value::Null => &empty,
+        serde_json::Value::Object(map) => map,
+        _ => {
+            return vec![PayloadViolation {
+                field: String::new(),
+                message: "payload must be a JSON object".into(),
+            }]
         }
     };
-    Ok(next)
+
+    payload_schema(event)
+        .iter()
+        .filter_map(|spec| match fields.get(spec.name) {
+            None | Some(serde_json::Value::Null) if spec.required => Some(PayloadViolation {
+                field: spec.name.into(),
+                message: format!("required {} is missing", spec.kind.as_str()),
+            }),
+            None | Some(serde_json::Value::Null) => None,
+            Some(value) if !spec.kind.accepts(value) => Some(PayloadViolation {
+                field: spec.name.into(),
+                message: format!("expected {}, got {}", spec.kind.as_str(), value),
+            }),
+            Some(_) => None,
+        })
+        .collect()
 }
 
-/// Return the set of events that are legally applicable to `state`.
-pub fn legal_transitions(state: &WorkflowState) -> Vec<EventType> {
-    match state {
-        WorkflowState::Draft => vec![
-            EventType::WorkflowCreated,
-            EventType::CompareStarted,
-            EventType::WorkflowAborted,
-        ],
-        WorkflowState::CompareRunning => vec![EventType::CompareCompleted],
-        WorkflowState::FlowCreated => vec![EventType::ReviewStarted],
-        WorkflowState::InReview => vec![
-            EventType::ReviewerAssigned,
-            EventType::DeltaSubmitted,
-            EventType::ReviewClosed,
-            EventType::WorkflowAborted,
-        ],
-        WorkflowState::ReviewClosed => vec![
-            EventType::EditCompilationStarted,
-            EventType::WorkflowAborted,
-        ],
-        WorkflowState::CompilingEdits => vec![EventType::EditCompilationCompleted],
-        WorkflowState::ReadyForFinalization => vec![EventType::WorkflowCompleted],
-        WorkflowState::Completed => vec![],
-        WorkflowState::Aborted => vec![],
+/// Validate `payload` for `event`. Returns `Err(InvalidInput)` whose message
+/// is `invalid '<event>' payload: ` followed by the violations as a JSON
+/// array of `{"field", "message"}` objects.
+pub fn validate_payload(
+    event: &EventType,
+    payload: &serde_json::Value,
+) -> Result<(), rt_core::RtError> {
+    let violations = check_payload(event, payload);
+    if violations.is_empty() {
+        return Ok(());
     }
+    let details = serde_json::to_string(&violations)?;
+    Err(rt_core::RtError::InvalidInput(format!(
+        "invalid '{}' payload: {}",
+        event.as_str(),
+        details
+    )))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     // Helper: assert a transition succeeds and yields the expected state.
     fn ok(current: WorkflowState, event: EventType, expected: WorkflowState) {
-        let result = validate_transition(&current, &event);
+        let result = validate_transition(&current, &event, &ApprovalStatus::default());
         assert!(
             result.is_ok(),
             "expected Ok for ({:?}, {:?}), got {:?}",
@@ -120,7 +265,7 @@ mod tests {
 
     // Helper: assert a transition fails.
     fn err(current: WorkflowState, event: EventType) {
-        let result = validate_transition(&current, &event);
+        let result = validate_transition(&current, &event, &ApprovalStatus::default());
         assert!(
             result.is_err(),
             "expected Err for ({:?}, {:?}), got Ok({:?})",
@@ -182,11 +327,29 @@ mod tests {
             EventType::ReviewerAssigned,
             WorkflowState::InReview,
         );
+        ok(
+            WorkflowState::InReview,
+            EventType::ReviewerUnassigned,
+            WorkflowState::InReview,
+        );
         ok(
             WorkflowState::InReview,
             EventType::DeltaSubmitted,
             WorkflowState::InReview,
         );
+        ok(
+            WorkflowState::InReview,
+            EventType::SubworkflowUpdated,
+            WorkflowState::InReview,
+        );
+        err(WorkflowState::ReviewClosed, EventType::SubworkflowUpdated);
+        let update =
+            json!({ "subworkflow_id": Uuid::new_v4(), "reviewer_id": "bob", "state": "X" });
+        let fields: Vec<_> = check_payload(&EventType::SubworkflowUpdated, &update)
+            .into_iter()
+            .map(|v| v.field)
+            .collect();
+        assert_eq!(fields, ["state"]);
     }
 
     #[test]
@@ -243,6 +406,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn completion_requires_a_satisfied_approval_policy() {
+        let mut approvals = ApprovalStatus {
+            policy: Some(ApprovalPolicy {
+                required: 2,
+                approvers: vec![],
+            }),
+            approved_by: vec!["carol".into()],
+        };
+        let ready = WorkflowState::ReadyForFinalization;
+        let refused = validate_transition(&ready, &EventType::WorkflowCompleted, &approvals)
+            .unwrap_err()
+            .to_string();
+        assert!(refused.contains("1 of 2 approvals"), "{refused}");
+        ok(ready.clone(), EventType::ApprovalGranted, ready.clone());
+
+        approvals.approved_by.push("dave".into());
+        let next = validate_transition(&ready, &EventType::WorkflowCompleted, &approvals).unwrap();
+        assert_eq!(next, WorkflowState::Completed);
+        err(WorkflowState::InReview, EventType::ApprovalGranted);
+    }
+
     #[test]
     fn completed_any_event_is_illegal() {
         err(WorkflowState::Completed, EventType::WorkflowCreated);
@@ -268,6 +453,36 @@ mod tests {
         err(WorkflowState::CompilingEdits, EventType::ReviewClosed);
     }
 
+    #[test]
+    fn compare_progress_keeps_every_live_state() {
+        ok(
+            WorkflowState::CompareRunning,
+            EventType::CompareProgress,
+            WorkflowState::CompareRunning,
+        );
+        ok(WorkflowState::InReview, EventType::CompareProgress, WorkflowState::InReview);
+        err(WorkflowState::Completed, EventType::CompareProgress);
+        ok(WorkflowState::Draft, EventType::DeadlineBreached, WorkflowState::Draft);
+        err(WorkflowState::Aborted, EventType::DeadlineBreached);
+        let breach = json!({ "state": "IN_REVIEW", "due_at": "yesterday" });
+        let fields: Vec<_> = check_payload(&EventType::DeadlineBreached, &breach)
+            .into_iter()
+            .map(|v| v.field)
+            .collect();
+        assert_eq!(fields, ["due_at"]);
+        assert!(legal_transitions(&WorkflowState::FlowCreated).contains(&EventType::CompareProgress));
+
+        let run_id = Uuid::new_v4().to_string();
+        let payload = json!({ "run_id": run_id, "percent": 42.5, "blocks_processed": 17 });
+        assert!(check_payload(&EventType::CompareProgress, &payload).is_empty());
+        let payload = json!({ "run_id": run_id, "percent": 120, "blocks_processed": -1 });
+        let fields: Vec<_> = check_payload(&EventType::CompareProgress, &payload)
+            .into_iter()
+            .map(|v| v.field)
+            .collect();
+        assert_eq!(fields, ["percent", "blocks_processed"]);
+    }
+
     #[test]
     fn legal_transitions_coverage() {
         assert!(legal_transitions(&WorkflowState::Draft).contains(&EventType::CompareStarted));
@@ -280,4 +495,61 @@ mod tests {
             "Aborted should have no legal transitions"
         );
     }
+
+    // -----------------------------------------------------------------------
+    // Payload schemas
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn reviewer_assigned_requires_reviewer_id() {
+        let violations = check_payload(&EventType::ReviewerAssigned, &json!({}));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].field, "reviewer_id");
+
+        let blank = check_payload(&EventType::ReviewerAssigned, &json!({ "reviewer_id": " " }));
+        assert_eq!(blank.len(), 1);
+
+        assert!(check_payload(&EventType::ReviewerAssigned, &json!({ "reviewer_id": "bob" }))
+            .is_empty());
+
+        let role = json!({ "reviewer_id": "bob", "role": "owner" });
+        let violations = check_payload(&EventType::ReviewerAssigned, &role);
+        assert_eq!(violations[0].field, "role");
+    }
+
+    #[test]
+    fn delta_submitted_requires_layer_and_delta_refs() {
+        let violations = check_payload(
+            &EventType::DeltaSubmitted,
+            &json!({ "layer_id": "not-a-uuid", "delta_ids": [] }),
+        );
+        let fields: Vec<_> = violations.iter().map(|v| v.field.as_str()).collect();
+        assert_eq!(fields, vec!["layer_id", "delta_ids"]);
+
+        let ok = json!({
+            "layer_id": Uuid::new_v4().to_string(),
+            "delta_ids": [Uuid::new_v4().to_string()],
+            "note": "extra fields are kept",
+        });
+        assert!(check_payload(&EventType::DeltaSubmitted, &ok).is_empty());
+    }
+
+    #[test]
+    fn null_payload_is_empty_object_and_non_objects_are_rejected() {
+        assert!(check_payload(&EventType::ReviewStarted, &serde_json::Value::Null).is_empty());
+        assert!(!check_payload(&EventType::ReviewerAssigned, &serde_json::Value::Null).is_empty());
+
+        let violations = check_payload(&EventType::ReviewStarted, &json!([1, 2]));
+        assert_eq!(violations[0].field, "");
+    }
+
+    #[test]
+    fn validate_payload_reports_details_as_json() {
+        let err = validate_payload(&EventType::WorkflowAborted, &json!({ "reason": 3 }))
+            .unwrap_err()
+            .to_string();
+        let details = err.split_once("payload: ").unwrap().1;
+        let parsed: serde_json::Value = serde_json::from_str(details).unwrap();
+        assert_eq!(parsed[0]["field"], "reason");
+    }
 }
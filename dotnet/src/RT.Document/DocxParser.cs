@@ -265,8 +265,10 @@ public class DocxParser
             }
             case Inserted ins:
             {
+                var inserted = new List<ModelRun>();
                 foreach (var r in ins.Elements<OxmlRun>())
-                    ExtractRunsFromElement(r, result);
+                    ExtractRunsFromElement(r, inserted);
+                result.AddRange(inserted.Select(r => r with { Revision = ChangeType.Insert }));
                 break;
             }
             case Deleted del:
@@ -278,7 +280,7 @@ public class DocxParser
                     if (!string.IsNullOrEmpty(delText))
                     {
                         var fmt = GetFormatting(r.GetFirstChild<RunProperties>());
-                        result.Add(new ModelRun(delText, fmt));
+                        result.Add(new ModelRun(delText, fmt, ChangeType.Delete));
                     }
                 }
                 break;
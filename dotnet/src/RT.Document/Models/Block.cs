@@ -52,6 +52,17 @@ public enum ChangeType
     [JsonPropertyName("format_change")] FormatChange,
 }
 
+/// <summary>
+/// Kind of non-text content embedded in a block.
+/// Mirrors the Rust <c>AttachmentType</c> enum; serialised as a snake_case string.
+/// </summary>
+[JsonConverter(typeof(JsonStringEnumConverter))]
+public enum AttachmentType
+{
+    [JsonPropertyName("image")]           Image,
+    [JsonPropertyName("embedded_object")] EmbeddedObject,
+}
+
 /// <summary>
 /// Provenance classification of a document ingested into RT_Flow.
 /// Mirrors the Rust <c>DocumentType</c> enum; serialised as a snake_case string.
@@ -120,7 +131,34 @@ public record RunFormatting(
 /// </summary>
 public record Run(
     [property: JsonPropertyName("text")]       string Text,
-    [property: JsonPropertyName("formatting")] RunFormatting Formatting
+    [property: JsonPropertyName("formatting")] RunFormatting Formatting,
+    /// <summary>
+    /// Tracked revision the run belongs to: <c>Insert</c> for text only in
+    /// the changes-accepted view, <c>Delete</c> for text only in the
+    /// changes-rejected one; <c>null</c> for text in both.
+    /// </summary>
+    [property: JsonPropertyName("revision")]   ChangeType? Revision = null
+);
+
+// ---------------------------------------------------------------------------
+// Attachment
+// ---------------------------------------------------------------------------
+
+/// <summary>
+/// Binary content anchored in a block (an image or embedded object); only a
+/// reference and a content hash are stored.
+/// Mirrors the Rust <c>Attachment</c> struct.
+/// </summary>
+public record Attachment(
+    [property: JsonPropertyName("attachment_type")] AttachmentType AttachmentType,
+    /// <summary>SHA-256 of the attachment bytes.</summary>
+    [property: JsonPropertyName("content_hash")]    string ContentHash,
+    /// <summary>
+    /// Where the bytes live, e.g. the package part <c>"word/media/image1.png"</c>.
+    /// </summary>
+    [property: JsonPropertyName("storage_ref")]     string StorageRef,
+    /// <summary>Display name or OLE program id, if known.</summary>
+    [property: JsonPropertyName("name")]            string? Name
 );
 
 // ---------------------------------------------------------------------------
@@ -237,7 +275,16 @@ public record Block(
     [property: JsonPropertyName("runs")]             List<Run> Runs,
     /// <summary>Direct children in document order.</summary>
     [property: JsonPropertyName("children")]         List<Block> Children
-);
+)
+{
+    /// <summary>
+    /// Images and embedded objects in document order; omitted from JSON when
+    /// the block has none.
+    /// </summary>
+    [JsonPropertyName("attachments")]
+    [JsonIgnore(Condition = JsonIgnoreCondition.WhenWritingNull)]
+    public List<Attachment>? Attachments { get; init; }
+}
 
 // ---------------------------------------------------------------------------
 // Document
@@ -278,5 +325,10 @@ public record Document(
     /// Arbitrary key/value metadata (e.g. parties, jurisdiction, matter ID);
     /// serialised as a free-form JSON object.
     /// </summary>
-    [property: JsonPropertyName("metadata")]               System.Text.Json.JsonElement? Metadata
+    [property: JsonPropertyName("metadata")]               System.Text.Json.JsonElement? Metadata,
+    /// <summary>
+    /// Strategy the anchor signatures were computed with: <c>"1"</c>, or a v2
+    /// strategy such as <c>"2+shingles"</c>.
+    /// </summary>
+    [property: JsonPropertyName("anchor_version")]         string AnchorVersion = "1"
 );
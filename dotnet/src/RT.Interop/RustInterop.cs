@@ -52,6 +52,12 @@ public sealed class RtflowManagedResult
 /// pointer to <see cref="rtflow_free"/> exactly once when they are done with
 /// it.  <see cref="MarshalResult"/> handles marshalling and freeing in a
 /// single, safe call.
+///
+/// Functions that read or write documents, workflows, merges or runs take
+/// the tenant they act for as their first argument, <c>tenantId</c>: 1 to 64
+/// ASCII letters, digits, <c>-</c>, <c>_</c> or <c>.</c>, or <c>null</c>
+/// for the <c>default</c> tenant.  Rows of other tenants are reported as not
+/// found.
 /// </summary>
 public static class RustInterop
 {
@@ -84,6 +90,139 @@ public static class RustInterop
     [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
     public static extern IntPtr rtflow_init(string dbPath);
 
+    /// <summary>
+    /// Report liveness / readiness as a <c>HealthReport</c> JSON object.
+    /// Succeeds even before <see cref="rtflow_init"/>; gate readiness
+    /// probes on the report's <c>ready</c> field.
+    /// </summary>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_health();
+
+    /// <summary>
+    /// Select the hash contract (and key) for newly ingested documents.
+    /// Call once, after <see cref="rtflow_init"/>.
+    /// </summary>
+    /// <param name="configJson">
+    /// JSON object with <c>contract_version</c> (<c>"1.0.0"</c>,
+    /// <c>"1.0.0-blake3"</c> or <c>"1.0.0-hmac-sha256"</c>) and an optional
+    /// hex-encoded <c>key_hex</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_configure_hashing(string configJson);
+
+    /// <summary>
+    /// Recompute a stored document's clause hashes and anchor signatures
+    /// under the current hash contract.  Compare and merge refuse documents
+    /// hashed under different contracts.  On success <c>data</c> holds a
+    /// <c>RehashSummary</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID string of the document.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_rehash_document(string? tenantId, string docId);
+
+    /// <summary>
+    /// Enable content-addressed block storage and move the tenant's stored
+    /// blocks onto it: identical blocks keep one shared copy of their tokens
+    /// and runs.  Reads are unchanged.  On success <c>data</c> holds a
+    /// <c>ContentMigration</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_migrate_content_store(string? tenantId);
+
+    /// <summary>
+    /// Select how stored block types and token kinds are read back:
+    /// <c>"lenient"</c> (default) maps unknown values to <c>paragraph</c> /
+    /// <c>word</c>; <c>"strict"</c> fails with an error naming the value.
+    /// </summary>
+    /// <param name="mode"><c>"strict"</c> or <c>"lenient"</c>.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_set_parsing_mode(string mode);
+
+    /// <summary>
+    /// Host callback writing <paramref name="len"/> bytes at
+    /// <paramref name="data"/> to the file at <paramref name="path"/> (a
+    /// null-terminated UTF-8 string).  Returns 0 on success.  May be invoked
+    /// from any thread.
+    /// </summary>
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate int RtflowWriteFn(IntPtr path, IntPtr data, UIntPtr len, IntPtr userData);
+
+    /// <summary>
+    /// Route file writes (exports and their artifacts) through the host
+    /// instead of the local file system.  The selection is process-wide.
+    /// Keep a reference to <paramref name="write"/> for as long as it is
+    /// registered so the delegate is not garbage collected.
+    /// </summary>
+    /// <param name="write">
+    /// Write callback, or <c>null</c> to write to the local file system again.
+    /// </param>
+    /// <param name="userData">
+    /// Opaque pointer handed back to every <paramref name="write"/> call.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_set_vfs(RtflowWriteFn? write, IntPtr userData);
+
+    /// <summary>
+    /// Host callback receiving one workflow or merge event as a
+    /// null-terminated UTF-8 JSON object with a <c>kind</c> of
+    /// <c>workflow_event</c> or <c>merge_conflicts</c>.  Runs synchronously
+    /// on the thread that persisted the event, possibly on several threads
+    /// at once; <paramref name="eventJson"/> is only valid during the call.
+    /// </summary>
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void RtflowEventFn(IntPtr eventJson, IntPtr userData);
+
+    /// <summary>
+    /// Notify the host of every persisted workflow event and of merges that
+    /// leave conflicts for review.  The registration is process-wide; once
+    /// this returns the previous callback is no longer running.  The
+    /// callback must not re-register itself or record workflow events.  Keep
+    /// a reference to <paramref name="callback"/> for as long as it is
+    /// registered so the delegate is not garbage collected.
+    /// </summary>
+    /// <param name="callback">
+    /// Event callback, or <c>null</c> to stop notifications.
+    /// </param>
+    /// <param name="userData">
+    /// Opaque pointer handed back to every <paramref name="callback"/> call.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_set_event_callback(RtflowEventFn? callback, IntPtr userData);
+
     // -----------------------------------------------------------------------
     // Document ingestion
     // -----------------------------------------------------------------------
@@ -92,6 +231,9 @@ public static class RustInterop
     /// Ingest a JSON array of blocks into the store under the given document
     /// UUID.
     /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
     /// <param name="json">Serialized block array.</param>
     /// <param name="docId">UUID string identifying the document.</param>
     /// <returns>
@@ -99,83 +241,1682 @@ public static class RustInterop
     /// <see cref="rtflow_free"/>.
     /// </returns>
     [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
-    public static extern IntPtr rtflow_ingest_blocks(string json, string docId);
+    public static extern IntPtr rtflow_ingest_blocks(string? tenantId, string json, string docId);
 
-    // -----------------------------------------------------------------------
-    // Compare
-    // -----------------------------------------------------------------------
+    /// <summary>
+    /// Like <see cref="rtflow_ingest_blocks"/>, with ingest options: how blocks
+    /// already stored are handled (<c>on_conflict</c>: <c>fail</c>,
+    /// <c>skip</c> or <c>replace</c>) and whether to only validate
+    /// (<c>dry_run</c>), returning the validation report.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="json">Serialized block array.</param>
+    /// <param name="docId">UUID string identifying the document.</param>
+    /// <param name="optionsJson">Ingest options JSON; <c>"{}"</c> for the defaults.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_ingest_blocks_with_options(
+        string? tenantId,
+        string json,
+        string docId,
+        string optionsJson);
 
     /// <summary>
-    /// Compare two documents and return a <c>CompareResult</c> JSON object.
+    /// Start ingesting blocks into a document in chunks, for documents too
+    /// large for <see cref="rtflow_ingest_blocks"/>. Returns
+    /// <c>{"batch_id": ..., "doc_id": ..., "count": 0}</c>; the batch holds
+    /// one transaction open until it is committed or aborted, or until it has
+    /// gone five minutes without an append, when it is rolled back.
     /// </summary>
-    /// <param name="leftDocId">UUID of the left (base) document.</param>
-    /// <param name="rightDocId">UUID of the right (incoming) document.</param>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID string identifying the document.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_ingest_begin(string? tenantId, string docId);
+
+    /// <summary>
+    /// Send the next chunk of NDJSON blocks (one block object per line) to an
+    /// ingest batch. A chunk may end mid-line; a failure rolls the whole
+    /// batch back.
+    /// </summary>
+    /// <param name="batchId">Batch id returned by <see cref="rtflow_ingest_begin"/>.</param>
+    /// <param name="chunk">NDJSON text.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_ingest_append(string batchId, string chunk);
+
+    /// <summary>
+    /// Commit an ingest batch; the result is as for
+    /// <see cref="rtflow_ingest_blocks"/>.
+    /// </summary>
+    /// <param name="batchId">Batch id returned by <see cref="rtflow_ingest_begin"/>.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_ingest_commit(string batchId);
+
+    /// <summary>
+    /// Roll back an ingest batch and return <c>{"aborted": bool}</c>.
+    /// </summary>
+    /// <param name="batchId">Batch id returned by <see cref="rtflow_ingest_begin"/>.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_ingest_abort(string batchId);
+
+    /// <summary>
+    /// Read a <c>.docx</c> file directly and ingest its blocks under the
+    /// given document UUID.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="path">Filesystem path of the <c>.docx</c> file.</param>
+    /// <param name="docId">UUID string for the new document.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_ingest_docx(string? tenantId, string path, string docId);
+
+    /// <summary>
+    /// Like <see cref="rtflow_ingest_docx"/>, with ingest options.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="path">Filesystem path of the <c>.docx</c> file.</param>
+    /// <param name="docId">UUID string for the new document.</param>
     /// <param name="optionsJson">
-    /// JSON object with compare options.  Pass <c>"{}"</c> for defaults.
+    /// JSON object, e.g. <c>{"max_block_tokens": 512}</c> to split longer
+    /// blocks at sentence boundaries; <c>"anchor_version"</c> selects the
+    /// anchor strategy (<c>"1"</c>, <c>"2"</c>, <c>"2+shingles"</c> or
+    /// <c>"2+path+shingles"</c>).  Pass <c>"{}"</c> for defaults.
     /// </param>
     /// <returns>
     /// Pointer to a <c>RtflowResult</c>.  Must be freed with
     /// <see cref="rtflow_free"/>.
     /// </returns>
     [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
-    public static extern IntPtr rtflow_compare(
-        string leftDocId,
-        string rightDocId,
+    public static extern IntPtr rtflow_ingest_docx_with_options(
+        string? tenantId,
+        string path,
+        string docId,
         string optionsJson);
 
-    // -----------------------------------------------------------------------
-    // Merge
-    // -----------------------------------------------------------------------
+    /// <summary>
+    /// Segment plain contract text (OCR output, pasted text) into blocks by
+    /// its numbering labels and ingest it under the given document UUID.
+    /// On success <c>data</c> holds the same payload as
+    /// <see cref="rtflow_ingest_docx"/>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="text">The contract text.</param>
+    /// <param name="docId">UUID string for the new document.</param>
+    /// <param name="optionsJson">
+    /// JSON object, e.g. <c>{"name": "msa.txt", "max_block_tokens": 512}</c>.
+    /// Pass <c>"{}"</c> for defaults.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_ingest_text(
+        string? tenantId,
+        string text,
+        string docId,
+        string optionsJson);
 
     /// <summary>
-    /// Merge an incoming document into a base document and return a
-    /// <c>MergeResult</c> JSON object.
+    /// Re-read a <c>.docx</c> file into an already ingested document,
+    /// writing only the blocks that changed.  Unchanged and edited blocks
+    /// keep their ids.  On success <c>data</c> holds
+    /// <c>{"doc_id", "doc_type", "unchanged", "inserted", "updated",
+    /// "deleted", "near_duplicates", "warnings"}</c>.
     /// </summary>
-    /// <param name="baseDocId">UUID of the base document.</param>
-    /// <param name="incomingDocId">UUID of the incoming document.</param>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="path">Filesystem path of the <c>.docx</c> file.</param>
+    /// <param name="docId">UUID string of the stored document.</param>
     /// <param name="optionsJson">
-    /// JSON object with merge options.  Pass <c>"{}"</c> for defaults.
+    /// Ingest options as for <see cref="rtflow_ingest_docx_with_options"/>.
+    /// Pass <c>"{}"</c> for defaults.
     /// </param>
     /// <returns>
     /// Pointer to a <c>RtflowResult</c>.  Must be freed with
     /// <see cref="rtflow_free"/>.
     /// </returns>
     [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
-    public static extern IntPtr rtflow_merge(
-        string baseDocId,
-        string incomingDocId,
+    public static extern IntPtr rtflow_reingest_docx(
+        string? tenantId,
+        string path,
+        string docId,
         string optionsJson);
 
-    // -----------------------------------------------------------------------
-    // Workflow
-    // -----------------------------------------------------------------------
+    /// <summary>
+    /// Store the changes-accepted or changes-rejected reading of a redline
+    /// as a new document, so compare and merge can run against the intended
+    /// baseline.  On success <c>data</c> holds the view <c>Document</c>,
+    /// whose metadata carries <c>view_of</c> and <c>view</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID string of the stored document.</param>
+    /// <param name="mode">
+    /// <c>"accepted"</c> (every tracked change accepted) or
+    /// <c>"rejected"</c> (every one rejected).
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_materialize_view(
+        string? tenantId,
+        string docId,
+        string mode);
 
     /// <summary>
-    /// Submit a workflow event and advance the workflow state machine.
+    /// List stored documents that are near duplicates of a document.  The
+    /// ingest functions run the same check and report matches in their
+    /// <c>near_duplicates</c> and <c>warnings</c> fields.
     /// </summary>
-    /// <param name="workflowId">UUID of the workflow.</param>
-    /// <param name="eventJson">JSON object describing the event.</param>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID string of the document.</param>
+    /// <param name="threshold">
+    /// Minimum similarity in (0, 1]; pass <c>0</c> for the default of 0.95.
+    /// </param>
     /// <returns>
-    /// Pointer to a <c>RtflowResult</c> containing the updated
-    /// <c>WorkflowState</c> JSON on success.  Must be freed with
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
     /// <see cref="rtflow_free"/>.
     /// </returns>
     [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
-    public static extern IntPtr rtflow_workflow_event(
-        string workflowId,
-        string eventJson);
+    public static extern IntPtr rtflow_find_near_duplicates(
+        string? tenantId,
+        string docId,
+        double threshold);
 
     /// <summary>
-    /// Retrieve the current state of a workflow.
+    /// Return the defined-term dictionary of a document, extracted from its
+    /// definition clauses at ingest, as a JSON array of
+    /// <c>{ term, normalized, block_id }</c> objects in document order.
     /// </summary>
-    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID string of the document.</param>
     /// <returns>
-    /// Pointer to a <c>RtflowResult</c> containing the current
-    /// <c>WorkflowState</c> JSON on success.  Must be freed with
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_get_defined_terms(string? tenantId, string docId);
+
+    /// <summary>
+    /// Return the parties (defined in the preamble) and dates of a document,
+    /// recognised at ingest, as a <c>DocumentEntities</c> JSON object with
+    /// <c>parties</c> and <c>dates</c> arrays in document order.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID string of the document.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_get_entities(string? tenantId, string docId);
+
+    /// <summary>
+    /// Return the section references found in a document's text at ingest,
+    /// as a JSON array of <c>{ block_id, text, target, offset }</c> objects
+    /// in document order.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID string of the document.</param>
+    /// <param name="target">
+    /// Structural path to return references to, or an empty string for all.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_get_cross_references(
+        string? tenantId,
+        string docId,
+        string target);
+
+    /// <summary>
+    /// Resolve a deep-link anchor from a compare result's <c>anchors</c> to
+    /// current block ids, following blocks across re-ingestion.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="queryJson">
+    /// JSON object with <c>anchor</c> and <c>doc_id</c> (block anchors) or
+    /// <c>left_doc_id</c> / <c>right_doc_id</c> (delta anchors).
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_resolve_anchor(string? tenantId, string queryJson);
+
+    /// <summary>
+    /// Store a point-in-time snapshot of a document for audit: a deep copy
+    /// of its block tree under new ids, with <c>snapshot_of</c> metadata
+    /// linking back to the original.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID of the document to snapshot.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing the snapshot
+    /// <c>Document</c> on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_create_snapshot(string? tenantId, string docId);
+
+    /// <summary>
+    /// List stored documents matching a filter, oldest ingest first.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="filterJson">
+    /// JSON object with optional <c>doc_type</c>, <c>name_contains</c>
+    /// (case-insensitive), <c>ingested_from</c> (inclusive),
+    /// <c>ingested_to</c> (exclusive), <c>metadata_key</c> and
+    /// <c>metadata_value</c> filters plus <c>offset</c> and <c>limit</c>
+    /// (default 100, max 1000).  Pass <c>""</c> for all documents.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>Document</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_list_documents(string? tenantId, string filterJson);
+
+    /// <summary>
+    /// Full-text search over the text of stored blocks, best match first.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="queryJson">
+    /// JSON object with a required <c>query</c> string and optional
+    /// <c>document_ids</c>, <c>doc_type</c>, <c>block_type</c>,
+    /// <c>offset</c> and <c>limit</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>BlockSearchHit</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_search_blocks(string? tenantId, string queryJson);
+
+    /// <summary>
+    /// Check that every stored block's runs reproduce its
+    /// <c>display_text</c>, for all documents matching a filter.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="filterJson">
+    /// The same filter object as <see cref="rtflow_list_documents"/>;
+    /// <c>offset</c> and <c>limit</c> are ignored.  Pass <c>""</c> for all
+    /// documents.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>FidelityReport</c> objects, one per document, on success.  Must be
+    /// freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_check_fidelity(string? tenantId, string filterJson);
+
+    // -----------------------------------------------------------------------
+    // Document versions
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Record that a document is the next version of another stored
+    /// document, and return the resulting version chain.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID of the newer document.</param>
+    /// <param name="previousVersionId">UUID of the document it revises.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_link_version(
+        string? tenantId,
+        string docId,
+        string previousVersionId);
+
+    /// <summary>
+    /// The version chain a document belongs to, oldest version first.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID of any document in the chain.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>DocumentVersion</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_document_versions(string? tenantId, string docId);
+
+    // -----------------------------------------------------------------------
+    // Clause history
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Every stored version of the clause with the given anchor signature,
+    /// ordered by document ingestion time, each with the token diff from
+    /// the previous version.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="anchorSignature">The block's <c>anchor_signature</c>.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a <c>ClauseHistory</c>
+    /// JSON object on success.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_clause_history(string? tenantId, string anchorSignature);
+
+    /// <summary>
+    /// The clauses of a document that also appear elsewhere, within the
+    /// document or in other stored documents, grouped by clause hash.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID of the document.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>DuplicateCluster</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_find_duplicate_clauses(string? tenantId, string docId);
+
+    // -----------------------------------------------------------------------
+    // Compare
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Compare two documents and return a <c>CompareResult</c> JSON object.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="leftDocId">UUID of the left (base) document.</param>
+    /// <param name="rightDocId">UUID of the right (incoming) document.</param>
+    /// <param name="optionsJson">
+    /// JSON object with compare options.  Pass <c>"{}"</c> for defaults.  A
+    /// <c>workflow_id</c> applies that workflow's compare overrides to every
+    /// option not given; with it, <c>"progress_events": true</c> records
+    /// throttled <c>compare_progress</c> events in the workflow's log.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare(
+        string? tenantId,
+        string leftDocId,
+        string rightDocId,
+        string optionsJson);
+
+    /// <summary>
+    /// Compare the two latest versions of a document's version chain and
+    /// return a <c>CompareResult</c> JSON object.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="docId">UUID of any document in the chain.</param>
+    /// <param name="optionsJson">
+    /// JSON object with compare options, as for <see cref="rtflow_compare"/>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_versions(
+        string? tenantId,
+        string docId,
+        string optionsJson);
+
+    /// <summary>
+    /// Compare two documents and return a JSON object with
+    /// <c>diffstat</c> and <c>unified</c> text renderings of the result.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="leftDocId">UUID of the left (base) document.</param>
+    /// <param name="rightDocId">UUID of the right (incoming) document.</param>
+    /// <param name="optionsJson">
+    /// JSON object with compare options.  Pass <c>"{}"</c> for defaults.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_text(
+        string? tenantId,
+        string leftDocId,
+        string rightDocId,
+        string optionsJson);
+
+    /// <summary>
+    /// Compare two documents again after an edit, reusing the deltas of an
+    /// earlier comparison for every block the edit did not touch.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="previous">
+    /// UUID of a recorded compare run, or a <c>CompareResult</c> JSON object.
+    /// </param>
+    /// <param name="dirtyJson">
+    /// JSON array of the UUIDs of every changed or added block.
+    /// </param>
+    /// <param name="optionsJson">
+    /// JSON object with compare options.  Pass <c>"{}"</c> for defaults.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> whose data is a
+    /// <c>CompareResult</c>.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_incremental(
+        string? tenantId,
+        string previous,
+        string dirtyJson,
+        string optionsJson);
+
+    /// <summary>
+    /// Render a comparison as a self-contained HTML redline with insertions
+    /// underlined, deletions struck through and moved blocks annotated.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="runIdOrJson">
+    /// UUID of a recorded compare run, or a <c>CompareResult</c> JSON object.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> whose data is <c>{"html": ...}</c>.
+    /// Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_render_compare_html(string? tenantId, string runIdOrJson);
+
+    /// <summary>
+    /// Report how two comparisons of the same document pair differ: the deltas
+    /// that appeared or disappeared, and the block pairs whose kind changed.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="before">
+    /// UUID of a recorded compare run, or a <c>CompareResult</c> JSON object.
+    /// </param>
+    /// <param name="after">
+    /// UUID of a recorded compare run, or a <c>CompareResult</c> JSON object,
+    /// of the same left and right documents.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> whose data is a <c>ResultDiff</c>
+    /// (<c>{appeared, disappeared, reclassified, unchanged}</c>).
+    /// Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_result_diff(
+        string? tenantId,
+        string before,
+        string after);
+
+    /// <summary>
+    /// Compare a base document against itself as changed by an amendment
+    /// document's instructions, tracing each change to the amendment block
+    /// that made it.  The run is not recorded in the run history.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="baseDocId">UUID of the amended document.</param>
+    /// <param name="amendmentDocId">UUID of the amendment document.</param>
+    /// <param name="optionsJson">
+    /// JSON object with compare options.  Pass <c>"{}"</c> for defaults.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> whose data is an
+    /// <c>AmendmentCompareResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_amendment(
+        string? tenantId,
+        string baseDocId,
+        string amendmentDocId,
+        string optionsJson);
+
+    /// <summary>
+    /// Apply an amendment document's instructions to a base document and
+    /// return the as-amended view.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="baseDocId">UUID of the amended document.</param>
+    /// <param name="amendmentDocId">UUID of the amendment document.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> whose data is an <c>AmendedView</c>.
+    /// Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_apply_amendment(
+        string? tenantId,
+        string baseDocId,
+        string amendmentDocId);
+
+    /// <summary>
+    /// Start comparing two documents in the background and return
+    /// <c>{"run_id": ...}</c>.  Drain the deltas with
+    /// <see cref="rtflow_compare_poll"/>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="leftDocId">UUID of the left (base) document.</param>
+    /// <param name="rightDocId">UUID of the right (incoming) document.</param>
+    /// <param name="optionsJson">
+    /// JSON object with compare options.  Pass <c>"{}"</c> for defaults.
+    /// </param>
+    /// <param name="batchSize">
+    /// Block alignments diffed per batch; <c>0</c> for the default (64).
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_start(
+        string? tenantId,
+        string leftDocId,
+        string rightDocId,
+        string optionsJson,
+        uint batchSize);
+
+    /// <summary>
+    /// Take the deltas completed so far by a streamed compare run, in
+    /// document order.  The final poll has <c>done = true</c> and carries the
+    /// finished <c>CompareResult</c> (with an empty <c>deltas</c> list); the
+    /// run is forgotten afterwards.  Another tenant's run is reported unknown.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="runId">Run id returned by <see cref="rtflow_compare_start"/>.</param>
+    /// <param name="maxDeltas">Most deltas to return; <c>0</c> for all queued.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_poll(string? tenantId, string runId, uint maxDeltas);
+
+    /// <summary>
+    /// Abandon (and stop) a streamed compare run and return
+    /// <c>{"cancelled": bool}</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="runId">Run id returned by <see cref="rtflow_compare_start"/>.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_cancel(string? tenantId, string runId);
+
+    /// <summary>
+    /// Compare two documents on a background worker and return
+    /// <c>{"job_id": ..., "run_id": ...}</c> at once.  At most four jobs run
+    /// at a time; later ones wait their turn.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="leftDocId">UUID of the left (original) document.</param>
+    /// <param name="rightDocId">UUID of the right (modified) document.</param>
+    /// <param name="optionsJson">Compare options, as for <see cref="rtflow_compare"/>.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_async(
+        string? tenantId,
+        string leftDocId,
+        string rightDocId,
+        string optionsJson);
+
+    /// <summary>
+    /// Report the state and progress percentage of a background compare or
+    /// merge job.  Another tenant's job is reported unknown.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="jobId">
+    /// Job id returned by <see cref="rtflow_compare_async"/> or
+    /// <see cref="rtflow_merge_async"/>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_job_status(string? tenantId, string jobId);
+
+    /// <summary>
+    /// Collect the <c>CompareResult</c> (or <c>MergeResult</c>) of a finished
+    /// background job.
+    /// Fails while the job is running; the job is forgotten once collected,
+    /// or an hour after it finished if it never is.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="jobId">
+    /// Job id returned by <see cref="rtflow_compare_async"/> or
+    /// <see cref="rtflow_merge_async"/>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_job_result(string? tenantId, string jobId);
+
+    /// <summary>
+    /// Ask a background compare or merge job to stop and return
+    /// <c>{"cancelled": bool}</c>.  The job reports the <c>cancelled</c>
+    /// state once the engine notices.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="jobId">
+    /// Job id returned by <see cref="rtflow_compare_async"/> or
+    /// <see cref="rtflow_merge_async"/>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_job_cancel(string? tenantId, string jobId);
+
+    /// <summary>
+    /// Per-section delta counts of a compare run, for an outline navigation
+    /// tree.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="runId">
+    /// Run id of a <see cref="rtflow_compare"/> or
+    /// <see cref="rtflow_compare_start"/> run.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>{"section", "total", "by_kind"}</c> objects in document order on
+    /// success.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_sections(string? tenantId, string runId);
+
+    /// <summary>
+    /// One page of the deltas of a compare run under one section.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="queryJson">
+    /// JSON object with <c>run_id</c> and <c>section</c>, plus optional
+    /// <c>kind</c>, <c>offset</c> and <c>limit</c> (default 100, max 1000).
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>BlockDelta</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_compare_section_deltas(string? tenantId, string queryJson);
+
+    // -----------------------------------------------------------------------
+    // Run history
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Return the recorded <c>RunRecord</c> (<c>run_kind</c>, <c>manifest</c>,
+    /// <c>recorded_at</c>) of a compare or merge run.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="runId">
+    /// <c>run_id</c> of a <c>CompareResult</c> or <c>merge_id</c> of a
+    /// <c>MergeResult</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_run_manifest(string? tenantId, string runId);
+
+    /// <summary>
+    /// List every recorded compare and merge run that read a document,
+    /// newest first.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="documentId">UUID of the document.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_run_history(string? tenantId, string documentId);
+
+    /// <summary>
+    /// Recompute the manifest of a recorded run and report every component
+    /// (engine version, options, input document) that changed since, as
+    /// <c>{"reproducible": bool, "drift": [...]}</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="runId">UUID of the recorded run.</param>
+    /// <param name="optionsJson">
+    /// Compare options a re-run would use; pass <c>"{}"</c> for defaults.
+    /// Ignored for merge runs.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_reproduce_check(
+        string? tenantId,
+        string runId,
+        string optionsJson);
+
+    /// <summary>
+    /// Flattens a <c>CompareResult</c> into JSONL annotation records (one per
+    /// changed block, with before/after text, section heading, tags and risk
+    /// score) for an external summarizer.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="compareResultJson">
+    /// <c>CompareResult</c> JSON as returned by <see cref="rtflow_compare"/>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_export_annotations(
+        string? tenantId,
+        string compareResultJson);
+
+    /// <summary>
+    /// Attaches summarizer output back onto the deltas of a
+    /// <c>CompareResult</c>.
+    /// </summary>
+    /// <param name="compareResultJson">The result the annotations came from.</param>
+    /// <param name="summariesJsonl">
+    /// One JSON object per line with <c>delta_id</c> and <c>summary</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_attach_summaries(
+        string compareResultJson,
+        string summariesJsonl);
+
+    // -----------------------------------------------------------------------
+    // Threshold calibration
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Records a reviewer's correction of how two blocks align.  A later
+    /// correction of the same pair in the same corpus replaces the earlier one.
+    /// </summary>
+    /// <param name="overrideJson">
+    /// JSON object <c>{"corpus", "left_block_id", "right_block_id", "label"}</c>
+    /// where <c>label</c> is <c>match</c>, <c>move</c> or <c>distinct</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_record_alignment_override(string overrideJson);
+
+    /// <summary>
+    /// Fits <c>similarity_threshold</c> and <c>move_threshold</c> to the
+    /// alignment overrides of a corpus and stores them in a compare preset,
+    /// keeping any other options already in the preset.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="corpus">Corpus whose overrides are used.</param>
+    /// <param name="presetName">Preset to create or update.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_calibrate_thresholds(
+        string? tenantId,
+        string corpus,
+        string presetName);
+
+    /// <summary>
+    /// Loads a stored compare preset, ready to pass as <c>optionsJson</c> to
+    /// <see cref="rtflow_compare"/>.
+    /// </summary>
+    /// <param name="name">Preset name.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_get_compare_preset(string name);
+
+    // -----------------------------------------------------------------------
+    // Merge
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Merge an incoming document into a base document and return a
+    /// <c>MergeResult</c> JSON object.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="baseDocId">UUID of the base document.</param>
+    /// <param name="incomingDocId">UUID of the incoming document.</param>
+    /// <param name="optionsJson">
+    /// JSON object with merge options (<c>similarity_threshold</c>,
+    /// <c>auto_accept_insertions</c>, <c>auto_accept_deletions</c>).  Pass
+    /// <c>"{}"</c> for defaults.  A <c>workflow_id</c> applies that
+    /// workflow's merge overrides to every option not given.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_merge(
+        string? tenantId,
+        string baseDocId,
+        string incomingDocId,
+        string optionsJson);
+
+    /// <summary>
+    /// Merge an incoming document into a base document on a background
+    /// worker and return <c>{"job_id": ..., "merge_id": ...}</c> at once.
+    /// Poll, cancel and collect it with the job functions.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="baseDocId">UUID of the base document.</param>
+    /// <param name="incomingDocId">UUID of the incoming document.</param>
+    /// <param name="optionsJson">Merge options, as for <see cref="rtflow_merge"/>.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_merge_async(
+        string? tenantId,
+        string baseDocId,
+        string incomingDocId,
+        string optionsJson);
+
+    /// <summary>
+    /// Three-way merge of a base and an incoming document against their
+    /// common ancestor and return a <c>MergeResult</c> JSON object.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="ancestorDocId">UUID of the common ancestor document.</param>
+    /// <param name="baseDocId">UUID of the base document.</param>
+    /// <param name="incomingDocId">UUID of the incoming document.</param>
+    /// <param name="optionsJson">
+    /// JSON object with merge options.  Pass <c>"{}"</c> for defaults.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_merge3(
+        string? tenantId,
+        string ancestorDocId,
+        string baseDocId,
+        string incomingDocId,
+        string optionsJson);
+
+    /// <summary>
+    /// Reload a merge recorded by <see cref="rtflow_merge"/> and return its
+    /// <c>MergeResult</c> JSON object.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="mergeId">UUID of the merge run.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_get_merge(string? tenantId, string mergeId);
+
+    /// <summary>
+    /// Re-run a merge that failed or was left running by a process that
+    /// died, keeping the resolutions of conflicts detected again, and return
+    /// its <c>MergeResult</c> JSON object.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="mergeId">UUID of the merge run.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_resume_merge(string? tenantId, string mergeId);
+
+    /// <summary>
+    /// List the merge runs in one run state as a JSON array of
+    /// <c>MergeRun</c> objects.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="state">
+    /// <c>"running"</c>, <c>"failed"</c> or <c>"completed"</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_list_merge_runs(string? tenantId, string state);
+
+    /// <summary>
+    /// Mark running merges whose lease has expired as failed and return
+    /// their ids as a JSON array.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_expire_stale_merges(string? tenantId);
+
+    /// <summary>
+    /// Delete a failed merge run with its conflicts and suggestions.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="mergeId">UUID of the merge run.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_delete_merge_run(string? tenantId, string mergeId);
+
+    /// <summary>
+    /// List the conflicts recorded for a merge, with their current
+    /// resolution states, as a JSON array of <c>MergeConflict</c> objects.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="mergeId">UUID of the merge run.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_list_conflicts(string? tenantId, string mergeId);
+
+    /// <summary>
+    /// Compute ranked resolution suggestions, each with a rationale, for the
+    /// pending conflicts of a merge and store them alongside the conflicts.
+    /// Suggestions are never applied automatically.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="mergeId">UUID of the merge run.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_suggest_resolutions(string? tenantId, string mergeId);
+
+    /// <summary>
+    /// List the resolution suggestions stored for a merge.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="mergeId">UUID of the merge run.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_list_suggestions(string? tenantId, string mergeId);
+
+    // -----------------------------------------------------------------------
+    // Export
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Render one reviewer's changes against the base document, write the
+    /// redline to <c>output_path</c> and record it as an artifact of the
+    /// layer's workflow; returns the recorded <c>Artifact</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="baseDocId">UUID of the base document.</param>
+    /// <param name="exportJson">
+    /// JSON object with <c>layer</c> (<c>ReviewLayer</c>), <c>deltas</c>,
+    /// optional <c>comments</c>, <c>format</c> (<c>"html"</c> or
+    /// <c>"docx"</c>), <c>output_path</c> and optional
+    /// <c>comments_output_path</c>.  The layer's workflow must be one of the
+    /// tenant's, reviewing the base document.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_export_reviewer_redline(
+        string? tenantId,
+        string baseDocId,
+        string exportJson);
+
+    // -----------------------------------------------------------------------
+    // Workflow
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Select the state machine every workflow follows; call once at
+    /// start-up.  <c>COMPLETED</c> and <c>ABORTED</c> stay terminal whatever
+    /// the definition.
+    /// </summary>
+    /// <param name="definitionJson">JSON object <c>{"transitions": [{"from":
+    /// "DRAFT", "event": "compare_started", "to": "COMPARE_RUNNING"},
+    /// ...]}</c>, or an empty string for the built-in machine.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing the selected definition
+    /// on success.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_set_state_machine(string definitionJson);
+
+    /// <summary>
+    /// Report the state machine workflows follow, as accepted by
+    /// <see cref="rtflow_set_state_machine"/>.
+    /// </summary>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing the definition JSON.
+    /// Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_get_state_machine();
+
+    /// <summary>
+    /// Create a workflow in <c>DRAFT</c> for an ingested document and emit its
+    /// <c>workflow_created</c> event.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="documentId">UUID of the document.</param>
+    /// <param name="initiatorId">User or system starting the workflow.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing the new <c>Workflow</c>
+    /// JSON on success.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_create(
+        string? tenantId,
+        string documentId,
+        string initiatorId);
+
+    /// <summary>
+    /// Create a workflow as <see cref="rtflow_workflow_create"/>, with an
+    /// optional approval policy gating its <c>workflow_completed</c> event
+    /// and optional per-state deadlines.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="documentId">UUID of the document.</param>
+    /// <param name="initiatorId">User or system starting the workflow.</param>
+    /// <param name="optionsJson">
+    /// JSON object: <c>{"approval_policy": {"required": 2, "approvers":
+    /// [...]}, "deadlines": [{"state": "IN_REVIEW", "within":
+    /// {"business_days": 5}}]}</c>, or an empty string for none.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing the new <c>Workflow</c>
+    /// JSON on success.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_create_with_options(
+        string? tenantId,
+        string documentId,
+        string initiatorId,
+        string optionsJson);
+
+    /// <summary>
+    /// Report a workflow's approval policy and the approvers who have
+    /// signed off so far.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing an <c>ApprovalStatus</c>
+    /// on success.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_approvals(string? tenantId, string workflowId);
+
+    /// <summary>
+    /// Record a <c>deadline_breached</c> event for every workflow past due
+    /// in its current state; call on a schedule.
+    /// </summary>
+    /// <param name="now">RFC 3339 time to check against, or an empty string
+    /// for the current time.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>DeadlineBreach</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_check_deadlines(string now);
+
+    /// <summary>
+    /// Submit a workflow event and advance the workflow state machine.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <param name="eventJson">
+    /// JSON object describing the event.  Set <c>snapshot_on_close</c> on a
+    /// <c>review_closed</c> event to snapshot the workflow's document first.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing the updated
+    /// <c>WorkflowState</c> JSON on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_event(
+        string? tenantId,
+        string workflowId,
+        string eventJson);
+
+    /// <summary>
+    /// Retrieve the current state of a workflow.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing the current
+    /// <c>WorkflowState</c> JSON on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_state(string? tenantId, string workflowId);
+
+    /// <summary>
+    /// Retrieve the event log of a workflow, ordered by sequence number, for
+    /// audit views.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <param name="optionsJson">
+    /// JSON object with optional <c>after_seq</c> (exclusive) and
+    /// <c>limit</c>.  Pass <c>""</c> for the whole log.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>WorkflowEvent</c> objects (actor, payload, seq, created_at) on
+    /// success.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_events(
+        string? tenantId,
+        string workflowId,
+        string optionsJson);
+
+    /// <summary>
+    /// Verify the hash chain of a workflow's event log.  Fails with a
+    /// "hash mismatch" error if any event was edited, reordered or deleted.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing <c>verified_events</c>,
+    /// <c>unverified_events</c> and <c>head_hash</c> on success.  Must be
+    /// freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_verify_chain(string? tenantId, string workflowId);
+
+    /// <summary>
+    /// List workflows matching a filter, oldest first.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="filterJson">
+    /// JSON object with optional <c>document_id</c>, <c>state</c>,
+    /// <c>initiator_id</c>, <c>created_from</c> (inclusive) and
+    /// <c>created_to</c> (exclusive) filters plus <c>offset</c> and
+    /// <c>limit</c> (default 100, max 1000).  Pass <c>""</c> for all workflows.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>Workflow</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_list(string? tenantId, string filterJson);
+
+    /// <summary>
+    /// Store a workflow's compare and merge configuration overrides, applied
+    /// to every compare or merge whose options carry the workflow's
+    /// <c>workflow_id</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <param name="configJson">
+    /// JSON object <c>{"compare": {...}, "merge": {...}}</c> with options in
+    /// the shape <see cref="rtflow_compare"/> / <see cref="rtflow_merge"/>
+    /// accept.  Either section may be omitted; pass <c>""</c> to clear every
+    /// override.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing the stored
+    /// <c>WorkflowConfig</c> on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_set_config(
+        string? tenantId,
+        string workflowId,
+        string configJson);
+
+    /// <summary>
+    /// Retrieve a workflow's compare and merge configuration overrides.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a <c>WorkflowConfig</c>
+    /// on success.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_get_config(string? tenantId, string workflowId);
+
+    /// <summary>
+    /// List the reviewers assigned to a workflow through
+    /// <c>reviewer_assigned</c> and <c>reviewer_unassigned</c> events.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>Assignment</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_reviewers(string? tenantId, string workflowId);
+
+    /// <summary>
+    /// Start a sub-workflow of a workflow in review for one of its assigned
+    /// reviewers.  The parent's <c>review_closed</c> is refused until every
+    /// sub-workflow has completed or been aborted.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the parent workflow.</param>
+    /// <param name="reviewerId">Reviewer assigned to the parent workflow.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing the new <c>Workflow</c>
+    /// JSON object on success.  Must be freed with <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_create_subworkflow(
+        string? tenantId,
+        string workflowId,
+        string reviewerId);
+
+    /// <summary>
+    /// List the sub-workflows of a workflow with their latest states.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the parent workflow.</param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>Subworkflow</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_subworkflows(string? tenantId, string workflowId);
+
+    /// <summary>
+    /// Suggest senior reviewers for the heavily changed or risk-tagged
+    /// sections of a compare run, optionally recording the suggestions as
+    /// <c>ReviewerAssigned</c> events.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="workflowId">UUID of the workflow.</param>
+    /// <param name="requestJson">
+    /// JSON object: <c>{"run_id": "...", "rules": {"density_threshold": 0.5,
+    /// "min_changes": 3, "risk_tags": [...], "senior_reviewers": [...]},
+    /// "section_tags": {...}, "apply": false, "actor": "system"}</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c> containing a JSON array of
+    /// <c>RoutingSuggestion</c> objects on success.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_workflow_route_review(
+        string? tenantId,
+        string workflowId,
+        string requestJson);
+
+    // -----------------------------------------------------------------------
+    // Review activity
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Record a reviewer's layer and its deltas for review analytics.
+    /// Deltas already recorded are left unchanged.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="layerJson">
+    /// <c>LayerDeltas</c> JSON object: <c>{"layer": ..., "deltas": [...]}</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_save_review_layer(string? tenantId, string layerJson);
+
+    /// <summary>
+    /// Start a reviewer's layer of edits on a stored document; returns the
+    /// new <c>ReviewLayer</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="layerJson">
+    /// JSON object <c>{"workflow_id": ..., "reviewer_id": ...,
+    /// "document_id": ...}</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_create_review_layer(string? tenantId, string layerJson);
+
+    /// <summary>
+    /// Record one edit in a review layer; returns the recorded
+    /// <c>BlockDelta</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="deltaJson">
+    /// JSON object with <c>review_layer_id</c>, <c>block_id</c>,
+    /// <c>delta_type</c> (<c>insert</c>, <c>delete</c>, <c>modify</c>),
+    /// <c>token_start</c>, <c>token_end</c> and optional <c>delta_payload</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_append_review_delta(string? tenantId, string deltaJson);
+
+    /// <summary>
+    /// List the recorded edits of a review layer, or of every layer on a
+    /// block, as a JSON array of <c>BlockDelta</c> objects.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="queryJson">
+    /// JSON object with exactly one of <c>review_layer_id</c> or
+    /// <c>block_id</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_list_review_deltas(string? tenantId, string queryJson);
+
+    /// <summary>
+    /// Delete a review layer and every edit recorded in it.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="reviewLayerId">
+    /// UUID string of the layer.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_delete_review_layer(string? tenantId, string reviewLayerId);
+
+    /// <summary>
+    /// Merge every review layer recorded on a document; returns a
+    /// <c>LayerMergeResult</c>.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="documentId">
+    /// UUID string of the reviewed document.
+    /// </param>
+    /// <param name="optionsJson">
+    /// Merge options as for <see cref="rtflow_merge"/>; may be <c>"{}"</c>.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_merge_review_layers(
+        string? tenantId,
+        string documentId,
+        string optionsJson);
+
+    /// <summary>
+    /// Count recorded review deltas as a JSON array of
+    /// <c>DeltaActivity</c> objects, largest group first.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="queryJson">
+    /// JSON object with optional <c>group_by</c> (<c>reviewer</c>,
+    /// <c>block</c>, <c>delta_type</c>, <c>day</c>), <c>reviewer_id</c>,
+    /// <c>document_id</c>, <c>block_id</c> and <c>delta_type</c> filters and
+    /// RFC 3339 <c>from</c> (inclusive) and <c>to</c> (exclusive) bounds.
+    /// Pass <c>"{}"</c> to count every delta.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_delta_activity(string? tenantId, string queryJson);
+
+    // -----------------------------------------------------------------------
+    // Usage
+    // -----------------------------------------------------------------------
+
+    /// <summary>
+    /// Report a tenant's usage totals (documents ingested, blocks stored,
+    /// compare runs, merge runs) as a <c>UsageReport</c> JSON object.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant whose usage is reported; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="rangeJson">
+    /// JSON object with optional RFC 3339 <c>from</c> (inclusive) and
+    /// <c>to</c> (exclusive) bounds.  Pass <c>"{}"</c> for all usage.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
+    /// <see cref="rtflow_free"/>.
+    /// </returns>
+    [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
+    public static extern IntPtr rtflow_usage_report(string? tenantId, string rangeJson);
+
+    /// <summary>
+    /// Remove the tenant's compare runs and merges that refer to deleted
+    /// documents, and optionally runs and artifact records past a
+    /// retention window, returning a <c>GcReport</c> JSON object.  Artifact
+    /// files are not deleted; the report lists their paths.
+    /// </summary>
+    /// <param name="tenantId">
+    /// Tenant the call acts for; <c>null</c> for the <c>default</c> tenant.
+    /// </param>
+    /// <param name="optionsJson">
+    /// JSON object with optional <c>dry_run</c>, <c>run_retention_days</c>,
+    /// <c>artifact_retention_days</c> and <c>interval_hours</c>.  Pass
+    /// <c>"{}"</c> to remove orphaned data only.
+    /// </param>
+    /// <returns>
+    /// Pointer to a <c>RtflowResult</c>.  Must be freed with
     /// <see cref="rtflow_free"/>.
     /// </returns>
     [DllImport(LibName, CallingConvention = CallingConvention.Cdecl)]
-    public static extern IntPtr rtflow_workflow_state(string workflowId);
+    public static extern IntPtr rtflow_collect_garbage(string? tenantId, string optionsJson);
 
     // -----------------------------------------------------------------------
     // Marshalling helper
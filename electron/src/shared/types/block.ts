@@ -104,6 +104,12 @@ export interface RunFormatting {
 export interface Run {
   text: string;
   formatting: RunFormatting;
+  /**
+   * Tracked revision the run belongs to: `insert` for text only in the
+   * changes-accepted view, `delete` for text only in the changes-rejected
+   * one. Absent or `null` for text in both.
+   */
+  revision?: ChangeType | null;
 }
 
 // ---------------------------------------------------------------------------
@@ -128,6 +134,28 @@ export interface TrackedChange {
   original: string | null;
 }
 
+// ---------------------------------------------------------------------------
+// Attachment
+// ---------------------------------------------------------------------------
+
+/** Kind of non-text content embedded in a block. */
+export type AttachmentType = 'image' | 'embedded_object';
+
+/**
+ * Binary content anchored in a block (an image or embedded object); only a
+ * reference and a content hash are stored.
+ * Mirrors the Rust `Attachment` struct.
+ */
+export interface Attachment {
+  attachment_type: AttachmentType;
+  /** SHA-256 of the attachment bytes. */
+  content_hash: string;
+  /** Where the bytes live, e.g. the package part `"word/media/image1.png"`. */
+  storage_ref: string;
+  /** Display name or OLE program id (e.g. `"Excel.Sheet.12"`); `null` if unknown. */
+  name: string | null;
+}
+
 // ---------------------------------------------------------------------------
 // FormattingMeta
 // ---------------------------------------------------------------------------
@@ -218,6 +246,11 @@ export interface Block {
    * Run stream derived from `display_text` (preserves formatting spans).
    */
   runs: Run[];
+  /**
+   * Images and embedded objects in document order; omitted when the block
+   * has none.
+   */
+  attachments?: Attachment[];
   /** Direct children in document order. */
   children: Block[];
 }
@@ -248,6 +281,11 @@ export interface Document {
   normalization_version: string;
   /** Semver string identifying the clause-hashing contract. */
   hash_contract_version: string;
+  /**
+   * Strategy the anchor signatures were computed with: `"1"`, or a v2
+   * strategy such as `"2+shingles"`.
+   */
+  anchor_version: string;
   /** ISO 8601 UTC timestamp when the document was ingested. */
   ingested_at: string;
   /**
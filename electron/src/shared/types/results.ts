@@ -6,7 +6,7 @@
  * JSON.parse / JSON.stringify round-trips cleanly without a mapping layer.
  */
 
-import type { Block } from './block';
+import type { AttachmentType, Block } from './block';
 
 // ---------------------------------------------------------------------------
 // Generic FFI result envelope
@@ -58,8 +58,41 @@ export interface BlockDiff {
    * `"equal"` or `"modified"`.
    */
   anchor_signature: string | null;
+  /** Content-derived deep-link anchors of this delta and its blocks. */
+  anchors?: DeltaAnchors;
+  /** Authors of the tracked changes on this delta's blocks; absent when none. */
+  authors?: string[];
 }
 
+/** Deep-link anchors of a delta; resolve them with `rtflow_resolve_anchor`. */
+export interface DeltaAnchors {
+  /** `d-{left}-{right}`, `_` standing in for a missing side. */
+  delta: string;
+  /** `b-` anchor of the left block; `null` for insertions. */
+  left_block: string | null;
+  /** `b-` anchor of the right block; `null` for deletions. */
+  right_block: string | null;
+}
+
+/** A block anchor resolved by `rtflow_resolve_anchor`. */
+export interface ResolvedBlock {
+  anchor: string;
+  /** Current id of the block; `null` when it has since been deleted. */
+  block_id: string | null;
+}
+
+/** Result of `rtflow_resolve_anchor`. */
+export type AnchorResolution =
+  | ({ kind: 'block' } & ResolvedBlock)
+  | {
+      kind: 'delta';
+      anchor: string;
+      /** `null` for an insertion. */
+      left: ResolvedBlock | null;
+      /** `null` for a deletion. */
+      right: ResolvedBlock | null;
+    };
+
 /**
  * Top-level result returned by `rtflow_compare`.
  * Placeholder — full field set will be fleshed out when rt-compare is implemented.
@@ -77,6 +110,186 @@ export interface CompareResult {
   equal_count: number;
 }
 
+/**
+ * An image or embedded object added, removed or replaced within an aligned
+ * block pair (`CompareResult.attachment_changes`), compared by content hash.
+ */
+export interface AttachmentChange {
+  kind: 'added' | 'removed' | 'replaced';
+  attachment_type: AttachmentType;
+  /** `null` when the block itself was inserted. */
+  left_block_id: string | null;
+  /** `null` when the block itself was deleted. */
+  right_block_id: string | null;
+  /** SHA-256 of the left content; `null` for `added`. */
+  left_hash: string | null;
+  /** SHA-256 of the right content; `null` for `removed`. */
+  right_hash: string | null;
+  name: string | null;
+}
+
+/**
+ * A row or column present in only one of two aligned tables
+ * (`CompareResult.table_changes`); its cells also appear as inserted or
+ * deleted deltas.
+ */
+export interface TableChange {
+  kind: 'row_inserted' | 'row_deleted' | 'column_inserted' | 'column_deleted';
+  left_table_id: string;
+  right_table_id: string;
+  /** Row or column index in the right table for insertions, left for deletions. */
+  index: number;
+  /** The inserted or deleted row block; `null` for columns. */
+  row_block_id: string | null;
+  /** Cells of the inserted or deleted row or column, in order. */
+  cell_block_ids: string[];
+}
+
+/**
+ * Delta counts of one section of a compare run, returned (as array elements,
+ * in document order) by `rtflow_compare_sections`. `section` is the
+ * structural path of the top-level block; pass it to
+ * `rtflow_compare_section_deltas` to page through the section's deltas.
+ */
+export interface SectionSummary {
+  section: string;
+  total: number;
+  /** Counts keyed by delta kind, e.g. `{"modified": 3, "inserted": 1}`. */
+  by_kind: Record<string, number>;
+}
+
+// ---------------------------------------------------------------------------
+// Ingestion
+// ---------------------------------------------------------------------------
+
+/** What is wrong with one incoming block. */
+export type BlockIssueKind =
+  | 'wrong_document'
+  | 'duplicate_id'
+  | 'duplicate_path'
+  | 'missing_parent'
+  | 'id_in_other_document'
+  | 'id_taken'
+  | 'path_taken';
+
+/** One problem found by block validation. */
+export interface BlockIssue {
+  /** Position of the block in the incoming array. */
+  index: number;
+  block_id: string;
+  structural_path: string;
+  kind: BlockIssueKind;
+  message: string;
+}
+
+/**
+ * Validation of the blocks passed to `rtflow_ingest_blocks`; returned on
+ * its own by a `dry_run` of `rtflow_ingest_blocks_with_options`.
+ */
+export interface ValidationReport {
+  doc_id: string;
+  /** Number of blocks checked. */
+  blocks: number;
+  /** Empty when the blocks may be written. */
+  issues: BlockIssue[];
+}
+
+/** Result of `rtflow_rehash_document`. */
+export interface RehashSummary {
+  doc_id: string;
+  /** Hash contract the document was hashed under before. */
+  previous_contract: string;
+  contract_version: string;
+  /** Blocks whose clause hash or anchor signature changed. */
+  updated: number;
+}
+
+/** Result of `rtflow_migrate_content_store`. */
+export interface ContentMigration {
+  /** Blocks that kept their own tokens and runs before the migration. */
+  blocks: number;
+  /** Of those, blocks now referencing shared content. */
+  shared: number;
+  /** Distinct contents stored once the migration is done. */
+  contents: number;
+}
+
+/** One document of a version chain, from `rtflow_document_versions`. */
+export interface DocumentVersion {
+  doc_id: string;
+  /** Position in the chain, from 1 for the oldest document. */
+  version: number;
+  name: string;
+  ingested_at: string;
+  previous_version_id: string | null;
+}
+
+/**
+ * Returned by `rtflow_ingest_begin` and `rtflow_ingest_append`; the blocks
+ * themselves are sent as NDJSON, one block per line.
+ */
+export interface IngestBatch {
+  batch_id: string;
+  doc_id: string;
+  /** Blocks written so far. */
+  count: number;
+}
+
+// ---------------------------------------------------------------------------
+// Compare jobs
+// ---------------------------------------------------------------------------
+
+/** Returned by `rtflow_compare_async`. */
+export interface CompareJob {
+  job_id: string;
+  run_id: string;
+}
+
+/** Returned by `rtflow_merge_async`. */
+export interface MergeJob {
+  job_id: string;
+  merge_id: string;
+}
+
+export type JobState = "running" | "completed" | "failed" | "cancelled";
+
+/**
+ * Stage of a running comparison, in running order. The `align_*` phases are
+ * the alignment passes; `align_splits` only runs with split detection on.
+ */
+export type ComparePhase =
+  | "flatten"
+  | "align_tables"
+  | "align_paths"
+  | "align_anchors"
+  | "align_similarity"
+  | "align_sequence"
+  | "align_splits"
+  | "diff"
+  | "stats";
+
+/**
+ * Progress of a background compare or merge job, from `rtflow_job_status`.
+ * Collect a finished job's `CompareResult` (or `MergeResult`) with
+ * `rtflow_job_result`; stop a running job with `rtflow_job_cancel`. A merge
+ * job reports no progress.
+ */
+export interface JobStatus {
+  job_id: string;
+  /** The compare run, or a merge job's `merge_id`. */
+  run_id: string;
+  state: JobState;
+  /** Phase the comparison last reported. */
+  phase: ComparePhase;
+  /** 0 to 100; stays 0 until the `diff` phase. */
+  percent: number;
+  /** Aligned blocks diffed so far; a matched pair counts once. */
+  blocks_processed: number;
+  blocks_total: number;
+  /** Why a `failed` or `cancelled` job stopped; `null` otherwise. */
+  error: string | null;
+}
+
 // ---------------------------------------------------------------------------
 // Merge
 // ---------------------------------------------------------------------------
@@ -125,6 +338,78 @@ export interface MergeResult {
   conflict_count: number;
 }
 
+/** Execution state of a merge run. */
+export type MergeRunState = 'running' | 'failed' | 'completed';
+
+/** A merge run, returned by `rtflow_list_merge_runs`. */
+export interface MergeRun {
+  merge_id: string;
+  base_doc_id: string;
+  incoming_doc_id: string;
+  /** Present for three-way merges. */
+  ancestor_doc_id?: string;
+  state: MergeRunState;
+  /** ISO 8601 UTC timestamp of the runner's last heartbeat. */
+  heartbeat_at: string | null;
+  /** When a `running` run is presumed dead; `null` once it has ended. */
+  expires_at: string | null;
+  /** Why a `failed` run failed. */
+  failure: string | null;
+}
+
+// ---------------------------------------------------------------------------
+// Run history
+// ---------------------------------------------------------------------------
+
+/** Content digest of one input document of a compare or merge run. */
+export interface InputDigest {
+  document_id: string;
+  /** Number of blocks, children included, covered by `digest`. */
+  block_count: number;
+  /** SHA-256 over every block's anchor signature and clause hash. */
+  digest: string;
+  schema_version?: string;
+  normalization_version?: string;
+  hash_contract_version?: string;
+}
+
+/** How a compare or merge result was produced. */
+export interface RunManifest {
+  /** Producing crates and their versions, e.g. `{"rt-compare": "0.1.0"}`. */
+  engines: Record<string, string>;
+  /** SHA-256 of the engine configuration. */
+  config_fingerprint: string;
+  /** Input documents in the order the engine took them. */
+  inputs: InputDigest[];
+}
+
+/** A recorded run, returned by `rtflow_run_manifest` / `rtflow_run_history`. */
+export interface RunRecord {
+  run_id: string;
+  run_kind: 'compare' | 'merge';
+  manifest: RunManifest;
+  /** ISO 8601 UTC timestamp when the run was recorded. */
+  recorded_at: string;
+}
+
+/** One component that changed since a run was recorded. */
+export interface ManifestDrift {
+  /** e.g. `"engine rt-compare"`, `"config"`, `"input <uuid> digest"`. */
+  component: string;
+  recorded: string | null;
+  /** `null` when the component is gone (e.g. a deleted input document). */
+  current: string | null;
+}
+
+/** Result of `rtflow_reproduce_check`. */
+export interface ReproduceCheck {
+  run_id: string;
+  run_kind: 'compare' | 'merge';
+  /** `true` when `drift` is empty. */
+  reproducible: boolean;
+  drift: ManifestDrift[];
+}
+
 // ---------------------------------------------------------------------------
 // Workflow
 // ---------------------------------------------------------------------------
@@ -159,8 +444,47 @@ export interface WorkflowEvent {
 }
 
 /**
- * Snapshot of a workflow's current state, returned by both
- * `rtflow_workflow_state` and `rtflow_workflow_event`.
+ * One entry of a workflow's stored event log, as returned (in `seq` order)
+ * by `rtflow_workflow_events`.
+ */
+export interface WorkflowEventRecord {
+  /** Stable UUID of the event. */
+  id: string;
+  /** UUID of the workflow the event belongs to. */
+  workflow_id: string;
+  /** Event type, e.g. `"compare_started"`. */
+  event_type: string;
+  /** Who submitted the event. */
+  actor: string;
+  /** Event-specific payload. */
+  payload: Record<string, unknown> | null;
+  /** ISO 8601 UTC timestamp when the event was recorded. */
+  created_at: string;
+  /** 1-based position in the workflow's log. */
+  seq: number;
+  /** Hash of the preceding event; `null` for events recorded before chaining. */
+  prev_hash: string | null;
+  /** Hash of this event and its `prev_hash`; `null` before chaining. */
+  event_hash: string | null;
+}
+
+/**
+ * Payload of a `compare_progress` event, recorded for compare runs started
+ * with `progress_events` in a workflow's context.
+ */
+export interface CompareProgressPayload {
+  run_id: string;
+  /** 0 to 100. */
+  percent: number;
+  /** Aligned blocks diffed so far; a matched pair counts once. */
+  blocks_processed: number;
+  blocks_total: number;
+}
+
+/**
+ * Snapshot of a workflow's current state, returned by
+ * `rtflow_workflow_create`, `rtflow_workflow_state` and `rtflow_workflow_event`
+ * (and as array elements by `rtflow_workflow_list`).
  * Placeholder — full field set will be fleshed out when rt-workflow is implemented.
  */
 export interface WorkflowState {
@@ -183,3 +507,489 @@ export interface WorkflowState {
   /** ISO 8601 UTC timestamp of the most recent state transition. */
   updated_at: string;
 }
+
+/**
+ * Compare and merge option overrides stored with a workflow, returned by
+ * `rtflow_workflow_set_config` and `rtflow_workflow_get_config`. They apply
+ * to runs whose options carry the workflow's `workflow_id`; options passed
+ * with a run take precedence.
+ */
+export interface WorkflowConfig {
+  /** Compare options, as accepted by `rtflow_compare`. */
+  compare: Record<string, unknown>;
+  /** Merge options, as accepted by `rtflow_merge`. */
+  merge: Record<string, unknown>;
+}
+
+/**
+ * Sign-off a workflow needs before it can complete, passed to
+ * `rtflow_workflow_create_with_options`.
+ */
+export interface ApprovalPolicy {
+  /** Number of distinct approvers that must sign off. */
+  required: number;
+  /** Who may sign off; empty means reviewers assigned as approvers. */
+  approvers?: string[];
+}
+
+/** One allowed move of a workflow state machine. */
+export interface Transition {
+  /** Workflow state, e.g. `"REVIEW_CLOSED"`. */
+  from: string;
+  /** Event type, e.g. `"review_started"`. */
+  event: string;
+  to: string;
+}
+
+/**
+ * Workflow state machine, passed to `rtflow_set_state_machine` and returned
+ * by `rtflow_get_state_machine`.
+ */
+export interface StateMachine {
+  transitions: Transition[];
+}
+
+/**
+ * How long a workflow may stay in `state`, passed to
+ * `rtflow_workflow_create_with_options`.
+ */
+export interface Deadline {
+  /** Workflow state, e.g. `"IN_REVIEW"`. */
+  state: string;
+  within: { hours: number } | { business_days: number };
+}
+
+/** A breach recorded by `rtflow_workflow_check_deadlines`. */
+export interface DeadlineBreach {
+  workflow_id: string;
+  state: string;
+  /** ISO 8601 UTC timestamp the workflow was due to leave `state`. */
+  due_at: string;
+}
+
+/** A workflow's approvals, returned by `rtflow_workflow_approvals`. */
+export interface ApprovalStatus {
+  policy: ApprovalPolicy | null;
+  /** Approvers who signed off, in order. */
+  approved_by: string[];
+}
+
+/** What an assigned reviewer may do; observers cannot submit deltas. */
+export type ReviewerRole = 'editor' | 'approver' | 'observer';
+
+/** A reviewer assigned to a workflow, returned by `rtflow_workflow_reviewers`. */
+export interface Assignment {
+  workflow_id: string;
+  reviewer_id: string;
+  role: ReviewerRole;
+  /** Actor of the event that last assigned the reviewer. */
+  assigned_by: string;
+  /** ISO 8601 UTC timestamp of that event. */
+  assigned_at: string;
+}
+
+/**
+ * A reviewer's sub-workflow as last recorded in its parent's log, returned
+ * by `rtflow_workflow_subworkflows`.
+ */
+export interface Subworkflow {
+  subworkflow_id: string;
+  reviewer_id: string;
+  /** Workflow state, e.g. `"IN_REVIEW"`. */
+  state: string;
+}
+
+/** Why `rtflow_workflow_route_review` routed a section. */
+export type RoutingReason =
+  | { rule: 'density' }
+  | { rule: 'risk_tag'; tag: string };
+
+/** A suggested reviewer for one section of a compare run. */
+export interface RoutingSuggestion {
+  section: string;
+  reviewer_id: string;
+  reason: RoutingReason;
+  /** Share of the section's deltas that are changes. */
+  density: number;
+  /** Number of changed deltas in the section. */
+  changed: number;
+  /** `ReviewerAssigned` event payload for the suggestion. */
+  payload: Record<string, unknown>;
+}
+
+// ---------------------------------------------------------------------------
+// Event callback
+// ---------------------------------------------------------------------------
+
+/**
+ * An event passed to the callback registered with
+ * `rtflow_set_event_callback`.
+ */
+export type HostEvent =
+  | {
+      kind: 'workflow_event';
+      event: WorkflowEventRecord;
+      /** The workflow's state after the event, e.g. `"REVIEW_CLOSED"`. */
+      state: string;
+    }
+  | {
+      kind: 'merge_conflicts';
+      merge_id: string;
+      base_doc_id: string;
+      /** Number of conflicts awaiting review. */
+      pending_review: number;
+    };
+
+// ---------------------------------------------------------------------------
+// Review layers
+// ---------------------------------------------------------------------------
+
+/** A reviewer's layer of edits on a document (`rtflow_create_review_layer`). */
+export interface ReviewLayer {
+  id: string;
+  workflow_id: string;
+  reviewer_id: string;
+  document_id: string;
+  /** ISO 8601 UTC timestamp. */
+  created_at: string;
+}
+
+/** Kind of edit recorded in a review layer. */
+export type ReviewDeltaType = 'insert' | 'delete' | 'modify';
+
+/**
+ * One edit recorded in a review layer, returned by
+ * `rtflow_append_review_delta` and `rtflow_list_review_deltas`.
+ */
+export interface ReviewDelta {
+  id: string;
+  review_layer_id: string;
+  reviewer_id: string;
+  block_id: string;
+  delta_type: ReviewDeltaType;
+  /** First token index affected (inclusive). */
+  token_start: number;
+  /** Last token index affected (inclusive). */
+  token_end: number;
+  delta_payload: Record<string, unknown>;
+  /** ISO 8601 UTC timestamp. */
+  created_at: string;
+}
+
+/** A conflict between two reviewers' edits of one block. */
+export interface LayerConflict {
+  first_layer_id: string;
+  first_reviewer_id: string;
+  second_layer_id: string;
+  second_reviewer_id: string;
+  conflict: Record<string, unknown>;
+}
+
+/** Every cross-reviewer conflict on one base block. */
+export interface BlockLayerConflicts {
+  block_id: string;
+  /** Reviewers involved in at least one conflict, sorted. */
+  reviewer_ids: string[];
+  conflicts: LayerConflict[];
+}
+
+/** Result returned by `rtflow_merge_review_layers`. */
+export interface LayerMergeResult {
+  merge_id: string;
+  base_doc_id: string;
+  /** The merged layers, in creation order. */
+  layer_ids: string[];
+  /** Conflicted blocks in base document order. */
+  blocks: BlockLayerConflicts[];
+  /** Number of edited blocks whose layers do not conflict. */
+  auto_resolved: number;
+  /** Number of conflicts awaiting human review. */
+  pending_review: number;
+}
+
+// ---------------------------------------------------------------------------
+// Review activity
+// ---------------------------------------------------------------------------
+
+/** A column `rtflow_delta_activity` can group by. */
+export type DeltaDimension = 'reviewer' | 'block' | 'delta_type' | 'day';
+
+/**
+ * One group of recorded review deltas, returned (as array elements) by
+ * `rtflow_delta_activity`. Only the fields of the grouped dimensions are set.
+ */
+export interface DeltaActivity {
+  reviewer_id?: string;
+  block_id?: string;
+  /** Structural path (section) of `block_id`. */
+  structural_path?: string;
+  delta_type?: string;
+  /** UTC day the deltas were recorded, as `YYYY-MM-DD`. */
+  day?: string;
+  /** Number of deltas in the group. */
+  count: number;
+}
+
+// ---------------------------------------------------------------------------
+// Display text fidelity
+// ---------------------------------------------------------------------------
+
+/** A block whose runs do not reproduce its `display_text`. */
+export interface FidelityIssue {
+  block_id: string;
+  structural_path: string;
+  display_text: string;
+  /** The concatenated text of the block's runs. */
+  run_text: string;
+  /** Index (in characters) of the first difference. */
+  char_index: number;
+  /** Description of the first difference, naming code points. */
+  message: string;
+}
+
+/** One document's result from `rtflow_check_fidelity`. */
+export interface FidelityReport {
+  document_id: string;
+  /** Blocks whose runs were compared with their `display_text`. */
+  blocks_checked: number;
+  /** Blocks without runs, which were not compared. */
+  blocks_without_runs: number;
+  issues: FidelityIssue[];
+}
+
+// ---------------------------------------------------------------------------
+// Redline views
+// ---------------------------------------------------------------------------
+
+/**
+ * Reading of a redline stored by `rtflow_materialize_view`: every tracked
+ * change accepted, or every one rejected. The view is returned as a
+ * `Document` whose metadata carries `view_of` (the redline's id) and `view`.
+ */
+export type RedlineView = 'accepted' | 'rejected';
+
+// ---------------------------------------------------------------------------
+// Defined terms
+// ---------------------------------------------------------------------------
+
+/** One entry of the dictionary returned by `rtflow_get_defined_terms`. */
+export interface DefinedTerm {
+  /** The term as written in its definition clause. */
+  term: string;
+  /** Normalized tokens of the term joined by single spaces. */
+  normalized: string;
+  /** Block holding the definition. */
+  block_id: string;
+}
+
+// ---------------------------------------------------------------------------
+// Entities
+// ---------------------------------------------------------------------------
+
+/** A party defined in a document's preamble, e.g. `ACME Corp. (the “Lender”)`. */
+export interface Party {
+  /** The term the party is referred to by, e.g. `Lender`. */
+  role: string;
+  /** Normalized tokens of `role` joined by single spaces. */
+  normalized: string;
+  /** The party's name as written before the definition, when there is one. */
+  name: string | null;
+  /** Block holding the definition. */
+  block_id: string;
+}
+
+/** A date written in a document, e.g. `January 1, 2025`. */
+export interface DateMention {
+  /** The date as written. */
+  text: string;
+  /** `YYYY-MM-DD`, or `YYYY-MM` for a month without a day. */
+  value: string;
+  block_id: string;
+  /** Byte offset of `text` in the block's canonical text. */
+  offset: number;
+}
+
+/** Result of `rtflow_get_entities`, each list in document order. */
+export interface DocumentEntities {
+  parties: Party[];
+  dates: DateMention[];
+}
+
+// ---------------------------------------------------------------------------
+// Cross-references
+// ---------------------------------------------------------------------------
+
+/** A section reference returned by `rtflow_get_cross_references`. */
+export interface CrossReference {
+  /** Block whose text holds the reference. */
+  block_id: string;
+  /** The reference as written, e.g. `Section 5.2(b)`. */
+  text: string;
+  /** Referenced structural path, lowercased and without a trailing `.`. */
+  target: string;
+  /** Byte offset of `text` in the block's canonical text. */
+  offset: number;
+}
+
+/**
+ * A reference whose target was deleted or renumbered
+ * (`CompareResult.broken_references`, `MergeResult.broken_references`).
+ */
+export interface BrokenReference {
+  kind: 'target_deleted' | 'target_renumbered';
+  /** Right (incoming) block holding the reference. */
+  block_id: string;
+  text: string;
+  target: string;
+  /** Left (base) block the reference pointed to. */
+  left_target_id: string;
+  /** For `target_renumbered`: the path the target now has. */
+  renumbered_to?: string;
+}
+
+// ---------------------------------------------------------------------------
+// Block search
+// ---------------------------------------------------------------------------
+
+/** Query accepted by `rtflow_search_blocks`. */
+export interface BlockSearchQuery {
+  /** Every word must appear in a matching block's canonical text. */
+  query: string;
+  document_ids?: string[];
+  doc_type?: string;
+  block_type?: string;
+  offset?: number;
+  limit?: number;
+}
+
+/** A block matching `rtflow_search_blocks`. */
+export interface BlockSearchHit {
+  block_id: string;
+  document_id: string;
+  structural_path: string;
+  block_type: string;
+  /** Excerpt with matches wrapped in `<mark>` tags; not HTML-escaped. */
+  snippet: string;
+  /** Relevance; higher is better. */
+  score: number;
+}
+
+// ---------------------------------------------------------------------------
+// Duplicate clauses
+// ---------------------------------------------------------------------------
+
+/** One place a duplicated clause appears. */
+export interface ClauseOccurrence {
+  document_id: string;
+  document_name: string;
+  block_id: string;
+  structural_path: string;
+}
+
+/** Blocks sharing a clause hash; element of `rtflow_find_duplicate_clauses`. */
+export interface DuplicateCluster {
+  clause_hash: string;
+  /** Display text of the first occurrence in the queried document. */
+  display_text: string;
+  /** Number of distinct documents the clause appears in. */
+  document_count: number;
+  /** The queried document's occurrences first, then the others by ingestion. */
+  occurrences: ClauseOccurrence[];
+}
+
+// ---------------------------------------------------------------------------
+// Amendments
+// ---------------------------------------------------------------------------
+
+/** An instruction read from an amendment document. */
+export interface AmendmentInstruction {
+  /** Amendment block holding the instruction. */
+  amendment_block_id: string;
+  /** The instruction as written. */
+  text: string;
+  action: 'replace' | 'delete' | 'insert' | 'substitute';
+  /** Normalized path of the changed clause (for `insert`, the new clause). */
+  target: string;
+  /** For `insert`: the clause the new one follows, if named. */
+  after?: string | null;
+  /** For `substitute`: the words replaced, and their replacement. */
+  from?: string;
+  to?: string;
+  /** For `replace` and `insert`: the new clause text. */
+  new_text?: string;
+}
+
+/** An instruction that could not be applied to the base document. */
+export interface UnappliedInstruction {
+  /** Index into `instructions`. */
+  instruction: number;
+  reason: string;
+}
+
+/** Result of `rtflow_apply_amendment`. */
+export interface AmendedView {
+  /** The as-amended document as a flat block list in document order. */
+  blocks: Block[];
+  instructions: AmendmentInstruction[];
+  unapplied: UnappliedInstruction[];
+}
+
+/** Links a changed delta to the instruction that caused it. */
+export interface AmendmentTrace {
+  /** Index into `compare.deltas`. */
+  delta: number;
+  /** Index into `instructions`. */
+  instruction: number;
+  amendment_block_id: string;
+}
+
+/** Result of `rtflow_compare_amendment`. */
+export interface AmendmentCompareResult {
+  /** The base document compared against its as-amended view. */
+  compare: CompareResult;
+  instructions: AmendmentInstruction[];
+  traces: AmendmentTrace[];
+  unapplied: UnappliedInstruction[];
+}
+
+// ---------------------------------------------------------------------------
+// Garbage collection
+// ---------------------------------------------------------------------------
+
+/** Options accepted by `rtflow_collect_garbage`. */
+export interface GcOptions {
+  /** Report what would be removed without removing anything. */
+  dry_run?: boolean;
+  /** Also remove runs recorded more than this many days ago. */
+  run_retention_days?: number;
+  /** Also remove artifact records created more than this many days ago. */
+  artifact_retention_days?: number;
+  /** Skip the pass when the last one finished less than this many hours ago. */
+  interval_hours?: number;
+}
+
+/** An artifact record removed by garbage collection; its file is left alone. */
+export interface GcArtifact {
+  id: string;
+  workflow_id: string;
+  file_path: string;
+}
+
+/** Result of `rtflow_collect_garbage`. */
+export interface GcReport {
+  dry_run: boolean;
+  /** The pass was skipped because the last one was too recent. */
+  skipped: boolean;
+  /** RFC 3339 timestamp. */
+  ran_at: string;
+  /** RFC 3339 timestamp; present with `interval_hours`. */
+  next_due_at?: string;
+  /** Runs that read a document which no longer exists. */
+  orphaned_runs: string[];
+  /** Merges whose base, incoming or ancestor document no longer exists. */
+  orphaned_merges: string[];
+  expired_runs: string[];
+  expired_artifacts: GcArtifact[];
+  /** Stored compare deltas of the removed runs. */
+  deltas: number;
+}